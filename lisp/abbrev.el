@@ -742,11 +742,9 @@ then ABBREV is looked up in that table only."
                            ;; We used to default `re' to "\\<\\(\\w+\\)\\W*"
                            ;; but when words-include-escapes is set, that
                            ;; is not right and fixing it is boring.
-                           (let ((lim (point)))
-                             (backward-word 1)
-                             (setq start (point))
-                             (forward-word 1)
-                             (setq end (min (point) lim)))
+                           (let ((bounds (abbrev--word-before-point)))
+                             (setq start (car bounds))
+                             (setq end (cdr bounds)))
                          (when (looking-back re (line-beginning-position))
                            (setq start (match-beginning 1))
                            (setq end   (match-end 1)))))
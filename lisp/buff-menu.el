@@ -657,49 +657,19 @@ means list those buffers and no others."
 		  '("File" 1 t))))
   (setq tabulated-list-use-header-line Buffer-menu-use-header-line)
   ;; Collect info for each buffer we're interested in.
-  (let ((buffer-menu-buffer (current-buffer))
-	(show-non-file (not Buffer-menu-files-only))
-	entries)
-    (dolist (buffer (or buffer-list
-			(buffer-list (if Buffer-menu-use-frame-buffer-list
-					 (selected-frame)))))
-      (with-current-buffer buffer
-	(let* ((name (buffer-name))
-	       (file buffer-file-name))
-	  (when (and (buffer-live-p buffer)
-		     (or buffer-list
-			 (and (or (not (string= (substring name 0 1) " "))
-                                  file)
-			      (not (eq buffer buffer-menu-buffer))
-			      (or file show-non-file))))
-	    (push (list buffer
-			(vector (if (eq buffer old-buffer) "." " ")
-				(if buffer-read-only "%" " ")
-				(if (buffer-modified-p) "*" " ")
-				(Buffer-menu--pretty-name name)
-				(number-to-string (buffer-size))
-				(concat (format-mode-line mode-name nil nil buffer)
-					(if mode-line-process
-					    (format-mode-line mode-line-process
-							      nil nil buffer)))
-				(Buffer-menu--pretty-file-name file)))
-		  entries)))))
-    (setq tabulated-list-entries (nreverse entries)))
+  (setq tabulated-list-entries
+	(buffer-menu--entries (or buffer-list
+				  (buffer-list (if Buffer-menu-use-frame-buffer-list
+						   (selected-frame))))
+			      (cond (buffer-list nil)
+				    (Buffer-menu-files-only 'files)
+				    (t t))
+			      old-buffer
+			      Buffer-menu-mode-width))
   (tabulated-list-init-header))
 
 (defun tabulated-list-entry-size-> (entry1 entry2)
   (> (string-to-number (aref (cadr entry1) 4))
      (string-to-number (aref (cadr entry2) 4))))
 
-(defun Buffer-menu--pretty-name (name)
-  (propertize name
-	      'font-lock-face 'buffer-menu-buffer
-	      'mouse-face 'highlight))
-
-(defun Buffer-menu--pretty-file-name (file)
-  (cond (file
-	 (abbreviate-file-name file))
-	((bound-and-true-p list-buffers-directory))
-	(t "")))
-
 ;;; buff-menu.el ends here
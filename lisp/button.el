@@ -187,18 +187,6 @@ changes to a supertype are not reflected in its subtypes)."
     (or (next-single-property-change button 'button)
 	(point-max))))
 
-(defun button-get (button prop)
-  "Get the property of button BUTTON named PROP."
-  (cond ((overlayp button)
-	 (overlay-get button prop))
-	((button--area-button-p button)
-	 (get-text-property (cdr button)
-			    prop (button--area-button-string button)))
-	((markerp button)
-	 (get-text-property button prop (marker-buffer button)))
-	(t ; Must be a text-property button.
-	 (get-text-property button prop))))
-
 (defun button-put (button prop val)
   "Set BUTTON's PROP property to VAL."
   ;; Treat some properties specially.
@@ -236,8 +224,7 @@ marker then goto it.  Otherwise if it is a function then it is
 called with BUTTON as only argument.  BUTTON is either an
 overlay, a buffer position, or (for buttons in the mode-line or
 header-line) a string."
-  (let ((action (or (and use-mouse-action (button-get button 'mouse-action))
-		    (button-get button 'action))))
+  (let ((action (button-action button use-mouse-action)))
     (if (markerp action)
 	(save-selected-window
 	  (select-window (display-buffer (marker-buffer action)))
@@ -377,16 +364,6 @@ Also see `make-text-button'."
 
 ;; Finding buttons in a buffer
 
-(defun button-at (pos)
-  "Return the button at position POS in the current buffer, or nil.
-If the button at POS is a text property button, the return value
-is a marker pointing to POS."
-  (let ((button (get-char-property pos 'button)))
-    (if (or (overlayp button) (null button))
-	button
-      ;; Must be a text-property button; return a marker pointing to it.
-      (copy-marker pos t))))
-
 (defun next-button (pos &optional count-current)
   "Return the next button after position POS in the current buffer.
 If COUNT-CURRENT is non-nil, count any button at POS in the search,
@@ -448,12 +425,12 @@ return t."
    (list (if (integerp last-command-event) (point) last-command-event)))
   (if (and (not (integerp pos)) (eventp pos))
       ;; POS is a mouse event; switch to the proper window/buffer
-      (let ((posn (event-start pos)))
+      (let* ((posn (event-start pos))
+	     (button (button-at-posn posn)))
 	(with-current-buffer (window-buffer (posn-window posn))
-	  (if (posn-string posn)
-	      ;; mode-line, header-line, or display string event.
-	      (button-activate (posn-string posn) t)
-	    (push-button (posn-point posn) t))))
+	  (when button
+	    (button-activate button t)
+	    t)))
     ;; POS is just normal position
     (let ((button (button-at (or pos (point)))))
       (when button
@@ -638,6 +638,18 @@ Only valid during frame saving & restoring; intended for internal use.")
   "When the desktop file was last modified to the knowledge of this Emacs.
 Used to detect desktop file conflicts.")
 
+(defvar desktop--pending-write nil
+  "Name of the desktop file being written in the background, if any.
+Its modification time is only known once the write has finished.")
+
+(defun desktop--finish-write ()
+  "Wait until the desktop file has been written, and record its modtime."
+  (when desktop--pending-write
+    (let ((file desktop--pending-write))
+      (setq desktop--pending-write nil)
+      (desktop-wait-for-write)
+      (setq desktop-file-modtime (nth 5 (file-attributes file))))))
+
 (defvar desktop-var-serdes-funs
   (list (list
 	 'mark-ring
@@ -832,84 +844,8 @@ buffer, which is (in order):
                 desktop-var-serdes-funs)))))
 
 ;; ----------------------------------------------------------------------------
-(defun desktop--v2s (value)
-  "Convert VALUE to a pair (QUOTE . SEXP); (eval SEXP) gives VALUE.
-SEXP is an sexp that when evaluated yields VALUE.
-QUOTE may be `may' (value may be quoted),
-`must' (value must be quoted), or nil (value must not be quoted)."
-  (cond
-    ((or (numberp value) (null value) (eq t value) (keywordp value))
-     (cons 'may value))
-    ((stringp value)
-     (let ((copy (copy-sequence value)))
-       (set-text-properties 0 (length copy) nil copy)
-       ;; Get rid of text properties because we cannot read them.
-       (cons 'may copy)))
-    ((symbolp value)
-     (cons 'must value))
-    ((vectorp value)
-     (let* ((pass1 (mapcar #'desktop--v2s value))
-	    (special (assq nil pass1)))
-       (if special
-	   (cons nil `(vector
-                       ,@(mapcar (lambda (el)
-                                   (if (eq (car el) 'must)
-                                       `',(cdr el) (cdr el)))
-                                 pass1)))
-	 (cons 'may `[,@(mapcar #'cdr pass1)]))))
-    ((consp value)
-     (let ((p value)
-	   newlist
-	   use-list*)
-       (while (consp p)
-	 (let ((q.sexp (desktop--v2s (car p))))
-           (push q.sexp newlist))
-	 (setq p (cdr p)))
-       (when p
-         (let ((last (desktop--v2s p)))
-           (setq use-list* t)
-           (push last newlist)))
-       (if (assq nil newlist)
-	   (cons nil
-		 `(,(if use-list* 'desktop-list* 'list)
-                   ,@(mapcar (lambda (el)
-                               (if (eq (car el) 'must)
-                                   `',(cdr el) (cdr el)))
-                             (nreverse newlist))))
-	 (cons 'must
-	       `(,@(mapcar #'cdr
-                           (nreverse (if use-list* (cdr newlist) newlist)))
-                 ,@(if use-list* (cdar newlist)))))))
-    ((subrp value)
-     (cons nil `(symbol-function
-                 ',(intern-soft (substring (prin1-to-string value) 7 -1)))))
-    ((markerp value)
-     (let ((pos (marker-position value))
-	   (buf (buffer-name (marker-buffer value))))
-       (cons nil
-             `(let ((mk (make-marker)))
-                (add-hook 'desktop-delay-hook
-                          (lambda ()
-                            (set-marker mk ,pos (get-buffer ,buf))))
-                mk))))
-    (t                                  ; Save as text.
-     (cons 'may "Unprintable entity"))))
-
-;; ----------------------------------------------------------------------------
-(defun desktop-value-to-string (value)
-  "Convert VALUE to a string that when read evaluates to the same value.
-Not all types of values are supported."
-  (let* ((print-escape-newlines t)
-	 (print-length nil)
-	 (print-level nil)
-	 (float-output-format nil)
-	 (quote.sexp (desktop--v2s value))
-	 (quote (car quote.sexp))
-	 (print-quoted t)
-	 (txt (prin1-to-string (cdr quote.sexp))))
-    (if (eq quote 'must)
-	(concat "'" txt)
-      txt)))
+;; `desktop--v2s' and `desktop-value-to-string', which turn the
+;; saved values into text, are defined in desktop.rs.
 
 ;; ----------------------------------------------------------------------------
 (defun desktop-outvar (varspec)
@@ -1030,6 +966,7 @@ without further confirmation."
                 nil
                 current-prefix-arg))
   (setq desktop-dirname (file-name-as-directory (expand-file-name dirname)))
+  (desktop--finish-write)
   (save-excursion
     (let ((eager desktop-restore-eager)
 	  (new-modtime (nth 5 (file-attributes (desktop-full-file-name)))))
@@ -1131,11 +1068,13 @@ without further confirmation."
 			     (point))))
 		 (checksum (and beg (md5 (current-buffer) beg (point-max) 'utf-8-emacs))))
 	    (unless (and checksum (equal checksum desktop-file-checksum))
-	      (let ((coding-system-for-write 'utf-8-emacs))
-		(write-region (point-min) (point-max) (desktop-full-file-name) nil 'nomessage))
+	      ;; Write the file in the background, so that saving a large
+	      ;; desktop doesn't block, e.g. when exiting.
+	      (desktop-write-file (desktop-full-file-name) (buffer-string))
 	      (setq desktop-file-checksum checksum)
-	      ;; We remember when it was modified (which is presumably just now).
-	      (setq desktop-file-modtime (nth 5 (file-attributes (desktop-full-file-name)))))))))))
+	      ;; We remember when it was modified once it has been written,
+	      ;; see `desktop--finish-write'.
+	      (setq desktop--pending-write (desktop-full-file-name)))))))))
 
 ;; ----------------------------------------------------------------------------
 ;;;###autoload
@@ -1143,6 +1082,7 @@ without further confirmation."
   "Delete desktop file in `desktop-dirname'.
 This function also sets `desktop-dirname' to nil."
   (interactive)
+  (desktop--finish-write)
   (when desktop-dirname
     (let ((filename (desktop-full-file-name)))
       (setq desktop-dirname nil)
@@ -1205,6 +1145,7 @@ It returns t if a desktop file was loaded, nil otherwise."
              (and desktop-path (car desktop-path))
              ;; Default: .emacs.d.
              user-emacs-directory))))
+    (desktop--finish-write)
     (if (file-exists-p (desktop-full-file-name))
 	;; Desktop file found, but is it already in use?
 	(let ((desktop-first-buffer nil)
@@ -45,43 +45,6 @@ This affects `insert-parentheses' and `insert-pair'."
   :type 'boolean
   :group 'lisp)
 
-(defvar forward-sexp-function nil
-  ;; FIXME:
-  ;; - for some uses, we may want a "sexp-only" version, which only
-  ;;   jumps over a well-formed sexp, rather than some dwimish thing
-  ;;   like jumping from an "else" back up to its "if".
-  ;; - for up-list, we could use the "sexp-only" behavior as well
-  ;;   to treat the dwimish halfsexp as a form of "up-list" step.
-  "If non-nil, `forward-sexp' delegates to this function.
-Should take the same arguments and behave similarly to `forward-sexp'.")
-
-(defun forward-sexp (&optional arg)
-  "Move forward across one balanced expression (sexp).
-With ARG, do it that many times.  Negative arg -N means move
-backward across N balanced expressions.  This command assumes
-point is not in a string or comment.  Calls
-`forward-sexp-function' to do the work, if that is non-nil.  If
-unable to move over a sexp, signal `scan-error' with three
-arguments: a message, the start of the obstacle (usually a
-parenthesis or list marker of some kind), and end of the
-obstacle."
-  (interactive "^p")
-  (or arg (setq arg 1))
-  (if forward-sexp-function
-      (funcall forward-sexp-function arg)
-    (goto-char (or (scan-sexps (point) arg) (buffer-end arg)))
-    (if (< arg 0) (backward-prefix-chars))))
-
-(defun backward-sexp (&optional arg)
-  "Move backward across one balanced expression (sexp).
-With ARG, do it that many times.  Negative arg -N means
-move forward across N balanced expressions.
-This command assumes point is not in a string or comment.
-Uses `forward-sexp' to do the work."
-  (interactive "^p")
-  (or arg (setq arg 1))
-  (forward-sexp (- arg)))
-
 (defun mark-sexp (&optional arg allow-extend)
   "Set mark ARG sexps from point.
 The place mark goes is the same place \\[forward-sexp] would
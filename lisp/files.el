@@ -187,6 +187,18 @@ if the file has changed on disk and you have not edited the buffer."
   :type '(repeat regexp)
   :group 'find-file)
 
+(defcustom revert-buffer-apply-diffs nil
+  "Non-nil means `revert-buffer' changes only the lines that differ.
+The file is read into a temporary buffer, and only the lines that
+differ from the buffer being reverted are replaced, with
+`replace-buffer-lines'.  This keeps markers, point and window starts
+in unchanged text where they were, and is faster than replacing the
+whole text when a large file has changed little.  It is not used when
+reverting from an auto-save file, or in buffers with a file format."
+  :type 'boolean
+  :group 'find-file
+  :version "27.1")
+
 (defvar buffer-file-number nil
   "The device number and file number of the file visited in the current buffer.
 The value is a list of the form (FILENUM DEVNUM).
@@ -3346,6 +3358,38 @@ n  -- to ignore the local variables list.")
 (defconst hack-local-variable-regexp
   "[ \t]*\\([^][;\"'?()\\ \t\n]+\\)[ \t]*:[ \t]*")
 
+(defun hack-read-symbol-shorthands ()
+  "Return the `read-symbol-shorthands' of the Local Variables section.
+Only the last page of the current buffer is searched, like
+`hack-local-variables' does, and nil is returned when there is no
+valid specification."
+  (save-excursion
+    (goto-char (point-max))
+    (search-backward "\n\^L" (max (- (point-max) 3000) (point-min)) 'move)
+    (when (let ((case-fold-search t))
+	    (search-forward "Local Variables:" nil t))
+      (let* ((prefix
+	      (concat "^" (regexp-quote
+			   (buffer-substring (line-beginning-position)
+					     (match-beginning 0)))))
+	     (end (save-excursion
+		    (let ((case-fold-search t))
+		      (re-search-forward (concat prefix "[ \t]*End:") nil t)))))
+	(when (and end
+		   (re-search-forward
+		    (concat prefix "[ \t]*read-symbol-shorthands:[ \t]*")
+		    end t))
+	  (ignore-errors
+	    (let ((shorthands (read (current-buffer))))
+	      (and (not (memq nil (mapcar (lambda (elt)
+					    (and (consp elt)
+						 (stringp (car elt))
+						 (stringp (cdr elt))))
+					  shorthands)))
+		   shorthands))))))))
+
+(setq hack-read-symbol-shorthands-function #'hack-read-symbol-shorthands)
+
 (defun hack-local-variables-prop-line (&optional handle-mode)
   "Return local variables specified in the -*- line.
 Usually returns an alist of elements (VAR . VAL), where VAR is a
@@ -5892,12 +5936,36 @@ an auto-save file."
       (kill-local-variable 'buffer-file-coding-system)
 
       ;; Note that this preserves point in an intelligent way.
-      (if revert-buffer-preserve-modes
-          (let ((buffer-file-format buffer-file-format))
-            (insert-file-contents file-name (not auto-save-p)
-                                  nil nil t))
+      (cond
+       ((and revert-buffer-apply-diffs
+             (not auto-save-p)
+             (null buffer-file-format))
+        (revert-buffer--apply-file-diffs file-name))
+       (revert-buffer-preserve-modes
+        (let ((buffer-file-format buffer-file-format))
+          (insert-file-contents file-name (not auto-save-p)
+                                nil nil t)))
+       (t
         (insert-file-contents file-name (not auto-save-p)
-                              nil nil t))))))
+                              nil nil t)))))))
+
+(defun revert-buffer--apply-file-diffs (file-name)
+  "Revert the current buffer from FILE-NAME, changing only what differs.
+This is how `revert-buffer' reads the file when
+`revert-buffer-apply-diffs' is non-nil."
+  (let ((multibyte enable-multibyte-characters)
+        (buffer (current-buffer))
+        coding)
+    (with-temp-buffer
+      (set-buffer-multibyte multibyte)
+      (insert-file-contents file-name)
+      (setq coding last-coding-system-used)
+      (let ((source (current-buffer)))
+        (with-current-buffer buffer
+          (replace-buffer-lines source))))
+    (setq buffer-file-coding-system coding)
+    (set-visited-file-modtime)
+    (set-buffer-modified-p nil)))
 
 (defun recover-this-file ()
   "Recover the visited file--get contents from its last auto-save file."
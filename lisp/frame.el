@@ -148,9 +148,12 @@ This function runs the hook `focus-out-hook'."
 
 (defun handle-move-frame (event)
   "Handle a move-frame event.
-This function runs the abnormal hook `move-frame-functions'."
+This function runs the abnormal hook `move-frame-functions', and
+`scale-change-functions' if the frame moved to a monitor with another
+scale factor."
   (interactive "e")
   (let ((frame (posn-window (event-start event))))
+    (monitors--update-scale-factor frame)
     (run-hook-with-args 'move-frame-functions frame)))
 
 ;;;; Arrangement of frames at startup
@@ -714,6 +717,14 @@ the new frame according to its own rules."
 
     ;; We can run `window-configuration-change-hook' for this frame now.
     (frame-after-make-frame frame t)
+    ;; Remember the monitors of a new display, to tell
+    ;; `monitor-configuration-change-functions' what changed later.
+    (let ((terminal (frame-terminal frame)))
+      (when (and (display-graphic-p frame)
+                 (not (terminal-parameter terminal 'monitors--attributes)))
+        (monitors--remember-configuration terminal)))
+    ;; Likewise for the scale factor of the frame.
+    (monitors--update-scale-factor frame)
     (run-hook-with-args 'after-make-frame-functions frame)
     frame))
 
@@ -1599,65 +1610,6 @@ See `display-monitor-attributes-list' for information on the
 workarea attribute."
   (frame-monitor-attribute 'workarea frame x y))
 
-(declare-function x-frame-list-z-order "xfns.c" (&optional display))
-(declare-function w32-frame-list-z-order "w32fns.c" (&optional display))
-(declare-function ns-frame-list-z-order "nsfns.m" (&optional display))
-
-(defun frame-list-z-order (&optional display)
-  "Return list of Emacs' frames, in Z (stacking) order.
-The optional argument DISPLAY specifies which display to poll.
-DISPLAY should be either a frame or a display name (a string).
-If omitted or nil, that stands for the selected frame's display.
-
-Frames are listed from topmost (first) to bottommost (last).  As
-a special case, if DISPLAY is non-nil and specifies a live frame,
-return the child frames of that frame in Z (stacking) order.
-
-Return nil if DISPLAY contains no Emacs frame."
-  (let ((frame-type (framep-on-display display)))
-    (cond
-     ((eq frame-type 'x)
-      (x-frame-list-z-order display))
-     ((eq frame-type 'w32)
-      (w32-frame-list-z-order display))
-     ((eq frame-type 'ns)
-      (ns-frame-list-z-order display)))))
-
-(declare-function x-frame-restack "xfns.c" (frame1 frame2 &optional above))
-(declare-function w32-frame-restack "w32fns.c" (frame1 frame2 &optional above))
-(declare-function ns-frame-restack "nsfns.m" (frame1 frame2 &optional above))
-
-(defun frame-restack (frame1 frame2 &optional above)
-  "Restack FRAME1 below FRAME2.
-This implies that if both frames are visible and the display
-areas of these frames overlap, FRAME2 will (partially) obscure
-FRAME1.  If the optional third argument ABOVE is non-nil, restack
-FRAME1 above FRAME2.  This means that if both frames are visible
-and the display areas of these frames overlap, FRAME1 will
-\(partially) obscure FRAME2.
-
-This may be thought of as an atomic action performed in two
-steps: The first step removes FRAME1's window-system window from
-the display.  The second step reinserts FRAME1's window
-below (above if ABOVE is true) that of FRAME2.  Hence the
-position of FRAME2 in its display's Z (stacking) order relative
-to all other frames excluding FRAME1 remains unaltered.
-
-Some window managers may refuse to restack windows. "
-  (if (and (frame-live-p frame1)
-           (frame-live-p frame2)
-           (equal (frame-parameter frame1 'display)
-                  (frame-parameter frame2 'display)))
-      (let ((frame-type (framep-on-display frame1)))
-        (cond
-         ((eq frame-type 'x)
-          (x-frame-restack frame1 frame2 above))
-         ((eq frame-type 'w32)
-          (w32-frame-restack frame1 frame2 above))
-         ((eq frame-type 'ns)
-          (ns-frame-restack frame1 frame2 above))))
-    (error "Cannot restack frames")))
-
 (defun frame-size-changed-p (&optional frame)
   "Return non-nil when the size of FRAME has changed.
 More precisely, return non-nil when the inner width or height of
@@ -567,100 +567,52 @@ FILE is the file where FUNCTION was probably defined."
   ;; FIXME: Document/explain the differences between FUNCTION,
   ;; REAL-FUNCTION, DEF, and REAL-DEF.
   "Return information about FUNCTION.
-Returns a list of the form (REAL-FUNCTION DEF ALIASED REAL-DEF)."
-  (let* ((advised (and (symbolp function)
-		       (advice--p (advice--symbol-function function))))
-	 ;; If the function is advised, use the symbol that has the
-	 ;; real definition, if that symbol is already set up.
-	 (real-function
-	  (or (and advised
-                   (advice--cd*r (advice--symbol-function function)))
-	      function))
-	 ;; Get the real definition, if any.
-	 (def (if (symbolp real-function)
-                  (cond ((symbol-function real-function))
-                        ((get real-function 'function-documentation)
-                         nil)
-                        (t (signal 'void-function (list real-function))))
-		real-function))
-	 (aliased (and def
-                       (or (symbolp def)
-                           ;; Advised & aliased function.
-                           (and advised (symbolp real-function)
-                                (not (eq 'autoload (car-safe def))))
-                           (and (subrp def)
-                                (not (string= (subr-name def)
-                                              (symbol-name function)))))))
-	 (real-def (cond
-                    ((and aliased (not (subrp def)))
-                     (let ((f real-function))
-                       (while (and (fboundp f)
-                                   (symbolp (symbol-function f)))
-                         (setq f (symbol-function f)))
-                       f))
-		    ((subrp def) (intern (subr-name def)))
-                    (t def))))
-    (list real-function def aliased real-def)))
+Returns a list of the form (REAL-FUNCTION DEF ALIASED REAL-DEF).
+See `help--function-info' for the full set of collected data."
+  (let ((info (help--function-info function)))
+    (list (plist-get info :real-function)
+          (plist-get info :def)
+          (plist-get info :aliased)
+          (plist-get info :real-def))))
 
 (defun help-fns-function-description-header (function)
   "Print a line describing FUNCTION to `standard-output'."
-  (pcase-let* ((`(,_real-function ,def ,aliased ,real-def)
-                (help-fns--analyze-function function))
-               (file-name (find-lisp-object-file-name function (if aliased 'defun
-                                                                 def)))
-               (beg (if (and (or (byte-code-function-p def)
-                                 (keymapp def)
-                                 (memq (car-safe def) '(macro lambda closure)))
-                             (stringp file-name)
-                             (help-fns--autoloaded-p function file-name))
-                        (if (commandp def)
-                            "an interactive autoloaded "
-                          "an autoloaded ")
-                      (if (commandp def) "an interactive " "a "))))
+  (let* ((info (help--function-info function))
+         (def (plist-get info :def))
+         (aliased (plist-get info :aliased))
+         (real-def (plist-get info :real-def))
+         (kind (plist-get info :kind))
+         (interactive (plist-get info :interactive))
+         (file-name (plist-get info :file))
+         (beg (if (and (memq kind '(compiled-function macro lambda closure
+                                    keymap sparse-keymap))
+                       (stringp file-name)
+                       (help-fns--autoloaded-p function file-name))
+                  (if interactive
+                      "an interactive autoloaded "
+                    "an autoloaded ")
+                (if interactive "an interactive " "a "))))
 
     ;; Print what kind of function-like object FUNCTION is.
-    (princ (cond ((or (stringp def) (vectorp def))
-		  "a keyboard macro")
-		 ((and (symbolp function)
-                       (get function 'reader-construct))
-                  "a reader construct")
-		 ;; Aliases are Lisp functions, so we need to check
-		 ;; aliases before functions.
-		 (aliased
-		  (format-message "an alias for `%s'" real-def))
-		 ((subrp def)
-		  (if (eq 'unevalled (cdr (subr-arity def)))
-		      (concat beg "special form")
-		    (concat beg "built-in function")))
-		 ((autoloadp def)
-		  (format "%s autoloaded %s"
-			  (if (commandp def) "an interactive" "an")
-			  (if (eq (nth 4 def) 'keymap) "keymap"
-			    (if (nth 4 def) "Lisp macro" "Lisp function"))))
-		 ((or (eq (car-safe def) 'macro)
-		      ;; For advised macros, def is a lambda
-		      ;; expression or a byte-code-function-p, so we
-		      ;; need to check macros before functions.
-		      (macrop function))
-		  (concat beg "Lisp macro"))
-		 ((byte-code-function-p def)
-		  (concat beg "compiled Lisp function"))
-                 ((module-function-p def)
-                  (concat beg "module function"))
-		 ((eq (car-safe def) 'lambda)
-		  (concat beg "Lisp function"))
-		 ((eq (car-safe def) 'closure)
-		  (concat beg "Lisp closure"))
-		 ((keymapp def)
-		  (let ((is-full nil)
-			(elts (cdr-safe def)))
-		    (while elts
-		      (if (char-table-p (car-safe elts))
-			  (setq is-full t
-				elts nil))
-		      (setq elts (cdr-safe elts)))
-		    (concat beg (if is-full "keymap" "sparse keymap"))))
-		 (t "")))
+    (princ (pcase kind
+             ('keyboard-macro "a keyboard macro")
+             ('reader-construct "a reader construct")
+             ('alias (format-message "an alias for `%s'" real-def))
+             ('special-form (concat beg "special form"))
+             ('built-in-function (concat beg "built-in function"))
+             ('autoload
+              (format "%s autoloaded %s"
+                      (if interactive "an interactive" "an")
+                      (if (eq (nth 4 def) 'keymap) "keymap"
+                        (if (nth 4 def) "Lisp macro" "Lisp function"))))
+             ('macro (concat beg "Lisp macro"))
+             ('compiled-function (concat beg "compiled Lisp function"))
+             ('module-function (concat beg "module function"))
+             ('lambda (concat beg "Lisp function"))
+             ('closure (concat beg "Lisp closure"))
+             ('keymap (concat beg "keymap"))
+             ('sparse-keymap (concat beg "sparse keymap"))
+             (_ "")))
 
     (if (and aliased (not (fboundp real-def)))
 	(princ ",\nwhich is not defined.  Please make a bug report.")
@@ -677,7 +629,7 @@ Returns a list of the form (REAL-FUNCTION DEF ALIASED REAL-DEF)."
 	;; but that's completely wrong when the user used load-file.
 	(princ (format-message " in `%s'"
                                (if (eq file-name 'C-source)
-                                   (concat (subr-lang (indirect-function function)) " source code")
+                                   (concat (plist-get info :language) " source code")
                                  (help-fns-short-filename file-name))))
 	;; Make a hyperlink to the library.
 	(with-current-buffer standard-output
@@ -831,22 +783,21 @@ it is displayed along with the global value."
     (if (not (symbolp variable))
 	(message "You did not specify a variable")
       (save-excursion
-	(let ((valvoid (not (with-current-buffer buffer (boundp variable))))
-	      (permanent-local (get variable 'permanent-local))
-	      val val-start-pos locus)
-	  ;; Extract the value before setting up the output buffer,
-	  ;; in case `buffer' *is* the output buffer.
-	  (unless valvoid
-	    (with-selected-frame frame
-	      (with-current-buffer buffer
-		(setq val (symbol-value variable)
-		      locus (variable-binding-locus variable)))))
+	;; Extract the value before setting up the output buffer,
+	;; in case `buffer' *is* the output buffer.
+	(let* ((info (with-selected-frame frame
+                       (help--variable-info variable buffer)))
+               (valvoid (plist-get info :void))
+	       (permanent-local (plist-get info :permanent-local))
+	       (val (plist-get info :value))
+	       (locus (plist-get info :locus))
+	       val-start-pos)
 	  (help-setup-xref (list #'describe-variable variable buffer)
 			   (called-interactively-p 'interactive))
 	  (with-help-window (help-buffer)
 	    (with-current-buffer buffer
 	      (prin1 variable)
-	      (setq file-name (find-lisp-object-file-name variable 'defvar))
+	      (setq file-name (plist-get info :file))
 
 	      (if file-name
 		  (progn
@@ -873,14 +824,7 @@ it is displayed along with the global value."
 		(setq val-start-pos (point))
 		(princ "value is")
 		(let ((line-beg (line-beginning-position))
-		      (print-rep
-		       (let ((rep
-			      (let ((print-quoted t)
-                                    (print-circle t))
-				(cl-prin1-to-string val))))
-			 (if (and (symbolp val) (not (booleanp val)))
-			     (format-message "`%s'" rep)
-			   rep))))
+		      (print-rep (plist-get info :printed)))
 		  (if (< (+ (length print-rep) (point) (- line-beg)) 68)
 		      (insert " " print-rep)
 		    (terpri)
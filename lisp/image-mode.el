@@ -537,6 +537,28 @@ call."
 
 (put 'image-mode 'mode-class 'special)
 
+(defun image-mode--tty ()
+  "Set up Image mode on a text terminal that can show images.
+The image is put in place of the buffer's text with `tty-image-put'.
+Only PNG, PPM and PGM images can be shown, and not transformed."
+  (kill-all-local-variables)
+  (setq major-mode 'image-mode
+        mode-name "Image"
+        cursor-type nil
+        truncate-lines t)
+  (use-local-map image-mode-map)
+  (condition-case err
+      (tty-image-put (point-min) (point-max)
+                     (string-make-unibyte
+                      (buffer-substring-no-properties (point-min) (point-max)))
+                     t)
+    (error
+     (image-mode-as-text)
+     (message "Cannot display image: %s" (cdr err))))
+  (add-hook 'change-major-mode-hook
+            (lambda () (tty-image-remove (point-min) (point-max))) nil t)
+  (run-mode-hooks 'image-mode-hook))
+
 ;;;###autoload
 (defun image-mode ()
   "Major mode for image files.
@@ -612,10 +634,13 @@ mouse-3: Previous frame"
         (message "%s" (concat msg1 "text or hex."))))))
 
     (error
-     (image-mode-as-text)
-     (funcall
-      (if (called-interactively-p 'any) 'error 'message)
-      "Cannot display image: %s" (cdr err)))))
+     (if (and (not (display-images-p)) (tty-image-available-p))
+	 ;; Text terminals that can show images have their own setup.
+	 (image-mode--tty)
+       (image-mode-as-text)
+       (funcall
+	(if (called-interactively-p 'any) 'error 'message)
+	"Cannot display image: %s" (cdr err))))))
 
 ;;;###autoload
 (define-minor-mode image-minor-mode
@@ -294,6 +294,11 @@ attribute."
     (apply 'define-charset-internal name (mapcar 'cdr attrs))))
 
 
+(defvar hack-read-symbol-shorthands-function nil
+  "Function that returns the `read-symbol-shorthands' of the current buffer.
+`load-with-code-conversion' calls it in the buffer of the file it
+loads, once files.el has set it.")
+
 (defun load-with-code-conversion (fullname file &optional noerror nomessage)
   "Execute a file of Lisp code named FILE whose absolute name is FULLNAME.
 The file contents are decoded before evaluation if necessary.
@@ -311,7 +316,8 @@ Return t if file exists."
             ;; is not yet loaded.
             (get-buffer-create (generate-new-buffer-name " *load*")))
 	   (load-in-progress t)
-	   (source (save-match-data (string-match "\\.el\\'" fullname))))
+	   (source (save-match-data (string-match "\\.el\\'" fullname)))
+	   (shorthands nil))
       (unless nomessage
 	(if source
 	    (message "Loading %s (source)..." file)
@@ -338,13 +344,17 @@ Return t if file exists."
 		       (or (eq (coding-system-type last-coding-system-used)
 			       'raw-text)))
 		  (set-buffer-multibyte nil))
+	      (when hack-read-symbol-shorthands-function
+		(setq shorthands (funcall hack-read-symbol-shorthands-function)))
 	      ;; Make `kill-buffer' quiet.
 	      (set-buffer-modified-p nil))
-	    ;; Have the original buffer current while we eval.
-	    (eval-buffer buffer nil
-			 ;; This is compatible with what `load' does.
-			 (if purify-flag file fullname)
-			 nil t))
+	    ;; Have the original buffer current while we eval, but read
+	    ;; with the shorthands of the file.
+	    (let ((read-symbol-shorthands shorthands))
+	      (eval-buffer buffer nil
+			   ;; This is compatible with what `load' does.
+			   (if purify-flag file fullname)
+			   nil t)))
 	(let (kill-buffer-hook kill-buffer-query-functions)
 	  (kill-buffer buffer)))
       (do-after-load-evaluation fullname)
@@ -19341,7 +19341,7 @@ images between BEG and END.  This will create new image displays
 only if necessary.  BEG and END default to the buffer
 boundaries."
   (interactive "P")
-  (when (display-graphic-p)
+  (when (or (display-graphic-p) (tty-image-available-p))
     (unless refresh
       (org-remove-inline-images)
       (when (fboundp 'clear-image-cache) (clear-image-cache)))
@@ -19407,8 +19407,29 @@ boundaries."
 		       (old (get-char-property-and-overlay
 			     (org-element-property :begin link)
 			     'org-image-overlay)))
-		   (if (and (car-safe old) refresh)
-		       (image-refresh (overlay-get (cdr old) 'display))
+		   (cond
+		    ((and (car-safe old) refresh)
+		     (when (display-graphic-p)
+		       (image-refresh (overlay-get (cdr old) 'display))))
+		    ((not (display-graphic-p))
+		     ;; Images on text terminals are put over the link
+		     ;; by `tty-image-put', which can't scale them to a
+		     ;; given width.
+		     (let ((ov (ignore-errors
+				 (tty-image-put
+				  (org-element-property :begin link)
+				  (progn
+				    (goto-char (org-element-property :end link))
+				    (skip-chars-backward " \t")
+				    (point))
+				  file))))
+		       (when ov
+			 (overlay-put ov 'org-image-overlay t)
+			 (overlay-put
+			  ov 'modification-hooks
+			  (list 'org-display-inline-remove-overlay))
+			 (push ov org-inline-image-overlays))))
+		    (t
 		     (let ((image (create-image file
 						(and width 'imagemagick)
 						nil
@@ -19427,7 +19448,7 @@ boundaries."
 			   (overlay-put
 			    ov 'modification-hooks
 			    (list 'org-display-inline-remove-overlay))
-			   (push ov org-inline-image-overlays)))))))))))))))
+			   (push ov org-inline-image-overlays))))))))))))))))
 
 (defun org-display-inline-remove-overlay (ov after _beg _end &optional _len)
   "Remove inline-display overlay if a corresponding region is modified."
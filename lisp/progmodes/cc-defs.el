@@ -1828,7 +1828,10 @@ non-nil, a caret is prepended to invert the set."
     ;; syntax tables differently to handle this.
     (let ((table (copy-syntax-table))
 	  entry)
-      (modify-syntax-entry ?a ". 12345678" table)
+      (condition-case nil
+	  (modify-syntax-entry ?a ". 12345678" table)
+	;; Emacs rejects the flags it doesn't have.
+	(error (modify-syntax-entry ?a ". 1234" table)))
       (cond
        ;; Emacs
        ((arrayp table)
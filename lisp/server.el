@@ -495,33 +495,6 @@ If CLIENT is non-nil, add a description of it to the logged message."
       (file-error                       ;The pipe/socket was closed.
        (ignore-errors (server-delete-client proc))))))
 
-(defun server-unquote-arg (arg)
-  "Remove &-quotation from ARG.
-See `server-quote-arg' and `server-process-filter'."
-  (replace-regexp-in-string
-   "&." (lambda (s)
-	  (pcase (aref s 1)
-	    (?& "&")
-	    (?- "-")
-	    (?n "\n")
-	    (_ " ")))
-   arg t t))
-
-(defun server-quote-arg (arg)
-  "In ARG, insert a & before each &, each space, each newline, and -.
-Change spaces to underscores, too, so that the return value never
-contains a space.
-
-See `server-unquote-arg' and `server-process-filter'."
-  (replace-regexp-in-string
-   "[-&\n ]" (lambda (s)
-	       (pcase (aref s 0)
-		 (?& "&&")
-		 (?- "&-")
-		 (?\n "&n")
-		 (?\s "&_")))
-   arg t t))
-
 (defun server-send-string (proc string)
   "A wrapper around `process-send-string' for logging."
   (server-log (concat "Sent " string) proc)
@@ -682,27 +655,10 @@ server or call `\\[server-force-delete]' to forcibly disconnect it."))
           ;; questions (e.g., desktop-kill).
 	  (add-hook 'kill-emacs-hook 'server-force-stop t) ;Cleanup upon exit.
 	  (setq server-process
-		(apply #'make-network-process
-		       :name server-name
-		       :server t
-		       :noquery t
-		       :sentinel #'server-sentinel
-		       :filter #'server-process-filter
-		       :use-external-socket t
-		       ;; We must receive file names without being decoded.
-		       ;; Those are decoded by server-process-filter according
-		       ;; to file-name-coding-system.  Also don't get
-		       ;; confused by CRs since we don't quote them.
-		       :coding 'raw-text-unix
-		       ;; The other args depend on the kind of socket used.
-		       (if server-use-tcp
-			   (list :family 'ipv4  ;; We're not ready for IPv6 yet
-				 :service (or server-port t)
-				 :host (or server-host 'local)
-				 :plist '(:authenticated nil))
-			 (list :family 'local
-			       :service server-file
-			       :plist '(:authenticated t)))))
+		(if server-use-tcp
+		    (server-make-listener
+		     server-name (or server-port t) (or server-host 'local))
+		  (server-make-listener server-name server-file)))
 	  (unless server-process (error "Could not start server process"))
 	  (process-put server-process :server-file server-file)
 	  (when server-use-tcp
@@ -1049,12 +1005,8 @@ The following commands are accepted by the client:
   (server-log (concat "Received " string) proc)
   ;; First things first: let's check the authentication
   (unless (process-get proc :authenticated)
-    (if (and (string-match "-auth \\([!-~]+\\)\n?" string)
-	     (equal (match-string 1 string) (process-get proc :auth-key)))
-	(progn
-	  (setq string (substring string (match-end 0)))
-	  (process-put proc :authenticated t)
-	  (server-log "Authentication successful" proc))
+    (if (setq string (server-authenticate proc string))
+	(server-log "Authentication successful" proc)
       (server-log "Authentication failed" proc)
       (server-send-string
        proc (concat "-error " (server-quote-arg "Authentication failed")))
@@ -1104,7 +1056,7 @@ The following commands are accepted by the client:
 	    ;; Remove this line from STRING.
 	    (setq string (substring string (match-end 0)))
 	    (setq args-left
-		  (mapcar 'server-unquote-arg (split-string request " " t)))
+		  (server-split-request request))
 	    (while args-left
               (pcase (pop args-left)
                 ;; -version CLIENT-VERSION: obsolete at birth.
@@ -688,9 +688,12 @@ In programming language modes, this is the same as TAB.
 In some text modes, where TAB inserts a tab, this command indents to the
 column specified by the function `current-left-margin'."
   (interactive "*")
-  (delete-horizontal-space t)
-  (newline nil t)
-  (indent-according-to-mode))
+  ;; `newline-and-indent-core' does all of this natively, unless the
+  ;; buffer needs what `newline' does for hard newlines and margins.
+  (unless (newline-and-indent-core)
+    (delete-horizontal-space t)
+    (newline nil t)
+    (indent-according-to-mode)))
 
 (defun reindent-then-newline-and-indent ()
   "Reindent current line, insert newline, then indent the new line.
@@ -1670,6 +1673,20 @@ to get different commands to edit and resubmit."
 (defvar extended-command-history nil)
 (defvar execute-extended-command--last-typed nil)
 
+(defcustom read-extended-command-predicate nil
+  "Predicate to use to determine which commands to include when completing.
+If it's nil, include all the commands.
+If it's a function, it is called with two arguments: the symbol of
+the command and the buffer in which \\[execute-extended-command] was
+typed.  The command is offered as a completion only if the predicate
+returns non-nil.
+Commands belonging to a mode enabled in that buffer are annotated
+with the mode; see `extended-command-mode'."
+  :group 'keyboard
+  :type '(choice (const :tag "All commands" nil)
+                 (function :tag "Other function"))
+  :version "27.1")
+
 (defun read-extended-command ()
   "Read command name to invoke in `execute-extended-command'."
   (minibuffer-with-setup-hook
@@ -1706,16 +1723,29 @@ to get different commands to edit and resubmit."
 	     ;; because "M-x" is a well-known prompt to read a command
 	     ;; and it serves as a shorthand for "Extended command: ".
 	     "M-x ")
-     (lambda (string pred action)
-       (let ((pred
-              (if (memq action '(nil t))
-                  ;; Exclude obsolete commands from completions.
-                  (lambda (sym)
-                    (and (funcall pred sym)
-                         (or (equal string (symbol-name sym))
-                             (not (get sym 'byte-obsolete-info)))))
-                pred)))
-         (complete-with-action action obarray string pred)))
+     ;; Collect the candidates once, in the buffer M-x was typed in.
+     (let ((commands (extended-command-candidates))
+           (buffer (current-buffer)))
+       (lambda (string pred action)
+         (cond
+          ((eq action 'metadata)
+           `(metadata
+             (annotation-function
+              . ,(lambda (name)
+                   (let* ((sym (intern-soft name))
+                          (mode (and sym (extended-command-mode sym buffer))))
+                     (and mode (format " (%s)" mode)))))))
+          ((memq action '(nil t))
+           ;; Obsolete commands are excluded from completions unless
+           ;; typed in full.
+           (let ((sym (intern-soft string)))
+             (complete-with-action
+              action
+              (if (and sym (commandp sym) (get sym 'byte-obsolete-info))
+                  (cons string commands)
+                commands)
+              string nil)))
+          (t (complete-with-action action obarray string pred)))))
      #'commandp t nil 'extended-command-history)))
 
 (defcustom suggest-key-bindings t
@@ -4129,19 +4159,6 @@ Runs `prefix-command-preserve-state-hook'."
 
 (add-hook 'prefix-command-echo-keystrokes-functions
           #'universal-argument--description)
-(defun universal-argument--description ()
-  (when prefix-arg
-    (concat "C-u"
-            (pcase prefix-arg
-              (`(-) " -")
-              (`(,(and (pred integerp) n))
-               (let ((str ""))
-                 (while (and (> n 4) (= (mod n 4) 0))
-                   (setq str (concat str " C-u"))
-                   (setq n (/ n 4)))
-                 (if (= n 4) str (format " %s" prefix-arg))))
-              (_ (format " %s" prefix-arg))))))
-
 (add-hook 'prefix-command-preserve-state-hook
           #'universal-argument--preserve)
 (defun universal-argument--preserve ()
@@ -4188,62 +4205,6 @@ Runs `prefix-command-preserve-state-hook'."
   (prefix-command-update)
   (set-transient-map universal-argument-map nil))
 
-(defun universal-argument ()
-  "Begin a numeric argument for the following command.
-Digits or minus sign following \\[universal-argument] make up the numeric argument.
-\\[universal-argument] following the digits or minus sign ends the argument.
-\\[universal-argument] without digits or minus sign provides 4 as argument.
-Repeating \\[universal-argument] without digits or minus sign
- multiplies the argument by 4 each time.
-For some commands, just \\[universal-argument] by itself serves as a flag
-which is different in effect from any particular numeric argument.
-These commands include \\[set-mark-command] and \\[start-kbd-macro]."
-  (interactive)
-  (prefix-command-preserve-state)
-  (setq prefix-arg (list 4))
-  (universal-argument--mode))
-
-(defun universal-argument-more (arg)
-  ;; A subsequent C-u means to multiply the factor by 4 if we've typed
-  ;; nothing but C-u's; otherwise it means to terminate the prefix arg.
-  (interactive "P")
-  (prefix-command-preserve-state)
-  (setq prefix-arg (if (consp arg)
-                       (list (* 4 (car arg)))
-                     (if (eq arg '-)
-                         (list -4)
-                       arg)))
-  (when (consp prefix-arg) (universal-argument--mode)))
-
-(defun negative-argument (arg)
-  "Begin a negative numeric argument for the next command.
-\\[universal-argument] following digits or minus sign ends the argument."
-  (interactive "P")
-  (prefix-command-preserve-state)
-  (setq prefix-arg (cond ((integerp arg) (- arg))
-                         ((eq arg '-) nil)
-                         (t '-)))
-  (universal-argument--mode))
-
-(defun digit-argument (arg)
-  "Part of the numeric argument for the next command.
-\\[universal-argument] following digits or minus sign ends the argument."
-  (interactive "P")
-  (prefix-command-preserve-state)
-  (let* ((char (if (integerp last-command-event)
-		   last-command-event
-		 (get last-command-event 'ascii-character)))
-	 (digit (- (logand char ?\177) ?0)))
-    (setq prefix-arg (cond ((integerp arg)
-                            (+ (* arg 10)
-			       (if (< arg 0) (- digit) digit)))
-                           ((eq arg '-)
-                            ;; Treat -0 as just -, so that -01 will work.
-                            (if (zerop digit) '- (- digit)))
-                           (t
-                            digit))))
-  (universal-argument--mode))
-
 
 (defvar filter-buffer-substring-functions nil
   "This variable is a wrapper hook around `buffer-substring--filter'.
@@ -7075,19 +7036,6 @@ current object."
       (delete-region (point) (+ (point) len2))
       (set-marker boundary nil))))
 
-(defun backward-word (&optional arg)
-  "Move backward until encountering the beginning of a word.
-With argument ARG, do this that many times.
-If ARG is omitted or nil, move point backward one word.
-
-The word boundaries are normally determined by the buffer's syntax
-table, but `find-word-boundary-function-table', such as set up
-by `subword-mode', can change that.  If a Lisp program needs to
-move by words determined strictly by the syntax table, it should
-use `backward-word-strictly' instead."
-  (interactive "^p")
-  (forward-word (- (or arg 1))))
-
 (defun mark-word (&optional arg allow-extend)
   "Set mark ARG words away from point.
 The place mark goes is the same place \\[forward-word] would
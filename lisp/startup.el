@@ -1289,6 +1289,9 @@ please check its value")
     (load-user-init-file
      (lambda ()
        (cond
+        ;; With --init-directory, only look there.
+        (command-line-init-directory
+         (expand-file-name "init" command-line-init-directory))
         ((eq system-type 'ms-dos)
          (concat "~" init-file-user "/_emacs"))
         ((not (eq system-type 'windows-nt))
@@ -1309,8 +1312,9 @@ please check its value")
      (lambda ()
        (expand-file-name
         "init"
-        (file-name-as-directory
-         (concat "~" init-file-user "/.emacs.d"))))
+        (or command-line-init-directory
+            (file-name-as-directory
+             (concat "~" init-file-user "/.emacs.d")))))
      (not inhibit-default-init))
 
     (when (and deactivate-mark transient-mark-mode)
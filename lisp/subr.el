@@ -519,18 +519,6 @@ was called."
   (declare (compiler-macro internal--compiler-macro-cXXr))
   (cdr (cdr (cdr (cdr x)))))
 
-(defun last (list &optional n)
-  "Return the last link of LIST.  Its car is the last element.
-If LIST is nil, return nil.
-If N is non-nil, return the Nth-to-last link of LIST.
-If N is bigger than the length of LIST, return LIST."
-  (if n
-      (and (>= n 0)
-           (let ((m (safe-length list)))
-             (if (< n m) (nthcdr (- m n) list) list)))
-    (and list
-         (nthcdr (1- (safe-length list)) list))))
-
 (defun butlast (list &optional n)
   "Return a copy of LIST with the last N elements removed.
 If N is omitted or nil, the last element is removed from the
@@ -555,28 +543,6 @@ If N is omitted or nil, remove the last element."
   (declare (compiler-macro (lambda (_) `(= 0 ,number))))
   (= 0 number))
 
-(defun delete-dups (list)
-  "Destructively remove `equal' duplicates from LIST.
-Store the result in LIST and return it.  LIST must be a proper list.
-Of several `equal' occurrences of an element in LIST, the first
-one is kept."
-  (let ((l (length list)))
-    (if (> l 100)
-        (let ((hash (make-hash-table :test #'equal :size l))
-              (tail list) retail)
-          (puthash (car list) t hash)
-          (while (setq retail (cdr tail))
-            (let ((elt (car retail)))
-              (if (gethash elt hash)
-                  (setcdr tail (cdr retail))
-                (puthash elt t hash)
-                (setq tail retail)))))
-      (let ((tail list))
-        (while tail
-          (setcdr tail (delete (car tail) (cdr tail)))
-          (setq tail (cdr tail))))))
-  list)
-
 ;; See https://lists.gnu.org/r/emacs-devel/2013-05/msg00204.html
 (defun delete-consecutive-dups (list &optional circular)
   "Destructively remove `equal' consecutive duplicates from LIST.
@@ -636,50 +602,8 @@ of course, also replace TO with a slightly larger value
                 next (+ from (* n inc)))))
       (nreverse seq))))
 
-(defun copy-tree (tree &optional vecp)
-  "Make a copy of TREE.
-If TREE is a cons cell, this recursively copies both its car and its cdr.
-Contrast to `copy-sequence', which copies only along the cdrs.  With second
-argument VECP, this copies vectors as well as conses."
-  (if (consp tree)
-      (let (result)
-	(while (consp tree)
-	  (let ((newcar (car tree)))
-	    (if (or (consp (car tree)) (and vecp (vectorp (car tree))))
-		(setq newcar (copy-tree (car tree) vecp)))
-	    (push newcar result))
-	  (setq tree (cdr tree)))
-	(nconc (nreverse result)
-               (if (and vecp (vectorp tree)) (copy-tree tree vecp) tree)))
-    (if (and vecp (vectorp tree))
-	(let ((i (length (setq tree (copy-sequence tree)))))
-	  (while (>= (setq i (1- i)) 0)
-	    (aset tree i (copy-tree (aref tree i) vecp)))
-	  tree)
-      tree)))
-
 ;;;; Various list-search functions.
 
-(defun assoc-default (key alist &optional test default)
-  "Find object KEY in a pseudo-alist ALIST.
-ALIST is a list of conses or objects.  Each element
- (or the element's car, if it is a cons) is compared with KEY by
- calling TEST, with two arguments: (i) the element or its car,
- and (ii) KEY.
-If that is non-nil, the element matches; then `assoc-default'
- returns the element's cdr, if it is a cons, or DEFAULT if the
- element is not a cons.
-
-If no element matches, the value is nil.
-If TEST is omitted or nil, `equal' is used."
-  (let (found (tail alist) value)
-    (while (and tail (not found))
-      (let ((elt (car tail)))
-	(when (funcall (or test 'equal) (if (consp elt) (car elt) elt) key)
-	  (setq found t value (if (consp elt) (cdr elt) default))))
-      (setq tail (cdr tail)))
-    value))
-
 (defun member-ignore-case (elt list)
   "Like `member', but ignore differences in case and text representation.
 ELT must be a string.  Upper-case and lower-case letters are treated as equal.
@@ -691,76 +615,6 @@ Non-strings in LIST are ignored."
     (setq list (cdr list)))
   list)
 
-(defun assoc-delete-all (key alist &optional test)
-  "Delete from ALIST all elements whose car is KEY.
-Compare keys with TEST.  Defaults to `equal'.
-Return the modified alist.
-Elements of ALIST that are not conses are ignored."
-  (unless test (setq test #'equal))
-  (while (and (consp (car alist))
-	      (funcall test (caar alist) key))
-    (setq alist (cdr alist)))
-  (let ((tail alist) tail-cdr)
-    (while (setq tail-cdr (cdr tail))
-      (if (and (consp (car tail-cdr))
-	       (funcall test (caar tail-cdr) key))
-	  (setcdr tail (cdr tail-cdr))
-	(setq tail tail-cdr))))
-  alist)
-
-(defun assq-delete-all (key alist)
-  "Delete from ALIST all elements whose car is `eq' to KEY.
-Return the modified alist.
-Elements of ALIST that are not conses are ignored."
-  (assoc-delete-all key alist #'eq))
-
-(defun rassq-delete-all (value alist)
-  "Delete from ALIST all elements whose cdr is `eq' to VALUE.
-Return the modified alist.
-Elements of ALIST that are not conses are ignored."
-  (while (and (consp (car alist))
-	      (eq (cdr (car alist)) value))
-    (setq alist (cdr alist)))
-  (let ((tail alist) tail-cdr)
-    (while (setq tail-cdr (cdr tail))
-      (if (and (consp (car tail-cdr))
-	       (eq (cdr (car tail-cdr)) value))
-	  (setcdr tail (cdr tail-cdr))
-	(setq tail tail-cdr))))
-  alist)
-
-(defun alist-get (key alist &optional default remove testfn)
-  "Return the value associated with KEY in ALIST.
-If KEY is not found in ALIST, return DEFAULT.
-Use TESTFN to lookup in the alist if non-nil.  Otherwise, use `assq'.
-
-This is a generalized variable suitable for use with `setf'.
-When using it to set a value, optional argument REMOVE non-nil
-means to remove KEY from ALIST if the new value is `eql' to DEFAULT."
-  (ignore remove) ;;Silence byte-compiler.
-  (let ((x (if (not testfn)
-               (assq key alist)
-             (assoc key alist testfn))))
-    (if x (cdr x) default)))
-
-(defun remove (elt seq)
-  "Return a copy of SEQ with all occurrences of ELT removed.
-SEQ must be a list, vector, or string.  The comparison is done with `equal'."
-  (if (nlistp seq)
-      ;; If SEQ isn't a list, there's no need to copy SEQ because
-      ;; `delete' will return a new object.
-      (delete elt seq)
-    (delete elt (copy-sequence seq))))
-
-(defun remq (elt list)
-  "Return LIST with all occurrences of ELT removed.
-The comparison is done with `eq'.  Contrary to `delq', this does not use
-side-effects, and the argument LIST is not modified."
-  (while (and (eq elt (car list)) (setq list (cdr list))))
-  (if (memq elt list)
-      (delq elt (copy-sequence list))
-    list))
-
 ;;;; Keymap support.
 
 (defun kbd (keys)
@@ -2579,85 +2433,6 @@ user can undo the change normally."
 	     (accept-change-group ,handle)
 	   (cancel-change-group ,handle))))))
 
-(defun prepare-change-group (&optional buffer)
-  "Return a handle for the current buffer's state, for a change group.
-If you specify BUFFER, make a handle for BUFFER's state instead.
-
-Pass the handle to `activate-change-group' afterward to initiate
-the actual changes of the change group.
-
-To finish the change group, call either `accept-change-group' or
-`cancel-change-group' passing the same handle as argument.  Call
-`accept-change-group' to accept the changes in the group as final;
-call `cancel-change-group' to undo them all.  You should use
-`unwind-protect' to make sure the group is always finished.  The call
-to `activate-change-group' should be inside the `unwind-protect'.
-Once you finish the group, don't use the handle again--don't try to
-finish the same group twice.  For a simple example of correct use, see
-the source code of `atomic-change-group'.
-
-The handle records only the specified buffer.  To make a multibuffer
-change group, call this function once for each buffer you want to
-cover, then use `nconc' to combine the returned values, like this:
-
-  (nconc (prepare-change-group buffer-1)
-         (prepare-change-group buffer-2))
-
-You can then activate that multibuffer change group with a single
-call to `activate-change-group' and finish it with a single call
-to `accept-change-group' or `cancel-change-group'."
-
-  (if buffer
-      (list (cons buffer (with-current-buffer buffer buffer-undo-list)))
-    (list (cons (current-buffer) buffer-undo-list))))
-
-(defun activate-change-group (handle)
-  "Activate a change group made with `prepare-change-group' (which see)."
-  (dolist (elt handle)
-    (with-current-buffer (car elt)
-      (if (eq buffer-undo-list t)
-	  (setq buffer-undo-list nil)))))
-
-(defun accept-change-group (handle)
-  "Finish a change group made with `prepare-change-group' (which see).
-This finishes the change group by accepting its changes as final."
-  (dolist (elt handle)
-    (with-current-buffer (car elt)
-      (if (eq (cdr elt) t)
-	  (setq buffer-undo-list t)))))
-
-(defun cancel-change-group (handle)
-  "Finish a change group made with `prepare-change-group' (which see).
-This finishes the change group by reverting all of its changes."
-  (dolist (elt handle)
-    (with-current-buffer (car elt)
-      (setq elt (cdr elt))
-      (save-restriction
-	;; Widen buffer temporarily so if the buffer was narrowed within
-	;; the body of `atomic-change-group' all changes can be undone.
-	(widen)
-	(let ((old-car (car-safe elt))
-	      (old-cdr (cdr-safe elt)))
-          (unwind-protect
-              (progn
-                ;; Temporarily truncate the undo log at ELT.
-                (when (consp elt)
-                  (setcar elt nil) (setcdr elt nil))
-                (unless (eq last-command 'undo) (undo-start))
-                ;; Make sure there's no confusion.
-                (when (and (consp elt) (not (eq elt (last pending-undo-list))))
-                  (error "Undoing to some unrelated state"))
-                ;; Undo it all.
-                (save-excursion
-                  (while (listp pending-undo-list) (undo-more 1)))
-                ;; Revert the undo info to what it was when we grabbed
-                ;; the state.
-                (setq buffer-undo-list elt))
-            ;; Reset the modified cons cell ELT to its original content.
-            (when (consp elt)
-              (setcar elt old-car)
-              (setcdr elt old-cdr))))))))
-
 ;;;; Display-related functions.
 
 ;; For compatibility.
@@ -3666,41 +3441,8 @@ meaning as for `replace-match'."
       (replace-match replacement fixedcase literal match subexp))))
 
 
-(defun looking-back (regexp &optional limit greedy)
-  "Return non-nil if text before point matches regular expression REGEXP.
-Like `looking-at' except matches before point, and is slower.
-LIMIT if non-nil speeds up the search by specifying a minimum
-starting position, to avoid checking matches that would start
-before LIMIT.
-
-If GREEDY is non-nil, extend the match backwards as far as
-possible, stopping when a single additional previous character
-cannot be part of a match for REGEXP.  When the match is
-extended, its starting position is allowed to occur before
-LIMIT.
-
-As a general recommendation, try to avoid using `looking-back'
-wherever possible, since it is slow."
-  (declare
-   (advertised-calling-convention (regexp limit &optional greedy) "25.1"))
-  (let ((start (point))
-	(pos
-	 (save-excursion
-	   (and (re-search-backward (concat "\\(?:" regexp "\\)\\=") limit t)
-		(point)))))
-    (if (and greedy pos)
-	(save-restriction
-	  (narrow-to-region (point-min) start)
-	  (while (and (> pos (point-min))
-		      (save-excursion
-			(goto-char pos)
-			(backward-char 1)
-			(looking-at (concat "\\(?:"  regexp "\\)\\'"))))
-	    (setq pos (1- pos)))
-	  (save-excursion
-	    (goto-char pos)
-	    (looking-at (concat "\\(?:"  regexp "\\)\\'")))))
-    (not (null pos))))
+(set-advertised-calling-convention
+ 'looking-back '(regexp limit &optional greedy) "25.1")
 
 (defsubst looking-at-p (regexp)
   "\
@@ -3895,64 +3637,6 @@ Unless optional argument INPLACE is non-nil, return a new string."
 	  (aset newstr i tochar)))
     newstr))
 
-(defun replace-regexp-in-string (regexp rep string &optional
-					fixedcase literal subexp start)
-  "Replace all matches for REGEXP with REP in STRING.
-
-Return a new string containing the replacements.
-
-Optional arguments FIXEDCASE, LITERAL and SUBEXP are like the
-arguments with the same names of function `replace-match'.  If START
-is non-nil, start replacements at that index in STRING.
-
-REP is either a string used as the NEWTEXT arg of `replace-match' or a
-function.  If it is a function, it is called with the actual text of each
-match, and its value is used as the replacement text.  When REP is called,
-the match data are the result of matching REGEXP against a substring
-of STRING, the same substring that is the actual text of the match which
-is passed to REP as its argument.
-
-To replace only the first match (if any), make REGEXP match up to \\\\='
-and replace a sub-expression, e.g.
-  (replace-regexp-in-string \"\\\\(foo\\\\).*\\\\\\='\" \"bar\" \" foo foo\" nil nil 1)
-    => \" bar foo\""
-
-  ;; To avoid excessive consing from multiple matches in long strings,
-  ;; don't just call `replace-match' continually.  Walk down the
-  ;; string looking for matches of REGEXP and building up a (reversed)
-  ;; list MATCHES.  This comprises segments of STRING which weren't
-  ;; matched interspersed with replacements for segments that were.
-  ;; [For a `large' number of replacements it's more efficient to
-  ;; operate in a temporary buffer; we can't tell from the function's
-  ;; args whether to choose the buffer-based implementation, though it
-  ;; might be reasonable to do so for long enough STRING.]
-  (let ((l (length string))
-	(start (or start 0))
-	matches str mb me)
-    (save-match-data
-      (while (and (< start l) (string-match regexp string start))
-	(setq mb (match-beginning 0)
-	      me (match-end 0))
-	;; If we matched the empty string, make sure we advance by one char
-	(when (= me mb) (setq me (min l (1+ mb))))
-	;; Generate a replacement for the matched substring.
-	;; Operate only on the substring to minimize string consing.
-	;; Set up match data for the substring for replacement;
-	;; presumably this is likely to be faster than munging the
-	;; match data directly in Lisp.
-	(string-match regexp (setq str (substring string mb me)))
-	(setq matches
-	      (cons (replace-match (if (stringp rep)
-				       rep
-				     (funcall rep (match-string 0 str)))
-				   fixedcase literal str subexp)
-		    (cons (substring string start mb) ; unmatched prefix
-			  matches)))
-	(setq start me))
-      ;; Reconstruct a string from the pieces.
-      (setq matches (cons (substring string start l) matches)) ; leftover
-      (apply #'concat (nreverse matches)))))
-
 (defun string-prefix-p (prefix string &optional ignore-case)
   "Return non-nil if PREFIX is a prefix of STRING.
 If IGNORE-CASE is non-nil, the comparison is done without paying attention
@@ -3989,12 +3673,6 @@ consisting of STR followed by an invisible left-to-right mark
       (concat str (propertize (string ?\x200e) 'invisible t))
     str))
 
-(defun string-greaterp (string1 string2)
-  "Return non-nil if STRING1 is greater than STRING2 in lexicographic order.
-Case is significant.
-Symbols are also allowed; their print names are used instead."
-  (string-lessp string2 string1))
-
 
 ;;;; Specifying things to do later.
 
@@ -4677,60 +4355,6 @@ To test whether a function can be called interactively, use
 (define-obsolete-function-alias
   'set-temporary-overlay-map 'set-transient-map "24.4")
 
-(defun set-transient-map (map &optional keep-pred on-exit)
-  "Set MAP as a temporary keymap taking precedence over other keymaps.
-Normally, MAP is used only once, to look up the very next key.
-However, if the optional argument KEEP-PRED is t, MAP stays
-active if a key from MAP is used.  KEEP-PRED can also be a
-function of no arguments: it is called from `pre-command-hook' and
-if it returns non-nil, then MAP stays active.
-
-Optional arg ON-EXIT, if non-nil, specifies a function that is
-called, with no arguments, after MAP is deactivated.
-
-This uses `overriding-terminal-local-map' which takes precedence over all other
-keymaps.  As usual, if no match for a key is found in MAP, the normal key
-lookup sequence then continues.
-
-This returns an \"exit function\", which can be called with no argument
-to deactivate this transient map, regardless of KEEP-PRED."
-  (let* ((clearfun (make-symbol "clear-transient-map"))
-         (exitfun
-          (lambda ()
-            (internal-pop-keymap map 'overriding-terminal-local-map)
-            (remove-hook 'pre-command-hook clearfun)
-            (when on-exit (funcall on-exit)))))
-    ;; Don't use letrec, because equal (in add/remove-hook) would get trapped
-    ;; in a cycle.
-    (fset clearfun
-          (lambda ()
-            (with-demoted-errors "set-transient-map PCH: %S"
-              (unless (cond
-                       ((null keep-pred) nil)
-                       ((and (not (eq map (cadr overriding-terminal-local-map)))
-                             (memq map (cddr overriding-terminal-local-map)))
-                        ;; There's presumably some other transient-map in
-                        ;; effect.  Wait for that one to terminate before we
-                        ;; remove ourselves.
-                        ;; For example, if isearch and C-u both use transient
-                        ;; maps, then the lifetime of the C-u should be nested
-                        ;; within isearch's, so the pre-command-hook of
-                        ;; isearch should be suspended during the C-u one so
-                        ;; we don't exit isearch just because we hit 1 after
-                        ;; C-u and that 1 exits isearch whereas it doesn't
-                        ;; exit C-u.
-                        t)
-                       ((eq t keep-pred)
-                        (let ((mc (lookup-key map (this-command-keys-vector))))
-                          ;; If the key is unbound `this-command` is
-                          ;; nil and so is `mc`.
-                          (and mc (eq this-command mc))))
-                       (t (funcall keep-pred)))
-                (funcall exitfun)))))
-    (add-hook 'pre-command-hook clearfun)
-    (internal-push-keymap map 'overriding-terminal-local-map)
-    exitfun))
-
 ;;;; Progress reporters.
 
 ;; Progress reporter has the following structure:
@@ -95,10 +95,19 @@ Return the pasted text as a string."
 	(decode-coding-region (point-min) (point) (keyboard-coding-system)
                               t)))))
 
-(defun xterm-paste ()
-  "Handle the start of a terminal paste operation."
-  (interactive)
-  (let* ((pasted-text (xterm--pasted-text))
+(defun xterm-paste (&optional event)
+  "Handle a terminal paste operation.
+EVENT is either an `xterm-paste' event carrying the pasted text, or
+the start of the paste, in which case the rest of it is read here.
+When EVENT is nil, as in calls from code written for the old
+argument-less version, the rest of the paste is read as well."
+  (interactive "e")
+  (let* ((pasted-text
+          (if (eq (car-safe event) 'xterm-paste)
+              (decode-coding-string
+               (subst-char-in-string ?\r ?\n (nth 1 event) t)
+               (keyboard-coding-system) t)
+            (xterm--pasted-text)))
          (interprogram-paste-function (lambda () pasted-text)))
     (yank)))
 
@@ -822,15 +831,12 @@ We run the first FUNCTION whose STRING matches the input events."
 
 (defun xterm--init-modify-other-keys ()
   "Terminal initialization for xterm's modifyOtherKeys support."
-  (send-string-to-terminal "\e[>4;1m")
-  (push "\e[>4m" (terminal-parameter nil 'tty-mode-reset-strings))
-  (push "\e[>4;1m" (terminal-parameter nil 'tty-mode-set-strings)))
+  (tty-enable-protocol 'modify-other-keys))
 
 (defun xterm--init-bracketed-paste-mode ()
-  "Terminal initialization for bracketed paste mode."
-  (send-string-to-terminal "\e[?2004h")
-  (push "\e[?2004l" (terminal-parameter nil 'tty-mode-reset-strings))
-  (push "\e[?2004h" (terminal-parameter nil 'tty-mode-set-strings)))
+  "Terminal initialization for bracketed paste mode.
+This also asks the terminal for the kitty keyboard protocol."
+  (tty-negotiate-protocols))
 
 (defun xterm--init-activate-get-selection ()
   "Terminal initialization for `gui-get-selection'."
@@ -868,34 +874,28 @@ The title is constructed from `frame-title-format'."
    (format "\e]2;%s\a" (format-mode-line frame-title-format))
    terminal))
 
-(defun xterm--selection-char (type)
-  (pcase type
-    ('PRIMARY "p")
-    ('CLIPBOARD "c")
-    (_ (error "Invalid selection type: %S" type))))
-
 (cl-defmethod gui-backend-get-selection
     (type data-type
      &context (window-system nil)
               ;; Only applies to terminals which have it enabled.
               ((terminal-parameter nil 'xterm--get-selection) (eql t)))
+  "Return the contents of the X selection using the OSC 52 escape sequence.
+The terminal may only be asked as `tty-clipboard-allow-read' says.
+If it does not answer within `xterm-query-timeout' seconds, it is
+not asked again."
   (unless (eq data-type 'STRING)
     (error "Unsupported data type %S" data-type))
-  (let* ((screen (eq (terminal-parameter nil 'terminal-initted)
-                     'terminal-init-screen))
-         (query (concat "\e]52;" (xterm--selection-char type) ";")))
-    (with-temp-buffer
-      (set-buffer-multibyte nil)
-      (xterm--query
-       (concat (when screen "\eP") query "?\a" (when screen "\e\\"))
-       (list (cons query (lambda ()
-                           (while (let ((char (read-char)))
-                                    (unless (eq char ?\a)
-                                      (insert char)
-                                      t))))))
-       'no-async)
-      (base64-decode-region (point-min) (point-max))
-      (decode-coding-region (point-min) (point-max) 'utf-8-unix t))))
+  (let ((text (tty-clipboard-get type xterm-query-timeout)))
+    (when (and (null text)
+               (or (eq tty-clipboard-allow-read t)
+                   (and (eq tty-clipboard-allow-read 'ask)
+                        (eq (terminal-parameter nil 'tty-clipboard-read-allowed)
+                            t))))
+      ;; Either the terminal doesn't support reading the selection,
+      ;; or it has it disabled.  Don't make every yank wait for it.
+      (set-terminal-parameter nil 'xterm--get-selection nil)
+      (message "Terminal did not send the selection; not asking again"))
+    text))
 
 (cl-defmethod gui-backend-set-selection
     (type data
@@ -915,31 +915,17 @@ If the resulting OSC 52 sequence would be longer than
 `xterm-max-cut-length', then the TEXT is not sent to the system
 clipboard.
 
-This function either sends a raw OSC 52 sequence or wraps the OSC
-52 in a Device Control String sequence.  This way, it will work
-on a bare terminal emulators as well as inside the screen
-program.  When inside the screen program, this function also
-chops long DCS sequences into multiple smaller ones to avoid
-hitting screen's max DCS length."
-  (let* ((screen (eq (terminal-parameter nil 'terminal-initted)
-                     'terminal-init-screen))
-         (bytes (encode-coding-string data 'utf-8-unix))
-         (base-64 (if screen
-                      (replace-regexp-in-string
-                       "\n" "\e\\\eP"
-                       (base64-encode-string bytes)
-                       :fixedcase :literal)
-                    (base64-encode-string bytes :no-line-break)))
-         (length (length base-64)))
+See `tty-clipboard-set', which sends the sequence, also inside the
+screen program."
+  (let ((length (* 4 (/ (+ (string-bytes (encode-coding-string
+                                          data 'utf-8-unix))
+                           2)
+                        3))))
     (if (> length xterm-max-cut-length)
         (progn
           (warn "Selection too long to send to terminal: %d bytes" length)
           (sit-for 2))
-      (send-string-to-terminal
-       (concat
-        (when screen "\eP")
-        "\e]52;" (xterm--selection-char type) ";" base-64 "\a"
-        (when screen "\e\\"))))))
+      (tty-clipboard-set data type))))
 
 (defun xterm-rgb-convert-to-16bit (prim)
   "Convert an 8-bit primary color value PRIM to a corresponding 16-bit value."
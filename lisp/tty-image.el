@@ -0,0 +1,166 @@
+;;; tty-image.el --- display images on text terminals  -*- lexical-binding: t -*-
+
+;; Copyright (C) 2018 Free Software Foundation, Inc.
+
+;; Maintainer: emacs-devel@gnu.org
+;; Keywords: multimedia, terminals
+
+;; This file is part of GNU Emacs.
+
+;; GNU Emacs is free software: you can redistribute it and/or modify
+;; it under the terms of the GNU General Public License as published by
+;; the Free Software Foundation, either version 3 of the License, or
+;; (at your option) any later version.
+
+;; GNU Emacs is distributed in the hope that it will be useful,
+;; but WITHOUT ANY WARRANTY; without even the implied warranty of
+;; MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+;; GNU General Public License for more details.
+
+;; You should have received a copy of the GNU General Public License
+;; along with GNU Emacs.  If not, see <https://www.gnu.org/licenses/>.
+
+;;; Commentary:
+
+;; Terminals that support the kitty graphics protocol or sixel
+;; graphics can show images, which `tty-negotiate-protocols' finds
+;; out.  Redisplay does not know about such images, so an image is
+;; put in the buffer as an overlay that displays blank lines of its
+;; size, and the images of the overlays that are visible are drawn
+;; over these blanks when redisplay is done.
+;;
+;; Images can be PNG files, or binary PPM and PGM files.  `image-mode'
+;; and Org's inline images use this when the frame can't display
+;; images otherwise.
+
+;;; Code:
+
+(defvar tty-image--drawn nil
+  "Alist of the images last drawn on each frame.
+The elements are (FRAME . PLACEMENTS), where PLACEMENTS is a list
+of the arguments passed to `tty-draw-image'.")
+
+(defvar tty-image--timer nil
+  "The idle timer that draws the images after redisplay.")
+
+;;;###autoload
+(defun tty-image-available-p (&optional frame)
+  "Return non-nil if images can be put on text terminal FRAME.
+FRAME defaults to the selected frame.  The value is the protocol
+used to draw them, see `tty-graphics-protocol'."
+  (tty-graphics-protocol frame))
+
+(defun tty-image--blank (cols rows)
+  "Return a string of ROWS lines of COLS spaces."
+  (mapconcat #'identity (make-list rows (make-string cols ?\s)) "\n"))
+
+(defun tty-image--size (data window)
+  "Return the size (COLS . ROWS) of the image in DATA on WINDOW.
+The image is scaled down to fit into WINDOW, if it is larger."
+  (let* ((size (tty-image-size data))
+         (cell (tty-cell-size (window-frame window)))
+         (max-width (* (car cell) (max 1 (1- (window-body-width window)))))
+         (max-height (* (cdr cell) (max 1 (1- (window-body-height window)))))
+         (scale (min 1.0
+                     (/ (float max-width) (car size))
+                     (/ (float max-height) (cdr size)))))
+    (cons (max 1 (ceiling (* scale (car size)) (car cell)))
+          (max 1 (ceiling (* scale (cdr size)) (cdr cell))))))
+
+;;;###autoload
+(defun tty-image-put (beg end file-or-data &optional data-p)
+  "Show an image in place of the text between BEG and END.
+FILE-OR-DATA is the name of a PNG, PPM or PGM file, or if DATA-P is
+non-nil, a unibyte string with its contents.  The image is scaled
+down to fit into the window showing the current buffer.
+
+Return the overlay that shows the image.  Deleting it removes the
+image again."
+  (let* ((data (if data-p
+                   file-or-data
+                 (with-temp-buffer
+                   (set-buffer-multibyte nil)
+                   (insert-file-contents-literally file-or-data)
+                   (buffer-string))))
+         (size (tty-image--size data (or (get-buffer-window) (selected-window))))
+         (ov (make-overlay beg end nil t)))
+    (overlay-put ov 'display (tty-image--blank (car size) (cdr size)))
+    (overlay-put ov 'tty-image data)
+    (overlay-put ov 'tty-image-size size)
+    (overlay-put ov 'evaporate t)
+    (add-hook 'post-command-hook #'tty-image--schedule)
+    (add-hook 'window-configuration-change-hook #'tty-image--schedule)
+    (tty-image--schedule)
+    ov))
+
+(defun tty-image-remove (beg end)
+  "Remove the images put between BEG and END with `tty-image-put'."
+  (dolist (ov (overlays-in beg end))
+    (when (overlay-get ov 'tty-image)
+      (delete-overlay ov)))
+  (tty-image--schedule))
+
+(defun tty-image-refresh ()
+  "Draw the images on the selected frame again.
+This is needed when the terminal was cleared by something other
+than Emacs."
+  (interactive)
+  (setq tty-image--drawn (assq-delete-all (selected-frame) tty-image--drawn))
+  (tty-image--schedule))
+
+(defun tty-image--schedule ()
+  "Arrange for the images to be drawn after the next redisplay."
+  (unless tty-image--timer
+    (setq tty-image--timer (run-with-idle-timer 0 nil #'tty-image--draw))))
+
+(defun tty-image--placements (frame)
+  "Return the images to be drawn on FRAME, as arguments to `tty-draw-image'.
+Only the images whose first line is visible are drawn, cut off at
+the bottom of their window."
+  (let (placements)
+    (dolist (window (window-list frame 'no-minibuf))
+      (let ((edges (window-inside-edges window))
+            (start (window-start window))
+            (end (window-end window t)))
+        (with-current-buffer (window-buffer window)
+          (dolist (ov (overlays-in start end))
+            (let ((data (overlay-get ov 'tty-image))
+                  (size (overlay-get ov 'tty-image-size))
+                  (posn (posn-at-point (overlay-start ov) window)))
+              (when (and data posn (>= (overlay-start ov) start))
+                (let* ((col-row (posn-col-row posn))
+                       (x (+ (nth 0 edges) (car col-row)))
+                       (y (+ (nth 1 edges) (cdr col-row)))
+                       (visible (min (cdr size) (- (nth 3 edges) y))))
+                  (when (> visible 0)
+                    (push (list data x y (car size) (cdr size) visible)
+                          placements)))))))))
+    (nreverse placements)))
+
+(defun tty-image--draw ()
+  "Draw the images that are visible on the text terminal frames.
+Frames are only drawn on when their images changed since the last
+time."
+  (setq tty-image--timer nil)
+  (let (drawn)
+    (dolist (frame (frame-list))
+      (when (and (frame-visible-p frame) (tty-image-available-p frame))
+        (let ((placements (tty-image--placements frame))
+              (old (cdr (assq frame tty-image--drawn))))
+          (unless (equal placements old)
+            (if (eq (tty-graphics-protocol frame) 'kitty)
+                (tty-clear-images frame)
+              ;; Sixel images only go away when the text over them
+              ;; is written again.
+              (when old
+                (redraw-frame frame)
+                (redisplay t)))
+            (dolist (placement placements)
+              (apply #'tty-draw-image (append placement (list frame)))))
+          (when placements
+            (push (cons frame placements) drawn)))))
+    (setq tty-image--drawn drawn)))
+
+(provide 'tty-image)
+
+;;; tty-image.el ends here
@@ -0,0 +1,88 @@
+;;; undo-persist.el --- keep undo history across sessions  -*- lexical-binding: t -*-
+
+;; Copyright (C) 2018 Free Software Foundation, Inc.
+
+;; Maintainer: emacs-devel@gnu.org
+;; Keywords: convenience, files
+
+;; This file is part of GNU Emacs.
+
+;; GNU Emacs is free software: you can redistribute it and/or modify
+;; it under the terms of the GNU General Public License as published by
+;; the Free Software Foundation, either version 3 of the License, or
+;; (at your option) any later version.
+
+;; GNU Emacs is distributed in the hope that it will be useful,
+;; but WITHOUT ANY WARRANTY; without even the implied warranty of
+;; MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+;; GNU General Public License for more details.
+
+;; You should have received a copy of the GNU General Public License
+;; along with GNU Emacs.  If not, see <https://www.gnu.org/licenses/>.
+
+;;; Commentary:
+
+;; When `undo-persist-mode' is on, the undo history of a buffer is
+;; saved to a file in `undo-persist-directory' whenever the buffer is
+;; saved, and restored when the file is visited again, provided it
+;; still has the text it had when the history was saved.  Saving and
+;; restoring is done by `undo-persist-save' and `undo-persist-load'.
+
+;;; Code:
+
+(defgroup undo-persist nil
+  "Keeping undo history across sessions."
+  :group 'undo
+  :version "27.1")
+
+(defcustom undo-persist-directory (locate-user-emacs-file "undo/")
+  "Directory where `undo-persist-mode' keeps the undo history of files."
+  :type 'directory
+  :version "27.1")
+
+(defun undo-persist-file-name (file)
+  "Return the name of the file holding the undo history of FILE.
+This is FILE's true name in `undo-persist-directory', with each
+`/' replaced by `!' and each `!' by `!!', as for backup files."
+  (expand-file-name
+   (concat (subst-char-in-string
+            ?/ ?! (replace-regexp-in-string "!" "!!" (file-truename file)))
+           ".undo")
+   undo-persist-directory))
+
+(defun undo-persist--save ()
+  "Save the undo history of the current buffer's file."
+  (when buffer-file-name
+    (with-demoted-errors "Error saving undo history: %S"
+      ;; The history holds deleted text, so keep it private.
+      (with-file-modes #o700
+        (make-directory undo-persist-directory t))
+      (with-file-modes #o600
+        (undo-persist-save (undo-persist-file-name buffer-file-name))))))
+
+(defun undo-persist--load ()
+  "Restore the undo history of the current buffer's file."
+  (when buffer-file-name
+    (with-demoted-errors "Error restoring undo history: %S"
+      (undo-persist-load (undo-persist-file-name buffer-file-name)))))
+
+;;;###autoload
+(define-minor-mode undo-persist-mode
+  "Toggle keeping the undo history of files across sessions.
+
+When enabled, the undo history of a buffer visiting a file is
+saved to `undo-persist-directory' each time the buffer is saved,
+and restored when the file is visited again if its contents have
+not changed since."
+  :global t
+  :group 'undo-persist
+  (if undo-persist-mode
+      (progn
+        (add-hook 'after-save-hook #'undo-persist--save)
+        (add-hook 'find-file-hook #'undo-persist--load))
+    (remove-hook 'after-save-hook #'undo-persist--save)
+    (remove-hook 'find-file-hook #'undo-persist--load)))
+
+(provide 'undo-persist)
+
+;;; undo-persist.el ends here
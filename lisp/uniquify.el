@@ -172,9 +172,6 @@ contains the name of the directory which the buffer is visiting.")
 	     (base dirname buffer &optional proposed)))
   base dirname buffer proposed)
 
-;; Internal variables used free
-(defvar uniquify-possibly-resolvable nil)
-
 (defvar-local uniquify-managed nil
   "Non-nil if the name of this buffer is managed by uniquify.
 It actually holds the list of `uniquify-item's corresponding to the conflict.")
@@ -200,49 +197,7 @@ this rationalization."
 	     (uniquify-item-base (car uniquify-managed)) (buffer-name))
 	 (uniquify-buffer-file-name (current-buffer))
 	 (current-buffer)))
-  ;; Make sure we don't get confused by outdated uniquify-managed info in
-  ;; this buffer.
-  (with-current-buffer newbuf (setq uniquify-managed nil))
-  (when dirname
-    (setq dirname (expand-file-name (directory-file-name dirname)))
-    (let ((fix-list (list (uniquify-make-item base dirname newbuf)))
-	  items)
-      (dolist (buffer (buffer-list))
-	(when (and (not (and uniquify-ignore-buffers-re
-			     (string-match uniquify-ignore-buffers-re
-					   (buffer-name buffer))))
-		   ;; Only try to rename buffers we actually manage.
-		   (setq items (buffer-local-value 'uniquify-managed buffer))
-		   (equal base (uniquify-item-base (car items)))
-		   ;; Don't re-add stuff we already have.  Actually this
-		   ;; whole `and' test should only match at most once.
-		   (not (memq (car items) fix-list)))
-	  (unless (cdr items)
-	    ;; If there was no conflict, the buffer-name is equal to the
-	    ;; base-name and we may have missed a rename-buffer because
-	    ;; of code like in set-visited-file-name:
-	    ;; (or (string= new-name (buffer-name)) (rename-buffer new-name t))
-	    ;; So we need to refresh the dirname of the uniquify-item.
-	    (setf (uniquify-item-dirname (car items))
-		  (uniquify-buffer-file-name
-		   (uniquify-item-buffer (car items))))
-	    ;; This shouldn't happen, but maybe there's no dirname any more.
-	    (unless (uniquify-item-dirname (car items))
-	      (with-current-buffer (uniquify-item-buffer (car items))
-		(setq uniquify-managed nil))
-	      (setq items nil)))
-          ;; In case we missed some calls to kill-buffer, there may be dead
-          ;; buffers in uniquify-managed, so filter them out.
-          (setq items
-                (delq nil (mapcar
-                           (lambda (item)
-                             (if (buffer-live-p (uniquify-item-buffer item))
-                                 item))
-                           items)))
-	  (setq fix-list (append fix-list items))))
-      ;; selects buffers whose names may need changing, and others that
-      ;; may conflict, then bring conflicting names together
-      (uniquify-rationalize fix-list))))
+  (uniquify--rationalize-file-buffer-names base dirname newbuf))
 
 ;; uniquify's version of buffer-file-name; result never contains trailing slash
 (defun uniquify-buffer-file-name (buffer)
@@ -268,153 +223,7 @@ in `uniquify-list-buffers-directory-modes', otherwise returns nil."
 	(unless (or (eq buf (current-buffer)) (not (buffer-live-p buf)))
 	  (push item new-fix-list))))
     (when new-fix-list
-      (uniquify-rationalize new-fix-list))))
-
-(defun uniquify-rationalize (fix-list)
-  ;; Set up uniquify to re-rationalize after killing/renaming
-  ;; if there is a conflict.
-  (dolist (item fix-list)
-    (with-current-buffer (uniquify-item-buffer item)
-      ;; Refresh the dirnames and proposed names.
-      (setf (uniquify-item-proposed item)
-	    (uniquify-get-proposed-name (uniquify-item-base item)
-					(uniquify-item-dirname item)))
-      (setq uniquify-managed fix-list)))
-  ;; Strip any shared last directory names of the dirname.
-  (when (and (cdr fix-list) uniquify-strip-common-suffix)
-    (let ((strip t))
-      (while (let* ((base (file-name-nondirectory
-			   (uniquify-item-dirname (car fix-list))))
-		    (items fix-list))
-	       (when (> (length base) 0)
-		 (while (and strip items)
-		   (unless (equal base (file-name-nondirectory
-					(uniquify-item-dirname (pop items))))
-		     (setq strip nil)))
-		 strip))
-	;; It's all the same => strip.
-	(dolist (item (prog1 fix-list (setq fix-list nil)))
-	  ;; Create new items because the old ones are kept (with the true
-	  ;; `dirname') for later rerationalizing.
-	  (push (uniquify-make-item (uniquify-item-base item)
-				    (let ((f (file-name-directory
-					      (uniquify-item-dirname item))))
-				      (and f (directory-file-name f)))
-				    (uniquify-item-buffer item)
-				    (uniquify-item-proposed item))
-		fix-list)))))
-  ;; If uniquify-min-dir-content is 0, this will end up just
-  ;; passing fix-list to uniquify-rationalize-conflicting-sublist.
-  (uniquify-rationalize-a-list fix-list))
-
-(defun uniquify-item-greaterp (item1 item2)
-  (string-lessp (uniquify-item-proposed item2)
-		(uniquify-item-proposed item1)))
-
-(defun uniquify-rationalize-a-list (fix-list &optional depth)
-  (unless depth (setq depth uniquify-min-dir-content))
-  (let (conflicting-sublist	; all elements have the same proposed name
-	(old-proposed "")
-	proposed)
-    ;; Divide fix-list into items with same proposed names and pass them
-    ;; to uniquify-rationalize-conflicting-sublist.
-    (dolist (item (sort (copy-sequence fix-list) 'uniquify-item-greaterp))
-      (setq proposed (uniquify-item-proposed item))
-      (unless (equal proposed old-proposed)
-	(uniquify-rationalize-conflicting-sublist conflicting-sublist
-						  old-proposed depth)
-	(setq conflicting-sublist nil))
-      (push item conflicting-sublist)
-      (setq old-proposed proposed))
-    (uniquify-rationalize-conflicting-sublist conflicting-sublist
-					      old-proposed depth)))
-
-(defun uniquify-get-proposed-name (base dirname &optional depth)
-  (unless depth (setq depth uniquify-min-dir-content))
-  (cl-assert (equal (directory-file-name dirname) dirname)) ;No trailing slash.
-
-  ;; Distinguish directories by adding extra separator.
-  (if (and uniquify-trailing-separator-p
-	   (file-directory-p (expand-file-name base dirname))
-	   (not (string-equal base "")))
-      (cond ((eq uniquify-buffer-name-style 'forward)
-	     (setq base (file-name-as-directory base)))
-	    ;; (setq base (concat base "/")))
-	    ((eq uniquify-buffer-name-style 'reverse)
-	     (setq base (concat (or uniquify-separator "\\") base)))))
-
-  (let ((extra-string nil)
-	(n depth))
-    (while (and (> n 0) dirname)
-      (let ((file (file-name-nondirectory dirname)))
-	(when (setq dirname (file-name-directory dirname))
-	  (setq dirname (directory-file-name dirname)))
-	(setq n (1- n))
-	(push (if (zerop (length file)) ;nil or "".
-		  (prog1 (or (file-remote-p dirname) "")
-		    (setq dirname nil)) ;Could be `dirname' iso "".
-		file)
-	      extra-string)))
-    (when (zerop n)
-      (if (and dirname extra-string
-	       (equal dirname (file-name-directory dirname)))
-	  ;; We're just before the root.  Let's add the leading / already.
-	  ;; With "/a/b"+"/c/d/b" this leads to "/a/b" and "d/b" but with
-	  ;; "/a/b"+"/c/a/b" this leads to "/a/b" and "a/b".
-	  (push "" extra-string))
-      (setq uniquify-possibly-resolvable t))
-
-    (cond
-     ((null extra-string) base)
-     ((string-equal base "") ;Happens for dired buffers on the root directory.
-      (mapconcat 'identity extra-string "/"))
-     ((eq uniquify-buffer-name-style 'reverse)
-      (mapconcat 'identity
-		 (cons base (nreverse extra-string))
-		 (or uniquify-separator "\\")))
-     ((eq uniquify-buffer-name-style 'forward)
-      (mapconcat 'identity (nconc extra-string (list base))
-		 "/"))
-     ((eq uniquify-buffer-name-style 'post-forward)
-      (concat base (or uniquify-separator "|")
-	      (mapconcat 'identity extra-string "/")))
-     ((eq uniquify-buffer-name-style 'post-forward-angle-brackets)
-      (concat base "<" (mapconcat 'identity extra-string "/")
-	      ">"))
-     (t (error "Bad value for uniquify-buffer-name-style: %s"
-	       uniquify-buffer-name-style)))))
-
-
-;; Deal with conflicting-sublist, all of whose elements have identical
-;; "base" components.
-(defun uniquify-rationalize-conflicting-sublist (conf-list old-name depth)
-  (when conf-list
-    (if (or (cdr conf-list)
-	    ;; Check that the proposed name doesn't conflict with some
-	    ;; existing buffer.
-	    (let ((buf (get-buffer old-name)))
-	      (and buf (not (eq buf (uniquify-item-buffer (car conf-list)))))))
-	(when uniquify-possibly-resolvable
-	  (setq uniquify-possibly-resolvable nil
-		depth (1+ depth))
-	  (dolist (item conf-list)
-	    (setf (uniquify-item-proposed item)
-		  (uniquify-get-proposed-name
-		   (uniquify-item-base item)
-		   (uniquify-item-dirname item)
-		   depth)))
-	  (uniquify-rationalize-a-list conf-list depth))
-      (unless (string= old-name "")
-	(uniquify-rename-buffer (car conf-list) old-name)))))
-
-
-(defun uniquify-rename-buffer (item newname)
-  (let ((buffer (uniquify-item-buffer item)))
-    (unless (equal newname (buffer-name buffer))
-      (with-current-buffer buffer
-	(let ((uniquify-buffer-name-style nil))	;Avoid hooks on rename-buffer.
-	  ;; Pass the `unique' arg, so the advice doesn't mark it as unmanaged.
-	  (rename-buffer newname t))))))
+      (uniquify--rationalize new-fix-list))))
 
 ;;; Hooks from the rest of Emacs
 
@@ -432,6 +241,7 @@ in `uniquify-list-buffers-directory-modes', otherwise returns nil."
 (defun uniquify-kill-buffer-function ()
   "Re-rationalize buffer names, ignoring current buffer.
 For use on `kill-buffer-hook'."
+  (uniquify--forget-buffer (current-buffer))
   (and uniquify-after-kill-buffer-p
        (uniquify-maybe-rerationalize-w/o-cb)))
 
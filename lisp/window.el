@@ -2440,110 +2440,6 @@ and no others."
 
 (defalias 'some-window 'get-window-with-predicate)
 
-(defun get-lru-window (&optional all-frames dedicated not-selected)
-   "Return the least recently used window on frames specified by ALL-FRAMES.
-Return a full-width window if possible.  A minibuffer window is
-never a candidate.  A dedicated window is never a candidate
-unless DEDICATED is non-nil, so if all windows are dedicated, the
-value is nil.  Avoid returning the selected window if possible.
-Optional argument NOT-SELECTED non-nil means never return the
-selected window.
-
-The following non-nil values of the optional argument ALL-FRAMES
-have special meanings:
-
-- t means consider all windows on all existing frames.
-
-- `visible' means consider all windows on all visible frames on
-  the current terminal.
-
-- 0 (the number zero) means consider all windows on all visible
-  and iconified frames on the current terminal.
-
-- A frame means consider all windows on that frame only.
-
-Any other value of ALL-FRAMES means consider all windows on the
-selected frame and no others."
-   (let (best-window best-time second-best-window second-best-time time)
-    (dolist (window (window-list-1 nil 'nomini all-frames))
-      (when (and (or dedicated (not (window-dedicated-p window)))
-		 (or (not not-selected) (not (eq window (selected-window)))))
-	(setq time (window-use-time window))
-	(if (or (eq window (selected-window))
-		(not (window-full-width-p window)))
-	    (when (or (not second-best-time) (< time second-best-time))
-	      (setq second-best-time time)
-	      (setq second-best-window window))
-	  (when (or (not best-time) (< time best-time))
-	    (setq best-time time)
-	    (setq best-window window)))))
-    (or best-window second-best-window)))
-
-(defun get-mru-window (&optional all-frames dedicated not-selected)
-   "Return the most recently used window on frames specified by ALL-FRAMES.
-A minibuffer window is never a candidate.  A dedicated window is
-never a candidate unless DEDICATED is non-nil, so if all windows
-are dedicated, the value is nil.  Optional argument NOT-SELECTED
-non-nil means never return the selected window.
-
-The following non-nil values of the optional argument ALL-FRAMES
-have special meanings:
-
-- t means consider all windows on all existing frames.
-
-- `visible' means consider all windows on all visible frames on
-  the current terminal.
-
-- 0 (the number zero) means consider all windows on all visible
-  and iconified frames on the current terminal.
-
-- A frame means consider all windows on that frame only.
-
-Any other value of ALL-FRAMES means consider all windows on the
-selected frame and no others."
-   (let (best-window best-time time)
-    (dolist (window (window-list-1 nil 'nomini all-frames))
-      (setq time (window-use-time window))
-      (when (and (or dedicated (not (window-dedicated-p window)))
-		 (or (not not-selected) (not (eq window (selected-window))))
-		 (or (not best-time) (> time best-time)))
-	(setq best-time time)
-	(setq best-window window)))
-    best-window))
-
-(defun get-largest-window (&optional all-frames dedicated not-selected)
-  "Return the largest window on frames specified by ALL-FRAMES.
-A minibuffer window is never a candidate.  A dedicated window is
-never a candidate unless DEDICATED is non-nil, so if all windows
-are dedicated, the value is nil.  Optional argument NOT-SELECTED
-non-nil means never return the selected window.
-
-The following non-nil values of the optional argument ALL-FRAMES
-have special meanings:
-
-- t means consider all windows on all existing frames.
-
-- `visible' means consider all windows on all visible frames on
-  the current terminal.
-
-- 0 (the number zero) means consider all windows on all visible
-  and iconified frames on the current terminal.
-
-- A frame means consider all windows on that frame only.
-
-Any other value of ALL-FRAMES means consider all windows on the
-selected frame and no others."
-  (let ((best-size 0)
-	best-window size)
-    (dolist (window (window-list-1 nil 'nomini all-frames))
-      (when (and (or dedicated (not (window-dedicated-p window)))
-		 (or (not not-selected) (not (eq window (selected-window)))))
-	(setq size (* (window-pixel-height window)
-		      (window-pixel-width window)))
-	(when (> size best-size)
-	  (setq best-size size)
-	  (setq best-window window))))
-    best-window))
 
 (defun get-buffer-window-list (&optional buffer-or-name minibuf all-frames)
   "Return list of all windows displaying BUFFER-OR-NAME, or nil if none.
@@ -4232,10 +4128,12 @@ This may be a useful alternative binding for \\[delete-other-windows]
 ;; `switch-to-prev-buffer' pushes the buffer it just replaced on it,
 ;; `switch-to-next-buffer' pops the last pushed buffer from it.
 
-;; Both `prev-buffers' and `next-buffers' may reference killed buffers
-;; if such a buffer was killed while the window was hidden within a
-;; window configuration.  Such killed buffers get removed whenever
-;; `switch-to-prev-buffer' or `switch-to-next-buffer' encounter them.
+;; `kill-buffer' removes killed buffers from `prev-buffers' and
+;; `next-buffers' of all live windows, see `window-prune-buffer-lists'.
+;; Both lists may still reference killed buffers if such a buffer was
+;; killed while the window was hidden within a window configuration.
+;; Such killed buffers get removed whenever `switch-to-prev-buffer' or
+;; `switch-to-next-buffer' encounter them.
 
 ;; The following function is called by `set-window-buffer' _before_ it
 ;; replaces the buffer of the argument window with the new buffer.
@@ -4273,7 +4171,10 @@ WINDOW must be a live window and defaults to the selected one."
 			       ;; (Bug#12588).
 			       point window-point-insertion-type)))))
 	  (set-window-prev-buffers
-	   window (cons entry (window-prev-buffers window)))))
+	   window (cons entry (window-prev-buffers window)))
+	  ;; Drop killed buffers and the oldest entries beyond
+	  ;; `window-buffer-lists-max'.
+	  (window-prune-buffer-lists window)))
 
       (run-hooks 'buffer-list-update-hook))))
 
@@ -5283,133 +5184,7 @@ right, if any."
 
 ;;; Balancing windows.
 
-;; The following routine uses the recycled code from an old version of
-;; `window--resize-child-windows'.  It's not very pretty, but coding it the way the
-;; new `window--resize-child-windows' code does would hardly make it any shorter or
-;; more readable (FWIW we'd need three loops - one to calculate the
-;; minimum sizes per window, one to enlarge or shrink windows until the
-;; new parent-size matches, and one where we shrink the largest/enlarge
-;; the smallest window).
-(defun balance-windows-2 (window horizontal)
-  "Subroutine of `balance-windows-1'.
-WINDOW must be a vertical combination (horizontal if HORIZONTAL
-is non-nil)."
-  (let* ((char-size (if window-resize-pixelwise
-			1
-		      (frame-char-size window horizontal)))
-	 (first (window-child window))
-	 (sub first)
-	 (number-of-children 0)
-	 (parent-size (window-new-pixel window))
-	 (total-sum parent-size)
-	 failed size sub-total sub-delta sub-amount rest)
-    (while sub
-      (setq number-of-children (1+ number-of-children))
-      (when (window-size-fixed-p sub horizontal)
-	(setq total-sum
-	      (- total-sum (window-size sub horizontal t)))
-	(set-window-new-normal sub 'ignore))
-      (setq sub (window-right sub)))
-
-    (setq failed t)
-    (while (and failed (> number-of-children 0))
-      (setq size (/ total-sum number-of-children))
-      (setq failed nil)
-      (setq sub first)
-      (while (and sub (not failed))
-	;; Ignore child windows that should be ignored or are stuck.
-	(unless (window--resize-child-windows-skip-p sub)
-	  (setq sub-total (window-size sub horizontal t))
-	  (setq sub-delta (- size sub-total))
-	  (setq sub-amount
-		(window-sizable sub sub-delta horizontal nil t))
-	  ;; Register the new total size for this child window.
-	  (set-window-new-pixel sub (+ sub-total sub-amount))
-	  (unless (= sub-amount sub-delta)
-	    (setq total-sum (- total-sum sub-total sub-amount))
-	    (setq number-of-children (1- number-of-children))
-	    ;; We failed and need a new round.
-	    (setq failed t)
-	    (set-window-new-normal sub 'skip)))
-	(setq sub (window-right sub))))
-
-    ;; How can we be sure that `number-of-children' is NOT zero here ?
-    (setq rest (% total-sum number-of-children))
-    ;; Fix rounding by trying to enlarge non-stuck windows by one line
-    ;; (column) until `rest' is zero.
-    (setq sub first)
-    (while (and sub (> rest 0))
-      (unless (window--resize-child-windows-skip-p window)
-	(set-window-new-pixel sub (min rest char-size) t)
-	(setq rest (- rest char-size)))
-      (setq sub (window-right sub)))
-
-    ;; Fix rounding by trying to enlarge stuck windows by one line
-    ;; (column) until `rest' equals zero.
-    (setq sub first)
-    (while (and sub (> rest 0))
-      (unless (eq (window-new-normal sub) 'ignore)
-	(set-window-new-pixel sub (min rest char-size) t)
-	(setq rest (- rest char-size)))
-      (setq sub (window-right sub)))
-
-    (setq sub first)
-    (while sub
-      ;; Record new normal sizes.
-      (set-window-new-normal
-       sub (/ (if (eq (window-new-normal sub) 'ignore)
-		  (window-size sub horizontal t)
-		(window-new-pixel sub))
-	      (float parent-size)))
-      ;; Recursively balance each window's child windows.
-      (balance-windows-1 sub horizontal)
-      (setq sub (window-right sub)))))
-
-(defun balance-windows-1 (window &optional horizontal)
-  "Subroutine of `balance-windows'."
-  (if (window-child window)
-      (let ((sub (window-child window)))
-	(if (window-combined-p sub horizontal)
-	    (balance-windows-2 window horizontal)
-	  (let ((size (window-new-pixel window)))
-	    (while sub
-	      (set-window-new-pixel sub size)
-	      (balance-windows-1 sub horizontal)
-	      (setq sub (window-right sub))))))))
-
-(defun balance-windows (&optional window-or-frame)
-  "Balance the sizes of windows of WINDOW-OR-FRAME.
-WINDOW-OR-FRAME is optional and defaults to the selected frame.
-If WINDOW-OR-FRAME denotes a frame, balance the sizes of all
-windows of that frame.  If WINDOW-OR-FRAME denotes a window,
-recursively balance the sizes of all child windows of that
-window."
-  (interactive)
-  (let* ((window
-	  (cond
-	   ((or (not window-or-frame)
-		(frame-live-p window-or-frame))
-	    (frame-root-window window-or-frame))
-	   ((or (window-live-p window-or-frame)
-		(window-child window-or-frame))
-	    window-or-frame)
-	   (t
-	    (error "Not a window or frame %s" window-or-frame))))
-	 (frame (window-frame window)))
-    ;; Balance vertically.
-    (window--resize-reset (window-frame window))
-    (balance-windows-1 window)
-    (when (window--resize-apply-p frame)
-      (window-resize-apply frame)
-      (window--pixel-to-total frame)
-      (run-window-configuration-change-hook frame))
-    ;; Balance horizontally.
-    (window--resize-reset (window-frame window) t)
-    (balance-windows-1 window t)
-    (when (window--resize-apply-p frame t)
-      (window-resize-apply frame t)
-      (window--pixel-to-total frame t)
-      (run-window-configuration-change-hook frame))))
+;; `balance-windows' is in windows.rs.
 
 (defun window-fixed-size-p (&optional window direction)
   "Return t if WINDOW cannot be resized in DIRECTION.
@@ -5428,105 +5203,7 @@ This is used by `balance-windows-area'.
 Changing this globally has no effect.")
 (make-variable-buffer-local 'window-area-factor)
 
-(defun balance-windows-area-adjust (window delta horizontal pixelwise)
-  "Wrapper around `window-resize' with error checking.
-Arguments WINDOW, DELTA and HORIZONTAL are passed on to that function."
-  ;; `window-resize' may fail if delta is too large.
-  (while (>= (abs delta) 1)
-    (condition-case nil
-        (progn
-	  ;; It was wrong to use `window-resize' here.  Somehow
-	  ;; `balance-windows-area' depends on resizing windows
-	  ;; asymmetrically.
-	  (adjust-window-trailing-edge window delta horizontal pixelwise)
-          (setq delta 0))
-      (error
-       ;;(message "adjust: %s" (error-message-string err))
-       (setq delta (/ delta 2))))))
-
-(defun balance-windows-area ()
-  "Make all visible windows the same area (approximately).
-See also `window-area-factor' to change the relative size of
-specific buffers."
-  (interactive)
-  (let* ((unchanged 0) (carry 0) (round 0)
-         ;; Remove fixed-size windows.
-         (wins (delq nil (mapcar (lambda (win)
-                                   (if (not (window-fixed-size-p win)) win))
-                                 (window-list nil 'nomini))))
-         (changelog nil)
-	 (pixelwise window-resize-pixelwise)
-	 next)
-    ;; Resizing a window changes the size of surrounding windows in complex
-    ;; ways, so it's difficult to balance them all.  The introduction of
-    ;; `adjust-window-trailing-edge' made it a bit easier, but it is still
-    ;; very difficult to do.  `balance-window' above takes an off-line
-    ;; approach: get the whole window tree, then balance it, then try to
-    ;; adjust the windows so they fit the result.
-    ;; Here, instead, we take a "local optimization" approach, where we just
-    ;; go through all the windows several times until nothing needs to be
-    ;; changed.  The main problem with this approach is that it's difficult
-    ;; to make sure it terminates, so we use some heuristic to try and break
-    ;; off infinite loops.
-    ;; After a round without any change, we allow a second, to give a chance
-    ;; to the carry to propagate a minor imbalance from the end back to
-    ;; the beginning.
-    (while (< unchanged 2)
-      ;; (message "New round")
-      (setq unchanged (1+ unchanged) round (1+ round))
-      (dolist (win wins)
-        (setq next win)
-        (while (progn (setq next (next-window next))
-                      (window-fixed-size-p next)))
-        ;; (assert (eq next (or (cadr (member win wins)) (car wins))))
-        (let* ((horiz
-                (< (car (window-pixel-edges win)) (car (window-pixel-edges next))))
-               (areadiff (/ (- (* (window-size next nil pixelwise)
-				  (window-size next t pixelwise)
-                                  (buffer-local-value 'window-area-factor
-                                                      (window-buffer next)))
-                               (* (window-size win nil pixelwise)
-				  (window-size win t pixelwise)
-                                  (buffer-local-value 'window-area-factor
-                                                      (window-buffer win))))
-                            (max (buffer-local-value 'window-area-factor
-                                                     (window-buffer win))
-                                 (buffer-local-value 'window-area-factor
-                                                     (window-buffer next)))))
-               (edgesize (if horiz
-                             (+ (window-size win nil pixelwise)
-				(window-size next nil pixelwise))
-                           (+ (window-size win t pixelwise)
-			      (window-size next t pixelwise))))
-               (diff (/ areadiff edgesize)))
-          (when (zerop diff)
-            ;; Maybe diff is actually closer to 1 than to 0.
-            (setq diff (/ (* 3 areadiff) (* 2 edgesize))))
-          (when (and (zerop diff) (not (zerop areadiff)))
-            (setq diff (/ (+ areadiff carry) edgesize))
-            ;; Change things smoothly.
-            (if (or (> diff 1) (< diff -1)) (setq diff (/ diff 2))))
-          (if (zerop diff)
-              ;; Make sure negligible differences don't accumulate to
-              ;; become significant.
-              (setq carry (+ carry areadiff))
-	    ;; This used `adjust-window-trailing-edge' before and uses
-	    ;; `window-resize' now.  Error wrapping is still needed.
-	    (balance-windows-area-adjust win diff horiz pixelwise)
-            ;; (sit-for 0.5)
-            (let ((change (cons win (window-pixel-edges win))))
-              ;; If the same change has been seen already for this window,
-              ;; we're most likely in an endless loop, so don't count it as
-              ;; a change.
-              (unless (member change changelog)
-                (push change changelog)
-                (setq unchanged 0 carry 0)))))))
-    ;; We've now basically balanced all the windows.
-    ;; But there may be some minor off-by-one imbalance left over,
-    ;; so let's do some fine tuning.
-    ;; (bw-finetune wins)
-    ;; (message "Done in %d rounds" round)
-    ))
+;; `balance-windows-area' is in windows.rs.
 
 ;;; Window states, how to get them and how to put them in a window.
 (defun window--state-get-1 (window &optional writable)
@@ -6877,18 +6554,6 @@ specified, e.g. by the user options `display-buffer-alist' or
 `display-buffer-base-action'.  See `display-buffer'.")
 (put 'display-buffer-fallback-action 'risky-local-variable t)
 
-(defun display-buffer-assq-regexp (buffer-name alist action)
-  "Retrieve ALIST entry corresponding to BUFFER-NAME.
-ACTION is the action argument passed to `display-buffer'."
-  (catch 'match
-    (dolist (entry alist)
-      (let ((key (car entry)))
-	(when (or (and (stringp key)
-		       (string-match-p key buffer-name))
-		  (and (functionp key)
-		       (funcall key buffer-name action)))
-	  (throw 'match (cdr entry)))))))
-
 (defvar display-buffer--same-window-action
   '(display-buffer-same-window
     (inhibit-same-window . nil))
@@ -7013,31 +6678,13 @@ argument, ACTION is t."
     (if display-buffer-function
 	;; If `display-buffer-function' is defined, let it do the job.
 	(funcall display-buffer-function buffer inhibit-same-window)
-      ;; Otherwise, use the defined actions.
-      (let* ((user-action
-	      (display-buffer-assq-regexp
-	       (buffer-name buffer) display-buffer-alist action))
-             (special-action (display-buffer--special-action buffer))
-	     ;; Extra actions from the arguments to this function:
-	     (extra-action
-	      (cons nil (append (if inhibit-same-window
-				    '((inhibit-same-window . t)))
-				(if frame
-				    `((reusable-frames . ,frame))))))
-	     ;; Construct action function list and action alist.
-	     (actions (list display-buffer-overriding-action
-			    user-action special-action action extra-action
-			    display-buffer-base-action
-			    display-buffer-fallback-action))
-	     (functions (apply 'append
-			       (mapcar (lambda (x)
-					 (setq x (car x))
-					 (if (functionp x) (list x) x))
-				       actions)))
-	     (alist (apply 'append (mapcar 'cdr actions)))
+      ;; Otherwise, use the defined actions.  See `display-buffer-explain'
+      ;; for where they come from.
+      (let* ((actions (display-buffer--actions
+		       buffer action frame inhibit-same-window))
+	     (functions (car actions))
+	     (alist (cdr actions))
 	     window)
-	(unless (buffer-live-p buffer)
-	  (error "Invalid buffer"))
 	(while (and functions (not window))
 	  (setq window (funcall (car functions) buffer alist)
 	  	functions (cdr functions)))
@@ -8969,183 +8616,9 @@ With arg N, put point N/10 of the way from the true end."
 	  (recenter '(t)))
       (select-window orig-window))))
 
-(defvar mouse-autoselect-window-timer nil
-  "Timer used by delayed window autoselection.")
-
-(defvar mouse-autoselect-window-position-1 nil
-  "First mouse position recorded by delayed window autoselection.")
-
-(defvar mouse-autoselect-window-position nil
-  "Last mouse position recorded by delayed window autoselection.")
-
-(defvar mouse-autoselect-window-window nil
-  "Last window recorded by delayed window autoselection.")
-
-(defvar mouse-autoselect-window-state nil
-  "When non-nil, special state of delayed window autoselection.
-Possible values are `suspend' (suspend autoselection after a menu or
-scrollbar interaction) and `select' (the next invocation of
-`handle-select-window' shall select the window immediately).")
-
-(defun mouse-autoselect-window-cancel (&optional force)
-  "Cancel delayed window autoselection.
-Optional argument FORCE means cancel unconditionally."
-  (unless (and (not force)
-	       ;; Don't cancel for select-window or select-frame events
-	       ;; or when the user drags a scroll bar.
-	       (or (memq this-command
-			 '(handle-select-window handle-switch-frame))
-		   (and (eq this-command 'scroll-bar-toolkit-scroll)
-			(memq (nth 4 (event-end last-input-event))
-			      '(handle end-scroll)))))
-    (setq mouse-autoselect-window-state nil)
-    (setq mouse-autoselect-window-position-1 nil)
-    (when (timerp mouse-autoselect-window-timer)
-      (cancel-timer mouse-autoselect-window-timer))
-    (remove-hook 'pre-command-hook 'mouse-autoselect-window-cancel)))
-
-(defun mouse-autoselect-window-start (mouse-position &optional window suspend)
-  "Start delayed window autoselection.
-MOUSE-POSITION is the last position where the mouse was seen as returned
-by `mouse-position'.  Optional argument WINDOW non-nil denotes the
-window where the mouse was seen.  Optional argument SUSPEND non-nil
-means suspend autoselection."
-  ;; Record values for MOUSE-POSITION, WINDOW, and SUSPEND.
-  (setq mouse-autoselect-window-position mouse-position)
-  (when window (setq mouse-autoselect-window-window window))
-  (setq mouse-autoselect-window-state (when suspend 'suspend))
-  ;; Install timer which runs `mouse-autoselect-window-select' after
-  ;; `mouse-autoselect-window' seconds.
-  (setq mouse-autoselect-window-timer
-	(run-at-time
-	 (abs mouse-autoselect-window) nil 'mouse-autoselect-window-select)))
-
-(defun mouse-autoselect-window-select ()
-  "Select window with delayed window autoselection.
-If the mouse position has stabilized in a non-selected window, select
-that window.  The minibuffer window is selected only if the minibuffer
-is active.  This function is run by `mouse-autoselect-window-timer'."
-  (let* ((mouse-position (mouse-position))
-         (mouse-x (and (numberp (cadr mouse-position))
-                       (cadr mouse-position)))
-         (mouse-y (and (numberp (cddr mouse-position))
-                       (cddr mouse-position)))
-         (frame (and mouse-x mouse-y (car mouse-position)))
-         (window (and frame (window-at mouse-x mouse-y frame))))
-    (cond
-      ((or (and (fboundp 'menu-or-popup-active-p) (menu-or-popup-active-p))
-	   (and window
-		(let ((coords (coordinates-in-window-p
-			       (cdr mouse-position) window)))
-		  (and (not (consp coords))
-		       (not (memq coords '(left-margin right-margin)))))))
-       ;; A menu / popup dialog is active or the mouse is not on the
-       ;; text region of WINDOW: Suspend autoselection temporarily.
-       (mouse-autoselect-window-start mouse-position nil t))
-      ((or (eq mouse-autoselect-window-state 'suspend)
-          ;; When the mouse is at its first recorded position, restart
-          ;; delayed autoselection.  This works around a scenario with
-          ;; two two-window frames with identical dimensions: select the
-          ;; first window of the first frame, switch to the second
-          ;; frame, move the mouse to its second window, minimize the
-          ;; second frame.  Now the second window of the first frame
-          ;; gets selected although the mouse never really "moved" into
-          ;; that window.
-          (and (numberp mouse-autoselect-window)
-               (equal (mouse-position) mouse-autoselect-window-position-1)))
-      ;; Delayed autoselection was temporarily suspended, reenable it.
-      (mouse-autoselect-window-start mouse-position))
-     ((and window
-           (or (not (numberp mouse-autoselect-window))
-               (and (>= mouse-autoselect-window 0)
-                    ;; If `mouse-autoselect-window' is non-negative,
-                    ;; select window if it's the same as before.
-                    (eq window mouse-autoselect-window-window))
-               ;; Otherwise select window iff the mouse is at the same
-               ;; position as before.  Observe that the first test
-               ;; after starting autoselection usually fails since the
-               ;; value of `mouse-autoselect-window-position' recorded
-               ;; there is the position where the mouse has entered the
-               ;; new window and not necessarily where the mouse has
-               ;; stopped moving.
-               (equal mouse-position mouse-autoselect-window-position))
-           ;; The minibuffer is a candidate window if it's active.
-           (or (not (window-minibuffer-p window))
-               (eq window (active-minibuffer-window))))
-      ;; Mouse position has stabilized in non-selected window: Cancel
-      ;; delayed autoselection and try to select that window.
-      (mouse-autoselect-window-cancel t)
-      ;; Use `unread-command-events' in order to execute pre- and
-      ;; post-command hooks and trigger idle timers.  To avoid delaying
-      ;; autoselection again, set `mouse-autoselect-window-state'."
-      (setq mouse-autoselect-window-state 'select)
-      (setq unread-command-events
-            (cons (list 'select-window (list window))
-                  unread-command-events)))
-     ((or (not (numberp mouse-autoselect-window))
-          (equal mouse-position mouse-autoselect-window-position))
-      ;; Mouse position has stabilized at
-      ;; `mouse-autoselect-window-position': Cancel delayed
-      ;; autoselection.
-     (mouse-autoselect-window-cancel t))
-    (window
-     ;; Mouse position has not stabilized yet, resume delayed
-     ;; autoselection.
-     (mouse-autoselect-window-start mouse-position window)))))
-
-(defun handle-select-window (event)
-  "Handle select-window events."
-  (interactive "^e")
-  (let* ((window (posn-window (event-start event)))
-         (frame (and (window-live-p window) (window-frame window)))
-         (old-frame (selected-frame)))
-    (unless (or (not (window-live-p window))
-		;; Don't switch when autoselection shall be delayed.
-		(and (numberp mouse-autoselect-window)
-		     (not (eq mouse-autoselect-window-state 'select))
-		     (let ((position (mouse-position)))
-		       ;; Cancel any delayed autoselection.
-		       (mouse-autoselect-window-cancel t)
-		       ;; Start delayed autoselection from current mouse
-		       ;; position and window.
-		       (setq mouse-autoselect-window-position-1 position)
-		       (mouse-autoselect-window-start position window)
-		       ;; Executing a command cancels delayed autoselection.
-		       (add-hook
-			'pre-command-hook 'mouse-autoselect-window-cancel)))
-                ;; Don't switch to a `no-accept-focus' frame unless it's
-                ;; already selected.
-                (and (not (eq frame (selected-frame)))
-                     (frame-parameter frame 'no-accept-focus))
-                ;; Don't switch to minibuffer window unless it's active.
-                (and (window-minibuffer-p window)
-                     (not (minibuffer-window-active-p window))))
-      ;; Reset state of delayed autoselection.
-      (setq mouse-autoselect-window-state nil)
-      ;; Run `mouse-leave-buffer-hook' when autoselecting window.
-      (run-hooks 'mouse-leave-buffer-hook)
-      ;; Clear echo area.
-      (message nil)
-      ;; Select the window before giving the frame focus since otherwise
-      ;; we might get two windows with an active cursor.
-      (select-window window)
-      (cond
-       ((or (not (memq (window-system frame) '(x w32 ns)))
-            (not focus-follows-mouse)
-            ;; Focus FRAME if it's either a child frame or an ancestor
-            ;; of the frame switched from.
-            (and (not (frame-parameter frame 'parent-frame))
-                 (not (frame-ancestor-p frame old-frame)))))
-       ((eq focus-follows-mouse 'auto-raise)
-        ;; Focus and auto-raise frame.
-        (x-focus-frame frame)
-        ;; This doesn't seem to work when we move from a normal frame
-        ;; right into the child frame of another frame - we should raise
-        ;; that child frame's ancestor frame first ...
-        (raise-frame frame))
-       (t
-        ;; Just focus frame.
-        (x-focus-frame frame t))))))
+;; Window autoselection with the mouse, `mouse-autoselect-window-cancel',
+;; `mouse-autoselect-window-select' and `handle-select-window', is in
+;; mouse_autoselect.rs.
 
 (defun truncated-partial-width-window-p (&optional window)
   "Return non-nil if lines in WINDOW are specifically truncated due to its width.
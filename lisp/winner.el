@@ -55,8 +55,6 @@
       (lambda () (delq (minibuffer-window) (window-list nil 0)))
     (lambda () (window-list nil 0))))
 
-(require 'ring)
-
 (defgroup winner nil
   "Restoring window configurations."
   :group 'windows)
@@ -124,17 +122,17 @@ You may want to include buffer names such as *Help*, *Apropos*,
 
 ;;; Saved configurations
 
-;; This variable contains the window configuration rings.
-;; The key in this alist is the frame.
-(defvar winner-ring-alist nil)
+;; The window configuration rings are kept for each frame by
+;; `window-configuration-ring-push'.
 
-;; Find the right ring.  If it does not exist, create one.
+;; Find the right ring, newest configuration first.  If it is empty,
+;; start it with the current configuration.
 (defsubst winner-ring (frame)
-  (or (cdr (assq frame winner-ring-alist))
-      (let ((ring (make-ring winner-ring-size)))
-        (ring-insert ring (winner-configuration frame))
-        (push (cons frame ring) winner-ring-alist)
-        ring)))
+  (or (window-configuration-ring frame)
+      (progn
+        (window-configuration-ring-push (winner-configuration frame)
+                                        frame winner-ring-size)
+        (window-configuration-ring frame))))
 
 
 ;; If the same command is called several times in a row,
@@ -158,12 +156,10 @@ You may want to include buffer names such as *Help*, *Apropos*,
 	      (eq this-command 'winner-redo))
     (let ((conf (winner-configuration frame))
 	  (ring (winner-ring frame)))
-      (when (and (not (ring-empty-p ring))
-		 (winner-equal conf (ring-ref ring 0)))
-        ;; When the previous configuration was very similar,
-        ;; keep only the latest.
-	(ring-remove ring 0))
-      (ring-insert ring conf)
+      ;; When the previous configuration was very similar,
+      ;; keep only the latest.
+      (window-configuration-ring-push conf frame winner-ring-size
+                                      (winner-equal conf (car ring)))
       (push frame winner-last-frames)
       frame)))
 
@@ -171,32 +167,20 @@ You may want to include buffer names such as *Help*, *Apropos*,
 
 ;;; Hooks
 
-;; Frames affected by the current command.
-(defvar winner-modified-list nil)
-
-;; Called whenever the window configuration changes
-;; (a `window-configuration-change-hook').
-(defun winner-change-fun ()
-
-  ;; Cull dead frames.
-  (setq winner-modified-list
-        (cl-loop for frame in winner-modified-list
-             if (frame-live-p frame) collect frame))
-
-  (unless (or (memq (selected-frame) winner-modified-list)
-              (/= 0 (minibuffer-depth)))
-    (push (selected-frame) winner-modified-list)))
+;; The frames affected by the current command are those
+;; `window-configuration-ring-changed-frames' returns.
 
 ;; A `post-command-hook' for emacsen with
-;; `window-configuration-change-hook'.
-(defun winner-save-old-configurations ()
+;; `window-configuration-change-hook'.  FRAMES are saved too, in
+;; addition to the frames whose configuration changed.
+(defun winner-save-old-configurations (&optional frames)
   (when (zerop (minibuffer-depth))
     (unless (eq this-command winner-last-command)
       (setq winner-last-frames nil)
       (setq winner-last-command this-command))
-    (dolist (frame winner-modified-list)
+    (dolist (frame (append frames
+                           (window-configuration-ring-changed-frames t)))
       (winner-insert-if-new frame))
-    (setq winner-modified-list nil)
     (winner-remember)))
 
 ;; A `minibuffer-setup-hook'.
@@ -364,12 +348,9 @@ you can press `C-c <right>' (calling `winner-redo')."
   :global t
   (if winner-mode
       (progn
-        (add-hook 'window-configuration-change-hook 'winner-change-fun)
         (add-hook 'post-command-hook 'winner-save-old-configurations)
         (add-hook 'minibuffer-setup-hook 'winner-save-unconditionally)
-        (setq winner-modified-list (frame-list))
-        (winner-save-old-configurations))
-    (remove-hook 'window-configuration-change-hook 'winner-change-fun)
+        (winner-save-old-configurations (frame-list)))
     (remove-hook 'post-command-hook 'winner-save-old-configurations)
     (remove-hook 'minibuffer-setup-hook 'winner-save-unconditionally)))
 
@@ -378,7 +359,7 @@ you can press `C-c <right>' (calling `winner-redo')."
 (defvar winner-undo-frame nil)
 
 (defvar winner-pending-undo-ring nil
-  "The ring currently used by `winner-undo'.")
+  "The configurations currently used by `winner-undo', newest first.")
 (defvar winner-undo-counter nil)
 (defvar winner-undone-data  nil) ; There confs have been passed.
 
@@ -401,7 +382,7 @@ In other words, \"undo\" changes in window configuration."
  		 (not (window-minibuffer-p)))
  	(message "Winner undo (%d / %d)"
  		 winner-undo-counter
- 		 (1- (ring-length winner-pending-undo-ring)))))))
+ 		 (1- (length winner-pending-undo-ring)))))))
 
 
 
@@ -409,20 +390,21 @@ In other words, \"undo\" changes in window configuration."
 (defun winner-undo-this ()		; The heart of winner undo.
   (cl-loop
    (cond
-    ((>= winner-undo-counter (ring-length winner-pending-undo-ring))
+    ((>= winner-undo-counter (length winner-pending-undo-ring))
      (message "No further window configuration undo information")
      (cl-return nil))
 
     ((and				; If possible configuration
-      (winner-set (ring-ref winner-pending-undo-ring
- 			    winner-undo-counter))
+      (winner-set (nth winner-undo-counter winner-pending-undo-ring))
                                         ; .. and new configuration
       (let ((data (winner-win-data)))
  	(and (not (member data winner-undone-data))
  	     (push data winner-undone-data))))
      (cl-return t))			; .. then everything is fine.
     (t ;; Otherwise, discharge it (and try the next one).
-     (ring-remove winner-pending-undo-ring winner-undo-counter)))))
+     (window-configuration-ring-pop winner-undo-frame winner-undo-counter)
+     (setq winner-pending-undo-ring
+           (window-configuration-ring winner-undo-frame))))))
 
 
 (defun winner-redo ()			; If you change your mind.
@@ -432,8 +414,10 @@ In other words, \"undo\" changes in window configuration."
    ((eq last-command 'winner-undo)
     (winner-set
      (if (zerop (minibuffer-depth))
-         (ring-remove winner-pending-undo-ring 0)
-       (ring-ref winner-pending-undo-ring 0)))
+         (prog1 (window-configuration-ring-pop winner-undo-frame)
+           (setq winner-pending-undo-ring
+                 (window-configuration-ring winner-undo-frame)))
+       (car winner-pending-undo-ring)))
     (unless (eq (selected-window) (minibuffer-window))
       (message "Winner undid undo")))
    (t (user-error "Previous command was not a `winner-undo'"))))
@@ -1,20 +1,14 @@
-#[macro_use]
-extern crate lazy_static;
 extern crate libc;
-extern crate regex;
 
 use std::env;
 use std::ffi::OsStr;
 use std::fs;
 use std::fs::File;
-use std::fs::OpenOptions;
 use std::io;
 use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
 use std::process;
 
-use regex::Regex;
-
 static C_NAME: &str = "c_name = \"";
 
 /// Exit with error $code after printing the $fmtstr to stderr
@@ -99,7 +93,6 @@ struct ModuleData {
     pub info: ModuleInfo,
     pub c_exports: Vec<String>,
     pub lisp_fns: Vec<String>,
-    pub protected_statics: Vec<String>,
 }
 
 impl ModuleData {
@@ -108,7 +101,6 @@ impl ModuleData {
             info: info,
             c_exports: Vec::new(),
             lisp_fns: Vec::new(),
-            protected_statics: Vec::new(),
         }
     }
 }
@@ -142,10 +134,7 @@ impl<'a> ModuleParser<'a> {
                 continue;
             }
 
-            if line.starts_with("declare_GC_protected_static!") {
-                let var = self.parse_gc_protected_static(&line)?;
-                mod_data.protected_statics.push(var);
-            } else if line.starts_with("#[no_mangle]") {
+            if line.starts_with("#[no_mangle]") {
                 if let Some(next) = reader.next() {
                     let line = next?;
 
@@ -213,10 +202,9 @@ impl<'a> ModuleParser<'a> {
             }
         }
 
-        if !has_include && !(mod_data.lisp_fns.is_empty() && mod_data.protected_statics.is_empty())
-        {
+        if !has_include && !mod_data.lisp_fns.is_empty() {
             let msg = format!(
-                "{} is missing the required include for protected statics or lisp_fn exports.",
+                "{} is missing the required include for lisp_fn exports.",
                 path_as_str(self.info.path.file_name()).to_string()
             );
 
@@ -244,24 +232,6 @@ impl<'a> ModuleParser<'a> {
         }
     }
 
-    fn parse_gc_protected_static(&mut self, line: &str) -> Result<String, LintMsg> {
-        lazy_static! {
-            static ref RE: Regex = Regex::new(r#"GC_protected_static!\((.+), .+\);"#).unwrap();
-        }
-
-        match RE.captures(line) {
-            Some(caps) => {
-                let name = caps[1].to_string();
-                Ok(name)
-            }
-            None => Err(LintMsg::new(
-                &self.info.name,
-                self.lineno,
-                "could not parse protected static".to_string(),
-            )),
-        }
-    }
-
     // Determine if a function is exported correctly and return that function's name or None.
     fn validate_exported_function(
         &mut self,
@@ -418,25 +388,6 @@ fn generate_include_files() -> Result<(), BuildError> {
 
             write!(out_file, "    {}::rust_init_syms();\n", mod_data.info.name)?;
         }
-
-        if !mod_data.protected_statics.is_empty() {
-            let mut file = OpenOptions::new()
-                .create(true)
-                .write(true)
-                .append(true)
-                .open(exports_path)?;
-            write!(
-                file,
-                "protect_statics_from_GC! {{ {} }}\n",
-                mod_data.protected_statics.join(", ")
-            )?;
-
-            write!(
-                out_file,
-                "    {}::rust_static_syms();\n",
-                mod_data.info.name
-            )?;
-        }
     }
 
     // Add this one by hand.
@@ -0,0 +1,24 @@
+;;; bench-server.el --- Evaluate benchmark forms read from stdin  -*- lexical-binding: t -*-
+
+;;; Commentary:
+
+;; Loaded by remacs-bench into an `emacs --batch' process.  Every line
+;; read from stdin is a single Lisp form; it is evaluated and its value
+;; is printed back on a single line.  Errors are reported as
+;; (error . DATA) so that the driver can fail the benchmark instead of
+;; hanging.
+
+;;; Code:
+
+(defun remacs-bench-serve ()
+  "Evaluate forms from stdin until it is closed."
+  (let (line)
+    (while (setq line (ignore-errors (read-from-minibuffer "")))
+      (let ((value (condition-case err
+                       (eval (car (read-from-string line)) t)
+                     (error (cons 'error err)))))
+        (princ (format "%S\n" (prin1-to-string value)))))))
+
+(remacs-bench-serve)
+
+;;; bench-server.el ends here
@@ -0,0 +1,117 @@
+//! Compare ported primitives against GNU Emacs.
+//!
+//! See the README for how to point this at a reference binary.
+
+#[macro_use]
+extern crate criterion;
+extern crate remacs_bench;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use criterion::{Criterion, Fun};
+
+use remacs_bench::{reference_binary, remacs_binary, EmacsProcess};
+
+/// How many times the body of a case is run per criterion iteration.
+/// This amortizes the cost of the round trip to the child process.
+const BODY_REPETITIONS: usize = 1000;
+
+/// A buffer of Lisp code with strings, comments and escaped parens, for
+/// the cases exercising syntax.
+const SEXP_SETUP: &str = r#"(with-current-buffer (get-buffer-create "*bench-sexp*") (erase-buffer) (set-syntax-table emacs-lisp-mode-syntax-table) (setq-local parse-sexp-ignore-comments t) (dotimes (_ 50) (insert "(defun f (x) \"doc (paren\" ; comment )\n  (let ((y ?\\()) (list 'x y \"\u00e9t\u00e9\")))\n")))"#;
+
+struct Case {
+    name: &'static str,
+    setup: &'static str,
+    body: &'static str,
+}
+
+const CASES: &[Case] = &[
+    Case {
+        name: "window-geometry",
+        setup: "(progn (split-window) (split-window nil nil t))",
+        body: "(progn (window-total-width) (window-body-height) (window-pixel-left) (window-pixel-top))",
+    },
+    Case {
+        name: "format",
+        setup: "nil",
+        body: "(format \"%s-%d-%S-%5.2f\" \"abc\" 42 'sym 3.14159)",
+    },
+    Case {
+        name: "string-match",
+        setup: "(setq remacs-bench-string (mapconcat #'number-to-string (number-sequence 1 200) \" \"))",
+        body: "(string-match \"19[0-9]\" remacs-bench-string)",
+    },
+    Case {
+        name: "search-forward",
+        setup: "(progn (set-buffer (get-buffer-create \"*bench*\")) (dotimes (i 200) (insert (format \"line %d\\n\" i))))",
+        body: "(progn (goto-char (point-min)) (search-forward \"line 150\" nil t))",
+    },
+    Case {
+        name: "parse-partial-sexp",
+        setup: SEXP_SETUP,
+        body: r#"(with-current-buffer "*bench-sexp*" (parse-partial-sexp (point-min) (point-max)))"#,
+    },
+    Case {
+        name: "syntax-ppss",
+        setup: SEXP_SETUP,
+        body: r#"(with-current-buffer "*bench-sexp*" (syntax-ppss-flush-cache (point-min)) (syntax-ppss (point-max)))"#,
+    },
+    Case {
+        name: "scan-sexps",
+        setup: SEXP_SETUP,
+        body: r#"(with-current-buffer "*bench-sexp*" (scan-sexps (point-min) 50) (scan-sexps (point-max) -50))"#,
+    },
+    Case {
+        name: "sort",
+        setup: "(setq remacs-bench-list (mapcar (lambda (_) (random 10000)) (make-list 200 nil)))",
+        body: "(sort (copy-sequence remacs-bench-list) #'<)",
+    },
+];
+
+fn prepare(process: &mut EmacsProcess, case: &Case) -> String {
+    let name = format!("remacs-bench--{}", case.name);
+    process.eval(case.setup);
+    process.define(&name, case.body, BODY_REPETITIONS);
+    name
+}
+
+fn bench_ported(c: &mut Criterion) {
+    let remacs = Rc::new(RefCell::new(
+        EmacsProcess::spawn(&remacs_binary()).expect("failed to start remacs"),
+    ));
+    let reference = reference_binary().map(|path| {
+        Rc::new(RefCell::new(
+            EmacsProcess::spawn(&path).expect("failed to start reference emacs"),
+        ))
+    });
+    if reference.is_none() {
+        eprintln!("REMACS_BENCH_REFERENCE is not set; only measuring remacs.");
+    }
+
+    for case in CASES {
+        let name = prepare(&mut remacs.borrow_mut(), case);
+
+        let mut functions = vec![{
+            let remacs = Rc::clone(&remacs);
+            let name = name.clone();
+            Fun::new("remacs", move |b, _: &()| {
+                b.iter(|| remacs.borrow_mut().call(&name))
+            })
+        }];
+
+        if let Some(ref reference) = reference {
+            prepare(&mut reference.borrow_mut(), case);
+            let reference = Rc::clone(reference);
+            functions.push(Fun::new("emacs", move |b, _: &()| {
+                b.iter(|| reference.borrow_mut().call(&name))
+            }));
+        }
+
+        c.bench_functions(case.name, functions, ());
+    }
+}
+
+criterion_group!(benches, bench_ported);
+criterion_main!(benches);
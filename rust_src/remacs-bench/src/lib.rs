@@ -0,0 +1,117 @@
+//! Drive `emacs --batch` processes for benchmarking ported primitives.
+//!
+//! A benchmark never calls into remacs directly: the forms under test
+//! are sent to a child process running `bench-server.el`, which means
+//! the ported function is exercised through the same Lisp calling
+//! convention as its C counterpart in a reference Emacs.
+
+use std::env;
+use std::ffi::OsString;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// Environment variable naming the remacs binary under test.
+pub const REMACS_VAR: &str = "REMACS_BENCH_EMACS";
+
+/// Environment variable naming a GNU Emacs binary to compare against.
+pub const REFERENCE_VAR: &str = "REMACS_BENCH_REFERENCE";
+
+/// The remacs binary to benchmark. Defaults to the one in the build tree.
+pub fn remacs_binary() -> PathBuf {
+    env::var_os(REMACS_VAR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../src/remacs"))
+}
+
+/// The reference Emacs binary, if one was configured.
+pub fn reference_binary() -> Option<PathBuf> {
+    env::var_os(REFERENCE_VAR)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+fn server_script() -> OsString {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("bench-server.el")
+        .into_os_string()
+}
+
+/// A batch Emacs process evaluating forms on request.
+pub struct EmacsProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    line: String,
+}
+
+impl EmacsProcess {
+    pub fn spawn(program: &Path) -> io::Result<Self> {
+        let mut child = Command::new(program)
+            .arg("-Q")
+            .arg("--batch")
+            .arg("-l")
+            .arg(server_script())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        Ok(Self {
+            child,
+            stdin,
+            stdout,
+            line: String::new(),
+        })
+    }
+
+    /// Evaluate FORM and return the printed representation of its value.
+    ///
+    /// Panics if FORM signals an error or the process goes away, since
+    /// a benchmark measuring an error path is not measuring anything
+    /// useful.
+    pub fn eval(&mut self, form: &str) -> &str {
+        debug_assert!(!form.contains('\n'), "forms must fit on one line");
+
+        writeln!(self.stdin, "{}", form).expect("emacs process closed its input");
+        self.line.clear();
+        let read = self
+            .stdout
+            .read_line(&mut self.line)
+            .expect("failed to read from emacs process");
+        if read == 0 {
+            panic!("emacs process exited while evaluating {}", form);
+        }
+
+        let value = self.line.trim_end();
+        if value.starts_with("\"(error ") {
+            panic!("{} signaled {}", form, value);
+        }
+        value
+    }
+
+    /// Byte-compile BODY into a loop of REPETITIONS iterations bound to
+    /// NAME, so that it can be invoked with `call`.
+    pub fn define(&mut self, name: &str, body: &str, repetitions: usize) {
+        let form = format!(
+            "(defalias '{} (byte-compile (lambda () (dotimes (_ {}) {}))))",
+            name, repetitions, body
+        );
+        self.eval(&form);
+    }
+
+    /// Call a function previously set up with `define`.
+    pub fn call(&mut self, name: &str) {
+        let form = format!("({})", name);
+        self.eval(&form);
+    }
+}
+
+impl Drop for EmacsProcess {
+    fn drop(&mut self) {
+        let _ = writeln!(self.stdin, "(kill-emacs 0)");
+        let _ = self.child.wait();
+    }
+}
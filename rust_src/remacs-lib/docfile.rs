@@ -11,7 +11,7 @@ use std::io::{stdout, BufRead, BufReader, Write};
 use std::mem;
 use std::ptr;
 
-use remacs_util::parse_lisp_fn;
+use remacs_util::{lisp_arglist, parse_lisp_fn};
 
 #[allow(dead_code)]
 const INVALID: c_int = 0;
@@ -136,22 +136,14 @@ pub unsafe extern "C" fn scan_rust_file(
             } else {
                 // Create usage line (fn ARG1 ...) from signature if necessary
                 if docstring_usage.is_empty() {
-                    for (i, chunk) in args.chunks(2).enumerate() {
-                        if chunk[1].contains("&mut") || chunk[1].contains("&[") {
-                            docstring_usage.push(' ');
-                            docstring_usage.push_str("&rest");
-                        } else if i == attr_props.min as usize {
-                            docstring_usage.push(' ');
-                            docstring_usage.push_str("&optional");
-                        }
-                        let argname = chunk[0]
-                            .trim()
-                            .trim_left_matches("mut ")
-                            .trim()
-                            .to_uppercase()
-                            .replace("_", "-");
+                    let names = args.chunks(2).map(|chunk| chunk[0]).collect::<Vec<_>>();
+                    for arg in lisp_arglist(&names, attr_props.min as usize, has_many_args) {
                         docstring_usage.push(' ');
-                        docstring_usage.push_str(&argname);
+                        if arg.starts_with('&') {
+                            docstring_usage.push_str(&arg);
+                        } else {
+                            docstring_usage.push_str(&arg.to_uppercase());
+                        }
                     }
                     docstring_usage.push(')');
                 }
@@ -39,6 +39,24 @@ pub fn lisp_fn(attr_ts: TokenStream, fn_ts: TokenStream) -> TokenStream {
         quote!{ std::ptr::null() }
     };
 
+    let arglist = if lisp_fn_args.unevalled {
+        quote! { std::ptr::null() }
+    } else {
+        let names = function
+            .args
+            .iter()
+            .map(|ident| ident.to_string())
+            .collect::<Vec<_>>();
+        let many = match function.fntype {
+            function::LispFnType::Normal(_) => false,
+            function::LispFnType::Many => true,
+        };
+        let arglist =
+            remacs_util::lisp_arglist(&names, lisp_fn_args.min as usize, many).join(" ");
+        let cbyte_arglist = CByteLiteral(arglist.as_str());
+        quote! { (#cbyte_arglist).as_ptr() as *const libc::c_char }
+    };
+
     match function.fntype {
         function::LispFnType::Normal(_) => {
             for ident in function.args {
@@ -143,6 +161,7 @@ pub fn lisp_fn(attr_ts: TokenStream, fn_ts: TokenStream) -> TokenStream {
                     intspec: #intspec,
                     doc: 0,
                     lang: crate::remacs_sys::Lisp_Subr_Lang::Lisp_Subr_Lang_Rust,
+                    arglist: #arglist,
                 };
 
                 unsafe {
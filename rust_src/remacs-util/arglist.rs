@@ -0,0 +1,42 @@
+//! Derive Lisp argument lists from the signatures of #[lisp_fn] functions.
+
+/// The Lisp name of the Rust argument NAME: any `mut ` and leading
+/// underscores dropped, and "_" -> "-".
+fn lisp_arg_name(name: &str) -> String {
+    name.trim()
+        .trim_left_matches("mut ")
+        .trim()
+        .trim_left_matches('_')
+        .replace("_", "-")
+}
+
+/// The Lisp argument list of a function whose Rust arguments are NAMES,
+/// of which the first MIN are required.  If MANY is true, the function
+/// takes its arguments as a single slice, which becomes a `&rest'
+/// argument.
+pub fn lisp_arglist<S: AsRef<str>>(names: &[S], min: usize, many: bool) -> Vec<String> {
+    let mut arglist = Vec::new();
+    for (i, name) in names.iter().enumerate() {
+        if many {
+            arglist.push("&rest".to_string());
+        } else if i == min {
+            arglist.push("&optional".to_string());
+        }
+        arglist.push(lisp_arg_name(name.as_ref()));
+    }
+    arglist
+}
+
+#[test]
+fn test_lisp_arglist() {
+    assert_eq!(
+        lisp_arglist(&["string", "from", "to"], 1, false),
+        vec!["string", "&optional", "from", "to"]
+    );
+    assert_eq!(lisp_arglist(&["args"], 0, true), vec!["&rest", "args"]);
+    assert_eq!(
+        lisp_arglist(&["mut buffer_or_name", "_noerror"], 2, false),
+        vec!["buffer-or-name", "noerror"]
+    );
+    assert!(lisp_arglist::<&str>(&[], 0, false).is_empty());
+}
@@ -7,7 +7,9 @@ extern crate libc;
 extern crate rand;
 extern crate syn;
 
+mod arglist;
 mod attributes;
 
 // Used by remacs-macros and remacs-lib
+pub use self::arglist::lisp_arglist;
 pub use self::attributes::parse_lisp_fn;
@@ -0,0 +1,109 @@
+//! The primitives abbrev expansion is built on.  Abbrev tables and
+//! the expansion itself live in abbrev.el.
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    editfns::constrain_to_field,
+    lisp::defsubr,
+    lisp::LispObject,
+    multibyte::Codepoint,
+    numbers::LispNumber,
+    obarray::intern,
+    remacs_sys::{syntaxcode, EmacsInt},
+    remacs_sys::{Fget, Qexpand_abbrev, Qnil},
+    syntax::{current_syntax_table, scan_words, syntax_entry, BufferSyntax, SyntaxCode},
+    threads::ThreadState,
+};
+
+/// What happened when self-inserting a character tried to expand the
+/// abbrev before point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelfInsertExpansion {
+    /// `expand-abbrev' was not called.
+    NotTried,
+    /// `expand-abbrev' was called; MODIFIED says whether it changed
+    /// the buffer.
+    Tried { modified: bool },
+    /// The abbrev that was expanded has a hook with a non-nil
+    /// `no-self-insert' property, so the character must not be
+    /// inserted.
+    NoSelfInsert,
+}
+
+/// Whether self-inserting the character C should expand the abbrev
+/// before point: `abbrev-mode' is on, C doesn't have word syntax and
+/// the character before point does.
+fn expands_before(c: Codepoint) -> bool {
+    let buffer = ThreadState::current_buffer_unchecked();
+    if buffer.abbrev_mode_.is_nil() || buffer.read_only_.is_not_nil() || buffer.pt <= buffer.begv {
+        return false;
+    }
+    let is_word = |code: SyntaxCode| code.class() == syntaxcode::Sword as EmacsInt;
+    if is_word(SyntaxCode::of_entry(syntax_entry(
+        current_syntax_table(),
+        c,
+    ))) {
+        return false;
+    }
+    let mut syntax = BufferSyntax::new();
+    let previous = syntax.char_at(buffer.pt - 1);
+    is_word(syntax.code_at(buffer.pt - 1, previous))
+}
+
+/// Expand the abbrev before point if self-inserting the character C
+/// calls for it.
+pub fn expand_before_self_insert(c: Codepoint) -> SelfInsertExpansion {
+    if !expands_before(c) {
+        return SelfInsertExpansion::NotTried;
+    }
+    let buffer = ThreadState::current_buffer_unchecked();
+    let modiff = buffer.modifications();
+
+    let sym = call!(Qexpand_abbrev);
+
+    let hook = sym.as_symbol().and_then(|s| s.get_function().as_symbol());
+    if let Some(hook) = hook {
+        if unsafe { Fget(hook.into(), intern("no-self-insert").into()) }.is_not_nil() {
+            return SelfInsertExpansion::NoSelfInsert;
+        }
+    }
+    SelfInsertExpansion::Tried {
+        modified: buffer.modifications() != modiff,
+    }
+}
+
+/// The bounds of the word that ends before POS or contains it, as
+/// moving backward a word from POS and then forward a word would find
+/// them, except that the end is never after POS.
+fn word_before(pos: isize) -> (isize, isize) {
+    let buffer = ThreadState::current_buffer_unchecked();
+    let across = |from: isize, count: EmacsInt, edge: isize| {
+        let to = match scan_words(from, count) {
+            0 => edge,
+            to => to,
+        };
+        constrain_to_field(
+            Some(LispNumber::Fixnum(to as EmacsInt)),
+            LispNumber::Fixnum(from as EmacsInt),
+            false,
+            false,
+            Qnil,
+        ) as isize
+    };
+    let start = across(pos, -1, buffer.begv);
+    let end = across(start, 1, buffer.zv);
+    (start, end.min(pos))
+}
+
+/// Return the bounds of the word before point, as (START . END).
+/// START is where `backward-word' would move point to, and END is where
+/// `forward-word' would move from there, but never after point.  Point
+/// is not moved.
+#[lisp_fn(name = "abbrev--word-before-point")]
+pub fn abbrev_word_before_point() -> LispObject {
+    let (start, end) = word_before(ThreadState::current_buffer_unchecked().pt);
+    LispObject::cons(start, end)
+}
+
+include!(concat!(env!("OUT_DIR"), "/abbrev_exports.rs"));
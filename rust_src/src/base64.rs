@@ -1,5 +1,5 @@
 //! Base64 de- and encoding functions.
-use std::{cmp::max, slice};
+use std::{cmp::min, slice};
 
 use libc::{c_char, c_uchar};
 use remacs_macros::lisp_fn;
@@ -7,6 +7,7 @@ use remacs_macros::lisp_fn;
 use crate::{
     base64_crate,
     buffers::validate_region,
+    insdel::signal_after_change,
     lisp::defsubr,
     lisp::LispObject,
     marker::buf_charpos_to_bytepos,
@@ -14,23 +15,45 @@ use crate::{
     remacs_sys::EmacsInt,
     remacs_sys::{
         del_range_both, del_range_byte, insert, insert_1_both, make_unibyte_string, move_gap_both,
-        set_point, set_point_both, signal_after_change, temp_set_point_both,
+        set_point, set_point_both, temp_set_point_both,
     },
     threads::ThreadState,
 };
 
-fn base64_encode_1(bytes: &[u8], line_break: bool, multibyte: bool) -> Result<String, ()> {
-    let config = if line_break {
-        // base64_crate::MIME, but with LF instead of CRLF
-        base64_crate::Config::new(
-            base64_crate::CharacterSet::Standard,
-            true, // pad
-            true, // strip whitespace
-            base64_crate::LineWrap::Wrap(76, base64_crate::LineEnding::LF),
-        )
-    } else {
-        base64_crate::STANDARD
-    };
+/// The variants of base64 encoding.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Variant {
+    /// The standard alphabet.  If LINE_BREAK, lines are broken after 76
+    /// characters, like MIME does.
+    Standard { line_break: bool },
+    /// The URL and file name safe alphabet of RFC 4648, padded with `='
+    /// if PAD.  Lines are never broken.
+    Url { pad: bool },
+}
+
+impl Variant {
+    fn config(self) -> base64_crate::Config {
+        match self {
+            // base64_crate::MIME, but with LF instead of CRLF
+            Variant::Standard { line_break: true } => base64_crate::Config::new(
+                base64_crate::CharacterSet::Standard,
+                true, // pad
+                true, // strip whitespace
+                base64_crate::LineWrap::Wrap(76, base64_crate::LineEnding::LF),
+            ),
+            Variant::Standard { line_break: false } => base64_crate::STANDARD,
+            Variant::Url { pad } => base64_crate::Config::new(
+                base64_crate::CharacterSet::UrlSafe,
+                pad,
+                true, // strip whitespace
+                base64_crate::LineWrap::NoWrap,
+            ),
+        }
+    }
+}
+
+fn base64_encode_1(bytes: &[u8], variant: Variant, multibyte: bool) -> Result<String, ()> {
+    let config = variant.config();
 
     let encoded_string = if multibyte {
         // Transform non-ASCII characters in multibyte string to Latin1,
@@ -57,11 +80,17 @@ fn base64_encode_1(bytes: &[u8], line_break: bool, multibyte: bool) -> Result<St
     Ok(encoded_string)
 }
 
-/// Base64-decode the data in ENCODED. If MULTIBYTE, the decoded result should be in multibyte
-/// form. It returns the decoded data and the number of bytes in the original decoded string.
-fn base64_decode_1(encoded: &[u8], multibyte: bool) -> Result<(Vec<u8>, usize), ()> {
-    // Use the MIME config to allow embedded newlines.
-    match base64_crate::decode_config(encoded, base64_crate::MIME) {
+/// Base64-decode the data in ENCODED, in the URL variant if URL. If MULTIBYTE, the decoded
+/// result should be in multibyte form. It returns the decoded data and the number of bytes in the
+/// original decoded string.
+fn base64_decode_1(encoded: &[u8], url: bool, multibyte: bool) -> Result<(Vec<u8>, usize), ()> {
+    // Use configs that strip whitespace, to allow embedded newlines.
+    let config = if url {
+        Variant::Url { pad: true }.config()
+    } else {
+        base64_crate::MIME
+    };
+    match base64_crate::decode_config(encoded, config) {
         Ok(decoded) => {
             if multibyte {
                 // Decode non-ASCII bytes into UTF-8 pairs.
@@ -99,7 +128,12 @@ fn test_simple_base64_encode_1() {
     let input = "hello world";
     let mut encoded = [0u8; 20];
 
-    let encoded = base64_encode_1(input.as_bytes(), false, false).unwrap();
+    let encoded = base64_encode_1(
+        input.as_bytes(),
+        Variant::Standard { line_break: false },
+        false,
+    )
+    .unwrap();
     assert_eq!("aGVsbG8gd29ybGQ=", encoded);
 }
 
@@ -108,12 +142,18 @@ fn test_multibyte_base64_encode_1() {
     let input = "Dobrý den"; // Czech
 
     // Treat the input as unibyte, meaning just a buffer of bytes
-    let encoded = base64_encode_1(input.as_bytes(), false, false).unwrap();
+    let encoded = base64_encode_1(
+        input.as_bytes(),
+        Variant::Standard { line_break: false },
+        false,
+    )
+    .unwrap();
     assert_eq!("RG9icsO9IGRlbg==", encoded);
 
     // When we specify 'mutlibyte' we mean the input is encoded with emacs' own encoding
     let as_multibyte = encode_multibyte_string(input.as_bytes());
-    let encoded = base64_encode_1(&as_multibyte, false, true).unwrap();
+    let encoded =
+        base64_encode_1(&as_multibyte, Variant::Standard { line_break: false }, true).unwrap();
     assert_eq!("RG9icsO9IGRlbg==", encoded);
 }
 
@@ -123,7 +163,12 @@ fn test_linewrap_base64_encode_1() {
 support and strong backward compatibility requirements. The core team
 is understandably cautious in making far-reaching changes.";
 
-    let encoded = base64_encode_1(input.as_bytes(), true, false).unwrap();
+    let encoded = base64_encode_1(
+        input.as_bytes(),
+        Variant::Standard { line_break: true },
+        false,
+    )
+    .unwrap();
     let expected = "RW1hY3MgaXMgYSB3aWRlbHkgdXNlZCB0b29sIHdpdGggYSBsb25nIGhpc3RvcnksIGJyb2FkIHBs
 YXRmb3JtCnN1cHBvcnQgYW5kIHN0cm9uZyBiYWNrd2FyZCBjb21wYXRpYmlsaXR5IHJlcXVpcmVt
 ZW50cy4gVGhlIGNvcmUgdGVhbQppcyB1bmRlcnN0YW5kYWJseSBjYXV0aW91cyBpbiBtYWtpbmcg
@@ -138,7 +183,7 @@ fn test_simple_base64_decode_1() {
     let input = "aGVsbG8gd29ybGQ=";
     let clear = "hello world";
 
-    let (decoded, nchars) = base64_decode_1(input.as_bytes(), true).unwrap();
+    let (decoded, nchars) = base64_decode_1(input.as_bytes(), false, true).unwrap();
     assert_eq!(clear.len(), nchars);
     assert_eq!(clear, String::from_utf8(decoded).unwrap());
 }
@@ -151,7 +196,7 @@ fn test_multibyte_base64_decode_1() {
     // When we specify multibyte we want the return to be encoded with bytes/chars > 128 using
     // emacs' own encoding
 
-    let (decoded, nchars) = base64_decode_1(input.as_bytes(), true).unwrap();
+    let (decoded, nchars) = base64_decode_1(input.as_bytes(), false, true).unwrap();
 
     let decoded_multibyte = vec![68, 111, 98, 114, 193, 131, 192, 189, 32, 100, 101, 110];
 
@@ -161,7 +206,7 @@ fn test_multibyte_base64_decode_1() {
 
     // Now run again, but disable multibyte so we get the unchanged result of base64-decoding
 
-    let (decoded, nchars) = base64_decode_1(input.as_bytes(), false).unwrap();
+    let (decoded, nchars) = base64_decode_1(input.as_bytes(), false, false).unwrap();
 
     assert_eq!(clear.len(), nchars);
     assert_eq!(clear.as_bytes(), decoded.as_slice());
@@ -177,7 +222,7 @@ fn test_multibyte_base64_decode_1() {
         10,
     ];
 
-    let (decoded, nchars) = base64_decode_1(input.as_bytes(), true).unwrap();
+    let (decoded, nchars) = base64_decode_1(input.as_bytes(), false, true).unwrap();
 
     // We don't round-trip on multibyte decode but use a particular encoding
     assert_eq!(clear.len(), nchars);
@@ -216,8 +261,8 @@ ZiBtYWlsaW5nIGxpc3RzIHJlbGF0ZWQgdG8gRW1hY3MsIHNlZQo8aHR0cHM6Ly9zYXZhbm5haC5n
 bnUub3JnL21haWwvP2dyb3VwPWVtYWNzPi4gIEZvciB0aGUgY29tcGxldGUKbGlzdCBvZiBHTlUg
 bWFpbGluZyBsaXN0cywgc2VlIDxodHRwOi8vbGlzdHMuZ251Lm9yZy8+LgoK";
 
-    let (decoded1, _) = base64_decode_1(input1.as_bytes(), true).unwrap();
-    let (decoded2, _) = base64_decode_1(input2.as_bytes(), true).unwrap();
+    let (decoded1, _) = base64_decode_1(input1.as_bytes(), false, true).unwrap();
+    let (decoded2, _) = base64_decode_1(input2.as_bytes(), false, true).unwrap();
 
     assert_eq!(decoded1.len(), decoded2.len());
     assert_eq!(
@@ -226,23 +271,62 @@ bWFpbGluZyBsaXN0cywgc2VlIDxodHRwOi8vbGlzdHMuZ251Lm9yZy8+LgoK";
     );
 }
 
+#[test]
+fn test_url_base64_1() {
+    let input = [0xfbu8, 0xff, 0xbf];
+
+    let encoded = base64_encode_1(&input, Variant::Url { pad: true }, false).unwrap();
+    assert_eq!("-_-_", encoded);
+    let encoded = base64_encode_1(&input[..2], Variant::Url { pad: true }, false).unwrap();
+    assert_eq!("-_8=", encoded);
+    let encoded = base64_encode_1(&input[..2], Variant::Url { pad: false }, false).unwrap();
+    assert_eq!("-_8", encoded);
+
+    let (decoded, _) = base64_decode_1(b"-_8=", true, false).unwrap();
+    assert_eq!(&input[..2], decoded.as_slice());
+    let (decoded, _) = base64_decode_1(b"-_8", true, false).unwrap();
+    assert_eq!(&input[..2], decoded.as_slice());
+    assert!(base64_decode_1(b"+/8=", true, false).is_err());
+    assert!(base64_decode_1(b"-_8=", false, false).is_err());
+}
+
+fn encode_string(string: LispStringRef, variant: Variant) -> LispObject {
+    match base64_encode_1(string.as_slice(), variant, string.is_multibyte()) {
+        Ok(encoded) => unsafe {
+            make_unibyte_string(encoded.as_ptr() as *const c_char, encoded.len() as isize)
+        },
+        Err(_) => error!("Multibyte character in data for base64 encoding"),
+    }
+}
+
 /// Base64-encode STRING and return the result.
 /// Optional second argument NO-LINE-BREAK means do not break long lines
 /// into shorter lines.
 #[lisp_fn(min = "1")]
 pub fn base64_encode_string(string: LispStringRef, no_line_break: bool) -> LispObject {
-    match base64_encode_1(string.as_slice(), !no_line_break, string.is_multibyte()) {
-        Ok(encoded) => unsafe {
-            make_unibyte_string(encoded.as_ptr() as *const c_char, encoded.len() as isize)
+    encode_string(
+        string,
+        Variant::Standard {
+            line_break: !no_line_break,
         },
-        Err(_) => error!("Multibyte character in data for base64 encoding"),
-    }
+    )
 }
 
-/// Base64-decode STRING and return the result.
-#[lisp_fn]
-pub fn base64_decode_string(string: LispStringRef) -> LispObject {
-    let decoded = match base64_decode_1(string.as_slice(), false) {
+/// Base64url-encode STRING and return the result.
+/// Optional second argument NO-PAD means do not add padding char =.
+///
+/// This produces the URL variant of base 64 encoding defined in RFC 4648.
+#[lisp_fn(min = "1")]
+pub fn base64url_encode_string(string: LispStringRef, no_pad: bool) -> LispObject {
+    encode_string(string, Variant::Url { pad: !no_pad })
+}
+
+/// Base64-decode STRING and return the result as a string.
+/// Optional argument BASE64URL determines whether to use the URL variant of
+/// the base 64 encoding, as defined in RFC 4648.
+#[lisp_fn(min = "1")]
+pub fn base64_decode_string(string: LispStringRef, base64url: bool) -> LispObject {
+    let decoded = match base64_decode_1(string.as_slice(), base64url, false) {
         Ok((decoded, _)) => decoded,
         Err(_) => error!("Invalid base64 data"),
     };
@@ -250,31 +334,36 @@ pub fn base64_decode_string(string: LispStringRef) -> LispObject {
     unsafe { make_unibyte_string(decoded.as_ptr() as *const c_char, decoded.len() as isize) }
 }
 
-/// Base64-encode the region between BEG and END. Return the length of the encoded text. Optional
-/// third argument NO-LINE-BREAK means do not break long lines into shorter lines.
-#[lisp_fn(min = "2", intspec = "r")]
-pub fn base64_encode_region(
-    mut beg: LispObject,
-    mut end: LispObject,
-    no_line_break: bool,
-) -> EmacsInt {
-    unsafe { validate_region(&mut beg, &mut end) };
+/// Return the text of the current buffer between BEG and END, which
+/// are validated, as a contiguous slice, with BEG and END as character
+/// and byte positions.
+fn region_text<'a>(
+    beg: &mut LispObject,
+    end: &mut LispObject,
+) -> (&'a [u8], (isize, isize), (isize, isize)) {
+    unsafe { validate_region(beg, end) };
     let mut current_buffer = ThreadState::current_buffer_unchecked();
-    let old_pos = current_buffer.pt;
 
     let ibeg = beg.as_natnum_or_error() as isize;
     let begpos = buf_charpos_to_bytepos(current_buffer.as_mut(), ibeg);
     let iend = end.as_natnum_or_error() as isize;
     let endpos = buf_charpos_to_bytepos(current_buffer.as_mut(), iend);
 
+    // Move the gap out of the way, so the region is contiguous.
     unsafe { move_gap_both(ibeg, begpos) };
 
-    // Allocate room for the extra 33% plus newlines
     let length = (endpos - begpos) as usize;
-    let input = unsafe { slice::from_raw_parts(current_buffer.byte_pos_addr(begpos), length) };
+    let text = unsafe { slice::from_raw_parts(current_buffer.byte_pos_addr(begpos), length) };
+    (text, (ibeg, begpos), (iend, endpos))
+}
+
+fn encode_region(mut beg: LispObject, mut end: LispObject, variant: Variant) -> EmacsInt {
+    let current_buffer = ThreadState::current_buffer_unchecked();
+    let old_pos = current_buffer.pt;
+    let (input, (ibeg, begpos), (iend, endpos)) = region_text(&mut beg, &mut end);
 
     let multibyte = current_buffer.multibyte_characters_enabled();
-    let encoded = match base64_encode_1(input, !no_line_break, multibyte) {
+    let encoded = match base64_encode_1(input, variant, multibyte) {
         Ok(encoded) => encoded,
         Err(_) => error!("Multibyte character in data for base64 encoding"),
     };
@@ -282,40 +371,69 @@ pub fn base64_encode_region(
 
     // We now insert the new contents and delete the old in the region
     unsafe {
-        set_point_both(begpos, ibeg);
+        set_point_both(ibeg, begpos);
         insert(encoded.as_ptr() as *const c_char, encoded_length);
         del_range_byte(begpos + encoded_length, endpos + encoded_length);
     }
 
+    // If point was outside of the region, restore it exactly; else just
+    // move to the beginning of the region.
     let pos_to_set = if old_pos >= iend {
         old_pos + encoded_length - (iend - ibeg)
     } else if old_pos > ibeg {
-        old_pos - ibeg
+        ibeg
     } else {
         old_pos
     };
     unsafe { set_point(pos_to_set) };
 
-    encoded_length as i64
+    encoded_length as EmacsInt
+}
+
+/// Base64-encode the region between BEG and END.
+/// Return the length of the encoded text.
+/// Optional third argument NO-LINE-BREAK means do not break long lines
+/// into shorter lines.
+#[lisp_fn(min = "2", intspec = "r")]
+pub fn base64_encode_region(beg: LispObject, end: LispObject, no_line_break: bool) -> EmacsInt {
+    encode_region(
+        beg,
+        end,
+        Variant::Standard {
+            line_break: !no_line_break,
+        },
+    )
 }
 
-#[lisp_fn(intspec = "r")]
-pub fn base64_decode_region(mut beg: LispObject, mut end: LispObject) -> EmacsInt {
-    unsafe { validate_region(&mut beg, &mut end) };
+/// Base64url-encode the region between BEG and END.
+/// Return the length of the encoded text.
+/// Optional second argument NO-PAD means do not add padding char =.
+///
+/// This produces the URL variant of base 64 encoding defined in RFC 4648.
+#[lisp_fn(min = "2", intspec = "r")]
+pub fn base64url_encode_region(beg: LispObject, end: LispObject, no_pad: bool) -> EmacsInt {
+    encode_region(beg, end, Variant::Url { pad: !no_pad })
+}
 
+/// Base64-decode the region between BEG and END.
+/// Return the length of the decoded data.
+///
+/// Note that after calling this function, the data in the region will
+/// represent bytes, not text.  If you want to end up with text, you have
+/// to call `decode-coding-region' afterwards with an appropriate coding
+/// system.
+///
+/// If the region can't be decoded, signal an error and don't modify the buffer.
+/// Optional third argument BASE64URL determines whether to use the URL variant
+/// of the base 64 encoding, as defined in RFC 4648.
+#[lisp_fn(min = "2", intspec = "r")]
+pub fn base64_decode_region(mut beg: LispObject, mut end: LispObject, base64url: bool) -> EmacsInt {
     let mut current_buffer = ThreadState::current_buffer_unchecked();
     let mut old_pos = current_buffer.pt;
-
-    let ibeg = beg.as_natnum_or_error() as isize;
-    let begpos = buf_charpos_to_bytepos(current_buffer.as_mut(), ibeg);
-    let iend = end.as_natnum_or_error() as isize;
-    let endpos = buf_charpos_to_bytepos(current_buffer.as_mut(), iend);
+    let (input, (ibeg, begpos), (iend, endpos)) = region_text(&mut beg, &mut end);
 
     let multibyte = current_buffer.multibyte_characters_enabled();
-    let length = (endpos - begpos) as usize;
-
-    let input = unsafe { slice::from_raw_parts(current_buffer.byte_pos_addr(begpos), length) };
-    let (decoded, nchars) = match base64_decode_1(input, multibyte) {
+    let (decoded, nchars) = match base64_decode_1(input, base64url, multibyte) {
         Ok(decoded) => decoded,
         Err(_) => error!("Invalid base64 data"),
     };
@@ -344,14 +462,16 @@ pub fn base64_decode_region(mut beg: LispObject, mut end: LispObject) -> EmacsIn
         );
     }
 
+    // If point was outside of the region, restore it exactly; else just
+    // move to the beginning of the region.
     if old_pos >= iend {
         old_pos += inserted_chars - (iend - ibeg);
     } else if old_pos > ibeg {
         old_pos = ibeg;
     }
-    unsafe { set_point(max(current_buffer.zv, old_pos)) };
+    unsafe { set_point(min(current_buffer.zv, old_pos)) };
 
-    inserted_chars as i64
+    inserted_chars as EmacsInt
 }
 
 include!(concat!(env!("OUT_DIR"), "/base64_exports.rs"));
@@ -0,0 +1,364 @@
+//! Standard input and output when running noninteractively.
+//!
+//! In batch mode (and in a daemon before it detaches) there is no
+//! frame to display messages on or read input from, so `message',
+//! `princ' and the minibuffer go through the process' stdio instead.
+
+use std::io::{self, Write};
+use std::ptr;
+use std::slice;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use errno::errno;
+use libc::{self, c_char, c_void, ptrdiff_t};
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    crypto::Unchanged,
+    lisp::{defsubr, LispObject},
+    multibyte::LispStringRef,
+    remacs_sys::Qnil,
+    remacs_sys::{
+        code_convert_string_norecord, emacs_get_tty, emacs_set_tty, emacs_tty, globals,
+        make_multibyte_string, make_string, maybe_quit, noninteractive_need_newline,
+        string_to_object, suppress_echo_on_tty,
+    },
+};
+
+const STDIN_FILENO: libc::c_int = 0;
+
+lazy_static! {
+    /// Bytes read from stdin that are not part of a complete line yet.
+    static ref PENDING_INPUT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+}
+
+/// The coding system to encode text for the terminal with:
+/// `coding-system-for-write' if it is set and `locale-coding-system'
+/// otherwise.
+fn output_coding_system() -> LispObject {
+    unsafe {
+        if globals.Vcoding_system_for_write.is_not_nil() {
+            globals.Vcoding_system_for_write
+        } else {
+            globals.Vlocale_coding_system
+        }
+    }
+}
+
+/// Whether the text BYTES has to be encoded for the terminal.
+/// This is not the case in the usual UTF-8 locale, which spares
+/// `printchar' making a string of each character it prints.
+fn needs_encoding(bytes: &[u8]) -> bool {
+    let coding_system = output_coding_system();
+    coding_system.is_not_nil() && !Unchanged::for_coding_system(coding_system).covers(bytes)
+}
+
+/// Encode STRING for the terminal.
+fn encode_for_output(string: LispStringRef) -> LispStringRef {
+    if !needs_encoding(string.as_slice()) {
+        string
+    } else {
+        unsafe { code_convert_string_norecord(string.into(), output_coding_system(), true) }
+            .as_string_or_error()
+    }
+}
+
+/// Write BYTES to stdout, after anything C code has buffered there.
+fn write_stdout(bytes: &[u8]) {
+    // `princ' and friends go through the C stdio buffer of stdout.
+    unsafe { libc::fflush(ptr::null_mut()) };
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    // There is nowhere to report a failure to write to stdout.
+    let _ = stdout.write_all(bytes);
+    let _ = stdout.flush();
+}
+
+/// Write NBYTES bytes of text at PTR, NCHARS characters long, to the C
+/// stdio STREAM, encoding any non-ASCII text for the terminal.
+#[no_mangle]
+pub unsafe extern "C" fn write_to_stream(
+    ptr: *const c_char,
+    nchars: ptrdiff_t,
+    nbytes: ptrdiff_t,
+    stream: *mut libc::FILE,
+) {
+    let bytes = slice::from_raw_parts(ptr as *const u8, nbytes as usize);
+
+    // Unibyte text is raw bytes already.
+    let encoded;
+    let bytes = if bytes.is_ascii() || nchars == nbytes || !needs_encoding(bytes) {
+        bytes
+    } else {
+        encoded =
+            encode_for_output(make_multibyte_string(ptr, nchars, nbytes).as_string_or_error());
+        encoded.as_slice()
+    };
+
+    libc::fwrite(bytes.as_ptr() as *const c_void, 1, bytes.len(), stream);
+}
+
+/// Log the message M to stderr.  Log an empty line if M is not a string.
+#[no_mangle]
+pub extern "C" fn message_to_stderr(m: LispObject) {
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+
+    unsafe {
+        if noninteractive_need_newline {
+            noninteractive_need_newline = false;
+            let _ = stderr.write_all(b"\n");
+        }
+    }
+    if let Some(string) = m.as_string() {
+        let _ = stderr.write_all(encode_for_output(string).as_slice());
+    }
+    if !unsafe { globals.cursor_in_echo_area } {
+        let _ = stderr.write_all(b"\n");
+    }
+    let _ = stderr.flush();
+}
+
+fn time_left(deadline: Instant) -> Duration {
+    let now = Instant::now();
+    if now < deadline {
+        deadline - now
+    } else {
+        Duration::new(0, 0)
+    }
+}
+
+/// Wait up to TIMEOUT, or forever if it is `None`, for stdin to have
+/// input or reach end of file.
+fn wait_for_stdin(timeout: Option<Duration>) -> bool {
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let mut fds = libc::pollfd {
+        fd: STDIN_FILENO,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+
+    loop {
+        let millis = deadline.map_or(-1, |deadline| {
+            let left = time_left(deadline);
+            (left.as_secs() * 1000 + u64::from(left.subsec_millis())) as libc::c_int
+        });
+
+        match unsafe { libc::poll(&mut fds, 1, millis) } {
+            -1 if errno().0 == libc::EINTR => unsafe { maybe_quit() },
+            -1 => return true, // Let the following read report the error.
+            n => return n > 0,
+        }
+    }
+}
+
+/// Remove the first line from PENDING, without its terminator.  Unless
+/// at EOF, a line ended by the last byte of PENDING, a `\r', is left
+/// pending, as it may be the first half of a `\r\n' split across reads.
+fn take_line(pending: &mut Vec<u8>, eof: bool) -> Option<Vec<u8>> {
+    let end = pending.iter().position(|&b| b == b'\n' || b == b'\r')?;
+    if !eof && end + 1 == pending.len() && pending[end] == b'\r' {
+        return None;
+    }
+    let mut rest = pending.split_off(end);
+
+    let terminator = if rest.starts_with(b"\r\n") { 2 } else { 1 };
+    rest.drain(..terminator);
+
+    Some(std::mem::replace(pending, rest))
+}
+
+enum StdinLine {
+    Line(Vec<u8>),
+    /// End of file; holds whatever was read after the last newline.
+    Eof(Vec<u8>),
+    Timeout,
+}
+
+/// Read the next line from stdin, waiting no longer than TIMEOUT.
+/// Signals are handled while waiting, so that C-c still quits.
+fn read_stdin_line(timeout: Option<Duration>) -> StdinLine {
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let mut buf = [0u8; 4096];
+
+    // `PENDING_INPUT' is only locked for as long as it takes to update
+    // it: quitting unwinds with a longjmp, which would leave it locked.
+    loop {
+        if let Some(line) = take_line(&mut PENDING_INPUT.lock().unwrap(), false) {
+            return StdinLine::Line(line);
+        }
+
+        if !wait_for_stdin(deadline.map(time_left)) {
+            return StdinLine::Timeout;
+        }
+
+        let n = unsafe { libc::read(STDIN_FILENO, buf.as_mut_ptr() as *mut c_void, buf.len()) };
+        match n {
+            -1 if errno().0 == libc::EINTR => unsafe { maybe_quit() },
+            n if n <= 0 => {
+                let mut pending = PENDING_INPUT.lock().unwrap();
+                if let Some(line) = take_line(&mut pending, true) {
+                    return StdinLine::Line(line);
+                }
+                return StdinLine::Eof(pending.drain(..).collect());
+            }
+            n => PENDING_INPUT
+                .lock()
+                .unwrap()
+                .extend_from_slice(&buf[..n as usize]),
+        }
+    }
+}
+
+fn seconds_to_duration(seconds: LispObject) -> Option<Duration> {
+    if seconds.is_nil() {
+        return None;
+    }
+    let seconds = seconds.any_to_float_or_error().max(0.0);
+    Some(Duration::new(
+        seconds.trunc() as u64,
+        (seconds.fract() * 1e9) as u32,
+    ))
+}
+
+/// Like `read_minibuf', but reading from stdin.  `read_minibuf' calls
+/// this when running noninteractively.
+#[no_mangle]
+pub extern "C" fn read_minibuf_noninteractive(
+    _map: LispObject,
+    _initial: LispObject,
+    prompt: LispObject,
+    _backup_n: LispObject,
+    expflag: bool,
+    _histvar: LispObject,
+    _histpos: LispObject,
+    defalt: LispObject,
+    _allow_props: bool,
+    _inherit_input_method: bool,
+) -> LispObject {
+    // Check whether we need to suppress echoing.
+    let read_hide_char = unsafe { globals.Vread_hide_char };
+    let hide_char = if read_hide_char.is_character() {
+        Some(read_hide_char.force_fixnum() as u8)
+    } else {
+        None
+    };
+
+    let mut etty: emacs_tty = unsafe { std::mem::zeroed() };
+    let etty_valid = hide_char.map_or(false, |_| unsafe {
+        let valid = emacs_get_tty(STDIN_FILENO, &mut etty) == 0;
+        suppress_echo_on_tty(STDIN_FILENO);
+        valid
+    });
+
+    write_stdout(encode_for_output(prompt.as_string_or_error()).as_slice());
+
+    let line = match read_stdin_line(None) {
+        StdinLine::Line(line) => Some(line),
+        StdinLine::Eof(ref rest) if rest.is_empty() => None,
+        StdinLine::Eof(rest) => Some(rest),
+        StdinLine::Timeout => None,
+    };
+
+    if let Some(c) = hide_char {
+        let mut echo = vec![c; line.as_ref().map_or(0, Vec::len)];
+        echo.push(b'\n');
+        write_stdout(&echo);
+        if etty_valid {
+            unsafe { emacs_set_tty(STDIN_FILENO, &mut etty, false) };
+        }
+    }
+
+    let line = match line {
+        Some(line) => line,
+        None => error!("Error reading from stdin"),
+    };
+
+    let val = unsafe { make_string(line.as_ptr() as *const c_char, line.len() as isize) };
+
+    // If Lisp form desired instead of string, parse it.
+    if expflag {
+        let defalt = defalt.as_cons().map_or(defalt, |cons| cons.car());
+        unsafe { string_to_object(val, defalt) }
+    } else {
+        val
+    }
+}
+
+/// Return non-nil if standard input has a line available to read.
+/// Optional argument TIMEOUT is the number of seconds to wait for one to
+/// arrive; it defaults to 0, meaning don't wait.  End of file counts as
+/// input, since `batch-read-line' will not block then either.
+///
+/// This is meant for scripts running in batch mode, where standard input
+/// is not the terminal Emacs reads commands from.
+#[lisp_fn(min = "0")]
+pub fn batch_input_pending_p(timeout: LispObject) -> bool {
+    let timeout = seconds_to_duration(timeout).unwrap_or_else(|| Duration::new(0, 0));
+    let has_line = {
+        let pending = PENDING_INPUT.lock().unwrap();
+        pending.iter().any(|&b| b == b'\n' || b == b'\r')
+    };
+    has_line || wait_for_stdin(Some(timeout))
+}
+
+/// Read a line from standard input and return it as a string.
+/// The line terminator is not included.  Optional argument TIMEOUT is the
+/// maximum number of seconds to wait for a complete line; if none arrives
+/// in time return nil, and keep what was read for the next call.  With no
+/// TIMEOUT, wait as long as it takes.
+///
+/// At end of file, return the text after the last line terminator, or nil
+/// if there is none.
+///
+/// Unlike `read-from-minibuffer' in batch mode, this does not print a
+/// prompt and never signals an error at end of file.
+#[lisp_fn(min = "0")]
+pub fn batch_read_line(timeout: LispObject) -> LispObject {
+    let line = match read_stdin_line(seconds_to_duration(timeout)) {
+        StdinLine::Line(line) => line,
+        StdinLine::Eof(ref rest) if rest.is_empty() => return Qnil,
+        StdinLine::Eof(rest) => rest,
+        StdinLine::Timeout => return Qnil,
+    };
+
+    unsafe { make_string(line.as_ptr() as *const c_char, line.len() as isize) }
+}
+
+include!(concat!(env!("OUT_DIR"), "/batch_exports.rs"));
+
+#[test]
+fn test_take_line() {
+    let mut pending = b"one\ntwo\r\nthree\rfour".to_vec();
+    assert_eq!(take_line(&mut pending, false), Some(b"one".to_vec()));
+    assert_eq!(take_line(&mut pending, false), Some(b"two".to_vec()));
+    assert_eq!(take_line(&mut pending, false), Some(b"three".to_vec()));
+    // An incomplete line stays pending.
+    assert_eq!(take_line(&mut pending, false), None);
+    assert_eq!(pending, b"four".to_vec());
+}
+
+#[test]
+fn test_take_line_empty() {
+    let mut pending = b"\n\r\n".to_vec();
+    assert_eq!(take_line(&mut pending, false), Some(vec![]));
+    assert_eq!(take_line(&mut pending, false), Some(vec![]));
+    assert_eq!(take_line(&mut pending, false), None);
+    assert!(pending.is_empty());
+}
+
+#[test]
+fn test_take_line_split_crlf() {
+    let mut pending = b"one\r".to_vec();
+    assert_eq!(take_line(&mut pending, false), None);
+    pending.extend_from_slice(b"\ntwo\n");
+    assert_eq!(take_line(&mut pending, false), Some(b"one".to_vec()));
+    assert_eq!(take_line(&mut pending, false), Some(b"two".to_vec()));
+    assert_eq!(take_line(&mut pending, false), None);
+
+    let mut pending = b"one\r".to_vec();
+    assert_eq!(take_line(&mut pending, true), Some(b"one".to_vec()));
+    assert!(pending.is_empty());
+}
@@ -0,0 +1,244 @@
+//! Laying out the bindings under a prefix key as a table, the way
+//! which-key style popups show them.
+
+use std::cmp::Ordering;
+
+use libc::c_void;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    editfns::propertize,
+    keymap::{current_active_maps, keymapp, lookup_key, map_keymap},
+    lisp::{defsubr, LispObject},
+    lists::{car, car_safe, cdr, nth},
+    lists::{LispConsCircularChecks, LispConsEndChecks},
+    multibyte::LispStringRef,
+    obarray::intern,
+    remacs_sys::Fwindow_body_width,
+    remacs_sys::{EmacsInt, Fframe_char_width, Fsingle_key_description, Fsubstring},
+    remacs_sys::{Qface, Qnil, Qremap, Qt},
+    sequences::{concat2, concat_lisp},
+    windows::{window_frame, LispWindowLiveOrSelected, LispWindowRef},
+};
+
+/// Descriptions longer than this many columns are cut short unless the
+/// caller asks for another limit.
+const MAX_DESCRIPTION_WIDTH: usize = 27;
+
+/// What separates a key from its description.
+const SEPARATOR: &str = " → ";
+
+/// Columns left blank between two columns of the table.
+const COLUMN_GAP: usize = 2;
+
+/// Add the binding of KEY to DEF to the list that DATA points to, unless
+/// a binding for KEY is there already.  Keymaps are visited in order of
+/// precedence, so the first binding found for a key is the one in effect.
+unsafe extern "C" fn collect_binding(
+    key: LispObject,
+    def: LispObject,
+    _args: LispObject,
+    data: *mut c_void,
+) {
+    let bindings = &mut *(data as *mut LispObject);
+    let shadowed = bindings
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+        .any(|binding| car(binding).equal(key));
+    if !shadowed {
+        *bindings = LispObject::cons(LispObject::cons(key, def), *bindings);
+    }
+}
+
+/// Return whether the binding of KEY to DEF is worth showing.
+fn is_shown(key: LispObject, def: LispObject) -> bool {
+    let hidden_keys = ["menu-bar", "tool-bar", "header-line", "mode-line"];
+    if def.is_nil() || def.eq(intern("undefined")) || key.eq(Qremap) {
+        return false;
+    }
+    !hidden_keys.iter().any(|&name| key.eq(intern(name)))
+}
+
+/// Return the string that describes DEF, and whether DEF is a prefix.
+fn describe_definition(def: LispObject) -> (LispObject, bool) {
+    if keymapp(def) {
+        let name = def
+            .as_symbol()
+            .map_or_else(|| LispObject::from("prefix"), |sym| sym.symbol_name());
+        return (concat2(LispObject::from("+"), name), true);
+    }
+    if let Some(sym) = def.as_symbol() {
+        return (sym.symbol_name(), false);
+    }
+    if def.is_string() || def.is_vector() {
+        return (LispObject::from("kbd-macro"), false);
+    }
+    if let Some((first, rest)) = def.into() {
+        // (menu-item NAME DEFN ...) and (STRING . DEFN).
+        if first.eq(intern("menu-item")) {
+            let (_, prefix) = describe_definition(nth(2, def));
+            return (car(rest), prefix);
+        }
+        if first.is_string() {
+            let (_, prefix) = describe_definition(rest);
+            return (first, prefix);
+        }
+        if first.eq(intern("lambda")) || first.eq(intern("closure")) {
+            return (LispObject::from("lambda"), false);
+        }
+    }
+    (LispObject::from("??"), false)
+}
+
+/// Order keys the way they are listed: characters by code, then other
+/// events by name.
+fn compare_keys(a: LispObject, b: LispObject) -> Ordering {
+    let rank = |key: LispObject| match car_safe(key).as_fixnum().or_else(|| key.as_fixnum()) {
+        Some(code) => (0, code, Vec::new()),
+        None => (
+            1,
+            0,
+            key.as_symbol().map_or_else(Vec::new, |sym| {
+                sym.symbol_name().force_string().as_slice().to_vec()
+            }),
+        ),
+    };
+    rank(a).cmp(&rank(b))
+}
+
+/// Return STRING shortened to WIDTH columns, marking the cut with `..'.
+fn truncate(string: LispStringRef, width: usize) -> LispObject {
+    if string.width() <= width {
+        return string.into();
+    }
+    let (end, _) = string.width_prefix(width.saturating_sub(2));
+    concat2(
+        unsafe { Fsubstring(string.into(), LispObject::from(0), LispObject::from(end)) },
+        LispObject::from(".."),
+    )
+}
+
+/// A binding as shown in the table.
+struct Entry {
+    key: LispObject,
+    text: LispObject,
+    width: usize,
+}
+
+/// Return a table of the bindings that follow the key sequence PREFIX.
+/// The bindings are those of the keymaps currently active, as returned
+/// by `current-active-maps', with each key described by the binding that
+/// is in effect.  They are laid out in columns, filled from top to
+/// bottom, that fit in the width of WINDOW's text area in pixels.
+///
+/// WINDOW defaults to the selected window.  Descriptions wider than
+/// MAX-DESCRIPTION-WIDTH columns, 27 by default, are cut short.  Keys
+/// are shown in `font-lock-constant-face', and the descriptions of
+/// prefix keys in `font-lock-keyword-face'.
+///
+/// The value is a string with one line for each row of the table, or nil
+/// if PREFIX has no bindings.
+#[lisp_fn(min = "1")]
+pub fn keymap_binding_table(
+    prefix: LispObject,
+    window: LispWindowLiveOrSelected,
+    max_description_width: Option<EmacsInt>,
+) -> LispObject {
+    let mut bindings = Qnil;
+    for map in current_active_maps(Qnil, Qnil)
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+    {
+        let map = lookup_key(map, prefix, Qt);
+        if keymapp(map) {
+            unsafe {
+                map_keymap(
+                    map,
+                    Some(collect_binding),
+                    Qnil,
+                    &mut bindings as *mut LispObject as *mut c_void,
+                    true,
+                )
+            };
+        }
+    }
+
+    let max_description_width =
+        max_description_width.map_or(MAX_DESCRIPTION_WIDTH, |width| width.max(3) as usize);
+    let separator_width = LispObject::from(SEPARATOR).force_string().width();
+    let mut entries: Vec<Entry> = bindings
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+        .filter(|&binding| is_shown(car(binding), cdr(binding)))
+        .map(|binding| {
+            let (key, def) = binding.into();
+            let (description, prefix) = describe_definition(def);
+            let description = truncate(description.force_string(), max_description_width);
+            let key_description = unsafe { Fsingle_key_description(key, Qnil) };
+            let width = key_description.force_string().width()
+                + separator_width
+                + description.force_string().width();
+            Entry {
+                key,
+                text: concat2(
+                    propertize(&[
+                        key_description,
+                        Qface,
+                        intern("font-lock-constant-face").into(),
+                    ]),
+                    concat2(
+                        LispObject::from(SEPARATOR),
+                        if prefix {
+                            propertize(&[
+                                description,
+                                Qface,
+                                intern("font-lock-keyword-face").into(),
+                            ])
+                        } else {
+                            description
+                        },
+                    ),
+                ),
+                width,
+            }
+        })
+        .collect();
+    if entries.is_empty() {
+        return Qnil;
+    }
+    entries.sort_by(|a, b| compare_keys(a.key, b.key));
+
+    // Fit as many columns as the window takes.  Columns are aligned to
+    // pixel positions, so variable pitch fonts don't spoil the table.
+    let window: LispWindowRef = window.into();
+    let window = LispObject::from(window);
+    let char_width = unsafe { Fframe_char_width(window_frame(window.into())) }
+        .as_fixnum_or_error()
+        .max(1) as usize;
+    let window_width = unsafe { Fwindow_body_width(window, Qt) }.as_fixnum_or_error() as usize;
+    let column_width = entries.iter().map(|entry| entry.width).max().unwrap_or(0) + COLUMN_GAP;
+    let columns = (window_width / (column_width * char_width)).max(1);
+    let rows = (entries.len() + columns - 1) / columns;
+
+    let mut pieces = Vec::with_capacity(entries.len() * 2 + rows);
+    for row in 0..rows {
+        let row_entries: Vec<&Entry> = entries.iter().skip(row).step_by(rows).collect();
+        for (column, entry) in row_entries.iter().enumerate() {
+            pieces.push(entry.text);
+            if column + 1 < row_entries.len() {
+                let align_to = ((column + 1) * column_width * char_width) as EmacsInt;
+                pieces.push(propertize(&[
+                    LispObject::from(" "),
+                    intern("display").into(),
+                    list!(
+                        intern("space").into(),
+                        intern(":align-to").into(),
+                        list!(LispObject::from(align_to))
+                    ),
+                ]));
+            }
+        }
+        pieces.push(LispObject::from("\n"));
+    }
+    concat_lisp(&mut pieces)
+}
+
+include!(concat!(env!("OUT_DIR"), "/binding_table_exports.rs"));
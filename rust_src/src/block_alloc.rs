@@ -0,0 +1,423 @@
+//! Allocation of conses and floats.
+//!
+//! Conses and floats live in blocks of `BLOCK_ALIGN` bytes, aligned on
+//! that boundary, so that the block of a cell and its index in it can
+//! be computed from the cell's address.  Their mark bits are kept in a
+//! bitmap at the end of the block rather than in the cells, which
+//! leaves all of a cell's bits to its contents.
+//!
+//! New cells are taken from the free list of cells reclaimed by garbage
+//! collection, and then from the newest block, whose cells after
+//! `BlockAllocator::index` have never been used.  When collecting
+//! garbage, the unmarked cells are put on the free list, and blocks
+//! left free entirely are given back, except the first two.
+
+use std::mem;
+use std::ptr;
+
+use libc::c_void;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    gc_stats::consing_since_gc,
+    lisp::{defsubr, ExternalPtr, LispObject},
+    remacs_sys::{
+        globals, lisp_align_free, lisp_align_malloc_conses, lisp_align_malloc_floats, Vdead,
+    },
+    remacs_sys::{EmacsDouble, EmacsInt, Lisp_Cons, Lisp_Float, Lisp_Type, Qnil},
+};
+
+/// The alignment and size of a block.  This must match `BLOCK_ALIGN`
+/// in alloc.c.
+const BLOCK_ALIGN: usize = 1 << 10;
+
+/// The bytes of a block that can be used, which `lisp_align_malloc`
+/// keeps a pointer after.
+const BLOCK_BYTES: usize = BLOCK_ALIGN - mem::size_of::<*mut c_void>();
+
+const BITS_PER_WORD: usize = mem::size_of::<usize>() * 8;
+
+/// Return the number of cells of SIZE bytes that fit in a block with
+/// a mark bit each, the pointer to the next block, and padding.
+const fn block_size(size: usize) -> usize {
+    ((BLOCK_BYTES - mem::size_of::<*mut c_void>() - (size - mem::size_of::<usize>())) * 8)
+        / (size * 8 + 1)
+}
+
+const CONS_BLOCK_SIZE: usize = block_size(mem::size_of::<Lisp_Cons>());
+const FLOAT_BLOCK_SIZE: usize = block_size(mem::size_of::<Lisp_Float>());
+
+/// A block of cells.  The cells must come first, so that their index
+/// can be computed from their offset in the block.
+trait Block: Sized {
+    type Cell;
+
+    /// The number of cells in a block.
+    const SIZE: usize;
+
+    /// Allocate a new block, with no cell marked.
+    unsafe fn allocate() -> *mut Self;
+
+    fn cells(&mut self) -> *mut Self::Cell;
+    fn gcmarkbits(&mut self) -> &mut [usize];
+    fn next(&mut self) -> &mut *mut Self;
+
+    /// Make CELL free and chain it to NEXT.
+    unsafe fn free_cell(cell: *mut Self::Cell, next: *mut Self::Cell);
+
+    /// Return the cell the free CELL is chained to.
+    unsafe fn next_free(cell: *mut Self::Cell) -> *mut Self::Cell;
+
+    fn is_marked(&mut self, index: usize) -> bool {
+        self.gcmarkbits()[index / BITS_PER_WORD] & (1 << (index % BITS_PER_WORD)) != 0
+    }
+
+    fn mark(&mut self, index: usize) {
+        self.gcmarkbits()[index / BITS_PER_WORD] |= 1 << (index % BITS_PER_WORD);
+    }
+
+    fn unmark(&mut self, index: usize) {
+        self.gcmarkbits()[index / BITS_PER_WORD] &= !(1 << (index % BITS_PER_WORD));
+    }
+}
+
+/// Return the block holding CELL, and the index of CELL in it.
+fn block_of<B: Block>(cell: *const B::Cell) -> (*mut B, usize) {
+    let address = cell as usize;
+    (
+        (address & !(BLOCK_ALIGN - 1)) as *mut B,
+        (address & (BLOCK_ALIGN - 1)) / mem::size_of::<B::Cell>(),
+    )
+}
+
+#[repr(C)]
+struct ConsBlock {
+    conses: [Lisp_Cons; CONS_BLOCK_SIZE],
+    gcmarkbits: [usize; 1 + CONS_BLOCK_SIZE / BITS_PER_WORD],
+    next: *mut ConsBlock,
+}
+
+impl Block for ConsBlock {
+    type Cell = Lisp_Cons;
+    const SIZE: usize = CONS_BLOCK_SIZE;
+
+    unsafe fn allocate() -> *mut Self {
+        let b = lisp_align_malloc_conses(mem::size_of::<Self>()) as *mut Self;
+        (*b).gcmarkbits = [0; 1 + CONS_BLOCK_SIZE / BITS_PER_WORD];
+        b
+    }
+
+    fn cells(&mut self) -> *mut Lisp_Cons {
+        self.conses.as_mut_ptr()
+    }
+
+    fn gcmarkbits(&mut self) -> &mut [usize] {
+        &mut self.gcmarkbits
+    }
+
+    fn next(&mut self) -> &mut *mut Self {
+        &mut self.next
+    }
+
+    // The cdr is used for chaining, and the car tells free conses from
+    // live ones.
+    unsafe fn free_cell(cell: *mut Lisp_Cons, next: *mut Lisp_Cons) {
+        (*cell).u.s.u.chain = next;
+        (*cell).u.s.car = Vdead;
+    }
+
+    unsafe fn next_free(cell: *mut Lisp_Cons) -> *mut Lisp_Cons {
+        (*cell).u.s.u.chain
+    }
+}
+
+#[repr(C)]
+struct FloatBlock {
+    floats: [Lisp_Float; FLOAT_BLOCK_SIZE],
+    gcmarkbits: [usize; 1 + FLOAT_BLOCK_SIZE / BITS_PER_WORD],
+    next: *mut FloatBlock,
+}
+
+impl Block for FloatBlock {
+    type Cell = Lisp_Float;
+    const SIZE: usize = FLOAT_BLOCK_SIZE;
+
+    unsafe fn allocate() -> *mut Self {
+        let b = lisp_align_malloc_floats(mem::size_of::<Self>()) as *mut Self;
+        (*b).gcmarkbits = [0; 1 + FLOAT_BLOCK_SIZE / BITS_PER_WORD];
+        b
+    }
+
+    fn cells(&mut self) -> *mut Lisp_Float {
+        self.floats.as_mut_ptr()
+    }
+
+    fn gcmarkbits(&mut self) -> &mut [usize] {
+        &mut self.gcmarkbits
+    }
+
+    fn next(&mut self) -> &mut *mut Self {
+        &mut self.next
+    }
+
+    unsafe fn free_cell(cell: *mut Lisp_Float, next: *mut Lisp_Float) {
+        (*cell).u.chain = next;
+    }
+
+    unsafe fn next_free(cell: *mut Lisp_Float) -> *mut Lisp_Float {
+        (*cell).u.chain
+    }
+}
+
+struct BlockAllocator<B, C> {
+    /// The newest block, which links to the older ones.
+    block: *mut B,
+    /// The index of the first cell of `block` that was never used.
+    index: usize,
+    free_list: *mut C,
+}
+
+impl<B, C> BlockAllocator<B, C> {
+    const fn new() -> Self {
+        Self {
+            block: ptr::null_mut(),
+            index: 0,
+            free_list: ptr::null_mut(),
+        }
+    }
+}
+
+impl<B: Block<Cell = C>, C> BlockAllocator<B, C> {
+    /// Return a new cell, counting it off TOTAL_FREE.
+    unsafe fn allocate(&mut self, total_free: &mut EmacsInt) -> *mut C {
+        let cell = if !self.free_list.is_null() {
+            let cell = self.free_list;
+            self.free_list = B::next_free(cell);
+            cell
+        } else {
+            if self.block.is_null() || self.index == B::SIZE {
+                let b = B::allocate();
+                *(*b).next() = self.block;
+                self.block = b;
+                self.index = 0;
+                *total_free += B::SIZE as EmacsInt;
+            }
+            let cell = (*self.block).cells().add(self.index);
+            self.index += 1;
+            cell
+        };
+        *total_free -= 1;
+        cell
+    }
+
+    /// Put CELL back on the free list.
+    unsafe fn free(&mut self, cell: *mut C) {
+        B::free_cell(cell, self.free_list);
+        self.free_list = cell;
+    }
+
+    /// If P points into a cell of BLOCK that was handed out, return the
+    /// cell, and the offset of P in it.
+    unsafe fn cell_holding(&self, block: *mut B, p: *const c_void) -> Option<(*mut C, usize)> {
+        let start = (*block).cells() as usize;
+        let p = p as usize;
+        let size = mem::size_of::<C>();
+        if p < start || p - start >= B::SIZE * size {
+            return None;
+        }
+        let index = (p - start) / size;
+        if block == self.block && index >= self.index {
+            return None;
+        }
+        Some(((*block).cells().add(index), (p - start) % size))
+    }
+
+    /// Put the cells that weren't marked on the free list, unmark the
+    /// others, and free the blocks left empty except two.  Return the
+    /// numbers of cells in use and free.
+    unsafe fn sweep(&mut self) -> (EmacsInt, EmacsInt) {
+        let (mut used, mut free) = (0, 0);
+        // Only the cells of the newest block before `index` were used.
+        let mut limit = self.index;
+        self.free_list = ptr::null_mut();
+
+        let mut prev: *mut *mut B = &mut self.block;
+        while !(*prev).is_null() {
+            let b = *prev;
+            let mut this_free = 0;
+            for i in 0..limit {
+                if (*b).is_marked(i) {
+                    (*b).unmark(i);
+                    used += 1;
+                } else {
+                    self.free((*b).cells().add(i));
+                    this_free += 1;
+                }
+            }
+            limit = B::SIZE;
+
+            if this_free == B::SIZE && free > B::SIZE {
+                // Unhook the block's cells from the free list, which
+                // they were all pushed onto from the first one on.
+                *prev = *(*b).next();
+                self.free_list = B::next_free((*b).cells());
+                lisp_align_free(b as *mut c_void);
+            } else {
+                free += this_free;
+                prev = (*b).next();
+            }
+        }
+        (used as EmacsInt, free as EmacsInt)
+    }
+}
+
+static mut CONSES: BlockAllocator<ConsBlock, Lisp_Cons> = BlockAllocator::new();
+static mut FLOATS: BlockAllocator<FloatBlock, Lisp_Float> = BlockAllocator::new();
+
+/// The numbers of conses and floats in use and free, as of the last
+/// garbage collection.
+#[no_mangle]
+pub static mut total_conses: EmacsInt = 0;
+#[no_mangle]
+pub static mut total_free_conses: EmacsInt = 0;
+#[no_mangle]
+pub static mut total_floats: EmacsInt = 0;
+#[no_mangle]
+pub static mut total_free_floats: EmacsInt = 0;
+
+/// The size of a cons block, for the memory reserve in alloc.c.
+#[no_mangle]
+pub static cons_block_bytes: usize = mem::size_of::<ConsBlock>();
+
+/// Create a new cons, give it CAR and CDR as components, and return it.
+#[lisp_fn]
+pub fn cons(car: LispObject, cdr: LispObject) -> LispObject {
+    unsafe {
+        let c = CONSES.allocate(&mut total_free_conses);
+        (*c).u.s.car = car;
+        (*c).u.s.u.cdr = cdr;
+        debug_assert!(!is_cons_marked(c));
+        consing_since_gc += mem::size_of::<Lisp_Cons>() as EmacsInt;
+        globals.cons_cells_consed += 1;
+        LispObject::tag_ptr(ExternalPtr::new(c), Lisp_Type::Lisp_Cons)
+    }
+}
+
+/// Return a new float object with value F.
+#[no_mangle]
+pub extern "C" fn make_float(f: EmacsDouble) -> LispObject {
+    unsafe {
+        let c = FLOATS.allocate(&mut total_free_floats);
+        (*c).u.data = f;
+        debug_assert!(!is_float_marked(c));
+        consing_since_gc += mem::size_of::<Lisp_Float>() as EmacsInt;
+        globals.floats_consed += 1;
+        LispObject::tag_ptr(ExternalPtr::new(c), Lisp_Type::Lisp_Float)
+    }
+}
+
+/// Explicitly free a cons cell by putting it on the free list.
+#[no_mangle]
+pub unsafe extern "C" fn free_cons(c: *mut Lisp_Cons) {
+    CONSES.free(c);
+    consing_since_gc -= mem::size_of::<Lisp_Cons>() as EmacsInt;
+    total_free_conses += 1;
+}
+
+unsafe fn is_cons_marked(c: *const Lisp_Cons) -> bool {
+    let (b, i) = block_of::<ConsBlock>(c);
+    (*b).is_marked(i)
+}
+
+unsafe fn is_float_marked(f: *const Lisp_Float) -> bool {
+    let (b, i) = block_of::<FloatBlock>(f);
+    (*b).is_marked(i)
+}
+
+/// Return true if the cons OBJ is marked.
+#[no_mangle]
+pub extern "C" fn cons_marked_p(obj: LispObject) -> bool {
+    unsafe { is_cons_marked(obj.get_untaggedptr() as *const Lisp_Cons) }
+}
+
+/// Return true if the float OBJ is marked.
+#[no_mangle]
+pub extern "C" fn float_marked_p(obj: LispObject) -> bool {
+    unsafe { is_float_marked(obj.get_untaggedptr() as *const Lisp_Float) }
+}
+
+/// Mark the cons OBJ, and return true if it wasn't marked already.
+#[no_mangle]
+pub extern "C" fn gc_mark_cons(obj: LispObject) -> bool {
+    let (b, i) = block_of::<ConsBlock>(obj.get_untaggedptr() as *const Lisp_Cons);
+    unsafe {
+        if (*b).is_marked(i) {
+            return false;
+        }
+        (*b).mark(i);
+    }
+    true
+}
+
+/// Mark the float OBJ.
+#[no_mangle]
+pub extern "C" fn gc_mark_float(obj: LispObject) {
+    let (b, i) = block_of::<FloatBlock>(obj.get_untaggedptr() as *const Lisp_Float);
+    unsafe { (*b).mark(i) };
+}
+
+/// Return the live cons in the cons block BLOCK that P points into, or
+/// nil if there is none.
+#[no_mangle]
+pub unsafe extern "C" fn live_cons_in_block(block: *mut c_void, p: *const c_void) -> LispObject {
+    match CONSES.cell_holding(block as *mut ConsBlock, p) {
+        Some((c, _)) if !(*c).u.s.car.eq(Vdead) => {
+            LispObject::tag_ptr(ExternalPtr::new(c), Lisp_Type::Lisp_Cons)
+        }
+        _ => Qnil,
+    }
+}
+
+/// Return true if P points to the start of a float in the float block
+/// BLOCK that was handed out.  Free floats can't be told from live ones.
+#[no_mangle]
+pub unsafe extern "C" fn live_float_in_block(block: *mut c_void, p: *const c_void) -> bool {
+    match FLOATS.cell_holding(block as *mut FloatBlock, p) {
+        Some((_, offset)) => offset == 0,
+        None => false,
+    }
+}
+
+/// Free the conses that weren't marked, and unmark the others.
+#[no_mangle]
+pub unsafe extern "C" fn sweep_conses() {
+    let (used, free) = CONSES.sweep();
+    total_conses = used;
+    total_free_conses = free;
+}
+
+/// Free the floats that weren't marked, and unmark the others.
+#[no_mangle]
+pub unsafe extern "C" fn sweep_floats() {
+    let (used, free) = FLOATS.sweep();
+    total_floats = used;
+    total_free_floats = free;
+}
+
+include!(concat!(env!("OUT_DIR"), "/block_alloc_exports.rs"));
+
+#[test]
+fn test_block_size() {
+    for &(size, n) in &[
+        (mem::size_of::<Lisp_Cons>(), CONS_BLOCK_SIZE),
+        (mem::size_of::<Lisp_Float>(), FLOAT_BLOCK_SIZE),
+    ] {
+        let marks = (1 + n / BITS_PER_WORD) * mem::size_of::<usize>();
+        assert!(n * size + marks + mem::size_of::<*mut c_void>() <= BLOCK_BYTES);
+        // There is no room for many more.
+        assert!((n + 2) * size > BLOCK_BYTES - marks - mem::size_of::<*mut c_void>());
+    }
+    assert!(mem::size_of::<ConsBlock>() <= BLOCK_BYTES);
+    assert!(mem::size_of::<FloatBlock>() <= BLOCK_BYTES);
+}
@@ -0,0 +1,164 @@
+//! Collecting the entries of the Buffer Menu.  buff-menu.el sets up the
+//! table and prints it; the columns of each buffer's line are computed
+//! here, without making every buffer current in turn.
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::LispBufferRef,
+    editfns::propertize,
+    lisp::{defsubr, LispObject},
+    lists::{LispConsCircularChecks, LispConsEndChecks},
+    multibyte::LispStringRef,
+    obarray::intern,
+    remacs_sys::{buffer_local_value, EmacsInt, Fformat_mode_line, Fsubstring, Fvector},
+    remacs_sys::{Qnil, Qunbound},
+    sequences::{concat2, nreverse},
+    symbols::{boundp, symbol_value},
+    threads::ThreadState,
+};
+
+/// Return the value of VARIABLE in BUFFER, or nil if it is void there.
+fn local_value(variable: &str, buffer: LispBufferRef) -> LispObject {
+    let value = unsafe { buffer_local_value(intern(variable).into(), buffer.into()) };
+    if value.eq(Qunbound) {
+        Qnil
+    } else {
+        value
+    }
+}
+
+/// Return the string `truncate-string-to-width' marks a cut with.
+fn ellipsis() -> LispObject {
+    let ellipsis = intern("truncate-string-ellipsis");
+    if boundp(ellipsis) {
+        symbol_value(ellipsis)
+    } else {
+        LispObject::from("...")
+    }
+}
+
+/// Return LABEL cut short to WIDTH columns, the way `tabulated-list-print'
+/// would.  A label that is cut short keeps its full text as its
+/// `help-echo', headed by the name of COLUMN.
+fn truncate(label: LispStringRef, width: usize, column: &str) -> LispObject {
+    if label.width() <= width {
+        return label.into();
+    }
+    let ellipsis = ellipsis();
+    let ellipsis_width = ellipsis.force_string().width();
+    let (end, _) = label.width_prefix(width.saturating_sub(ellipsis_width));
+    let help_echo = concat2(LispObject::from(column), concat2(": ".into(), label.into()));
+    propertize(&[
+        concat2(
+            unsafe { Fsubstring(label.into(), LispObject::from(0), LispObject::from(end)) },
+            ellipsis,
+        ),
+        intern("help-echo").into(),
+        help_echo,
+    ])
+}
+
+/// Return the mode column for BUFFER: its mode name followed by its
+/// `mode-line-process', if any.
+fn mode_string(buffer: LispBufferRef) -> LispObject {
+    let format = |spec| unsafe { Fformat_mode_line(spec, Qnil, Qnil, buffer.into()) };
+    let mode = format(local_value("mode-name", buffer));
+    let process = local_value("mode-line-process", buffer);
+    if process.is_nil() {
+        mode
+    } else {
+        concat2(mode, format(process))
+    }
+}
+
+/// Return the file column for BUFFER.
+fn file_string(buffer: LispBufferRef) -> LispObject {
+    let file = buffer.filename();
+    if file.is_not_nil() {
+        return call!(intern("abbreviate-file-name").into(), file);
+    }
+    let directory = local_value("list-buffers-directory", buffer);
+    if directory.is_not_nil() {
+        directory
+    } else {
+        LispObject::from("")
+    }
+}
+
+/// Return whether BUFFER belongs in a Buffer Menu filtered by FILTER.
+fn is_listed(buffer: LispBufferRef, filter: LispObject, menu_buffer: LispBufferRef) -> bool {
+    if filter.is_nil() {
+        return true;
+    }
+    let has_file = buffer.filename().is_not_nil();
+    let hidden = buffer
+        .name()
+        .force_string()
+        .as_slice()
+        .first()
+        .map_or(false, |&c| c == b' ');
+    (has_file || !hidden) && buffer != menu_buffer && (has_file || !filter.eq(intern("files")))
+}
+
+/// Return the Buffer Menu entries for the buffers in BUFFER-LIST.
+/// Each entry is a list (BUFFER [C R M NAME SIZE MODE FILE]) whose vector
+/// holds the strings shown in the columns of BUFFER's line, in the form
+/// `tabulated-list-entries' wants.  Killed buffers are left out.
+///
+/// If FILTER is non-nil, also leave out the current buffer, which is
+/// taken to be the Buffer Menu itself, and buffers whose names start
+/// with a space unless they visit a file.  If FILTER is `files', leave
+/// out all buffers that don't visit a file.
+///
+/// The C column of OLD-BUFFER shows a `.'.  If MODE-WIDTH is non-nil,
+/// mode names wider than that many columns are cut short, keeping the
+/// full name as their `help-echo'.
+#[lisp_fn(min = "1", name = "buffer-menu--entries")]
+pub fn buffer_menu_entries(
+    buffer_list: LispObject,
+    filter: LispObject,
+    old_buffer: LispObject,
+    mode_width: Option<EmacsInt>,
+) -> LispObject {
+    let menu_buffer = ThreadState::current_buffer_unchecked();
+    let flag = |set: bool, mark: &str| LispObject::from(if set { mark } else { " " });
+    let mut entries = Qnil;
+    for object in buffer_list.iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on) {
+        let buffer: LispBufferRef = object.into();
+        if !buffer.is_live() || !is_listed(buffer, filter, menu_buffer) {
+            continue;
+        }
+        let name = propertize(&[
+            buffer.name(),
+            intern("font-lock-face").into(),
+            intern("buffer-menu-buffer").into(),
+            intern("mouse-face").into(),
+            intern("highlight").into(),
+        ]);
+        let size = LispObject::from(format!("{}", buffer.z() - buffer.beg()).as_str());
+        let mode = mode_string(buffer);
+        let mode = match mode_width {
+            Some(width) => truncate(mode.force_string(), width.max(0) as usize, "Mode"),
+            None => mode,
+        };
+        let file = file_string(buffer);
+        let columns = callN_raw!(
+            Fvector,
+            flag(object.eq(old_buffer), "."),
+            flag(buffer.is_read_only(), "%"),
+            flag(
+                buffer.modifications_since_save() < buffer.modifications(),
+                "*"
+            ),
+            name,
+            size,
+            mode,
+            file
+        );
+        entries = LispObject::cons(list!(object, columns), entries);
+    }
+    nreverse(entries)
+}
+
+include!(concat!(env!("OUT_DIR"), "/buffer_menu_exports.rs"));
@@ -15,6 +15,7 @@ use crate::{
     data::Lisp_Fwd,
     editfns::point,
     eval::unbind_to,
+    fns::internal_equal,
     frames::LispFrameRef,
     lisp::defsubr,
     lisp::{ExternalPtr, LispMiscRef, LispObject, LiveBufferIter},
@@ -26,8 +27,8 @@ use crate::{
     numbers::MOST_POSITIVE_FIXNUM,
     remacs_sys::{
         allocate_misc, bset_update_mode_line, buffer_local_flags, buffer_local_value,
-        buffer_window_count, concat2, del_range, delete_all_overlays, globals, internal_equal,
-        last_per_buffer_idx, lookup_char_property, make_timespec, marker_position, modify_overlay,
+        buffer_window_count, concat2, del_range, delete_all_overlays, globals, last_per_buffer_idx,
+        lookup_char_property, make_timespec, marker_position, modify_overlay,
         set_buffer_internal_1, specbind, unchain_both, unchain_marker, update_mode_lines,
     },
     remacs_sys::{
@@ -35,13 +36,14 @@ use crate::{
         Lisp_Misc_Type, Lisp_Overlay, Lisp_Type, Vbuffer_alist,
     },
     remacs_sys::{
-        windows_or_buffers_changed, Fcopy_sequence, Fexpand_file_name, Ffind_file_name_handler,
-        Fget_text_property, Fnconc, Fnreverse, Fwiden,
+        windows_or_buffers_changed, Fexpand_file_name, Ffind_file_name_handler, Fget_text_property,
+        Fnconc, Fwiden,
     },
     remacs_sys::{
         Qafter_string, Qbefore_string, Qbuffer_read_only, Qbufferp, Qget_file_buffer,
         Qinhibit_quit, Qinhibit_read_only, Qnil, Qoverlayp, Qt, Qunbound, UNKNOWN_MODTIME_NSECS,
     },
+    sequences::{copy_sequence, nreverse},
     strings::string_equal,
     threads::{c_specpdl_index, ThreadState},
 };
@@ -156,7 +158,6 @@ impl LispBufferRef {
         self.mark_
     }
 
-    #[allow(dead_code)]
     pub fn name(self) -> LispObject {
         self.name_
     }
@@ -177,6 +178,14 @@ impl LispBufferRef {
         self.case_fold_search_
     }
 
+    pub fn undo_list(self) -> LispObject {
+        self.undo_list_
+    }
+
+    pub fn set_undo_list(&mut self, list: LispObject) {
+        self.undo_list_ = list;
+    }
+
     // Check if buffer is live
     pub fn is_live(self) -> bool {
         self.name_.is_not_nil()
@@ -318,6 +327,11 @@ impl LispBufferRef {
 
     // Methods for accessing struct buffer_text fields
 
+    /// True if redisplay was requested for the text of this buffer.
+    pub fn text_needs_redisplay(self) -> bool {
+        unsafe { (*self.text).redisplay() }
+    }
+
     pub fn beg_addr(self) -> *mut c_uchar {
         unsafe { (*self.text).beg }
     }
@@ -353,6 +367,11 @@ impl LispBufferRef {
         unsafe { (*self.text).chars_modiff }
     }
 
+    /// Number of modifications to the buffer's overlays.
+    pub fn overlay_modifications(self) -> EmacsInt {
+        unsafe { (*self.text).overlay_modiff }
+    }
+
     pub fn z_byte(self) -> ptrdiff_t {
         unsafe { (*self.text).z_byte }
     }
@@ -369,6 +388,10 @@ impl LispBufferRef {
         unsafe { self.overlays_after.as_ref().map(|m| mem::transmute(m)) }
     }
 
+    pub fn has_overlays(self) -> bool {
+        !self.overlays_before.is_null() || !self.overlays_after.is_null()
+    }
+
     pub fn as_live(self) -> Option<LispBufferRef> {
         if self.is_live() {
             Some(self)
@@ -520,11 +543,9 @@ impl LispOverlayRef {
         depth: i32,
         ht: LispObject,
     ) -> bool {
-        unsafe {
-            let overlays_equal = internal_equal(self.start, other.start, kind, depth + 1, ht)
-                && internal_equal(self.end, other.end, kind, depth + 1, ht);
-            overlays_equal && internal_equal(self.plist, other.plist, kind, depth + 1, ht)
-        }
+        internal_equal(self.start, other.start, kind, depth + 1, ht)
+            && internal_equal(self.end, other.end, kind, depth + 1, ht)
+            && internal_equal(self.plist, other.plist, kind, depth + 1, ht)
     }
 }
 
@@ -679,8 +700,8 @@ pub fn buffer_list(frame: Option<LispFrameRef>) -> LispObject {
         None => list(&buffers),
 
         Some(frame) => {
-            let framelist = unsafe { Fcopy_sequence(frame.buffer_list) };
-            let prevlist = unsafe { Fnreverse(Fcopy_sequence(frame.buried_buffer_list)) };
+            let framelist = copy_sequence(frame.buffer_list);
+            let prevlist = nreverse(copy_sequence(frame.buried_buffer_list));
 
             // Remove any buffer that duplicates one in FRAMELIST or PREVLIST.
             buffers.retain(|e| member(*e, framelist) == Qnil && member(*e, prevlist) == Qnil);
@@ -792,7 +813,7 @@ pub fn overlay_buffer(overlay: LispOverlayRef) -> Option<LispBufferRef> {
 /// effect on OVERLAY.
 #[lisp_fn]
 pub fn overlay_properties(overlay: LispOverlayRef) -> LispObject {
-    unsafe { Fcopy_sequence(overlay.plist) }
+    copy_sequence(overlay.plist)
 }
 
 #[no_mangle]
@@ -877,7 +898,7 @@ pub fn overlay_lists() -> LispObject {
     let cur_buf = ThreadState::current_buffer_unchecked();
     let before = cur_buf.overlays_before().map_or(Qnil, &list_overlays);
     let after = cur_buf.overlays_after().map_or(Qnil, &list_overlays);
-    unsafe { (Fnreverse(before), Fnreverse(after)).into() }
+    (nreverse(before), nreverse(after)).into()
 }
 
 fn get_truename_buffer_1(filename: LispObject) -> LispObject {
@@ -1200,7 +1221,7 @@ pub unsafe extern "C" fn copy_overlays(
         let end = duplicate_marker(overlay.end);
 
         let mut overlay_new =
-            build_overlay(start, end, Fcopy_sequence(overlay.plist)).as_overlay_or_error();
+            build_overlay(start, end, copy_sequence(overlay.plist)).as_overlay_or_error();
 
         match tail {
             Some(mut tail_ref) => tail_ref.next = overlay_new.as_mut(),
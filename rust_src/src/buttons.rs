@@ -0,0 +1,113 @@
+//! Buttons.
+//!
+//! A button made by button.el is either an overlay whose `button'
+//! property is the overlay itself, or text whose `button' property is
+//! non-nil.  In the mode line and header line, it is the (STRING . POS)
+//! of the string clicked on.  Every click and RET on a button looks up
+//! the button and its action, so that is done here.
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::overlay_get,
+    eval::unbind_to,
+    lisp::{defsubr, LispObject},
+    lists::{car_safe, nth},
+    marker::copy_marker,
+    obarray::intern,
+    remacs_sys::{record_unwind_current_buffer, set_buffer_internal_1},
+    remacs_sys::{Fget_text_property, Qnil, Qt},
+    textprop::get_char_property,
+    threads::c_specpdl_index,
+};
+
+/// Return the button at position POS in the current buffer, or nil.
+/// If the button at POS is a text property button, the return value
+/// is a marker pointing to POS.
+#[lisp_fn]
+pub fn button_at(pos: LispObject) -> LispObject {
+    let position = pos.as_fixnum_coerce_marker_or_error();
+    let button = get_char_property(position, intern("button").into(), Qnil);
+    if button.is_nil() || button.is_overlay() {
+        button
+    } else {
+        // Must be a text-property button; return a marker pointing to it.
+        copy_marker(position.into(), Qt)
+    }
+}
+
+/// Return the button at POSITION, a mouse position list as returned by
+/// `event-start', or nil.
+/// For a position in the mode line, header line or a display string,
+/// the button is the (STRING . STRING-POS) clicked on.  Otherwise, it is
+/// the button at the buffer position clicked on, as `button-at' returns
+/// it, in the buffer of the window clicked in.
+#[lisp_fn]
+pub fn button_at_posn(position: LispObject) -> LispObject {
+    let string = nth(4, position);
+    if string.is_not_nil() {
+        return string;
+    }
+
+    // The position is that of `posn-point'.
+    let area = nth(1, position);
+    let point = nth(5, position)
+        .as_fixnum()
+        .or_else(|| car_safe(area).as_fixnum())
+        .or_else(|| area.as_fixnum());
+    let point = match point {
+        Some(point) => point,
+        None => return Qnil,
+    };
+
+    let window = car_safe(position);
+    let mut buffer = match window.as_window().and_then(|w| w.contents.as_buffer()) {
+        Some(buffer) => buffer,
+        None => return button_at(point.into()),
+    };
+    if !buffer.is_live() {
+        return Qnil;
+    }
+
+    let count = c_specpdl_index();
+    unsafe {
+        record_unwind_current_buffer();
+        set_buffer_internal_1(buffer.as_mut());
+    }
+    unbind_to(count, button_at(point.into()))
+}
+
+/// Get the property of button BUTTON named PROP.
+#[lisp_fn]
+pub fn button_get(button: LispObject, prop: LispObject) -> LispObject {
+    if let Some(overlay) = button.as_overlay() {
+        return overlay_get(overlay, prop);
+    }
+    let (position, object) = match button.as_cons() {
+        // An area button, whose button-string is a string.
+        Some(cons) if cons.car().is_string() => (cons.cdr(), cons.car()),
+        _ => match button.as_marker() {
+            Some(marker) => (button, marker.buffer().map_or(Qnil, LispObject::from)),
+            // Must be a text-property button.
+            None => (button, Qnil),
+        },
+    };
+    unsafe { Fget_text_property(position, prop, object) }
+}
+
+/// Return the action BUTTON runs when activated.
+/// This is the `action' property of BUTTON, or, if USE-MOUSE-ACTION is
+/// non-nil and BUTTON has a `mouse-action' property, that property.
+/// See `button-activate' for what actions are.
+#[lisp_fn(min = "1")]
+pub fn button_action(button: LispObject, use_mouse_action: bool) -> LispObject {
+    if use_mouse_action {
+        let action = button_get(button, intern("mouse-action").into());
+        if action.is_not_nil() {
+            return action;
+        }
+    }
+    button_get(button, intern("action").into())
+}
+
+include!(concat!(env!("OUT_DIR"), "/buttons_exports.rs"));
@@ -9,7 +9,7 @@ use crate::{
 };
 
 // Temporary Rust wrapper for C's exec_byte_code
-fn rust_exec_byte_code(
+pub fn rust_exec_byte_code(
     bytestr: LispObject,
     vector: LispObject,
     maxdepth: LispObject,
@@ -4,24 +4,446 @@ use std::ffi::CString;
 use remacs_macros::lisp_fn;
 
 use crate::{
+    buffers::{validate_region, LispBufferRef},
+    casetab::{downcase_char, set_case_table_lisp, upcase_char},
+    chartable::LispCharTableRef,
+    insdel::signal_after_change,
     keymap::Ctl,
     lisp::defsubr,
     lisp::LispObject,
     lists::put,
     lists::{LispConsCircularChecks, LispConsEndChecks},
+    marker::buf_charpos_to_bytepos,
+    multibyte::{
+        char_to_byte8, is_ascii, make_char_multibyte, multibyte_char_at, multibyte_length_by_head,
+        write_codepoint, Codepoint, LispStringRef, MAX_MULTIBYTE_LENGTH,
+    },
     obarray::intern,
     remacs_sys::EmacsInt,
-    remacs_sys::{case_action, casify_object, casify_region},
-    remacs_sys::{control_x_map, initial_define_key, meta_map, scan_words, set_point},
-    remacs_sys::{Qdisabled, Qt},
+    remacs_sys::{case_action, char_bits, syntaxcode},
+    remacs_sys::{control_x_map, initial_define_key, meta_map, set_point},
+    remacs_sys::{
+        make_buffer_string, make_multibyte_string, modify_text, record_delete, record_insert,
+        replace_range_2, temp_set_point_both, uniprop_table, update_compositions, CHECK_ALL,
+    },
+    remacs_sys::{
+        Qchar_or_string_p, Qdisabled, Qspecial_lowercase, Qspecial_titlecase, Qspecial_uppercase,
+        Qt, Qtitlecase,
+    },
+    sequences::copy_sequence,
     symbols::symbol_value,
+    syntax::{current_syntax_table, scan_words, syntax_entry, SyntaxCode},
     threads::ThreadState,
 };
 
+/// In Greek, lower case sigma has two forms: one when used in the
+/// middle and one when used at the end of a word.  The rule does not
+/// conflict with any other casing rules so while it is a conditional
+/// one, it is independent of language.
+const GREEK_CAPITAL_LETTER_SIGMA: Codepoint = 0x03A3; // Σ
+const GREEK_SMALL_LETTER_FINAL_SIGMA: Codepoint = 0x03C2; // ς
+
+/// The case a single character is converted to.
+#[derive(Clone, Copy, PartialEq)]
+enum CharCase {
+    Upper,
+    Lower,
+    Title,
+}
+
+/// The result of casing a character.
+enum Cased {
+    /// A single character, which may be the original one.
+    Char(Codepoint),
+    /// Several characters, from the special casing rules, e.g. ß → SS.
+    Chars(LispStringRef),
+}
+
+/// State for casing individual characters.
+struct CasingContext {
+    /// A char-table with title-case character mappings, when the action
+    /// capitalizes.
+    titlecase_table: Option<LispCharTableRef>,
+
+    /// The unconditional special-casing Unicode property char tables for
+    /// upper casing, lower casing and title casing respectively.
+    specialcase_tables: [Option<LispCharTableRef>; 3],
+
+    /// User-requested action.
+    action: case_action,
+
+    /// If true, the text is in a buffer, as opposed to a string or
+    /// character.  In buffers, characters with the syntax prefix flag
+    /// don't start words.
+    in_buffer: bool,
+
+    /// Whether the context is within a word.
+    in_word: bool,
+
+    syntax_table: LispCharTableRef,
+}
+
+impl CasingContext {
+    fn new(action: case_action, in_buffer: bool) -> Self {
+        let capitalizes =
+            action == case_action::CASE_CAPITALIZE || action == case_action::CASE_CAPITALIZE_UP;
+        let table = |wanted: bool, prop: LispObject| {
+            if wanted {
+                unsafe { uniprop_table(prop) }.as_char_table()
+            } else {
+                None
+            }
+        };
+
+        // If the case table is flagged as modified, rescan it.
+        let downcase_table = ThreadState::current_buffer_unchecked().downcase_table_;
+        let extras = unsafe { LispCharTableRef::from(downcase_table).extras.as_slice(2) };
+        if extras[1].is_nil() {
+            set_case_table_lisp(downcase_table);
+        }
+
+        Self {
+            titlecase_table: table(capitalizes, Qtitlecase),
+            specialcase_tables: [
+                table(action != case_action::CASE_DOWN, Qspecial_uppercase),
+                table(action != case_action::CASE_UP, Qspecial_lowercase),
+                table(capitalizes, Qspecial_titlecase),
+            ],
+            action,
+            in_buffer,
+            in_word: false,
+            syntax_table: current_syntax_table(),
+        }
+    }
+
+    fn syntax(&self, c: Codepoint) -> SyntaxCode {
+        SyntaxCode::of_entry(syntax_entry(self.syntax_table, c))
+    }
+
+    fn is_word(&self, c: Codepoint) -> bool {
+        self.syntax(c).class() == syntaxcode::Sword as EmacsInt
+    }
+
+    /// Case the character C, updating the word state.  Special casing
+    /// rules that turn one character into several are applied only if
+    /// SPECIAL is true.
+    fn case_char(&mut self, c: Codepoint, special: bool) -> Cased {
+        let was_in_word = self.in_word;
+        self.in_word =
+            self.is_word(c) && (!self.in_buffer || was_in_word || !self.syntax(c).prefix());
+
+        let case = match self.action {
+            case_action::CASE_UP => CharCase::Upper,
+            case_action::CASE_DOWN => CharCase::Lower,
+            case_action::CASE_CAPITALIZE if was_in_word => CharCase::Lower,
+            case_action::CASE_CAPITALIZE => CharCase::Title,
+            case_action::CASE_CAPITALIZE_UP if was_in_word => return Cased::Char(c),
+            case_action::CASE_CAPITALIZE_UP => CharCase::Title,
+        };
+
+        // Look through the special casing entries.
+        let special_table = match case {
+            CharCase::Upper => self.specialcase_tables[0],
+            CharCase::Lower => self.specialcase_tables[1],
+            CharCase::Title => self.specialcase_tables[2],
+        };
+        if let Some(string) = special_table
+            .filter(|_| special)
+            .and_then(|table| table.get(c as isize).as_string())
+        {
+            return Cased::Chars(string);
+        }
+
+        // Handle the simple, one-to-one case.
+        if case == CharCase::Lower {
+            return Cased::Char(downcase_char(c));
+        }
+        let title = self
+            .titlecase_table
+            .map(|table| table.get(c as isize))
+            .filter(|prop| prop.is_character())
+            .map(|prop| prop.as_fixnum_or_error() as Codepoint);
+        Cased::Char(title.unwrap_or_else(|| upcase_char(c)))
+    }
+
+    /// Case the character C, without the special casing rules.  Characters
+    /// whose casing results in multiple code points are left alone.
+    fn case_single_char(&mut self, c: Codepoint) -> Codepoint {
+        match self.case_char(c, false) {
+            Cased::Char(cased) => cased,
+            Cased::Chars(_) => c,
+        }
+    }
+
+    /// Case the character C, followed by the character NEXT unless it is
+    /// the last character being cased.  Return `None` if C is unchanged.
+    fn case_char_in_context(&mut self, c: Codepoint, next: Option<Codepoint>) -> Option<Cased> {
+        let was_in_word = self.in_word;
+        match self.case_char(c, true) {
+            Cased::Char(cased) if cased == c => None,
+            // If we have just down-cased a capital sigma and the next
+            // character no longer has a word syntax (i.e. the current
+            // character is the end of a word), use final sigma.
+            Cased::Char(_)
+                if was_in_word
+                    && c == GREEK_CAPITAL_LETTER_SIGMA
+                    && next.map_or(true, |next| !self.is_word(next)) =>
+            {
+                Some(Cased::Char(GREEK_SMALL_LETTER_FINAL_SIGMA))
+            }
+            cased => Some(cased),
+        }
+    }
+}
+
+impl Cased {
+    /// Append the multibyte representation of the result to BYTES, and
+    /// return the number of characters appended.
+    fn write_to(&self, bytes: &mut Vec<u8>) -> usize {
+        match *self {
+            Cased::Char(c) => {
+                let mut buf = [0; MAX_MULTIBYTE_LENGTH];
+                let len = write_codepoint(&mut buf, c);
+                bytes.extend_from_slice(&buf[..len]);
+                1
+            }
+            Cased::Chars(string) => {
+                bytes.extend_from_slice(string.as_slice());
+                string.len_chars() as usize
+            }
+        }
+    }
+}
+
+fn casify_character(ctx: &mut CasingContext, obj: LispObject) -> LispObject {
+    let flagbits = char_bits::CHAR_MODIFIER_MASK as EmacsInt;
+    let ch = obj.as_fixnum_or_error();
+
+    // If the character has higher bits set above the flags, return it
+    // unchanged.  It is not a real character.
+    if ch > flagbits {
+        return obj;
+    }
+
+    let flags = ch & flagbits;
+    let mut c = (ch & !flagbits) as Codepoint;
+
+    // FIXME: Even if enable-multibyte-characters is nil, we may
+    // manipulate multibyte chars.  This means we have a bug for latin-1
+    // chars since when we receive an int 128-255 we can't tell whether
+    // it's an eight-bit byte or a latin-1 char.
+    let multibyte =
+        c >= 256 || ThreadState::current_buffer_unchecked().multibyte_characters_enabled();
+    if !multibyte {
+        c = make_char_multibyte(c);
+    }
+    let mut cased = ctx.case_single_char(c);
+    if cased == c {
+        return obj;
+    }
+
+    if !multibyte && !is_ascii(cased) {
+        cased = Codepoint::from(char_to_byte8(cased));
+    }
+    LispObject::from(EmacsInt::from(cased) | flags)
+}
+
+fn casify_multibyte_string(ctx: &mut CasingContext, string: LispStringRef) -> LispObject {
+    let mut bytes = Vec::with_capacity(string.len_bytes() as usize);
+    let mut nchars = 0;
+
+    let mut rest = string.as_slice();
+    while !rest.is_empty() {
+        let (c, len) = multibyte_char_at(rest);
+        rest = &rest[len..];
+        let next = if rest.is_empty() {
+            None
+        } else {
+            Some(multibyte_char_at(rest).0)
+        };
+        nchars += match ctx.case_char_in_context(c, next) {
+            Some(cased) => cased.write_to(&mut bytes),
+            None => Cased::Char(c).write_to(&mut bytes),
+        };
+    }
+
+    unsafe {
+        make_multibyte_string(
+            bytes.as_ptr() as *const libc::c_char,
+            nchars as isize,
+            bytes.len() as isize,
+        )
+    }
+}
+
+fn casify_unibyte_string(ctx: &mut CasingContext, string: LispStringRef) -> LispObject {
+    let mut copy = copy_sequence(string.into()).as_string_or_error();
+    for i in 0..copy.len_bytes() {
+        let c = make_char_multibyte(Codepoint::from(copy.byte_at(i)));
+        let cased = ctx.case_single_char(c);
+        if cased != c {
+            copy.set_byte(i, char_to_byte8(cased));
+        }
+    }
+    copy.into()
+}
+
+/// Common case-conversion routine, used by upcase, capitalize, etc.
+fn casify_object(action: case_action, obj: LispObject) -> LispObject {
+    let mut ctx = CasingContext::new(action, false);
+
+    if obj.is_natnum() {
+        casify_character(&mut ctx, obj)
+    } else if let Some(string) = obj.as_string() {
+        if string.len_chars() == 0 {
+            obj
+        } else if string.is_multibyte() {
+            casify_multibyte_string(&mut ctx, string)
+        } else {
+            casify_unibyte_string(&mut ctx, string)
+        }
+    } else {
+        wrong_type!(Qchar_or_string_p, obj)
+    }
+}
+
+/// The positions of the first and last changes made to a region, or
+/// `None` if nothing changed.
+type Changes = Option<(isize, isize)>;
+
+/// Case the text of the unibyte BUFFER from START to END.
+fn casify_unibyte_region(
+    ctx: &mut CasingContext,
+    buffer: LispBufferRef,
+    start: isize,
+    end: isize,
+) -> Changes {
+    let mut changes = None;
+
+    for pos in start..end {
+        let c = make_char_multibyte(Codepoint::from(buffer.fetch_byte(pos)));
+        let cased = ctx.case_single_char(c);
+        if cased == c {
+            continue;
+        }
+
+        changes = Some((changes.map_or(pos, |(first, _)| first), pos + 1));
+        unsafe { *buffer.byte_pos_addr(pos) = char_to_byte8(cased) };
+    }
+
+    changes
+}
+
+/// Case the text of the multibyte BUFFER from START to END.  Return the
+/// changes and the number of characters added, which is negative if
+/// more characters were deleted than inserted.
+fn casify_multibyte_region(
+    ctx: &mut CasingContext,
+    mut buffer: LispBufferRef,
+    start: isize,
+    end: isize,
+) -> (Changes, isize) {
+    let mut changes = None;
+    let mut added = 0;
+    let mut pos = start;
+    let mut pos_byte = buf_charpos_to_bytepos(buffer.as_mut(), pos);
+    let opoint = buffer.pt;
+    let mut new_point = opoint;
+
+    for remaining in (1..=end - start).rev() {
+        let c = buffer.fetch_multibyte_char(pos_byte) as Codepoint;
+        let len = multibyte_length_by_head(buffer.fetch_byte(pos_byte)) as isize;
+        let next = if remaining > 1 {
+            Some(buffer.fetch_multibyte_char(pos_byte + len) as Codepoint)
+        } else {
+            None
+        };
+        let cased = match ctx.case_char_in_context(c, next) {
+            Some(cased) => cased,
+            None => {
+                pos_byte += len;
+                pos += 1;
+                continue;
+            }
+        };
+
+        let mut bytes = Vec::with_capacity(MAX_MULTIBYTE_LENGTH);
+        let nchars = cased.write_to(&mut bytes) as isize;
+        changes = Some((changes.map_or(pos, |(first, _)| first), pos + nchars));
+
+        if nchars == 1 && bytes.len() as isize == len {
+            for (i, &byte) in bytes.iter().enumerate() {
+                unsafe { *buffer.byte_pos_addr(pos_byte + i as isize) = byte };
+            }
+        } else {
+            // Replace one character with the other(s), keeping text
+            // properties the same.
+            unsafe {
+                replace_range_2(
+                    pos,
+                    pos_byte,
+                    pos + 1,
+                    pos_byte + len,
+                    bytes.as_ptr() as *const libc::c_char,
+                    nchars,
+                    bytes.len() as isize,
+                    false,
+                )
+            };
+            added += nchars - 1;
+            if new_point > pos {
+                new_point += nchars - 1;
+            }
+        }
+
+        pos_byte += bytes.len() as isize;
+        pos += nchars;
+    }
+
+    if buffer.pt != new_point {
+        let new_point_byte = buf_charpos_to_bytepos(buffer.as_mut(), new_point);
+        unsafe { temp_set_point_both(buffer.as_mut(), new_point, new_point_byte) };
+    }
+
+    (changes, added)
+}
+
+/// Apply ACTION to the region of the current buffer between B and E.
+/// Return the position of the end of the region after the changes.
+fn casify_region(action: case_action, mut b: LispObject, mut e: LispObject) -> isize {
+    unsafe { validate_region(&mut b, &mut e) };
+    let start = b.as_fixnum_or_error() as isize;
+    let end = e.as_fixnum_or_error() as isize;
+    if start == end {
+        // Not modifying because nothing marked.
+        return end;
+    }
+    unsafe { modify_text(start, end) };
+    let mut ctx = CasingContext::new(action, true);
+    let buffer = ThreadState::current_buffer_unchecked();
+
+    unsafe { record_delete(start, make_buffer_string(start, end, true), false) };
+    let (changes, added) = if buffer.multibyte_characters_enabled() {
+        let result = casify_multibyte_region(&mut ctx, buffer, start, end);
+        unsafe { record_insert(start, end - start + result.1) };
+        result
+    } else {
+        unsafe { record_insert(start, end - start) };
+        (casify_unibyte_region(&mut ctx, buffer, start, end), 0)
+    };
+
+    if let Some((first, last)) = changes {
+        signal_after_change(first, last - first - added, last - first);
+        unsafe { update_compositions(first, last, CHECK_ALL as i32) };
+    }
+
+    end + added
+}
+
 fn casify_word(flag: case_action, words: EmacsInt) {
     let buffer_ref = ThreadState::current_buffer_unchecked();
 
-    let far_end = match unsafe { scan_words(buffer_ref.pt, words) } {
+    let far_end = match scan_words(buffer_ref.pt, words) {
         0 => {
             if words <= 0 {
                 buffer_ref.begv
@@ -32,13 +454,11 @@ fn casify_word(flag: case_action, words: EmacsInt) {
         n => n,
     };
 
-    let new_pos = unsafe {
-        casify_region(
-            flag,
-            LispObject::from(buffer_ref.pt),
-            LispObject::from(far_end),
-        )
-    };
+    let new_pos = casify_region(
+        flag,
+        LispObject::from(buffer_ref.pt),
+        LispObject::from(far_end),
+    );
 
     unsafe { set_point(new_pos) };
 }
@@ -52,7 +472,7 @@ fn casify_word(flag: case_action, words: EmacsInt) {
 /// cased, e.g. ﬁ, are returned unchanged.
 #[lisp_fn]
 pub fn capitalize(object: LispObject) -> LispObject {
-    unsafe { casify_object(case_action::CASE_CAPITALIZE, object) }
+    casify_object(case_action::CASE_CAPITALIZE, object)
 }
 
 /// Convert the region to capitalized form.
@@ -62,7 +482,7 @@ pub fn capitalize(object: LispObject) -> LispObject {
 /// positions to operate on.
 #[lisp_fn(intspec = "r")]
 pub fn capitalize_region(beg: LispObject, end: LispObject) {
-    unsafe { casify_region(case_action::CASE_CAPITALIZE, beg, end) };
+    casify_region(case_action::CASE_CAPITALIZE, beg, end);
 }
 
 /// Capitalize from point to the end of word, moving over.
@@ -84,7 +504,7 @@ pub fn capitalize_word(words: EmacsInt) {
 /// The argument object is not altered--the value is a copy.
 #[lisp_fn]
 pub fn downcase(object: LispObject) -> LispObject {
-    unsafe { casify_object(case_action::CASE_DOWN, object) }
+    casify_object(case_action::CASE_DOWN, object)
 }
 
 /// Convert the region to lower case.  In programs, wants two arguments.
@@ -118,7 +538,7 @@ pub fn downcase_word(words: EmacsInt) {
 /// See also `capitalize', `downcase' and `upcase-initials'.
 #[lisp_fn]
 pub fn upcase(object: LispObject) -> LispObject {
-    unsafe { casify_object(case_action::CASE_UP, object) }
+    casify_object(case_action::CASE_UP, object)
 }
 
 /* Like Fcapitalize but change only the initials.  */
@@ -132,7 +552,7 @@ pub fn upcase(object: LispObject) -> LispObject {
 /// points when cased, e.g. ﬁ, are returned unchanged.
 #[lisp_fn]
 pub fn upcase_initials(obj: LispObject) -> LispObject {
-    unsafe { casify_object(case_action::CASE_CAPITALIZE_UP, obj) }
+    casify_object(case_action::CASE_CAPITALIZE_UP, obj)
 }
 
 // Like Fcapitalize_region but change only the initials.
@@ -144,7 +564,7 @@ pub fn upcase_initials(obj: LispObject) -> LispObject {
 /// positions to operate on.
 #[lisp_fn(intspec = "r")]
 pub fn upcase_initials_region(beg: LispObject, end: LispObject) {
-    unsafe { casify_region(case_action::CASE_CAPITALIZE_UP, beg, end) };
+    casify_region(case_action::CASE_CAPITALIZE_UP, beg, end);
 }
 
 /// Convert the region to upper case.  In programs, wants two arguments.
@@ -181,7 +601,7 @@ fn casefiddle_region(
     action: case_action,
 ) {
     if !region_noncontiguous_p {
-        unsafe { casify_region(action, beg, end) };
+        casify_region(action, beg, end);
     } else {
         let bounds = call!(
             symbol_value(intern("region-extract-function")),
@@ -190,7 +610,7 @@ fn casefiddle_region(
 
         for elt in bounds.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
             let (car, cdr) = elt.into();
-            unsafe { casify_region(action, car, cdr) };
+            casify_region(action, car, cdr);
         }
     }
 }
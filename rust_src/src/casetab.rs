@@ -4,6 +4,7 @@ use remacs_macros::lisp_fn;
 
 use crate::{
     lisp::{defsubr, LispObject},
+    multibyte::Codepoint,
     objects::eq,
     remacs_sys::{set_case_table, Qcase_table, Vascii_downcase_table},
     threads::ThreadState,
@@ -75,6 +76,36 @@ pub fn set_standard_case_table(table: LispObject) -> LispObject {
     unsafe { set_case_table(table, true) }
 }
 
+/// Look C up in the case table TABLE, or return C if TABLE doesn't map it
+/// to a character.
+fn case_table_char(table: LispObject, c: Codepoint) -> Codepoint {
+    table
+        .as_char_table()
+        .and_then(|table| table.get(c as isize).as_fixnum())
+        .filter(|&mapped| mapped >= 0)
+        .map_or(c, |mapped| mapped as Codepoint)
+}
+
+/// Downcase the character C with the current buffer's case table.
+pub fn downcase_char(c: Codepoint) -> Codepoint {
+    case_table_char(ThreadState::current_buffer_unchecked().downcase_table_, c)
+}
+
+/// Upcase the character C with the current buffer's case table.
+pub fn upcase_char(c: Codepoint) -> Codepoint {
+    case_table_char(ThreadState::current_buffer_unchecked().upcase_table_, c)
+}
+
+/// Whether the character C is upper case.
+pub fn uppercasep(c: Codepoint) -> bool {
+    downcase_char(c) != c
+}
+
+/// Whether the character C is lower case.
+pub fn lowercasep(c: Codepoint) -> bool {
+    !uppercasep(c) && upcase_char(c) != c
+}
+
 #[no_mangle]
 pub extern "C" fn rust_syms_of_casetab() {
     def_lisp_sym!(Qcase_table_p, "case-table-p");
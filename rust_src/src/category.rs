@@ -3,7 +3,8 @@
 use crate::{
     chartable::LispCharTableRef,
     lisp::{defsubr, LispObject},
-    remacs_sys::Qcategory_table,
+    multibyte::{Codepoint, MAX_CHAR},
+    remacs_sys::{Qcategory_table, BITS_PER_BITS_WORD},
     threads::ThreadState,
 };
 
@@ -23,4 +24,19 @@ pub fn category_table() -> LispObject {
     buffer_ref.category_table_
 }
 
+/// Return whether the character C has CATEGORY, a character from ` ' to
+/// `~', in the current category table.  This is CHAR_HAS_CATEGORY.
+pub fn char_has_category(c: Codepoint, category: u8) -> bool {
+    if c > MAX_CHAR {
+        return false;
+    }
+    let table: LispCharTableRef = category_table().into();
+    table.get(c as isize).as_bool_vector().map_or(false, |set| {
+        let bits_per_word = BITS_PER_BITS_WORD as usize;
+        let category = category as usize;
+        category < set.len()
+            && set.as_slice()[category / bits_per_word] & (1 << (category % bits_per_word)) != 0
+    })
+}
+
 include!(concat!(env!("OUT_DIR"), "/category_exports.rs"));
@@ -5,14 +5,15 @@ use libc;
 use remacs_macros::lisp_fn;
 
 use crate::{
+    fns::internal_equal,
     lisp::defsubr,
     lisp::{ExternalPtr, LispObject},
+    remacs_sys::{uniprop_table_uncompress, Fmake_vector},
     remacs_sys::{
         char_table_specials, equal_kind, pvec_type, Lisp_Char_Table, Lisp_Sub_Char_Table,
         Lisp_Type, More_Lisp_Bits, CHARTAB_SIZE_BITS,
     },
-    remacs_sys::{internal_equal, uniprop_table_uncompress},
-    remacs_sys::{Qchar_code_property_table, Qchar_table_p},
+    remacs_sys::{Qchar_code_property_table, Qchar_table_p, Qnil},
 };
 
 pub type LispCharTableRef = ExternalPtr<Lisp_Char_Table>;
@@ -64,7 +65,7 @@ impl LispObject {
     }
 }
 
-fn chartab_size(depth: i32) -> usize {
+pub fn chartab_size(depth: i32) -> usize {
     match depth {
         0 => 1 << CHARTAB_SIZE_BITS::CHARTAB_SIZE_BITS_0 as isize,
         1 => 1 << CHARTAB_SIZE_BITS::CHARTAB_SIZE_BITS_1 as isize,
@@ -166,24 +167,17 @@ impl LispCharTableRef {
         // char table is 4 LispObjects + an array
         size1 -= 4;
 
-        unsafe {
-            if !internal_equal(self.defalt, other.defalt, kind, depth + 1, ht) {
-                return false;
-            }
-            if !internal_equal(self.parent, other.parent, kind, depth + 1, ht) {
-                return false;
-            }
-            if !internal_equal(self.purpose, other.purpose, kind, depth + 1, ht) {
-                return false;
-            }
-            if !internal_equal(self.ascii, other.ascii, kind, depth + 1, ht) {
-                return false;
-            }
+        if !internal_equal(self.defalt, other.defalt, kind, depth + 1, ht)
+            || !internal_equal(self.parent, other.parent, kind, depth + 1, ht)
+            || !internal_equal(self.purpose, other.purpose, kind, depth + 1, ht)
+            || !internal_equal(self.ascii, other.ascii, kind, depth + 1, ht)
+        {
+            return false;
         }
         for i in 0..size1 {
             let v1 = self.contents[i];
             let v2 = other.contents[i];
-            if !unsafe { internal_equal(v1, v2, kind, depth + 1, ht) } {
+            if !internal_equal(v1, v2, kind, depth + 1, ht) {
                 return false;
             }
         }
@@ -194,7 +188,7 @@ impl LispCharTableRef {
             for i in 0..extras {
                 let v1 = self_extras[i];
                 let v2 = other_extras[i];
-                if !unsafe { internal_equal(v1, v2, kind, depth + 1, ht) } {
+                if !internal_equal(v1, v2, kind, depth + 1, ht) {
                     return false;
                 }
             }
@@ -233,6 +227,26 @@ impl From<LispSubCharTableRef> for LispObject {
 }
 
 impl LispSubCharTableRef {
+    /// Make a sub char-table of DEPTH for the characters from MIN_CHAR
+    /// on, with all its contents nil.
+    pub fn new(depth: i32, min_char: i32) -> Self {
+        let slots = char_table_specials::SUB_CHAR_TABLE_OFFSET as usize + chartab_size(depth);
+        let mut vector = unsafe { Fmake_vector(slots.into(), Qnil) }
+            .as_vectorlike()
+            .unwrap();
+        vector.set_pseudovector_type(pvec_type::PVEC_SUB_CHAR_TABLE);
+        let mut table = vector.as_sub_char_table().unwrap();
+        table.depth = depth;
+        table.min_char = min_char;
+        table
+    }
+
+    /// Set the IDXth slot of the contents to VALUE.
+    pub fn set(&mut self, idx: usize, value: LispObject) {
+        let size = chartab_size(self.depth);
+        unsafe { self.contents.as_mut_slice(size)[idx] = value };
+    }
+
     fn _get(self, idx: usize) -> LispObject {
         unsafe {
             let d = self.depth;
@@ -0,0 +1,769 @@
+//! Command line option parsing.
+//!
+//! `main' in emacs.c acts on the options that are needed before Lisp is
+//! available, and `command-line' in startup.el handles the rest.  For
+//! that to work, the arguments are first sorted so that the most
+//! important options come first.  `OPTIONS' describes every option Emacs
+//! knows about: how it is spelled, how it is sorted, and how `--help'
+//! documents it.
+
+use std::ffi::CStr;
+use std::io::{self, Write};
+use std::ptr;
+use std::sync::Mutex;
+
+use libc::{c_char, c_int};
+
+use crate::{
+    data::set,
+    lisp::LispObject,
+    obarray::intern,
+    remacs_sys::Qnil,
+    remacs_sys::{fatal, globals, initialized},
+    remacs_sys::{Fexpand_file_name, Ffile_name_as_directory},
+    string_alloc::make_unibyte_string,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Section {
+    Initialization,
+    Action,
+    Display,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OptionArg {
+    None,
+    /// An argument that is either the next element of argv or given as
+    /// `--OPTION=VALUE`, with the name `--help' uses for it.
+    Required(&'static str),
+    /// An argument that can only be given as `--OPTION=VALUE`.
+    Optional(&'static str),
+}
+
+pub struct CliOption {
+    /// Spellings that only match exactly, such as `-d` or `-display`.
+    pub names: &'static [&'static str],
+    /// Spellings that may be abbreviated, such as `--display`.
+    pub longnames: &'static [&'static str],
+    /// Options with a higher priority are moved towards the front of argv.
+    /// Options with priority 0 stay in place relative to file names.
+    pub priority: i32,
+    pub arg: OptionArg,
+    pub section: Section,
+    /// The description printed by `--help'.  Undocumented options have
+    /// none.
+    pub doc: Option<&'static str>,
+}
+
+impl CliOption {
+    fn takes_argument(&self) -> bool {
+        if let OptionArg::Required(_) = self.arg {
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The spellings shown by `--help', such as `--display, -d DISPLAY`.
+    fn usage(&self) -> String {
+        let mut spellings: Vec<&str> = Vec::new();
+        spellings.extend(self.longnames.first());
+        // `-batch` need not be listed next to `--batch`.
+        spellings.extend(self.names.iter().filter(|name| {
+            self.longnames
+                .first()
+                .map_or(true, |long| long[1..] != name[..])
+        }));
+
+        let mut usage = spellings.join(", ");
+        match self.arg {
+            OptionArg::None => {}
+            OptionArg::Required(arg) => {
+                usage.push(' ');
+                usage.push_str(arg);
+            }
+            OptionArg::Optional(arg) => {
+                usage.push_str("[=");
+                usage.push_str(arg);
+                usage.push(']');
+            }
+        }
+        usage
+    }
+}
+
+macro_rules! option {
+    ($names:expr, $longnames:expr, $priority:expr, $arg:expr, $section:ident, $doc:expr) => {
+        CliOption {
+            names: &$names,
+            longnames: &$longnames,
+            priority: $priority,
+            arg: $arg,
+            section: Section::$section,
+            doc: $doc,
+        }
+    };
+}
+
+use self::OptionArg::{None as NoArg, Optional, Required};
+
+/// The options Emacs knows about on every platform, in decreasing order
+/// of priority.
+#[cfg_attr(rustfmt, rustfmt_skip)]
+static OPTIONS: &[CliOption] = &[
+    option!(["-version"], ["--version"], 150, NoArg, Display,
+            Some("output version information and exit")),
+    option!(["-chdir"], ["--chdir"], 130, Required("DIR"), Initialization,
+            Some("change to directory DIR")),
+    option!(["-t"], ["--terminal"], 120, Required("DEVICE"), Initialization,
+            Some("use DEVICE for terminal I/O")),
+    option!(["-nw"], ["--no-window-system", "--no-windows"], 110, NoArg, Initialization,
+            Some("do not communicate with X, ignoring $DISPLAY")),
+    option!(["-batch"], ["--batch"], 100, NoArg, Initialization,
+            Some("do not do interactive display; implies -q")),
+    option!(["-script"], ["--script"], 100, Required("FILE"), Initialization,
+            Some("run FILE as an Emacs Lisp script")),
+    option!(["-daemon"], ["--daemon"], 99, Optional("NAME"), Initialization,
+            Some("start a (named) server in the background")),
+    option!(["-bg-daemon"], ["--bg-daemon"], 99, Optional("NAME"), Initialization,
+            Some("same as --daemon")),
+    option!(["-fg-daemon"], ["--fg-daemon"], 99, Optional("NAME"), Initialization,
+            Some("start a (named) server in the foreground")),
+    option!(["-help"], ["--help"], 90, NoArg, Display,
+            Some("display this help and exit")),
+    option!(["-nl"], ["--no-loadup"], 70, NoArg, Initialization,
+            Some("do not load loadup.el into bare Emacs")),
+    option!(["-nsl"], ["--no-site-lisp"], 65, NoArg, Initialization,
+            Some("do not add site-lisp directories to load-path")),
+    option!(["-no-build-details"], ["--no-build-details"], 63, NoArg, Initialization,
+            Some("do not add build details such as time stamps")),
+    // -d must come last before the options handled in startup.el.
+    option!(["-d", "-display"], ["--display"], 60, Required("DISPLAY"), Initialization,
+            Some("use X server DISPLAY")),
+    // This one is handled entirely in `init_cli'.
+    option!(["-init-directory"], ["--init-directory"], 58, Required("DIR"), Initialization,
+            Some("use DIR when looking for the Emacs init files")),
+    // Now for the options handled in `command-line' (startup.el).
+    // (Note that to imply -nsl, -Q is partially handled in emacs.c.)
+    option!(["-Q", "-quick"], ["--quick"], 55, NoArg, Initialization,
+            Some("equivalent to:\n\
+                  -q --no-site-file --no-site-lisp --no-splash\n\
+                  --no-x-resources")),
+    option!(["-q", "-no-init-file"], ["--no-init-file"], 50, NoArg, Initialization,
+            Some("load neither ~/.emacs nor default.el")),
+    option!(["-no-x-resources"], ["--no-x-resources"], 40, NoArg, Initialization,
+            Some("do not load X resources")),
+    option!(["-no-site-file"], ["--no-site-file"], 40, NoArg, Initialization,
+            Some("do not load site-start.el")),
+    option!(["-u", "-user"], ["--user"], 30, Required("USER"), Initialization,
+            Some("load ~USER/.emacs instead of your own")),
+    option!(["-debug-init"], ["--debug-init"], 20, NoArg, Initialization,
+            Some("enable Emacs Lisp debugger for init file")),
+    option!(["-iconic"], ["--iconic"], 15, NoArg, Display,
+            Some("start Emacs in iconified state")),
+    option!(["-D", "-basic-display"], ["--basic-display"], 12, NoArg, Display,
+            Some("disable many display features;\nused for debugging Emacs")),
+    option!(["-nbc"], ["--no-blinking-cursor"], 12, NoArg, Display,
+            Some("disable blinking cursor")),
+    // Now for the options handled in `command-line-1' (startup.el).
+    option!(["-nbi"], ["--no-bitmap-icon"], 10, NoArg, Display,
+            Some("do not use picture of gnu for Emacs icon")),
+    option!(["-bg", "-background"], ["--background-color"], 10, Required("COLOR"), Display,
+            Some("window background color")),
+    option!(["-fg", "-foreground"], ["--foreground-color"], 10, Required("COLOR"), Display,
+            Some("window foreground color")),
+    option!(["-bd"], ["--border-color"], 10, Required("COLOR"), Display,
+            Some("main border color")),
+    option!(["-bw"], ["--border-width"], 10, Required("WIDTH"), Display,
+            Some("width of main border")),
+    option!(["-ib"], ["--internal-border"], 10, Required("WIDTH"), Display,
+            Some("width between text and main border")),
+    option!(["-ms"], ["--mouse-color"], 10, Required("COLOR"), Display,
+            Some("mouse cursor color in Emacs window")),
+    option!(["-cr"], ["--cursor-color"], 10, Required("COLOR"), Display,
+            Some("color of the Emacs cursor indicating point")),
+    option!(["-fn", "-font"], ["--font"], 10, Required("FONT"), Display,
+            Some("default font; must be fixed-width")),
+    option!(["-fs"], ["--fullscreen"], 10, NoArg, Display,
+            Some("make the first frame fullscreen")),
+    option!(["-fw"], ["--fullwidth"], 10, NoArg, Display,
+            Some("make the first frame wide as the screen")),
+    option!(["-fh"], ["--fullheight"], 10, NoArg, Display,
+            Some("make the first frame high as the screen")),
+    option!(["-mm"], ["--maximized"], 10, NoArg, Display,
+            Some("make the first frame maximized")),
+    option!(["-g", "-geometry"], ["--geometry"], 10, Required("GEOMETRY"), Display,
+            Some("window geometry")),
+    option!(["-T", "-title"], ["--title"], 10, Required("TITLE"), Display,
+            Some("title for initial Emacs frame")),
+    option!(["-name"], ["--name"], 10, Required("NAME"), Display,
+            Some("title for initial Emacs frame")),
+    option!(["-xrm"], ["--xrm"], 10, Required("XRESOURCES"), Display,
+            Some("set additional X resources")),
+    option!(["-parent-id"], ["--parent-id"], 10, Required("XID"), Display,
+            Some("set parent window")),
+    option!(["-r", "-rv", "-reverse"], ["--reverse-video"], 5, NoArg, Display,
+            Some("switch foreground and background")),
+    option!(["-hb"], ["--horizontal-scroll-bars"], 5, NoArg, Display,
+            Some("enable horizontal scroll bars")),
+    option!(["-vb"], ["--vertical-scroll-bars"], 5, NoArg, Display,
+            Some("enable vertical scroll bars")),
+    option!(["-color"], ["--color"], 5, Optional("MODE"), Display,
+            Some("override color mode for character terminals;\n\
+                  MODE defaults to `auto', and\n\
+                  can also be `never', `always',\n\
+                  or a mode name like `ansi8'")),
+    option!(["-no-splash"], ["--no-splash"], 3, NoArg, Initialization,
+            Some("do not display a splash screen on startup")),
+    option!(["-no-desktop"], ["--no-desktop"], 3, NoArg, Initialization,
+            Some("do not load a saved desktop")),
+    // These have the same priority as ordinary file name args,
+    // so they are not reordered with respect to those.
+    option!(["-L", "-directory"], ["--directory"], 0, Required("DIR"), Action,
+            Some("prepend DIR to load-path (with :DIR, append DIR)")),
+    option!(["-l", "-load"], ["--load"], 0, Required("FILE"), Action,
+            Some("load Emacs Lisp FILE using the load function")),
+    // This has no long name, because then the --script long option
+    // would be a prefix of it.  In any case, this is entirely an
+    // internal option.
+    option!(["-scriptload"], [], 0, Required("FILE"), Action, None),
+    option!(["-f", "-funcall"], ["--funcall"], 0, Required("FUNC"), Action,
+            Some("call Emacs Lisp function FUNC with no arguments")),
+    option!(["-eval"], ["--eval"], 0, Required("EXPR"), Action,
+            Some("evaluate Emacs Lisp expression EXPR")),
+    option!(["-execute"], ["--execute"], 0, Required("EXPR"), Action,
+            Some("evaluate Emacs Lisp expression EXPR")),
+    option!(["-find-file"], ["--find-file"], 0, Required("FILE"), Action,
+            Some("visit FILE")),
+    option!(["-visit"], ["--visit"], 0, Required("FILE"), Action,
+            Some("visit FILE")),
+    option!(["-file"], ["--file"], 0, Required("FILE"), Action,
+            Some("visit FILE")),
+    option!(["-insert"], ["--insert"], 0, Required("FILE"), Action,
+            Some("insert contents of FILE into current buffer")),
+    // This should be processed after ordinary file name args and the like.
+    option!(["-kill"], ["--kill"], -10, NoArg, Action,
+            Some("exit without asking for confirmation")),
+];
+
+#[cfg(feature = "modules")]
+#[cfg_attr(rustfmt, rustfmt_skip)]
+static MODULE_OPTIONS: &[CliOption] = &[
+    option!(["-module-assertions"], ["--module-assertions"], 62, NoArg, Initialization,
+            Some("assert behavior of dynamic modules")),
+];
+
+#[cfg(not(feature = "modules"))]
+static MODULE_OPTIONS: &[CliOption] = &[];
+
+/// Options passed by the NeXTstep launcher, which are handled in nsterm.m.
+#[cfg(feature = "ns")]
+#[cfg_attr(rustfmt, rustfmt_skip)]
+static NS_OPTIONS: &[CliOption] = &[
+    option!(["-NSAutoLaunch", "-NXAutoLaunch"], [], 5, Required("BOOL"), Display, None),
+    option!(["-_NSMachLaunch", "-MachLaunch", "-NSHost"], [], 85, Required("ARG"), Display,
+            None),
+    option!(["-macosx"], [], 85, NoArg, Display, None),
+    option!(["-NXOpen", "-NXOpenTemp", "-NSOpen", "-NSOpenTemp", "-GSFilePath"], [], 0,
+            Required("FILE"), Action, None),
+];
+
+#[cfg(not(feature = "ns"))]
+static NS_OPTIONS: &[CliOption] = &[];
+
+/// All the options this Emacs was built with.
+pub fn options() -> impl Iterator<Item = &'static CliOption> {
+    OPTIONS
+        .iter()
+        .chain(MODULE_OPTIONS.iter())
+        .chain(NS_OPTIONS.iter())
+}
+
+/// The ways an element of argv can fail to name an option.
+enum Lookup {
+    Found(&'static CliOption),
+    NotFound,
+    /// An abbreviation of the long names of several options.
+    Ambiguous,
+}
+
+/// Find the option that ARG spells.  A `--OPTION=VALUE` argument is
+/// looked up by the part before the equal sign.
+fn lookup(arg: &[u8]) -> Lookup {
+    if let Some(option) =
+        options().find(|option| option.names.iter().any(|name| name.as_bytes() == arg))
+    {
+        return Lookup::Found(option);
+    }
+
+    if !arg.starts_with(b"--") {
+        return Lookup::NotFound;
+    }
+
+    let name = arg.split(|&b| b == b'=').next().unwrap_or(arg);
+
+    // An exact match wins over abbreviations of longer names.
+    if let Some(option) =
+        options().find(|option| option.longnames.iter().any(|long| long.as_bytes() == name))
+    {
+        return Lookup::Found(option);
+    }
+
+    let mut candidates = options().filter(|option| {
+        option
+            .longnames
+            .iter()
+            .any(|long| long.as_bytes().starts_with(name))
+    });
+    match (candidates.next(), candidates.next()) {
+        (Some(option), None) => Lookup::Found(option),
+        (Some(_), Some(_)) => Lookup::Ambiguous,
+        (None, _) => Lookup::NotFound,
+    }
+}
+
+lazy_static! {
+    /// The directory given with `--init-directory', saved by `sort_args'
+    /// until `init_cli' can store it in a Lisp variable.
+    static ref INIT_DIRECTORY: Mutex<Option<Vec<u8>>> = Mutex::new(None);
+}
+
+/// Where the value of an option that takes an argument is in ARGS:
+/// after the equal sign of `--OPTION=VALUE`, or in the next element.
+fn value_offset(args: &[&[u8]], index: usize) -> (usize, usize) {
+    let arg = args[index];
+    match arg.iter().position(|&b| b == b'=') {
+        Some(equals) if arg.starts_with(b"--") => (index, equals + 1),
+        _ => (index + 1, 0),
+    }
+}
+
+fn is_init_directory(option: &CliOption) -> bool {
+    option.names.contains(&"-init-directory")
+}
+
+/// How `sort_args' rearranges argv.
+#[derive(Debug, PartialEq)]
+struct SortedArgs {
+    /// The indices of the elements to keep, in their new order.
+    order: Vec<usize>,
+    /// The value of `--init-directory', if it was given.
+    init_directory: Option<Vec<u8>>,
+}
+
+/// Compute how to sort ARGS, the elements of argv, for `sort_args'.
+/// Return the index of an option that lacks its argument as an error.
+fn sort_order(args: &[&[u8]]) -> Result<SortedArgs, usize> {
+    let argc = args.len();
+
+    // For each element of argv that is an option: the option, and the
+    // number of following elements that are its arguments.
+    let mut options: Vec<Option<(&CliOption, usize)>> = vec![None; argc];
+    let mut priority = vec![0; argc];
+
+    let mut from = 1;
+    while from < argc {
+        let arg = args[from];
+
+        // If we have found "--", leave it and everything following it
+        // at the end.
+        if arg == b"--" {
+            for p in &mut priority[from..] {
+                *p = -100;
+            }
+            break;
+        }
+
+        if arg.starts_with(b"-") {
+            match lookup(arg) {
+                Lookup::Found(option) => {
+                    // If --OPTION=VALUE syntax is used, this option uses
+                    // just one argv element.
+                    let nargs = if option.takes_argument() && !arg.contains(&b'=') {
+                        1
+                    } else {
+                        0
+                    };
+                    if from + nargs >= argc {
+                        return Err(from);
+                    }
+                    options[from] = Some((option, nargs));
+                    priority[from] = option.priority;
+                    from += nargs;
+                }
+                Lookup::Ambiguous => {
+                    // This is an internal error, e.g. if one long option
+                    // is a prefix of another.
+                    let _ = writeln!(
+                        io::stderr(),
+                        "Option '{}' matched multiple standard arguments",
+                        String::from_utf8_lossy(arg)
+                    );
+                }
+                Lookup::NotFound => {}
+            }
+        }
+        from += 1;
+    }
+
+    // Group each option with its arguments, and sort the groups by
+    // decreasing priority.  The sort is stable, so groups of equal
+    // priority keep their order.
+    let nargs = |index: usize| options[index].map_or(0, |(_, nargs)| nargs);
+    let mut groups: Vec<usize> = Vec::new();
+    let mut from = 1;
+    while from < argc {
+        groups.push(from);
+        from += 1 + nargs(from);
+    }
+    groups.sort_by_key(|&index| -priority[index]);
+
+    let mut sorted = SortedArgs {
+        order: Vec::with_capacity(argc),
+        init_directory: None,
+    };
+    sorted.order.extend(args.first().map(|_| 0));
+    for index in groups {
+        match options[index] {
+            Some((option, _)) if is_init_directory(option) => {
+                let (element, offset) = value_offset(args, index);
+                sorted.init_directory = Some(args[element][offset..].to_vec());
+                continue;
+            }
+            // Drop an option without arguments that duplicates the
+            // previous one.
+            Some((_, 0)) if sorted.order.last().map(|&last| args[last]) == Some(args[index]) => {
+                continue;
+            }
+            _ => {}
+        }
+
+        sorted.order.extend(index..=index + nargs(index));
+    }
+    Ok(sorted)
+}
+
+/// Reorder the elements of ARGV (assumed to have ARGC elements) so that
+/// the highest priority ones come first.  Do not change the order of
+/// elements of equal priority.  If an option takes an argument, keep it
+/// and its argument together.
+///
+/// If an option that takes no argument appears more than once,
+/// eliminate all but one copy of it.  `--init-directory' is removed,
+/// and its value saved for `init_cli'.  Elements freed up this way are
+/// set to null pointers at the end of ARGV.
+#[no_mangle]
+pub unsafe extern "C" fn sort_args(argc: c_int, argv: *mut *mut c_char) {
+    let argv = std::slice::from_raw_parts_mut(argv, argc as usize);
+    let args: Vec<&[u8]> = argv
+        .iter()
+        .map(|&arg| CStr::from_ptr(arg).to_bytes())
+        .collect();
+
+    let sorted = match sort_order(&args) {
+        Ok(sorted) => sorted,
+        Err(index) => fatal(
+            b"Option '%s' requires an argument\n\0".as_ptr() as *const c_char,
+            argv[index],
+        ),
+    };
+    if sorted.init_directory.is_some() {
+        *INIT_DIRECTORY.lock().unwrap() = sorted.init_directory;
+    }
+
+    // If arguments were deleted, fill up extra space with null pointers.
+    let mut new_argv: Vec<*mut c_char> = sorted.order.iter().map(|&i| argv[i]).collect();
+    new_argv.resize(argv.len(), ptr::null_mut());
+    argv.copy_from_slice(&new_argv);
+}
+
+/// Match the element of ARGS at INDEX against the option whose first
+/// name is NAME, spelled in any of the ways `OPTIONS' allows.  Return
+/// the number of elements used, and where the value of the option
+/// starts if it has one, as an element and a byte offset into it.
+fn match_option(
+    args: &[&[u8]],
+    index: usize,
+    name: &str,
+) -> Option<(usize, Option<(usize, usize)>)> {
+    let arg = *args.get(index)?;
+    let option = match lookup(arg) {
+        Lookup::Found(option) if option.names.first() == Some(&name) => option,
+        _ => return None,
+    };
+    let equals = arg.starts_with(b"--") && arg.contains(&b'=');
+
+    match option.arg {
+        OptionArg::None if equals => None,
+        OptionArg::None => Some((1, None)),
+        OptionArg::Optional(_) if equals => Some((1, Some(value_offset(args, index)))),
+        OptionArg::Optional(_) => Some((1, None)),
+        OptionArg::Required(_) if equals => Some((1, Some(value_offset(args, index)))),
+        OptionArg::Required(_) if index + 1 < args.len() => Some((2, Some((index + 1, 0)))),
+        OptionArg::Required(_) => None,
+    }
+}
+
+/// Check whether the element of ARGV after position *SKIPPTR is the
+/// option whose first name in `OPTIONS' is NAME, spelled in any of the
+/// ways it may be, including unambiguous abbreviations of its long
+/// names.  If the option has a value, store in *VALPTR, unless it is
+/// null, either the next argument or the portion of this one after the
+/// equal sign, and null if an optional value is missing.  *SKIPPTR is
+/// advanced by the number of arguments used.
+///
+/// Too bad we can't just use getopt for all of this, but we don't have
+/// enough information to do it right.
+#[no_mangle]
+pub unsafe extern "C" fn argmatch(
+    argv: *mut *mut c_char,
+    argc: c_int,
+    name: *const c_char,
+    valptr: *mut *mut c_char,
+    skipptr: *mut c_int,
+) -> bool {
+    let argv = std::slice::from_raw_parts(argv, argc.max(0) as usize);
+    // sort_args may have left null pointers at the end of argv.
+    let args: Vec<&[u8]> = argv
+        .iter()
+        .take_while(|arg| !arg.is_null())
+        .map(|&arg| CStr::from_ptr(arg).to_bytes())
+        .collect();
+    let name = CStr::from_ptr(name).to_string_lossy();
+
+    match match_option(&args, (*skipptr + 1) as usize, &name) {
+        Some((used, value)) => {
+            if !valptr.is_null() {
+                *valptr = value.map_or(ptr::null_mut(), |(element, offset)| {
+                    argv[element].add(offset)
+                });
+            }
+            *skipptr += used as c_int;
+            true
+        }
+        None => false,
+    }
+}
+
+const USAGE_INTRO: &str = "
+Run Emacs, the extensible, customizable, self-documenting real-time
+display editor.  The recommended way to start Emacs for normal editing
+is with no options at all.
+
+Run M-x info RET m emacs RET m emacs invocation RET inside Emacs to
+read the main documentation for these command-line arguments.
+";
+
+/// Lines of the action section that are not options.
+const USAGE_ACTIONS: &[(&str, &str)] = &[
+    ("FILE", "visit FILE"),
+    ("+LINE", "go to line LINE in next FILE"),
+    (
+        "+LINE:COLUMN",
+        "go to line LINE, column COLUMN, in next FILE",
+    ),
+];
+
+const USAGE_OUTRO: &str = "
+You can generally also specify long option names with a single -; for
+example, -batch as well as --batch.  You can use any unambiguous
+abbreviation for a --option.
+
+Various environment variables and window system resources also affect
+the operation of Emacs.  See the main documentation.
+";
+
+/// Write the lines of one section of the usage message to OUT, with
+/// the descriptions lined up in a column.
+fn write_section(out: &mut impl Write, title: &str, lines: &[(String, &str)]) -> io::Result<()> {
+    let column = lines
+        .iter()
+        .map(|(usage, _)| usage.len())
+        .max()
+        .unwrap_or(0)
+        + 2;
+
+    writeln!(out, "\n{}:\n", title)?;
+    for (usage, doc) in lines {
+        let mut doc_lines = doc.lines();
+        writeln!(
+            out,
+            "{:width$}{}",
+            usage,
+            doc_lines.next().unwrap_or(""),
+            width = column
+        )?;
+        for line in doc_lines {
+            writeln!(
+                out,
+                "{:width$}{}",
+                "",
+                line.trim_start(),
+                width = column + 2
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_usage(out: &mut impl Write, program: &str, bugreport: &str) -> io::Result<()> {
+    writeln!(out, "Usage: {} [OPTION-OR-FILENAME]...", program)?;
+    write!(out, "{}", USAGE_INTRO)?;
+
+    let sections = [
+        (Section::Initialization, "Initialization options"),
+        (Section::Action, "Action options"),
+        (Section::Display, "Display options"),
+    ];
+    for &(section, title) in &sections {
+        let mut lines: Vec<(String, &str)> = Vec::new();
+        if section == Section::Action {
+            lines.extend(
+                USAGE_ACTIONS
+                    .iter()
+                    .map(|&(usage, doc)| (usage.to_owned(), doc)),
+            );
+        }
+
+        let mut options: Vec<(String, &str)> = options()
+            .filter(|option| option.section == section)
+            .filter_map(|option| option.doc.map(|doc| (option.usage(), doc)))
+            .collect();
+        options.sort_by(|(a, _), (b, _)| a.to_lowercase().cmp(&b.to_lowercase()));
+        lines.extend(options);
+
+        write_section(out, title, &lines)?;
+    }
+
+    write!(out, "{}", USAGE_OUTRO)?;
+    writeln!(
+        out,
+        "\nReport bugs to {}.  First, please see the Bugs",
+        bugreport
+    )?;
+    writeln!(out, "section of the Emacs manual or the file BUGS.")?;
+    out.flush()
+}
+
+/// Print the `--help' message for PROGRAM to stdout.
+#[no_mangle]
+pub unsafe extern "C" fn print_usage(program: *const c_char, bugreport: *const c_char) {
+    let program = CStr::from_ptr(program).to_string_lossy();
+    let bugreport = CStr::from_ptr(bugreport).to_string_lossy();
+    let stdout = io::stdout();
+    // There is nowhere to report a failure to write the message.
+    let _ = write_usage(&mut stdout.lock(), &program, &bugreport);
+}
+
+/// Act on the options that `sort_args' removed from argv, now that Lisp
+/// is available.
+#[no_mangle]
+pub extern "C" fn init_cli() {
+    if !unsafe { initialized } {
+        return;
+    }
+
+    let directory = match INIT_DIRECTORY.lock().unwrap().take() {
+        Some(directory) => directory,
+        None => return,
+    };
+
+    let name = make_unibyte_string(&directory);
+    let directory = unsafe { Ffile_name_as_directory(Fexpand_file_name(name, Qnil)) };
+
+    unsafe { globals.Vcommand_line_init_directory = directory };
+    set(intern("user-emacs-directory"), directory);
+}
+
+#[no_mangle]
+pub extern "C" fn syms_of_cli() {
+    /// The directory given with the `--init-directory' option, or nil.
+    /// When it is set, `user-emacs-directory' is set to the same directory,
+    /// and the user's init file is looked for there.
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    defvar_lisp!(Vcommand_line_init_directory, "command-line-init-directory", Qnil);
+}
+
+#[test]
+fn test_lookup() {
+    let found = |arg: &str| match lookup(arg.as_bytes()) {
+        Lookup::Found(option) => Some(option.names[0]),
+        _ => None,
+    };
+    assert_eq!(found("-batch"), Some("-batch"));
+    assert_eq!(found("--batch"), Some("-batch"));
+    assert_eq!(found("-display"), Some("-d"));
+    assert_eq!(found("--disp"), Some("-d"));
+    assert_eq!(found("--display=:1"), Some("-d"));
+    assert_eq!(found("--no-windows"), Some("-nw"));
+    // An exact long name wins over the longer names it abbreviates.
+    assert_eq!(found("--daemon"), Some("-daemon"));
+    // Single-dash names are never abbreviated.
+    assert_eq!(found("-disp"), None);
+    assert_eq!(found("foo"), None);
+    match lookup(b"--d") {
+        Lookup::Ambiguous => {}
+        _ => panic!("--d should be ambiguous"),
+    }
+}
+
+#[test]
+fn test_sort_order() {
+    let sort = |args: &[&str]| {
+        let args: Vec<&[u8]> = args.iter().map(|arg| arg.as_bytes()).collect();
+        sort_order(&args)
+    };
+    let order = |args: &[&str]| sort(args).unwrap().order;
+
+    // Higher priorities first, keeping options with their arguments.
+    assert_eq!(
+        order(&["emacs", "file", "-l", "x.el", "-batch", "-q"]),
+        vec![0, 4, 5, 1, 2, 3]
+    );
+    // Equal priorities keep their order, and -kill goes last.
+    assert_eq!(order(&["emacs", "-kill", "b", "a"]), vec![0, 2, 3, 1]);
+    // Duplicates of options without arguments are dropped.
+    assert_eq!(order(&["emacs", "-q", "-q", "-Q"]), vec![0, 3, 1]);
+    // Everything after -- stays at the end.
+    assert_eq!(order(&["emacs", "--", "-batch", "f"]), vec![0, 1, 2, 3]);
+    // --OPTION=VALUE is a single element.
+    assert_eq!(order(&["emacs", "f", "--chdir=/tmp"]), vec![0, 2, 1]);
+
+    let sorted = sort(&["emacs", "--init-directory", "/d", "f"]).unwrap();
+    assert_eq!(sorted.order, vec![0, 3]);
+    assert_eq!(sorted.init_directory, Some(b"/d".to_vec()));
+    let sorted = sort(&["emacs", "--init-dir=/e"]).unwrap();
+    assert_eq!(sorted.init_directory, Some(b"/e".to_vec()));
+
+    assert_eq!(sort(&["emacs", "f", "-l"]), Err(2));
+}
+
+#[test]
+fn test_match_option() {
+    let matches = |args: &[&str], index, name| {
+        let args: Vec<&[u8]> = args.iter().map(|arg| arg.as_bytes()).collect();
+        match_option(&args, index, name)
+    };
+    assert_eq!(matches(&["emacs", "--batch"], 1, "-batch"), Some((1, None)));
+    assert_eq!(matches(&["emacs", "--batch=x"], 1, "-batch"), None);
+    assert_eq!(matches(&["emacs", "-batch"], 1, "-q"), None);
+    assert_eq!(matches(&["emacs"], 1, "-batch"), None);
+    assert_eq!(
+        matches(&["emacs", "-display", ":1"], 1, "-d"),
+        Some((2, Some((2, 0))))
+    );
+    assert_eq!(
+        matches(&["emacs", "--disp=:1"], 1, "-d"),
+        Some((1, Some((1, 7))))
+    );
+    assert_eq!(matches(&["emacs", "-d"], 1, "-d"), None);
+    assert_eq!(
+        matches(&["emacs", "--daemon"], 1, "-daemon"),
+        Some((1, None))
+    );
+    assert_eq!(
+        matches(&["emacs", "--daemon=x"], 1, "-daemon"),
+        Some((1, Some((1, 9))))
+    );
+}
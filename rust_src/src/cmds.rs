@@ -7,10 +7,13 @@ use std::ffi::CString;
 use remacs_macros::lisp_fn;
 
 use crate::{
+    abbrev::{expand_before_self_insert, SelfInsertExpansion},
+    buffers::barf_if_buffer_read_only,
     character::{self, characterp},
     data::set,
     dispnew::ding_internal,
-    editfns::{line_beginning_position, line_end_position, preceding_char},
+    editfns::{constrain_to_field, line_beginning_position, line_end_position},
+    eval::unbind_to,
     frames::selected_frame,
     keymap::{current_global_map, Ctl},
     lisp::defsubr,
@@ -19,22 +22,23 @@ use crate::{
         char_to_byte8, single_byte_charp, unibyte_to_char, write_codepoint, Codepoint,
         MAX_MULTIBYTE_LENGTH,
     },
-    numbers::MOST_POSITIVE_FIXNUM,
+    numbers::{LispNumber, MOST_POSITIVE_FIXNUM},
     obarray::intern,
     remacs_sys::EmacsInt,
     remacs_sys::{
         concat2, current_column, del_range, frame_make_pointer_invisible, globals,
         initial_define_key, insert_and_inherit, memory_full, replace_range, run_hook,
-        scan_newline_from_point, set_point, set_point_both, syntax_property, syntaxcode,
-        translate_char,
+        scan_newline_from_point, set_point, set_point_both, specbind, translate_char,
     },
-    remacs_sys::{Fchar_width, Fget, Fmake_string, Fmove_to_column},
+    remacs_sys::{Fchar_width, Fmake_string, Fmove_to_column},
     remacs_sys::{
-        Qbeginning_of_buffer, Qend_of_buffer, Qexpand_abbrev, Qinternal_auto_fill,
-        Qkill_forward_chars, Qnil, Qoverwrite_mode_binary, Qpost_self_insert_hook,
-        Qundo_auto__this_command_amalgamating, Qundo_auto_amalgamate,
+        Qbeginning_of_buffer, Qend_of_buffer, Qinternal_auto_fill, Qkill_forward_chars, Qnil,
+        Qoverwrite_mode_binary, Qpost_self_insert_hook, Qundo_auto__this_command_amalgamating,
+        Qundo_auto_amalgamate,
     },
-    threads::ThreadState,
+    symbols::symbol_value,
+    textprop::get_char_property,
+    threads::{c_specpdl_index, ThreadState},
 };
 
 /// Add N to point; or subtract N if FORWARD is false. N defaults to 1.
@@ -282,6 +286,86 @@ pub fn self_insert_command(n: EmacsInt) {
     }
 }
 
+/// Whether the newline `newline' would insert at point needs more than
+/// `self-insert-command': hard newlines have to be marked, and a left
+/// margin has to be indented to.
+fn newline_needs_postprocessing() -> bool {
+    let buffer = ThreadState::current_buffer_unchecked();
+    let margin_property = |pos: isize| {
+        pos >= buffer.begv
+            && pos < buffer.zv
+            && get_char_property(pos as EmacsInt, intern("left-margin").into(), Qnil).is_not_nil()
+    };
+    symbol_value(intern("use-hard-newlines")).is_not_nil()
+        || buffer
+            .left_margin_
+            .as_fixnum()
+            .map_or(true, |margin| margin != 0)
+        || margin_property(buffer.pt - 1)
+        || margin_property(buffer.pt)
+}
+
+/// Delete the spaces and tabs before point, within its field.
+fn delete_horizontal_space_backward() {
+    let buffer = ThreadState::current_buffer_unchecked();
+    let mut pos = buffer.pt;
+    let mut pos_byte = buffer.pt_byte;
+    while pos > buffer.begv {
+        let prev_byte = buffer.dec_pos(pos_byte);
+        let c = buffer.fetch_char(prev_byte);
+        if c != ' ' as libc::c_int && c != '\t' as libc::c_int {
+            break;
+        }
+        pos -= 1;
+        pos_byte = prev_byte;
+    }
+    let start = constrain_to_field(
+        Some(LispNumber::Fixnum(pos as EmacsInt)),
+        LispNumber::Fixnum(buffer.pt as EmacsInt),
+        false,
+        false,
+        Qnil,
+    ) as isize;
+    if start < buffer.pt {
+        unsafe { del_range(start, buffer.pt) };
+    }
+}
+
+/// Insert a newline, then indent the new line according to the major mode.
+/// This does what `newline-and-indent' does, in the common case where
+/// the buffer has no hard newlines or left margin: it deletes the spaces
+/// and tabs before point, inserts the newline as `self-insert-command'
+/// does, running auto-fill and `post-self-insert-hook' with
+/// `last-command-event' bound to a newline, and finally calls
+/// `indent-according-to-mode'.
+///
+/// Return t if the newline was inserted.  Return nil without changing
+/// anything if the buffer does have hard newlines or a left margin,
+/// which need the full `newline'.
+#[lisp_fn]
+pub fn newline_and_indent_core() -> bool {
+    barf_if_buffer_read_only(None);
+    if newline_needs_postprocessing() {
+        return false;
+    }
+
+    delete_horizontal_space_backward();
+
+    let count = c_specpdl_index();
+    unsafe {
+        specbind(
+            intern("last-command-event").into(),
+            LispObject::from('\n' as EmacsInt),
+        )
+    };
+    call!(Qundo_auto_amalgamate);
+    internal_self_insert('\n' as Codepoint, 1);
+    unbind_to(count, Qnil);
+
+    call!(intern("indent-according-to-mode").into());
+    true
+}
+
 //enum Hairyness {
 //    Simple,
 //    Maybe,
@@ -295,7 +379,6 @@ pub fn self_insert_command(n: EmacsInt) {
 /// A value of 2 means this did things that call for an undo boundary.
 fn internal_self_insert(mut c: Codepoint, n: usize) -> EmacsInt {
     let mut hairy: EmacsInt = 0;
-    let synt: syntaxcode;
     // Length of multi-byte form of C.
     let len: usize;
     // Working buffer and pointer for multi-byte form of C.
@@ -382,43 +465,10 @@ fn internal_self_insert(mut c: Codepoint, n: usize) -> EmacsInt {
         }
         hairy = 2;
     }
-    synt = unsafe { syntax_property(c as i32, true) };
-
-    let previous_char = if current_buffer.multibyte_characters_enabled() {
-        preceding_char() as Codepoint
-    } else {
-        unibyte_to_char(preceding_char() as Codepoint)
-    };
-    if current_buffer.abbrev_mode_.is_not_nil()
-        && synt != syntaxcode::Sword
-        && current_buffer.read_only_.is_nil()
-        && current_buffer.pt > current_buffer.begv
-        && unsafe { syntax_property(previous_char as libc::c_int, true) } == syntaxcode::Sword
-    {
-        let modiff = unsafe { (*current_buffer.text).modiff };
-
-        let sym = call!(Qexpand_abbrev);
-
-        // If we expanded an abbrev which has a hook,
-        // and the hook has a non-nil `no-self-insert' property,
-        // return right away--don't really self-insert.  */
-        if let Some(s) = sym.as_symbol() {
-            if let Some(f) = s.get_function().as_symbol() {
-                let prop = unsafe {
-                    Fget(
-                        LispObject::from(f),
-                        LispObject::from(intern("no-self-insert")),
-                    )
-                };
-                if prop.is_not_nil() {
-                    return 1;
-                }
-            }
-        }
-
-        if unsafe { (*current_buffer.text).modiff != modiff } {
-            hairy = 2;
-        }
+    match expand_before_self_insert(c) {
+        SelfInsertExpansion::NoSelfInsert => return 1,
+        SelfInsertExpansion::Tried { modified: true } => hairy = 2,
+        _ => (),
     }
 
     if chars_to_delete > 0 {
@@ -0,0 +1,244 @@
+//! Crash reports.
+//!
+//! When Emacs dies from a fatal signal, `terminate_due_to_signal' calls
+//! `write_crash_report' after the orderly shutdown.  The report holds
+//! the C and Rust backtraces, the command being executed, the recent
+//! keys and some GC statistics, and is written to a file in
+//! `crash-report-directory'; only its name is printed on stderr.
+//!
+//! The heap may be corrupted at this point, so Lisp objects are only
+//! looked at, never created, and the Rust backtrace, which needs to
+//! allocate while resolving symbols, comes last.
+
+use std::{
+    ffi::CStr,
+    io::{self, Write},
+    process,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use libc::{c_char, c_int};
+
+use crate::{
+    gc_stats::consing_since_gc,
+    lisp::LispObject,
+    remacs_sys::globals,
+    remacs_sys::Qnil,
+    remacs_sys::{
+        emacs_backtrace, emacs_backtrace_to_fd, gc_in_progress, recent_key, recent_keys_count,
+        safe_strsignal,
+    },
+};
+
+/// A file descriptor opened for the report.  This does not buffer, so
+/// whatever was written before a second crash stays in the file.
+struct ReportFile(c_int);
+
+impl ReportFile {
+    fn create(name: &[u8]) -> Option<Self> {
+        let mut path = name.to_vec();
+        path.push(0);
+        let fd = unsafe {
+            libc::open(
+                path.as_ptr() as *const c_char,
+                libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL,
+                0o600,
+            )
+        };
+        if fd < 0 {
+            None
+        } else {
+            Some(ReportFile(fd))
+        }
+    }
+}
+
+impl Write for ReportFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = unsafe { libc::write(self.0, buf.as_ptr() as *const libc::c_void, buf.len() as _) };
+        if n < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(n as usize)
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for ReportFile {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.0) };
+    }
+}
+
+/// The name of the symbol OBJ, if it is one.
+fn symbol_name_bytes(obj: LispObject) -> Option<&'static [u8]> {
+    let name = obj.as_symbol()?.symbol_name().as_string()?;
+    // Symbol names are not freed while Emacs is shutting down.
+    Some(unsafe { std::slice::from_raw_parts(name.const_data_ptr(), name.len_bytes() as usize) })
+}
+
+/// Write a short description of the event KEY.
+fn write_key(out: &mut impl Write, key: LispObject) -> io::Result<()> {
+    if let Some(c) = key.as_fixnum() {
+        match c {
+            0x21..=0x7e => write!(out, "{}", c as u8 as char),
+            _ => write!(out, "#{}", c),
+        }
+    } else if let Some(name) = symbol_name_bytes(key) {
+        write!(out, "<")?;
+        out.write_all(name)?;
+        write!(out, ">")
+    } else if let Some(cons) = key.as_cons() {
+        // A command recorded by `recent-keys', or a mouse event.
+        let (head, command) = (cons.car(), cons.cdr());
+        let (open, name, close) = if head.is_nil() {
+            ("[", symbol_name_bytes(command), "]")
+        } else {
+            ("<", symbol_name_bytes(head), ">")
+        };
+        write!(out, "{}", open)?;
+        out.write_all(name.unwrap_or(b"?"))?;
+        write!(out, "{}", close)
+    } else {
+        write!(out, "?")
+    }
+}
+
+fn write_command(out: &mut impl Write, label: &str, command: LispObject) -> io::Result<()> {
+    write!(out, "{}: ", label)?;
+    match symbol_name_bytes(command) {
+        Some(name) => out.write_all(name)?,
+        None if command.is_nil() => write!(out, "nil")?,
+        None => write!(out, "(not a symbol)")?,
+    }
+    writeln!(out)
+}
+
+fn write_report(out: &mut ReportFile, sig: c_int, backtrace_limit: c_int) -> io::Result<()> {
+    let description = unsafe { CStr::from_ptr(safe_strsignal(sig)) };
+    writeln!(out, "Emacs crash report")?;
+    writeln!(out)?;
+    writeln!(
+        out,
+        "Fatal error {}: {}",
+        sig,
+        description.to_string_lossy()
+    )?;
+    writeln!(out, "Process: {}", process::id())?;
+    if let Ok(time) = SystemTime::now().duration_since(UNIX_EPOCH) {
+        writeln!(out, "Time: {} seconds since the epoch", time.as_secs())?;
+    }
+
+    writeln!(out)?;
+    write_command(out, "Command", unsafe { globals.Vthis_command })?;
+    write_command(out, "Real command", unsafe { globals.Vreal_this_command })?;
+
+    writeln!(out)?;
+    writeln!(out, "Recent keys:")?;
+    let count = unsafe { recent_keys_count() };
+    for i in 0..count {
+        write_key(out, unsafe { recent_key(i) })?;
+        write!(out, "{}", if i % 20 == 19 { "\n" } else { " " })?;
+    }
+    writeln!(out)?;
+
+    writeln!(out)?;
+    writeln!(out, "Garbage collection:")?;
+    unsafe {
+        writeln!(out, "  in progress: {}", gc_in_progress)?;
+        writeln!(out, "  collections: {}", globals.gcs_done)?;
+        if let Some(elapsed) = globals.Vgc_elapsed.as_float() {
+            writeln!(out, "  time spent: {:.3}s", elapsed)?;
+        }
+        writeln!(out, "  consed since last: {} bytes", consing_since_gc)?;
+        writeln!(out, "  threshold: {} bytes", globals.gc_cons_threshold)?;
+        writeln!(
+            out,
+            "  objects consed: {} conses, {} floats, {} vector cells, {} symbols, {} strings",
+            globals.cons_cells_consed,
+            globals.floats_consed,
+            globals.vector_cells_consed,
+            globals.symbols_consed,
+            globals.strings_consed
+        )?;
+    }
+
+    // The C backtrace does not allocate, unlike the Rust one, which
+    // needs to resolve symbols.
+    writeln!(out)?;
+    writeln!(out, "C backtrace:")?;
+    unsafe { emacs_backtrace_to_fd(out.0, backtrace_limit) };
+
+    writeln!(out)?;
+    writeln!(out, "Rust backtrace:")?;
+    let backtrace = backtrace::Backtrace::new();
+    let frames = backtrace.frames();
+    for frame in frames.iter().take(backtrace_limit.max(0) as usize) {
+        for symbol in frame.symbols() {
+            match symbol.name() {
+                Some(name) => write!(out, "  {}", name)?,
+                None => write!(out, "  {:?}", frame.ip())?,
+            }
+            if let (Some(file), Some(line)) = (symbol.filename(), symbol.lineno()) {
+                write!(out, " at {}:{}", file.display(), line)?;
+            }
+            writeln!(out)?;
+        }
+    }
+    if frames.len() > backtrace_limit.max(0) as usize {
+        writeln!(out, "  ...")?;
+    }
+    Ok(())
+}
+
+/// The directory crash reports are written to.
+fn report_directory() -> Vec<u8> {
+    let dir = unsafe { globals.Vcrash_report_directory };
+    let dir = if dir.is_nil() {
+        unsafe { globals.Vtemporary_file_directory }
+    } else {
+        dir
+    };
+    match dir.as_string() {
+        Some(s) => s.as_slice().to_vec(),
+        None => b"/tmp".to_vec(),
+    }
+}
+
+/// Write a crash report for the fatal signal SIG, with backtraces of at
+/// most BACKTRACE_LIMIT frames, and tell the user where to find it.  If
+/// the report cannot be written, print the C backtrace on stderr.
+#[no_mangle]
+pub extern "C" fn write_crash_report(sig: c_int, backtrace_limit: c_int) {
+    let mut name = report_directory();
+    if name.last() != Some(&b'/') {
+        name.push(b'/');
+    }
+    let _ = write!(name, "emacs-crash-{}.txt", process::id());
+
+    let written = ReportFile::create(&name)
+        .map(|mut file| write_report(&mut file, sig, backtrace_limit).is_ok())
+        .unwrap_or(false);
+
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+    if written {
+        let _ = stderr.write_all(b"\nA crash report was written to ");
+        let _ = stderr.write_all(&name);
+        let _ = stderr.write_all(b"\n");
+    } else {
+        unsafe { emacs_backtrace(backtrace_limit) };
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn syms_of_crash() {
+    /// Directory where Emacs writes a report when it crashes.
+    /// If nil, `temporary-file-directory' is used.  The report is named
+    /// emacs-crash-PID.txt, where PID is the process ID of Emacs.
+    defvar_lisp!(Vcrash_report_directory, "crash-report-directory", Qnil);
+}
@@ -1,34 +1,44 @@
-#![allow(dead_code)] // XXX unused code belongs into translation of new extract_data_from_object fn
+//! Message digests of strings and buffer text.
+//!
+//! The text is hashed where it is, without copying it, unless it has to
+//! be encoded first: buffer text is fed to the hash on both sides of
+//! the gap.  Only multibyte text whose coding system changes it is
+//! converted, like `write-region' would, and hashed afterwards.
+
+use std::cmp::{max, min};
+use std::slice;
 
 use libc::ptrdiff_t;
 use md5;
 use sha1;
 use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
-use std;
-use std::slice;
 
 use remacs_macros::lisp_fn;
 
 use crate::{
-    buffers::{buffer_file_name, LispBufferOrName, LispBufferRef},
+    buffers::{buffer_file_name, LispBufferOrCurrent, LispBufferOrName, LispBufferRef},
+    eval::unbind_to,
     lisp::defsubr,
     lisp::LispObject,
+    lists::plist_get,
+    marker::buf_charpos_to_bytepos,
     multibyte::LispStringRef,
+    obarray::intern,
     remacs_sys::{
-        code_convert_string, extract_data_from_object, preferred_coding_system,
-        string_char_to_byte, validate_subarray, Fcoding_system_p,
+        code_convert_string, extract_data_from_object, make_buffer_string, preferred_coding_system,
+        record_unwind_current_buffer, set_buffer_internal, validate_subarray,
     },
+    remacs_sys::{globals, make_uninit_string, EmacsInt},
     remacs_sys::{
-        current_thread, make_buffer_string, record_unwind_current_buffer, set_buffer_internal,
+        Fcoding_system_base, Fcoding_system_eol_type, Fcoding_system_p, Fcoding_system_plist,
+        Ffind_operation_coding_system, Flocal_variable_p,
     },
-    remacs_sys::{globals, Ffind_operation_coding_system, Flocal_variable_p},
-    remacs_sys::{make_specified_string, make_uninit_string, EmacsInt},
     remacs_sys::{
         Qbuffer_file_coding_system, Qcoding_system_error, Qmd5, Qnil, Qraw_text, Qsha1, Qsha224,
-        Qsha256, Qsha384, Qsha512, Qstringp, Qwrite_region,
+        Qsha256, Qsha384, Qsha512, Qutf_8, Qutf_8_emacs, Qwrite_region,
     },
-    symbols::{fboundp, symbol_name},
-    threads::ThreadState,
+    symbols::fboundp,
+    threads::{c_specpdl_index, ThreadState},
 };
 
 #[derive(Clone, Copy)]
@@ -41,15 +51,8 @@ enum HashAlg {
     SHA512,
 }
 
-static MD5_DIGEST_LEN: usize = 16;
-static SHA1_DIGEST_LEN: usize = 20;
-static SHA224_DIGEST_LEN: usize = 224 / 8;
-static SHA256_DIGEST_LEN: usize = 256 / 8;
-static SHA384_DIGEST_LEN: usize = 384 / 8;
-static SHA512_DIGEST_LEN: usize = 512 / 8;
-
 fn hash_alg(algorithm: LispObject) -> HashAlg {
-    algorithm.as_symbol_or_error();
+    let symbol = algorithm.as_symbol_or_error();
     if algorithm == Qmd5 {
         HashAlg::MD5
     } else if algorithm == Qsha1 {
@@ -63,11 +66,82 @@ fn hash_alg(algorithm: LispObject) -> HashAlg {
     } else if algorithm == Qsha512 {
         HashAlg::SHA512
     } else {
-        let name = symbol_name(algorithm.as_symbol_or_error()).as_string_or_error();
-        error!("Invalid algorithm arg: {:?}\0", &name.as_slice());
+        error!(
+            "Invalid algorithm arg: {}",
+            symbol.symbol_name().as_string_or_error()
+        );
+    }
+}
+
+/// A message digest being computed.
+enum Hasher {
+    MD5(md5::Context),
+    SHA1(sha1::Sha1),
+    SHA224(Sha224),
+    SHA256(Sha256),
+    SHA384(Sha384),
+    SHA512(Sha512),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlg) -> Self {
+        match algorithm {
+            HashAlg::MD5 => Hasher::MD5(md5::Context::new()),
+            HashAlg::SHA1 => Hasher::SHA1(sha1::Sha1::new()),
+            HashAlg::SHA224 => Hasher::SHA224(Sha224::new()),
+            HashAlg::SHA256 => Hasher::SHA256(Sha256::new()),
+            HashAlg::SHA384 => Hasher::SHA384(Sha384::new()),
+            HashAlg::SHA512 => Hasher::SHA512(Sha512::new()),
+        }
+    }
+
+    /// Hash BYTES, after the bytes hashed so far.
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Hasher::MD5(context) => context.consume(bytes),
+            Hasher::SHA1(hasher) => hasher.update(bytes),
+            Hasher::SHA224(hasher) => hasher.input(bytes),
+            Hasher::SHA256(hasher) => hasher.input(bytes),
+            Hasher::SHA384(hasher) => hasher.input(bytes),
+            Hasher::SHA512(hasher) => hasher.input(bytes),
+        }
+    }
+
+    /// Return the digest of all the bytes hashed.
+    fn finish(self) -> Vec<u8> {
+        match self {
+            Hasher::MD5(context) => context.compute().to_vec(),
+            Hasher::SHA1(hasher) => hasher.digest().bytes().to_vec(),
+            Hasher::SHA224(hasher) => hasher.result().to_vec(),
+            Hasher::SHA256(hasher) => hasher.result().to_vec(),
+            Hasher::SHA384(hasher) => hasher.result().to_vec(),
+            Hasher::SHA512(hasher) => hasher.result().to_vec(),
+        }
     }
 }
 
+/// Return DIGEST as a Lisp string, in hexadecimal unless BINARY.
+fn digest_string(digest: &[u8], binary: bool) -> LispObject {
+    const HEXDIGIT: &[u8; 16] = b"0123456789abcdef";
+    let len = if binary {
+        digest.len()
+    } else {
+        digest.len() * 2
+    };
+    let string = unsafe { make_uninit_string(len as EmacsInt) };
+    let mut digest_str = string.as_string_or_error();
+    let dest = digest_str.as_mut_slice();
+    if binary {
+        dest.copy_from_slice(digest);
+    } else {
+        for (hex, &byte) in dest.chunks_mut(2).zip(digest) {
+            hex[0] = HEXDIGIT[(byte >> 4) as usize];
+            hex[1] = HEXDIGIT[(byte & 0xf) as usize];
+        }
+    }
+    string
+}
+
 fn check_coding_system_or_error(coding_system: LispObject, noerror: LispObject) -> LispObject {
     if unsafe { Fcoding_system_p(coding_system) }.is_nil() {
         /* Invalid coding system. */
@@ -95,177 +169,265 @@ fn get_coding_system_for_string(string: LispStringRef, coding_system: LispObject
     }
 }
 
+/// Decide the coding system to encode the text of BUFFER, which is
+/// current, between B and E with, like `write-region' does.  START and
+/// END are the positions as given.
 fn get_coding_system_for_buffer(
-    object: LispObject,
     buffer: LispBufferRef,
     start: LispObject,
     end: LispObject,
-    start_byte: ptrdiff_t,
-    end_byte: ptrdiff_t,
-    coding_system: LispObject,
+    b: EmacsInt,
+    e: EmacsInt,
 ) -> LispObject {
-    /* Decide the coding-system to encode the data with.
-    See fileio.c:Fwrite-region */
-    if coding_system.is_not_nil() {
-        return coding_system;
-    }
     if unsafe { globals.Vcoding_system_for_write }.is_not_nil() {
         return unsafe { globals.Vcoding_system_for_write };
     }
-    if (buffer.buffer_file_coding_system_.is_nil()
-        || unsafe { Flocal_variable_p(Qbuffer_file_coding_system, Qnil) }.is_nil())
-        && !buffer.multibyte_characters_enabled()
+
+    let mut force_raw_text = false;
+    let mut coding_system = buffer.buffer_file_coding_system_;
+    if coding_system.is_nil()
+        || unsafe { Flocal_variable_p(Qbuffer_file_coding_system, Qnil) }.is_nil()
     {
-        return Qraw_text;
+        coding_system = Qnil;
+        if !buffer.multibyte_characters_enabled() {
+            force_raw_text = true;
+        }
     }
-    let file_name = buffer_file_name(object.into());
-    if file_name.is_not_nil() {
+
+    let file_name = buffer_file_name(LispBufferOrCurrent::Buffer(buffer));
+    if coding_system.is_nil() && file_name.is_not_nil() {
         // Check file-coding-system-alist.
         let mut args = [Qwrite_region, start, end, file_name];
         let val = unsafe { Ffind_operation_coding_system(4, args.as_mut_ptr()) };
         if let Some((_, d)) = val.into() {
             if d.is_not_nil() {
-                return d;
+                coding_system = d;
             }
         }
     }
-    if buffer.buffer_file_coding_system_.is_not_nil() {
+
+    if coding_system.is_nil() && buffer.buffer_file_coding_system_.is_not_nil() {
         /* If we still have not decided a coding system, use the
         default value of buffer-file-coding-system. */
-        return buffer.buffer_file_coding_system_;
+        coding_system = buffer.buffer_file_coding_system_;
     }
+
     let sscsf = unsafe { globals.Vselect_safe_coding_system_function };
-    if fboundp(sscsf.as_symbol_or_error()) {
+    if !force_raw_text && fboundp(sscsf.as_symbol_or_error()) {
         /* Confirm that VAL can surely encode the current region. */
-        return call!(
+        coding_system = call!(
             sscsf,
-            LispObject::from(start_byte),
-            LispObject::from(end_byte),
+            LispObject::from(b),
+            LispObject::from(e),
             coding_system,
             Qnil
         );
     }
-    Qnil
+
+    if force_raw_text {
+        Qraw_text
+    } else {
+        coding_system
+    }
 }
 
-fn get_input_from_string(
-    object: LispObject,
+/// Which multibyte text a coding system encodes to its internal
+/// representation unchanged.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Unchanged {
+    Nothing,
+    Ascii,
+    /// Characters that are also Unicode characters in UTF-8.  Raw bytes
+    /// are not, and neither are the characters beyond Unicode.
+    Unicode,
+}
+
+impl Unchanged {
+    pub(crate) fn for_coding_system(coding_system: LispObject) -> Self {
+        if unsafe { Fcoding_system_p(coding_system) }.is_nil() {
+            // Leave the error to the conversion.
+            return Unchanged::Nothing;
+        }
+        let plist = unsafe { Fcoding_system_plist(coding_system) };
+        let property = |name: &str| plist_get(plist, LispObject::from(intern(name)));
+        if [":pre-write-conversion", ":encode-translation-table", ":bom"]
+            .iter()
+            .any(|name| property(name).is_not_nil())
+        {
+            return Unchanged::Nothing;
+        }
+        // Newlines must stay what they are.  Unless the coding system
+        // says, text is encoded with the system's end of line, which
+        // is a newline on Unix.
+        let eol_type = unsafe { Fcoding_system_eol_type(coding_system) };
+        if !(eol_type.eq(LispObject::from(0)) || (cfg!(unix) && eol_type.is_vector())) {
+            return Unchanged::Nothing;
+        }
+        let base = unsafe { Fcoding_system_base(coding_system) };
+        if base.eq(Qutf_8) || base.eq(Qutf_8_emacs) {
+            Unchanged::Unicode
+        } else if property(":ascii-compatible-p").is_not_nil() {
+            Unchanged::Ascii
+        } else {
+            Unchanged::Nothing
+        }
+    }
+
+    /// Return true if TEXT, in the internal representation, is left
+    /// as it is.
+    pub(crate) fn covers(self, text: &[u8]) -> bool {
+        match self {
+            Unchanged::Nothing => false,
+            Unchanged::Ascii => text.iter().all(|&b| b < 0x80),
+            // Raw bytes start with 0xC0 or 0xC1, and characters beyond
+            // Unicode with 0xF4 or above, as do some that are not.
+            Unchanged::Unicode => text.iter().all(|&b| b != 0xc0 && b != 0xc1 && b < 0xf4),
+        }
+    }
+}
+
+/// Hash the part of STRING between START and END, encoded with
+/// CODING-SYSTEM.  Like `write-region', START and END count the bytes of
+/// the encoded string.
+fn hash_string(
+    hasher: &mut Hasher,
     string: LispStringRef,
     start: LispObject,
     end: LispObject,
-) -> LispObject {
-    let size: ptrdiff_t;
-    let start_byte: ptrdiff_t;
-    let end_byte: ptrdiff_t;
-    let mut start_char: ptrdiff_t = 0;
-    let mut end_char: ptrdiff_t = 0;
+    coding_system: LispObject,
+    noerror: LispObject,
+) {
+    let coding_system =
+        check_coding_system_or_error(get_coding_system_for_string(string, coding_system), noerror);
+    let mut object = LispObject::from(string);
+    if string.is_multibyte()
+        && !Unchanged::for_coding_system(coding_system).covers(string.as_slice())
+    {
+        object = unsafe { code_convert_string(object, coding_system, Qnil, true, false, true) };
+    }
 
-    size = string.len_bytes();
+    let encoded = object.as_string_or_error();
+    let bytes = encoded.as_slice();
+    let (mut start_byte, mut end_byte) = (0, 0);
     unsafe {
-        validate_subarray(object, start, end, size, &mut start_char, &mut end_char);
-    }
-    start_byte = if start_char == 0 {
-        0
-    } else {
-        unsafe { string_char_to_byte(object, start_char) }
-    };
-    end_byte = if end_char == size {
-        string.len_bytes()
-    } else {
-        unsafe { string_char_to_byte(object, end_char) }
+        validate_subarray(
+            object,
+            start,
+            end,
+            bytes.len() as ptrdiff_t,
+            &mut start_byte,
+            &mut end_byte,
+        )
     };
-    if start_byte == 0 && end_byte == size {
-        object
-    } else {
-        unsafe {
-            make_specified_string(
-                string.const_sdata_ptr().offset(start_byte),
-                -1 as ptrdiff_t,
-                end_byte - start_byte,
-                string.is_multibyte(),
-            )
+    hasher.update(&bytes[start_byte as usize..end_byte as usize]);
+}
+
+/// Return the text of BUFFER between the byte positions START and END,
+/// as the parts before and after the gap.
+fn buffer_text<'a>(buffer: LispBufferRef, start: ptrdiff_t, end: ptrdiff_t) -> [&'a [u8]; 2] {
+    let part = |from: ptrdiff_t, to: ptrdiff_t| -> &'a [u8] {
+        if from < to {
+            unsafe { slice::from_raw_parts(buffer.byte_pos_addr(from), (to - from) as usize) }
+        } else {
+            &[]
         }
-    }
+    };
+    let gpt = buffer.gpt_byte();
+    [part(start, min(end, gpt)), part(max(start, gpt), end)]
 }
 
-fn get_input_from_buffer(
+/// Hash the text of BUFFER between START and END, encoded with
+/// CODING-SYSTEM, or the coding system it would be written with.
+fn hash_buffer(
+    hasher: &mut Hasher,
     mut buffer: LispBufferRef,
     start: LispObject,
     end: LispObject,
-    start_byte: &mut ptrdiff_t,
-    end_byte: &mut ptrdiff_t,
-) -> LispObject {
-    let prev_buffer = ThreadState::current_buffer_unchecked().as_mut();
-    unsafe { record_unwind_current_buffer() };
-    unsafe { set_buffer_internal(buffer.as_mut()) };
+    coding_system: LispObject,
+    noerror: LispObject,
+) {
+    let count = c_specpdl_index();
+    unsafe {
+        record_unwind_current_buffer();
+        set_buffer_internal(buffer.as_mut());
+    }
 
-    *start_byte = start.map_or(buffer.begv, |v| {
-        v.as_number_coerce_marker_or_error().to_fixnum() as ptrdiff_t
+    let mut b = start.map_or(buffer.begv as EmacsInt, |v| {
+        v.as_fixnum_coerce_marker_or_error()
     });
-    *end_byte = end.map_or(buffer.zv, |v| {
-        v.as_number_coerce_marker_or_error().to_fixnum() as ptrdiff_t
+    let mut e = end.map_or(buffer.zv as EmacsInt, |v| {
+        v.as_fixnum_coerce_marker_or_error()
     });
-
-    if start_byte > end_byte {
-        std::mem::swap(start_byte, end_byte);
+    if b > e {
+        std::mem::swap(&mut b, &mut e);
     }
-    if !(buffer.begv <= *start_byte && *end_byte <= buffer.zv) {
+    if !(buffer.begv as EmacsInt <= b && e <= buffer.zv as EmacsInt) {
         args_out_of_range!(start, end);
     }
-    let string = unsafe { make_buffer_string(*start_byte, *end_byte, false) };
-    unsafe { set_buffer_internal(prev_buffer) };
-    // TODO: this needs to be std::mem::size_of<specbinding>()
-    unsafe { (*current_thread).m_specpdl_ptr = (*current_thread).m_specpdl_ptr.offset(-40) };
-    string
+
+    let coding_system = if coding_system.is_nil() {
+        check_coding_system_or_error(
+            get_coding_system_for_buffer(buffer, start, end, b, e),
+            noerror,
+        )
+    } else {
+        coding_system
+    };
+
+    let unchanged = if buffer.multibyte_characters_enabled() {
+        Unchanged::for_coding_system(coding_system)
+    } else {
+        // Unibyte text is never encoded.
+        Unchanged::Unicode
+    };
+    let start_byte = buf_charpos_to_bytepos(buffer.as_mut(), b as ptrdiff_t);
+    let end_byte = buf_charpos_to_bytepos(buffer.as_mut(), e as ptrdiff_t);
+    let text = buffer_text(buffer, start_byte, end_byte);
+    if !buffer.multibyte_characters_enabled() || text.iter().all(|part| unchanged.covers(part)) {
+        for part in &text {
+            hasher.update(part);
+        }
+    } else {
+        let string = unsafe { make_buffer_string(b as ptrdiff_t, e as ptrdiff_t, false) };
+        let encoded =
+            unsafe { code_convert_string(string, coding_system, Qnil, true, false, false) };
+        hasher.update(encoded.as_string_or_error().as_slice());
+    }
+
+    unbind_to(count, Qnil);
 }
 
-fn get_input(
+fn _secure_hash(
+    algorithm: HashAlg,
     object: LispObject,
-    string: &mut Option<LispStringRef>,
-    buffer: &Option<LispBufferRef>,
     start: LispObject,
     end: LispObject,
     coding_system: LispObject,
     noerror: LispObject,
-) -> LispStringRef {
-    if object.is_string() {
-        if string.unwrap().is_multibyte() {
-            let coding_system = check_coding_system_or_error(
-                get_coding_system_for_string(string.unwrap(), coding_system),
-                noerror,
-            );
-            *string = Some(
-                unsafe { code_convert_string(object, coding_system, Qnil, true, false, true) }
-                    .as_string_or_error(),
-            )
-        }
-        get_input_from_string(object, string.unwrap(), start, end).as_string_or_error()
-    } else if object.is_buffer() {
+    binary: LispObject,
+) -> LispObject {
+    let mut hasher = Hasher::new(algorithm);
+
+    if let Some(string) = object.as_string() {
+        hash_string(&mut hasher, string, start, end, coding_system, noerror);
+    } else if let Some(buffer) = object.as_buffer() {
+        hash_buffer(&mut hasher, buffer, start, end, coding_system, noerror);
+    } else {
+        // Let `extract_data_from_object' deal with the objects the GnuTLS
+        // functions accept, and with invalid ones.
+        let spec = list!(object, start, end, coding_system, noerror);
         let mut start_byte: ptrdiff_t = 0;
         let mut end_byte: ptrdiff_t = 0;
-        let s = get_input_from_buffer(buffer.unwrap(), start, end, &mut start_byte, &mut end_byte);
-        let ss = s.as_string_or_error();
-        if ss.is_multibyte() {
-            let coding_system = check_coding_system_or_error(
-                get_coding_system_for_buffer(
-                    object,
-                    buffer.unwrap(),
-                    start,
-                    end,
-                    start_byte,
-                    end_byte,
-                    coding_system,
-                ),
-                noerror,
-            );
-            unsafe { code_convert_string(s, coding_system, Qnil, true, false, false) }
-                .as_string_or_error()
-        } else {
-            ss
-        }
-    } else {
-        wrong_type!(Qstringp, object);
+        let input = unsafe { extract_data_from_object(spec, &mut start_byte, &mut end_byte) };
+        hasher.update(unsafe {
+            slice::from_raw_parts(
+                input.offset(start_byte) as *const u8,
+                (end_byte - start_byte) as usize,
+            )
+        });
     }
+
+    digest_string(&hasher.finish(), binary.is_not_nil())
 }
 
 /// Return MD5 message digest of OBJECT, a buffer or string.
@@ -335,116 +497,10 @@ pub fn secure_hash(
     _secure_hash(hash_alg(algorithm), object, start, end, Qnil, Qnil, binary)
 }
 
-fn _secure_hash(
-    algorithm: HashAlg,
-    object: LispObject,
-    start: LispObject,
-    end: LispObject,
-    coding_system: LispObject,
-    noerror: LispObject,
-    binary: LispObject,
-) -> LispObject {
-    type HashFn = fn(&[u8], &mut [u8]);
-
-    let spec = list!(object, start, end, coding_system, noerror);
-    let mut start_byte: ptrdiff_t = 0;
-    let mut end_byte: ptrdiff_t = 0;
-    let input = unsafe { extract_data_from_object(spec, &mut start_byte, &mut end_byte) };
-
-    if input.is_null() {
-        error!("secure_hash: failed to extract data from object, aborting!");
-    }
-
-    let input_slice = unsafe {
-        slice::from_raw_parts(
-            input.offset(start_byte) as *mut u8,
-            (end_byte - start_byte) as usize,
-        )
-    };
-
-    let (digest_size, hash_func) = match algorithm {
-        HashAlg::MD5 => (MD5_DIGEST_LEN, md5_buffer as HashFn),
-        HashAlg::SHA1 => (SHA1_DIGEST_LEN, sha1_buffer as HashFn),
-        HashAlg::SHA224 => (SHA224_DIGEST_LEN, sha224_buffer as HashFn),
-        HashAlg::SHA256 => (SHA256_DIGEST_LEN, sha256_buffer as HashFn),
-        HashAlg::SHA384 => (SHA384_DIGEST_LEN, sha384_buffer as HashFn),
-        HashAlg::SHA512 => (SHA512_DIGEST_LEN, sha512_buffer as HashFn),
-    };
-
-    let buffer_size = if binary.is_nil() {
-        (digest_size * 2) as EmacsInt
-    } else {
-        digest_size as EmacsInt
-    };
-    let digest = unsafe { make_uninit_string(buffer_size as EmacsInt) };
-    let mut digest_str = digest.as_string_or_error();
-    hash_func(input_slice, digest_str.as_mut_slice());
-    if binary.is_nil() {
-        hexify_digest_string(digest_str.as_mut_slice(), digest_size);
-    }
-    digest
-}
-
-/// To avoid a copy, buffer is both the source and the destination of
-/// this transformation. Buffer must contain len bytes of data and
-/// 2*len bytes of space for the final hex string.
-fn hexify_digest_string(buffer: &mut [u8], len: usize) {
-    static hexdigit: [u8; 16] = *b"0123456789abcdef";
-    debug_assert_eq!(
-        buffer.len(),
-        2 * len,
-        "buffer must be long enough to hold 2*len hex digits"
-    );
-    for i in (0..len).rev() {
-        let v = buffer[i];
-        buffer[2 * i] = hexdigit[(v >> 4) as usize];
-        buffer[2 * i + 1] = hexdigit[(v & 0xf) as usize];
-    }
-}
-
-// For the following hash functions, the caller must ensure that the
-// destination buffer is at least long enough to hold the
-// digest. Additionally, the caller may have been asked to return a
-// hex string, in which case dest_buf will be twice as long as the
-// digest.
-
-fn md5_buffer(buffer: &[u8], dest_buf: &mut [u8]) {
-    let output = md5::compute(buffer);
-    dest_buf[..output.len()].copy_from_slice(&*output)
-}
-
-fn sha1_buffer(buffer: &[u8], dest_buf: &mut [u8]) {
-    let mut hasher = sha1::Sha1::new();
-    hasher.update(buffer);
-    let output = hasher.digest().bytes();
-    dest_buf[..output.len()].copy_from_slice(&output)
-}
-
-/// Given an instance of `Digest`, and `buffer` write its hash to `dest_buf`.
-fn sha2_hash_buffer<D>(hasher: D, buffer: &[u8], dest_buf: &mut [u8])
-where
-    D: Digest,
-{
-    let mut hasher = hasher;
-    hasher.input(buffer);
-    let output = hasher.result();
-    dest_buf[..output.len()].copy_from_slice(&output)
-}
-
-fn sha224_buffer(buffer: &[u8], dest_buf: &mut [u8]) {
-    sha2_hash_buffer(Sha224::new(), buffer, dest_buf);
-}
-
-fn sha256_buffer(buffer: &[u8], dest_buf: &mut [u8]) {
-    sha2_hash_buffer(Sha256::new(), buffer, dest_buf);
-}
-
-fn sha384_buffer(buffer: &[u8], dest_buf: &mut [u8]) {
-    sha2_hash_buffer(Sha384::new(), buffer, dest_buf);
-}
-
-fn sha512_buffer(buffer: &[u8], dest_buf: &mut [u8]) {
-    sha2_hash_buffer(Sha512::new(), buffer, dest_buf);
+/// Return a list of all the supported `secure_hash' algorithms.
+#[lisp_fn]
+pub fn secure_hash_algorithms() -> LispObject {
+    list!(Qmd5, Qsha1, Qsha224, Qsha256, Qsha384, Qsha512)
 }
 
 /// Return a hash of the contents of BUFFER-OR-NAME.
@@ -453,28 +509,52 @@ fn sha512_buffer(buffer: &[u8], dest_buf: &mut [u8]) {
 #[lisp_fn(min = "0")]
 pub fn buffer_hash(buffer_or_name: Option<LispBufferOrName>) -> LispObject {
     let b = buffer_or_name.map_or_else(ThreadState::current_buffer_unchecked, |b| b.into());
-    let mut ctx = sha1::Sha1::new();
+    let mut hasher = Hasher::new(HashAlg::SHA1);
+    for part in &buffer_text(b, b.beg_byte(), b.z_byte()) {
+        hasher.update(part);
+    }
+    digest_string(&hasher.finish(), false)
+}
 
-    ctx.update(unsafe {
-        slice::from_raw_parts(b.beg_addr(), (b.gpt_byte() - b.beg_byte()) as usize)
-    });
-    if b.gpt_byte() < b.z_byte() {
-        ctx.update(unsafe {
-            slice::from_raw_parts(
-                b.gap_end_addr(),
-                b.z_addr() as usize - b.gap_end_addr() as usize,
-            )
-        });
+include!(concat!(env!("OUT_DIR"), "/crypto_exports.rs"));
+
+#[test]
+fn test_hasher_parts() {
+    let text = b"The quick brown fox jumps over the lazy dog";
+    for &algorithm in &[
+        HashAlg::MD5,
+        HashAlg::SHA1,
+        HashAlg::SHA224,
+        HashAlg::SHA256,
+        HashAlg::SHA384,
+        HashAlg::SHA512,
+    ] {
+        let mut whole = Hasher::new(algorithm);
+        whole.update(text);
+        let mut parts = Hasher::new(algorithm);
+        parts.update(&text[..10]);
+        parts.update(&[]);
+        parts.update(&text[10..]);
+        assert_eq!(whole.finish(), parts.finish());
     }
 
-    let formatted = ctx.digest().to_string();
-    let digest = unsafe { make_uninit_string(formatted.len() as EmacsInt) };
-    digest
-        .as_string()
-        .unwrap()
-        .as_mut_slice()
-        .copy_from_slice(formatted.as_bytes());
-    digest
+    let mut hasher = Hasher::new(HashAlg::MD5);
+    hasher.update(text);
+    assert_eq!(
+        hasher.finish(),
+        vec![
+            0x9e, 0x10, 0x7d, 0x9d, 0x37, 0x2b, 0xb6, 0x82, 0x6b, 0xd8, 0x1d, 0x35, 0x42, 0xa4,
+            0x19, 0xd6
+        ]
+    );
 }
 
-include!(concat!(env!("OUT_DIR"), "/crypto_exports.rs"));
+#[test]
+fn test_unchanged_covers() {
+    assert!(Unchanged::Ascii.covers(b"abc\n"));
+    assert!(!Unchanged::Ascii.covers("é".as_bytes()));
+    assert!(Unchanged::Unicode.covers("é€😀".as_bytes()));
+    // The raw byte 0xFF.
+    assert!(!Unchanged::Unicode.covers(b"\xc1\xbf"));
+    assert!(!Unchanged::Nothing.covers(b""));
+}
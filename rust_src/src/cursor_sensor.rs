@@ -0,0 +1,245 @@
+//! Cursor sensors and intangible text.
+//!
+//! `cursor-intangible-mode' keeps point out of text whose
+//! `cursor-intangible' property is non-nil, and `cursor-sensor-mode'
+//! calls the `cursor-sensor-functions' of the text that point enters or
+//! leaves.  cursor-sensor.el adds the functions here to
+//! `pre-redisplay-functions', so they run before every redisplay, in
+//! every window that shows a buffer using them.
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::current_buffer,
+    editfns::{point, point_max, point_min},
+    lisp::{defsubr, LispObject},
+    lists::{car, cdr, memq},
+    lists::{LispConsCircularChecks, LispConsEndChecks},
+    marker::{copy_marker, set_marker},
+    obarray::intern,
+    remacs_sys::{
+        EmacsInt, Fget_pos_property, Fnext_single_char_property_change,
+        Fnext_single_property_change, Fprevious_single_char_property_change, Qnil,
+    },
+    textprop::get_char_property,
+    windows::{set_window_parameter, set_window_point, window_point, LispWindowRef},
+};
+
+/// Return whether `cursor-sensor-inhibit' is non-nil.
+fn is_inhibited() -> bool {
+    intern("cursor-sensor-inhibit")
+        .value()
+        .map_or(false, |v| v.is_not_nil())
+}
+
+fn char_property(position: EmacsInt, prop: &str) -> LispObject {
+    get_char_property(position, intern(prop).into(), Qnil)
+}
+
+/// Return the `cursor-intangible' property that keeps point away from
+/// POSITION, or nil.
+fn intangible_at(position: EmacsInt) -> LispObject {
+    let prop = intern("cursor-intangible").into();
+    let p = unsafe { Fget_pos_property(position.into(), prop, Qnil) };
+    if p.is_nil() {
+        return p;
+    }
+    // If we're right between two different intangible thingies, we can
+    // stop here.  This is not quite consistent with the interpretation
+    // of "if it's sticky, then this boundary is itself intangible", but
+    // it better matches the behavior of `intangible'.
+    let a = char_property(position, "cursor-intangible");
+    if a.is_not_nil() && position > point_min() {
+        let b = char_property(position - 1, "cursor-intangible");
+        if b.is_not_nil() && !a.eq(b) {
+            return Qnil;
+        }
+    }
+    p
+}
+
+fn is_intangible(position: EmacsInt) -> bool {
+    intangible_at(position).is_not_nil()
+}
+
+/// Return the tangible position nearest CURPOS in the direction point
+/// moved in WINDOW, or `None` if CURPOS is tangible.
+fn tangible_pos(curpos: EmacsInt, window: LispWindowRef, second_chance: bool) -> Option<EmacsInt> {
+    if !is_intangible(curpos) {
+        return None;
+    }
+    let prop = LispObject::from(intern("cursor-intangible"));
+    let (min, max) = (point_min(), point_max());
+    let oldpos = window
+        .get_parameter(intern("cursor-intangible--last-point").into())
+        .as_fixnum();
+
+    let mut newpos = curpos;
+    if oldpos.map_or(false, |old| old < newpos) || newpos == min {
+        while newpos < max {
+            newpos = if char_property(newpos, "cursor-intangible").is_not_nil() {
+                unsafe { Fnext_single_char_property_change(newpos.into(), prop, Qnil, max.into()) }
+                    .as_fixnum_or_error()
+            } else {
+                newpos + 1
+            };
+            if !is_intangible(newpos) {
+                break;
+            }
+        }
+    } else {
+        while newpos > min {
+            newpos = if char_property(newpos - 1, "cursor-intangible").is_not_nil() {
+                unsafe {
+                    Fprevious_single_char_property_change(newpos.into(), prop, Qnil, min.into())
+                }
+                .as_fixnum_or_error()
+            } else {
+                newpos - 1
+            };
+            if !is_intangible(newpos) {
+                break;
+            }
+        }
+    }
+
+    if !((newpos == min || newpos == max) && is_intangible(newpos)) {
+        // All clear, we're good to go.
+        Some(newpos)
+    } else if second_chance {
+        // We bumped into an intangible BOB or EOB moving both ways.
+        Some(curpos)
+    } else {
+        // We bumped into an intangible BOB or EOB: try to move in the
+        // other direction.
+        tangible_pos(newpos, window, true)
+    }
+}
+
+/// Return the position nearest CURPOS that isn't intangible, or nil if
+/// CURPOS isn't.
+/// The position is searched for in the direction point moved since the
+/// last call of `cursor-sensor-move-to-tangible' for WINDOW, and in the
+/// other direction if that runs into the beginning or end of the buffer.
+/// If SECOND-CHANCE is non-nil, don't try the other direction, but
+/// return CURPOS.
+#[lisp_fn(min = "2")]
+pub fn cursor_sensor_tangible_pos(
+    curpos: EmacsInt,
+    window: LispWindowRef,
+    second_chance: bool,
+) -> Option<EmacsInt> {
+    tangible_pos(curpos, window, second_chance)
+}
+
+/// Move the point of WINDOW out of `cursor-intangible' text.
+#[lisp_fn]
+pub fn cursor_sensor_move_to_tangible(window: LispWindowRef) -> LispObject {
+    let window_obj = LispObject::from(window);
+    let curpos = window_point(window_obj.into()).unwrap_or_else(point);
+    let newpos = tangible_pos(curpos, window, false);
+    if let Some(pos) = newpos {
+        set_window_point(window_obj.into(), pos.into());
+    }
+    set_window_parameter(
+        window_obj.into(),
+        intern("cursor-intangible--last-point").into(),
+        newpos.unwrap_or(curpos).into(),
+    )
+}
+
+/// Move the point of WINDOW out of `cursor-intangible' text, unless
+/// `cursor-sensor-inhibit' is non-nil.
+#[lisp_fn(name = "cursor-sensor--move-to-tangible")]
+pub fn cursor_sensor_move_to_tangible_unless_inhibited(window: LispWindowRef) {
+    if !is_inhibited() {
+        cursor_sensor_move_to_tangible(window);
+    }
+}
+
+/// Return whether FUNCTION is missing from the `cursor-sensor-functions'
+/// somewhere after START up to END.
+fn is_missing(function: LispObject, start: EmacsInt, end: EmacsInt) -> bool {
+    let prop = LispObject::from(intern("cursor-sensor-functions"));
+    let mut pos = start;
+    let mut missing = false;
+    while pos < end {
+        pos = unsafe { Fnext_single_property_change(pos.into(), prop, Qnil, end.into()) }
+            .as_fixnum_or_error();
+        if memq(function, char_property(pos, "cursor-sensor-functions")).is_nil() {
+            missing = true;
+        }
+    }
+    missing
+}
+
+/// Call the `cursor-sensor-functions' that point entered or left in
+/// WINDOW since the last call for WINDOW, unless `cursor-sensor-inhibit'
+/// is non-nil.
+#[lisp_fn(name = "cursor-sensor--detect")]
+pub fn cursor_sensor_detect(window: LispWindowRef) {
+    if is_inhibited() {
+        return;
+    }
+    let window_obj = LispObject::from(window);
+    let position = window_point(window_obj.into()).unwrap_or_else(point);
+    let new = char_property(position, "cursor-sensor-functions");
+    let state = intern("cursor-sensor--last-state").into();
+    let old = window.get_parameter(state);
+    let oldposmark = car(old);
+    let oldpos = oldposmark
+        .as_marker()
+        .and_then(|m| m.charpos())
+        .map_or_else(point_min, |pos| pos as EmacsInt);
+    let (start, end) = (oldpos.min(position), oldpos.max(position));
+
+    if let Some(cons) = old.as_cons() {
+        let marker_buffer = oldposmark
+            .as_marker()
+            .and_then(|m| m.buffer())
+            .map_or(Qnil, LispObject::from);
+        if !marker_buffer.eq(current_buffer()) {
+            // WINDOW does not display the same buffer any more!
+            cons.set_cdr(Qnil);
+        }
+    }
+    let old_functions = cdr(old);
+
+    let prop = LispObject::from(intern("cursor-sensor-functions"));
+    let unchanged = (new.is_nil() && old_functions.is_nil())
+        || (new.eq(old_functions)
+            && unsafe { Fnext_single_property_change(start.into(), prop, Qnil, end.into()) }
+                .eq(end.into()));
+    if !unchanged {
+        let stays = |function: LispObject, functions: LispObject| {
+            memq(function, functions).is_not_nil() && !is_missing(function, start, end)
+        };
+        let left = intern("left").into();
+        for function in old_functions.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+        {
+            if !stays(function, new) {
+                call!(function, window_obj, oldpos.into(), left);
+            }
+        }
+        let entered = intern("entered").into();
+        for function in new.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+            if !stays(function, old_functions) {
+                call!(function, window_obj, oldpos.into(), entered);
+            }
+        }
+    }
+
+    // Remember the current state for next time.
+    match old.as_cons() {
+        Some(cons) => {
+            set_marker(oldposmark.into(), position.into(), Qnil);
+            cons.set_cdr(new);
+        }
+        None => {
+            let marker = copy_marker(position.into(), Qnil);
+            set_window_parameter(window_obj.into(), state, (marker, new).into());
+        }
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/cursor_sensor_exports.rs"));
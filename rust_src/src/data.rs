@@ -1,5 +1,7 @@
 //! data helpers
 
+use std::ptr;
+
 use field_offset::FieldOffset;
 use libc::c_int;
 
@@ -7,20 +9,23 @@ use remacs_macros::lisp_fn;
 
 use crate::{
     buffers::per_buffer_idx,
+    eval::eval_sub,
     frames::selected_frame,
     keymap::get_keymap,
     lisp::{defsubr, is_autoload},
     lisp::{LispObject, LispSubrRef, LiveBufferIter},
+    lists::{car, cdr},
     lists::{get, member, memq, put},
     math::leq,
     multibyte::{is_ascii, is_single_byte_char},
-    obarray::{loadhist_attach, map_obarray},
+    obarray::{intern, loadhist_attach, map_obarray},
     remacs_sys,
     remacs_sys::Vautoload_queue,
     remacs_sys::{
         aset_multibyte_string, bool_vector_binop_driver, buffer_defaults, build_string, globals,
-        rust_count_one_bits, set_default_internal, set_internal, symbol_trapped_write,
-        valid_lisp_object_p, wrong_choice, wrong_range, CHAR_TABLE_SET, CHECK_IMPURE,
+        notify_variable_watchers, rust_count_one_bits, set_internal, set_per_buffer_default,
+        symbol_trapped_write, valid_lisp_object_p, wrong_choice, wrong_range, CHAR_TABLE_SET,
+        CHECK_IMPURE,
     },
     remacs_sys::{buffer_local_flags, per_buffer_default, symbol_redirect},
     remacs_sys::{pvec_type, BoolVectorOp, EmacsInt, Lisp_Misc_Type, Lisp_Type, Set_Internal_Bind},
@@ -31,10 +36,11 @@ use crate::{
         Qcondition_variable, Qcons, Qcyclic_function_indirection, Qdefalias_fset_function, Qdefun,
         Qfinalizer, Qfloat, Qfont, Qfont_entity, Qfont_object, Qfont_spec, Qframe,
         Qfunction_documentation, Qhash_table, Qinteger, Qmany, Qmarker, Qmodule_function, Qmutex,
-        Qnil, Qnone, Qoverlay, Qprocess, Qrange, Qstring, Qsubr, Qsymbol, Qterminal, Qthread,
-        Qunbound, Qunevalled, Quser_ptr, Qvector, Qwatchers, Qwindow, Qwindow_configuration,
+        Qnil, Qnone, Qoverlay, Qprocess, Qrange, Qset_default, Qstring, Qsubr, Qsymbol, Qterminal,
+        Qthread, Qunbound, Qunevalled, Quser_ptr, Qvector, Qwatchers, Qwindow,
+        Qwindow_configuration,
     },
-    symbols::LispSymbolRef,
+    symbols::{keywordp, symbol_value, LispSymbolRef},
     threads::ThreadState,
 };
 
@@ -341,6 +347,19 @@ pub fn subr_name(subr: LispSubrRef) -> LispObject {
     unsafe { build_string(name) }
 }
 
+/// Return the argument names of subroutine SUBR, as a list of symbols.
+/// This is nil if SUBR takes no arguments, or if it doesn't record the
+/// names of its arguments, as subrs defined in C don't.
+#[lisp_fn]
+pub fn subr_arglist(subr: LispSubrRef) -> LispObject {
+    subr.arglist().map_or(Qnil, |arglist| {
+        arglist
+            .split_whitespace()
+            .rev()
+            .fold(Qnil, |list, name| LispObject::cons(intern(name), list))
+    })
+}
+
 /// Return the byteorder for the machine.
 /// Returns 66 (ASCII uppercase B) for big endian machines or 108
 /// (ASCII lowercase l) for small endian machines.
@@ -355,7 +374,7 @@ pub fn byteorder() -> u8 {
 
 /// Return the default value of SYMBOL, but don't check for voidness.
 /// Return Qunbound if it is void.
-fn default_value(mut symbol: LispSymbolRef) -> LispObject {
+pub fn default_value(mut symbol: LispSymbolRef) -> LispObject {
     while symbol.get_redirect() == symbol_redirect::SYMBOL_VARALIAS {
         symbol = symbol.get_indirect_variable();
     }
@@ -406,7 +425,7 @@ fn default_value(mut symbol: LispSymbolRef) -> LispObject {
 /// for this variable.
 #[lisp_fn]
 pub fn default_boundp(symbol: LispSymbolRef) -> bool {
-    !default_value(symbol).eq(Qunbound)
+    symbol.default_value().is_some()
 }
 
 /// Return SYMBOL's default value.
@@ -415,13 +434,9 @@ pub fn default_boundp(symbol: LispSymbolRef) -> bool {
 /// local bindings in certain buffers.
 #[lisp_fn(c_name = "default_value", name = "default-value")]
 pub fn default_value_lisp(symbol: LispSymbolRef) -> LispObject {
-    let value = default_value(symbol);
-
-    if value.eq(Qunbound) {
-        void_variable!(symbol);
-    }
-
-    value
+    symbol
+        .default_value()
+        .unwrap_or_else(|| void_variable!(symbol))
 }
 
 /***********************************************************************
@@ -697,6 +712,109 @@ pub fn set_default(symbol: LispSymbolRef, value: LispObject) -> LispObject {
     value
 }
 
+/// Set the default value of variable VAR to VALUE.
+/// VAR, the variable name, is literal (not evaluated);
+/// VALUE is an expression: it is evaluated and its value returned.
+/// The default value of a variable is seen in buffers
+/// that do not have their own values for the variable.
+///
+/// More generally, you can use multiple variables and values, as in
+///   (setq-default VAR VALUE VAR VALUE...)
+/// This sets each VAR's default value to the corresponding VALUE.
+/// The VALUE for the Nth VAR can refer to the new default values
+/// of previous VARs.
+/// usage: (setq-default [VAR VALUE]...)
+#[lisp_fn(min = "0", unevalled = "true")]
+pub fn setq_default(args: LispObject) -> LispObject {
+    let mut val = args;
+    let mut args_left = args;
+
+    while let Some((symbol, tail)) = args_left.into() {
+        val = eval_sub(car(tail));
+        set_default(symbol.as_symbol_or_error(), val);
+        args_left = cdr(tail);
+    }
+
+    val
+}
+
+/// Set the default value of SYMBOL to VALUE.  This is what
+/// `set-default' does; BINDFLAG tells whether the value is being set,
+/// bound or unbound.
+#[no_mangle]
+pub unsafe extern "C" fn set_default_internal(
+    symbol: LispObject,
+    value: LispObject,
+    bindflag: Set_Internal_Bind::Type,
+) {
+    let mut sym = symbol.as_symbol_or_error();
+    match sym.get_trapped_write() {
+        symbol_trapped_write::SYMBOL_NOWRITE => {
+            // Allow setting keywords to their own value.
+            if !keywordp(symbol) || !value.eq(symbol_value(sym)) {
+                setting_constant!(symbol);
+            }
+            return;
+        }
+        symbol_trapped_write::SYMBOL_TRAPPED_WRITE => {
+            // Don't notify here if we're going to call Fset anyway.
+            // Setting due to thread switching doesn't count.
+            if sym.get_redirect() != symbol_redirect::SYMBOL_PLAINVAL
+                && bindflag != Set_Internal_Bind::SET_INTERNAL_THREAD_SWITCH
+            {
+                notify_variable_watchers(symbol, value, Qset_default, Qnil);
+            }
+        }
+        _ => {}
+    }
+
+    while sym.get_redirect() == symbol_redirect::SYMBOL_VARALIAS {
+        sym = sym.get_indirect_variable();
+    }
+    match sym.get_redirect() {
+        symbol_redirect::SYMBOL_PLAINVAL => set_internal(symbol, value, Qnil, bindflag),
+        symbol_redirect::SYMBOL_LOCALIZED => {
+            let blv = sym.get_blv();
+
+            // Store new value into the DEFAULT-VALUE slot.
+            blv.defcell.force_cons().set_cdr(value);
+
+            // If the default binding is now loaded, set the REALVALUE slot too.
+            let fwd = blv.get_fwd();
+            if !fwd.is_null() && blv.defcell.eq(blv.valcell) {
+                store_symval_forwarding(fwd as *mut Lisp_Fwd, value, ptr::null_mut());
+            }
+        }
+        symbol_redirect::SYMBOL_FORWARDED => {
+            let valcontents = sym.get_fwd();
+
+            // Handle variables like case-fold-search that have special
+            // slots in the buffer.  Make them work apparently like
+            // Lisp_Buffer_Local_Value variables.
+            if (*valcontents).u_intfwd.ty == Lisp_Fwd_Buffer_Obj {
+                let offset = (*valcontents).u_buffer_objfwd.offset;
+                let idx = (*offset.apply_ptr_mut(&mut buffer_local_flags)).as_fixnum_or_error();
+
+                set_per_buffer_default(offset.get_byte_offset() as c_int, value);
+
+                // If this variable is not always local in all buffers,
+                // set it in the buffers that don't nominally have a local
+                // value.
+                if idx > 0 {
+                    LiveBufferIter::new().for_each(|mut buf| {
+                        if !buf.value_p(idx as isize) {
+                            *offset.apply_ptr_mut(buf.as_mut()) = value;
+                        }
+                    });
+                }
+            } else {
+                set_internal(symbol, value, Qnil, bindflag);
+            }
+        }
+        _ => panic!("Symbol type has no default value"),
+    }
+}
+
 extern "C" fn harmonize_variable_watchers(alias: LispObject, base_variable: LispObject) {
     if !base_variable.eq(alias)
         && base_variable.eq(alias.as_symbol_or_error().get_indirect_variable())
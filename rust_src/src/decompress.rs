@@ -8,11 +8,12 @@ use remacs_macros::lisp_fn;
 
 use crate::{
     buffers::validate_region,
+    insdel::signal_after_change,
     lisp::defsubr,
     lisp::LispObject,
     remacs_sys::{
         buf_charpos_to_bytepos, del_range_2, insert_from_gap, make_gap, maybe_quit, modify_text,
-        move_gap_both, signal_after_change, update_compositions, CHECK_HEAD,
+        move_gap_both, update_compositions, CHECK_HEAD,
     },
     threads::ThreadState,
 };
@@ -0,0 +1,270 @@
+//! Serializer and background writer for desktop files (see desktop.el).
+//!
+//! Large sessions make the desktop file big, and saving it used to
+//! block Emacs, most noticeably on exit.  The values of the saved
+//! variables and buffers are turned into text here, with the printer,
+//! and encoding is the only work left for the main thread once the
+//! contents are complete: the file itself is written by a background
+//! thread, to a temporary file which is then renamed over the desktop
+//! file, so a crash never leaves a truncated desktop behind.
+
+use std::{
+    ffi::CStr,
+    io,
+    sync::Mutex,
+    thread::{self, JoinHandle},
+};
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    editfns::message,
+    eval::SpecBinding,
+    fileio::{bytes_to_path, write_atomically},
+    lisp::defsubr,
+    lisp::LispObject,
+    lists::list,
+    marker::{marker_buffer, marker_position_lisp},
+    multibyte::LispStringRef,
+    obarray::{intern, intern_soft},
+    remacs_sys::report_file_errno,
+    remacs_sys::{
+        code_convert_string_norecord, encode_file_name, Fexpand_file_name, Fprin1_to_string,
+        Fset_text_properties,
+    },
+    remacs_sys::{Qnil, Qquote, Qt, Qutf_8_emacs},
+    sequences::{copy_sequence, vector_of},
+    string_alloc::make_unibyte_string,
+    symbols::keywordp,
+    threads::ThreadState,
+};
+
+/// How the expression made by `value_to_sexp' has to be written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Quote {
+    /// The value is self-evaluating, and may be quoted.
+    May,
+    /// The value has to be quoted.
+    Must,
+    /// The expression has to be evaluated to give the value.
+    Never,
+}
+
+impl Quote {
+    fn to_lisp(self) -> LispObject {
+        match self {
+            Quote::May => intern("may").into(),
+            Quote::Must => intern("must").into(),
+            Quote::Never => Qnil,
+        }
+    }
+}
+
+/// SEXP, quoted if it has to be, for use as an element of a list or
+/// vector built by evaluation.
+fn element((quote, sexp): (Quote, LispObject)) -> LispObject {
+    if quote == Quote::Must {
+        list!(Qquote, sexp)
+    } else {
+        sexp
+    }
+}
+
+/// Return (QUOTE, SEXP), where SEXP evaluates to VALUE when QUOTE
+/// says so.  Values which can't be read back are saved as a string.
+fn value_to_sexp(value: LispObject) -> (Quote, LispObject) {
+    if value.is_number() || value.is_nil() || value.is_t() || keywordp(value) {
+        (Quote::May, value)
+    } else if let Some(string) = value.as_string() {
+        // Get rid of text properties because we cannot read them.
+        let copy = copy_sequence(value);
+        unsafe { Fset_text_properties(0.into(), string.len_chars().into(), Qnil, copy) };
+        (Quote::May, copy)
+    } else if value.is_symbol() {
+        (Quote::Must, value)
+    } else if let Some(vector) = value.as_vector() {
+        let items: Vec<_> = vector.iter().map(value_to_sexp).collect();
+        if items.iter().any(|&(quote, _)| quote == Quote::Never) {
+            let mut args = vec![intern("vector").into()];
+            args.extend(items.into_iter().map(element));
+            (Quote::Never, list(&args))
+        } else {
+            let sexps: Vec<_> = items.into_iter().map(|(_, sexp)| sexp).collect();
+            (Quote::May, vector_of(&sexps))
+        }
+    } else if value.is_cons() {
+        let mut items = Vec::new();
+        let mut tail = value;
+        while let Some(cons) = tail.as_cons() {
+            let (car, cdr) = cons.into();
+            items.push(value_to_sexp(car));
+            tail = cdr;
+        }
+        let last = if tail.is_nil() {
+            None
+        } else {
+            Some(value_to_sexp(tail))
+        };
+        let never = |&(quote, _): &(Quote, LispObject)| quote == Quote::Never;
+        if items.iter().any(never) || last.iter().any(never) {
+            let head = if last.is_some() {
+                "desktop-list*"
+            } else {
+                "list"
+            };
+            let mut args = vec![intern(head).into()];
+            args.extend(items.into_iter().chain(last).map(element));
+            (Quote::Never, list(&args))
+        } else {
+            let end = last.map_or(Qnil, |(_, sexp)| sexp);
+            let sexp = items
+                .into_iter()
+                .rev()
+                .fold(end, |rest, (_, sexp)| LispObject::cons(sexp, rest));
+            (Quote::Must, sexp)
+        }
+    } else if let Some(subr) = value.as_subr() {
+        let name = unsafe { CStr::from_ptr(subr.symbol_name()) }.to_string_lossy();
+        let symbol = intern_soft(LispObject::from(&*name), None);
+        (
+            Quote::Never,
+            list!(intern("symbol-function"), list!(Qquote, symbol)),
+        )
+    } else if let Some(marker) = value.as_marker() {
+        let position: LispObject = marker_position_lisp(marker).into();
+        // Like `buffer-name', use the current buffer for a marker
+        // pointing nowhere.
+        let buffer = marker_buffer(marker)
+            .unwrap_or_else(ThreadState::current_buffer_unchecked)
+            .name();
+        let mk: LispObject = intern("mk").into();
+        let set = list!(
+            intern("set-marker"),
+            mk,
+            position,
+            list!(intern("get-buffer"), buffer)
+        );
+        let hook = list!(
+            intern("add-hook"),
+            list!(Qquote, intern("desktop-delay-hook")),
+            list!(intern("lambda"), Qnil, set)
+        );
+        let make = list!(list!(mk, list!(intern("make-marker"))));
+        (Quote::Never, list!(intern("let"), make, hook, mk))
+    } else {
+        (Quote::May, LispObject::from("Unprintable entity"))
+    }
+}
+
+/// Convert VALUE to a pair (QUOTE . SEXP); (eval SEXP) gives VALUE.
+/// SEXP is an sexp that when evaluated yields VALUE.
+/// QUOTE may be `may' (value may be quoted),
+/// `must' (value must be quoted), or nil (value must not be quoted).
+#[lisp_fn(name = "desktop--v2s")]
+pub fn desktop_v2s(value: LispObject) -> LispObject {
+    let (quote, sexp) = value_to_sexp(value);
+    LispObject::cons(quote.to_lisp(), sexp)
+}
+
+/// Convert VALUE to a string that when read evaluates to the same value.
+/// Not all types of values are supported.
+#[lisp_fn]
+pub fn desktop_value_to_string(value: LispObject) -> LispObject {
+    let (quote, sexp) = value_to_sexp(value);
+    let binding = SpecBinding::push(intern("print-escape-newlines").into(), Qt);
+    binding.bind(intern("print-length").into(), Qnil);
+    binding.bind(intern("print-level").into(), Qnil);
+    binding.bind(intern("float-output-format").into(), Qnil);
+    binding.bind(intern("print-quoted").into(), Qt);
+    let text = binding.unbind(unsafe { Fprin1_to_string(sexp, Qnil) });
+    if quote == Quote::Must {
+        call!(intern("concat").into(), LispObject::from("'"), text)
+    } else {
+        text
+    }
+}
+
+struct PendingWrite {
+    file: Vec<u8>,
+    thread: JoinHandle<io::Result<()>>,
+}
+
+lazy_static! {
+    /// The desktop file currently being written, if any.
+    static ref PENDING_WRITE: Mutex<Option<PendingWrite>> = Mutex::new(None);
+}
+
+/// Wait for the pending write, if any.  Return the encoded name of the
+/// file and the result of writing it.
+fn finish_pending_write() -> Option<(Vec<u8>, io::Result<()>)> {
+    let pending = PENDING_WRITE.lock().unwrap().take()?;
+    let result = pending
+        .thread
+        .join()
+        .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::Other, "writer panicked")));
+    Some((pending.file, result))
+}
+
+/// Write the desktop CONTENTS to FILE in the background.
+/// CONTENTS is encoded with `utf-8-emacs' before this function returns,
+/// and FILE is replaced atomically once it has been written.
+/// Use `desktop-wait-for-write' to wait until that has happened.
+/// If the previous write failed, signal a `file-error' for it instead of
+/// writing FILE.
+#[lisp_fn]
+pub fn desktop_write_file(file: LispStringRef, contents: LispStringRef) {
+    let absname = unsafe { Fexpand_file_name(file.into(), Qnil) };
+    let encoded_name = unsafe { encode_file_name(absname) }.as_string_or_error();
+    let encoded = unsafe { code_convert_string_norecord(contents.into(), Qutf_8_emacs, true) }
+        .as_string_or_error();
+
+    let name = encoded_name.as_slice().to_vec();
+    let bytes = encoded.as_slice().to_vec();
+
+    // Writes have to reach the disk in order, so finish the previous
+    // one, and report its errors, before starting this one.
+    if let Some((previous, Err(e))) = finish_pending_write() {
+        report_write_error(&previous, &e);
+    }
+
+    let path = bytes_to_path(&name);
+    let thread = thread::spawn(move || write_atomically(&path, &bytes));
+    *PENDING_WRITE.lock().unwrap() = Some(PendingWrite { file: name, thread });
+}
+
+fn report_write_error(name: &[u8], error: &io::Error) -> ! {
+    let file = make_unibyte_string(name);
+    unsafe {
+        report_file_errno(
+            "Writing desktop file\0".as_ptr() as *const libc::c_char,
+            file,
+            error.raw_os_error().unwrap_or(libc::EIO),
+        )
+    }
+}
+
+/// Wait until the desktop file written by `desktop-write-file' is on disk.
+/// Return t if there was a pending write, nil otherwise.  Signal a
+/// `file-error' if writing the file failed.
+#[lisp_fn]
+pub fn desktop_wait_for_write() -> bool {
+    match finish_pending_write() {
+        Some((name, Err(e))) => report_write_error(&name, &e),
+        Some(_) => true,
+        None => false,
+    }
+}
+
+/// Make sure a desktop file being written is complete before Emacs exits.
+/// Emacs is past signaling errors by then, so a failure is only reported.
+pub fn desktop_flush_write() {
+    if let Some((name, Err(e))) = finish_pending_write() {
+        message(&mut [
+            LispObject::from("Error writing desktop file %s: %s"),
+            make_unibyte_string(&name),
+            LispObject::from(e.to_string().as_str()),
+        ]);
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/desktop_exports.rs"));
@@ -0,0 +1,195 @@
+//! Finding the differences between two sequences, with Myers' O(ND)
+//! algorithm.  This is used to change buffer text in place, so that
+//! only the parts that differ are replaced.
+
+use std::ops::Range;
+
+/// A run of elements of the old sequence that is replaced by a run of
+/// elements of the new one.  Either run may be empty.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hunk {
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+/// Return the hunks that turn OLD into NEW, in order.  If that takes
+/// more than MAX_EDITS insertions and deletions of elements, everything
+/// between the common prefix and suffix of the sequences is replaced as
+/// a single hunk instead.
+pub fn diff<T: PartialEq>(old: &[T], new: &[T], max_edits: usize) -> Vec<Hunk> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let hunks = if old_middle.is_empty() && new_middle.is_empty() {
+        Vec::new()
+    } else if old_middle.is_empty() || new_middle.is_empty() {
+        vec![Hunk {
+            old: 0..old_middle.len(),
+            new: 0..new_middle.len(),
+        }]
+    } else {
+        shortest_edit(old_middle, new_middle, max_edits).unwrap_or_else(|| {
+            vec![Hunk {
+                old: 0..old_middle.len(),
+                new: 0..new_middle.len(),
+            }]
+        })
+    };
+
+    hunks
+        .into_iter()
+        .map(|hunk| Hunk {
+            old: hunk.old.start + prefix..hunk.old.end + prefix,
+            new: hunk.new.start + prefix..hunk.new.end + prefix,
+        })
+        .collect()
+}
+
+/// The hunks of a shortest edit script from OLD to NEW, or `None` if it
+/// has more than MAX_EDITS edits.
+fn shortest_edit<T: PartialEq>(old: &[T], new: &[T], max_edits: usize) -> Option<Vec<Hunk>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let limit = max_edits.min(old.len() + new.len()) as isize;
+
+    // V[K] is the furthest X reached on diagonal K = X - Y; ROUNDS[D]
+    // holds V for the diagonals -D..=D after D edits, to retrace the path
+    // that reached the end.
+    let offset = limit + 1;
+    let mut v = vec![0isize; 2 * offset as usize + 1];
+    let mut rounds: Vec<Vec<isize>> = Vec::new();
+    let further = |v: &[isize], k: isize, d: isize| {
+        k == -d || (k != d && v[(offset + k - 1) as usize] < v[(offset + k + 1) as usize])
+    };
+
+    for d in 0..=limit {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if further(&v, k, d) {
+                v[(offset + k + 1) as usize]
+            } else {
+                v[(offset + k - 1) as usize] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(offset + k) as usize] = x;
+
+            if x >= n && y >= m {
+                rounds.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+                return Some(retrace(&rounds, n, m));
+            }
+        }
+        rounds.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+    }
+    None
+}
+
+/// Follow the path found by `shortest_edit` back from (N, M) to the
+/// start, collecting its edits into hunks.
+fn retrace(rounds: &[Vec<isize>], n: isize, m: isize) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let (mut x, mut y) = (n, m);
+
+    for d in (1..rounds.len() as isize).rev() {
+        // The values of V in the previous round, by diagonal.
+        let prev = &rounds[(d - 1) as usize];
+        let at = |k: isize| prev[(k + d - 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        // The edit is an insertion if it moved down to diagonal K from
+        // K + 1, and a deletion otherwise.  The rest of the way to (X, Y)
+        // is made of elements both sequences have.
+        let (end_x, end_y) = if prev_k == k + 1 {
+            (prev_x, prev_y + 1)
+        } else {
+            (prev_x + 1, prev_y)
+        };
+        let edit = Hunk {
+            old: prev_x as usize..end_x as usize,
+            new: prev_y as usize..end_y as usize,
+        };
+        match hunks.last_mut() {
+            Some(last) if last.old.start == edit.old.end && last.new.start == edit.new.end => {
+                last.old.start = edit.old.start;
+                last.new.start = edit.new.start;
+            }
+            _ => hunks.push(edit),
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    hunks.reverse();
+    hunks
+}
+
+#[cfg(test)]
+fn apply(old: &str, new: &str, hunks: &[Hunk]) -> String {
+    let (old, new): (Vec<char>, Vec<char>) = (old.chars().collect(), new.chars().collect());
+    let mut result = String::new();
+    let mut pos = 0;
+    for hunk in hunks {
+        result.extend(&old[pos..hunk.old.start]);
+        result.extend(&new[hunk.new.clone()]);
+        pos = hunk.old.end;
+    }
+    result.extend(&old[pos..]);
+    result
+}
+
+#[test]
+fn test_diff() {
+    let chars = |s: &str| s.chars().collect::<Vec<char>>();
+    for &(old, new) in &[
+        ("abcabba", "cbabac"),
+        ("", "abc"),
+        ("abc", ""),
+        ("abc", "abc"),
+        ("abcdef", "abXdef"),
+        ("xabc", "abcx"),
+    ] {
+        let hunks = diff(&chars(old), &chars(new), 100);
+        assert_eq!(apply(old, new, &hunks), new, "{} -> {}", old, new);
+    }
+
+    assert_eq!(
+        diff(&chars("abcdef"), &chars("abXdeYf"), 100),
+        vec![
+            Hunk {
+                old: 2..3,
+                new: 2..3
+            },
+            Hunk {
+                old: 5..5,
+                new: 5..6
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_diff_max_edits() {
+    let chars = |s: &str| s.chars().collect::<Vec<char>>();
+    assert_eq!(
+        diff(&chars("aXbYc"), &chars("aZbWc"), 2),
+        vec![Hunk {
+            old: 1..4,
+            new: 1..4
+        }]
+    );
+}
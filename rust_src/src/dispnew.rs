@@ -1,6 +1,8 @@
 //! Updating of data structures for redisplay.
 
-use std::{cmp, ptr};
+use std::{cmp, ptr, slice};
+
+use libc::c_int;
 
 use remacs_lib::current_timespec;
 use remacs_macros::lisp_fn;
@@ -14,13 +16,15 @@ use crate::{
     lists::{LispConsCircularChecks, LispConsEndChecks},
     remacs_sys::{
         clear_current_matrices, detect_input_pending_run_timers, dtotimespec, fset_redisplay,
-        mark_window_display_accurate, putchar_unlocked, redisplay_preserve_echo_area, ring_bell,
-        specbind, swallow_events, timespec_add, timespec_sub, wait_reading_process_output,
+        image_glyph_spec, mark_window_display_accurate, putchar_unlocked,
+        redisplay_preserve_echo_area, ring_bell, specbind, swallow_events, timespec_add,
+        timespec_sub, wait_reading_process_output,
     },
     remacs_sys::{
         globals, noninteractive, redisplaying_p, Qnil, Qredisplay_dont_pause, Qt, Vframe_list,
         WAIT_READING_MAX,
     },
+    remacs_sys::{glyph_row_area, glyph_type, window_part},
     remacs_sys::{EmacsDouble, EmacsInt, Lisp_Glyph},
     terminal::{clear_frame, update_begin, update_end},
     threads::c_specpdl_index,
@@ -58,7 +62,7 @@ pub fn sleep_for(seconds: EmacsDouble, milliseconds: Option<EmacsInt>) {
 }
 
 /**********************************************************************
-		    Redrawing Frames
+            Redrawing Frames
 **********************************************************************/
 
 /// Redraw frame FRAME.
@@ -119,8 +123,97 @@ pub extern "C" fn set_window_update_flags(w: LispWindowRef, on_p: bool) {
     }
 }
 
+/// Return the index of the glyph under X pixels from the start of a row
+/// whose glyphs are WIDTHS pixels wide, and X relative to that glyph.
+/// The index is the number of glyphs if X is past them all.
+fn glyph_under_x<I: IntoIterator<Item = i32>>(widths: I, mut x: i32) -> (usize, i32) {
+    let mut index = 0;
+    for width in widths {
+        if x < width {
+            break;
+        }
+        x -= width;
+        index += 1;
+    }
+    (index, x)
+}
+
+/// Value is the string under window-relative coordinates X/Y in the
+/// mode line or header line (PART says which) of window W, or nil if
+/// none.  *CHARPOS is set to the position in the string returned.
+///
+/// X and Y are pixels on entry; they are set to the column and row of
+/// the glyph clicked on.  The string is found in the mode line row of
+/// W's current glyph matrix, as last displayed.
+#[no_mangle]
+pub unsafe extern "C" fn mode_line_string(
+    w: LispWindowRef,
+    part: window_part::Type,
+    x: *mut c_int,
+    y: *mut c_int,
+    charpos: *mut isize,
+    object: *mut LispObject,
+    dx: *mut c_int,
+    dy: *mut c_int,
+    width: *mut c_int,
+    height: *mut c_int,
+) -> LispObject {
+    let matrix = w.current_matrix;
+    let row = if part == window_part::ON_MODE_LINE {
+        (*matrix).rows.offset(((*matrix).nrows - 1) as isize)
+    } else {
+        (*matrix).rows
+    };
+    let first_text_row = if (*(*matrix).rows).mode_line_p() {
+        (*matrix).rows.offset(1)
+    } else {
+        (*matrix).rows
+    };
+    let mut y0 = *y - (*row).y;
+    *y = row.offset_from(first_text_row) as c_int;
+
+    let mut string = Qnil;
+    let x0 = if (*row).mode_line_p() && (*row).enabled_p() {
+        // Find the glyph under X.  If it has a string object, that's
+        // the one we're looking for.
+        let area = glyph_row_area::TEXT_AREA as usize;
+        let glyphs = slice::from_raw_parts((*row).glyphs[area], (*row).used[area] as usize);
+        let (index, x0) = glyph_under_x(glyphs.iter().map(|g| i32::from(g.pixel_width)), *x);
+        *x = index as c_int;
+        if let Some(glyph) = glyphs.get(index) {
+            string = glyph.object;
+            *charpos = glyph.charpos;
+            *width = glyph.pixel_width.into();
+            *height = i32::from(glyph.ascent) + i32::from(glyph.descent);
+            if glyph.type_() == glyph_type::IMAGE_GLYPH {
+                let mut frame = w.frame.as_frame_or_error();
+                let spec = image_glyph_spec(frame.as_mut(), glyph as *const Lisp_Glyph as *mut _);
+                if spec.is_not_nil() {
+                    *object = spec;
+                }
+                y0 -= (*row).ascent - i32::from(glyph.ascent);
+            }
+        } else {
+            // Add extra (default width) columns if clicked after EOL.
+            *x += x0 / w.frame.as_frame_or_error().column_width;
+            *width = 0;
+            *height = (*row).height;
+        }
+        x0
+    } else {
+        *x = 0;
+        *width = 0;
+        *height = 0;
+        0
+    };
+
+    *dx = x0;
+    *dy = y0;
+    string
+}
+
 /***********************************************************************
-		   Blinking cursor
+           Blinking cursor
 ***********************************************************************/
 
 /// Set the cursor-visibility flag of WINDOW to SHOW.
@@ -209,3 +302,14 @@ pub fn redisplay(force: LispObject) -> bool {
 }
 
 include!(concat!(env!("OUT_DIR"), "/dispnew_exports.rs"));
+
+#[test]
+fn test_glyph_under_x() {
+    let widths = [8, 8, 16, 8];
+    assert_eq!(glyph_under_x(widths.iter().cloned(), 0), (0, 0));
+    assert_eq!(glyph_under_x(widths.iter().cloned(), 7), (0, 7));
+    assert_eq!(glyph_under_x(widths.iter().cloned(), 8), (1, 0));
+    assert_eq!(glyph_under_x(widths.iter().cloned(), 30), (2, 14));
+    assert_eq!(glyph_under_x(widths.iter().cloned(), 45), (4, 5));
+    assert_eq!(glyph_under_x(Vec::new(), 12), (0, 12));
+}
@@ -14,9 +14,12 @@ use crate::{
     buffers::{current_buffer, validate_region},
     buffers::{LispBufferOrCurrent, LispBufferOrName, LispBufferRef, BUF_BYTES_MAX},
     character::{char_head_p, dec_pos},
+    diff::diff,
     eval::{progn, unbind_to},
     indent::invalidate_current_column,
+    insdel::signal_after_change,
     lisp::{defsubr, LispObject},
+    lists::{LispConsCircularChecks, LispConsEndChecks},
     marker::{
         buf_bytepos_to_charpos, buf_charpos_to_bytepos, marker_position_lisp, point_marker,
         set_point_from_marker,
@@ -31,18 +34,23 @@ use crate::{
     remacs_sys::{
         buffer_overflow, build_string, current_message, del_range, del_range_1, downcase,
         find_before_next_newline, find_newline, get_char_property_and_overlay, globals, insert,
-        insert_and_inherit, insert_from_buffer, make_buffer_string, make_buffer_string_both,
-        make_save_obj_obj_obj_obj, make_string_from_bytes, maybe_quit, message1, message3,
+        insert_and_inherit, insert_from_buffer, insert_from_string, make_buffer_string,
+        make_buffer_string_both, make_save_obj_obj_obj_obj, make_specified_string,
+        make_string_from_bytes, maybe_quit, message1, message3, prepare_to_modify_buffer,
         record_unwind_current_buffer, record_unwind_protect, save_excursion_restore,
         save_restriction_restore, save_restriction_save, scan_newline_from_point,
-        set_buffer_internal_1, set_point, set_point_both, styled_format, update_buffer_properties,
-        STRING_BYTES,
+        set_buffer_internal_1, set_point, set_point_both, specbind, styled_format,
+        update_buffer_properties, STRING_BYTES,
     },
     remacs_sys::{
-        Fadd_text_properties, Fcopy_sequence, Fget_pos_property, Fnext_single_char_property_change,
+        Fadd_text_properties, Fget_pos_property, Fnext_single_char_property_change,
         Fprevious_single_char_property_change, Fx_popup_dialog,
     },
-    remacs_sys::{Qboundary, Qfield, Qinteger_or_marker_p, Qmark_inactive, Qnil, Qt},
+    remacs_sys::{
+        Qboundary, Qfield, Qinhibit_modification_hooks, Qinteger_or_marker_p, Qmark_inactive, Qnil,
+        Qt,
+    },
+    sequences::copy_sequence,
     textprop::get_char_property,
     threads::{c_specpdl_index, ThreadState},
     time::{lisp_time_struct, time_overflow, LispTime},
@@ -422,7 +430,7 @@ pub fn propertize(args: &[LispObject]) -> LispObject {
     let first = it.next().unwrap();
     let orig_string = first.as_string_or_error();
 
-    let copy = unsafe { Fcopy_sequence(*first) };
+    let copy = copy_sequence(*first);
 
     let mut properties = Qnil;
 
@@ -1389,6 +1397,254 @@ pub fn delete_and_extract_region(
     }
 }
 
+/// What a single edit passed to `apply-edits-batch' does.
+#[derive(Clone, Copy)]
+enum BufferEditKind {
+    Insert(LispStringRef),
+    Delete(ptrdiff_t),
+}
+
+#[derive(Clone, Copy)]
+struct BufferEdit {
+    pos: ptrdiff_t,
+    kind: BufferEditKind,
+}
+
+impl BufferEdit {
+    fn from_lisp(edit: LispObject) -> Self {
+        let (pos, what) = edit.as_cons_or_error().into();
+        let pos = pos.as_fixnum_coerce_marker_or_error() as ptrdiff_t;
+        let kind = if let Some(string) = what.as_string() {
+            BufferEditKind::Insert(string)
+        } else {
+            let count = what.as_natnum_or_error() as ptrdiff_t;
+            BufferEditKind::Delete(count)
+        };
+        BufferEdit { pos, kind }
+    }
+
+    /// The end of the text this edit replaces.
+    fn end(self) -> ptrdiff_t {
+        match self.kind {
+            BufferEditKind::Insert(_) => self.pos,
+            BufferEditKind::Delete(count) => self.pos + count,
+        }
+    }
+
+    fn is_deletion(self) -> bool {
+        match self.kind {
+            BufferEditKind::Insert(_) => false,
+            BufferEditKind::Delete(_) => true,
+        }
+    }
+}
+
+/// Apply EDITS to the current buffer as a single change.
+/// EDITS is a list of edits of the form (POSITION . STRING), which inserts
+/// STRING at POSITION, or (POSITION . COUNT), which deletes the COUNT
+/// characters following POSITION.  POSITION may be a number or a marker.
+///
+/// All positions refer to the buffer text as it was before any of the
+/// edits was made; each edit is shifted to account for the others.
+/// Insertions at the same position appear in the order in which they
+/// are given, and an insertion at the position of a deletion replaces
+/// the deleted text.  Deletions must not overlap one another, and no
+/// insertion may fall inside deleted text.  All edits are checked
+/// before the buffer is changed, so an invalid batch changes nothing.
+///
+/// Markers are relocated as by `insert' and `delete-region'; point is
+/// preserved as by `save-excursion'.  The change hooks run once, for
+/// the smallest region containing all edits, and the edits form a
+/// single undo group.  Return the number of characters by which the
+/// buffer grew, which is negative if it shrank.
+#[lisp_fn]
+pub fn apply_edits_batch(edits: LispObject) -> EmacsInt {
+    let batch: Vec<BufferEdit> = edits
+        .iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on)
+        .map(BufferEdit::from_lisp)
+        .collect();
+    apply_edits(batch)
+}
+
+/// Apply the edits of BATCH to the current buffer as `apply-edits-batch'
+/// does, and return the number of characters by which the buffer grew.
+fn apply_edits(mut batch: Vec<BufferEdit>) -> EmacsInt {
+    if batch.is_empty() {
+        return 0;
+    }
+
+    let buf = ThreadState::current_buffer_unchecked();
+    for edit in &batch {
+        if edit.pos < buf.begv || edit.end() > buf.zv {
+            args_out_of_range!(edit.pos, edit.end());
+        }
+    }
+
+    // The sort is stable, so insertions at one position keep their
+    // order.  A deletion sorts after the insertions at its position so
+    // that it is made first below, and removes none of the new text.
+    batch.sort_by_key(|edit| (edit.pos, edit.is_deletion()));
+    let mut deleted_until = buf.begv;
+    for edit in &batch {
+        if edit.pos < deleted_until {
+            error!("Overlapping edits at position {}", edit.pos);
+        }
+        if edit.is_deletion() {
+            deleted_until = edit.end();
+        }
+    }
+
+    let start = batch[0].pos;
+    let end = batch.iter().map(|edit| edit.end()).max().unwrap_or(start);
+    let growth: ptrdiff_t = batch
+        .iter()
+        .map(|edit| match edit.kind {
+            BufferEditKind::Insert(string) => string.len_chars(),
+            BufferEditKind::Delete(length) => -length,
+        })
+        .sum();
+
+    let count = c_specpdl_index();
+    unsafe {
+        record_unwind_protect(Some(save_excursion_restore), save_excursion_save());
+        prepare_to_modify_buffer(start, end, ptr::null_mut());
+        specbind(Qinhibit_modification_hooks, Qt);
+    }
+
+    // Working from the end of the buffer backwards means no edit moves
+    // the text the remaining ones refer to.
+    for edit in batch.iter().rev() {
+        match edit.kind {
+            BufferEditKind::Delete(length) => unsafe { del_range(edit.pos, edit.pos + length) },
+            BufferEditKind::Insert(string) => {
+                goto_char(edit.pos.into());
+                unsafe {
+                    insert_from_string(
+                        string.into(),
+                        0,
+                        0,
+                        string.len_chars(),
+                        string.len_bytes(),
+                        false,
+                    )
+                };
+            }
+        }
+    }
+
+    unbind_to(count, Qnil);
+    signal_after_change(start, end - start, end - start + growth);
+
+    growth as EmacsInt
+}
+
+/// The accessible text of BUFFER.
+fn accessible_bytes(buffer: LispBufferRef) -> Vec<u8> {
+    (buffer.begv_byte..buffer.zv_byte)
+        .map(|pos| buffer.fetch_byte(pos))
+        .collect()
+}
+
+/// The lines of TEXT, each with the newline that ends it.
+fn split_lines(text: &[u8]) -> Vec<&[u8]> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for (i, &byte) in text.iter().enumerate() {
+        if byte == b'\n' {
+            lines.push(&text[start..=i]);
+            start = i + 1;
+        }
+    }
+    if start < text.len() {
+        lines.push(&text[start..]);
+    }
+    lines
+}
+
+/// Replace the accessible portion of the current buffer with that of SOURCE.
+/// SOURCE is a buffer or the name of one.  Only the lines that differ
+/// between the two are replaced, as by `apply-edits-batch' (which see),
+/// so markers, point and window starts in the text that stays the same
+/// are not moved.  The text taken from SOURCE has no text properties.
+///
+/// Finding the lines that differ takes time and memory that grow with
+/// the square of their number.  If more than MAX-CHANGES lines have to
+/// be inserted or deleted, which defaults to 2000, all the text between
+/// the first and the last line that differ is replaced instead.
+///
+/// The two buffers must both be multibyte or both unibyte.  Return the
+/// number of runs of lines that were replaced.
+#[lisp_fn(min = "1")]
+pub fn replace_buffer_lines(source: LispBufferOrName, max_changes: Option<EmacsInt>) -> EmacsInt {
+    let source = LispBufferRef::from(source)
+        .as_live()
+        .unwrap_or_else(|| error!("Selecting deleted buffer"));
+    let mut buffer = ThreadState::current_buffer_unchecked();
+    let multibyte = buffer.multibyte_characters_enabled();
+    if source.multibyte_characters_enabled() != multibyte {
+        error!("Buffers differ in multibyteness");
+    }
+
+    let old_text = accessible_bytes(buffer);
+    let new_text = accessible_bytes(source);
+    let old_lines = split_lines(&old_text);
+    let new_lines = split_lines(&new_text);
+    let hunks = diff(
+        &old_lines,
+        &new_lines,
+        max_changes.unwrap_or(2000).max(0) as usize,
+    );
+
+    // The byte offsets at which each line starts, and the one just past
+    // the last.
+    let starts = |lines: &[&[u8]]| {
+        let mut offsets = Vec::with_capacity(lines.len() + 1);
+        let mut offset = 0;
+        offsets.push(offset);
+        for line in lines {
+            offset += line.len();
+            offsets.push(offset);
+        }
+        offsets
+    };
+    let old_starts = starts(&old_lines);
+    let new_starts = starts(&new_lines);
+    let begv_byte = buffer.begv_byte;
+
+    let mut batch = Vec::with_capacity(2 * hunks.len());
+    for hunk in &hunks {
+        let from_byte = begv_byte + old_starts[hunk.old.start] as ptrdiff_t;
+        let to_byte = begv_byte + old_starts[hunk.old.end] as ptrdiff_t;
+        let pos = unsafe { buf_bytepos_to_charpos(buffer.as_mut(), from_byte) };
+        if to_byte > from_byte {
+            let end = unsafe { buf_bytepos_to_charpos(buffer.as_mut(), to_byte) };
+            batch.push(BufferEdit {
+                pos,
+                kind: BufferEditKind::Delete(end - pos),
+            });
+        }
+
+        let text = &new_text[new_starts[hunk.new.start]..new_starts[hunk.new.end]];
+        if !text.is_empty() {
+            let string = unsafe {
+                make_specified_string(
+                    text.as_ptr() as *const libc::c_char,
+                    -1,
+                    text.len() as ptrdiff_t,
+                    multibyte,
+                )
+            };
+            batch.push(BufferEdit {
+                pos,
+                kind: BufferEditKind::Insert(string.as_string_or_error()),
+            });
+        }
+    }
+
+    apply_edits(batch);
+    hunks.len() as EmacsInt
+}
+
 fn time_arith<F>(a: LispObject, b: LispObject, op: F) -> Vec<EmacsInt>
 where
     F: FnOnce(LispTime, LispTime) -> LispTime,
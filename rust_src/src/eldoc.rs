@@ -0,0 +1,187 @@
+//! Composing and displaying ElDoc messages.  eldoc.el decides when
+//! documentation should be shown; fitting it into the echo area and
+//! keeping idle timer runs from redisplaying a message that is already
+//! there happen here.
+
+use std::cell::Cell;
+
+use libc::ptrdiff_t;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::current_buffer,
+    data::set,
+    editfns::{lisp_current_message, point, propertize},
+    eval::SpecBinding,
+    frames::LispFrameOrSelected,
+    lisp::{defsubr, LispObject},
+    multibyte::{Codepoint, LispStringRef},
+    obarray::intern,
+    remacs_sys::{EmacsInt, Fsubstring, Fwindow_body_width},
+    remacs_sys::{Qface, Qnil, Qt},
+    sequences::concat2,
+    symbols::symbol_value,
+    threads::ThreadState,
+    windows::{minibuffer_window, selected_window},
+};
+
+/// What the documentation shown for point depends on.  When none of it
+/// changed since the last message was computed, and that message is
+/// still in the echo area, there is nothing to recompute or redisplay.
+#[derive(Clone, Copy, PartialEq)]
+struct Context {
+    buffer: LispObject,
+    window: LispObject,
+    point: EmacsInt,
+    modiff: EmacsInt,
+    function: LispObject,
+}
+
+impl Context {
+    fn current() -> Self {
+        Self {
+            buffer: current_buffer(),
+            window: selected_window(),
+            point: point(),
+            modiff: ThreadState::current_buffer_unchecked().modifications(),
+            function: symbol_value(intern("eldoc-documentation-function")),
+        }
+    }
+}
+
+thread_local! {
+    /// The context `eldoc-last-message' was computed in, if it is
+    /// still displayed.
+    static LAST_CONTEXT: Cell<Option<Context>> = Cell::new(None);
+}
+
+/// Return the number of characters to drop from the start of STRING so
+/// that it loses at least COLUMNS columns.
+fn chars_to_strip(string: LispStringRef, columns: usize) -> ptrdiff_t {
+    let (nchars, width) = string.width_prefix(columns);
+    if width < columns {
+        nchars + 1
+    } else {
+        nchars
+    }
+}
+
+/// Return the width of PREFIX without the `:' and blanks that separate
+/// it from the documentation.
+fn name_width(prefix: LispStringRef) -> usize {
+    let chars: Vec<_> = prefix.chars().collect();
+    let mut end = chars.len();
+    while end > 0 && chars[end - 1] == ' ' as Codepoint {
+        end -= 1;
+    }
+    if end > 0 && chars[end - 1] == ':' as Codepoint {
+        end -= 1;
+    }
+    prefix.width() - (chars.len() - end)
+}
+
+/// Combine PREFIX and DOC, and shorten the result to fit in the echo area.
+///
+/// When PREFIX is a symbol, propertize its symbol name with FACE before
+/// combining it with DOC.  Widths are measured in columns, so wide
+/// characters are accounted for.  See `eldoc-docstring-format-sym-doc'.
+#[lisp_fn(min = "2", name = "eldoc--format-sym-doc")]
+pub fn eldoc_format_sym_doc(
+    prefix: LispObject,
+    doc: LispStringRef,
+    face: LispObject,
+) -> LispObject {
+    let prefix = match prefix.as_symbol() {
+        Some(sym) => concat2(
+            propertize(&[sym.symbol_name(), Qface, face]),
+            LispObject::from(": "),
+        ),
+        None => prefix,
+    };
+    let prefix_string = prefix.force_string();
+    let ea_multi = symbol_value(intern("eldoc-echo-area-use-multiline-p"));
+
+    // Subtract 1 from window width since emacs will not write any chars
+    // to the last column, or in later versions, will cause a wraparound
+    // and resize of the echo area.
+    let window = minibuffer_window(LispFrameOrSelected::Selected);
+    let ea_width = unsafe { Fwindow_body_width(window, Qnil) }.as_fixnum_or_error() - 1;
+    let ea_width = ea_width.max(0) as usize;
+    let doc_width = doc.width();
+    let width = prefix_string.width() + doc_width;
+
+    if width <= ea_width || ea_multi.eq(Qt) || (ea_multi.is_not_nil() && doc_width > ea_width) {
+        concat2(prefix, doc.into())
+    } else if doc_width > ea_width {
+        let (end, _) = doc.width_prefix(ea_width);
+        unsafe { Fsubstring(doc.into(), LispObject::from(0), LispObject::from(end)) }
+    } else if width - ea_width >= name_width(prefix_string) {
+        doc.into()
+    } else {
+        // Show the end of the partial symbol name, rather than the
+        // beginning, since the former is more likely to be unique given
+        // package namespace conventions.
+        let start = chars_to_strip(prefix_string, width - ea_width);
+        concat2(
+            unsafe { Fsubstring(prefix, LispObject::from(start), Qnil) },
+            doc.into(),
+        )
+    }
+}
+
+/// Display STRING as an ElDoc message if it's non-nil.
+/// Otherwise clear the message shown by ElDoc, if any.  Either way,
+/// store STRING in `eldoc-last-message' and return it.
+#[lisp_fn(min = "0", name = "eldoc--message")]
+pub fn eldoc_message(string: LispObject) -> LispObject {
+    let last_message = intern("eldoc-last-message");
+    let omessage = symbol_value(last_message);
+    set(last_message, string);
+    if string.is_nil() {
+        LAST_CONTEXT.with(|context| context.set(None));
+    }
+
+    // Do not put eldoc messages in the message log since they are Legion.
+    let _binding = SpecBinding::push(intern("message-log-max").into(), Qnil);
+    let function = symbol_value(intern("eldoc-message-function"));
+    if string.is_not_nil() {
+        call!(function, LispObject::from("%s"), string);
+    } else if omessage.is_not_nil() {
+        call!(function, Qnil);
+    }
+    string
+}
+
+/// Display what `eldoc-documentation-function' returns for point.
+/// When it isn't a good time to display a message, just clear the last
+/// one.  When neither point nor the buffer changed since the message in
+/// the echo area was computed, leave it alone instead of calling the
+/// documentation function and redisplaying the same text.
+#[lisp_fn(name = "eldoc--print-current-symbol-info")]
+pub fn eldoc_print_current_symbol_info() -> LispObject {
+    let last_message = symbol_value(intern("eldoc-last-message"));
+    if call!(intern("eldoc-display-message-p").into()).is_nil() {
+        // Erase the last message if we won't display a new one.
+        if last_message.is_not_nil() {
+            eldoc_message(Qnil);
+        }
+        return Qnil;
+    }
+
+    let context = Context::current();
+    if last_message.is_not_nil()
+        && LAST_CONTEXT.with(Cell::get) == Some(context)
+        && lisp_current_message().equal(last_message)
+    {
+        return last_message;
+    }
+    let doc = call!(context.function);
+    let string = eldoc_message(doc);
+    if string.is_not_nil() {
+        LAST_CONTEXT.with(|last| last.set(Some(context)));
+    }
+    string
+}
+
+include!(concat!(env!("OUT_DIR"), "/eldoc_exports.rs"));
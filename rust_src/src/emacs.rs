@@ -1,24 +1,223 @@
 //! Emacs!
 
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use libc::c_int;
+
 use remacs_macros::lisp_fn;
 
 use crate::{
-    lisp::{defsubr, LispObject},
+    desktop::desktop_flush_write,
+    eval::run_hook,
+    lisp::{defsubr, LispObject, ProcessIter},
+    obarray::intern,
+    process::process_status,
     remacs_sys::globals,
-    remacs_sys::Fcopy_sequence,
+    remacs_sys::{
+        check_glyph_memory, check_message_stack, current_thread, inhibit_sentinels,
+        kill_buffer_processes, kill_emacs_release_pool, kill_emacs_save_clipboards, noninteractive,
+        safe_run_hooks, shut_down_platform, shut_down_terminal, stdin_at_eof, stuff_buffered_input,
+        unlock_all_files, unrequest_sigio, Vrun_hooks,
+    },
+    remacs_sys::{Fdo_auto_save, Fexpand_file_name, Fsignal_process},
+    remacs_sys::{Qkill_emacs_hook, Qnil, Qrun, Qstop, Qt},
+    sequences::copy_sequence,
 };
 
 /// Return the program name that was used to run Emacs.
 /// Any directory names are omitted.
 #[lisp_fn]
 pub fn invocation_name() -> LispObject {
-    unsafe { Fcopy_sequence(globals.Vinvocation_name) }
+    copy_sequence(unsafe { globals.Vinvocation_name })
 }
 
 /// Return the directory name in which the Emacs executable was located.
 #[lisp_fn]
 pub fn invocation_directory() -> LispObject {
-    unsafe { Fcopy_sequence(globals.Vinvocation_directory) }
+    copy_sequence(unsafe { globals.Vinvocation_directory })
+}
+
+/// Exit the Emacs job and kill it.
+/// If ARG is an integer, return ARG as the exit program code.
+/// If ARG is a string, stuff it as keyboard input.
+///
+/// This function is called upon receipt of the signals SIGTERM
+/// or SIGHUP, and upon SIGINT in batch mode.
+///
+/// The value of `kill-emacs-hook', if not void,
+/// is a list of functions (of no args),
+/// all of which are called before Emacs is actually killed.
+#[lisp_fn(min = "0", intspec = "P")]
+pub fn kill_emacs(arg: LispObject) -> ! {
+    unsafe {
+        // Fsignal calls emacs_abort () if it sees that waiting_for_input
+        // is set.
+        (*current_thread).m_waiting_for_input = false;
+        if noninteractive {
+            safe_run_hooks(Qkill_emacs_hook);
+        } else {
+            run_hook(Qkill_emacs_hook);
+        }
+        kill_emacs_save_clipboards();
+    }
+
+    let stuff = if arg.is_string() && !unsafe { stdin_at_eof() } {
+        arg
+    } else {
+        Qnil
+    };
+    shut_down_emacs(0, stuff);
+
+    unsafe { kill_emacs_release_pool() };
+
+    // If we have an auto-save list file, kill it because we are exiting
+    // Emacs deliberately (not crashing).  Do it after shut_down_emacs,
+    // which does an auto-save.
+    let list_file = unsafe { globals.Vauto_save_list_file_name };
+    if list_file.is_string() {
+        let list_file = unsafe { Fexpand_file_name(list_file, Qnil) }.force_string();
+        unsafe { libc::unlink(list_file.const_sdata_ptr()) };
+    }
+
+    let exit_code = match arg.as_fixnum() {
+        Some(code) if code < 0 => code as c_int | c_int::min_value(),
+        Some(code) => code as c_int & c_int::max_value(),
+        None => libc::EXIT_SUCCESS,
+    };
+    unsafe { libc::exit(exit_code) }
+}
+
+/// Return true if the subprocess PROC has not terminated yet.
+fn subprocess_running_p(proc: LispObject) -> bool {
+    let status = process_status(proc);
+    status.eq(Qrun) || status.eq(Qstop)
+}
+
+/// Hang up all subprocesses.  If GRACE is given, wait at most that long
+/// for them to exit, and then kill the ones that are left.
+struct SubprocessShutdown {
+    deadline: Option<Instant>,
+}
+
+impl SubprocessShutdown {
+    fn start(grace: Option<Duration>) -> Self {
+        unsafe { kill_buffer_processes(Qnil) };
+        Self {
+            deadline: grace.map(|grace| Instant::now() + grace),
+        }
+    }
+
+    fn finish(self) {
+        let deadline = match self.deadline {
+            Some(deadline) => deadline,
+            None => return,
+        };
+
+        // Processes are reaped by the SIGCHLD handler, which updates
+        // their status while we sleep.
+        let running = || {
+            ProcessIter::new()
+                .map(LispObject::from)
+                .filter(|&proc| subprocess_running_p(proc))
+                .collect::<Vec<_>>()
+        };
+        let mut left = running();
+        while !left.is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(10));
+            left = running();
+        }
+
+        let sigkill = LispObject::from(intern("SIGKILL"));
+        for proc in left {
+            unsafe { Fsignal_process(proc, sigkill) };
+        }
+    }
+}
+
+/// Return the grace period given to subprocesses by `kill-emacs'.
+fn process_grace_period() -> Option<Duration> {
+    let seconds = unsafe { globals.Vkill_emacs_process_grace_period }.any_to_float()?;
+    Some(Duration::from_millis((seconds.max(0.0) * 1000.0) as u64))
+}
+
+/// Wait for the background work of Rust subsystems to complete.  This
+/// is done in a fixed order, after buffers are auto-saved and files
+/// unlocked.
+fn flush_subsystems() {
+    desktop_flush_write();
+}
+
+/// Perform an orderly shutdown of Emacs.  Autosave any modified
+/// buffers, kill any child processes, clean up the terminal modes (if
+/// we're in the foreground), and other stuff like that.  Don't perform
+/// any redisplay; this may be called when Emacs is shutting down in
+/// the background, or after its X connection has died.
+///
+/// If SIG is a signal number, print a message for it.
+///
+/// This is called by fatal signal handlers, X protocol error handlers,
+/// and `kill-emacs'.
+#[no_mangle]
+pub extern "C" fn shut_down_emacs(sig: c_int, stuff: LispObject) {
+    unsafe {
+        // Prevent running of hooks from now on.
+        Vrun_hooks = Qnil;
+        // Don't update display from now on.
+        globals.Vinhibit_redisplay = Qt;
+
+        shut_down_terminal(sig);
+        stuff_buffered_input(stuff);
+        inhibit_sentinels = true;
+    }
+
+    // Only wait for subprocesses when exiting deliberately; after a
+    // fatal signal, Emacs should go away as quickly as possible.
+    let grace = if sig == 0 {
+        process_grace_period()
+    } else {
+        None
+    };
+    let subprocesses = SubprocessShutdown::start(grace);
+    unsafe {
+        Fdo_auto_save(Qt, Qnil);
+        unlock_all_files();
+    }
+    subprocesses.finish();
+
+    // After a fatal signal, don't wait for background work that may
+    // never finish.
+    if sig == 0 {
+        flush_subsystems();
+    }
+
+    // There is a tendency for a SIGIO signal to arrive within exit,
+    // and cause a SIGHUP because the input descriptor is already closed.
+    unsafe { unrequest_sigio() };
+
+    // Do this only if terminating normally, we want glyph matrices
+    // etc. in a core dump.
+    if sig == 0 || sig == libc::SIGTERM {
+        unsafe {
+            check_glyph_memory();
+            check_message_stack();
+        }
+    }
+
+    unsafe { shut_down_platform(sig) };
+}
+
+#[no_mangle]
+pub extern "C" fn rust_syms_of_emacs() {
+    /// How long `kill-emacs' waits for subprocesses to exit, in seconds.
+    /// When Emacs exits, it sends SIGHUP to its subprocesses.  If this is a
+    /// number, Emacs waits at most that many seconds for them to exit, and
+    /// then kills the remaining ones with SIGKILL.  If nil, Emacs exits
+    /// without waiting, leaving subprocesses that ignore SIGHUP running.
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    defvar_lisp!(Vkill_emacs_process_grace_period, "kill-emacs-process-grace-period", Qnil);
 }
 
 include!(concat!(env!("OUT_DIR"), "/emacs_exports.rs"));
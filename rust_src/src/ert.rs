@@ -0,0 +1,376 @@
+//! Running ert test files in parallel batch processes.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    editfns::message,
+    eval::record_unwind,
+    lisp::{defsubr, LispObject},
+    lists::{LispConsCircularChecks, LispConsEndChecks},
+    remacs_sys::Qnil,
+    remacs_sys::{globals, maybe_quit, EmacsUint, Fexpand_file_name},
+};
+
+/// Suffix identifying files that contain ert tests.
+const TEST_FILE_SUFFIX: &str = "-tests.el";
+
+/// How often, in milliseconds, the processes are checked on, and the
+/// user is given a chance to quit.
+const POLL_INTERVAL_MS: u64 = 100;
+
+/// The outcome of running a single test file.
+struct FileResult {
+    /// Number of tests run, as reported by ert.
+    total: u32,
+    /// Number of tests whose result was not as expected.
+    unexpected: u32,
+    /// False if the worker process could not be run or died without
+    /// reporting a result.
+    completed: bool,
+    elapsed: f64,
+    output: String,
+}
+
+impl FileResult {
+    fn failed(elapsed: f64, output: String) -> Self {
+        Self {
+            total: 0,
+            unexpected: 0,
+            completed: false,
+            elapsed,
+            output,
+        }
+    }
+
+    fn is_success(&self) -> bool {
+        self.completed && self.unexpected == 0
+    }
+
+    /// Parse the summary that `ert-run-tests-batch' prints, such as
+    /// "Ran 12 tests, 11 results as expected, 1 unexpected (...)".
+    fn parse(output: String, elapsed: f64) -> Self {
+        let summary = output
+            .lines()
+            .rev()
+            .find(|line| line.starts_with("Ran ") && line.contains(" tests,"));
+
+        let summary = match summary {
+            Some(line) => line.to_owned(),
+            None => return Self::failed(elapsed, output),
+        };
+
+        let number_before = |word: &str| -> u32 {
+            summary
+                .find(word)
+                .and_then(|end| {
+                    summary[..end]
+                        .trim_end()
+                        .rsplit(|c: char| !c.is_ascii_digit())
+                        .next()
+                        .and_then(|digits| digits.parse().ok())
+                })
+                .unwrap_or(0)
+        };
+
+        Self {
+            total: number_before(" test"),
+            unexpected: number_before(" unexpected"),
+            completed: true,
+            elapsed,
+            output,
+        }
+    }
+}
+
+/// Collect the test files named by PATH. Directories are searched
+/// recursively for files ending in `TEST_FILE_SUFFIX'.
+fn discover_test_files(path: &Path, found: &mut Vec<PathBuf>) {
+    if !path.is_dir() {
+        found.push(path.to_owned());
+        return;
+    }
+
+    let mut entries: Vec<PathBuf> = match fs::read_dir(path) {
+        Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+        Err(_) => return,
+    };
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            discover_test_files(&entry, found);
+        } else if entry
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| name.ends_with(TEST_FILE_SUFFIX))
+        {
+            found.push(entry);
+        }
+    }
+}
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) * 1e-9
+}
+
+/// Read all of PIPE on another thread, so that the process writing to
+/// it never blocks on a full pipe.
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+/// Run FILE in a fresh batch Emacs, the same way `make check` does.
+/// The process is killed if CANCELLED becomes true before it is done.
+fn run_test_file(emacs: &Path, file: &Path, cancelled: &AtomicBool) -> FileResult {
+    let start = Instant::now();
+    let directory = file.parent().unwrap_or_else(|| Path::new("."));
+
+    let child = Command::new(emacs)
+        .arg("-Q")
+        .arg("--batch")
+        .arg("-L")
+        .arg(directory)
+        .arg("-l")
+        .arg("ert")
+        .arg("-l")
+        .arg(file)
+        .arg("-f")
+        .arg("ert-run-tests-batch-and-exit")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => return FileResult::failed(seconds(start.elapsed()), e.to_string()),
+    };
+
+    // ert reports through `message', which goes to stderr in batch mode.
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let status = loop {
+        if cancelled.load(Ordering::SeqCst) {
+            let _ = child.kill();
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break Ok(status),
+            Ok(None) => thread::sleep(Duration::from_millis(POLL_INTERVAL_MS)),
+            Err(e) => break Err(e),
+        }
+    };
+    let elapsed = seconds(start.elapsed());
+
+    let mut text = stdout.join().unwrap_or_default();
+    text.push_str(&stderr.join().unwrap_or_default());
+    match status {
+        Ok(_) => FileResult::parse(text, elapsed),
+        Err(e) => FileResult::failed(elapsed, e.to_string()),
+    }
+}
+
+/// Run FILES on JOBS worker threads, each of which drives one batch
+/// process at a time. The results are in the same order as FILES.
+///
+/// While the workers run, the user can quit; the workers then kill
+/// their processes and stop.
+fn run_in_parallel(emacs: PathBuf, files: Vec<PathBuf>, jobs: usize) -> Vec<FileResult> {
+    let count = files.len();
+    let queue = Arc::new(Mutex::new(
+        files.into_iter().enumerate().collect::<Vec<_>>(),
+    ));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let emacs = Arc::new(emacs);
+    let (sender, receiver) = mpsc::channel();
+
+    for _ in 0..jobs {
+        let queue = Arc::clone(&queue);
+        let cancelled = Arc::clone(&cancelled);
+        let emacs = Arc::clone(&emacs);
+        let sender = sender.clone();
+        thread::spawn(move || {
+            while !cancelled.load(Ordering::SeqCst) {
+                let next = queue.lock().unwrap().pop();
+                match next {
+                    Some((index, file)) => {
+                        let result = run_test_file(&emacs, &file, &cancelled);
+                        if sender.send((index, result)).is_err() {
+                            break;
+                        }
+                    }
+                    None => break,
+                }
+            }
+        });
+    }
+    // The results are complete once every worker is done with its
+    // sender.
+    drop(sender);
+
+    let stop_workers = {
+        let cancelled = Arc::clone(&cancelled);
+        record_unwind(move || cancelled.store(true, Ordering::SeqCst))
+    };
+    let mut results: Vec<Option<FileResult>> = (0..count).map(|_| None).collect();
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(POLL_INTERVAL_MS)) {
+            Ok((index, result)) => results[index] = Some(result),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        unsafe { maybe_quit() };
+    }
+    drop(stop_workers);
+
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or_else(|| FileResult::failed(0.0, "not run".to_owned())))
+        .collect()
+}
+
+/// The Emacs binary that is currently running.
+fn current_emacs() -> PathBuf {
+    let (directory, name) = unsafe { (globals.Vinvocation_directory, globals.Vinvocation_name) };
+    match (directory.as_string(), name.as_string()) {
+        (Some(directory), Some(name)) => Path::new(&directory.to_string()).join(name.to_string()),
+        _ => error!("Cannot determine the Emacs executable to run tests with"),
+    }
+}
+
+/// The number of processors online, at least 1.
+fn cpu_count() -> usize {
+    let count = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_ONLN) };
+    count.max(1) as usize
+}
+
+/// Run the ert tests in FILES in parallel, each file in its own batch Emacs.
+/// FILES is a list of test file names and directories.  Directories are
+/// searched recursively for files whose names end in `-tests.el'.
+///
+/// Optional argument JOBS is the maximum number of worker processes to
+/// run at the same time; it defaults to the number of processors.  No
+/// more processes are run than there are test files.
+///
+/// Each file is loaded into a fresh `emacs -Q --batch' with its own
+/// directory on `load-path', and run with `ert-run-tests-batch-and-exit'.
+/// A summary line is printed for each file together with totals.
+///
+/// The value is a list with one element per test file, of the form
+/// (FILE TESTS UNEXPECTED SECONDS OUTPUT), where TESTS is the number of
+/// tests that were run, UNEXPECTED the number whose result was not as
+/// expected, SECONDS the wall clock time the file took, and OUTPUT the
+/// text the worker printed.  TESTS is nil if the worker did not finish.
+#[lisp_fn(min = "1")]
+pub fn ert_run_tests_parallel(files: LispObject, jobs: Option<EmacsUint>) -> LispObject {
+    let mut paths = Vec::new();
+    for file in files.iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on) {
+        let expanded = unsafe { Fexpand_file_name(file.as_string_or_error().into(), Qnil) };
+        let expanded = expanded.as_string_or_error().to_string();
+        discover_test_files(Path::new(&expanded), &mut paths);
+    }
+
+    if paths.is_empty() {
+        return Qnil;
+    }
+
+    let jobs = jobs
+        .map_or_else(cpu_count, |n| n.max(1) as usize)
+        .min(paths.len());
+    let start = Instant::now();
+    let results = run_in_parallel(current_emacs(), paths.clone(), jobs);
+    let elapsed = start.elapsed();
+
+    let mut total = 0;
+    let mut unexpected = 0;
+    let mut failed_files = 0;
+
+    for (path, result) in paths.iter().zip(results.iter()) {
+        let status = if result.is_success() {
+            "passed"
+        } else {
+            "FAILED"
+        };
+        message(&mut [
+            "%-6s %s (%d tests, %d unexpected, %.2fs)".into(),
+            status.into(),
+            (&*path.to_string_lossy()).into(),
+            result.total.into(),
+            result.unexpected.into(),
+            LispObject::from_float(result.elapsed),
+        ]);
+
+        total += result.total;
+        unexpected += result.unexpected;
+        if !result.is_success() {
+            failed_files += 1;
+        }
+    }
+
+    message(&mut [
+        "Ran %d tests in %d files, %d unexpected, %d files failed (%.2fs on %d jobs)".into(),
+        total.into(),
+        paths.len().into(),
+        unexpected.into(),
+        failed_files.into(),
+        LispObject::from_float(seconds(elapsed)),
+        jobs.into(),
+    ]);
+
+    paths
+        .iter()
+        .zip(results.iter())
+        .rev()
+        .fold(Qnil, |value, (path, result)| {
+            let tests = if result.completed {
+                result.total.into()
+            } else {
+                Qnil
+            };
+            let entry = list!(
+                (&*path.to_string_lossy()).into(),
+                tests,
+                result.unexpected.into(),
+                LispObject::from_float(result.elapsed),
+                result.output.as_str().into()
+            );
+            LispObject::cons(entry, value)
+        })
+}
+
+include!(concat!(env!("OUT_DIR"), "/ert_exports.rs"));
+
+#[test]
+fn test_parse_summary() {
+    let output = "Running 3 tests (2018-11-03)\n   passed  1/3  a\n\n\
+                  Ran 3 tests, 2 results as expected, 1 unexpected (2018-11-03)\n"
+        .to_owned();
+    let result = FileResult::parse(output, 1.5);
+    assert!(result.completed);
+    assert_eq!(result.total, 3);
+    assert_eq!(result.unexpected, 1);
+    assert!(!result.is_success());
+
+    let result = FileResult::parse("Ran 1 tests, 1 results as expected (x)\n".to_owned(), 0.0);
+    assert_eq!((result.total, result.unexpected), (1, 0));
+    assert!(result.is_success());
+
+    let result = FileResult::parse("Cannot open load file\n".to_owned(), 0.0);
+    assert!(!result.completed);
+    assert!(!result.is_success());
+}
@@ -1,37 +1,54 @@
 //! Generic Lisp eval functions
 
+use std::cell::Cell;
 use std::ptr;
+use std::slice;
 
 use remacs_macros::lisp_fn;
 
 use crate::{
-    data::{defalias, fset, indirect_function, indirect_function_lisp, set, set_default},
+    bytecode::rust_exec_byte_code,
+    data::subr_arity,
+    data::{aref, defalias, fset, indirect_function, indirect_function_lisp, set, set_default},
+    gc_stats::maybe_gc,
     lisp::{defsubr, is_autoload},
-    lisp::{LispObject, LispSubrRef},
-    lists::{assq, car, cdr, get, memq, nth, put, Fcar, Fcdr},
+    lisp::{LispObject, LispSubrRef, MANY},
+    lists::{assq, car, cdr, get, list, memq, nth, put, Fcar, Fcdr},
     lists::{LispCons, LispConsCircularChecks, LispConsEndChecks},
     multibyte::LispStringRef,
-    obarray::loadhist_attach,
+    obarray::{intern, loadhist_attach},
     objects::equal,
     remacs_sys::{
-        backtrace_debug_on_exit, build_string, call_debugger, check_cons_list, do_debug_on_call,
-        do_one_unbind, eval_sub, find_symbol_value, funcall_lambda, funcall_subr, globals,
-        internal_catch, list2, maybe_gc, maybe_quit, record_in_backtrace, record_unwind_protect,
-        record_unwind_save_match_data, specbind, COMPILEDP, MODULE_FUNCTIONP,
+        backtrace_debug_on_exit, call_debugger, check_cons_list, do_debug_on_call, do_one_unbind,
+        find_symbol_value, globals, internal_catch, internal_condition_case_n, list2, maybe_quit,
+        record_in_backtrace, record_unwind_protect, record_unwind_protect_ptr,
+        record_unwind_save_match_data, set_backtrace_args, specbind, COMPILEDP, MODULE_FUNCTIONP,
     },
-    remacs_sys::{pvec_type, EmacsInt, Lisp_Compiled, Set_Internal_Bind},
-    remacs_sys::{Fapply, Fdefault_value, Fload, Fpurecopy},
     remacs_sys::{
-        QCdocumentation, Qautoload, Qclosure, Qerror, Qexit, Qfunction, Qinteractive,
-        Qinteractive_form, Qinternal_interpreter_environment, Qinvalid_function, Qlambda, Qmacro,
-        Qnil, Qrisky_local_variable, Qsetq, Qt, Qunbound, Qvariable_documentation, Qvoid_function,
+        backtrace_function, backtrace_next, backtrace_p, backtrace_top, default_toplevel_binding,
+        emacs_abort, fatal, gc_in_progress, internal_lisp_condition_case, maybe_call_debugger,
+        set_specpdl_old_value, specpdl_old_value, unwind_to_catch, waiting_for_input,
+    },
+    remacs_sys::{get_byte_code_arity, Ferror_message_string, Set_Internal_Bind},
+    remacs_sys::{handler, handlertype, pvec_type, EmacsInt, Lisp_Compiled, Lisp_Subr},
+    remacs_sys::{Fdefault_value, Ffetch_bytecode, Fload, Fmake_vector, Fpurecopy, Fsignal},
+    remacs_sys::{
+        QCdocumentation, Qand_optional, Qand_rest, Qautoload, Qclosure, Qdebug, Qerror,
+        Qerror_conditions, Qexit, Qfunction, Qinteractive, Qinteractive_form,
+        Qinternal_interpreter_environment, Qinvalid_function, Qlambda, Qlexical_binding, Qlistp,
+        Qmacro, Qmany, Qnil, Qno_catch, Qquit, Qrisky_local_variable, Qsetq, Qt, Qtop_level,
+        Qunbound, Qvariable_documentation, Qvoid_function,
     },
     remacs_sys::{Vautoload_queue, Vrun_hooks},
-    symbols::{fboundp, symbol_function, LispSymbolRef},
+    string_alloc::make_string,
+    symbols::{fboundp, symbol_function, symbol_value, LispSymbolRef},
     threads::{c_specpdl_index, ThreadState},
     vectors::length,
 };
 
+#[cfg(feature = "modules")]
+use crate::remacs_sys::{funcall_module, Lisp_Module_Function};
+
 /* * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * * *
  *   NOTE!!! Every function that can call EVAL must protect its args   *
  *   and temporaries from garbage collection while it needs them.      *
@@ -64,7 +81,7 @@ where
     let mut val = initial;
 
     for elt in args.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
-        val = unsafe { eval_sub(elt) };
+        val = eval_sub(elt);
         if cmp(val, Qnil) {
             break;
         }
@@ -82,10 +99,10 @@ where
 pub fn lisp_if(args: LispCons) -> LispObject {
     let (cond, consq) = args.into();
     let (then, else_) = consq.into();
-    let result = unsafe { eval_sub(cond) };
+    let result = eval_sub(cond);
 
     if result.is_not_nil() {
-        unsafe { eval_sub(then) }
+        eval_sub(then)
     } else {
         progn(else_)
     }
@@ -106,7 +123,7 @@ pub fn cond(args: LispObject) -> LispObject {
 
     for clause in args.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
         let (head, tail) = clause.into();
-        val = unsafe { eval_sub(head) };
+        val = eval_sub(head);
         if val != Qnil {
             if tail.is_not_nil() {
                 val = progn(tail);
@@ -123,7 +140,7 @@ pub fn cond(args: LispObject) -> LispObject {
 #[lisp_fn(min = "0", unevalled = "true")]
 pub fn progn(body: LispObject) -> LispObject {
     body.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
-        .map(|form| unsafe { eval_sub(form) })
+        .map(eval_sub)
         .last()
         .into()
 }
@@ -142,7 +159,7 @@ pub extern "C" fn prog_ignore(body: LispObject) {
 pub fn prog1(args: LispCons) -> LispObject {
     let (first, body) = args.into();
 
-    let val = unsafe { eval_sub(first) };
+    let val = eval_sub(first);
     progn(body);
     val
 }
@@ -155,7 +172,7 @@ pub fn prog1(args: LispCons) -> LispObject {
 pub fn prog2(args: LispCons) -> LispObject {
     let (form1, tail) = args.into();
 
-    unsafe { eval_sub(form1) };
+    eval_sub(form1);
     prog1(tail.into())
 }
 
@@ -179,7 +196,7 @@ pub fn setq(args: LispObject) -> LispObject {
             wrong_number_of_arguments!(Qsetq, nargs + 1);
         });
 
-        val = unsafe { eval_sub(arg) };
+        val = eval_sub(arg);
 
         let mut lexical = false;
 
@@ -231,7 +248,7 @@ pub fn function(args: LispCons) -> LispObject {
                         // Handle the special (:documentation <form>) to build the docstring
                         // dynamically.
 
-                        let docstring = unsafe { eval_sub(car(tail)) };
+                        let docstring = eval_sub(car(tail));
                         docstring.as_string_or_error();
                         let (a, b) = cdr.into();
                         let (_, bd) = b.into();
@@ -267,6 +284,112 @@ pub fn special_variable_p(symbol: LispSymbolRef) -> bool {
     symbol.get_declared_special()
 }
 
+/// Define SYMBOL as a variable, and return SYMBOL.
+/// You are not required to define a variable in order to use it, but
+/// defining it lets you supply an initial value and documentation, which
+/// can be referred to by the Emacs help facilities and other programming
+/// tools.  The `defvar' form also declares the variable as \"special\",
+/// so that it is always dynamically bound even if `lexical-binding' is t.
+///
+/// If SYMBOL's value is void and the optional argument INITVALUE is
+/// provided, INITVALUE is evaluated and the result used to set SYMBOL's
+/// value.  If SYMBOL is buffer-local, its default value is what is set;
+/// buffer-local values are not affected.  If INITVALUE is missing,
+/// SYMBOL's value is not set.
+///
+/// If SYMBOL has a local binding, then this form affects the local
+/// binding.  This is usually not what you want.  Thus, if you need to
+/// load a file defining variables, with this form or with `defconst' or
+/// `defcustom', you should always load that file _outside_ any bindings
+/// for these variables.  (`defconst' and `defcustom' behave similarly in
+/// this respect.)
+///
+/// The optional argument DOCSTRING is a documentation string for the
+/// variable.
+///
+/// To define a user option, use `defcustom' instead of `defvar'.
+/// usage: (defvar SYMBOL &optional INITVALUE DOCSTRING)
+#[lisp_fn(min = "1", unevalled = "true")]
+pub fn defvar(args: LispCons) -> LispSymbolRef {
+    let (sym, tail) = args.into();
+    let sym_ref = sym.as_symbol_or_error();
+
+    if let Some((initvalue, tail)) = tail.into() {
+        if tail.is_not_nil() && cdr(tail).is_not_nil() {
+            error!("Too many arguments");
+        }
+
+        let bound = sym_ref.default_value().is_some();
+
+        // Do it before evaluating the initial value, for self-references.
+        sym_ref.set_declared_special(true);
+
+        if !bound {
+            set_default(sym_ref, eval_sub(initvalue));
+        } else {
+            // Check if there is really a global binding rather than just a
+            // let binding that shadows the global unboundness of the var.
+            let binding = unsafe { default_toplevel_binding(sym) };
+            if !binding.is_null() && unsafe { specpdl_old_value(binding) }.eq(Qunbound) {
+                let value = eval_sub(initvalue);
+                // Evaluating INITVALUE may have moved the specpdl stack.
+                let binding = unsafe { default_toplevel_binding(sym) };
+                unsafe { set_specpdl_old_value(binding, value) };
+            }
+        }
+
+        let mut docstring = car(tail);
+        if docstring.is_not_nil() {
+            if unsafe { globals.Vpurify_flag }.is_not_nil() {
+                docstring = unsafe { Fpurecopy(docstring) };
+            }
+            put(sym_ref, Qvariable_documentation, docstring);
+        }
+        loadhist_attach(sym);
+    } else if unsafe { globals.Vinternal_interpreter_environment }.is_not_nil()
+        && !sym_ref.get_declared_special()
+    {
+        // A simple (defvar foo) with lexical scoping does "nothing" except
+        // declare that var to be dynamically scoped *locally* (i.e. within
+        // the current file or let-block).
+        unsafe {
+            globals.Vinternal_interpreter_environment =
+                LispObject::cons(sym, globals.Vinternal_interpreter_environment);
+        }
+    } else {
+        // Simple (defvar <var>) should not count as a definition at all.
+        // It could get in the way of other definitions, and unloading this
+        // package could try to make the variable unbound.
+    }
+
+    sym_ref
+}
+
+/// Return SYMBOL's toplevel default value.
+/// "Toplevel" means outside of any let binding.
+#[lisp_fn]
+pub fn default_toplevel_value(symbol: LispSymbolRef) -> LispObject {
+    let binding = unsafe { default_toplevel_binding(symbol.into()) };
+    let value = if binding.is_null() {
+        symbol.default_value()
+    } else {
+        Some(unsafe { specpdl_old_value(binding) }).filter(|value| !value.eq(Qunbound))
+    };
+    value.unwrap_or_else(|| void_variable!(symbol))
+}
+
+/// Set SYMBOL's toplevel default value to VALUE.
+/// "Toplevel" means outside of any let binding.
+#[lisp_fn]
+pub fn set_default_toplevel_value(symbol: LispSymbolRef, value: LispObject) {
+    let binding = unsafe { default_toplevel_binding(symbol.into()) };
+    if binding.is_null() {
+        symbol.set_default_value(value);
+    } else {
+        unsafe { set_specpdl_old_value(binding, value) };
+    }
+}
+
 /// Define SYMBOL as a constant variable.
 /// This declares that neither programs nor users should ever change the
 /// value.  This constancy is not actually enforced by Emacs Lisp, but
@@ -296,7 +419,7 @@ pub fn defconst(args: LispCons) -> LispSymbolRef {
         Qnil
     };
 
-    let mut tem = unsafe { eval_sub(car(tail)) };
+    let mut tem = eval_sub(car(tail));
     if unsafe { globals.Vpurify_flag } != Qnil {
         tem = unsafe { Fpurecopy(tem) };
     }
@@ -335,7 +458,7 @@ fn let_binding_value(obj: LispObject) -> (LispObject, LispObject) {
         };
 
         if tail.is_nil() {
-            (front, unsafe { eval_sub(to_eval) })
+            (front, eval_sub(to_eval))
         } else {
             signal_error("`let' bindings can have only one value-form", obj);
         }
@@ -466,13 +589,17 @@ pub fn lisp_let(args: LispCons) -> LispObject {
 pub fn lisp_while(args: LispCons) {
     let (test, body) = args.into();
 
-    while unsafe { eval_sub(test) } != Qnil {
+    while eval_sub(test) != Qnil {
         unsafe { maybe_quit() };
 
         prog_ignore(body);
     }
 }
 
+/// How many times `macroexpand' expands a form before deciding that
+/// the expansion never ends.
+const MAX_MACRO_EXPANSIONS: usize = 10_000;
+
 /// Return result of expanding macros at top level of FORM.
 /// If FORM is not a macro call, it is returned unchanged.
 /// Otherwise, the macro is expanded and the expansion is considered
@@ -480,8 +607,23 @@ pub fn lisp_while(args: LispCons) {
 ///
 /// The second optional arg ENVIRONMENT specifies an environment of macro
 /// definitions to shadow the loaded ones for use in file byte-compilation.
+///
+/// An error is signaled if the expansion comes back to a form it
+/// already went through, or goes on for ten thousand steps,
+/// since expanding it further would never end.
 #[lisp_fn(min = "1")]
 pub fn macroexpand(mut form: LispObject, environment: LispObject) -> LispObject {
+    // To catch cycles, each expansion is compared with one that came
+    // before it, which moves forward whenever the number of expansions
+    // reaches a power of two.  This finds any cycle through the same
+    // forms while remembering just one of them.  The first expansion
+    // isn't checked: a macro that expands into a constant form that
+    // expands into itself stops there.  Forms are compared with `eq',
+    // as comparing them with `equal' would walk each of them, so
+    // cycles through fresh forms are caught by the step bound instead.
+    let mut checkpoint = Qnil;
+    let mut expansions: usize = 0;
+
     while let Some((mut sym, body)) = form.into() {
         // Come back here each time we expand a macro call,
         // in case it expands into another macro call.
@@ -534,14 +676,63 @@ pub fn macroexpand(mut form: LispObject, environment: LispObject) -> LispObject
         let newform = apply1(expander, body);
         if form.eq(newform) {
             break;
-        } else {
-            form = newform;
+        }
+        if (expansions > 0 && newform.eq(checkpoint)) || expansions >= MAX_MACRO_EXPANSIONS {
+            xsignal!(Qerror, LispObject::from("Macro expansion cycle"), newform);
+        }
+        form = newform;
+        expansions += 1;
+        if expansions.is_power_of_two() {
+            checkpoint = form;
         }
     }
 
     form
 }
 
+/// Perform (at most) one step of macroexpansion.
+/// If FORM is a macro call, return its expansion, without expanding
+/// any macro call that results.  Otherwise return FORM unchanged.
+///
+/// ENVIRONMENT is as for `macroexpand'.  A macro defined by autoload
+/// is loaded first.  If the head of FORM is an alias for a macro, the
+/// value is FORM with that macro in place of the alias.
+#[lisp_fn(name = "macroexpand-1", c_name = "macroexpand_1", min = "1")]
+pub fn macroexpand_1(form: LispObject, environment: LispObject) -> LispObject {
+    let (head, args) = match form.into() {
+        Some(cons) => cons,
+        None => return form,
+    };
+
+    let env_expander = assq(head, environment);
+    if let Some((_, expander)) = env_expander.into() {
+        return if expander.is_nil() {
+            form
+        } else {
+            apply1(expander, args)
+        };
+    }
+
+    let sym = match head.as_symbol() {
+        Some(sym) if fboundp(sym) => sym,
+        _ => return form,
+    };
+    let def = autoload_do_load(sym.get_function(), head, Qmacro);
+    if def.is_symbol() {
+        // Follow alias, but only for macros, otherwise we may end up
+        // skipping an important compiler-macro (e.g. cl--block-wrapper).
+        return if call!(intern("macrop").into(), def).is_not_nil() {
+            LispObject::cons(def, args)
+        } else {
+            form
+        };
+    }
+    match def.into() {
+        Some((kind, expander)) if kind.eq(Qmacro) => apply1(expander, args),
+        _ => form,
+    }
+}
+
 /// Evaluate FORM and return its value.
 /// If LEXICAL is t, evaluate using lexical scoping.
 /// LEXICAL can also be an actual lexical environment, in the form of an
@@ -559,7 +750,236 @@ pub fn eval(form: LispObject, lexical: LispObject) -> LispObject {
         specbind(Qinternal_interpreter_environment, value);
     }
 
-    unbind_to(count, unsafe { eval_sub(form) })
+    unbind_to(count, eval_sub(form))
+}
+
+/// Count one more level of Lisp evaluation, signaling an error if that
+/// goes beyond `max-lisp-eval-depth'.
+fn enter_lisp_eval_depth() {
+    let mut current_thread = ThreadState::current_thread();
+    current_thread.m_lisp_eval_depth += 1;
+
+    unsafe {
+        if current_thread.m_lisp_eval_depth > globals.max_lisp_eval_depth {
+            if globals.max_lisp_eval_depth < 100 {
+                globals.max_lisp_eval_depth = 100;
+            }
+
+            if current_thread.m_lisp_eval_depth > globals.max_lisp_eval_depth {
+                error!("Lisp nesting exceeds `max-lisp-eval-depth'");
+            }
+        }
+    }
+}
+
+/// Leave the backtrace frame recorded at COUNT for a call that returned
+/// VAL, giving the debugger its chance if the frame asked for
+/// debug-on-exit.  The arguments recorded in the frame must still be
+/// live.  Return the (possibly debugger-supplied) value of the call.
+fn exit_backtrace_frame(count: libc::ptrdiff_t, mut val: LispObject) -> LispObject {
+    unsafe { check_cons_list() };
+
+    let mut current_thread = ThreadState::current_thread();
+    current_thread.m_lisp_eval_depth -= 1;
+
+    unsafe {
+        if backtrace_debug_on_exit(current_thread.m_specpdl.offset(count)) {
+            val = call_debugger(list2(Qexit, val));
+        }
+
+        current_thread.m_specpdl_ptr = current_thread.m_specpdl_ptr.offset(-1);
+    }
+
+    val
+}
+
+/// Point the backtrace frame recorded at COUNT at the evaluated ARGS.
+fn set_backtrace_frame_args(count: libc::ptrdiff_t, args: &mut [LispObject]) {
+    let current_thread = ThreadState::current_thread();
+    unsafe {
+        set_backtrace_args(
+            current_thread.m_specpdl.offset(count),
+            args.as_mut_ptr(),
+            args.len() as libc::ptrdiff_t,
+        )
+    };
+}
+
+/// The most arguments kept on the stack by `Arguments'.
+const ARGUMENTS_ON_STACK: usize = 8;
+
+/// Room for the arguments of a function call, where the garbage
+/// collector can see them: on the stack when there are few enough, in a
+/// Lisp vector otherwise.  This replaces SAFE_ALLOCA_LISP.
+enum Arguments {
+    Stack([LispObject; ARGUMENTS_ON_STACK], usize),
+    Vector(LispObject),
+}
+
+impl Arguments {
+    fn new(len: usize) -> Self {
+        if len <= ARGUMENTS_ON_STACK {
+            Arguments::Stack([Qnil; ARGUMENTS_ON_STACK], len)
+        } else {
+            Arguments::Vector(unsafe { Fmake_vector((len as EmacsInt).into(), Qnil) })
+        }
+    }
+
+    /// Evaluate the forms in ARGS into room for LEN arguments; any
+    /// arguments past the end of ARGS are nil.
+    fn evaluate(args: LispObject, len: usize) -> Self {
+        let mut arguments = Self::new(len);
+        for (slot, form) in arguments
+            .as_mut_slice()
+            .iter_mut()
+            .zip(args.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off))
+        {
+            *slot = eval_sub(form);
+        }
+        arguments
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [LispObject] {
+        match self {
+            Arguments::Stack(args, len) => &mut args[..*len],
+            Arguments::Vector(vector) => {
+                let mut vector = vector.as_vector().unwrap();
+                let args = vector.as_mut_slice();
+                unsafe { slice::from_raw_parts_mut(args.as_mut_ptr(), args.len()) }
+            }
+        }
+    }
+}
+
+/// Eval a sub-expression of the current expression (i.e. in the same
+/// lexical scope).
+#[no_mangle]
+pub extern "C" fn eval_sub(form: LispObject) -> LispObject {
+    if let Some(symbol) = form.as_symbol() {
+        // Look up its binding in the lexical environment.
+        // We do not pay attention to the declared_special flag here, since we
+        // already did that when let-binding the variable.
+        let environment = unsafe { globals.Vinternal_interpreter_environment };
+        let lex_binding = if environment.is_nil() {
+            // Mere optimization!
+            Qnil
+        } else {
+            assq(form, environment)
+        };
+        return match lex_binding.as_cons() {
+            Some(binding) => binding.cdr(),
+            None => symbol_value(symbol),
+        };
+    }
+
+    let (original_fun, mut original_args) = match form.as_cons() {
+        None => return form,
+        Some(cons) => cons.into(),
+    };
+
+    unsafe {
+        maybe_quit();
+    }
+    maybe_gc();
+
+    enter_lisp_eval_depth();
+
+    if !original_args.is_list() {
+        wrong_type!(Qlistp, original_args);
+    }
+
+    // This also protects them from gc.
+    let count = unsafe { record_in_backtrace(original_fun, &mut original_args, -1) };
+
+    unsafe {
+        if globals.debug_on_next_call {
+            do_debug_on_call(Qt, count);
+        }
+    }
+
+    // The evaluated arguments must outlive the debug-on-exit check in
+    // `exit_backtrace_frame', since the backtrace frame points at them.
+    let mut arguments: Arguments;
+
+    let val = loop {
+        // Optimize for no indirection.
+        let fun = match original_fun.as_symbol() {
+            None => function(list!(original_fun).into()),
+            Some(symbol) => symbol.get_indirect_function(),
+        };
+
+        if let Some(subr) = fun.as_subr() {
+            let numargs = length(original_args);
+
+            unsafe { check_cons_list() };
+
+            if numargs < subr.min_args() as usize
+                || (subr.max_args() >= 0 && (subr.max_args() as usize) < numargs)
+            {
+                wrong_number_of_arguments!(original_fun, numargs as EmacsInt);
+            }
+
+            if subr.is_unevalled() {
+                break unsafe { (subr.function.aUNEVALLED.unwrap())(original_args) };
+            }
+
+            // A fixed number of arguments are passed all of them, the
+            // missing optional ones as nil.
+            let len = if subr.is_many() {
+                numargs
+            } else {
+                subr.max_args() as usize
+            };
+            arguments = Arguments::evaluate(original_args, len);
+            let args = arguments.as_mut_slice();
+            set_backtrace_frame_args(count, &mut args[..numargs]);
+            break call_subr(subr, args);
+        }
+
+        if unsafe { COMPILEDP(fun) || MODULE_FUNCTIONP(fun) } {
+            arguments = Arguments::evaluate(original_args, length(original_args));
+            let args = arguments.as_mut_slice();
+            set_backtrace_frame_args(count, args);
+            break funcall_lambda(fun, args);
+        }
+
+        if fun.is_nil() {
+            xsignal!(Qvoid_function, original_fun);
+        }
+
+        let funcar = match fun.as_cons() {
+            Some(cons) if cons.car().is_symbol() => cons.car(),
+            _ => xsignal!(Qinvalid_function, original_fun),
+        };
+
+        if funcar.eq(Qautoload) {
+            autoload_do_load(fun, original_fun, Qnil);
+            continue;
+        }
+
+        if funcar.eq(Qmacro) {
+            let count1 = c_specpdl_index();
+            // Bind lexical-binding during expansion of the macro, so the
+            // macro can know reliably if the code it outputs will be
+            // interpreted using lexical-binding or not.
+            let lexical = unsafe { globals.Vinternal_interpreter_environment }.is_not_nil();
+            unsafe { specbind(Qlexical_binding, lexical.into()) };
+            let exp = apply1(cdr(fun), original_args);
+            unbind_to(count1, Qnil);
+            break eval_sub(exp);
+        }
+
+        if funcar.eq(Qlambda) || funcar.eq(Qclosure) {
+            arguments = Arguments::evaluate(original_args, length(original_args));
+            let args = arguments.as_mut_slice();
+            set_backtrace_frame_args(count, args);
+            break funcall_lambda(fun, args);
+        }
+
+        xsignal!(Qinvalid_function, original_fun);
+    };
+
+    exit_backtrace_frame(count, val)
 }
 
 /// Apply fn to arg.
@@ -568,20 +988,88 @@ pub extern "C" fn apply1(func: LispObject, arg: LispObject) -> LispObject {
     if arg == Qnil {
         call!(func)
     } else {
-        callN_raw!(Fapply, func, arg)
+        apply(&mut [func, arg])
     }
 }
 
+/// Call FUNCTION with our remaining args, using our last arg as list of args.
+/// Then return the value FUNCTION returns.
+/// Thus, (apply \\='+ 1 2 \\='(3 4)) returns 10.
+/// usage: (apply FUNCTION &rest ARGUMENTS)
+#[lisp_fn(min = "1")]
+pub fn apply(args: &mut [LispObject]) -> LispObject {
+    let nargs = args.len();
+    let spread_arg = args[nargs - 1];
+
+    if !spread_arg.is_list() {
+        wrong_type!(Qlistp, spread_arg);
+    }
+
+    let numargs = length(spread_arg);
+
+    if numargs == 0 {
+        return funcall(&mut args[..nargs - 1]);
+    } else if numargs == 1 {
+        args[nargs - 1] = car(spread_arg);
+        return funcall(args);
+    }
+
+    let numargs = numargs + nargs - 2;
+
+    // Optimize for no indirection.
+    let fun = match args[0].as_symbol() {
+        Some(symbol) if args[0].is_not_nil() => {
+            let fun = symbol.get_indirect_function();
+            if fun.is_nil() {
+                // Let funcall get the error.
+                args[0]
+            } else {
+                fun
+            }
+        }
+        _ => args[0],
+    };
+
+    // Avoid making funcall cons up a yet another new vector of arguments
+    // by explicitly supplying nil's for optional values.  Don't hide an
+    // error by adding missing arguments, though.
+    let funcall_nargs = match fun.as_subr() {
+        Some(subr)
+            if subr.max_args() as isize > numargs as isize
+                && numargs >= subr.min_args() as usize =>
+        {
+            1 + subr.max_args() as usize
+        }
+        // We add 1 to numargs because funcall_args includes the
+        // function itself as well as its arguments.
+        _ => 1 + numargs,
+    };
+
+    let mut funcall_args = Arguments::new(funcall_nargs);
+    let funcall_slice = funcall_args.as_mut_slice();
+    funcall_slice[..nargs - 1].copy_from_slice(&args[..nargs - 1]);
+    // Spread the last arg we got.  Its first element goes in the slot
+    // that it used to occupy.
+    for (slot, arg) in funcall_slice[nargs - 1..]
+        .iter_mut()
+        .zip(spread_arg.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off))
+    {
+        *slot = arg;
+    }
+
+    funcall(funcall_slice)
+}
+
 /// Signal `error' with message MSG, and additional arg ARG.
 /// If ARG is not a genuine list, make it a one-element list.
-fn signal_error(msg: &str, arg: LispObject) -> ! {
+pub(crate) fn signal_error(msg: &str, arg: LispObject) -> ! {
     let it = arg.iter_tails(LispConsEndChecks::off, LispConsCircularChecks::safe);
     let arg = match it.last() {
         None => list!(arg),
         Some(_) => arg,
     };
 
-    xsignal!(Qerror, (build_string(msg.as_ptr() as *const i8), arg));
+    xsignal!(Qerror, (make_string(msg), arg));
 }
 
 /// Non-nil if FUNCTION makes provisions for interactive calling.
@@ -757,6 +1245,9 @@ pub unsafe extern "C" fn un_autoload(oldqueue: LispObject) {
     }
 }
 
+/// The functions being autoloaded, innermost first.
+declare_GC_protected_static!(autoloads_in_progress, Qnil);
+
 // Load an autoloaded function.
 // FUNNAME is the symbol which is the function's name.
 // FUNDEF is the autoload definition (a list).
@@ -772,8 +1263,6 @@ pub fn autoload_do_load(
     funname: LispObject,
     macro_only: LispObject,
 ) -> LispObject {
-    let count = c_specpdl_index();
-
     if !(fundef.is_cons() && car(fundef).eq(Qautoload)) {
         return fundef;
     }
@@ -785,16 +1274,39 @@ pub fn autoload_do_load(
 
     let sym = funname.as_symbol_or_error();
 
-    unsafe {
-        // This is to make sure that loadup.el gives a clear picture
-        // of what files are preloaded and when.
-        if globals.Vpurify_flag != Qnil {
-            error!(
-                "Attempt to autoload {} while preparing to dump",
-                sym.symbol_name().as_string_or_error()
-            );
+    // This is to make sure that loadup.el gives a clear picture
+    // of what files are preloaded and when.
+    if unsafe { globals.Vpurify_flag } != Qnil {
+        error!(
+            "Attempt to autoload {} while preparing to dump",
+            sym.symbol_name().as_string_or_error()
+        );
+    }
+
+    // If `macro_only' is set and fundef isn't a macro, assume this autoload to
+    // be a "best-effort" (e.g. to try and find a compiler macro),
+    // so don't signal an error if autoloading fails.
+    let ignore_errors = if kind.eq(Qt) || kind.eq(Qmacro) {
+        Qnil
+    } else {
+        macro_only
+    };
+
+    // Loading the file again from within itself can't define the
+    // function any better, it would only load the file over and over.
+    if funname.is_not_nil() && memq(funname, autoloads_in_progress.get()).is_not_nil() {
+        if ignore_errors.is_not_nil() {
+            return Qnil;
         }
+        error!(
+            "Recursive autoload of {} from {}",
+            sym.symbol_name().as_string_or_error(),
+            car(cdr(fundef)).as_string_or_error()
+        );
+    }
 
+    let binding = SpecBinding::mark();
+    unsafe {
         // Preserve the match data.
         record_unwind_save_match_data();
 
@@ -810,15 +1322,12 @@ pub fn autoload_do_load(
         record_unwind_protect(Some(un_autoload), Vautoload_queue);
         Vautoload_queue = Qt;
     }
-
-    // If `macro_only' is set and fundef isn't a macro, assume this autoload to
-    // be a "best-effort" (e.g. to try and find a compiler macro),
-    // so don't signal an error if autoloading fails.
-    let ignore_errors = if kind.eq(Qt) || kind.eq(Qmacro) {
-        Qnil
-    } else {
-        macro_only
-    };
+    autoloads_in_progress.set(LispObject::cons(funname, autoloads_in_progress.get()));
+    // The rest of the list stays reachable from the static until this
+    // runs, so the closure needn't protect it.
+    let _in_progress = record_unwind(|| {
+        autoloads_in_progress.set(cdr(autoloads_in_progress.get()));
+    });
 
     unsafe {
         Fload(Fcar(Fcdr(fundef)), ignore_errors, Qt, Qnil, Qt);
@@ -827,7 +1336,7 @@ pub fn autoload_do_load(
         Vautoload_queue = Qt;
     }
 
-    unbind_to(count, Qnil);
+    binding.unbind(());
 
     if funname.is_nil() || ignore_errors.is_not_nil() {
         Qnil
@@ -1074,30 +1583,340 @@ fn resolve_fun(fun: LispObject) -> Result<LispFun, LispFunError> {
     }
 }
 
+/// Apply the built-in function SUBR to the evaluated arguments ARGS and
+/// return the result.  Unless SUBR takes MANY arguments, ARGS must hold
+/// exactly as many as its maximum.
+fn call_subr(subr: LispSubrRef, args: &mut [LispObject]) -> LispObject {
+    let function = &subr.function;
+    unsafe {
+        match subr.max_args() {
+            MANY => (function.aMANY.unwrap())(args.len() as libc::ptrdiff_t, args.as_mut_ptr()),
+            0 => (function.a0.unwrap())(),
+            1 => (function.a1.unwrap())(args[0]),
+            2 => (function.a2.unwrap())(args[0], args[1]),
+            3 => (function.a3.unwrap())(args[0], args[1], args[2]),
+            4 => (function.a4.unwrap())(args[0], args[1], args[2], args[3]),
+            5 => (function.a5.unwrap())(args[0], args[1], args[2], args[3], args[4]),
+            6 => (function.a6.unwrap())(args[0], args[1], args[2], args[3], args[4], args[5]),
+            7 => (function.a7.unwrap())(
+                args[0], args[1], args[2], args[3], args[4], args[5], args[6],
+            ),
+            8 => (function.a8.unwrap())(
+                args[0], args[1], args[2], args[3], args[4], args[5], args[6], args[7],
+            ),
+            // If a subr takes more than 8 arguments without using MANY
+            // or UNEVALLED, we need to extend this function to support
+            // it.  Until this is done, there is no way to call the
+            // function.
+            _ => panic!("subr takes too many arguments"),
+        }
+    }
+}
+
+/// Apply the built-in function SUBR to the NUMARGS evaluated arguments
+/// in ARGS and return the result.
+#[no_mangle]
+pub unsafe extern "C" fn funcall_subr(
+    subr: *mut Lisp_Subr,
+    numargs: libc::ptrdiff_t,
+    args: *mut LispObject,
+) -> LispObject {
+    let subr = LispSubrRef::new(subr);
+    let fun = LispObject::from(subr);
+    let numargs = numargs as usize;
+
+    if numargs < subr.min_args() as usize
+        || (subr.max_args() >= 0 && (subr.max_args() as usize) < numargs)
+    {
+        wrong_number_of_arguments!(fun, numargs as EmacsInt);
+    } else if subr.is_unevalled() {
+        xsignal!(Qinvalid_function, fun);
+    }
+
+    let args: &mut [LispObject] = if numargs == 0 {
+        &mut []
+    } else {
+        slice::from_raw_parts_mut(args, numargs)
+    };
+    if subr.is_many() || subr.max_args() as usize == numargs {
+        call_subr(subr, args)
+    } else {
+        let mut internal_args = Arguments::new(subr.max_args() as usize);
+        internal_args.as_mut_slice()[..numargs].copy_from_slice(args);
+        call_subr(subr, internal_args.as_mut_slice())
+    }
+}
+
+/// Take apart the interpreted function FUN, either a lambda expression
+/// (lambda ARGS . BODY) or a closure (closure ENV ARGS . BODY), and
+/// return its ARGS along with the lexical environment ENV its body runs
+/// in.  A lambda expression has no environment, so it binds its
+/// arguments dynamically; a closure made where no lexical variable was
+/// bound has the environment (t), which is non-nil all the same.
+///
+/// FUN is left pointing at (lambda ARGS . BODY) or (ENV ARGS . BODY),
+/// so that its body is (cdr (cdr FUN)) either way.
+fn interpreted_function_parts(fun: &mut LispObject) -> (LispObject, LispObject) {
+    let mut lexenv = Qnil;
+    if car(*fun).eq(Qclosure) {
+        // Drop `closure'.
+        let cdr = cdr(*fun);
+        if !cdr.is_cons() {
+            xsignal!(Qinvalid_function, *fun);
+        }
+        *fun = cdr;
+        lexenv = car(*fun);
+    }
+    match cdr(*fun).as_cons() {
+        Some(cell) => (cell.car(), lexenv),
+        None => xsignal!(Qinvalid_function, *fun),
+    }
+}
+
+/// Apply a Lisp function FUN to the evaluated arguments ARGS and return
+/// the result of evaluation.  FUN must be either a lambda-expression, a
+/// compiled-code object, or a module function.
+fn funcall_lambda(mut fun: LispObject, args: &mut [LispObject]) -> LispObject {
+    let count = c_specpdl_index();
+    let nargs = args.len();
+
+    let (mut syms_left, mut lexenv) = if fun.is_cons() {
+        interpreted_function_parts(&mut fun)
+    } else if fun.is_byte_code_function() {
+        let size = fun.as_vectorlike().unwrap().pseudovector_size();
+        if size <= EmacsInt::from(Lisp_Compiled::COMPILED_STACK_DEPTH) {
+            xsignal!(Qinvalid_function, fun);
+        }
+        let syms_left = aref(fun, EmacsInt::from(Lisp_Compiled::COMPILED_ARGLIST));
+        if syms_left.is_fixnum() {
+            // A byte-code object with an integer args template means we
+            // shouldn't bind any arguments, instead just call the
+            // byte-code interpreter directly; it will push arguments as
+            // necessary.
+            //
+            // Byte-code objects with a nil args template (the default)
+            // have dynamically-bound arguments, and use the
+            // argument-binding code below instead (as do all interpreted
+            // functions, even lexically bound ones).
+            return exec_byte_code_function(fun, syms_left, args);
+        }
+        (syms_left, Qnil)
+    } else if fun.is_module_function() {
+        return funcall_module_function(fun, args);
+    } else {
+        panic!("funcall_lambda called on a non-function");
+    };
+
+    let mut i = 0;
+    let mut optional = false;
+    let mut rest = false;
+    let mut previous_optional_or_rest = false;
+    while let Some(cell) = syms_left.as_cons() {
+        unsafe { maybe_quit() };
+
+        let next = cell.car();
+        if !next.is_symbol() {
+            xsignal!(Qinvalid_function, fun);
+        }
+
+        if next.eq(Qand_rest) {
+            if rest || previous_optional_or_rest {
+                xsignal!(Qinvalid_function, fun);
+            }
+            rest = true;
+            previous_optional_or_rest = true;
+        } else if next.eq(Qand_optional) {
+            if optional || rest || previous_optional_or_rest {
+                xsignal!(Qinvalid_function, fun);
+            }
+            optional = true;
+            previous_optional_or_rest = true;
+        } else {
+            let arg = if rest {
+                let arg = list(&args[i..]);
+                i = nargs;
+                arg
+            } else if i < nargs {
+                i += 1;
+                args[i - 1]
+            } else if !optional {
+                wrong_number_of_arguments!(fun, nargs as EmacsInt);
+            } else {
+                Qnil
+            };
+
+            if lexenv.is_not_nil() {
+                // Lexically bind NEXT by adding it to the lexenv alist.
+                lexenv = LispObject::cons(LispObject::cons(next, arg), lexenv);
+            } else {
+                // Dynamically bind NEXT.
+                unsafe { specbind(next, arg) };
+            }
+            previous_optional_or_rest = false;
+        }
+
+        syms_left = cell.cdr();
+    }
+
+    if syms_left.is_not_nil() || previous_optional_or_rest {
+        xsignal!(Qinvalid_function, fun);
+    } else if i < nargs {
+        wrong_number_of_arguments!(fun, nargs as EmacsInt);
+    }
+
+    if lexenv != unsafe { globals.Vinternal_interpreter_environment } {
+        // Instantiate a new lexical environment.
+        unsafe { specbind(Qinternal_interpreter_environment, lexenv) };
+    }
+
+    let val = if fun.is_cons() {
+        progn(cdr(cdr(fun)))
+    } else {
+        exec_byte_code_function(fun, Qnil, &mut [])
+    };
+
+    unbind_to(count, val)
+}
+
+/// Return minimum and maximum number of args allowed for FUNCTION.
+/// FUNCTION must be a function of some kind.
+/// The returned value is a cons cell (MIN . MAX).  MIN is the minimum number
+/// of args.  MAX is the maximum number, or the symbol `many', for a
+/// function with `&rest' args, or `unevalled' for a special form.
+#[lisp_fn]
+pub fn func_arity(function: LispObject) -> LispObject {
+    let original = function;
+    loop {
+        let mut function = indirect_function(original);
+        if let Some(cons) = function.as_cons() {
+            if cons.car().eq(Qmacro) {
+                function = cons.cdr();
+            }
+        }
+
+        if let Some(subr) = function.as_subr() {
+            return subr_arity(subr).into();
+        } else if function.is_byte_code_function() {
+            return lambda_arity(function);
+        } else if function.is_module_function() {
+            return module_function_arity(function);
+        }
+
+        if function.is_nil() {
+            xsignal!(Qvoid_function, original);
+        }
+        let funcar = match function.as_cons() {
+            Some(cons) if cons.car().is_symbol() => cons.car(),
+            _ => xsignal!(Qinvalid_function, original),
+        };
+        if funcar.eq(Qlambda) || funcar.eq(Qclosure) {
+            return lambda_arity(function);
+        } else if funcar.eq(Qautoload) {
+            autoload_do_load(function, original, Qnil);
+        } else {
+            xsignal!(Qinvalid_function, original);
+        }
+    }
+}
+
+/// Return the arity of FUN, which must be either a lambda expression, a
+/// closure or a compiled-code object, as `func-arity' does.
+fn lambda_arity(mut fun: LispObject) -> LispObject {
+    let mut syms_left = if fun.is_cons() {
+        interpreted_function_parts(&mut fun).0
+    } else if fun.is_byte_code_function() {
+        let size = fun.as_vectorlike().unwrap().pseudovector_size();
+        if size <= EmacsInt::from(Lisp_Compiled::COMPILED_STACK_DEPTH) {
+            xsignal!(Qinvalid_function, fun);
+        }
+        let syms_left = aref(fun, EmacsInt::from(Lisp_Compiled::COMPILED_ARGLIST));
+        if syms_left.is_fixnum() {
+            return unsafe { get_byte_code_arity(syms_left) };
+        }
+        syms_left
+    } else {
+        panic!("lambda_arity called on a non-function");
+    };
+
+    let mut minargs: EmacsInt = 0;
+    let mut maxargs: EmacsInt = 0;
+    let mut optional = false;
+    while let Some(cell) = syms_left.as_cons() {
+        let next = cell.car();
+        if !next.is_symbol() {
+            xsignal!(Qinvalid_function, fun);
+        }
+        if next.eq(Qand_rest) {
+            return LispObject::cons(LispObject::from(minargs), Qmany);
+        } else if next.eq(Qand_optional) {
+            optional = true;
+        } else {
+            if !optional {
+                minargs += 1;
+            }
+            maxargs += 1;
+        }
+        syms_left = cell.cdr();
+    }
+
+    if syms_left.is_not_nil() {
+        xsignal!(Qinvalid_function, fun);
+    }
+
+    LispObject::cons(LispObject::from(minargs), LispObject::from(maxargs))
+}
+
+#[cfg(feature = "modules")]
+fn module_function_arity(fun: LispObject) -> LispObject {
+    let function = fun.as_vectorlike().unwrap().as_ptr() as *const Lisp_Module_Function;
+    unsafe { crate::remacs_sys::module_function_arity(function) }
+}
+
+#[cfg(not(feature = "modules"))]
+fn module_function_arity(_fun: LispObject) -> LispObject {
+    panic!("module functions need Emacs built with module support");
+}
+
+/// Run the byte-code function FUN with ARGS_TEMPLATE and ARGS, fetching
+/// its byte code and constants from the file first if they haven't been
+/// read yet.
+fn exec_byte_code_function(
+    fun: LispObject,
+    args_template: LispObject,
+    args: &mut [LispObject],
+) -> LispObject {
+    let slot = |idx: Lisp_Compiled::Type| aref(fun, EmacsInt::from(idx));
+    if slot(Lisp_Compiled::COMPILED_BYTECODE).is_cons() {
+        unsafe { Ffetch_bytecode(fun) };
+    }
+    rust_exec_byte_code(
+        slot(Lisp_Compiled::COMPILED_BYTECODE),
+        slot(Lisp_Compiled::COMPILED_CONSTANTS),
+        slot(Lisp_Compiled::COMPILED_STACK_DEPTH),
+        args_template,
+        args,
+    )
+}
+
+#[cfg(feature = "modules")]
+fn funcall_module_function(fun: LispObject, args: &mut [LispObject]) -> LispObject {
+    unsafe { funcall_module(fun, args.len() as libc::ptrdiff_t, args.as_mut_ptr()) }
+}
+
+#[cfg(not(feature = "modules"))]
+fn funcall_module_function(_fun: LispObject, _args: &mut [LispObject]) -> LispObject {
+    panic!("module functions need Emacs built with module support");
+}
+
 /// Call first argument as a function, passing remaining arguments to it.
 /// Return the value that function returns.
 /// Thus, (funcall \\='cons \\='x \\='y) returns (x . y).
 /// usage: (funcall FUNCTION &rest ARGUMENTS)
-#[allow(unused_assignments)]
 #[lisp_fn(min = "1")]
 pub fn funcall(args: &mut [LispObject]) -> LispObject {
     unsafe { maybe_quit() };
 
-    // Increment the lisp eval depth
-    let mut current_thread = ThreadState::current_thread();
-    current_thread.m_lisp_eval_depth += 1;
-
-    unsafe {
-        if current_thread.m_lisp_eval_depth > globals.max_lisp_eval_depth {
-            if globals.max_lisp_eval_depth < 100 {
-                globals.max_lisp_eval_depth = 100;
-            }
-
-            if current_thread.m_lisp_eval_depth > globals.max_lisp_eval_depth {
-                error!("Lisp nesting exceeds `max-lisp-eval-depth'");
-            }
-        }
-    }
+    enter_lisp_eval_depth();
 
     // The first element in args is the called function.
     let numargs = args.len() as isize - 1;
@@ -1112,7 +1931,7 @@ pub fn funcall(args: &mut [LispObject]) -> LispObject {
 
     let count = unsafe { record_in_backtrace(fun, fun_args, numargs) };
 
-    unsafe { maybe_gc() };
+    maybe_gc();
 
     unsafe {
         if globals.debug_on_next_call {
@@ -1122,36 +1941,337 @@ pub fn funcall(args: &mut [LispObject]) -> LispObject {
 
     unsafe { check_cons_list() };
 
-    let mut val = Qnil;
-
-    match resolve_fun(fun) {
-        Ok(LispFun::SubrFun(mut f)) => {
-            val = unsafe { funcall_subr(f.as_mut(), numargs, fun_args) };
-        }
-        Ok(LispFun::LambdaFun(f)) => {
-            val = unsafe { funcall_lambda(f, numargs, fun_args) };
-        }
+    let val = match resolve_fun(fun) {
+        Ok(LispFun::SubrFun(mut f)) => unsafe { funcall_subr(f.as_mut(), numargs, fun_args) },
+        Ok(LispFun::LambdaFun(f)) => funcall_lambda(f, &mut args[1..]),
         Err(LispFunError::InvalidFun) => {
             xsignal!(Qinvalid_function, fun);
         }
         Err(LispFunError::VoidFun) => {
             xsignal!(Qvoid_function, fun);
         }
+    };
+
+    exit_backtrace_frame(count, val)
+}
+
+/// A signal on its way to a handler: the error symbol and the data it
+/// was signaled with, as a `condition-case' handler would see them.
+#[derive(Clone, Copy)]
+pub struct LispSignal {
+    pub symbol: LispObject,
+    pub data: LispObject,
+}
+
+/// The result of something that can signal.  Returning the signal as an
+/// `Err`, rather than signaling it on the spot, lets it pass through Rust
+/// frames with `?`, so that they are left normally; it is only signaled,
+/// with a longjmp, once it gets back to Lisp.  A `lisp_fn` can return a
+/// `LispResult` directly, and its `Err` is signaled by the wrapper.
+pub type LispResult<T> = Result<T, LispSignal>;
+
+impl LispSignal {
+    pub fn new(symbol: LispObject, data: LispObject) -> Self {
+        Self { symbol, data }
     }
 
-    unsafe { check_cons_list() };
+    /// An `error' with MESSAGE, as signaled by `error!`.
+    pub fn error(message: &str) -> Self {
+        Self::new(Qerror, list!(LispObject::from(message)))
+    }
 
-    current_thread.m_lisp_eval_depth -= 1;
+    /// Signal the error again, from where it was caught.
+    pub fn resignal(self) -> ! {
+        unsafe { Fsignal(self.symbol, self.data) }
+    }
+}
+
+impl From<LispSignal> for LispObject {
+    fn from(signal: LispSignal) -> Self {
+        LispObject::cons(signal.symbol, signal.data)
+    }
+}
+
+impl<T> From<LispResult<T>> for LispObject
+where
+    LispObject: From<T>,
+{
+    fn from(result: LispResult<T>) -> Self {
+        match result {
+            Ok(value) => LispObject::from(value),
+            Err(signal) => signal.resignal(),
+        }
+    }
+}
+
+thread_local! {
+    /// Set by `catch_signal` when a `funcall_checked` call was unwound.
+    static SIGNALED: Cell<bool> = Cell::new(false);
+}
+
+extern "C" fn catch_signal(
+    error: LispObject,
+    _nargs: libc::ptrdiff_t,
+    _args: *mut LispObject,
+) -> LispObject {
+    SIGNALED.with(|signaled| signaled.set(true));
+    error
+}
+
+/// Call the function in ARGS[0] with the rest of ARGS, like `funcall`,
+/// but return the signal instead of unwinding when the call signals,
+/// quits included.  `throw`s to a `catch` outside the call still unwind.
+/// ARGS stays on the caller's stack, where the garbage collector finds
+/// it, for the duration of the call.
+pub fn funcall_checked(args: &mut [LispObject]) -> LispResult<LispObject> {
+    let val = unsafe {
+        internal_condition_case_n(
+            Some(Ffuncall),
+            args.len() as libc::ptrdiff_t,
+            args.as_mut_ptr(),
+            Qt,
+            Some(catch_signal),
+        )
+    };
+    if SIGNALED.with(|signaled| signaled.replace(false)) {
+        let (symbol, data) = val.into();
+        Err(LispSignal::new(symbol, data))
+    } else {
+        Ok(val)
+    }
+}
+
+/// An iterator over a chain of handlers, following their `next`.
+struct HandlerIter {
+    current: *mut handler,
+}
+
+impl Iterator for HandlerIter {
+    type Item = *mut handler;
+
+    fn next(&mut self) -> Option<*mut handler> {
+        if self.current.is_null() {
+            return None;
+        }
+        let handler = self.current;
+        self.current = unsafe { (*handler).next };
+        Some(handler)
+    }
+}
+
+/// The catches and condition-cases in effect, innermost first.
+fn handlers() -> HandlerIter {
+    HandlerIter {
+        current: ThreadState::current_thread().m_handlerlist,
+    }
+}
+
+/// Throw to the catch for TAG and return VALUE from it.  This is the
+/// guts of `throw'.
+#[no_mangle]
+pub extern "C" fn throw_to_catch(tag: LispObject, value: LispObject) -> ! {
+    if tag.is_not_nil() {
+        for h in handlers() {
+            let (kind, tag_or_ch) = unsafe { ((*h).type_, (*h).tag_or_ch) };
+            if kind == handlertype::CATCHER_ALL {
+                unsafe { unwind_to_catch(h, LispObject::cons(tag, value)) };
+            }
+            if kind == handlertype::CATCHER && tag_or_ch.eq(tag) {
+                unsafe { unwind_to_catch(h, value) };
+            }
+        }
+    }
+    xsignal!(Qno_catch, tag, value);
+}
+
+/// Return the clause of HANDLERS, the conditions of a condition-case,
+/// that applies to an error with CONDITIONS, or nil if none does.
+fn find_handler_clause(handlers: LispObject, conditions: LispObject) -> LispObject {
+    // t is used by handlers for all conditions, set up by C code.  error
+    // is used similarly, but means print an error message and run the
+    // debugger if that is enabled.
+    if handlers.eq(Qt) || handlers.eq(Qerror) {
+        return Qt;
+    }
+    let applies = handlers
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+        .any(|handler| memq(handler, conditions).is_not_nil());
+    if applies {
+        handlers
+    } else {
+        Qnil
+    }
+}
+
+/// Signal an error, or quit.  ERROR_SYMBOL and DATA are as with `signal'.
+/// If KEYBOARD_QUIT, this is a quit; ERROR_SYMBOL should be `quit' and
+/// DATA should be nil, and this function may return.  Otherwise it does
+/// not return.
+#[no_mangle]
+pub extern "C" fn signal_or_quit(
+    error_symbol: LispObject,
+    data: LispObject,
+    keyboard_quit: bool,
+) -> LispObject {
+    // When memory is full, ERROR-SYMBOL is nil, and DATA is
+    // (REAL-ERROR-SYMBOL . REAL-DATA).  That is a special case--don't do
+    // this in other situations.
+    let real_error_symbol = if error_symbol.is_nil() {
+        car(data)
+    } else {
+        error_symbol
+    };
 
     unsafe {
-        if backtrace_debug_on_exit(current_thread.m_specpdl.offset(count)) {
-            val = call_debugger(list2(Qexit, val));
+        if gc_in_progress || waiting_for_input {
+            emacs_abort();
         }
+    }
 
-        current_thread.m_specpdl_ptr = current_thread.m_specpdl_ptr.offset(-1);
+    // This hook is used by edebug.
+    let current_thread = ThreadState::current_thread();
+    let specpdl_end = unsafe {
+        current_thread
+            .m_specpdl
+            .offset(current_thread.m_specpdl_size)
+    };
+    if unsafe { globals.Vsignal_hook_function }.is_not_nil()
+        && error_symbol.is_not_nil()
+        // Don't try to call a lisp function if we've already overflowed
+        // the specpdl stack.
+        && current_thread.m_specpdl_ptr < specpdl_end
+    {
+        // Edebug takes care of restoring these variables when it exits.
+        unsafe {
+            if current_thread.m_lisp_eval_depth + 20 > globals.max_lisp_eval_depth {
+                globals.max_lisp_eval_depth = current_thread.m_lisp_eval_depth + 20;
+            }
+            let count = c_specpdl_index() as EmacsInt;
+            if count + 40 > globals.max_specpdl_size {
+                globals.max_specpdl_size = count + 40;
+            }
+        }
+        call!(unsafe { globals.Vsignal_hook_function }, error_symbol, data);
     }
 
-    val
+    let conditions = get(real_error_symbol.into(), Qerror_conditions);
+
+    // Remember from where signal was called.  Skip over the frame for
+    // `signal' itself.  If a frame for `error' follows, skip that, too.
+    // Don't do this when ERROR_SYMBOL is nil, because that is a
+    // memory-full error.
+    unsafe {
+        globals.Vsignaling_function = Qnil;
+        if error_symbol.is_not_nil() {
+            let mut pdl = backtrace_next(backtrace_top());
+            if backtrace_p(pdl) && backtrace_function(pdl).eq(Qerror) {
+                pdl = backtrace_next(pdl);
+            }
+            if backtrace_p(pdl) {
+                globals.Vsignaling_function = backtrace_function(pdl);
+            }
+        }
+    }
+
+    let found = handlers()
+        .filter(|&h| unsafe { (*h).type_ } == handlertype::CONDITION_CASE)
+        .map(|h| {
+            (
+                h,
+                find_handler_clause(unsafe { (*h).tag_or_ch }, conditions),
+            )
+        })
+        .find(|&(_, clause)| clause.is_not_nil());
+
+    // Don't run the debugger for a memory-full error.  (There is no room
+    // in memory to do that!)
+    let run_debugger = error_symbol.is_not_nil()
+        && match found {
+            // If no handler is present now, try to run the debugger.
+            None => true,
+            Some((h, clause)) => {
+                unsafe { globals.Vdebug_on_signal }.is_not_nil()
+                    // A `debug' symbol in the handler list disables the
+                    // normal suppression of the debugger.
+                    || (clause.is_cons() && memq(Qdebug, clause).is_not_nil())
+                    // Special handler that means "print a message and
+                    // run debugger if requested".
+                    || unsafe { (*h).tag_or_ch }.eq(Qerror)
+            }
+        };
+    if run_debugger {
+        let debugger_called = unsafe { maybe_call_debugger(conditions, error_symbol, data) };
+        // We can't return values to code which signaled an error, but we
+        // can continue code which has signaled a quit.
+        if keyboard_quit && debugger_called && real_error_symbol.eq(Qquit) {
+            return Qnil;
+        }
+    }
+
+    match found {
+        Some((h, _)) => {
+            let unwind_data = if error_symbol.is_nil() {
+                data
+            } else {
+                LispObject::cons(error_symbol, data)
+            };
+            unsafe { unwind_to_catch(h, unwind_data) }
+        }
+        None => {
+            if current_thread.m_handlerlist != current_thread.m_handlerlist_sentinel {
+                // FIXME: This will come right back here if there's no
+                // `top-level' catcher.  A better solution would be to
+                // abort here, and instead add a catch-all condition
+                // handler so we never come here.
+                throw_to_catch(Qtop_level, Qt);
+            }
+        }
+    }
+
+    let data = if error_symbol.is_nil() {
+        data
+    } else {
+        LispObject::cons(error_symbol, data)
+    };
+    let string = unsafe { Ferror_message_string(data) }.force_string();
+    unsafe {
+        fatal(
+            b"%s\0".as_ptr() as *const libc::c_char,
+            string.const_sdata_ptr(),
+        )
+    }
+}
+
+/// Regain control when an error is signaled.
+/// Executes BODYFORM and returns its value if no error happens.
+/// Each element of HANDLERS looks like (CONDITION-NAME BODY...)
+/// where the BODY is made of Lisp expressions.
+///
+/// A handler is applicable to an error
+/// if CONDITION-NAME is one of the error's condition names.
+/// If an error happens, the first applicable handler is run.
+///
+/// The car of a handler may be a list of condition names instead of a
+/// single condition name; then it handles all of them.  If the special
+/// condition name `debug' is present in this list, it allows another
+/// condition in the list to run the debugger if `debug-on-error' and the
+/// other usual mechanisms says it should (otherwise, `condition-case'
+/// suppresses the debugger).
+///
+/// When a handler handles an error, control returns to the `condition-case'
+/// and it executes the handler's BODY...
+/// with VAR bound to (ERROR-SYMBOL . SIGNAL-DATA) from the error.
+/// (If VAR is nil, the handler can't access that information.)
+/// Then the value of the last BODY form is returned from the `condition-case'
+/// expression.
+///
+/// See also the function `signal' for more info.
+/// usage: (condition-case VAR BODYFORM &rest HANDLERS)
+#[lisp_fn(min = "2", unevalled = "true")]
+pub fn condition_case(args: LispCons) -> LispObject {
+    let (var, rest) = args.into();
+    let (bodyform, handlers) = rest.into();
+    unsafe { internal_lisp_condition_case(var, bodyform, handlers) }
 }
 
 /// Pop and execute entries from the unwind-protect stack until the
@@ -1187,6 +2307,81 @@ pub extern "C" fn unbind_to(count: libc::ptrdiff_t, value: LispObject) -> LispOb
     value
 }
 
+/// A mark on the specpdl.  When the guard is dropped, the bindings made
+/// and the unwind functions recorded since it was made are undone, as
+/// `unbind_to` would.
+///
+/// A signal or `throw` that goes past the Rust frame holding the guard
+/// longjmps over it without dropping it, but the specpdl is unwound all
+/// the same by the `condition-case` or `catch` that gets it.  Signals
+/// returned as the `Err` of a `LispResult`, as by `call_checked!`, leave
+/// the frame normally, so the guard is dropped before they are passed on.
+#[must_use]
+pub struct SpecBinding {
+    count: libc::ptrdiff_t,
+}
+
+impl SpecBinding {
+    /// Return a guard for what is pushed on the specpdl from now on.
+    pub fn mark() -> Self {
+        Self {
+            count: c_specpdl_index(),
+        }
+    }
+
+    /// Bind SYMBOL to VALUE, as `let' does, until the guard returned is
+    /// dropped.
+    pub fn push(symbol: LispObject, value: LispObject) -> Self {
+        let guard = Self::mark();
+        unsafe { specbind(symbol, value) };
+        guard
+    }
+
+    /// Also bind SYMBOL to VALUE until the guard is dropped.
+    pub fn bind(&self, symbol: LispObject, value: LispObject) -> &Self {
+        unsafe { specbind(symbol, value) };
+        self
+    }
+
+    /// Undo the bindings now, and return VALUE.
+    pub fn unbind<T>(self, value: T) -> T {
+        drop(self);
+        value
+    }
+}
+
+impl Drop for SpecBinding {
+    fn drop(&mut self) {
+        // Someone may have unbound past this guard already.
+        if c_specpdl_index() > self.count {
+            unbind_to(self.count, Qnil);
+        }
+    }
+}
+
+extern "C" fn run_unwind_function<F: FnOnce()>(function: *mut libc::c_void) {
+    let function = unsafe { *Box::from_raw(function as *mut F) };
+    function();
+}
+
+/// Arrange for FUNCTION to be called when the specpdl is unwound past
+/// this point: when the guard returned is dropped, by `unbind_to`, or by
+/// a signal or `throw`.  This is `record_unwind_protect` for closures.
+///
+/// The closure lives on the heap, where the garbage collector doesn't
+/// look, so any Lisp object it captures must be protected otherwise.
+pub fn record_unwind<F: FnOnce() + 'static>(function: F) -> SpecBinding {
+    let guard = SpecBinding::mark();
+    let function = Box::new(function);
+    unsafe {
+        record_unwind_protect_ptr(
+            Some(run_unwind_function::<F>),
+            Box::into_raw(function) as *mut libc::c_void,
+        )
+    };
+    guard
+}
+
 /// Do BODYFORM, protecting with UNWINDFORMS.
 /// If BODYFORM completes normally, its value is returned
 /// after executing the UNWINDFORMS.
@@ -1199,7 +2394,7 @@ pub fn unwind_protect(args: LispCons) -> LispObject {
 
     unsafe { record_unwind_protect(Some(prog_ignore), unwindforms) };
 
-    unbind_to(count, unsafe { eval_sub(bodyform) })
+    unbind_to(count, eval_sub(bodyform))
 }
 
 /// Eval BODY allowing nonlocal exits using `throw'.
@@ -1213,7 +2408,7 @@ pub fn unwind_protect(args: LispCons) -> LispObject {
 pub fn catch(args: LispCons) -> LispObject {
     let (tag, body) = args.into();
 
-    let val = unsafe { eval_sub(tag) };
+    let val = eval_sub(tag);
 
     unsafe { internal_catch(val, Some(Fprogn), body) }
 }
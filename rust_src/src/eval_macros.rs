@@ -29,7 +29,9 @@ macro_rules! xsignal {
 }
 
 /// Macro to call Lisp functions with any number of arguments.
-/// Replaces call0, call1, etc. in the C layer.
+/// Replaces call0, call1, etc. in the C layer.  The function and its
+/// arguments are kept in an array on the stack, so they are safe from
+/// garbage collection during the call.
 macro_rules! call {
     ($func:expr, $($arg:expr),*) => {
         crate::eval::funcall(&mut [$func, $($arg),*])
@@ -39,6 +41,19 @@ macro_rules! call {
     }
 }
 
+/// Like `call!`, but evaluate to a `Result` that holds the
+/// `LispSignal` when the function signals, instead of unwinding.  For
+/// hooks and predicates whose errors must not escape.
+#[allow(unused_macros)]
+macro_rules! call_checked {
+    ($func:expr, $($arg:expr),*) => {
+        crate::eval::funcall_checked(&mut [$func, $($arg),*])
+    };
+    ($func:expr) => {
+        crate::eval::funcall_checked(&mut [$func])
+    }
+}
+
 macro_rules! callN_raw {
     ($func:expr, $($arg:expr),*) => {{
         let mut argsarray = [$($arg),*];
@@ -203,14 +218,14 @@ macro_rules! defvar_bool {
         #[allow(unused_unsafe)]
         unsafe {
             #[allow(const_err)]
-            static mut o_fwd: ::hacks::Hack<::data::Lisp_Boolfwd> =
-                unsafe { ::hacks::Hack::uninitialized() };
-            ::remacs_sys::defvar_bool(
+            static mut o_fwd: crate::hacks::Hack<crate::data::Lisp_Boolfwd> =
+                unsafe { crate::hacks::Hack::uninitialized() };
+            crate::remacs_sys::defvar_bool(
                 o_fwd.get_mut(),
                 concat!($lisp_name, "\0").as_ptr() as *const i8,
-                &mut ::remacs_sys::globals.$field_name,
+                &mut crate::remacs_sys::globals.$field_name,
             );
-            ::remacs_sys::globals.$field_name = $value;
+            crate::remacs_sys::globals.$field_name = $value;
         }
     }};
 }
@@ -220,14 +235,14 @@ macro_rules! defvar_int {
         #[allow(unused_unsafe)]
         unsafe {
             #[allow(const_err)]
-            static mut o_fwd: ::hacks::Hack<::data::Lisp_Intfwd> =
-                unsafe { ::hacks::Hack::uninitialized() };
-            ::remacs_sys::defvar_int(
+            static mut o_fwd: crate::hacks::Hack<crate::data::Lisp_Intfwd> =
+                unsafe { crate::hacks::Hack::uninitialized() };
+            crate::remacs_sys::defvar_int(
                 o_fwd.get_mut(),
                 concat!($lisp_name, "\0").as_ptr() as *const i8,
-                &mut ::remacs_sys::globals.$field_name,
+                &mut crate::remacs_sys::globals.$field_name,
             );
-            ::remacs_sys::globals.$field_name = $value;
+            crate::remacs_sys::globals.$field_name = $value;
         }
     }};
 }
@@ -277,7 +292,8 @@ macro_rules! defvar_per_buffer {
 #[allow(unused_macros)]
 macro_rules! declare_GC_protected_static {
     ($var: ident, $value: expr) => {
-        static mut $var: LispObject = $value;
+        static $var: crate::gc::StaticRoot<crate::lisp::LispObject> =
+            crate::gc::StaticRoot::new($value);
     };
 }
 
@@ -1,7 +1,12 @@
 //! Functions to deal with files
 use errno::{set_errno, Errno};
 
-use std::path;
+use std::{
+    fs,
+    io::{self, Write},
+    path::{self, Path, PathBuf},
+    process,
+};
 
 use remacs_macros::lisp_fn;
 
@@ -136,4 +141,43 @@ pub fn file_executable_p(filename: LispStringRef) -> bool {
     }
 }
 
+/// Convert an encoded file name to a path.
+#[cfg(unix)]
+pub fn bytes_to_path(name: &[u8]) -> PathBuf {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+    PathBuf::from(OsStr::from_bytes(name))
+}
+
+/// Convert an encoded file name to a path.  File names are encoded
+/// in UTF-8 on MS-Windows.
+#[cfg(windows)]
+pub fn bytes_to_path(name: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(name).into_owned())
+}
+
+/// Write CONTENTS to FILE through a temporary file in the same directory,
+/// so that FILE is never left partially written.  If FILE exists, the
+/// new file gets its permissions.
+pub fn write_atomically(file: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut temp = file.as_os_str().to_os_string();
+    temp.push(format!(".{}.tmp", process::id()));
+    let temp = PathBuf::from(temp);
+
+    let permissions = fs::metadata(file).ok().map(|m| m.permissions());
+    let result = fs::File::create(&temp).and_then(|mut f| {
+        if let Some(permissions) = permissions {
+            f.set_permissions(permissions)?;
+        }
+        f.write_all(contents)?;
+        f.sync_all()
+    });
+    match result.and_then(|_| fs::rename(&temp, file)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
 include!(concat!(env!("OUT_DIR"), "/fileio_exports.rs"));
@@ -0,0 +1,243 @@
+//! Finalizers.
+//!
+//! A finalizer made by `make-finalizer' runs its function after the
+//! garbage collection that finds it unreachable, other than from its
+//! function and other finalizers.  Rust code holding external resources
+//! can likewise have a closure run once a Lisp object of its own is
+//! collected, with `on_gc_finalize`.
+
+use std::mem;
+use std::ptr;
+
+use libc::c_char;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    eval::{funcall_checked, unbind_to},
+    gc::mark_object,
+    gc_stats::MemoryUse,
+    hacks::Hack,
+    lisp::{defsubr, LispObject},
+    remacs_sys::{add_to_log, allocate_misc, internal_condition_case, specbind, survives_gc_p},
+    remacs_sys::{Lisp_Finalizer, Lisp_Misc_Type},
+    remacs_sys::{Qinhibit_quit, Qnil, Qt},
+    threads::c_specpdl_index,
+};
+
+/// The head of the circular list of the finalizers alive.
+static mut FINALIZERS: Hack<Lisp_Finalizer> = unsafe { Hack::uninitialized() };
+
+/// The head of the circular list of the finalizers found unreachable,
+/// whose functions are to run.  This is not local to a collection, in
+/// case another one happens while the functions run.
+static mut DOOMED_FINALIZERS: Hack<Lisp_Finalizer> = unsafe { Hack::uninitialized() };
+
+/// The closures registered with `on_gc_finalize`, each with the object
+/// whose collection runs it.  The objects are not roots.  Set up by
+/// `init_finalizers`.
+static mut NATIVE_FINALIZERS: Option<Vec<(LispObject, Box<FnMut()>)>> = None;
+
+/// The closures whose objects did not survive the current collection.
+static mut DOOMED_NATIVE_FINALIZERS: Option<Vec<Box<FnMut()>>> = None;
+
+/// The closure that `call_native_finalizer` is to run.
+static mut NATIVE_FINALIZER: Option<Box<FnMut()>> = None;
+
+unsafe fn native_finalizers() -> &'static mut Vec<(LispObject, Box<FnMut()>)> {
+    NATIVE_FINALIZERS.as_mut().unwrap()
+}
+
+unsafe fn doomed_native_finalizers() -> &'static mut Vec<Box<FnMut()>> {
+    DOOMED_NATIVE_FINALIZERS.as_mut().unwrap()
+}
+
+/// Return the list head HEAD as a pointer.
+unsafe fn list_head(head: &'static mut Hack<Lisp_Finalizer>) -> *mut Lisp_Finalizer {
+    head.get_mut()
+}
+
+unsafe fn init_finalizer_list(head: *mut Lisp_Finalizer) {
+    (*head).prev = head;
+    (*head).next = head;
+}
+
+/// Set up the lists of finalizers.  Called from init_alloc_once.
+#[no_mangle]
+pub unsafe extern "C" fn init_finalizers() {
+    init_finalizer_list(list_head(&mut FINALIZERS));
+    init_finalizer_list(list_head(&mut DOOMED_FINALIZERS));
+    NATIVE_FINALIZERS = Some(Vec::new());
+    DOOMED_NATIVE_FINALIZERS = Some(Vec::new());
+}
+
+/// Insert FINALIZER before ELEMENT.
+unsafe fn finalizer_insert(element: *mut Lisp_Finalizer, finalizer: *mut Lisp_Finalizer) {
+    debug_assert!((*finalizer).prev.is_null() && (*finalizer).next.is_null());
+    (*finalizer).next = element;
+    (*finalizer).prev = (*element).prev;
+    (*(*finalizer).prev).next = finalizer;
+    (*element).prev = finalizer;
+}
+
+/// Take FINALIZER out of the list it is in, if any.
+#[no_mangle]
+pub unsafe extern "C" fn unchain_finalizer(finalizer: *mut Lisp_Finalizer) {
+    if !(*finalizer).prev.is_null() {
+        debug_assert!(!(*finalizer).next.is_null());
+        (*(*finalizer).prev).next = (*finalizer).next;
+        (*(*finalizer).next).prev = (*finalizer).prev;
+        (*finalizer).prev = ptr::null_mut();
+        (*finalizer).next = ptr::null_mut();
+    }
+}
+
+/// Call F on each finalizer of the list headed by HEAD.  F may move the
+/// finalizer it is given to another list.
+unsafe fn for_each_finalizer(head: *mut Lisp_Finalizer, mut f: impl FnMut(*mut Lisp_Finalizer)) {
+    let mut finalizer = (*head).next;
+    while finalizer != head {
+        let next = (*finalizer).next;
+        f(finalizer);
+        finalizer = next;
+    }
+}
+
+/// Queue the finalizers that garbage collection found unreachable, and
+/// mark them and their functions so that these survive until they run.
+/// Called once everything else is marked, so that the finalizers left
+/// unmarked are reachable only from their functions and from other
+/// finalizers.  The closures of `on_gc_finalize` whose objects are
+/// unmarked are queued as well.
+#[no_mangle]
+pub unsafe extern "C" fn queue_doomed_finalizers() {
+    let doomed = list_head(&mut DOOMED_FINALIZERS);
+    for_each_finalizer(list_head(&mut FINALIZERS), |finalizer| {
+        if !(*finalizer).base.gcmarkbit() && (*finalizer).function.is_not_nil() {
+            unchain_finalizer(finalizer);
+            finalizer_insert(doomed, finalizer);
+        }
+    });
+    for_each_finalizer(doomed, |finalizer| {
+        (*finalizer).base.set_gcmarkbit(true);
+        mark_object((*finalizer).function);
+    });
+
+    let (alive, dead): (Vec<_>, Vec<_>) = mem::replace(native_finalizers(), Vec::new())
+        .into_iter()
+        .partition(|&(object, _)| survives_gc_p(object));
+    *native_finalizers() = alive;
+    doomed_native_finalizers().extend(dead.into_iter().map(|(_, f)| f));
+}
+
+fn log_finalizer_error(error: LispObject) {
+    unsafe { add_to_log(b"finalizer failed: %S\0".as_ptr() as *const c_char, error) };
+}
+
+/// Call FUNCTION with quitting inhibited, logging the error it signals,
+/// if any.
+unsafe fn run_finalizer_function(function: LispObject) {
+    let count = c_specpdl_index();
+    specbind(Qinhibit_quit, Qt);
+    if let Err(signal) = funcall_checked(&mut [function]) {
+        log_finalizer_error(LispObject::cons(signal.symbol, signal.data));
+    }
+    unbind_to(count, Qnil);
+}
+
+extern "C" fn call_native_finalizer() -> LispObject {
+    if let Some(mut f) = unsafe { NATIVE_FINALIZER.take() } {
+        f();
+    }
+    Qnil
+}
+
+extern "C" fn native_finalizer_failed(error: LispObject) -> LispObject {
+    log_finalizer_error(error);
+    Qnil
+}
+
+/// Run the closure F of `on_gc_finalize` as `run_finalizer_function`
+/// runs a function: with quitting inhibited, logging the error it
+/// signals, if any.
+unsafe fn run_native_finalizer(f: Box<FnMut()>) {
+    let count = c_specpdl_index();
+    specbind(Qinhibit_quit, Qt);
+    NATIVE_FINALIZER = Some(f);
+    internal_condition_case(
+        Some(call_native_finalizer),
+        Qt,
+        Some(native_finalizer_failed),
+    );
+    unbind_to(count, Qnil);
+}
+
+/// Run the closures queued by the last garbage collection, and the
+/// functions of the doomed finalizers.  Called once garbage collection
+/// is complete.
+#[no_mangle]
+pub unsafe extern "C" fn run_finalizers() {
+    // The closures run first, so that what they release for the objects
+    // collected is gone before Lisp code can make objects in their place.
+    // A collection while the functions run queues more of them.
+    let doomed = list_head(&mut DOOMED_FINALIZERS);
+    loop {
+        if !doomed_native_finalizers().is_empty() {
+            for f in mem::replace(doomed_native_finalizers(), Vec::new()) {
+                run_native_finalizer(f);
+            }
+            continue;
+        }
+        if (*doomed).next == doomed {
+            break;
+        }
+        let finalizer = (*doomed).next;
+        unchain_finalizer(finalizer);
+        let function = (*finalizer).function;
+        if function.is_not_nil() {
+            (*finalizer).function = Qnil;
+            run_finalizer_function(function);
+        }
+    }
+}
+
+/// Run F after the garbage collection that finds OBJECT unreachable.
+/// This is how Rust code that holds an external resource on behalf of a
+/// Lisp object releases it.  F runs outside of garbage collection, so it
+/// may call Lisp, but it must not refer to OBJECT, which is gone by then.
+/// Objects that are never collected, like pure ones, never run F.  F is
+/// called once.
+pub fn on_gc_finalize(object: LispObject, f: Box<FnMut()>) {
+    unsafe { native_finalizers().push((object, f)) };
+}
+
+/// Return the use of memory by the closures of `on_gc_finalize`.
+pub fn memory_use() -> Vec<MemoryUse> {
+    unsafe {
+        vec![
+            MemoryUse::of_vec("native-finalizers", native_finalizers()),
+            MemoryUse::of_vec("doomed-native-finalizers", doomed_native_finalizers()),
+        ]
+    }
+}
+
+/// Make a finalizer that will run FUNCTION.
+/// FUNCTION will be called after garbage collection when the returned
+/// finalizer object becomes unreachable.  If the finalizer object is
+/// reachable only through references from finalizer objects, it does not
+/// count as reachable for the purpose of deciding whether to run
+/// FUNCTION.  FUNCTION will be run once per finalizer object.
+#[lisp_fn]
+pub fn make_finalizer(function: LispObject) -> LispObject {
+    let val = unsafe { allocate_misc(Lisp_Misc_Type::Lisp_Misc_Finalizer) };
+    let finalizer = val.get_untaggedptr() as *mut Lisp_Finalizer;
+    unsafe {
+        (*finalizer).function = function;
+        (*finalizer).prev = ptr::null_mut();
+        (*finalizer).next = ptr::null_mut();
+        finalizer_insert(list_head(&mut FINALIZERS), finalizer);
+    }
+    val
+}
+
+include!(concat!(env!("OUT_DIR"), "/finalizers_exports.rs"));
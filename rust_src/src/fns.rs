@@ -1,28 +1,27 @@
 //* Random utility Lisp functions.
 
-use std::ptr;
-
 use libc;
 
 use remacs_macros::lisp_fn;
 
 use crate::{
     eval::{un_autoload, unbind_to},
+    hashtable::{HashLookupResult, LispHashTableRef},
     lisp::defsubr,
     lisp::LispObject,
-    lists::{assq, car, get, mapcar1, member, memq, put},
+    lists::{assq, car, get, member, memq, put},
     lists::{LispCons, LispConsCircularChecks, LispConsEndChecks},
     numbers::LispNumber,
     obarray::loadhist_attach,
     objects::equal,
+    remacs_sys::equal_kind,
     remacs_sys::Fload,
     remacs_sys::Vautoload_queue,
-    remacs_sys::{concat as lisp_concat, globals, record_unwind_protect},
-    remacs_sys::{equal_kind, EmacsInt, Lisp_Type},
-    remacs_sys::{Qfuncall, Qlistp, Qnil, Qprovide, Qquote, Qrequire, Qsubfeatures, Qt},
+    remacs_sys::{globals, record_unwind_protect},
+    remacs_sys::{Lisp_Type, Qfuncall, Qlistp, Qnil, Qprovide, Qquote, Qrequire, Qsubfeatures, Qt},
+    sequences::mapc,
     symbols::LispSymbolRef,
     threads::c_specpdl_index,
-    vectors::length,
 };
 
 /// Return t if FEATURE is present in this Emacs.
@@ -68,7 +67,7 @@ pub fn provide(feature: LispSymbolRef, subfeature: LispObject) -> LispObject {
     // Run any load-hooks for this file.
     unsafe {
         if let Some((_, d)) = assq(feature.into(), globals.Vafter_load_alist).into() {
-            Fmapc(Qfuncall, d);
+            mapc(Qfuncall, d);
         }
     }
     feature.into()
@@ -93,25 +92,12 @@ pub fn quote(args: LispCons) -> LispObject {
     args.car()
 }
 
-/// Apply FUNCTION to each element of SEQUENCE, and make a list of the
-/// results.  The result is a list just as long as SEQUENCE.  SEQUENCE
-/// may be a list, a vector, a bool-vector, or a string.
-#[lisp_fn]
-pub fn mapc(function: LispObject, sequence: LispObject) -> LispObject {
-    let leni = length(sequence) as EmacsInt;
-    if sequence.is_char_table() {
-        wrong_type!(Qlistp, sequence);
-    }
-    mapcar1(leni, ptr::null_mut(), function, sequence);
-    sequence
-}
-
 /* List of features currently being require'd, innermost first.  */
 
 declare_GC_protected_static!(require_nesting_list, Qnil);
 
-unsafe extern "C" fn require_unwind(old_value: LispObject) {
-    require_nesting_list = old_value;
+extern "C" fn require_unwind(old_value: LispObject) {
+    require_nesting_list.set(old_value);
 }
 
 /// If feature FEATURE is not loaded, load it from FILENAME.
@@ -172,7 +158,8 @@ pub fn require(feature: LispObject, filename: LispObject, noerror: LispObject) -
     // A certain amount of recursive `require' is legitimate,
     // but if we require the same feature recursively 3 times,
     // signal an error.
-    let nesting = unsafe { require_nesting_list }
+    let nesting = require_nesting_list
+        .get()
         .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
         .filter(|elt| equal(feature, *elt))
         .count();
@@ -186,8 +173,8 @@ pub fn require(feature: LispObject, filename: LispObject, noerror: LispObject) -
 
     unsafe {
         // Update the list for any nested `require's that occur.
-        record_unwind_protect(Some(require_unwind), require_nesting_list);
-        require_nesting_list = (feature, require_nesting_list).into();
+        record_unwind_protect(Some(require_unwind), require_nesting_list.get());
+        require_nesting_list.set((feature, require_nesting_list.get()).into());
 
         // Value saved here is to be restored into Vautoload_queue
         record_unwind_protect(Some(un_autoload), Vautoload_queue);
@@ -240,92 +227,93 @@ pub fn require(feature: LispObject, filename: LispObject, noerror: LispObject) -
 }
 def_lisp_sym!(Qrequire, "require");
 
-/// Concatenate all the arguments and make the result a list.
-/// The result is a list whose elements are the elements of all the arguments.
-/// Each argument may be a list, vector or string.
-/// The last argument is not copied, just used as the tail of the new list.
-/// usage: (append &rest SEQUENCES)
-#[lisp_fn]
-pub fn append(args: &mut [LispObject]) -> LispObject {
-    unsafe {
-        lisp_concat(
-            args.len() as isize,
-            args.as_mut_ptr() as *mut LispObject,
-            Lisp_Type::Lisp_Cons,
-            true,
-        )
-    }
-}
-
-/// Concatenate all the arguments and make the result a string.
-/// The result is a string whose elements are the elements of all the arguments.
-/// Each argument may be a string or a list or vector of characters (integers).
-/// usage: (concat &rest SEQUENCES)
-#[lisp_fn]
-pub fn concat(args: &mut [LispObject]) -> LispObject {
-    unsafe {
-        lisp_concat(
-            args.len() as isize,
-            args.as_mut_ptr() as *mut LispObject,
-            Lisp_Type::Lisp_String,
-            false,
-        )
-    }
-}
-
+/// Return true if O1 and O2 are equal.  Do not quit or check for cycles.
+/// Use this only on arguments that are cycle-free and not too large and
+/// are not window configurations.
 #[no_mangle]
-pub extern "C" fn internal_equal_cons(
-    o1: LispObject,
-    o2: LispObject,
-    kind: equal_kind::Type,
-    depth: i32,
-    ht: LispObject,
-) -> bool {
-    match (o1.as_cons(), o2.as_cons()) {
-        (Some(cons1), Some(cons2)) => cons1.equal(cons2, kind, depth, ht),
-        _ => false,
-    }
+pub extern "C" fn equal_no_quit(o1: LispObject, o2: LispObject) -> bool {
+    internal_equal(o1, o2, equal_kind::EQUAL_NO_QUIT, 0, Qnil)
 }
 
+/// Return true if O1 and O2 are equal.  KIND specifies what kind of
+/// equality test to use: if it is `EQUAL_NO_QUIT', do not check for
+/// cycles or large arguments or quits; if `EQUAL_PLAIN', do ordinary
+/// Lisp equality; and if `EQUAL_INCLUDING_PROPERTIES', do
+/// equal-including-properties.
+///
+/// DEPTH is the current depth of recursion; signal an error if it gets
+/// too deep.  HT is a hash table used to detect cycles; if nil, it has
+/// not been allocated yet.  But ignore the last two arguments if KIND
+/// is `EQUAL_NO_QUIT'.
 #[no_mangle]
-pub extern "C" fn internal_equal_string(
+pub extern "C" fn internal_equal(
     o1: LispObject,
     o2: LispObject,
     kind: equal_kind::Type,
     depth: i32,
-    ht: LispObject,
+    mut ht: LispObject,
 ) -> bool {
-    let s1 = o1.as_string_or_error();
-    let s2 = o2.as_string_or_error();
+    if depth > 10 {
+        debug_assert!(kind != equal_kind::EQUAL_NO_QUIT);
+        if depth > 200 {
+            error!("Stack overflow in equal");
+        }
+        if ht.is_nil() {
+            ht = LispHashTableRef::make_eq().into();
+        }
+        match o1.get_type() {
+            Lisp_Type::Lisp_Cons | Lisp_Type::Lisp_Misc | Lisp_Type::Lisp_Vectorlike => {
+                if compared_before(ht.into(), o1, o2) {
+                    return true;
+                }
+            }
+            _ => {}
+        }
+    }
 
-    s1.equal(s2, kind, depth, ht)
-}
+    if o1.eq(o2) {
+        return true;
+    }
+    if o1.get_type() != o2.get_type() {
+        return false;
+    }
 
-#[no_mangle]
-pub extern "C" fn internal_equal_misc(
-    o1: LispObject,
-    o2: LispObject,
-    kind: equal_kind::Type,
-    depth: i32,
-    ht: LispObject,
-) -> bool {
-    match (o1.as_misc(), o2.as_misc()) {
-        (Some(m1), Some(m2)) => m1.equal(m2, kind, depth, ht),
+    match o1.get_type() {
+        Lisp_Type::Lisp_Float => {
+            let (d1, d2) = (o1.as_float_or_error(), o2.as_float_or_error());
+            // If d is a NaN, then d != d.  Two NaNs should be `equal' even
+            // though they are not =.
+            d1 == d2 || (d1.is_nan() && d2.is_nan())
+        }
+        Lisp_Type::Lisp_Cons => o1.force_cons().equal(o2.force_cons(), kind, depth, ht),
+        Lisp_Type::Lisp_Misc => o1.force_misc().equal(o2.force_misc(), kind, depth, ht),
+        Lisp_Type::Lisp_Vectorlike => {
+            o1.force_vectorlike()
+                .equal(o2.force_vectorlike(), kind, depth, ht)
+        }
+        Lisp_Type::Lisp_String => o1.force_string().equal(o2.force_string(), kind, depth, ht),
         _ => false,
     }
 }
 
-#[no_mangle]
-pub extern "C" fn internal_equal_vectorlike(
-    o1: LispObject,
-    o2: LispObject,
-    kind: equal_kind::Type,
-    depth: i32,
-    ht: LispObject,
-) -> bool {
-    match (o1.as_vectorlike(), o2.as_vectorlike()) {
-        (Some(v1), Some(v2)) => v1.equal(v2, kind, depth, ht),
-        _ => false,
+/// Record in the table HT that O1 is compared with O2, and return true
+/// if it was before.  Then the comparison went around a cycle, and O1
+/// and O2 are equal as far as the comparison in progress is concerned.
+fn compared_before(ht: LispHashTableRef, o1: LispObject, o2: LispObject) -> bool {
+    match ht.lookup(o1) {
+        HashLookupResult::Found(idx) => {
+            let others = ht.get_hash_value(idx);
+            if memq(o2, others).is_not_nil() {
+                true
+            } else {
+                ht.set_hash_value(idx, (o2, others).into());
+                false
+            }
+        }
+        HashLookupResult::Missing(hash) => {
+            ht.put(o1, list!(o2), hash);
+            false
+        }
     }
 }
 
@@ -0,0 +1,399 @@
+//! Applying frame parameters to window system frames.
+//!
+//! `modify-frame-parameters' hands the parameters of window system
+//! frames to `x_set_frame_parameters', which stores each in the frame's
+//! parameter alist and calls its handler.  The handlers are found in
+//! FRAME_PARAMETERS: most are functions of the window system, the rest
+//! are common to all of them.  The size and position parameters have no
+//! handlers; they are acted on together, once everything they depend on
+//! has been set.
+
+use crate::{
+    frames::LispFrameRef,
+    lisp::LispObject,
+    lists::{assq, car, cdr, get, put, LispConsCircularChecks, LispConsEndChecks},
+    obarray::intern,
+    remacs_sys::{
+        frame_call_parm_handler, frame_float_type, frame_float_value, frame_set_fullscreen,
+        frame_set_icon_position, frame_set_position, frame_size_history_add, get_frame_param,
+        store_frame_param, EmacsInt,
+    },
+    remacs_sys::{
+        Qbackground_color, Qfont, Qforeground_color, Qfullscreen, Qheight, Qicon_left, Qicon_top,
+        Qleft, Qminus, Qplus, Qtext_pixels, Qtop, Qwidth, Qx_frame_parameter,
+        Qx_set_frame_parameters, Qx_set_fullscreen,
+    },
+    symbols::LispSymbolRef,
+};
+
+/// What acts on a new value of a frame parameter.
+#[derive(Clone, Copy)]
+enum Handler {
+    /// The function of the frame's window system at the index of the
+    /// parameter in its `frame_parm_handlers'.
+    WindowSystem,
+    /// A function for all window systems, called with the frame, the new
+    /// value and the old value.
+    Common(fn(LispFrameRef, LispObject, LispObject)),
+}
+use self::Handler::{Common, WindowSystem};
+
+/// The frame parameters that have handlers.  The symbol of each has its
+/// index here as its `x-frame-parameter' property.
+///
+/// The parameters handled by the window systems come first, in the order
+/// of the `frame_parm_handlers' arrays in xfns.c, w32fns.c and nsfns.m.
+/// A parameter that all window systems act on alike only needs a Common
+/// handler at the end.
+const FRAME_PARAMETERS: &[(&str, Handler)] = &[
+    ("background-color", WindowSystem),
+    ("border-color", WindowSystem),
+    ("border-width", WindowSystem),
+    ("cursor-color", WindowSystem),
+    ("cursor-type", WindowSystem),
+    ("font", WindowSystem),
+    ("foreground-color", WindowSystem),
+    ("icon-name", WindowSystem),
+    ("icon-type", WindowSystem),
+    ("internal-border-width", WindowSystem),
+    ("right-divider-width", WindowSystem),
+    ("bottom-divider-width", WindowSystem),
+    ("menu-bar-lines", WindowSystem),
+    ("mouse-color", WindowSystem),
+    ("name", WindowSystem),
+    ("scroll-bar-width", WindowSystem),
+    ("scroll-bar-height", WindowSystem),
+    ("title", WindowSystem),
+    ("vertical-scroll-bars", WindowSystem),
+    ("horizontal-scroll-bars", WindowSystem),
+    ("visibility", WindowSystem),
+    ("tool-bar-lines", WindowSystem),
+    ("scroll-bar-foreground", WindowSystem),
+    ("scroll-bar-background", WindowSystem),
+    ("screen-gamma", WindowSystem),
+    ("line-spacing", WindowSystem),
+    ("left-fringe", WindowSystem),
+    ("right-fringe", WindowSystem),
+    ("wait-for-wm", WindowSystem),
+    ("fullscreen", WindowSystem),
+    ("font-backend", WindowSystem),
+    ("alpha", WindowSystem),
+    ("sticky", WindowSystem),
+    ("tool-bar-position", WindowSystem),
+    ("inhibit-double-buffering", WindowSystem),
+    ("undecorated", WindowSystem),
+    ("parent-frame", WindowSystem),
+    ("skip-taskbar", WindowSystem),
+    ("no-focus-on-map", WindowSystem),
+    ("no-accept-focus", WindowSystem),
+    ("z-group", WindowSystem),
+    ("override-redirect", WindowSystem),
+    ("no-special-glyphs", WindowSystem),
+    ("ns-appearance", WindowSystem),
+    ("ns-transparent-titlebar", WindowSystem),
+    ("auto-raise", Common(set_auto_raise)),
+    ("auto-lower", Common(set_auto_lower)),
+    ("unsplittable", Common(set_unsplittable)),
+];
+
+fn set_auto_raise(mut f: LispFrameRef, new_value: LispObject, _old_value: LispObject) {
+    f.set_auto_raise(new_value.is_not_nil());
+}
+
+fn set_auto_lower(mut f: LispFrameRef, new_value: LispObject, _old_value: LispObject) {
+    f.set_auto_lower(new_value.is_not_nil());
+}
+
+fn set_unsplittable(mut f: LispFrameRef, new_value: LispObject, _old_value: LispObject) {
+    f.set_no_split(new_value.is_not_nil());
+}
+
+/// Call the handler of the parameter PARAMETER of the window system
+/// frame F, if it has one, with NEW_VALUE and OLD_VALUE.
+#[no_mangle]
+pub extern "C" fn handle_frame_parameter(
+    mut f: LispFrameRef,
+    parameter: LispObject,
+    new_value: LispObject,
+    old_value: LispObject,
+) {
+    let index = get(LispSymbolRef::from(parameter), Qx_frame_parameter);
+    let index = match index.as_fixnum() {
+        Some(index) if index >= 0 => index as usize,
+        _ => return,
+    };
+    match FRAME_PARAMETERS.get(index) {
+        Some((_, WindowSystem)) => unsafe {
+            frame_call_parm_handler(f.as_mut(), index as isize, new_value, old_value)
+        },
+        Some((_, Common(handler))) => handler(f, new_value, old_value),
+        None => {}
+    }
+}
+
+/// The size and position parameters of a frame, which are acted on once
+/// all other parameters have been set.
+struct Geometry {
+    /// The text size in pixels, if given.
+    width: Option<i32>,
+    height: Option<i32>,
+    left: Option<LispObject>,
+    top: Option<LispObject>,
+    icon_left: Option<LispObject>,
+    icon_top: Option<LispObject>,
+    fullscreen: Option<LispObject>,
+    /// Whether the size of the parent frame or display and the outer
+    /// size of the frame have been looked up, for float values.
+    parent_done: i32,
+    outer_done: i32,
+}
+
+impl Geometry {
+    fn new() -> Self {
+        Self {
+            width: None,
+            height: None,
+            left: None,
+            top: None,
+            icon_left: None,
+            icon_top: None,
+            fullscreen: None,
+            parent_done: -1,
+            outer_done: -1,
+        }
+    }
+
+    /// Return the pixels that the float VAL of the parameter WHAT means
+    /// for F, or DEFAULT if it means nothing.
+    fn float_value(
+        &mut self,
+        mut f: LispFrameRef,
+        val: LispObject,
+        what: frame_float_type::Type,
+        default: i32,
+    ) -> i32 {
+        unsafe {
+            frame_float_value(
+                f.as_mut(),
+                val,
+                what,
+                &mut self.parent_done,
+                &mut self.outer_done,
+                default,
+            )
+        }
+    }
+
+    /// Return the text width or height in pixels that VAL means for F,
+    /// where CELL is the width of a column or the height of a line.
+    fn size(
+        &mut self,
+        f: LispFrameRef,
+        val: LispObject,
+        what: frame_float_type::Type,
+        cell: i32,
+    ) -> Option<i32> {
+        if let Some(n) = int_value(val).filter(|&n| n >= 0) {
+            Some(n.saturating_mul(cell))
+        } else if val.is_cons() && car(val).eq(Qtext_pixels) {
+            int_value(cdr(val)).filter(|&n| n >= 0)
+        } else if val.is_float() {
+            Some(self.float_value(f, val, what, -1))
+        } else {
+            None
+        }
+    }
+
+    /// Return the position in pixels that the value VAL of the parameter
+    /// `left' or `top' means for F, and whether it is relative to the
+    /// right or bottom edge of the display.
+    fn position(
+        &mut self,
+        f: LispFrameRef,
+        val: LispObject,
+        what: frame_float_type::Type,
+    ) -> (i32, bool) {
+        // (- N) or (+ N).
+        let signed = |val: LispObject| -> Option<(i32, bool)> {
+            let (sign, rest) = val.as_cons()?.into();
+            let n = int_value(rest.as_cons()?.car())?;
+            if sign.eq(Qminus) && n != i32::min_value() {
+                Some((-n, true))
+            } else if sign.eq(Qplus) {
+                Some((n, false))
+            } else {
+                None
+            }
+        };
+
+        if val.eq(Qminus) {
+            (0, true)
+        } else if let Some(n) = int_value(val) {
+            (n, n < 0)
+        } else if let Some(position) = signed(val) {
+            position
+        } else if val.is_float() {
+            (self.float_value(f, val, what, 0), false)
+        } else {
+            (0, false)
+        }
+    }
+
+    /// Record the value VAL of the parameter PROP, and return true, if it
+    /// is a size or position parameter.
+    fn record(&mut self, f: LispFrameRef, prop: LispObject, val: LispObject) -> bool {
+        if prop.eq(Qwidth) {
+            let width = self.size(f, val, frame_float_type::FRAME_FLOAT_WIDTH, f.column_width);
+            self.width = width.or(self.width);
+        } else if prop.eq(Qheight) {
+            let height = self.size(f, val, frame_float_type::FRAME_FLOAT_HEIGHT, f.line_height);
+            self.height = height.or(self.height);
+        } else if prop.eq(Qtop) {
+            self.top = Some(val);
+        } else if prop.eq(Qleft) {
+            self.left = Some(val);
+        } else if prop.eq(Qicon_top) {
+            self.icon_top = Some(val);
+        } else if prop.eq(Qicon_left) {
+            self.icon_left = Some(val);
+        } else if prop.eq(Qfullscreen) {
+            self.fullscreen = Some(val);
+        } else {
+            return false;
+        }
+        true
+    }
+
+    /// Resize and move F as recorded.
+    fn apply(mut self, mut f: LispFrameRef) {
+        // Don't resize or move F unless asked to, as it may have been
+        // resized or moved meanwhile, nor if it already has that size or
+        // position, as its window may not exist yet.  A float size that
+        // means nothing is -1.
+        let changed = |size: Option<i32>, current: i32| {
+            size.map_or(false, |size| size != -1 && size != current)
+        };
+        if changed(self.width, f.text_width) || changed(self.height, f.text_height) {
+            f.set_text_pixel_size(
+                self.width.unwrap_or(-1),
+                self.height.unwrap_or(-1),
+                false,
+                Qx_set_frame_parameters,
+            );
+        }
+
+        if self.left.is_some() || self.top.is_some() {
+            let left = self.left.unwrap_or_else(|| current_position(f.left_pos));
+            let top = self.top.unwrap_or_else(|| current_position(f.top_pos));
+            let unchanged = left.as_fixnum() == Some(EmacsInt::from(f.left_pos))
+                && top.as_fixnum() == Some(EmacsInt::from(f.top_pos));
+            if (left.is_not_nil() || top.is_not_nil()) && !unchanged {
+                let (left, x_negative) = self.position(f, left, frame_float_type::FRAME_FLOAT_LEFT);
+                let (top, y_negative) = self.position(f, top, frame_float_type::FRAME_FLOAT_TOP);
+                unsafe { frame_set_position(f.as_mut(), left, top, x_negative, y_negative) };
+            }
+        }
+
+        if let Some(fullscreen) = self.fullscreen {
+            let old_value = unsafe { get_frame_param(f.as_mut(), Qfullscreen) };
+            unsafe {
+                frame_size_history_add(
+                    f.as_mut(),
+                    Qx_set_fullscreen,
+                    0,
+                    0,
+                    list!(old_value, fullscreen),
+                );
+                store_frame_param(f.as_mut(), Qfullscreen, fullscreen);
+            }
+            if !fullscreen.eq(old_value) {
+                unsafe { frame_set_fullscreen(f.as_mut(), fullscreen, old_value) };
+            }
+        }
+
+        // A missing icon position is left as it is, or defaults to 0.
+        let icon_left = self.icon_left.and_then(int_value);
+        let icon_top = self.icon_top.and_then(int_value);
+        if icon_left.is_some() || icon_top.is_some() {
+            let icon_position = |given: Option<i32>, parameter| {
+                given.unwrap_or_else(|| int_value(cdr(assq(parameter, f.param_alist))).unwrap_or(0))
+            };
+            let left = icon_position(icon_left, Qicon_left);
+            let top = icon_position(icon_top, Qicon_top);
+            unsafe { frame_set_icon_position(f.as_mut(), left, top) };
+        }
+    }
+}
+
+/// Return VAL if it is an integer in the range of a C int.
+fn int_value(val: LispObject) -> Option<i32> {
+    val.as_fixnum()
+        .filter(|&n| n >= EmacsInt::from(i32::min_value()) && n <= EmacsInt::from(i32::max_value()))
+        .map(|n| n as i32)
+}
+
+/// Return the value of the parameter `left' or `top' that means POS.
+fn current_position(pos: i32) -> LispObject {
+    if pos < 0 {
+        list!(Qplus, pos)
+    } else {
+        LispObject::from(pos)
+    }
+}
+
+/// Change the parameters of the window system frame F as specified by
+/// ALIST.  Each parameter is stored in the parameter alist of F and its
+/// handler called, except for the size and position parameters, which
+/// are acted on at the end.
+#[no_mangle]
+pub extern "C" fn x_set_frame_parameters(mut f: LispFrameRef, alist: LispObject) {
+    let params: Vec<(LispObject, LispObject)> = alist
+        .iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on)
+        .map(|elt| (car(elt), cdr(elt)))
+        .collect();
+
+    // The cursor color depends on the foreground and background colors,
+    // and the fringe widths on the font, so these come first.  The rest
+    // are set last to first, so that earlier elements of ALIST win.
+    let (first, rest): (Vec<_>, Vec<_>) = params.into_iter().partition(|&(prop, _)| {
+        prop.eq(Qforeground_color) || prop.eq(Qbackground_color) || prop.eq(Qfont)
+    });
+
+    let mut geometry = Geometry::new();
+    for (prop, val) in first.into_iter().chain(rest.into_iter().rev()) {
+        if geometry.record(f, prop, val) {
+            continue;
+        }
+        let old_value = unsafe { get_frame_param(f.as_mut(), prop) };
+        unsafe { store_frame_param(f.as_mut(), prop, val) };
+        handle_frame_parameter(f, prop, val, old_value);
+    }
+
+    geometry.apply(f);
+}
+
+#[no_mangle]
+pub extern "C" fn syms_of_frame_parameters() {
+    for (index, &(name, _)) in FRAME_PARAMETERS.iter().enumerate() {
+        put(intern(name), Qx_frame_parameter, LispObject::from(index));
+    }
+}
+
+#[test]
+fn test_window_system_parameters_first() {
+    // The index of a parameter is also its index in the
+    // `frame_parm_handlers' of the window systems.
+    let first_common = FRAME_PARAMETERS
+        .iter()
+        .position(|&(_, handler)| match handler {
+            Common(_) => true,
+            WindowSystem => false,
+        })
+        .unwrap_or_else(|| FRAME_PARAMETERS.len());
+    assert!(FRAME_PARAMETERS[first_common..]
+        .iter()
+        .all(|&(_, handler)| match handler {
+            Common(_) => true,
+            WindowSystem => false,
+        }));
+}
@@ -1,22 +1,58 @@
 //! Generic frame functions.
 
-use libc::c_int;
+use std::{mem, ptr, slice};
+
+use libc::{c_char, c_int};
 
 use remacs_macros::lisp_fn;
 
 use crate::{
+    eval::run_hook,
     lisp::defsubr,
     lisp::{ExternalPtr, LispObject},
     lists::{LispConsCircularChecks, LispConsEndChecks},
+    obarray::intern,
+    remacs_sys::selected_window as current_window,
     remacs_sys::Vframe_list,
+    remacs_sys::{adjust_frame_size, fget_parent_frame, frame_set_offset, Fframe_parameter},
     remacs_sys::{candidate_frame, delete_frame as c_delete_frame, frame_dimension, output_method},
+    remacs_sys::{check_minibuf_window, minibuf_window, other_frames, windows_or_buffers_changed},
+    remacs_sys::{current_kboard, globals, internal_last_event_frame, last_nonminibuf_frame},
+    remacs_sys::{
+        fget_tooltip, format_frame_title, frame_implicitly_set_name, kboard, make_string,
+    },
+    remacs_sys::{frame_display_focus_frame, frame_make_tty_top_frame, resize_mini_window},
+    remacs_sys::{frame_iconify, frame_make_invisible, frame_make_visible},
+    remacs_sys::{frame_warp_mouse, pixel_to_glyph_coords},
     remacs_sys::{pvec_type, selected_frame as current_frame, Lisp_Frame, Lisp_Type},
-    remacs_sys::{Qframe_live_p, Qframep, Qicon, Qnil, Qns, Qpc, Qt, Qw32, Qx},
+    remacs_sys::{scroll_bar_part, Time},
+    remacs_sys::{
+        Qdisplay, Qframe_live_p, Qframep, Qheight, Qicon, Qiconify_top_level, Qmake_invisible,
+        Qmouse_leave_buffer_hook, Qnil, Qns, Qpc, Qsize, Qswitch_frame, Qt, Qw32, Qwidth, Qx,
+    },
+    time::current_time,
     windows::{select_window_lisp, selected_window, LispWindowRef},
 };
 
 pub type LispFrameRef = ExternalPtr<Lisp_Frame>;
 
+/// The states `LispFrameRef::set_visibility` can put a frame in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Visibility {
+    Visible,
+    Invisible,
+    Iconified,
+}
+
+macro_rules! for_each_frame {
+    ($name:ident => $action:block) => {
+        let frame_it = unsafe { Vframe_list.iter_cars(LispConsEndChecks::off,
+                                                      LispConsCircularChecks::off) };
+        for $name in frame_it.map(|f| f.as_frame_or_error())
+            $action
+    };
+}
+
 impl LispFrameRef {
     pub fn is_live(self) -> bool {
         !self.terminal.is_null()
@@ -34,6 +70,163 @@ impl LispFrameRef {
     pub fn total_fringe_width(self) -> i32 {
         self.left_fringe_width + self.right_fringe_width
     }
+
+    // Same as FRAME_HAS_MINIBUF_P
+    pub fn has_minibuffer(self) -> bool {
+        self.minibuffer_window
+            .as_window()
+            .map_or(false, |w| w.frame.as_frame() == Some(self))
+    }
+
+    // Same as FRAME_MINIBUF_ONLY_P
+    pub fn is_minibuffer_only(self) -> bool {
+        self.root_window.eq(self.minibuffer_window)
+    }
+
+    // Same as FRAME_WINDOW_P
+    pub fn is_gui_window(self) -> bool {
+        match self.output_method() {
+            output_method::output_x_window
+            | output_method::output_w32
+            | output_method::output_ns => true,
+            _ => false,
+        }
+    }
+
+    /// Convert WIDTH in columns to pixels, unless PIXELWISE is set in
+    /// which case WIDTH is already in pixels.
+    pub fn width_in_pixels(self, width: i32, pixelwise: bool) -> i32 {
+        if pixelwise {
+            width
+        } else {
+            width * self.column_width
+        }
+    }
+
+    /// Convert HEIGHT in lines to pixels, unless PIXELWISE is set in
+    /// which case HEIGHT is already in pixels.
+    pub fn height_in_pixels(self, height: i32, pixelwise: bool) -> i32 {
+        if pixelwise {
+            height
+        } else {
+            height * self.line_height
+        }
+    }
+
+    /// Convert WIDTH in pixels to columns, rounding up if CEILING and
+    /// down otherwise.
+    pub fn pixels_to_columns(self, width: i32, ceiling: bool) -> i32 {
+        pixels_to_units(width, self.column_width, ceiling)
+    }
+
+    /// Convert HEIGHT in pixels to lines, rounding up if CEILING and
+    /// down otherwise.
+    pub fn pixels_to_lines(self, height: i32, ceiling: bool) -> i32 {
+        pixels_to_units(height, self.line_height, ceiling)
+    }
+
+    /// Resize the text area of the frame to WIDTH by HEIGHT pixels and
+    /// redistribute the space among its windows. A dimension of -1 is
+    /// left unchanged.
+    pub fn set_text_pixel_size(
+        mut self,
+        width: i32,
+        height: i32,
+        pretend: bool,
+        parameter: LispObject,
+    ) {
+        unsafe { adjust_frame_size(self.as_mut(), width, height, 1, pretend, parameter) };
+    }
+
+    /// Show, hide or iconify the frame on its window system.  Frames on
+    /// text terminals are always considered visible, so for them this
+    /// only records that the buffers they show were displayed when
+    /// making them visible.
+    pub fn set_visibility(mut self, visibility: Visibility) {
+        unsafe {
+            match visibility {
+                Visibility::Visible => frame_make_visible(self.as_mut()),
+                Visibility::Invisible => frame_make_invisible(self.as_mut()),
+                Visibility::Iconified => frame_iconify(self.as_mut()),
+            }
+        }
+
+        if visibility == Visibility::Visible {
+            update_display_times(self.root_window);
+        }
+    }
+
+    /// The frame whose window-system window is the parent of this
+    /// frame's window, if this is a child frame. Always `None` on
+    /// builds without a window system.
+    pub fn parent_frame(self) -> Option<LispFrameRef> {
+        unsafe { fget_parent_frame(self.as_ptr()) }.as_frame()
+    }
+
+    pub fn is_child_frame(self) -> bool {
+        self.parent_frame().is_some()
+    }
+
+    // Same as FRAME_TOOLTIP_P
+    pub fn is_tooltip(self) -> bool {
+        unsafe { fget_tooltip(self.as_ptr()) }
+    }
+
+    // Same as FRAME_KBOARD
+    pub fn kboard(self) -> *mut kboard {
+        unsafe { (*self.terminal).kboard }
+    }
+
+    /// Iterate over the parent of this frame, its parent, and so on up
+    /// to the top-level frame.
+    pub fn ancestors(self) -> FrameAncestors {
+        FrameAncestors {
+            current: self.parent_frame(),
+        }
+    }
+
+    /// Same as `frame_ancestor_p`: true if this frame is the parent of
+    /// DESCENDANT or an ancestor of its parent.
+    pub fn is_ancestor_of(self, descendant: LispFrameRef) -> bool {
+        descendant.ancestors().any(|f| f == self)
+    }
+
+    /// The live frames whose parent frame is this frame.
+    pub fn child_frames(self) -> Vec<LispFrameRef> {
+        let mut children = Vec::new();
+        for_each_frame!(f => {
+            if f.parent_frame() == Some(self) {
+                children.push(f);
+            }
+        });
+        children
+    }
+}
+
+pub struct FrameAncestors {
+    current: Option<LispFrameRef>,
+}
+
+/// Return how many UNITs of pixels PIXELS are, rounding up if CEILING.
+/// The units of a frame change when it moves to a monitor with another
+/// scale factor, and may be 0 before its font is set up.
+fn pixels_to_units(pixels: i32, unit: i32, ceiling: bool) -> i32 {
+    let unit = unit.max(1);
+    if ceiling {
+        (pixels + unit - 1) / unit
+    } else {
+        pixels / unit
+    }
+}
+
+impl Iterator for FrameAncestors {
+    type Item = LispFrameRef;
+
+    fn next(&mut self) -> Option<LispFrameRef> {
+        let frame = self.current?;
+        self.current = frame.parent_frame();
+        Some(frame)
+    }
 }
 
 impl From<LispObject> for LispFrameRef {
@@ -81,15 +274,6 @@ impl LispObject {
     }
 }
 
-macro_rules! for_each_frame {
-    ($name:ident => $action:block) => {
-        let frame_it = unsafe { Vframe_list.iter_cars(LispConsEndChecks::off,
-                                                      LispConsCircularChecks::off) };
-        for $name in frame_it.map(|f| f.as_frame_or_error())
-            $action
-    };
-}
-
 #[derive(Clone, Copy)]
 pub enum LispFrameOrSelected {
     Frame(LispFrameRef),
@@ -238,6 +422,273 @@ pub fn set_frame_selected_window(
     }
 }
 
+/// Perform the switch to frame FRAME.
+///
+/// If FRAME is a switch-frame event `(switch-frame FRAME1)', use
+/// FRAME1 as frame.
+///
+/// If TRACK is non-zero and the frame that currently has the focus
+/// redirects its focus to the selected frame, redirect that focused
+/// frame's focus to FRAME instead.
+///
+/// FOR_DELETION non-zero means that the selected frame is being
+/// deleted, which includes the possibility that the frame's terminal
+/// is dead.
+///
+/// The value of NORECORD is passed as argument to `select-window'.
+#[no_mangle]
+pub extern "C" fn do_switch_frame(
+    mut frame: LispObject,
+    track: c_int,
+    for_deletion: c_int,
+    norecord: LispObject,
+) -> LispObject {
+    let sf = selected_frame();
+
+    // If FRAME is a switch-frame event, extract the frame we should
+    // switch to.
+    if let Some(event) = frame.as_cons() {
+        if event.car().eq(Qswitch_frame) {
+            if let Some(args) = event.cdr().as_cons() {
+                frame = args.car();
+            }
+        }
+    }
+
+    // This used to say CHECK_LIVE_FRAME, but apparently it's possible for
+    // a switch-frame event to arrive after a frame is no longer live,
+    // especially when deleting the initial frame during startup.
+    let mut f = frame.as_frame_or_error();
+    if !f.is_live() {
+        return Qnil;
+    } else if f == sf {
+        return frame;
+    }
+
+    // If the focus of the frame that has the window-system focus is
+    // redirected toward the selected frame, redirect it to FRAME instead.
+    // Only the frame we're pointing to is affected; doing this for all
+    // frames is too greedy.
+    if track != 0 {
+        let xfocus = unsafe { frame_display_focus_frame(f.as_mut()) };
+        if let Some(xfocus) = xfocus.as_frame() {
+            let focus = xfocus.focus_frame;
+            if focus.as_frame() == Some(sf)
+                // Redirect frame focus also when FRAME has its minibuffer
+                // window on the selected frame (see Bug#24500).
+                || (focus.is_nil() && f.minibuffer_window.eq(sf.selected_window))
+            {
+                redirect_frame_focus(LispFrameOrSelected::Frame(xfocus), frame);
+            }
+        }
+    }
+
+    if for_deletion == 0 && sf.has_minibuffer() {
+        let mut mini_window = sf.minibuffer_window.as_window_or_error();
+        unsafe { resize_mini_window(mini_window.as_mut(), true) };
+    }
+
+    if f.output_method() == output_method::output_termcap {
+        unsafe { frame_make_tty_top_frame(f.as_mut()) };
+    }
+
+    unsafe {
+        current_frame = frame;
+        if !f.is_minibuffer_only() {
+            last_nonminibuf_frame = f.as_mut();
+        }
+    }
+
+    select_window_lisp(f.selected_window, norecord);
+
+    // We want to make sure that the next event generates a frame-switch
+    // event to the appropriate frame.  This seems kludgy to me, but
+    // before you take it out, make sure that evaluating something like
+    // (select-window (frame-root-window (make-frame))) doesn't end up
+    // with your typing being interpreted in the new frame instead of
+    // the one you're actually typing in.
+    if !f.is_ancestor_of(sf) {
+        unsafe { internal_last_event_frame = Qnil };
+    }
+
+    frame
+}
+
+/// Set the title of FRAME, if it has changed.  The title format is
+/// `icon-title-format' if FRAME is iconified, otherwise it is
+/// `frame-title-format'.
+#[no_mangle]
+pub extern "C" fn consider_frame_title(frame: LispObject) {
+    let mut f = frame.as_frame_or_error();
+    if !(f.is_gui_window() || f.is_minibuffer_only() || f.explicit_name()) || f.is_tooltip() {
+        return;
+    }
+
+    // Do we have more than one visible frame on this X display?
+    let mut multiple_frames = false;
+    for_each_frame!(tf => {
+        if tf != f
+            && tf.kboard() == f.kboard()
+            && !tf.is_minibuffer_only()
+            && !tf.is_child_frame()
+            && !tf.is_tooltip()
+            && (tf.is_visible() || tf.iconified())
+        {
+            multiple_frames = true;
+        }
+    });
+    unsafe { globals.multiple_frames = multiple_frames };
+
+    let format = unsafe {
+        if f.iconified() {
+            globals.Vicon_title_format
+        } else {
+            globals.Vframe_title_format
+        }
+    };
+    let mut len = 0;
+    let title = unsafe {
+        let title = format_frame_title(f.as_mut(), format, &mut len);
+        slice::from_raw_parts(title as *const u8, len as usize)
+    };
+
+    // Set the title only if it's changed.  This avoids consing in the
+    // common case where it hasn't, and the window system redrawing the
+    // title on every redisplay.
+    if f.name
+        .as_string()
+        .map_or(true, |name| name.as_slice() != title)
+    {
+        unsafe {
+            let name = make_string(title.as_ptr() as *const c_char, len);
+            frame_implicitly_set_name(f.as_mut(), name);
+        }
+    }
+}
+
+/// Update the titles of all frames based on their buffer names, etc.
+/// This is done before the menu bars are updated, so that the buffer
+/// menu shows the up-to-date frame titles.  If SOME_WINDOWS, only the
+/// frames whose selected window is to be redisplayed are considered.
+#[no_mangle]
+pub extern "C" fn update_frame_titles(some_windows: bool) {
+    for_each_frame!(f => {
+        let w = f.selected_window.as_window_or_error();
+        if some_windows
+            && !f.redisplay()
+            && !w.redisplay()
+            && !w.contents.as_buffer_or_error().text_needs_redisplay()
+        {
+            continue;
+        }
+
+        // Exclude TTY frames that are obscured because they are not the
+        // top frame on their console.  consider_frame_title switches to
+        // the frame, which for TTY frames means that it is garbaged and
+        // completely redrawn on the next redisplay cycle, even though
+        // nothing on display should change.
+        if !f.is_tooltip()
+            && !f.is_child_frame()
+            && (f.iconified() || f.visible() == 1 || (f.visible() == 2 && f.is_gui_window()))
+        {
+            consider_frame_title(f.into());
+        }
+    });
+}
+
+/// Select FRAME.
+/// Subsequent editing commands apply to its selected window.
+/// Optional argument NORECORD means to neither change the order of
+/// recently selected windows nor the buffer list.
+///
+/// The selection of FRAME lasts until the next time the user does
+/// something to select a different frame, or until the next time
+/// this function is called.  If you are using a window system, the
+/// previously selected frame may be restored as the selected frame
+/// when returning to the command loop, because it still may have
+/// the window system's input focus.  On a text terminal, the next
+/// redisplay will display FRAME.
+///
+/// This function returns FRAME, or nil if FRAME has been deleted.
+#[lisp_fn(min = "1", intspec = "e")]
+pub fn select_frame(frame: LispObject, norecord: LispObject) -> LispObject {
+    do_switch_frame(frame, 1, 0, norecord)
+}
+
+/// Handle a switch-frame event EVENT.
+/// Switch-frame events are usually bound to this function.
+/// A switch-frame event tells Emacs that the window manager has requested
+/// that the user's events be directed to the frame mentioned in the event.
+/// This function selects the selected window of the frame of EVENT.
+///
+/// If EVENT is frame object, handle it as if it were a switch-frame event
+/// to that frame.
+#[lisp_fn(intspec = "^e")]
+pub fn handle_switch_frame(event: LispObject) -> LispObject {
+    // Preserve prefix arg that the command loop just cleared.
+    unsafe { (*current_kboard).Vprefix_arg_ = globals.Vcurrent_prefix_arg };
+    run_hook(Qmouse_leave_buffer_hook);
+
+    // `switch-frame' implies a focus in.
+    let value = do_switch_frame(event, 0, 0, Qnil);
+    call!(intern("handle-focus-in").into(), event);
+    value
+}
+
+/// Arrange for keystrokes typed at FRAME to be sent to FOCUS-FRAME.
+/// In other words, switch-frame events caused by events in FRAME will
+/// request a switch to FOCUS-FRAME, and `last-event-frame' will be
+/// FOCUS-FRAME after reading an event typed at FRAME.
+///
+/// If FOCUS-FRAME is nil, any existing redirection is canceled, and the
+/// frame again receives its own keystrokes.
+///
+/// Focus redirection is useful for temporarily redirecting keystrokes to
+/// a surrogate minibuffer frame when a frame doesn't have its own
+/// minibuffer window.
+///
+/// A frame's focus redirection can be changed by `select-frame'.  If frame
+/// FOO is selected, and then a different frame BAR is selected, any
+/// frames redirecting their focus to FOO are shifted to redirect their
+/// focus to BAR.  This allows focus redirection to work properly when the
+/// user switches from one frame to another using `select-window'.
+///
+/// This means that a frame whose focus is redirected to itself is treated
+/// differently from a frame whose focus is redirected to nil; the former
+/// is affected by `select-frame', while the latter is not.
+///
+/// The redirection lasts until `redirect-frame-focus' is called to change it.
+#[lisp_fn(min = "1")]
+pub fn redirect_frame_focus(frame: LispFrameOrSelected, focus_frame: LispObject) {
+    // Note that we don't check for a live frame here.  It's reasonable
+    // to redirect the focus of a frame you're about to delete, if you
+    // know what other frame should receive those keystrokes.
+    let mut frame: LispFrameRef = frame.into();
+
+    if focus_frame.is_not_nil() {
+        focus_frame.as_live_frame_or_error();
+    }
+
+    frame.focus_frame = focus_frame;
+
+    if frame.is_live() {
+        unsafe {
+            if let Some(hook) = (*frame.terminal).frame_rehighlight_hook {
+                hook(frame.as_mut());
+            }
+        }
+    }
+}
+
+/// Return the frame to which FRAME's keystrokes are currently being sent.
+/// If FRAME is omitted or nil, the selected frame is used.
+/// Return nil if FRAME's focus is not redirected.
+/// See `redirect-frame-focus'.
+#[lisp_fn(min = "0")]
+pub fn frame_focus(frame: LispFrameOrSelected) -> LispObject {
+    frame.live_or_error().focus_frame
+}
+
 /// The name of the window system that FRAME is displaying through.
 /// The value is a symbol:
 ///  nil for a termcap frame (a character-only terminal),
@@ -283,6 +734,235 @@ pub fn frame_visible_p(frame: LispFrameRef) -> LispObject {
     }
 }
 
+/// Update the display_time slot of the buffers shown in WINDOW, the
+/// windows following it and all their descendants.
+fn update_display_times(mut window: LispObject) {
+    while let Some(w) = window.as_window() {
+        if w.contents.is_window() {
+            update_display_times(w.contents);
+        } else if let Some(mut buffer) = w.contents.as_buffer() {
+            buffer.display_time_ = current_time();
+        }
+        window = w.next;
+    }
+}
+
+/// Make the frame FRAME visible (assuming it is an X window).
+/// If omitted, FRAME defaults to the currently selected frame.
+#[lisp_fn(min = "0", intspec = "")]
+pub fn make_frame_visible(frame: LispFrameOrSelected) -> LispFrameRef {
+    let frame = frame.live_or_error();
+    frame.set_visibility(Visibility::Visible);
+
+    // Make menu bar update for the Buffers and Frames menus.
+    // windows_or_buffers_changed = 15; FIXME: Why?
+
+    frame
+}
+
+/// Make the frame FRAME invisible.
+/// If omitted, FRAME defaults to the currently selected frame.
+/// On graphical displays, invisible frames are not updated and are
+/// usually not displayed at all, even in a window system's \"taskbar\".
+///
+/// Normally you may not make FRAME invisible if all other frames are invisible,
+/// but if the second optional argument FORCE is non-nil, you may do so.
+///
+/// This function has no effect on text terminal frames.  Such frames are
+/// always considered visible, whether or not they are currently being
+/// displayed in the terminal.
+#[lisp_fn(min = "0", intspec = "")]
+pub fn make_frame_invisible(frame: LispFrameOrSelected, force: bool) {
+    let mut frame = frame.live_or_error();
+
+    if !force && !unsafe { other_frames(frame.as_mut(), true, false) } {
+        error!("Attempt to make invisible the sole visible or iconified frame");
+    }
+
+    // Don't allow minibuf_window to remain on an invisible frame.
+    unsafe {
+        let select = minibuf_window.eq(current_window);
+        check_minibuf_window(frame.into(), select as c_int);
+    }
+
+    frame.set_visibility(Visibility::Invisible);
+
+    // Make menu bar update for the Buffers and Frames menus.
+    unsafe { windows_or_buffers_changed = 16 };
+}
+
+/// Make the frame FRAME into an icon.
+/// If omitted, FRAME defaults to the currently selected frame.
+///
+/// If FRAME is a child frame, consult the variable `iconify-child-frame'
+/// for how to proceed.
+#[lisp_fn(min = "0", intspec = "")]
+pub fn iconify_frame(frame: LispFrameOrSelected) {
+    let frame = frame.live_or_error();
+
+    if let Some(parent) = frame.parent_frame() {
+        let action = unsafe { globals.iconify_child_frame };
+        if action.is_nil() {
+            // Do nothing.
+            return;
+        } else if action.eq(Qiconify_top_level) {
+            // Iconify top level frame instead (the default).
+            iconify_frame(LispFrameOrSelected::Frame(parent));
+            return;
+        } else if action.eq(Qmake_invisible) {
+            // Make frame invisible instead.
+            make_frame_invisible(LispFrameOrSelected::Frame(frame), false);
+            return;
+        }
+    }
+
+    // Don't allow minibuf_window to remain on an iconified frame.
+    unsafe {
+        let select = minibuf_window.eq(current_window);
+        check_minibuf_window(frame.into(), select as c_int);
+    }
+
+    frame.set_visibility(Visibility::Iconified);
+}
+
+/// Where the mouse is, as reported by the terminal of the selected frame.
+pub struct MousePosition {
+    pub frame: LispFrameRef,
+    /// Pixel coordinates relative to FRAME, or None if the terminal
+    /// doesn't know where the mouse is.
+    pub coords: Option<(i32, i32)>,
+}
+
+impl MousePosition {
+    pub fn query() -> Self {
+        let mut frame = selected_frame();
+        let mut x = Qnil;
+        let mut y = Qnil;
+
+        // It's okay for the hook to refrain from storing anything.
+        if let Some(hook) = unsafe { (*frame.terminal).mouse_position_hook } {
+            let mut f_ptr = frame.as_mut();
+            let mut bar_window = Qnil;
+            let mut part: scroll_bar_part = unsafe { mem::zeroed() };
+            let mut time: Time = 0;
+            unsafe {
+                hook(
+                    &mut f_ptr,
+                    -1,
+                    &mut bar_window,
+                    &mut part,
+                    &mut x,
+                    &mut y,
+                    &mut time,
+                )
+            };
+            frame = LispFrameRef::new(f_ptr);
+        }
+
+        let coords = if x.is_nil() {
+            None
+        } else {
+            Some((x.as_fixnum_or_error() as i32, y.as_fixnum_or_error() as i32))
+        };
+
+        Self { frame, coords }
+    }
+
+    /// The position in canonical character cells.
+    pub fn glyph_coords(&self) -> Option<(i32, i32)> {
+        let mut frame = self.frame;
+        self.coords.map(|(x, y)| {
+            let (mut col, mut row) = (x, y);
+            unsafe {
+                pixel_to_glyph_coords(
+                    frame.as_mut(),
+                    x,
+                    y,
+                    &mut col,
+                    &mut row,
+                    ptr::null_mut(),
+                    true,
+                )
+            };
+            (col, row)
+        })
+    }
+
+    fn to_lisp(&self, coords: Option<(i32, i32)>) -> LispObject {
+        let (x, y) = coords.map_or((Qnil, Qnil), |(x, y)| (x.into(), y.into()));
+        let retval = LispObject::cons(self.frame, LispObject::cons(x, y));
+        let function = unsafe { globals.Vmouse_position_function };
+        if function.is_nil() {
+            retval
+        } else {
+            call!(function, retval)
+        }
+    }
+}
+
+/// Return a list (FRAME X . Y) giving the current mouse frame and position.
+/// The position is given in canonical character cells, where (0, 0) is the
+/// upper-left corner of the frame, X is the horizontal offset, and Y is the
+/// vertical offset, measured in units of the frame's default character size.
+/// If Emacs is running on a mouseless terminal or hasn't been programmed
+/// to read the mouse position, it returns the selected frame for FRAME
+/// and nil for X and Y.
+/// If `mouse-position-function' is non-nil, `mouse-position' calls it,
+/// passing the normal return value to that function as an argument,
+/// and returns whatever that function returns.
+#[lisp_fn]
+pub fn mouse_position() -> LispObject {
+    let position = MousePosition::query();
+    position.to_lisp(position.glyph_coords())
+}
+
+/// Return a list (FRAME X . Y) giving the current mouse frame and position.
+/// The position is given in pixel units, where (0, 0) is the
+/// upper-left corner of the frame, X is the horizontal offset, and Y is
+/// the vertical offset.
+/// If Emacs is running on a mouseless terminal or hasn't been programmed
+/// to read the mouse position, it returns the selected frame for FRAME
+/// and nil for X and Y.
+#[lisp_fn]
+pub fn mouse_pixel_position() -> LispObject {
+    let position = MousePosition::query();
+    position.to_lisp(position.coords)
+}
+
+/// Move the mouse pointer to the center of character cell (X,Y) in FRAME.
+/// Coordinates are relative to the frame, not a window,
+/// so the coordinates of the top left character in the frame
+/// may be nonzero due to left-hand scroll bars or the menu bar.
+///
+/// The position is given in canonical character cells, where (0, 0) is
+/// the upper-left corner of the frame, X is the horizontal offset, and
+/// Y is the vertical offset, measured in units of the frame's default
+/// character size.
+///
+/// This function is a no-op for an X frame that is not visible.
+/// If you have just created a frame, you must wait for it to become visible
+/// before calling this function on it, like this.
+///   (while (not (frame-visible-p frame)) (sleep-for .5))
+#[lisp_fn]
+pub fn set_mouse_position(frame: LispObject, x: i32, y: i32) {
+    let mut frame = frame.as_live_frame_or_error();
+    unsafe { frame_warp_mouse(frame.as_mut(), x, y, false) };
+}
+
+/// Move the mouse pointer to pixel position (X,Y) in FRAME.
+/// The position is given in pixels, where (0, 0) is the upper-left corner
+/// of the frame, X is the horizontal offset, and Y is the vertical offset.
+///
+/// Note, this is a no-op for an X frame that is not visible.
+/// If you have just created a frame, you must wait for it to become visible
+/// before calling this function on it, like this.
+///   (while (not (frame-visible-p frame)) (sleep-for .5))
+#[lisp_fn]
+pub fn set_mouse_pixel_position(frame: LispObject, x: i32, y: i32) {
+    let mut frame = frame.as_live_frame_or_error();
+    unsafe { frame_warp_mouse(frame.as_mut(), x, y, true) };
+}
+
 /// Return top left corner of FRAME in pixels.
 /// FRAME must be a live frame and defaults to the selected one.  The return
 /// value is a cons (x, y) of the coordinates of the top left corner of
@@ -294,6 +974,173 @@ pub fn frame_position(frame: LispFrameOrSelected) -> (c_int, c_int) {
     (frame_ref.left_pos, frame_ref.top_pos)
 }
 
+/// Set text height of frame FRAME to HEIGHT lines.
+/// Optional third arg PRETEND non-nil means that redisplay should use
+/// HEIGHT lines but that the idea of the actual height of the frame should
+/// not be changed.
+///
+/// Optional fourth argument PIXELWISE non-nil means that FRAME should be
+/// HEIGHT pixels high.  Note: When `frame-resize-pixelwise' is nil, some
+/// window managers may refuse to honor a HEIGHT that is not an integer
+/// multiple of the default frame font height.
+#[lisp_fn(min = "2")]
+pub fn set_frame_height(
+    frame: LispFrameOrSelected,
+    height: i32,
+    pretend: bool,
+    pixelwise: bool,
+) -> LispObject {
+    let frame_ref = frame.live_or_error();
+    let pixel_height = frame_ref.height_in_pixels(height, pixelwise);
+    frame_ref.set_text_pixel_size(-1, pixel_height, pretend, Qheight);
+    Qnil
+}
+
+/// Set text width of frame FRAME to WIDTH columns.
+/// Optional third arg PRETEND non-nil means that redisplay should use WIDTH
+/// columns but that the idea of the actual width of the frame should not
+/// be changed.
+///
+/// Optional fourth argument PIXELWISE non-nil means that FRAME should be
+/// WIDTH pixels wide.  Note: When `frame-resize-pixelwise' is nil, some
+/// window managers may refuse to honor a WIDTH that is not an integer
+/// multiple of the default frame font width.
+#[lisp_fn(min = "2")]
+pub fn set_frame_width(
+    frame: LispFrameOrSelected,
+    width: i32,
+    pretend: bool,
+    pixelwise: bool,
+) -> LispObject {
+    let frame_ref = frame.live_or_error();
+    let pixel_width = frame_ref.width_in_pixels(width, pixelwise);
+    frame_ref.set_text_pixel_size(pixel_width, -1, pretend, Qwidth);
+    Qnil
+}
+
+/// Set text size of FRAME to WIDTH by HEIGHT, measured in characters.
+/// Optional argument PIXELWISE non-nil means to measure in pixels.  Note:
+/// When `frame-resize-pixelwise' is nil, some window managers may refuse to
+/// honor a WIDTH that is not an integer multiple of the default frame font
+/// width or a HEIGHT that is not an integer multiple of the default frame
+/// font height.
+#[lisp_fn(min = "3")]
+pub fn set_frame_size(
+    frame: LispFrameOrSelected,
+    width: i32,
+    height: i32,
+    pixelwise: bool,
+) -> LispObject {
+    let frame_ref = frame.live_or_error();
+    let pixel_width = frame_ref.width_in_pixels(width, pixelwise);
+    let pixel_height = frame_ref.height_in_pixels(height, pixelwise);
+    frame_ref.set_text_pixel_size(pixel_width, pixel_height, false, Qsize);
+    Qnil
+}
+
+/// Set position of FRAME to (X, Y).
+/// FRAME must be a live frame and defaults to the selected one.  X and Y,
+/// if positive, specify the coordinate of the left and top edge of FRAME's
+/// outer frame in pixels relative to an origin (0, 0) of FRAME's display.
+/// If any of X or Y is negative, it specifies the coordinates of the right
+/// or bottom edge of the outer frame of FRAME relative to the right or
+/// bottom edge of FRAME's display.
+#[lisp_fn]
+pub fn set_frame_position(frame: LispFrameOrSelected, x: i32, y: i32) -> bool {
+    let mut frame_ref = frame.live_or_error();
+    if frame_ref.is_gui_window() {
+        unsafe { frame_set_offset(frame_ref.as_mut(), x, y) };
+    }
+    true
+}
+
+/// Return the parent frame of FRAME.
+/// The parent frame of FRAME is the Emacs frame whose window-system window
+/// is the parent window of FRAME's window-system window.  When such a frame
+/// exists, FRAME is considered a child frame of that frame.
+///
+/// Return nil if FRAME has no parent frame.  This means that FRAME's
+/// window-system window is either a "top-level" window (a window whose
+/// parent window is the window-system's root window) or an embedded window
+/// (a window whose parent window is owned by some other application).
+#[lisp_fn(min = "0")]
+pub fn frame_parent(frame: LispFrameOrSelected) -> Option<LispFrameRef> {
+    frame.live_or_error().parent_frame()
+}
+
+/// Return non-nil if ANCESTOR is an ancestor of DESCENDANT.
+/// ANCESTOR is an ancestor of DESCENDANT when it is either DESCENDANT's
+/// parent frame or it is an ancestor of DESCENDANT's parent frame.  Both,
+/// ANCESTOR and DESCENDANT must be live frames and default to the selected
+/// frame.
+#[lisp_fn]
+pub fn frame_ancestor_p(ancestor: LispFrameOrSelected, descendant: LispFrameOrSelected) -> bool {
+    let ancestor = ancestor.live_or_error();
+    let descendant = descendant.live_or_error();
+    ancestor.is_ancestor_of(descendant)
+}
+
+/// Return the name of the window-system specific variant of FUNCTION
+/// to use for frames on DISPLAY, e.g. `x-frame-restack' for X frames.
+fn window_system_function(display: LispObject, function: &str) -> Option<LispObject> {
+    let frame_type = call!(intern("framep-on-display").into(), display);
+    let prefix = match frame_type {
+        Qx => "x",
+        Qw32 => "w32",
+        Qns => "ns",
+        _ => return None,
+    };
+    Some(intern(format!("{}-{}", prefix, function)).into())
+}
+
+/// Return list of Emacs' frames, in Z (stacking) order.
+/// The optional argument DISPLAY specifies which display to poll.
+/// DISPLAY should be either a frame or a display name (a string).
+/// If omitted or nil, that stands for the selected frame's display.
+///
+/// Frames are listed from topmost (first) to bottommost (last).  As
+/// a special case, if DISPLAY is non-nil and specifies a live frame,
+/// return the child frames of that frame in Z (stacking) order.
+///
+/// Return nil if DISPLAY contains no Emacs frame.
+#[lisp_fn(min = "0")]
+pub fn frame_list_z_order(display: LispObject) -> LispObject {
+    window_system_function(display, "frame-list-z-order")
+        .map_or(Qnil, |function| call!(function, display))
+}
+
+/// Restack FRAME1 below FRAME2.
+/// This implies that if both frames are visible and the display
+/// areas of these frames overlap, FRAME2 will (partially) obscure
+/// FRAME1.  If the optional third argument ABOVE is non-nil, restack
+/// FRAME1 above FRAME2.  This means that if both frames are visible
+/// and the display areas of these frames overlap, FRAME1 will
+/// (partially) obscure FRAME2.
+///
+/// This may be thought of as an atomic action performed in two
+/// steps: The first step removes FRAME1's window-system window from
+/// the display.  The second step reinserts FRAME1's window
+/// below (above if ABOVE is true) that of FRAME2.  Hence the
+/// position of FRAME2 in its display's Z (stacking) order relative
+/// to all other frames excluding FRAME1 remains unaltered.
+///
+/// Some window managers may refuse to restack windows.
+#[lisp_fn(min = "2")]
+pub fn frame_restack(frame1: LispObject, frame2: LispObject, above: LispObject) -> LispObject {
+    let same_display = match (frame1.as_live_frame(), frame2.as_live_frame()) {
+        (Some(f1), Some(f2)) => unsafe {
+            Fframe_parameter(f1.into(), Qdisplay).equal(Fframe_parameter(f2.into(), Qdisplay))
+        },
+        _ => false,
+    };
+    if !same_display {
+        error!("Cannot restack frames");
+    }
+
+    window_system_function(frame1, "frame-restack")
+        .map_or(Qnil, |function| call!(function, frame1, frame2, above))
+}
+
 /// Returns t if the mouse pointer displayed on FRAME is visible.
 /// Otherwise it returns nil. FRAME omitted or nil means the selected frame.
 /// This is useful when `make-pointer-invisible` is set
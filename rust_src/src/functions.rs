@@ -99,14 +99,6 @@ macro_rules! assert_nil {
 }
 
 // Note(db48x): see if we can go back to using mock-derive for these
-#[cfg(test)]
-#[allow(unused_variables)]
-#[allow(dead_code)]
-#[no_mangle]
-pub extern "C" fn Fcons(car: LispObject, cdr: LispObject) -> LispObject {
-    return Qnil;
-}
-
 #[cfg(test)]
 #[allow(unused_variables)]
 #[allow(dead_code)]
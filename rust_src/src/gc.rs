@@ -0,0 +1,321 @@
+//! The mark phase of garbage collection.
+//!
+//! `mark_object` marks everything reachable from an object.  Instead of
+//! recursing into the objects an object refers to, it pushes them onto
+//! a stack of objects left to visit, so that long lists and deep trees
+//! don't use up the C stack.  The mark bits of conses and floats are
+//! kept by block_alloc.rs, and the objects with special needs like
+//! buffers, windows and markers are left to alloc.c.
+//!
+//! Besides the roots alloc.c knows about, the statics declared with
+//! `declare_GC_protected_static!` are marked as well.  Each is a
+//! `StaticRoot`, which registers itself with `register_roots` when it is
+//! first set, so that it needs no `staticpro`.
+
+use std::cell::{Cell, UnsafeCell};
+use std::mem;
+use std::slice;
+
+use crate::{
+    block_alloc::{gc_mark_cons, gc_mark_float},
+    gc_stats::MemoryUse,
+    lisp::LispObject,
+    remacs_sys::{char_table_specials, emacs_abort, pvec_type, symbol_redirect, Lisp_Type, Qnil},
+    remacs_sys::{
+        get_symbol_gcmarkbit, mark_buffer, mark_frame, mark_interval_tree, mark_localized_symbol,
+        mark_misc, mark_window, pure_object_p, set_symbol_gcmarkbit,
+    },
+    remacs_sys::{Lisp_Buffer, Lisp_Frame, Lisp_Hash_Table, Lisp_Window},
+    symbols::LispSymbolRef,
+    vectors::LispVectorlikeRef,
+};
+
+/// The bit of the size of a string or vectorlike object that marks it.
+pub(crate) const ARRAY_MARK_FLAG: isize = isize::min_value();
+
+const LAST_MARKED_SIZE: usize = 500;
+
+/// The last objects marked, for debugging.  `last_marked_index` is the
+/// index of the slot the next object goes into.
+#[no_mangle]
+pub static mut last_marked: [LispObject; LAST_MARKED_SIZE] = [Qnil; LAST_MARKED_SIZE];
+#[no_mangle]
+pub static mut last_marked_index: usize = 0;
+
+/// The objects left to visit.  Calls of `mark_object` nest when alloc.c
+/// marks the objects a buffer or window refers to, so each call only
+/// visits what lies above the stack's height when it was entered.
+static mut MARK_STACK: Option<Vec<LispObject>> = None;
+
+/// The locations registered as roots, each as its start and its number
+/// of objects.
+static mut ROOTS: Option<Vec<(*const LispObject, usize)>> = None;
+
+unsafe fn mark_stack() -> &'static mut Vec<LispObject> {
+    MARK_STACK.get_or_insert_with(Vec::new)
+}
+
+unsafe fn roots() -> &'static mut Vec<(*const LispObject, usize)> {
+    ROOTS.get_or_insert_with(Vec::new)
+}
+
+/// Register the LEN objects starting at ROOTS as roots of the garbage
+/// collector, so that whatever is stored there is kept alive.
+///
+/// The locations must stay valid, and must not move, from then on.
+unsafe fn register_roots(roots: *const LispObject, len: usize) {
+    self::roots().push((roots, len));
+}
+
+/// Types made of nothing but Lisp objects, which can be marked as the
+/// run of `LispObject`s they occupy.
+pub unsafe trait LispObjects: Copy {
+    /// The number of objects in a value of this type.
+    fn len() -> usize {
+        mem::size_of::<Self>() / mem::size_of::<LispObject>()
+    }
+}
+
+unsafe impl LispObjects for LispObject {}
+
+macro_rules! impl_lisp_objects_for_arrays {
+    ($($n: expr),*) => {
+        $(unsafe impl LispObjects for [LispObject; $n] {})*
+    };
+}
+
+impl_lisp_objects_for_arrays!(1, 2, 3, 4, 5, 6, 7, 8);
+
+/// A static holding Lisp objects, which the collector marks.
+///
+/// A `StaticRoot` registers itself as a root the first time it is set,
+/// so it can be initialized in a constant expression, but only with
+/// objects that need no marking, like `Qnil`.  This is what
+/// `declare_GC_protected_static!` declares.
+pub struct StaticRoot<T> {
+    value: UnsafeCell<T>,
+    registered: Cell<bool>,
+}
+
+// Lisp runs in one thread at a time.
+unsafe impl<T> Sync for StaticRoot<T> {}
+
+impl<T> StaticRoot<T> {
+    pub const fn new(value: T) -> Self {
+        Self {
+            value: UnsafeCell::new(value),
+            registered: Cell::new(false),
+        }
+    }
+}
+
+impl<T: LispObjects> StaticRoot<T> {
+    pub fn get(&'static self) -> T {
+        unsafe { *self.value.get() }
+    }
+
+    pub fn set(&'static self, value: T) {
+        if !self.registered.get() {
+            unsafe { register_roots(self.value.get() as *const LispObject, T::len()) };
+            self.registered.set(true);
+        }
+        unsafe { *self.value.get() = value };
+    }
+}
+
+/// Return the use of memory by the stack of objects left to mark, and
+/// by the roots registered.
+pub fn memory_use() -> Vec<MemoryUse> {
+    unsafe {
+        vec![
+            MemoryUse::of_vec("mark-stack", mark_stack()),
+            MemoryUse::of_vec("gc-roots", roots()),
+        ]
+    }
+}
+
+/// Mark the objects registered by `register_roots`.
+/// This is called by `garbage_collect_1` along with the other roots.
+#[no_mangle]
+pub extern "C" fn mark_rust_roots() {
+    unsafe {
+        for &(start, len) in roots().iter() {
+            for i in 0..len {
+                mark_object(*start.add(i));
+            }
+        }
+    }
+}
+
+/// Mark OBJ and everything reachable from it.
+#[no_mangle]
+pub extern "C" fn mark_object(obj: LispObject) {
+    unsafe {
+        let base = mark_stack().len();
+        mark_stack().push(obj);
+        while mark_stack().len() > base {
+            let obj = mark_stack().pop().unwrap();
+            mark_one(obj);
+        }
+    }
+}
+
+fn push(obj: LispObject) {
+    unsafe { mark_stack().push(obj) };
+}
+
+fn push_all(objects: &[LispObject]) {
+    unsafe { mark_stack().extend_from_slice(objects) };
+}
+
+/// Mark OBJ itself, and push the objects it refers to.
+unsafe fn mark_one(obj: LispObject) {
+    if pure_object_p(obj) {
+        return;
+    }
+
+    last_marked[last_marked_index] = obj;
+    last_marked_index = (last_marked_index + 1) % LAST_MARKED_SIZE;
+
+    match obj.get_type() {
+        Lisp_Type::Lisp_String => mark_string(obj),
+        Lisp_Type::Lisp_Vectorlike => mark_vectorlike(obj.force_vectorlike()),
+        Lisp_Type::Lisp_Symbol => mark_symbol(obj.force_symbol()),
+        Lisp_Type::Lisp_Misc => mark_misc(obj),
+        Lisp_Type::Lisp_Cons => {
+            if gc_mark_cons(obj) {
+                let (car, cdr) = obj.force_cons().into();
+                push(cdr);
+                push(car);
+            }
+        }
+        Lisp_Type::Lisp_Float => gc_mark_float(obj),
+        Lisp_Type::Lisp_Int0 | Lisp_Type::Lisp_Int1 => {}
+    }
+}
+
+/// Mark the string OBJ and its text properties.  Strings refer to no
+/// other objects but through their properties.
+unsafe fn mark_string(obj: LispObject) {
+    let mut s = obj.force_string();
+    if s.u.s.size & ARRAY_MARK_FLAG != 0 {
+        return;
+    }
+    s.u.s.size |= ARRAY_MARK_FLAG;
+    mark_interval_tree(s.u.s.intervals);
+}
+
+/// Mark SYMBOL, and the symbols chained to it in its obarray bucket.
+unsafe fn mark_symbol(symbol: LispSymbolRef) {
+    let mut next = symbol;
+    loop {
+        let mut symbol = next;
+        if get_symbol_gcmarkbit(symbol.as_ptr()) {
+            return;
+        }
+        set_symbol_gcmarkbit(symbol.as_mut(), true);
+        push(symbol.get_function());
+        push(symbol.get_plist());
+        match symbol.get_redirect() {
+            symbol_redirect::SYMBOL_PLAINVAL => push(symbol.get_value()),
+            symbol_redirect::SYMBOL_VARALIAS => push(symbol.get_alias().into()),
+            symbol_redirect::SYMBOL_LOCALIZED => mark_localized_symbol(symbol.as_mut()),
+            // If the value is forwarded to a buffer or keyboard field,
+            // it is marked along with that object.  If it is forwarded
+            // to a C variable, either it's not a Lisp_Object variable,
+            // or it's staticpro'd already.
+            symbol_redirect::SYMBOL_FORWARDED => {}
+        }
+
+        let name = symbol.symbol_name();
+        if !pure_object_p(name) {
+            let mut s = name.force_string();
+            s.u.s.size |= ARRAY_MARK_FLAG;
+        }
+        mark_interval_tree(name.force_string().u.s.intervals);
+
+        let s = symbol.u.s.as_ref();
+        if s.next.is_null() {
+            return;
+        }
+        next = LispSymbolRef::new(s.next);
+    }
+}
+
+fn is_marked(v: LispVectorlikeRef) -> bool {
+    unsafe { v.header.size & ARRAY_MARK_FLAG != 0 }
+}
+
+fn set_marked(mut v: LispVectorlikeRef) {
+    unsafe { v.header.size |= ARRAY_MARK_FLAG };
+}
+
+/// Return the Lisp_Object slots of V, a vector or pseudovector.
+fn slots(v: LispVectorlikeRef) -> &'static [LispObject] {
+    let size = if v.is_vector() {
+        unsafe { (v.header.size & !ARRAY_MARK_FLAG) as usize }
+    } else {
+        v.pseudovector_size() as usize
+    };
+    unsafe { slice::from_raw_parts(v.as_vector_unchecked().contents.as_ptr(), size) }
+}
+
+/// Mark the vector or pseudovector V and push its slots.
+fn mark_vectorlike(v: LispVectorlikeRef) {
+    if is_marked(v) {
+        return;
+    }
+    if v.is_vector() {
+        set_marked(v);
+        push_all(slots(v));
+        return;
+    }
+
+    match v.pseudovector_type() {
+        pvec_type::PVEC_BUFFER => unsafe { mark_buffer(v.as_ptr() as *mut Lisp_Buffer) },
+        pvec_type::PVEC_FRAME => unsafe { mark_frame(v.as_ptr() as *mut Lisp_Frame) },
+        pvec_type::PVEC_WINDOW => unsafe { mark_window(v.as_ptr() as *mut Lisp_Window) },
+        pvec_type::PVEC_HASH_TABLE => {
+            set_marked(v);
+            push_all(slots(v));
+            let h = unsafe { &*(v.as_ptr() as *const Lisp_Hash_Table) };
+            push(h.test.name);
+            push(h.test.user_hash_function);
+            push(h.test.user_cmp_function);
+            // The keys and values of a weak table are left to
+            // sweep_weak_table; only their vector is marked here.
+            if h.weak.is_nil() {
+                push(h.key_and_value);
+            } else {
+                set_marked(h.key_and_value.force_vectorlike());
+            }
+        }
+        pvec_type::PVEC_CHAR_TABLE => mark_char_table(v, 0),
+        pvec_type::PVEC_SUB_CHAR_TABLE => {
+            mark_char_table(v, char_table_specials::SUB_CHAR_TABLE_OFFSET as usize)
+        }
+        pvec_type::PVEC_BOOL_VECTOR => set_marked(v),
+        pvec_type::PVEC_SUBR => {}
+        pvec_type::PVEC_FREE => unsafe { emacs_abort() },
+        _ => {
+            set_marked(v);
+            push_all(slots(v));
+        }
+    }
+}
+
+/// Mark the char-table or sub-char-table V, whose Lisp_Object slots
+/// start at FIRST.  Their elements are mostly integers or symbols, which
+/// are skipped when there is nothing to mark.
+fn mark_char_table(v: LispVectorlikeRef, first: usize) {
+    set_marked(v);
+    for &val in slots(v).iter().skip(first) {
+        let done = val.is_fixnum()
+            || val
+                .as_symbol()
+                .map_or(false, |s| unsafe { get_symbol_gcmarkbit(s.as_ptr()) });
+        if !done {
+            push(val);
+        }
+    }
+}
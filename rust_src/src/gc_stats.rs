@@ -0,0 +1,247 @@
+//! Garbage collection accounting.
+//!
+//! Allocation adds the bytes it takes to `consing_since_gc`, and
+//! `maybe_gc` collects garbage once that exceeds both
+//! `gc-cons-threshold` and the part of the live objects given by
+//! `gc-cons-percentage`.  After a collection, the counts of objects
+//! live and free kept by the allocators are what `garbage-collect`
+//! reports.
+
+use std::mem;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    finalizers, gc,
+    lisp::{defsubr, LispObject},
+    lists::list,
+    numbers::MOST_POSITIVE_FIXNUM,
+    obarray::intern,
+    remacs_sys::{globals, malloc_heap_statistics, Fgarbage_collect},
+    remacs_sys::{interval, vectorlike_header, EmacsInt, Lisp_Buffer, Lisp_Cons, Lisp_Float},
+    remacs_sys::{
+        total_buffers, total_conses, total_floats, total_free_conses, total_free_floats,
+        total_free_intervals, total_free_markers, total_free_strings, total_free_symbols,
+        total_free_vector_slots, total_intervals, total_markers, total_string_bytes, total_strings,
+        total_symbols, total_vector_slots, total_vectors,
+    },
+    remacs_sys::{Lisp_Misc, Lisp_String, Lisp_Symbol},
+    remacs_sys::{
+        Qbuffers, Qconses, Qfloats, Qintervals, Qmiscs, Qstring_bytes, Qstrings, Qsymbols,
+        Qvector_slots, Qvectors,
+    },
+    sequences::append,
+};
+
+/// The number of bytes allocated since the last garbage collection.
+#[no_mangle]
+pub static mut consing_since_gc: EmacsInt = 0;
+
+/// The part of the live objects `gc-cons-percentage` asks for, in bytes,
+/// as of the last garbage collection.
+#[no_mangle]
+pub static mut gc_relative_threshold: EmacsInt = 0;
+
+/// The bytes to allocate before collecting garbage when memory is full.
+#[no_mangle]
+pub static mut memory_full_cons_threshold: EmacsInt = 0;
+
+/// The default value of `gc-cons-threshold`, which must match
+/// `GC_DEFAULT_THRESHOLD` in alloc.c.  The threshold is never let below
+/// a tenth of it.
+const GC_DEFAULT_THRESHOLD: EmacsInt = 100_000 * mem::size_of::<LispObject>() as EmacsInt;
+
+/// Collect garbage if enough was allocated since the last collection.
+/// C calls the inline `maybe_gc` of lisp.h instead, which must agree.
+pub fn maybe_gc() {
+    unsafe {
+        if (consing_since_gc > globals.gc_cons_threshold
+            && consing_since_gc > gc_relative_threshold)
+            || (globals.Vmemory_full.is_not_nil() && consing_since_gc > memory_full_cons_threshold)
+        {
+            Fgarbage_collect();
+        }
+    }
+}
+
+/// Return the number of bytes taken by the objects live after the last
+/// garbage collection.
+#[no_mangle]
+pub extern "C" fn total_bytes_of_live_objects() -> usize {
+    unsafe {
+        total_conses as usize * mem::size_of::<Lisp_Cons>()
+            + total_symbols as usize * mem::size_of::<Lisp_Symbol>()
+            + total_markers as usize * mem::size_of::<Lisp_Misc>()
+            + total_string_bytes as usize
+            + total_vector_slots as usize * mem::size_of::<LispObject>()
+            + total_floats as usize * mem::size_of::<Lisp_Float>()
+            + total_intervals as usize * mem::size_of::<interval>()
+            + total_strings as usize * mem::size_of::<Lisp_String>()
+    }
+}
+
+/// Start counting the bytes allocated towards the next garbage
+/// collection.  Called once a collection has swept the heap.
+#[no_mangle]
+pub extern "C" fn update_gc_thresholds() {
+    unsafe {
+        consing_since_gc = 0;
+        if globals.gc_cons_threshold < GC_DEFAULT_THRESHOLD / 10 {
+            globals.gc_cons_threshold = GC_DEFAULT_THRESHOLD / 10;
+        }
+
+        gc_relative_threshold = match globals.Vgc_cons_percentage.as_float() {
+            Some(percentage) => {
+                let tot = total_bytes_of_live_objects() as f64 * percentage;
+                if tot <= 0.0 {
+                    0
+                } else if tot < EmacsInt::max_value() as f64 {
+                    tot as EmacsInt
+                } else {
+                    EmacsInt::max_value()
+                }
+            }
+            None => 0,
+        };
+    }
+}
+
+/// The use of memory by one kind of object, as `garbage-collect`
+/// reports it: the size of each object in bytes, and the numbers of
+/// objects in use and free.
+pub struct MemoryUse {
+    pub name: &'static str,
+    pub size: usize,
+    pub used: usize,
+    pub free: Option<usize>,
+}
+
+impl MemoryUse {
+    /// The use of memory by the elements of VEC, whose spare capacity
+    /// counts as free.
+    pub fn of_vec<T>(name: &'static str, vec: &Vec<T>) -> Self {
+        Self {
+            name,
+            size: mem::size_of::<T>(),
+            used: vec.len(),
+            free: Some(vec.capacity() - vec.len()),
+        }
+    }
+}
+
+/// Return a count as a fixnum, or the largest fixnum if it doesn't fit.
+fn bounded_number(n: EmacsInt) -> LispObject {
+    LispObject::from(n.min(MOST_POSITIVE_FIXNUM))
+}
+
+/// Return the entry (NAME SIZE USED FREE) of `garbage-collect`, without
+/// FREE if that is `None`.
+fn entry(name: LispObject, size: usize, used: EmacsInt, free: Option<EmacsInt>) -> LispObject {
+    let size = LispObject::from(size);
+    match free {
+        Some(free) => list(&[name, size, bounded_number(used), bounded_number(free)]),
+        None => list(&[name, size, bounded_number(used)]),
+    }
+}
+
+/// Return the list `garbage-collect` returns, from the counts of the
+/// last collection.
+#[no_mangle]
+pub extern "C" fn gc_statistics() -> LispObject {
+    let header_size = mem::size_of::<vectorlike_header>();
+    let word_size = mem::size_of::<LispObject>();
+    let mut entries = unsafe {
+        vec![
+            entry(
+                Qconses,
+                mem::size_of::<Lisp_Cons>(),
+                total_conses,
+                Some(total_free_conses),
+            ),
+            entry(
+                Qsymbols,
+                mem::size_of::<Lisp_Symbol>(),
+                total_symbols,
+                Some(total_free_symbols),
+            ),
+            entry(
+                Qmiscs,
+                mem::size_of::<Lisp_Misc>(),
+                total_markers,
+                Some(total_free_markers),
+            ),
+            entry(
+                Qstrings,
+                mem::size_of::<Lisp_String>(),
+                total_strings,
+                Some(total_free_strings),
+            ),
+            entry(Qstring_bytes, 1, total_string_bytes, None),
+            entry(Qvectors, header_size + word_size, total_vectors, None),
+            entry(
+                Qvector_slots,
+                word_size,
+                total_vector_slots,
+                Some(total_free_vector_slots),
+            ),
+            entry(
+                Qfloats,
+                mem::size_of::<Lisp_Float>(),
+                total_floats,
+                Some(total_free_floats),
+            ),
+            entry(
+                Qintervals,
+                mem::size_of::<interval>(),
+                total_intervals,
+                Some(total_free_intervals),
+            ),
+            entry(Qbuffers, mem::size_of::<Lisp_Buffer>(), total_buffers, None),
+        ]
+    };
+
+    let heap = unsafe { malloc_heap_statistics() };
+    if heap.is_not_nil() {
+        entries.push(heap);
+    }
+    list(&entries)
+}
+
+/// Return the use of memory by the parts of Emacs written in Rust,
+/// beyond the Lisp objects they allocate.
+fn rust_memory_use() -> Vec<MemoryUse> {
+    let mut uses = gc::memory_use();
+    uses.extend(finalizers::memory_use());
+    uses
+}
+
+/// Reclaim storage for Lisp objects no longer needed, and report it.
+/// This is like `garbage-collect', but the list returned also has entries
+/// for the memory used by the parts of Emacs written in Rust, like the
+/// stack of objects left to mark.  Their names start with `rust-', and
+/// their form is the same (NAME SIZE USED FREE), where SIZE is the size
+/// of an element in bytes, USED is the number of elements in use, and
+/// FREE is the number of elements allocated but unused.
+#[lisp_fn]
+pub fn garbage_collect_verbose() -> LispObject {
+    let stats = unsafe { Fgarbage_collect() };
+    if stats.is_nil() {
+        return stats;
+    }
+
+    let uses = rust_memory_use()
+        .into_iter()
+        .map(|u| {
+            let name = intern(&format!("rust-{}", u.name)).into();
+            entry(
+                name,
+                u.size,
+                u.used as EmacsInt,
+                u.free.map(|n| n as EmacsInt),
+            )
+        })
+        .collect::<Vec<_>>();
+    append(&mut [stats, list(&uses)])
+}
+
+include!(concat!(env!("OUT_DIR"), "/gc_stats_exports.rs"));
@@ -1,27 +1,86 @@
 //! hashtable support
+//!
+//! A hash table is a pseudovector of the vectors of its keys and values,
+//! their hash codes, the buckets of its index and the chains through
+//! them.  Entries are found by hashing their key with the test of the
+//! table, and following the chain from the bucket of the hash code.
+//! Free entries are chained through the same vector, from `next_free`.
+//!
+//! When `hash-table-rehash-seed-randomization' is non-nil, `equal'
+//! tables hash strings with SipHash keyed by a seed chosen at random
+//! for the session, so that strings read from elsewhere can't be made
+//! to collide in them.
 
-use libc::c_void;
-use std::ptr;
+use libc::{c_char, c_int, c_void, ptrdiff_t};
+use rand::{Rng, StdRng};
+use std::{mem, ptr, slice};
 
 use remacs_macros::lisp_fn;
 
 use crate::{
     data::aref,
+    eval::signal_error,
+    gc::{mark_object, ARRAY_MARK_FLAG},
     lisp::defsubr,
     lisp::{ExternalPtr, LispObject},
-    lists::{list, put},
+    lists::{get, list, put},
+    numbers::MOST_POSITIVE_FIXNUM,
     remacs_sys::{
-        gc_aset, hash_clear, hash_lookup, hash_put, hash_remove_from_table, Fcopy_sequence,
+        emacs_abort, globals, hash_table_test, larger_vector, next_almost_prime, survives_gc_p,
+        Fmake_vector,
     },
     remacs_sys::{
-        pvec_type, EmacsDouble, EmacsInt, EmacsUint, Lisp_Hash_Table, Lisp_Type, CHECK_IMPURE,
+        pvec_type, EmacsDouble, EmacsInt, EmacsUint, Lisp_Bits, Lisp_Hash_Table, Lisp_Type,
+        BITS_PER_BITS_WORD, CHECK_IMPURE, INTMASK, USE_LSB_TAG,
     },
-    remacs_sys::{Qhash_table_p, Qhash_table_test},
+    remacs_sys::{
+        QCpurecopy, QCrehash_size, QCrehash_threshold, QCsize, QCtest, QCweakness, Qeq, Qeql,
+        Qequal, Qhash_table_p, Qhash_table_test,
+    },
+    remacs_sys::{Qkey, Qkey_and_value, Qkey_or_value, Qnil, Qvalue},
+    sequences::copy_sequence,
     symbols::LispSymbolRef,
 };
 
 pub type LispHashTableRef = ExternalPtr<Lisp_Hash_Table>;
 
+/// The size of a hash table made without `:size'.  This must match
+/// `DEFAULT_HASH_SIZE` in lisp.h.
+pub const DEFAULT_HASH_SIZE: EmacsInt = 65;
+
+/// The ratio of entries to size at which a hash table made without
+/// `:rehash-threshold' grows.  This must match lisp.h.
+pub const DEFAULT_REHASH_THRESHOLD: f32 = 0.8125;
+
+/// The factor by which a hash table made without `:rehash-size' grows,
+/// minus 1.  This must match lisp.h.
+pub const DEFAULT_REHASH_SIZE: f32 = 1.5 - 1.0;
+
+/// The depth up to which `sxhash` dives into conses and vectors.
+const SXHASH_MAX_DEPTH: c_int = 3;
+
+/// The number of elements of a list or vector that `sxhash` hashes.
+const SXHASH_MAX_LEN: usize = 7;
+
+/// An upper bound on the size of the index of a hash table, which must
+/// fit in a `ptrdiff_t` and be a fixnum.
+fn index_size_bound() -> EmacsInt {
+    let words = ptrdiff_t::max_value() / mem::size_of::<LispObject>() as ptrdiff_t;
+    MOST_POSITIVE_FIXNUM.min(words as EmacsInt)
+}
+
+/// Return the size of the index of a hash table with SIZE entries and
+/// REHASH_THRESHOLD, or one more than `index_size_bound` if it is larger.
+fn index_size_for(size: EmacsInt, rehash_threshold: f32) -> EmacsInt {
+    let bound = index_size_bound();
+    let index_float = size as f64 / f64::from(rehash_threshold);
+    if index_float < (bound + 1) as f64 {
+        unsafe { next_almost_prime(index_float as EmacsInt) }
+    } else {
+        bound + 1
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Debug)]
 pub enum HashLookupResult {
     Missing(EmacsUint),
@@ -30,6 +89,58 @@ pub enum HashLookupResult {
 use self::HashLookupResult::{Found, Missing};
 
 impl LispHashTableRef {
+    /// Return a new hash table that compares keys with `equal', with room
+    /// for SIZE entries.
+    pub fn make_equal(size: usize) -> LispHashTableRef {
+        make_hash_table(
+            hashtest_equal,
+            size as EmacsInt,
+            DEFAULT_REHASH_SIZE,
+            DEFAULT_REHASH_THRESHOLD,
+            Qnil,
+            false,
+        )
+        .into()
+    }
+
+    /// Return a new hash table that compares keys with `eq'.
+    pub fn make_eq() -> LispHashTableRef {
+        make_hash_table(
+            hashtest_eq,
+            DEFAULT_HASH_SIZE,
+            DEFAULT_REHASH_SIZE,
+            DEFAULT_REHASH_THRESHOLD,
+            Qnil,
+            false,
+        )
+        .into()
+    }
+
+    /// Return a new hash table that compares keys with TEST, one of
+    /// `eq', `eql' and `equal', and whose entries garbage collection
+    /// removes as WEAKNESS says.  This is what caches that mustn't keep
+    /// buffers or strings alive are made of.
+    pub fn make_weak(test: LispObject, weakness: Weakness) -> LispHashTableRef {
+        let test = if test.eq(Qeq) {
+            hashtest_eq
+        } else if test.eq(Qeql) {
+            hashtest_eql
+        } else if test.eq(Qequal) {
+            hashtest_equal
+        } else {
+            signal_error("Invalid hash table test", test)
+        };
+        make_hash_table(
+            test,
+            DEFAULT_HASH_SIZE,
+            DEFAULT_REHASH_SIZE,
+            DEFAULT_REHASH_THRESHOLD,
+            weakness.into(),
+            false,
+        )
+        .into()
+    }
+
     pub fn allocate() -> LispHashTableRef {
         let vec_ptr = allocate_pseudovector!(Lisp_Hash_Table, count, pvec_type::PVEC_HASH_TABLE);
         LispHashTableRef::new(vec_ptr)
@@ -88,7 +199,7 @@ impl LispHashTableRef {
     }
 
     pub fn set_hash_value(self, idx: isize, value: LispObject) {
-        unsafe { gc_aset(self.key_and_value, 2 * idx + 1, value) };
+        unsafe { Self::set_slot(self.key_and_value, 2 * idx + 1, value) };
     }
 
     pub fn get_hash_key(self, idx: isize) -> LispObject {
@@ -99,24 +210,160 @@ impl LispHashTableRef {
         aref(self.hash, idx as EmacsInt)
     }
 
+    /// Return the hash code of KEY by the test of this table.
+    fn hash_code(mut self, key: LispObject) -> EmacsUint {
+        let hashfn = self.test.hashfn.unwrap_or_else(|| unsafe { emacs_abort() });
+        let hash = unsafe { hashfn(&mut self.test, key) };
+        debug_assert!(hash & !(INTMASK as EmacsUint) == 0);
+        hash
+    }
+
+    /// Return the bucket of the index for the hash code HASH.
+    fn bucket_of(self, hash: EmacsUint) -> isize {
+        (hash % unsafe { self.index_size() } as EmacsUint) as isize
+    }
+
+    /// Return whether KEY, whose hash code is HASH, is the key of entry
+    /// IDX by the test of this table.
+    fn matches(mut self, key: LispObject, hash: EmacsUint, idx: isize) -> bool {
+        let other = unsafe { Self::slot(self.key_and_value, 2 * idx) };
+        if key.eq(other) {
+            return true;
+        }
+        match self.test.cmpfn {
+            Some(cmpfn) => {
+                hash == fixnum_bits(unsafe { Self::slot(self.hash, idx) })
+                    && unsafe { cmpfn(&mut self.test, key, other) }
+            }
+            None => false,
+        }
+    }
+
+    /// Return the entry whose key is KEY, whose hash code is HASH, with
+    /// the entry before it in its collision chain, or -1 if it is first.
+    fn find(self, key: LispObject, hash: EmacsUint) -> Option<(isize, isize)> {
+        let mut prev = -1;
+        let mut i = unsafe { self.bucket(self.bucket_of(hash)) };
+        while i >= 0 {
+            if self.matches(key, hash, i) {
+                return Some((prev, i));
+            }
+            prev = i;
+            i = unsafe { self.next_entry(i) };
+        }
+        None
+    }
+
     pub fn lookup(self, key: LispObject) -> HashLookupResult {
-        // This allows `self` to be immutable.
-        let mutself = self.as_ptr() as *mut Lisp_Hash_Table;
-        let mut hash = 0;
-        let idx = unsafe { hash_lookup(mutself, key, &mut hash) };
-        if idx < 0 {
-            Missing(hash)
+        let hash = self.hash_code(key);
+        match self.find(key, hash) {
+            Some((_, idx)) => Found(idx),
+            None => Missing(hash),
+        }
+    }
+
+    /// Grow the table if it has no free entry left, and rehash it.
+    fn maybe_resize(mut self) {
+        if self.next_free >= 0 {
+            return;
+        }
+
+        let old_size = self.size() as EmacsInt;
+        let bound = index_size_bound();
+        let rehash_size = f64::from(self.rehash_size);
+        let mut new_size = if rehash_size < 0.0 {
+            old_size - rehash_size as EmacsInt
         } else {
-            Found(idx)
+            let float_new_size = old_size as f64 * (rehash_size + 1.0);
+            if float_new_size < (bound + 1) as f64 {
+                float_new_size as EmacsInt
+            } else {
+                bound + 1
+            }
+        };
+        if new_size <= old_size {
+            new_size = old_size + 1;
+        }
+        let index_size = index_size_for(new_size, self.rehash_threshold);
+        if bound < index_size.max(2 * new_size) {
+            error!("Hash table too large to resize");
+        }
+
+        let incr = (new_size - old_size) as ptrdiff_t;
+        let new_size = new_size as ptrdiff_t;
+        unsafe {
+            self.key_and_value = larger_vector(self.key_and_value, 2 * incr, 2 * new_size);
+            self.hash = larger_vector(self.hash, incr, new_size);
+            self.next = larger_vector(self.next, incr, new_size);
+            self.index = Fmake_vector(index_size.into(), LispObject::from(-1_isize));
+
+            // The table was full, so the new entries are all that is
+            // free.
+            for i in old_size as isize..new_size - 1 {
+                Self::set_slot(self.next, i, (i + 1).into());
+            }
+            Self::set_slot(self.next, new_size - 1, LispObject::from(-1_isize));
+            self.next_free = old_size as isize;
+
+            for i in 0..old_size as isize {
+                let hash = Self::slot(self.hash, i);
+                if hash.is_not_nil() {
+                    let bucket = self.bucket_of(fixnum_bits(hash));
+                    Self::set_slot(self.next, i, self.bucket(bucket).into());
+                    Self::set_slot(self.index, bucket, i.into());
+                }
+            }
         }
     }
 
+    /// Add an entry for KEY, whose hash code is HASH, with VALUE, and
+    /// return its index.  KEY must not have an entry already.
     pub fn put(mut self, key: LispObject, value: LispObject, hash: EmacsUint) -> isize {
-        unsafe { hash_put(self.as_mut(), key, value, hash) }
+        debug_assert!(hash & !(INTMASK as EmacsUint) == 0);
+
+        // Count the entry after resizing, which may fail.
+        self.maybe_resize();
+        self.count += 1;
+
+        let i = self.next_free;
+        unsafe {
+            self.next_free = self.next_entry(i);
+            Self::set_slot(self.key_and_value, 2 * i, key);
+            Self::set_slot(self.key_and_value, 2 * i + 1, value);
+            Self::set_slot(self.hash, i, LispObject::from_natnum(hash));
+
+            let bucket = self.bucket_of(hash);
+            Self::set_slot(self.next, i, self.bucket(bucket).into());
+            Self::set_slot(self.index, bucket, i.into());
+        }
+        i
+    }
+
+    /// Clear the entry IDX, which is out of its collision chain, and add
+    /// it to the free entries.
+    unsafe fn free_entry(mut self, idx: isize) {
+        Self::set_slot(self.key_and_value, 2 * idx, Qnil);
+        Self::set_slot(self.key_and_value, 2 * idx + 1, Qnil);
+        Self::set_slot(self.hash, idx, Qnil);
+        Self::set_slot(self.next, idx, self.next_free.into());
+        self.next_free = idx;
+        self.count -= 1;
+        debug_assert!(self.count >= 0);
     }
 
-    pub fn remove(mut self, key: LispObject) {
-        unsafe { hash_remove_from_table(self.as_mut(), key) };
+    pub fn remove(self, key: LispObject) {
+        let hash = self.hash_code(key);
+        if let Some((prev, i)) = self.find(key, hash) {
+            unsafe {
+                let next = self.next_entry(i);
+                if prev < 0 {
+                    Self::set_slot(self.index, self.bucket_of(hash), next.into());
+                } else {
+                    Self::set_slot(self.next, prev, next.into());
+                }
+                self.free_entry(i);
+            }
+        }
     }
 
     pub fn size(self) -> usize {
@@ -124,7 +371,25 @@ impl LispHashTableRef {
     }
 
     pub fn clear(mut self) {
-        unsafe { hash_clear(self.as_mut()) }
+        if self.count == 0 {
+            return;
+        }
+
+        let size = self.size() as isize;
+        unsafe {
+            for i in 0..size {
+                let next = if i < size - 1 { i + 1 } else { -1 };
+                Self::set_slot(self.next, i, next.into());
+                Self::set_slot(self.key_and_value, 2 * i, Qnil);
+                Self::set_slot(self.key_and_value, 2 * i + 1, Qnil);
+                Self::set_slot(self.hash, i, Qnil);
+            }
+            for bucket in 0..self.index_size() {
+                Self::set_slot(self.index, bucket, LispObject::from(-1_isize));
+            }
+        }
+        self.next_free = 0;
+        self.count = 0;
     }
 
     pub fn check_impure(self, object: LispHashTableRef) {
@@ -225,6 +490,727 @@ impl LispHashTableRef {
     }
 }
 
+/// Which entries of a weak hash table garbage collection removes: those
+/// whose key, value, key or value, or key and value are not referred to
+/// from anywhere else.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Weakness {
+    Key,
+    Value,
+    KeyOrValue,
+    KeyAndValue,
+}
+
+impl Weakness {
+    pub fn from_symbol(weak: LispObject) -> Option<Weakness> {
+        if weak.eq(Qkey) {
+            Some(Weakness::Key)
+        } else if weak.eq(Qvalue) {
+            Some(Weakness::Value)
+        } else if weak.eq(Qkey_or_value) {
+            Some(Weakness::KeyOrValue)
+        } else if weak.eq(Qkey_and_value) {
+            Some(Weakness::KeyAndValue)
+        } else {
+            None
+        }
+    }
+
+    /// Return whether an entry is removed, given whether its key and its
+    /// value survive garbage collection.
+    pub fn removes(self, key_survives: bool, value_survives: bool) -> bool {
+        match self {
+            Weakness::Key => !key_survives,
+            Weakness::Value => !value_survives,
+            Weakness::KeyOrValue => !(key_survives || value_survives),
+            Weakness::KeyAndValue => !(key_survives && value_survives),
+        }
+    }
+}
+
+impl From<Weakness> for LispObject {
+    fn from(weakness: Weakness) -> Self {
+        match weakness {
+            Weakness::Key => Qkey,
+            Weakness::Value => Qvalue,
+            Weakness::KeyOrValue => Qkey_or_value,
+            Weakness::KeyAndValue => Qkey_and_value,
+        }
+    }
+}
+
+/// Return the value of OBJ as an unsigned integer, which for objects
+/// other than fixnums are the bits of their address.  This is XUINT.
+fn uint_value(obj: LispObject) -> EmacsUint {
+    let bits = obj.to_C_unsigned();
+    if USE_LSB_TAG {
+        bits >> Lisp_Bits::INTTYPEBITS
+    } else {
+        bits & INTMASK as EmacsUint
+    }
+}
+
+/// Combine the hash codes X and Y.  The result may not be a fixnum.
+fn sxhash_combine(x: EmacsUint, y: EmacsUint) -> EmacsUint {
+    let width = 8 * mem::size_of::<EmacsUint>() as u32;
+    (x << 4).wrapping_add(x >> (width - 4)).wrapping_add(y)
+}
+
+/// Reduce the hash code X to a fixnum.
+fn sxhash_reduce(x: EmacsUint) -> EmacsUint {
+    let width = 8 * mem::size_of::<EmacsUint>() as u32;
+    let fixnum_bits = Lisp_Bits::VALBITS as u32 + 1;
+    (x ^ x >> (width - fixnum_bits)) & INTMASK as EmacsUint
+}
+
+/// A key of SipHash.
+type SipKey = (u64, u64);
+
+lazy_static! {
+    /// The key with which seeded tables hash strings.  It is chosen the
+    /// first time such a table hashes a string.
+    static ref SESSION_SIP_KEY: SipKey = {
+        let mut rng = StdRng::new().unwrap();
+        (rng.gen(), rng.gen())
+    };
+}
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+/// Return the SipHash-2-4 of BYTES with KEY.
+fn siphash(key: SipKey, bytes: &[u8]) -> u64 {
+    let (k0, k1) = key;
+    let mut v = [
+        k0 ^ 0x736f_6d65_7073_6575,
+        k1 ^ 0x646f_7261_6e64_6f6d,
+        k0 ^ 0x6c79_6765_6e65_7261,
+        k1 ^ 0x7465_6462_7974_6573,
+    ];
+    let compress = |v: &mut [u64; 4], m: u64| {
+        v[3] ^= m;
+        sip_round(v);
+        sip_round(v);
+        v[0] ^= m;
+    };
+    let little_endian = |chunk: &[u8]| {
+        chunk
+            .iter()
+            .rev()
+            .fold(0, |word, &b| word << 8 | u64::from(b))
+    };
+
+    let whole = bytes.len() - bytes.len() % 8;
+    for chunk in bytes[..whole].chunks(8) {
+        compress(&mut v, little_endian(chunk));
+    }
+    // The last word holds the remaining bytes and the length.
+    compress(
+        &mut v,
+        (bytes.len() as u64) << 56 | little_endian(&bytes[whole..]),
+    );
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        sip_round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+fn hash_bytes(bytes: &[u8]) -> EmacsUint {
+    bytes
+        .iter()
+        .fold(0, |hash, &c| sxhash_combine(hash, EmacsUint::from(c)))
+}
+
+/// Return a hash code for the LEN bytes at PTR.  This need not be a
+/// fixnum.
+#[no_mangle]
+pub unsafe extern "C" fn hash_string(ptr: *const c_char, len: ptrdiff_t) -> EmacsUint {
+    hash_bytes(slice::from_raw_parts(ptr as *const u8, len as usize))
+}
+
+fn sxhash_float(val: EmacsDouble) -> EmacsUint {
+    sxhash_reduce(sxhash_combine(0, val.to_bits() as EmacsUint))
+}
+
+/// Return a hash code for LIST, of which at most `SXHASH_MAX_LEN`
+/// elements count, when it is at DEPTH in the object hashed.
+fn sxhash_list(list: LispObject, depth: c_int, key: Option<SipKey>) -> EmacsUint {
+    let mut hash = 0;
+    let mut tail = list;
+    if depth < SXHASH_MAX_DEPTH {
+        for _ in 0..SXHASH_MAX_LEN {
+            match tail.as_cons() {
+                Some(cons) => {
+                    hash = sxhash_combine(hash, sxhash_1(cons.car(), depth + 1, key));
+                    tail = cons.cdr();
+                }
+                None => break,
+            }
+        }
+    }
+    if tail.is_not_nil() {
+        hash = sxhash_combine(hash, sxhash_1(tail, depth + 1, key));
+    }
+    sxhash_reduce(hash)
+}
+
+/// Return a hash code for a vector or record whose size word is SIZE
+/// and whose slots are CONTENTS.
+fn sxhash_vector(
+    size: EmacsUint,
+    contents: &[LispObject],
+    depth: c_int,
+    key: Option<SipKey>,
+) -> EmacsUint {
+    let hash = contents
+        .iter()
+        .take(SXHASH_MAX_LEN)
+        .fold(size, |hash, &obj| {
+            sxhash_combine(hash, sxhash_1(obj, depth + 1, key))
+        });
+    sxhash_reduce(hash)
+}
+
+fn sxhash_bool_vector(size: usize, words: &[usize]) -> EmacsUint {
+    let bits_per_word = BITS_PER_BITS_WORD as usize;
+    let hash = words
+        .iter()
+        .take(((size + bits_per_word - 1) / bits_per_word).min(SXHASH_MAX_LEN))
+        .fold(size as EmacsUint, |hash, &word| {
+            sxhash_combine(hash, word as EmacsUint)
+        });
+    sxhash_reduce(hash)
+}
+
+/// Return a hash code for OBJ, which is at DEPTH in the object hashed,
+/// such that objects that are `equal' have the same hash code.  The
+/// hash code is a fixnum.
+#[no_mangle]
+pub extern "C" fn sxhash(obj: LispObject, depth: c_int) -> EmacsUint {
+    sxhash_1(obj, depth, None)
+}
+
+/// Return a hash code for OBJ like `sxhash`, except that strings are
+/// hashed with SipHash if KEY is given.
+fn sxhash_1(obj: LispObject, depth: c_int, key: Option<SipKey>) -> EmacsUint {
+    if depth > SXHASH_MAX_DEPTH {
+        return 0;
+    }
+
+    match obj.get_type() {
+        Lisp_Type::Lisp_Int0
+        | Lisp_Type::Lisp_Int1
+        | Lisp_Type::Lisp_Misc
+        | Lisp_Type::Lisp_Symbol => uint_value(obj),
+        Lisp_Type::Lisp_String => {
+            let bytes = obj.force_string().as_slice();
+            match key {
+                Some(key) => sxhash_reduce(siphash(key, bytes) as EmacsUint),
+                None => sxhash_reduce(hash_bytes(bytes)),
+            }
+        }
+        Lisp_Type::Lisp_Vectorlike => {
+            let vectorlike = obj.force_vectorlike();
+            // Vectors and records are `equal' when their elements are,
+            // and bool vectors when their bits are.  Others are `equal'
+            // only if they are `eq'.
+            if let Some(vector) = vectorlike.as_vector() {
+                sxhash_vector(vector.len() as EmacsUint, vector.as_slice(), depth, key)
+            } else if let Some(record) = vectorlike.as_record() {
+                let size = unsafe { record.header.size } as EmacsUint;
+                sxhash_vector(size, record.as_slice(), depth, key)
+            } else if let Some(bool_vector) = vectorlike.as_bool_vector() {
+                sxhash_bool_vector(bool_vector.len(), bool_vector.as_slice())
+            } else {
+                uint_value(obj)
+            }
+        }
+        Lisp_Type::Lisp_Cons => sxhash_list(obj, depth, key),
+        Lisp_Type::Lisp_Float => sxhash_float(obj.as_float().unwrap()),
+    }
+}
+
+extern "C" fn hashfn_eq(_test: *mut hash_table_test, key: LispObject) -> EmacsUint {
+    uint_value(key) ^ key.get_type() as EmacsUint
+}
+
+extern "C" fn hashfn_eql(test: *mut hash_table_test, key: LispObject) -> EmacsUint {
+    if key.is_float() {
+        hashfn_equal(test, key)
+    } else {
+        hashfn_eq(test, key)
+    }
+}
+
+extern "C" fn hashfn_equal(_test: *mut hash_table_test, key: LispObject) -> EmacsUint {
+    sxhash(key, 0)
+}
+
+extern "C" fn hashfn_equal_seeded(_test: *mut hash_table_test, key: LispObject) -> EmacsUint {
+    sxhash_1(key, 0, Some(*SESSION_SIP_KEY))
+}
+
+/// Hash KEY with the hash function of `define-hash-table-test', whose
+/// result is hashed as by `eq'.
+unsafe extern "C" fn hashfn_user_defined(test: *mut hash_table_test, key: LispObject) -> EmacsUint {
+    let hash = call!((*test).user_hash_function, key);
+    hashfn_eq(test, hash)
+}
+
+extern "C" fn cmpfn_eql(_test: *mut hash_table_test, a: LispObject, b: LispObject) -> bool {
+    match (a.as_float(), b.as_float()) {
+        (Some(x), Some(y)) => x == y,
+        _ => false,
+    }
+}
+
+extern "C" fn cmpfn_equal(_test: *mut hash_table_test, a: LispObject, b: LispObject) -> bool {
+    a.equal(b)
+}
+
+unsafe extern "C" fn cmpfn_user_defined(
+    test: *mut hash_table_test,
+    a: LispObject,
+    b: LispObject,
+) -> bool {
+    call!((*test).user_cmp_function, a, b).is_not_nil()
+}
+
+/// The test of hash tables made with `:test eq'.  Keys that are `eq'
+/// match without calling a comparison function.
+#[no_mangle]
+pub static hashtest_eq: hash_table_test = hash_table_test {
+    name: Qeq,
+    user_hash_function: Qnil,
+    user_cmp_function: Qnil,
+    cmpfn: None,
+    hashfn: Some(hashfn_eq),
+};
+
+#[no_mangle]
+pub static hashtest_eql: hash_table_test = hash_table_test {
+    name: Qeql,
+    user_hash_function: Qnil,
+    user_cmp_function: Qnil,
+    cmpfn: Some(cmpfn_eql),
+    hashfn: Some(hashfn_eql),
+};
+
+#[no_mangle]
+pub static hashtest_equal: hash_table_test = hash_table_test {
+    name: Qequal,
+    user_hash_function: Qnil,
+    user_cmp_function: Qnil,
+    cmpfn: Some(cmpfn_equal),
+    hashfn: Some(hashfn_equal),
+};
+
+/// The test of `equal' tables made while
+/// `hash-table-rehash-seed-randomization' is non-nil.
+static hashtest_equal_seeded: hash_table_test = hash_table_test {
+    name: Qequal,
+    user_hash_function: Qnil,
+    user_cmp_function: Qnil,
+    cmpfn: Some(cmpfn_equal),
+    hashfn: Some(hashfn_equal_seeded),
+};
+
+/// The weak hash tables, chained through their `next_weak`.  Tables are
+/// added when they are made, and dropped by `sweep_weak_hash_tables`
+/// once they are garbage.
+#[no_mangle]
+pub static mut weak_hash_tables: *mut Lisp_Hash_Table = ptr::null_mut();
+
+/// Accessors of the slots of a hash table.  These also work during
+/// garbage collection, when the vectors of a marked table have their
+/// mark flag set in their size, so they don't check their indices.
+impl LispHashTableRef {
+    fn is_marked(self) -> bool {
+        self.header.size & ARRAY_MARK_FLAG != 0
+    }
+
+    unsafe fn slot(vector: LispObject, idx: isize) -> LispObject {
+        vector.as_vector_unchecked().get_unchecked(idx as usize)
+    }
+
+    unsafe fn set_slot(vector: LispObject, idx: isize, value: LispObject) {
+        vector
+            .as_vector_unchecked()
+            .set_unchecked(idx as usize, value)
+    }
+
+    unsafe fn index_size(self) -> isize {
+        self.index.as_vector_unchecked().header.size & !ARRAY_MARK_FLAG
+    }
+
+    /// Return the first entry of the collision chain of BUCKET, or -1.
+    unsafe fn bucket(self, bucket: isize) -> isize {
+        Self::slot(self.index, bucket).to_fixnum_unchecked() as isize
+    }
+
+    /// Return the entry after IDX in its chain, or -1.
+    unsafe fn next_entry(self, idx: isize) -> isize {
+        Self::slot(self.next, idx).to_fixnum_unchecked() as isize
+    }
+}
+
+/// Sweep the weak hash table H.  If REMOVE_ENTRIES, remove the entries
+/// that don't survive the current garbage collection.  Otherwise, mark
+/// the entries that are in use.  Return whether anything was marked.
+unsafe fn sweep_weak_table(h: LispHashTableRef, remove_entries: bool) -> bool {
+    let weakness = Weakness::from_symbol(h.weak).unwrap_or_else(|| emacs_abort());
+    let mut marked = false;
+
+    for bucket in 0..h.index_size() {
+        // Follow the collision chain, removing the entries that don't
+        // survive.
+        let mut prev = -1;
+        let mut i = h.bucket(bucket);
+        while i >= 0 {
+            let key = LispHashTableRef::slot(h.key_and_value, 2 * i);
+            let value = LispHashTableRef::slot(h.key_and_value, 2 * i + 1);
+            let key_survives = survives_gc_p(key);
+            let value_survives = survives_gc_p(value);
+            let remove = weakness.removes(key_survives, value_survives);
+            let next = h.next_entry(i);
+
+            if remove_entries {
+                if remove {
+                    // Take the entry out of the collision chain, and
+                    // free it.
+                    if prev < 0 {
+                        LispHashTableRef::set_slot(h.index, bucket, next.into());
+                    } else {
+                        LispHashTableRef::set_slot(h.next, prev, next.into());
+                    }
+                    h.free_entry(i);
+                } else {
+                    prev = i;
+                }
+            } else if !remove {
+                // Make sure the key and the value survive.
+                if !key_survives {
+                    mark_object(key);
+                    marked = true;
+                }
+                if !value_survives {
+                    mark_object(value);
+                    marked = true;
+                }
+            }
+            i = next;
+        }
+    }
+
+    marked
+}
+
+/// Remove the entries of weak hash tables that don't survive the current
+/// garbage collection, and drop the weak tables that don't survive from
+/// `weak_hash_tables`.  Called from gc_sweep, before any object is
+/// unmarked.
+#[no_mangle]
+pub unsafe extern "C" fn sweep_weak_hash_tables() {
+    // Mark the keys and values that are in use until nothing changes.
+    // An entry X -> Y of a value-weak table A must stay when Y is used
+    // in a key-weak table B, Z -> Y, even if B comes after A.
+    loop {
+        let mut marked = false;
+        let mut next = weak_hash_tables;
+        while !next.is_null() {
+            let h = LispHashTableRef::new(next);
+            next = h.next_weak;
+            if h.is_marked() {
+                marked |= sweep_weak_table(h, false);
+            }
+        }
+        if !marked {
+            break;
+        }
+    }
+
+    // Sweep the entries of the tables that are used, and forget the
+    // others.
+    let mut used: *mut Lisp_Hash_Table = ptr::null_mut();
+    let mut next = weak_hash_tables;
+    while !next.is_null() {
+        let mut h = LispHashTableRef::new(next);
+        next = h.next_weak;
+        if h.is_marked() {
+            if h.count > 0 {
+                sweep_weak_table(h, true);
+            }
+            h.next_weak = used;
+            used = h.as_mut();
+        }
+    }
+    weak_hash_tables = used;
+}
+
+/// Make a hash table that compares keys with TEST, with room for SIZE
+/// entries, 0 <= SIZE <= `MOST_POSITIVE_FIXNUM`.
+///
+/// When the table is full, it grows by -REHASH_SIZE entries if that is
+/// a negative integer, or else by REHASH_SIZE times its size.  It is
+/// full when the ratio of its entries to its size gets to
+/// REHASH_THRESHOLD, 0 < REHASH_THRESHOLD <= 1.  WEAK is nil or the
+/// weakness of the table, and PURE says whether `purecopy' may copy the
+/// table to pure storage, after which it can't change.
+#[no_mangle]
+pub extern "C" fn make_hash_table(
+    test: hash_table_test,
+    size: EmacsInt,
+    rehash_size: f32,
+    rehash_threshold: f32,
+    weak: LispObject,
+    pure: bool,
+) -> LispObject {
+    debug_assert!(test.name.is_symbol());
+    debug_assert!(0 <= size && size <= MOST_POSITIVE_FIXNUM);
+    debug_assert!(rehash_size <= -1.0 || 0.0 < rehash_size);
+    debug_assert!(0.0 < rehash_threshold && rehash_threshold <= 1.0);
+
+    let size = size.max(1);
+    let index_size = index_size_for(size, rehash_threshold);
+    if index_size_bound() < index_size.max(2 * size) {
+        error!("Hash table too large");
+    }
+
+    let mut h = LispHashTableRef::allocate();
+    h.test = test;
+    h.weak = weak;
+    h.rehash_threshold = rehash_threshold;
+    h.rehash_size = rehash_size;
+    h.count = 0;
+    h.pure = pure;
+    unsafe {
+        h.key_and_value = Fmake_vector((2 * size).into(), Qnil);
+        h.hash = Fmake_vector(size.into(), Qnil);
+        h.next = Fmake_vector(size.into(), LispObject::from(-1_isize));
+        h.index = Fmake_vector(index_size.into(), LispObject::from(-1_isize));
+
+        // All entries are free.
+        for i in 0..size as isize - 1 {
+            LispHashTableRef::set_slot(h.next, i, (i + 1).into());
+        }
+    }
+    h.next_free = 0;
+
+    if weak.is_not_nil() {
+        unsafe {
+            h.next_weak = weak_hash_tables;
+            weak_hash_tables = h.as_mut();
+        }
+    }
+    h.into()
+}
+
+/// Return the index of the entry of KEY in H, or -1 if there is none.
+/// If HASH is not null, store the hash code of KEY there.
+#[no_mangle]
+pub unsafe extern "C" fn hash_lookup(
+    h: *mut Lisp_Hash_Table,
+    key: LispObject,
+    hash: *mut EmacsUint,
+) -> ptrdiff_t {
+    let h = LispHashTableRef::new(h);
+    let hash_code = h.hash_code(key);
+    if !hash.is_null() {
+        *hash = hash_code;
+    }
+    h.find(key, hash_code).map_or(-1, |(_, idx)| idx)
+}
+
+/// Add an entry for KEY, whose hash code is HASH, with VALUE to H, and
+/// return its index.
+#[no_mangle]
+pub unsafe extern "C" fn hash_put(
+    h: *mut Lisp_Hash_Table,
+    key: LispObject,
+    value: LispObject,
+    hash: EmacsUint,
+) -> ptrdiff_t {
+    LispHashTableRef::new(h).put(key, value, hash)
+}
+
+/// Remove the entry of KEY from H, if there is one.
+#[no_mangle]
+pub unsafe extern "C" fn hash_remove_from_table(h: *mut Lisp_Hash_Table, key: LispObject) {
+    LispHashTableRef::new(h).remove(key)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn hash_clear(h: *mut Lisp_Hash_Table) {
+    LispHashTableRef::new(h).clear()
+}
+
+/// Return an integer hash code for OBJ suitable for `eq'.
+/// If (eq A B), then (= (sxhash-eq A) (sxhash-eq B)).
+#[lisp_fn]
+pub fn sxhash_eq(obj: LispObject) -> EmacsInt {
+    hashfn_eq(ptr::null_mut(), obj) as EmacsInt
+}
+
+/// Return an integer hash code for OBJ suitable for `eql'.
+/// If (eql A B), then (= (sxhash-eql A) (sxhash-eql B)).
+#[lisp_fn]
+pub fn sxhash_eql(obj: LispObject) -> EmacsInt {
+    hashfn_eql(ptr::null_mut(), obj) as EmacsInt
+}
+
+/// Return an integer hash code for OBJ suitable for `equal'.
+/// If (equal A B), then (= (sxhash-equal A) (sxhash-equal B)).
+#[lisp_fn]
+pub fn sxhash_equal(obj: LispObject) -> EmacsInt {
+    hashfn_equal(ptr::null_mut(), obj) as EmacsInt
+}
+
+/// Create and return a new hash table.
+///
+/// Arguments are specified as keyword/argument pairs.  The following
+/// arguments are defined:
+///
+/// :test TEST -- TEST must be a symbol that specifies how to compare
+/// keys.  Default is `eql'.  Predefined are the tests `eq', `eql', and
+/// `equal'.  User-supplied test and hash functions can be specified via
+/// `define-hash-table-test'.
+///
+/// :size SIZE -- A hint as to how many elements will be put in the table.
+/// Default is 65.
+///
+/// :rehash-size REHASH-SIZE - Indicates how to expand the table when it
+/// fills up.  If REHASH-SIZE is an integer, increase the size by that
+/// amount.  If it is a float, it must be > 1.0, and the new size is the
+/// old size multiplied by that factor.  Default is 1.5.
+///
+/// :rehash-threshold THRESHOLD -- THRESHOLD must a float > 0, and <= 1.0.
+/// Resize the hash table when the ratio (table entries / table size)
+/// exceeds an approximation to THRESHOLD.  Default is 0.8125.
+///
+/// :weakness WEAK -- WEAK must be one of nil, t, `key', `value',
+/// `key-or-value', or `key-and-value'.  If WEAK is not nil, the table
+/// returned is a weak table.  Key/value pairs are removed from a weak
+/// hash table when there are no non-weak references pointing to their
+/// key, value, one of key or value, or both key and value, depending on
+/// WEAK.  WEAK t is equivalent to `key-and-value'.  Default value of WEAK
+/// is nil.
+///
+/// :purecopy PURECOPY -- If PURECOPY is non-nil, the table can be copied
+/// to pure storage when Emacs is being dumped, making the contents of the
+/// table read only. Any further changes to purified tables will result
+/// in an error.
+///
+/// usage: (make-hash-table &rest KEYWORD-ARGS)
+#[lisp_fn(name = "make-hash-table", c_name = "make_hash_table")]
+pub fn make_hash_table_lisp(args: &mut [LispObject]) -> LispObject {
+    // The arguments consumed so far.
+    let mut used = vec![false; args.len()];
+    let mut key_arg = |key: LispObject| {
+        for i in 1..args.len() {
+            if !used[i - 1] && args[i - 1].eq(key) {
+                used[i - 1] = true;
+                used[i] = true;
+                return Some(args[i]);
+            }
+        }
+        None
+    };
+
+    let test = key_arg(QCtest).unwrap_or(Qeql);
+    let testdesc = if test.eq(Qeq) {
+        hashtest_eq
+    } else if test.eq(Qeql) {
+        hashtest_eql
+    } else if test.eq(Qequal) {
+        if unsafe { globals.hash_table_rehash_seed_randomization } {
+            hashtest_equal_seeded
+        } else {
+            hashtest_equal
+        }
+    } else {
+        // A test of `define-hash-table-test'.
+        let prop = get(test.into(), Qhash_table_test);
+        match prop
+            .as_cons()
+            .and_then(|c| c.cdr().as_cons().map(|d| (c.car(), d.car())))
+        {
+            Some((cmp, hash)) => hash_table_test {
+                name: test,
+                user_hash_function: hash,
+                user_cmp_function: cmp,
+                cmpfn: Some(cmpfn_user_defined),
+                hashfn: Some(hashfn_user_defined),
+            },
+            None => signal_error("Invalid hash table test", test),
+        }
+    };
+
+    let pure = key_arg(QCpurecopy).map_or(false, |p| p.is_not_nil());
+
+    let size_arg = key_arg(QCsize).unwrap_or(Qnil);
+    let size = if size_arg.is_nil() {
+        DEFAULT_HASH_SIZE
+    } else if size_arg.is_natnum() {
+        size_arg.force_fixnum()
+    } else {
+        signal_error("Invalid hash table size", size_arg)
+    };
+
+    let rehash_size = match key_arg(QCrehash_size) {
+        None => DEFAULT_REHASH_SIZE,
+        Some(arg) => {
+            if let Some(n) = arg.as_fixnum().filter(|&n| n > 0) {
+                -(n as f32)
+            } else if let Some(f) = arg
+                .as_float()
+                .map(|f| (f - 1.0) as f32)
+                .filter(|&f| f > 0.0)
+            {
+                f
+            } else {
+                signal_error("Invalid hash table rehash size", arg)
+            }
+        }
+    };
+
+    let threshold_arg = key_arg(QCrehash_threshold);
+    let rehash_threshold = threshold_arg.map_or(DEFAULT_REHASH_THRESHOLD, |arg| {
+        arg.as_float().map_or(0.0, |f| f as f32)
+    });
+    if !(0.0 < rehash_threshold && rehash_threshold <= 1.0) {
+        signal_error(
+            "Invalid hash table rehash threshold",
+            threshold_arg.unwrap_or(Qnil),
+        );
+    }
+
+    let mut weak = key_arg(QCweakness).unwrap_or(Qnil);
+    if weak.is_t() {
+        weak = Qkey_and_value;
+    }
+    if weak.is_not_nil() && Weakness::from_symbol(weak).is_none() {
+        signal_error("Invalid hash table weakness", weak);
+    }
+
+    if let Some(i) = used.iter().position(|&u| !u) {
+        signal_error("Invalid argument list", args[i]);
+    }
+
+    make_hash_table(testdesc, size, rehash_size, rehash_threshold, weak, pure)
+}
+
 /// Return a copy of hash table TABLE.
 /// Keys and values are not copied, only the table itself is.
 #[lisp_fn]
@@ -233,10 +1219,10 @@ pub fn copy_hash_table(mut table: LispHashTableRef) -> LispHashTableRef {
     unsafe { new_table.copy(table) };
     assert_ne!(new_table.as_ptr(), table.as_ptr());
 
-    let key_and_value = unsafe { Fcopy_sequence(new_table.get_key_and_value()) };
-    let hash = unsafe { Fcopy_sequence(new_table.get_hash()) };
-    let next = unsafe { Fcopy_sequence(new_table.get_next()) };
-    let index = unsafe { Fcopy_sequence(new_table.get_index()) };
+    let key_and_value = copy_sequence(new_table.get_key_and_value());
+    let hash = copy_sequence(new_table.get_hash());
+    let next = copy_sequence(new_table.get_next());
+    let index = copy_sequence(new_table.get_index());
     new_table.set_key_and_value(key_and_value);
     new_table.set_hash(hash);
     new_table.set_next(next);
@@ -308,6 +1294,17 @@ pub fn hash_table_count(table: LispHashTableRef) -> EmacsInt {
     table.count as EmacsInt
 }
 
+/// Return the current rehash size of TABLE.
+#[lisp_fn]
+pub fn hash_table_rehash_size(table: LispHashTableRef) -> LispObject {
+    let rehash_size = EmacsDouble::from(table.rehash_size);
+    if rehash_size < 0.0 {
+        LispObject::from((-rehash_size as EmacsInt).min(MOST_POSITIVE_FIXNUM))
+    } else {
+        LispObject::from_float(rehash_size + 1.0)
+    }
+}
+
 /// Return the current rehash threshold of TABLE.
 #[lisp_fn]
 pub fn hash_table_rehash_threshold(table: LispHashTableRef) -> EmacsDouble {
@@ -363,4 +1360,55 @@ pub fn define_hash_table_test(
     put(name, sym, list(&[test, hash]))
 }
 
+#[no_mangle]
+pub extern "C" fn syms_of_hashtable() {
+    /// Non-nil means `equal' hash tables hash strings with a random seed.
+    /// This applies to the tables that `make-hash-table' makes after it is
+    /// set.  The seed is chosen once per session, so that the hash codes
+    /// of strings can't be predicted, and many strings can't be made to
+    /// collide in a table, slowing down every lookup.  Set this when
+    /// tables are keyed by strings from untrusted sources, such as the
+    /// JSON of a network protocol.  `sxhash-equal' is unaffected.
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    defvar_bool!(hash_table_rehash_seed_randomization, "hash-table-rehash-seed-randomization", false);
+}
+
 include!(concat!(env!("OUT_DIR"), "/hashtable_exports.rs"));
+
+#[test]
+fn test_weakness_removes() {
+    assert!(Weakness::Key.removes(false, true));
+    assert!(!Weakness::Key.removes(true, false));
+    assert!(Weakness::Value.removes(true, false));
+    assert!(!Weakness::KeyOrValue.removes(false, true));
+    assert!(Weakness::KeyOrValue.removes(false, false));
+    assert!(Weakness::KeyAndValue.removes(true, false));
+    assert!(!Weakness::KeyAndValue.removes(true, true));
+}
+
+#[test]
+fn test_sxhash_combine() {
+    assert_eq!(sxhash_combine(0, 5), 5);
+    assert_eq!(sxhash_combine(1, 0), 16);
+    // The high bits of X rotate into the low bits.
+    let top = 0xf << (8 * mem::size_of::<EmacsUint>() - 4);
+    assert_eq!(sxhash_combine(top, 0), 0xf);
+    assert_eq!(hash_bytes(b"ab"), sxhash_combine(sxhash_combine(0, 97), 98));
+
+    for &x in &[0, 1, EmacsUint::max_value(), top, 0x1234_5678_9abc_def0] {
+        assert_eq!(sxhash_reduce(x) & !(INTMASK as EmacsUint), 0);
+    }
+}
+
+#[test]
+fn test_siphash() {
+    // The reference vectors of SipHash-2-4, for the key 00 01 ... 0f and
+    // the messages 00 01 ... (N - 1).
+    let key = (0x0706_0504_0302_0100, 0x0f0e_0d0c_0b0a_0908);
+    let message: Vec<u8> = (0..16).collect();
+    assert_eq!(siphash(key, &message[..0]), 0x726f_db47_dd0e_0e31);
+    assert_eq!(siphash(key, &message[..8]), 0x93f5_f579_9a93_2462);
+    assert_eq!(siphash(key, &message[..15]), 0xa129_ca61_49be_45e5);
+
+    assert_ne!(siphash(key, b"a"), siphash((0, 0), b"a"));
+}
@@ -0,0 +1,466 @@
+//! Gathering what the help commands show about functions and
+//! variables.  help-fns.el does the rendering; the plists built here
+//! hold everything it needs to know about the object described.
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::{LispBufferOrCurrent, LispBufferRef},
+    data::{aref, indirect_function, subr_arglist, subr_lang},
+    eval::{commandp, func_arity, unbind_to, SpecBinding},
+    keymap::keymapp,
+    lisp::{defsubr, is_autoload, LispObject},
+    lists::{car_safe, cdr_safe, get, memq, nth},
+    lists::{LispConsCircularChecks, LispConsEndChecks},
+    obarray::intern,
+    reader::read_from_string,
+    remacs_sys::{
+        record_unwind_current_buffer, set_buffer_internal_1, EmacsInt, Fdocumentation,
+        Fprin1_to_string, Fsubstring, Fvariable_binding_locus,
+    },
+    remacs_sys::{Qclosure, Qdefun, Qlambda, Qmacro, Qnil, Qt, Qvoid_function},
+    sequences::concat2,
+    symbols::{boundp, fboundp, symbol_value, LispSymbolRef},
+    threads::c_specpdl_index,
+};
+
+/// Call the nadvice.el function NAME with argument ARG.
+fn advice(name: &str, arg: LispObject) -> LispObject {
+    call!(intern(name).into(), arg)
+}
+
+/// FUNCTION taken apart the way the help commands need it.  REAL_FUNCTION
+/// is FUNCTION with any advice peeled off, DEF the definition of
+/// REAL_FUNCTION, ALIASED whether FUNCTION is an alias, and REAL_DEF the
+/// function at the end of the alias chain (or DEF when there is none).
+struct FunctionInfo {
+    real_function: LispObject,
+    def: LispObject,
+    aliased: bool,
+    real_def: LispObject,
+}
+
+impl FunctionInfo {
+    fn analyze(function: LispObject) -> Self {
+        let advised = function.is_symbol()
+            && advice("advice--p", advice("advice--symbol-function", function)).is_not_nil();
+        // If the function is advised, use the symbol that has the real
+        // definition, if that symbol is already set up.
+        let real_function = if advised {
+            let inner = advice("advice--cd*r", advice("advice--symbol-function", function));
+            if inner.is_nil() {
+                function
+            } else {
+                inner
+            }
+        } else {
+            function
+        };
+
+        let def = match real_function.as_symbol() {
+            None => real_function,
+            Some(sym) => {
+                let def = sym.get_function();
+                if def.is_not_nil() {
+                    def
+                } else if get(sym, intern("function-documentation").into()).is_not_nil() {
+                    Qnil
+                } else {
+                    xsignal!(Qvoid_function, real_function)
+                }
+            }
+        };
+
+        let aliased = def.is_not_nil()
+            && (def.is_symbol()
+                // Advised & aliased function.
+                || (advised && real_function.is_symbol() && !is_autoload(def))
+                || def.as_subr().map_or(false, |subr| {
+                    function.as_symbol().map_or(true, |sym| {
+                        let name = unsafe { std::ffi::CStr::from_ptr(subr.symbol_name()) };
+                        name.to_bytes() != sym.symbol_name().force_string().as_slice()
+                    })
+                }));
+
+        let real_def = match def.as_subr() {
+            Some(subr) => {
+                let name = unsafe { std::ffi::CStr::from_ptr(subr.symbol_name()) };
+                intern(name.to_string_lossy()).into()
+            }
+            None if aliased => {
+                let mut f = real_function;
+                while let Some(sym) = f.as_symbol() {
+                    let next = sym.get_function();
+                    if next.is_nil() || !next.is_symbol() {
+                        break;
+                    }
+                    f = next;
+                }
+                f
+            }
+            None => def,
+        };
+
+        Self {
+            real_function,
+            def,
+            aliased,
+            real_def,
+        }
+    }
+
+    /// The symbol naming what kind of function-like object this is.
+    fn kind(&self, function: LispObject) -> LispObject {
+        let def = self.def;
+        let name = if def.is_string() || def.is_vector() {
+            "keyboard-macro"
+        } else if function.as_symbol().map_or(false, |sym| {
+            get(sym, intern("reader-construct").into()).is_not_nil()
+        }) {
+            "reader-construct"
+        } else if self.aliased {
+            // Aliases are Lisp functions, so they come before functions.
+            "alias"
+        } else if let Some(subr) = def.as_subr() {
+            if subr.is_unevalled() {
+                "special-form"
+            } else {
+                "built-in-function"
+            }
+        } else if is_autoload(def) {
+            "autoload"
+        } else if car_safe(def).eq(Qmacro)
+            // For advised macros, DEF is a lambda expression or a
+            // byte-code function, so macros come before functions.
+            || call!(intern("macrop").into(), function).is_not_nil()
+        {
+            "macro"
+        } else if def.is_byte_code_function() {
+            "compiled-function"
+        } else if def.is_module_function() {
+            "module-function"
+        } else if car_safe(def).eq(Qlambda) {
+            "lambda"
+        } else if car_safe(def).eq(Qclosure) {
+            "closure"
+        } else if keymapp(def) {
+            let is_full = cdr_safe(def)
+                .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+                .any(|elt| elt.is_char_table());
+            if is_full {
+                "keymap"
+            } else {
+                "sparse-keymap"
+            }
+        } else {
+            return Qnil;
+        };
+        intern(name).into()
+    }
+}
+
+/// The argument part of the usage info at the end of the doc string
+/// DOC, that is ARGS in a trailing "\n\n(fn ARGS)".  Return None if DOC
+/// has no usage info.
+fn usage_arguments(doc: &[u8]) -> Option<&[u8]> {
+    const MARKER: &[u8] = b"\n\n(fn";
+    let start = doc.windows(MARKER.len()).rposition(|w| w == MARKER)? + MARKER.len();
+    let tail = &doc[start..];
+    let args = &tail[..tail.len().checked_sub(1)?];
+    if tail.last() != Some(&b')') || args.contains(&b'\n') {
+        return None;
+    }
+    match args.split_first() {
+        None => Some(args),
+        Some((b' ', rest)) => Some(rest),
+        Some(_) => None,
+    }
+}
+
+/// The argument list in the usage info of DEF's doc string, if it has
+/// one and every element is a plausible argument name.
+fn documented_arglist(def: LispObject) -> Option<LispObject> {
+    let doc = unsafe { Fdocumentation(def, Qnil) }.as_string()?;
+    let args = usage_arguments(doc.as_slice())?;
+    let source = format!("({})", String::from_utf8_lossy(args).to_lowercase());
+    let arglist = car_safe(read_from_string(
+        LispObject::from(source.as_str()).into(),
+        Qnil,
+        Qnil,
+    ));
+
+    let valid = arglist
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+        .all(|arg| match arg.as_symbol() {
+            None => false,
+            Some(sym) => {
+                let name = sym.symbol_name().force_string();
+                match name.as_slice().first() {
+                    Some(b'&') => {
+                        memq(arg, list!(intern("&rest"), intern("&optional"))).is_not_nil()
+                    }
+                    _ => !name.as_slice().contains(&b'.'),
+                }
+            }
+        });
+    if valid {
+        Some(arglist)
+    } else {
+        None
+    }
+}
+
+/// An argument list made up from DEF's arity: (arg1 ... &optional ...)
+/// or (arg1 ... &rest rest).
+fn arity_arglist(def: LispObject) -> LispObject {
+    let arity = func_arity(def);
+    let min = EmacsInt::from(car_safe(arity));
+    let max = cdr_safe(arity);
+
+    let arg = |i: EmacsInt| LispObject::from(intern(format!("arg{}", i)));
+    let mut args: Vec<LispObject> = (1..=min).map(arg).collect();
+    match max.as_fixnum() {
+        Some(max) => {
+            if max > min {
+                args.push(intern("&optional").into());
+                args.extend((min + 1..=max).map(arg));
+            }
+        }
+        None => {
+            args.push(intern("&rest").into());
+            args.push(intern("rest").into());
+        }
+    }
+    args.into_iter()
+        .rev()
+        .fold(Qnil, |list, arg| LispObject::cons(arg, list))
+}
+
+/// Return a formal argument list for the function DEF.
+/// If PRESERVE-NAMES is non-nil, return a formal arglist that uses
+/// the same names as used in the original source code, when possible.
+/// For built-in functions, those names come from the usage info at the
+/// end of the doc string.  Functions defined in Rust record the names
+/// of their arguments, so theirs are known even without PRESERVE-NAMES.
+#[lisp_fn(min = "1", name = "help--function-arglist")]
+pub fn help_function_arglist(def: LispObject, preserve_names: bool) -> LispObject {
+    let mut def = def;
+    // Handle symbols aliased to other symbols.
+    if def.as_symbol().map_or(false, fboundp) {
+        def = indirect_function(def);
+    }
+    // Advice wrappers have "catch all" args, so fetch the actual
+    // underlying function to find the real arguments.
+    while advice("advice--p", def).is_not_nil() {
+        def = advice("advice--cdr", def);
+    }
+    // If definition is a macro, find the function inside it.
+    if car_safe(def).eq(Qmacro) {
+        def = cdr_safe(def);
+    }
+
+    let byte_code_arglist = if def.is_byte_code_function() {
+        Some(aref(def, 0))
+    } else {
+        None
+    };
+    match byte_code_arglist {
+        Some(arglist) if arglist.is_list() => return arglist,
+        _ => {}
+    }
+    if car_safe(def).eq(Qlambda) {
+        nth(1, def)
+    } else if car_safe(def).eq(Qclosure) {
+        nth(2, def)
+    } else if byte_code_arglist.map_or(false, |a| a.is_fixnum())
+        || def.is_subr()
+        || def.is_module_function()
+    {
+        let documented = if preserve_names {
+            documented_arglist(def)
+        } else {
+            None
+        };
+        documented
+            .or_else(|| {
+                def.as_subr()
+                    .filter(|subr| subr.arglist().is_some())
+                    .map(subr_arglist)
+            })
+            .unwrap_or_else(|| arity_arglist(def))
+    } else if is_autoload(def) && !nth(4, def).eq(intern("keymap").into()) {
+        LispObject::from("[Arg list not available until function definition is loaded.]")
+    } else {
+        Qt
+    }
+}
+
+/// Return a plist describing FUNCTION, for `describe-function'.
+/// The properties are:
+///
+///  :real-function  FUNCTION, or the function under its advice.
+///  :def            The definition of :real-function.
+///  :aliased        Non-nil if FUNCTION is an alias.
+///  :real-def       The function an alias ultimately refers to, or :def.
+///  :kind           A symbol saying what sort of function this is, one of
+///                  `keyboard-macro', `reader-construct', `alias',
+///                  `special-form', `built-in-function', `autoload',
+///                  `macro', `compiled-function', `module-function',
+///                  `lambda', `closure', `keymap', `sparse-keymap', or nil.
+///  :interactive    Non-nil if the definition is a command.
+///  :arglist        The argument list, as `help-function-arglist' with
+///                  PRESERVE-NAMES returns it.
+///  :file           Where FUNCTION is defined, as returned by
+///                  `find-lisp-object-file-name'.
+///  :language       For functions defined in C-source, "C" or "Rust".
+#[lisp_fn(name = "help--function-info")]
+pub fn help_function_info(function: LispObject) -> LispObject {
+    let info = FunctionInfo::analyze(function);
+    let kind = info.kind(function);
+    let arglist_of = if info.def.is_subr() {
+        info.def
+    } else {
+        info.real_def
+    };
+    let file = call!(
+        intern("find-lisp-object-file-name").into(),
+        function,
+        if info.aliased { Qdefun } else { info.def }
+    );
+    let language = if file.eq(intern("C-source").into()) {
+        indirect_function(function)
+            .as_subr()
+            .map_or(Qnil, subr_lang)
+    } else {
+        Qnil
+    };
+
+    list!(
+        intern(":real-function"),
+        info.real_function,
+        intern(":def"),
+        info.def,
+        intern(":aliased"),
+        info.aliased,
+        intern(":real-def"),
+        info.real_def,
+        intern(":kind"),
+        kind,
+        intern(":interactive"),
+        commandp(info.def, false),
+        intern(":arglist"),
+        help_function_arglist(arglist_of, true),
+        intern(":file"),
+        file,
+        intern(":language"),
+        language
+    )
+}
+
+/// VALUE printed the way `describe-variable' shows it, cut down to
+/// LIMIT characters.  Return the text and whether it was truncated.
+fn printed_value(value: LispObject, limit: Option<EmacsInt>) -> (LispObject, bool) {
+    let binding = SpecBinding::push(intern("print-quoted").into(), Qt);
+    binding.bind(intern("print-circle").into(), Qt);
+    let mut printed = binding.unbind(unsafe { Fprin1_to_string(value, Qnil) });
+
+    if value.is_symbol() && !value.is_nil() && !value.is_t() {
+        printed = call!(
+            intern("format-message").into(),
+            LispObject::from("`%s'"),
+            printed
+        );
+    }
+    let length = printed.force_string().len_chars() as EmacsInt;
+    match limit {
+        Some(limit) if length > limit => {
+            let head = unsafe { Fsubstring(printed, 0.into(), limit.into()) };
+            (concat2(head, LispObject::from("...")), true)
+        }
+        _ => (printed, false),
+    }
+}
+
+/// Return a plist describing VARIABLE as seen from BUFFER, for
+/// `describe-variable'.  BUFFER defaults to the current buffer.
+/// If LIMIT is non-nil, the printed value is cut down to that many
+/// characters, followed by "...".  The properties are:
+///
+///  :void             Non-nil if VARIABLE has no value in BUFFER.
+///  :value            The value of VARIABLE in BUFFER.
+///  :printed          The value printed with `print-quoted' and
+///                    `print-circle' bound to t, quoted if it is a symbol.
+///  :truncated        Non-nil if :printed was cut down to LIMIT.
+///  :locus            The buffer or frame VARIABLE is local to, if any.
+///  :file             Where VARIABLE is defined, as returned by
+///                    `find-lisp-object-file-name'.
+///  :permanent-local  VARIABLE's `permanent-local' property.
+#[lisp_fn(min = "1", name = "help--variable-info")]
+pub fn help_variable_info(
+    variable: LispSymbolRef,
+    buffer: LispBufferOrCurrent,
+    limit: Option<EmacsInt>,
+) -> LispObject {
+    let mut buffer: LispBufferRef = buffer.into();
+    if !buffer.is_live() {
+        error!("Selecting deleted buffer");
+    }
+
+    let count = c_specpdl_index();
+    unsafe {
+        record_unwind_current_buffer();
+        set_buffer_internal_1(buffer.as_mut());
+    }
+    let void = !boundp(variable);
+    let (value, locus) = if void {
+        (Qnil, Qnil)
+    } else {
+        (symbol_value(variable), unsafe {
+            Fvariable_binding_locus(variable.into())
+        })
+    };
+    let file = call!(
+        intern("find-lisp-object-file-name").into(),
+        variable.into(),
+        intern("defvar").into()
+    );
+    unbind_to(count, Qnil);
+
+    let (printed, truncated) = if void {
+        (Qnil, false)
+    } else {
+        printed_value(value, limit)
+    };
+
+    list!(
+        intern(":void"),
+        void,
+        intern(":value"),
+        value,
+        intern(":printed"),
+        printed,
+        intern(":truncated"),
+        truncated,
+        intern(":locus"),
+        locus,
+        intern(":file"),
+        file,
+        intern(":permanent-local"),
+        get(variable, intern("permanent-local").into())
+    )
+}
+
+include!(concat!(env!("OUT_DIR"), "/help_exports.rs"));
+
+#[test]
+fn test_usage_arguments() {
+    assert_eq!(
+        usage_arguments(b"Doc.\n\n(fn STRING &optional START)"),
+        Some(&b"STRING &optional START"[..])
+    );
+    assert_eq!(usage_arguments(b"\n\n(fn)"), Some(&b""[..]));
+    assert_eq!(usage_arguments(b"Doc.\n\n(fn X)\nMore."), None);
+    assert_eq!(usage_arguments(b"Doc.\n\n(fnord X)"), None);
+    assert_eq!(usage_arguments(b"Doc without usage."), None);
+    assert_eq!(usage_arguments(b"\n\n(fn A\nB)"), None);
+}
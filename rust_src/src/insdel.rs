@@ -0,0 +1,315 @@
+//! Change hooks: running `before-change-functions',
+//! `after-change-functions' and the overlay and text property hooks
+//! around changes to buffer text, and deferring after-change calls for
+//! `combine-after-change-calls'.
+
+use libc::{c_void, ptrdiff_t};
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::{LispBufferRef, BEG},
+    eval::unbind_to,
+    lisp::defsubr,
+    lisp::LispObject,
+    lists::{LispConsCircularChecks, LispConsEndChecks},
+    marker::{copy_marker, marker_position},
+    remacs_sys::{
+        free_marker, globals, record_unwind_current_buffer, record_unwind_protect,
+        record_unwind_protect_ptr, report_interval_modification, report_overlay_modification,
+        run_hook, set_buffer_internal, specbind, update_compositions, CHECK_ALL,
+    },
+    remacs_sys::{
+        Frun_hook_with_args, Qafter_change_functions, Qbefore_change_functions, Qfirst_change_hook,
+        Qinhibit_modification_hooks, Qnil, Qt,
+    },
+    threads::{c_specpdl_index, ThreadState},
+};
+
+/// The after-change calls deferred by `combine-after-change-calls'.
+/// Each element is (BEG-UNCHANGED END-UNCHANGED CHANGE): the number of
+/// characters before and after the changed text that the change left
+/// alone, and the number of characters it added (negative if it
+/// removed some).
+declare_GC_protected_static!(combine_after_change_list, Qnil);
+
+/// The buffer the calls in `combine_after_change_list` are for.
+declare_GC_protected_static!(combine_after_change_buffer, Qnil);
+
+/// A buffer position that is followed by a marker once Lisp code that
+/// might change the buffer is about to run.
+struct TrackedPosition {
+    pos: ptrdiff_t,
+    marker: LispObject,
+}
+
+impl TrackedPosition {
+    fn new(pos: ptrdiff_t) -> Self {
+        TrackedPosition { pos, marker: Qnil }
+    }
+
+    fn track(&mut self) {
+        if self.marker.is_nil() {
+            self.marker = copy_marker(self.pos.into(), Qnil);
+        }
+    }
+
+    fn get(&self) -> ptrdiff_t {
+        if self.marker.is_nil() {
+            self.pos
+        } else {
+            marker_position(self.marker)
+        }
+    }
+
+    /// Stop tracking the position and return its final value.
+    fn release(self) -> ptrdiff_t {
+        let pos = self.get();
+        if self.marker.is_not_nil() {
+            unsafe { free_marker(self.marker) };
+        }
+        pos
+    }
+}
+
+/// The hook variable to reset by `reset_var_on_error`.
+struct ResetOnError {
+    location: *mut LispObject,
+    error: bool,
+}
+
+/// Set the variable described by PTR, a boxed `ResetOnError`, to nil if
+/// the hook it holds signaled an error, so that a broken hook function
+/// does not make every later change fail as well.  Free the box.
+extern "C" fn reset_var_on_error(ptr: *mut c_void) {
+    let arg = unsafe { Box::from_raw(ptr as *mut ResetOnError) };
+    if arg.error {
+        unsafe { *arg.location = Qnil };
+    }
+}
+
+/// Run the change hook HOOK, whose value is at LOCATION, with ARGS.
+/// The caller must unbind the specpdl entry this pushes, which may
+/// happen after this returns, so its argument lives on the heap.
+fn run_change_hook(hook: LispObject, location: *mut LispObject, args: &[LispObject]) {
+    let reset = Box::into_raw(Box::new(ResetOnError {
+        location,
+        error: true,
+    }));
+    unsafe { record_unwind_protect_ptr(Some(reset_var_on_error), reset as *mut c_void) };
+
+    let mut hook_args = vec![hook];
+    hook_args.extend_from_slice(args);
+    unsafe { Frun_hook_with_args(hook_args.len() as ptrdiff_t, hook_args.as_mut_ptr()) };
+
+    // There was no error: disarm the reset.
+    unsafe { (*reset).error = false };
+}
+
+/// Signal a change to the buffer immediately before it happens.
+/// START and END are the bounds of the text to be changed.
+///
+/// If PRESERVE_PTR is not null, *PRESERVE_PTR is relocated by holding
+/// its value in a marker while the hooks run.
+#[no_mangle]
+pub extern "C" fn signal_before_change(
+    start: ptrdiff_t,
+    end: ptrdiff_t,
+    preserve_ptr: *mut ptrdiff_t,
+) {
+    let buffer = ThreadState::current_buffer_unchecked();
+    let count = c_specpdl_index();
+    let mut start = TrackedPosition::new(start);
+    let mut end = TrackedPosition::new(end);
+    let mut preserve = if preserve_ptr.is_null() {
+        None
+    } else {
+        Some(TrackedPosition::new(unsafe { *preserve_ptr }))
+    };
+
+    unsafe { specbind(Qinhibit_modification_hooks, Qt) };
+
+    // The hooks below may change the buffer, so follow the region and
+    // *PRESERVE_PTR with markers while they run.
+    let mut track_all = |start: &mut TrackedPosition, end: &mut TrackedPosition| {
+        start.track();
+        end.track();
+        if let Some(ref mut preserve) = preserve {
+            preserve.track();
+        }
+    };
+
+    // If the buffer is unmodified, run a special hook for that case.
+    if buffer.modifications_since_save() >= buffer.modifications()
+        && unsafe { globals.Vfirst_change_hook }.is_not_nil()
+    {
+        track_all(&mut start, &mut end);
+        unsafe { run_hook(Qfirst_change_hook) };
+    }
+
+    if unsafe { globals.Vbefore_change_functions }.is_not_nil() {
+        track_all(&mut start, &mut end);
+        run_change_hook(
+            Qbefore_change_functions,
+            unsafe { &mut globals.Vbefore_change_functions },
+            &[start.get().into(), end.get().into()],
+        );
+    }
+
+    if ThreadState::current_buffer_unchecked().has_overlays() {
+        if let Some(ref mut preserve) = preserve {
+            preserve.track();
+        }
+        let (start, end) = (LispObject::from(start.get()), LispObject::from(end.get()));
+        unsafe { report_overlay_modification(start, end, false, start, end, Qnil) };
+    }
+
+    start.release();
+    end.release();
+    if let Some(preserve) = preserve {
+        unsafe { *preserve_ptr = preserve.release() };
+    }
+
+    unbind_to(count, Qnil);
+}
+
+/// Signal a change immediately after it happens.
+/// CHARPOS is the character position of the start of the changed text.
+/// LENDEL is the number of characters of the text before the change.
+/// (Not the whole buffer; just the part that was changed.)
+/// LENINS is the number of characters in that part of the text
+/// after the change.
+#[no_mangle]
+pub extern "C" fn signal_after_change(charpos: ptrdiff_t, lendel: ptrdiff_t, lenins: ptrdiff_t) {
+    if unsafe { globals.inhibit_modification_hooks } {
+        return;
+    }
+
+    let buffer = ThreadState::current_buffer_unchecked();
+
+    // If we are deferring calls to the after-change functions and there
+    // are no before-change functions, just record the change.
+    if unsafe { globals.Vcombine_after_change_calls }.is_not_nil()
+        && unsafe { globals.Vbefore_change_functions }.is_nil()
+        && !buffer.has_overlays()
+    {
+        let current: LispObject = buffer.into();
+        if combine_after_change_list.get().is_not_nil()
+            && !combine_after_change_buffer.get().eq(current)
+        {
+            combine_after_change_execute();
+        }
+
+        let elt = list!(
+            charpos - BEG,
+            buffer.z() - (charpos - lendel + lenins),
+            lenins - lendel
+        );
+        combine_after_change_list.set(LispObject::cons(elt, combine_after_change_list.get()));
+        combine_after_change_buffer.set(current);
+        return;
+    }
+
+    if combine_after_change_list.get().is_not_nil() {
+        combine_after_change_execute();
+    }
+
+    let count = c_specpdl_index();
+    unsafe { specbind(Qinhibit_modification_hooks, Qt) };
+
+    let start = LispObject::from(charpos);
+    let end = LispObject::from(charpos + lenins);
+
+    if unsafe { globals.Vafter_change_functions }.is_not_nil() {
+        run_change_hook(
+            Qafter_change_functions,
+            unsafe { &mut globals.Vafter_change_functions },
+            &[start, end, lendel.into()],
+        );
+    }
+
+    if ThreadState::current_buffer_unchecked().has_overlays() {
+        unsafe { report_overlay_modification(start, end, true, start, end, lendel.into()) };
+    }
+
+    // After an insertion, call the text properties
+    // insert-behind-hooks or insert-in-front-hooks.
+    if lendel == 0 {
+        unsafe { report_interval_modification(start, end) };
+    }
+
+    unbind_to(count, Qnil);
+}
+
+extern "C" fn restore_combine_after_change_calls(val: LispObject) {
+    unsafe { globals.Vcombine_after_change_calls = val };
+}
+
+/// This function is for use internally in the function `combine-after-change-calls'.
+#[lisp_fn]
+pub fn combine_after_change_execute() -> LispObject {
+    let changes = combine_after_change_list.get();
+    if changes.is_nil() {
+        return Qnil;
+    }
+
+    // It is rare for the buffer to be dead, but possible: insertion can
+    // call a file handler (e.g. through lock_file) which scribbles into
+    // a temp file.
+    let mut buffer: LispBufferRef = match combine_after_change_buffer
+        .get()
+        .as_buffer()
+        .and_then(|b| b.as_live())
+    {
+        Some(buffer) => buffer,
+        None => {
+            combine_after_change_list.set(Qnil);
+            return Qnil;
+        }
+    };
+
+    let count = c_specpdl_index();
+    unsafe {
+        record_unwind_current_buffer();
+        set_buffer_internal(buffer.as_mut());
+    }
+
+    // Merge the individual changes: the text left alone at either end
+    // is what all of them left alone.
+    let mut beg = buffer.z() - BEG;
+    let mut end = beg;
+    let mut change = 0;
+    for elt in changes.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+        let fields: Vec<LispObject> = elt
+            .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+            .take(3)
+            .collect();
+        if let [this_beg, this_end, this_change] = &fields[..] {
+            beg = beg.min(this_beg.as_fixnum_or_error() as ptrdiff_t);
+            end = end.min(this_end.as_fixnum_or_error() as ptrdiff_t);
+            change += this_change.as_fixnum_or_error() as ptrdiff_t;
+        }
+    }
+
+    let begpos = BEG + beg;
+    let endpos = buffer.z() - end;
+
+    // We are about to handle these, so discard them.
+    combine_after_change_list.set(Qnil);
+
+    // Now run the after-change functions for real, without deferring
+    // them again.
+    unsafe {
+        record_unwind_protect(
+            Some(restore_combine_after_change_calls),
+            globals.Vcombine_after_change_calls,
+        );
+        globals.Vcombine_after_change_calls = Qnil;
+    }
+    signal_after_change(begpos, endpos - begpos - change, endpos - begpos);
+    unsafe { update_compositions(begpos, endpos, CHECK_ALL as i32) };
+
+    unbind_to(count, Qnil)
+}
+
+include!(concat!(env!("OUT_DIR"), "/insdel_exports.rs"));
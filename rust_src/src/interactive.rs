@@ -5,25 +5,251 @@ use remacs_macros::lisp_fn;
 use crate::{
     lisp::defsubr,
     lisp::LispObject,
-    remacs_sys::{EmacsInt, Qminus},
+    lists::get,
+    obarray::intern,
+    remacs_sys::{current_kboard, globals, EmacsInt, Fprin1_to_string},
+    remacs_sys::{Qminus, Qnil, Qt},
 };
 
+/// A raw prefix argument, as found in `prefix-arg' and
+/// `current-prefix-arg'.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrefixArg {
+    /// No prefix argument.
+    None,
+    /// A lone minus sign, the symbol `-'.
+    Minus,
+    /// Digits typed after C-u or a minus sign.
+    Number(EmacsInt),
+    /// C-u typed one or more times without digits, which is the list (N).
+    Raw(EmacsInt),
+    /// Any other value, which counts as 1.
+    Other(LispObject),
+}
+
+impl PrefixArg {
+    /// The numeric meaning of this argument, as `prefix-numeric-value'.
+    pub fn numeric_value(self) -> EmacsInt {
+        match self {
+            PrefixArg::None | PrefixArg::Other(_) => 1,
+            PrefixArg::Minus => -1,
+            PrefixArg::Number(n) | PrefixArg::Raw(n) => n,
+        }
+    }
+
+    /// The argument after another C-u: that multiplies the factor by 4
+    /// if nothing but C-u was typed so far, and ends the argument
+    /// otherwise.
+    pub fn more(self) -> Self {
+        match self {
+            PrefixArg::Raw(n) => PrefixArg::Raw(n.wrapping_mul(4)),
+            PrefixArg::Minus => PrefixArg::Raw(-4),
+            arg => arg,
+        }
+    }
+
+    /// The argument after a minus sign.
+    pub fn negated(self) -> Self {
+        match self {
+            PrefixArg::Number(n) => PrefixArg::Number(-n),
+            PrefixArg::Minus => PrefixArg::None,
+            _ => PrefixArg::Minus,
+        }
+    }
+
+    /// The argument after the digit DIGIT.
+    pub fn with_digit(self, digit: EmacsInt) -> Self {
+        match self {
+            PrefixArg::Number(n) if n < 0 => PrefixArg::Number(n.wrapping_mul(10) - digit),
+            PrefixArg::Number(n) => PrefixArg::Number(n.wrapping_mul(10) + digit),
+            // Treat -0 as just -, so that -01 will work.
+            PrefixArg::Minus if digit == 0 => PrefixArg::Minus,
+            PrefixArg::Minus => PrefixArg::Number(-digit),
+            _ => PrefixArg::Number(digit),
+        }
+    }
+
+    /// How the argument is echoed while it is being typed, without the
+    /// leading "C-u".  A factor that is a power of 4 is shown as a
+    /// chain of C-u.  Return `None` for `Other` arguments, which are
+    /// printed as Lisp objects.
+    fn echo_suffix(self) -> Option<String> {
+        match self {
+            PrefixArg::None | PrefixArg::Other(_) => None,
+            PrefixArg::Minus => Some(" -".to_string()),
+            PrefixArg::Number(n) => Some(format!(" {}", n)),
+            PrefixArg::Raw(factor) => {
+                let mut suffix = String::new();
+                let mut n = factor;
+                while n > 4 && n % 4 == 0 {
+                    suffix.push_str(" C-u");
+                    n /= 4;
+                }
+                if n == 4 {
+                    Some(suffix)
+                } else {
+                    Some(format!(" ({})", factor))
+                }
+            }
+        }
+    }
+}
+
+impl From<LispObject> for PrefixArg {
+    fn from(raw: LispObject) -> Self {
+        if raw.is_nil() {
+            PrefixArg::None
+        } else if raw.eq(Qminus) {
+            PrefixArg::Minus
+        } else if let Some(n) = raw.as_fixnum() {
+            PrefixArg::Number(n)
+        } else if let Some(n) = raw.as_cons().and_then(|v| v.car().as_fixnum()) {
+            PrefixArg::Raw(n)
+        } else {
+            PrefixArg::Other(raw)
+        }
+    }
+}
+
+impl From<PrefixArg> for LispObject {
+    fn from(arg: PrefixArg) -> Self {
+        match arg {
+            PrefixArg::None => Qnil,
+            PrefixArg::Minus => Qminus,
+            PrefixArg::Number(n) => n.into(),
+            PrefixArg::Raw(n) => list!(n),
+            PrefixArg::Other(raw) => raw,
+        }
+    }
+}
+
 /// Return numeric meaning of raw prefix argument RAW.
 /// A raw prefix argument is what you get from `(interactive "P")'.
 /// Its numeric meaning is what you would get from `(interactive "p")'.
 #[lisp_fn]
 pub fn prefix_numeric_value(raw: LispObject) -> EmacsInt {
-    if raw.is_nil() {
-        1
-    } else if raw.eq(Qminus) {
-        -1
-    } else if raw.is_integer() {
-        raw.as_fixnum_or_error()
-    } else if let Some(number) = raw.as_cons().and_then(|v| v.car().as_fixnum()) {
-        number
-    } else {
-        1
+    PrefixArg::from(raw).numeric_value()
+}
+
+/// Set the prefix argument of the next command to ARG.
+fn set_prefix_arg(arg: PrefixArg) {
+    unsafe { (*current_kboard).Vprefix_arg_ = arg.into() };
+}
+
+fn prefix_command_preserve_state() {
+    call!(intern("prefix-command-preserve-state").into());
+}
+
+fn universal_argument_mode() {
+    call!(intern("universal-argument--mode").into());
+}
+
+/// Begin a numeric argument for the following command.
+/// Digits or minus sign following \\[universal-argument] make up the numeric argument.
+/// \\[universal-argument] following the digits or minus sign ends the argument.
+/// \\[universal-argument] without digits or minus sign provides 4 as argument.
+/// Repeating \\[universal-argument] without digits or minus sign
+///  multiplies the argument by 4 each time.
+/// For some commands, just \\[universal-argument] by itself serves as a flag
+/// which is different in effect from any particular numeric argument.
+/// These commands include \\[set-mark-command] and \\[start-kbd-macro].
+#[lisp_fn(intspec = "")]
+pub fn universal_argument() {
+    prefix_command_preserve_state();
+    set_prefix_arg(PrefixArg::Raw(4));
+    universal_argument_mode();
+}
+
+/// Continue or end a numeric argument begun with \\[universal-argument].
+/// A subsequent \\[universal-argument] multiplies the factor by 4 if
+/// nothing but \\[universal-argument] was typed; otherwise it ends the
+/// prefix argument.
+#[lisp_fn(intspec = "P")]
+pub fn universal_argument_more(arg: LispObject) {
+    prefix_command_preserve_state();
+    let arg = PrefixArg::from(arg).more();
+    set_prefix_arg(arg);
+    if let PrefixArg::Raw(_) = arg {
+        universal_argument_mode();
+    }
+}
+
+/// Begin a negative numeric argument for the next command.
+/// \\[universal-argument] following digits or minus sign ends the argument.
+#[lisp_fn(intspec = "P")]
+pub fn negative_argument(arg: LispObject) {
+    prefix_command_preserve_state();
+    set_prefix_arg(PrefixArg::from(arg).negated());
+    universal_argument_mode();
+}
+
+/// Part of the numeric argument for the next command.
+/// \\[universal-argument] following digits or minus sign ends the argument.
+#[lisp_fn(intspec = "P")]
+pub fn digit_argument(arg: LispObject) {
+    prefix_command_preserve_state();
+    let event = unsafe { globals.Vlast_command_event };
+    let c = match event.as_fixnum() {
+        Some(c) => c,
+        None => {
+            get(event.as_symbol_or_error(), intern("ascii-character").into()).as_fixnum_or_error()
+        }
+    };
+    let digit = (c & 0o177) - EmacsInt::from(b'0');
+    set_prefix_arg(PrefixArg::from(arg).with_digit(digit));
+    universal_argument_mode();
+}
+
+/// Return how the prefix argument being typed is echoed, or nil if none.
+#[lisp_fn(name = "universal-argument--description")]
+pub fn universal_argument_description() -> LispObject {
+    let raw = unsafe { (*current_kboard).Vprefix_arg_ };
+    match PrefixArg::from(raw) {
+        PrefixArg::None => Qnil,
+        PrefixArg::Other(raw) => {
+            let printed = unsafe { Fprin1_to_string(raw, Qt) }.as_string_or_error();
+            let echo = format!("C-u {}", String::from_utf8_lossy(printed.as_slice()));
+            LispObject::from(echo.as_str())
+        }
+        arg => {
+            let echo = format!("C-u{}", arg.echo_suffix().unwrap_or_default());
+            LispObject::from(echo.as_str())
+        }
     }
 }
 
 include!(concat!(env!("OUT_DIR"), "/interactive_exports.rs"));
+
+#[test]
+fn test_prefix_arg_transitions() {
+    assert_eq!(PrefixArg::None.more(), PrefixArg::None);
+    assert_eq!(PrefixArg::Raw(4).more(), PrefixArg::Raw(16));
+    assert_eq!(PrefixArg::Minus.more(), PrefixArg::Raw(-4));
+    assert_eq!(PrefixArg::Number(12).more(), PrefixArg::Number(12));
+
+    assert_eq!(PrefixArg::None.negated(), PrefixArg::Minus);
+    assert_eq!(PrefixArg::Raw(4).negated(), PrefixArg::Minus);
+    assert_eq!(PrefixArg::Minus.negated(), PrefixArg::None);
+    assert_eq!(PrefixArg::Number(3).negated(), PrefixArg::Number(-3));
+
+    assert_eq!(PrefixArg::Raw(4).with_digit(1), PrefixArg::Number(1));
+    assert_eq!(PrefixArg::Number(1).with_digit(2), PrefixArg::Number(12));
+    assert_eq!(PrefixArg::Minus.with_digit(0), PrefixArg::Minus);
+    assert_eq!(PrefixArg::Minus.with_digit(3), PrefixArg::Number(-3));
+    assert_eq!(PrefixArg::Number(-3).with_digit(4), PrefixArg::Number(-34));
+
+    assert_eq!(PrefixArg::None.numeric_value(), 1);
+    assert_eq!(PrefixArg::Minus.numeric_value(), -1);
+    assert_eq!(PrefixArg::Raw(16).numeric_value(), 16);
+}
+
+#[test]
+fn test_prefix_arg_echo() {
+    assert_eq!(PrefixArg::None.echo_suffix(), None);
+    assert_eq!(PrefixArg::Minus.echo_suffix().unwrap(), " -");
+    assert_eq!(PrefixArg::Number(-12).echo_suffix().unwrap(), " -12");
+    assert_eq!(PrefixArg::Raw(4).echo_suffix().unwrap(), "");
+    assert_eq!(PrefixArg::Raw(64).echo_suffix().unwrap(), " C-u C-u");
+    assert_eq!(PrefixArg::Raw(12).echo_suffix().unwrap(), " (12)");
+    assert_eq!(PrefixArg::Raw(-4).echo_suffix().unwrap(), " (-4)");
+}
@@ -1,26 +1,37 @@
 //! keyboard
 
+use std::mem;
+
 use remacs_macros::lisp_fn;
 
 use crate::{
     buffers::current_buffer,
-    eval::unbind_to,
+    data::{aref, indirect_function},
+    eval::{run_hook, unbind_to},
     frames::{selected_frame, window_frame_live_or_selected_with_action},
+    interactive::prefix_numeric_value,
     lisp::defsubr,
     lisp::LispObject,
     lists::{LispCons, LispConsCircularChecks, LispConsEndChecks},
+    multibyte::LispStringRef,
     numbers::IsLispNatnum,
     remacs_sys::{
-        command_loop_level, glyph_row_area, interrupt_input_blocked, minibuf_level,
-        recursive_edit_1, recursive_edit_unwind, update_mode_lines,
+        char_bits, command_loop_level, current_kboard, executing_kbd_macro,
+        executing_kbd_macro_iterations, globals, glyph_row_area, interrupt_input_blocked, kboard,
+        minibuf_level, recursive_edit_1, recursive_edit_unwind, update_mode_lines, EmacsInt,
     },
     remacs_sys::{
-        make_lispy_position, record_unwind_protect, temporarily_switch_to_single_kboard,
-        window_box_left_offset,
+        command_loop_1, make_event_array, make_lispy_position, maybe_quit, message1,
+        record_unwind_protect, temporarily_switch_to_single_kboard, window_box_left_offset,
+        xmalloc, xpalloc, xrealloc,
     },
     remacs_sys::{Fpos_visible_in_window_p, Fthrow},
-    remacs_sys::{Qexit, Qheader_line, Qhelp_echo, Qmode_line, Qnil, Qt, Qvertical_line},
+    remacs_sys::{
+        Qarrayp, Qexit, Qheader_line, Qhelp_echo, Qkbd_macro_termination_hook, Qmode_line, Qnil,
+        Qt, Qvertical_line,
+    },
     threads::c_specpdl_index,
+    vectors::LispVectorRef,
     windows::{selected_window, LispWindowOrSelected},
 };
 
@@ -191,8 +202,441 @@ pub fn recursive_edit() {
     }
 }
 
+/// A keyboard macro: the value of `last-kbd-macro' or of the macro
+/// being executed.
+#[derive(Clone, Copy)]
+pub enum KbdMacro {
+    /// A string of characters, where characters 128 to 255 stand for
+    /// meta characters.
+    Chars(LispStringRef),
+    /// A vector of arbitrary input events.
+    Events(LispVectorRef),
+}
+
+impl KbdMacro {
+    pub fn from_array(object: LispObject) -> Option<Self> {
+        match object.as_string() {
+            Some(string) => Some(KbdMacro::Chars(string)),
+            None => object.as_vector().map(KbdMacro::Events),
+        }
+    }
+
+    pub fn len(self) -> usize {
+        match self {
+            KbdMacro::Chars(string) => string.len_chars() as usize,
+            KbdMacro::Events(vector) => vector.len(),
+        }
+    }
+
+    /// The event at index IDX, with the meta bit of characters in a
+    /// string converted to the meta modifier.
+    pub fn event(self, idx: usize) -> LispObject {
+        match self {
+            KbdMacro::Chars(string) => {
+                let c = aref(string.into(), idx as EmacsInt).as_fixnum_or_error();
+                if c & 0x80 != 0 && c <= 0xff {
+                    LispObject::from(char_bits::CHAR_META as EmacsInt | (c & !0x80))
+                } else {
+                    LispObject::from(c)
+                }
+            }
+            KbdMacro::Events(vector) => vector.get(idx),
+        }
+    }
+}
+
+impl From<KbdMacro> for LispObject {
+    fn from(kbd_macro: KbdMacro) -> Self {
+        match kbd_macro {
+            KbdMacro::Chars(string) => string.into(),
+            KbdMacro::Events(vector) => vector.into(),
+        }
+    }
+}
+
+/// The events recorded for the keyboard macro being defined on a
+/// kboard.  They live in the kboard, where the garbage collector finds
+/// them.  Events up to `kbd_macro_end' belong to completed commands,
+/// and those between it and `kbd_macro_ptr' to the current one.
+struct MacroRecording(*mut kboard);
+
+impl MacroRecording {
+    /// The size to which the buffer is reset for a new macro.
+    const INITIAL_SIZE: isize = 30;
+
+    fn current() -> Self {
+        MacroRecording(unsafe { current_kboard })
+    }
+
+    fn is_defining(&self) -> bool {
+        unsafe { (*self.0).defining_kbd_macro_.is_not_nil() }
+    }
+
+    /// Discard all events, shrinking the buffer if an earlier macro made
+    /// it large.
+    fn clear(&mut self) {
+        let word_size = mem::size_of::<LispObject>();
+        unsafe {
+            let kb = &mut *self.0;
+            if kb.kbd_macro_buffer.is_null() {
+                kb.kbd_macro_buffer =
+                    xmalloc(Self::INITIAL_SIZE as usize * word_size) as *mut LispObject;
+                kb.kbd_macro_bufsize = Self::INITIAL_SIZE;
+            } else if kb.kbd_macro_bufsize > 200 {
+                kb.kbd_macro_buffer = xrealloc(
+                    kb.kbd_macro_buffer as *mut libc::c_void,
+                    Self::INITIAL_SIZE as usize * word_size,
+                ) as *mut LispObject;
+                kb.kbd_macro_bufsize = Self::INITIAL_SIZE;
+            }
+            kb.kbd_macro_ptr = kb.kbd_macro_buffer;
+            kb.kbd_macro_end = kb.kbd_macro_buffer;
+        }
+    }
+
+    /// Make room for at least ADDITIONAL more events.
+    fn reserve(&mut self, additional: isize) {
+        unsafe {
+            let kb = &mut *self.0;
+            let ptr_offset = kb.kbd_macro_ptr.offset_from(kb.kbd_macro_buffer);
+            let end_offset = kb.kbd_macro_end.offset_from(kb.kbd_macro_buffer);
+            let missing = ptr_offset + additional - kb.kbd_macro_bufsize;
+            if missing > 0 {
+                kb.kbd_macro_buffer = xpalloc(
+                    kb.kbd_macro_buffer as *mut libc::c_void,
+                    &mut kb.kbd_macro_bufsize,
+                    missing,
+                    -1,
+                    mem::size_of::<LispObject>() as isize,
+                ) as *mut LispObject;
+                kb.kbd_macro_ptr = kb.kbd_macro_buffer.offset(ptr_offset);
+                kb.kbd_macro_end = kb.kbd_macro_buffer.offset(end_offset);
+            }
+        }
+    }
+
+    fn push(&mut self, event: LispObject) {
+        self.reserve(1);
+        unsafe {
+            let kb = &mut *self.0;
+            *kb.kbd_macro_ptr = event;
+            kb.kbd_macro_ptr = kb.kbd_macro_ptr.offset(1);
+        }
+    }
+
+    /// Replace the recorded events with those of KBD_MACRO, as if they
+    /// belonged to completed commands.
+    fn set_events(&mut self, kbd_macro: KbdMacro) {
+        self.clear();
+        for idx in 0..kbd_macro.len() {
+            self.push(kbd_macro.event(idx));
+        }
+        self.finalize();
+    }
+
+    /// Make the events of the current command part of the macro.
+    fn finalize(&mut self) {
+        unsafe { (*self.0).kbd_macro_end = (*self.0).kbd_macro_ptr };
+    }
+
+    /// Drop the events of the current command.
+    fn cancel(&mut self) {
+        unsafe { (*self.0).kbd_macro_ptr = (*self.0).kbd_macro_end };
+    }
+
+    /// The events of completed commands, as a string if they are all
+    /// characters and a vector otherwise.
+    fn finished(&self) -> LispObject {
+        unsafe {
+            let kb = &*self.0;
+            make_event_array(
+                kb.kbd_macro_end.offset_from(kb.kbd_macro_buffer),
+                kb.kbd_macro_buffer,
+            )
+        }
+    }
+}
+
+/// Store character C into kbd macro being defined.
+#[no_mangle]
+pub extern "C" fn store_kbd_macro_char(c: LispObject) {
+    let mut recording = MacroRecording::current();
+    if recording.is_defining() {
+        recording.push(c);
+    }
+}
+
+/// Declare that all chars stored so far in the kbd macro being defined
+/// really belong to it.  This is done in between editor commands.
+#[no_mangle]
+pub extern "C" fn finalize_kbd_macro_chars() {
+    MacroRecording::current().finalize();
+}
+
+/// Finish defining the current keyboard macro.
+#[no_mangle]
+pub extern "C" fn end_kbd_macro() {
+    let recording = MacroRecording::current();
+    unsafe {
+        (*current_kboard).defining_kbd_macro_ = Qnil;
+        update_mode_lines = 20;
+        (*current_kboard).Vlast_kbd_macro_ = recording.finished();
+    }
+}
+
+/// Return the next event of the executing kbd macro and advance
+/// `executing-kbd-macro-index' past it, or nil at the end of the macro.
+/// Some things replace the macro with t to force an early exit.
+#[no_mangle]
+pub extern "C" fn next_kbd_macro_event() -> LispObject {
+    let kbd_macro = match KbdMacro::from_array(unsafe { globals.Vexecuting_kbd_macro }) {
+        Some(kbd_macro) => kbd_macro,
+        None => return Qnil,
+    };
+    let idx = unsafe { globals.executing_kbd_macro_index };
+    if idx as usize >= kbd_macro.len() {
+        return Qnil;
+    }
+    unsafe { globals.executing_kbd_macro_index += 1 };
+    kbd_macro.event(idx as usize)
+}
+
+/// Record subsequent keyboard input, defining a keyboard macro.
+/// The commands are recorded even as they are executed.
+/// Use \\[end-kbd-macro] to finish recording and make the macro available.
+/// Use \\[name-last-kbd-macro] to give it a permanent name.
+/// Non-nil arg (prefix arg) means append to last macro defined;
+/// this begins by re-executing that macro as if you typed it again.
+/// If optional second arg, NO-EXEC, is non-nil, do not re-execute last
+/// macro before appending to it.
+#[lisp_fn(min = "1", intspec = "P")]
+pub fn start_kbd_macro(append: LispObject, no_exec: LispObject) {
+    let mut recording = MacroRecording::current();
+    if recording.is_defining() {
+        error!("Already defining kbd macro");
+    }
+
+    unsafe { update_mode_lines = 19 };
+    if append.is_nil() {
+        recording.clear();
+        unsafe { message1(b"Defining kbd macro...\0".as_ptr() as *const libc::c_char) };
+    } else {
+        // Check the type of last-kbd-macro in case Lisp code changed it,
+        // and copy it into the buffer, in case the Lisp code has put
+        // another macro there.
+        let last = unsafe { (*current_kboard).Vlast_kbd_macro_ };
+        let kbd_macro = KbdMacro::from_array(last).unwrap_or_else(|| wrong_type!(Qarrayp, last));
+        recording.set_events(kbd_macro);
+
+        // Re-execute the macro we are appending to, for consistency of
+        // behavior.
+        if no_exec.is_nil() {
+            execute_kbd_macro(last, LispObject::from(1), Qnil);
+        }
+
+        unsafe { message1(b"Appending to kbd macro...\0".as_ptr() as *const libc::c_char) };
+    }
+    unsafe { (*current_kboard).defining_kbd_macro_ = Qt };
+}
+
+/// Finish defining a keyboard macro.
+/// The definition was started by \\[start-kbd-macro].
+/// The macro is now available for use via \\[call-last-kbd-macro],
+/// or it can be given a name with \\[name-last-kbd-macro] and then invoked
+/// under that name.
+///
+/// With numeric arg, repeat macro now that many times,
+/// counting the definition just completed as the first repetition.
+/// An argument of zero means repeat until error.
+///
+/// In Lisp, optional second arg LOOPFUNC may be a function that is called prior to
+/// each iteration of the macro.  Iteration stops if LOOPFUNC returns nil.
+#[lisp_fn(
+    name = "end-kbd-macro",
+    c_name = "end_kbd_macro",
+    min = "0",
+    intspec = "p"
+)]
+pub fn end_kbd_macro_lisp(repeat: Option<EmacsInt>, loopfunc: LispObject) {
+    if !MacroRecording::current().is_defining() {
+        error!("Not defining kbd macro");
+    }
+    let repeat = repeat.unwrap_or(1);
+
+    end_kbd_macro();
+    unsafe { message1(b"Keyboard macro defined\0".as_ptr() as *const libc::c_char) };
+
+    let last = unsafe { (*current_kboard).Vlast_kbd_macro_ };
+    if repeat == 0 {
+        execute_kbd_macro(last, LispObject::from(0), loopfunc);
+    } else if repeat > 1 {
+        execute_kbd_macro(last, LispObject::from(repeat - 1), loopfunc);
+    }
+}
+
+/// Cancel the events added to a keyboard macro for this command.
+#[lisp_fn]
+pub fn cancel_kbd_macro_events() {
+    MacroRecording::current().cancel();
+}
+
+/// Store EVENT into the keyboard macro being defined.
+#[lisp_fn]
+pub fn store_kbd_macro_event(event: LispObject) {
+    store_kbd_macro_char(event);
+}
+
+/// Call the last keyboard macro that you defined with \\[start-kbd-macro].
+///
+/// A prefix argument serves as a repeat count.  Zero means repeat until error.
+///
+/// To make a macro permanent so you can call it even after
+/// defining others, use \\[name-last-kbd-macro].
+///
+/// In Lisp, optional second arg LOOPFUNC may be a function that is called prior to
+/// each iteration of the macro.  Iteration stops if LOOPFUNC returns nil.
+#[lisp_fn(min = "0", intspec = "p")]
+pub fn call_last_kbd_macro(prefix: LispObject, loopfunc: LispObject) {
+    unsafe {
+        // Don't interfere with recognition of the previous command
+        // from before this macro started.
+        globals.Vthis_command = (*current_kboard).Vlast_command_;
+        // C-x z after the macro should repeat the macro.
+        globals.Vreal_this_command = (*current_kboard).Vlast_kbd_macro_;
+    }
+
+    let last = unsafe { (*current_kboard).Vlast_kbd_macro_ };
+    if MacroRecording::current().is_defining() {
+        error!("Can't execute anonymous macro while defining one");
+    } else if last.is_nil() {
+        error!("No kbd macro has been defined");
+    }
+    execute_kbd_macro(last, prefix, loopfunc);
+
+    // command_loop_1 sets this to nil before it returns; get back the
+    // last command within the macro so that it can be last, again,
+    // after we return.
+    unsafe { globals.Vthis_command = (*current_kboard).Vlast_command_ };
+}
+
+/// Restore Vexecuting_kbd_macro and executing_kbd_macro_index.
+/// Called when the unwind-protect in `execute_kbd_macro' gets invoked.
+extern "C" fn pop_kbd_macro(info: LispObject) {
+    let (executing, rest) = info.as_cons_or_error().into();
+    let (index, real_this_command) = rest.as_cons_or_error().into();
+    unsafe {
+        globals.Vexecuting_kbd_macro = executing;
+        globals.executing_kbd_macro_index = index.as_fixnum_or_error();
+        globals.Vreal_this_command = real_this_command;
+    }
+    run_hook(Qkbd_macro_termination_hook);
+}
+
+/// Execute MACRO as string of editor command characters.
+/// MACRO can also be a vector of keyboard events.  If MACRO is a symbol,
+/// its function definition is used.
+/// COUNT is a repeat count, or nil for once, or 0 for infinite loop.
+///
+/// Optional third arg LOOPFUNC may be a function that is called prior to
+/// each iteration of the macro.  Iteration stops if LOOPFUNC returns nil.
+///
+/// usage: (fn MACRO &optional COUNT LOOPFUNC)
+#[lisp_fn(min = "1")]
+pub fn execute_kbd_macro(kbd_macro: LispObject, count: LispObject, loopfunc: LispObject) {
+    let pdlcount = c_specpdl_index();
+    let mut repeat = if count.is_nil() {
+        1
+    } else {
+        prefix_numeric_value(count)
+    };
+    let mut success_count: EmacsInt = 0;
+
+    unsafe { executing_kbd_macro_iterations = 0 };
+
+    let final_ = indirect_function(kbd_macro);
+    if KbdMacro::from_array(final_).is_none() {
+        error!("Keyboard macros must be strings or vectors");
+    }
+
+    unsafe {
+        let info = LispObject::cons(
+            globals.Vexecuting_kbd_macro,
+            LispObject::cons(
+                globals.executing_kbd_macro_index,
+                globals.Vreal_this_command,
+            ),
+        );
+        record_unwind_protect(Some(pop_kbd_macro), info);
+    }
+
+    loop {
+        unsafe {
+            globals.Vexecuting_kbd_macro = final_;
+            executing_kbd_macro = final_;
+            globals.executing_kbd_macro_index = 0;
+            (*current_kboard).Vprefix_arg_ = Qnil;
+        }
+
+        if loopfunc.is_not_nil() && call!(loopfunc).is_nil() {
+            break;
+        }
+
+        unsafe { command_loop_1() };
+
+        success_count += 1;
+        unsafe {
+            executing_kbd_macro_iterations = success_count;
+            maybe_quit();
+        }
+
+        repeat -= 1;
+        if repeat == 0 || KbdMacro::from_array(unsafe { globals.Vexecuting_kbd_macro }).is_none() {
+            break;
+        }
+    }
+
+    unsafe {
+        executing_kbd_macro = Qnil;
+        globals.Vreal_this_command = globals.Vexecuting_kbd_macro;
+    }
+
+    unbind_to(pdlcount, Qnil);
+}
+
+/// Return the number of iterations of the innermost keyboard macro
+/// that completed so far.
+/// This counts the iterations of the macro being executed, or, when
+/// no macro is executing, those of the last macro that was, including
+/// one that was interrupted by an error.
+#[lisp_fn]
+pub fn kbd_macro_iterations() -> EmacsInt {
+    unsafe { executing_kbd_macro_iterations }
+}
+
 #[no_mangle]
 pub extern "C" fn rust_syms_of_keyboard() {
+    def_lisp_sym!(Qkbd_macro_termination_hook, "kbd-macro-termination-hook");
+
+    /// Normal hook run whenever a keyboard macro terminates.
+    /// This is run whether the macro ends normally or prematurely due to an error.
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    defvar_lisp!(Vkbd_macro_termination_hook, "kbd-macro-termination-hook", Qnil);
+
+    /// Non-nil while a keyboard macro is being defined.  Don't set this!
+    /// The value is the symbol `append' while appending to the definition of
+    /// an existing macro.
+    defvar_kboard!(defining_kbd_macro_, "defining-kbd-macro");
+
+    /// Currently executing keyboard macro (string or vector).
+    /// This is nil when not executing a keyboard macro.
+    defvar_lisp!(Vexecuting_kbd_macro, "executing-kbd-macro", Qnil);
+
+    /// Index in currently executing keyboard macro; undefined if none executing.
+    defvar_int!(executing_kbd_macro_index, "executing-kbd-macro-index", 0);
+
+    /// Last kbd macro defined, as a string or vector; nil if none defined.
+    defvar_kboard!(Vlast_kbd_macro_, "last-kbd-macro");
+
     /// The last command executed.
     /// Normally a symbol with a function definition, but can be whatever was found
     /// in the keymap, or whatever the variable `this-command' was set to by that
@@ -13,7 +13,7 @@ use crate::{
     eval::{autoload_do_load, unbind_to},
     keyboard::lucid_event_type_list_p,
     lisp::{defsubr, LispObject},
-    lists::{nth, setcdr},
+    lists::{car_safe, list, nth, setcdr},
     lists::{LispCons, LispConsCircularChecks, LispConsEndChecks},
     obarray::intern,
     remacs_sys::{
@@ -23,12 +23,18 @@ use crate::{
     },
     remacs_sys::{char_bits, current_global_map as _current_global_map, globals, EmacsInt},
     remacs_sys::{
-        Fcopy_sequence, Fevent_convert_list, Findent_to, Fmake_char_table, Fpurecopy,
+        current_kboard, current_minor_maps, get_local_map_property, record_unwind_current_buffer,
+        resolve_local_map, set_buffer_internal,
+    },
+    remacs_sys::{
+        Fevent_convert_list, Fget_text_property, Findent_to, Fmake_char_table, Fpurecopy,
         Fset_char_table_range, Fterpri,
     },
     remacs_sys::{
-        Qautoload, Qkeymap, Qkeymapp, Qnil, Qstandard_output, Qt, Qvector_or_char_table_p,
+        Qautoload, Qkeymap, Qkeymapp, Qlocal_map, Qnil, Qremap, Qstandard_output, Qt,
+        Qvector_or_char_table_p,
     },
+    sequences::{copy_sequence, vector_of},
     symbols::LispSymbolRef,
     threads::{c_specpdl_index, ThreadState},
 };
@@ -43,15 +49,13 @@ declare_GC_protected_static!(where_is_cache, Qnil);
 /// Allows the C code to get the value of `where_is_cache`
 #[no_mangle]
 pub extern "C" fn get_where_is_cache() -> LispObject {
-    unsafe { where_is_cache }
+    where_is_cache.get()
 }
 
 /// Allows the C code to set the value of `where_is_cache`
 #[no_mangle]
 pub extern "C" fn set_where_is_cache(val: LispObject) {
-    unsafe {
-        where_is_cache = val;
-    }
+    where_is_cache.set(val);
 }
 
 /// Which keymaps are reverse-stored in the cache.
@@ -60,17 +64,20 @@ declare_GC_protected_static!(where_is_cache_keymaps, Qt);
 /// Allows the C code to get the value of `where_is_cache_keymaps`
 #[no_mangle]
 pub extern "C" fn get_where_is_cache_keymaps() -> LispObject {
-    unsafe { where_is_cache_keymaps }
+    where_is_cache_keymaps.get()
 }
 
 /// Allows the C code to set the value of `where_is_cache_keymaps`
 #[no_mangle]
 pub extern "C" fn set_where_is_cache_keymaps(val: LispObject) {
-    unsafe {
-        where_is_cache_keymaps = val;
-    }
+    where_is_cache_keymaps.set(val);
 }
 
+/// The `local-map' and `keymap' properties last looked up by
+/// `current-active-maps', after what they depend on.  See
+/// `char_property_maps'.
+declare_GC_protected_static!(char_property_maps_cache, Qnil);
+
 /// Check that OBJECT is a keymap (after dereferencing through any
 /// symbols).  If it is, return it.
 ///
@@ -215,10 +222,8 @@ pub extern "C" fn keymap_memberp(map: LispObject, maps: LispObject) -> bool {
 #[lisp_fn]
 pub fn set_keymap_parent(keymap: LispObject, parent: LispObject) -> LispObject {
     // Flush any reverse-map cache
-    unsafe {
-        where_is_cache = Qnil;
-        where_is_cache_keymaps = Qt;
-    }
+    where_is_cache.set(Qnil);
+    where_is_cache_keymaps.set(Qt);
 
     let mut parent = parent;
     let keymap = get_keymap(keymap, true, true);
@@ -398,6 +403,187 @@ pub fn map_keymap_internal_lisp(function: LispObject, mut keymap: LispObject) ->
     unsafe { map_keymap_internal(keymap, Some(map_keymap_call), function, ptr::null_mut()) }
 }
 
+/// Return the offset of POSITION, a click position, in the style of
+/// the respective argument of `key-binding'.
+fn click_position(position: LispObject) -> isize {
+    let buffer = ThreadState::current_buffer_unchecked();
+    let pos = match (position.as_fixnum(), position.as_marker()) {
+        (Some(pos), _) => pos as isize,
+        (None, Some(marker)) => marker.charpos_or_error(),
+        (None, None) => buffer.pt,
+    };
+    if !(buffer.begv <= pos && pos <= buffer.zv) {
+        args_out_of_range!(current_buffer(), position);
+    }
+    pos
+}
+
+/// Return the keymaps of the `local-map' and `keymap' properties at POS
+/// in the current buffer, as `get_local_map' finds them.
+///
+/// This is done for every key sequence read, and looking at the text
+/// properties and overlays is the expensive part of computing the
+/// active keymaps.  So the properties are only looked up again when the
+/// buffer, POS, the buffer's text, overlays, narrowing or local map, or
+/// the variables that say how properties are found changed since the
+/// last time.  Only the values of the properties are cached: a symbol
+/// can be given a new keymap as its function definition without any of
+/// these changing, so they are made keymaps every time.
+fn char_property_maps(pos: isize) -> (LispObject, LispObject) {
+    let mut buffer = ThreadState::current_buffer_unchecked();
+    let key = unsafe {
+        [
+            LispObject::from(buffer),
+            LispObject::from(pos as EmacsInt),
+            LispObject::from(buffer.modifications()),
+            LispObject::from(buffer.overlay_modifications()),
+            LispObject::from(buffer.begv as EmacsInt),
+            LispObject::from(buffer.zv as EmacsInt),
+            buffer.keymap_,
+            globals.Vchar_property_alias_alist,
+            globals.Vdefault_text_properties,
+            globals.Vtext_property_default_nonsticky,
+        ]
+    };
+
+    let cached = char_property_maps_cache
+        .get()
+        .as_vector()
+        .filter(|cache| key.iter().enumerate().all(|(i, &k)| cache.get(i).eq(k)))
+        .map(|cache| (cache.get(key.len()), cache.get(key.len() + 1)));
+    let (local_map, keymap) = cached.unwrap_or_else(|| {
+        let local_map = unsafe { get_local_map_property(pos, buffer.as_mut(), Qlocal_map) };
+        let keymap = unsafe { get_local_map_property(pos, buffer.as_mut(), Qkeymap) };
+        let mut cache = key.to_vec();
+        cache.extend_from_slice(&[local_map, keymap]);
+        char_property_maps_cache.set(vector_of(&cache));
+        (local_map, keymap)
+    });
+
+    unsafe {
+        (
+            resolve_local_map(local_map, buffer.as_mut(), Qlocal_map),
+            resolve_local_map(keymap, buffer.as_mut(), Qkeymap),
+        )
+    }
+}
+
+/// Return the `local-map' and `keymap' properties of the string clicked
+/// on, given as the (STRING . CHARPOS) of a mouse position.  This is how
+/// clicks on a mode line or header line segment, an overlay string or a
+/// string displayed via the `display' property find their bindings.
+fn string_property_maps(string_info: LispObject) -> (LispObject, LispObject) {
+    let (string, posn) = match string_info.into() {
+        Some(pair) => pair,
+        None => return (Qnil, Qnil),
+    };
+    match (string.as_string(), posn.as_fixnum()) {
+        (Some(s), Some(posn)) if 0 <= posn && posn < s.len_chars() as EmacsInt => unsafe {
+            (
+                Fget_text_property(posn.into(), Qlocal_map, string),
+                Fget_text_property(posn.into(), Qkeymap, string),
+            )
+        },
+        _ => (Qnil, Qnil),
+    }
+}
+
+/// Return the keymaps of the currently active minor modes, including
+/// those of `emulation-mode-map-alists' and
+/// `minor-mode-overriding-map-alist', in order of precedence.
+pub fn minor_mode_maps() -> Vec<LispObject> {
+    let mut maps: *mut LispObject = ptr::null_mut();
+    let n = unsafe { current_minor_maps(ptr::null_mut(), &mut maps) };
+    if n == 0 {
+        return Vec::new();
+    }
+    unsafe { std::slice::from_raw_parts(maps, n as usize) }
+        .iter()
+        .cloned()
+        .filter(|map| map.is_not_nil())
+        .collect()
+}
+
+/// Return a list of the currently active keymaps.
+/// OLP if non-nil indicates that we should obey `overriding-local-map' and
+/// `overriding-terminal-local-map'.  POSITION can specify a click position
+/// like in the respective argument of `key-binding'.
+#[lisp_fn(min = "0")]
+pub fn current_active_maps(olp: LispObject, position: LispObject) -> LispObject {
+    let count = c_specpdl_index();
+
+    // If a mouse click position is given, our variables are based on
+    // the buffer clicked on, not the current buffer.  So we may have to
+    // switch the buffer here.  As in `read-key-sequence', point is not
+    // saved.
+    if position.is_cons() {
+        if let Some(window) = car_safe(position).as_window() {
+            if let Some(mut buffer) = window.contents.as_buffer() {
+                if buffer != ThreadState::current_buffer_unchecked() {
+                    unsafe {
+                        record_unwind_current_buffer();
+                        set_buffer_internal(buffer.as_mut());
+                    }
+                }
+            }
+        }
+    }
+
+    let otlp = unsafe { (*current_kboard).Voverriding_terminal_local_map_ };
+    let overriding_local_map = unsafe { globals.Voverriding_local_map };
+
+    // The doc said that overriding-terminal-local-map should override
+    // overriding-local-map.  The code used them both, but it seems
+    // clearer to use just one.  rms, jan 2005.
+    let keymaps = if olp.is_not_nil() && otlp.is_nil() && overriding_local_map.is_not_nil() {
+        vec![overriding_local_map, current_global_map()]
+    } else {
+        let mut pos = click_position(position);
+        let mut string_maps = (Qnil, Qnil);
+
+        if position.is_cons() {
+            let string = nth(4, position);
+            if string.is_nil() {
+                // For a mouse click, get the local text-property keymap
+                // of the place clicked on, rather than point.
+                let buffer = ThreadState::current_buffer_unchecked();
+                if let Some(posn) = nth(5, position).as_fixnum() {
+                    let posn = posn as isize;
+                    if buffer.beg() <= posn && posn <= buffer.z() {
+                        pos = posn;
+                    }
+                }
+            } else {
+                string_maps = string_property_maps(string);
+            }
+        }
+
+        let (mut local_map, mut keymap) = char_property_maps(pos);
+        if string_maps.0.is_not_nil() {
+            local_map = string_maps.0;
+        }
+        if string_maps.1.is_not_nil() {
+            keymap = string_maps.1;
+        }
+
+        let mut keymaps = Vec::new();
+        if olp.is_not_nil() && otlp.is_not_nil() {
+            keymaps.push(otlp);
+        }
+        if keymap.is_not_nil() {
+            keymaps.push(keymap);
+        }
+        keymaps.extend(minor_mode_maps());
+        if local_map.is_not_nil() {
+            keymaps.push(local_map);
+        }
+        keymaps.push(current_global_map());
+        keymaps
+    };
+
+    unbind_to(count, list(&keymaps))
+}
+
 /// Return the binding for command KEYS in current local keymap only.
 /// KEYS is a string or vector, a sequence of keystrokes.
 /// The binding is probably a symbol with a function definition.
@@ -530,6 +716,65 @@ pub fn lookup_key(keymap: LispObject, key: LispObject, accept_default: LispObjec
     }
 }
 
+/// Return the remapping of COMMAND in KEYMAP, the binding of the event
+/// sequence [remap COMMAND] in it, or nil if there is none.
+fn keymap_remapping(keymap: LispObject, command: LispObject) -> LispObject {
+    let keymap = get_keymap(keymap, false, true);
+    if keymap.is_nil() {
+        return Qnil;
+    }
+    let remaps = get_keymap(
+        unsafe { access_keymap(keymap, Qremap, false, false, true) },
+        false,
+        true,
+    );
+    if remaps.is_nil() {
+        return Qnil;
+    }
+    unsafe { access_keymap(remaps, command, false, false, true) }
+}
+
+/// Return the remapping for command COMMAND.
+/// Returns nil if COMMAND is not remapped (or not a symbol).
+///
+/// If the optional argument POSITION is non-nil, it specifies a mouse
+/// position as returned by `event-start' and `event-end', and the
+/// remapping occurs in the keymaps associated with it.  It can also be a
+/// number or marker, in which case the keymap properties at the specified
+/// buffer position instead of point are used.  The KEYMAPS argument is
+/// ignored if POSITION is non-nil.
+///
+/// If the optional argument KEYMAPS is non-nil, it should be a list of
+/// keymaps to search for command remapping.  Otherwise, search for the
+/// remapping in all currently active keymaps.
+#[lisp_fn(min = "1")]
+pub fn command_remapping(
+    command: LispObject,
+    position: LispObject,
+    keymaps: LispObject,
+) -> LispObject {
+    if !command.is_symbol() {
+        return Qnil;
+    }
+
+    // This is consulted for every command the command loop runs, so
+    // look into the maps one by one instead of making a key sequence
+    // and a composed keymap of them.
+    let keymaps = if keymaps.is_nil() || position.is_not_nil() {
+        current_active_maps(Qt, position)
+    } else {
+        keymaps
+    };
+    keymaps
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe)
+        .map(|keymap| keymap_remapping(keymap, command))
+        .find(|binding| binding.is_not_nil())
+        .map_or(
+            Qnil,
+            |binding| if binding.is_fixnum() { Qnil } else { binding },
+        )
+}
+
 /// Define COMMAND as a prefix command.  COMMAND should be a symbol.
 /// A new sparse keymap is stored as COMMAND's function definition and its
 /// value.
@@ -651,10 +896,10 @@ pub fn copy_keymap(keymap: LispObject) -> LispObject {
         }
 
         if elt.is_char_table() {
-            elt = unsafe { Fcopy_sequence(elt) };
+            elt = copy_sequence(elt);
             unsafe { map_char_table(Some(copy_keymap_1), Qnil, elt, elt) };
         } else if let Some(v) = elt.as_vector() {
-            elt = unsafe { Fcopy_sequence(elt) };
+            elt = copy_sequence(elt);
             let mut v2 = elt.as_vector().unwrap();
             for (i, obj) in v.iter().enumerate() {
                 v2.set(i, unsafe { copy_keymap_item(obj) });
@@ -27,10 +27,12 @@ extern crate if_chain;
 #[macro_use]
 extern crate lazy_static;
 
+extern crate backtrace;
 extern crate base64 as base64_crate;
 extern crate libc;
 extern crate md5;
 extern crate rand;
+extern crate regex;
 extern crate sha1;
 extern crate sha2;
 
@@ -61,9 +63,15 @@ mod frames;
 mod vector_macros;
 mod str2sig;
 
+mod abbrev;
 mod alloc;
 mod base64;
+mod batch;
+mod binding_table;
+mod block_alloc;
+mod buffer_menu;
 mod buffers;
+mod buttons;
 mod bytecode;
 mod callint;
 mod callproc;
@@ -73,11 +81,16 @@ mod category;
 mod character;
 mod charset;
 mod chartable;
+mod cli;
 mod cmds;
 mod coding;
+mod crash;
 mod crypto;
+mod cursor_sensor;
 mod data;
 mod decompress;
+mod diff;
+mod desktop;
 mod dired;
 #[cfg(unix)]
 mod dired_unix;
@@ -85,15 +98,23 @@ mod dired_unix;
 mod dired_windows;
 mod dispnew;
 mod editfns;
+mod eldoc;
 mod emacs;
+mod ert;
 mod eval;
 mod ffi;
 mod fileio;
+mod finalizers;
 mod floatfns;
 mod fns;
 mod fonts;
+mod frame_parameters;
+mod gc;
+mod gc_stats;
 mod hashtable;
+mod help;
 mod indent;
+mod insdel;
 mod interactive;
 mod keyboard;
 mod keymap;
@@ -103,15 +124,25 @@ mod lread;
 mod marker;
 mod math;
 mod minibuf;
+mod monitors;
+mod mouse_autoselect;
 mod multibyte;
 mod numbers;
 mod obarray;
 mod objects;
+mod parse_sexp;
+mod pixel_fill;
 mod process;
 mod profiler;
+mod reader;
 #[allow(clippy::all)]
 mod remacs_sys;
 mod search;
+mod sequences;
+mod server;
+mod shr_layout;
+mod snapshot;
+mod string_alloc;
 mod strings;
 mod symbols;
 mod syntax;
@@ -119,10 +150,20 @@ mod terminal;
 mod textprop;
 mod threads;
 mod time;
+mod tool_bar;
+mod transient_map;
+mod tty_clipboard;
+mod tty_graphics;
+mod tty_input;
+mod undo;
+mod undo_persist;
+mod uniquify;
 mod util;
 mod vectors;
 mod window_configuration;
+mod window_ring;
 mod windows;
+mod word_wrap;
 mod xfaces;
 mod xml;
 
@@ -137,7 +178,7 @@ static ALLOCATOR: OsxUnexecAlloc = OsxUnexecAlloc;
 include!(concat!(env!("OUT_DIR"), "/c_exports.rs"));
 
 #[cfg(test)]
-pub use crate::functions::{lispsym, make_string, make_unibyte_string, Fcons, Fsignal};
+pub use crate::functions::{lispsym, make_string, make_unibyte_string, Fsignal};
 
 #[cfg(feature = "compile-errors")]
 mod compile_errors {
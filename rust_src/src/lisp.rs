@@ -2,7 +2,7 @@
 //! lisp.h.
 
 use std::convert::From;
-use std::ffi::CString;
+use std::ffi::CStr;
 use std::fmt::{Debug, Error, Formatter};
 use std::mem;
 use std::ops::{Deref, DerefMut};
@@ -10,16 +10,18 @@ use std::ops::{Deref, DerefMut};
 use libc::{c_char, c_void, intptr_t, uintptr_t};
 
 use crate::{
+    block_alloc::make_float,
     buffers::LispBufferRef,
     eval::FUNCTIONP,
+    fns::{equal_no_quit, internal_equal},
     lists::{list, CarIter, LispConsCircularChecks, LispConsEndChecks},
     process::LispProcessRef,
-    remacs_sys::{build_string, internal_equal, make_float},
     remacs_sys::{
         equal_kind, pvec_type, EmacsDouble, EmacsInt, EmacsUint, Lisp_Bits, USE_LSB_TAG, VALMASK,
     },
     remacs_sys::{Lisp_Misc_Any, Lisp_Misc_Type, Lisp_Subr, Lisp_Type},
     remacs_sys::{Qautoload, Qnil, Qsubrp, Qt, Vbuffer_alist, Vprocess_alist},
+    string_alloc,
 };
 
 // TODO: tweak Makefile to rebuild C files if this changes.
@@ -71,7 +73,7 @@ impl LispObject {
     }
 
     pub fn from_float(v: EmacsDouble) -> Self {
-        unsafe { make_float(v) }
+        make_float(v)
     }
 }
 
@@ -234,6 +236,17 @@ impl LispSubrRef {
     pub fn symbol_name(self) -> *const c_char {
         unsafe { (*self.0).symbol_name }
     }
+
+    /// The argument names recorded for a Rust subr, separated by
+    /// spaces, or None for subrs that don't record them.
+    pub fn arglist(self) -> Option<&'static str> {
+        let arglist = unsafe { (*self.0).arglist };
+        if arglist.is_null() {
+            None
+        } else {
+            unsafe { CStr::from_ptr(arglist) }.to_str().ok()
+        }
+    }
 }
 
 impl LispObject {
@@ -257,6 +270,12 @@ impl From<LispObject> for LispSubrRef {
     }
 }
 
+impl From<LispSubrRef> for LispObject {
+    fn from(s: LispSubrRef) -> Self {
+        LispObject::tag_ptr(s, Lisp_Type::Lisp_Vectorlike)
+    }
+}
+
 impl From<LispObject> for Option<LispSubrRef> {
     fn from(o: LispObject) -> Self {
         o.as_vectorlike().and_then(|v| v.as_subr())
@@ -331,8 +350,7 @@ impl From<!> for LispObject {
 /// Copies a Rust str into a new Lisp string
 impl<'a> From<&'a str> for LispObject {
     fn from(s: &str) -> Self {
-        let cs = CString::new(s).unwrap();
-        unsafe { build_string(cs.as_ptr()) }
+        string_alloc::make_string(s)
     }
 }
 
@@ -383,6 +401,17 @@ impl From<LispObject> for Option<EmacsInt> {
     }
 }
 
+// Same as CHECK_TYPE_RANGED_INTEGER (int, o)
+impl From<LispObject> for i32 {
+    fn from(o: LispObject) -> Self {
+        let n = o.as_fixnum_or_error();
+        if n < EmacsInt::from(i32::min_value()) || n > EmacsInt::from(i32::max_value()) {
+            args_out_of_range!(o, i32::min_value(), i32::max_value());
+        }
+        n as i32
+    }
+}
+
 impl From<LispObject> for EmacsUint {
     fn from(o: LispObject) -> Self {
         o.as_natnum_or_error()
@@ -569,14 +598,14 @@ impl LispObject {
     where
         LispObject: From<T>,
     {
-        unsafe { internal_equal(self, other.into(), equal_kind::EQUAL_PLAIN, 0, Qnil) }
+        internal_equal(self, other.into(), equal_kind::EQUAL_PLAIN, 0, Qnil)
     }
 
     pub fn equal_no_quit<T>(self, other: T) -> bool
     where
         LispObject: From<T>,
     {
-        unsafe { internal_equal(self, other.into(), equal_kind::EQUAL_NO_QUIT, 0, Qnil) }
+        equal_no_quit(self, other.into())
     }
 
     pub fn is_function(self) -> bool {
@@ -651,19 +680,6 @@ macro_rules! export_lisp_fns {
     }
 }
 
-#[allow(unused_macros)]
-macro_rules! protect_statics_from_GC {
-    ($($f:ident),+) => {
-        pub fn rust_static_syms() {
-            unsafe {
-                $(
-                    crate::remacs_sys::staticpro(&$f as *const LispObject as *mut LispObject);
-                )+
-            }
-        }
-    }
-}
-
 #[test]
 fn test_lisp_misc_any_size() {
     // Should be 32 bits, which is 4 bytes.
@@ -8,12 +8,15 @@ use libc::c_void;
 use remacs_macros::lisp_fn;
 
 use crate::{
+    block_alloc,
+    fns::internal_equal,
+    hashtable::{HashLookupResult, LispHashTableRef},
     lisp::defsubr,
     lisp::LispObject,
     numbers::MOST_POSITIVE_FIXNUM,
+    remacs_sys::CHECK_IMPURE,
     remacs_sys::{equal_kind, globals, EmacsInt, EmacsUint, Lisp_Cons, Lisp_Type},
-    remacs_sys::{internal_equal, Fcons, CHECK_IMPURE},
-    remacs_sys::{Qcircular_list, Qconsp, Qlistp, Qnil, Qplistp},
+    remacs_sys::{Qcircular_list, Qconsp, Qlistp, Qnil, Qplistp, Qt},
     symbols::LispSymbolRef,
 };
 
@@ -71,7 +74,7 @@ impl Debug for LispCons {
 
 impl LispObject {
     pub fn cons<A: Into<LispObject>, D: Into<LispObject>>(car: A, cdr: D) -> Self {
-        unsafe { Fcons(car.into(), cdr.into()) }
+        block_alloc::cons(car.into(), cdr.into())
     }
 
     pub fn is_list(self) -> bool {
@@ -124,6 +127,12 @@ impl LispObject {
     ) -> CarIter {
         CarIter::new(TailsIter::new(self, Qlistp, end_checks, circular_checks))
     }
+
+    /// Iterate over all tails of self, yielding an error instead of
+    /// signaling one or looping forever if self is not a proper list.
+    pub fn iter_tails_checked(self) -> CheckedTailsIter {
+        CheckedTailsIter::new(self)
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -258,6 +267,83 @@ impl Iterator for CarIter {
     }
 }
 
+/// Why the walk along a list stopped before reaching nil.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ListError {
+    /// The list is circular.  TAIL is the tail reached when this was
+    /// noticed, and PERIOD the number of conses in the cycle.
+    Circular { tail: LispObject, period: usize },
+    /// The list ends in the value, which is not nil.
+    Dotted(LispObject),
+}
+
+/// Iterate over the tails of a list, yielding an error at its end if it
+/// is circular or dotted.  This never loops forever, and never signals.
+///
+/// Circularity is detected with Brent's algorithm, which also tells the
+/// length of the cycle.
+pub struct CheckedTailsIter {
+    tail: LispObject,
+    tortoise: LispObject,
+    power: usize,
+    steps: usize,
+    done: bool,
+}
+
+impl CheckedTailsIter {
+    pub fn new(list: LispObject) -> Self {
+        Self {
+            tail: list,
+            tortoise: list,
+            power: 1,
+            steps: 0,
+            done: false,
+        }
+    }
+
+    /// Return the tail after the last one yielded.
+    pub fn rest(&self) -> LispObject {
+        self.tail
+    }
+}
+
+impl Iterator for CheckedTailsIter {
+    type Item = Result<LispCons, ListError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let cons = match self.tail.as_cons() {
+            Some(cons) => cons,
+            None => {
+                self.done = true;
+                return if self.tail.is_nil() {
+                    None
+                } else {
+                    Some(Err(ListError::Dotted(self.tail)))
+                };
+            }
+        };
+        if self.steps > 0 && self.tail.eq(self.tortoise) {
+            self.done = true;
+            return Some(Err(ListError::Circular {
+                tail: self.tail,
+                period: self.steps,
+            }));
+        }
+
+        if self.steps == self.power {
+            self.tortoise = self.tail;
+            self.power = self.power.saturating_mul(2);
+            self.steps = 0;
+        }
+        self.tail = cons.cdr();
+        self.steps += 1;
+        Some(Ok(cons))
+    }
+}
+
 impl From<LispObject> for LispCons {
     fn from(o: LispObject) -> Self {
         o.as_cons().unwrap_or_else(|| wrong_type!(Qconsp, o))
@@ -364,7 +450,7 @@ impl LispCons {
                 (Some(cons1), Some(cons2)) => {
                     let (item1, tail1) = cons1.into();
                     let (item2, tail2) = cons2.into();
-                    if !unsafe { internal_equal(item1, item2, kind, item_depth, item_ht) } {
+                    if !internal_equal(item1, item2, kind, item_depth, item_ht) {
                         return false;
                     } else if tail1.eq(tail2) {
                         return true;
@@ -375,7 +461,7 @@ impl LispCons {
             }
         }
 
-        unsafe { internal_equal(it1.rest(), it2.rest(), kind, depth + 1, ht) }
+        internal_equal(it1.rest(), it2.rest(), kind, depth + 1, ht)
     }
 
     pub fn length(self) -> usize {
@@ -404,6 +490,12 @@ impl LispCons {
     ) -> CarIter {
         CarIter::new(TailsIter::new(self.0, Qlistp, end_checks, circular_checks))
     }
+
+    /// Iterate over all tails of the list, yielding an error at its end
+    /// if it is circular or dotted.
+    pub fn iter_checked(self) -> CheckedTailsIter {
+        CheckedTailsIter::new(self.0)
+    }
 }
 
 /// Return t if OBJECT is not a cons cell.  This includes nil.
@@ -502,16 +594,29 @@ pub fn nthcdr(n: EmacsInt, list: LispObject) -> LispObject {
         return list;
     }
 
-    // The iterator's `nth` method looks like it would fit here. However, it has
-    // different semantics from Lisp. Lisp will happily return the `nth` item even if
-    // it is not a valid cons cell. The tails iterator does not handle this.
-    // By counting down manually, the code can exit before the iterator would
-    // allowing `rest()` to still have valid data.
-    let mut it = list.iter_tails(LispConsEndChecks::on, LispConsCircularChecks::safe);
-    for _ in 0..n {
-        it.next();
+    let mut n = n as usize;
+    let mut it = list.iter_tails_checked();
+    loop {
+        match it.next() {
+            Some(Ok(cons)) => {
+                n -= 1;
+                if n == 0 {
+                    return cons.cdr();
+                }
+            }
+            None => return Qnil,
+            Some(Err(ListError::Dotted(_))) => wrong_type!(Qlistp, list),
+            // Going around the cycle changes nothing, so skip the
+            // full turns instead of making them.
+            Some(Err(ListError::Circular { tail, period })) => {
+                let mut tail = tail;
+                for _ in 0..n % period {
+                    tail = cdr(tail);
+                }
+                return tail;
+            }
+        }
     }
-    it.rest()
 }
 
 /// Return the Nth element of LIST.
@@ -521,6 +626,22 @@ pub fn nth(n: EmacsInt, list: LispObject) -> LispObject {
     car(nthcdr(n, list))
 }
 
+/// Return the last link of LIST.  Its car is the last element.
+/// If LIST is nil, return nil.
+/// If N is non-nil, return the Nth-to-last link of LIST.
+/// If N is bigger than the length of LIST, return LIST.
+#[lisp_fn(min = "1")]
+pub fn last(list: LispObject, n: Option<EmacsInt>) -> LispObject {
+    let length = safe_length(list) as EmacsInt;
+    match n {
+        None if list.is_nil() => Qnil,
+        None => nthcdr(length - 1, list),
+        Some(n) if n < 0 => Qnil,
+        Some(n) if n < length => nthcdr(length - n, list),
+        Some(_) => list,
+    }
+}
+
 fn lookup_member<CmpFunc>(elt: LispObject, list: LispObject, cmp: CmpFunc) -> LispObject
 where
     CmpFunc: Fn(LispObject, LispObject) -> bool,
@@ -554,13 +675,25 @@ pub fn member(elt: LispObject, list: LispObject) -> LispObject {
     lookup_member(elt, list, LispObject::equal)
 }
 
-fn assoc_impl<CmpFunc>(key: LispObject, list: LispObject, cmp: CmpFunc) -> LispObject
+/// Return the first element of the alist LIST that is a cons for which
+/// PRED is true.  Elements of LIST that are not conses are ignored.
+fn find_association<P>(list: LispObject, pred: P) -> LispObject
 where
-    CmpFunc: Fn(LispObject, LispObject) -> bool,
+    P: Fn(LispCons) -> bool,
 {
     list.iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on)
-        .find(|item| item.as_cons().map_or(false, |cons| cmp(key, cons.car())))
-        .unwrap_or(Qnil)
+        .filter_map(LispObject::as_cons)
+        .find(|&cons| pred(cons))
+        .map_or(Qnil, LispObject::from)
+}
+
+/// Delete the elements of the alist LIST that are conses for which PRED
+/// is true, by modifying its list structure, and return the result.
+fn delete_associations<P>(list: LispObject, pred: P) -> LispObject
+where
+    P: Fn(LispCons) -> bool,
+{
+    delete_if(list, |elt| elt.as_cons().map_or(false, |cons| pred(cons)))
 }
 
 /// Return non-nil if KEY is `eq' to the car of an element of LIST.
@@ -568,7 +701,7 @@ where
 /// Elements of LIST that are not conses are ignored.
 #[lisp_fn]
 pub fn assq(key: LispObject, list: LispObject) -> LispObject {
-    assoc_impl(key, list, LispObject::eq)
+    find_association(list, |cons| key.eq(cons.car()))
 }
 
 /// Return non-nil if KEY is equal to the car of an element of LIST.
@@ -578,26 +711,17 @@ pub fn assq(key: LispObject, list: LispObject) -> LispObject {
 #[lisp_fn(min = "2")]
 pub fn assoc(key: LispObject, list: LispObject, testfn: LispObject) -> LispObject {
     if testfn.is_nil() {
-        assoc_impl(key, list, |k, item| k.eq(item) || k.equal(item))
+        find_association(list, |cons| key.eq(cons.car()) || key.equal(cons.car()))
     } else {
-        assoc_impl(key, list, |k, item| call!(testfn, k, item).is_not_nil())
+        find_association(list, |cons| call!(testfn, key, cons.car()).is_not_nil())
     }
 }
 
-fn rassoc_impl<CmpFunc>(key: LispObject, list: LispObject, cmp: CmpFunc) -> LispObject
-where
-    CmpFunc: Fn(LispObject, LispObject) -> bool,
-{
-    list.iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on)
-        .find(|item| item.as_cons().map_or(false, |cons| cmp(key, cons.cdr())))
-        .unwrap_or(Qnil)
-}
-
 /// Return non-nil if KEY is `eq' to the cdr of an element of LIST.
 /// The value is actually the first element of LIST whose cdr is KEY.
 #[lisp_fn]
 pub fn rassq(key: LispObject, list: LispObject) -> LispObject {
-    rassoc_impl(key, list, LispObject::eq)
+    find_association(list, |cons| key.eq(cons.cdr()))
 }
 
 /// Return non-nil if KEY is `equal' to the cdr of an element of LIST.
@@ -605,25 +729,108 @@ pub fn rassq(key: LispObject, list: LispObject) -> LispObject {
 /// (fn KEY LIST)
 #[lisp_fn]
 pub fn rassoc(key: LispObject, list: LispObject) -> LispObject {
-    rassoc_impl(key, list, |k, item| k.eq(item) || k.equal(item))
+    find_association(list, |cons| key.eq(cons.cdr()) || key.equal(cons.cdr()))
 }
 
-/// Delete members of LIST which are `eq' to ELT, and return the result.
-/// More precisely, this function skips any members `eq' to ELT at the
-/// front of LIST, then removes members `eq' to ELT from the remaining
-/// sublist by modifying its list structure, then returns the resulting
-/// list.
+/// Find object KEY in a pseudo-alist ALIST.
+/// ALIST is a list of conses or objects.  Each element
+///  (or the element's car, if it is a cons) is compared with KEY by
+///  calling TEST, with two arguments: (i) the element or its car,
+///  and (ii) KEY.
+/// If that is non-nil, the element matches; then `assoc-default'
+///  returns the element's cdr, if it is a cons, or DEFAULT if the
+///  element is not a cons.
 ///
-/// Write `(setq foo (delq element foo))' to be sure of correctly changing
-/// the value of a list `foo'.  See also `remq', which does not modify the
-/// argument.
+/// If no element matches, the value is nil.
+/// If TEST is omitted or nil, `equal' is used.
+#[lisp_fn(min = "2")]
+pub fn assoc_default(
+    key: LispObject,
+    alist: LispObject,
+    test: LispObject,
+    default: LispObject,
+) -> LispObject {
+    for elt in alist.iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on) {
+        let (candidate, value) = match elt.as_cons() {
+            Some(cons) => cons.into(),
+            None => (elt, default),
+        };
+        let found = if test.is_nil() {
+            candidate.equal(key)
+        } else {
+            call!(test, candidate, key).is_not_nil()
+        };
+        if found {
+            return value;
+        }
+    }
+
+    Qnil
+}
+
+/// Return the value associated with KEY in ALIST.
+/// If KEY is not found in ALIST, return DEFAULT.
+/// Use TESTFN to lookup in the alist if non-nil.  Otherwise, use `assq'.
+///
+/// This is a generalized variable suitable for use with `setf'.
+/// When using it to set a value, optional argument REMOVE non-nil
+/// means to remove KEY from ALIST if the new value is `eql' to DEFAULT.
+#[lisp_fn(min = "2")]
+pub fn alist_get(
+    key: LispObject,
+    alist: LispObject,
+    default: LispObject,
+    _remove: LispObject,
+    testfn: LispObject,
+) -> LispObject {
+    let found = if testfn.is_nil() {
+        assq(key, alist)
+    } else {
+        assoc(key, alist, testfn)
+    };
+    found.as_cons().map_or(default, LispCons::cdr)
+}
+
+/// Delete from ALIST all elements whose car is KEY.
+/// Compare keys with TEST.  Defaults to `equal'.
+/// Return the modified alist.
+/// Elements of ALIST that are not conses are ignored.
+#[lisp_fn(min = "2")]
+pub fn assoc_delete_all(key: LispObject, alist: LispObject, test: LispObject) -> LispObject {
+    if test.is_nil() {
+        delete_associations(alist, |cons| cons.car().equal(key))
+    } else {
+        delete_associations(alist, |cons| call!(test, cons.car(), key).is_not_nil())
+    }
+}
+
+/// Delete from ALIST all elements whose car is `eq' to KEY.
+/// Return the modified alist.
+/// Elements of ALIST that are not conses are ignored.
 #[lisp_fn]
-pub fn delq(elt: LispObject, list: LispObject) -> LispObject {
+pub fn assq_delete_all(key: LispObject, alist: LispObject) -> LispObject {
+    delete_associations(alist, |cons| cons.car().eq(key))
+}
+
+/// Delete from ALIST all elements whose cdr is `eq' to VALUE.
+/// Return the modified alist.
+/// Elements of ALIST that are not conses are ignored.
+#[lisp_fn]
+pub fn rassq_delete_all(value: LispObject, alist: LispObject) -> LispObject {
+    delete_associations(alist, |cons| cons.cdr().eq(value))
+}
+
+/// Delete the members of LIST for which PRED is true, by modifying its
+/// list structure, and return the result.  LIST must be a proper list.
+pub fn delete_if<P>(list: LispObject, mut pred: P) -> LispObject
+where
+    P: FnMut(LispObject) -> bool,
+{
     let mut prev = None;
     list.iter_tails(LispConsEndChecks::on, LispConsCircularChecks::on)
         .fold(list, |remaining, tail| {
             let (item, rest) = tail.into();
-            if elt.eq(item) {
+            if pred(item) {
                 match prev {
                     Some(cons) => setcdr(cons, rest),
                     None => return rest,
@@ -636,6 +843,73 @@ pub fn delq(elt: LispObject, list: LispObject) -> LispObject {
         })
 }
 
+/// Delete members of LIST which are `eq' to ELT, and return the result.
+/// More precisely, this function skips any members `eq' to ELT at the
+/// front of LIST, then removes members `eq' to ELT from the remaining
+/// sublist by modifying its list structure, then returns the resulting
+/// list.
+///
+/// Write `(setq foo (delq element foo))' to be sure of correctly changing
+/// the value of a list `foo'.  See also `remq', which does not modify the
+/// argument.
+#[lisp_fn]
+pub fn delq(elt: LispObject, list: LispObject) -> LispObject {
+    delete_if(list, |item| elt.eq(item))
+}
+
+/// Return LIST with all occurrences of ELT removed.
+/// The comparison is done with `eq'.  Contrary to `delq', this does not use
+/// side-effects, and the argument LIST is not modified.
+#[lisp_fn]
+pub fn remq(elt: LispObject, list: LispObject) -> LispObject {
+    // The tail after any leading occurrences is returned as it is, unless
+    // ELT occurs in it too.
+    let rest = list
+        .iter_tails(LispConsEndChecks::on, LispConsCircularChecks::on)
+        .find(|tail| !elt.eq(tail.car()))
+        .map_or(Qnil, LispObject::from);
+    if memq(elt, rest).is_nil() {
+        return rest;
+    }
+
+    let items: Vec<LispObject> = rest
+        .iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on)
+        .filter(|&item| !elt.eq(item))
+        .collect();
+    self::list(&items)
+}
+
+/// Lists longer than this are rid of duplicates with a hash table.
+const DELETE_DUPS_HASH_THRESHOLD: usize = 100;
+
+/// Destructively remove `equal' duplicates from LIST.
+/// Store the result in LIST and return it.  LIST must be a proper list.
+/// Of several `equal' occurrences of an element in LIST, the first
+/// one is kept.
+#[lisp_fn]
+pub fn delete_dups(list: LispObject) -> LispObject {
+    list.check_list();
+    let len = list.as_cons().map_or(0, |cons| cons.length());
+    if len > DELETE_DUPS_HASH_THRESHOLD {
+        let seen = LispHashTableRef::make_equal(len);
+        delete_if(list, |elt| match seen.lookup(elt) {
+            HashLookupResult::Found(_) => true,
+            HashLookupResult::Missing(hash) => {
+                seen.put(elt, Qt, hash);
+                false
+            }
+        });
+    } else {
+        let mut tail = list.as_cons();
+        while let Some(cons) = tail {
+            let elt = cons.car();
+            setcdr(cons, delete_if(cons.cdr(), |item| elt.equal(item)));
+            tail = cons.cdr().as_cons();
+        }
+    }
+    list
+}
+
 /// Extract a value from a property list.
 /// PLIST is a property list, which is a list of the form
 /// (PROP1 VALUE1 PROP2 VALUE2...).  This function returns the value
@@ -827,28 +1101,26 @@ pub fn make_list(length: EmacsUint, init: LispObject) -> LispObject {
 /// which is at least the number of distinct elements.
 #[lisp_fn]
 pub fn safe_length(list: LispObject) -> usize {
-    list.iter_tails(LispConsEndChecks::off, LispConsCircularChecks::safe)
+    list.iter_tails_checked()
+        .take_while(|tail| tail.is_ok())
         .count()
 }
 
-// Used by sort() in vectors.rs.
-
-pub fn sort_list(list: LispObject, pred: LispObject) -> LispObject {
-    let length = safe_length(list) as EmacsInt;
-    if length < 2 {
-        return list;
+/// Return OBJECT's length if it is a proper list, nil otherwise.
+/// A proper list is neither circular nor dotted (i.e., its last cdr is nil).
+#[lisp_fn]
+pub fn proper_list_p(object: LispObject) -> Option<EmacsInt> {
+    let mut length: EmacsInt = 0;
+    for tail in object.iter_tails_checked() {
+        tail.ok()?;
+        length = length
+            .checked_add(1)
+            .filter(|&n| n <= MOST_POSITIVE_FIXNUM)?;
     }
-
-    let item = nthcdr(length / 2 - 1, list);
-    let back = cdr(item);
-    setcdr(item.into(), Qnil);
-
-    let front = sort_list(list, pred);
-    let back = sort_list(back, pred);
-    merge(front, back, pred)
+    Some(length)
 }
 
-// also needed by vectors.rs
+// Used by merge().
 pub fn inorder(pred: LispObject, a: LispObject, b: LispObject) -> bool {
     call!(pred, b, a).is_nil()
 }
@@ -902,68 +1174,4 @@ pub fn circular_list(obj: LispObject) -> ! {
     xsignal!(Qcircular_list, obj);
 }
 
-fn mapcar_over_iterator<I: Iterator<Item = LispObject>>(
-    output: &mut [LispObject],
-    fun: LispObject,
-    it: I,
-) -> EmacsInt {
-    let mut mapped = 0;
-
-    for (i, elt) in it.enumerate() {
-        let dummy = call!(fun, elt);
-        if output.len() > i {
-            output[i as usize] = dummy;
-        }
-        mapped = i + 1;
-    }
-
-    mapped as EmacsInt
-}
-
-// This is the guts of all mapping functions.
-// Apply FUN to each element of SEQ, one by one, storing the results
-// into elements of VALS, a C vector of Lisp_Objects.  LENI is the
-// length of VALS, which should also be the length of SEQ.  Return the
-// number of results; although this is normally LENI, it can be less
-// if SEQ is made shorter as a side effect of FN.
-#[no_mangle]
-pub extern "C" fn mapcar1(
-    leni: EmacsInt,
-    vals: *mut LispObject,
-    fun: LispObject,
-    seq: LispObject,
-) -> EmacsInt {
-    let mut safe_value = [Qnil];
-
-    let output = if vals.is_null() {
-        &mut safe_value
-    } else {
-        unsafe { std::slice::from_raw_parts_mut(vals, leni as usize) }
-    };
-
-    if let Some(v) = seq.as_vectorlike() {
-        if let Some(vc) = v.as_vector() {
-            return mapcar_over_iterator(output, fun, vc.iter());
-        } else if let Some(cf) = v.as_compiled() {
-            return mapcar_over_iterator(output, fun, cf.iter());
-        } else if let Some(bv) = v.as_bool_vector() {
-            return mapcar_over_iterator(output, fun, bv.iter());
-        }
-    }
-    if let Some(s) = seq.as_string() {
-        return mapcar_over_iterator(
-            output,
-            fun,
-            s.char_indices()
-                .map(|(_, c)| LispObject::from_fixnum(c as EmacsInt)),
-        );
-    }
-
-    mapcar_over_iterator(
-        output,
-        fun,
-        seq.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off),
-    )
-}
-
 include!(concat!(env!("OUT_DIR"), "/lists_exports.rs"));
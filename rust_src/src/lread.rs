@@ -16,9 +16,10 @@ use crate::{
     eval::unbind_to,
     lisp::{defsubr, LispObject},
     obarray::{intern, intern_c_string_1},
+    reader::read_internal_start,
     remacs_sys,
     remacs_sys::{
-        build_string, read_internal_start, readevalloop, specbind, staticpro, symbol_redirect,
+        build_string, readevalloop, specbind, staticpro, symbol_redirect,
     },
     remacs_sys::{globals, EmacsInt},
     remacs_sys::{Qeval_buffer_list, Qnil, Qread_char, Qstandard_output, Qsymbolp},
@@ -187,7 +188,7 @@ pub fn read(stream: LispObject) -> LispObject {
             build_string(cs.as_ptr())
         })
     } else {
-        unsafe { read_internal_start(input, Qnil, Qnil) }
+        read_internal_start(input, Qnil, Qnil)
     }
 }
 
@@ -3,23 +3,24 @@
 use remacs_macros::lisp_fn;
 
 use crate::{
-    buffers::{current_buffer, LispBufferOrName},
+    buffers::{current_buffer, LispBufferOrCurrent, LispBufferOrName, LispBufferRef},
     editfns::field_end,
-    eval::unbind_to,
+    eval::{commandp, unbind_to},
     keymap::get_keymap,
     lisp::defsubr,
     lisp::LispObject,
-    lists::{car_safe, cdr_safe, memq},
-    obarray::{intern, lisp_intern},
+    lists::{car_safe, cdr_safe, get, list, memq, LispConsCircularChecks, LispConsEndChecks},
+    obarray::{intern, lisp_intern, LispObarrayRef},
     remacs_sys::{
-        globals, Qcommandp, Qcustom_variable_p, Qfield, Qminibuffer_completion_table,
-        Qminibuffer_history, Qnil, Qt, Vminibuffer_list,
+        buffer_local_value, globals, Qcommandp, Qcustom_variable_p, Qfield,
+        Qminibuffer_completion_table, Qminibuffer_history, Qnil, Qt, Qunbound, Vminibuffer_list,
     },
     remacs_sys::{
         make_buffer_string, minibuf_level, minibuf_prompt, minibuf_window, read_minibuf, specbind,
-        EmacsInt, Fcopy_sequence,
+        EmacsInt,
     },
-    symbols::symbol_value,
+    sequences::copy_sequence,
+    symbols::{symbol_value, LispSymbolRef},
     textprop::get_char_property,
     threads::{c_specpdl_index, ThreadState},
 };
@@ -70,7 +71,7 @@ pub fn minibuffer_depth() -> EmacsInt {
 /// minibuffer. If no minibuffer is active return nil.
 #[lisp_fn]
 pub fn minibuffer_prompt() -> LispObject {
-    unsafe { Fcopy_sequence(minibuf_prompt) }
+    copy_sequence(unsafe { minibuf_prompt })
 }
 
 /// Return the buffer position of the end of the minibuffer prompt.
@@ -426,4 +427,98 @@ pub fn read_no_blanks_input(
     }
 }
 
+/// Return the names of the commands that `execute-extended-command' offers.
+/// This is every command interned in `obarray', except obsolete ones.
+/// If `read-extended-command-predicate' is non-nil, it is called with
+/// the symbol of each command and BUFFER, and only the commands for which
+/// it returns non-nil are included.  BUFFER defaults to the current
+/// buffer.
+#[lisp_fn(min = "0")]
+pub fn extended_command_candidates(buffer: LispBufferOrCurrent) -> LispObject {
+    let buffer: LispObject = LispBufferRef::from(buffer).into();
+    let predicate = symbol_value(intern("read-extended-command-predicate"));
+    let obsolete = LispObject::from(intern("byte-obsolete-info"));
+
+    let mut names = Vec::new();
+    let obarray = LispObarrayRef::global();
+    for bucket in LispObject::from(obarray).as_vector_or_error().iter() {
+        let first = match bucket.as_symbol() {
+            Some(sym) => sym,
+            None => continue,
+        };
+        for sym in first.iter() {
+            let object = LispObject::from(sym);
+            if commandp(object, false)
+                && get(sym, obsolete).is_nil()
+                && (predicate.is_nil() || call!(predicate, object, buffer).is_not_nil())
+            {
+                names.push(sym.symbol_name());
+            }
+        }
+    }
+    list(&names)
+}
+
+/// Return true if the command named COMMAND belongs to the mode named
+/// MODE: COMMAND is MODE, or MODE ends in `-mode' and COMMAND starts
+/// with what comes before, and a dash.
+fn command_of_mode(command: &[u8], mode: &[u8]) -> bool {
+    if command == mode {
+        return true;
+    }
+    if !mode.ends_with(b"-mode") {
+        return false;
+    }
+    let prefix = &mode[..mode.len() - "mode".len()];
+    prefix.len() > 1 && command.starts_with(prefix)
+}
+
+/// Return the mode of BUFFER that the command COMMAND belongs to, or nil.
+/// A command belongs to a mode when its name starts with the name of the
+/// mode without `mode', like `dired-do-copy' and `dired-mode', or when
+/// it is the mode itself.  The major mode of BUFFER is tried first, then
+/// the minor modes enabled in BUFFER, in the order of `minor-mode-alist'.
+/// BUFFER defaults to the current buffer.
+///
+/// `read-extended-command' uses this to annotate the commands that are
+/// relevant in the buffer where \[execute-extended-command] was typed.
+#[lisp_fn(min = "1")]
+pub fn extended_command_mode(command: LispSymbolRef, buffer: LispBufferOrCurrent) -> LispObject {
+    let buffer: LispObject = LispBufferRef::from(buffer).into();
+    let name = command.symbol_name().force_string();
+    let belongs = |mode: LispObject| {
+        mode.as_symbol().map_or(false, |mode| {
+            let mode_name = mode.symbol_name().force_string();
+            command_of_mode(name.as_slice(), mode_name.as_slice())
+        })
+    };
+
+    let major_mode = unsafe { buffer_local_value(intern("major-mode").into(), buffer) };
+    if belongs(major_mode) {
+        return major_mode;
+    }
+    let minor_modes = symbol_value(intern("minor-mode-alist"));
+    minor_modes
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe)
+        .map(car_safe)
+        .find(|&mode| {
+            mode.is_symbol() && belongs(mode) && {
+                let value = unsafe { buffer_local_value(mode, buffer) };
+                !value.eq(Qunbound) && value.is_not_nil()
+            }
+        })
+        .unwrap_or(Qnil)
+}
+
 include!(concat!(env!("OUT_DIR"), "/minibuf_exports.rs"));
+
+#[test]
+fn test_command_of_mode() {
+    assert!(command_of_mode(b"dired-do-copy", b"dired-mode"));
+    assert!(command_of_mode(b"dired-mode", b"dired-mode"));
+    assert!(command_of_mode(b"auto-fill-mode", b"auto-fill-mode"));
+    assert!(!command_of_mode(b"diredp-copy", b"dired-mode"));
+    assert!(!command_of_mode(b"forward-char", b"dired-mode"));
+    assert!(!command_of_mode(b"fill-function-x", b"fill-function"));
+    assert!(!command_of_mode(b"-x", b"-mode"));
+}
@@ -0,0 +1,404 @@
+//! Monitor configuration changes.
+//!
+//! The window system backends queue a `monitors-changed' event when
+//! monitors of a display are connected, disconnected or rearranged.
+//! The event is handled by comparing the monitor attributes of the
+//! display with those seen before, and telling the functions in
+//! `monitor-configuration-change-functions' what changed.
+//!
+//! Monitors of one display can have different resolutions, and so the
+//! scale factor of a frame changes when it moves to another monitor, or
+//! when its monitor changes.  The functions in `scale-change-functions'
+//! are told about that.  Asking the window system for the monitors is
+//! too slow to do whenever a frame moves, so the attributes seen when
+//! the monitors last changed are used to find the monitor of a frame.
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    eval::run_hook_with_args,
+    frames::{LispFrameOrSelected, LispFrameRef},
+    lisp::{defsubr, LispObject},
+    lists::{assq, car, cdr, list, nth, LispConsCircularChecks, LispConsEndChecks},
+    obarray::intern,
+    remacs_sys::{store_frame_param, EmacsDouble, EmacsInt, Vframe_list},
+    remacs_sys::{Fframe_parameter, Fframe_terminal},
+    remacs_sys::{Fset_terminal_parameter, Fterminal_live_p, Fterminal_parameter},
+    remacs_sys::{Qdisplay_monitor_attributes_list, Qnil},
+};
+
+/// The terminal parameter holding the monitor attributes last seen.
+const ATTRIBUTES_PARAMETER: &str = "monitors--attributes";
+
+/// The frame parameter holding the scale factor last seen.
+const SCALE_FACTOR_PARAMETER: &str = "monitors--scale-factor";
+
+/// The resolution, in dots per inch, of monitors with scale factor 1.
+const BASE_DPI: EmacsDouble = 96.0;
+
+/// Return the value that identifies the monitor with the attributes
+/// ATTRS: its name, or its geometry if it has none.
+fn monitor_key(attrs: LispObject) -> LispObject {
+    let name = assq(LispObject::from(intern("name")), attrs);
+    if name.is_not_nil() {
+        name
+    } else {
+        assq(LispObject::from(intern("geometry")), attrs)
+    }
+}
+
+/// Return true if the monitor attributes A and B are the same, except
+/// for the frames on the monitors.
+fn same_attributes(a: LispObject, b: LispObject) -> bool {
+    let frames = LispObject::from(intern("frames"));
+    let significant = |attrs: LispObject| {
+        attrs
+            .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe)
+            .filter(|attr| !car(*attr).eq(frames))
+            .collect::<Vec<_>>()
+    };
+    let (a, b) = (significant(a), significant(b));
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(x, y)| x.equal(*y))
+}
+
+/// Return the changes between the monitor attributes lists OLD and NEW,
+/// as a list of (added ATTRS), (removed ATTRS) and (changed OLD-ATTRS
+/// NEW-ATTRS) elements.
+fn monitor_changes(old: LispObject, new: LispObject) -> LispObject {
+    let monitors = |attributes_list: LispObject| {
+        attributes_list
+            .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe)
+            .map(|attrs| (monitor_key(attrs), attrs))
+            .collect::<Vec<_>>()
+    };
+    let (old, new) = (monitors(old), monitors(new));
+    let find = |monitors: &[(LispObject, LispObject)], key: LispObject| {
+        monitors
+            .iter()
+            .find(|(k, _)| k.equal(key))
+            .map(|&(_, attrs)| attrs)
+    };
+
+    let mut changes = Vec::new();
+    for &(key, attrs) in &old {
+        match find(&new, key) {
+            None => changes.push(list!(LispObject::from(intern("removed")), attrs)),
+            Some(new_attrs) if !same_attributes(attrs, new_attrs) => {
+                changes.push(list!(LispObject::from(intern("changed")), attrs, new_attrs))
+            }
+            Some(_) => {}
+        }
+    }
+    for &(key, attrs) in &new {
+        if find(&old, key).is_none() {
+            changes.push(list!(LispObject::from(intern("added")), attrs));
+        }
+    }
+
+    list(&changes)
+}
+
+/// Return the value of the number OBJ as a float, or None if it is not
+/// a number.
+fn float_value(obj: LispObject) -> Option<EmacsDouble> {
+    obj.as_float()
+        .or_else(|| obj.as_fixnum().map(|n| n as EmacsDouble))
+}
+
+/// Return the scale factor of the monitor with the attributes ATTRS.
+/// It is the `scale-factor' attribute if the window system tells it,
+/// else the resolution of the monitor over `BASE_DPI', in steps of a
+/// quarter and at least 1.
+fn monitor_scale_factor(attrs: LispObject) -> EmacsDouble {
+    let attribute = |name: &str| cdr(assq(LispObject::from(intern(name)), attrs));
+
+    if let Some(scale) = float_value(attribute("scale-factor")) {
+        return scale;
+    }
+    let pixels = float_value(nth(2, attribute("geometry")));
+    let mm = float_value(car(attribute("mm-size")));
+    match (pixels, mm) {
+        (Some(pixels), Some(mm)) if pixels > 0.0 && mm > 0.0 => {
+            let dpi = pixels * 25.4 / mm;
+            ((dpi / BASE_DPI * 4.0).round() / 4.0).max(1.0)
+        }
+        _ => 1.0,
+    }
+}
+
+/// A rectangle as [X, Y, WIDTH, HEIGHT], like the `geometry' and
+/// `workarea' monitor attributes.
+type Rectangle = [EmacsDouble; 4];
+
+/// Return the rectangle in OBJ, a list of four numbers, if it is one.
+fn rectangle(obj: LispObject) -> Option<Rectangle> {
+    let mut rect = [0.0; 4];
+    for (i, value) in rect.iter_mut().enumerate() {
+        *value = float_value(nth(i as EmacsInt, obj))?;
+    }
+    Some(rect)
+}
+
+/// Return the area of the intersection of the rectangles A and B.
+fn overlap(a: Rectangle, b: Rectangle) -> EmacsDouble {
+    let width = (a[0] + a[2]).min(b[0] + b[2]) - a[0].max(b[0]);
+    let height = (a[1] + a[3]).min(b[1] + b[3]) - a[1].max(b[1]);
+    width.max(0.0) * height.max(0.0)
+}
+
+/// Return the index of the monitor in MONITORS, given by their
+/// geometry, that has the largest part of the rectangle FRAME, or 0 if
+/// FRAME is on none of them.
+fn dominating_monitor(frame: Rectangle, monitors: &[Rectangle]) -> usize {
+    let mut best = (0, 0.0);
+    for (i, &monitor) in monitors.iter().enumerate() {
+        let area = overlap(frame, monitor);
+        if area > best.1 {
+            best = (i, area);
+        }
+    }
+    best.0
+}
+
+/// Return the attributes of the monitor that dominates FRAME, from the
+/// monitor attributes of its terminal last remembered by
+/// `monitors--remember-configuration'.  Unlike `frame-monitor-attributes',
+/// this doesn't ask the window system, so it can be done whenever
+/// FRAME moves.
+fn frame_monitor(frame: LispFrameRef) -> LispObject {
+    let terminal = unsafe { Fframe_terminal(LispObject::from(frame)) };
+    let mut attributes_list =
+        unsafe { Fterminal_parameter(terminal, LispObject::from(intern(ATTRIBUTES_PARAMETER))) };
+    if attributes_list.is_nil() {
+        attributes_list = monitors_remember_configuration(terminal);
+    }
+    let monitors: Vec<_> = attributes_list
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe)
+        .collect();
+    let geometries: Vec<_> = monitors
+        .iter()
+        .map(|&attrs| {
+            rectangle(cdr(assq(LispObject::from(intern("geometry")), attrs))).unwrap_or([0.0; 4])
+        })
+        .collect();
+
+    let edges = call!(
+        LispObject::from(intern("frame-edges")),
+        LispObject::from(frame),
+        LispObject::from(intern("outer-edges"))
+    );
+    let frame_rect = rectangle(edges).map_or([0.0; 4], |[left, top, right, bottom]| {
+        [left, top, right - left, bottom - top]
+    });
+
+    monitors
+        .get(dominating_monitor(frame_rect, &geometries))
+        .cloned()
+        .unwrap_or(Qnil)
+}
+
+/// Return the scale factor of FRAME's monitor.
+/// This is the size of a logical pixel in pixels of the monitor that
+/// dominates FRAME, see `frame-monitor-attributes'.  It is 1.0 on
+/// monitors of standard resolution and on text terminals, and 2.0 on
+/// monitors of twice that resolution, say.
+///
+/// FRAME defaults to the selected frame.
+#[lisp_fn(min = "0")]
+pub fn frame_scale_factor(frame: LispFrameOrSelected) -> EmacsDouble {
+    let frame = frame.live_or_error();
+    if !frame.is_gui_window() {
+        return 1.0;
+    }
+    monitor_scale_factor(frame_monitor(frame))
+}
+
+/// Return RECT, in pixels of a monitor with scale factor SCALE, with
+/// its size in logical pixels.  The origin is left alone, because the
+/// monitors of a display, which may have different scale factors,
+/// share its coordinates.
+fn logical_rectangle(rect: Rectangle, scale: EmacsDouble) -> Rectangle {
+    [
+        rect[0],
+        rect[1],
+        (rect[2] / scale).round(),
+        (rect[3] / scale).round(),
+    ]
+}
+
+/// Return the monitor ATTRIBUTES with the sizes in logical pixels.
+/// ATTRIBUTES is an element of `display-monitor-attributes-list'.  The
+/// value is a copy where the width and height of the `geometry' and
+/// `workarea' attributes are divided by the scale factor of the monitor,
+/// and which has a `scale-factor' attribute if ATTRIBUTES has none.
+/// The origins stay in pixels of the display.  See `frame-scale-factor'.
+#[lisp_fn]
+pub fn monitor_logical_attributes(attributes: LispObject) -> LispObject {
+    let scale = monitor_scale_factor(attributes);
+    let scale_factor = LispObject::from(intern("scale-factor"));
+    let resized = [
+        LispObject::from(intern("geometry")),
+        LispObject::from(intern("workarea")),
+    ];
+
+    let mut result = Vec::new();
+    for attr in attributes.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe) {
+        let key = car(attr);
+        match rectangle(cdr(attr)) {
+            Some(rect) if resized.iter().any(|k| k.eq(key)) => {
+                let values: Vec<_> = logical_rectangle(rect, scale)
+                    .iter()
+                    .map(|&v| LispObject::from(v as EmacsInt))
+                    .collect();
+                result.push(LispObject::cons(key, list(&values)));
+            }
+            _ => result.push(attr),
+        }
+    }
+    if assq(scale_factor, attributes).is_nil() {
+        result.push(LispObject::cons(scale_factor, scale));
+    }
+    list(&result)
+}
+
+/// Update the scale factor recorded for FRAME.
+/// If it was recorded before and has changed, call the functions in
+/// `scale-change-functions'.  This is done for new frames, frames that
+/// moved, and the frames of a terminal whose monitors changed.  The
+/// monitors are not asked for their attributes again: those remembered
+/// when they last changed are used.
+#[lisp_fn(
+    name = "monitors--update-scale-factor",
+    c_name = "monitors_update_scale_factor"
+)]
+pub fn monitors_update_scale_factor(frame: LispFrameRef) {
+    if !frame.is_live() {
+        return;
+    }
+    let parameter = LispObject::from(intern(SCALE_FACTOR_PARAMETER));
+    let old = unsafe { Fframe_parameter(LispObject::from(frame), parameter) };
+    let new = LispObject::from(frame_scale_factor(LispObject::from(frame).into()));
+    if old.eql(new) {
+        return;
+    }
+
+    let mut f = frame;
+    unsafe { store_frame_param(f.as_mut(), parameter, new) };
+    if old.is_not_nil() {
+        run_hook_with_args(&mut [
+            LispObject::from(intern("scale-change-functions")),
+            LispObject::from(frame),
+            old,
+            new,
+        ]);
+    }
+}
+
+/// Remember the monitor attributes of TERMINAL, to tell later what changed.
+/// This is done for the terminals of new frames on window systems, and
+/// when the monitors of TERMINAL changed.  Return the attributes.
+#[lisp_fn(
+    name = "monitors--remember-configuration",
+    c_name = "monitors_remember_configuration"
+)]
+pub fn monitors_remember_configuration(terminal: LispObject) -> LispObject {
+    let attrs = call!(Qdisplay_monitor_attributes_list, terminal);
+    unsafe {
+        Fset_terminal_parameter(
+            terminal,
+            LispObject::from(intern(ATTRIBUTES_PARAMETER)),
+            attrs,
+        )
+    };
+    attrs
+}
+
+/// Handle a monitors-changed event EVENT.
+/// The event says that monitors were connected to, disconnected from,
+/// or rearranged on the terminal in (cadr EVENT).  The functions in
+/// `monitor-configuration-change-functions' are called with the
+/// terminal and a list of the changes, if there are any.
+#[lisp_fn(intspec = "e")]
+pub fn handle_monitors_changed_event(event: LispObject) {
+    let terminal = car(cdr(event));
+    if unsafe { Fterminal_live_p(terminal) }.is_nil() {
+        return;
+    }
+
+    let old =
+        unsafe { Fterminal_parameter(terminal, LispObject::from(intern(ATTRIBUTES_PARAMETER))) };
+    let new = monitors_remember_configuration(terminal);
+    let changes = monitor_changes(old, new);
+    if changes.is_not_nil() {
+        run_hook_with_args(&mut [
+            LispObject::from(intern("monitor-configuration-change-functions")),
+            terminal,
+            changes,
+        ]);
+    }
+
+    for_each_frame!(frame => {
+        if unsafe { Fframe_terminal(LispObject::from(frame)) }.eq(terminal) {
+            monitors_update_scale_factor(frame);
+        }
+    });
+}
+
+#[no_mangle]
+pub extern "C" fn syms_of_monitors() {
+    /// Functions called when the monitors of a display change.
+    /// Each function is called with two arguments, the terminal whose
+    /// monitors changed and a list of the changes.  Its elements are
+    /// (added ATTRIBUTES) for a monitor that was connected, (removed
+    /// ATTRIBUTES) for one that was disconnected, and (changed OLD NEW)
+    /// for one whose geometry, work area, or other attributes changed.
+    /// ATTRIBUTES, OLD and NEW are elements of the value of
+    /// `display-monitor-attributes-list', see there.
+    ///
+    /// Monitors are told apart by their names, or by their geometry if
+    /// they have no name.
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    defvar_lisp!(Vmonitor_configuration_change_functions, "monitor-configuration-change-functions", Qnil);
+
+    /// Functions called when the scale factor of a frame changes.
+    /// Each function is called with three arguments, the frame, and its
+    /// old and new scale factor, see `frame-scale-factor'.  The scale
+    /// factor changes when the frame moves to a monitor of another
+    /// resolution, or when the resolution of its monitor changes.
+    defvar_lisp!(Vscale_change_functions, "scale-change-functions", Qnil);
+}
+
+include!(concat!(env!("OUT_DIR"), "/monitors_exports.rs"));
+
+#[test]
+fn test_dominating_monitor() {
+    let monitors = [[0.0, 0.0, 1920.0, 1080.0], [1920.0, 0.0, 3840.0, 2160.0]];
+    assert_eq!(
+        dominating_monitor([100.0, 100.0, 800.0, 600.0], &monitors),
+        0
+    );
+    assert_eq!(
+        dominating_monitor([1800.0, 0.0, 800.0, 600.0], &monitors),
+        1
+    );
+    assert_eq!(
+        dominating_monitor([-900.0, 0.0, 800.0, 600.0], &monitors),
+        0
+    );
+    assert_eq!(
+        overlap([0.0, 0.0, 10.0, 10.0], [5.0, 5.0, 10.0, 10.0]),
+        25.0
+    );
+}
+
+#[test]
+fn test_logical_rectangle() {
+    assert_eq!(
+        logical_rectangle([1920.0, 0.0, 3840.0, 2160.0], 2.0),
+        [1920.0, 0.0, 1920.0, 1080.0]
+    );
+    assert_eq!(
+        logical_rectangle([0.0, 0.0, 2880.0, 1620.0], 1.5),
+        [0.0, 0.0, 1920.0, 1080.0]
+    );
+}
@@ -0,0 +1,304 @@
+//! Window autoselection, as `mouse-autoselect-window' asks for.
+//!
+//! A `select-window' event is generated when the mouse moves into
+//! another window.  With immediate autoselection, `handle-select-window'
+//! selects that window right away.  With a delay, it starts a timer
+//! instead, which looks up the window under the mouse each time it
+//! fires, and selects that window once the mouse has settled in it.
+//! Any command cancels the timer.
+
+use std::cell::Cell;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    frames::{frame_ancestor_p, mouse_position, selected_frame, window_system},
+    lisp::{defsubr, LispObject},
+    lists::nth,
+    minibuf::active_minibuffer_window,
+    obarray::intern,
+    objects::equal,
+    remacs_sys::globals,
+    remacs_sys::{Fcoordinates_in_window_p, Fframe_parameter, Fwindow_at},
+    remacs_sys::{Qnil, Qt},
+    symbols::fboundp,
+    windows::{select_window_lisp, selected_window},
+};
+
+/// Where delayed autoselection stands.
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    /// Waiting for the mouse to settle.
+    Waiting,
+    /// Suspended while a menu or the scroll bar is in use.
+    Suspended,
+    /// The mouse has settled; the next `handle-select-window' selects
+    /// its window right away.
+    Select,
+}
+
+thread_local! {
+    static STATE: Cell<State> = Cell::new(State::Waiting);
+}
+
+/// The timer that runs `mouse-autoselect-window-select'.
+declare_GC_protected_static!(autoselect_timer, Qnil);
+
+/// The mouse position where delayed autoselection started, as returned
+/// by `mouse-position'.
+declare_GC_protected_static!(first_position, Qnil);
+
+/// The last mouse position seen by delayed autoselection.
+declare_GC_protected_static!(last_position, Qnil);
+
+/// The last window the mouse was seen in by delayed autoselection.
+declare_GC_protected_static!(last_window, Qnil);
+
+fn state() -> State {
+    STATE.with(Cell::get)
+}
+
+fn set_state(state: State) {
+    STATE.with(|s| s.set(state));
+}
+
+/// Return the delay of autoselection in seconds, or `None` if windows
+/// are to be selected right away.
+fn autoselect_delay() -> Option<f64> {
+    let value = unsafe { globals.Vmouse_autoselect_window };
+    value
+        .as_fixnum()
+        .map(|n| n as f64)
+        .or_else(|| value.as_float())
+}
+
+/// Return the live window under the mouse at POSITION, as returned by
+/// `mouse-position', if there is one.
+fn window_under_mouse(position: LispObject) -> Option<LispObject> {
+    let (frame, coordinates) = position.into();
+    let (x, y) = coordinates.into();
+    if !(x.is_number() && y.is_number()) {
+        return None;
+    }
+    let window = unsafe { Fwindow_at(x, y, frame) };
+    window.as_live_window().map(|_| window)
+}
+
+/// Return whether POSITION is on the text area or a margin of WINDOW.
+fn is_in_text(position: LispObject, window: LispObject) -> bool {
+    let part = unsafe { Fcoordinates_in_window_p(position.force_cons().cdr(), window) };
+    part.is_cons() || part.eq(intern("left-margin")) || part.eq(intern("right-margin"))
+}
+
+fn is_menu_or_popup_active() -> bool {
+    let predicate = intern("menu-or-popup-active-p");
+    fboundp(predicate) && call!(predicate.into()).is_not_nil()
+}
+
+/// Return whether the command being run is one that mustn't cancel
+/// delayed autoselection: a select-window or select-frame event, or
+/// dragging the scroll bar.
+fn is_autoselect_command() -> bool {
+    let command = unsafe { globals.Vthis_command };
+    if command.eq(intern("handle-select-window")) || command.eq(intern("handle-switch-frame")) {
+        return true;
+    }
+    if !command.eq(intern("scroll-bar-toolkit-scroll")) {
+        return false;
+    }
+    let posn = call!(intern("event-end").into(), unsafe {
+        globals.last_input_event
+    });
+    let part = nth(4, posn);
+    part.eq(intern("handle")) || part.eq(intern("end-scroll"))
+}
+
+fn cancel_timer() {
+    let timer = autoselect_timer.get();
+    if call!(intern("timerp").into(), timer).is_not_nil() {
+        call!(intern("cancel-timer").into(), timer);
+    }
+    autoselect_timer.set(Qnil);
+}
+
+/// Start delayed autoselection at POSITION, as returned by
+/// `mouse-position', where the mouse was seen in WINDOW if that is
+/// known.  If SUSPEND, autoselection is suspended until the mouse is
+/// back on the text of a window.
+fn start(position: LispObject, window: Option<LispObject>, suspend: bool) {
+    last_position.set(position);
+    if let Some(window) = window {
+        last_window.set(window);
+    }
+    set_state(if suspend {
+        State::Suspended
+    } else {
+        State::Waiting
+    });
+    // A timer left over would fire early.
+    cancel_timer();
+    let delay = autoselect_delay().map_or(0.0, f64::abs);
+    autoselect_timer.set(call!(
+        intern("run-at-time").into(),
+        LispObject::from(delay),
+        Qnil,
+        intern("mouse-autoselect-window-select").into()
+    ));
+}
+
+/// Cancel delayed window autoselection.
+/// Optional argument FORCE means cancel unconditionally.
+#[lisp_fn(min = "0")]
+pub fn mouse_autoselect_window_cancel(force: bool) {
+    if !force && is_autoselect_command() {
+        return;
+    }
+    set_state(State::Waiting);
+    first_position.set(Qnil);
+    cancel_timer();
+    call!(
+        intern("remove-hook").into(),
+        intern("pre-command-hook").into(),
+        intern("mouse-autoselect-window-cancel").into()
+    );
+}
+
+/// Select window with delayed window autoselection.
+/// If the mouse position has stabilized in a non-selected window, select
+/// that window.  The minibuffer window is selected only if the minibuffer
+/// is active.  This function is run by the timer of delayed window
+/// autoselection.
+#[lisp_fn]
+pub fn mouse_autoselect_window_select() {
+    let position = mouse_position();
+    let window = window_under_mouse(position);
+    let delay = autoselect_delay();
+
+    if is_menu_or_popup_active() || window.map_or(false, |w| !is_in_text(position, w)) {
+        // A menu or popup dialog is active, or the mouse is not on the
+        // text of WINDOW: suspend autoselection for now.
+        start(position, None, true);
+        return;
+    }
+    if state() == State::Suspended || (delay.is_some() && equal(position, first_position.get())) {
+        // Autoselection was suspended, or the mouse is back where it
+        // started, which it may seem to be in another window when a
+        // frame was minimized: restart.
+        start(position, None, false);
+        return;
+    }
+
+    // With a non-negative delay, the mouse has settled when it is in the
+    // same window as before, otherwise when it is at the same position.
+    let same_window = window.map_or(false, |w| w.eq(last_window.get()));
+    let settled = match delay {
+        None => true,
+        Some(delay) if delay >= 0.0 && same_window => true,
+        Some(_) => equal(position, last_position.get()),
+    };
+    let candidate = window.filter(|&w| {
+        let is_minibuffer = w.as_window().map_or(false, |w| w.is_minibuffer());
+        !is_minibuffer || w.eq(active_minibuffer_window())
+    });
+    match candidate {
+        Some(window) if settled => {
+            mouse_autoselect_window_cancel(true);
+            // Selecting the window that is selected already would only
+            // clear the echo area.
+            if !window.eq(selected_window()) {
+                // Go through `unread-command-events', so that the pre-
+                // and post-command hooks run and idle timers are
+                // triggered, and don't delay the event again.
+                set_state(State::Select);
+                let event = list!(intern("select-window"), list!(window));
+                unsafe {
+                    globals.Vunread_command_events =
+                        LispObject::cons(event, globals.Vunread_command_events)
+                };
+            }
+        }
+        // The mouse has settled where it isn't to select a window.
+        _ if delay.is_none() || equal(position, last_position.get()) => {
+            mouse_autoselect_window_cancel(true)
+        }
+        // The mouse hasn't settled yet.
+        _ if window.is_some() => start(position, window, false),
+        _ => {}
+    }
+}
+
+/// Handle select-window events.
+#[lisp_fn(intspec = "^e")]
+pub fn handle_select_window(event: LispObject) {
+    let window = call!(
+        intern("posn-window").into(),
+        call!(intern("event-start").into(), event)
+    );
+    let frame = match window.as_live_window() {
+        Some(w) => w.frame,
+        None => return,
+    };
+    let old_frame: LispObject = selected_frame().into();
+
+    // Don't switch when autoselection shall be delayed.
+    if autoselect_delay().is_some() && state() != State::Select {
+        let position = mouse_position();
+        mouse_autoselect_window_cancel(true);
+        first_position.set(position);
+        start(position, Some(window), false);
+        // Executing a command cancels delayed autoselection.
+        call!(
+            intern("add-hook").into(),
+            intern("pre-command-hook").into(),
+            intern("mouse-autoselect-window-cancel").into()
+        );
+        return;
+    }
+    // Don't switch to a `no-accept-focus' frame unless it's already
+    // selected.
+    if !frame.eq(old_frame)
+        && unsafe { Fframe_parameter(frame, intern("no-accept-focus").into()) }.is_not_nil()
+    {
+        return;
+    }
+    // Don't switch to the minibuffer window unless it's active.
+    if window.as_window_or_error().is_minibuffer()
+        && call!(intern("minibuffer-window-active-p").into(), window).is_nil()
+    {
+        return;
+    }
+
+    set_state(State::Waiting);
+    if window.eq(selected_window()) {
+        return;
+    }
+    call!(
+        intern("run-hooks").into(),
+        intern("mouse-leave-buffer-hook").into()
+    );
+    // Clear echo area.
+    call!(intern("message").into(), Qnil);
+    // Select the window before giving the frame focus since otherwise
+    // we might get two windows with an active cursor.
+    select_window_lisp(window, Qnil);
+
+    let system = window_system(frame.into());
+    let follows_mouse = unsafe { globals.focus_follows_mouse };
+    let focus = ["x", "w32", "ns"].iter().any(|&s| system.eq(intern(s)))
+        && follows_mouse.is_not_nil()
+        // Focus FRAME only if it's a child frame or an ancestor of the
+        // frame switched from.
+        && (unsafe { Fframe_parameter(frame, intern("parent-frame").into()) }.is_not_nil()
+            || frame_ancestor_p(frame.into(), old_frame.into()));
+    if !focus {
+        return;
+    }
+    if follows_mouse.eq(intern("auto-raise")) {
+        call!(intern("x-focus-frame").into(), frame);
+        call!(intern("raise-frame").into(), frame);
+    } else {
+        call!(intern("x-focus-frame").into(), frame, Qt);
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/mouse_autoselect_exports.rs"));
@@ -32,6 +32,7 @@
 //! `&str`, and this module regrettably contains adapted copies of
 //! stretches of `std::str` functions.
 
+use std::cmp;
 use std::fmt;
 use std::ptr;
 use std::slice;
@@ -94,6 +95,25 @@ impl LispStringRef {
         }
     }
 
+    /// Return the number of characters at the start of the string that
+    /// fit in COLUMNS columns, together with the width they take.
+    pub fn width_prefix(self, columns: usize) -> (ptrdiff_t, usize) {
+        if columns == 0 {
+            return (0, 0);
+        }
+        let mut nchars = 0;
+        let mut nbytes = 0;
+        let width = unsafe {
+            lisp_string_width(
+                LispObject::from(self),
+                columns as ptrdiff_t,
+                &mut nchars,
+                &mut nbytes,
+            )
+        };
+        (nchars, width as usize)
+    }
+
     pub fn is_multibyte(self) -> bool {
         let s = unsafe { self.u.s };
         s.size_byte >= 0
@@ -251,7 +271,6 @@ impl LispStringRef {
         }
     }
 
-    #[allow(dead_code)]
     pub fn chars(&self) -> LispStringRefCharIterator {
         LispStringRefCharIterator(self.char_indices())
     }
@@ -785,31 +804,92 @@ pub unsafe extern "C" fn string_char(
     cp as c_int
 }
 
-/// Convert eight-bit chars in SRC (in multibyte form) to the
-/// corresponding byte and store in DST.  CHARS is the number of
-/// characters in SRC.  The value is the number of bytes stored in DST.
-/// Usually, the value is the same as CHARS, but is less than it if SRC
-/// contains a non-ASCII, non-eight-bit character.
-#[no_mangle]
-pub unsafe extern "C" fn str_to_unibyte(
-    src: *const c_uchar,
-    dst: *mut c_uchar,
-    chars: ptrdiff_t,
-) -> ptrdiff_t {
-    let mut srcslice = slice::from_raw_parts(src, chars as usize);
-    let dstslice = slice::from_raw_parts_mut(dst, chars as usize);
-    for i in 0..chars {
-        let (cp, cplen) = multibyte_char_at(srcslice);
-        srcslice = &srcslice[cplen..];
-        dstslice[i as usize] = if cp > MAX_5_BYTE_CHAR {
-            raw_byte_from_codepoint(cp)
-        } else if !is_ascii(cp) {
-            return i;
+/// Append the multibyte form of the raw byte BYTE to TO.  An ASCII byte
+/// is its own character; any other byte becomes an `eight-bit'
+/// character.
+fn push_raw_byte(to: &mut Vec<u8>, byte: u8) {
+    let mut buf = [0; MAX_MULTIBYTE_LENGTH];
+    let len = write_codepoint(&mut buf, raw_byte_codepoint(byte));
+    to.extend_from_slice(&buf[..len]);
+}
+
+/// Convert the unibyte text BYTES to multibyte, character by character:
+/// each byte is one character of the result, so every non-ASCII byte
+/// becomes an `eight-bit' character.  This is `string-to-multibyte'.
+pub fn unibyte_to_multibyte(bytes: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(bytes.len());
+    for &byte in bytes {
+        push_raw_byte(&mut result, byte);
+    }
+    result
+}
+
+/// Reinterpret the unibyte text BYTES as multibyte: byte sequences that
+/// form a valid multibyte character are kept as that character, and
+/// every other byte becomes an `eight-bit' character.  Return the text
+/// and its number of characters.  This is `string-as-multibyte'.
+pub fn unibyte_as_multibyte(bytes: &[u8]) -> (Vec<u8>, usize) {
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut chars = 0;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        // Sequences encoding raw bytes are only valid in text that is
+        // already multibyte.
+        match multibyte_length(&bytes[idx..], false) {
+            Some(n) => {
+                result.extend_from_slice(&bytes[idx..idx + n]);
+                idx += n;
+            }
+            None => {
+                push_raw_byte(&mut result, bytes[idx]);
+                idx += 1;
+            }
+        }
+        chars += 1;
+    }
+    (result, chars)
+}
+
+/// Reinterpret the multibyte text BYTES as unibyte: each `eight-bit'
+/// character becomes its byte, and every other character contributes
+/// the bytes of its multibyte form.  This is `string-as-unibyte'.
+pub fn multibyte_as_unibyte(bytes: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let head = bytes[idx];
+        let len = multibyte_length_by_head(head);
+        if len == 2 && head & 0xFE == 0xC0 && idx + 1 < bytes.len() {
+            result.push(0x80 | ((head & 1) << 6) | (bytes[idx + 1] & 0x3F));
+            idx += 2;
         } else {
-            cp as c_uchar
-        };
+            let end = cmp::min(idx + len, bytes.len());
+            result.extend_from_slice(&bytes[idx..end]);
+            idx = end;
+        }
     }
-    chars
+    result
+}
+
+/// Convert the multibyte text BYTES to unibyte, character by character:
+/// ASCII characters are kept and `eight-bit' characters become their
+/// byte.  If some other character is found, return its index as the
+/// error.  This is `string-to-unibyte'.
+pub fn multibyte_to_unibyte(bytes: &[u8]) -> Result<Vec<u8>, usize> {
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let (cp, len) = multibyte_char_at(rest);
+        if is_ascii(cp) {
+            result.push(cp as u8);
+        } else if char_byte8_p(cp) {
+            result.push(raw_byte_from_codepoint(cp));
+        } else {
+            return Err(result.len());
+        }
+        rest = &rest[len..];
+    }
+    Ok(result)
 }
 
 pub fn char_byte8_p(c: Codepoint) -> bool {
@@ -827,3 +907,60 @@ pub fn char_to_byte8(c: Codepoint) -> u8 {
 pub fn single_byte_charp(c: Codepoint) -> bool {
     c < 0x100
 }
+
+#[test]
+fn test_unibyte_to_multibyte() {
+    assert_eq!(unibyte_to_multibyte(b"abc"), b"abc".to_vec());
+    // Every non-ASCII byte is a raw byte, even in a valid UTF-8 sequence.
+    assert_eq!(
+        unibyte_to_multibyte(&[b'a', 0xC3, 0xA9]),
+        vec![b'a', 0xC1, 0x83, 0xC0, 0xA9]
+    );
+}
+
+#[test]
+fn test_unibyte_as_multibyte() {
+    assert_eq!(unibyte_as_multibyte(b"abc"), (b"abc".to_vec(), 3));
+    // A valid sequence is kept as the character it encodes.
+    assert_eq!(unibyte_as_multibyte(&[0xC3, 0xA9]), (vec![0xC3, 0xA9], 1));
+    // Stray bytes become raw bytes.
+    assert_eq!(
+        unibyte_as_multibyte(&[0xA9, b'a', 0xC3]),
+        (vec![0xC0, 0xA9, b'a', 0xC1, 0x83], 3)
+    );
+    // So do the bytes of a sequence encoding a raw byte.
+    assert_eq!(
+        unibyte_as_multibyte(&[0xC1, 0x83]),
+        (vec![0xC1, 0x81, 0xC0, 0x83], 2)
+    );
+}
+
+#[test]
+fn test_multibyte_as_unibyte() {
+    assert_eq!(multibyte_as_unibyte(b"abc"), b"abc".to_vec());
+    assert_eq!(
+        multibyte_as_unibyte(&[0xC1, 0x83, b'a', 0xC0, 0xA9]),
+        vec![0xC3, b'a', 0xA9]
+    );
+    // Other characters keep the bytes of their multibyte form.
+    assert_eq!(multibyte_as_unibyte(&[0xC3, 0xA9]), vec![0xC3, 0xA9]);
+}
+
+#[test]
+fn test_multibyte_to_unibyte() {
+    assert_eq!(multibyte_to_unibyte(b"abc"), Ok(b"abc".to_vec()));
+    assert_eq!(
+        multibyte_to_unibyte(&[b'a', 0xC1, 0x83, 0xC0, 0xA9]),
+        Ok(vec![b'a', 0xC3, 0xA9])
+    );
+    assert_eq!(multibyte_to_unibyte(&[b'a', b'b', 0xC3, 0xA9]), Err(2));
+}
+
+#[test]
+fn test_multibyte_round_trips() {
+    let bytes: Vec<u8> = (0..=255).collect();
+    let multibyte = unibyte_to_multibyte(&bytes);
+    assert_eq!(multibyte_to_unibyte(&multibyte), Ok(bytes.clone()));
+    assert_eq!(multibyte_as_unibyte(&multibyte), bytes.clone());
+    assert_eq!(multibyte_as_unibyte(&unibyte_as_multibyte(&bytes).0), bytes);
+}
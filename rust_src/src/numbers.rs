@@ -1,17 +1,23 @@
 //! Functions operating on numbers.
 
 use rand::{Rng, SeedableRng, StdRng};
-use std::sync::Mutex;
+use std::{f64, ffi::CStr, str, sync::Mutex};
+
+use libc::{c_char, c_int};
 
 use remacs_macros::lisp_fn;
 
 use crate::{
     lisp::defsubr,
     lisp::LispObject,
+    multibyte::LispStringRef,
+    remacs_sys::{float_to_string, FLOAT_TO_STRING_BUFSIZE},
     remacs_sys::{
         EmacsDouble, EmacsInt, EmacsUint, Lisp_Bits, Lisp_Type, EMACS_INT_MAX, INTMASK, USE_LSB_TAG,
     },
-    remacs_sys::{Qinteger_or_marker_p, Qintegerp, Qnumber_or_marker_p, Qwholenump},
+    remacs_sys::{Qargs_out_of_range, Qnil, Qoverflow_error},
+    remacs_sys::{Qinteger_or_marker_p, Qintegerp, Qnumber_or_marker_p, Qnumberp, Qwholenump},
+    string_alloc::make_unibyte_string,
 };
 
 lazy_static! {
@@ -303,4 +309,191 @@ pub fn random(limit: LispObject) -> LispObject {
     }
 }
 
+/// Return the value of the digit CHARACTER in BASE, if it is one.
+fn digit_to_number(character: u8, base: u32) -> Option<u32> {
+    (character as char)
+        .to_digit(36)
+        .filter(|&digit| digit < base)
+}
+
+/// Convert BYTES to a number, assuming base BASE.  Return a fixnum if
+/// BYTES has integer syntax and fits in a fixnum, else return the
+/// nearest float if BYTES has either floating point or integer syntax
+/// and BASE is 10, else return None.  If IGNORE_TRAILING, consider just
+/// the longest prefix of BYTES that has valid floating point syntax.
+/// Signal an overflow if BASE is not 10 and the number has integer
+/// syntax but does not fit.
+pub fn parse_number(bytes: &[u8], base: u32, ignore_trailing: bool) -> Option<LispObject> {
+    let at = |i: usize| bytes.get(i).cloned().unwrap_or(0);
+    let skip_digits = |mut i: usize| {
+        while at(i).is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+
+    // Negate the value ourselves.  This treats 0, NaNs, and infinity
+    // properly, and keeps the sign of -0.0.
+    let negative = at(0) == b'-';
+    let signed = negative || at(0) == b'+';
+    let start = signed as usize;
+    let mut cp = start;
+
+    let mut overflow = false;
+    let mut lead_int = false;
+    let mut n: u64 = 0;
+    while let Some(digit) = digit_to_number(at(cp), base) {
+        lead_int = true;
+        match n
+            .checked_mul(u64::from(base))
+            .and_then(|n| n.checked_add(u64::from(digit)))
+        {
+            Some(m) => n = m,
+            None => {
+                overflow = true;
+                n = n
+                    .wrapping_mul(u64::from(base))
+                    .wrapping_add(u64::from(digit));
+            }
+        }
+        cp += 1;
+    }
+    let int_end = cp;
+
+    let dot = at(cp) == b'.';
+    if dot {
+        cp += 1;
+    }
+
+    let mut trail_int = false;
+    let mut exponent = false;
+    let mut special = None;
+    if base == 10 {
+        if at(cp).is_ascii_digit() {
+            trail_int = true;
+            cp = skip_digits(cp);
+        }
+        if at(cp) == b'e' || at(cp) == b'E' {
+            let mut ecp = cp + 1;
+            let plus = at(ecp) == b'+';
+            if plus || at(ecp) == b'-' {
+                ecp += 1;
+            }
+            if at(ecp).is_ascii_digit() {
+                exponent = true;
+                cp = skip_digits(ecp);
+            } else if plus && bytes[ecp..].starts_with(b"INF") {
+                exponent = true;
+                cp = ecp + 3;
+                special = Some(f64::INFINITY);
+            } else if plus && bytes[ecp..].starts_with(b"NaN") {
+                exponent = true;
+                cp = ecp + 3;
+                // NaN is a "positive" NaN on all known Emacs hosts.
+                special = Some(f64::NAN);
+            }
+        }
+    }
+
+    let float_syntax = (dot && trail_int) || (lead_int && !dot && !trail_int && exponent);
+
+    // Return None if the number uses invalid syntax.  If IGNORE_TRAILING,
+    // accept any prefix that matches.  Otherwise, the entire string must
+    // match.
+    let valid = if ignore_trailing {
+        lead_int || float_syntax
+    } else {
+        cp == bytes.len() && ((lead_int && !trail_int && !exponent) || float_syntax)
+    };
+    if !valid {
+        return None;
+    }
+
+    // If the number uses integer and not float syntax, and is in range,
+    // use its value, preferably as a fixnum.
+    let mut value = special;
+    if lead_int && !float_syntax {
+        if overflow {
+            // Unfortunately there's no simple and accurate way to convert
+            // non-base-10 numbers that are out of range.
+            if base != 10 {
+                xsignal!(Qoverflow_error, make_unibyte_string(bytes));
+            }
+        } else if n <= MOST_POSITIVE_FIXNUM as u64 + negative as u64 {
+            let n = n as EmacsInt;
+            return Some(LispObject::from(if negative { -n } else { n }));
+        } else {
+            value = Some(n as f64);
+        }
+    }
+
+    // Either the number uses float syntax, or it does not fit into a
+    // fixnum.  Convert it from the text, unless the value is already
+    // known because it is an infinity, a NaN, or it fits in 64 bits.
+    let value = value.unwrap_or_else(|| {
+        let end = if float_syntax { cp } else { int_end };
+        str::from_utf8(&bytes[start..end])
+            .ok()
+            .and_then(|text| text.parse::<f64>().ok())
+            .unwrap_or(0.0)
+    });
+
+    let value = if negative { -value } else { value };
+    Some(LispObject::from_float(value))
+}
+
+/// C interface to `parse_number', which returns nil for invalid syntax.
+#[no_mangle]
+pub unsafe extern "C" fn string_to_number(
+    string: *const c_char,
+    base: c_int,
+    ignore_trailing: bool,
+) -> LispObject {
+    let bytes = CStr::from_ptr(string).to_bytes();
+    parse_number(bytes, base as u32, ignore_trailing).unwrap_or(Qnil)
+}
+
+/// Return the decimal representation of NUMBER as a string.
+/// Uses a minus sign if negative.
+/// NUMBER may be an integer or a floating point number.
+#[lisp_fn]
+pub fn number_to_string(number: LispObject) -> LispObject {
+    if let Some(n) = number.as_fixnum() {
+        n.to_string().as_str().into()
+    } else if let Some(f) = number.as_float() {
+        let mut buffer = [0u8; FLOAT_TO_STRING_BUFSIZE as usize];
+        let len = unsafe { float_to_string(buffer.as_mut_ptr() as *mut c_char, f) };
+        make_unibyte_string(&buffer[..len as usize])
+    } else {
+        wrong_type!(Qnumberp, number)
+    }
+}
+
+/// Parse STRING as a decimal number and return the number.
+/// Ignore leading spaces and tabs, and all trailing chars.  Return 0 if
+/// STRING cannot be parsed as an integer or floating point number.
+///
+/// If BASE, interpret STRING as a number in that base.  If BASE isn't
+/// present, base 10 is used.  BASE must be between 2 and 36 (inclusive).
+/// If the base used is not 10, STRING is always parsed as an integer.
+#[lisp_fn(min = "1", name = "string-to-number", c_name = "string_to_number")]
+pub fn string_to_number_lisp(string: LispStringRef, base: LispObject) -> LispObject {
+    let base = if base.is_nil() {
+        10
+    } else {
+        match base.as_fixnum_or_error() {
+            b @ 2..=36 => b as u32,
+            _ => xsignal!(Qargs_out_of_range, base),
+        }
+    };
+
+    let bytes = string.as_slice();
+    let start = bytes
+        .iter()
+        .position(|&b| b != b' ' && b != b'\t')
+        .unwrap_or_else(|| bytes.len());
+
+    parse_number(&bytes[start..], base, true).unwrap_or_else(|| LispObject::from(0))
+}
+
 include!(concat!(env!("OUT_DIR"), "/numbers_exports.rs"));
@@ -1,19 +1,33 @@
 //! obarray code
-use libc;
+//!
+//! An obarray is a vector of buckets, each of which is 0 or the first of
+//! a chain of symbols linked through their `next`.  A symbol is in the
+//! bucket that the hash code of its name picks.  The symbols of the
+//! initial obarray are also indexed by name, so that interning them
+//! doesn't compare names along their bucket.
+use std::collections::{BTreeMap, HashMap};
+use std::slice;
+use std::sync::Mutex;
+
+use libc::{self, c_char, ptrdiff_t};
 
 use remacs_macros::lisp_fn;
 
 use crate::{
+    hashtable::hash_string,
     lisp::defsubr,
     lisp::LispObject,
-    multibyte::LispStringRef,
+    lists::list,
+    multibyte::{unibyte_to_multibyte, LispStringRef},
     remacs_sys::{
-        fatal_error_in_progress, globals, initial_obarray, initialized, intern_sym,
-        make_pure_c_string, make_unibyte_string, oblookup,
+        fatal_error_in_progress, gc_in_progress, globals, initial_obarray, initialized,
+        make_pure_c_string, make_unibyte_string,
     },
+    remacs_sys::{symbol_interned, symbol_redirect, symbol_trapped_write, EmacsInt, EmacsUint},
     remacs_sys::{Fmake_symbol, Fpurecopy},
-    remacs_sys::{Qnil, Qvectorp},
+    remacs_sys::{Qnil, Qt, Qvectorp},
     symbols::LispSymbolRef,
+    vectors::LispVectorRef,
 };
 
 /// A lisp object containing an `obarray`.
@@ -190,6 +204,83 @@ pub unsafe extern "C" fn intern_c_string_1(
     }
 }
 
+/// Return the index of the bucket of OBARRAY for the name whose bytes
+/// are NAME.
+fn bucket_of(obarray: LispVectorRef, name: &[u8]) -> usize {
+    let hash = unsafe { hash_string(name.as_ptr() as *const c_char, name.len() as ptrdiff_t) };
+    (hash % obarray.len() as EmacsUint) as usize
+}
+
+/// Return the symbol in OBARRAY whose name matches the string of SIZE
+/// characters (SIZE_BYTE bytes) at PTR.  If there is no such symbol,
+/// return the integer bucket number of where the symbol would be if it
+/// were present.
+#[no_mangle]
+pub unsafe extern "C" fn oblookup(
+    obarray: LispObject,
+    ptr: *const c_char,
+    size: ptrdiff_t,
+    size_byte: ptrdiff_t,
+) -> LispObject {
+    let obarray = check_obarray(obarray);
+    let name = slice::from_raw_parts(ptr as *const u8, size_byte as usize);
+    if let Some(found) = indexed_lookup(obarray, name, size) {
+        return found;
+    }
+
+    // This is sometimes needed in the middle of GC, when the size of
+    // the obarray has its mark bit set, which `len` ignores.
+    let vector = obarray.as_vector_or_error();
+    let index = bucket_of(vector, name);
+    let bucket = vector.get(index);
+    if let Some(first) = bucket.as_symbol() {
+        let found = first.iter().find(|sym| {
+            let sym_name = sym.symbol_name().force_string();
+            sym_name.len_bytes() == size_byte
+                && sym_name.len_chars() == size
+                && sym_name.as_slice() == name
+        });
+        if let Some(sym) = found {
+            return sym.into();
+        }
+    } else if !bucket.eq(LispObject::from_fixnum(0)) {
+        // Like CADR error message.
+        error!("Bad data in guts of obarray");
+    }
+    LispObject::from(index as EmacsInt)
+}
+
+/// Intern symbol SYM in OBARRAY using bucket INDEX.
+#[no_mangle]
+pub extern "C" fn intern_sym(
+    sym: LispObject,
+    obarray: LispObject,
+    index: LispObject,
+) -> LispObject {
+    let symbol = sym.as_symbol_or_error();
+    let is_initial = obarray.eq(unsafe { initial_obarray });
+    symbol.set_interned(if is_initial {
+        symbol_interned::SYMBOL_INTERNED_IN_INITIAL_OBARRAY
+    } else {
+        symbol_interned::SYMBOL_INTERNED
+    });
+
+    let name = symbol.symbol_name().force_string();
+    if is_initial && name.as_slice().first() == Some(&b':') {
+        // Keywords evaluate to themselves.
+        symbol.set_trapped_write(symbol_trapped_write::SYMBOL_NOWRITE);
+        symbol.set_redirect(symbol_redirect::SYMBOL_PLAINVAL);
+        symbol.set_plain_value(sym);
+    }
+
+    let mut vector = obarray.as_vector_or_error();
+    let index = index.force_fixnum() as usize;
+    symbol.set_next(vector.get(index).as_symbol());
+    vector.set(index, sym);
+    symbol_index_insert(sym, obarray);
+    sym
+}
+
 /// Intern a symbol with name STRING in OBARRAY using bucket INDEX.
 #[no_mangle]
 pub extern "C" fn intern_driver(
@@ -217,6 +308,43 @@ pub fn intern_soft(name: LispObject, obarray: Option<LispObarrayRef>) -> LispObj
     }
 }
 
+/// Delete the symbol named NAME, if any, from OBARRAY.
+/// The value is t if a symbol was found and deleted, nil otherwise.
+/// NAME may be a string or a symbol.  If it is a symbol, that symbol
+/// is deleted, if it belongs to OBARRAY--no other symbol is deleted.
+/// OBARRAY, if nil, defaults to the value of the variable `obarray'.
+/// usage: (unintern NAME OBARRAY)
+#[lisp_fn(min = "1")]
+pub fn unintern(name: LispObject, obarray: Option<LispObarrayRef>) -> bool {
+    let obarray = obarray.unwrap_or_else(LispObarrayRef::global);
+    let tem = obarray.lookup(name);
+    // If NAME is a symbol, don't delete anything but that symbol itself.
+    if tem.is_integer() || (name.is_symbol() && !name.eq(tem)) {
+        return false;
+    }
+
+    // There are plenty of other symbols which will screw up the Emacs
+    // session if we unintern them, as well as even more ways to use
+    // `setq' or `fset' or whatnot to make the Emacs session unusable.
+    // Let's not go down this silly road.
+    let symbol = tem.force_symbol();
+    symbol.set_interned(symbol_interned::SYMBOL_UNINTERNED);
+    symbol_index_remove(tem, LispObject::from(&obarray));
+
+    let mut vector = LispObject::from(&obarray).as_vector_or_error();
+    let index = bucket_of(vector, symbol.symbol_name().force_string().as_slice());
+    let first = vector.get(index).force_symbol();
+    if first == symbol {
+        let next = symbol
+            .get_next()
+            .map_or(LispObject::from_fixnum(0), LispObject::from);
+        vector.set(index, next);
+    } else if let Some(previous) = first.iter().find(|sym| sym.get_next() == Some(symbol)) {
+        previous.set_next(symbol.get_next());
+    }
+    true
+}
+
 /// Return the canonical symbol whose name is STRING.
 /// If there is none, one is created by this function and returned.
 /// A second optional argument specifies the obarray to use;
@@ -241,4 +369,147 @@ pub fn mapatoms(function: LispObject, obarray: Option<LispObarrayRef>) {
     map_obarray(obarray.into(), mapatoms_1, function);
 }
 
+lazy_static! {
+    /// The symbols interned in the initial obarray, by name, so that the
+    /// ones whose names start with a given prefix are found without
+    /// scanning the whole obarray.  The index is built the first time
+    /// it is needed once Emacs has been dumped, and then kept up to date
+    /// as symbols are interned and uninterned.
+    static ref SYMBOL_INDEX: Mutex<Option<BTreeMap<Vec<u8>, LispObject>>> = Mutex::new(None);
+
+    /// The symbols interned in the initial obarray, by the bytes of their
+    /// names, for `oblookup'.  Names of the same bytes may differ in their
+    /// number of characters, so each holds a list of symbols.  The index
+    /// is built and kept up to date like `SYMBOL_INDEX`.
+    static ref NAME_INDEX: Mutex<Option<HashMap<Vec<u8>, Vec<LispObject>>>> = Mutex::new(None);
+}
+
+fn symbol_name_bytes(sym: LispObject) -> Vec<u8> {
+    sym.as_symbol_or_error()
+        .symbol_name()
+        .as_string_or_error()
+        .as_slice()
+        .to_vec()
+}
+
+/// Index the symbols of OBARRAY by the bytes of their names.
+fn build_name_index(obarray: LispObject) -> HashMap<Vec<u8>, Vec<LispObject>> {
+    let mut index: HashMap<_, Vec<_>> = HashMap::new();
+    for bucket in obarray.as_vector_or_error().iter() {
+        if let Some(first) = bucket.as_symbol() {
+            for sym in first.iter() {
+                let sym = LispObject::from(sym);
+                index.entry(symbol_name_bytes(sym)).or_default().push(sym);
+            }
+        }
+    }
+    index
+}
+
+/// Look up the name of NCHARS characters whose bytes are NAME in
+/// OBARRAY like `oblookup`, or return `None` if OBARRAY isn't indexed.
+/// Only the initial obarray is indexed, once Emacs has been dumped, and
+/// the index isn't used during garbage collection.
+fn indexed_lookup(obarray: LispObject, name: &[u8], nchars: ptrdiff_t) -> Option<LispObject> {
+    if !unsafe { initialized }
+        || unsafe { gc_in_progress }
+        || !obarray.eq(unsafe { initial_obarray })
+    {
+        return None;
+    }
+
+    let mut guard = NAME_INDEX.lock().unwrap();
+    let index = guard.get_or_insert_with(|| build_name_index(obarray));
+    let found = index.get(name).and_then(|symbols| {
+        symbols.iter().cloned().find(|&sym| {
+            let sym_name = sym.force_symbol().symbol_name().force_string();
+            sym_name.len_chars() == nchars
+        })
+    });
+    Some(found.unwrap_or_else(|| {
+        LispObject::from(bucket_of(obarray.as_vector_or_error(), name) as EmacsInt)
+    }))
+}
+
+/// The multibyte representation of STRING, so that names compare the
+/// same way whether they are unibyte or multibyte, and a name starts
+/// with a prefix exactly when its representation does.
+fn multibyte_representation(string: LispStringRef) -> Vec<u8> {
+    if string.is_multibyte() {
+        string.as_slice().to_vec()
+    } else {
+        unibyte_to_multibyte(string.as_slice())
+    }
+}
+
+fn symbol_index_key(sym: LispObject) -> Vec<u8> {
+    multibyte_representation(sym.as_symbol_or_error().symbol_name().as_string_or_error())
+}
+
+/// Index the symbols of OBARRAY by name.
+fn build_symbol_index(obarray: LispObject) -> BTreeMap<Vec<u8>, LispObject> {
+    let mut index = BTreeMap::new();
+    for bucket in obarray.as_vector_or_error().iter() {
+        if let Some(first) = bucket.as_symbol() {
+            for sym in first.iter() {
+                let sym = LispObject::from(sym);
+                index.insert(symbol_index_key(sym), sym);
+            }
+        }
+    }
+    index
+}
+
+/// Record that SYM has been interned in OBARRAY.
+fn symbol_index_insert(sym: LispObject, obarray: LispObject) {
+    if obarray.eq(unsafe { initial_obarray }) {
+        if let Some(index) = SYMBOL_INDEX.lock().unwrap().as_mut() {
+            index.insert(symbol_index_key(sym), sym);
+        }
+        if let Some(index) = NAME_INDEX.lock().unwrap().as_mut() {
+            index.entry(symbol_name_bytes(sym)).or_default().push(sym);
+        }
+    }
+}
+
+/// Record that SYM has been uninterned from OBARRAY.
+fn symbol_index_remove(sym: LispObject, obarray: LispObject) {
+    if obarray.eq(unsafe { initial_obarray }) {
+        if let Some(index) = SYMBOL_INDEX.lock().unwrap().as_mut() {
+            index.remove(&symbol_index_key(sym));
+        }
+        if let Some(index) = NAME_INDEX.lock().unwrap().as_mut() {
+            let name = symbol_name_bytes(sym);
+            if let Some(symbols) = index.get_mut(&name) {
+                symbols.retain(|&s| !s.eq(sym));
+                if symbols.is_empty() {
+                    index.remove(&name);
+                }
+            }
+        }
+    }
+}
+
+/// Return the list of the symbols of OBARRAY whose names start with
+/// PREFIX, in the order of their names, or t if OBARRAY isn't indexed.
+/// Only the initial obarray is indexed.
+#[no_mangle]
+pub extern "C" fn obarray_prefix_matches(obarray: LispObject, prefix: LispObject) -> LispObject {
+    if !unsafe { initialized } || !obarray.eq(unsafe { initial_obarray }) {
+        return Qt;
+    }
+    let prefix = multibyte_representation(prefix.as_string_or_error());
+
+    let matches: Vec<LispObject> = {
+        let mut guard = SYMBOL_INDEX.lock().unwrap();
+        let index = guard.get_or_insert_with(|| build_symbol_index(obarray));
+        index
+            .range(prefix.clone()..)
+            .take_while(|(name, _)| name.starts_with(&prefix))
+            .map(|(_, &sym)| sym)
+            .collect()
+    };
+    list(&matches)
+}
+
 include!(concat!(env!("OUT_DIR"), "/obarray_exports.rs"));
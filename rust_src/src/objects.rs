@@ -3,8 +3,9 @@
 use remacs_macros::lisp_fn;
 
 use crate::{
+    fns::internal_equal,
     lisp::{defsubr, LispObject},
-    remacs_sys::{equal_kind, internal_equal, Qnil},
+    remacs_sys::{equal_kind, Qnil},
 };
 
 /// Return t if OBJECT is nil, and return nil otherwise.
@@ -43,7 +44,7 @@ pub fn equal(o1: LispObject, o2: LispObject) -> bool {
 /// of strings.  (`equal' ignores text properties.)
 #[lisp_fn]
 pub fn equal_including_properties(o1: LispObject, o2: LispObject) -> bool {
-    unsafe { internal_equal(o1, o2, equal_kind::EQUAL_INCLUDING_PROPERTIES, 0, Qnil) }
+    internal_equal(o1, o2, equal_kind::EQUAL_INCLUDING_PROPERTIES, 0, Qnil)
 }
 
 /// Return the argument unchanged.
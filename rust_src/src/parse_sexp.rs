@@ -0,0 +1,890 @@
+//! Parsing balanced expressions forward, as `parse-partial-sexp' does.
+//!
+//! The state of a parse is a `ParseState`, which converts to and from
+//! the list that `parse-partial-sexp' returns and takes as OLDSTATE.
+//! Parsing from the start of the accessible portion of a buffer, as
+//! `syntax-ppss' does, can go through `syntax-ppss-cached', which keeps
+//! the states it reached along the way until the buffer text changes.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::{validate_region, LispBufferRef},
+    lisp::defsubr,
+    lisp::LispObject,
+    lists::{list, LispConsCircularChecks, LispConsEndChecks},
+    multibyte::Codepoint,
+    remacs_sys::{globals, maybe_quit, set_point, syntaxcode, EmacsInt, Lisp_Buffer},
+    remacs_sys::{Qnil, Qsyntax_table, Qt},
+    syntax::{current_syntax_table, BufferSyntax, QuitCounter, SyntaxCode},
+    threads::ThreadState,
+};
+
+/// The comment style of comments started by a comment fence.
+pub(crate) const ST_COMMENT_STYLE: i32 = 256 + 1;
+
+/// The syntax of no character, for positions that can't start a
+/// two-character construct.
+const NO_SYNTAX: SyntaxCode = SyntaxCode(syntaxcode::Smax as EmacsInt);
+
+fn is_class(code: SyntaxCode, class: syntaxcode) -> bool {
+    code.class() == class as EmacsInt
+}
+
+/// What ends the string a parse is in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StringEnd {
+    /// The same character as the one that started the string.
+    Char(Codepoint),
+    /// A character with string fence syntax.
+    Fence,
+}
+
+/// The state of a parse at the position where it stopped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseState {
+    /// The depth in parentheses.
+    pub depth: EmacsInt,
+    /// The string the parse is in, if any.
+    pub instring: Option<StringEnd>,
+    /// 0 outside comments, -1 inside a comment that doesn't nest, and
+    /// the nesting of the comment otherwise.
+    pub incomment: EmacsInt,
+    /// The style of the comment, or `ST_COMMENT_STYLE` for comments
+    /// started by a comment fence.
+    pub comstyle: i32,
+    /// Whether the parse stopped right after an escape character.
+    pub quoted: bool,
+    /// The smallest depth reached.
+    pub mindepth: EmacsInt,
+    /// The start of the last complete expression at the current level.
+    pub thislevelstart: Option<isize>,
+    /// The start of the innermost list containing the position.
+    pub prevlevelstart: Option<isize>,
+    /// The position where the parse stopped.
+    pub location: isize,
+    /// The start of the comment or string the parse is in.
+    pub comstr_start: Option<isize>,
+    /// The starts of the open lists, outermost first.
+    pub levelstarts: Vec<isize>,
+    /// The syntax of the character before `location`, if it may be the
+    /// first character of a two-character construct.
+    pub prev_syntax: Option<SyntaxCode>,
+}
+
+impl ParseState {
+    /// The state at the start of a defun, where nothing is open.
+    pub fn new(location: isize) -> Self {
+        Self {
+            depth: 0,
+            instring: None,
+            incomment: 0,
+            comstyle: 0,
+            quoted: false,
+            mindepth: 0,
+            thislevelstart: None,
+            prevlevelstart: None,
+            location,
+            comstr_start: None,
+            levelstarts: Vec::new(),
+            prev_syntax: None,
+        }
+    }
+
+    /// The state described by STATE, a list like those returned by
+    /// `parse-partial-sexp', for a parse that continues at LOCATION.  A
+    /// nil STATE is the state at the start of a defun.
+    pub fn from_lisp(state: LispObject, location: isize) -> Self {
+        let mut parsed = Self::new(location);
+        if state.is_nil() {
+            return parsed;
+        }
+
+        let elements: Vec<LispObject> = state
+            .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe)
+            .take(11)
+            .collect();
+        let nth = |n: usize| elements.get(n).cloned().unwrap_or(Qnil);
+        let position = |object: LispObject| object.as_fixnum().map(|pos| pos as isize);
+
+        parsed.depth = nth(0).as_fixnum().unwrap_or(0);
+        parsed.instring = match nth(3) {
+            tem if tem.is_nil() => None,
+            tem if tem.is_character() => {
+                Some(StringEnd::Char(tem.as_fixnum_or_error() as Codepoint))
+            }
+            _ => Some(StringEnd::Fence),
+        };
+        parsed.incomment = match nth(4) {
+            tem if tem.is_nil() => 0,
+            tem => tem.as_fixnum().unwrap_or(-1),
+        };
+        parsed.quoted = nth(5).is_not_nil();
+        parsed.comstyle = match nth(7) {
+            tem if tem.is_nil() => 0,
+            tem => tem
+                .as_fixnum()
+                .filter(|&style| 0 <= style && style <= EmacsInt::from(ST_COMMENT_STYLE))
+                .map_or(ST_COMMENT_STYLE, |style| style as i32),
+        };
+        parsed.comstr_start = position(nth(8));
+        // Elements that aren't positions still open a level.
+        parsed.levelstarts = nth(9)
+            .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe)
+            .map(|start| position(start).unwrap_or(-1))
+            .collect();
+        parsed.prev_syntax = nth(10).as_fixnum().map(SyntaxCode);
+        parsed
+    }
+}
+
+impl From<&ParseState> for LispObject {
+    fn from(state: &ParseState) -> Self {
+        let position = |pos: Option<isize>| pos.map_or(Qnil, LispObject::from);
+        let levelstarts: Vec<LispObject> = state
+            .levelstarts
+            .iter()
+            .map(|&start| LispObject::from(start))
+            .collect();
+
+        list(&[
+            state.depth.into(),
+            position(state.prevlevelstart),
+            position(state.thislevelstart),
+            match state.instring {
+                None => Qnil,
+                Some(StringEnd::Fence) => Qt,
+                Some(StringEnd::Char(c)) => EmacsInt::from(c).into(),
+            },
+            match state.incomment {
+                0 => Qnil,
+                nesting if nesting < 0 => Qt,
+                nesting => nesting.into(),
+            },
+            state.quoted.into(),
+            state.mindepth.into(),
+            match state.comstyle {
+                0 => Qnil,
+                ST_COMMENT_STYLE => Qsyntax_table,
+                style => EmacsInt::from(style).into(),
+            },
+            if state.incomment != 0 || state.instring.is_some() {
+                position(state.comstr_start)
+            } else {
+                Qnil
+            },
+            list(&levelstarts),
+            state.prev_syntax.map_or(Qnil, |syntax| syntax.0.into()),
+        ])
+    }
+}
+
+/// Where `parse-partial-sexp' stops, besides TO and the target depth.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CommentStop {
+    /// Don't stop at comments.
+    Never,
+    /// Stop at the start of a comment.
+    Start,
+    /// Stop at the start or end of a comment or string.
+    Boundary,
+}
+
+/// The start of a list the parse is in, and of the last complete
+/// expression in it, or -1.
+#[derive(Clone, Copy)]
+struct Level {
+    last: isize,
+    prev: isize,
+}
+
+const NEW_LEVEL: Level = Level { last: -1, prev: -1 };
+
+/// The places the parser can resume at.
+enum Step {
+    /// Between expressions.
+    Top,
+    /// After an escape character.
+    Quoted,
+    /// In a symbol.
+    Symbol,
+    /// At the start of a comment.
+    AtComment,
+    /// In a comment.
+    InComment,
+    /// In a string.
+    InString,
+    /// After an escape character in a string.
+    QuotedInString,
+}
+
+/// How a parse ended.
+enum Finish {
+    Done,
+    /// Just before the start of an expression.
+    Stop,
+    /// Right after an escape character.
+    EndQuoted,
+}
+
+/// The outcome of skipping a comment with `Parser::forward_comment`.
+pub(crate) enum CommentEnd {
+    /// The last character of the comment was found.
+    Found,
+    /// The comment goes on past the end of the parse, with this
+    /// nesting and syntax of its last character.
+    Unfinished(EmacsInt, SyntaxCode),
+}
+
+/// Scans the current buffer from `from`, going no further than `end`.
+pub(crate) struct Parser {
+    pub(crate) syntax: BufferSyntax,
+    pub(crate) quit: QuitCounter,
+    end: isize,
+    pub(crate) from: isize,
+    /// The character before FROM and its syntax, and the syntax of the
+    /// one before that.
+    prev_from: isize,
+    prev_from_syntax: SyntaxCode,
+    prev_prev_from_syntax: SyntaxCode,
+}
+
+impl Parser {
+    /// A parser at FROM, with nothing before it that may start a
+    /// two-character construct.
+    pub(crate) fn new(syntax: BufferSyntax, from: isize, end: isize) -> Self {
+        Self {
+            syntax,
+            quit: QuitCounter(0),
+            end,
+            from,
+            prev_from: from,
+            prev_from_syntax: NO_SYNTAX,
+            prev_prev_from_syntax: NO_SYNTAX,
+        }
+    }
+
+    pub(crate) fn code_at(&mut self, pos: isize) -> SyntaxCode {
+        let c = self.syntax.char_at(pos);
+        self.syntax.code_at(pos, c)
+    }
+
+    /// Move past the character at FROM.
+    fn advance(&mut self) {
+        self.prev_from = self.from;
+        self.prev_prev_from_syntax = self.prev_from_syntax;
+        self.prev_from_syntax = self.code_at(self.from);
+        self.from += 1;
+    }
+
+    /// If the character at FROM is the second of a two-character comment
+    /// starter, record the comment in STATE and return true.
+    fn at_2char_comment_start(&mut self, state: &mut ParseState) -> bool {
+        if !self.prev_from_syntax.comstart_first() {
+            return false;
+        }
+        let syntax = self.code_at(self.from);
+        if !syntax.comstart_second() {
+            return false;
+        }
+        // Only a comment ender of the same style ends the comment.
+        state.comstyle = syntax.comment_style(self.prev_from_syntax);
+        let nested = self.prev_from_syntax.comment_nested() || syntax.comment_nested();
+        state.incomment = if nested { 1 } else { -1 };
+        state.comstr_start = Some(self.prev_from);
+        true
+    }
+
+    /// Whether the character at POS is escaped.
+    pub(crate) fn char_quoted(&mut self, mut pos: isize) -> bool {
+        let beg = self.syntax.buffer.begv;
+        let mut quoted = false;
+        while pos > beg {
+            pos -= 1;
+            let code = self.code_at(pos);
+            if !is_class(code, syntaxcode::Scharquote) && !is_class(code, syntaxcode::Sescape) {
+                break;
+            }
+            quoted = !quoted;
+        }
+        quoted
+    }
+
+    /// Skip the comment of STYLE the parse is in, up to `end`.  NESTING
+    /// is the nesting of the comment, or 0 or less if it doesn't nest.
+    /// PREV_SYNTAX is the syntax of the character before FROM, to find a
+    /// comment ender that FROM is in the middle of, or 0.  FROM is left
+    /// on the last character of the comment, or at `end`.
+    pub(crate) fn forward_comment(
+        &mut self,
+        nesting: EmacsInt,
+        style: i32,
+        prev_syntax: SyntaxCode,
+    ) -> CommentEnd {
+        let mut nesting = if nesting <= 0 { -1 } else { nesting };
+        let mut syntax = prev_syntax;
+        let mut code = syntax.class();
+        // Start in the middle of the loop, to find a two-character
+        // comment ender that FROM is in the middle of.
+        let mut in_middle = syntax.0 != 0 && self.from < self.end;
+
+        loop {
+            if !in_middle {
+                if self.from == self.end {
+                    let last_syntax = if code == syntaxcode::Sescape as EmacsInt
+                        || code == syntaxcode::Scharquote as EmacsInt
+                        || syntax.comend_first()
+                        || (nesting > 0 && syntax.comstart_first())
+                    {
+                        syntax
+                    } else {
+                        NO_SYNTAX
+                    };
+                    return CommentEnd::Unfinished(nesting, last_syntax);
+                }
+                syntax = self.code_at(self.from);
+                code = syntax.class();
+                if code == syntaxcode::Sendcomment as EmacsInt
+                    && syntax.comment_style(SyntaxCode(0)) == style
+                    && if syntax.comment_nested() {
+                        nesting > 0 && {
+                            nesting -= 1;
+                            nesting == 0
+                        }
+                    } else {
+                        nesting < 0
+                    }
+                    && !(unsafe { globals.Vcomment_end_can_be_escaped }
+                        && self.char_quoted(self.from))
+                {
+                    break;
+                }
+                if code == syntaxcode::Scomment_fence as EmacsInt && style == ST_COMMENT_STYLE {
+                    break;
+                }
+                if nesting > 0
+                    && code == syntaxcode::Scomment as EmacsInt
+                    && syntax.comment_nested()
+                    && syntax.comment_style(SyntaxCode(0)) == style
+                {
+                    // A nested comment of the same style.
+                    nesting += 1;
+                }
+                self.from += 1;
+            }
+            in_middle = false;
+
+            if self.from < self.end && syntax.comend_first() {
+                let other = self.code_at(self.from);
+                if other.comend_second()
+                    && syntax.comment_style(other) == style
+                    && if syntax.comment_nested() || other.comment_nested() {
+                        nesting > 0
+                    } else {
+                        nesting < 0
+                    }
+                {
+                    // So that the last character of "|#" doesn't count
+                    // as the start of another construct.
+                    syntax = NO_SYNTAX;
+                    nesting -= 1;
+                    if nesting <= 0 {
+                        break;
+                    }
+                    self.from += 1;
+                }
+            }
+            if nesting > 0 && self.from < self.end && syntax.comstart_first() {
+                let other = self.code_at(self.from);
+                if other.comment_style(syntax) == style
+                    && other.comstart_second()
+                    && (syntax.comment_nested() || other.comment_nested())
+                {
+                    // A nested comment of the same style.
+                    syntax = NO_SYNTAX;
+                    self.from += 1;
+                    nesting += 1;
+                }
+            }
+
+            self.quit.tick();
+        }
+        CommentEnd::Found
+    }
+}
+
+/// Parse forward from the location of STATE to END, and leave the state
+/// of the parse there in STATE.  The parse stops early if the depth in
+/// parentheses becomes TARGETDEPTH, at the start of an expression if
+/// STOPBEFORE, and at comments and strings as COMMENTSTOP says.
+pub fn scan_sexps_forward(
+    state: &mut ParseState,
+    end: isize,
+    targetdepth: Option<EmacsInt>,
+    stopbefore: bool,
+    commentstop: CommentStop,
+) {
+    let boundary_stop = commentstop == CommentStop::Boundary;
+    let from = state.location;
+    let syntax = BufferSyntax::new();
+    let begv = syntax.buffer.begv;
+    let mut parser = Parser {
+        prev_from: if from == begv { from } else { from - 1 },
+        prev_from_syntax: state.prev_syntax.unwrap_or(NO_SYNTAX),
+        ..Parser::new(syntax, from, end)
+    };
+
+    unsafe { maybe_quit() };
+
+    let mut depth = state.depth;
+    let mut mindepth = depth;
+    let start_quoted = state.quoted;
+    state.quoted = false;
+
+    let mut levels = vec![NEW_LEVEL];
+    for &start in &state.levelstarts {
+        if start >= 0 {
+            levels.last_mut().unwrap().last = start;
+        }
+        levels.push(NEW_LEVEL);
+    }
+
+    let mut step = if state.incomment != 0 {
+        Step::InComment
+    } else if state.instring.is_some() {
+        if start_quoted {
+            Step::QuotedInString
+        } else {
+            Step::InString
+        }
+    } else if start_quoted {
+        Step::Quoted
+    } else if parser.from < end && parser.at_2char_comment_start(state) {
+        parser.advance();
+        // The syntax has been used up by the comment starter.
+        parser.prev_from_syntax = NO_SYNTAX;
+        Step::AtComment
+    } else {
+        Step::Top
+    };
+
+    let finish = loop {
+        match step {
+            Step::Top => {
+                if parser.from >= end {
+                    break Finish::Done;
+                }
+                parser.quit.tick();
+                parser.advance();
+
+                if parser.from < end && parser.at_2char_comment_start(state) {
+                    parser.advance();
+                    parser.prev_from_syntax = NO_SYNTAX;
+                    step = Step::AtComment;
+                    continue;
+                }
+                let syntax = parser.prev_from_syntax;
+                if syntax.prefix() {
+                    continue;
+                }
+                let code = syntax.class();
+                if code == syntaxcode::Sescape as EmacsInt
+                    || code == syntaxcode::Scharquote as EmacsInt
+                {
+                    if stopbefore {
+                        break Finish::Stop;
+                    }
+                    levels.last_mut().unwrap().last = parser.prev_from;
+                    step = Step::Quoted;
+                } else if code == syntaxcode::Sword as EmacsInt
+                    || code == syntaxcode::Ssymbol as EmacsInt
+                {
+                    if stopbefore {
+                        break Finish::Stop;
+                    }
+                    levels.last_mut().unwrap().last = parser.prev_from;
+                    step = Step::Symbol;
+                } else if code == syntaxcode::Scomment_fence as EmacsInt {
+                    state.comstyle = ST_COMMENT_STYLE;
+                    state.incomment = -1;
+                    state.comstr_start = Some(parser.prev_from);
+                    step = Step::AtComment;
+                } else if code == syntaxcode::Scomment as EmacsInt {
+                    state.comstyle = syntax.comment_style(SyntaxCode(0));
+                    state.incomment = if syntax.comment_nested() { 1 } else { -1 };
+                    state.comstr_start = Some(parser.prev_from);
+                    step = Step::AtComment;
+                } else if code == syntaxcode::Sopen as EmacsInt {
+                    if stopbefore {
+                        break Finish::Stop;
+                    }
+                    depth += 1;
+                    levels.last_mut().unwrap().last = parser.prev_from;
+                    levels.push(NEW_LEVEL);
+                    if targetdepth == Some(depth) {
+                        break Finish::Done;
+                    }
+                } else if code == syntaxcode::Sclose as EmacsInt {
+                    depth -= 1;
+                    mindepth = mindepth.min(depth);
+                    if levels.len() > 1 {
+                        levels.pop();
+                    }
+                    let level = levels.last_mut().unwrap();
+                    level.prev = level.last;
+                    if targetdepth == Some(depth) {
+                        break Finish::Done;
+                    }
+                } else if code == syntaxcode::Sstring as EmacsInt
+                    || code == syntaxcode::Sstring_fence as EmacsInt
+                {
+                    state.comstr_start = Some(parser.from - 1);
+                    if stopbefore {
+                        break Finish::Stop;
+                    }
+                    levels.last_mut().unwrap().last = parser.prev_from;
+                    state.instring = Some(if code == syntaxcode::Sstring as EmacsInt {
+                        StringEnd::Char(parser.syntax.char_at(parser.prev_from))
+                    } else {
+                        StringEnd::Fence
+                    });
+                    if boundary_stop {
+                        break Finish::Done;
+                    }
+                    step = Step::InString;
+                }
+                // Whitespace, punctuation, quotes and comment enders are
+                // skipped, and so is math syntax for now.
+            }
+
+            Step::Quoted => {
+                if parser.from == end {
+                    break Finish::EndQuoted;
+                }
+                parser.advance();
+                step = Step::Symbol;
+            }
+
+            Step::Symbol => {
+                step = Step::Top;
+                let mut end_quoted = false;
+                while parser.from < end {
+                    if parser.at_2char_comment_start(state) {
+                        parser.advance();
+                        parser.prev_from_syntax = NO_SYNTAX;
+                        step = Step::AtComment;
+                        break;
+                    }
+                    let code = parser.code_at(parser.from).class();
+                    if code == syntaxcode::Scharquote as EmacsInt
+                        || code == syntaxcode::Sescape as EmacsInt
+                    {
+                        parser.advance();
+                        if parser.from == end {
+                            end_quoted = true;
+                            break;
+                        }
+                    } else if code != syntaxcode::Sword as EmacsInt
+                        && code != syntaxcode::Ssymbol as EmacsInt
+                        && code != syntaxcode::Squote as EmacsInt
+                    {
+                        break;
+                    }
+                    parser.advance();
+                    parser.quit.tick();
+                }
+                if end_quoted {
+                    break Finish::EndQuoted;
+                }
+                if let Step::Top = step {
+                    let level = levels.last_mut().unwrap();
+                    level.prev = level.last;
+                }
+            }
+
+            Step::AtComment => {
+                if commentstop != CommentStop::Never {
+                    break Finish::Done;
+                }
+                step = Step::InComment;
+            }
+
+            Step::InComment => {
+                // Don't look for a comment ender that FROM is in the
+                // middle of at the start of the buffer, where the comment
+                // has only just started.
+                let prev_syntax = if parser.from == begv {
+                    SyntaxCode(0)
+                } else {
+                    parser.prev_from_syntax
+                };
+                match parser.forward_comment(state.incomment, state.comstyle, prev_syntax) {
+                    CommentEnd::Unfinished(nesting, last_syntax) => {
+                        state.incomment = nesting;
+                        parser.prev_from_syntax = last_syntax;
+                        break Finish::Done;
+                    }
+                    CommentEnd::Found => (),
+                }
+                parser.advance();
+                state.incomment = 0;
+                state.comstyle = 0;
+                // The comment ender has been used up.
+                parser.prev_from_syntax = NO_SYNTAX;
+                if boundary_stop {
+                    break Finish::Done;
+                }
+                step = Step::Top;
+            }
+
+            Step::InString => {
+                let nofence = state.instring != Some(StringEnd::Fence);
+                let finish = loop {
+                    if parser.from >= end {
+                        break Some(Finish::Done);
+                    }
+                    let c = parser.syntax.char_at(parser.from);
+                    let code = parser.syntax.code_at(parser.from, c).class();
+
+                    // Check the syntax too, so that a character whose
+                    // `syntax-table' property says it isn't a string
+                    // delimiter doesn't end the string.
+                    if nofence
+                        && state.instring == Some(StringEnd::Char(c))
+                        && code == syntaxcode::Sstring as EmacsInt
+                    {
+                        break None;
+                    }
+                    if code == syntaxcode::Sstring_fence as EmacsInt && !nofence {
+                        break None;
+                    }
+                    if code == syntaxcode::Scharquote as EmacsInt
+                        || code == syntaxcode::Sescape as EmacsInt
+                    {
+                        parser.advance();
+                        if parser.from >= end {
+                            break Some(Finish::EndQuoted);
+                        }
+                    }
+                    parser.advance();
+                    parser.quit.tick();
+                };
+                if let Some(finish) = finish {
+                    break finish;
+                }
+
+                state.instring = None;
+                let level = levels.last_mut().unwrap();
+                level.prev = level.last;
+                parser.advance();
+                if boundary_stop {
+                    break Finish::Done;
+                }
+                step = Step::Top;
+            }
+
+            Step::QuotedInString => {
+                if parser.from >= end {
+                    break Finish::EndQuoted;
+                }
+                parser.advance();
+                step = Step::InString;
+            }
+        }
+    };
+
+    match finish {
+        Finish::Done => (),
+        Finish::Stop => {
+            // Stop before the character that starts the expression.
+            parser.from = parser.prev_from;
+            parser.prev_from_syntax = parser.prev_prev_from_syntax;
+        }
+        Finish::EndQuoted => state.quoted = true,
+    }
+
+    let level = levels.pop().unwrap();
+    state.depth = depth;
+    state.mindepth = mindepth;
+    state.thislevelstart = Some(level.prev).filter(|&pos| pos >= 0);
+    state.prevlevelstart = levels
+        .last()
+        .map(|level| level.last)
+        .filter(|&pos| pos >= 0);
+    state.location = parser.from;
+    state.levelstarts = levels.iter().map(|level| level.last).collect();
+    let prev_syntax = parser.prev_from_syntax;
+    state.prev_syntax =
+        if prev_syntax.comstart_first() || prev_syntax.comend_first() || state.quoted {
+            Some(prev_syntax)
+        } else {
+            None
+        };
+}
+
+/// Parse Lisp syntax starting at FROM until TO; return status of parse at TO.
+/// Parsing stops at TO or when certain criteria are met;
+///  point is set to where parsing stops.
+/// If fifth arg OLDSTATE is omitted or nil,
+///  parsing assumes that FROM is the beginning of a function.
+///
+/// Value is a list of elements describing final state of parsing:
+///  0. depth in parens.
+///  1. character address of start of innermost containing list; nil if none.
+///  2. character address of start of last complete sexp terminated.
+///  3. non-nil if inside a string.
+///     (it is the character that will terminate the string,
+///      or t if the string should be terminated by a generic string delimiter.)
+///  4. nil if outside a comment, t if inside a non-nestable comment,
+///     else an integer (the current comment nesting).
+///  5. t if following a quote character.
+///  6. the minimum paren-depth encountered during this scan.
+///  7. style of comment, if any.
+///  8. character address of start of comment or string; nil if not in one.
+///  9. List of positions of currently open parens, outermost first.
+/// 10. When the last position scanned holds the first character of a
+///     (potential) two character construct, the syntax of that position,
+///     otherwise nil.  That construct can be a two character comment
+///     delimiter or an Escaped or Char-quoted character.
+/// 11..... Possible further internal information used by `parse-partial-sexp'.
+///
+/// If third arg TARGETDEPTH is non-nil, parsing stops if the depth
+/// in parentheses becomes equal to TARGETDEPTH.
+/// Fourth arg STOPBEFORE non-nil means stop when we come to
+///  any character that starts a sexp.
+/// Fifth arg OLDSTATE is a list like what this function returns.
+///  It is used to initialize the state of the parse.  Elements number 1, 2, 6
+///  are ignored.
+/// Sixth arg COMMENTSTOP non-nil means stop after the start of a comment.
+///  If it is the symbol `syntax-table', stop after the start of a comment or a
+///  string, or after end of a comment or a string.
+#[lisp_fn(min = "2")]
+pub fn parse_partial_sexp(
+    mut from: LispObject,
+    mut to: LispObject,
+    targetdepth: Option<EmacsInt>,
+    stopbefore: bool,
+    oldstate: LispObject,
+    commentstop: LispObject,
+) -> LispObject {
+    unsafe { validate_region(&mut from, &mut to) };
+    let (from, to) = (
+        from.as_fixnum_or_error() as isize,
+        to.as_fixnum_or_error() as isize,
+    );
+    let commentstop = if commentstop.is_nil() {
+        CommentStop::Never
+    } else if commentstop.eq(Qsyntax_table) {
+        CommentStop::Boundary
+    } else {
+        CommentStop::Start
+    };
+
+    let mut state = ParseState::from_lisp(oldstate, from);
+    scan_sexps_forward(&mut state, to, targetdepth, stopbefore, commentstop);
+    unsafe { set_point(state.location) };
+    LispObject::from(&state)
+}
+
+/// How far apart the states kept by `syntax-ppss-cached' are.
+const CHECKPOINT_INTERVAL: isize = 2000;
+
+/// The states reached by parsing a buffer from the start of its
+/// accessible portion, every `CHECKPOINT_INTERVAL` characters.
+struct ParseCache {
+    /// The text modification count, accessible portion and syntax table
+    /// the states were found with.
+    char_modifications: EmacsInt,
+    begv: isize,
+    syntax_table: usize,
+    lookup_properties: bool,
+    checkpoints: Vec<ParseState>,
+}
+
+lazy_static! {
+    /// The parse caches, by buffer.
+    static ref PARSE_CACHES: Mutex<HashMap<usize, ParseCache>> = Mutex::new(HashMap::new());
+}
+
+impl ParseCache {
+    fn new(buffer: LispBufferRef) -> Self {
+        Self {
+            char_modifications: buffer.char_modifications(),
+            begv: buffer.begv,
+            syntax_table: current_syntax_table().as_ptr() as usize,
+            lookup_properties: unsafe { globals.parse_sexp_lookup_properties },
+            checkpoints: vec![ParseState::new(buffer.begv)],
+        }
+    }
+
+    fn is_valid_for(&self, buffer: LispBufferRef) -> bool {
+        let fresh = Self::new(buffer);
+        self.char_modifications == fresh.char_modifications
+            && self.begv == fresh.begv
+            && self.syntax_table == fresh.syntax_table
+            && self.lookup_properties == fresh.lookup_properties
+    }
+
+    /// The state of the parse at POS, adding the checkpoints before it.
+    fn state_at(&mut self, pos: isize) -> ParseState {
+        let index = self
+            .checkpoints
+            .iter()
+            .rposition(|state| state.location <= pos)
+            .unwrap_or(0);
+        self.checkpoints.truncate(index + 1);
+        let mut state = self.checkpoints[index].clone();
+
+        while state.location + CHECKPOINT_INTERVAL <= pos {
+            let next = state.location + CHECKPOINT_INTERVAL;
+            scan_sexps_forward(&mut state, next, None, false, CommentStop::Never);
+            self.checkpoints.push(state.clone());
+        }
+        scan_sexps_forward(&mut state, pos, None, false, CommentStop::Never);
+        state
+    }
+}
+
+/// Forget the parse cache of BUFFER, which is being killed.
+#[no_mangle]
+pub extern "C" fn forget_parse_cache(buffer: *mut Lisp_Buffer) {
+    PARSE_CACHES.lock().unwrap().remove(&(buffer as usize));
+}
+
+/// Return the parse state at POS, parsing from the start of the buffer.
+/// POS defaults to point.  The value is that of `parse-partial-sexp'
+/// run from `point-min' to POS, except that the values at positions 2
+/// and 6 in the returned list (counting from 0) can't be relied upon.
+/// Unlike `syntax-ppss', this doesn't move point.
+///
+/// The states found along the way are kept, so that parsing up to a
+/// later position needn't start from the beginning.  They are forgotten
+/// when the text of the buffer changes, or its accessible portion,
+/// syntax table or `parse-sexp-lookup-properties' does.  Changing the
+/// syntax table itself, or only the text properties of the buffer,
+/// doesn't forget them.
+#[lisp_fn(min = "0")]
+pub fn syntax_ppss_cached(pos: Option<EmacsInt>) -> LispObject {
+    let mut buffer = ThreadState::current_buffer_unchecked();
+    let pos = pos.map_or(buffer.pt, |pos| pos as isize);
+    if pos < buffer.begv || pos > buffer.zv {
+        args_out_of_range!(pos, buffer.begv, buffer.zv);
+    }
+
+    // Take the cache out of the table while parsing, which may signal
+    // or run Lisp code.
+    let key = buffer.as_mut() as usize;
+    let cached = PARSE_CACHES.lock().unwrap().remove(&key);
+    let mut cache = cached
+        .filter(|cache| cache.is_valid_for(buffer))
+        .unwrap_or_else(|| ParseCache::new(buffer));
+    let state = cache.state_at(pos);
+    PARSE_CACHES.lock().unwrap().insert(key, cache);
+
+    LispObject::from(&state)
+}
+
+include!(concat!(env!("OUT_DIR"), "/parse_sexp_exports.rs"));
@@ -0,0 +1,238 @@
+//! Filling text to a pixel width.
+//!
+//! Variable pitch text can't be filled by counting columns.  Instead,
+//! the width of each character is that of its glyph in the font that
+//! displays it, and lines are broken once they get wider than the
+//! width to fill to.  Glyph widths are cached by font.
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    editfns::buffer_substring,
+    hashtable::{gethash, puthash, LispHashTableRef, Weakness},
+    lisp::{defsubr, LispObject},
+    lists::list,
+    multibyte::Codepoint,
+    obarray::intern,
+    remacs_sys::{EmacsInt, Fchar_width, Ffont_at, Ffont_get_glyphs, Fframe_char_width},
+    remacs_sys::{Ftext_properties_at, Qeq, Qnil},
+    string_alloc::make_string,
+    symbols::symbol_value,
+    windows::{window_frame, LispWindowLiveOrSelected, LispWindowRef},
+};
+
+/// The widths of the glyphs measured so far.  Keys are font objects,
+/// held weakly so that fonts no longer open are dropped, and values are
+/// tables from characters to widths in pixels.
+declare_GC_protected_static!(glyph_widths, Qnil);
+
+fn glyph_width_table(font: LispObject) -> LispHashTableRef {
+    if glyph_widths.get().is_nil() {
+        glyph_widths.set(LispHashTableRef::make_weak(Qeq, Weakness::Key).into());
+    }
+    let fonts: LispHashTableRef = glyph_widths.get().into();
+    let table = gethash(font, fonts, Qnil);
+    if table.is_not_nil() {
+        return table.into();
+    }
+    let table = LispHashTableRef::make_eq();
+    puthash(font, table.into(), fonts);
+    table
+}
+
+/// Return the width in pixels of the glyph of FONT for the character C,
+/// or `None` if FONT has no glyph for it.
+fn glyph_width(font: LispObject, c: Codepoint) -> Option<i32> {
+    let table = glyph_width_table(font);
+    let key = LispObject::from(c);
+    if let Some(width) = gethash(key, table, Qnil).as_fixnum() {
+        return Some(width as i32);
+    }
+
+    let string = make_string(&std::char::from_u32(c)?.to_string());
+    let glyphs = unsafe { Ffont_get_glyphs(font, 0.into(), 1.into(), string) };
+    // A glyph is [FROM-IDX TO-IDX C CODE WIDTH ...].
+    let width = glyphs.as_vector()?.get(0).as_vector()?.get(4).as_fixnum()? as i32;
+    puthash(key, width.into(), table);
+    Some(width)
+}
+
+/// How a character takes part in filling.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) enum Kind {
+    /// A newline, which ends a line.
+    Newline,
+    /// A space or tab, where a line can be broken.
+    Space,
+    /// Any other character, which is part of a word.
+    Other,
+}
+
+impl Kind {
+    pub(crate) fn of(c: Codepoint) -> Kind {
+        match c {
+            0x0a => Kind::Newline,
+            0x20 | 0x09 => Kind::Space,
+            _ => Kind::Other,
+        }
+    }
+}
+
+/// Return the indices where to break the characters of GLYPHS, given as
+/// their kind and width, so that no line is wider than WIDTH.  An index
+/// is either that of a space, which the line break replaces, or, inside
+/// a word that doesn't fit on a line of its own, that of the first
+/// character to go on the next line.
+pub(crate) fn fill_breaks(glyphs: impl Iterator<Item = (Kind, i32)>, width: i32) -> Vec<usize> {
+    let mut breaks = Vec::new();
+    // The width of the current line, the last space on it, and the width
+    // of what follows that space.
+    let mut line_width = 0;
+    let mut last_space = None;
+    let mut word_width = 0;
+
+    for (i, (kind, w)) in glyphs.enumerate() {
+        match kind {
+            Kind::Newline => {
+                line_width = 0;
+                last_space = None;
+                word_width = 0;
+            }
+            Kind::Space if line_width + w > width => {
+                breaks.push(i);
+                line_width = 0;
+                last_space = None;
+                word_width = 0;
+            }
+            Kind::Space => {
+                line_width += w;
+                last_space = Some(i);
+                word_width = 0;
+            }
+            Kind::Other => {
+                if line_width + w > width {
+                    if let Some(space) = last_space.take() {
+                        breaks.push(space);
+                        line_width = word_width;
+                    }
+                    if line_width > 0 && line_width + w > width {
+                        breaks.push(i);
+                        line_width = 0;
+                        word_width = 0;
+                    }
+                }
+                line_width += w;
+                word_width += w;
+            }
+        }
+    }
+    breaks
+}
+
+/// Return the positions where to break the text from FROM to TO in the
+/// current buffer to fill it to WIDTH pixels, in increasing order.
+///
+/// Characters are as wide as their glyphs in the fonts that display
+/// them in WINDOW, which defaults to the selected window; it need not
+/// display the current buffer.  On a text terminal, or for characters
+/// without a glyph, the width is that of their columns.  Lines are
+/// broken at spaces and tabs, and newlines in the text start new lines.
+///
+/// Each position is either that of a space or tab, which the caller
+/// replaces with a newline, or, within a word too wide for a line of its
+/// own, that of the character the caller inserts a newline before.
+#[lisp_fn(min = "3")]
+pub fn pixel_fill_breaks(
+    from: LispObject,
+    to: LispObject,
+    width: EmacsInt,
+    window: LispWindowLiveOrSelected,
+) -> LispObject {
+    let window: LispWindowRef = window.into();
+    let window = LispObject::from(window);
+    let column_width = unsafe { Fframe_char_width(window_frame(window.into())) }
+        .as_fixnum_or_error()
+        .max(1) as i32;
+    let tab_width = symbol_value(intern("tab-width"))
+        .as_fixnum()
+        .filter(|&n| 0 < n && n <= 1000)
+        .unwrap_or(8) as i32;
+
+    // Faces come from the text properties of the substring, so that
+    // WINDOW needn't show the current buffer.
+    let text = buffer_substring(from, to);
+    let start = from
+        .as_fixnum_coerce_marker_or_error()
+        .min(to.as_fixnum_coerce_marker_or_error());
+    let string = text.force_string();
+
+    let mut last_props = Qnil;
+    let mut ascii_font = Qnil;
+    let glyphs = string.chars().enumerate().map(|(i, c)| {
+        let kind = Kind::of(c);
+        let columns = if c == 0x09 {
+            tab_width
+        } else {
+            unsafe { Fchar_width(c.into()) }.as_fixnum_or_error() as i32
+        };
+
+        // Characters of the same properties share their font if they
+        // are ASCII; others may be displayed by another font of the
+        // fontset.
+        let position = LispObject::from(i as EmacsInt);
+        let props = unsafe { Ftext_properties_at(position, text) };
+        let font = if c < 0x80 && props.eq(last_props) && ascii_font.is_not_nil() {
+            ascii_font
+        } else {
+            let font = unsafe { Ffont_at(position, window, text) };
+            if c < 0x80 {
+                last_props = props;
+                ascii_font = font;
+            }
+            font
+        };
+
+        let width = if kind == Kind::Other && font.is_not_nil() {
+            glyph_width(font, c)
+        } else {
+            None
+        };
+        (kind, width.unwrap_or(columns * column_width))
+    });
+
+    let breaks = fill_breaks(glyphs, width as i32);
+    list(
+        &breaks
+            .into_iter()
+            .map(|i| LispObject::from(start + i as EmacsInt))
+            .collect::<Vec<_>>(),
+    )
+}
+
+include!(concat!(env!("OUT_DIR"), "/pixel_fill_exports.rs"));
+
+#[test]
+fn test_fill_breaks() {
+    let glyphs = |text: &str| {
+        text.chars()
+            .map(|c| (Kind::of(c as Codepoint), 1))
+            .collect::<Vec<_>>()
+    };
+    let breaks = |text: &str, width| fill_breaks(glyphs(text).into_iter(), width);
+
+    assert_eq!(breaks("aaa bbb ccc", 7), vec![7]);
+    assert_eq!(breaks("aaa bbb ccc", 3), vec![3, 7]);
+    assert_eq!(breaks("aaa bbb\nccc ddd", 8), vec![]);
+    assert_eq!(breaks("aaaaaaa bb", 3), vec![3, 6, 7]);
+    assert_eq!(breaks("aa bbbbbbb", 4), vec![2, 7]);
+    assert_eq!(breaks("", 4), vec![]);
+
+    // Wide glyphs count by their width.
+    let wide = vec![
+        (Kind::Other, 10),
+        (Kind::Space, 3),
+        (Kind::Other, 10),
+        (Kind::Other, 10),
+    ];
+    assert_eq!(fill_breaks(wide.into_iter(), 25), vec![1]);
+}
@@ -0,0 +1,1625 @@
+//! The Lisp reader, which turns the printed representation of Lisp
+//! objects back into objects.
+//!
+//! Getting characters from the different kinds of input streams, and
+//! the doc strings skipped in .elc files, are left to lread.c.
+
+use libc::{c_char, c_void};
+use std::ptr;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    chartable::{chartab_size, LispSubCharTableRef},
+    data::aset,
+    editfns::{format, format_message},
+    hashtable::{make_hash_table_lisp, puthash, HashLookupResult, LispHashTableRef},
+    lisp::defsubr,
+    lisp::LispObject,
+    lists::{memq, plist_get, LispCons, LispConsCircularChecks, LispConsEndChecks},
+    lread::read,
+    multibyte::{
+        char_byte8_p, char_to_byte8, is_ascii, is_single_byte_char, multibyte_as_unibyte,
+        multibyte_char_at, raw_byte_codepoint, write_codepoint, Codepoint, LispStringRef, MAX_CHAR,
+        MAX_MULTIBYTE_LENGTH,
+    },
+    numbers::{parse_number, MOST_POSITIVE_FIXNUM},
+    obarray::intern,
+    remacs_sys::{
+        bool_vector_set, char_bits, char_table_specials, check_obarray, concat2,
+        confusable_symbol_character_p, get_lazy_doc_string, globals, intern_driver, make_byte_code,
+        make_pure_string, make_specified_string, make_uninit_bool_vector, oblookup, pvec_type,
+        read_from_string_index, read_from_string_index_byte, read_from_string_limit,
+        read_objects_completed, read_objects_map, readchar, readchar_count, skip_dyn_doc_string,
+        skip_dyn_eof, string_char_to_byte, traverse_intervals_noorder, unreadchar,
+        validate_subarray, EmacsInt, INTERVAL,
+    },
+    remacs_sys::{
+        Fdecode_coding_string, Ffile_name_nondirectory, Fmake_record, Fmake_symbol, Fmake_vector,
+        Fset_text_properties,
+    },
+    remacs_sys::{
+        QCpurecopy, QCrehash_size, QCrehash_threshold, QCsize, QCtest, QCweakness, Qbackquote,
+        Qchar_from_name, Qcomma, Qcomma_at, Qcomma_dot, Qdata, Qemacs_mule, Qend_of_file, Qerror,
+        Qfunction, Qget_emacs_mule_file_char, Qhash_table, Qinvalid_read_syntax, Qlistp, Qnil,
+        Qpurecopy, Qquote, Qrehash_size, Qrehash_threshold, Qsequencep, Qsize, Qt, Qtest,
+        Qweakness,
+    },
+    sequences::nreverse,
+    strings::string_as_multibyte,
+    vectors::LispVectorRef,
+};
+
+const CHAR_ALT: i32 = char_bits::CHAR_ALT as i32;
+const CHAR_SUPER: i32 = char_bits::CHAR_SUPER as i32;
+const CHAR_HYPER: i32 = char_bits::CHAR_HYPER as i32;
+const CHAR_SHIFT: i32 = char_bits::CHAR_SHIFT as i32;
+const CHAR_CTL: i32 = char_bits::CHAR_CTL as i32;
+const CHAR_META: i32 = char_bits::CHAR_META as i32;
+const CHAR_MODIFIER_MASK: i32 = char_bits::CHAR_MODIFIER_MASK as i32;
+
+const NO_BREAK_SPACE: i32 = 0xA0;
+
+/// Bound on the length of a Unicode character name.  As of Unicode
+/// 9.0.0 the maximum is 83, so this should be safe.
+const UNICODE_CHARACTER_NAME_LENGTH_BOUND: usize = 200;
+
+/// The most characters `read_integer` keeps: a sign, a leading zero
+/// and the digits of the largest integer in base 2.
+const INTEGER_LENGTH_BOUND: usize = 1 + 1 + 64;
+
+/// The slots of a byte-code object that need care when its doc string
+/// is loaded lazily.
+const COMPILED_BYTECODE: usize = 1;
+const COMPILED_CONSTANTS: usize = 2;
+const COMPILED_DOC_STRING: usize = 4;
+
+/// True means inside a new-style backquote with no surrounding
+/// parentheses.  `read_internal_start` initializes this to the value of
+/// `force-new-style-backquotes', so we need not specbind it or worry
+/// about what happens to it when there is an error.
+static mut NEW_BACKQUOTE_FLAG: bool = false;
+
+/// The character C as returned by `readchar`, if it is ASCII, or the
+/// replacement character otherwise, which never means anything to the
+/// reader.
+fn ascii(c: i32) -> char {
+    if 0 <= c && c < 0x80 {
+        c as u8 as char
+    } else {
+        std::char::REPLACEMENT_CHARACTER
+    }
+}
+
+/// Whether the character C is in SET.
+fn is_one_of(c: i32, set: &str) -> bool {
+    0 <= c && c < 0x80 && set.contains(ascii(c))
+}
+
+/// Whether the character C can be part of a symbol or number without
+/// being escaped.
+fn is_symbol_char(c: i32) -> bool {
+    c > 0o40 && c != NO_BREAK_SPACE && (c >= 0o200 || !is_one_of(c, "\"';()[]#`,"))
+}
+
+/// Return the value of the digit C in BASE.  Fail with `true` if C is
+/// a digit that is out of range for BASE, and with `false` if it is not
+/// a digit in any base.
+fn digit_to_number(c: i32, base: u32) -> Result<u32, bool> {
+    match ascii(c).to_digit(36) {
+        Some(digit) if digit < base => Ok(digit),
+        Some(_) => Err(true),
+        None => Err(false),
+    }
+}
+
+/// Signal an `end-of-file' error, if possible with file name information.
+fn end_of_file_error() -> ! {
+    let file = unsafe { globals.Vload_file_name };
+    if file.is_string() {
+        xsignal!(Qend_of_file, file);
+    }
+    xsignal!(Qend_of_file);
+}
+
+fn invalid_syntax(msg: &str) -> ! {
+    xsignal!(Qinvalid_read_syntax, LispObject::from(msg));
+}
+
+fn old_style_backquotes_error() -> ! {
+    let file = unsafe { globals.Vload_file_name };
+    if file.is_nil() {
+        xsignal!(Qerror, LispObject::from("Old-style backquotes detected!"));
+    }
+    let message = LispObject::from("Loading `%s': old-style backquotes detected!");
+    xsignal!(Qerror, format_message(&mut [message, file]));
+}
+
+/// Return the scalar value that has the Unicode character name NAME.
+/// Signal `invalid-read-syntax' if there is no such character.
+fn character_name_to_code(name: &[u8]) -> i32 {
+    // For "U+XXXX", pass the leading '+' to `parse_number' to reject
+    // monstrosities like "U+-0000".
+    let string = unsafe {
+        make_specified_string(
+            name.as_ptr() as *const c_char,
+            name.len() as isize,
+            name.len() as isize,
+            false,
+        )
+    };
+    let code = if name.starts_with(b"U+") {
+        parse_number(&name[1..], 16, false).unwrap_or(Qnil)
+    } else {
+        call!(Qchar_from_name, string, Qt)
+    };
+
+    match code.as_fixnum() {
+        Some(c) if 0 <= c && c <= 0x10_FFFF && !(0xD800 <= c && c <= 0xDFFF) => c as i32,
+        _ => {
+            let message = LispObject::from("\\N{%s}");
+            xsignal!(Qinvalid_read_syntax, format(&mut [message, string]));
+        }
+    }
+}
+
+/// What `Reader::read1` found: an object, or one of the characters
+/// `)', `]' and `.' that close lists and vectors.
+#[derive(Clone, Copy, PartialEq)]
+enum Token {
+    Object(LispObject),
+    Close(char),
+}
+
+/// Whether a list that starts with #$ stands for something else while
+/// loading.
+#[derive(Clone, Copy, PartialEq)]
+enum DocReference {
+    None,
+    /// The list stands for 0, because its doc string is in the DOC file.
+    Zero,
+    /// The list is (#$ . POSITION) and stands for the doc string found
+    /// there, which `load-force-doc-strings' says to load now.
+    DocString,
+}
+
+/// Reads objects from one of the kinds of streams `read' accepts.
+#[derive(Clone, Copy)]
+struct Reader {
+    stream: LispObject,
+}
+
+impl Reader {
+    fn new(stream: LispObject) -> Self {
+        Self { stream }
+    }
+
+    /// Return the next character of the stream, or -1 at its end.
+    fn read_char(self) -> i32 {
+        unsafe { readchar(self.stream, ptr::null_mut()) }
+    }
+
+    /// Like `read_char`, but also say whether the character was read
+    /// from a multibyte source.
+    fn read_char_multibyte(self) -> (i32, bool) {
+        let mut multibyte = false;
+        let c = unsafe { readchar(self.stream, &mut multibyte) };
+        (c, multibyte)
+    }
+
+    /// Like `read_char`, but signal `end-of-file' at the end.
+    fn read_char_or_eof(self) -> i32 {
+        match self.read_char() {
+            -1 => end_of_file_error(),
+            c => c,
+        }
+    }
+
+    /// Make C the next character of the stream again.
+    fn unread(self, c: i32) {
+        unsafe { unreadchar(self.stream, c) }
+    }
+
+    fn peek(self) -> i32 {
+        let c = self.read_char();
+        self.unread(c);
+        c
+    }
+
+    /// Read one object; the characters that close lists and vectors are
+    /// errors here.
+    fn read0(self) -> LispObject {
+        match self.read1(false) {
+            Token::Object(object) => object,
+            Token::Close(c) => invalid_syntax(&c.to_string()),
+        }
+    }
+
+    /// Read one object, or the `)', `]' or `.' that closes a list or
+    /// vector.  FIRST_IN_LIST is true if this is the first element of a
+    /// list.
+    fn read1(self, first_in_list: bool) -> Token {
+        loop {
+            let (c, multibyte) = self.read_char_multibyte();
+            let object = match ascii(c) {
+                _ if c < 0 => end_of_file_error(),
+                '(' => self.read_list(false),
+                '[' => self.read_vector(false).into(),
+                ')' | ']' => return Token::Close(ascii(c)),
+                '#' => match self.read_hash_syntax(multibyte) {
+                    Some(object) => object,
+                    None => continue,
+                },
+                ';' => {
+                    let mut c = c;
+                    while c >= 0 && ascii(c) != '\n' {
+                        c = self.read_char();
+                    }
+                    continue;
+                }
+                '\'' => list!(Qquote, self.read0()),
+                '`' => self.read_backquote(first_in_list),
+                ',' => self.read_comma(first_in_list),
+                '?' => self.read_character_literal(),
+                '"' => self.read_string(),
+                '.' => {
+                    let next = self.peek();
+                    if next <= 0o40 || is_one_of(next, "\"';([#?`,") {
+                        return Token::Close('.');
+                    }
+                    // The symbol-reading loop consumes the period.
+                    self.read_symbol(c, multibyte, SymbolSyntax::Plain)
+                }
+                _ if c <= 0o40 || c == NO_BREAK_SPACE => continue,
+                _ => self.read_symbol(c, multibyte, SymbolSyntax::Plain),
+            };
+            return Token::Object(object);
+        }
+    }
+
+    /// Read what follows a `#'.  Return `None` if it was something to
+    /// skip, like a #@NUMBER form.
+    fn read_hash_syntax(self, multibyte: bool) -> Option<LispObject> {
+        let c = self.read_char();
+        let object = match ascii(c) {
+            's' => {
+                let c = self.read_char();
+                if ascii(c) != '(' {
+                    self.unread(c);
+                    invalid_syntax("#");
+                }
+                self.read_record()
+            }
+            '^' => self.read_char_table(),
+            '&' => self.read_bool_vector(),
+            '[' => {
+                // Accept compiled functions at read-time so that we
+                // don't have to build them using function calls.
+                let mut vector = self.read_vector(true);
+                if vector.len() == 0 {
+                    invalid_syntax("Empty byte-code object");
+                }
+                unsafe { make_byte_code(vector.as_mut()) };
+                vector.into()
+            }
+            '(' => self.read_propertized_string(),
+            '@' => {
+                // #@NUMBER is used to skip NUMBER following bytes.
+                // That's used in .elc files to skip over doc strings
+                // and function definitions.
+                if self.skip_dyn_bytes() {
+                    return Some(Qnil);
+                }
+                return None;
+            }
+            '!' => {
+                // #! appears at the beginning of an executable file.
+                // Skip the first line.
+                let mut c = c;
+                while c >= 0 && ascii(c) != '\n' {
+                    c = self.read_char();
+                }
+                return None;
+            }
+            '$' => unsafe { globals.Vload_file_name },
+            '\'' => list!(Qfunction, self.read0()),
+            ':' => {
+                // #:foo is the uninterned symbol named foo.
+                let c = self.read_char();
+                if !is_symbol_char(c) {
+                    // No symbol character follows, this is the empty
+                    // symbol.
+                    self.unread(c);
+                    unsafe { Fmake_symbol(LispObject::empty_unibyte_string().into()) }
+                } else {
+                    self.read_symbol(c, multibyte, SymbolSyntax::Uninterned)
+                }
+            }
+            // ## is the empty symbol.
+            '#' => intern("").into(),
+            '_' => {
+                // #_foo is the symbol foo, whatever the shorthands.
+                let c = self.read_char();
+                if !is_symbol_char(c) {
+                    self.unread(c);
+                    intern("").into()
+                } else {
+                    self.read_symbol(c, multibyte, SymbolSyntax::Longhand)
+                }
+            }
+            '0'..='9' => self.read_numbered(c),
+            'x' | 'X' => self.read_integer(16),
+            'o' | 'O' => self.read_integer(8),
+            'b' | 'B' => self.read_integer(2),
+            _ => {
+                self.unread(c);
+                invalid_syntax("#");
+            }
+        };
+        Some(object)
+    }
+
+    /// Read the rest of a record, #s(TYPE SLOTS...), or of a hash table
+    /// in the extended format, like
+    /// #s(hash-table size 2 test equal data (k1 v1 k2 v2)).
+    fn read_record(self) -> LispObject {
+        let list = self.read_list(false);
+        let head = list.as_cons().map_or(Qnil, LispCons::car);
+
+        if !head.eq(Qhash_table) {
+            let size = list.as_cons().map_or(0, LispCons::length);
+            let record = unsafe { Fmake_record(head, (size as EmacsInt - 1).into(), Qnil) };
+            let slots = list.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off);
+            for (i, slot) in slots.enumerate().skip(1) {
+                aset(record, i as EmacsInt, slot);
+            }
+            return record;
+        }
+
+        let plist = list.as_cons().map_or(Qnil, LispCons::cdr);
+        // The size is twice the number of keywords `make-hash-table'
+        // accepts.
+        let mut params = [Qnil; 12];
+        let mut count = 0;
+        for &(keyword, name) in &[
+            (QCsize, Qsize),
+            (QCtest, Qtest),
+            (QCweakness, Qweakness),
+            (QCrehash_size, Qrehash_size),
+            (QCrehash_threshold, Qrehash_threshold),
+            (QCpurecopy, Qpurecopy),
+        ] {
+            let value = plist_get(plist, name);
+            if value.is_not_nil() {
+                params[count] = keyword;
+                params[count + 1] = value;
+                count += 2;
+            }
+        }
+
+        let table = make_hash_table_lisp(&mut params[..count]);
+        let mut data = plist_get(plist, Qdata);
+        while let Some(cons) = data.as_cons() {
+            let rest = cons
+                .cdr()
+                .as_cons()
+                .unwrap_or_else(|| error!("Odd number of elements in hash table data"));
+            puthash(cons.car(), rest.car(), table.into());
+            data = rest.cdr();
+        }
+        table
+    }
+
+    /// Read the rest of a char-table, #^[...], or of a sub char-table,
+    /// #^^[...].
+    fn read_char_table(self) -> LispObject {
+        let c = self.read_char();
+        if ascii(c) == '[' {
+            let vector = self.read_vector(false);
+            if vector.len() < char_table_specials::CHAR_TABLE_STANDARD_SLOTS as usize {
+                error!("Invalid size char-table");
+            }
+            let table = LispObject::from(vector);
+            table
+                .as_vectorlike()
+                .unwrap()
+                .set_pseudovector_type(pvec_type::PVEC_CHAR_TABLE);
+            return table;
+        }
+        if ascii(c) != '^' {
+            invalid_syntax("#^");
+        }
+        if ascii(self.read_char()) != '[' {
+            invalid_syntax("#^^");
+        }
+
+        // A sub char-table can't be read as a regular vector because of
+        // its two C integer fields.
+        let list = self.read_list(true);
+        let size = list.as_cons().map_or(0, LispCons::length);
+        if size == 0 {
+            error!("Zero-sized sub char-table");
+        }
+        let mut items = list.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off);
+        let depth = match items.next().and_then(LispObject::as_fixnum) {
+            Some(depth) if 1 <= depth && depth <= 3 => depth as i32,
+            _ => error!("Invalid depth in sub char-table"),
+        };
+        if chartab_size(depth) != size - 2 {
+            error!("Invalid size in sub char-table");
+        }
+        let min_char = match items.next().and_then(LispObject::as_fixnum) {
+            Some(c) if 0 <= c && c <= EmacsInt::from(MAX_CHAR) => c as i32,
+            _ => error!("Invalid minimum character in sub-char-table"),
+        };
+
+        let mut table = LispSubCharTableRef::new(depth, min_char);
+        for (i, item) in items.enumerate() {
+            table.set(i, item);
+        }
+        table.into()
+    }
+
+    /// Read the rest of a bool-vector, #&LENGTH"BITS".
+    fn read_bool_vector(self) -> LispObject {
+        let length = match self.read0().as_fixnum() {
+            Some(length) if length >= 0 => length as usize,
+            _ => invalid_syntax("#&..."),
+        };
+        let c = self.read_char();
+        if ascii(c) != '"' {
+            invalid_syntax("#&...");
+        }
+        self.unread(c);
+
+        let bits = match self.read0().as_string() {
+            Some(bits) if !bits.is_multibyte() => bits.as_slice(),
+            _ => invalid_syntax("#&..."),
+        };
+        // We used to print 1 char too many when the number of bits was
+        // a multiple of 8.  Accept such input in case it came from an
+        // old version.
+        if (length + 7) / 8 != bits.len() && length + 8 != bits.len() * 8 {
+            invalid_syntax("#&...");
+        }
+
+        let vector = unsafe { make_uninit_bool_vector(length as EmacsInt) };
+        for i in 0..length {
+            let bit = bits[i / 8] & (1 << (i % 8)) != 0;
+            unsafe { bool_vector_set(vector, i as EmacsInt, bit) };
+        }
+        vector
+    }
+
+    /// Read the rest of a string with text properties,
+    /// #("STRING" START END PLIST...).
+    fn read_propertized_string(self) -> LispObject {
+        let string = match self.read1(false) {
+            Token::Object(string) if string.is_string() => string,
+            _ => invalid_syntax("#"),
+        };
+        loop {
+            let start = match self.read1(false) {
+                Token::Close(')') => break,
+                Token::Object(start) => start,
+                Token::Close(_) => invalid_syntax("Invalid string property list"),
+            };
+            let next = || match self.read1(false) {
+                Token::Object(object) => object,
+                Token::Close(_) => invalid_syntax("Invalid string property list"),
+            };
+            let end = next();
+            let plist = next();
+            unsafe { Fset_text_properties(start, end, plist, string) };
+        }
+        string
+    }
+
+    /// Skip the bytes a #@NUMBER form says to skip.  Return true if it
+    /// was #@00, which skips to the end of the stream.
+    fn skip_dyn_bytes(self) -> bool {
+        let mut nskip: isize = 0;
+        let mut digits = 0;
+        let mut c = self.read_char();
+        while let Some(digit) = ascii(c).to_digit(10) {
+            nskip = nskip
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(digit as isize))
+                .unwrap_or_else(|| error!("Maximum string size exceeded"));
+            digits += 1;
+            if digits == 2 && nskip == 0 {
+                unsafe { skip_dyn_eof(self.stream) };
+                return true;
+            }
+            c = self.read_char();
+        }
+        if nskip > 0 {
+            // We can't unread here, see Bug#17776.  The character after
+            // the number is a space, which the count includes.
+            nskip -= 1;
+        } else {
+            self.unread(c);
+        }
+        unsafe { skip_dyn_doc_string(self.stream, nskip) };
+        false
+    }
+
+    /// Read the rest of a form that starts with # and a decimal number
+    /// whose first digit is C: #NrDIGITS, #N=OBJECT or #N#.
+    fn read_numbered(self, mut c: i32) -> LispObject {
+        let mut n: EmacsInt = 0;
+        let mut overflow = false;
+        while let Some(digit) = ascii(c).to_digit(10) {
+            match n
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(EmacsInt::from(digit)))
+            {
+                Some(m) => n = m,
+                None => overflow = true,
+            }
+            c = self.read_char();
+        }
+
+        if !overflow && n <= MOST_POSITIVE_FIXNUM {
+            if ascii(c) == 'r' || ascii(c) == 'R' {
+                return self.read_integer(n);
+            }
+            if unsafe { globals.Vread_circle }.is_not_nil() {
+                if ascii(c) == '=' {
+                    return self.read_labeled(n);
+                }
+                if ascii(c) == '#' {
+                    let map = LispHashTableRef::from(unsafe { read_objects_map });
+                    if let HashLookupResult::Found(i) = map.lookup(n.into()) {
+                        return map.get_hash_value(i);
+                    }
+                }
+            }
+        }
+        self.unread(c);
+        invalid_syntax("#");
+    }
+
+    /// Read the object of #N=OBJECT, and associate it with N for #N#.
+    fn read_labeled(self, n: EmacsInt) -> LispObject {
+        // Make a placeholder for #N# to use temporarily.
+        let placeholder = LispObject::cons(Qnil, Qnil);
+        let map = LispHashTableRef::from(unsafe { read_objects_map });
+        let number = LispObject::from(n);
+        match map.lookup(number) {
+            // Not normal, but input could be malformed.
+            HashLookupResult::Found(i) => map.set_hash_value(i, placeholder),
+            HashLookupResult::Missing(hash) => {
+                map.put(number, placeholder, hash);
+            }
+        }
+
+        let object = self.read0();
+
+        // If it can be recursive, remember it for future substitutions.
+        if !object.is_symbol() && !object.is_number() && !is_plain_string(object) {
+            let completed = LispHashTableRef::from(unsafe { read_objects_completed });
+            if let HashLookupResult::Missing(hash) = completed.lookup(object) {
+                completed.put(object, Qnil, hash);
+            }
+        }
+
+        // Now put it everywhere the placeholder was...
+        if let Some(cons) = object.as_cons() {
+            let cell = LispCons::from(placeholder);
+            cell.set_car(cons.car());
+            cell.set_cdr(cons.cdr());
+            return placeholder;
+        }
+        lread_substitute_object_in_subtree(object, placeholder, unsafe { read_objects_completed });
+
+        // ...and #N# will use the real value from now on.
+        if let HashLookupResult::Found(i) = map.lookup(number) {
+            map.set_hash_value(i, object);
+        }
+        object
+    }
+
+    /// Read an integer in RADIX, which should be between 2 and 36.
+    fn read_integer(self, radix: EmacsInt) -> LispObject {
+        let mut text: Vec<u8> = Vec::with_capacity(INTEGER_LENGTH_BOUND);
+        // Whether the digits are valid, or `None` while there are none.
+        let mut valid = None;
+
+        if 2 <= radix && radix <= 36 {
+            let mut c = self.read_char();
+            if ascii(c) == '-' || ascii(c) == '+' {
+                text.push(c as u8);
+                c = self.read_char();
+            }
+            if ascii(c) == '0' {
+                text.push(c as u8);
+                valid = Some(true);
+                // Ignore redundant leading zeros, so the text doesn't
+                // fill up with them.
+                while ascii(c) == '0' {
+                    c = self.read_char();
+                }
+            }
+            loop {
+                match digit_to_number(c, radix as u32) {
+                    Ok(_) => valid = valid.or(Some(true)),
+                    Err(true) => valid = Some(false),
+                    Err(false) => break,
+                }
+                if text.len() < INTEGER_LENGTH_BOUND {
+                    text.push(c as u8);
+                } else {
+                    valid = Some(false);
+                }
+                c = self.read_char();
+            }
+            self.unread(c);
+        } else {
+            valid = Some(false);
+        }
+
+        if valid == Some(false) {
+            invalid_syntax(&format!("integer, radix {}", radix));
+        }
+        parse_number(&text, radix as u32, false).unwrap_or(Qnil)
+    }
+
+    /// Read the rest of a backquoted form.
+    fn read_backquote(self, first_in_list: bool) -> LispObject {
+        // Transition from old-style to new-style: if we see "(`" it used
+        // to mean old-style, which usually works fine because ` should
+        // almost never appear in such a position for new-style.  But
+        // occasionally we need "(`" to mean new style, so we try to
+        // distinguish the two by the fact that we can either write
+        // "( `foo" or "(` foo", where the first intends to use
+        // new-style whereas the second intends to use old-style.
+        if unsafe { !NEW_BACKQUOTE_FLAG } && first_in_list && ascii(self.peek()) == ' ' {
+            old_style_backquotes_error();
+        }
+        let saved = unsafe { NEW_BACKQUOTE_FLAG };
+        unsafe { NEW_BACKQUOTE_FLAG = true };
+        let value = self.read0();
+        unsafe { NEW_BACKQUOTE_FLAG = saved };
+        list!(Qbackquote, value)
+    }
+
+    /// Read the rest of an unquoted form, ,X, ,@X or ,.X.
+    fn read_comma(self, first_in_list: bool) -> LispObject {
+        // Old-style unquotes are only recognized when first on a list,
+        // and when followed by a space.  Because it's more difficult to
+        // peek 2 chars ahead, a new-style ,@ can still not be used
+        // outside of a `, unless it's in the middle of a list.
+        let next = ascii(self.peek());
+        if unsafe { !NEW_BACKQUOTE_FLAG } && first_in_list && (next == ' ' || next == '@') {
+            old_style_backquotes_error();
+        }
+        let c = self.read_char();
+        let comma_type = match ascii(c) {
+            '@' => Qcomma_at,
+            '.' => Qcomma_dot,
+            _ => {
+                if c >= 0 {
+                    self.unread(c);
+                }
+                Qcomma
+            }
+        };
+        list!(comma_type, self.read0())
+    }
+
+    /// Read the rest of a character literal, like ?a or ?\C-a.
+    fn read_character_literal(self) -> LispObject {
+        let mut c = self.read_char_or_eof();
+
+        // Accept `single space' syntax like (list ? x) where the
+        // whitespace character is SPC or TAB.  Other literal whitespace
+        // like NL, CR, and FF are not accepted, as there are
+        // well-established escape sequences for these.
+        if ascii(c) == ' ' || ascii(c) == '\t' {
+            return c.into();
+        }
+
+        if is_one_of(c, "()[]\";") {
+            let literals = unsafe { globals.Vlread_unescaped_character_literals };
+            if !literals.is_list() {
+                wrong_type!(Qlistp, literals);
+            }
+            let literal = LispObject::from(c);
+            if memq(literal, literals).is_nil() {
+                unsafe {
+                    globals.Vlread_unescaped_character_literals =
+                        LispObject::cons(literal, literals)
+                };
+            }
+        }
+
+        if ascii(c) == '\\' {
+            c = self.read_escape(false);
+        }
+        let modifiers = c & CHAR_MODIFIER_MASK;
+        c &= !CHAR_MODIFIER_MASK;
+        if char_byte8_p(c as Codepoint) {
+            c = i32::from(char_to_byte8(c as Codepoint));
+        }
+        c |= modifiers;
+
+        let next = self.peek();
+        if next <= 0o40 || is_one_of(next, "\"';()[]#?`,.") {
+            c.into()
+        } else {
+            invalid_syntax("?");
+        }
+    }
+
+    /// Read the character after a modifier prefix like \C-, which may
+    /// itself be an escape sequence.
+    fn read_modified_char(self) -> i32 {
+        let c = self.read_char_or_eof();
+        if ascii(c) == '\\' {
+            self.read_escape(false)
+        } else {
+            c
+        }
+    }
+
+    /// Read the `-' after the letter of a modifier prefix like \M-.
+    fn read_modifier_dash(self) {
+        if ascii(self.read_char()) != '-' {
+            error!("Invalid escape character syntax");
+        }
+    }
+
+    /// Read an escape sequence, whose `\' was read.  STRINGP is true
+    /// when reading a string, where `\ ' and `\<newline>' stand for no
+    /// character; -1 is returned for them.
+    fn read_escape(self, stringp: bool) -> i32 {
+        let c = self.read_char_or_eof();
+
+        match ascii(c) {
+            'a' => 0o7,
+            'b' => 0o10,
+            'd' => 0o177,
+            'e' => 0o33,
+            'f' => 0o14,
+            'n' => 0o12,
+            'r' => 0o15,
+            't' => 0o11,
+            'v' => 0o13,
+            '\n' => -1,
+            ' ' if stringp => -1,
+
+            'M' => {
+                self.read_modifier_dash();
+                self.read_modified_char() | CHAR_META
+            }
+            'S' => {
+                self.read_modifier_dash();
+                self.read_modified_char() | CHAR_SHIFT
+            }
+            'H' => {
+                self.read_modifier_dash();
+                self.read_modified_char() | CHAR_HYPER
+            }
+            'A' => {
+                self.read_modifier_dash();
+                self.read_modified_char() | CHAR_ALT
+            }
+            's' => {
+                let c = self.read_char();
+                if stringp || ascii(c) != '-' {
+                    self.unread(c);
+                    return i32::from(b' ');
+                }
+                self.read_modified_char() | CHAR_SUPER
+            }
+            'C' | '^' => {
+                if ascii(c) == 'C' {
+                    self.read_modifier_dash();
+                }
+                let c = self.read_modified_char();
+                let base = c & !CHAR_MODIFIER_MASK;
+                if ascii(base) == '?' {
+                    0o177 | (c & CHAR_MODIFIER_MASK)
+                } else if !is_single_byte_char(base as Codepoint) {
+                    c | CHAR_CTL
+                } else if (c & 0o137) >= 0o101 && (c & 0o137) <= 0o132 {
+                    // ASCII control chars are made from letters (both
+                    // cases), as well as the non-letters within
+                    // 0100...0137.
+                    c & (0o37 | !0o177)
+                } else if (c & 0o177) >= 0o100 && (c & 0o177) <= 0o137 {
+                    c & (0o37 | !0o177)
+                } else {
+                    c | CHAR_CTL
+                }
+            }
+
+            '0'..='7' => {
+                // An octal escape, as in ANSI C.
+                let mut i = c - i32::from(b'0');
+                for _ in 1..3 {
+                    let c = self.read_char();
+                    match ascii(c).to_digit(8) {
+                        Some(digit) => i = i * 8 + digit as i32,
+                        None => {
+                            self.unread(c);
+                            break;
+                        }
+                    }
+                }
+                if 0x80 <= i && i < 0x100 {
+                    raw_byte_codepoint(i as u8) as i32
+                } else {
+                    i
+                }
+            }
+
+            'x' => {
+                // A hex escape, as in ANSI C.
+                let mut i: u32 = 0;
+                let mut count = 0;
+                loop {
+                    let c = self.read_char();
+                    let digit = match ascii(c).to_digit(16) {
+                        Some(digit) => digit,
+                        None => {
+                            self.unread(c);
+                            break;
+                        }
+                    };
+                    i = (i << 4) + digit;
+                    // Allow hex escapes as large as ?\xfffffff, because
+                    // some packages use them to denote characters with
+                    // modifiers.
+                    if ((CHAR_META | (CHAR_META - 1)) as u32) < i {
+                        error!("Hex character out of range: \\x{:x}...", i);
+                    }
+                    if count < 3 {
+                        count += 1;
+                    }
+                }
+                if count < 3 && i >= 0x80 {
+                    raw_byte_codepoint(i as u8) as i32
+                } else {
+                    i as i32
+                }
+            }
+
+            'u' | 'U' => {
+                // A Unicode escape.  We only permit them in strings and
+                // characters, not arbitrarily in the source code, as in
+                // some other languages.
+                let count = if ascii(c) == 'u' { 4 } else { 8 };
+                let mut i: u32 = 0;
+                for _ in 0..count {
+                    match ascii(self.read_char()).to_digit(16) {
+                        Some(digit) => i = (i << 4) + digit,
+                        None => error!("Non-hex digit used for Unicode escape"),
+                    }
+                }
+                if i > 0x10_FFFF {
+                    error!("Non-Unicode character: 0x{:x}", i);
+                }
+                i as i32
+            }
+
+            'N' => {
+                // Named character.
+                if ascii(self.read_char()) != '{' {
+                    invalid_syntax("Expected opening brace after \\N");
+                }
+                let mut name: Vec<u8> = Vec::new();
+                let mut whitespace = false;
+                loop {
+                    let c = self.read_char_or_eof();
+                    if ascii(c) == '}' {
+                        break;
+                    }
+                    if !(0 < c && c < 0x80) {
+                        let message =
+                            LispObject::from("Invalid character U+%04X in character name");
+                        xsignal!(Qinvalid_read_syntax, format(&mut [message, c.into()]));
+                    }
+                    // Treat multiple adjacent whitespace characters as a
+                    // single space character.  This makes it easier to
+                    // use character names in e.g. multi-line strings.
+                    if ascii(c).is_ascii_whitespace() || ascii(c) == '\x0B' {
+                        if whitespace {
+                            continue;
+                        }
+                        whitespace = true;
+                        name.push(b' ');
+                    } else {
+                        whitespace = false;
+                        name.push(c as u8);
+                    }
+                    if name.len() > UNICODE_CHARACTER_NAME_LENGTH_BOUND {
+                        invalid_syntax("Character name too long");
+                    }
+                }
+                if name.is_empty() {
+                    invalid_syntax("Empty character name");
+                }
+                character_name_to_code(&name)
+            }
+
+            _ => c,
+        }
+    }
+
+    /// Read the rest of a string, after its opening `"'.
+    fn read_string(self) -> LispObject {
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut nchars = 0;
+        let mut buf = [0; MAX_MULTIBYTE_LENGTH];
+        // Whether an escape sequence or a character forces the string to
+        // be multibyte, or to be unibyte.
+        let mut force_multibyte = false;
+        let mut force_singlebyte = false;
+        // Whether the string starts with \ newline.
+        let mut cancel = false;
+
+        loop {
+            let mut ch = self.read_char_or_eof();
+            if ascii(ch) == '"' {
+                break;
+            }
+
+            if ascii(ch) == '\\' {
+                ch = self.read_escape(true);
+
+                // CH is -1 if \ newline or \ space has just been seen.
+                if ch == -1 {
+                    if bytes.is_empty() {
+                        cancel = true;
+                    }
+                    continue;
+                }
+
+                let mut modifiers = ch & CHAR_MODIFIER_MASK;
+                ch &= !CHAR_MODIFIER_MASK;
+
+                if char_byte8_p(ch as Codepoint) {
+                    force_singlebyte = true;
+                } else if !is_ascii(ch as Codepoint) {
+                    force_multibyte = true;
+                } else {
+                    // Allow `\C- ' and `\C-?'.
+                    if modifiers == CHAR_CTL {
+                        if ascii(ch) == ' ' {
+                            ch = 0;
+                            modifiers = 0;
+                        } else if ascii(ch) == '?' {
+                            ch = 0o177;
+                            modifiers = 0;
+                        }
+                    }
+                    if modifiers & CHAR_SHIFT != 0 {
+                        // The shift modifier is valid only with [A-Za-z].
+                        if ascii(ch).is_ascii_uppercase() {
+                            modifiers &= !CHAR_SHIFT;
+                        } else if ascii(ch).is_ascii_lowercase() {
+                            ch -= i32::from(b'a' - b'A');
+                            modifiers &= !CHAR_SHIFT;
+                        }
+                    }
+                    if modifiers & CHAR_META != 0 {
+                        // Move the meta bit to the right place for a
+                        // string.
+                        modifiers &= !CHAR_META;
+                        ch = raw_byte_codepoint((ch | 0x80) as u8) as i32;
+                        force_singlebyte = true;
+                    }
+                }
+
+                // Any modifiers remaining are invalid.
+                if modifiers != 0 {
+                    error!("Invalid modifier in string");
+                }
+            } else if char_byte8_p(ch as Codepoint) {
+                force_singlebyte = true;
+            } else if !is_ascii(ch as Codepoint) {
+                force_multibyte = true;
+            }
+
+            let len = write_codepoint(&mut buf, ch as Codepoint);
+            bytes.extend_from_slice(&buf[..len]);
+            nchars += 1;
+        }
+
+        // If purifying, and the string starts with \ newline, return
+        // zero instead.  This is for doc strings that we are really
+        // going to find in etc/DOC.nn.nn.
+        if cancel && unsafe { globals.Vpurify_flag.is_not_nil() && globals.Vdoc_file_name.is_nil() }
+        {
+            return LispObject::from(0);
+        }
+
+        if !force_multibyte && force_singlebyte {
+            // BYTES holds raw 8-bit bytes and no multibyte forms, so
+            // convert it to unibyte.
+            bytes = multibyte_as_unibyte(&bytes);
+            nchars = bytes.len();
+        }
+
+        unsafe {
+            make_specified_string(
+                bytes.as_ptr() as *const c_char,
+                nchars as isize,
+                bytes.len() as isize,
+                force_multibyte || bytes.len() != nchars,
+            )
+        }
+    }
+
+    /// Read a symbol or number whose first character is C.  MULTIBYTE
+    /// says whether the stream is multibyte, and SYNTAX what to make of
+    /// the name.
+    fn read_symbol(self, mut c: i32, multibyte: bool, syntax: SymbolSyntax) -> LispObject {
+        let mut bytes: Vec<u8> = Vec::new();
+        let mut nchars = 0;
+        let mut buf = [0; MAX_MULTIBYTE_LENGTH];
+        let mut quoted = false;
+        let start_position = unsafe { readchar_count } - 1;
+
+        loop {
+            if ascii(c) == '\\' {
+                c = self.read_char_or_eof();
+                quoted = true;
+            }
+            if multibyte {
+                let len = write_codepoint(&mut buf, c as Codepoint);
+                bytes.extend_from_slice(&buf[..len]);
+            } else {
+                bytes.push(c as u8);
+            }
+            nchars += 1;
+
+            c = self.read_char();
+            if !is_symbol_char(c) {
+                break;
+            }
+        }
+        self.unread(c);
+
+        if !quoted && syntax == SymbolSyntax::Plain {
+            if let Some(number) = parse_number(&bytes, 10, false) {
+                return number;
+            }
+        }
+
+        if !quoted && multibyte {
+            let (first, len) = multibyte_char_at(&bytes);
+            if unsafe { confusable_symbol_character_p(first as i32) } {
+                let quote = unsafe {
+                    make_specified_string(bytes.as_ptr() as *const c_char, 1, len as isize, true)
+                };
+                xsignal!(
+                    Qinvalid_read_syntax,
+                    LispObject::from("strange quote"),
+                    quote
+                );
+            }
+        }
+
+        if syntax == SymbolSyntax::Plain {
+            if let Some((longhand, longhand_chars)) = expand_shorthand(&bytes, nchars) {
+                bytes = longhand;
+                nchars = longhand_chars;
+            }
+        }
+
+        let make_name = |pure: bool| unsafe {
+            let make = if pure {
+                make_pure_string
+            } else {
+                make_specified_string
+            };
+            make(
+                bytes.as_ptr() as *const c_char,
+                nchars as isize,
+                bytes.len() as isize,
+                multibyte,
+            )
+        };
+
+        let result = if syntax == SymbolSyntax::Uninterned {
+            let name = make_name(unsafe { globals.Vpurify_flag }.is_not_nil());
+            unsafe { Fmake_symbol(name) }
+        } else {
+            // Don't create the string object for the name unless we're
+            // going to retain it in a new symbol.
+            let obarray = unsafe { check_obarray(globals.Vobarray) };
+            let found = unsafe {
+                oblookup(
+                    obarray,
+                    bytes.as_ptr() as *const c_char,
+                    nchars as isize,
+                    bytes.len() as isize,
+                )
+            };
+            if found.is_symbol() {
+                found
+            } else {
+                unsafe { intern_driver(make_name(false), obarray, found) }
+            }
+        };
+
+        if records_symbol_positions(self.stream) {
+            unsafe {
+                globals.Vread_symbol_positions_list = LispObject::cons(
+                    LispObject::cons(result, start_position),
+                    globals.Vread_symbol_positions_list,
+                )
+            };
+        }
+        result
+    }
+
+    /// Read the elements of a list up to its closing `)', or if VECTOR
+    /// is true, those of a vector up to its `]'.
+    fn read_list(self, vector: bool) -> LispObject {
+        let mut val = Qnil;
+        let mut tail: Option<LispCons> = None;
+        let mut doc_reference = DocReference::None;
+        let mut first_in_list = !vector;
+
+        loop {
+            let mut elt = match self.read1(first_in_list) {
+                Token::Object(elt) => elt,
+                Token::Close(']') if vector => return val,
+                Token::Close(_) if vector => invalid_syntax(") or . in a vector"),
+                Token::Close(')') => return val,
+                Token::Close('.') => {
+                    let rest = self.read0();
+                    match tail {
+                        Some(tail) => tail.set_cdr(rest),
+                        None => val = rest,
+                    }
+                    if self.read1(false) != Token::Close(')') {
+                        invalid_syntax(". in wrong context");
+                    }
+                    return match doc_reference {
+                        DocReference::Zero => LispObject::from(0),
+                        DocReference::DocString
+                            if val.as_cons().map_or(false, |c| c.cdr().is_fixnum()) =>
+                        unsafe { get_lazy_doc_string(val) },
+                        _ => val,
+                    };
+                }
+                Token::Close(_) => invalid_syntax("] in a list"),
+            };
+            first_in_list = false;
+
+            // While building, if the list starts with #$, treat it
+            // specially.
+            let file = unsafe { globals.Vload_file_name };
+            if elt.eq(file) && elt.is_not_nil() {
+                if unsafe { globals.Vpurify_flag }.is_not_nil() {
+                    if unsafe { globals.Vdoc_file_name }.is_nil() {
+                        // We have not yet called Snarf-documentation, so
+                        // assume this file is described in the DOC file
+                        // and Snarf-documentation will fill in the right
+                        // value later.  For now, replace the whole list
+                        // with 0.
+                        doc_reference = DocReference::Zero;
+                    } else {
+                        // We have already called Snarf-documentation, so
+                        // make a relative file name for this file, so it
+                        // can be found properly in the installed Lisp
+                        // directory.  We don't use Fexpand_file_name
+                        // because that would make the directory absolute
+                        // now.
+                        elt = unsafe {
+                            concat2(LispObject::from("../lisp/"), Ffile_name_nondirectory(elt))
+                        };
+                    }
+                } else if unsafe { globals.load_force_doc_strings } {
+                    doc_reference = DocReference::DocString;
+                }
+            }
+
+            let cell = LispObject::cons(elt, Qnil);
+            match tail {
+                Some(tail) => tail.set_cdr(cell),
+                None => val = cell,
+            }
+            tail = cell.as_cons();
+        }
+    }
+
+    /// Read the elements of a vector, after its opening `['.  BYTECODE
+    /// is true if this is a byte-code object.
+    fn read_vector(self, bytecode: bool) -> LispVectorRef {
+        let list = self.read_list(true);
+        let size = list.as_cons().map_or(0, LispCons::length);
+        let mut vector = unsafe { Fmake_vector(size.into(), Qnil) }
+            .as_vector()
+            .unwrap();
+
+        let items = list.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off);
+        for (i, mut item) in items.enumerate() {
+            // If `load-force-doc-strings' is t when reading a
+            // lazily-loaded bytecode object, the docstring containing the
+            // bytecode and constants values must be treated as unibyte
+            // and passed to `read', to get the actual bytecode string and
+            // constants vector.
+            if bytecode && unsafe { globals.load_force_doc_strings } {
+                if i == COMPILED_BYTECODE {
+                    if !item.is_string() {
+                        error!("Invalid byte code");
+                    }
+                    // Delay handling the bytecode slot until we know
+                    // whether it is lazily-loaded (we can tell by whether
+                    // the constants slot is nil).
+                    vector.set(COMPILED_CONSTANTS, item);
+                    item = Qnil;
+                } else if i == COMPILED_CONSTANTS {
+                    let mut bytestr = vector.get(COMPILED_CONSTANTS);
+                    if item.is_nil() {
+                        // Coerce the string to unibyte (like
+                        // `string-as-unibyte', but without generating
+                        // extra garbage and guaranteeing no change in the
+                        // contents).
+                        let mut string = LispStringRef::from(bytestr);
+                        unsafe {
+                            string.u.s.size = string.len_bytes();
+                            string.u.s.size_byte = -1;
+                        }
+
+                        let pair = read(LispObject::cons(bytestr, self.stream))
+                            .as_cons()
+                            .unwrap_or_else(|| error!("Invalid byte code"));
+                        bytestr = pair.car();
+                        item = pair.cdr();
+                    }
+                    // Now handle the bytecode slot.
+                    vector.set(COMPILED_BYTECODE, bytestr);
+                } else if i == COMPILED_DOC_STRING {
+                    if let Some(doc) = item.as_string() {
+                        if !doc.is_multibyte() {
+                            item = if self.stream.eq(Qget_emacs_mule_file_char) {
+                                unsafe { Fdecode_coding_string(item, Qemacs_mule, Qnil, Qnil) }
+                            } else {
+                                string_as_multibyte(doc)
+                            };
+                        }
+                    }
+                }
+            }
+            vector.set(i, item);
+        }
+        vector
+    }
+}
+
+/// How `read_symbol` makes a symbol of the name it reads.
+#[derive(Clone, Copy, PartialEq)]
+enum SymbolSyntax {
+    /// It interns the name, once expanded by `read-symbol-shorthands'.
+    Plain,
+    /// It makes an uninterned symbol of it, as for #:foo.
+    Uninterned,
+    /// It interns the name as it is, as for #_foo.
+    Longhand,
+}
+
+/// The length of the prefix of the symbol name BYTES made of the
+/// characters that are left out of shorthands: a name made only of
+/// them, like `--' or `<=', is never a shorthand.
+fn symbol_char_span(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .take_while(|&&b| b"^*+-/<=>_|".contains(&b))
+        .count()
+}
+
+/// If the symbol name BYTES, of NCHARS characters, starts with the
+/// shorthand prefix SHORTHAND, return the name with LONGHAND in its
+/// place, and its number of characters.  Each prefix is given with its
+/// number of characters.
+fn apply_shorthand(
+    bytes: &[u8],
+    nchars: usize,
+    shorthand: (&[u8], usize),
+    longhand: (&[u8], usize),
+) -> Option<(Vec<u8>, usize)> {
+    if !bytes.starts_with(shorthand.0) {
+        return None;
+    }
+    let mut expanded = longhand.0.to_vec();
+    expanded.extend_from_slice(&bytes[shorthand.0.len()..]);
+    Some((expanded, nchars - shorthand.1 + longhand.1))
+}
+
+/// The symbol name BYTES, of NCHARS characters, with the first of the
+/// shorthand prefixes of `read-symbol-shorthands' it starts with
+/// replaced by its longhand, and its number of characters.  Return
+/// `None` if it starts with none of them.  Elements of the variable that
+/// aren't conses of two strings are ignored.
+fn expand_shorthand(bytes: &[u8], nchars: usize) -> Option<(Vec<u8>, usize)> {
+    let shorthands = unsafe { globals.Vread_symbol_shorthands };
+    if shorthands.is_nil() || symbol_char_span(bytes) >= bytes.len() {
+        return None;
+    }
+    shorthands
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe)
+        .filter_map(|pair| {
+            let (shorthand, longhand) = pair.as_cons()?.into();
+            Some((shorthand.as_string()?, longhand.as_string()?))
+        })
+        .filter_map(|(shorthand, longhand)| {
+            apply_shorthand(
+                bytes,
+                nchars,
+                (shorthand.as_slice(), shorthand.len_chars() as usize),
+                (longhand.as_slice(), longhand.len_chars() as usize),
+            )
+        })
+        .next()
+}
+
+/// Whether `read-with-symbol-positions' says to record the positions of
+/// the symbols read from STREAM.
+fn records_symbol_positions(stream: LispObject) -> bool {
+    let positions = unsafe { globals.Vread_with_symbol_positions };
+    positions.is_t() || positions.eq(stream)
+}
+
+/// Whether OBJECT is a string without text properties, which can't
+/// contain other objects.
+fn is_plain_string(object: LispObject) -> bool {
+    object
+        .as_string()
+        .map_or(false, |s| unsafe { s.u.s.intervals }.is_null())
+}
+
+/// The state of `lread--substitute-object-in-subtree'.
+struct Substitution {
+    object: LispObject,
+    placeholder: LispObject,
+    /// Hash table of the subobjects of OBJECT that might be circular, or
+    /// t if any of them might be.
+    completed: LispObject,
+    /// List of the subobjects of OBJECT that were visited already.
+    seen: LispObject,
+}
+
+impl Substitution {
+    /// Substitute the object for the placeholder in SUBTREE, and return
+    /// the result.
+    fn substitute(&mut self, subtree: LispObject) -> LispObject {
+        // If we find the placeholder, return the target object.
+        if subtree.eq(self.placeholder) {
+            return self.object;
+        }
+
+        // For common object types that can't contain other objects,
+        // don't bother looking them up; we're done.
+        if subtree.is_symbol() || subtree.is_number() || is_plain_string(subtree) {
+            return subtree;
+        }
+
+        // If we've been to this node before, don't explore it again.
+        if memq(subtree, self.seen).is_not_nil() {
+            return subtree;
+        }
+
+        // If this node can be the entry point to a cycle, remember that
+        // we've seen it.  It can only be such an entry point if it was
+        // made by #n=, which means that we can find it as a value in
+        // COMPLETED.
+        let completed = self.completed.is_t()
+            || match LispHashTableRef::from(self.completed).lookup(subtree) {
+                HashLookupResult::Found(_) => true,
+                HashLookupResult::Missing(_) => false,
+            };
+        if completed {
+            self.seen = LispObject::cons(subtree, self.seen);
+        }
+
+        if let Some(cons) = subtree.as_cons() {
+            let car = self.substitute(cons.car());
+            cons.set_car(car);
+            let cdr = self.substitute(cons.cdr());
+            cons.set_cdr(cdr);
+        } else if let Some(string) = subtree.as_string() {
+            // Check for text properties in each interval.
+            unsafe {
+                traverse_intervals_noorder(
+                    string.u.s.intervals,
+                    Some(substitute_in_interval),
+                    self as *mut Self as *mut c_void,
+                )
+            };
+        } else if let Some(vectorlike) = subtree.as_vectorlike() {
+            let length = if vectorlike.as_bool_vector().is_some() {
+                // No sub-objects anyway.
+                return subtree;
+            } else if let Some(vector) = vectorlike.as_vector() {
+                vector.len()
+            } else if [
+                pvec_type::PVEC_CHAR_TABLE,
+                pvec_type::PVEC_SUB_CHAR_TABLE,
+                pvec_type::PVEC_COMPILED,
+                pvec_type::PVEC_HASH_TABLE,
+                pvec_type::PVEC_RECORD,
+            ]
+            .iter()
+            .any(|&tp| vectorlike.is_pseudovector(tp))
+            {
+                vectorlike.pseudovector_size() as usize
+            } else {
+                // An unknown pseudovector may contain non-Lisp fields,
+                // so we can't just blindly traverse all its fields.
+                wrong_type!(Qsequencep, subtree);
+            };
+
+            let start = if vectorlike.is_pseudovector(pvec_type::PVEC_SUB_CHAR_TABLE) {
+                2
+            } else {
+                0
+            };
+            let mut slots = unsafe { vectorlike.as_vector_unchecked() };
+            for i in start..length {
+                let item = self.substitute(slots.get(i));
+                slots.set(i, item);
+            }
+        }
+        subtree
+    }
+}
+
+/// Substitute in the text properties of INTERVAL, for
+/// `traverse_intervals_noorder`.  ARG points to the `Substitution`.
+unsafe extern "C" fn substitute_in_interval(interval: INTERVAL, arg: *mut c_void) {
+    let subst = &mut *(arg as *mut Substitution);
+    (*interval).plist = subst.substitute((*interval).plist);
+}
+
+/// In OBJECT, replace every occurrence of PLACEHOLDER with OBJECT.
+/// COMPLETED is a hash table of objects that might be circular, or is t
+/// if any object might be circular.
+#[lisp_fn(name = "lread--substitute-object-in-subtree")]
+pub fn lread_substitute_object_in_subtree(
+    object: LispObject,
+    placeholder: LispObject,
+    completed: LispObject,
+) {
+    let mut subst = Substitution {
+        object,
+        placeholder,
+        completed,
+        seen: Qnil,
+    };
+    // The returned object here is expected to always eq the original.
+    if !subst.substitute(object).eq(object) {
+        error!("Unexpected mutation error in reader");
+    }
+}
+
+/// Set up the global context we need in toplevel read calls, and read
+/// an object from STREAM.  START and END delimit the part to read when
+/// STREAM is a string, or a cons whose car is a string.
+#[no_mangle]
+pub extern "C" fn read_internal_start(
+    stream: LispObject,
+    start: LispObject,
+    end: LispObject,
+) -> LispObject {
+    unsafe {
+        readchar_count = 0;
+        NEW_BACKQUOTE_FLAG = globals.force_new_style_backquotes;
+    }
+    // We can get called from readevalloop which may have set these
+    // already.
+    unsafe {
+        for table in &mut [&mut read_objects_map, &mut read_objects_completed] {
+            if !table.is_hash_table() || LispHashTableRef::from(**table).count > 0 {
+                **table = LispHashTableRef::make_eq().into();
+            }
+        }
+    }
+    if records_symbol_positions(stream) {
+        unsafe { globals.Vread_symbol_positions_list = Qnil };
+    }
+
+    let string = stream
+        .as_string()
+        .or_else(|| stream.as_cons().and_then(|c| c.car().as_string()));
+    if let Some(string) = string {
+        let (mut from, mut to) = (0, 0);
+        unsafe {
+            validate_subarray(
+                string.into(),
+                start,
+                end,
+                string.len_chars(),
+                &mut from,
+                &mut to,
+            );
+            read_from_string_index = from;
+            read_from_string_index_byte = string_char_to_byte(string.into(), from);
+            read_from_string_limit = to;
+        }
+    }
+
+    let object = Reader::new(stream).read0();
+    if records_symbol_positions(stream) {
+        unsafe {
+            globals.Vread_symbol_positions_list = nreverse(globals.Vread_symbol_positions_list)
+        };
+    }
+
+    // Keep the hash tables for the next read if they are still empty.
+    unsafe {
+        for table in &mut [&mut read_objects_map, &mut read_objects_completed] {
+            if table.is_hash_table() && LispHashTableRef::from(**table).count > 0 {
+                **table = Qnil;
+            }
+        }
+    }
+    object
+}
+
+/// Read the rest of a list whose `(' `readevalloop' read from
+/// READCHARFUN, after it set up the hash tables for #N= and #N#.
+#[no_mangle]
+pub extern "C" fn read_toplevel_list(readcharfun: LispObject) -> LispObject {
+    Reader::new(readcharfun).read_list(false)
+}
+
+/// Read one Lisp expression which is represented as text by STRING.
+/// Returns a cons: (OBJECT-READ . FINAL-STRING-INDEX).
+/// FINAL-STRING-INDEX is an integer giving the position of the next
+/// remaining character in STRING.  START and END optionally delimit
+/// a substring of STRING from which to read;  they default to 0 and
+/// (length STRING) respectively.  Negative values are counted from
+/// the end of STRING.
+#[lisp_fn(min = "1")]
+pub fn read_from_string(string: LispStringRef, start: LispObject, end: LispObject) -> LispObject {
+    let object = read_internal_start(string.into(), start, end);
+    LispObject::cons(object, unsafe { read_from_string_index })
+}
+
+#[test]
+fn test_digit_to_number() {
+    assert_eq!(digit_to_number(i32::from(b'7'), 10), Ok(7));
+    assert_eq!(digit_to_number(i32::from(b'f'), 16), Ok(15));
+    assert_eq!(digit_to_number(i32::from(b'Z'), 36), Ok(35));
+    assert_eq!(digit_to_number(i32::from(b'8'), 8), Err(true));
+    assert_eq!(digit_to_number(i32::from(b'-'), 16), Err(false));
+    assert_eq!(digit_to_number(-1, 10), Err(false));
+}
+
+#[test]
+fn test_apply_shorthand() {
+    assert_eq!(symbol_char_span(b"--"), 2);
+    assert_eq!(symbol_char_span(b"-foo"), 1);
+    assert_eq!(
+        apply_shorthand(b"s-trim", 6, (b"s-", 2), (b"string-", 7)),
+        Some((b"string-trim".to_vec(), 11))
+    );
+    assert_eq!(
+        apply_shorthand("é-x".as_bytes(), 3, ("é-".as_bytes(), 2), (b"e-", 2)),
+        Some((b"e-x".to_vec(), 3))
+    );
+    assert_eq!(
+        apply_shorthand(b"trim", 4, (b"s-", 2), (b"string-", 7)),
+        None
+    );
+}
+
+#[test]
+fn test_is_symbol_char() {
+    assert!(is_symbol_char(i32::from(b'a')));
+    assert!(is_symbol_char(i32::from(b'-')));
+    assert!(is_symbol_char(0xE9));
+    assert!(!is_symbol_char(i32::from(b' ')));
+    assert!(!is_symbol_char(i32::from(b'(')));
+    assert!(!is_symbol_char(NO_BREAK_SPACE));
+    assert!(!is_symbol_char(-1));
+}
+
+include!(concat!(env!("OUT_DIR"), "/reader_exports.rs"));
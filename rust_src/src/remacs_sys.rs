@@ -68,12 +68,6 @@ extern "C" {
     // these weren't declared in a header, for example
     pub static Vprocess_alist: Lisp_Object;
     pub fn update_buffer_defaults(objvar: *mut LispObject, newval: LispObject);
-    pub fn concat(
-        nargs: ptrdiff_t,
-        args: *mut LispObject,
-        target_type: Lisp_Type,
-        last_special: bool,
-    ) -> LispObject;
     pub fn map_keymap_item(
         fun: map_keymap_function_t,
         args: LispObject,
@@ -83,12 +77,6 @@ extern "C" {
     );
     pub fn map_keymap_char_table_item(args: LispObject, key: LispObject, val: LispObject);
     pub static initial_obarray: LispObject;
-    pub fn scan_lists(
-        from: EmacsInt,
-        count: EmacsInt,
-        depth: EmacsInt,
-        sexpflag: bool,
-    ) -> LispObject;
     pub fn read_minibuf(
         map: Lisp_Object,
         initial: Lisp_Object,
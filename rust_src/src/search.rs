@@ -1,20 +1,1124 @@
 //! String search routines
 
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::{cmp, slice, str};
+
+use libc::ptrdiff_t;
+use regex::bytes::{Locations, Regex, RegexBuilder};
+
 use remacs_macros::lisp_fn;
 
 use crate::{
+    buffers::LispBufferRef,
+    casefiddle::{upcase, upcase_initials, upcase_initials_region, upcase_region},
+    casetab::{lowercasep, uppercasep},
+    character::char_head_p,
+    chartable::LispCharTableRef,
+    editfns::{goto_char, point, point_min, save_excursion_save},
+    eval::unbind_to,
     lisp::defsubr,
     lisp::LispObject,
-    remacs_sys::{looking_at_1, match_limit, search_command, string_match_1},
+    marker::{buf_bytepos_to_charpos, buf_charpos_to_bytepos},
+    multibyte::{
+        char_to_byte8, unibyte_to_char, unibyte_to_multibyte, write_codepoint, Codepoint,
+        LispStringRef, MAX_MULTIBYTE_LENGTH,
+    },
+    remacs_sys::EmacsInt,
+    remacs_sys::{globals, syntaxcode, Qnil, Qsearch_failed, Qt},
+    remacs_sys::{
+        looking_at_1, make_specified_string, match_limit, move_gap_both, move_if_not_intangible,
+        record_unwind_protect, record_unwind_save_match_data, replace_range,
+        save_excursion_restore, save_restriction_restore, save_restriction_save, search_command,
+        set_point_both, string_byte_to_char, string_char_to_byte, string_match_1, syntax_property,
+        temp_set_point, Fnarrow_to_region, Fset_match_data, Fsubstring,
+    },
+    sequences::concat_lisp,
+    threads::{c_specpdl_index, ThreadState},
 };
 
+/// How many compiled regexps `REGEXP_CACHE` holds, as in search.c.
+const REGEXP_CACHE_SIZE: usize = 20;
+
+/// An Emacs regexp rewritten in the syntax of the `regex` crate.
+#[derive(Debug, PartialEq)]
+struct Translation {
+    pattern: String,
+    /// Whether the pattern uses `^' or `\`', whose meaning depends on
+    /// the text before the match.
+    looks_behind: bool,
+}
+
+/// Translate the Emacs regexp PATTERN for the `regex` crate, or return
+/// `None` if it uses a construct that only the C engine supports:
+/// back references, explicitly numbered groups, character classes, and
+/// anything that depends on the syntax or category tables.
+fn translate_regexp(pattern: &str) -> Option<Translation> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut out = String::with_capacity(pattern.len() + 8);
+    let mut looks_behind = false;
+    // Whether we are where `^' is an anchor and a postfix operator is
+    // an ordinary character: at the start of the pattern or of an
+    // alternative, or right after such an anchor.
+    let mut at_start = true;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let starts = at_start;
+        at_start = false;
+        i += 1;
+
+        match c {
+            '^' if starts => {
+                out.push('^');
+                looks_behind = true;
+                at_start = true;
+            }
+            '$' if ends_alternative(&chars, i) => out.push('$'),
+            '*' | '+' | '?' if starts => push_literal(&mut out, c),
+            '*' | '+' | '?' | '.' => out.push(c),
+            '[' => i = translate_bracket(&chars, i, &mut out)?,
+            '\\' => {
+                let escaped = *chars.get(i)?;
+                i += 1;
+                match escaped {
+                    '(' => {
+                        if chars.get(i) == Some(&'?') {
+                            if chars.get(i + 1) != Some(&':') {
+                                return None;
+                            }
+                            i += 2;
+                            out.push_str("(?:");
+                        } else {
+                            out.push('(');
+                        }
+                        at_start = true;
+                    }
+                    ')' => out.push(')'),
+                    '|' => {
+                        out.push('|');
+                        at_start = true;
+                    }
+                    '{' if !starts => i = translate_interval(&chars, i, &mut out)?,
+                    '`' => {
+                        out.push_str(r"\A");
+                        looks_behind = true;
+                    }
+                    '\'' => out.push_str(r"\z"),
+                    '0'..='9'
+                    | '{'
+                    | 'w'
+                    | 'W'
+                    | 's'
+                    | 'S'
+                    | 'c'
+                    | 'C'
+                    | 'b'
+                    | 'B'
+                    | '<'
+                    | '>'
+                    | '_'
+                    | '=' => return None,
+                    _ => push_literal(&mut out, escaped),
+                }
+            }
+            _ => push_literal(&mut out, c),
+        }
+    }
+
+    Some(Translation {
+        pattern: out,
+        looks_behind,
+    })
+}
+
+/// Whether a `$' before index I of CHARS is an anchor, which it is at
+/// the end of the pattern and before `\)' or `\|'.
+fn ends_alternative(chars: &[char], i: usize) -> bool {
+    match chars.get(i) {
+        None => true,
+        Some('\\') => match chars.get(i + 1) {
+            Some(')') | Some('|') => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn push_literal(out: &mut String, c: char) {
+    let mut buf = [0; 4];
+    out.push_str(&regex::escape(c.encode_utf8(&mut buf)));
+}
+
+/// Translate the bracket expression whose contents start at index I of
+/// CHARS, returning the index after its closing `]'.
+fn translate_bracket(chars: &[char], mut i: usize, out: &mut String) -> Option<usize> {
+    out.push('[');
+    if chars.get(i) == Some(&'^') {
+        out.push('^');
+        i += 1;
+    }
+
+    let first = i;
+    loop {
+        let c = *chars.get(i)?;
+        i += 1;
+        match c {
+            ']' if i - 1 > first => break,
+            '[' if chars.get(i) == Some(&':') => return None,
+            _ => {}
+        }
+
+        push_class_char(out, c);
+        if chars.get(i) == Some(&'-') && chars.get(i + 1).map_or(false, |&c| c != ']') {
+            let to = chars[i + 1];
+            // A reversed range matches nothing, which the `regex' crate
+            // has no way to say.
+            if to < c {
+                return None;
+            }
+            out.push('-');
+            push_class_char(out, to);
+            i += 2;
+        }
+    }
+
+    out.push(']');
+    Some(i)
+}
+
+fn push_class_char(out: &mut String, c: char) {
+    if "\\[]^-&~".contains(c) {
+        out.push('\\');
+    }
+    out.push(c);
+}
+
+/// Translate the interval `\{M,N\}' whose contents start at index I of
+/// CHARS, returning the index after its end.
+fn translate_interval(chars: &[char], mut i: usize, out: &mut String) -> Option<usize> {
+    let mut bounds = String::new();
+    while chars.get(i) != Some(&'\\') {
+        let c = *chars.get(i)?;
+        if !(c.is_ascii_digit() || c == ',') {
+            return None;
+        }
+        bounds.push(c);
+        i += 1;
+    }
+    if chars.get(i + 1) != Some(&'}') || bounds.is_empty() {
+        return None;
+    }
+
+    out.push('{');
+    if bounds.starts_with(',') {
+        out.push('0');
+    }
+    out.push_str(&bounds);
+    out.push('}');
+    Some(i + 2)
+}
+
+/// What a regexp compiles to for matching text of a given kind.
+#[derive(Clone)]
+struct CompiledRegexp {
+    regex: Regex,
+    /// The regexp anchored to the start of the text, for `looking-at'.
+    /// It is missing when the regexp looks behind the match.
+    anchored: Option<Regex>,
+}
+
+#[derive(PartialEq)]
+struct RegexpKey {
+    pattern: Vec<u8>,
+    multibyte: bool,
+    target_multibyte: bool,
+    case_fold: bool,
+}
+
+thread_local! {
+    /// Recently used regexps, most recent first, with what they compiled
+    /// to; `None` means that the C engine has to match them.
+    static REGEXP_CACHE: RefCell<Vec<(RegexpKey, Option<CompiledRegexp>)>> =
+        RefCell::new(Vec::new());
+}
+
+fn compile_regexp(key: &RegexpKey) -> Option<CompiledRegexp> {
+    let pattern = str::from_utf8(&key.pattern).ok()?;
+    if !pattern.is_ascii() && !(key.multibyte && key.target_multibyte) {
+        return None;
+    }
+    let translation = translate_regexp(pattern)?;
+
+    let build = |pattern: &str| {
+        RegexBuilder::new(pattern)
+            .multi_line(true)
+            .case_insensitive(key.case_fold)
+            .unicode(key.target_multibyte)
+            .build()
+            .ok()
+    };
+    let regex = build(&translation.pattern)?;
+    let anchored = if translation.looks_behind {
+        None
+    } else {
+        Some(build(&format!(r"\A(?:{})", translation.pattern))?)
+    };
+
+    Some(CompiledRegexp { regex, anchored })
+}
+
+/// Return REGEXP compiled by the `regex` crate for searching text that
+/// is multibyte if TARGET_MULTIBYTE, or `None` if the C engine should
+/// do the search instead.
+fn rust_regexp(regexp: LispObject, target_multibyte: bool) -> Option<CompiledRegexp> {
+    if unsafe { !globals.prefer_rust_regex_engine || globals.Vsearch_spaces_regexp.is_not_nil() } {
+        return None;
+    }
+    let regexp = regexp.as_string()?;
+    let key = RegexpKey {
+        pattern: regexp.as_slice().to_vec(),
+        multibyte: regexp.is_multibyte(),
+        target_multibyte,
+        case_fold: ThreadState::current_buffer_unchecked()
+            .case_fold_search()
+            .is_not_nil(),
+    };
+
+    REGEXP_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        let entry = match cache.iter().position(|(k, _)| *k == key) {
+            Some(index) => cache.remove(index),
+            None => {
+                let compiled = compile_regexp(&key);
+                cache.truncate(REGEXP_CACHE_SIZE - 1);
+                (key, compiled)
+            }
+        };
+        let compiled = entry.1.clone();
+        cache.insert(0, entry);
+        compiled
+    })
+}
+
+/// REGEXP compiled by the `regex` crate for searching text that is
+/// multibyte if TARGET_MULTIBYTE, whatever `prefer-rust-regex-engine'
+/// says, or `None` if only the C engine can match it.  This is for text
+/// the C engine can't search, like buffer snapshots on other threads.
+pub fn native_regexp(
+    regexp: LispStringRef,
+    target_multibyte: bool,
+    case_fold: bool,
+) -> Option<Regex> {
+    let key = RegexpKey {
+        pattern: regexp.as_slice().to_vec(),
+        multibyte: regexp.is_multibyte(),
+        target_multibyte,
+        case_fold,
+    };
+    compile_regexp(&key).map(|compiled| compiled.regex)
+}
+
+/// The byte offsets of a match and of each of its groups.
+type MatchOffsets = Vec<Option<(usize, usize)>>;
+
+fn match_offsets(locations: &Locations, base: usize) -> MatchOffsets {
+    (0..locations.len())
+        .map(|i| {
+            locations
+                .pos(i)
+                .map(|(start, end)| (start + base, end + base))
+        })
+        .collect()
+}
+
+/// Make OFFSETS the match data, unless `inhibit-changing-match-data'
+/// says not to.  POSITION turns an offset into the position to record,
+/// and BUFFER is the buffer searched, or nil if it was a string.
+fn set_match_data(
+    offsets: &[Option<(usize, usize)>],
+    position: impl Fn(usize) -> EmacsInt,
+    buffer: LispObject,
+) {
+    if unsafe { globals.Vinhibit_changing_match_data }.is_not_nil() {
+        return;
+    }
+
+    let mut data = Vec::with_capacity(2 * offsets.len() + 1);
+    for group in offsets {
+        match *group {
+            Some((start, end)) => {
+                data.push(LispObject::from(position(start)));
+                data.push(LispObject::from(position(end)));
+            }
+            None => {
+                data.push(Qnil);
+                data.push(Qnil);
+            }
+        }
+    }
+    if buffer.is_not_nil() {
+        data.push(buffer);
+    }
+    unsafe { Fset_match_data(LispObject::from(data), Qnil) };
+}
+
+/// The accessible portion of the current buffer BUFFER, which is made
+/// contiguous by moving the gap out of it if need be.
+fn accessible_text<'a>(buffer: LispBufferRef) -> &'a [u8] {
+    if buffer.begv_byte < buffer.gpt_byte() && buffer.gpt_byte() < buffer.zv_byte {
+        unsafe { move_gap_both(buffer.zv, buffer.zv_byte) };
+    }
+    unsafe {
+        slice::from_raw_parts(
+            buffer.byte_pos_addr(buffer.begv_byte),
+            (buffer.zv_byte - buffer.begv_byte) as usize,
+        )
+    }
+}
+
+/// The character position of the byte at OFFSET in the accessible
+/// portion of BUFFER.
+fn buffer_charpos(mut buffer: LispBufferRef, offset: usize) -> ptrdiff_t {
+    unsafe { buf_bytepos_to_charpos(buffer.as_mut(), buffer.begv_byte + offset as ptrdiff_t) }
+}
+
+fn next_char_offset(text: &[u8], offset: usize, multibyte: bool) -> usize {
+    let mut next = offset + 1;
+    if multibyte {
+        while next < text.len() && !char_head_p(text[next]) {
+            next += 1;
+        }
+    }
+    next
+}
+
+fn char_start_offset(text: &[u8], mut offset: usize, multibyte: bool) -> usize {
+    if multibyte {
+        while offset > 0 && offset < text.len() && !char_head_p(text[offset]) {
+            offset -= 1;
+        }
+    }
+    offset
+}
+
+fn rust_string_match(regex: &Regex, string: LispStringRef, start: LispObject) -> LispObject {
+    let pos = if start.is_nil() {
+        0
+    } else {
+        let len = string.len_chars() as EmacsInt;
+        let pos = start.as_fixnum_or_error();
+        if pos < 0 && -pos <= len {
+            len + pos
+        } else if pos < 0 || pos > len {
+            args_out_of_range!(string, start)
+        } else {
+            pos
+        }
+    };
+    let pos_byte = unsafe { string_char_to_byte(string.into(), pos as ptrdiff_t) } as usize;
+
+    let mut locations = regex.locations();
+    let start = match regex.read_captures_at(&mut locations, string.as_slice(), pos_byte) {
+        Some(m) => m.start(),
+        None => return Qnil,
+    };
+
+    let position =
+        |offset| unsafe { string_byte_to_char(string.into(), offset as ptrdiff_t) } as EmacsInt;
+    set_match_data(&match_offsets(&locations, 0), position, Qnil);
+    LispObject::from(position(start))
+}
+
+fn rust_looking_at(anchored: &Regex, buffer: LispBufferRef) -> bool {
+    let text = accessible_text(buffer);
+    let pt = (buffer.pt_byte - buffer.begv_byte) as usize;
+    let mut locations = anchored.locations();
+    if anchored
+        .read_captures_at(&mut locations, &text[pt..], 0)
+        .is_none()
+    {
+        return false;
+    }
+
+    let offsets = match_offsets(&locations, pt);
+    set_match_data(
+        &offsets,
+        |offset| buffer_charpos(buffer, offset) as EmacsInt,
+        buffer.into(),
+    );
+    true
+}
+
+/// What a search found: how many matches, and where the last of them
+/// is, or `None` if there were none.
+type Found = Option<(EmacsInt, MatchOffsets)>;
+
+/// A match ran past the bound of a search.  The C engine stops matching
+/// at the bound while its anchors still see the text after it, which
+/// the `regex` crate can't do, so such a search is left to the C engine.
+struct PastBound;
+
+/// Search forward in TEXT from START for COUNT successive matches for
+/// REGEX that end at or before LIMIT.  Anchors like `$' and `\'' see
+/// the whole of TEXT, as they do in the C engine.
+fn search_forward_offsets(
+    regex: &Regex,
+    text: &[u8],
+    start: usize,
+    limit: usize,
+    count: EmacsInt,
+) -> Result<Found, PastBound> {
+    let mut locations = regex.locations();
+    let mut pos = start;
+    let mut found = 0;
+    while found < count {
+        match regex.read_captures_at(&mut locations, text, pos) {
+            Some(m) if m.end() <= limit => pos = m.end(),
+            Some(m) if m.start() <= limit => return Err(PastBound),
+            _ => break,
+        }
+        found += 1;
+    }
+    if found > 0 {
+        Ok(Some((found, match_offsets(&locations, 0))))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Like `search_forward_offsets`, but search backward for matches that
+/// start at or after LIMIT.  As in the C engine, each match has to end
+/// before the position the search for it started from.
+fn search_backward_offsets(
+    regex: &Regex,
+    text: &[u8],
+    start: usize,
+    limit: usize,
+    count: EmacsInt,
+    multibyte: bool,
+) -> Result<Found, PastBound> {
+    let mut locations = regex.locations();
+    let mut pos = start;
+    let mut found = 0;
+    while found < count {
+        match last_match_start(regex, text, limit, pos, multibyte)? {
+            Some(match_start) => pos = match_start,
+            None => break,
+        }
+        regex.read_captures_at(&mut locations, text, pos);
+        found += 1;
+    }
+    if found > 0 {
+        Ok(Some((found, match_offsets(&locations, 0))))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The last offset between LIMIT and END at which a match for REGEX
+/// starts in TEXT.  The text is scanned back from END in windows that
+/// double in size, so that a match nearby is found quickly.
+fn last_match_start(
+    regex: &Regex,
+    text: &[u8],
+    limit: usize,
+    end: usize,
+    multibyte: bool,
+) -> Result<Option<usize>, PastBound> {
+    let mut upper = end;
+    let mut window = 64;
+    loop {
+        let lower = char_start_offset(
+            text,
+            cmp::max(limit, upper.saturating_sub(window)),
+            multibyte,
+        );
+
+        let mut last = None;
+        let mut from = lower;
+        while let Some(m) = regex.find_at(text, from) {
+            if m.start() > upper {
+                break;
+            }
+            if m.end() > end {
+                return Err(PastBound);
+            }
+            last = Some(m.start());
+            if m.start() >= text.len() {
+                break;
+            }
+            from = next_char_offset(text, m.start(), multibyte);
+        }
+
+        if last.is_some() || lower <= limit {
+            return Ok(last);
+        }
+        upper = lower - 1;
+        window *= 2;
+    }
+}
+
+/// The bytes of the current buffer BUFFER between byte positions FROM
+/// and TO, which must both be on the same side of the gap.
+fn buffer_bytes<'a>(buffer: LispBufferRef, from: ptrdiff_t, to: ptrdiff_t) -> &'a [u8] {
+    unsafe { slice::from_raw_parts(buffer.byte_pos_addr(from), (to - from) as usize) }
+}
+
+fn inc_byte_pos(buffer: LispBufferRef, pos: ptrdiff_t) -> ptrdiff_t {
+    if buffer.multibyte_characters_enabled() {
+        buffer.inc_pos(pos)
+    } else {
+        pos + 1
+    }
+}
+
+fn dec_byte_pos(buffer: LispBufferRef, pos: ptrdiff_t) -> ptrdiff_t {
+    if buffer.multibyte_characters_enabled() {
+        buffer.dec_pos(pos)
+    } else {
+        pos - 1
+    }
+}
+
+/// The offset of the first occurrence of NEEDLE in HAYSTACK, found with
+/// the Boyer-Moore-Horspool algorithm.
+fn find_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let m = needle.len();
+    if m == 0 {
+        return Some(0);
+    }
+    if m == 1 {
+        return haystack.iter().position(|&b| b == needle[0]);
+    }
+
+    // How far the window can move when its last byte is a given byte.
+    let mut shift = [m; 256];
+    for (i, &b) in needle[..m - 1].iter().enumerate() {
+        shift[b as usize] = m - 1 - i;
+    }
+
+    let mut i = 0;
+    while i + m <= haystack.len() {
+        let last = haystack[i + m - 1];
+        if last == needle[m - 1] && haystack[i..i + m - 1] == needle[..m - 1] {
+            return Some(i);
+        }
+        i += shift[last as usize];
+    }
+    None
+}
+
+/// The offset of the last occurrence of NEEDLE in HAYSTACK.  This is
+/// `find_bytes` run from the other end.
+fn rfind_bytes(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    let m = needle.len();
+    if m > haystack.len() {
+        return None;
+    }
+    if m == 0 {
+        return Some(haystack.len());
+    }
+    if m == 1 {
+        return haystack.iter().rposition(|&b| b == needle[0]);
+    }
+
+    // How far the window can move back when its first byte is a given
+    // byte.
+    let mut shift = [m; 256];
+    for (i, &b) in needle.iter().enumerate().skip(1).rev() {
+        shift[b as usize] = i;
+    }
+
+    let mut i = haystack.len() - m;
+    loop {
+        let first = haystack[i];
+        if first == needle[0] && haystack[i + 1..i + m] == needle[1..] {
+            return Some(i);
+        }
+        let s = shift[first as usize];
+        if s > i {
+            return None;
+        }
+        i -= s;
+    }
+}
+
+/// Find PATTERN in the current buffer BUFFER between byte positions FROM
+/// and TO, returning the position of its first occurrence if FORWARD and
+/// of its last one otherwise.  The text on each side of the gap is
+/// searched where it is; only occurrences straddling the gap are looked
+/// for in a copy.
+fn find_in_buffer(
+    buffer: LispBufferRef,
+    pattern: &[u8],
+    from: ptrdiff_t,
+    to: ptrdiff_t,
+    forward: bool,
+) -> Option<ptrdiff_t> {
+    let find = |text: &[u8]| {
+        if forward {
+            find_bytes(text, pattern)
+        } else {
+            rfind_bytes(text, pattern)
+        }
+    };
+
+    let gpt = buffer.gpt_byte();
+    if to <= gpt || from >= gpt {
+        return find(buffer_bytes(buffer, from, to)).map(|i| from + i as ptrdiff_t);
+    }
+
+    let m = pattern.len() as ptrdiff_t;
+    let across_from = cmp::max(from, gpt - m + 1);
+    let across: Vec<u8> = (across_from..cmp::min(to, gpt + m - 1))
+        .map(|pos| buffer.fetch_byte(pos))
+        .collect();
+    let parts = [
+        (from, buffer_bytes(buffer, from, gpt)),
+        (across_from, &across[..]),
+        (gpt, buffer_bytes(buffer, gpt, to)),
+    ];
+
+    let found = |&(base, text): &(ptrdiff_t, &[u8])| find(text).map(|i| base + i as ptrdiff_t);
+    if forward {
+        parts.iter().filter_map(found).next()
+    } else {
+        parts.iter().rev().filter_map(found).next()
+    }
+}
+
+/// The case translation of a search that ignores case.
+struct CaseFold {
+    canon: LispCharTableRef,
+    eqv: LispCharTableRef,
+}
+
+impl CaseFold {
+    /// The case translation for searching the current buffer, or `None`
+    /// if `case-fold-search' is nil.
+    fn current() -> Option<CaseFold> {
+        let buffer = ThreadState::current_buffer_unchecked();
+        if buffer.case_fold_search().is_nil() {
+            return None;
+        }
+        Some(CaseFold {
+            canon: buffer.case_canon_table_.as_char_table()?,
+            eqv: buffer.case_eqv_table_.as_char_table()?,
+        })
+    }
+
+    fn translate(table: LispCharTableRef, c: Codepoint) -> Codepoint {
+        table
+            .get(c as isize)
+            .as_fixnum()
+            .map_or(c, |t| t as Codepoint)
+    }
+
+    fn canon(&self, c: Codepoint) -> Codepoint {
+        Self::translate(self.canon, c)
+    }
+
+    /// Whether C matches only itself.
+    fn is_caseless(&self, c: Codepoint) -> bool {
+        self.canon(c) == c && Self::translate(self.eqv, c) == c
+    }
+}
+
+/// The shifts of the Boyer-Moore-Horspool algorithm for CHARS: how
+/// far the window can move forward when the character at its end is a
+/// given one, and how far it can move back when the character at its
+/// start is.  Characters not in the tables let it move by the length
+/// of CHARS.
+fn char_shifts(chars: &[Codepoint]) -> (HashMap<Codepoint, usize>, HashMap<Codepoint, usize>) {
+    let m = chars.len();
+    let mut forward = HashMap::new();
+    for (i, &c) in chars.iter().enumerate().take(m.saturating_sub(1)) {
+        forward.insert(c, m - 1 - i);
+    }
+    let mut backward = HashMap::new();
+    for (i, &c) in chars.iter().enumerate().skip(1).rev() {
+        backward.insert(c, i);
+    }
+    (forward, backward)
+}
+
+/// The characters of a string in canonical case, for finding them in a
+/// buffer with `find_bytes` and `rfind_bytes`'s algorithm, one
+/// character at a time.
+struct FoldedPattern {
+    chars: Vec<Codepoint>,
+    case_fold: CaseFold,
+    forward_shifts: HashMap<Codepoint, usize>,
+    backward_shifts: HashMap<Codepoint, usize>,
+}
+
+impl FoldedPattern {
+    fn new(chars: Vec<Codepoint>, case_fold: CaseFold) -> FoldedPattern {
+        let (forward_shifts, backward_shifts) = char_shifts(&chars);
+        FoldedPattern {
+            chars,
+            case_fold,
+            forward_shifts,
+            backward_shifts,
+        }
+    }
+
+    /// The character of BUFFER at byte position POS, in canonical case.
+    fn canon_at(&self, buffer: LispBufferRef, pos: ptrdiff_t) -> Codepoint {
+        self.case_fold.canon(buffer.fetch_char(pos) as Codepoint)
+    }
+
+    /// Find the first occurrence in BUFFER between byte positions FROM
+    /// and TO, returning where it starts and ends.  The window is moved
+    /// by the character at its end, and compared from there backward.
+    fn find_forward(
+        &self,
+        buffer: LispBufferRef,
+        from: ptrdiff_t,
+        to: ptrdiff_t,
+    ) -> Option<(ptrdiff_t, ptrdiff_t)> {
+        let m = self.chars.len();
+        // The position of the last character of the window.
+        let mut last = from;
+        for _ in 1..m {
+            if last >= to {
+                return None;
+            }
+            last = inc_byte_pos(buffer, last);
+        }
+
+        while last < to {
+            let c = self.canon_at(buffer, last);
+            if c == self.chars[m - 1] {
+                let mut start = last;
+                let matched = self.chars[..m - 1].iter().rev().all(|&expected| {
+                    start = dec_byte_pos(buffer, start);
+                    self.canon_at(buffer, start) == expected
+                });
+                if matched {
+                    return Some((start, inc_byte_pos(buffer, last)));
+                }
+            }
+            for _ in 0..*self.forward_shifts.get(&c).unwrap_or(&m) {
+                last = inc_byte_pos(buffer, last);
+                if last >= to {
+                    return None;
+                }
+            }
+        }
+        None
+    }
+
+    /// Find the last occurrence in BUFFER between byte positions FROM
+    /// and TO, returning where it starts and ends.  This is
+    /// `find_forward` run from the other end.
+    fn find_backward(
+        &self,
+        buffer: LispBufferRef,
+        from: ptrdiff_t,
+        to: ptrdiff_t,
+    ) -> Option<(ptrdiff_t, ptrdiff_t)> {
+        let m = self.chars.len();
+        // The position of the first character of the window.
+        let mut first = to;
+        for _ in 0..m {
+            if first <= from {
+                return None;
+            }
+            first = dec_byte_pos(buffer, first);
+        }
+
+        loop {
+            let c = self.canon_at(buffer, first);
+            if c == self.chars[0] {
+                let mut end = inc_byte_pos(buffer, first);
+                let matched = self.chars[1..].iter().all(|&expected| {
+                    let matches = self.canon_at(buffer, end) == expected;
+                    end = inc_byte_pos(buffer, end);
+                    matches
+                });
+                if matched {
+                    return Some((first, end));
+                }
+            }
+            for _ in 0..*self.backward_shifts.get(&c).unwrap_or(&m) {
+                if first <= from {
+                    return None;
+                }
+                first = dec_byte_pos(buffer, first);
+            }
+        }
+    }
+}
+
+/// How a literal search compares the text with the string sought.
+enum LiteralPattern {
+    /// The bytes of the string, in the representation of the buffer.
+    Exact(Vec<u8>),
+    /// The characters of the string in canonical case.
+    Folded(FoldedPattern),
+}
+
+impl LiteralPattern {
+    /// How to search for STRING in a buffer whose text is multibyte if
+    /// MULTIBYTE.
+    fn new(string: LispStringRef, multibyte: bool) -> LiteralPattern {
+        if let Some(case_fold) = CaseFold::current() {
+            // The characters of STRING, as the buffer would hold them.
+            let chars = string.chars().map(|c| {
+                if string.is_multibyte() == multibyte {
+                    c
+                } else if multibyte {
+                    unibyte_to_char(c)
+                } else {
+                    Codepoint::from(char_to_byte8(c))
+                }
+            });
+            if !string.chars().all(|c| case_fold.is_caseless(c)) {
+                let chars = chars.map(|c| case_fold.canon(c)).collect();
+                return LiteralPattern::Folded(FoldedPattern::new(chars, case_fold));
+            }
+        }
+
+        let bytes = if string.is_multibyte() == multibyte {
+            string.as_slice().to_vec()
+        } else if multibyte {
+            unibyte_to_multibyte(string.as_slice())
+        } else {
+            string.chars().map(char_to_byte8).collect()
+        };
+        LiteralPattern::Exact(bytes)
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            LiteralPattern::Exact(bytes) => bytes.is_empty(),
+            LiteralPattern::Folded(folded) => folded.chars.is_empty(),
+        }
+    }
+
+    /// Find the first occurrence in BUFFER between byte positions FROM and
+    /// TO if FORWARD, or the last one otherwise, returning where it starts
+    /// and ends.
+    fn find(
+        &self,
+        buffer: LispBufferRef,
+        from: ptrdiff_t,
+        to: ptrdiff_t,
+        forward: bool,
+    ) -> Option<(ptrdiff_t, ptrdiff_t)> {
+        match self {
+            LiteralPattern::Exact(bytes) => find_in_buffer(buffer, bytes, from, to, forward)
+                .map(|start| (start, start + bytes.len() as ptrdiff_t)),
+            LiteralPattern::Folded(folded) if forward => folded.find_forward(buffer, from, to),
+            LiteralPattern::Folded(folded) => folded.find_backward(buffer, from, to),
+        }
+    }
+}
+
+/// Search the current buffer BUFFER for PATTERN from START to LIMIT,
+/// which are offsets from the start of its accessible portion, the way
+/// `search_forward_offsets` and `search_backward_offsets` search for a
+/// regexp.
+fn search_literal_offsets(
+    buffer: LispBufferRef,
+    pattern: &LiteralPattern,
+    start: usize,
+    limit: usize,
+    n: EmacsInt,
+) -> Found {
+    let base = buffer.begv_byte;
+    if pattern.is_empty() {
+        return Some((n.abs(), vec![Some((start, start))]));
+    }
+
+    let mut pos = base + start as ptrdiff_t;
+    let limit = base + limit as ptrdiff_t;
+    let mut found = 0;
+    let mut last = None;
+    while found < n.abs() {
+        let occurrence = if n > 0 {
+            pattern.find(buffer, pos, limit, true)
+        } else {
+            pattern.find(buffer, limit, pos, false)
+        };
+        match occurrence {
+            Some((match_start, match_end)) => {
+                pos = if n > 0 { match_end } else { match_start };
+                last = Some(((match_start - base) as usize, (match_end - base) as usize));
+            }
+            None => break,
+        }
+        found += 1;
+    }
+    last.map(|last| (found, vec![Some(last)]))
+}
+
+/// Do what `search_command' does for STRING, going forward if DIRECTION
+/// is 1 and backward if it is -1, using SEARCH to find the occurrences.
+/// SEARCH is passed the current buffer, the offsets of point and of the
+/// bound of the search from the start of the accessible portion, and
+/// the number of occurrences to find (negative to search backward).
+/// If it returns `PastBound`, the C engine searches for STRING as a
+/// regexp instead.
+fn run_search_command<F>(
+    string: LispObject,
+    bound: LispObject,
+    noerror: LispObject,
+    count: LispObject,
+    direction: EmacsInt,
+    search: F,
+) -> LispObject
+where
+    F: FnOnce(LispBufferRef, usize, usize, EmacsInt) -> Result<Found, PastBound>,
+{
+    let n = if count.is_nil() {
+        direction
+    } else {
+        direction * count.as_fixnum_or_error()
+    };
+
+    let mut buffer = ThreadState::current_buffer_unchecked();
+    let (lim, lim_byte) = if bound.is_nil() {
+        if n > 0 {
+            (buffer.zv, buffer.zv_byte)
+        } else {
+            (buffer.begv, buffer.begv_byte)
+        }
+    } else {
+        let lim = bound.as_fixnum_coerce_marker_or_error() as ptrdiff_t;
+        if (n > 0 && lim < buffer.pt) || (n <= 0 && lim > buffer.pt) {
+            error!("Invalid search bound (wrong side of point)");
+        }
+        if lim > buffer.zv {
+            (buffer.zv, buffer.zv_byte)
+        } else if lim < buffer.begv {
+            (buffer.begv, buffer.begv_byte)
+        } else {
+            (lim, buf_charpos_to_bytepos(buffer.as_mut(), lim))
+        }
+    };
+
+    let start = (buffer.pt_byte - buffer.begv_byte) as usize;
+    let found = if n == 0 {
+        Some((0, vec![Some((start, start))]))
+    } else {
+        match search(buffer, start, (lim_byte - buffer.begv_byte) as usize, n) {
+            Ok(found) => found,
+            Err(PastBound) => unsafe {
+                return search_command(string, bound, noerror, count, direction as i32, 1, false);
+            },
+        }
+    };
+
+    // Like the C engine, leave the match data describing the last match
+    // found even if there were fewer than COUNT.
+    if let Some((_, ref offsets)) = found {
+        set_match_data(
+            offsets,
+            |offset| buffer_charpos(buffer, offset) as EmacsInt,
+            buffer.into(),
+        );
+    }
+
+    match found {
+        Some((found, offsets)) if found == n.abs() => {
+            let (start, end) = offsets[0].unwrap();
+            let offset = if n > 0 { end } else { start };
+            let pos = buffer_charpos(buffer, offset);
+            unsafe { set_point_both(pos, buffer.begv_byte + offset as ptrdiff_t) };
+            LispObject::from(pos)
+        }
+        _ => {
+            if noerror.is_nil() {
+                xsignal!(Qsearch_failed, string);
+            }
+            if !noerror.is_t() {
+                unsafe { set_point_both(lim, lim_byte) };
+            }
+            Qnil
+        }
+    }
+}
+
+/// Search the current buffer for STRING, as `search-forward' does if
+/// DIRECTION is 1 and `search-backward' if it is -1.
+fn search_literal(
+    string: LispObject,
+    bound: LispObject,
+    noerror: LispObject,
+    count: LispObject,
+    direction: EmacsInt,
+) -> LispObject {
+    let string = string.as_string_or_error();
+    run_search_command(
+        string.into(),
+        bound,
+        noerror,
+        count,
+        direction,
+        |buffer, start, limit, n| {
+            let pattern = LiteralPattern::new(string, buffer.multibyte_characters_enabled());
+            Ok(search_literal_offsets(buffer, &pattern, start, limit, n))
+        },
+    )
+}
+
+/// Search the current buffer for REGEXP, as `re-search-forward' does if
+/// DIRECTION is 1 and `re-search-backward' if it is -1.
+fn search_regexp(
+    regexp: LispObject,
+    bound: LispObject,
+    noerror: LispObject,
+    count: LispObject,
+    direction: EmacsInt,
+) -> LispObject {
+    let regex = match rust_regexp(
+        regexp,
+        ThreadState::current_buffer_unchecked().multibyte_characters_enabled(),
+    ) {
+        Some(compiled) => compiled.regex,
+        None => {
+            return unsafe {
+                search_command(regexp, bound, noerror, count, direction as i32, 1, false)
+            };
+        }
+    };
+
+    run_search_command(
+        regexp,
+        bound,
+        noerror,
+        count,
+        direction,
+        |buffer, start, limit, n| {
+            let text = accessible_text(buffer);
+            if n > 0 {
+                search_forward_offsets(&regex, text, start, limit, n)
+            } else {
+                let multibyte = buffer.multibyte_characters_enabled();
+                search_backward_offsets(&regex, text, start, limit, -n, multibyte)
+            }
+        },
+    )
+}
+
 /// Return t if text after point matches regular expression REGEXP.
 /// This function modifies the match data that `match-beginning',
 /// `match-end' and `match-data' access; save and restore the match
 /// data if you want to preserve them.
 #[lisp_fn]
 pub fn looking_at(regexp: LispObject) -> LispObject {
-    unsafe { looking_at_1(regexp, false) }
+    let buffer = ThreadState::current_buffer_unchecked();
+    match rust_regexp(regexp, buffer.multibyte_characters_enabled()) {
+        Some(CompiledRegexp {
+            anchored: Some(anchored),
+            ..
+        }) => rust_looking_at(&anchored, buffer).into(),
+        _ => unsafe { looking_at_1(regexp, false) },
+    }
 }
 
 /// Return t if text after point matches regular expression REGEXP.
@@ -27,6 +1131,85 @@ pub fn posix_looking_at(regexp: LispObject) -> LispObject {
     unsafe { looking_at_1(regexp, true) }
 }
 
+/// REGEXP as a shy group followed by SUFFIX.
+fn grouped_regexp(regexp: LispStringRef, suffix: &str) -> LispObject {
+    concat_lisp(&mut ["\\(?:".into(), regexp.into(), suffix.into()])
+}
+
+/// Return non-nil if text before point matches regular expression REGEXP.
+/// Like `looking-at' except matches before point, and is slower.
+/// LIMIT if non-nil speeds up the search by specifying a minimum
+/// starting position, to avoid checking matches that would start
+/// before LIMIT.
+///
+/// If GREEDY is non-nil, extend the match backwards as far as
+/// possible, stopping when a single additional previous character
+/// cannot be part of a match for REGEXP.  When the match is
+/// extended, its starting position is allowed to occur before
+/// LIMIT.
+///
+/// As a general recommendation, try to avoid using `looking-back'
+/// wherever possible, since it is slow.
+#[lisp_fn(min = "1")]
+pub fn looking_back(regexp: LispStringRef, limit: LispObject, greedy: bool) -> bool {
+    let start = point();
+    let found = re_search_backward(grouped_regexp(regexp, "\\)\\="), limit, Qt, Qnil);
+    if found.is_nil() {
+        return false;
+    }
+    goto_char(start.into());
+    if !greedy {
+        return true;
+    }
+
+    // Extend the match one character at a time, for as long as the
+    // text from there to the original match still matches.
+    let count = c_specpdl_index();
+    unsafe {
+        record_unwind_protect(Some(save_excursion_restore), save_excursion_save());
+        record_unwind_protect(Some(save_restriction_restore), save_restriction_save());
+        Fnarrow_to_region(point_min().into(), start.into());
+    }
+    let to_end = grouped_regexp(regexp, "\\)\\'");
+    let mut pos = found.as_fixnum_or_error();
+    while pos > point_min() {
+        goto_char((pos - 1).into());
+        if looking_at(to_end).is_nil() {
+            break;
+        }
+        pos -= 1;
+    }
+    goto_char(pos.into());
+    looking_at(to_end);
+    unbind_to(count, Qnil);
+    true
+}
+
+/// Return a regexp string which matches exactly STRING and nothing else.
+#[lisp_fn]
+pub fn regexp_quote(string: LispStringRef) -> LispObject {
+    let mut quoted = Vec::with_capacity(2 * string.len_bytes() as usize);
+    let mut backslashes_added = 0;
+    // The special characters are all ASCII, so the bytes of multibyte
+    // characters are copied unchanged.
+    for &byte in string.as_slice() {
+        if b"[*.\\?+^$".contains(&byte) {
+            quoted.push(b'\\');
+            backslashes_added += 1;
+        }
+        quoted.push(byte);
+    }
+
+    unsafe {
+        make_specified_string(
+            quoted.as_ptr() as *const libc::c_char,
+            string.len_chars() + backslashes_added,
+            quoted.len() as ptrdiff_t,
+            string.is_multibyte(),
+        )
+    }
+}
+
 /// Return index of start of first match for REGEXP in STRING, or nil.
 /// Matching ignores case if `case-fold-search' is non-nil.
 /// If third arg START is non-nil, start search at that index in STRING.
@@ -38,6 +1221,11 @@ pub fn posix_looking_at(regexp: LispObject) -> LispObject {
 /// matched by the parenthesis constructions in REGEXP.
 #[lisp_fn(min = "2")]
 pub fn string_match(regexp: LispObject, string: LispObject, start: LispObject) -> LispObject {
+    if let Some(s) = string.as_string() {
+        if let Some(compiled) = rust_regexp(regexp, s.is_multibyte()) {
+            return rust_string_match(&compiled.regex, s, start);
+        }
+    }
     unsafe { string_match_1(regexp, string, start, false) }
 }
 
@@ -79,7 +1267,7 @@ pub fn search_backward(
     noerror: LispObject,
     count: LispObject,
 ) -> LispObject {
-    unsafe { search_command(string, bound, noerror, count, -1, 0, false) }
+    search_literal(string, bound, noerror, count, -1)
 }
 
 /// Search forward from point for STRING.
@@ -108,7 +1296,7 @@ pub fn search_forward(
     noerror: LispObject,
     count: LispObject,
 ) -> LispObject {
-    unsafe { search_command(string, bound, noerror, count, 1, 0, false) }
+    search_literal(string, bound, noerror, count, 1)
 }
 
 /// Search backward from point for regular expression REGEXP.
@@ -124,7 +1312,7 @@ pub fn re_search_backward(
     noerror: LispObject,
     count: LispObject,
 ) -> LispObject {
-    unsafe { search_command(regexp, bound, noerror, count, -1, 1, false) }
+    search_regexp(regexp, bound, noerror, count, -1)
 }
 
 /// Search forward from point for regular expression REGEXP.
@@ -158,7 +1346,7 @@ pub fn re_search_forward(
     noerror: LispObject,
     count: LispObject,
 ) -> LispObject {
-    unsafe { search_command(regexp, bound, noerror, count, 1, 1, false) }
+    search_regexp(regexp, bound, noerror, count, 1)
 }
 
 /// Search backward from point for match for regular expression REGEXP.
@@ -249,4 +1437,637 @@ pub fn match_end(subexp: LispObject) -> LispObject {
     unsafe { match_limit(subexp, false) }
 }
 
+/// A copy of the registers of the last match.
+struct MatchRegisters {
+    groups: Vec<(ptrdiff_t, ptrdiff_t)>,
+}
+
+impl MatchRegisters {
+    fn current() -> MatchRegisters {
+        let regs = &ThreadState::current_thread().m_search_regs;
+        let groups = if regs.num_regs == 0 {
+            Vec::new()
+        } else {
+            let len = regs.num_regs as usize;
+            let (start, end) = unsafe {
+                (
+                    slice::from_raw_parts(regs.start, len),
+                    slice::from_raw_parts(regs.end, len),
+                )
+            };
+            start.iter().cloned().zip(end.iter().cloned()).collect()
+        };
+        MatchRegisters { groups }
+    }
+
+    fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Where group N of the last match starts and ends, or `None` if it
+    /// didn't match.
+    fn group(&self, n: usize) -> Option<(ptrdiff_t, ptrdiff_t)> {
+        self.groups.get(n).cloned().filter(|&(start, _)| start >= 0)
+    }
+}
+
+/// How `replace-match' changes the case of the replacement text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CaseAction {
+    NoChange,
+    AllCaps,
+    CapInitial,
+}
+
+/// Decide how to change the case of the replacement for the text made of
+/// CHARS.
+fn case_action(chars: impl Iterator<Item = Codepoint>) -> CaseAction {
+    let is_word =
+        |c: Codepoint| unsafe { syntax_property(c as libc::c_int, false) } == syntaxcode::Sword;
+
+    // Whether any word of the text is more than one letter long.
+    let mut some_multiletter_word = false;
+    let mut some_lowercase = false;
+    let mut some_uppercase = false;
+    let mut some_nonuppercase_initial = false;
+    let mut prevc = Codepoint::from(b'\n');
+
+    for c in chars {
+        if lowercasep(c) {
+            // Cannot be all caps if any original char is lower case.
+            some_lowercase = true;
+            if is_word(prevc) {
+                some_multiletter_word = true;
+            } else {
+                some_nonuppercase_initial = true;
+            }
+        } else if uppercasep(c) {
+            some_uppercase = true;
+            if is_word(prevc) {
+                some_multiletter_word = true;
+            }
+        } else if !is_word(prevc) {
+            // If the initial is a caseless word constituent, treat that
+            // like a lowercase initial.
+            some_nonuppercase_initial = true;
+        }
+        prevc = c;
+    }
+
+    if !some_lowercase && some_multiletter_word {
+        // Convert to all caps if the old text is all caps and has at
+        // least one multiletter word.
+        CaseAction::AllCaps
+    } else if !some_nonuppercase_initial && some_multiletter_word {
+        // Capitalize each word, if the old text has all capitalized
+        // words.
+        CaseAction::CapInitial
+    } else if !some_nonuppercase_initial && some_uppercase {
+        // Should x -> yz, operating on X, give Yz or YZ?  We'll assume
+        // the latter.
+        CaseAction::AllCaps
+    } else {
+        CaseAction::NoChange
+    }
+}
+
+/// A part of the replacement text of `replace-match'.
+#[derive(Debug, PartialEq)]
+enum ReplacementPart {
+    /// The characters of NEWTEXT between two character indices.
+    Literal(usize, usize),
+    /// What group N of the last match matched, or nothing if it didn't.
+    Group(usize),
+}
+
+/// Split the replacement text made of CHARS into its parts, for a match
+/// with GROUPS groups of which SUB is replaced.  When replacing text
+/// matched in a string (IN_STRING), `\N' for a group the match doesn't
+/// have stands for nothing and `\?' for itself; in a buffer, both are
+/// errors.
+fn parse_replacement(
+    chars: &[Codepoint],
+    sub: usize,
+    groups: usize,
+    in_string: bool,
+) -> Vec<ReplacementPart> {
+    let mut parts = Vec::new();
+    let mut literal_start = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        if chars[i] != Codepoint::from(b'\\') {
+            i += 1;
+            continue;
+        }
+
+        // The part the escape stands for, and where the literal text
+        // resumes after it.
+        let (part, resume) = match chars.get(i + 1).and_then(|&c| char::from_u32(c)) {
+            Some('&') => (Some(ReplacementPart::Group(sub)), i + 2),
+            Some(c @ '1'..='9') if (c as usize - '0' as usize) < groups => (
+                Some(ReplacementPart::Group(c as usize - '0' as usize)),
+                i + 2,
+            ),
+            Some('1'..='9') if in_string => (None, i + 2),
+            // The second backslash is kept.
+            Some('\\') => (None, i + 1),
+            Some('?') if in_string => {
+                i += 2;
+                continue;
+            }
+            _ => error!("Invalid use of `\\' in replacement text"),
+        };
+
+        if literal_start < i {
+            parts.push(ReplacementPart::Literal(literal_start, i));
+        }
+        parts.extend(part);
+        literal_start = resume;
+        i += 2;
+    }
+
+    if literal_start < chars.len() {
+        parts.push(ReplacementPart::Literal(literal_start, chars.len()));
+    }
+    parts
+}
+
+/// The group of the last match that `replace-match' replaces, as the
+/// argument SUBEXP says.
+fn replaced_group(subexp: LispObject, regs: &MatchRegisters) -> usize {
+    if subexp.is_nil() {
+        return 0;
+    }
+    let n = subexp.as_fixnum_or_error();
+    if n < 0 || n >= regs.len() as EmacsInt {
+        args_out_of_range!(subexp, regs.len() as EmacsInt);
+    }
+    n as usize
+}
+
+/// Do what `replace-match' does when replacing text matched in STRING.
+fn replace_match_in_string(
+    newtext: LispStringRef,
+    fixedcase: bool,
+    literal: bool,
+    string: LispStringRef,
+    regs: &MatchRegisters,
+    sub: usize,
+) -> LispObject {
+    let (start, end) = regs.groups[sub];
+    if start < 0 || start > end || end > string.len_chars() {
+        args_out_of_range!(start, end);
+    }
+
+    let substring =
+        |from: ptrdiff_t, to: LispObject| unsafe { Fsubstring(string.into(), from.into(), to) };
+
+    let action = if fixedcase {
+        CaseAction::NoChange
+    } else {
+        let multibyte = string.is_multibyte();
+        case_action(
+            string
+                .chars()
+                .skip(start as usize)
+                .take((end - start) as usize)
+                .map(|c| if multibyte { c } else { unibyte_to_char(c) }),
+        )
+    };
+
+    let mut newtext = LispObject::from(newtext);
+    if !literal {
+        let text = newtext.as_string_or_error();
+        let chars: Vec<Codepoint> = text.chars().collect();
+        let mut pieces: Vec<LispObject> = parse_replacement(&chars, sub, regs.len(), true)
+            .into_iter()
+            .filter_map(|part| match part {
+                ReplacementPart::Literal(from, to) => {
+                    Some(unsafe { Fsubstring(newtext, from.into(), to.into()) })
+                }
+                ReplacementPart::Group(n) => {
+                    regs.group(n).map(|(from, to)| substring(from, to.into()))
+                }
+            })
+            .collect();
+        newtext = concat_lisp(&mut pieces);
+    }
+
+    newtext = match action {
+        CaseAction::AllCaps => upcase(newtext),
+        CaseAction::CapInitial => upcase_initials(newtext),
+        CaseAction::NoChange => newtext,
+    };
+
+    concat_lisp(&mut [substring(0, start.into()), newtext, substring(end, Qnil)])
+}
+
+/// The text of the current buffer BUFFER that NEWTEXT stands for, made
+/// of bytes in the representation of the buffer.
+fn substitute_in_buffer(
+    mut buffer: LispBufferRef,
+    newtext: LispStringRef,
+    regs: &MatchRegisters,
+    sub: usize,
+) -> LispObject {
+    let multibyte = buffer.multibyte_characters_enabled();
+    let chars: Vec<Codepoint> = newtext.chars().collect();
+    let parts = parse_replacement(&chars, sub, regs.len(), false);
+    if parts == [ReplacementPart::Literal(0, chars.len())] {
+        return newtext.into();
+    }
+
+    let mut bytes = Vec::with_capacity(newtext.len_bytes() as usize * 2 + 100);
+    let mut buf = [0; MAX_MULTIBYTE_LENGTH];
+    for part in parts {
+        match part {
+            ReplacementPart::Literal(from, to) => {
+                for &c in &chars[from..to] {
+                    if multibyte {
+                        let c = if newtext.is_multibyte() {
+                            c
+                        } else {
+                            unibyte_to_char(c)
+                        };
+                        let len = write_codepoint(&mut buf, c);
+                        bytes.extend_from_slice(&buf[..len]);
+                    } else if newtext.is_multibyte() {
+                        bytes.push(char_to_byte8(c));
+                    } else {
+                        bytes.push(c as u8);
+                    }
+                }
+            }
+            ReplacementPart::Group(n) => {
+                if let Some((start, end)) = regs.group(n) {
+                    let start_byte = buf_charpos_to_bytepos(buffer.as_mut(), start);
+                    let end_byte = buf_charpos_to_bytepos(buffer.as_mut(), end);
+                    bytes.extend((start_byte..end_byte).map(|pos| buffer.fetch_byte(pos)));
+                }
+            }
+        }
+    }
+
+    unsafe {
+        make_specified_string(
+            bytes.as_ptr() as *const libc::c_char,
+            -1,
+            bytes.len() as ptrdiff_t,
+            multibyte,
+        )
+    }
+}
+
+/// Do what `replace-match' does when replacing text matched in the
+/// current buffer.
+fn replace_match_in_buffer(
+    newtext: LispStringRef,
+    fixedcase: bool,
+    literal: bool,
+    regs: &MatchRegisters,
+    sub: usize,
+) {
+    let mut buffer = ThreadState::current_buffer_unchecked();
+    let (start, end) = regs.groups[sub];
+    if start < buffer.begv || start > end || end > buffer.zv {
+        args_out_of_range!(start, end);
+    }
+
+    let action = if fixedcase {
+        CaseAction::NoChange
+    } else {
+        let mut pos_byte = buf_charpos_to_bytepos(buffer.as_mut(), start);
+        let multibyte = buffer.multibyte_characters_enabled();
+        case_action((start..end).map(|_| {
+            let c = buffer.fetch_char(pos_byte) as Codepoint;
+            pos_byte = inc_byte_pos(buffer, pos_byte);
+            if multibyte {
+                c
+            } else {
+                unibyte_to_char(c)
+            }
+        }))
+    };
+
+    // Record point, counting from the end of the buffer if it is after
+    // the start of the match.
+    let opoint = if buffer.pt >= end {
+        buffer.pt - buffer.zv
+    } else if buffer.pt > start {
+        end - buffer.zv
+    } else {
+        buffer.pt
+    };
+
+    let newtext = if literal {
+        newtext.into()
+    } else {
+        substitute_in_buffer(buffer, newtext, regs, sub)
+    };
+
+    // The functions below modify the buffer, so they could trigger
+    // modification hooks.  If these hooks clobber the match data we
+    // error out since otherwise this will result in confusing bugs.
+    let newpoint = start + newtext.as_string_or_error().len_chars();
+    unsafe { replace_range(start, end, newtext, true, false, true, true) };
+
+    // Update the saved data to match the adjustment made by
+    // replace_range.
+    let change = newpoint - end;
+    let sub_start = if start >= end { start + change } else { start };
+    let sub_end = end + change;
+
+    let adjusted = MatchRegisters::current();
+    let match_start = adjusted.groups.get(sub).map_or(start, |&(s, _)| s);
+    match action {
+        CaseAction::AllCaps => upcase_region(match_start.into(), newpoint.into(), false),
+        CaseAction::CapInitial => upcase_initials_region(match_start.into(), newpoint.into()),
+        CaseAction::NoChange => (),
+    }
+
+    let after = MatchRegisters::current();
+    if after.len() != regs.len() || after.groups.get(sub) != Some(&(sub_start, sub_end)) {
+        error!("Match data clobbered by buffer modification hooks");
+    }
+
+    // Put point back where it was in the text, then move it "officially"
+    // to the end of the replacement.
+    unsafe {
+        let pos = if opoint <= 0 {
+            opoint + buffer.zv
+        } else {
+            opoint
+        };
+        temp_set_point(buffer.as_mut(), pos);
+        move_if_not_intangible(newpoint);
+    }
+}
+
+/// Replace text matched by last search with NEWTEXT.
+/// Leave point at the end of the replacement text.
+///
+/// If optional second arg FIXEDCASE is non-nil, do not alter the case of
+/// the replacement text.  Otherwise, maybe capitalize the whole text, or
+/// maybe just word initials, based on the replaced text.  If the replaced
+/// text has only capital letters and has at least one multiletter word,
+/// convert NEWTEXT to all caps.  Otherwise if all words are capitalized
+/// in the replaced text, capitalize each word in NEWTEXT.
+///
+/// If optional third arg LITERAL is non-nil, insert NEWTEXT literally.
+/// Otherwise treat `\\' as special:
+///   `\\&' in NEWTEXT means substitute original matched text.
+///   `\\N' means substitute what matched the Nth `\\(...\\)'.
+///        If Nth parens didn't match, substitute nothing.
+///   `\\\\' means insert one `\\'.
+///   `\\?' is treated literally
+///        (for compatibility with `query-replace-regexp').
+///   Any other character following `\\' signals an error.
+/// Case conversion does not apply to these substitutions.
+///
+/// If optional fourth argument STRING is non-nil, it should be a string
+/// to act on; this should be the string on which the previous match was
+/// done via `string-match'.  In this case, `replace-match' creates and
+/// returns a new string, made by copying STRING and replacing the part of
+/// STRING that was matched (the original STRING itself is not altered).
+///
+/// The optional fifth argument SUBEXP specifies a subexpression;
+/// it says to replace just that subexpression with NEWTEXT,
+/// rather than replacing the entire matched text.
+/// This is, in a vague sense, the inverse of using `\\N' in NEWTEXT;
+/// `\\N' copies subexp N into NEWTEXT, but using N as SUBEXP puts
+/// NEWTEXT in place of subexp N.
+/// This is useful only after a regular expression search or match,
+/// since only regular expressions have distinguished subexpressions.
+#[lisp_fn(min = "1")]
+pub fn replace_match(
+    newtext: LispStringRef,
+    fixedcase: bool,
+    literal: bool,
+    string: Option<LispStringRef>,
+    subexp: LispObject,
+) -> LispObject {
+    let regs = MatchRegisters::current();
+    if regs.len() == 0 {
+        error!("`replace-match' called before any match found");
+    }
+
+    let sub = replaced_group(subexp, &regs);
+    match string {
+        Some(string) => replace_match_in_string(newtext, fixedcase, literal, string, &regs, sub),
+        None => {
+            replace_match_in_buffer(newtext, fixedcase, literal, &regs, sub);
+            Qnil
+        }
+    }
+}
+
+/// Replace all matches for REGEXP with REP in STRING.
+///
+/// Return a new string containing the replacements.
+///
+/// Optional arguments FIXEDCASE, LITERAL and SUBEXP are like the
+/// arguments with the same names of function `replace-match'.  If START
+/// is non-nil, start replacements at that index in STRING.
+///
+/// REP is either a string used as the NEWTEXT arg of `replace-match' or a
+/// function.  If it is a function, it is called with the actual text of each
+/// match, and its value is used as the replacement text.  When REP is called,
+/// the match data are the result of matching REGEXP against a substring
+/// of STRING, the same substring that is the actual text of the match which
+/// is passed to REP as its argument.
+///
+/// To replace only the first match (if any), make REGEXP match up to \\\\='
+/// and replace a sub-expression, e.g.
+///   (replace-regexp-in-string "\\\\(foo\\\\).*\\\\\\='" "bar" " foo foo" nil nil 1)
+///     => " bar foo"
+#[lisp_fn(min = "3")]
+pub fn replace_regexp_in_string(
+    regexp: LispObject,
+    rep: LispObject,
+    string: LispStringRef,
+    fixedcase: bool,
+    literal: bool,
+    subexp: LispObject,
+    start: Option<EmacsInt>,
+) -> LispObject {
+    let len = string.len_chars();
+    let mut start = start.unwrap_or(0) as ptrdiff_t;
+    // The parts of STRING that weren't matched, interspersed with the
+    // replacements of those that were.
+    let mut pieces = Vec::new();
+    let substring = |s: LispStringRef, from: ptrdiff_t, to: ptrdiff_t| unsafe {
+        Fsubstring(s.into(), from.into(), to.into())
+    };
+
+    let count = c_specpdl_index();
+    unsafe { record_unwind_save_match_data() };
+
+    while start < len && string_match(regexp, string.into(), start.into()).is_not_nil() {
+        let (mb, mut me) = MatchRegisters::current().groups[0];
+        // If we matched the empty string, make sure we advance by one
+        // char.
+        if me == mb {
+            me = cmp::min(len, mb + 1);
+        }
+
+        // Generate a replacement for the matched substring, operating
+        // only on the substring to minimize string consing.
+        let matched = substring(string, mb, me).as_string_or_error();
+        string_match(regexp, matched.into(), Qnil);
+        let newtext = if rep.is_string() {
+            rep
+        } else {
+            let (from, to) = MatchRegisters::current().groups[0];
+            call!(rep, substring(matched, from, to))
+        };
+        let regs = MatchRegisters::current();
+        let sub = replaced_group(subexp, &regs);
+        pieces.push(substring(string, start, mb));
+        pieces.push(replace_match_in_string(
+            newtext.as_string_or_error(),
+            fixedcase,
+            literal,
+            matched,
+            &regs,
+            sub,
+        ));
+        start = me;
+    }
+
+    unbind_to(count, Qnil);
+    pieces.push(substring(string, start, len));
+    concat_lisp(&mut pieces)
+}
+
+#[no_mangle]
+pub extern "C" fn rust_syms_of_search() {
+    /// Non-nil means match regexps with the `regex' crate where possible.
+    /// This applies to `string-match', `looking-at', `re-search-forward'
+    /// and `re-search-backward'.  Regexps using constructs the crate
+    /// lacks, such as back references, syntax classes and character
+    /// classes, are still matched by the built-in engine.  Case is folded
+    /// by Unicode rules rather than by the buffer's case table, and
+    /// `.' does not match raw bytes in multibyte text.
+    defvar_bool!(prefer_rust_regex_engine, "prefer-rust-regex-engine", false);
+}
+
 include!(concat!(env!("OUT_DIR"), "/search_exports.rs"));
+
+#[cfg(test)]
+fn translated(pattern: &str) -> Option<String> {
+    translate_regexp(pattern).map(|t| t.pattern)
+}
+
+#[test]
+fn test_translate_regexp_operators() {
+    assert_eq!(translated(r"a\(b\|c\)*"), Some("a(b|c)*".to_string()));
+    assert_eq!(translated(r"\(?:ab\)+?"), Some("(?:ab)+?".to_string()));
+    assert_eq!(
+        translated(r"x\{2,3\}y\{,4\}"),
+        Some("x{2,3}y{0,4}".to_string())
+    );
+    // Characters special only to the `regex' crate are quoted.
+    assert_eq!(translated("(a)|{b}"), Some(r"\(a\)\|\{b\}".to_string()));
+}
+
+#[test]
+fn test_translate_regexp_context() {
+    // `^' and `$' are anchors only at the edges of an alternative.
+    assert_eq!(translated(r"^a\|^b$"), Some("^a|^b$".to_string()));
+    assert_eq!(translated("a^b$c"), Some(r"a\^b\$c".to_string()));
+    // So are postfix operators ordinary characters there.
+    assert_eq!(translated("*a"), Some(r"\*a".to_string()));
+    assert_eq!(translated(r"^+\(*a\)"), Some(r"^\+(\*a)".to_string()));
+
+    assert!(translate_regexp("^a").unwrap().looks_behind);
+    assert!(translate_regexp(r"\`a\'").unwrap().looks_behind);
+    assert!(!translate_regexp(r"a\'").unwrap().looks_behind);
+}
+
+#[test]
+fn test_translate_regexp_brackets() {
+    assert_eq!(translated("[]a-z]"), Some(r"[\]a-z]".to_string()));
+    assert_eq!(translated("[^]-]"), Some(r"[^\]\-]".to_string()));
+    assert_eq!(translated(r"[\^]"), Some(r"[\\\^]".to_string()));
+}
+
+#[test]
+fn test_translate_regexp_unsupported() {
+    for pattern in &[
+        r"\(a\)\1",
+        r"\(?1:a\)",
+        "[[:alpha:]]",
+        "[z-a]",
+        r"\w",
+        r"\sw",
+        r"\ca",
+        r"\bfoo",
+        r"\<foo\>",
+        r"\_<foo",
+        r"\=",
+        "foo\\",
+        "[a",
+    ] {
+        assert_eq!(translated(pattern), None, "{}", pattern);
+    }
+}
+
+#[test]
+fn test_find_bytes() {
+    assert_eq!(find_bytes(b"abcabcab", b"cab"), Some(2));
+    assert_eq!(find_bytes(b"abcabcab", b"a"), Some(0));
+    assert_eq!(find_bytes(b"abcabcab", b""), Some(0));
+    assert_eq!(find_bytes(b"abcabcab", b"cba"), None);
+    assert_eq!(find_bytes(b"ab", b"abc"), None);
+}
+
+#[test]
+fn test_rfind_bytes() {
+    assert_eq!(rfind_bytes(b"abcabcab", b"cab"), Some(5));
+    assert_eq!(rfind_bytes(b"abcabcab", b"a"), Some(6));
+    assert_eq!(rfind_bytes(b"abcabcab", b""), Some(8));
+    assert_eq!(rfind_bytes(b"abcabcab", b"cba"), None);
+    assert_eq!(rfind_bytes(b"ab", b"abc"), None);
+}
+
+#[test]
+fn test_char_shifts() {
+    let chars: Vec<Codepoint> = "abcab".chars().map(|c| c as Codepoint).collect();
+    let (forward, backward) = char_shifts(&chars);
+    let shift = |table: &HashMap<Codepoint, usize>, c| table.get(&(c as Codepoint)).cloned();
+    assert_eq!(shift(&forward, 'a'), Some(1));
+    assert_eq!(shift(&forward, 'b'), Some(3));
+    assert_eq!(shift(&forward, 'c'), Some(2));
+    assert_eq!(shift(&backward, 'a'), Some(3));
+    assert_eq!(shift(&backward, 'b'), Some(1));
+    assert_eq!(shift(&backward, 'c'), Some(2));
+    assert_eq!(shift(&forward, 'x'), None);
+
+    let (forward, backward) = char_shifts(&['é' as Codepoint]);
+    assert!(forward.is_empty() && backward.is_empty());
+}
+
+#[test]
+fn test_parse_replacement() {
+    use self::ReplacementPart::*;
+
+    let chars = |text: &str| text.chars().map(Codepoint::from).collect::<Vec<_>>();
+    assert_eq!(
+        parse_replacement(&chars("abc"), 0, 1, false),
+        vec![Literal(0, 3)]
+    );
+    assert_eq!(
+        parse_replacement(&chars(r"<\&\1>"), 1, 3, false),
+        vec![Literal(0, 1), Group(1), Group(1), Literal(5, 6)]
+    );
+    assert_eq!(
+        parse_replacement(&chars(r"a\\b"), 0, 1, false),
+        vec![Literal(0, 1), Literal(2, 4)]
+    );
+    // Groups that the match doesn't have stand for nothing in strings.
+    assert_eq!(
+        parse_replacement(&chars(r"a\2\?"), 0, 2, true),
+        vec![Literal(0, 1), Literal(3, 5)]
+    );
+}
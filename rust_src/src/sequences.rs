@@ -0,0 +1,928 @@
+//! Generic sequence functions: concatenation, mapping and sorting of
+//! lists, vectors, strings and bool-vectors.
+
+use libc::{c_char, ptrdiff_t};
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    lisp::defsubr,
+    lisp::LispObject,
+    lists::{
+        circular_list, delete_if, inorder, list, CarIter, LispCons, LispConsCircularChecks,
+        LispConsEndChecks, ListError,
+    },
+    math::{arithcompare, ArithComparison},
+    multibyte::{
+        char_to_byte8, is_ascii, multibyte_char_at, raw_byte_codepoint, write_codepoint, Codepoint,
+        LispStringRef, MAX_5_BYTE_CHAR, MAX_MULTIBYTE_LENGTH,
+    },
+    numbers::MOST_POSITIVE_FIXNUM,
+    obarray::intern,
+    remacs_sys::{add_text_properties_from_list, make_composition_value_copy},
+    remacs_sys::{copy_char_table, make_uninit_bool_vector},
+    remacs_sys::{make_specified_string, memory_full, string_char_to_byte, text_property_list},
+    remacs_sys::{
+        EmacsInt, Lisp_Type, Qarrayp, Qintegerp, Qlistp, Qnil, Qnumber_or_marker_p, Qsequencep,
+        Qstringp,
+    },
+    remacs_sys::{Fmake_vector, Fnconc, Frecord},
+    strings::string_lessp,
+    vectors::{LispBoolVecIterator, LispBoolVecRef, LispVecIterator, LispVectorRef},
+    vectors::{LispVecSlotsIterator, LispVectorlikeSlotsRef},
+};
+
+/// A sequence that can be taken apart element by element.
+#[derive(Clone, Copy)]
+pub enum LispSequence {
+    List(LispObject),
+    Vector(LispVectorRef),
+    Compiled(LispVectorlikeSlotsRef),
+    String(LispStringRef),
+    BoolVector(LispBoolVecRef),
+}
+
+impl LispObject {
+    /// Return OBJECT as a sequence, if it is a list, vector, string,
+    /// bool-vector or byte-code function.
+    pub fn as_sequence(self) -> Option<LispSequence> {
+        if self.is_nil() || self.is_cons() {
+            Some(LispSequence::List(self))
+        } else if let Some(s) = self.as_string() {
+            Some(LispSequence::String(s))
+        } else if let Some(v) = self.as_vectorlike() {
+            if let Some(v) = v.as_vector() {
+                Some(LispSequence::Vector(v))
+            } else if let Some(bv) = v.as_bool_vector() {
+                Some(LispSequence::BoolVector(bv))
+            } else {
+                v.as_compiled().map(LispSequence::Compiled)
+            }
+        } else {
+            None
+        }
+    }
+
+    pub fn as_sequence_or_error(self) -> LispSequence {
+        self.as_sequence()
+            .unwrap_or_else(|| wrong_type!(Qsequencep, self))
+    }
+}
+
+impl LispSequence {
+    /// The number of elements.  Signals an error for dotted or circular
+    /// lists.
+    pub fn len(self) -> usize {
+        match self {
+            LispSequence::List(l) => l.as_cons().map_or(0, |c| c.length()),
+            LispSequence::Vector(v) => v.len(),
+            LispSequence::Compiled(v) => v.len(),
+            LispSequence::String(s) => s.len_chars() as usize,
+            LispSequence::BoolVector(bv) => bv.len(),
+        }
+    }
+
+    /// Iterate over the elements.  The elements of a unibyte string are
+    /// its bytes.
+    pub fn iter(&self) -> SequenceIter {
+        match *self {
+            LispSequence::List(l) => {
+                SequenceIter::List(l.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off))
+            }
+            LispSequence::Vector(ref v) => SequenceIter::Vector(v.iter()),
+            LispSequence::Compiled(ref v) => SequenceIter::Compiled(v.iter()),
+            LispSequence::String(ref s) => SequenceIter::String(s.as_slice(), s.is_multibyte()),
+            LispSequence::BoolVector(ref bv) => SequenceIter::BoolVector(bv.iter()),
+        }
+    }
+}
+
+impl From<LispSequence> for LispObject {
+    fn from(seq: LispSequence) -> Self {
+        match seq {
+            LispSequence::List(l) => l,
+            LispSequence::Vector(v) => v.into(),
+            LispSequence::Compiled(v) => v.into(),
+            LispSequence::String(s) => s.into(),
+            LispSequence::BoolVector(bv) => bv.into(),
+        }
+    }
+}
+
+pub enum SequenceIter<'a> {
+    List(CarIter),
+    Vector(LispVecIterator<'a>),
+    Compiled(LispVecSlotsIterator<'a>),
+    String(&'a [u8], bool),
+    BoolVector(LispBoolVecIterator<'a>),
+}
+
+impl<'a> Iterator for SequenceIter<'a> {
+    type Item = LispObject;
+
+    fn next(&mut self) -> Option<LispObject> {
+        match self {
+            SequenceIter::List(it) => it.next(),
+            SequenceIter::Vector(it) => it.next(),
+            SequenceIter::Compiled(it) => it.next(),
+            SequenceIter::BoolVector(it) => it.next(),
+            SequenceIter::String(bytes, multibyte) => {
+                if bytes.is_empty() {
+                    return None;
+                }
+                let (c, len) = if *multibyte {
+                    multibyte_char_at(bytes)
+                } else {
+                    (u32::from(bytes[0]), 1)
+                };
+                *bytes = &bytes[len..];
+                Some(LispObject::from(c))
+            }
+        }
+    }
+}
+
+/// The kind of sequence `concat_sequences` makes.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ConcatTarget {
+    List,
+    Vector,
+    String,
+}
+
+/// Concatenate the elements of ARGS into a new sequence of type TARGET.
+/// If LAST_SPECIAL, the last element of ARGS is not copied but used as
+/// the tail of the new list, as in `append'.
+pub fn concat_sequences(
+    args: &[LispObject],
+    target: ConcatTarget,
+    last_special: bool,
+) -> LispObject {
+    // In append, the last arg isn't treated like the others.
+    let (args, last_tail) = match args.split_last() {
+        Some((&last, rest)) if last_special => (rest, last),
+        _ => (args, Qnil),
+    };
+
+    let sequences: Vec<LispSequence> = args.iter().map(|a| a.as_sequence_or_error()).collect();
+
+    let result_len = sequences.iter().fold(0usize, |total, seq| {
+        let total = total + seq.len();
+        if total > MOST_POSITIVE_FIXNUM as usize {
+            unsafe { memory_full(usize::max_value()) };
+        }
+        total
+    });
+
+    match target {
+        ConcatTarget::List => sequences
+            .iter()
+            .flat_map(|seq| seq.iter())
+            .collect::<Vec<LispObject>>()
+            .into_iter()
+            .rev()
+            .fold(last_tail, |tail, elt| LispObject::cons(elt, tail)),
+        ConcatTarget::Vector => {
+            let mut vector = unsafe { Fmake_vector(LispObject::from(result_len), Qnil) }
+                .as_vector()
+                .unwrap();
+            for (i, elt) in sequences.iter().flat_map(|seq| seq.iter()).enumerate() {
+                vector.set(i, elt);
+            }
+            vector.into()
+        }
+        ConcatTarget::String => concat_strings(&sequences, result_len),
+    }
+}
+
+/// Concatenate SEQUENCES, which have RESULT_LEN elements, into a string.
+fn concat_strings(sequences: &[LispSequence], result_len: usize) -> LispObject {
+    // Determine whether the result should be a multibyte string.
+    let mut some_multibyte = false;
+    for seq in sequences {
+        match *seq {
+            LispSequence::String(s) => some_multibyte |= s.is_multibyte(),
+            LispSequence::BoolVector(bv) if bv.len() > 0 => {
+                wrong_type!(Qintegerp, bv.get(0));
+            }
+            _ => {
+                for elt in seq.iter() {
+                    let c = elt.as_character_or_error();
+                    if !is_ascii(c) && c <= MAX_5_BYTE_CHAR {
+                        some_multibyte = true;
+                    }
+                }
+            }
+        }
+    }
+
+    // When we make a multibyte string, we can't copy text properties
+    // while concatenating each string because the length of resulting
+    // string can't be decided until we finish the whole concatenation.
+    // So, we record strings that have text properties to be copied
+    // here, and copy the text properties after the concatenation.
+    let mut textprops: Vec<(LispStringRef, usize)> = Vec::new();
+    let mut bytes: Vec<u8> = Vec::with_capacity(result_len);
+    let mut buf = [0; MAX_MULTIBYTE_LENGTH];
+    let mut toindex = 0;
+
+    for seq in sequences {
+        if let LispSequence::String(s) = *seq {
+            if !unsafe { s.u.s.intervals }.is_null() {
+                textprops.push((s, toindex));
+            }
+            if s.is_multibyte() == some_multibyte {
+                // Between strings of the same kind, copy fast.
+                bytes.extend_from_slice(s.as_slice());
+            } else {
+                // Copy a single-byte string to a multibyte string.
+                for &b in s.as_slice() {
+                    let len = write_codepoint(&mut buf, raw_byte_codepoint(b));
+                    bytes.extend_from_slice(&buf[..len]);
+                }
+            }
+            toindex += s.len_chars() as usize;
+            continue;
+        }
+
+        for elt in seq.iter() {
+            let c = elt.as_character_or_error();
+            if some_multibyte {
+                let len = write_codepoint(&mut buf, c);
+                bytes.extend_from_slice(&buf[..len]);
+            } else if is_ascii(c) {
+                bytes.push(c as u8);
+            } else {
+                bytes.push(char_to_byte8(c));
+            }
+            toindex += 1;
+        }
+    }
+
+    let val = unsafe {
+        make_specified_string(
+            bytes.as_ptr() as *const c_char,
+            result_len as ptrdiff_t,
+            bytes.len() as ptrdiff_t,
+            some_multibyte,
+        )
+    };
+
+    let mut last_to_end = None;
+    for (s, to) in textprops {
+        let len = s.len_chars();
+        let props = unsafe {
+            text_property_list(s.into(), LispObject::from(0), LispObject::from(len), Qnil)
+        };
+        // If successive arguments have properties, be sure that the
+        // value of `composition' property be the copy.
+        if last_to_end == Some(to) {
+            unsafe { make_composition_value_copy(props) };
+        }
+        unsafe { add_text_properties_from_list(val, props, LispObject::from(to)) };
+        last_to_end = Some(to + len as usize);
+    }
+
+    val
+}
+
+/// C interface to `concat_sequences`.
+#[no_mangle]
+pub unsafe extern "C" fn concat(
+    nargs: ptrdiff_t,
+    args: *mut LispObject,
+    target_type: Lisp_Type,
+    last_special: bool,
+) -> LispObject {
+    let args = std::slice::from_raw_parts(args, nargs as usize);
+    let target = match target_type {
+        Lisp_Type::Lisp_Cons => ConcatTarget::List,
+        Lisp_Type::Lisp_String => ConcatTarget::String,
+        _ => ConcatTarget::Vector,
+    };
+    concat_sequences(args, target, last_special)
+}
+
+#[no_mangle]
+pub extern "C" fn concat2(s1: LispObject, s2: LispObject) -> LispObject {
+    concat_sequences(&[s1, s2], ConcatTarget::String, false)
+}
+
+#[no_mangle]
+pub extern "C" fn concat3(s1: LispObject, s2: LispObject, s3: LispObject) -> LispObject {
+    concat_sequences(&[s1, s2, s3], ConcatTarget::String, false)
+}
+
+/// Concatenate all the arguments and make the result a list.
+/// The result is a list whose elements are the elements of all the arguments.
+/// Each argument may be a list, vector or string.
+/// The last argument is not copied, just used as the tail of the new list.
+/// usage: (append &rest SEQUENCES)
+#[lisp_fn]
+pub fn append(args: &mut [LispObject]) -> LispObject {
+    concat_sequences(args, ConcatTarget::List, true)
+}
+
+/// Concatenate all the arguments and make the result a string.
+/// The result is a string whose elements are the elements of all the arguments.
+/// Each argument may be a string or a list or vector of characters (integers).
+/// usage: (concat &rest SEQUENCES)
+#[lisp_fn(name = "concat", c_name = "concat")]
+pub fn concat_lisp(args: &mut [LispObject]) -> LispObject {
+    concat_sequences(args, ConcatTarget::String, false)
+}
+
+/// Concatenate all the arguments and make the result a vector.
+/// The result is a vector whose elements are the elements of all the arguments.
+/// Each argument may be a list, vector or string.
+/// usage: (vconcat &rest SEQUENCES)
+#[lisp_fn]
+pub fn vconcat(args: &mut [LispObject]) -> LispObject {
+    concat_sequences(args, ConcatTarget::Vector, false)
+}
+
+/// Return a copy of a list, vector, string, char-table or record.
+/// The elements of a list, vector or record are not copied; they are
+/// shared with the original.
+/// If the original sequence is empty, this function may return
+/// the same empty object instead of its copy.
+#[lisp_fn]
+pub fn copy_sequence(arg: LispObject) -> LispObject {
+    if arg.is_nil() {
+        return arg;
+    }
+
+    if let Some(v) = arg.as_vectorlike() {
+        if let Some(mut record) = v.as_record() {
+            let slots = record.as_mut_slice();
+            return unsafe { Frecord(slots.len() as ptrdiff_t, slots.as_mut_ptr()) };
+        }
+
+        if arg.is_char_table() {
+            return unsafe { copy_char_table(arg) };
+        }
+
+        if let Some(bv) = v.as_bool_vector() {
+            let copy = unsafe { make_uninit_bool_vector(bv.len() as EmacsInt) };
+            let mut copy_ref = copy.as_bool_vector().unwrap();
+            copy_ref.as_mut_slice().copy_from_slice(bv.as_slice());
+            return copy;
+        }
+    }
+
+    let target = if arg.is_cons() {
+        ConcatTarget::List
+    } else if arg.is_vector() {
+        ConcatTarget::Vector
+    } else if arg.is_string() {
+        ConcatTarget::String
+    } else {
+        wrong_type!(Qsequencep, arg)
+    };
+
+    concat_sequences(&[arg], target, false)
+}
+
+/// Return a copy of ALIST.
+/// This is an alist which represents the same mapping from objects to objects,
+/// but does not share the alist structure with ALIST.
+/// The objects mapped (cars and cdrs of elements of the alist)
+/// are shared, however.
+/// Elements of ALIST that are not conses are also shared.
+#[lisp_fn]
+pub fn copy_alist(alist: LispObject) -> LispObject {
+    if alist.is_nil() {
+        return alist;
+    }
+
+    let copy = concat_sequences(&[alist], ConcatTarget::List, false);
+    for tail in copy.iter_tails(LispConsEndChecks::off, LispConsCircularChecks::off) {
+        if let Some(elt) = tail.car().as_cons() {
+            tail.set_car(LispObject::cons(elt.car(), elt.cdr()));
+        }
+    }
+    copy
+}
+
+/// Make a copy of TREE.
+/// If TREE is a cons cell, this recursively copies both its car and its cdr.
+/// Contrast to `copy-sequence', which copies only along the cdrs.  With second
+/// argument VECP, this copies vectors as well as conses.
+#[lisp_fn(min = "1")]
+pub fn copy_tree(tree: LispObject, vecp: bool) -> LispObject {
+    if tree.is_cons() {
+        let mut result = Qnil;
+        let mut last: Option<LispCons> = None;
+        let mut tail = tree;
+
+        while let Some(cell) = tail.as_cons() {
+            let new_cell = LispObject::cons(copy_tree(cell.car(), vecp), Qnil);
+            match last {
+                Some(prev) => prev.set_cdr(new_cell),
+                None => result = new_cell,
+            }
+            last = new_cell.as_cons();
+            tail = cell.cdr();
+        }
+
+        if let Some(prev) = last {
+            prev.set_cdr(copy_tree(tail, vecp));
+        }
+        result
+    } else if vecp && tree.is_vector() {
+        let copy = copy_sequence(tree);
+        let mut vector = copy.as_vector().unwrap();
+        for i in 0..vector.len() {
+            let elt = vector.get(i);
+            vector.set(i, copy_tree(elt, vecp));
+        }
+        copy
+    } else {
+        tree
+    }
+}
+
+/// Reverse LIST in place and return the new head.  The list is checked
+/// for cycles and a dotted end before any cdr is changed, so an
+/// invalid list is left untouched.
+fn nreverse_list(list: LispObject) -> LispObject {
+    for tail in list.iter_tails_checked() {
+        match tail {
+            Ok(_) => {}
+            Err(ListError::Circular { .. }) => circular_list(list),
+            Err(ListError::Dotted(_)) => wrong_type!(Qlistp, list),
+        }
+    }
+
+    let mut prev = Qnil;
+    let mut tail = list;
+    while let Some(cell) = tail.as_cons() {
+        tail = cell.cdr();
+        cell.set_cdr(prev);
+        prev = cell.into();
+    }
+    prev
+}
+
+/// Reverse the bits of BV in place.
+fn nreverse_bool_vector(mut bv: LispBoolVecRef) {
+    let size = bv.len();
+    for i in 0..size / 2 {
+        let a = bv.get(i).is_not_nil();
+        let b = bv.get(size - i - 1).is_not_nil();
+        bv.set(i, b);
+        bv.set(size - i - 1, a);
+    }
+}
+
+/// Return a new string with the characters of STRING in reverse order.
+fn reverse_string(string: LispStringRef) -> LispObject {
+    let bytes = string.as_slice();
+    let mut reversed = vec![0; bytes.len()];
+
+    if string.is_multibyte() {
+        let mut end = bytes.len();
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let (_, len) = multibyte_char_at(rest);
+            end -= len;
+            reversed[end..end + len].copy_from_slice(&rest[..len]);
+            rest = &rest[len..];
+        }
+    } else {
+        reversed.copy_from_slice(bytes);
+        reversed.reverse();
+    }
+
+    unsafe {
+        make_specified_string(
+            reversed.as_ptr() as *const c_char,
+            string.len_chars(),
+            reversed.len() as ptrdiff_t,
+            string.is_multibyte(),
+        )
+    }
+}
+
+/// Reverse order of items in a list, vector or string SEQ.
+/// If SEQ is a list, it should be nil-terminated.
+/// This function may destructively modify SEQ to produce the value.
+#[lisp_fn]
+pub fn nreverse(seq: LispObject) -> LispObject {
+    if seq.is_nil() || seq.is_cons() {
+        nreverse_list(seq)
+    } else if let Some(s) = seq.as_string() {
+        reverse_string(s)
+    } else if let Some(mut v) = seq.as_vector() {
+        v.as_mut_slice().reverse();
+        seq
+    } else if let Some(bv) = seq.as_bool_vector() {
+        nreverse_bool_vector(bv);
+        seq
+    } else {
+        wrong_type!(Qarrayp, seq)
+    }
+}
+
+/// Return the reversed copy of list, vector, or string SEQ.
+/// See also the function `nreverse', which is used more often.
+#[lisp_fn]
+pub fn reverse(seq: LispObject) -> LispObject {
+    if seq.is_nil() || seq.is_cons() {
+        let mut new = Qnil;
+        for tail in seq.iter_tails_checked() {
+            match tail {
+                Ok(cell) => new = LispObject::cons(cell.car(), new),
+                Err(ListError::Circular { .. }) => circular_list(seq),
+                Err(ListError::Dotted(_)) => wrong_type!(Qlistp, seq),
+            }
+        }
+        new
+    } else if let Some(s) = seq.as_string() {
+        reverse_string(s)
+    } else if seq.is_vector() {
+        nreverse(copy_sequence(seq))
+    } else if seq.is_bool_vector() {
+        let copy = copy_sequence(seq);
+        nreverse_bool_vector(copy.as_bool_vector().unwrap());
+        copy
+    } else {
+        wrong_type!(Qsequencep, seq)
+    }
+}
+
+/// Call FUNCTION on each element of SEQUENCE in turn, and pass the
+/// results to CONSUMER.  Return the number of elements visited.
+///
+/// FUNCTION may modify SEQUENCE.  At most as many elements as SEQUENCE
+/// had at the start are visited, and the elements of lists and strings
+/// are looked up afresh at each step, so this neither loops forever nor
+/// reads stale data.  Values passed to CONSUMER are not otherwise
+/// protected from GC; store them in a Lisp object that is.
+pub fn map_sequence<F>(function: LispObject, sequence: LispObject, mut consumer: F) -> usize
+where
+    F: FnMut(LispObject),
+{
+    if sequence.is_char_table() {
+        wrong_type!(Qlistp, sequence);
+    }
+    let seq = sequence.as_sequence_or_error();
+    let len = seq.len();
+
+    let mut mapped = 0;
+    match seq {
+        LispSequence::List(list) => {
+            let mut tail = list;
+            while mapped < len {
+                let cons = match tail.as_cons() {
+                    Some(cons) => cons,
+                    None => break,
+                };
+                consumer(call!(function, cons.car()));
+                tail = cons.cdr();
+                mapped += 1;
+            }
+        }
+        LispSequence::String(s) => {
+            while mapped < len && (mapped as isize) < s.len_chars() {
+                let pos_byte = unsafe { string_char_to_byte(s.into(), mapped as isize) } as usize;
+                let bytes = &s.as_slice()[pos_byte..];
+                let c = if s.is_multibyte() {
+                    multibyte_char_at(bytes).0
+                } else {
+                    u32::from(bytes[0])
+                };
+                consumer(call!(function, LispObject::from(c)));
+                mapped += 1;
+            }
+        }
+        LispSequence::Vector(v) => {
+            while mapped < len {
+                consumer(call!(function, v.get(mapped)));
+                mapped += 1;
+            }
+        }
+        LispSequence::Compiled(v) => {
+            while mapped < len {
+                consumer(call!(function, v.get(mapped)));
+                mapped += 1;
+            }
+        }
+        LispSequence::BoolVector(bv) => {
+            while mapped < len {
+                consumer(call!(function, bv.get(mapped)));
+                mapped += 1;
+            }
+        }
+    }
+    mapped
+}
+
+/// Map FUNCTION over SEQUENCE, storing the results at every STEPth slot
+/// of a new vector, which is filled with FILL.  Return the vector and
+/// the number of results.  The vector keeps the results safe from GC
+/// while FUNCTION runs.
+fn map_into_vector(
+    function: LispObject,
+    sequence: LispObject,
+    step: usize,
+    fill: LispObject,
+) -> (LispVectorRef, usize) {
+    let len = sequence.as_sequence_or_error().len();
+    let size = (len * step).saturating_sub(step - 1);
+    let mut results = unsafe { Fmake_vector(LispObject::from(size), fill) }
+        .as_vector()
+        .unwrap();
+    let mut i = 0;
+    let mapped = map_sequence(function, sequence, |val| {
+        results.set(i, val);
+        i += step;
+    });
+    (results, mapped)
+}
+
+/// Apply FUNCTION to each element of SEQUENCE for side effects only.
+/// Unlike `mapcar', don't accumulate the results.  Return SEQUENCE.
+/// SEQUENCE may be a list, a vector, a bool-vector, or a string.
+#[lisp_fn]
+pub fn mapc(function: LispObject, sequence: LispObject) -> LispObject {
+    map_sequence(function, sequence, |_| ());
+    sequence
+}
+
+/// Apply FUNCTION to each element of SEQUENCE, and make a list of the results.
+/// The result is a list just as long as SEQUENCE.
+/// SEQUENCE may be a list, a vector, a bool-vector, or a string.
+#[lisp_fn]
+pub fn mapcar(function: LispObject, sequence: LispObject) -> LispObject {
+    // The list is built as we go, so the results are reachable from
+    // RESULT, which is on the stack.
+    let mut result = Qnil;
+    let mut last: Option<LispCons> = None;
+    map_sequence(function, sequence, |val| {
+        let cell = LispObject::cons(val, Qnil);
+        match last {
+            Some(last) => last.set_cdr(cell),
+            None => result = cell,
+        }
+        last = cell.as_cons();
+    });
+    result
+}
+
+/// Apply FUNCTION to each element of SEQUENCE, and concatenate
+/// the results by altering them (using `nconc').
+/// SEQUENCE may be a list, a vector, a bool-vector, or a string.
+#[lisp_fn]
+pub fn mapcan(function: LispObject, sequence: LispObject) -> LispObject {
+    let (mut results, mapped) = map_into_vector(function, sequence, 1, Qnil);
+    let args = &mut results.as_mut_slice()[..mapped];
+    unsafe { Fnconc(args.len() as ptrdiff_t, args.as_mut_ptr()) }
+}
+
+/// Apply FUNCTION to each element of SEQUENCE, and concat the results as strings.
+/// In between each pair of results, stick in SEPARATOR.  Thus, " " as
+/// SEPARATOR results in spaces between the values returned by FUNCTION.
+/// SEQUENCE may be a list, a vector, a bool-vector, or a string.
+#[lisp_fn]
+pub fn mapconcat(function: LispObject, sequence: LispObject, separator: LispObject) -> LispObject {
+    let (results, mapped) = map_into_vector(function, sequence, 2, separator);
+    let nargs = (2 * mapped).saturating_sub(1);
+    concat_sequences(&results.as_slice()[..nargs], ConcatTarget::String, false)
+}
+
+/// Return a new vector of ITEMS.
+pub fn vector_of(items: &[LispObject]) -> LispObject {
+    let mut vector = unsafe { Fmake_vector(LispObject::from(items.len()), Qnil) }
+        .as_vector()
+        .unwrap();
+    for (i, &item) in items.iter().enumerate() {
+        vector.set(i, item);
+    }
+    vector.into()
+}
+
+/// Return a copy of the string STRING without the character C, or
+/// STRING itself if C does not occur in it.  Text properties are not
+/// copied.
+fn string_without_char(string: LispStringRef, c: EmacsInt) -> LispObject {
+    let bytes = string.as_slice();
+    let starts: Vec<(usize, Codepoint)> = string.char_indices().collect();
+    let mut kept = Vec::with_capacity(bytes.len());
+    let mut nchars = 0;
+    for (i, &(start, cp)) in starts.iter().enumerate() {
+        if EmacsInt::from(cp) != c {
+            let end = starts.get(i + 1).map_or(bytes.len(), |&(next, _)| next);
+            kept.extend_from_slice(&bytes[start..end]);
+            nchars += 1;
+        }
+    }
+    if nchars == starts.len() {
+        return string.into();
+    }
+
+    unsafe {
+        make_specified_string(
+            kept.as_ptr() as *const c_char,
+            nchars as ptrdiff_t,
+            kept.len() as ptrdiff_t,
+            string.is_multibyte(),
+        )
+    }
+}
+
+/// Delete members of SEQ which are `equal' to ELT, and return the result.
+/// SEQ must be a sequence (i.e. a list, a vector, or a string).
+/// The return value is a sequence of the same type.
+///
+/// If SEQ is a list, this behaves like `delq', except that it compares
+/// with `equal' instead of `eq'.  In particular, it may remove elements
+/// by altering the list structure.
+///
+/// If SEQ is not a list, deletion is never performed destructively;
+/// instead this function creates and returns a new vector or string.
+///
+/// Write `(setq foo (delete element foo))' to be sure of correctly
+/// changing the value of a sequence `foo'.
+#[lisp_fn]
+pub fn delete(elt: LispObject, seq: LispObject) -> LispObject {
+    if let Some(vector) = seq.as_vector() {
+        let kept: Vec<LispObject> = vector.iter().filter(|&item| !elt.equal(item)).collect();
+        if kept.len() == vector.len() {
+            seq
+        } else {
+            vector_of(&kept)
+        }
+    } else if let Some(string) = seq.as_string() {
+        match elt.as_fixnum() {
+            Some(c) => string_without_char(string, c),
+            None => seq,
+        }
+    } else {
+        delete_if(seq, |item| elt.equal(item))
+    }
+}
+
+/// Return a copy of SEQ with all occurrences of ELT removed.
+/// SEQ must be a list, vector, or string.  The comparison is done with `equal'.
+#[lisp_fn]
+pub fn remove(elt: LispObject, seq: LispObject) -> LispObject {
+    if seq.is_nil() || seq.is_cons() {
+        let kept: Vec<LispObject> = seq
+            .iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on)
+            .filter(|&item| !elt.equal(item))
+            .collect();
+        list(&kept)
+    } else {
+        // Vectors and strings are never changed by `delete'.
+        delete(elt, seq)
+    }
+}
+
+/// Return true if A precedes B in standard value order, for the `sort'
+/// predicate `value<'.  A and B must be of the same kind: numbers and
+/// markers are compared with `<', strings and symbols with `string<'.
+fn value_lt(a: LispObject, b: LispObject) -> bool {
+    let is_number = |obj: LispObject| obj.is_number() || obj.is_marker();
+    let is_string = |obj: LispObject| obj.is_string() || obj.is_symbol();
+    if is_number(a) {
+        if !is_number(b) {
+            wrong_type!(Qnumber_or_marker_p, b);
+        }
+        arithcompare(a, b, ArithComparison::Less)
+    } else if is_string(a) {
+        if !is_string(b) {
+            wrong_type!(Qstringp, b);
+        }
+        string_lessp(a, b)
+    } else {
+        wrong_type!(Qnumber_or_marker_p, a)
+    }
+}
+
+/// The ordering `sort' uses.
+#[derive(Clone, Copy)]
+enum SortPredicate {
+    /// `value<', which is compared directly instead of through funcall.
+    ValueLess,
+    Function(LispObject),
+}
+
+impl SortPredicate {
+    fn new(predicate: LispObject) -> Self {
+        if predicate.eq(LispObject::from(intern("value<"))) {
+            SortPredicate::ValueLess
+        } else {
+            SortPredicate::Function(predicate)
+        }
+    }
+
+    /// Return true if A may stay in front of B, i.e. B does not sort
+    /// before A.
+    fn inorder(self, a: LispObject, b: LispObject) -> bool {
+        match self {
+            SortPredicate::ValueLess => !value_lt(b, a),
+            SortPredicate::Function(predicate) => inorder(predicate, a, b),
+        }
+    }
+}
+
+/// Sort ITEMS stably.  TMP is scratch space for at least half as many
+/// elements.
+fn merge_sort(items: &mut [LispObject], tmp: &mut [LispObject], predicate: SortPredicate) {
+    let len = items.len();
+    if len < 2 {
+        return;
+    }
+    let mid = len / 2;
+    merge_sort(&mut items[..mid], tmp, predicate);
+    merge_sort(&mut items[mid..], tmp, predicate);
+    if predicate.inorder(items[mid - 1], items[mid]) {
+        return;
+    }
+
+    // Merge the front half, moved out of the way into TMP, with the
+    // back half.  The elements of the back half are never overwritten
+    // before they are merged, and those left at the end are in place.
+    tmp[..mid].copy_from_slice(&items[..mid]);
+    let (mut i, mut j, mut k) = (0, mid, 0);
+    while i < mid && j < len {
+        if predicate.inorder(tmp[i], items[j]) {
+            items[k] = tmp[i];
+            i += 1;
+        } else {
+            items[k] = items[j];
+            j += 1;
+        }
+        k += 1;
+    }
+    items[k..k + mid - i].copy_from_slice(&tmp[i..mid]);
+}
+
+/// Return a vector of the first LEN elements of ITEMS, sorted by PREDICATE.
+///
+/// The work is done in fresh Lisp vectors, so that the elements are
+/// seen by the garbage collector while the predicate runs, and so that
+/// the sequence being sorted is left alone if the predicate exits
+/// nonlocally.
+fn sorted_vector(
+    len: usize,
+    items: impl Iterator<Item = LispObject>,
+    predicate: LispObject,
+) -> LispVectorRef {
+    let mut work = unsafe { Fmake_vector(LispObject::from(len), Qnil) }
+        .as_vector()
+        .unwrap();
+    for (i, item) in items.take(len).enumerate() {
+        work.set(i, item);
+    }
+    let mut tmp = unsafe { Fmake_vector(LispObject::from(len / 2), Qnil) }
+        .as_vector()
+        .unwrap();
+    merge_sort(
+        work.as_mut_slice(),
+        tmp.as_mut_slice(),
+        SortPredicate::new(predicate),
+    );
+    work
+}
+
+/// Sort SEQ, stably, comparing elements using PREDICATE.
+/// Returns the sorted sequence.  SEQ should be a list or vector.  SEQ is
+/// modified by side effects.  PREDICATE is called with two elements of
+/// SEQ, and should return non-nil if the first element should sort before
+/// the second.
+#[lisp_fn]
+pub fn sort(seq: LispObject, predicate: LispObject) -> LispObject {
+    if seq.is_cons() {
+        let len = seq
+            .iter_tails(LispConsEndChecks::on, LispConsCircularChecks::on)
+            .count();
+        if len < 2 {
+            return seq;
+        }
+        let items = seq.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off);
+        let sorted = sorted_vector(len, items, predicate);
+
+        // Store the elements back into the conses of SEQ, which thus
+        // stays the head of the list.  The predicate may have shortened
+        // the list in the meantime.
+        let tails = seq.iter_tails(LispConsEndChecks::off, LispConsCircularChecks::off);
+        for (tail, &item) in tails.zip(sorted.as_slice()) {
+            tail.set_car(item);
+        }
+        seq
+    } else if let Some(mut vec) = seq.as_vector() {
+        if vec.len() >= 2 {
+            let sorted = sorted_vector(vec.len(), vec.as_slice().iter().cloned(), predicate);
+            vec.as_mut_slice().copy_from_slice(sorted.as_slice());
+        }
+        seq
+    } else if seq.is_nil() {
+        seq
+    } else {
+        wrong_type!(Qsequencep, seq)
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/sequences_exports.rs"));
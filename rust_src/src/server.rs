@@ -0,0 +1,221 @@
+//! Native core of the Emacs server (see server.el).
+//!
+//! This implements the socket side of the emacsclient protocol: creating
+//! the listening process, the &-quoting used on the wire, splitting a
+//! request into its arguments, and authenticating TCP clients.
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    lisp::defsubr,
+    lisp::LispObject,
+    lists::{plist_get, plist_put},
+    multibyte::{multibyte_char_at, LispStringRef},
+    process::{process_plist, set_process_plist, LispProcessRef},
+    remacs_sys::{make_specified_string, Fmake_network_process},
+    remacs_sys::{
+        QCauth_key, QCauthenticated, QCcoding, QCfamily, QCfilter, QChost, QCname, QCnoquery,
+        QCplist, QCsentinel, QCserver, QCservice, QCuse_external_socket, Qipv4, Qlocal, Qnil,
+        Qraw_text_unix, Qserver_process_filter, Qserver_sentinel, Qt,
+    },
+};
+
+/// Make a Lisp string from BYTES, multibyte if MULTIBYTE.
+fn make_string(bytes: &[u8], multibyte: bool) -> LispObject {
+    unsafe {
+        make_specified_string(
+            bytes.as_ptr() as *const libc::c_char,
+            -1,
+            bytes.len() as libc::ptrdiff_t,
+            multibyte,
+        )
+    }
+}
+
+/// Length in bytes of the character starting at BYTES.
+fn char_len(bytes: &[u8], multibyte: bool) -> usize {
+    if multibyte {
+        multibyte_char_at(bytes).1
+    } else {
+        1
+    }
+}
+
+fn unquote(bytes: &[u8], multibyte: bool) -> Vec<u8> {
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'&' && i + 1 < bytes.len() {
+            let quoted = bytes[i + 1];
+            result.push(match quoted {
+                b'&' => b'&',
+                b'-' => b'-',
+                b'n' => b'\n',
+                _ => b' ',
+            });
+            i += 1 + char_len(&bytes[i + 1..], multibyte);
+        } else {
+            result.push(bytes[i]);
+            i += 1;
+        }
+    }
+    result
+}
+
+fn quote(bytes: &[u8]) -> Vec<u8> {
+    let mut result = Vec::with_capacity(bytes.len());
+    for &b in bytes {
+        match b {
+            b'&' => result.extend_from_slice(b"&&"),
+            b'-' => result.extend_from_slice(b"&-"),
+            b'\n' => result.extend_from_slice(b"&n"),
+            b' ' => result.extend_from_slice(b"&_"),
+            _ => result.push(b),
+        }
+    }
+    result
+}
+
+/// Find an `-auth KEY' command in BYTES.  Return the key and the byte
+/// position just after the command.
+fn find_auth(bytes: &[u8]) -> Option<(&[u8], usize)> {
+    const AUTH: &[u8] = b"-auth ";
+
+    let start = bytes.windows(AUTH.len()).position(|w| w == AUTH)? + AUTH.len();
+    let len = bytes[start..]
+        .iter()
+        .take_while(|&&b| b >= b'!' && b <= b'~')
+        .count();
+    if len == 0 {
+        return None;
+    }
+    let mut end = start + len;
+    if bytes.get(end) == Some(&b'\n') {
+        end += 1;
+    }
+    Some((&bytes[start..start + len], end))
+}
+
+/// Remove &-quotation from ARG.
+/// See `server-quote-arg' and `server-process-filter'.
+#[lisp_fn]
+pub fn server_unquote_arg(arg: LispStringRef) -> LispObject {
+    let multibyte = arg.is_multibyte();
+    make_string(&unquote(arg.as_slice(), multibyte), multibyte)
+}
+
+/// In ARG, insert a & before each &, each space, each newline, and -.
+/// Change spaces to underscores, too, so that the return value never
+/// contains a space.
+///
+/// See `server-unquote-arg' and `server-process-filter'.
+#[lisp_fn]
+pub fn server_quote_arg(arg: LispStringRef) -> LispObject {
+    make_string(&quote(arg.as_slice()), arg.is_multibyte())
+}
+
+/// Split the emacsclient REQUEST into its unquoted arguments.
+/// REQUEST is a single line sent by emacsclient, without the final
+/// newline.  Arguments are separated by spaces and &-quoted; see
+/// `server-unquote-arg'.
+#[lisp_fn]
+pub fn server_split_request(request: LispStringRef) -> LispObject {
+    let multibyte = request.is_multibyte();
+    let args: Vec<LispObject> = request
+        .as_slice()
+        .split(|&b| b == b' ')
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| make_string(&unquote(arg, multibyte), multibyte))
+        .collect();
+    args.into()
+}
+
+/// Authenticate the client PROC using the data in STRING.
+/// If PROC is already authenticated, return STRING.  Otherwise look for
+/// an `-auth AUTH-STRING' command in STRING and compare AUTH-STRING with
+/// PROC's `:auth-key' property.  If they are equal, mark PROC as
+/// authenticated and return the part of STRING following the command;
+/// otherwise return nil.
+#[lisp_fn]
+pub fn server_authenticate(proc: LispProcessRef, string: LispStringRef) -> LispObject {
+    let plist = process_plist(proc);
+    if plist_get(plist, QCauthenticated).is_not_nil() {
+        return string.into();
+    }
+
+    let bytes = string.as_slice();
+    let rest = find_auth(bytes).and_then(|(key, end)| {
+        let expected = plist_get(plist, QCauth_key);
+        match expected.as_string() {
+            Some(expected) if expected.as_slice() == key => Some(&bytes[end..]),
+            _ => None,
+        }
+    });
+
+    match rest {
+        Some(rest) => {
+            let plist = plist_put(plist, QCauthenticated, Qt);
+            set_process_plist(proc.into(), plist);
+            make_string(rest, string.is_multibyte())
+        }
+        None => Qnil,
+    }
+}
+
+/// Start a server process called NAME listening on SERVICE.
+/// If HOST is nil, SERVICE is the file name of a local socket.
+/// Otherwise listen for TCP connections on HOST, and SERVICE is the
+/// port number, or t for a random port.  Clients of a TCP server have
+/// to authenticate themselves, see `server-authenticate'.
+///
+/// Requests are handed to `server-process-filter', and changes in the
+/// connection state to `server-sentinel'.
+#[lisp_fn(min = "2")]
+pub fn server_make_listener(name: LispObject, service: LispObject, host: LispObject) -> LispObject {
+    let (family, plist) = if host.is_not_nil() {
+        (Qipv4, list!(QCauthenticated, Qnil))
+    } else {
+        (Qlocal, list!(QCauthenticated, Qt))
+    };
+
+    callN_raw!(
+        Fmake_network_process,
+        QCname,
+        name,
+        QCserver,
+        Qt,
+        QCnoquery,
+        Qt,
+        QCsentinel,
+        Qserver_sentinel,
+        QCfilter,
+        Qserver_process_filter,
+        QCuse_external_socket,
+        Qt,
+        // We must receive file names without being decoded.  Those are
+        // decoded by `server-process-filter' according to
+        // `file-name-coding-system'.  Also don't get confused by CRs
+        // since we don't quote them.
+        QCcoding,
+        Qraw_text_unix,
+        QCfamily,
+        family,
+        QCservice,
+        service,
+        QChost,
+        host,
+        QCplist,
+        plist
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn syms_of_server() {
+    def_lisp_sym!(QCauthenticated, ":authenticated");
+    def_lisp_sym!(QCauth_key, ":auth-key");
+    def_lisp_sym!(Qraw_text_unix, "raw-text-unix");
+    def_lisp_sym!(Qserver_process_filter, "server-process-filter");
+    def_lisp_sym!(Qserver_sentinel, "server-sentinel");
+}
+
+include!(concat!(env!("OUT_DIR"), "/server_exports.rs"));
@@ -0,0 +1,581 @@
+//! Layout of HTML documents for shr.
+//!
+//! `shr-layout-dom` inserts the text of a parse tree made by
+//! `libxml-parse-html-region' at point, laid out in blocks the way shr
+//! lays it out: paragraphs and headings are separated by blank lines
+//! and filled to a width in pixels by `pixel-fill-breaks`, list items
+//! are indented under their bullets or numbers, and the columns of
+//! tables are sized to their cells and aligned with `(space :align-to
+//! COLUMN)` display properties.  Inline elements give the text faces,
+//! and links the `shr-url` property the commands of shr look for.
+//!
+//! A node of the tree is either a string, or (TAG ATTRIBUTES . CHILDREN)
+//! where TAG is a symbol and ATTRIBUTES is an alist.
+
+use std::mem;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    editfns::{buffer_substring, char_after, char_before, delete_region, goto_char, point},
+    lisp::{defsubr, LispObject},
+    lists::{assq, cdr, list, LispConsCircularChecks, LispConsEndChecks},
+    multibyte::Codepoint,
+    obarray::intern,
+    pixel_fill::{fill_breaks, pixel_fill_breaks, Kind},
+    remacs_sys::{insert_from_string, Fadd_face_text_property, Fchar_width, Fframe_char_width},
+    remacs_sys::{window_body_width, EmacsInt, Fput_text_property},
+    remacs_sys::{QCalign_to, Qbold, Qdisplay, Qhelp_echo, Qitalic, Qmouse_face, Qnil, Qspace, Qt},
+    string_alloc::make_string,
+    windows::selected_window,
+};
+
+/// Return the tag of NODE, or `None` if it is text.
+fn tag(node: LispObject) -> Option<String> {
+    let tag = node.as_cons()?.car().as_symbol()?;
+    Some(tag.symbol_name().force_string().to_string())
+}
+
+/// Return the value of the attribute NAME of the element NODE, or nil.
+fn attribute(node: LispObject, name: &str) -> LispObject {
+    cdr(assq(
+        intern(name).into(),
+        cdr(node).as_cons().map_or(Qnil, |c| c.car()),
+    ))
+}
+
+/// Return the children of the element NODE.
+fn children(node: LispObject) -> Vec<LispObject> {
+    cdr(cdr(node))
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe)
+        .collect()
+}
+
+/// Return the number of columns taken by the character C.
+fn char_columns(c: char) -> usize {
+    let width = unsafe { Fchar_width((c as Codepoint).into()) };
+    width.as_natnum_or_error() as usize
+}
+
+/// Return TEXT with each run of whitespace made a single space, without
+/// a leading space if AT_SPACE, when it follows a space already.
+fn collapse_whitespace(text: &str, mut at_space: bool) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !at_space {
+                collapsed.push(' ');
+                at_space = true;
+            }
+        } else {
+            collapsed.push(c);
+            at_space = false;
+        }
+    }
+    collapsed
+}
+
+/// Return the text of the inline contents of NODE, with whitespace
+/// collapsed, for a table cell.
+fn inline_text(node: LispObject, text: &mut String) {
+    if let Some(s) = node.as_string() {
+        let at_space = text.is_empty() || text.ends_with(' ');
+        text.push_str(&collapse_whitespace(&s.to_string(), at_space));
+        return;
+    }
+    match tag(node).as_ref().map(String::as_str) {
+        Some("script") | Some("style") | Some("template") => {}
+        Some("br") | Some("p") | Some("div") | Some("li") | Some("tr") => {
+            if !text.is_empty() && !text.ends_with(' ') {
+                text.push(' ');
+            }
+            children(node)
+                .into_iter()
+                .for_each(|child| inline_text(child, text));
+        }
+        Some(_) => children(node)
+            .into_iter()
+            .for_each(|child| inline_text(child, text)),
+        None => {}
+    }
+}
+
+/// Return the widths of the columns of a table, given the NATURAL width
+/// of each, the widest of its cells, to fit AVAILABLE columns.  When
+/// they don't all fit, the columns narrower than an equal share of what
+/// is left keep their width, and the others share the rest equally.
+fn column_widths(natural: &[usize], available: usize) -> Vec<usize> {
+    if natural.iter().sum::<usize>() <= available {
+        return natural.to_vec();
+    }
+
+    let mut widths: Vec<Option<usize>> = vec![None; natural.len()];
+    let mut left = available;
+    loop {
+        let open = widths.iter().filter(|w| w.is_none()).count();
+        if open == 0 {
+            break;
+        }
+        let share = left / open;
+        let mut fixed = false;
+        for (w, &n) in widths.iter_mut().zip(natural) {
+            if w.is_none() && n <= share {
+                *w = Some(n);
+                left -= n;
+                fixed = true;
+            }
+        }
+        if !fixed {
+            break;
+        }
+    }
+
+    let open = widths.iter().filter(|w| w.is_none()).count().max(1);
+    let (share, mut extra) = (left / open, left % open);
+    widths
+        .into_iter()
+        .map(|w| {
+            w.unwrap_or_else(|| {
+                let more = if extra > 0 { 1 } else { 0 };
+                extra -= more;
+                share + more
+            })
+            .max(1)
+        })
+        .collect()
+}
+
+/// Return the lines of TEXT wrapped to WIDTH columns, where COLUMNS is
+/// the width of a character.
+fn wrap_cell(text: &str, width: usize, columns: impl Fn(char) -> usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let glyphs = chars
+        .iter()
+        .map(|&c| (Kind::of(c as Codepoint), columns(c) as i32));
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for i in fill_breaks(glyphs, width as i32) {
+        lines.push(chars[start..i].iter().collect::<String>());
+        start = if chars[i] == ' ' { i + 1 } else { i };
+    }
+    lines.push(chars[start..].iter().collect());
+    lines
+}
+
+/// Where a line starts in the text of a block, for its indentation.
+#[derive(Clone, Copy)]
+enum LineStart {
+    /// The start of the text, or of a line after a newline in it.
+    At(EmacsInt),
+    /// A break found by filling, which replaces a space if there is one.
+    Break(EmacsInt),
+}
+
+impl LineStart {
+    fn pos(self) -> EmacsInt {
+        match self {
+            LineStart::At(pos) | LineStart::Break(pos) => pos,
+        }
+    }
+}
+
+/// The state of the layout of a document.
+struct Layout {
+    /// The width to fill to, and that of a column, in pixels.
+    width: EmacsInt,
+    column_width: EmacsInt,
+    /// Where the layout started, before which no newlines are added.
+    start: EmacsInt,
+    /// The start of the text of the current block not filled yet.
+    run_start: EmacsInt,
+    /// The indentation of the current block, in columns.
+    indent: usize,
+    /// The bullet or number of the list item whose first line is to come.
+    bullet: Option<String>,
+    /// The enclosing lists, innermost last, as the number of the next
+    /// item of an ordered list, or `None`.
+    lists: Vec<Option<usize>>,
+    /// The faces of the enclosing elements, innermost last.
+    faces: Vec<LispObject>,
+    /// The URL of the enclosing link, or nil.
+    url: LispObject,
+    /// Whether the text keeps its whitespace and newlines.
+    pre: bool,
+}
+
+impl Layout {
+    /// Insert TEXT at point, and return where it starts and ends.
+    fn insert(&self, text: &str) -> (EmacsInt, EmacsInt) {
+        let start = point();
+        let string = make_string(text).force_string();
+        unsafe {
+            insert_from_string(
+                string.into(),
+                0,
+                0,
+                string.len_chars(),
+                string.len_bytes(),
+                false,
+            )
+        };
+        (start, point())
+    }
+
+    /// Insert the text of a node, and give it the faces and the link of
+    /// the elements it is in.
+    fn text(&mut self, text: &str) {
+        let text = if self.pre {
+            text.to_string()
+        } else {
+            let at_space = point() == self.run_start
+                || char_before(Qnil)
+                    .map_or(true, |c| c == ' ' as EmacsInt || c == '\n' as EmacsInt);
+            collapse_whitespace(text, at_space)
+        };
+        if text.is_empty() {
+            return;
+        }
+
+        let (start, end) = self.insert(&text);
+        let (start, end) = (LispObject::from(start), LispObject::from(end));
+        // Faces added last come first, so inner elements' faces win.
+        for &face in &self.faces {
+            unsafe { Fadd_face_text_property(start, end, face, Qnil, Qnil) };
+        }
+        if self.url.is_not_nil() {
+            let props = [
+                (intern("shr-url").into(), self.url),
+                (Qhelp_echo, self.url),
+                (Qmouse_face, intern("highlight").into()),
+                (intern("follow-link").into(), Qt),
+            ];
+            for &(prop, value) in &props {
+                unsafe { Fput_text_property(start, end, prop, value, Qnil) };
+            }
+        }
+    }
+
+    /// Fill the text from `run_start` to point, and indent its lines.
+    fn flush(&mut self) {
+        if !self.pre {
+            while point() > self.run_start && char_before(Qnil) == Some(' ' as EmacsInt) {
+                delete_region((point() - 1).into(), point().into());
+            }
+        }
+        let (start, mut end) = (self.run_start, point());
+        if start == end {
+            return;
+        }
+
+        let text = buffer_substring(start.into(), end.into()).force_string();
+        let mut starts = vec![LineStart::At(start)];
+        starts.extend(
+            text.chars()
+                .enumerate()
+                .filter(|&(i, c)| c == '\n' as Codepoint && start + i as EmacsInt + 1 < end)
+                .map(|(i, _)| LineStart::At(start + i as EmacsInt + 1)),
+        );
+        if !self.pre {
+            let indent_width = self.indent as EmacsInt * self.column_width;
+            let width = (self.width - indent_width).max(self.column_width);
+            let breaks = pixel_fill_breaks(start.into(), end.into(), width, Qnil.into());
+            starts.extend(
+                breaks
+                    .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+                    .map(|pos| LineStart::Break(pos.as_fixnum_or_error())),
+            );
+        }
+        starts.sort_by_key(|line| line.pos());
+
+        let indent = " ".repeat(self.indent);
+        let first = match self.bullet.take() {
+            Some(bullet) => {
+                let pad = self.indent.saturating_sub(bullet.chars().count());
+                format!("{}{}", " ".repeat(pad), bullet)
+            }
+            None => indent.clone(),
+        };
+
+        for &line in starts.iter().rev() {
+            let pos = line.pos();
+            goto_char(pos.into());
+            let prefix = if pos == start { &first } else { &indent };
+            if let LineStart::Break(_) = line {
+                if char_after(pos.into()) == Some(' ' as EmacsInt) {
+                    delete_region(pos.into(), (pos + 1).into());
+                    end -= 1;
+                }
+                self.insert("\n");
+                end += 1;
+            }
+            let (from, to) = self.insert(prefix);
+            end += to - from;
+        }
+        goto_char(end.into());
+        self.run_start = end;
+    }
+
+    /// End the text before a block, and start a line for the block,
+    /// after a blank line if BLANK.
+    fn break_block(&mut self, blank: bool) {
+        self.flush();
+        if point() > self.start {
+            if char_before(Qnil) != Some('\n' as EmacsInt) {
+                self.insert("\n");
+            }
+            let before = LispObject::from(point() - 1);
+            if blank && point() - 1 > self.start && char_before(before) != Some('\n' as EmacsInt) {
+                self.insert("\n");
+            }
+        }
+        self.run_start = point();
+    }
+
+    /// Lay out the children of NODE as a block indented by INDENT more
+    /// columns, separated from the text around by a blank line if BLANK.
+    fn block(&mut self, node: LispObject, blank: bool, indent: usize) {
+        self.break_block(blank);
+        self.indent += indent;
+        self.children(node);
+        self.break_block(blank);
+        self.indent -= indent;
+    }
+
+    /// Lay out the children of NODE with FACE.
+    fn with_face(&mut self, node: LispObject, face: LispObject) {
+        self.faces.push(face);
+        self.children(node);
+        self.faces.pop();
+    }
+
+    fn children(&mut self, node: LispObject) {
+        for child in children(node) {
+            self.node(child);
+        }
+    }
+
+    fn node(&mut self, node: LispObject) {
+        if let Some(s) = node.as_string() {
+            self.text(&s.to_string());
+            return;
+        }
+        let tag = match tag(node) {
+            Some(tag) => tag,
+            None => return,
+        };
+
+        match tag.as_str() {
+            "script" | "style" | "head" | "title" | "template" => {}
+            "br" => {
+                self.insert("\n");
+            }
+            "p" | "dl" => self.block(node, true, 0),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.break_block(true);
+                self.with_face(node, intern(&format!("shr-{}", tag)).into());
+                self.break_block(true);
+            }
+            "blockquote" => self.block(node, true, 4),
+            "dd" => self.block(node, false, 4),
+            "pre" => {
+                let outer = mem::replace(&mut self.pre, true);
+                self.break_block(true);
+                self.with_face(node, intern("fixed-pitch").into());
+                self.break_block(true);
+                self.pre = outer;
+            }
+            "ul" | "ol" => {
+                let blank = self.lists.is_empty();
+                let start = attribute(node, "start")
+                    .as_string()
+                    .and_then(|s| s.to_string().trim().parse().ok())
+                    .unwrap_or(1);
+                self.lists
+                    .push(if tag == "ol" { Some(start) } else { None });
+                self.block(node, blank, 0);
+                self.lists.pop();
+            }
+            "li" => {
+                let bullet = match self.lists.last_mut() {
+                    Some(Some(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "* ".to_string(),
+                };
+                let indent = bullet.chars().count();
+                self.break_block(false);
+                self.bullet = Some(bullet);
+                self.block(node, false, indent);
+                self.bullet = None;
+            }
+            "div" | "dt" | "section" | "article" | "header" | "footer" | "nav" | "main"
+            | "aside" | "address" | "figure" | "figcaption" | "form" | "center" | "body"
+            | "html" => self.block(node, false, 0),
+            "table" => self.table(node),
+            "b" | "strong" => self.with_face(node, Qbold),
+            "i" | "em" | "cite" | "var" | "dfn" => self.with_face(node, Qitalic),
+            "u" | "ins" => self.with_face(node, intern("underline").into()),
+            "s" | "strike" | "del" => self.with_face(node, intern("shr-strike-through").into()),
+            "code" | "tt" | "kbd" | "samp" => self.with_face(node, intern("fixed-pitch").into()),
+            "a" => {
+                let url = attribute(node, "href");
+                let outer = mem::replace(&mut self.url, url);
+                self.with_face(node, intern("shr-link").into());
+                self.url = outer;
+            }
+            "img" => {
+                if let Some(alt) = attribute(node, "alt").as_string() {
+                    self.text(&alt.to_string());
+                }
+            }
+            _ => self.children(node),
+        }
+    }
+
+    /// Lay out the table NODE, with a line of text for each line of the
+    /// cells of a row, and each cell aligned to the column it is in.
+    fn table(&mut self, node: LispObject) {
+        let mut rows = Vec::new();
+        table_rows(node, &mut rows);
+        self.break_block(true);
+        let ncolumns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if ncolumns == 0 {
+            return;
+        }
+
+        let mut natural = vec![0; ncolumns];
+        for row in &rows {
+            for (width, (text, _)) in natural.iter_mut().zip(row) {
+                *width = (*width).max(text.chars().map(char_columns).sum());
+            }
+        }
+        let available = (self.width / self.column_width) as usize;
+        let available = available.saturating_sub(self.indent + ncolumns - 1);
+        let widths = column_widths(&natural, available);
+
+        for row in &rows {
+            let cells: Vec<Vec<String>> = row
+                .iter()
+                .zip(&widths)
+                .map(|((text, _), &width)| wrap_cell(text, width, char_columns))
+                .collect();
+            let height = cells.iter().map(Vec::len).max().unwrap_or(1);
+            for line in 0..height {
+                self.insert(&" ".repeat(self.indent));
+                let mut column = self.indent;
+                for (j, cell) in cells.iter().enumerate() {
+                    if let Some(text) = cell.get(line) {
+                        let (start, end) = self.insert(text);
+                        if row[j].1 {
+                            unsafe {
+                                Fadd_face_text_property(start.into(), end.into(), Qbold, Qnil, Qnil)
+                            };
+                        }
+                    }
+                    column += widths[j] + 1;
+                    if j + 1 < cells.len() {
+                        let (start, end) = self.insert(" ");
+                        let space = list(&[Qspace, QCalign_to, column.into()]);
+                        unsafe {
+                            Fput_text_property(start.into(), end.into(), Qdisplay, space, Qnil)
+                        };
+                    }
+                }
+                self.insert("\n");
+            }
+        }
+        self.run_start = point();
+        self.break_block(true);
+    }
+}
+
+/// Add the rows of the table NODE to ROWS, each as the text of its cells
+/// and whether they are headers.
+fn table_rows(node: LispObject, rows: &mut Vec<Vec<(String, bool)>>) {
+    for child in children(node) {
+        match tag(child).as_ref().map(String::as_str) {
+            Some("thead") | Some("tbody") | Some("tfoot") => table_rows(child, rows),
+            Some("tr") => {
+                let cells = children(child)
+                    .into_iter()
+                    .filter_map(|cell| match tag(cell).as_ref().map(String::as_str) {
+                        Some("td") => Some((cell, false)),
+                        Some("th") => Some((cell, true)),
+                        _ => None,
+                    })
+                    .map(|(cell, header)| {
+                        let mut text = String::new();
+                        inline_text(cell, &mut text);
+                        (text.trim_end().to_string(), header)
+                    })
+                    .collect();
+                rows.push(cells);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Insert the text of the HTML document DOM at point, laid out to WIDTH.
+/// DOM is a parse tree as made by `libxml-parse-html-region'.  WIDTH is
+/// in pixels, and defaults to that of the selected window's text area.
+///
+/// Paragraphs are filled with `pixel-fill-breaks', so that text in
+/// variable pitch fonts is wrapped by the width of its glyphs.  List
+/// items are indented under their bullets or numbers, and the columns
+/// of tables are aligned with `:align-to' display properties.  Text in
+/// links has the `shr-url' property, and the faces of shr, like
+/// `shr-link' and `shr-h1', are given to the text of the elements they
+/// are for.
+#[lisp_fn(min = "1")]
+pub fn shr_layout_dom(dom: LispObject, width: Option<EmacsInt>) {
+    let mut window = selected_window().as_window_or_error();
+    let column_width = unsafe { Fframe_char_width(window.frame) }
+        .as_fixnum_or_error()
+        .max(1);
+    let width = width
+        .unwrap_or_else(|| unsafe { EmacsInt::from(window_body_width(window.as_mut(), true)) });
+
+    let start = point();
+    let mut layout = Layout {
+        width,
+        column_width,
+        start,
+        run_start: start,
+        indent: 0,
+        bullet: None,
+        lists: Vec::new(),
+        faces: Vec::new(),
+        url: Qnil,
+        pre: false,
+    };
+    layout.node(dom);
+    layout.flush();
+}
+
+include!(concat!(env!("OUT_DIR"), "/shr_layout_exports.rs"));
+
+#[test]
+fn test_collapse_whitespace() {
+    assert_eq!(collapse_whitespace("  a \n\t b  ", false), " a b ");
+    assert_eq!(collapse_whitespace("  a \n\t b  ", true), "a b ");
+    assert_eq!(collapse_whitespace("", false), "");
+}
+
+#[test]
+fn test_column_widths() {
+    assert_eq!(column_widths(&[3, 10, 20], 33), vec![3, 10, 20]);
+    assert_eq!(column_widths(&[3, 10, 20], 20), vec![3, 9, 8]);
+    assert_eq!(column_widths(&[10, 10], 4), vec![2, 2]);
+    assert_eq!(column_widths(&[0, 10], 4), vec![1, 4]);
+}
+
+#[test]
+fn test_wrap_cell() {
+    let wrap = |text, width| wrap_cell(text, width, |_| 1);
+    assert_eq!(wrap("aaa bbb", 10), vec!["aaa bbb"]);
+    assert_eq!(wrap("aaa bbb", 5), vec!["aaa", "bbb"]);
+    assert_eq!(wrap("aaaaaa", 4), vec!["aaaa", "aa"]);
+    assert_eq!(wrap("", 4), vec![""]);
+}
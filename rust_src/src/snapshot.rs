@@ -0,0 +1,682 @@
+//! Snapshots of buffer text, for searching and parsing it on other
+//! threads while the buffer keeps being edited.
+//!
+//! Taking a snapshot copies nothing: the snapshot reads the text where
+//! the buffer keeps it, once the gap is moved out of the way to its
+//! end.  Before the buffer changes that text, or moves or reallocates
+//! its gap, `detach_buffer_snapshots` gives the snapshots still reading
+//! it one copy of it to share.  Until then, every new snapshot of the
+//! buffer shares the text of the previous one.
+//!
+//! Lisp sees a snapshot as a handle, a record of type `buffer-snapshot'
+//! that is only a handle as long as it is the very object that
+//! `buffer-snapshot' returned: copies of it, and records made with the
+//! same slots, are not.  The snapshot is released when the handle is
+//! garbage collected, or before with `buffer-snapshot-release'.  Native
+//! code gets at the snapshot with `snapshot_of`, and can then hand it
+//! to any thread, as `buffer-snapshot-search-start' does.
+
+use std::collections::HashMap;
+use std::ptr;
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::thread;
+
+use libc::ptrdiff_t;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::{LispBufferOrCurrent, LispBufferRef, BEG_BYTE},
+    character::char_head_p,
+    eval::unbind_to,
+    finalizers::on_gc_finalize,
+    lisp::defsubr,
+    lisp::LispObject,
+    lists::list,
+    multibyte::LispStringRef,
+    obarray::intern,
+    remacs_sys::{
+        make_specified_string, move_gap_both, record_unwind_current_buffer, set_buffer_internal_1,
+        EmacsInt, Frecord, Lisp_Buffer, Qnil,
+    },
+    search::native_regexp,
+    threads::{c_specpdl_index, ThreadState},
+};
+
+/// Where the text of snapshots is.
+enum Storage {
+    /// In the buffer, from `BEG` to `Z`, with the gap after it.
+    Buffer(*const u8, usize),
+    /// In a copy made before the buffer changed.
+    Copy(Box<[u8]>),
+}
+
+/// The text shared by the snapshots taken while it didn't change.
+struct SharedText {
+    storage: RwLock<Storage>,
+}
+
+// The text of the buffer is only read under the lock, and
+// `detach_buffer_snapshots` takes the lock to copy it before the
+// buffer changes it.
+unsafe impl Send for SharedText {}
+unsafe impl Sync for SharedText {}
+
+impl SharedText {
+    fn len(&self) -> usize {
+        match *self.storage.read().unwrap() {
+            Storage::Buffer(_, len) => len,
+            Storage::Copy(ref copy) => copy.len(),
+        }
+    }
+
+    /// Copy the text, if it is still read from the buffer.
+    fn detach(&self) {
+        let mut storage = self.storage.write().unwrap();
+        if let Storage::Buffer(beg, len) = *storage {
+            let copy = unsafe { slice::from_raw_parts(beg, len) }.into();
+            *storage = Storage::Copy(copy);
+        }
+    }
+}
+
+/// The text of a buffer at the time a snapshot of it was taken.
+pub struct BufferSnapshot {
+    text: Arc<SharedText>,
+    multibyte: bool,
+    /// The modification count of the buffer when the snapshot was taken.
+    modifications: EmacsInt,
+    /// The byte offsets from `BEG` of the accessible portion.
+    begv: usize,
+    zv: usize,
+    /// The character position of `BEGV`.
+    begv_charpos: ptrdiff_t,
+}
+
+impl BufferSnapshot {
+    /// Call F with the accessible portion of the text.  While the text
+    /// is still read from the buffer, changing the buffer waits for F
+    /// to return, so F should not take long unless `detach` is called
+    /// first.  F must not change the buffer itself.
+    pub fn read<R>(&self, f: impl FnOnce(SnapshotText) -> R) -> R {
+        let storage = self.text.storage.read().unwrap();
+        let all = match *storage {
+            Storage::Buffer(beg, len) => unsafe { slice::from_raw_parts(beg, len) },
+            Storage::Copy(ref copy) => copy,
+        };
+        f(SnapshotText {
+            bytes: &all[self.begv..self.zv],
+            multibyte: self.multibyte,
+            begv_charpos: self.begv_charpos,
+        })
+    }
+
+    /// Give the snapshot its own copy of the text, unless it already
+    /// has one, so that reading it doesn't hold up changes to the buffer.
+    pub fn detach(&self) {
+        self.text.detach();
+    }
+
+    pub fn is_multibyte(&self) -> bool {
+        self.multibyte
+    }
+
+    /// The value of `buffer-modified-tick' when the snapshot was taken.
+    pub fn modifications(&self) -> EmacsInt {
+        self.modifications
+    }
+}
+
+/// The accessible portion of the text of a snapshot, as `read` lends it.
+pub struct SnapshotText<'a> {
+    bytes: &'a [u8],
+    multibyte: bool,
+    begv_charpos: ptrdiff_t,
+}
+
+impl<'a> SnapshotText<'a> {
+    pub fn bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// The number of characters in BYTES, a part of the text.
+    fn chars_in(&self, bytes: &[u8]) -> usize {
+        if self.multibyte {
+            bytes.iter().filter(|&&byte| char_head_p(byte)).count()
+        } else {
+            bytes.len()
+        }
+    }
+
+    /// The character position of the byte at OFFSET in `bytes`.
+    pub fn charpos(&self, offset: usize) -> ptrdiff_t {
+        self.begv_charpos + self.chars_in(&self.bytes[..offset]) as ptrdiff_t
+    }
+
+    /// The offset in `bytes` of the character at CHARPOS, or `None` if
+    /// CHARPOS is outside the accessible portion.
+    pub fn byte_offset(&self, charpos: ptrdiff_t) -> Option<usize> {
+        let chars = charpos - self.begv_charpos;
+        if chars < 0 {
+            None
+        } else if !self.multibyte {
+            Some(chars as usize).filter(|&offset| offset <= self.bytes.len())
+        } else {
+            let mut heads = self
+                .bytes
+                .iter()
+                .enumerate()
+                .filter(|&(_, &byte)| char_head_p(byte))
+                .map(|(offset, _)| offset)
+                .chain(Some(self.bytes.len()));
+            heads.nth(chars as usize)
+        }
+    }
+}
+
+/// What a Lisp handle stands for.
+enum Handled {
+    /// A snapshot, or `None` once it is released.
+    Snapshot(Option<Arc<BufferSnapshot>>),
+    /// A search started by `buffer-snapshot-search-start'.
+    Search(SearchJob),
+}
+
+impl Handled {
+    /// The type of the records that are handles for this.
+    fn type_name(&self) -> &'static str {
+        match self {
+            Handled::Snapshot(_) => "buffer-snapshot",
+            Handled::Search(_) => "buffer-snapshot-search",
+        }
+    }
+}
+
+struct Handle {
+    /// The address of the record that is the handle.
+    address: usize,
+    handled: Handled,
+}
+
+struct Registry {
+    next_id: EmacsInt,
+    /// The live handles, by the number in their second slot.
+    handles: HashMap<EmacsInt, Handle>,
+    /// The text that the latest snapshot of each buffer text reads in
+    /// place, by the address of the buffer text.
+    live: HashMap<usize, Weak<SharedText>>,
+}
+
+lazy_static! {
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+        next_id: 0,
+        handles: HashMap::new(),
+        live: HashMap::new(),
+    });
+}
+
+/// The number of entries in `Registry::live`, for `detach_buffer_snapshots`
+/// to return at once when there is nothing to detach, without locking.
+static LIVE_TEXTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Give the snapshots that read the text of buffer B in place their own
+/// copy of it.  Called before the text changes, its gap moves or its
+/// memory is reallocated or freed.
+#[no_mangle]
+pub extern "C" fn detach_buffer_snapshots(b: *mut Lisp_Buffer) {
+    if LIVE_TEXTS.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let key = unsafe { (*b).text } as usize;
+    let text = {
+        let mut registry = REGISTRY.lock().unwrap();
+        let text = registry.live.remove(&key);
+        LIVE_TEXTS.store(registry.live.len(), Ordering::Relaxed);
+        text
+    };
+    // Readers on other threads hold the text while copying, without
+    // the registry lock.
+    if let Some(text) = text.and_then(|text| text.upgrade()) {
+        text.detach();
+    }
+}
+
+/// Move the gap of BUFFER to its end, so that its text is contiguous.
+fn move_gap_to_end(mut buffer: LispBufferRef) {
+    if buffer.gpt_byte() == buffer.z_byte() {
+        return;
+    }
+    let count = c_specpdl_index();
+    unsafe {
+        record_unwind_current_buffer();
+        set_buffer_internal_1(buffer.as_mut());
+        move_gap_both(buffer.z(), buffer.z_byte());
+    }
+    unbind_to(count, Qnil);
+}
+
+/// Start sharing the text of BUFFER, whose address is KEY, in place.
+fn new_shared_text(buffer: LispBufferRef, key: usize) -> Arc<SharedText> {
+    // Moving the gap detaches the text the snapshots read before.
+    move_gap_to_end(buffer);
+    let len = (buffer.z_byte() - BEG_BYTE) as usize;
+    let text = Arc::new(SharedText {
+        storage: RwLock::new(Storage::Buffer(buffer.beg_addr(), len)),
+    });
+    let mut registry = REGISTRY.lock().unwrap();
+    registry.live.insert(key, Arc::downgrade(&text));
+    LIVE_TEXTS.store(registry.live.len(), Ordering::Relaxed);
+    text
+}
+
+/// Take a snapshot of the text of BUFFER, which must be live.
+pub fn take_snapshot(buffer: LispBufferRef) -> Arc<BufferSnapshot> {
+    let key = buffer.text as usize;
+    let len = (buffer.z_byte() - BEG_BYTE) as usize;
+    let live = REGISTRY
+        .lock()
+        .unwrap()
+        .live
+        .get(&key)
+        .and_then(Weak::upgrade);
+    let text = match live {
+        Some(ref text) if text.len() == len => Arc::clone(text),
+        _ => {
+            // Inserting at the end is the one change that doesn't detach
+            // the text, as it leaves the bytes read alone.  The snapshots
+            // taken before it get their copy now, since only the new text
+            // will be detached from now on.
+            if let Some(text) = live {
+                text.detach();
+            }
+            new_shared_text(buffer, key)
+        }
+    };
+
+    Arc::new(BufferSnapshot {
+        text,
+        multibyte: buffer.multibyte_characters_enabled(),
+        modifications: buffer.modifications(),
+        begv: (buffer.begv_byte - BEG_BYTE) as usize,
+        zv: (buffer.zv_byte - BEG_BYTE) as usize,
+        begv_charpos: buffer.begv,
+    })
+}
+
+/// Make a handle for HANDLED, a record with the handle number and then
+/// SLOTS in its slots.  HANDLED is dropped once the record is garbage
+/// collected.
+fn make_handle(handled: Handled, slots: &[LispObject]) -> LispObject {
+    let id = {
+        let mut registry = REGISTRY.lock().unwrap();
+        registry.next_id += 1;
+        registry.next_id
+    };
+
+    let mut record_slots: Vec<LispObject> = vec![intern(handled.type_name()).into(), id.into()];
+    record_slots.extend_from_slice(slots);
+    let record = unsafe { Frecord(record_slots.len() as ptrdiff_t, record_slots.as_mut_ptr()) };
+
+    let handle = Handle {
+        address: record.get_untaggedptr() as usize,
+        handled,
+    };
+    REGISTRY.lock().unwrap().handles.insert(id, handle);
+    on_gc_finalize(
+        record,
+        Box::new(move || {
+            REGISTRY.lock().unwrap().handles.remove(&id);
+        }),
+    );
+    record
+}
+
+/// The number of OBJECT if it is a handle of type TYPE.
+fn handle_id(registry: &Registry, type_: &str, object: LispObject) -> Option<EmacsInt> {
+    let record = object.as_vectorlike()?.as_record()?;
+    if record.len() < 2 || !record.get(0).eq(intern(type_)) {
+        return None;
+    }
+    let id = record.get(1).as_fixnum()?;
+    registry
+        .handles
+        .get(&id)
+        .filter(|handle| handle.address == object.get_untaggedptr() as usize)
+        .filter(|handle| handle.handled.type_name() == type_)
+        .map(|_| id)
+}
+
+/// Call F with what OBJECT, a handle of type TYPE, stands for.  Signal
+/// an error if OBJECT is not such a handle.  F must not signal.
+fn with_handle<R>(type_: &str, object: LispObject, f: impl FnOnce(&mut Handled) -> R) -> R {
+    let result = {
+        let mut registry = REGISTRY.lock().unwrap();
+        handle_id(&registry, type_, object)
+            .map(|id| f(&mut registry.handles.get_mut(&id).unwrap().handled))
+    };
+    // The lock has to be released before signaling.
+    result.unwrap_or_else(|| wrong_type!(intern(&format!("{}-p", type_)), object))
+}
+
+/// The snapshot whose handle is HANDLE.  Signal an error if HANDLE is
+/// not a snapshot handle, or if it has been released.
+pub fn snapshot_of(handle: LispObject) -> Arc<BufferSnapshot> {
+    let snapshot = with_handle("buffer-snapshot", handle, |handled| match handled {
+        Handled::Snapshot(snapshot) => snapshot.clone(),
+        Handled::Search(_) => unreachable!(),
+    });
+    snapshot.unwrap_or_else(|| error!("Buffer snapshot has been released"))
+}
+
+/// Return a snapshot of the text of BUFFER, which defaults to the current buffer.
+/// The snapshot keeps the text, and the bounds of its accessible portion,
+/// as they are now, whatever is done to BUFFER afterwards.  The text is
+/// only copied once BUFFER changes while the snapshot is live.
+///
+/// The value is a handle for use with `buffer-snapshot-substring' and
+/// `buffer-snapshot-search', and for native code that reads the text on
+/// other threads.  The snapshot lives until the handle is garbage
+/// collected, or until `buffer-snapshot-release' is called on it.
+#[lisp_fn(min = "0")]
+pub fn buffer_snapshot(buffer: LispBufferOrCurrent) -> LispObject {
+    let buffer: LispBufferRef = buffer.into();
+    if !buffer.is_live() {
+        error!("Selecting deleted buffer");
+    }
+    let snapshot = take_snapshot(buffer);
+    make_handle(Handled::Snapshot(Some(snapshot)), &[buffer.into()])
+}
+
+/// Return t if OBJECT is a handle returned by `buffer-snapshot'.
+/// It may have been released.
+#[lisp_fn]
+pub fn buffer_snapshot_p(object: LispObject) -> bool {
+    handle_id(&REGISTRY.lock().unwrap(), "buffer-snapshot", object).is_some()
+}
+
+/// Return the buffer of which SNAPSHOT was taken.
+#[lisp_fn]
+pub fn buffer_snapshot_buffer(snapshot: LispObject) -> LispObject {
+    snapshot_of(snapshot);
+    snapshot
+        .as_vectorlike()
+        .unwrap()
+        .as_record()
+        .unwrap()
+        .get(2)
+}
+
+/// Return the value `buffer-modified-tick' had when SNAPSHOT was taken.
+#[lisp_fn]
+pub fn buffer_snapshot_modified_tick(snapshot: LispObject) -> EmacsInt {
+    snapshot_of(snapshot).modifications()
+}
+
+/// Release SNAPSHOT, freeing its text unless other snapshots share it,
+/// without waiting for its handle to be garbage collected.  SNAPSHOT
+/// can't be used any more afterwards.  Return t if it was live, nil if
+/// it had already been released.
+#[lisp_fn]
+pub fn buffer_snapshot_release(snapshot: LispObject) -> bool {
+    with_handle("buffer-snapshot", snapshot, |handled| match handled {
+        Handled::Snapshot(snapshot) => snapshot.take().is_some(),
+        Handled::Search(_) => unreachable!(),
+    })
+}
+
+/// The offset in TEXT of the position POS, or DEFAULT if POS is nil.
+fn snapshot_offset(text: &SnapshotText, pos: Option<EmacsInt>, default: usize) -> usize {
+    match pos {
+        None => default,
+        Some(pos) => text
+            .byte_offset(pos as ptrdiff_t)
+            .unwrap_or_else(|| args_out_of_range!(pos, text.charpos(text.bytes().len()))),
+    }
+}
+
+/// Return the text of SNAPSHOT between START and END, as a string.
+/// START and END are positions in the buffer when the snapshot was taken,
+/// and default to the bounds of its accessible portion then.  The
+/// string has no text properties.
+#[lisp_fn(min = "1")]
+pub fn buffer_snapshot_substring(
+    snapshot: LispObject,
+    start: Option<EmacsInt>,
+    end: Option<EmacsInt>,
+) -> LispObject {
+    let snapshot = snapshot_of(snapshot);
+    let multibyte = snapshot.is_multibyte();
+    snapshot.read(|text| {
+        let from = snapshot_offset(&text, start, 0);
+        let to = snapshot_offset(&text, end, text.bytes().len());
+        let (from, to) = if from <= to { (from, to) } else { (to, from) };
+
+        let bytes = &text.bytes()[from..to];
+        let ptr = if bytes.is_empty() {
+            ptr::null()
+        } else {
+            bytes.as_ptr() as *const libc::c_char
+        };
+        unsafe { make_specified_string(ptr, -1, bytes.len() as ptrdiff_t, multibyte) }
+    })
+}
+
+/// REGEXP compiled for searching SNAPSHOT, with case folded if
+/// `case-fold-search' is non-nil in the current buffer.
+fn snapshot_regexp(snapshot: &BufferSnapshot, regexp: LispStringRef) -> regex::bytes::Regex {
+    let case_fold = ThreadState::current_buffer_unchecked()
+        .case_fold_search()
+        .is_not_nil();
+    native_regexp(regexp, snapshot.is_multibyte(), case_fold).unwrap_or_else(|| {
+        error!(
+            "Regexp can't be searched for in a snapshot: {}",
+            String::from_utf8_lossy(regexp.as_slice())
+        )
+    })
+}
+
+/// Search forward in SNAPSHOT from START for a match for REGEXP.
+/// START is a position in the buffer when the snapshot was taken, and
+/// defaults to the start of its accessible portion then.  Case is
+/// ignored if `case-fold-search' is non-nil in the current buffer.
+///
+/// Return a list of the start and end of the match and of each of its
+/// subexpressions, like `match-data' but with nil for subexpressions
+/// that didn't match, or nil if there is no match.  The match data is
+/// not changed.  REGEXP has to be one that the `regex' crate engine can
+/// match (see `prefer-rust-regex-engine'); other regexps signal an error.
+#[lisp_fn(min = "2")]
+pub fn buffer_snapshot_search(
+    snapshot: LispObject,
+    regexp: LispStringRef,
+    start: Option<EmacsInt>,
+) -> LispObject {
+    let snapshot = snapshot_of(snapshot);
+    let regex = snapshot_regexp(&snapshot, regexp);
+    let mut locations = regex.locations();
+
+    let positions = snapshot.read(|text| {
+        let from = snapshot_offset(&text, start, 0);
+        regex.read_captures_at(&mut locations, text.bytes(), from)?;
+        let positions: Vec<_> = (0..locations.len())
+            .flat_map(|i| match locations.pos(i) {
+                Some((start, end)) => vec![
+                    LispObject::from(text.charpos(start) as EmacsInt),
+                    LispObject::from(text.charpos(end) as EmacsInt),
+                ],
+                None => vec![Qnil, Qnil],
+            })
+            .collect();
+        Some(positions)
+    });
+    positions.map_or(Qnil, |positions| list(&positions))
+}
+
+/// The start and end positions of matches.
+type Matches = Vec<(ptrdiff_t, ptrdiff_t)>;
+
+/// A search running on another thread, or its result.
+enum SearchJob {
+    Running(Receiver<Matches>),
+    Done(Matches),
+}
+
+/// The start and end of each of the matches for REGEX in TEXT that
+/// don't overlap.
+fn find_all(regex: &regex::bytes::Regex, text: &SnapshotText) -> Matches {
+    let mut matches = Vec::new();
+    // The position of the byte at OFFSET.
+    let (mut offset, mut charpos) = (0, text.charpos(0));
+    let mut advance = |to: usize| {
+        charpos += text.chars_in(&text.bytes()[offset..to]) as ptrdiff_t;
+        offset = to;
+        charpos
+    };
+    for found in regex.find_iter(text.bytes()) {
+        let start = advance(found.start());
+        let end = advance(found.end());
+        matches.push((start, end));
+    }
+    matches
+}
+
+/// Start searching SNAPSHOT for all the matches for REGEXP, on another thread.
+/// Case is ignored if `case-fold-search' is non-nil in the current buffer.
+/// REGEXP has to be one that `buffer-snapshot-search' can search for.
+///
+/// The text of SNAPSHOT is copied first, unless it already was, so that
+/// the search doesn't hold up changes to the buffer.  Return a handle
+/// for `buffer-snapshot-search-result', which gives the matches once
+/// the search is done.
+#[lisp_fn]
+pub fn buffer_snapshot_search_start(snapshot: LispObject, regexp: LispStringRef) -> LispObject {
+    let handle = snapshot;
+    let snapshot = snapshot_of(handle);
+    let regex = snapshot_regexp(&snapshot, regexp);
+
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        snapshot.detach();
+        let matches = snapshot.read(|text| find_all(&regex, &text));
+        // The handle may have been collected, and the result with it.
+        let _ = sender.send(matches);
+    });
+    make_handle(
+        Handled::Search(SearchJob::Running(receiver)),
+        &[handle, regexp.into()],
+    )
+}
+
+/// Return t if OBJECT is a handle returned by `buffer-snapshot-search-start'.
+#[lisp_fn]
+pub fn buffer_snapshot_search_p(object: LispObject) -> bool {
+    handle_id(&REGISTRY.lock().unwrap(), "buffer-snapshot-search", object).is_some()
+}
+
+/// Return the matches found by SEARCH, or `running' if it isn't done yet.
+/// SEARCH is a handle returned by `buffer-snapshot-search-start'.  The
+/// matches are a list of (START . END) for each match that doesn't
+/// overlap the ones before it, in order.
+#[lisp_fn]
+pub fn buffer_snapshot_search_result(search: LispObject) -> LispObject {
+    let matches = with_handle("buffer-snapshot-search", search, |handled| {
+        let job = match handled {
+            Handled::Search(job) => job,
+            Handled::Snapshot(_) => unreachable!(),
+        };
+        if let SearchJob::Running(receiver) = job {
+            match receiver.try_recv() {
+                Ok(matches) => *job = SearchJob::Done(matches),
+                Err(TryRecvError::Empty) => return None,
+                // The worker panicked.
+                Err(TryRecvError::Disconnected) => *job = SearchJob::Done(Vec::new()),
+            }
+        }
+        match job {
+            SearchJob::Done(matches) => Some(matches.clone()),
+            SearchJob::Running(_) => unreachable!(),
+        }
+    });
+
+    match matches {
+        None => intern("running").into(),
+        Some(matches) => {
+            let matches: Vec<_> = matches
+                .into_iter()
+                .map(|(start, end)| {
+                    LispObject::cons(
+                        LispObject::from(start as EmacsInt),
+                        LispObject::from(end as EmacsInt),
+                    )
+                })
+                .collect();
+            list(&matches)
+        }
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/snapshot_exports.rs"));
+
+#[cfg(test)]
+fn test_snapshot(text: &str, begv: usize, zv: usize) -> BufferSnapshot {
+    BufferSnapshot {
+        text: Arc::new(SharedText {
+            storage: RwLock::new(Storage::Copy(text.as_bytes().into())),
+        }),
+        multibyte: true,
+        modifications: 1,
+        begv,
+        zv,
+        begv_charpos: 1 + text[..begv].chars().count() as ptrdiff_t,
+    }
+}
+
+#[test]
+fn test_snapshot_positions() {
+    let snapshot = test_snapshot("aé\nbçd", 1, 7);
+    snapshot.read(|text| {
+        assert_eq!(text.bytes(), "é\nbç".as_bytes());
+        assert_eq!(text.charpos(0), 2);
+        assert_eq!(text.charpos(2), 3);
+        assert_eq!(text.charpos(6), 6);
+        assert_eq!(text.byte_offset(2), Some(0));
+        assert_eq!(text.byte_offset(3), Some(2));
+        assert_eq!(text.byte_offset(6), Some(6));
+        assert_eq!(text.byte_offset(7), None);
+        assert_eq!(text.byte_offset(1), None);
+    });
+}
+
+#[test]
+fn test_snapshot_detach() {
+    let mut buffer = b"one two".to_vec();
+    let snapshot = BufferSnapshot {
+        text: Arc::new(SharedText {
+            storage: RwLock::new(Storage::Buffer(buffer.as_ptr(), buffer.len())),
+        }),
+        zv: buffer.len(),
+        ..test_snapshot("", 0, 0)
+    };
+    snapshot.detach();
+    buffer[0] = b'O';
+    snapshot.read(|text| assert_eq!(text.bytes(), b"one two"));
+}
+
+#[test]
+fn test_find_all() {
+    let snapshot = Arc::new(test_snapshot("é one\ntwo\ntöne", 0, 16));
+    let worker = {
+        let snapshot = Arc::clone(&snapshot);
+        thread::spawn(move || {
+            let regex = regex::bytes::Regex::new("(?m)^t|ne").unwrap();
+            snapshot.read(|text| find_all(&regex, &text))
+        })
+    };
+    assert_eq!(
+        worker.join().unwrap(),
+        vec![(4, 6), (7, 8), (11, 12), (13, 15)]
+    );
+}
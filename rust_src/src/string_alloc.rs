@@ -0,0 +1,484 @@
+//! Allocation of Lisp strings.
+//!
+//! `Lisp_String`s are allocated in string blocks.  When a new string
+//! block is allocated, all the strings it contains are put on a free
+//! list, and new strings are taken from that list.  During the sweep
+//! phase of garbage collection, string blocks that are entirely free
+//! are freed, except two which are kept.
+//!
+//! String data is allocated from sblocks.  Strings larger than
+//! `LARGE_STRING_BYTES` get an sblock of their own; the data of smaller
+//! strings is sub-allocated from sblocks of `SBLOCK_SIZE` bytes.  Each
+//! string's data is preceded by an `SData` header pointing back to the
+//! string, which points to the data in turn.  When a string is freed,
+//! the back pointer is cleared and the size of its data is recorded in
+//! the header instead, so that the sblocks of small strings can be
+//! compacted by sliding the live data down over the dead.
+//!
+//! Code that makes strings should use `make_string`,
+//! `make_unibyte_string` and `make_multibyte_string`.
+
+use std::mem;
+use std::ptr;
+
+use libc::c_void;
+
+use crate::{
+    gc::ARRAY_MARK_FLAG,
+    gc_stats::consing_since_gc,
+    lisp::LispObject,
+    multibyte::LispStringRef,
+    numbers::MOST_POSITIVE_FIXNUM,
+    remacs_sys::{
+        balance_intervals, emacs_abort, empty_multibyte_string, empty_unibyte_string, globals,
+        lisp_free, lisp_malloc_string_data, lisp_malloc_strings, string_overflow,
+    },
+    remacs_sys::{EmacsInt, Lisp_String},
+};
+
+/// The size of an sblock for small strings: 8192 minus malloc overhead.
+const SBLOCK_SIZE: usize = 8188;
+
+/// Strings with more bytes than this get an sblock of their own.
+const LARGE_STRING_BYTES: usize = 1024;
+
+/// The number of strings in a string block, which takes 1024 bytes
+/// with malloc's overhead.
+const STRING_BLOCK_SIZE: usize =
+    (1020 - mem::size_of::<*mut StringBlock>()) / mem::size_of::<Lisp_String>();
+
+#[repr(C)]
+struct StringBlock {
+    strings: [Lisp_String; STRING_BLOCK_SIZE],
+    next: *mut StringBlock,
+}
+
+/// The size of a string block, for the memory reserve in alloc.c.
+#[no_mangle]
+pub static string_block_bytes: usize = mem::size_of::<StringBlock>();
+
+/// The header of the data of a string in an sblock.  The data follows
+/// the `string` pointer, and `nbytes` is only there while the data is
+/// free.
+#[repr(C)]
+struct SData {
+    /// The string the data belongs to, or null if the data is free.
+    string: *mut Lisp_String,
+    /// The number of bytes in the data, while it is free.
+    nbytes: isize,
+}
+
+const SDATA_DATA_OFFSET: usize = mem::size_of::<*mut Lisp_String>();
+
+#[repr(C)]
+struct SBlock {
+    next: *mut SBlock,
+    /// Where the next string's data goes.  This points past the end of
+    /// the sblock if it is full.
+    next_free: *mut SData,
+    data: [SData; 0],
+}
+
+/// The sblocks of small strings, from the oldest to the current one,
+/// which data is allocated from.
+static mut OLDEST_SBLOCK: *mut SBlock = ptr::null_mut();
+static mut CURRENT_SBLOCK: *mut SBlock = ptr::null_mut();
+
+/// The sblocks of large strings.
+static mut LARGE_SBLOCKS: *mut SBlock = ptr::null_mut();
+
+static mut STRING_BLOCKS: *mut StringBlock = ptr::null_mut();
+
+/// The free strings, chained through `u.next`.
+static mut STRING_FREE_LIST: *mut Lisp_String = ptr::null_mut();
+
+/// The numbers of live and free strings, and of the bytes of the live
+/// ones, as of the last garbage collection.
+#[no_mangle]
+pub static mut total_strings: EmacsInt = 0;
+#[no_mangle]
+pub static mut total_free_strings: EmacsInt = 0;
+#[no_mangle]
+pub static mut total_string_bytes: EmacsInt = 0;
+
+/// Return the size of the data of a string of NBYTES bytes, with its
+/// header, its terminating null byte and padding.  There is always room
+/// for the `nbytes` of a free header.
+fn sdata_size(nbytes: usize) -> usize {
+    let align = mem::align_of::<SData>();
+    let size = SDATA_DATA_OFFSET + (nbytes + 1).max(mem::size_of::<isize>());
+    (size + align - 1) & !(align - 1)
+}
+
+/// The largest number of bytes a string can have, not counting the
+/// terminating null byte.
+fn string_bytes_max() -> usize {
+    let bound = (MOST_POSITIVE_FIXNUM as usize).min(isize::max_value() as usize - 1);
+    bound.min(
+        (usize::max_value()
+            - mem::size_of::<SBlock>()
+            - SDATA_DATA_OFFSET
+            - mem::size_of::<isize>())
+            & !(mem::size_of::<EmacsInt>() - 1),
+    )
+}
+
+unsafe fn sdata_data(data: *mut SData) -> *mut u8 {
+    (data as *mut u8).add(SDATA_DATA_OFFSET)
+}
+
+/// Return the header of the data of the live string S.
+unsafe fn sdata_of_string(s: *mut Lisp_String) -> *mut SData {
+    (*s).u.s.data.sub(SDATA_DATA_OFFSET) as *mut SData
+}
+
+unsafe fn string_bytes(s: *const Lisp_String) -> usize {
+    let s = &(*s).u.s;
+    if s.size_byte < 0 {
+        (s.size & !ARRAY_MARK_FLAG) as usize
+    } else {
+        s.size_byte as usize
+    }
+}
+
+unsafe fn advance(data: *mut SData, size: usize) -> *mut SData {
+    (data as *mut u8).add(size) as *mut SData
+}
+
+unsafe fn sblock_end(b: *mut SBlock) -> *mut SData {
+    advance(b as *mut SData, SBLOCK_SIZE)
+}
+
+unsafe fn push_free_string(s: *mut Lisp_String) {
+    (*s).u.next = STRING_FREE_LIST;
+    STRING_FREE_LIST = s;
+}
+
+/// Return a new `Lisp_String`, without data.
+unsafe fn allocate_string() -> *mut Lisp_String {
+    // If the free list is empty, allocate a new string block, and put
+    // all the strings in it on the free list.
+    if STRING_FREE_LIST.is_null() {
+        let b = lisp_malloc_strings(mem::size_of::<StringBlock>()) as *mut StringBlock;
+        (*b).next = STRING_BLOCKS;
+        STRING_BLOCKS = b;
+        for s in (*b).strings.iter_mut().rev() {
+            // Every string on the free list has null data.
+            s.u.s.data = ptr::null_mut();
+            push_free_string(s);
+        }
+        total_free_strings += STRING_BLOCK_SIZE as EmacsInt;
+    }
+
+    let s = STRING_FREE_LIST;
+    debug_assert!(s as usize >= 1024, "bogus string free list");
+    STRING_FREE_LIST = (*s).u.next;
+
+    total_free_strings -= 1;
+    total_strings += 1;
+    globals.strings_consed += 1;
+    consing_since_gc += mem::size_of::<Lisp_String>() as EmacsInt;
+    s
+}
+
+/// Set up the string S for holding NCHARS characters in NBYTES bytes,
+/// plus a null byte at the end, which is stored.  If S had data
+/// already, that is freed; `aset' does this when a character it stores
+/// takes a different number of bytes.
+#[no_mangle]
+pub unsafe extern "C" fn allocate_string_data(
+    s: *mut Lisp_String,
+    nchars: EmacsInt,
+    nbytes: EmacsInt,
+) {
+    let nbytes = nbytes as usize;
+    if nbytes > string_bytes_max() {
+        string_overflow();
+    }
+
+    let needed = sdata_size(nbytes);
+    let old = if (*s).u.s.data.is_null() {
+        None
+    } else {
+        Some((sdata_of_string(s), string_bytes(s)))
+    };
+
+    let data = if nbytes > LARGE_STRING_BYTES {
+        let size = mem::size_of::<SBlock>() + needed;
+        let b = lisp_malloc_string_data(size, true) as *mut SBlock;
+        (*b).next = LARGE_SBLOCKS;
+        LARGE_SBLOCKS = b;
+        (*b).data.as_mut_ptr()
+    } else {
+        let current = CURRENT_SBLOCK;
+        if current.is_null()
+            || (sblock_end(current) as usize) - ((*current).next_free as usize) < needed
+        {
+            // Not enough room in the current sblock.
+            let b = lisp_malloc_string_data(SBLOCK_SIZE, false) as *mut SBlock;
+            (*b).next = ptr::null_mut();
+            (*b).next_free = (*b).data.as_mut_ptr();
+            if current.is_null() {
+                OLDEST_SBLOCK = b;
+            } else {
+                (*current).next = b;
+            }
+            CURRENT_SBLOCK = b;
+        }
+        let b = CURRENT_SBLOCK;
+        let data = (*b).next_free;
+        (*b).next_free = advance(data, needed);
+        data
+    };
+    (*data).string = s;
+
+    let string = &mut (*s).u.s;
+    string.data = sdata_data(data);
+    string.size = nchars as isize;
+    string.size_byte = nbytes as isize;
+    *string.data.add(nbytes) = 0;
+
+    if let Some((old_data, old_nbytes)) = old {
+        (*old_data).nbytes = old_nbytes as isize;
+        (*old_data).string = ptr::null_mut();
+    }
+
+    consing_since_gc += needed as EmacsInt;
+}
+
+/// Return a new multibyte string of NCHARS characters in NBYTES bytes,
+/// whose contents are left for the caller to fill in.
+#[no_mangle]
+pub extern "C" fn make_uninit_multibyte_string(nchars: EmacsInt, nbytes: EmacsInt) -> LispObject {
+    if nchars < 0 {
+        unsafe { emacs_abort() };
+    }
+    if nbytes == 0 {
+        return unsafe { empty_multibyte_string };
+    }
+    unsafe {
+        let s = allocate_string();
+        (*s).u.s.intervals = ptr::null_mut();
+        allocate_string_data(s, nchars, nbytes);
+        globals.string_chars_consed += nbytes;
+        LispStringRef::new(s).into()
+    }
+}
+
+/// Return a new unibyte string of LENGTH bytes, whose contents are left
+/// for the caller to fill in.
+#[no_mangle]
+pub extern "C" fn make_uninit_string(length: EmacsInt) -> LispObject {
+    if length == 0 {
+        return unsafe { empty_unibyte_string };
+    }
+    let string = make_uninit_multibyte_string(length, length);
+    unsafe { (*string.force_string().as_mut()).u.s.size_byte = -1 };
+    string
+}
+
+/// Return a new string holding BYTES, which must be NCHARS characters
+/// in the internal representation.
+fn make_string_from(bytes: &[u8], nchars: usize, multibyte: bool) -> LispObject {
+    let string = if multibyte {
+        make_uninit_multibyte_string(nchars as EmacsInt, bytes.len() as EmacsInt)
+    } else {
+        make_uninit_string(bytes.len() as EmacsInt)
+    };
+    if !bytes.is_empty() {
+        let mut s = string.force_string();
+        unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), s.data_ptr(), bytes.len()) };
+    }
+    string
+}
+
+/// Return a new string with the contents of S.  The string is unibyte if
+/// S is all ASCII, and multibyte otherwise.
+pub fn make_string(s: &str) -> LispObject {
+    make_string_from(s.as_bytes(), s.chars().count(), !s.is_ascii())
+}
+
+/// Return a new unibyte string with the contents of BYTES.
+pub fn make_unibyte_string(bytes: &[u8]) -> LispObject {
+    make_string_from(bytes, bytes.len(), false)
+}
+
+/// Return a new multibyte string of NCHARS characters, whose internal
+/// representation is BYTES.
+pub fn make_multibyte_string(bytes: &[u8], nchars: usize) -> LispObject {
+    make_string_from(bytes, nchars, true)
+}
+
+/// Return the live string in the string block BLOCK that P points into,
+/// or nil if there is none.  This is how the collector recognizes
+/// strings the stack refers to.
+#[no_mangle]
+pub unsafe extern "C" fn live_string_in_block(block: *mut c_void, p: *const c_void) -> LispObject {
+    let b = block as *mut StringBlock;
+    let start = (*b).strings.as_ptr() as usize;
+    let p = p as usize;
+    if p < start || p - start >= STRING_BLOCK_SIZE * mem::size_of::<Lisp_String>() {
+        return Qnil;
+    }
+    let s = &mut (*b).strings[(p - start) / mem::size_of::<Lisp_String>()];
+    if s.u.s.data.is_null() {
+        Qnil
+    } else {
+        LispStringRef::new(s).into()
+    }
+}
+
+/// Free the strings that weren't marked, and the blocks left empty, and
+/// compact the data of the strings left.  This is called when garbage
+/// is collected.
+#[no_mangle]
+pub unsafe extern "C" fn sweep_strings() {
+    let mut live_blocks: *mut StringBlock = ptr::null_mut();
+
+    STRING_FREE_LIST = ptr::null_mut();
+    total_strings = 0;
+    total_free_strings = 0;
+    total_string_bytes = 0;
+
+    let mut b = STRING_BLOCKS;
+    while !b.is_null() {
+        let next = (*b).next;
+        let free_list_before = STRING_FREE_LIST;
+        let mut nfree = 0;
+
+        for s in (*b).strings.iter_mut() {
+            if s.u.s.data.is_null() {
+                // The string was free already.
+                push_free_string(s);
+                nfree += 1;
+            } else if s.u.s.size & ARRAY_MARK_FLAG != 0 {
+                // The string is live; unmark it.
+                s.u.s.size &= !ARRAY_MARK_FLAG;
+                s.u.s.intervals = balance_intervals(s.u.s.intervals);
+                total_strings += 1;
+                total_string_bytes += string_bytes(s) as EmacsInt;
+            } else {
+                // The string is dead.  Record the size of its data, so
+                // that compaction can step over it, and free it.
+                let data = sdata_of_string(s);
+                (*data).nbytes = string_bytes(s) as isize;
+                (*data).string = ptr::null_mut();
+                s.u.s.data = ptr::null_mut();
+                push_free_string(s);
+                nfree += 1;
+            }
+        }
+
+        // Free the blocks that only hold free strings, except the first
+        // two of them.
+        if nfree == STRING_BLOCK_SIZE && total_free_strings > STRING_BLOCK_SIZE as EmacsInt {
+            lisp_free(b as *mut c_void);
+            STRING_FREE_LIST = free_list_before;
+        } else {
+            total_free_strings += nfree as EmacsInt;
+            (*b).next = live_blocks;
+            live_blocks = b;
+        }
+        b = next;
+    }
+
+    STRING_BLOCKS = live_blocks;
+    free_large_strings();
+    compact_small_strings();
+}
+
+/// Free the sblocks of dead large strings.
+unsafe fn free_large_strings() {
+    let mut live_blocks: *mut SBlock = ptr::null_mut();
+    let mut b = LARGE_SBLOCKS;
+    while !b.is_null() {
+        let next = (*b).next;
+        if (*(*b).data.as_mut_ptr()).string.is_null() {
+            lisp_free(b as *mut c_void);
+        } else {
+            (*b).next = live_blocks;
+            live_blocks = b;
+        }
+        b = next;
+    }
+    LARGE_SBLOCKS = live_blocks;
+}
+
+/// Move the data of the live small strings down over that of the dead
+/// ones, and free the sblocks left empty.  The blocks are gone through
+/// from the oldest to the youngest, in the expectation that old blocks
+/// settle over time, so that less data is moved.
+unsafe fn compact_small_strings() {
+    // TB is the sblock data is moved to, and TO where in TB it goes.
+    let mut tb = OLDEST_SBLOCK;
+    if tb.is_null() {
+        CURRENT_SBLOCK = tb;
+        return;
+    }
+    let mut tb_end = sblock_end(tb);
+    let mut to = (*tb).data.as_mut_ptr();
+
+    let mut b = tb;
+    while !b.is_null() {
+        let end = (*b).next_free;
+        debug_assert!(end <= sblock_end(b));
+        let mut from = (*b).data.as_mut_ptr();
+        while from < end {
+            // Compute where the next data starts now, as moving this
+            // data may overwrite what that is computed from.
+            let s = (*from).string;
+            let nbytes = if s.is_null() {
+                (*from).nbytes as usize
+            } else {
+                string_bytes(s)
+            };
+            debug_assert!(nbytes <= LARGE_STRING_BYTES);
+            let size = sdata_size(nbytes);
+            let from_end = advance(from, size);
+
+            if !s.is_null() {
+                // If TB is full, go on with the next sblock.
+                let mut to_end = advance(to, size);
+                if to_end > tb_end {
+                    (*tb).next_free = to;
+                    tb = (*tb).next;
+                    tb_end = sblock_end(tb);
+                    to = (*tb).data.as_mut_ptr();
+                    to_end = advance(to, size);
+                }
+                if from != to {
+                    debug_assert!(tb != b || to < from);
+                    ptr::copy(from as *const u8, to as *mut u8, size);
+                    (*(*to).string).u.s.data = sdata_data(to);
+                }
+                to = to_end;
+            }
+            from = from_end;
+        }
+        b = (*b).next;
+    }
+
+    // The sblocks after TB hold no live data any more.
+    let mut b = (*tb).next;
+    while !b.is_null() {
+        let next = (*b).next;
+        lisp_free(b as *mut c_void);
+        b = next;
+    }
+
+    (*tb).next_free = to;
+    (*tb).next = ptr::null_mut();
+    CURRENT_SBLOCK = tb;
+}
+
+#[test]
+fn test_sdata_size() {
+    let align = mem::align_of::<SData>();
+    for nbytes in 0..100 {
+        let size = sdata_size(nbytes);
+        assert_eq!(size % align, 0);
+        assert!(size >= SDATA_DATA_OFFSET + nbytes + 1);
+        assert!(size >= mem::size_of::<SData>());
+        assert!(size < SDATA_DATA_OFFSET + nbytes + 1 + align + mem::size_of::<isize>());
+    }
+}
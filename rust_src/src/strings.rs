@@ -1,6 +1,6 @@
 //! Functions operating on strings.
 
-use std::ptr;
+use std::cmp::Ordering;
 
 use libc;
 
@@ -12,12 +12,14 @@ use crate::{
     multibyte,
     multibyte::LispStringRef,
     remacs_sys::EmacsInt,
-    remacs_sys::{
-        make_unibyte_string, make_uninit_multibyte_string,
-        string_to_multibyte as c_string_to_multibyte,
-    },
+    string_alloc::{make_multibyte_string, make_unibyte_string},
 };
 
+#[cfg(any(target_os = "linux", windows))]
+use crate::remacs_sys::str_collate;
+#[cfg(not(any(target_os = "linux", windows)))]
+use crate::{casefiddle::downcase, multibyte::Codepoint};
+
 /// Return t if OBJECT is a string.
 #[lisp_fn]
 pub fn stringp(object: LispObject) -> bool {
@@ -63,38 +65,22 @@ pub fn string_as_multibyte(string: LispStringRef) -> LispObject {
         return string.into();
     }
 
-    let mut nchars = 0;
-    let mut nbytes = 0;
-    unsafe {
-        multibyte::parse_str_as_multibyte(
-            string.const_data_ptr(),
-            string.len_bytes(),
-            &mut nchars,
-            &mut nbytes,
-        )
-    };
-
-    let new_string =
-        unsafe { make_uninit_multibyte_string(nchars as EmacsInt, nbytes as EmacsInt) };
-    let mut new_s = new_string.as_string().unwrap();
-    unsafe {
-        ptr::copy_nonoverlapping(
-            string.const_data_ptr(),
-            new_s.data_ptr(),
-            string.len_bytes() as usize,
-        );
-    }
-    if nbytes != string.len_bytes() {
-        unsafe {
-            multibyte::str_as_multibyte(
-                new_s.data_ptr(),
-                nbytes,
-                string.len_bytes(),
-                ptr::null_mut(),
-            )
-        };
+    let (bytes, nchars) = multibyte::unibyte_as_multibyte(string.as_slice());
+    make_multibyte_string(&bytes, nchars)
+}
+
+/// Return a unibyte string with the same individual bytes as STRING.
+/// If STRING is unibyte, the result is STRING itself.
+/// Otherwise it is a newly created string, with no text properties.
+/// If STRING is multibyte and contains a character of charset
+/// `eight-bit', it is converted to the corresponding single byte.
+#[lisp_fn]
+pub fn string_as_unibyte(string: LispStringRef) -> LispObject {
+    if !string.is_multibyte() {
+        return string.into();
     }
-    new_string
+
+    make_unibyte_string(&multibyte::multibyte_as_unibyte(string.as_slice()))
 }
 
 /// Return a multibyte string with the same individual chars as STRING.
@@ -109,7 +95,14 @@ pub fn string_as_multibyte(string: LispStringRef) -> LispObject {
 /// correct sequence.
 #[lisp_fn]
 pub fn string_to_multibyte(string: LispStringRef) -> LispObject {
-    unsafe { c_string_to_multibyte(string.into()) }
+    if string.is_multibyte() {
+        return string.into();
+    }
+
+    make_multibyte_string(
+        &multibyte::unibyte_to_multibyte(string.as_slice()),
+        string.len_chars() as usize,
+    )
 }
 
 /// Return a unibyte string with the same individual chars as STRING.
@@ -120,31 +113,269 @@ pub fn string_to_multibyte(string: LispStringRef) -> LispObject {
 /// an error is signaled.
 #[lisp_fn]
 pub fn string_to_unibyte(string: LispStringRef) -> LispObject {
-    if string.is_multibyte() {
-        let size = string.len_bytes();
-        let mut buffer: Vec<libc::c_uchar> = Vec::with_capacity(size as usize);
-        let converted_size = unsafe {
-            multibyte::str_to_unibyte(string.const_data_ptr(), buffer.as_mut_ptr(), size)
-        };
-
-        if converted_size < size {
-            error!("Can't convert {}th character to unibyte", converted_size);
-        }
+    if !string.is_multibyte() {
+        return string.into();
+    }
 
-        unsafe { make_unibyte_string(buffer.as_ptr() as *const libc::c_char, size) }
-    } else {
-        string.into()
+    match multibyte::multibyte_to_unibyte(string.as_slice()) {
+        Ok(bytes) => make_unibyte_string(&bytes),
+        Err(index) => error!("Can't convert {}th character to unibyte", index),
     }
 }
 
 /// Return non-nil if STRING1 is less than STRING2 in lexicographic order.
 /// Case is significant.
+/// Symbols are also allowed; their print names are used instead.
 #[lisp_fn]
 pub fn string_lessp(string1: LispObject, string2: LispObject) -> bool {
     let s1 = LispObject::symbol_or_string_as_string(string1);
     let s2 = LispObject::symbol_or_string_as_string(string2);
 
-    s1.as_slice() < s2.as_slice()
+    // Only unibyte strings order like their bytes: in multibyte ones,
+    // raw 8-bit bytes are encoded below the other non-ASCII characters.
+    if s1.is_multibyte() || s2.is_multibyte() {
+        s1.chars().lt(s2.chars())
+    } else {
+        s1.as_slice() < s2.as_slice()
+    }
+}
+
+/// Return non-nil if STRING1 is greater than STRING2 in lexicographic order.
+/// Case is significant.
+/// Symbols are also allowed; their print names are used instead.
+#[lisp_fn]
+pub fn string_greaterp(string1: LispObject, string2: LispObject) -> bool {
+    string_lessp(string2, string1)
+}
+
+/// Return non-nil if S1 is less than S2, as version strings.
+///
+/// This function compares version strings S1 and S2:
+///    1) By prefix lexicographically.
+///    2) Then by version (similarly to version comparison of Debian's dpkg).
+///       Leading zeros in version numbers are ignored.
+///    3) If both prefix and version are equal, compare as ordinary strings.
+///
+/// For example, \"foo2.png\" compares less than \"foo12.png\".
+/// Case is significant.
+/// Symbols are also allowed; their print names are used instead.
+#[lisp_fn]
+pub fn string_version_lessp(string1: LispObject, string2: LispObject) -> bool {
+    let s1 = LispObject::symbol_or_string_as_string(string1);
+    let s2 = LispObject::symbol_or_string_as_string(string2);
+
+    // Null bytes end the C strings filevercmp was written for, so the
+    // pieces between them are compared one after the other.
+    let mut pieces1 = s1.as_slice().split(|&b| b == 0);
+    let mut pieces2 = s2.as_slice().split(|&b| b == 0);
+    loop {
+        match (pieces1.next(), pieces2.next()) {
+            (Some(p1), Some(p2)) => match filevercmp(p1, p2) {
+                Ordering::Equal => {}
+                ordering => return ordering == Ordering::Less,
+            },
+            (None, Some(_)) => return true,
+            (_, None) => return false,
+        }
+    }
+}
+
+/// The sort order of the non-digit byte C in version strings.
+fn version_order(c: u8) -> i32 {
+    if c.is_ascii_digit() {
+        0
+    } else if c.is_ascii_alphabetic() {
+        i32::from(c)
+    } else if c == b'~' {
+        -1
+    } else {
+        i32::from(c) + 256
+    }
+}
+
+/// Return the length of S without its file name suffix, the longest
+/// tail of the form (\.[A-Za-z~][A-Za-z0-9~]*)*.
+fn without_suffix(s: &[u8]) -> usize {
+    let mut suffix = None;
+    let mut read_alpha = false;
+    for (i, &c) in s.iter().enumerate() {
+        if read_alpha {
+            read_alpha = false;
+            if !c.is_ascii_alphabetic() && c != b'~' {
+                suffix = None;
+            }
+        } else if c == b'.' {
+            read_alpha = true;
+            if suffix.is_none() {
+                suffix = Some(i);
+            }
+        } else if !c.is_ascii_alphanumeric() && c != b'~' {
+            suffix = None;
+        }
+    }
+    suffix.unwrap_or_else(|| s.len())
+}
+
+/// Compare the first LEN1 bytes of S1 and LEN2 bytes of S2 by their
+/// alternating non-digit and digit parts, like dpkg.  Digits after the
+/// lengths still count when a number runs into them.
+fn verrevcmp(s1: &[u8], len1: usize, s2: &[u8], len2: usize) -> i32 {
+    let at = |s: &[u8], i: usize| s.get(i).cloned().unwrap_or(0);
+    let (mut i1, mut i2) = (0, 0);
+
+    while i1 < len1 || i2 < len2 {
+        while (i1 < len1 && !at(s1, i1).is_ascii_digit())
+            || (i2 < len2 && !at(s2, i2).is_ascii_digit())
+        {
+            let c1 = if i1 == len1 {
+                0
+            } else {
+                version_order(at(s1, i1))
+            };
+            let c2 = if i2 == len2 {
+                0
+            } else {
+                version_order(at(s2, i2))
+            };
+            if c1 != c2 {
+                return c1 - c2;
+            }
+            i1 += 1;
+            i2 += 1;
+        }
+        while at(s1, i1) == b'0' {
+            i1 += 1;
+        }
+        while at(s2, i2) == b'0' {
+            i2 += 1;
+        }
+        let mut first_diff = 0;
+        while at(s1, i1).is_ascii_digit() && at(s2, i2).is_ascii_digit() {
+            if first_diff == 0 {
+                first_diff = i32::from(at(s1, i1)) - i32::from(at(s2, i2));
+            }
+            i1 += 1;
+            i2 += 1;
+        }
+        if at(s1, i1).is_ascii_digit() {
+            return 1;
+        }
+        if at(s2, i2).is_ascii_digit() {
+            return -1;
+        }
+        if first_diff != 0 {
+            return first_diff;
+        }
+    }
+    0
+}
+
+/// Compare the file names S1 and S2 as versions, like gnulib's
+/// filevercmp.  Hidden files come first, and file name suffixes
+/// only count when the names are otherwise equal.
+fn filevercmp(mut s1: &[u8], mut s2: &[u8]) -> Ordering {
+    let simple = s1.cmp(s2);
+    if simple == Ordering::Equal {
+        return simple;
+    }
+
+    for special in &[&b""[..], b".", b".."] {
+        if s1 == *special {
+            return Ordering::Less;
+        }
+        if s2 == *special {
+            return Ordering::Greater;
+        }
+    }
+    match (s1[0] == b'.', s2[0] == b'.') {
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        (true, true) => {
+            s1 = &s1[1..];
+            s2 = &s2[1..];
+        }
+        (false, false) => {}
+    }
+
+    let mut len1 = without_suffix(s1);
+    let mut len2 = without_suffix(s2);
+    if (len1 < s1.len() || len2 < s2.len()) && s1[..len1] == s2[..len2] {
+        len1 = s1.len();
+        len2 = s2.len();
+    }
+
+    match verrevcmp(s1, len1, s2, len2) {
+        0 => simple,
+        n => n.cmp(&0),
+    }
+}
+
+/// Return t if first arg string is less than second in collation order.
+/// Symbols are also allowed; their print names are used instead.
+///
+/// This function obeys the conventions for collation order in your
+/// locale settings.  For example, punctuation and whitespace characters
+/// might be considered less significant for sorting:
+///
+/// \(sort \\='("11" "12" "1 1" "1 2" "1.1" "1.2") \\='string-collate-lessp)
+///   => ("11" "1 1" "1.1" "12" "1 2" "1.2")
+///
+/// The optional argument LOCALE, a string, overrides the setting of your
+/// current locale identifier for collation.  The value is system
+/// dependent; a LOCALE \"en_US.UTF-8\" is applicable on POSIX systems,
+/// while it would be, e.g., \"enu_USA.1252\" on MS-Windows systems.
+///
+/// If IGNORE-CASE is non-nil, characters are converted to lower-case
+/// before comparing them.
+///
+/// To emulate Unicode-compliant collation on MS-Windows systems,
+/// bind `w32-collate-ignore-punctuation' to a non-nil value, since
+/// the codeset part of the locale cannot be \"UTF-8\" on MS-Windows.
+///
+/// If your system does not support a locale environment, this function
+/// compares the characters of the strings like `string-lessp', and
+/// LOCALE is ignored.
+#[lisp_fn(min = "2")]
+pub fn string_collate_lessp(
+    s1: LispObject,
+    s2: LispObject,
+    locale: LispObject,
+    ignore_case: LispObject,
+) -> bool {
+    let s1 = LispObject::symbol_or_string_as_string(s1);
+    let s2 = LispObject::symbol_or_string_as_string(s2);
+    if locale.is_not_nil() {
+        locale.as_string_or_error();
+    }
+
+    collate(s1, s2, locale, ignore_case) == Ordering::Less
+}
+
+/// Compare S1 and S2 in the collation order of LOCALE.
+#[cfg(any(target_os = "linux", windows))]
+fn collate(
+    s1: LispStringRef,
+    s2: LispStringRef,
+    locale: LispObject,
+    ignore_case: LispObject,
+) -> Ordering {
+    unsafe { str_collate(s1.into(), s2.into(), locale, ignore_case) }.cmp(&0)
+}
+
+/// Compare S1 and S2 character by character, as there is no locale to
+/// tell their collation order.
+#[cfg(not(any(target_os = "linux", windows)))]
+fn collate(
+    s1: LispStringRef,
+    s2: LispStringRef,
+    _locale: LispObject,
+    ignore_case: LispObject,
+) -> Ordering {
+    if ignore_case.is_nil() {
+        return s1.chars().cmp(s2.chars());
+    }
+    let lower = |c: Codepoint| downcase(LispObject::from(c)).as_fixnum_or_error() as Codepoint;
+    s1.chars().map(lower).cmp(s2.chars().map(lower))
 }
 
 /// Return t if OBJECT is a multibyte string.
@@ -213,3 +444,19 @@ fn test_stringlessp() {
     assert!(string_lessp(string, string2));
     assert!(!string_lessp(string2, string));
 }
+
+#[test]
+fn test_filevercmp() {
+    let less = |a: &str, b: &str| filevercmp(a.as_bytes(), b.as_bytes()) == Ordering::Less;
+    assert!(less("foo2.png", "foo12.png"));
+    assert!(less("foo12", "foo12a"));
+    assert!(less("a1.0~rc1", "a1.0"));
+    assert!(less("1.02", "1.3"));
+    assert!(less("", "."));
+    assert!(less(".", ".."));
+    assert!(less("..", ".a"));
+    assert!(less(".z", "a"));
+    assert!(less("foo.tar.gz", "foo1.tar"));
+    assert!(less("01", "1"));
+    assert_eq!(filevercmp(b"abc", b"abc"), Ordering::Equal);
+}
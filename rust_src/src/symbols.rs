@@ -2,13 +2,14 @@
 
 use std::fmt;
 use std::fmt::{Debug, Formatter};
+use std::ptr;
 
 use remacs_macros::lisp_fn;
 
 use crate::{
     buffers::LispBufferLocalValueRef,
     data::Lisp_Fwd,
-    data::{indirect_function, set},
+    data::{default_value, indirect_function, set, set_default},
     lisp::defsubr,
     lisp::{ExternalPtr, LispObject},
     multibyte::LispStringRef,
@@ -49,6 +50,11 @@ impl LispSymbolRef {
         s.function = function;
     }
 
+    pub fn set_interned(mut self, interned: symbol_interned::Type) {
+        let s = unsafe { self.u.s.as_mut() };
+        s.set_interned(interned);
+    }
+
     pub fn is_interned_in_initial_obarray(self) -> bool {
         let s = unsafe { self.u.s.as_ref() };
         s.interned() == symbol_interned::SYMBOL_INTERNED_IN_INITIAL_OBARRAY as u32
@@ -149,9 +155,53 @@ impl LispSymbolRef {
         s.val.fwd = fwd;
     }
 
+    /// Set the value of this variable, which must be a plain one, without
+    /// checking whether it is constant or notifying variable watchers.
+    pub fn set_plain_value(mut self, value: LispObject) {
+        debug_assert!(self.get_redirect() == symbol_redirect::SYMBOL_PLAINVAL);
+        let s = unsafe { self.u.s.as_mut() };
+        s.val.value = value;
+    }
+
+    /// The symbol after this one in its obarray bucket.
+    pub fn get_next(self) -> Option<LispSymbolRef> {
+        let s = unsafe { self.u.s.as_ref() };
+        Some(LispSymbolRef::new(s.next)).filter(|next| !next.is_null())
+    }
+
+    pub fn set_next(mut self, next: Option<LispSymbolRef>) {
+        let s = unsafe { self.u.s.as_mut() };
+        s.next = next.map_or(ptr::null_mut(), |mut next| next.as_mut());
+    }
+
     pub fn iter(self) -> LispSymbolIter {
         LispSymbolIter { current: self }
     }
+
+    /// The value of this variable as seen by the current buffer, or
+    /// `None` if it is void.  Buffer-local and forwarded variables are
+    /// handled like `symbol-value' does.
+    pub fn value(self) -> Option<LispObject> {
+        let value = unsafe { find_symbol_value(self.into()) };
+        Some(value).filter(|value| !value.eq(Qunbound))
+    }
+
+    /// Set the value of this variable, like `set'.  Variable watchers
+    /// are notified.
+    pub fn set_value(self, value: LispObject) {
+        set(self, value);
+    }
+
+    /// The default value of this variable, the one seen in buffers that
+    /// don't have their own, or `None` if it is void.
+    pub fn default_value(self) -> Option<LispObject> {
+        Some(default_value(self)).filter(|value| !value.eq(Qunbound))
+    }
+
+    /// Set the default value of this variable, like `set-default'.
+    pub fn set_default_value(self, value: LispObject) {
+        set_default(self, value);
+    }
 }
 
 impl From<LispObject> for LispSymbolRef {
@@ -390,11 +440,7 @@ pub fn makunbound(symbol: LispSymbolRef) -> LispSymbolRef {
 /// outside of any lexical scope.
 #[lisp_fn]
 pub fn symbol_value(symbol: LispSymbolRef) -> LispObject {
-    let val = unsafe { find_symbol_value(symbol.into()) };
-    if val == Qunbound {
-        void_variable!(symbol);
-    }
-    val
+    symbol.value().unwrap_or_else(|| void_variable!(symbol))
 }
 
 include!(concat!(env!("OUT_DIR"), "/symbols_exports.rs"));
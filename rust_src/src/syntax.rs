@@ -1,22 +1,1154 @@
 //! Functions related to syntax
 
+use std::sync::Mutex;
+
 use remacs_macros::lisp_fn;
 
 use crate::{
+    buffers::LispBufferRef,
     chartable::LispCharTableRef,
     editfns::constrain_to_field,
     lisp::defsubr,
     lisp::LispObject,
+    lists::nth,
+    marker::buf_charpos_to_bytepos,
+    multibyte::{unibyte_to_char, Codepoint, LispStringRef},
     numbers::LispNumber,
+    parse_sexp::{
+        scan_sexps_forward, CommentEnd, CommentStop, ParseState, Parser, ST_COMMENT_STYLE,
+    },
     remacs_sys::{
-        buffer_defaults, scan_lists, scan_words, set_char_table_defalt, set_point, skip_chars,
-        skip_syntaxes,
+        buffer_defaults, globals, maybe_quit, safe_call1, set_char_table_defalt, set_point,
+        skip_chars, skip_syntaxes, syntaxcode, word_boundary_p, Vsyntax_code_object,
     },
-    remacs_sys::{EmacsInt, Qnil, Qsyntax_table, Qsyntax_table_p},
-    remacs_sys::{Fcopy_sequence, Fset_char_table_parent},
+    remacs_sys::{EmacsInt, Qinternal__syntax_propertize, Qnil, Qscan_error, Qsyntax_ppss},
+    remacs_sys::{Fbackward_prefix_chars, Fget_text_property, Fset_char_table_parent},
+    remacs_sys::{Fnext_single_property_change, Fprevious_single_property_change},
+    remacs_sys::{Qsyntax_table, Qsyntax_table_p},
+    sequences::copy_sequence,
+    symbols::fboundp,
     threads::ThreadState,
 };
 
+/// The characters that stand for the syntax classes in syntax
+/// descriptors, indexed by class.
+const SYNTAX_CLASS_CHARS: &[u8; syntaxcode::Smax as usize] = b" .w_()'\"$\\/<>@!|";
+
+/// The flags that may follow the class and matching character of a
+/// syntax descriptor, with the bits they set in its raw form.
+const SYNTAX_FLAGS: [(u8, EmacsInt); 8] = [
+    (b'1', 1 << 16),
+    (b'2', 1 << 17),
+    (b'3', 1 << 18),
+    (b'4', 1 << 19),
+    (b'p', 1 << 20),
+    (b'b', 1 << 21),
+    (b'n', 1 << 22),
+    (b'c', 1 << 23),
+];
+
+/// The class and flags of a syntax, the car of a raw syntax descriptor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SyntaxCode(pub EmacsInt);
+
+impl SyntaxCode {
+    /// The syntax code of ENTRY, an entry of a syntax table.  Entries that
+    /// aren't raw syntax descriptors count as whitespace.
+    pub fn of_entry(entry: LispObject) -> Self {
+        let code = entry
+            .as_cons()
+            .and_then(|cons| cons.car().as_fixnum())
+            .unwrap_or(syntaxcode::Swhitespace as EmacsInt);
+        SyntaxCode(code)
+    }
+
+    /// The syntax class, without the flags.
+    pub fn class(self) -> EmacsInt {
+        self.0 & 0xff
+    }
+
+    /// The character that stands for the class in syntax descriptors.
+    pub fn class_char(self) -> Option<u8> {
+        SYNTAX_CLASS_CHARS.get(self.class() as usize).cloned()
+    }
+
+    fn flag(self, bit: u32) -> bool {
+        (self.0 >> bit) & 1 != 0
+    }
+
+    /// Flag `1': the first character of a two-character comment starter.
+    pub fn comstart_first(self) -> bool {
+        self.flag(16)
+    }
+
+    /// Flag `2': the second character of a two-character comment starter.
+    pub fn comstart_second(self) -> bool {
+        self.flag(17)
+    }
+
+    /// Flag `3': the first character of a two-character comment ender.
+    pub fn comend_first(self) -> bool {
+        self.flag(18)
+    }
+
+    /// Flag `4': the second character of a two-character comment ender.
+    pub fn comend_second(self) -> bool {
+        self.flag(19)
+    }
+
+    /// Flag `p': a prefix character for `backward-prefix-chars'.
+    pub fn prefix(self) -> bool {
+        self.flag(20)
+    }
+
+    /// Flag `b': part of a comment delimiter of style b.
+    pub fn comment_style_b(self) -> bool {
+        self.flag(21)
+    }
+
+    /// Flag `n': part of a nestable comment delimiter.
+    pub fn comment_nested(self) -> bool {
+        self.flag(22)
+    }
+
+    /// Flag `c': part of a comment delimiter of style c.
+    pub fn comment_style_c(self) -> bool {
+        self.flag(23)
+    }
+
+    /// The style of a comment delimiter whose main character has this
+    /// syntax, and whose other character has the syntax OTHER: 1 for
+    /// style b, plus 2 if either character has style c.
+    pub fn comment_style(self, other: SyntaxCode) -> i32 {
+        i32::from(self.comment_style_b())
+            | (i32::from(self.comment_style_c() || other.comment_style_c()) << 1)
+    }
+}
+
+/// The syntax table of the current buffer.
+pub fn current_syntax_table() -> LispCharTableRef {
+    ThreadState::current_buffer_unchecked().syntax_table_.into()
+}
+
+/// The entry for the character C in TABLE, inherited from its parents
+/// if need be.
+pub fn syntax_entry(table: LispCharTableRef, c: Codepoint) -> LispObject {
+    table.get(c as isize)
+}
+
+/// Whether the syntax of the character C at the position POS of the
+/// current buffer has the prefix flag.
+#[no_mangle]
+pub extern "C" fn syntax_prefix_flag_p(pos: isize, c: libc::c_int) -> bool {
+    BufferSyntax::new().code_at(pos, c as Codepoint).prefix()
+}
+
+/// How far from the last position looked up `BufferSyntax::char_at`
+/// steps character by character, rather than converting the position.
+const CURSOR_REACH: isize = 1000;
+
+/// The `syntax-table' property of a stretch of text, and the buffer
+/// modification count it was looked up at.
+struct PropertyInterval {
+    start: isize,
+    end: isize,
+    value: LispObject,
+    modifications: EmacsInt,
+}
+
+/// Looks up the syntax of the characters of the current buffer.  If
+/// `parse-sexp-lookup-properties' is non-nil, `syntax-table' text
+/// properties override the syntax table, and the text is propertized
+/// with `syntax-propertize' as it is scanned.
+///
+/// Scans look at positions next to one another, so the byte position
+/// and the `syntax-table' property of the last position looked up are
+/// kept, to find those of the next one without searching the buffer.
+pub struct BufferSyntax {
+    pub buffer: LispBufferRef,
+    table: LispCharTableRef,
+    use_properties: bool,
+    /// The text modification count, and a character position and its
+    /// byte position.
+    cursor: Option<(EmacsInt, isize, isize)>,
+    interval: Option<PropertyInterval>,
+}
+
+impl BufferSyntax {
+    pub fn new() -> Self {
+        Self {
+            buffer: ThreadState::current_buffer_unchecked(),
+            table: current_syntax_table(),
+            use_properties: unsafe { globals.parse_sexp_lookup_properties },
+            cursor: None,
+            interval: None,
+        }
+    }
+
+    /// The byte position of the position POS.
+    fn byte_pos(&mut self, pos: isize) -> isize {
+        if !self.buffer.multibyte_characters_enabled() {
+            return pos;
+        }
+        let modifications = self.buffer.char_modifications();
+        let pos_byte = match self.cursor {
+            Some((cursor_modifications, mut charpos, mut bytepos))
+                if cursor_modifications == modifications
+                    && (pos - charpos).abs() <= CURSOR_REACH =>
+            {
+                while charpos < pos {
+                    bytepos = self.buffer.inc_pos(bytepos);
+                    charpos += 1;
+                }
+                while charpos > pos {
+                    bytepos = self.buffer.dec_pos(bytepos);
+                    charpos -= 1;
+                }
+                bytepos
+            }
+            _ => buf_charpos_to_bytepos(self.buffer.as_mut(), pos),
+        };
+        self.cursor = Some((modifications, pos, pos_byte));
+        pos_byte
+    }
+
+    /// The character at the position POS, as a multibyte character.
+    pub fn char_at(&mut self, pos: isize) -> Codepoint {
+        let pos_byte = self.byte_pos(pos);
+        let c = self.buffer.fetch_char(pos_byte) as Codepoint;
+        if self.buffer.multibyte_characters_enabled() {
+            c
+        } else {
+            unibyte_to_char(c)
+        }
+    }
+
+    /// The syntax of the character C at the position POS.
+    pub fn code_at(&mut self, pos: isize, c: Codepoint) -> SyntaxCode {
+        if self.use_properties {
+            self.propertize(pos);
+            let prop = self.property_at(pos);
+            if prop.is_cons() {
+                return SyntaxCode::of_entry(prop);
+            }
+            if syntax_table_p(prop) {
+                return SyntaxCode::of_entry(syntax_entry(prop.into(), c));
+            }
+        }
+        SyntaxCode::of_entry(syntax_entry(self.table, c))
+    }
+
+    /// The `syntax-table' property of the character at POS.
+    fn property_at(&mut self, pos: isize) -> LispObject {
+        let modifications = self.buffer.modifications();
+        match self.interval {
+            Some(ref interval)
+                if interval.modifications == modifications
+                    && interval.start <= pos
+                    && pos < interval.end =>
+            {
+                return interval.value;
+            }
+            _ => (),
+        }
+
+        let (begv, zv) = (self.buffer.begv, self.buffer.zv);
+        let value = unsafe { Fget_text_property(pos.into(), Qsyntax_table, Qnil) };
+        if pos < begv || pos >= zv {
+            return value;
+        }
+        let start = unsafe {
+            Fprevious_single_property_change((pos + 1).into(), Qsyntax_table, Qnil, begv.into())
+        };
+        let end =
+            unsafe { Fnext_single_property_change(pos.into(), Qsyntax_table, Qnil, zv.into()) };
+        self.interval = Some(PropertyInterval {
+            start: start.as_fixnum().map_or(pos, |start| start as isize),
+            end: end.as_fixnum().map_or(pos + 1, |end| end as isize),
+            value,
+            modifications,
+        });
+        value
+    }
+
+    /// Make sure `syntax-propertize' has been run up to POS.
+    fn propertize(&self, pos: isize) {
+        let zv = self.buffer.zv as EmacsInt;
+        let done = || unsafe { globals.syntax_propertize__done };
+        if done() > pos as EmacsInt || done() >= zv {
+            return;
+        }
+        let modifications = self.buffer.char_modifications();
+        unsafe {
+            safe_call1(
+                Qinternal__syntax_propertize,
+                (zv.min(pos as EmacsInt + 1)).into(),
+            )
+        };
+        if modifications != self.buffer.char_modifications() {
+            error!("parse-sexp-propertize-function modified the buffer!");
+        }
+        if done() <= pos as EmacsInt && done() < zv {
+            error!("parse-sexp-propertize-function did not move syntax-propertize--done");
+        }
+    }
+}
+
+/// Counts the iterations of a scanning loop, to check for quits now and
+/// then, like `rarely_quit' in C.
+pub struct QuitCounter(pub u16);
+
+impl QuitCounter {
+    pub fn tick(&mut self) {
+        self.0 = self.0.wrapping_add(1);
+        if self.0 == 0 {
+            unsafe { maybe_quit() };
+        }
+    }
+}
+
+/// Whether a character with syntax CODE is part of a word.
+fn is_word_syntax(code: SyntaxCode) -> bool {
+    let class = code.class();
+    class == syntaxcode::Sword as EmacsInt
+        || (unsafe { globals.words_include_escapes }
+            && (class == syntaxcode::Sescape as EmacsInt
+                || class == syntaxcode::Scharquote as EmacsInt))
+}
+
+/// The function that `find-word-boundary-function-table' gives for
+/// words starting or ending with the character C, if it has one.
+fn word_boundary_function(c: Codepoint) -> Option<LispObject> {
+    let func = unsafe { globals.Vfind_word_boundary_function_table }
+        .as_char_table()?
+        .get(c as isize);
+    if func.as_symbol().map_or(false, fboundp) {
+        Some(func)
+    } else {
+        None
+    }
+}
+
+/// Return the position across COUNT words from FROM in the current
+/// buffer, or 0 if that many words cannot be found before the edge of
+/// its accessible part.  A negative COUNT scans backward and stops at
+/// the beginning of a word.
+#[no_mangle]
+pub extern "C" fn scan_words(mut from: isize, mut count: EmacsInt) -> isize {
+    let mut syntax = BufferSyntax::new();
+    let mut quit = QuitCounter(0);
+    let beg = syntax.buffer.begv;
+    let end = syntax.buffer.zv;
+
+    while count > 0 {
+        // Find the first character of the next word; FROM is left after it.
+        let mut ch0 = loop {
+            if from == end {
+                return 0;
+            }
+            let c = syntax.char_at(from);
+            let code = syntax.code_at(from, c);
+            from += 1;
+            if is_word_syntax(code) {
+                break c;
+            }
+            quit.tick();
+        };
+        if let Some(func) = word_boundary_function(ch0) {
+            let pos = call!(func, (from - 1).into(), end.into());
+            match pos.as_fixnum() {
+                Some(pos) if from < pos as isize && pos as isize <= syntax.buffer.zv => {
+                    from = pos as isize
+                }
+                _ => (),
+            }
+        } else {
+            while from != end {
+                let ch1 = syntax.char_at(from);
+                if !is_word_syntax(syntax.code_at(from, ch1))
+                    || unsafe { word_boundary_p(ch0 as libc::c_int, ch1 as libc::c_int) }
+                {
+                    break;
+                }
+                from += 1;
+                ch0 = ch1;
+                quit.tick();
+            }
+        }
+        count -= 1;
+    }
+
+    while count < 0 {
+        // Find the last character of the previous word; FROM is left on it.
+        let mut ch1 = loop {
+            if from == beg {
+                return 0;
+            }
+            from -= 1;
+            let c = syntax.char_at(from);
+            if is_word_syntax(syntax.code_at(from, c)) {
+                break c;
+            }
+            quit.tick();
+        };
+        if let Some(func) = word_boundary_function(ch1) {
+            let pos = call!(func, from.into(), beg.into());
+            match pos.as_fixnum() {
+                Some(pos) if syntax.buffer.begv <= pos as isize && (pos as isize) < from => {
+                    from = pos as isize
+                }
+                _ => (),
+            }
+        } else {
+            while from != beg {
+                let ch0 = syntax.char_at(from - 1);
+                if !is_word_syntax(syntax.code_at(from - 1, ch0))
+                    || unsafe { word_boundary_p(ch0 as libc::c_int, ch1 as libc::c_int) }
+                {
+                    break;
+                }
+                from -= 1;
+                ch1 = ch0;
+                quit.tick();
+            }
+        }
+        count += 1;
+    }
+
+    from
+}
+
+/// The string style of strings started by a string fence, as opposed
+/// to the character that ends the other strings.
+const ST_STRING_STYLE: Codepoint = 256 + 2;
+
+/// Whether CLASS is one of CLASSES.
+fn class_in(class: EmacsInt, classes: &[syntaxcode]) -> bool {
+    classes.iter().any(|&other| class == other as EmacsInt)
+}
+
+/// The class of the character C, whose syntax is CODE, in a scan where
+/// non-ASCII characters are symbol constituents if MULTIBYTE_SYMBOL_P.
+fn class_of(c: Codepoint, code: SyntaxCode, multibyte_symbol_p: bool) -> EmacsInt {
+    if c < 0x80 || !multibyte_symbol_p {
+        code.class()
+    } else {
+        syntaxcode::Ssymbol as EmacsInt
+    }
+}
+
+/// Signal a `scan-error' with MESSAGE about the text from START to END.
+fn scan_error(message: &str, start: isize, end: isize) -> ! {
+    xsignal!(
+        Qscan_error,
+        LispObject::from(message),
+        LispObject::from(start),
+        LispObject::from(end)
+    );
+}
+
+/// Where `find_defun_start` last looked for a defun start, and the one
+/// it found.
+struct DefunStart {
+    buffer: usize,
+    modifications: EmacsInt,
+    begv: isize,
+    pos: isize,
+    value: isize,
+}
+
+lazy_static! {
+    static ref LAST_DEFUN_START: Mutex<Option<DefunStart>> = Mutex::new(None);
+}
+
+/// A position not too far before POS where a defun starts, which is
+/// assumed to be outside of strings and comments.
+///
+/// With `comment-use-syntax-ppss', that is the start of the string or
+/// comment POS is in, or POS.  Otherwise, unless
+/// `open-paren-in-column-0-is-defun-start' says that an open paren at
+/// the start of a line starts a defun, only the start of the accessible
+/// portion of the buffer counts.  The last value found is reused for
+/// positions a little after the last one looked up.
+fn find_defun_start(syntax: &mut BufferSyntax, pos: isize) -> isize {
+    let mut buffer = syntax.buffer;
+    let key = buffer.as_mut() as usize;
+    let begv = buffer.begv;
+    if let Some(ref last) = *LAST_DEFUN_START.lock().unwrap() {
+        if last.buffer == key
+            && pos <= last.pos + 1000
+            && pos >= last.value
+            && last.begv == begv
+            && last.modifications == buffer.modifications()
+        {
+            return last.value;
+        }
+    }
+
+    let value = if unsafe { globals.Vcomment_use_syntax_ppss }.is_not_nil() {
+        let modifications = buffer.char_modifications();
+        let (opoint, opoint_byte) = (buffer.pt, buffer.pt_byte);
+        let ppss = call!(Qsyntax_ppss, pos.into());
+        if modifications != buffer.char_modifications() {
+            error!("syntax-ppss modified the buffer!");
+        }
+        buffer.set_pt_both(opoint, opoint_byte);
+        nth(8, ppss).as_fixnum().map_or(pos, |start| start as isize)
+    } else if !unsafe { globals.open_paren_in_column_0_is_defun_start } {
+        begv
+    } else {
+        // Only an open paren that the syntax table and the text
+        // properties agree on counts.
+        let line_start = |syntax: &mut BufferSyntax, mut pos: isize| {
+            while pos > begv && syntax.char_at(pos - 1) != '\n' as Codepoint {
+                pos -= 1;
+            }
+            pos
+        };
+        let mut start = line_start(syntax, pos);
+        while start > begv {
+            let c = syntax.char_at(start);
+            let code = SyntaxCode::of_entry(syntax_entry(syntax.table, c));
+            if code.class() == syntaxcode::Sopen as EmacsInt
+                && syntax.code_at(start, c).class() == syntaxcode::Sopen as EmacsInt
+            {
+                break;
+            }
+            start = line_start(syntax, start - 1);
+        }
+        start
+    };
+
+    *LAST_DEFUN_START.lock().unwrap() = Some(DefunStart {
+        buffer: key,
+        modifications: buffer.modifications(),
+        begv,
+        pos,
+        value,
+    });
+    value
+}
+
+/// Look back from COMMENT_END, the position of a comment ender of
+/// COMSTYLE, for the start of its comment, going no further back than
+/// STOP.  COMNESTED says whether the comment nests.  Return the start of
+/// the comment, if there is one.
+///
+/// The parity of the string quotes on the way is counted, and the start
+/// is the earliest comment starter an even number of quotes away from a
+/// place assumed to be outside of strings.  When the quotes or comment
+/// delimiters are too mixed up for that, the comment is found by
+/// parsing forward from the start of the defun instead.
+fn back_comment(
+    parser: &mut Parser,
+    comment_end: isize,
+    stop: isize,
+    comnested: bool,
+    comstyle: i32,
+) -> Option<isize> {
+    let mut from = comment_end;
+    // The string the text before COMMENT_END is presumed to be in.
+    let mut string_style = None;
+    let mut string_lossage = false;
+    // A matching comment starter has been passed, and then a comment
+    // ender that doesn't match, so that a matching comment starter
+    // further back may be in another comment, as in { a (* b } c (* d *).
+    let mut comment_lossage = false;
+    let mut comstart_pos = None;
+    let mut defun_start = None;
+    let mut nesting = 1;
+    let mut syntax = SyntaxCode(0);
+    let mut lossage = false;
+
+    while from != stop {
+        parser.quit.tick();
+        from -= 1;
+        let prev_syntax = syntax;
+        let c = parser.syntax.char_at(from);
+        syntax = parser.syntax.code_at(from, c);
+        let mut code = syntax.class();
+
+        let com2start = syntax.comstart_first()
+            && prev_syntax.comstart_second()
+            && comstyle == prev_syntax.comment_style(syntax)
+            && (prev_syntax.comment_nested() || syntax.comment_nested()) == comnested;
+        let mut com2end = syntax.comend_first() && prev_syntax.comend_second();
+        let comstart = com2start || code == syntaxcode::Scomment as EmacsInt;
+
+        // A two-character comment delimiter that overlaps another one,
+        // like |*| in C, is too hard to make sense of backward.
+        if from > stop && (com2end || comstart) {
+            let next_syntax = parser.code_at(from - 1);
+            if ((comstart || comnested) && syntax.comend_second() && next_syntax.comend_first())
+                || ((com2end || comnested)
+                    && syntax.comstart_second()
+                    && comstyle == syntax.comment_style(prev_syntax)
+                    && next_syntax.comstart_first())
+            {
+                lossage = true;
+                break;
+            }
+        }
+
+        // A comment starter that is a comment ender as well, as in
+        // snmp-mode, starts the comment the first time it is seen.
+        if com2start && comstart_pos.is_none() {
+            com2end = false;
+        }
+
+        if com2end {
+            code = syntaxcode::Sendcomment as EmacsInt;
+        } else if com2start {
+            code = syntaxcode::Scomment as EmacsInt;
+        } else if code == syntaxcode::Scomment as EmacsInt
+            && (comstyle != syntax.comment_style(SyntaxCode(0))
+                || syntax.comment_nested() != comnested)
+        {
+            // A comment starter of another style.
+            continue;
+        }
+
+        // Escaped characters don't count, except comment enders, which
+        // can't be escaped.
+        if (unsafe { globals.Vcomment_end_can_be_escaped }
+            || code != syntaxcode::Sendcomment as EmacsInt)
+            && parser.char_quoted(from)
+        {
+            continue;
+        }
+
+        if class_in(
+            code,
+            &[
+                syntaxcode::Sstring,
+                syntaxcode::Sstring_fence,
+                syntaxcode::Scomment_fence,
+            ],
+        ) {
+            let style = if code == syntaxcode::Sstring_fence as EmacsInt {
+                ST_STRING_STYLE
+            } else if code == syntaxcode::Scomment_fence as EmacsInt {
+                ST_COMMENT_STYLE as Codepoint
+            } else {
+                c
+            };
+            match string_style {
+                None => string_style = Some(style),
+                Some(current) if current == style => string_style = None,
+                // There is no telling which of two kinds of string
+                // delimiters are paired backward.
+                Some(_) => string_lossage = true,
+            }
+        } else if code == syntaxcode::Scomment as EmacsInt {
+            if string_style.is_some() || comment_lossage || string_lossage {
+                // Odd string quotes are involved, as in Pascal's
+                // " { " a { " }.
+                lossage = true;
+                break;
+            }
+            if !comnested {
+                // The best comment starter so far.
+                comstart_pos = Some(from);
+            } else {
+                nesting -= 1;
+                if nesting <= 0 {
+                    // Nested comments are balanced, so this one is ours.
+                    return Some(from);
+                }
+            }
+        } else if code == syntaxcode::Sendcomment as EmacsInt {
+            if syntax.comment_style(SyntaxCode(0)) == comstyle
+                && ((com2end && prev_syntax.comment_nested()) || syntax.comment_nested())
+                    == comnested
+            {
+                if comnested {
+                    nesting += 1;
+                } else {
+                    // A comment ender like ours, which anything before
+                    // it would match instead.
+                    break;
+                }
+            } else if comstart_pos.is_some() || c != '\n' as Codepoint {
+                // Mixed comment styles, as in { (* } *).  Newlines are
+                // let through so that multiline comments in C don't all
+                // go the slow way.
+                comment_lossage = true;
+            }
+        } else if code == syntaxcode::Sopen as EmacsInt
+            && unsafe { globals.open_paren_in_column_0_is_defun_start }
+            && unsafe { globals.Vcomment_use_syntax_ppss }.is_nil()
+            && (from == stop || parser.syntax.char_at(from - 1) == '\n' as Codepoint)
+        {
+            // A defun start, assumed to be outside of strings.
+            defun_start = Some(from);
+            break;
+        }
+    }
+
+    if !lossage {
+        return comstart_pos;
+    }
+
+    // Parse forward to COMMENT_END from the start of the defun, which
+    // finds the comment starter passed last.
+    let (mut defun_start, mut adjusted) = match defun_start {
+        Some(start) => (start, true),
+        None => {
+            let start = find_defun_start(&mut parser.syntax, comment_end);
+            (start, start > parser.syntax.buffer.begv)
+        }
+    };
+    let ours = if comnested { 1 } else { -1 };
+    loop {
+        let mut state = ParseState::new(defun_start);
+        scan_sexps_forward(&mut state, comment_end, None, false, CommentStop::Never);
+        defun_start = comment_end;
+        if !adjusted {
+            // The outermost list the comment is in is a closer defun
+            // start for the next time.
+            adjusted = true;
+            if let Some(ref mut last) = *LAST_DEFUN_START.lock().unwrap() {
+                last.value = state
+                    .levelstarts
+                    .first()
+                    .cloned()
+                    .or(state.thislevelstart)
+                    .unwrap_or(last.value);
+            }
+        }
+
+        from = comment_end;
+        if let Some(start) = state.comstr_start {
+            if state.incomment == ours && state.comstyle == comstyle {
+                from = start;
+            } else if state.incomment != 0 {
+                // COMMENT_END is in another comment, which ours may be
+                // nested in, as in { a (* " *).  Try again from in there.
+                defun_start = start + 2;
+            }
+        }
+        parser.quit.tick();
+        if defun_start >= comment_end {
+            break;
+        }
+    }
+
+    Some(from).filter(|&from| from != comment_end)
+}
+
+/// Scan from FROM across COUNT lists, or COUNT balanced expressions if
+/// SEXPFLAG, starting DEPTH parentheses deep, as `scan-lists' and
+/// `scan-sexps' do.
+pub fn scan_lists(
+    from: EmacsInt,
+    mut count: EmacsInt,
+    mut depth: EmacsInt,
+    sexpflag: bool,
+) -> LispObject {
+    let syntax = BufferSyntax::new();
+    let (begv, zv) = (syntax.buffer.begv, syntax.buffer.zv);
+    let stop = if count > 0 { zv } else { begv };
+    let from = (from as isize).max(begv).min(zv);
+    let mut parser = Parser::new(syntax, from, stop);
+
+    // Err out if the depth gets less than this.
+    let min_depth = depth.min(0);
+    let mut last_good = from;
+    let mut mathexit = false;
+    let ignore_comments = unsafe { globals.parse_sexp_ignore_comments };
+    let multibyte_symbol_p = sexpflag && unsafe { globals.multibyte_syntax_as_symbol };
+
+    unsafe { maybe_quit() };
+
+    while count > 0 {
+        loop {
+            if parser.from >= stop {
+                // Between expressions, the end of the buffer is not an
+                // error.
+                if depth != 0 {
+                    scan_error("Unbalanced parentheses", last_good, parser.from);
+                }
+                return Qnil;
+            }
+            parser.quit.tick();
+            let c = parser.syntax.char_at(parser.from);
+            let syntax = parser.syntax.code_at(parser.from, c);
+            let mut code = class_of(c, syntax, multibyte_symbol_p);
+            let mut comnested = syntax.comment_nested();
+            let mut comstyle = syntax.comment_style(SyntaxCode(0));
+            if depth == min_depth {
+                last_good = parser.from;
+            }
+            parser.from += 1;
+            if parser.from < stop && syntax.comstart_first() && ignore_comments {
+                let other = parser.code_at(parser.from);
+                if other.comstart_second() {
+                    // Only a comment ender of the same style ends it.
+                    code = syntaxcode::Scomment as EmacsInt;
+                    comstyle = other.comment_style(syntax);
+                    comnested |= other.comment_nested();
+                    parser.from += 1;
+                }
+            }
+
+            if syntax.prefix() {
+                continue;
+            }
+
+            if class_in(
+                code,
+                &[
+                    syntaxcode::Sescape,
+                    syntaxcode::Scharquote,
+                    syntaxcode::Sword,
+                    syntaxcode::Ssymbol,
+                ],
+            ) {
+                if class_in(code, &[syntaxcode::Sescape, syntaxcode::Scharquote]) {
+                    if parser.from == stop {
+                        scan_error("Unbalanced parentheses", last_good, parser.from);
+                    }
+                    // The escaped character is a word constituent.
+                    parser.from += 1;
+                }
+                if depth != 0 || !sexpflag {
+                    continue;
+                }
+                // This word counts as an expression; return at its end.
+                while parser.from < stop {
+                    let c = parser.syntax.char_at(parser.from);
+                    let code =
+                        class_of(c, parser.syntax.code_at(parser.from, c), multibyte_symbol_p);
+                    if class_in(code, &[syntaxcode::Scharquote, syntaxcode::Sescape]) {
+                        parser.from += 1;
+                        if parser.from == stop {
+                            scan_error("Unbalanced parentheses", last_good, parser.from);
+                        }
+                    } else if !class_in(
+                        code,
+                        &[syntaxcode::Sword, syntaxcode::Ssymbol, syntaxcode::Squote],
+                    ) {
+                        break;
+                    }
+                    parser.from += 1;
+                    parser.quit.tick();
+                }
+                break;
+            } else if class_in(code, &[syntaxcode::Scomment_fence, syntaxcode::Scomment]) {
+                if !ignore_comments {
+                    continue;
+                }
+                if code == syntaxcode::Scomment_fence as EmacsInt {
+                    comstyle = ST_COMMENT_STYLE;
+                }
+                match parser.forward_comment(comnested.into(), comstyle, SyntaxCode(0)) {
+                    CommentEnd::Found => parser.from += 1,
+                    CommentEnd::Unfinished(..) if depth == 0 => break,
+                    CommentEnd::Unfinished(..) => {
+                        scan_error("Unbalanced parentheses", last_good, parser.from)
+                    }
+                }
+            } else if class_in(
+                code,
+                &[syntaxcode::Smath, syntaxcode::Sopen, syntaxcode::Sclose],
+            ) {
+                let opens = if code == syntaxcode::Smath as EmacsInt {
+                    if !sexpflag {
+                        continue;
+                    }
+                    if parser.from != stop && parser.syntax.char_at(parser.from) == c {
+                        parser.from += 1;
+                    }
+                    mathexit = !mathexit;
+                    mathexit
+                } else {
+                    code == syntaxcode::Sopen as EmacsInt
+                };
+                if opens {
+                    depth += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    if depth < min_depth {
+                        scan_error(
+                            "Containing expression ends prematurely",
+                            last_good,
+                            parser.from,
+                        );
+                    }
+                }
+            } else if class_in(code, &[syntaxcode::Sstring, syntaxcode::Sstring_fence]) {
+                let stringterm = parser.syntax.char_at(parser.from - 1);
+                loop {
+                    if parser.from >= stop {
+                        scan_error("Unbalanced parentheses", last_good, parser.from);
+                    }
+                    let c = parser.syntax.char_at(parser.from);
+                    let c_code =
+                        class_of(c, parser.syntax.code_at(parser.from, c), multibyte_symbol_p);
+                    if code == syntaxcode::Sstring as EmacsInt {
+                        if c == stringterm && c_code == syntaxcode::Sstring as EmacsInt {
+                            break;
+                        }
+                    } else if c_code == syntaxcode::Sstring_fence as EmacsInt {
+                        break;
+                    }
+                    if class_in(c_code, &[syntaxcode::Scharquote, syntaxcode::Sescape]) {
+                        parser.from += 1;
+                    }
+                    parser.from += 1;
+                    parser.quit.tick();
+                }
+                parser.from += 1;
+                if depth == 0 && sexpflag {
+                    break;
+                }
+            }
+            // Whitespace, punctuation, quotes and comment enders are
+            // skipped.
+        }
+        count -= 1;
+    }
+
+    while count < 0 {
+        loop {
+            if parser.from <= stop {
+                if depth != 0 {
+                    scan_error("Unbalanced parentheses", last_good, parser.from);
+                }
+                return Qnil;
+            }
+            parser.quit.tick();
+            parser.from -= 1;
+            let c = parser.syntax.char_at(parser.from);
+            let syntax = parser.syntax.code_at(parser.from, c);
+            let mut code = class_of(c, syntax, multibyte_symbol_p);
+            if depth == min_depth {
+                last_good = parser.from;
+            }
+            let mut comnested = syntax.comment_nested();
+            let mut comstyle = if code == syntaxcode::Sendcomment as EmacsInt {
+                syntax.comment_style(SyntaxCode(0))
+            } else {
+                0
+            };
+            if parser.from > stop
+                && syntax.comend_second()
+                && ignore_comments
+                && parser.code_at(parser.from - 1).comend_first()
+            {
+                // Only a comment starter of the same style starts the
+                // comment.
+                parser.from -= 1;
+                let other = parser.code_at(parser.from);
+                code = syntaxcode::Sendcomment as EmacsInt;
+                comstyle = other.comment_style(syntax);
+                comnested |= other.comment_nested();
+            }
+
+            // Quoting turns anything but a comment ender into a word
+            // constituent.
+            if code != syntaxcode::Sendcomment as EmacsInt && parser.char_quoted(parser.from) {
+                parser.from -= 1;
+                code = syntaxcode::Sword as EmacsInt;
+            } else if syntax.prefix() {
+                continue;
+            }
+
+            if class_in(
+                code,
+                &[
+                    syntaxcode::Sword,
+                    syntaxcode::Ssymbol,
+                    syntaxcode::Sescape,
+                    syntaxcode::Scharquote,
+                ],
+            ) {
+                if depth != 0 || !sexpflag {
+                    continue;
+                }
+                // This word counts as an expression, finished once it
+                // has been passed.
+                while parser.from > stop {
+                    let c1 = parser.syntax.char_at(parser.from - 1);
+                    let c1_code = class_of(
+                        c1,
+                        parser.syntax.code_at(parser.from - 1, c1),
+                        multibyte_symbol_p,
+                    );
+                    // A comment ender can't be quoted.
+                    if c1_code == syntaxcode::Sendcomment as EmacsInt {
+                        break;
+                    }
+                    if parser.char_quoted(parser.from - 1) {
+                        parser.from -= 1;
+                    } else if !class_in(
+                        c1_code,
+                        &[syntaxcode::Sword, syntaxcode::Ssymbol, syntaxcode::Squote],
+                    ) {
+                        break;
+                    }
+                    parser.from -= 1;
+                    parser.quit.tick();
+                }
+                break;
+            } else if class_in(
+                code,
+                &[syntaxcode::Smath, syntaxcode::Sclose, syntaxcode::Sopen],
+            ) {
+                let closes = if code == syntaxcode::Smath as EmacsInt {
+                    if !sexpflag {
+                        continue;
+                    }
+                    if parser.from > stop && parser.syntax.char_at(parser.from - 1) == c {
+                        parser.from -= 1;
+                    }
+                    mathexit = !mathexit;
+                    mathexit
+                } else {
+                    code == syntaxcode::Sclose as EmacsInt
+                };
+                if closes {
+                    depth += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    if depth < min_depth {
+                        scan_error(
+                            "Containing expression ends prematurely",
+                            last_good,
+                            parser.from,
+                        );
+                    }
+                }
+            } else if code == syntaxcode::Sendcomment as EmacsInt {
+                if !ignore_comments {
+                    continue;
+                }
+                // A comment ender with no comment before it is skipped.
+                let comment_end = parser.from;
+                if let Some(start) =
+                    back_comment(&mut parser, comment_end, stop, comnested, comstyle)
+                {
+                    parser.from = start;
+                }
+            } else if class_in(
+                code,
+                &[syntaxcode::Scomment_fence, syntaxcode::Sstring_fence],
+            ) {
+                loop {
+                    if parser.from == stop {
+                        scan_error("Unbalanced parentheses", last_good, parser.from);
+                    }
+                    parser.from -= 1;
+                    if !parser.char_quoted(parser.from) {
+                        let c = parser.syntax.char_at(parser.from);
+                        let c_code = parser.syntax.code_at(parser.from, c);
+                        if class_of(c, c_code, multibyte_symbol_p) == code {
+                            break;
+                        }
+                    }
+                    parser.quit.tick();
+                }
+                if code == syntaxcode::Sstring_fence as EmacsInt && depth == 0 && sexpflag {
+                    break;
+                }
+            } else if code == syntaxcode::Sstring as EmacsInt {
+                let stringterm = c;
+                loop {
+                    if parser.from == stop {
+                        scan_error("Unbalanced parentheses", last_good, parser.from);
+                    }
+                    parser.from -= 1;
+                    if !parser.char_quoted(parser.from) {
+                        let c = parser.syntax.char_at(parser.from);
+                        let c_code = parser.syntax.code_at(parser.from, c);
+                        if c == stringterm
+                            && class_of(c, c_code, multibyte_symbol_p)
+                                == syntaxcode::Sstring as EmacsInt
+                        {
+                            break;
+                        }
+                    }
+                    parser.quit.tick();
+                }
+                if depth == 0 && sexpflag {
+                    break;
+                }
+            }
+        }
+        count += 1;
+    }
+
+    LispObject::from(parser.from as EmacsInt)
+}
+
+/// Why a syntax descriptor is invalid.
+#[derive(Debug, PartialEq)]
+pub enum SyntaxDescriptorError {
+    /// The descriptor is empty, or starts with a character that stands
+    /// for no syntax class.
+    InvalidClass(Option<Codepoint>),
+    /// The flag at this index of the descriptor is not one of those
+    /// listed by `modify-syntax-entry'.
+    InvalidFlag(usize, Codepoint),
+}
+
+/// The syntax class that C stands for in a syntax descriptor.
+fn syntax_class_of_char(c: Codepoint) -> Option<EmacsInt> {
+    if c == Codepoint::from(b'-') {
+        return Some(syntaxcode::Swhitespace as EmacsInt);
+    }
+    SYNTAX_CLASS_CHARS
+        .iter()
+        .position(|&class_char| Codepoint::from(class_char) == c)
+        .map(|class| class as EmacsInt)
+}
+
+/// Parse the syntax descriptor DESCRIPTOR, as `string-to-syntax' does.
+/// Return the raw syntax code and matching character, or `None` if the
+/// descriptor says to inherit the syntax from the standard table.
+pub fn parse_syntax_descriptor(
+    descriptor: &[Codepoint],
+) -> Result<Option<(EmacsInt, Option<Codepoint>)>, SyntaxDescriptorError> {
+    let first = descriptor.first().cloned();
+    let mut code = first
+        .and_then(syntax_class_of_char)
+        .ok_or(SyntaxDescriptorError::InvalidClass(first))?;
+    if code == syntaxcode::Sinherit as EmacsInt {
+        return Ok(None);
+    }
+
+    let matching = descriptor
+        .get(1)
+        .cloned()
+        .filter(|&c| c != Codepoint::from(b' '));
+
+    for (index, &flag) in descriptor.iter().enumerate().skip(2) {
+        if flag == Codepoint::from(b' ') {
+            continue;
+        }
+        match SYNTAX_FLAGS
+            .iter()
+            .find(|&&(flag_char, _)| Codepoint::from(flag_char) == flag)
+        {
+            Some(&(_, bit)) => code |= bit,
+            None => return Err(SyntaxDescriptorError::InvalidFlag(index, flag)),
+        }
+    }
+    Ok(Some((code, matching)))
+}
+
 /// Return the current syntax table. This is the one specified by the
 /// current buffer.
 #[lisp_fn]
@@ -58,7 +1190,62 @@ def_lisp_sym!(Qsyntax_table_p, "syntax-table-p");
 // with the same name
 #[lisp_fn(name = "scan-lists", c_name = "scan_lists")]
 pub fn scan_lists_lisp(from: EmacsInt, count: EmacsInt, depth: EmacsInt) -> LispObject {
-    unsafe { scan_lists(from, count, depth, false) }
+    scan_lists(from, count, depth, false)
+}
+
+/// Scan from character number FROM by COUNT balanced expressions.
+/// If COUNT is negative, scan backwards.
+/// Returns the character number of the position thus found.
+///
+/// Comments are ignored if `parse-sexp-ignore-comments' is non-nil.
+///
+/// If the beginning or end of (the accessible part of) the buffer is reached
+/// in the middle of a parenthetical grouping, an error is signaled.
+/// If the beginning or end is reached between groupings
+/// but before count is used up, nil is returned.
+#[lisp_fn]
+pub fn scan_sexps(from: EmacsInt, count: EmacsInt) -> LispObject {
+    scan_lists(from, count, 0, true)
+}
+
+/// Move forward across one balanced expression (sexp).
+/// With ARG, do it that many times.  Negative arg -N means move
+/// backward across N balanced expressions.  This command assumes
+/// point is not in a string or comment.  Calls
+/// `forward-sexp-function' to do the work, if that is non-nil.  If
+/// unable to move over a sexp, signal `scan-error' with three
+/// arguments: a message, the start of the obstacle (usually a
+/// parenthesis or list marker of some kind), and end of the
+/// obstacle.
+#[lisp_fn(min = "0", intspec = "^p")]
+pub fn forward_sexp(arg: Option<EmacsInt>) -> LispObject {
+    let arg = arg.unwrap_or(1);
+    let function = unsafe { globals.Vforward_sexp_function };
+    if function.is_not_nil() {
+        return call!(function, arg.into());
+    }
+
+    let cur_buf = ThreadState::current_buffer_unchecked();
+    let pos = match scan_sexps(cur_buf.pt as EmacsInt, arg).as_fixnum() {
+        Some(pos) => pos as isize,
+        None if arg > 0 => cur_buf.zv,
+        None => cur_buf.begv,
+    };
+    unsafe { set_point(pos) };
+    if arg < 0 {
+        unsafe { Fbackward_prefix_chars() };
+    }
+    Qnil
+}
+
+/// Move backward across one balanced expression (sexp).
+/// With ARG, do it that many times.  Negative arg -N means
+/// move forward across N balanced expressions.
+/// This command assumes point is not in a string or comment.
+/// Uses `forward-sexp' to do the work.
+#[lisp_fn(min = "0", intspec = "^p")]
+pub fn backward_sexp(arg: Option<EmacsInt>) -> LispObject {
+    forward_sexp(Some(-arg.unwrap_or(1)))
 }
 
 /// Select a new syntax table for the current buffer.
@@ -105,7 +1292,7 @@ pub fn copy_syntax_table(mut table: LispObject) -> LispCharTableRef {
     } else {
         table = buffer_table;
     }
-    let copy: LispCharTableRef = unsafe { Fcopy_sequence(table) }.into();
+    let copy: LispCharTableRef = copy_sequence(table).into();
 
     // Only the standard syntax table should have a default element.
     // Other syntax tables should inherit from parents instead.
@@ -120,6 +1307,87 @@ pub fn copy_syntax_table(mut table: LispObject) -> LispCharTableRef {
     copy
 }
 
+/// Return the syntax code of CHARACTER, described by a character.
+/// For example, if CHARACTER is a word constituent, the
+/// character `w' (119) is returned.
+/// The characters that correspond to various syntax codes
+/// are listed in the documentation of `modify-syntax-entry'.
+///
+/// If you're trying to determine the syntax of characters in the buffer,
+/// this is probably the wrong function to use, because it can't take
+/// `syntax-table' text properties into account.  Consider using
+/// `syntax-after' instead.
+#[lisp_fn]
+pub fn char_syntax(character: LispObject) -> EmacsInt {
+    let c = character.as_character_or_error();
+    let code = SyntaxCode::of_entry(syntax_entry(current_syntax_table(), c));
+    EmacsInt::from(code.class_char().unwrap_or(b' '))
+}
+
+/// Return the matching parenthesis of CHARACTER, or nil if none.
+#[lisp_fn]
+pub fn matching_paren(character: LispObject) -> LispObject {
+    let c = character.as_character_or_error();
+    let entry = syntax_entry(current_syntax_table(), c);
+    let class = SyntaxCode::of_entry(entry).class();
+    if class == syntaxcode::Sopen as EmacsInt || class == syntaxcode::Sclose as EmacsInt {
+        entry.as_cons().map_or(Qnil, |cons| cons.cdr())
+    } else {
+        Qnil
+    }
+}
+
+/// Convert a syntax descriptor STRING into a raw syntax descriptor.
+/// STRING should be a string of the form allowed as argument of
+/// `modify-syntax-entry'.  The return value is a raw syntax descriptor: a
+/// cons cell (CODE . MATCHING-CHAR) which can be used, for example, as
+/// the value of a `syntax-table' text property.
+///
+/// An error is signaled if the class or any of the flags of STRING is
+/// invalid; its message gives the offending character and its index.
+#[lisp_fn]
+pub fn string_to_syntax(string: LispStringRef) -> LispObject {
+    let descriptor: Vec<Codepoint> = string.chars().collect();
+    match parse_syntax_descriptor(&descriptor) {
+        Ok(None) => Qnil,
+        Ok(Some((code, None))) if code < syntaxcode::Smax as EmacsInt => {
+            // The descriptors of plain classes are shared among syntax
+            // tables, so that `describe-syntax' can tell them apart.
+            unsafe { Vsyntax_code_object }
+                .as_vector_or_error()
+                .get(code as usize)
+        }
+        Ok(Some((code, matching))) => {
+            LispObject::cons(code, matching.map_or(Qnil, LispObject::from))
+        }
+        Err(SyntaxDescriptorError::InvalidClass(None)) => error!("Empty syntax descriptor"),
+        Err(SyntaxDescriptorError::InvalidClass(Some(c))) => {
+            error!("Invalid syntax description letter: {}", char_to_string(c))
+        }
+        Err(SyntaxDescriptorError::InvalidFlag(index, c)) => error!(
+            "Invalid syntax flag `{}' at index {} of \"{}\"",
+            char_to_string(c),
+            index,
+            String::from_utf8_lossy(string.as_slice())
+        ),
+    }
+}
+
+fn char_to_string(c: Codepoint) -> String {
+    std::char::from_u32(c).map_or_else(|| format!("\\{:o}", c), |c| c.to_string())
+}
+
+/// Return the character that stands for the syntax class SYNTAX.
+/// SYNTAX is the integer code of a syntax class, as in the car of a raw
+/// syntax descriptor (see `string-to-syntax'), without any flags.
+#[lisp_fn]
+pub fn syntax_class_to_char(syntax: EmacsInt) -> EmacsInt {
+    if syntax < 0 || syntax >= syntaxcode::Smax as EmacsInt {
+        args_out_of_range!(syntaxcode::Smax as EmacsInt - 1, syntax);
+    }
+    EmacsInt::from(SYNTAX_CLASS_CHARS[syntax as usize])
+}
+
 /// Move point forward ARG words (backward if ARG is negative).
 /// If ARG is omitted or nil, move point forward one word.
 /// Normally returns t.
@@ -138,7 +1406,7 @@ pub fn forward_word(arg: Option<EmacsInt>) -> bool {
     let cur_buf = ThreadState::current_buffer_unchecked();
     let point = cur_buf.pt;
 
-    let (mut val, orig_val) = match unsafe { scan_words(point, arg) } {
+    let (mut val, orig_val) = match scan_words(point, arg) {
         0 => {
             let val = if arg > 0 { cur_buf.zv } else { cur_buf.begv };
             (val, 0)
@@ -157,6 +1425,20 @@ pub fn forward_word(arg: Option<EmacsInt>) -> bool {
     val == orig_val
 }
 
+/// Move backward until encountering the beginning of a word.
+/// With argument ARG, do this that many times.
+/// If ARG is omitted or nil, move point backward one word.
+///
+/// The word boundaries are normally determined by the buffer's syntax
+/// table, but `find-word-boundary-function-table', such as set up
+/// by `subword-mode', can change that.  If a Lisp program needs to
+/// move by words determined strictly by the syntax table, it should
+/// use `backward-word-strictly' instead.
+#[lisp_fn(min = "0", intspec = "^p")]
+pub fn backward_word(arg: Option<EmacsInt>) -> bool {
+    forward_word(Some(-arg.unwrap_or(1)))
+}
+
 /// Move point forward, stopping before a char not in STRING, or at pos LIM.
 /// STRING is like the inside of a `[...]' in a regular expression
 /// except that `]' is never special and `\\' quotes `^', `-' or `\\'
@@ -201,3 +1483,62 @@ pub fn skip_syntax_backward(syntax: LispObject, lim: LispObject) -> LispObject {
 }
 
 include!(concat!(env!("OUT_DIR"), "/syntax_exports.rs"));
+
+#[test]
+fn test_parse_syntax_descriptor() {
+    let parse = |descriptor: &str| {
+        let chars: Vec<Codepoint> = descriptor.chars().map(Codepoint::from).collect();
+        parse_syntax_descriptor(&chars)
+    };
+    let code = |class: syntaxcode| class as EmacsInt;
+
+    assert_eq!(parse("w"), Ok(Some((code(syntaxcode::Sword), None))));
+    assert_eq!(parse("-"), Ok(Some((code(syntaxcode::Swhitespace), None))));
+    assert_eq!(
+        parse("()"),
+        Ok(Some((code(syntaxcode::Sopen), Some(Codepoint::from(b')')))))
+    );
+    assert_eq!(
+        parse(". 124b"),
+        Ok(Some((
+            code(syntaxcode::Spunct) | 1 << 16 | 1 << 17 | 1 << 19 | 1 << 21,
+            None
+        )))
+    );
+    assert_eq!(parse("@"), Ok(None));
+    assert_eq!(parse(""), Err(SyntaxDescriptorError::InvalidClass(None)));
+    assert_eq!(
+        parse("x"),
+        Err(SyntaxDescriptorError::InvalidClass(Some(Codepoint::from(
+            b'x'
+        ))))
+    );
+    assert_eq!(
+        parse(". 1x"),
+        Err(SyntaxDescriptorError::InvalidFlag(3, Codepoint::from(b'x')))
+    );
+}
+
+#[test]
+fn test_syntax_code_flags() {
+    let flags = |code: SyntaxCode| {
+        [
+            code.comstart_first(),
+            code.comstart_second(),
+            code.comend_first(),
+            code.comend_second(),
+            code.prefix(),
+            code.comment_style_b(),
+            code.comment_nested(),
+            code.comment_style_c(),
+        ]
+    };
+    for (i, &(flag_char, bit)) in SYNTAX_FLAGS.iter().enumerate() {
+        let code = SyntaxCode(syntaxcode::Spunct as EmacsInt | bit);
+        assert_eq!(code.class(), syntaxcode::Spunct as EmacsInt);
+        assert_eq!(code.class_char(), Some(b'.'));
+        let set: Vec<usize> = (0..8).filter(|&j| flags(code)[j]).collect();
+        assert_eq!(set, vec![i], "flag {}", flag_char as char);
+    }
+    assert_eq!(SyntaxCode(0xff).class_char(), None);
+}
@@ -1,6 +1,27 @@
+use std::{
+    ptr,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
 use libc::c_int;
 
-use crate::{dispnew::LispGlyphRef, frames::LispFrameRef};
+use remacs_macros::lisp_fn;
+
+use crate::{
+    dispnew::LispGlyphRef,
+    eval::unbind_to,
+    frames::{selected_frame, LispFrameRef},
+    lisp::{defsubr, LispObject},
+    obarray::intern,
+    remacs_sys::globals,
+    remacs_sys::Qnil,
+    remacs_sys::{
+        change_frame_size, get_selected_tty_size, init_all_sys_modes, noninteractive,
+        pending_signals, record_unwind_protect_void, reset_all_sys_modes, run_hook,
+        stuff_buffered_input, sys_subshell, tty_list,
+    },
+    threads::c_specpdl_index,
+};
 
 #[no_mangle]
 pub unsafe extern "C" fn update_begin(mut f: LispFrameRef) {
@@ -82,3 +103,215 @@ pub unsafe extern "C" fn ins_del_lines(mut f: LispFrameRef, vpos: c_int, n: c_in
         hook(f.as_mut(), vpos, n)
     }
 }
+
+// Suspending and resuming.
+
+/// Set by the SIGTSTP handler; Emacs suspends itself the next time it
+/// processes pending signals.
+static SUSPEND_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Set by the SIGCONT handler.  When Emacs was stopped behind its back,
+/// e.g. by SIGSTOP, the terminal modes have to be set up again.
+static CONTINUED: AtomicBool = AtomicBool::new(false);
+
+/// What the SIGTSTP and SIGCONT handler does with a signal.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SuspendAction {
+    /// Suspend the next time pending signals are processed.
+    Defer,
+    /// Stop right away, as if Emacs had no handler.
+    Stop,
+    /// Set up the terminal modes again.
+    Reinit,
+    /// Nothing to do.
+    Ignore,
+}
+
+/// Return what to do with signal SIG.  Only Emacs on a tty defers
+/// suspending, so that it can reset the terminal modes first; in batch
+/// mode or without a tty frame there is nothing to reset, and SIGTSTP
+/// has its usual immediate effect.
+fn suspend_action(sig: c_int, on_tty: bool) -> SuspendAction {
+    match sig {
+        libc::SIGTSTP if on_tty => SuspendAction::Defer,
+        libc::SIGTSTP => SuspendAction::Stop,
+        libc::SIGCONT if on_tty => SuspendAction::Reinit,
+        _ => SuspendAction::Ignore,
+    }
+}
+
+/// Handler for SIGTSTP and SIGCONT.  The terminal can't be touched
+/// from a signal handler, so on a tty only record the signal and let
+/// `process_pending_suspend' do the work.
+#[no_mangle]
+pub extern "C" fn handle_suspend_signal(sig: c_int) {
+    let on_tty = unsafe { !noninteractive && !tty_list.is_null() };
+    match suspend_action(sig, on_tty) {
+        SuspendAction::Defer => SUSPEND_REQUESTED.store(true, Ordering::SeqCst),
+        SuspendAction::Reinit => CONTINUED.store(true, Ordering::SeqCst),
+        SuspendAction::Stop => {
+            stop_with_default_action(unsafe { libc::getpid() });
+            return;
+        }
+        SuspendAction::Ignore => return,
+    }
+    unsafe { ptr::write_volatile(&mut pending_signals, true) };
+}
+
+/// Suspend the Emacs process; give terminal to its superior.
+#[no_mangle]
+pub extern "C" fn sys_suspend() {
+    stop_process();
+}
+
+#[cfg(unix)]
+fn stop_process() {
+    stop_with_default_action(0);
+}
+
+/// Send SIGTSTP to PID, which is 0 for the whole process group, and
+/// return once Emacs is continued.
+#[cfg(unix)]
+fn stop_with_default_action(pid: libc::pid_t) {
+    unsafe {
+        // Our own SIGTSTP handler may only schedule a suspension, so
+        // stop with the default action, and make sure the signal is not
+        // blocked.
+        let mut default: libc::sigaction = std::mem::zeroed();
+        let mut old: libc::sigaction = std::mem::zeroed();
+        default.sa_sigaction = libc::SIG_DFL;
+        libc::sigemptyset(&mut default.sa_mask);
+        libc::sigaction(libc::SIGTSTP, &default, &mut old);
+
+        let mut tstp: libc::sigset_t = std::mem::zeroed();
+        let mut oldset: libc::sigset_t = std::mem::zeroed();
+        libc::sigemptyset(&mut tstp);
+        libc::sigaddset(&mut tstp, libc::SIGTSTP);
+        libc::pthread_sigmask(libc::SIG_UNBLOCK, &tstp, &mut oldset);
+
+        libc::kill(pid, libc::SIGTSTP);
+
+        libc::pthread_sigmask(libc::SIG_SETMASK, &oldset, ptr::null_mut());
+        libc::sigaction(libc::SIGTSTP, &old, ptr::null_mut());
+    }
+}
+
+/// On a system where suspending is not implemented, instead fork a
+/// subshell and let it talk directly to the terminal while we wait.
+#[cfg(not(unix))]
+fn stop_process() {
+    unsafe { sys_subshell() };
+}
+
+/// Give the terminal to our superior, with the terminal modes reset
+/// while we are away.  Return when Emacs is continued.
+fn suspend_with_modes_reset(stuff: LispObject) {
+    let count = c_specpdl_index();
+    unsafe {
+        reset_all_sys_modes();
+        // sys_suspend can get an error if it tries to fork a subshell
+        // and the system resources aren't available for that.
+        record_unwind_protect_void(Some(init_all_sys_modes));
+        stuff_buffered_input(stuff);
+        if globals.cannot_suspend {
+            sys_subshell();
+        } else {
+            sys_suspend();
+        }
+    }
+    // init_all_sys_modes, run by unbind_to, takes care of the SIGCONT
+    // we got when continued, and marks the frames for redisplay.
+    CONTINUED.store(false, Ordering::SeqCst);
+    unbind_to(count, Qnil);
+}
+
+/// Act on the SIGTSTP and SIGCONT signals received since the last call.
+/// This is called by `process_pending_signals'.
+#[no_mangle]
+pub extern "C" fn process_pending_suspend() {
+    if SUSPEND_REQUESTED.swap(false, Ordering::SeqCst) {
+        suspend_with_modes_reset(Qnil);
+    } else if CONTINUED.swap(false, Ordering::SeqCst) {
+        unsafe {
+            reset_all_sys_modes();
+            init_all_sys_modes();
+        }
+    }
+}
+
+/// Return the size of the selected frame's terminal as (WIDTH, HEIGHT),
+/// or None if the selected frame is not on a tty.
+fn selected_tty_size() -> Option<(c_int, c_int)> {
+    let (mut width, mut height) = (0, 0);
+    if unsafe { get_selected_tty_size(&mut width, &mut height) } {
+        Some((width, height))
+    } else {
+        None
+    }
+}
+
+/// Stop Emacs and return to superior process.  You can resume later.
+/// If `cannot-suspend' is non-nil, or if the system doesn't support job
+/// control, run a subshell instead.
+///
+/// If optional arg STUFFSTRING is non-nil, its characters are stuffed
+/// to be read as terminal input by Emacs's parent, after suspension.
+///
+/// Before suspending, run the normal hook `suspend-hook'.
+/// After resumption run the normal hook `suspend-resume-hook'.
+///
+/// Some operating systems cannot stop the Emacs process and resume it later.
+/// On such systems, Emacs starts a subshell instead of suspending.
+#[lisp_fn(min = "0", intspec = "")]
+pub fn suspend_emacs(stuffstring: LispObject) -> LispObject {
+    unsafe {
+        if !tty_list.is_null() && !(*tty_list).next.is_null() {
+            error!("There are other tty frames open; close them before suspending Emacs");
+        }
+    }
+
+    if stuffstring.is_not_nil() {
+        stuffstring.as_string_or_error();
+    }
+
+    unsafe { run_hook(intern("suspend-hook").into()) };
+
+    let old_size = selected_tty_size();
+    suspend_with_modes_reset(stuffstring);
+
+    // Check if terminal/window size has changed.  Note that this is not
+    // useful when we are running directly with a window system; but
+    // suspend should be disabled in that case.
+    let new_size = selected_tty_size();
+    if let Some((width, height)) = new_size {
+        if new_size != old_size {
+            let mut frame = selected_frame();
+            unsafe {
+                change_frame_size(
+                    frame.as_mut(),
+                    width,
+                    height - frame.menu_bar_lines,
+                    false,
+                    false,
+                    false,
+                    false,
+                )
+            };
+        }
+    }
+
+    unsafe { run_hook(intern("suspend-resume-hook").into()) };
+
+    Qnil
+}
+
+include!(concat!(env!("OUT_DIR"), "/terminal_exports.rs"));
+
+#[test]
+fn test_suspend_action() {
+    assert_eq!(suspend_action(libc::SIGTSTP, true), SuspendAction::Defer);
+    assert_eq!(suspend_action(libc::SIGTSTP, false), SuspendAction::Stop);
+    assert_eq!(suspend_action(libc::SIGCONT, true), SuspendAction::Reinit);
+    assert_eq!(suspend_action(libc::SIGCONT, false), SuspendAction::Ignore);
+    assert_eq!(suspend_action(libc::SIGINT, true), SuspendAction::Ignore);
+}
@@ -1,13 +1,18 @@
 //! Text property support
 
+use libc::ptrdiff_t;
 use std::ptr;
 
 use remacs_macros::lisp_fn;
 
 use crate::{
+    editfns::{goto_char, point, point_max, point_min},
     lisp::{defsubr, LispObject},
+    obarray::intern,
     remacs_sys::get_char_property_and_overlay,
-    remacs_sys::EmacsInt,
+    remacs_sys::Fprevious_single_property_change,
+    remacs_sys::{EmacsInt, Frecord, Qnil},
+    remacs_sys::{Fget_text_property, Fnext_single_property_change},
 };
 
 /// Return the value of POSITION's property PROP, in OBJECT.
@@ -25,4 +30,238 @@ pub fn get_char_property(position: EmacsInt, prop: LispObject, object: LispObjec
     }
 }
 
+fn text_property(position: EmacsInt, prop: LispObject) -> LispObject {
+    unsafe { Fget_text_property(position.into(), prop, Qnil) }
+}
+
+/// Return the position after POSITION where PROP changes, if any.
+fn next_change(position: EmacsInt, prop: LispObject) -> Option<EmacsInt> {
+    unsafe { Fnext_single_property_change(position.into(), prop, Qnil, Qnil) }.as_fixnum()
+}
+
+/// Return the position before POSITION where PROP changes, if any.
+fn previous_change(position: EmacsInt, prop: LispObject) -> Option<EmacsInt> {
+    unsafe { Fprevious_single_property_change(position.into(), prop, Qnil, Qnil) }.as_fixnum()
+}
+
+/// How `text-property-search-forward' and `text-property-search-backward'
+/// match the values of a property with VALUE, as PREDICATE says.
+struct Matcher {
+    value: LispObject,
+    predicate: LispObject,
+}
+
+impl Matcher {
+    fn matches(&self, prop_value: LispObject) -> bool {
+        if self.predicate.is_t() {
+            self.value.equal(prop_value)
+        } else if self.predicate.is_nil() {
+            !self.value.equal(prop_value)
+        } else {
+            call!(self.predicate, self.value, prop_value).is_not_nil()
+        }
+    }
+
+    /// Return whether a match ends where the value of the property
+    /// changes, rather than where it stops matching.
+    fn ends_at_change(&self) -> bool {
+        self.value.is_nil() || self.predicate.is_not_nil()
+    }
+}
+
+/// A `prop-match', as defined in text-property-search.el: the region
+/// found by a search, and the value of the property there.
+fn make_prop_match(beginning: EmacsInt, end: EmacsInt, value: LispObject) -> LispObject {
+    let mut slots = [
+        intern("prop-match").into(),
+        beginning.into(),
+        end.into(),
+        value,
+    ];
+    unsafe { Frecord(slots.len() as ptrdiff_t, slots.as_mut_ptr()) }
+}
+
+/// Return the region from START that matches PROPERTY, and move point to
+/// its end.
+fn find_end_forward(start: EmacsInt, property: LispObject, matcher: &Matcher) -> LispObject {
+    let end = if matcher.ends_at_change() {
+        next_change(start, property).unwrap_or_else(point_max)
+    } else {
+        let mut position = start;
+        loop {
+            match next_change(position, property) {
+                None => break point_max(),
+                Some(change) if !matcher.matches(text_property(change, property)) => break change,
+                Some(change) => position = change,
+            }
+        }
+    };
+    goto_char(end.into());
+    make_prop_match(start, end, text_property(start, property))
+}
+
+/// Return the region that ends at END and matches PROPERTY, and move
+/// point to its beginning.
+fn find_end_backward(end: EmacsInt, property: LispObject, matcher: &Matcher) -> LispObject {
+    let beginning = if matcher.ends_at_change() {
+        previous_change(end, property).unwrap_or_else(point_min)
+    } else {
+        let mut position = end;
+        loop {
+            match previous_change(position, property) {
+                None => break point_min(),
+                Some(change) if !matcher.matches(text_property(change - 1, property)) => {
+                    break change
+                }
+                Some(change) => position = change - 1,
+            }
+        }
+    };
+    goto_char(beginning.into());
+    make_prop_match(beginning, end, text_property(beginning, property))
+}
+
+/// Search for the next region of text whose PROPERTY matches VALUE.
+///
+/// If not found, return nil and don't move point.
+/// If found, move point to end of the region and return a `prop-match'
+/// object describing the match.  To access the details of the match,
+/// use `prop-match-beginning' and `prop-match-end' for the buffer
+/// positions that limit the region, and `prop-match-value' for the
+/// value of PROPERTY in the region.
+///
+/// PREDICATE is used to decide whether a value of PROPERTY should be
+/// considered as matching VALUE.
+///
+/// If PREDICATE is a function, it will be called with two arguments:
+/// VALUE and the value of PROPERTY.  The function should return
+/// non-nil if these two values are to be considered a match.
+///
+/// Two special values of PREDICATE can also be used:
+/// If PREDICATE is t, that means a value must `equal' VALUE to be
+/// considered a match.
+/// If PREDICATE is nil (which is the default value), a value will
+/// match if is not `equal' to VALUE.  Furthermore, a nil PREDICATE
+/// means that the match region is ended if the value changes.  For
+/// instance, this means that if you loop with
+///
+///   (while (setq prop (text-property-search-forward 'face))
+///     ...)
+///
+/// you will get all distinct regions with non-nil `face' values in
+/// the buffer, and the `prop' object will have the details about the
+/// match.  See the manual for more details and examples about how
+/// VALUE and PREDICATE interact.
+///
+/// If NOT-CURRENT is non-nil, the function will search for the first
+/// region that doesn't include point and has a value of PROPERTY
+/// that matches VALUE.
+#[lisp_fn(min = "1", intspec = "SSearch for property: ")]
+pub fn text_property_search_forward(
+    property: LispObject,
+    value: LispObject,
+    predicate: LispObject,
+    not_current: bool,
+) -> LispObject {
+    let matcher = Matcher { value, predicate };
+    let origin = point();
+    if origin >= point_max() {
+        return Qnil;
+    }
+
+    // We're standing in the property we're looking for, so find the end.
+    if !not_current && matcher.matches(text_property(origin, property)) {
+        return find_end_forward(origin, property, &matcher);
+    }
+
+    let mut position = origin;
+    while let Some(change) = next_change(position, property) {
+        if matcher.matches(text_property(change, property)) {
+            return find_end_forward(change, property, &matcher);
+        }
+        position = change;
+    }
+    Qnil
+}
+
+/// Search for the previous region of text whose PROPERTY matches VALUE.
+///
+/// Like `text-property-search-forward', which see, but searches
+/// backward, and if a matching region is found, place point at the
+/// start of the region.
+#[lisp_fn(min = "1", intspec = "SSearch for property: ")]
+pub fn text_property_search_backward(
+    property: LispObject,
+    value: LispObject,
+    predicate: LispObject,
+    not_current: bool,
+) -> LispObject {
+    let matcher = Matcher { value, predicate };
+    let origin = point();
+    if origin <= point_min() {
+        return Qnil;
+    }
+
+    // We're standing in the property we're looking for, so find the
+    // beginning.
+    if matcher.matches(text_property(origin - 1, property)) {
+        let found = find_end_backward(origin, property, &matcher);
+        // When we want to ignore the current region, search again if we
+        // haven't moved out of it yet.
+        if not_current && text_property(point(), property).equal(text_property(origin, property)) {
+            return text_property_search_backward(property, value, predicate, false);
+        }
+        return found;
+    }
+
+    let mut position = origin;
+    while let Some(change) = previous_change(position, property) {
+        if matcher.matches(text_property(change - 1, property)) {
+            return find_end_backward(change, property, &matcher);
+        }
+        position = change - 1;
+    }
+    Qnil
+}
+
+/// Return the slot SLOT of the `prop-match' PROP_MATCH.
+fn prop_match_slot(prop_match: LispObject, slot: usize) -> LispObject {
+    match prop_match.as_vectorlike().and_then(|v| v.as_record()) {
+        Some(record) if prop_match_p(prop_match) => record.get(slot),
+        _ => wrong_type!(intern("prop-match").into(), prop_match),
+    }
+}
+
+/// Return t if OBJECT is a `prop-match'.
+#[lisp_fn]
+pub fn prop_match_p(object: LispObject) -> bool {
+    object
+        .as_vectorlike()
+        .and_then(|v| v.as_record())
+        .map_or(false, |record| {
+            record.len() == 4 && record.get(0).eq(intern("prop-match"))
+        })
+}
+
+/// Return the position where the region of the `prop-match' PROP-MATCH
+/// begins.
+#[lisp_fn]
+pub fn prop_match_beginning(prop_match: LispObject) -> LispObject {
+    prop_match_slot(prop_match, 1)
+}
+
+/// Return the position where the region of the `prop-match' PROP-MATCH
+/// ends.
+#[lisp_fn]
+pub fn prop_match_end(prop_match: LispObject) -> LispObject {
+    prop_match_slot(prop_match, 2)
+}
+
+/// Return the value of the property searched for in the region of the
+/// `prop-match' PROP-MATCH.
+#[lisp_fn]
+pub fn prop_match_value(prop_match: LispObject) -> LispObject {
+    prop_match_slot(prop_match, 3)
+}
+
 include!(concat!(env!("OUT_DIR"), "/textprop_exports.rs"));
@@ -0,0 +1,524 @@
+//! Tool-bar items.
+//!
+//! The items of a frame's tool bar are the bindings of the prefix key
+//! `tool-bar' in the keymaps currently in effect.  Redisplay keeps them
+//! in a vector of the frame, where each item occupies
+//! `TOOL_BAR_ITEM_NSLOTS' slots, and turns a click on the image of an
+//! item into a `tool-bar' event that looks up the item's key.
+
+use std::cell::Cell;
+use std::ptr;
+
+use libc::{c_char, c_int, c_void};
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    casefiddle::upcase_initials,
+    dispnew::LispGlyphRef,
+    frames::LispFrameRef,
+    keymap::{current_global_map, get_keymap, map_keymap, minor_mode_maps},
+    lisp::{defsubr, LispObject},
+    lists::{list, LispConsCircularChecks, LispConsEndChecks},
+    remacs_sys::tool_bar_item_idx::{
+        self, TOOL_BAR_ITEM_BINDING, TOOL_BAR_ITEM_CAPTION, TOOL_BAR_ITEM_ENABLED_P,
+        TOOL_BAR_ITEM_HELP, TOOL_BAR_ITEM_IMAGES, TOOL_BAR_ITEM_KEY, TOOL_BAR_ITEM_LABEL,
+        TOOL_BAR_ITEM_RTL_IMAGE, TOOL_BAR_ITEM_SELECTED_P, TOOL_BAR_ITEM_TYPE,
+        TOOL_BAR_ITEM_VERT_ONLY,
+    },
+    remacs_sys::{
+        access_keymap, apply_modifiers, current_kboard, event_kind, get_local_map, input_event,
+        kbd_buffer_store_event, menu_item_eval_property, menu_separator_name_p,
+        tool_bar_item_image,
+    },
+    remacs_sys::{globals, EmacsInt, Fget_text_property, Fmake_vector},
+    remacs_sys::{
+        QCbutton, QCenable, QCfilter, QChelp, QCimage, QClabel, QCradio, QCrtl, QCtoggle,
+        QCvert_only, QCvisible, Qkeymap, Qlocal_map, Qmenu_item, Qnil, Qquote, Qt, Qtool_bar,
+        Qundefined,
+    },
+    sequences::{nreverse, vector_of},
+    string_alloc::make_string,
+    threads::ThreadState,
+};
+
+/// The number of slots a tool-bar item occupies in a vector of items.
+const NSLOTS: usize = tool_bar_item_idx::TOOL_BAR_ITEM_NSLOTS as usize;
+
+/// The label of items whose `:label' is not a string.
+const BAD_LABEL: &str = "!!?GARBLED ITEM?!!";
+
+/// The items found so far by `tool_bar_items`.
+declare_GC_protected_static!(items_vector, Qnil);
+
+thread_local! {
+    /// The number of slots of `items_vector` in use.
+    static USED_SLOTS: Cell<usize> = Cell::new(0);
+}
+
+fn used_slots() -> usize {
+    USED_SLOTS.with(Cell::get)
+}
+
+fn set_used_slots(n: usize) {
+    USED_SLOTS.with(|u| u.set(n));
+}
+
+/// The properties of a tool-bar item, indexed by `tool_bar_item_idx`.
+struct Item([LispObject; NSLOTS]);
+
+impl Item {
+    fn new(key: LispObject) -> Self {
+        let mut item = Item([Qnil; NSLOTS]);
+        item.set(TOOL_BAR_ITEM_KEY, key);
+        item.set(TOOL_BAR_ITEM_ENABLED_P, Qt);
+        item
+    }
+
+    fn get(&self, idx: tool_bar_item_idx::Type) -> LispObject {
+        self.0[idx as usize]
+    }
+
+    fn set(&mut self, idx: tool_bar_item_idx::Type, value: LispObject) {
+        self.0[idx as usize] = value;
+    }
+}
+
+/// Return a list of the keymaps that contribute tool-bar items, in order
+/// of precedence.  A list rather than a `Vec`, so that the collector sees
+/// the maps while evaluating the items' properties.
+fn tool_bar_maps() -> LispObject {
+    let otlp = unsafe { (*current_kboard).Voverriding_terminal_local_map_ };
+    let overriding_local_map = unsafe { globals.Voverriding_local_map };
+    let menu_flag = unsafe { globals.Voverriding_local_map_menu_flag }.is_not_nil();
+
+    let mut maps = Vec::new();
+    if menu_flag && overriding_local_map.is_not_nil() {
+        if otlp.is_not_nil() {
+            maps.push(otlp);
+        }
+        maps.push(overriding_local_map);
+    } else {
+        // Tool-bar bindings in the local map and keymap properties may
+        // not work reliably, as they are only recognized when the tool
+        // bar is updated, which does not normally happen after every
+        // command.
+        if otlp.is_not_nil() && menu_flag {
+            maps.push(otlp);
+        }
+        let mut buffer = ThreadState::current_buffer_unchecked();
+        let pt = buffer.pt;
+        let keymap = unsafe { get_local_map(pt, buffer.as_mut(), Qkeymap) };
+        if keymap.is_not_nil() {
+            maps.push(keymap);
+        }
+        maps.extend(minor_mode_maps());
+        maps.push(unsafe { get_local_map(pt, buffer.as_mut(), Qlocal_map) });
+    }
+    maps.push(current_global_map());
+    list(&maps)
+}
+
+/// Return a vector of the tool-bar items of the keymaps currently in
+/// effect, and store their number in NITEMS.  Reuse the vector REUSE if
+/// it is one.
+#[no_mangle]
+pub unsafe extern "C" fn tool_bar_items(reuse: LispObject, nitems: *mut c_int) -> LispObject {
+    *nitems = 0;
+
+    // The keymap accessors call maybe_quit, but this is called during
+    // redisplay, when quitting is fatal.  Errors reset `inhibit-quit',
+    // so there is no need to specbind it.
+    let oquit = globals.Vinhibit_quit;
+    globals.Vinhibit_quit = Qt;
+
+    items_vector.set(if reuse.is_vector() {
+        reuse
+    } else {
+        Fmake_vector(LispObject::from(64 as EmacsInt), Qnil)
+    });
+    set_used_slots(0);
+
+    // Look up `tool-bar' in each map, starting with the map of lowest
+    // precedence, so that an `undefined' binding can remove an item of
+    // a map that comes later.
+    let maps = nreverse(tool_bar_maps());
+    for map in maps.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+        if map.is_nil() {
+            continue;
+        }
+        let keymap = get_keymap(
+            access_keymap(map, Qtool_bar, true, false, true),
+            false,
+            true,
+        );
+        if keymap.is_cons() {
+            map_keymap(
+                keymap,
+                Some(process_tool_bar_item),
+                Qnil,
+                ptr::null_mut(),
+                true,
+            );
+        }
+    }
+
+    globals.Vinhibit_quit = oquit;
+    *nitems = (used_slots() / NSLOTS) as c_int;
+    items_vector.get()
+}
+
+/// Return the tool-bar items of the keymaps currently in effect.
+/// Each item is a vector of its properties, in the order redisplay keeps
+/// them: its key, whether it is enabled, whether it is selected, its
+/// caption, its images, its binding, its button type, its help, its
+/// image for right-to-left text, its label, and whether it is only shown
+/// with labels below images.
+#[lisp_fn(name = "tool-bar--items")]
+pub fn tool_bar_items_list() -> LispObject {
+    let mut nitems = 0;
+    let items = unsafe { tool_bar_items(Qnil, &mut nitems) };
+    let slots = unsafe { items.as_vector_unchecked() };
+    let items: Vec<LispObject> = slots.as_slice()[..nitems as usize * NSLOTS]
+        .chunks(NSLOTS)
+        .map(vector_of)
+        .collect();
+    list(&items)
+}
+
+/// Process the binding of KEY to DEF in the `tool-bar' keymap.
+unsafe extern "C" fn process_tool_bar_item(
+    key: LispObject,
+    def: LispObject,
+    _args: LispObject,
+    _data: *mut c_void,
+) {
+    if def.eq(Qundefined) {
+        // An explicit `undefined' discards the item made for KEY by a
+        // map of lower precedence.
+        remove_item(key);
+    } else if let Some(item) = parse_tool_bar_item(key, def) {
+        // More than one definition for the same key is accepted.
+        append_item(&item);
+    }
+}
+
+/// Remove the first item for KEY from `items_vector`.
+fn remove_item(key: LispObject) {
+    let used = used_slots();
+    let mut vector = unsafe { items_vector.get().as_vector_unchecked() };
+    let slots = vector.as_mut_slice();
+    let key_slot = TOOL_BAR_ITEM_KEY as usize;
+    if let Some(start) = (0..used)
+        .step_by(NSLOTS)
+        .find(|&i| slots[i + key_slot].eq(key))
+    {
+        slots[start..used].rotate_left(NSLOTS);
+        set_used_slots(used - NSLOTS);
+    }
+}
+
+/// Append ITEM to `items_vector`, enlarging it if necessary.
+fn append_item(item: &Item) {
+    let used = used_slots();
+    let mut vector = unsafe { items_vector.get().as_vector_unchecked() };
+    if vector.len() < used + NSLOTS {
+        let size = (2 * vector.len()).max(used + NSLOTS);
+        let mut larger =
+            unsafe { Fmake_vector(LispObject::from(size), Qnil).as_vector_unchecked() };
+        larger.as_mut_slice()[..used].copy_from_slice(&vector.as_slice()[..used]);
+        items_vector.set(larger.into());
+        vector = larger;
+    }
+    vector.as_mut_slice()[used..used + NSLOTS].copy_from_slice(&item.0);
+    set_used_slots(used + NSLOTS);
+}
+
+/// Parse the tool-bar item ITEM, the binding of KEY.  Return `None` if
+/// ITEM is invalid.
+///
+/// ITEM is a list `(menu-item CAPTION BINDING PROPS...)'.  CAPTION is
+/// the caption of the item, evaluated if it is not a string.  Items
+/// whose BINDING is a keymap are ignored.  PROPS are `:enable FORM',
+/// `:visible FORM', `:filter FUNCTION', `:button (TYPE . SELECTED)',
+/// `:image IMAGES', `:help HELP-STRING', `:label LABEL-STRING',
+/// `:vert-only FLAG' and `:rtl IMAGE'.  IMAGES is either a single image
+/// specification or a vector of four, one for each state of the button,
+/// in the order of `tool_bar_item_image'.
+fn parse_tool_bar_item(key: LispObject, item: LispObject) -> Option<Item> {
+    let (head, tail) = item.as_cons()?.into();
+
+    // As an exception, allow old-style menu separators.
+    let item = if head.is_string() {
+        list!(head)
+    } else if head.eq(Qmenu_item) && tail.is_cons() {
+        tail
+    } else {
+        return None;
+    };
+
+    let mut props = Item::new(key);
+    let (caption, item) = item.into();
+    let caption = if caption.is_string() {
+        caption
+    } else {
+        unsafe { menu_item_eval_property(caption) }
+    };
+    let caption_string = caption.as_string()?;
+    props.set(TOOL_BAR_ITEM_CAPTION, caption);
+
+    // If the rest following the caption is not a list, the item is
+    // either a separator or invalid.
+    let (binding, mut item) = match item.as_cons() {
+        Some(cons) => cons.into(),
+        None => {
+            let name = caption_string.const_data_ptr() as *const c_char;
+            if !unsafe { menu_separator_name_p(name) } {
+                return None;
+            }
+            props.set(TOOL_BAR_ITEM_TYPE, Qt);
+            // When build_desired_tool_bar_string renders the tool bar,
+            // the separator is rendered as an image.
+            if cfg!(not(any(feature = "gtk", feature = "ns"))) {
+                props.set(TOOL_BAR_ITEM_IMAGES, unsafe {
+                    menu_item_eval_property(globals.Vtool_bar_separator_image_expression)
+                });
+                props.set(TOOL_BAR_ITEM_ENABLED_P, Qnil);
+                props.set(TOOL_BAR_ITEM_SELECTED_P, Qnil);
+                props.set(TOOL_BAR_ITEM_CAPTION, Qnil);
+            }
+            return Some(props);
+        }
+    };
+    props.set(TOOL_BAR_ITEM_BINDING, binding);
+
+    // Ignore the cached key binding, if any.
+    if let Some((cached, rest)) = item.into() {
+        if cached.is_cons() {
+            item = rest;
+        }
+    }
+
+    let mut filter = Qnil;
+    let mut have_label = false;
+    while let Some((ikey, rest)) = item.into() {
+        let (value, rest) = match rest.as_cons() {
+            Some(cons) => cons.into(),
+            None => break,
+        };
+        item = rest;
+
+        if ikey.eq(QCenable) {
+            let enabled = unsafe { globals.Venable_disabled_menus_and_buttons };
+            props.set(
+                TOOL_BAR_ITEM_ENABLED_P,
+                if enabled.is_not_nil() { Qt } else { value },
+            );
+        } else if ikey.eq(QCvisible) {
+            if unsafe { menu_item_eval_property(value) }.is_nil() {
+                return None;
+            }
+        } else if ikey.eq(QChelp) {
+            props.set(TOOL_BAR_ITEM_HELP, value);
+        } else if ikey.eq(QCvert_only) {
+            props.set(TOOL_BAR_ITEM_VERT_ONLY, value);
+        } else if ikey.eq(QClabel) {
+            props.set(
+                TOOL_BAR_ITEM_LABEL,
+                if value.is_string() {
+                    value
+                } else {
+                    make_string(BAD_LABEL)
+                },
+            );
+            have_label = true;
+        } else if ikey.eq(QCfilter) {
+            filter = value;
+        } else if ikey.eq(QCbutton) {
+            if let Some((button_type, selected)) = value.into() {
+                if button_type.eq(QCtoggle) || button_type.eq(QCradio) {
+                    props.set(TOOL_BAR_ITEM_SELECTED_P, selected);
+                    props.set(TOOL_BAR_ITEM_TYPE, button_type);
+                }
+            }
+        } else if ikey.eq(QCimage) {
+            let valid = value.is_cons() || value.as_vector().map_or(false, |v| v.len() == 4);
+            if valid {
+                props.set(TOOL_BAR_ITEM_IMAGES, value);
+            }
+        } else if ikey.eq(QCrtl) {
+            props.set(TOOL_BAR_ITEM_RTL_IMAGE, value);
+        }
+    }
+
+    if !have_label {
+        props.set(TOOL_BAR_ITEM_LABEL, label_from_caption(&props));
+    }
+
+    if filter.is_not_nil() {
+        let form = list!(filter, list!(Qquote, props.get(TOOL_BAR_ITEM_BINDING)));
+        props.set(TOOL_BAR_ITEM_BINDING, unsafe {
+            menu_item_eval_property(form)
+        });
+    }
+
+    // Items bound to keymaps are not supported.
+    if get_keymap(props.get(TOOL_BAR_ITEM_BINDING), false, true).is_cons() {
+        return None;
+    }
+
+    let enabled = props.get(TOOL_BAR_ITEM_ENABLED_P);
+    if !enabled.eq(Qt) {
+        props.set(TOOL_BAR_ITEM_ENABLED_P, unsafe {
+            menu_item_eval_property(enabled)
+        });
+    }
+
+    let selected = props.get(TOOL_BAR_ITEM_SELECTED_P);
+    if selected.is_not_nil() {
+        props.set(TOOL_BAR_ITEM_SELECTED_P, unsafe {
+            menu_item_eval_property(selected)
+        });
+    }
+
+    Some(props)
+}
+
+/// Return the label of the item PROPS, which has no `:label', made from
+/// its caption or key.  The label is empty if it would be longer than
+/// `tool-bar-max-label-size' characters.
+fn label_from_caption(props: &Item) -> LispObject {
+    let max_size = unsafe { globals.tool_bar_max_label_size }.max(0) as usize;
+    let key = props
+        .get(TOOL_BAR_ITEM_KEY)
+        .as_symbol()
+        .map_or_else(String::new, |s| s.symbol_name().force_string().to_string());
+    let caption = props
+        .get(TOOL_BAR_ITEM_CAPTION)
+        .as_string()
+        .map_or_else(String::new, |s| s.to_string());
+    let label = upcase_initials(make_string(&default_label(&key, &caption, 2 * max_size)));
+    if label.force_string().len_chars() as usize <= max_size {
+        label
+    } else {
+        make_string("")
+    }
+}
+
+/// Return a label made from CAPTION, without trailing dots, or else from
+/// KEY, the name of an item's key, with dashes replaced by spaces.  Names
+/// longer than MAX_LEN bytes are not used.
+fn default_label(key: &str, caption: &str, max_len: usize) -> String {
+    let label = if !caption.is_empty() && caption.len() <= max_len {
+        caption.trim_end_matches('.')
+    } else {
+        key
+    };
+    if !label.is_empty() && label.len() <= max_len {
+        label.replace('-', " ")
+    } else {
+        String::new()
+    }
+}
+
+/// Return the image to show for a tool-bar item whose `:image' is IMAGE,
+/// given whether it is ENABLED and SELECTED.  If IMAGE is a vector, the
+/// image for the state of the button is chosen from it, and its index is
+/// stored in IDX.  Otherwise, IMAGE is returned and IDX is set to -1.
+#[no_mangle]
+pub unsafe extern "C" fn tool_bar_image_for_state(
+    image: LispObject,
+    enabled: bool,
+    selected: bool,
+    idx: *mut c_int,
+) -> LispObject {
+    match image.as_vector() {
+        Some(images) => {
+            let state = match (enabled, selected) {
+                (true, true) => tool_bar_item_image::TOOL_BAR_IMAGE_ENABLED_SELECTED,
+                (true, false) => tool_bar_item_image::TOOL_BAR_IMAGE_ENABLED_DESELECTED,
+                (false, true) => tool_bar_item_image::TOOL_BAR_IMAGE_DISABLED_SELECTED,
+                (false, false) => tool_bar_item_image::TOOL_BAR_IMAGE_DISABLED_DESELECTED,
+            };
+            *idx = state as c_int;
+            images.get(state as usize)
+        }
+        None => {
+            *idx = -1;
+            image
+        }
+    }
+}
+
+/// Return the index in F's tool-bar items of the item displayed by
+/// GLYPH of F's tool-bar window, or -1 if GLYPH shows no item.
+///
+/// This can be called asynchronously, so it must not signal errors.
+#[no_mangle]
+pub unsafe extern "C" fn tool_bar_item_index(f: LispFrameRef, glyph: LispGlyphRef) -> c_int {
+    let string = f.current_tool_bar_string;
+    let nchars = string.as_string().map_or(0, |s| s.len_chars() as isize);
+    let charpos = glyph.charpos.min(nchars).max(0);
+
+    // The `menu-item' property of the tool-bar string is the start of
+    // the item's properties in F's tool-bar items.
+    Fget_text_property((charpos as EmacsInt).into(), Qmenu_item, string)
+        .as_fixnum()
+        .map_or(-1, |idx| idx as c_int)
+}
+
+/// Queue the events for a click on the tool-bar item of F that starts at
+/// PROP_IDX in F's tool-bar items: the prefix `tool-bar', then the key
+/// of the item with MODIFIERS.
+#[no_mangle]
+pub unsafe extern "C" fn store_tool_bar_click(f: LispFrameRef, prop_idx: c_int, modifiers: c_int) {
+    let key = f
+        .tool_bar_items
+        .as_vector_unchecked()
+        .get(prop_idx as usize + TOOL_BAR_ITEM_KEY as usize);
+    let frame: LispObject = f.into();
+
+    let mut event: input_event = std::mem::zeroed();
+    event.set_kind(event_kind::TOOL_BAR_EVENT);
+    event.frame_or_window = frame;
+    event.arg = frame;
+    kbd_buffer_store_event(&mut event);
+
+    event.arg = key;
+    event.modifiers = modifiers as u32;
+    kbd_buffer_store_event(&mut event);
+}
+
+/// Return the Lisp event for a `TOOL_BAR_EVENT' with ARG on the frame
+/// FRAME_OR_WINDOW, with MODIFIERS.
+#[no_mangle]
+pub unsafe extern "C" fn make_lispy_tool_bar_event(
+    frame_or_window: LispObject,
+    arg: LispObject,
+    modifiers: c_int,
+) -> LispObject {
+    if arg.eq(frame_or_window) {
+        // This is the prefix key.  It becomes `(tool-bar)', which the
+        // code reading tool-bar events relies on.
+        list!(Qtool_bar)
+    } else if arg.is_symbol() {
+        apply_modifiers(modifiers, arg)
+    } else {
+        arg
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/tool_bar_exports.rs"));
+
+#[test]
+fn test_default_label() {
+    assert_eq!(default_label("new-file", "Open File...", 28), "Open File");
+    assert_eq!(default_label("new-file", "", 28), "new file");
+    assert_eq!(default_label("new-file", "...", 28), "");
+    assert_eq!(
+        default_label("save-buffer", "Save the current buffer", 8),
+        ""
+    );
+    assert_eq!(default_label("undo", "Undo the last change", 8), "undo");
+    assert_eq!(default_label("a-b", "x-y", 28), "x y");
+}
@@ -0,0 +1,164 @@
+//! Transient keymaps, as set by `set-transient-map'.
+//!
+//! The maps in effect are kept on a stack here, innermost first, and the
+//! command loop asks this module before each command which of them to
+//! take down.  An error in a KEEP-PRED or ON-EXIT function is reported,
+//! and the map it belongs to is taken down all the same, so that a map
+//! can't be left active once nothing is deciding about it anymore.
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    eval::{LispResult, LispSignal},
+    keymap::lookup_key,
+    lisp::{defsubr, LispObject},
+    lists::{car, cdr, delq, memq, nth},
+    lists::{LispConsCircularChecks, LispConsEndChecks},
+    obarray::intern,
+    remacs_sys::globals,
+    remacs_sys::{Qlambda, Qnil, Qquote, Qt},
+    symbols::symbol_value,
+};
+
+/// The transient maps in effect, innermost first.  Each element is a
+/// list (MAP KEEP-PRED ON-EXIT).
+declare_GC_protected_static!(transient_maps, Qnil);
+
+fn overriding_terminal_local_map() -> LispObject {
+    symbol_value(intern("overriding-terminal-local-map"))
+}
+
+/// Report an error signaled by the KEEP-PRED or ON-EXIT of a transient
+/// map, the way `with-demoted-errors' would.
+fn report(signal: LispSignal) {
+    call!(
+        intern("message").into(),
+        LispObject::from("set-transient-map PCH: %S"),
+        signal.into()
+    );
+}
+
+/// Take down the transient map of ENTRY, if it is still in effect, and
+/// call its ON-EXIT function.  The map is gone before ON-EXIT runs, so an
+/// error there can't keep it around.
+fn exit_transient_map(entry: LispObject) {
+    if memq(entry, transient_maps.get()).is_nil() {
+        return;
+    }
+    transient_maps.set(delq(entry, transient_maps.get()));
+    call!(
+        intern("internal-pop-keymap").into(),
+        car(entry),
+        intern("overriding-terminal-local-map").into()
+    );
+    let on_exit = nth(2, entry);
+    if on_exit.is_not_nil() {
+        call!(on_exit);
+    }
+}
+
+/// Return whether the transient map of ENTRY stays in effect for the
+/// command about to run.
+fn keeps_map(entry: LispObject) -> LispResult<bool> {
+    let map = car(entry);
+    let keep_pred = nth(1, entry);
+    let maps = overriding_terminal_local_map();
+    if keep_pred.is_nil() {
+        Ok(false)
+    } else if !map.eq(car(cdr(maps))) && memq(map, cdr(cdr(maps))).is_not_nil() {
+        // There's presumably some other transient map in effect.  Wait
+        // for that one to terminate before we remove ourselves.  For
+        // example, if isearch and C-u both use transient maps, then the
+        // lifetime of the C-u should be nested within isearch's, so we
+        // don't exit isearch just because we hit 1 after C-u and that 1
+        // exits isearch whereas it doesn't exit C-u.
+        Ok(true)
+    } else if keep_pred.eq(Qt) {
+        let keys = call!(intern("this-command-keys-vector").into());
+        let command = lookup_key(map, keys, Qnil);
+        // If the key is unbound `this-command' is nil and so is COMMAND.
+        Ok(command.is_not_nil() && command.eq(unsafe { globals.Vthis_command }))
+    } else {
+        call_checked!(keep_pred).map(LispObject::is_not_nil)
+    }
+}
+
+/// Decide, before a command runs, which transient maps to take down.
+/// Maps whose KEEP-PRED says so stay, and so do maps that something
+/// else removed from `overriding-terminal-local-map' already -- those
+/// are just forgotten, after calling their ON-EXIT.
+#[no_mangle]
+pub extern "C" fn transient_maps_pre_command() {
+    let entries = transient_maps.get();
+    for entry in entries.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+        if memq(entry, transient_maps.get()).is_nil() {
+            // An ON-EXIT function took it down already.
+            continue;
+        }
+        let active = memq(car(entry), overriding_terminal_local_map()).is_not_nil();
+        let keep = active
+            && keeps_map(entry).unwrap_or_else(|signal| {
+                report(signal);
+                false
+            });
+        if !keep {
+            if let Err(signal) = call_checked!(intern("internal--exit-transient-map").into(), entry)
+            {
+                report(signal);
+            }
+        }
+    }
+}
+
+/// Take down the transient map of ENTRY, an element of the stack of
+/// transient maps.  This is what the exit function returned by
+/// `set-transient-map' calls; it does nothing when the map is gone
+/// already.
+#[lisp_fn(name = "internal--exit-transient-map")]
+pub fn internal_exit_transient_map(entry: LispObject) {
+    exit_transient_map(entry);
+}
+
+/// Set MAP as a temporary keymap taking precedence over other keymaps.
+/// Normally, MAP is used only once, to look up the very next key.
+/// However, if the optional argument KEEP-PRED is t, MAP stays
+/// active if a key from MAP is used.  KEEP-PRED can also be a
+/// function of no arguments: it is called before each command and
+/// if it returns non-nil, then MAP stays active.
+///
+/// Optional arg ON-EXIT, if non-nil, specifies a function that is
+/// called, with no arguments, after MAP is deactivated.
+///
+/// This uses `overriding-terminal-local-map' which takes precedence over all other
+/// keymaps.  As usual, if no match for a key is found in MAP, the normal key
+/// lookup sequence then continues.
+///
+/// If KEEP-PRED or ON-EXIT signal an error, the error is reported and
+/// MAP is deactivated.
+///
+/// This returns an "exit function", which can be called with no argument
+/// to deactivate this transient map, regardless of KEEP-PRED.
+#[lisp_fn(min = "1")]
+pub fn set_transient_map(
+    map: LispObject,
+    keep_pred: LispObject,
+    on_exit: LispObject,
+) -> LispObject {
+    let entry = list!(map, keep_pred, on_exit);
+    transient_maps.set(LispObject::cons(entry, transient_maps.get()));
+    call!(
+        intern("internal-push-keymap").into(),
+        map,
+        intern("overriding-terminal-local-map").into()
+    );
+    list!(
+        Qlambda,
+        Qnil,
+        list!(
+            intern("internal--exit-transient-map").into(),
+            list!(Qquote, entry)
+        )
+    )
+}
+
+include!(concat!(env!("OUT_DIR"), "/transient_map_exports.rs"));
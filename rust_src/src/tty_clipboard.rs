@@ -0,0 +1,282 @@
+//! The system clipboard of text terminals.
+//!
+//! Terminal emulators that implement OSC 52 set their system clipboard
+//! to the base64 encoded text in `ESC ] 52 ; c ; TEXT BEL', and answer
+//! `ESC ] 52 ; c ; ? BEL' with its contents.  This works over ssh, where
+//! nothing else knows the clipboard of the machine the user sits at.
+//!
+//! As the terminal gives away what the user copied in other programs,
+//! Emacs only reads the clipboard when `tty-clipboard-allow-read' says
+//! so, which it doesn't by default.
+
+use std::time::{Duration, Instant};
+
+use libc::c_char;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    base64_crate,
+    lisp::{defsubr, LispObject},
+    lists::{assq, cdr},
+    multibyte::LispStringRef,
+    numbers::LispNumber,
+    obarray::intern,
+    remacs_sys::{
+        code_convert_string, globals, gobble_input, make_unibyte_string, maybe_quit,
+        store_terminal_param, terminal,
+    },
+    remacs_sys::{Qerror, Qnil, Qt, Qutf_8_unix},
+    tty_input::{expect_clipboard_answer, send_to_tty, take_clipboard_answer, tty_terminal},
+};
+
+/// How long to wait for the terminal to answer, unless told.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The most base64 characters GNU screen passes on in one DCS sequence.
+const SCREEN_CHUNK_SIZE: usize = 76;
+
+/// Return the value of the parameter NAME of the tty T.
+fn terminal_parameter(t: *mut terminal, name: &str) -> LispObject {
+    let alist = unsafe { (*t).param_alist };
+    cdr(assq(LispObject::from(intern(name)), alist))
+}
+
+/// Return true if the tty T is a GNU screen window, which only passes
+/// OSC sequences on when they are wrapped in DCS sequences.
+fn inside_screen(t: *mut terminal) -> bool {
+    terminal_parameter(t, "terminal-initted").eq(LispObject::from(intern("terminal-init-screen")))
+}
+
+/// Return the OSC 52 selection parameter for the selection SELECTION.
+fn selection_char(selection: LispObject) -> u8 {
+    if selection.is_nil() || selection.eq(LispObject::from(intern("CLIPBOARD"))) {
+        b'c'
+    } else if selection.eq(LispObject::from(intern("PRIMARY"))) {
+        b'p'
+    } else {
+        xsignal!(
+            Qerror,
+            LispObject::from("Invalid selection type"),
+            selection
+        );
+    }
+}
+
+/// Return the OSC 52 sequence with the selection parameter SELECTION and
+/// the data DATA, wrapped in DCS sequences for GNU screen if SCREEN.
+fn osc52(selection: u8, data: &[u8], screen: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + 16);
+    if screen {
+        out.extend_from_slice(b"\x1bP");
+    }
+    out.extend_from_slice(b"\x1b]52;");
+    out.push(selection);
+    out.push(b';');
+    if screen {
+        // Screen drops long DCS sequences, so start a new one every
+        // so often.
+        for (n, chunk) in data.chunks(SCREEN_CHUNK_SIZE).enumerate() {
+            if n > 0 {
+                out.extend_from_slice(b"\x1b\\\x1bP");
+            }
+            out.extend_from_slice(chunk);
+        }
+    } else {
+        out.extend_from_slice(data);
+    }
+    out.push(0x07);
+    if screen {
+        out.extend_from_slice(b"\x1b\\");
+    }
+    out
+}
+
+/// Return the text of the answer ANSWER to a clipboard query, or None if
+/// the terminal refused to give it.
+fn answer_text(answer: &[u8]) -> Option<Vec<u8>> {
+    let data = match answer.iter().position(|&b| b == b';') {
+        Some(pos) => &answer[pos + 1..],
+        None => answer,
+    };
+    if data == b"?" {
+        return None;
+    }
+    base64_crate::decode_config(data, base64_crate::MIME).ok()
+}
+
+/// Return true if the clipboard of the tty T may be read, asking the
+/// user if `tty-clipboard-allow-read' says so.
+fn read_allowed(t: *mut terminal) -> bool {
+    let policy = unsafe { globals.Vtty_clipboard_allow_read };
+    if policy.is_nil() {
+        return false;
+    }
+    if !policy.eq(LispObject::from(intern("ask"))) {
+        return true;
+    }
+
+    let answer = terminal_parameter(t, "tty-clipboard-read-allowed");
+    if answer.is_not_nil() {
+        return answer.eq(Qt);
+    }
+    let allowed = call!(
+        LispObject::from(intern("y-or-n-p")),
+        LispObject::from("Let Emacs read the system clipboard through the terminal? ")
+    )
+    .is_not_nil();
+    let answer = if allowed {
+        Qt
+    } else {
+        LispObject::from(intern("denied"))
+    };
+    unsafe {
+        store_terminal_param(
+            t,
+            LispObject::from(intern("tty-clipboard-read-allowed")),
+            answer,
+        )
+    };
+    allowed
+}
+
+/// Read the input of the tty T until the answer to a clipboard query
+/// comes, or TIMEOUT passes.  Other input is queued as usual.
+fn wait_for_answer(t: *mut terminal, timeout: Duration) -> Option<Vec<u8>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        unsafe {
+            gobble_input();
+            maybe_quit();
+        }
+        if let Some(answer) = take_clipboard_answer(t) {
+            return Some(answer);
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return None;
+        }
+        wait_for_input(t, deadline - now);
+    }
+}
+
+#[cfg(unix)]
+fn wait_for_input(t: *mut terminal, timeout: Duration) {
+    let input = unsafe { (*(*t).display_info.tty).input };
+    let mut pollfd = libc::pollfd {
+        fd: unsafe { libc::fileno(input as *mut libc::FILE) },
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let millis = timeout.as_secs() * 1000 + u64::from(timeout.subsec_millis()) + 1;
+    unsafe { libc::poll(&mut pollfd, 1, millis as libc::c_int) };
+}
+
+#[cfg(not(unix))]
+fn wait_for_input(_t: *mut terminal, timeout: Duration) {
+    std::thread::sleep(std::cmp::min(timeout, Duration::from_millis(10)));
+}
+
+/// Set the system clipboard of the text terminal TERMINAL to DATA.
+/// DATA is a string, sent to the terminal in UTF-8.  SELECTION is
+/// `CLIPBOARD' (the default if nil) or `PRIMARY', for terminals that
+/// tell them apart.
+///
+/// The text is sent with the OSC 52 escape sequence, which terminals
+/// that lack it ignore.  Inside GNU screen, it is sent in pieces that
+/// screen passes on.
+///
+/// TERMINAL can be a terminal object, a frame, or nil (meaning the
+/// selected frame's terminal).  Return nil if it is not a text
+/// terminal, and t otherwise.
+#[lisp_fn(min = "1")]
+pub fn tty_clipboard_set(data: LispStringRef, selection: LispObject, terminal: LispObject) -> bool {
+    let selection = selection_char(selection);
+    let t = match tty_terminal(terminal) {
+        Some(t) => t,
+        None => return false,
+    };
+    let data = if data.is_multibyte() {
+        unsafe { code_convert_string(data.into(), Qutf_8_unix, Qt, true, true, true) }
+            .as_string_or_error()
+    } else {
+        data
+    };
+    let encoded = base64_crate::encode(data.as_slice());
+    send_to_tty(t, &osc52(selection, encoded.as_bytes(), inside_screen(t)));
+    true
+}
+
+/// Return the contents of the system clipboard of the text terminal TERMINAL.
+/// SELECTION is `CLIPBOARD' (the default if nil) or `PRIMARY', for
+/// terminals that tell them apart.
+///
+/// The terminal is asked with the OSC 52 escape sequence, and its answer
+/// awaited for TIMEOUT seconds, by default 2.  The value is nil if it
+/// does not answer in time, refuses to answer, or if it may not be asked,
+/// see `tty-clipboard-allow-read'.  The text is decoded from UTF-8.
+///
+/// TERMINAL can be a terminal object, a frame, or nil (meaning the
+/// selected frame's terminal).  The value is nil if it is not a text
+/// terminal.
+#[lisp_fn(min = "0")]
+pub fn tty_clipboard_get(
+    selection: LispObject,
+    timeout: Option<LispNumber>,
+    terminal: LispObject,
+) -> LispObject {
+    let selection = selection_char(selection);
+    let t = match tty_terminal(terminal) {
+        Some(t) => t,
+        None => return Qnil,
+    };
+    if !read_allowed(t) {
+        return Qnil;
+    }
+    let timeout = match timeout {
+        None => DEFAULT_TIMEOUT,
+        Some(LispNumber::Fixnum(secs)) => Duration::from_secs(secs.max(0) as u64),
+        Some(LispNumber::Float(secs)) => Duration::from_millis((secs.max(0.0) * 1000.0) as u64),
+    };
+
+    expect_clipboard_answer(t);
+    send_to_tty(t, &osc52(selection, b"?", inside_screen(t)));
+    let text = match wait_for_answer(t, timeout).and_then(|answer| answer_text(&answer)) {
+        Some(text) => text,
+        None => return Qnil,
+    };
+    let string =
+        unsafe { make_unibyte_string(text.as_ptr() as *const c_char, text.len() as isize) };
+    unsafe { code_convert_string(string, Qutf_8_unix, Qt, false, true, true) }
+}
+
+#[no_mangle]
+pub extern "C" fn syms_of_tty_clipboard() {
+    /// Whether Emacs may read the system clipboard of text terminals.
+    /// If t, it may, and if nil, the default, it may not.  The value `ask'
+    /// means to ask the first time it wants to, once for each terminal.
+    ///
+    /// Terminals that support this give away what the user copied in any
+    /// program, which is why this is not allowed unless asked for.
+    defvar_lisp!(Vtty_clipboard_allow_read, "tty-clipboard-allow-read", Qnil);
+}
+
+include!(concat!(env!("OUT_DIR"), "/tty_clipboard_exports.rs"));
+
+#[test]
+fn test_osc52() {
+    assert_eq!(osc52(b'c', b"aGk=", false), b"\x1b]52;c;aGk=\x07".to_vec());
+    let data = [b'A'; 100];
+    let out = osc52(b'p', &data, true);
+    assert!(out.starts_with(b"\x1bP\x1b]52;p;AAAA"));
+    assert!(out.ends_with(b"AAAA\x07\x1b\\"));
+    assert_eq!(out.windows(4).filter(|w| w == b"\x1b\\\x1bP").count(), 1);
+}
+
+#[test]
+fn test_answer_text() {
+    assert_eq!(answer_text(b"c;aGVsbG8="), Some(b"hello".to_vec()));
+    assert_eq!(answer_text(b"c;"), Some(vec![]));
+    assert_eq!(answer_text(b"c;?"), None);
+    assert_eq!(answer_text(b"c;!!"), None);
+}
@@ -0,0 +1,750 @@
+//! Display of images on text terminals.
+//!
+//! Terminals that implement the kitty graphics protocol or sixel
+//! graphics can show images in place of the characters of some cells.
+//! Which of them a terminal supports is found out when its protocols
+//! are negotiated, see `tty_input', and the kitty protocol is used if
+//! both are.
+//!
+//! Images are decoded here, from PNG or from the binary PPM and PGM
+//! formats, scaled to fit the cells they are put in, and written to the
+//! terminal as an escape sequence.  Redisplay knows nothing about them;
+//! `tty-image.el' makes room for them in the text, and draws them after
+//! redisplay.
+
+use std::io::prelude::{Read, Write};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use remacs_macros::lisp_fn;
+
+use crate::{
+    base64_crate,
+    lisp::{defsubr, LispObject},
+    multibyte::LispStringRef,
+    obarray::intern,
+    remacs_sys::{terminal, EmacsInt, Qnil},
+    tty_input::{send_to_tty, tty_graphics, tty_terminal},
+};
+
+/// The ways a terminal may display images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Graphics {
+    Kitty,
+    Sixel,
+}
+
+impl Graphics {
+    /// The name of the protocol at the Lisp level.
+    fn name(self) -> &'static str {
+        match self {
+            Graphics::Kitty => "kitty",
+            Graphics::Sixel => "sixel",
+        }
+    }
+}
+
+/// The size of a character cell in pixels, for terminals that don't say.
+const DEFAULT_CELL_SIZE: (u32, u32) = (10, 20);
+
+/// The largest image, in pixels, that is decoded.
+const MAX_PIXELS: usize = 1 << 26;
+
+/// An image with 8-bit RGBA pixels.
+#[derive(Debug, PartialEq)]
+struct Image {
+    width: usize,
+    height: usize,
+    rgba: Vec<u8>,
+}
+
+type DecodeResult<T> = Result<T, &'static str>;
+
+impl Image {
+    fn new(width: usize, height: usize) -> DecodeResult<Self> {
+        if width == 0 || height == 0 || width.saturating_mul(height) > MAX_PIXELS {
+            return Err("Invalid image size");
+        }
+        Ok(Image {
+            width,
+            height,
+            rgba: Vec::with_capacity(width * height * 4),
+        })
+    }
+
+    /// Decode DATA, which holds an image in one of the formats known here.
+    fn decode(data: &[u8]) -> DecodeResult<Self> {
+        if data.starts_with(PNG_SIGNATURE) {
+            decode_png(data)
+        } else if data.starts_with(b"P6") || data.starts_with(b"P5") {
+            decode_pnm(data)
+        } else {
+            Err("Unknown image format")
+        }
+    }
+
+    /// Return the image scaled down to fit into WIDTH by HEIGHT pixels,
+    /// keeping its aspect ratio.  Each pixel of the result is the
+    /// average of the pixels it covers.
+    fn fit(self, width: usize, height: usize) -> Self {
+        if self.width <= width && self.height <= height {
+            return self;
+        }
+        let scale = f64::min(
+            width as f64 / self.width as f64,
+            height as f64 / self.height as f64,
+        );
+        let new_width = ((self.width as f64 * scale) as usize).max(1);
+        let new_height = ((self.height as f64 * scale) as usize).max(1);
+
+        let mut rgba = Vec::with_capacity(new_width * new_height * 4);
+        for y in 0..new_height {
+            let (y0, y1) = span(y, new_height, self.height);
+            for x in 0..new_width {
+                let (x0, x1) = span(x, new_width, self.width);
+                let mut sum = [0usize; 4];
+                for sy in y0..y1 {
+                    let row = &self.rgba[(sy * self.width + x0) * 4..(sy * self.width + x1) * 4];
+                    for pixel in row.chunks(4) {
+                        for (s, &c) in sum.iter_mut().zip(pixel) {
+                            *s += c as usize;
+                        }
+                    }
+                }
+                let n = (y1 - y0) * (x1 - x0);
+                rgba.extend(sum.iter().map(|&s| (s / n) as u8));
+            }
+        }
+        Image {
+            width: new_width,
+            height: new_height,
+            rgba,
+        }
+    }
+
+    /// Drop the pixel rows below HEIGHT.
+    fn crop(mut self, height: usize) -> Self {
+        if height < self.height {
+            self.height = height.max(1);
+            self.rgba.truncate(self.width * self.height * 4);
+        }
+        self
+    }
+}
+
+/// The source pixels that the Nth of COUNT pixels, scaled from TOTAL,
+/// covers.
+fn span(n: usize, count: usize, total: usize) -> (usize, usize) {
+    let start = n * total / count;
+    let end = ((n + 1) * total / count).max(start + 1);
+    (start, end.min(total))
+}
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    (u32::from(bytes[0]) << 24)
+        | (u32::from(bytes[1]) << 16)
+        | (u32::from(bytes[2]) << 8)
+        | u32::from(bytes[3])
+}
+
+/// The Paeth predictor of the PNG specification.
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) = (
+        (p - i16::from(a)).abs(),
+        (p - i16::from(b)).abs(),
+        (p - i16::from(c)).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Decode a PNG image.  Interlaced images are not supported.
+fn decode_png(data: &[u8]) -> DecodeResult<Image> {
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut transparency: &[u8] = &[];
+    let mut compressed = Vec::new();
+
+    let mut rest = &data[PNG_SIGNATURE.len()..];
+    while rest.len() >= 12 {
+        let length = be_u32(rest) as usize;
+        if rest.len() < length + 12 {
+            return Err("Truncated PNG image");
+        }
+        let (kind, chunk) = (&rest[4..8], &rest[8..8 + length]);
+        match kind {
+            b"IHDR" if length == 13 => header = Some(chunk),
+            b"PLTE" => palette = chunk,
+            b"tRNS" => transparency = chunk,
+            b"IDAT" => compressed.extend_from_slice(chunk),
+            b"IEND" => break,
+            _ => {}
+        }
+        rest = &rest[length + 12..];
+    }
+
+    let header = header.ok_or("Invalid PNG image")?;
+    let (width, height) = (be_u32(header) as usize, be_u32(&header[4..]) as usize);
+    let (depth, color_type) = (header[8] as usize, header[9]);
+    if header[12] != 0 {
+        return Err("Interlaced PNG images are not supported");
+    }
+    let channels = match (color_type, depth) {
+        (0, 1) | (0, 2) | (0, 4) | (0, 8) | (0, 16) => 1,
+        (3, 1) | (3, 2) | (3, 4) | (3, 8) => 1,
+        (4, 8) | (4, 16) => 2,
+        (2, 8) | (2, 16) => 3,
+        (6, 8) | (6, 16) => 4,
+        _ => return Err("Invalid PNG image"),
+    };
+    let mut image = Image::new(width, height)?;
+
+    let stride = (width * channels * depth + 7) / 8;
+    let bpp = ((channels * depth) / 8).max(1);
+    let mut raw = Vec::new();
+    ZlibDecoder::new(compressed.as_slice())
+        .read_to_end(&mut raw)
+        .map_err(|_| "Invalid PNG image")?;
+    if raw.len() < (stride + 1) * height {
+        return Err("Truncated PNG image");
+    }
+
+    let mut previous = vec![0u8; stride];
+    let mut row = vec![0u8; stride];
+    for y in 0..height {
+        let line = &raw[y * (stride + 1)..(y + 1) * (stride + 1)];
+        let filter = line[0];
+        row.copy_from_slice(&line[1..]);
+        for i in 0..stride {
+            let a = if i >= bpp { row[i - bpp] } else { 0 };
+            let b = previous[i];
+            let c = if i >= bpp { previous[i - bpp] } else { 0 };
+            row[i] = row[i].wrapping_add(match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((u16::from(a) + u16::from(b)) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err("Invalid PNG image"),
+            });
+        }
+
+        for x in 0..width {
+            // The samples of the pixel, as 8-bit values, except for
+            // palette indices.
+            let sample = |n: usize| -> u8 {
+                match depth {
+                    16 => row[(x * channels + n) * 2],
+                    8 => row[x * channels + n],
+                    _ => {
+                        let bit = x * depth;
+                        let value = (row[bit / 8] >> (8 - depth - bit % 8)) & ((1 << depth) - 1);
+                        if color_type == 3 {
+                            value
+                        } else {
+                            (u32::from(value) * 255 / ((1 << depth) - 1)) as u8
+                        }
+                    }
+                }
+            };
+            let pixel = match color_type {
+                0 => [sample(0), sample(0), sample(0), 255],
+                2 => [sample(0), sample(1), sample(2), 255],
+                3 => {
+                    let index = sample(0) as usize;
+                    let color = palette
+                        .get(index * 3..index * 3 + 3)
+                        .ok_or("Invalid PNG image")?;
+                    let alpha = transparency.get(index).cloned().unwrap_or(255);
+                    [color[0], color[1], color[2], alpha]
+                }
+                4 => [sample(0), sample(0), sample(0), sample(1)],
+                _ => [sample(0), sample(1), sample(2), sample(3)],
+            };
+            image.rgba.extend_from_slice(&pixel);
+        }
+        std::mem::swap(&mut previous, &mut row);
+    }
+    Ok(image)
+}
+
+/// Decode a binary PPM or PGM image.
+fn decode_pnm(data: &[u8]) -> DecodeResult<Image> {
+    let gray = data[1] == b'5';
+    let mut fields = [0usize; 3];
+    let mut pos = 2;
+    for field in fields.iter_mut() {
+        loop {
+            match data.get(pos) {
+                Some(b'#') => {
+                    while data.get(pos).map_or(false, |&b| b != b'\n') {
+                        pos += 1;
+                    }
+                }
+                Some(b) if b.is_ascii_whitespace() => pos += 1,
+                _ => break,
+            }
+        }
+        let start = pos;
+        while data.get(pos).map_or(false, u8::is_ascii_digit) {
+            *field = field
+                .saturating_mul(10)
+                .saturating_add((data[pos] - b'0') as usize);
+            pos += 1;
+        }
+        if pos == start {
+            return Err("Invalid PNM image");
+        }
+    }
+    // A single whitespace character precedes the pixels.
+    pos += 1;
+
+    let [width, height, maxval] = fields;
+    if maxval == 0 || maxval > 65535 {
+        return Err("Invalid PNM image");
+    }
+    let mut image = Image::new(width, height)?;
+    let sample_size = if maxval > 255 { 2 } else { 1 };
+    let channels = if gray { 1 } else { 3 };
+    let pixels = data.get(pos..).unwrap_or(&[]);
+    if pixels.len() < width * height * channels * sample_size {
+        return Err("Truncated PNM image");
+    }
+    for pixel in pixels.chunks(channels * sample_size).take(width * height) {
+        let sample = |n: usize| -> u8 {
+            let value = if sample_size == 2 {
+                (pixel[n * 2] as usize) << 8 | pixel[n * 2 + 1] as usize
+            } else {
+                pixel[n] as usize
+            };
+            (value * 255 / maxval) as u8
+        };
+        if gray {
+            image.rgba.extend_from_slice(&[sample(0); 3]);
+        } else {
+            image
+                .rgba
+                .extend_from_slice(&[sample(0), sample(1), sample(2)]);
+        }
+        image.rgba.push(255);
+    }
+    Ok(image)
+}
+
+/// The size of the kitty protocol's chunks of image data.
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Return the kitty graphics command that displays IMAGE at the cursor
+/// in COLS by ROWS cells, without moving the cursor.
+fn kitty_encode(image: &Image, cols: usize, rows: usize) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&image.rgba).unwrap();
+    let payload = base64_crate::encode(&encoder.finish().unwrap());
+
+    let mut out = Vec::with_capacity(payload.len() + 64);
+    let chunks: Vec<&[u8]> = payload.as_bytes().chunks(KITTY_CHUNK_SIZE).collect();
+    for (n, chunk) in chunks.iter().enumerate() {
+        let more = if n + 1 < chunks.len() { 1 } else { 0 };
+        if n == 0 {
+            // Replies are suppressed, as they would arrive as input.
+            write!(
+                out,
+                "\x1b_Ga=T,f=32,o=z,s={},v={},c={},r={},C=1,q=2,m={};",
+                image.width, image.height, cols, rows, more
+            )
+            .unwrap();
+        } else {
+            write!(out, "\x1b_Gm={};", more).unwrap();
+        }
+        out.extend_from_slice(chunk);
+        out.extend_from_slice(b"\x1b\\");
+    }
+    out
+}
+
+/// The command that deletes all images the kitty protocol displays.
+const KITTY_CLEAR: &[u8] = b"\x1b_Ga=d,q=2\x1b\\";
+
+/// The number of levels of each primary in the sixel palette.
+const SIXEL_LEVELS: usize = 6;
+
+/// Return the index in the sixel palette of the color of PIXEL, or
+/// None if the pixel is transparent.
+fn sixel_color(pixel: &[u8]) -> Option<usize> {
+    if pixel[3] < 128 {
+        return None;
+    }
+    let level = |c: u8| (c as usize * (SIXEL_LEVELS - 1) + 127) / 255;
+    Some((level(pixel[0]) * SIXEL_LEVELS + level(pixel[1])) * SIXEL_LEVELS + level(pixel[2]))
+}
+
+/// Append the sixel character C, repeated COUNT times, to OUT.
+fn sixel_run(out: &mut Vec<u8>, c: u8, count: usize) {
+    match count {
+        0 => {}
+        1..=3 => out.extend(std::iter::repeat(c).take(count)),
+        _ => write!(out, "!{}{}", count, c as char).unwrap(),
+    }
+}
+
+/// Return the sixel sequence that displays IMAGE at the cursor.
+/// Transparent pixels are left alone.
+fn sixel_encode(image: &Image) -> Vec<u8> {
+    let mut out = Vec::new();
+    write!(out, "\x1bP0;1;0q\"1;1;{};{}", image.width, image.height).unwrap();
+
+    let colors: Vec<Option<usize>> = image.rgba.chunks(4).map(sixel_color).collect();
+    let mut used = vec![false; SIXEL_LEVELS.pow(3)];
+    for color in colors.iter().filter_map(|&c| c) {
+        used[color] = true;
+    }
+    let percent = |level: usize| level * 100 / (SIXEL_LEVELS - 1);
+    for (color, _) in used.iter().enumerate().filter(|&(_, &u)| u) {
+        let (r, g, b) = (
+            color / (SIXEL_LEVELS * SIXEL_LEVELS),
+            color / SIXEL_LEVELS % SIXEL_LEVELS,
+            color % SIXEL_LEVELS,
+        );
+        write!(
+            out,
+            "#{};2;{};{};{}",
+            color,
+            percent(r),
+            percent(g),
+            percent(b)
+        )
+        .unwrap();
+    }
+
+    let mut bits = vec![0u8; image.width];
+    for band in (0..image.height).step_by(6) {
+        let band_rows = band..(band + 6).min(image.height);
+        let mut in_band = vec![false; used.len()];
+        for y in band_rows.clone() {
+            for color in colors[y * image.width..(y + 1) * image.width]
+                .iter()
+                .filter_map(|&c| c)
+            {
+                in_band[color] = true;
+            }
+        }
+
+        let mut first = true;
+        for (color, _) in in_band.iter().enumerate().filter(|&(_, &u)| u) {
+            for (x, b) in bits.iter_mut().enumerate() {
+                *b = band_rows
+                    .clone()
+                    .filter(|&y| colors[y * image.width + x] == Some(color))
+                    .fold(0, |b, y| b | 1 << (y - band));
+            }
+            if !first {
+                // Go back to the start of the band.
+                out.push(b'$');
+            }
+            first = false;
+            write!(out, "#{}", color).unwrap();
+
+            let (mut run, mut count) = (0x3f + bits[0], 0);
+            for &b in &bits {
+                if 0x3f + b == run {
+                    count += 1;
+                } else {
+                    sixel_run(&mut out, run, count);
+                    run = 0x3f + b;
+                    count = 1;
+                }
+            }
+            // Trailing blanks need not be sent.
+            if run != 0x3f {
+                sixel_run(&mut out, run, count);
+            }
+        }
+        out.push(b'-');
+    }
+    out.extend_from_slice(b"\x1b\\");
+    out
+}
+
+/// The size of a character cell of the tty T in pixels.
+fn cell_size(t: *mut terminal) -> (u32, u32) {
+    #[cfg(unix)]
+    unsafe {
+        let output = (*(*t).display_info.tty).output;
+        let mut size: libc::winsize = std::mem::zeroed();
+        if !output.is_null()
+            && libc::ioctl(
+                libc::fileno(output as *mut libc::FILE),
+                libc::TIOCGWINSZ,
+                &mut size,
+            ) == 0
+            && size.ws_xpixel > 0
+            && size.ws_ypixel > 0
+            && size.ws_col > 0
+            && size.ws_row > 0
+        {
+            return (
+                u32::from(size.ws_xpixel / size.ws_col),
+                u32::from(size.ws_ypixel / size.ws_row),
+            );
+        }
+    }
+    tty_graphics(t).1.unwrap_or(DEFAULT_CELL_SIZE)
+}
+
+/// Decode DATA as an image, or signal an error.
+fn decode_image(data: LispStringRef) -> Image {
+    if data.is_multibyte() {
+        error!("Image data must be a unibyte string");
+    }
+    match Image::decode(data.as_slice()) {
+        Ok(image) => image,
+        Err(message) => error!(message),
+    }
+}
+
+/// Return the protocol the text terminal TERMINAL uses to display images.
+/// The value is `kitty' for the kitty graphics protocol, `sixel' for sixel
+/// graphics, and nil if it can't display images, or was not asked yet,
+/// see `tty-negotiate-protocols'.
+///
+/// TERMINAL can be a terminal object, a frame, or nil (meaning the
+/// selected frame's terminal).  The value is nil if it is not a text
+/// terminal.
+#[lisp_fn(min = "0")]
+pub fn tty_graphics_protocol(terminal: LispObject) -> LispObject {
+    tty_terminal(terminal)
+        .and_then(|t| tty_graphics(t).0)
+        .map_or(Qnil, |graphics| LispObject::from(intern(graphics.name())))
+}
+
+/// Return the size of a character cell of the text terminal TERMINAL.
+/// The value is a cons (WIDTH . HEIGHT) in pixels.  If the terminal does
+/// not tell, a guess is returned.
+///
+/// TERMINAL can be a terminal object, a frame, or nil (meaning the
+/// selected frame's terminal).  The value is nil if it is not a text
+/// terminal.
+#[lisp_fn(min = "0")]
+pub fn tty_cell_size(terminal: LispObject) -> LispObject {
+    tty_terminal(terminal).map_or(Qnil, |t| {
+        let (width, height) = cell_size(t);
+        LispObject::cons(EmacsInt::from(width), EmacsInt::from(height))
+    })
+}
+
+/// Return the size of the image in the unibyte string DATA.
+/// The value is a cons (WIDTH . HEIGHT) in pixels.  DATA is the contents
+/// of a PNG, binary PPM or binary PGM file.
+#[lisp_fn]
+pub fn tty_image_size(data: LispStringRef) -> LispObject {
+    let image = decode_image(data);
+    LispObject::cons(image.width as EmacsInt, image.height as EmacsInt)
+}
+
+/// Display the image in DATA on the text terminal TERMINAL.
+/// DATA is a unibyte string with the contents of a PNG, binary PPM or
+/// binary PGM file.  The image is scaled down to fit into COLS by ROWS
+/// character cells, with its top left corner at column X and line Y of
+/// the terminal.  If VISIBLE-ROWS is non-nil, only that many lines of
+/// the scaled image are shown.
+///
+/// The image stays until the text over it is redrawn, or for terminals
+/// using the kitty protocol, until `tty-clear-images' is called.
+/// Nothing is done if the terminal can't display images.
+///
+/// TERMINAL can be a terminal object, a frame, or nil (meaning the
+/// selected frame's terminal).
+#[lisp_fn(min = "5")]
+pub fn tty_draw_image(
+    data: LispStringRef,
+    x: EmacsInt,
+    y: EmacsInt,
+    cols: EmacsInt,
+    rows: EmacsInt,
+    visible_rows: Option<EmacsInt>,
+    terminal: LispObject,
+) {
+    let (t, graphics) = match tty_terminal(terminal).map(|t| (t, tty_graphics(t).0)) {
+        Some((t, Some(graphics))) => (t, graphics),
+        _ => return,
+    };
+    if x < 0 || y < 0 || cols <= 0 || rows <= 0 {
+        args_out_of_range!(LispObject::from(cols), LispObject::from(rows));
+    }
+    let (cols, rows) = (cols as usize, rows as usize);
+    let visible_rows = visible_rows.map_or(rows, |v| v.max(0) as usize).min(rows);
+    if visible_rows == 0 {
+        return;
+    }
+
+    let (cell_width, cell_height) = cell_size(t);
+    let (cell_width, cell_height) = (cell_width as usize, cell_height as usize);
+    let image = decode_image(data)
+        .fit(cols * cell_width, rows * cell_height)
+        .crop(visible_rows * cell_height);
+    let image_rows = (image.height + cell_height - 1) / cell_height;
+    let image_cols = (image.width + cell_width - 1) / cell_width;
+
+    let mut out = Vec::new();
+    // Save the cursor, so that Emacs finds it where it left it.
+    write!(out, "\x1b7\x1b[{};{}H", y + 1, x + 1).unwrap();
+    match graphics {
+        Graphics::Kitty => out.extend(kitty_encode(&image, image_cols, image_rows)),
+        Graphics::Sixel => out.extend(sixel_encode(&image)),
+    }
+    out.extend_from_slice(b"\x1b8");
+    send_to_tty(t, &out);
+}
+
+/// Remove the images shown on the text terminal TERMINAL.
+/// Only images shown with the kitty protocol can be removed this way,
+/// sixel images go away when the text over them is redrawn.
+///
+/// TERMINAL can be a terminal object, a frame, or nil (meaning the
+/// selected frame's terminal).
+#[lisp_fn(min = "0")]
+pub fn tty_clear_images(terminal: LispObject) {
+    if let Some(t) = tty_terminal(terminal) {
+        if tty_graphics(t).0 == Some(Graphics::Kitty) {
+            send_to_tty(t, KITTY_CLEAR);
+        }
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/tty_graphics_exports.rs"));
+
+#[cfg(test)]
+fn png(width: u32, height: u32, depth: u8, color_type: u8, rows: &[&[u8]]) -> Vec<u8> {
+    fn be_bytes(n: u32) -> [u8; 4] {
+        [(n >> 24) as u8, (n >> 16) as u8, (n >> 8) as u8, n as u8]
+    }
+    fn chunk(out: &mut Vec<u8>, kind: &[u8], data: &[u8]) {
+        out.extend_from_slice(&be_bytes(data.len() as u32));
+        out.extend_from_slice(kind);
+        out.extend_from_slice(data);
+        // The checksum is not checked.
+        out.extend_from_slice(&[0; 4]);
+    }
+    let mut header = Vec::new();
+    header.extend_from_slice(&be_bytes(width));
+    header.extend_from_slice(&be_bytes(height));
+    header.extend_from_slice(&[depth, color_type, 0, 0, 0]);
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in rows {
+        encoder.write_all(row).unwrap();
+    }
+
+    let mut out = PNG_SIGNATURE.to_vec();
+    chunk(&mut out, b"IHDR", &header);
+    chunk(&mut out, b"IDAT", &encoder.finish().unwrap());
+    chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+#[test]
+fn test_decode_png() {
+    // Two RGB pixels per row, the second row with the Up filter.
+    let data = png(
+        2,
+        2,
+        8,
+        2,
+        &[&[0, 255, 0, 0, 0, 0, 255], &[2, 0, 255, 0, 0, 0, 0]],
+    );
+    let image = Image::decode(&data).unwrap();
+    assert_eq!((image.width, image.height), (2, 2));
+    assert_eq!(
+        image.rgba,
+        vec![255, 0, 0, 255, 0, 0, 255, 255, 255, 255, 0, 255, 0, 0, 255, 255]
+    );
+
+    // 1-bit grayscale.
+    let data = png(3, 1, 1, 0, &[&[0, 0b1010_0000]]);
+    assert_eq!(
+        Image::decode(&data).unwrap().rgba,
+        vec![255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255]
+    );
+
+    let mut interlaced = png(1, 1, 8, 0, &[&[0, 0]]);
+    interlaced[PNG_SIGNATURE.len() + 8 + 12] = 1;
+    assert!(Image::decode(&interlaced).is_err());
+    assert!(Image::decode(&data[..20]).is_err());
+}
+
+#[test]
+fn test_decode_pnm() {
+    let image = Image::decode(b"P6\n# comment\n2 1\n255\n\xff\x00\x00\x00\x00\xff").unwrap();
+    assert_eq!(image.rgba, vec![255, 0, 0, 255, 0, 0, 255, 255]);
+    let image = Image::decode(b"P5 1 1 15 \x0f").unwrap();
+    assert_eq!(image.rgba, vec![255, 255, 255, 255]);
+    assert!(Image::decode(b"P6 2 2 255 \x00").is_err());
+    assert!(Image::decode(b"GIF89a").is_err());
+}
+
+#[test]
+fn test_fit_and_crop() {
+    let image = Image {
+        width: 4,
+        height: 2,
+        rgba: [[0u8, 0, 0, 255], [200, 100, 0, 255]]
+            .iter()
+            .cycle()
+            .take(8)
+            .flat_map(|p| p.iter().cloned())
+            .collect(),
+    };
+    let image = image.fit(2, 2);
+    assert_eq!((image.width, image.height), (2, 1));
+    assert_eq!(image.rgba, vec![100, 50, 0, 255, 100, 50, 0, 255]);
+
+    let image = Image {
+        width: 1,
+        height: 10,
+        rgba: vec![0; 40],
+    }
+    .crop(4);
+    assert_eq!((image.height, image.rgba.len()), (4, 16));
+}
+
+#[test]
+fn test_sixel_encode() {
+    // A red pixel above a transparent one.
+    let image = Image {
+        width: 1,
+        height: 2,
+        rgba: vec![255, 0, 0, 255, 0, 0, 0, 0],
+    };
+    assert_eq!(
+        sixel_encode(&image),
+        b"\x1bP0;1;0q\"1;1;1;2#180;2;100;0;0#180@-\x1b\\".to_vec()
+    );
+
+    let image = Image {
+        width: 5,
+        height: 1,
+        rgba: [255u8; 20].to_vec(),
+    };
+    assert!(sixel_encode(&image).ends_with(b"#215!5@-\x1b\\"));
+}
+
+#[test]
+fn test_kitty_encode() {
+    let image = Image {
+        width: 64,
+        height: 64,
+        rgba: (0..64 * 64 * 4u32)
+            .map(|n| (n.wrapping_mul(2_654_435_761) >> 13) as u8)
+            .collect(),
+    };
+    let out = kitty_encode(&image, 8, 4);
+    assert!(out.starts_with(b"\x1b_Ga=T,f=32,o=z,s=64,v=64,c=8,r=4,C=1,q=2,m=1;"));
+    assert!(out.windows(8).any(|w| w == b"\x1b_Gm=0;"));
+    assert!(out.ends_with(b"\x1b\\"));
+}
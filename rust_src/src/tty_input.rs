@@ -0,0 +1,901 @@
+//! Decoding of text terminal input.
+//!
+//! The bytes read from a tty are turned into keyboard events here.
+//! Until the terminal has been set up with `tty-negotiate-protocols',
+//! each byte becomes a character event of its own, and escape sequences
+//! are left to `input-decode-map'.  Afterwards, the sequences of the
+//! protocols in use are decoded directly:
+//!
+//! - bracketed paste, where pasted text arrives between `ESC [ 200 ~'
+//!   and `ESC [ 201 ~', and becomes a single `xterm-paste' event;
+//! - xterm's modifyOtherKeys, which sends `ESC [ 27 ; MODS ; CODE ~'
+//!   for keys like C-; that have no control character;
+//! - the kitty keyboard protocol, which sends `ESC [ CODE ; MODS u'
+//!   for the same purpose;
+//! - and the `ESC [ 1 ; MODS X' and `ESC [ N ; MODS ~' forms used by
+//!   all of them for function keys with modifiers, such as S-f5.
+//!
+//! Sequences that are not recognized, or that are split across reads,
+//! are passed on byte by byte, as before.
+//!
+//! The answers to the queries about graphics that are sent along with
+//! the keyboard ones are recorded here too, for `tty_graphics', and so
+//! are the answers to clipboard queries, for `tty_clipboard'.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use libc::{c_int, c_uchar};
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    lisp::{defsubr, LispObject},
+    lists::{assq, cdr},
+    obarray::intern,
+    remacs_sys::{
+        char_bits, decode_tty_terminal, event_kind, quit_char, store_terminal_param, terminal,
+        tty_display_info, tty_store_event,
+    },
+    remacs_sys::{Qnil, Qsymbolp},
+    string_alloc::make_unibyte_string,
+    tty_graphics::Graphics,
+};
+
+/// The protocols a terminal may use for keyboard input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Protocol {
+    BracketedPaste,
+    ModifyOtherKeys,
+    KittyKeyboard,
+}
+
+impl Protocol {
+    const ALL: [Protocol; 3] = [
+        Protocol::BracketedPaste,
+        Protocol::ModifyOtherKeys,
+        Protocol::KittyKeyboard,
+    ];
+
+    fn bit(self) -> u32 {
+        1 << (self as u32)
+    }
+
+    /// The name of the protocol at the Lisp level.
+    fn name(self) -> &'static str {
+        match self {
+            Protocol::BracketedPaste => "bracketed-paste",
+            Protocol::ModifyOtherKeys => "modify-other-keys",
+            Protocol::KittyKeyboard => "kitty-keyboard",
+        }
+    }
+
+    fn from_symbol(symbol: LispObject) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .cloned()
+            .find(|p| symbol.eq(LispObject::from(intern(p.name()))))
+    }
+
+    /// The sequence that turns the protocol on.
+    fn set_string(self) -> &'static [u8] {
+        match self {
+            Protocol::BracketedPaste => b"\x1b[?2004h",
+            Protocol::ModifyOtherKeys => b"\x1b[>4;1m",
+            // Only ask for keys to be disambiguated, which leaves the
+            // keys that were unambiguous as they are.
+            Protocol::KittyKeyboard => b"\x1b[>1u",
+        }
+    }
+
+    /// The sequence that turns the protocol off.
+    fn reset_string(self) -> &'static [u8] {
+        match self {
+            Protocol::BracketedPaste => b"\x1b[?2004l",
+            Protocol::ModifyOtherKeys => b"\x1b[>4m",
+            Protocol::KittyKeyboard => b"\x1b[<u",
+        }
+    }
+}
+
+/// The query for the flags of the kitty keyboard protocol.  Terminals
+/// that do not know the protocol ignore it.
+const KITTY_QUERY: &[u8] = b"\x1b[?u";
+
+/// The queries about graphics: whether the kitty graphics protocol is
+/// supported, which is answered with an APC sequence, the size of a
+/// character cell in pixels, and the primary device attributes, which
+/// include 4 if sixel graphics are supported.  Every terminal answers
+/// the last one, so its answer ends the queries.
+const GRAPHICS_QUERY: &[u8] = b"\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\\x1b[16t\x1b[c";
+
+/// The start of the answer to the kitty graphics query, and the end of
+/// APC sequences.
+const KITTY_GRAPHICS_ANSWER: &[u8] = b"\x1b_Gi=31;";
+const STRING_TERMINATOR: &[u8] = b"\x1b\\";
+
+/// The start of the answer to an OSC 52 clipboard query, which ends
+/// with BEL or `STRING_TERMINATOR'.
+const CLIPBOARD_ANSWER: &[u8] = b"\x1b]52;";
+/// The longest answer to a clipboard query that is kept.  The rest of a
+/// longer one is read and dropped, as if the terminal had refused.
+const MAX_CLIPBOARD_ANSWER: usize = 1 << 20;
+
+const PASTE_START: u32 = 200;
+const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// X keysyms of the function keys, as in `lispy_function_keys' in
+/// keyboard.c.
+const XK_BACKSPACE: u32 = 0xff08;
+const XK_TAB: u32 = 0xff09;
+const XK_RETURN: u32 = 0xff0d;
+const XK_ESCAPE: u32 = 0xff1b;
+const XK_HOME: u32 = 0xff50;
+const XK_LEFT: u32 = 0xff51;
+const XK_UP: u32 = 0xff52;
+const XK_RIGHT: u32 = 0xff53;
+const XK_DOWN: u32 = 0xff54;
+const XK_PRIOR: u32 = 0xff55;
+const XK_NEXT: u32 = 0xff56;
+const XK_END: u32 = 0xff57;
+const XK_INSERT: u32 = 0xff63;
+const XK_F1: u32 = 0xffbe;
+const XK_DELETE: u32 = 0xffff;
+
+/// The decoding state of a tty that has negotiated its protocols.
+#[derive(Default)]
+struct TtyInput {
+    /// The protocols in use, as a mask of `Protocol::bit'.
+    protocols: u32,
+    /// Whether an answer to `KITTY_QUERY' may still come.
+    kitty_query_pending: bool,
+    /// The text of a bracketed paste that is not complete yet.
+    paste: Option<Vec<u8>>,
+    /// Whether answers to `GRAPHICS_QUERY' may still come.
+    graphics_query_pending: bool,
+    /// The best graphics protocol the terminal supports.
+    graphics: Option<Graphics>,
+    /// The size of a character cell in pixels, as the terminal reported it.
+    cell_size: Option<(u32, u32)>,
+    /// Whether the answer to a clipboard query may still come.
+    clipboard_query_pending: bool,
+    /// The answer to a clipboard query that is not complete yet.
+    clipboard: Option<Vec<u8>>,
+    /// Whether that answer is longer than `MAX_CLIPBOARD_ANSWER'.
+    clipboard_too_long: bool,
+    /// The answer to the last clipboard query, without the `CLIPBOARD_ANSWER'
+    /// and the terminator.
+    clipboard_answer: Option<Vec<u8>>,
+}
+
+impl TtyInput {
+    fn uses(&self, protocol: Protocol) -> bool {
+        self.protocols & protocol.bit() != 0
+    }
+}
+
+thread_local! {
+    /// The state of each negotiated tty, by terminal id.
+    static TTYS: RefCell<HashMap<c_int, TtyInput>> = RefCell::new(HashMap::new());
+}
+
+/// Call F with the state of the tty T, creating it if necessary.
+fn with_state<R>(t: *mut terminal, f: impl FnOnce(&mut TtyInput) -> R) -> R {
+    let id = unsafe { (*t).id };
+    TTYS.with(|ttys| f(ttys.borrow_mut().entry(id).or_default()))
+}
+
+/// What the bytes read from a tty amount to.
+#[derive(Debug, PartialEq)]
+enum Input {
+    /// A byte to be handled the old way.
+    Byte(u8),
+    /// A character typed with the given modifier bits.
+    Char(u32, u32),
+    /// A function key, given by its X keysym, with modifier bits.
+    Key(u32, u32),
+    Paste(Vec<u8>),
+    /// The terminal answered `KITTY_QUERY'.
+    KittySupported,
+    /// A sequence that was consumed without producing an event.
+    Ignored,
+}
+
+/// A control sequence: `ESC [', an optional private marker, numeric
+/// parameters separated by `;', and a final byte.  Of parameters with
+/// `:'-separated parts, only the first part is kept.
+#[derive(Debug, PartialEq)]
+struct ControlSequence {
+    private: Option<u8>,
+    params: Vec<u32>,
+    final_byte: u8,
+    len: usize,
+}
+
+impl ControlSequence {
+    /// Parse the control sequence at the start of BYTES, if there is a
+    /// complete one.
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        if !bytes.starts_with(b"\x1b[") {
+            return None;
+        }
+        let mut i = 2;
+        let private = match bytes.get(i) {
+            Some(&b) if b'<' <= b && b <= b'?' => {
+                i += 1;
+                Some(b)
+            }
+            _ => None,
+        };
+        let mut params = Vec::new();
+        let mut param = None;
+        let mut in_subparam = false;
+        loop {
+            let b = *bytes.get(i)?;
+            i += 1;
+            match b {
+                b'0'..=b'9' if !in_subparam => {
+                    let digit = u32::from(b - b'0');
+                    param = Some(
+                        param
+                            .unwrap_or(0u32)
+                            .saturating_mul(10)
+                            .saturating_add(digit),
+                    );
+                }
+                b'0'..=b'9' => {}
+                b':' => in_subparam = true,
+                b';' => {
+                    params.push(param.take().unwrap_or(0));
+                    in_subparam = false;
+                }
+                0x40..=0x7e => {
+                    if param.is_some() || !params.is_empty() {
+                        params.push(param.unwrap_or(0));
+                    }
+                    return Some(ControlSequence {
+                        private,
+                        params,
+                        final_byte: b,
+                        len: i,
+                    });
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn param(&self, n: usize) -> u32 {
+        self.params.get(n).cloned().unwrap_or(0)
+    }
+}
+
+/// Convert a modifier parameter, which is one plus a mask of the
+/// modifiers held, to Emacs modifier bits.  Alt and Meta are both Meta
+/// to Emacs.
+fn key_modifiers(param: u32) -> u32 {
+    let mask = param.saturating_sub(1);
+    [
+        (1, char_bits::CHAR_SHIFT),
+        (2, char_bits::CHAR_META),
+        (4, char_bits::CHAR_CTL),
+        (8, char_bits::CHAR_SUPER),
+        (16, char_bits::CHAR_HYPER),
+        (32, char_bits::CHAR_META),
+    ]
+    .iter()
+    .filter(|&&(bit, _)| mask & bit != 0)
+    .fold(0, |modifiers, &(_, modifier)| modifiers | modifier as u32)
+}
+
+/// The event for the key that produces the character CODE, pressed
+/// with MODIFIERS.
+fn char_input(code: u32, modifiers: u32) -> Input {
+    let shift = char_bits::CHAR_SHIFT as u32;
+    let ctl = char_bits::CHAR_CTL as u32;
+    let (mut code, mut modifiers) = (code, modifiers);
+
+    // Terminals send the shifted character, except for letters.
+    if modifiers & shift != 0 {
+        if b'a' as u32 <= code && code <= b'z' as u32 {
+            code -= 0x20;
+        }
+        modifiers &= !shift;
+    }
+    // Keys that have a control character become that character, so
+    // that C-g still quits.
+    if modifiers & ctl != 0 && 0x40 <= code && code < 0x7f {
+        code &= 0x1f;
+        modifiers &= !ctl;
+    }
+
+    if modifiers == 0 {
+        return Input::Char(code, 0);
+    }
+    match code {
+        0x08 | 0x7f => Input::Key(XK_BACKSPACE, modifiers),
+        0x09 => Input::Key(XK_TAB, modifiers),
+        0x0d => Input::Key(XK_RETURN, modifiers),
+        0x1b => Input::Key(XK_ESCAPE, modifiers),
+        _ => Input::Char(code, modifiers),
+    }
+}
+
+/// The keysym of the function key sent as `ESC [ 1 ; MODS FINAL'.
+fn letter_key(final_byte: u8) -> Option<u32> {
+    Some(match final_byte {
+        b'A' => XK_UP,
+        b'B' => XK_DOWN,
+        b'C' => XK_RIGHT,
+        b'D' => XK_LEFT,
+        b'H' => XK_HOME,
+        b'F' => XK_END,
+        b'P'..=b'S' => XK_F1 + u32::from(final_byte - b'P'),
+        _ => return None,
+    })
+}
+
+/// The keysym of the function key sent as `ESC [ N ; MODS ~'.
+fn tilde_key(n: u32) -> Option<u32> {
+    Some(match n {
+        2 => XK_INSERT,
+        3 => XK_DELETE,
+        5 => XK_PRIOR,
+        6 => XK_NEXT,
+        // F5 to F10, and F11 and F12, with gaps in between.
+        15 => XK_F1 + 4,
+        17..=21 => XK_F1 + 5 + (n - 17),
+        23 | 24 => XK_F1 + 10 + (n - 23),
+        _ => return None,
+    })
+}
+
+/// Decode the control sequence SEQ, or return None to pass it on.
+fn decode_sequence(state: &mut TtyInput, seq: &ControlSequence) -> Option<Input> {
+    let keyboard = state.uses(Protocol::ModifyOtherKeys) || state.uses(Protocol::KittyKeyboard);
+    match (seq.private, seq.final_byte) {
+        (Some(b'?'), b'u') if state.kitty_query_pending => {
+            state.kitty_query_pending = false;
+            Some(Input::KittySupported)
+        }
+        (Some(b'?'), b'c') if state.graphics_query_pending => {
+            state.graphics_query_pending = false;
+            if state.graphics.is_none() && seq.params.contains(&4) {
+                state.graphics = Some(Graphics::Sixel);
+            }
+            Some(Input::Ignored)
+        }
+        (None, b't') if state.graphics_query_pending && seq.params.len() == 3 => {
+            if seq.param(0) == 6 && seq.param(1) > 0 && seq.param(2) > 0 {
+                state.cell_size = Some((seq.param(2), seq.param(1)));
+            }
+            Some(Input::Ignored)
+        }
+        (None, b'~') if seq.param(0) == PASTE_START && state.uses(Protocol::BracketedPaste) => {
+            state.paste = Some(Vec::new());
+            Some(Input::Ignored)
+        }
+        (None, b'~') if keyboard && seq.param(0) == 27 && seq.params.len() == 3 => {
+            Some(char_input(seq.param(2), key_modifiers(seq.param(1))))
+        }
+        (None, b'u') if keyboard && !seq.params.is_empty() => {
+            let code = seq.param(0);
+            // Keys without a character, like F13 or the keypad keys, are
+            // in the Private Use Area.  They are only sent when asked
+            // for; drop them.
+            if (0xe000 <= code && code < 0xf900) || std::char::from_u32(code).is_none() {
+                return Some(Input::Ignored);
+            }
+            Some(char_input(code, key_modifiers(seq.param(1))))
+        }
+        (None, b'~') if seq.params.len() == 2 && seq.param(1) > 1 => {
+            tilde_key(seq.param(0)).map(|key| Input::Key(key, key_modifiers(seq.param(1))))
+        }
+        (None, final_byte) if seq.params.len() == 2 && seq.param(0) == 1 && seq.param(1) > 1 => {
+            letter_key(final_byte).map(|key| Input::Key(key, key_modifiers(seq.param(1))))
+        }
+        _ => None,
+    }
+}
+
+/// Return the position and the length of the end of the OSC sequence
+/// whose contents start BYTES.
+fn osc_end(bytes: &[u8]) -> Option<(usize, usize)> {
+    let bel = bytes.iter().position(|&b| b == 0x07);
+    let st = bytes
+        .windows(STRING_TERMINATOR.len())
+        .position(|w| w == STRING_TERMINATOR);
+    match (bel, st) {
+        (Some(bel), Some(st)) if st < bel => Some((st, STRING_TERMINATOR.len())),
+        (Some(bel), _) => Some((bel, 1)),
+        (None, Some(st)) => Some((st, STRING_TERMINATOR.len())),
+        (None, None) => None,
+    }
+}
+
+/// Decode BYTES, read from a tty in state STATE.
+fn decode(state: &mut TtyInput, bytes: &[u8]) -> Vec<Input> {
+    let mut input = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if let Some(mut text) = state.paste.take() {
+            // The end marker may have started in an earlier read.
+            let searched = text.len().saturating_sub(PASTE_END.len() - 1);
+            text.extend_from_slice(&bytes[i..]);
+            match text[searched..]
+                .windows(PASTE_END.len())
+                .position(|w| w == PASTE_END)
+            {
+                Some(pos) => {
+                    let end = searched + pos;
+                    // The bytes after the marker were not part of the paste.
+                    let rest = text.len() - end - PASTE_END.len();
+                    i = bytes.len() - rest;
+                    text.truncate(end);
+                    input.push(Input::Paste(text));
+                }
+                None => {
+                    state.paste = Some(text);
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if let Some(mut answer) = state.clipboard.take() {
+            // The terminator may have started in an earlier read.
+            let searched = answer.len().saturating_sub(STRING_TERMINATOR.len() - 1);
+            answer.extend_from_slice(&bytes[i..]);
+            match osc_end(&answer[searched..]) {
+                Some((pos, len)) => {
+                    let end = searched + pos;
+                    let rest = answer.len() - end - len;
+                    i = bytes.len() - rest;
+                    answer.truncate(end);
+                    if state.clipboard_too_long || answer.len() > MAX_CLIPBOARD_ANSWER {
+                        answer = b"?".to_vec();
+                    }
+                    state.clipboard_query_pending = false;
+                    state.clipboard_too_long = false;
+                    state.clipboard_answer = Some(answer);
+                }
+                None => {
+                    if answer.len() > MAX_CLIPBOARD_ANSWER {
+                        // Keep only what may be the start of the
+                        // terminator.
+                        answer.drain(..answer.len() - (STRING_TERMINATOR.len() - 1));
+                        state.clipboard_too_long = true;
+                    }
+                    state.clipboard = Some(answer);
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if state.clipboard_query_pending && bytes[i..].starts_with(CLIPBOARD_ANSWER) {
+            state.clipboard = Some(Vec::new());
+            i += CLIPBOARD_ANSWER.len();
+            continue;
+        }
+
+        if state.graphics_query_pending && bytes[i..].starts_with(KITTY_GRAPHICS_ANSWER) {
+            let answer = &bytes[i + KITTY_GRAPHICS_ANSWER.len()..];
+            if let Some(end) = answer
+                .windows(STRING_TERMINATOR.len())
+                .position(|w| w == STRING_TERMINATOR)
+            {
+                if &answer[..end] == b"OK" {
+                    state.graphics = Some(Graphics::Kitty);
+                }
+                i += KITTY_GRAPHICS_ANSWER.len() + end + STRING_TERMINATOR.len();
+                continue;
+            }
+        }
+
+        if bytes[i] == 0x1b {
+            if let Some(seq) = ControlSequence::parse(&bytes[i..]) {
+                if let Some(decoded) = decode_sequence(state, &seq) {
+                    if decoded != Input::Ignored {
+                        input.push(decoded);
+                    }
+                    i += seq.len;
+                    continue;
+                }
+            }
+        }
+        input.push(Input::Byte(bytes[i]));
+        i += 1;
+    }
+    input
+}
+
+/// Write BYTES to the tty T, without alteration.
+pub(crate) fn send_to_tty(t: *mut terminal, bytes: &[u8]) {
+    unsafe {
+        let tty = (*t).display_info.tty;
+        for &stream in &[(*tty).termscript, (*tty).output] {
+            if stream.is_null() {
+                continue;
+            }
+            let stream = stream as *mut libc::FILE;
+            libc::fwrite(
+                bytes.as_ptr() as *const libc::c_void,
+                1,
+                bytes.len(),
+                stream,
+            );
+            libc::fflush(stream);
+        }
+    }
+}
+
+/// Push BYTES onto the list of strings in the terminal parameter PARAMETER.
+fn push_terminal_string(t: *mut terminal, parameter: &str, bytes: &[u8]) {
+    let parameter = LispObject::from(intern(parameter));
+    let string = make_unibyte_string(bytes);
+    let strings = cdr(assq(parameter, unsafe { (*t).param_alist }));
+    unsafe { store_terminal_param(t, parameter, (string, strings).into()) };
+}
+
+/// Turn PROTOCOL on for the tty T, also whenever its modes are set up
+/// again, e.g. after Emacs was suspended.
+fn enable_protocol(t: *mut terminal, protocol: Protocol) {
+    if with_state(t, |state| state.uses(protocol)) {
+        return;
+    }
+    send_to_tty(t, protocol.set_string());
+    push_terminal_string(t, "tty-mode-set-strings", protocol.set_string());
+    push_terminal_string(t, "tty-mode-reset-strings", protocol.reset_string());
+    with_state(t, |state| state.protocols |= protocol.bit());
+}
+
+/// Return the tty terminal TERMINAL designates, if it is one.
+pub(crate) fn tty_terminal(terminal: LispObject) -> Option<*mut terminal> {
+    let t = unsafe { decode_tty_terminal(terminal) };
+    if t.is_null() {
+        None
+    } else {
+        Some(t)
+    }
+}
+
+/// Store an event for INPUT, read from the tty TTY.  Return true if it
+/// was the quit character.
+fn store_input(t: *mut terminal, input: Input) -> bool {
+    let tty: *mut tty_display_info = unsafe { (*t).display_info.tty };
+    let (kind, code, modifiers, arg) = match input {
+        Input::Byte(byte) => {
+            let meta_key = unsafe { (*tty).meta_key };
+            let modifiers = if meta_key == 1 && byte & 0x80 != 0 {
+                char_bits::CHAR_META as u32
+            } else {
+                0
+            };
+            let code = if meta_key != 2 { byte & 0x7f } else { byte };
+            (
+                event_kind::ASCII_KEYSTROKE_EVENT,
+                u32::from(code),
+                modifiers,
+                Qnil,
+            )
+        }
+        Input::Char(code, modifiers) if code < 0x80 => {
+            (event_kind::ASCII_KEYSTROKE_EVENT, code, modifiers, Qnil)
+        }
+        Input::Char(code, modifiers) => (
+            event_kind::MULTIBYTE_CHAR_KEYSTROKE_EVENT,
+            code,
+            modifiers,
+            Qnil,
+        ),
+        Input::Key(keysym, modifiers) => (
+            event_kind::NON_ASCII_KEYSTROKE_EVENT,
+            keysym,
+            modifiers,
+            Qnil,
+        ),
+        Input::Paste(text) => {
+            let text = make_unibyte_string(&text);
+            (event_kind::TTY_PASTE_EVENT, 0, 0, text)
+        }
+        Input::KittySupported => {
+            enable_protocol(t, Protocol::KittyKeyboard);
+            return false;
+        }
+        Input::Ignored => return false,
+    };
+    unsafe { tty_store_event(tty, kind, code, modifiers, arg) };
+    kind == event_kind::ASCII_KEYSTROKE_EVENT
+        && modifiers == 0
+        && code as c_int == unsafe { quit_char }
+}
+
+/// Turn the NREAD bytes at BUF, read from the tty T, into input events.
+#[no_mangle]
+pub unsafe extern "C" fn tty_decode_input(t: *mut terminal, buf: *const c_uchar, nread: c_int) {
+    let bytes = std::slice::from_raw_parts(buf, nread as usize);
+    let id = (*t).id;
+    // The state is not borrowed while events are stored, as storing the
+    // quit character may exit nonlocally.
+    let input = TTYS.with(|ttys| match ttys.borrow_mut().get_mut(&id) {
+        Some(state) => decode(state, bytes),
+        None => bytes.iter().map(|&byte| Input::Byte(byte)).collect(),
+    });
+    for event in input {
+        // Don't look at input that follows a C-g too closely.
+        // This reduces lossage due to autorepeat on C-g.
+        if store_input(t, event) {
+            break;
+        }
+    }
+}
+
+/// Return the graphics protocol the tty T was found to support, and the
+/// size of its character cells if it reported it.
+pub(crate) fn tty_graphics(t: *mut terminal) -> (Option<Graphics>, Option<(u32, u32)>) {
+    let id = unsafe { (*t).id };
+    TTYS.with(|ttys| {
+        ttys.borrow()
+            .get(&id)
+            .map_or((None, None), |state| (state.graphics, state.cell_size))
+    })
+}
+
+/// Prepare for the answer to a clipboard query sent to the tty T.
+/// An answer to an earlier query that came too late is dropped.
+pub(crate) fn expect_clipboard_answer(t: *mut terminal) {
+    with_state(t, |state| {
+        state.clipboard_query_pending = true;
+        state.clipboard_answer = None;
+    });
+}
+
+/// Return the answer to the last clipboard query sent to the tty T, if
+/// it came already: the selection, `;', and the base64 encoded text.
+pub(crate) fn take_clipboard_answer(t: *mut terminal) -> Option<Vec<u8>> {
+    with_state(t, |state| state.clipboard_answer.take())
+}
+
+/// Forget the decoding state of the tty T, which is being deleted.
+#[no_mangle]
+pub unsafe extern "C" fn tty_forget_input(t: *mut terminal) {
+    let id = (*t).id;
+    TTYS.with(|ttys| ttys.borrow_mut().remove(&id));
+}
+
+/// Set up the keyboard protocols of the text terminal TERMINAL.
+/// Bracketed paste mode is turned on, which terminals that lack it
+/// ignore, and the terminal is asked whether it supports the kitty
+/// keyboard protocol, which is turned on when it answers.  From then on,
+/// the escape sequences of these protocols, and those xterm uses for
+/// function keys with modifiers, are decoded as they are read.
+///
+/// The terminal is also asked which graphics it can display, see
+/// `tty-graphics-protocol'.
+///
+/// TERMINAL can be a terminal object, a frame, or nil (meaning the
+/// selected frame's terminal).  Nothing is done if it is not a text
+/// terminal.  Use `tty-capabilities' to see which protocols are in use.
+#[lisp_fn(min = "0")]
+pub fn tty_negotiate_protocols(terminal: LispObject) {
+    if let Some(t) = tty_terminal(terminal) {
+        enable_protocol(t, Protocol::BracketedPaste);
+        with_state(t, |state| {
+            state.kitty_query_pending = true;
+            state.graphics_query_pending = true;
+        });
+        send_to_tty(t, KITTY_QUERY);
+        send_to_tty(t, GRAPHICS_QUERY);
+    }
+}
+
+/// Turn the keyboard protocol PROTOCOL on for the text terminal TERMINAL.
+/// PROTOCOL is one of `bracketed-paste', `modify-other-keys' (xterm's
+/// modifyOtherKeys mode), and `kitty-keyboard'.  The protocol stays in
+/// use when the terminal's modes are set up again, e.g. after Emacs
+/// is resumed.
+///
+/// TERMINAL can be a terminal object, a frame, or nil (meaning the
+/// selected frame's terminal).  Nothing is done if it is not a text
+/// terminal.
+#[lisp_fn(min = "1")]
+pub fn tty_enable_protocol(protocol: LispObject, terminal: LispObject) {
+    let protocol = match Protocol::from_symbol(protocol) {
+        Some(protocol) => protocol,
+        None => match protocol.as_symbol() {
+            Some(symbol) => error!(
+                "Unknown keyboard protocol: {}",
+                symbol.symbol_name().as_string_or_error()
+            ),
+            None => wrong_type!(Qsymbolp, protocol),
+        },
+    };
+    if let Some(t) = tty_terminal(terminal) {
+        enable_protocol(t, protocol);
+    }
+}
+
+/// Return the keyboard protocols in use on the text terminal TERMINAL.
+/// The value is a list of symbols, see `tty-enable-protocol'.
+/// TERMINAL can be a terminal object, a frame, or nil (meaning the
+/// selected frame's terminal).  The value is nil if it is not a text
+/// terminal, or its protocols were never negotiated.
+#[lisp_fn(min = "0")]
+pub fn tty_capabilities(terminal: LispObject) -> LispObject {
+    let t = match tty_terminal(terminal) {
+        Some(t) => t,
+        None => return Qnil,
+    };
+    let id = unsafe { (*t).id };
+    let protocols = TTYS.with(|ttys| ttys.borrow().get(&id).map_or(0, |state| state.protocols));
+    Protocol::ALL
+        .iter()
+        .rev()
+        .filter(|p| protocols & p.bit() != 0)
+        .fold(Qnil, |list, p| {
+            (LispObject::from(intern(p.name())), list).into()
+        })
+}
+
+include!(concat!(env!("OUT_DIR"), "/tty_input_exports.rs"));
+
+#[cfg(test)]
+fn keyboard_state() -> TtyInput {
+    TtyInput {
+        protocols: Protocol::BracketedPaste.bit() | Protocol::KittyKeyboard.bit(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_parse_control_sequence() {
+    let seq = ControlSequence::parse(b"\x1b[27;5;59~x").unwrap();
+    assert_eq!(seq.private, None);
+    assert_eq!(seq.params, vec![27, 5, 59]);
+    assert_eq!(seq.final_byte, b'~');
+    assert_eq!(seq.len, 10);
+
+    let seq = ControlSequence::parse(b"\x1b[?1u").unwrap();
+    assert_eq!(seq.private, Some(b'?'));
+    assert_eq!(seq.params, vec![1]);
+
+    let seq = ControlSequence::parse(b"\x1b[97:65;2u").unwrap();
+    assert_eq!(seq.params, vec![97, 2]);
+
+    assert_eq!(ControlSequence::parse(b"\x1b[A").unwrap().params, vec![]);
+    assert_eq!(ControlSequence::parse(b"\x1b[27;5"), None);
+    assert_eq!(ControlSequence::parse(b"\x1bOP"), None);
+}
+
+#[test]
+fn test_undecoded_input() {
+    let mut state = TtyInput::default();
+    assert_eq!(
+        decode(&mut state, b"a\x1b[27;5;59~"),
+        b"a\x1b[27;5;59~"
+            .iter()
+            .map(|&b| Input::Byte(b))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_modified_keys() {
+    let ctl = char_bits::CHAR_CTL as u32;
+    let meta = char_bits::CHAR_META as u32;
+    let shift = char_bits::CHAR_SHIFT as u32;
+    let mut state = keyboard_state();
+    assert_eq!(
+        decode(&mut state, b"\x1b[59;5u"),
+        vec![Input::Char(59, ctl)]
+    );
+    assert_eq!(
+        decode(&mut state, b"\x1b[27;5;59~"),
+        vec![Input::Char(59, ctl)]
+    );
+    // C-g is the control character, so that it quits.
+    assert_eq!(decode(&mut state, b"\x1b[103;5u"), vec![Input::Char(7, 0)]);
+    assert_eq!(
+        decode(&mut state, b"\x1b[97;4u"),
+        vec![Input::Char(65, meta)]
+    );
+    assert_eq!(
+        decode(&mut state, b"\x1b[13;5u"),
+        vec![Input::Key(XK_RETURN, ctl)]
+    );
+    assert_eq!(decode(&mut state, b"\x1b[27u"), vec![Input::Char(27, 0)]);
+    assert_eq!(
+        decode(&mut state, b"\x1b[15;2~"),
+        vec![Input::Key(XK_F1 + 4, shift)]
+    );
+    assert_eq!(
+        decode(&mut state, b"\x1b[24;5~"),
+        vec![Input::Key(XK_F1 + 11, ctl)]
+    );
+    assert_eq!(
+        decode(&mut state, b"\x1b[1;3A"),
+        vec![Input::Key(XK_UP, meta)]
+    );
+    // Unmodified keys are left to `input-decode-map'.
+    assert_eq!(decode(&mut state, b"\x1b[A").len(), 3);
+}
+
+#[test]
+fn test_bracketed_paste() {
+    let mut state = keyboard_state();
+    assert_eq!(
+        decode(&mut state, b"\x1b[200~hello\x1b[201~x"),
+        vec![Input::Paste(b"hello".to_vec()), Input::Byte(b'x')]
+    );
+    assert_eq!(decode(&mut state, b"\x1b[200~one\x1b[2"), vec![]);
+    assert_eq!(
+        decode(&mut state, b"01~\x1b"),
+        vec![Input::Paste(b"one".to_vec()), Input::Byte(0x1b)]
+    );
+}
+
+#[test]
+fn test_kitty_query() {
+    let mut state = TtyInput {
+        kitty_query_pending: true,
+        ..Default::default()
+    };
+    assert_eq!(decode(&mut state, b"\x1b[?0u"), vec![Input::KittySupported]);
+    assert!(!state.kitty_query_pending);
+}
+
+#[test]
+fn test_graphics_answers() {
+    let mut state = TtyInput {
+        graphics_query_pending: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        decode(&mut state, b"\x1b_Gi=31;OK\x1b\\\x1b[6;20;10tx"),
+        vec![Input::Byte(b'x')]
+    );
+    assert_eq!(state.cell_size, Some((10, 20)));
+    assert!(decode(&mut state, b"\x1b[?62;4;22c").is_empty());
+    assert!(!state.graphics_query_pending);
+    // Kitty is preferred to sixel.
+    assert_eq!(state.graphics, Some(Graphics::Kitty));
+
+    let mut state = TtyInput {
+        graphics_query_pending: true,
+        ..Default::default()
+    };
+    assert!(decode(&mut state, b"\x1b_Gi=31;ENOTSUPPORTED:\x1b\\\x1b[?62;4c").is_empty());
+    assert_eq!(state.graphics, Some(Graphics::Sixel));
+
+    // Without a query, the answers are passed on.
+    assert_eq!(decode(&mut state, b"\x1b[?1;2c").len(), 8);
+}
+
+#[test]
+fn test_clipboard_answer() {
+    let mut state = TtyInput {
+        clipboard_query_pending: true,
+        ..Default::default()
+    };
+    assert_eq!(decode(&mut state, b"\x1b]52;c;aGVs"), vec![]);
+    assert_eq!(decode(&mut state, b"bG8=\x1b\\x"), vec![Input::Byte(b'x')]);
+    assert_eq!(state.clipboard_answer, Some(b"c;aGVsbG8=".to_vec()));
+    assert!(!state.clipboard_query_pending);
+
+    // Without a query, the answer is passed on.
+    assert_eq!(decode(&mut state, b"\x1b]52;c;\x07").len(), 9);
+
+    state.clipboard_query_pending = true;
+    assert!(decode(&mut state, b"\x1b]52;p;\x07").is_empty());
+    assert_eq!(state.clipboard_answer, Some(b"p;".to_vec()));
+
+    // An answer that is too long is dropped, even in pieces.
+    state.clipboard_query_pending = true;
+    let mut long = b"\x1b]52;c;".to_vec();
+    long.resize(MAX_CLIPBOARD_ANSWER + 100, b'A');
+    assert!(decode(&mut state, &long).is_empty());
+    assert!(decode(&mut state, &long[..1000]).is_empty());
+    assert_eq!(decode(&mut state, b"\x1b\\y"), vec![Input::Byte(b'y')]);
+    assert_eq!(state.clipboard_answer, Some(b"?".to_vec()));
+    assert!(!state.clipboard_too_long);
+}
@@ -0,0 +1,289 @@
+//! Undo lists: change groups, sets of buffer changes that are kept or
+//! rolled back as a unit (see `atomic-change-group'), and keeping undo
+//! lists within their size limits.
+
+use std::mem;
+
+use libc::c_void;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::{LispBufferOrCurrent, LispBufferRef},
+    editfns::{save_excursion_save, widen},
+    eval::unbind_to,
+    lisp::defsubr,
+    lisp::LispObject,
+    lists::{LispConsCircularChecks, LispConsEndChecks},
+    multibyte::LispStringRef,
+    obarray::intern,
+    remacs_sys::{
+        globals, inhibit_garbage_collection, record_unwind_current_buffer, record_unwind_protect,
+        save_excursion_restore, save_restriction_restore, save_restriction_save,
+        set_buffer_internal,
+    },
+    remacs_sys::{EmacsInt, Lisp_Buffer, Lisp_Cons, Lisp_String, Qnil, Qt},
+    sequences::nreverse,
+    threads::c_specpdl_index,
+};
+
+/// Call FUNC with each buffer of the change group HANDLE and the state
+/// of its undo list that the group recorded.
+fn for_each_change_group_buffer<F>(handle: LispObject, mut func: F)
+where
+    F: FnMut(LispBufferRef, LispObject),
+{
+    for elt in handle.iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on) {
+        let (buffer, state) = elt.as_cons_or_error().into();
+        func(buffer.as_buffer_or_error(), state);
+    }
+}
+
+/// Return a handle for the current buffer's state, for a change group.
+/// If you specify BUFFER, make a handle for BUFFER's state instead.
+///
+/// Pass the handle to `activate-change-group' afterward to initiate
+/// the actual changes of the change group.
+///
+/// To finish the change group, call either `accept-change-group' or
+/// `cancel-change-group' passing the same handle as argument.  Call
+/// `accept-change-group' to accept the changes in the group as final;
+/// call `cancel-change-group' to undo them all.  You should use
+/// `unwind-protect' to make sure the group is always finished.  The call
+/// to `activate-change-group' should be inside the `unwind-protect'.
+/// Once you finish the group, don't use the handle again--don't try to
+/// finish the same group twice.  For a simple example of correct use, see
+/// the source code of `atomic-change-group'.
+///
+/// The handle records only the specified buffer.  To make a multibuffer
+/// change group, call this function once for each buffer you want to
+/// cover, then use `nconc' to combine the returned values, like this:
+///
+///   (nconc (prepare-change-group buffer-1)
+///          (prepare-change-group buffer-2))
+///
+/// You can then activate that multibuffer change group with a single
+/// call to `activate-change-group' and finish it with a single call
+/// to `accept-change-group' or `cancel-change-group'.
+#[lisp_fn(min = "0")]
+pub fn prepare_change_group(buffer: LispBufferOrCurrent) -> LispObject {
+    let buffer: LispBufferRef = buffer.into();
+    list!(LispObject::cons(buffer, buffer.undo_list()))
+}
+
+/// Activate a change group made with `prepare-change-group' (which see).
+#[lisp_fn]
+pub fn activate_change_group(handle: LispObject) {
+    for_each_change_group_buffer(handle, |mut buffer, _| {
+        if buffer.undo_list().eq(Qt) {
+            buffer.set_undo_list(Qnil);
+        }
+    });
+}
+
+/// Finish a change group made with `prepare-change-group' (which see).
+/// This finishes the change group by accepting its changes as final.
+#[lisp_fn]
+pub fn accept_change_group(handle: LispObject) {
+    for_each_change_group_buffer(handle, |mut buffer, state| {
+        if state.eq(Qt) {
+            buffer.set_undo_list(Qt);
+        }
+    });
+}
+
+/// Return the undo entries BUFFER recorded since its undo list was
+/// STATE, most recent first and without boundaries.
+fn changes_since(buffer: LispBufferRef, state: LispObject) -> LispObject {
+    let mut changes = Qnil;
+    let mut found = false;
+
+    for tail in buffer
+        .undo_list()
+        .iter_tails(LispConsEndChecks::off, LispConsCircularChecks::on)
+    {
+        if state.is_cons() && state.eq(tail) {
+            found = true;
+            break;
+        }
+        let entry = tail.car();
+        if entry.is_not_nil() {
+            changes = LispObject::cons(entry, changes);
+        }
+    }
+
+    if state.is_cons() && !found {
+        error!("Undoing to some unrelated state");
+    }
+    nreverse(changes)
+}
+
+/// Finish a change group made with `prepare-change-group' (which see).
+/// This finishes the change group by reverting all of its changes.
+#[lisp_fn]
+pub fn cancel_change_group(handle: LispObject) {
+    for_each_change_group_buffer(handle, |mut buffer, state| {
+        let count = c_specpdl_index();
+        unsafe {
+            record_unwind_current_buffer();
+            set_buffer_internal(buffer.as_mut());
+            // Widen the buffer temporarily so that changes made while
+            // the group was narrowed can all be undone.
+            record_unwind_protect(Some(save_restriction_restore), save_restriction_save());
+        }
+        widen();
+
+        // The group's changes are undone in one go, as a single change
+        // group, rather than by walking the list with `undo-more'.
+        let changes = changes_since(buffer, state);
+        if changes.is_not_nil() {
+            unsafe { record_unwind_protect(Some(save_excursion_restore), save_excursion_save()) };
+            call!(intern("primitive-undo").into(), 1.into(), changes);
+        }
+
+        // Revert the undo info to what it was when we grabbed the state.
+        buffer.set_undo_list(state);
+        unbind_to(count, Qnil);
+    });
+}
+
+/// The number of bytes a string takes up, counting its contents.
+fn string_size(string: LispStringRef) -> EmacsInt {
+    (mem::size_of::<Lisp_String>() + string.len_bytes() as usize + 1) as EmacsInt
+}
+
+/// The number of bytes the undo entry ELT takes up: the cons cell that
+/// links it into the undo list, the cons cells that make up the entry
+/// and any strings it holds, such as the text of a deletion.
+fn undo_entry_size(elt: LispObject) -> EmacsInt {
+    let cons_size = mem::size_of::<Lisp_Cons>() as EmacsInt;
+    let mut size = cons_size;
+    for tail in elt.iter_tails(LispConsEndChecks::off, LispConsCircularChecks::safe) {
+        size += cons_size;
+        if let Some(string) = tail.car().as_string() {
+            size += string_size(string);
+        }
+    }
+    size
+}
+
+/// Return the number of bytes BUFFER's undo list takes up.
+/// BUFFER defaults to the current buffer.  Each entry is counted with
+/// the cons cells it is made of and the text it records, which is how
+/// `undo-limit', `undo-strong-limit' and `undo-outer-limit' measure the
+/// list.  The value is 0 if undo is disabled in BUFFER.
+#[lisp_fn(min = "0")]
+pub fn undo_size(buffer: LispBufferOrCurrent) -> EmacsInt {
+    let buffer: LispBufferRef = buffer.into();
+    buffer
+        .undo_list()
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe)
+        .map(undo_entry_size)
+        .sum()
+}
+
+/// At garbage collection time, make the undo list of B shorter at the
+/// end.  How this is done depends on the variables `undo-limit',
+/// `undo-strong-limit' and `undo-outer-limit'.  In some cases this works
+/// by calling `undo-outer-limit-function'.
+#[no_mangle]
+pub extern "C" fn truncate_undo_list(b: *mut Lisp_Buffer) {
+    let mut buffer = LispBufferRef::from_ptr(b as *mut c_void).unwrap();
+
+    // Make sure that calling undo-outer-limit-function won't cause
+    // another GC.
+    let count = unsafe { inhibit_garbage_collection() };
+
+    // Make the buffer current to get its local values of variables such
+    // as undo-limit.  Also so that undo-outer-limit-function can tell
+    // which buffer to operate on.
+    unsafe {
+        record_unwind_current_buffer();
+        set_buffer_internal(buffer.as_mut());
+    }
+
+    let mut size_so_far = 0;
+    let mut prev = None;
+    let mut next = buffer.undo_list();
+
+    // If the first element is an undo boundary, skip past it.
+    if let Some(cons) = next.as_cons() {
+        if cons.car().is_nil() {
+            size_so_far += undo_entry_size(Qnil);
+            prev = Some(cons);
+            next = cons.cdr();
+        }
+    }
+
+    // Always preserve at least the most recent undo record unless it is
+    // really horribly big.
+    while let Some(cons) = next.as_cons() {
+        if cons.car().is_nil() {
+            break;
+        }
+        size_so_far += undo_entry_size(cons.car());
+        prev = Some(cons);
+        next = cons.cdr();
+    }
+
+    // If by the first boundary we have already passed undo-outer-limit,
+    // we're heading for memory full, so offer to clear out the list.
+    let (outer_limit, outer_limit_function) = unsafe {
+        (
+            globals.Vundo_outer_limit,
+            globals.Vundo_outer_limit_function,
+        )
+    };
+    if let Some(outer_limit) = outer_limit.as_fixnum() {
+        // Normally the function this calls is undo-outer-limit-truncate.
+        // It is responsible for making any desired changes in
+        // buffer-undo-list.
+        if size_so_far > outer_limit
+            && outer_limit_function.is_not_nil()
+            && call!(outer_limit_function, size_so_far.into()).is_not_nil()
+        {
+            unbind_to(count, Qnil);
+            return;
+        }
+    }
+
+    let mut last_boundary = if next.is_cons() { prev } else { None };
+
+    // Keep additional undo data, if it fits in the limits.
+    let (limit, strong_limit) = unsafe { (globals.undo_limit, globals.undo_strong_limit) };
+    while let Some(cons) = next.as_cons() {
+        let elt = cons.car();
+
+        // When we get to a boundary, decide whether to truncate either
+        // before or after it.  The lower threshold, undo-limit, tells us
+        // to truncate after it.  If its size pushes past the higher
+        // threshold undo-strong-limit, we truncate before it.
+        if elt.is_nil() {
+            if size_so_far > strong_limit {
+                break;
+            }
+            last_boundary = prev;
+            if size_so_far > limit {
+                break;
+            }
+        }
+
+        size_so_far += undo_entry_size(elt);
+        prev = Some(cons);
+        next = cons.cdr();
+    }
+
+    if next.is_nil() {
+        // We scanned the whole list, so it is short enough.
+    } else if let Some(boundary) = last_boundary {
+        // Truncate at the boundary where we decided to truncate.
+        boundary.set_cdr(Qnil);
+    } else {
+        // There's nothing we decided to keep, so clear it out.
+        buffer.set_undo_list(Qnil);
+    }
+
+    unbind_to(count, Qnil);
+}
+
+include!(concat!(env!("OUT_DIR"), "/undo_exports.rs"));
@@ -0,0 +1,236 @@
+//! Persistent undo: saving a buffer's undo list to a file and restoring
+//! it when the buffer visits its file again (see undo-persist.el).
+//!
+//! The undo list is written in its printed representation, tagged with
+//! a hash of the buffer text it applies to, so that it is only restored
+//! into a buffer holding that same text.  Entries that can't be read
+//! back are left out, and the file is replaced atomically so that a
+//! crash never leaves a truncated undo history behind.
+
+use std::{fs, io};
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::{LispBufferOrCurrent, LispBufferRef},
+    crypto::buffer_hash,
+    eval::unbind_to,
+    fileio::{bytes_to_path, write_atomically},
+    lisp::defsubr,
+    lisp::LispObject,
+    lists::{list, LispConsCircularChecks, LispConsEndChecks},
+    multibyte::LispStringRef,
+    obarray::intern,
+    remacs_sys::{code_convert_string_norecord, encode_file_name, Fexpand_file_name},
+    remacs_sys::{make_specified_string, report_file_errno, specbind},
+    remacs_sys::{Fprin1_to_string, Fread_from_string},
+    remacs_sys::{Qnil, Qt, Qutf_8_emacs},
+    string_alloc::make_unibyte_string,
+    threads::c_specpdl_index,
+};
+
+/// The version of the format of undo files, which is bumped whenever
+/// files written by older versions can no longer be restored.
+const UNDO_PERSIST_VERSION: i32 = 1;
+
+/// How an entry of an undo list is saved.
+enum SavedEntry {
+    /// Save this instead of the entry.
+    Keep(LispObject),
+    /// Leave the entry out: undoing it only restores state that doesn't
+    /// survive the buffer being killed, like the positions of markers.
+    Skip,
+    /// The entry can't be saved, nor can anything recorded before it.
+    Stop,
+}
+
+/// A copy of STRING without its text properties, whose values may not
+/// be readable.
+fn without_properties(string: LispStringRef) -> LispObject {
+    unsafe {
+        make_specified_string(
+            string.const_data_ptr() as *const libc::c_char,
+            string.len_chars(),
+            string.len_bytes(),
+            string.is_multibyte(),
+        )
+    }
+}
+
+fn saved_entry(entry: LispObject) -> SavedEntry {
+    let (car, cdr) = match entry.as_cons() {
+        Some(cons) => cons.into(),
+        // Boundaries and positions of point.
+        None if entry.is_nil() || entry.is_fixnum() => return SavedEntry::Keep(entry),
+        None => return SavedEntry::Stop,
+    };
+
+    if car.is_fixnum() && cdr.is_fixnum() {
+        // (BEG . END), an insertion.
+        SavedEntry::Keep(entry)
+    } else if let Some(text) = car.as_string() {
+        // (TEXT . POSITION), a deletion.
+        SavedEntry::Keep(LispObject::cons(without_properties(text), cdr))
+    } else if car.is_t() {
+        // (t . TIME-FLAG), the buffer was unmodified.
+        SavedEntry::Keep(entry)
+    } else if car.is_nil() {
+        // (nil PROPERTY VALUE BEG . END), a text property change.
+        let (property, value, bounds) = match cdr.as_cons().and_then(|c| {
+            let (property, rest) = c.into();
+            rest.as_cons().map(|r| (property, r.car(), r.cdr()))
+        }) {
+            Some(fields) => fields,
+            None => return SavedEntry::Stop,
+        };
+        if value.is_symbol() || value.is_number() {
+            SavedEntry::Keep(entry)
+        } else if let Some(value) = value.as_string() {
+            let rest = LispObject::cons(without_properties(value), bounds);
+            SavedEntry::Keep(LispObject::cons(Qnil, LispObject::cons(property, rest)))
+        } else {
+            SavedEntry::Skip
+        }
+    } else if car.is_marker() {
+        // (MARKER . ADJUSTMENT).
+        SavedEntry::Skip
+    } else {
+        // (apply ...) entries call arbitrary functions, and anything
+        // else is unknown.
+        SavedEntry::Stop
+    }
+}
+
+/// The part of UNDO_LIST that can be saved.  It ends at the boundary
+/// before the first entry that can't be saved.
+fn saved_undo_list(undo_list: LispObject) -> LispObject {
+    let mut saved = Vec::new();
+    let mut complete = 0;
+
+    for entry in undo_list.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe) {
+        match saved_entry(entry) {
+            SavedEntry::Keep(entry) => {
+                saved.push(entry);
+                if entry.is_nil() {
+                    complete = saved.len();
+                }
+            }
+            SavedEntry::Skip => (),
+            SavedEntry::Stop => {
+                saved.truncate(complete);
+                return list(&saved);
+            }
+        }
+    }
+    list(&saved)
+}
+
+/// The encoded absolute name of FILE.
+fn encoded_file_name(file: LispStringRef) -> Vec<u8> {
+    let absname = unsafe { Fexpand_file_name(file.into(), Qnil) };
+    unsafe { encode_file_name(absname) }
+        .as_string_or_error()
+        .as_slice()
+        .to_vec()
+}
+
+fn report_undo_file_error(action: &str, name: &[u8], error: &io::Error) -> ! {
+    let action = format!("{} undo file\0", action);
+    let file = make_unibyte_string(name);
+    unsafe {
+        report_file_errno(
+            action.as_ptr() as *const libc::c_char,
+            file,
+            error.raw_os_error().unwrap_or(libc::EIO),
+        )
+    }
+}
+
+/// Save the undo list of BUFFER to FILE.
+/// BUFFER defaults to the current buffer.  The list is saved together
+/// with a hash of the text of BUFFER, so that `undo-persist-load' only
+/// restores it into a buffer with the same text.  Entries that can't be
+/// read back, like the adjustments of markers, are left out, and so is
+/// the history before entries that call functions.  FILE is replaced
+/// atomically once it has been written.
+///
+/// Return t if the list was saved, nil if undo is disabled in BUFFER.
+#[lisp_fn(min = "1")]
+pub fn undo_persist_save(file: LispStringRef, buffer: LispBufferOrCurrent) -> bool {
+    let buffer: LispBufferRef = buffer.into();
+    if buffer.undo_list().eq(Qt) {
+        return false;
+    }
+
+    let contents = list!(
+        intern("undo-persist"),
+        UNDO_PERSIST_VERSION,
+        buffer_hash(Some(LispObject::from(buffer).into())),
+        saved_undo_list(buffer.undo_list())
+    );
+
+    let count = c_specpdl_index();
+    unsafe {
+        specbind(intern("print-length").into(), Qnil);
+        specbind(intern("print-level").into(), Qnil);
+        specbind(intern("print-circle").into(), Qnil);
+    }
+    let printed = unsafe { Fprin1_to_string(contents, Qnil) };
+    unbind_to(count, Qnil);
+
+    let encoded =
+        unsafe { code_convert_string_norecord(printed, Qutf_8_emacs, true) }.as_string_or_error();
+    let name = encoded_file_name(file);
+    if let Err(e) = write_atomically(&bytes_to_path(&name), encoded.as_slice()) {
+        report_undo_file_error("Writing", &name, &e);
+    }
+    true
+}
+
+/// Restore the undo list of BUFFER from FILE, written by `undo-persist-save'.
+/// BUFFER defaults to the current buffer.  Nothing is restored if undo
+/// is disabled in BUFFER, or unless FILE exists and was saved when
+/// BUFFER had the text it has now.
+///
+/// Return t if the undo list was restored, nil otherwise.
+#[lisp_fn(min = "1")]
+pub fn undo_persist_load(file: LispStringRef, buffer: LispBufferOrCurrent) -> bool {
+    let mut buffer: LispBufferRef = buffer.into();
+    if buffer.undo_list().eq(Qt) {
+        return false;
+    }
+
+    let name = encoded_file_name(file);
+    let bytes = match fs::read(bytes_to_path(&name)) {
+        Ok(bytes) => bytes,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return false,
+        Err(e) => report_undo_file_error("Reading", &name, &e),
+    };
+
+    let raw = make_unibyte_string(&bytes);
+    let text = unsafe { code_convert_string_norecord(raw, Qutf_8_emacs, false) };
+    let (contents, _) = unsafe { Fread_from_string(text, Qnil, Qnil) }
+        .as_cons_or_error()
+        .into();
+
+    let fields: Vec<LispObject> = contents
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe)
+        .collect();
+    let current_hash = buffer_hash(Some(LispObject::from(buffer).into())).as_string_or_error();
+    match fields.as_slice() {
+        &[tag, version, hash, undo_list]
+            if tag.eq(intern("undo-persist"))
+                && version.eq(UNDO_PERSIST_VERSION)
+                && hash
+                    .as_string()
+                    .map_or(false, |h| h.as_slice() == current_hash.as_slice())
+                && undo_list.is_list() =>
+        {
+            buffer.set_undo_list(undo_list);
+            true
+        }
+        _ => false,
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/undo_persist_exports.rs"));
@@ -0,0 +1,504 @@
+//! Unique buffer names for buffers visiting files of the same name, made
+//! by adding parts of the directory names.  uniquify.el holds the options
+//! and the hooks into buffer creation, renaming and killing; working out
+//! the names happens here.
+//!
+//! Buffers whose names uniquify manages are indexed by their base name,
+//! so a new buffer only has to be compared with the buffers that share
+//! its base name, rather than with every buffer.
+
+use libc::ptrdiff_t;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    buffers::{buffer_local_value_lisp, buffer_name, get_buffer, LispBufferRef},
+    data::{aref, aset, set},
+    eval::SpecBinding,
+    hashtable::{gethash, puthash, remhash, LispHashTableRef},
+    lisp::{defsubr, LispObject},
+    lists::{car, cdr, delq, memq},
+    lists::{LispConsCircularChecks, LispConsEndChecks},
+    obarray::intern,
+    remacs_sys::{record_unwind_current_buffer, set_buffer_internal_1},
+    remacs_sys::{EmacsInt, Frecord},
+    remacs_sys::{Fdirectory_file_name, Fexpand_file_name, Ffile_directory_p},
+    remacs_sys::{Ffile_name_as_directory, Ffile_name_directory, Ffile_name_nondirectory},
+    remacs_sys::{Qnil, Qt},
+    search::string_match,
+    sequences::{concat2, concat_lisp, nreverse},
+    strings::string_lessp,
+    symbols::symbol_value,
+};
+
+/// The buffers whose names uniquify may manage, by base name.  Each
+/// value is a list of buffers.  Buffers are added when their names are
+/// rationalized and dropped when they are killed; a buffer that stopped
+/// being managed some other way is dropped the next time its base name
+/// is looked up.
+declare_GC_protected_static!(uniquify_index, Qnil);
+
+fn index() -> LispHashTableRef {
+    if uniquify_index.get().is_nil() {
+        uniquify_index.set(LispHashTableRef::make_equal(64).into());
+    }
+    uniquify_index.get().into()
+}
+
+/// A `uniquify-item', as defined in uniquify.el: a buffer, the base name
+/// and directory it is named after, and the name proposed for it.
+#[derive(Clone, Copy)]
+struct Item(LispObject);
+
+impl Item {
+    fn new(
+        base: LispObject,
+        dirname: LispObject,
+        buffer: LispObject,
+        proposed: LispObject,
+    ) -> Self {
+        let mut slots = [
+            intern("uniquify-item").into(),
+            base,
+            dirname,
+            buffer,
+            proposed,
+        ];
+        Item(unsafe { Frecord(slots.len() as ptrdiff_t, slots.as_mut_ptr()) })
+    }
+
+    fn base(self) -> LispObject {
+        aref(self.0, 1)
+    }
+
+    fn dirname(self) -> LispObject {
+        aref(self.0, 2)
+    }
+
+    fn set_dirname(self, dirname: LispObject) {
+        aset(self.0, 2, dirname);
+    }
+
+    fn buffer(self) -> LispObject {
+        aref(self.0, 3)
+    }
+
+    fn proposed(self) -> LispObject {
+        aref(self.0, 4)
+    }
+
+    fn set_proposed(self, proposed: LispObject) {
+        aset(self.0, 4, proposed);
+    }
+}
+
+fn items(list: LispObject) -> impl Iterator<Item = Item> {
+    list.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+        .map(Item)
+}
+
+fn is_empty(string: LispObject) -> bool {
+    string.force_string().len_chars() == 0
+}
+
+fn is_live(buffer: LispObject) -> bool {
+    buffer.as_buffer().map_or(false, LispBufferRef::is_live)
+}
+
+/// Return the list of `uniquify-item's BUFFER's name is managed with.
+fn managed(buffer: LispObject) -> LispObject {
+    buffer_local_value_lisp(intern("uniquify-managed").into(), buffer)
+}
+
+/// Call F with BUFFER as the current buffer.
+fn with_buffer<T>(buffer: LispObject, f: impl FnOnce() -> T) -> T {
+    let binding = SpecBinding::mark();
+    unsafe {
+        record_unwind_current_buffer();
+        set_buffer_internal_1(buffer.as_buffer_or_error().as_mut());
+    }
+    binding.unbind(f())
+}
+
+fn set_managed(buffer: LispObject, items: LispObject) {
+    with_buffer(buffer, || set(intern("uniquify-managed"), items));
+}
+
+/// Add BUFFER to the index, under BASE.
+fn remember(base: LispObject, buffer: LispObject) {
+    let buffers = gethash(base, index(), Qnil);
+    if memq(buffer, buffers).is_nil() {
+        puthash(base, LispObject::cons(buffer, buffers), index());
+    }
+}
+
+/// Return the buffers whose names are managed with base name BASE,
+/// dropping from the index those that are not.
+fn buffers_named(base: LispObject) -> LispObject {
+    let mut buffers = gethash(base, index(), Qnil);
+    for buffer in buffers.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+        let items = if is_live(buffer) {
+            managed(buffer)
+        } else {
+            Qnil
+        };
+        if items.is_nil() || !Item(car(items)).base().equal(base) {
+            buffers = delq(buffer, buffers);
+        }
+    }
+    if buffers.is_nil() {
+        remhash(base, index());
+    } else {
+        puthash(base, buffers, index());
+    }
+    buffers
+}
+
+/// The state of one rationalization of buffer names.
+struct Rationalizer {
+    style: LispObject,
+    separator: LispObject,
+    trailing_separator: bool,
+    /// Whether some proposed name left out directory components, so
+    /// that looking deeper may resolve a conflict.
+    possibly_resolvable: bool,
+}
+
+impl Rationalizer {
+    fn new() -> Self {
+        Self {
+            style: symbol_value(intern("uniquify-buffer-name-style")),
+            separator: symbol_value(intern("uniquify-separator")),
+            trailing_separator: symbol_value(intern("uniquify-trailing-separator-p")).is_not_nil(),
+            possibly_resolvable: false,
+        }
+    }
+
+    fn separator(&self, default: &str) -> LispObject {
+        if self.separator.is_nil() {
+            LispObject::from(default)
+        } else {
+            self.separator
+        }
+    }
+
+    /// Return the name for a buffer visiting BASE in DIRNAME that
+    /// includes DEPTH components of DIRNAME.
+    fn proposed_name(
+        &mut self,
+        base: LispObject,
+        dirname: LispObject,
+        depth: EmacsInt,
+    ) -> LispObject {
+        let forward = self.style.eq(intern("forward"));
+        let reverse = self.style.eq(intern("reverse"));
+        let mut base = base;
+
+        // Distinguish directories by adding extra separator.
+        if self.trailing_separator
+            && !is_empty(base)
+            && unsafe { Ffile_directory_p(Fexpand_file_name(base, dirname)) }.is_not_nil()
+        {
+            if forward {
+                base = unsafe { Ffile_name_as_directory(base) };
+            } else if reverse {
+                base = concat2(self.separator("\\"), base);
+            }
+        }
+
+        // The components to add, outermost first.
+        let mut extra = Qnil;
+        let mut dirname = dirname;
+        let mut n = depth;
+        while n > 0 && dirname.is_not_nil() {
+            let file = unsafe { Ffile_name_nondirectory(dirname) };
+            dirname = unsafe { Ffile_name_directory(dirname) };
+            if dirname.is_not_nil() {
+                dirname = unsafe { Fdirectory_file_name(dirname) };
+            }
+            n -= 1;
+            let component = if is_empty(file) {
+                let remote = call!(intern("file-remote-p").into(), dirname);
+                dirname = Qnil;
+                if remote.is_nil() {
+                    LispObject::from("")
+                } else {
+                    remote
+                }
+            } else {
+                file
+            };
+            extra = LispObject::cons(component, extra);
+        }
+        if n == 0 {
+            if dirname.is_not_nil()
+                && extra.is_not_nil()
+                && dirname.equal(unsafe { Ffile_name_directory(dirname) })
+            {
+                // We're just before the root.  Let's add the leading /
+                // already.  With "/a/b"+"/c/d/b" this leads to "/a/b" and
+                // "d/b" but with "/a/b"+"/c/a/b" this leads to "/a/b" and
+                // "a/b".
+                extra = LispObject::cons(LispObject::from(""), extra);
+            }
+            self.possibly_resolvable = true;
+        }
+
+        let join = |components: LispObject, separator: LispObject| {
+            let mut pieces = Vec::new();
+            for component in
+                components.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+            {
+                if !pieces.is_empty() {
+                    pieces.push(separator);
+                }
+                pieces.push(component);
+            }
+            concat_lisp(&mut pieces)
+        };
+        let slash = LispObject::from("/");
+
+        if extra.is_nil() {
+            base
+        } else if is_empty(base) {
+            // Happens for dired buffers on the root directory.
+            join(extra, slash)
+        } else if reverse {
+            join(
+                LispObject::cons(base, nreverse(extra)),
+                self.separator("\\"),
+            )
+        } else if forward {
+            concat2(join(extra, slash), concat2(slash, base))
+        } else if self.style.eq(intern("post-forward")) {
+            concat2(base, concat2(self.separator("|"), join(extra, slash)))
+        } else if self.style.eq(intern("post-forward-angle-brackets")) {
+            concat2(
+                base,
+                concat2(
+                    LispObject::from("<"),
+                    concat2(join(extra, slash), LispObject::from(">")),
+                ),
+            )
+        } else {
+            error!("Bad value for uniquify-buffer-name-style: {:?}", self.style);
+        }
+    }
+
+    /// Give the items of FIX-LIST, whose proposed names are computed
+    /// from DEPTH directory components, names that don't conflict.
+    fn rationalize_list(&mut self, fix_list: LispObject, depth: EmacsInt) {
+        let mut sorted: Vec<Item> = items(fix_list).collect();
+        sorted.sort_by(|a, b| {
+            if string_lessp(b.proposed(), a.proposed()) {
+                std::cmp::Ordering::Less
+            } else if string_lessp(a.proposed(), b.proposed()) {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        });
+
+        // Pass on the items with the same proposed name together.
+        let mut conflicting = Qnil;
+        let mut old_proposed = LispObject::from("");
+        for item in sorted {
+            let proposed = item.proposed();
+            if !proposed.equal(old_proposed) {
+                self.rationalize_conflicting(conflicting, old_proposed, depth);
+                conflicting = Qnil;
+            }
+            conflicting = LispObject::cons(item.0, conflicting);
+            old_proposed = proposed;
+        }
+        self.rationalize_conflicting(conflicting, old_proposed, depth);
+    }
+
+    /// Deal with CONFLICTING, a list of items that were all proposed
+    /// OLD-NAME.  If there is only one, and no other buffer has that
+    /// name, that's its name.  Otherwise look one directory deeper.
+    fn rationalize_conflicting(
+        &mut self,
+        conflicting: LispObject,
+        old_name: LispObject,
+        depth: EmacsInt,
+    ) {
+        if conflicting.is_nil() {
+            return;
+        }
+        let first = Item(car(conflicting));
+        let taken = get_buffer(old_name.into())
+            .map_or(false, |buffer| !LispObject::from(buffer).eq(first.buffer()));
+        if cdr(conflicting).is_not_nil() || taken {
+            if self.possibly_resolvable {
+                self.possibly_resolvable = false;
+                let depth = depth + 1;
+                for item in items(conflicting) {
+                    let proposed = self.proposed_name(item.base(), item.dirname(), depth);
+                    item.set_proposed(proposed);
+                }
+                self.rationalize_list(conflicting, depth);
+            }
+        } else if !is_empty(old_name) {
+            rename(first, old_name);
+        }
+    }
+}
+
+/// Rename the buffer of ITEM to NEWNAME, unless that's its name already.
+fn rename(item: Item, newname: LispObject) {
+    let buffer = item.buffer();
+    if newname.equal(buffer_name(buffer.into())) {
+        return;
+    }
+    with_buffer(buffer, || {
+        // Avoid hooks on rename-buffer.
+        let _binding = SpecBinding::push(intern("uniquify-buffer-name-style").into(), Qnil);
+        // Pass the `unique' arg, so the advice doesn't mark it as unmanaged.
+        call!(intern("rename-buffer").into(), newname, Qt);
+    });
+}
+
+/// Give the buffers of FIX-LIST, a list of `uniquify-item's whose base
+/// names are the same, names that tell them apart.
+///
+/// Each buffer is renamed to its base name followed or preceded by as
+/// few components of its directory name as needed, but at least
+/// `uniquify-min-dir-content'.  The buffers are then managed together,
+/// so that their names are rationalized again when one of them is
+/// killed or renamed.
+#[lisp_fn(name = "uniquify--rationalize")]
+pub fn uniquify_rationalize(fix_list: LispObject) {
+    let mut rationalizer = Rationalizer::new();
+    let depth = symbol_value(intern("uniquify-min-dir-content")).as_fixnum_or_error();
+    let mut fix_list = fix_list;
+
+    for item in items(fix_list) {
+        let proposed = rationalizer.proposed_name(item.base(), item.dirname(), depth);
+        item.set_proposed(proposed);
+        set_managed(item.buffer(), fix_list);
+        remember(item.base(), item.buffer());
+    }
+
+    // Strip any shared last directory names of the dirname.
+    if cdr(fix_list).is_not_nil()
+        && symbol_value(intern("uniquify-strip-common-suffix")).is_not_nil()
+    {
+        loop {
+            let dirname = Item(car(fix_list)).dirname();
+            if dirname.is_nil() {
+                break;
+            }
+            let base = unsafe { Ffile_name_nondirectory(dirname) };
+            if is_empty(base)
+                || !items(fix_list).all(|item| {
+                    item.dirname().is_not_nil()
+                        && base.equal(unsafe { Ffile_name_nondirectory(item.dirname()) })
+                })
+            {
+                break;
+            }
+            // It's all the same => strip.  New items are made because
+            // the buffers keep the old ones, with the true dirname, for
+            // later rerationalizing.
+            let mut stripped = Qnil;
+            for item in items(fix_list) {
+                let directory = unsafe { Ffile_name_directory(item.dirname()) };
+                let dirname = if directory.is_nil() {
+                    Qnil
+                } else {
+                    unsafe { Fdirectory_file_name(directory) }
+                };
+                let new = Item::new(item.base(), dirname, item.buffer(), item.proposed());
+                stripped = LispObject::cons(new.0, stripped);
+            }
+            fix_list = stripped;
+        }
+    }
+
+    rationalizer.rationalize_list(fix_list, depth);
+}
+
+/// Make file buffer names unique by adding segments from file name.
+/// BASE and DIRNAME are the base name and directory of the file visited
+/// by NEWBUF, the new buffer that causes this rationalization.  It is
+/// rationalized together with the buffers managed by uniquify that have
+/// the same base name.  See `uniquify-rationalize-file-buffer-names'.
+#[lisp_fn(name = "uniquify--rationalize-file-buffer-names")]
+pub fn uniquify_rationalize_file_buffer_names(
+    base: LispObject,
+    dirname: LispObject,
+    newbuf: LispObject,
+) {
+    // Make sure we don't get confused by outdated uniquify-managed info
+    // in this buffer.
+    set_managed(newbuf, Qnil);
+    if dirname.is_nil() {
+        return;
+    }
+
+    let dirname = unsafe { Fexpand_file_name(Fdirectory_file_name(dirname), Qnil) };
+    let first = Item::new(base, dirname, newbuf, Qnil);
+    // The items of the other buffers, last first.
+    let mut others = Qnil;
+    let ignore_re = symbol_value(intern("uniquify-ignore-buffers-re"));
+    for buffer in buffers_named(base).iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+    {
+        if ignore_re.is_not_nil()
+            && string_match(ignore_re, buffer_name(buffer.into()), Qnil).is_not_nil()
+        {
+            continue;
+        }
+        let mut items = managed(buffer);
+        // Don't re-add stuff we already have.
+        if memq(car(items), others).is_not_nil() {
+            continue;
+        }
+        if cdr(items).is_nil() {
+            // If there was no conflict, the buffer-name is equal to the
+            // base-name and we may have missed a rename-buffer because of
+            // code like in set-visited-file-name:
+            // (or (string= new-name (buffer-name)) (rename-buffer new-name t))
+            // So we need to refresh the dirname of the uniquify-item.
+            let item = Item(car(items));
+            item.set_dirname(call!(
+                intern("uniquify-buffer-file-name").into(),
+                item.buffer()
+            ));
+            // This shouldn't happen, but maybe there's no dirname any more.
+            if item.dirname().is_nil() {
+                set_managed(item.buffer(), Qnil);
+                items = Qnil;
+            }
+        }
+        // In case we missed some calls to kill-buffer, there may be dead
+        // buffers in uniquify-managed, so filter them out.
+        for item in items(items) {
+            if is_live(item.buffer()) {
+                others = LispObject::cons(item.0, others);
+            }
+        }
+    }
+
+    uniquify_rationalize(LispObject::cons(first.0, nreverse(others)));
+}
+
+/// Forget that uniquify manages the name of BUFFER, which is about to
+/// be killed.
+#[lisp_fn(name = "uniquify--forget-buffer")]
+pub fn uniquify_forget_buffer(buffer: LispBufferRef) {
+    let buffer = LispObject::from(buffer);
+    let items = managed(buffer);
+    if items.is_nil() {
+        return;
+    }
+    let base = Item(car(items)).base();
+    let buffers = delq(buffer, gethash(base, index(), Qnil));
+    if buffers.is_nil() {
+        remhash(base, index());
+    } else {
+        puthash(base, buffers, index());
+    }
+}
+
+include!(concat!(env!("OUT_DIR"), "/uniquify_exports.rs"));
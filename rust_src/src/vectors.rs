@@ -1,6 +1,5 @@
 //! Functions operating on vector(like)s, and general sequences.
 
-use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 use std::mem;
@@ -14,13 +13,13 @@ use crate::{
     buffers::LispBufferRef,
     chartable::{LispCharTableRef, LispSubCharTableAsciiRef, LispSubCharTableRef},
     data::aref,
+    fns::internal_equal,
     frames::LispFrameRef,
     lisp::defsubr,
     lisp::{ExternalPtr, LispObject, LispSubrRef},
-    lists::{inorder, nth, sort_list},
+    lists::nth,
     multibyte::MAX_CHAR,
     process::LispProcessRef,
-    remacs_sys::internal_equal,
     remacs_sys::{
         equal_kind, pvec_type, EmacsInt, Lisp_Bool_Vector, Lisp_Char_Table, Lisp_Type, Lisp_Vector,
         Lisp_Vectorlike, Lisp_Vectorlike_With_Slots, More_Lisp_Bits, BITS_PER_BITS_WORD,
@@ -199,6 +198,16 @@ impl LispVectorlikeRef {
         }
     }
 
+    /// Turn a plain vector into a pseudovector of type TP, like
+    /// XSETPVECTYPE.
+    pub fn set_pseudovector_type(&mut self, tp: pvec_type) {
+        unsafe {
+            self.header.size |= (PSEUDOVECTOR_FLAG
+                | ((tp as usize) << More_Lisp_Bits::PSEUDOVECTOR_AREA_BITS))
+                as isize
+        };
+    }
+
     pub fn pseudovector_size(self) -> EmacsInt {
         (unsafe { self.header.size } & (More_Lisp_Bits::PSEUDOVECTOR_SIZE_MASK as isize))
             as EmacsInt
@@ -380,7 +389,7 @@ macro_rules! impl_vectorlike_ref {
                 for i in 0..self.len() {
                     let v1 = self.get(i as usize);
                     let v2 = other.get(i as usize);
-                    if !unsafe { internal_equal(v1, v2, kind, depth + 1, ht) } {
+                    if !internal_equal(v1, v2, kind, depth + 1, ht) {
                         return false;
                     }
                 }
@@ -605,37 +614,6 @@ pub fn elt(sequence: LispObject, n: EmacsInt) -> LispObject {
     }
 }
 
-/// Sort SEQ, stably, comparing elements using PREDICATE.
-/// Returns the sorted sequence.  SEQ should be a list or vector.  SEQ is
-/// modified by side effects.  PREDICATE is called with two elements of
-/// SEQ, and should return non-nil if the first element should sort before
-/// the second.
-#[lisp_fn]
-pub fn sort(seq: LispObject, predicate: LispObject) -> LispObject {
-    if seq.is_cons() {
-        sort_list(seq, predicate)
-    } else if let Some(mut vec) = seq.as_vectorlike().and_then(|v| v.as_vector()) {
-        vec.as_mut_slice().sort_by(|&a, &b| {
-            // XXX: since the `sort' predicate is a two-outcome comparison
-            // Less/!Less, and slice::sort_by() uses Greater/!Greater
-            // (which is not guaranteed anyway), this requires two calls
-            // instead of one in some cases.
-            if !inorder(predicate, a, b) {
-                Ordering::Greater
-            } else if inorder(predicate, b, a) {
-                Ordering::Equal
-            } else {
-                Ordering::Less
-            }
-        });
-        seq
-    } else if seq.is_nil() {
-        seq
-    } else {
-        wrong_type!(Qsequencep, seq)
-    }
-}
-
 /// Return t if OBJECT is a vector.
 #[lisp_fn]
 pub fn vectorp(object: LispObject) -> bool {
@@ -0,0 +1,189 @@
+//! Rings of window configurations, one for each frame, as Winner mode
+//! keeps them.
+//!
+//! A ring is a list of configurations, newest first.  Pushing a
+//! configuration conses it onto the list and popping one drops it, so
+//! the lists handed out by `window-configuration-ring' share their tails
+//! with the ring instead of being copied.  The frames whose window
+//! configuration changed are noted as `window-configuration-change-hook'
+//! runs, and the rings of dead frames are dropped, so that neither holds
+//! on to frames that are gone.
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    frames::LispFrameOrSelected,
+    lisp::{defsubr, LispObject},
+    lists::{assq, car, cdr, delq, memq, nthcdr, LispCons},
+    lists::{LispConsCircularChecks, LispConsEndChecks},
+    remacs_sys::Qnil,
+    remacs_sys::{minibuf_level, EmacsInt},
+};
+
+/// Configurations kept by default.  This is the default of
+/// `winner-ring-size'.
+const DEFAULT_RING_SIZE: EmacsInt = 200;
+
+/// An alist of (FRAME COUNT . CONFIGURATIONS), where CONFIGURATIONS
+/// holds the COUNT configurations in FRAME's ring, newest first.
+declare_GC_protected_static!(window_rings, Qnil);
+
+/// The frames whose window configuration changed since the last call
+/// of `window-configuration-ring-changed-frames' that cleared them.
+declare_GC_protected_static!(changed_frames, Qnil);
+
+fn is_live_frame(frame: LispObject) -> bool {
+    frame.as_frame().map_or(false, |f| f.is_live())
+}
+
+/// Forget about the frames that were deleted.
+fn cull_dead_frames() {
+    let rings = window_rings.get();
+    for entry in rings.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+        if !is_live_frame(car(entry)) {
+            window_rings.set(delq(entry, window_rings.get()));
+        }
+    }
+    let frames = changed_frames.get();
+    for frame in frames.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+        if !is_live_frame(frame) {
+            changed_frames.set(delq(frame, changed_frames.get()));
+        }
+    }
+}
+
+/// Return the (COUNT . CONFIGURATIONS) cell of the ring of FRAME,
+/// making an empty ring if FRAME has none.
+fn ring_of(frame: LispObject) -> LispObject {
+    let entry = assq(frame, window_rings.get());
+    if entry.is_not_nil() {
+        return cdr(entry);
+    }
+    let ring = LispObject::cons(LispObject::from(0), Qnil);
+    window_rings.set(LispObject::cons(
+        LispObject::cons(frame, ring),
+        window_rings.get(),
+    ));
+    ring
+}
+
+fn set_ring(ring: LispObject, count: EmacsInt, configurations: LispObject) {
+    let cell = ring.force_cons();
+    cell.set_car(LispObject::from(count));
+    cell.set_cdr(configurations);
+}
+
+/// Note that the window configuration of FRAME changed.  This is called
+/// as `window-configuration-change-hook' is run for FRAME; changes made
+/// while the minibuffer is active are not noted.
+#[no_mangle]
+pub extern "C" fn window_configuration_ring_note_change(frame: LispObject) {
+    cull_dead_frames();
+    if unsafe { minibuf_level } == 0 && memq(frame, changed_frames.get()).is_nil() {
+        changed_frames.set(LispObject::cons(frame, changed_frames.get()));
+    }
+}
+
+/// Return the frames whose window configuration changed.
+/// These are the frames `window-configuration-change-hook' was run for
+/// while no minibuffer was active, since the last time this function
+/// was called with CLEAR non-nil.  With CLEAR non-nil, also start
+/// noting changes afresh.
+#[lisp_fn(min = "0")]
+pub fn window_configuration_ring_changed_frames(clear: bool) -> LispObject {
+    cull_dead_frames();
+    let frames = changed_frames.get();
+    if clear {
+        changed_frames.set(Qnil);
+    }
+    frames
+}
+
+/// Return the window configurations in the ring of FRAME, newest first.
+/// FRAME defaults to the selected frame.
+///
+/// The list shares its structure with the ring, so it must not be
+/// modified.  It is not affected by configurations pushed or popped
+/// later, except that its oldest elements are dropped when the ring
+/// overflows.
+#[lisp_fn(min = "0")]
+pub fn window_configuration_ring(frame: LispFrameOrSelected) -> LispObject {
+    let entry = assq(frame.into(), window_rings.get());
+    cdr(cdr(entry))
+}
+
+/// Push CONFIGURATION onto the ring of window configurations of FRAME.
+/// FRAME defaults to the selected frame.  The ring keeps the SIZE newest
+/// configurations, 200 by default; older ones are dropped.
+///
+/// If REPLACE is non-nil, CONFIGURATION takes the place of the newest
+/// configuration in the ring instead, if there is one.
+#[lisp_fn(min = "1")]
+pub fn window_configuration_ring_push(
+    configuration: LispObject,
+    frame: LispFrameOrSelected,
+    size: Option<EmacsInt>,
+    replace: bool,
+) -> LispObject {
+    cull_dead_frames();
+    let size = size.unwrap_or(DEFAULT_RING_SIZE).max(1);
+    let ring = ring_of(frame.into());
+    let (count, mut configurations) = ring.into();
+    let mut count = count.as_fixnum_or_error();
+    if replace && configurations.is_not_nil() {
+        configurations = cdr(configurations);
+        count -= 1;
+    }
+    configurations = LispObject::cons(configuration, configurations);
+    count += 1;
+    if count > size {
+        // Lists handed out earlier share this tail; they just lose their
+        // oldest elements along with the ring.
+        LispCons::from(nthcdr(size - 1, configurations)).set_cdr(Qnil);
+        count = size;
+    }
+    set_ring(ring, count, configurations);
+    configuration
+}
+
+/// Remove the Nth newest window configuration from the ring of FRAME.
+/// FRAME defaults to the selected frame, and N to 0, the newest
+/// configuration.  Return the configuration removed, or nil if the ring
+/// has fewer than N + 1 elements.
+///
+/// Lists returned by `window-configuration-ring' earlier still hold the
+/// configuration.
+#[lisp_fn(min = "0")]
+pub fn window_configuration_ring_pop(
+    frame: LispFrameOrSelected,
+    n: Option<EmacsInt>,
+) -> LispObject {
+    let n = n.unwrap_or(0);
+    let entry = assq(frame.into(), window_rings.get());
+    if entry.is_nil() || n < 0 {
+        return Qnil;
+    }
+    let ring = cdr(entry);
+    let (count, configurations) = ring.into();
+    let count = count.as_fixnum_or_error();
+    if n >= count {
+        return Qnil;
+    }
+    // Copy the configurations before the one removed, and share the rest.
+    let tail = nthcdr(n, configurations);
+    let mut head = Qnil;
+    for configuration in configurations
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+        .take(n as usize)
+    {
+        head = LispObject::cons(configuration, head);
+    }
+    let mut rest = cdr(tail);
+    for configuration in head.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+        rest = LispObject::cons(configuration, rest);
+    }
+    set_ring(ring, count - 1, rest);
+    car(tail)
+}
+
+include!(concat!(env!("OUT_DIR"), "/window_ring_exports.rs"));
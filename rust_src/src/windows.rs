@@ -7,15 +7,26 @@ use libc::c_int;
 use remacs_macros::lisp_fn;
 
 use crate::{
+    buffers::{get_buffer, LispBufferRef},
     editfns::{goto_char, point},
+    eval::{unbind_to, SpecBinding, FUNCTIONP},
+    frames::{frame_first_window, frame_root_window, set_frame_selected_window},
     frames::{LispFrameOrSelected, LispFrameRef},
     interactive::prefix_numeric_value,
     lisp::defsubr,
     lisp::{ExternalPtr, LispObject},
-    lists::{assq, setcdr},
+    lists::{assq, car, cdr, list, plist_get, setcdr},
+    lists::{LispConsCircularChecks, LispConsEndChecks},
     marker::{marker_position_lisp, set_marker_restricted},
+    obarray::intern,
     remacs_sys::globals,
-    remacs_sys::Fcopy_alist,
+    remacs_sys::Lisp_Window,
+    remacs_sys::{buffer_local_value, Fnext_window, Frun_window_configuration_change_hook},
+    remacs_sys::{
+        clear_unwind_protect, record_unwind_protect, restore_window_configuration,
+        Fcurrent_window_configuration, Fdelete_other_windows_internal, Fget_buffer_create,
+        Fset_window_buffer,
+    },
     remacs_sys::{
         estimate_mode_line_height, minibuf_level,
         minibuf_selected_window as current_minibuf_window, scroll_command, select_window,
@@ -23,12 +34,15 @@ use crate::{
         update_mode_lines, window_body_width, window_list_1, window_menu_bar_p, window_tool_bar_p,
         wset_redisplay,
     },
-    remacs_sys::{face_id, glyph_matrix, pvec_type, EmacsInt, Lisp_Type, Lisp_Window},
+    remacs_sys::{face_id, glyph_matrix, pvec_type, EmacsInt, Fwindow_resize_apply, Lisp_Type},
     remacs_sys::{
-        Qceiling, Qfloor, Qheader_line_format, Qmode_line_format, Qnil, Qnone, Qwindow_live_p,
-        Qwindow_valid_p, Qwindowp,
+        Qceiling, Qerror, Qfloor, Qheader_line_format, Qmode_line_format, Qnil, Qnone, Qt,
+        Qwindow_live_p, Qwindow_valid_p, Qwindowp,
     },
-    threads::ThreadState,
+    search::string_match,
+    sequences::{copy_alist, nreverse},
+    symbols::symbol_value,
+    threads::{c_specpdl_index, ThreadState},
 };
 
 pub type LispWindowRef = ExternalPtr<Lisp_Window>;
@@ -101,14 +115,9 @@ impl LispWindowRef {
         if !(round == qfloor || round == qceiling) {
             self.total_cols
         } else {
-            let frame = self.frame.as_frame_or_error();
-            let unit = frame.column_width;
-
-            if round == qceiling {
-                (self.pixel_width + unit - 1) / unit
-            } else {
-                self.pixel_width / unit
-            }
+            self.frame
+                .as_frame_or_error()
+                .pixels_to_columns(self.pixel_width, round == qceiling)
         }
     }
 
@@ -119,14 +128,9 @@ impl LispWindowRef {
         if !(round == qfloor || round == qceiling) {
             self.total_lines
         } else {
-            let frame = self.frame.as_frame_or_error();
-            let unit = frame.line_height;
-
-            if round == qceiling {
-                (self.pixel_height + unit - 1) / unit
-            } else {
-                self.pixel_height / unit
-            }
+            self.frame
+                .as_frame_or_error()
+                .pixels_to_lines(self.pixel_height, round == qceiling)
         }
     }
 
@@ -900,6 +904,80 @@ pub fn set_window_next_buffers(
     next_buffers
 }
 
+/// Return the length `window-buffer-lists-max' caps the buffer lists of
+/// windows at, or None if they are not capped.
+fn buffer_lists_max() -> Option<usize> {
+    unsafe { globals.Vwindow_buffer_lists_max }
+        .as_fixnum()
+        .map(|max| max.max(0) as usize)
+}
+
+fn is_live_buffer(object: LispObject) -> bool {
+    object.as_buffer().map_or(false, |b| b.is_live())
+}
+
+/// Return the elements of LIST that satisfy KEEP, at most MAX of them.
+/// LIST itself is returned when nothing is dropped.
+fn pruned(list: LispObject, max: Option<usize>, keep: impl Fn(LispObject) -> bool) -> LispObject {
+    let mut kept = Qnil;
+    let mut count = 0;
+    let mut dropped = false;
+    for elt in list.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::safe) {
+        if max.map_or(false, |max| count >= max) {
+            dropped = true;
+            break;
+        }
+        if keep(elt) {
+            kept = LispObject::cons(elt, kept);
+            count += 1;
+        } else {
+            dropped = true;
+        }
+    }
+    if dropped {
+        nreverse(kept)
+    } else {
+        list
+    }
+}
+
+fn prune_buffer_lists(mut win: LispWindowRef, max: Option<usize>) {
+    win.prev_buffers = pruned(win.prev_buffers, max, |entry| {
+        entry.is_cons() && is_live_buffer(car(entry))
+    });
+    win.next_buffers = pruned(win.next_buffers, max, is_live_buffer);
+}
+
+/// Prune the buffer lists of all live windows.  This is called by
+/// `kill-buffer' once the buffer is dead, so that windows don't keep
+/// killed buffers alive.
+#[no_mangle]
+pub extern "C" fn prune_window_buffer_lists() {
+    let max = buffer_lists_max();
+    let windows = unsafe { window_list_1(Qnil, Qt, Qt) };
+    for window in windows.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+        prune_buffer_lists(window.into(), max);
+    }
+}
+
+/// Remove killed buffers from the previous and next buffers of WINDOW.
+/// WINDOW must be a live window; if it is nil, do this for all live
+/// windows.  Also drop the oldest elements of these lists beyond the
+/// first `window-buffer-lists-max'.
+///
+/// `kill-buffer' does this for all live windows, so killed buffers are
+/// only found in the buffer lists of windows restored from a window
+/// configuration.
+#[lisp_fn(min = "0")]
+pub fn window_prune_buffer_lists(window: LispObject) {
+    if window.is_nil() {
+        prune_window_buffer_lists();
+    } else {
+        let window: LispWindowLiveOrSelected = window.into();
+        prune_buffer_lists(window.into(), buffer_lists_max());
+    }
+}
+
 /// Make point value in WINDOW be at position POS in WINDOW's buffer.
 /// WINDOW must be a live window and defaults to the selected one.
 /// Return POS.
@@ -1176,7 +1254,7 @@ pub fn window_top_line(window: LispWindowValidOrSelected) -> EmacsInt {
 #[lisp_fn(min = "0")]
 pub fn window_parameters(window: LispWindowValidOrSelected) -> LispObject {
     let win: LispWindowRef = window.into();
-    unsafe { Fcopy_alist(win.window_parameters) }
+    copy_alist(win.window_parameters)
 }
 
 /// Return WINDOW's redisplay end trigger value.
@@ -1205,4 +1283,927 @@ pub fn set_window_redisplay_end_trigger(
     value
 }
 
+/// Return the windows on ALL-FRAMES that `get-lru-window' and its
+/// siblings choose from: no minibuffer windows, no dedicated windows
+/// unless DEDICATED, and not the selected window if NOT-SELECTED.
+fn candidate_windows(
+    all_frames: LispObject,
+    dedicated: bool,
+    not_selected: bool,
+) -> Vec<LispWindowRef> {
+    let selected = selected_window();
+    let windows = unsafe { window_list_1(Qnil, intern("nomini").into(), all_frames) };
+    windows
+        .iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+        .filter(|&window| !(not_selected && window.eq(selected)))
+        .map(LispObject::as_window_or_error)
+        .filter(|window| dedicated || window.dedicated.is_nil())
+        .collect()
+}
+
+/// Return whether WINDOW is as wide as the root window of its frame.
+fn is_full_width(window: LispWindowRef) -> bool {
+    let root = window.frame.as_frame_or_error().root_window;
+    window.pixel_width == root.as_window_or_error().pixel_width
+}
+
+/// Return the least recently used window on frames specified by ALL-FRAMES.
+/// Return a full-width window if possible.  A minibuffer window is
+/// never a candidate.  A dedicated window is never a candidate
+/// unless DEDICATED is non-nil, so if all windows are dedicated, the
+/// value is nil.  Avoid returning the selected window if possible.
+/// Optional argument NOT-SELECTED non-nil means never return the
+/// selected window.
+///
+/// The following non-nil values of the optional argument ALL-FRAMES
+/// have special meanings:
+///
+/// - t means consider all windows on all existing frames.
+///
+/// - `visible' means consider all windows on all visible frames on
+///   the current terminal.
+///
+/// - 0 (the number zero) means consider all windows on all visible
+///   and iconified frames on the current terminal.
+///
+/// - A frame means consider all windows on that frame only.
+///
+/// Any other value of ALL-FRAMES means consider all windows on the
+/// selected frame and no others.
+#[lisp_fn(min = "0")]
+pub fn get_lru_window(
+    all_frames: LispObject,
+    dedicated: bool,
+    not_selected: bool,
+) -> Option<LispWindowRef> {
+    let selected = selected_window();
+    let mut best: Option<LispWindowRef> = None;
+    let mut second_best: Option<LispWindowRef> = None;
+    for window in candidate_windows(all_frames, dedicated, not_selected) {
+        let slot = if LispObject::from(window).eq(selected) || !is_full_width(window) {
+            &mut second_best
+        } else {
+            &mut best
+        };
+        if slot.map_or(true, |other| window.use_time < other.use_time) {
+            *slot = Some(window);
+        }
+    }
+    best.or(second_best)
+}
+
+/// Return the most recently used window on frames specified by ALL-FRAMES.
+/// A minibuffer window is never a candidate.  A dedicated window is
+/// never a candidate unless DEDICATED is non-nil, so if all windows
+/// are dedicated, the value is nil.  Optional argument NOT-SELECTED
+/// non-nil means never return the selected window.
+///
+/// The following non-nil values of the optional argument ALL-FRAMES
+/// have special meanings:
+///
+/// - t means consider all windows on all existing frames.
+///
+/// - `visible' means consider all windows on all visible frames on
+///   the current terminal.
+///
+/// - 0 (the number zero) means consider all windows on all visible
+///   and iconified frames on the current terminal.
+///
+/// - A frame means consider all windows on that frame only.
+///
+/// Any other value of ALL-FRAMES means consider all windows on the
+/// selected frame and no others.
+#[lisp_fn(min = "0")]
+pub fn get_mru_window(
+    all_frames: LispObject,
+    dedicated: bool,
+    not_selected: bool,
+) -> Option<LispWindowRef> {
+    candidate_windows(all_frames, dedicated, not_selected)
+        .into_iter()
+        .fold(None, |best: Option<LispWindowRef>, window| match best {
+            Some(other) if window.use_time <= other.use_time => best,
+            _ => Some(window),
+        })
+}
+
+/// Return the largest window on frames specified by ALL-FRAMES.
+/// A minibuffer window is never a candidate.  A dedicated window is
+/// never a candidate unless DEDICATED is non-nil, so if all windows
+/// are dedicated, the value is nil.  Optional argument NOT-SELECTED
+/// non-nil means never return the selected window.
+///
+/// The following non-nil values of the optional argument ALL-FRAMES
+/// have special meanings:
+///
+/// - t means consider all windows on all existing frames.
+///
+/// - `visible' means consider all windows on all visible frames on
+///   the current terminal.
+///
+/// - 0 (the number zero) means consider all windows on all visible
+///   and iconified frames on the current terminal.
+///
+/// - A frame means consider all windows on that frame only.
+///
+/// Any other value of ALL-FRAMES means consider all windows on the
+/// selected frame and no others.
+#[lisp_fn(min = "0")]
+pub fn get_largest_window(
+    all_frames: LispObject,
+    dedicated: bool,
+    not_selected: bool,
+) -> Option<LispWindowRef> {
+    let mut best_size = 0;
+    let mut best = None;
+    for window in candidate_windows(all_frames, dedicated, not_selected) {
+        let size = i64::from(window.pixel_height) * i64::from(window.pixel_width);
+        if size > best_size {
+            best_size = size;
+            best = Some(window);
+        }
+    }
+    best
+}
+
+/// Retrieve ALIST entry corresponding to BUFFER-NAME.
+/// ACTION is the action argument passed to `display-buffer'.
+#[lisp_fn]
+pub fn display_buffer_assq_regexp(
+    buffer_name: LispObject,
+    alist: LispObject,
+    action: LispObject,
+) -> LispObject {
+    for entry in alist.iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on) {
+        let key = car(entry);
+        let matches = if key.is_string() {
+            let binding = SpecBinding::push(intern("inhibit-changing-match-data").into(), Qt);
+            binding.unbind(string_match(key, buffer_name, Qnil).is_not_nil())
+        } else {
+            FUNCTIONP(key) && call!(key, buffer_name, action).is_not_nil()
+        };
+        if matches {
+            return cdr(entry);
+        }
+    }
+    Qnil
+}
+
+/// Return the actions `display-buffer' merges to display BUFFER, most
+/// important first, as a list of (SOURCE . ACTION).  SOURCE is the
+/// variable ACTION comes from, or `special' for the action made up for
+/// special display, `action' for the ACTION argument, and `extra' for
+/// the action made up for the INHIBIT-SAME-WINDOW and FRAME arguments.
+fn display_buffer_sources(
+    buffer: LispBufferRef,
+    action: LispObject,
+    frame: LispObject,
+    inhibit_same_window: bool,
+) -> LispObject {
+    let user_action = display_buffer_assq_regexp(
+        buffer.name(),
+        symbol_value(intern("display-buffer-alist")),
+        action,
+    );
+    let special_action = call!(
+        intern("display-buffer--special-action").into(),
+        buffer.into()
+    );
+    let mut extra_alist = Qnil;
+    if frame.is_not_nil() {
+        extra_alist = LispObject::cons(
+            LispObject::cons(intern("reusable-frames").into(), frame),
+            extra_alist,
+        );
+    }
+    if inhibit_same_window {
+        extra_alist = LispObject::cons(
+            LispObject::cons(intern("inhibit-same-window").into(), Qt),
+            extra_alist,
+        );
+    }
+    let variable = |name: &str| {
+        let symbol = intern(name);
+        LispObject::cons(symbol.into(), symbol_value(symbol))
+    };
+    list!(
+        variable("display-buffer-overriding-action"),
+        LispObject::cons(intern("display-buffer-alist").into(), user_action),
+        LispObject::cons(intern("special").into(), special_action),
+        LispObject::cons(intern("action").into(), action),
+        LispObject::cons(intern("extra").into(), LispObject::cons(Qnil, extra_alist)),
+        variable("display-buffer-base-action"),
+        variable("display-buffer-fallback-action")
+    )
+}
+
+/// Merge the actions of SOURCES, as returned by `display_buffer_sources',
+/// into one: the list of all their functions and the alist of all their
+/// alist entries, in order.
+fn merge_display_actions(sources: LispObject) -> (LispObject, LispObject) {
+    let mut functions = Qnil;
+    let mut alist = Qnil;
+    for source in sources.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+        let action = cdr(source);
+        let function = car(action);
+        if FUNCTIONP(function) {
+            functions = LispObject::cons(function, functions);
+        } else {
+            for function in function.iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on) {
+                functions = LispObject::cons(function, functions);
+            }
+        }
+        for entry in cdr(action).iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on) {
+            alist = LispObject::cons(entry, alist);
+        }
+    }
+    (nreverse(functions), nreverse(alist))
+}
+
+fn live_buffer_or_error(buffer: LispObject) -> LispBufferRef {
+    match buffer.as_buffer() {
+        Some(buffer) if buffer.is_live() => buffer,
+        _ => error!("Invalid buffer"),
+    }
+}
+
+/// Return the action functions and the action alist for displaying BUFFER.
+/// The value is a cons (FUNCTIONS . ALIST), made by combining the
+/// functions and alists specified by `display-buffer-overriding-action',
+/// `display-buffer-alist', the ACTION argument, `display-buffer-base-action'
+/// and `display-buffer-fallback-action', in that order.  FRAME and
+/// INHIBIT-SAME-WINDOW are the corresponding arguments of `display-buffer'.
+#[lisp_fn(min = "2", name = "display-buffer--actions")]
+pub fn display_buffer_actions(
+    buffer: LispObject,
+    action: LispObject,
+    frame: LispObject,
+    inhibit_same_window: bool,
+) -> LispObject {
+    let buffer = live_buffer_or_error(buffer);
+    let (functions, alist) = merge_display_actions(display_buffer_sources(
+        buffer,
+        action,
+        frame,
+        inhibit_same_window,
+    ));
+    LispObject::cons(functions, alist)
+}
+
+/// Explain how `display-buffer' would display BUFFER-OR-NAME.
+/// ACTION and FRAME are as for `display-buffer'.  Nothing is displayed;
+/// the value is an alist describing what `display-buffer' would do:
+///
+/// - (display-buffer-function . FUNCTION) if `display-buffer-function'
+///   is set, in which case FUNCTION does the job and the rest doesn't
+///   apply.
+///
+/// - (sources (SOURCE . ACTION)...), the actions that contribute to the
+///   display, most important first.  SOURCE is the variable ACTION
+///   comes from, or `special' for the action that implements
+///   `special-display-function', `action' for the ACTION argument, or
+///   `extra' for the alist entries implied by the other arguments.
+///
+/// - (functions FUNCTION...), the action functions tried in turn.
+///
+/// - (alist ENTRY...), the action alist passed to each of them.
+#[lisp_fn(min = "1")]
+pub fn display_buffer_explain(
+    buffer_or_name: LispObject,
+    action: LispObject,
+    frame: LispObject,
+) -> LispObject {
+    let buffer = live_buffer_or_error(match buffer_or_name.as_string() {
+        Some(_) => get_buffer(buffer_or_name.into()).into(),
+        None => buffer_or_name,
+    });
+    let inhibit_same_window = action.is_not_nil() && !action.is_list();
+    let action = if action.is_list() { action } else { Qnil };
+    let sources = display_buffer_sources(buffer, action, frame, inhibit_same_window);
+    let (functions, alist) = merge_display_actions(sources);
+    let explanation = list!(
+        LispObject::cons(intern("sources").into(), sources),
+        LispObject::cons(intern("functions").into(), functions),
+        LispObject::cons(intern("alist").into(), alist)
+    );
+    let function = symbol_value(intern("display-buffer-function"));
+    if function.is_nil() {
+        explanation
+    } else {
+        LispObject::cons(
+            LispObject::cons(intern("display-buffer-function").into(), function),
+            explanation,
+        )
+    }
+}
+
+/// Return the size of WINDOW in pixels, its width if HORIZONTAL.
+fn pixel_size(window: LispWindowRef, horizontal: bool) -> EmacsInt {
+    EmacsInt::from(if horizontal {
+        window.pixel_width
+    } else {
+        window.pixel_height
+    })
+}
+
+/// An iterator over a window and the siblings after it.
+struct WindowSiblings {
+    current: Option<LispWindowRef>,
+}
+
+impl Iterator for WindowSiblings {
+    type Item = LispWindowRef;
+
+    fn next(&mut self) -> Option<LispWindowRef> {
+        let window = self.current?;
+        self.current = window.next.as_window();
+        Some(window)
+    }
+}
+
+/// Return the children of the internal window WINDOW, or nothing if
+/// WINDOW is live.
+fn child_windows(window: LispWindowRef) -> WindowSiblings {
+    WindowSiblings {
+        current: if window.is_internal() {
+            window.contents.as_window()
+        } else {
+            None
+        },
+    }
+}
+
+fn new_pixel(window: LispWindowRef) -> EmacsInt {
+    window.new_pixel.as_fixnum_or_error()
+}
+
+fn add_new_pixel(mut window: LispWindowRef, delta: EmacsInt) {
+    window.new_pixel = LispObject::from(new_pixel(window) + delta);
+}
+
+/// Return whether the resizing routines skip WINDOW, as
+/// `window--resize-child-windows-skip-p' does.
+fn is_resize_skipped(window: LispWindowRef) -> bool {
+    ["ignore", "stuck", "skip"]
+        .iter()
+        .any(|&name| window.new_normal.eq(intern(name)))
+}
+
+/// Give the children of WINDOW, a vertical combination, or a horizontal
+/// one if HORIZONTAL, the same size, as far as their minimum and fixed
+/// sizes allow.  The sizes are recorded as the new pixel sizes of the
+/// children, and then the children are balanced in turn.
+fn balance_combination(window: LispWindowRef, horizontal: bool) {
+    let pixelwise = symbol_value(intern("window-resize-pixelwise")).is_not_nil();
+    let char_size = if pixelwise {
+        1
+    } else {
+        call!(
+            intern("frame-char-size").into(),
+            window.into(),
+            horizontal.into()
+        )
+        .as_fixnum_or_error()
+    };
+    let parent_size = new_pixel(window);
+    let mut total_sum = parent_size;
+    let mut number_of_children: EmacsInt = 0;
+
+    for mut sub in child_windows(window) {
+        number_of_children += 1;
+        let fixed = call!(
+            intern("window-size-fixed-p").into(),
+            sub.into(),
+            horizontal.into()
+        );
+        if fixed.is_not_nil() {
+            total_sum -= pixel_size(sub, horizontal);
+            sub.new_normal = intern("ignore").into();
+        }
+    }
+
+    // Children that can't get the size wanted are given what they can
+    // get, and left out of the next round, until a round succeeds.
+    let mut failed = true;
+    while failed && number_of_children > 0 {
+        let size = total_sum / number_of_children;
+        failed = false;
+        for mut sub in child_windows(window) {
+            if is_resize_skipped(sub) {
+                continue;
+            }
+            let sub_total = pixel_size(sub, horizontal);
+            let sub_delta = size - sub_total;
+            let sub_amount = call!(
+                intern("window-sizable").into(),
+                sub.into(),
+                LispObject::from(sub_delta),
+                horizontal.into(),
+                Qnil,
+                Qt
+            )
+            .as_fixnum_or_error();
+            sub.new_pixel = LispObject::from(sub_total + sub_amount);
+            if sub_amount != sub_delta {
+                total_sum -= sub_total + sub_amount;
+                number_of_children -= 1;
+                failed = true;
+                sub.new_normal = intern("skip").into();
+                break;
+            }
+        }
+    }
+
+    // Spread what the rounding left over, a line or column at a time,
+    // over the children that can take it, then over the stuck ones.
+    let mut rest = if number_of_children > 0 {
+        total_sum % number_of_children
+    } else {
+        0
+    };
+    for sub in child_windows(window) {
+        if rest <= 0 {
+            break;
+        }
+        if !is_resize_skipped(sub) {
+            add_new_pixel(sub, rest.min(char_size));
+            rest -= char_size;
+        }
+    }
+    for sub in child_windows(window) {
+        if rest <= 0 {
+            break;
+        }
+        if !sub.new_normal.eq(intern("ignore")) {
+            add_new_pixel(sub, rest.min(char_size));
+            rest -= char_size;
+        }
+    }
+
+    for mut sub in child_windows(window) {
+        let size = if sub.new_normal.eq(intern("ignore")) {
+            pixel_size(sub, horizontal)
+        } else {
+            new_pixel(sub)
+        };
+        sub.new_normal = LispObject::from(size as f64 / parent_size as f64);
+        balance_window_tree(sub, horizontal);
+    }
+}
+
+/// Balance the heights of the windows in the tree of WINDOW, or their
+/// widths if HORIZONTAL, recording them as new pixel sizes.
+fn balance_window_tree(window: LispWindowRef, horizontal: bool) {
+    if !window.is_internal() {
+        return;
+    }
+    if window.horizontal() == horizontal {
+        balance_combination(window, horizontal);
+    } else {
+        let size = window.new_pixel;
+        for mut sub in child_windows(window) {
+            sub.new_pixel = size;
+            balance_window_tree(sub, horizontal);
+        }
+    }
+}
+
+/// Balance the sizes of windows of WINDOW-OR-FRAME.
+/// WINDOW-OR-FRAME is optional and defaults to the selected frame.
+/// If WINDOW-OR-FRAME denotes a frame, balance the sizes of all
+/// windows of that frame.  If WINDOW-OR-FRAME denotes a window,
+/// recursively balance the sizes of all child windows of that
+/// window.
+#[lisp_fn(min = "0", intspec = "")]
+pub fn balance_windows(window_or_frame: LispObject) {
+    let is_live_frame = window_or_frame.as_frame().map_or(false, |f| f.is_live());
+    let window = if window_or_frame.is_nil() || is_live_frame {
+        frame_root_window(window_or_frame).as_window_or_error()
+    } else {
+        match window_or_frame.as_window() {
+            Some(w) if w.is_live() || w.is_internal() => w,
+            _ => {
+                xsignal!(
+                    Qerror,
+                    LispObject::from("Not a window or frame"),
+                    window_or_frame
+                );
+            }
+        }
+    };
+    let frame = window.frame;
+
+    for &horizontal in &[false, true] {
+        call!(
+            intern("window--resize-reset").into(),
+            frame,
+            horizontal.into()
+        );
+        balance_window_tree(window, horizontal);
+        let applicable = call!(
+            intern("window--resize-apply-p").into(),
+            frame,
+            horizontal.into()
+        );
+        if applicable.is_not_nil() {
+            unsafe { Fwindow_resize_apply(frame, horizontal.into()) };
+            call!(
+                intern("window--pixel-to-total").into(),
+                frame,
+                horizontal.into()
+            );
+            unsafe { Frun_window_configuration_change_hook(frame) };
+        }
+    }
+}
+
+/// Return the value of `window-area-factor' for the buffer of WINDOW.
+fn area_factor(window: LispWindowRef) -> f64 {
+    let factor =
+        unsafe { buffer_local_value(intern("window-area-factor").into(), window.contents) };
+    factor
+        .as_fixnum()
+        .map(|n| n as f64)
+        .or_else(|| factor.as_float())
+        .unwrap_or(1.0)
+}
+
+/// Return the size of WINDOW, its width if HORIZONTAL, in pixels if
+/// PIXELWISE and in lines or columns otherwise, as `window-size' does.
+fn area_side(window: LispWindowRef, horizontal: bool, pixelwise: bool) -> f64 {
+    let size = if pixelwise {
+        pixel_size(window, horizontal) as i32
+    } else if horizontal {
+        window.total_width(Qnil)
+    } else {
+        window.total_height(Qnil)
+    };
+    f64::from(size)
+}
+
+/// Return the pixel edges of WINDOW, as `window-pixel-edges' does.
+fn pixel_edges(window: LispWindowRef) -> [i32; 4] {
+    let left = window.left_pixel_edge();
+    let top = window.top_pixel_edge();
+    [
+        left,
+        top,
+        left + window.pixel_width,
+        top + window.pixel_height,
+    ]
+}
+
+/// Move the trailing edge of WINDOW by DELTA, halving DELTA as long as
+/// that fails.
+fn adjust_trailing_edge(
+    window: LispWindowRef,
+    mut delta: EmacsInt,
+    horizontal: bool,
+    pixelwise: bool,
+) {
+    while delta.abs() >= 1 {
+        let adjusted = call_checked!(
+            intern("adjust-window-trailing-edge").into(),
+            window.into(),
+            LispObject::from(delta),
+            horizontal.into(),
+            pixelwise.into()
+        );
+        if adjusted.is_ok() {
+            break;
+        }
+        delta /= 2;
+    }
+}
+
+/// Make all visible windows the same area (approximately).
+/// See also `window-area-factor' to change the relative size of
+/// specific buffers.
+#[lisp_fn(min = "0", intspec = "")]
+pub fn balance_windows_area() {
+    let pixelwise = symbol_value(intern("window-resize-pixelwise")).is_not_nil();
+    let is_fixed =
+        |window: LispObject| call!(intern("window-fixed-size-p").into(), window).is_not_nil();
+    let mut windows = Qnil;
+    let all = window_list(LispFrameOrSelected::Selected, intern("nomini").into(), None);
+    for window in all.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+        if !is_fixed(window) {
+            windows = LispObject::cons(window, windows);
+        }
+    }
+    let windows = nreverse(windows);
+
+    // Resizing a window changes the sizes of the windows around it in
+    // complex ways, so rather than computing the result up front like
+    // `balance-windows', go through the windows, evening out the area of
+    // each with that of the next one, until a round changes nothing.  A
+    // change seen before doesn't count, to break endless loops, and a
+    // second round without changes lets the carry make its way around.
+    let mut unchanged = 0;
+    let mut carry = 0.0;
+    let mut changelog: Vec<(LispObject, [i32; 4])> = Vec::new();
+    while unchanged < 2 {
+        unchanged += 1;
+        for object in windows.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off) {
+            let win = object.as_window_or_error();
+            let mut next_object = object;
+            loop {
+                next_object = unsafe { Fnext_window(next_object, Qnil, Qnil) };
+                if !is_fixed(next_object) {
+                    break;
+                }
+            }
+            let next = next_object.as_window_or_error();
+
+            let horiz = win.left_pixel_edge() < next.left_pixel_edge();
+            let area =
+                |w| area_side(w, false, pixelwise) * area_side(w, true, pixelwise) * area_factor(w);
+            let areadiff =
+                ((area(next) - area(win)) / area_factor(win).max(area_factor(next))).trunc();
+            let edgesize = area_side(win, !horiz, pixelwise) + area_side(next, !horiz, pixelwise);
+            let mut diff = (areadiff / edgesize).trunc();
+            if diff == 0.0 {
+                // Maybe diff is actually closer to 1 than to 0.
+                diff = ((3.0 * areadiff) / (2.0 * edgesize)).trunc();
+            }
+            if diff == 0.0 && areadiff != 0.0 {
+                diff = ((areadiff + carry) / edgesize).trunc();
+                // Change things smoothly.
+                if diff.abs() > 1.0 {
+                    diff = (diff / 2.0).trunc();
+                }
+            }
+            if diff == 0.0 {
+                // Make sure negligible differences don't accumulate to
+                // become significant.
+                carry += areadiff;
+            } else {
+                adjust_trailing_edge(win, diff as EmacsInt, horiz, pixelwise);
+                let change = (object, pixel_edges(win));
+                if !changelog.contains(&change) {
+                    changelog.push(change);
+                    unchanged = 0;
+                    carry = 0.0;
+                }
+            }
+        }
+    }
+}
+
+/// A window layout, as described by a spec of `apply-window-layout'.
+/// The Lisp objects here are all reachable from the spec, which keeps
+/// them alive.
+enum WindowLayout {
+    /// A live window showing BUFFER, a buffer or the name of one, or
+    /// whatever it shows already if BUFFER is nil.
+    Leaf {
+        buffer: LispObject,
+        dedicated: LispObject,
+        parameters: LispObject,
+        select: bool,
+    },
+    /// Windows side by side if HORIZONTAL, and stacked otherwise, each
+    /// with the share of the space it takes.
+    Split {
+        horizontal: bool,
+        children: Vec<(f64, WindowLayout)>,
+    },
+}
+
+/// Signal an error with MESSAGE about the invalid part SPEC of a window
+/// layout.
+fn layout_error(message: &str, spec: LispObject) -> ! {
+    xsignal!(Qerror, LispObject::from(message), spec)
+}
+
+fn parse_layout_leaf(spec: LispObject, args: LispObject) -> WindowLayout {
+    let (buffer, props) = args.as_cons_or_error().into();
+    if !(buffer.is_nil() || buffer.is_string() || buffer.is_buffer()) {
+        layout_error("Invalid buffer in window layout", spec);
+    }
+    let keys = [
+        intern(":dedicated"),
+        intern(":parameters"),
+        intern(":select"),
+    ];
+    let mut tail = props;
+    while let Some(cell) = tail.as_cons() {
+        let (key, rest) = cell.into();
+        if !keys.iter().any(|&k| key.eq(k)) {
+            layout_error("Invalid window layout property", key);
+        }
+        tail = cdr(rest);
+    }
+    let parameters = plist_get(props, intern(":parameters").into());
+    for parameter in parameters.iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on) {
+        if !parameter.is_cons() {
+            layout_error("Invalid window parameter in window layout", parameter);
+        }
+    }
+    WindowLayout::Leaf {
+        buffer,
+        dedicated: plist_get(props, intern(":dedicated").into()),
+        parameters,
+        select: plist_get(props, intern(":select").into()).is_not_nil(),
+    }
+}
+
+/// Parse SPEC, signaling an error if it isn't a valid layout, before
+/// any window is touched.
+fn parse_window_layout(spec: LispObject) -> WindowLayout {
+    if spec.is_string() || spec.is_buffer() {
+        return WindowLayout::Leaf {
+            buffer: spec,
+            dedicated: Qnil,
+            parameters: Qnil,
+            select: false,
+        };
+    }
+    let (kind, args) = match spec.as_cons() {
+        Some(cell) => cell.into(),
+        None => layout_error("Invalid window layout", spec),
+    };
+    let horizontal = if kind.eq(intern("buffer")) {
+        return parse_layout_leaf(spec, args);
+    } else if kind.eq(intern("row")) {
+        true
+    } else if kind.eq(intern("column")) {
+        false
+    } else {
+        layout_error("Invalid window layout", spec)
+    };
+
+    let mut children = Vec::new();
+    for child in args.iter_cars(LispConsEndChecks::on, LispConsCircularChecks::on) {
+        let (car, cdr) = child.as_cons().map_or((Qnil, child), |cell| cell.into());
+        let ratio = car.as_fixnum().map(|n| n as f64).or_else(|| car.as_float());
+        match ratio {
+            Some(r) if r <= 0.0 || r >= 1.0 => {
+                layout_error("Invalid window layout ratio", car);
+            }
+            Some(_) => children.push((ratio, parse_window_layout(cdr))),
+            None => children.push((None, parse_window_layout(child))),
+        }
+    }
+    if children.is_empty() {
+        layout_error("Empty window layout", spec);
+    }
+
+    // Children without a ratio share what the others leave over, and
+    // the shares are taken relative to their sum.
+    let given: f64 = children.iter().filter_map(|&(ratio, _)| ratio).sum();
+    let others = children
+        .iter()
+        .filter(|&&(ratio, _)| ratio.is_none())
+        .count();
+    if given > 1.0 || (others > 0 && given >= 1.0) {
+        layout_error("Window layout ratios add up to too much", spec);
+    }
+    let rest = if others > 0 {
+        (1.0 - given) / others as f64
+    } else {
+        0.0
+    };
+    let total = if others > 0 { 1.0 } else { given };
+    let children = children
+        .into_iter()
+        .map(|(ratio, layout)| (ratio.unwrap_or(rest) / total, layout))
+        .collect();
+    WindowLayout::Split {
+        horizontal,
+        children,
+    }
+}
+
+/// Lay out LAYOUT in the live window WINDOW, adding the live windows
+/// made for its leaves to LEAVES, with whether they are to be selected.
+fn realize_window_layout(
+    window: LispWindowRef,
+    layout: &WindowLayout,
+    leaves: &mut Vec<(LispWindowRef, bool)>,
+) {
+    match *layout {
+        WindowLayout::Leaf {
+            buffer,
+            dedicated,
+            parameters,
+            select,
+        } => {
+            let object: LispObject = window.into();
+            // A dedicated window refuses another buffer.
+            set_window_dedicated_p(object.into(), Qnil);
+            if buffer.is_not_nil() {
+                unsafe { Fset_window_buffer(object, Fget_buffer_create(buffer), Qnil) };
+            }
+            set_window_dedicated_p(object.into(), dedicated);
+            for parameter in
+                parameters.iter_cars(LispConsEndChecks::off, LispConsCircularChecks::off)
+            {
+                let (name, value) = parameter.into();
+                set_window_parameter(object.into(), name, value);
+            }
+            leaves.push((window, select));
+        }
+        WindowLayout::Split {
+            horizontal,
+            ref children,
+        } => {
+            let total = if horizontal {
+                window.total_width(Qnil)
+            } else {
+                window.total_height(Qnil)
+            };
+            let side = intern(if horizontal { "right" } else { "below" });
+            // Split off each child but the last from the rest of the
+            // space in turn, rounding the edges rather than the sizes so
+            // that the errors don't add up.
+            let mut windows = vec![window];
+            let mut current = window;
+            let mut share = 0.0;
+            let mut edge = 0;
+            for &(ratio, _) in &children[..children.len() - 1] {
+                share += ratio;
+                let next_edge = (share * f64::from(total)).round() as EmacsInt;
+                current = call!(
+                    intern("split-window").into(),
+                    current.into(),
+                    LispObject::from(next_edge - edge),
+                    side.into()
+                )
+                .as_window_or_error();
+                edge = next_edge;
+                windows.push(current);
+            }
+            for (&sub, &(_, ref child)) in windows.iter().zip(children.iter()) {
+                realize_window_layout(sub, child, leaves);
+            }
+        }
+    }
+}
+
+/// Lay out the windows of FRAME as described by SPEC.
+/// FRAME must be a live frame and defaults to the selected one.  All
+/// windows of FRAME but one are deleted, and what is left is split as
+/// SPEC says.  SPEC is one of
+///
+///  BUFFER-OR-NAME -- a window showing that buffer, which is created if
+///    there is no buffer of that name.
+///  (buffer BUFFER-OR-NAME . PROPERTIES) -- a window showing that buffer,
+///    or whatever it shows already if BUFFER-OR-NAME is nil.
+///    PROPERTIES is a plist; `:dedicated' gives the window's dedicated
+///    flag, `:parameters' an alist of window parameters for it, and
+///    `:select' non-nil makes it the selected window of FRAME.
+///  (column CHILD...) -- windows stacked above each other.
+///  (row CHILD...) -- windows side by side.
+///
+/// A CHILD is a SPEC, or (RATIO . SPEC) where RATIO, a number between 0
+/// and 1, is the share of the height of a column or the width of a row
+/// the child takes.  Children without a RATIO share the space the others
+/// leave evenly; if every child has one, the ratios are taken relative to
+/// their sum.
+///
+/// Unless some window asks to be selected, the first one is.  If the
+/// layout can't be made, for instance because FRAME is too small for it,
+/// the windows of FRAME are left as they were.  Return the list of
+/// windows for the buffers in SPEC, in the order they appear there.
+#[lisp_fn(min = "1")]
+pub fn apply_window_layout(spec: LispObject, frame: LispFrameOrSelected) -> LispObject {
+    let layout = parse_window_layout(spec);
+    let frame: LispObject = frame.live_or_error().into();
+
+    let count = c_specpdl_index();
+    unsafe {
+        record_unwind_protect(
+            Some(restore_window_configuration),
+            Fcurrent_window_configuration(frame),
+        )
+    };
+    let first = frame_first_window(frame);
+    unsafe { Fdelete_other_windows_internal(first.into(), Qnil) };
+    let mut leaves = Vec::new();
+    realize_window_layout(first, &layout, &mut leaves);
+    let selected = leaves
+        .iter()
+        .find(|&&(_, select)| select)
+        .unwrap_or(&leaves[0])
+        .0;
+    set_frame_selected_window(frame.into(), selected.into(), Qnil);
+    // The layout is made; keep it.
+    unsafe { clear_unwind_protect(count) };
+    unbind_to(count, Qnil);
+
+    let windows: Vec<LispObject> = leaves.iter().map(|&(window, _)| window.into()).collect();
+    list(&windows)
+}
+
+#[no_mangle]
+pub extern "C" fn syms_of_windows() {
+    /// Maximum length of the lists of previous and next buffers of a window.
+    /// When a buffer is killed, or a window's buffer is recorded in its
+    /// previous buffers, the oldest elements beyond this many are dropped.
+    /// nil means there is no limit.
+    #[cfg_attr(rustfmt, rustfmt_skip)]
+    defvar_lisp!(Vwindow_buffer_lists_max, "window-buffer-lists-max", LispObject::from(100));
+}
+
 include!(concat!(env!("OUT_DIR"), "/windows_exports.rs"));
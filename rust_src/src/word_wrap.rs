@@ -0,0 +1,240 @@
+//! Where word wrapping breaks lines.
+//!
+//! With `word-wrap', continued lines are broken between words rather
+//! than at the edge of the window.  A line can be broken after spaces
+//! and tabs, before the next character that isn't one.  With
+//! `word-wrap-by-category', it can also be broken after characters of
+//! category `|', such as most CJK characters, unless they can't end a
+//! line, being of category `<', or the next character can't begin one,
+//! being of category `>'.  Redisplay asks here where it may wrap lines,
+//! and `visual-line-boundaries' wraps them by the same rules.
+
+use libc::c_int;
+
+use remacs_macros::lisp_fn;
+
+use crate::{
+    category::char_has_category,
+    editfns::buffer_substring,
+    lisp::{defsubr, LispObject},
+    lists::list,
+    multibyte::Codepoint,
+    obarray::intern,
+    pixel_fill::Kind,
+    remacs_sys::{globals, EmacsInt, Fchar_width, Fwindow_body_width, Qnil},
+    symbols::symbol_value,
+};
+
+/// Return whether a line may be wrapped before the character C, which
+/// is displayed as a space or tab if WHITESPACE.  REVERSED says whether
+/// C is in a right-to-left row, where the ends of lines are swapped.
+#[no_mangle]
+pub extern "C" fn char_can_wrap_before(c: c_int, whitespace: bool, reversed: bool) -> bool {
+    if !unsafe { globals.word_wrap_by_category } {
+        return !whitespace;
+    }
+    // Wrapping before a space or tab would start the next line with it.
+    let not_at_bol = if reversed { b'<' } else { b'>' };
+    !whitespace && !char_has_category(c as Codepoint, not_at_bol)
+}
+
+/// Return whether a line may be wrapped after the character C, like
+/// `char_can_wrap_before`.
+#[no_mangle]
+pub extern "C" fn char_can_wrap_after(c: c_int, whitespace: bool, reversed: bool) -> bool {
+    if !unsafe { globals.word_wrap_by_category } {
+        return whitespace;
+    }
+    let not_at_eol = if reversed { b'>' } else { b'<' };
+    whitespace
+        || (char_has_category(c as Codepoint, b'|')
+            && !char_has_category(c as Codepoint, not_at_eol))
+}
+
+/// A character as far as wrapping lines goes: its kind, its width, and
+/// whether a line may be wrapped before and after it.  A tab is as wide
+/// as it takes to reach the next tab stop instead.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct WrapChar {
+    pub kind: Kind,
+    pub width: i32,
+    pub tab: bool,
+    pub wrap_before: bool,
+    pub wrap_after: bool,
+}
+
+/// Return the indices of the characters of CHARS that start visual
+/// lines, other than the first, when they are word wrapped to lines
+/// WIDTH wide, with tab stops every TAB_WIDTH columns.  Spaces and tabs
+/// at the end of a line may go past WIDTH, and a word wider than a line
+/// is broken where it reaches WIDTH.
+pub(crate) fn visual_line_starts(
+    chars: impl Iterator<Item = WrapChar>,
+    width: i32,
+    tab_width: i32,
+) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut line_start = 0;
+    let mut line_width = 0;
+    // Whether the line may be wrapped before the next character, and
+    // the last character before which it may, with the width of the
+    // line up to it.
+    let mut may_wrap = false;
+    let mut wrap = None;
+
+    for (i, c) in chars.enumerate() {
+        if c.kind == Kind::Newline {
+            starts.push(i + 1);
+            line_start = i + 1;
+            line_width = 0;
+            may_wrap = false;
+            wrap = None;
+            continue;
+        }
+
+        if may_wrap && c.wrap_before {
+            wrap = Some((i, line_width));
+        }
+        may_wrap = c.wrap_after;
+
+        if c.tab {
+            line_width += tab_width - line_width % tab_width;
+            continue;
+        }
+        if c.kind != Kind::Space && i > line_start && line_width + c.width > width {
+            if let Some((at, width_before)) = wrap.take() {
+                starts.push(at);
+                line_start = at;
+                line_width -= width_before;
+            }
+            if i > line_start && line_width + c.width > width {
+                starts.push(i);
+                line_start = i;
+                line_width = 0;
+            }
+        }
+        line_width += c.width;
+    }
+    starts
+}
+
+/// Return the positions where visual lines start in the text from FROM
+/// to TO in the current buffer, other than FROM, in increasing order.
+///
+/// The text is wrapped as `word-wrap' wraps it in lines WIDTH columns
+/// wide, by default that of the text area of the selected window.  A
+/// line starts after each newline, and where wrapping breaks a line,
+/// which it does at spaces and tabs and, if `word-wrap-by-category' is
+/// non-nil, by the categories of the characters.  Characters are as wide
+/// as their columns, and a tab reaches the next tab stop, every
+/// `tab-width' columns from the start of the visual line.  Text
+/// properties and overlays that change how text is displayed, such as
+/// `display' and `invisible', are not considered.
+#[lisp_fn(min = "2")]
+pub fn visual_line_boundaries(from: LispObject, to: LispObject, width: LispObject) -> LispObject {
+    let width = if width.is_nil() {
+        unsafe { Fwindow_body_width(Qnil, Qnil) }.as_fixnum_or_error()
+    } else {
+        width.as_fixnum_or_error()
+    };
+    let tab_width = symbol_value(intern("tab-width"))
+        .as_fixnum()
+        .filter(|&n| 0 < n && n <= 1000)
+        .unwrap_or(8) as i32;
+
+    let text = buffer_substring(from, to);
+    let start = from
+        .as_fixnum_coerce_marker_or_error()
+        .min(to.as_fixnum_coerce_marker_or_error());
+    let chars = text.force_string().chars().map(|c| {
+        let kind = Kind::of(c);
+        let whitespace = kind == Kind::Space;
+        let tab = c == 0x09;
+        WrapChar {
+            kind,
+            width: if tab {
+                0
+            } else {
+                unsafe { Fchar_width(c.into()) }.as_fixnum_or_error() as i32
+            },
+            tab,
+            wrap_before: char_can_wrap_before(c as c_int, whitespace, false),
+            wrap_after: char_can_wrap_after(c as c_int, whitespace, false),
+        }
+    });
+
+    let starts = visual_line_starts(chars, width.max(1) as i32, tab_width);
+    list(
+        &starts
+            .into_iter()
+            .map(|i| LispObject::from(start + i as EmacsInt))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[no_mangle]
+pub extern "C" fn syms_of_word_wrap() {
+    /// Non-nil means also wrap after characters of a certain category.
+    /// Normally when `word-wrap' is on, Emacs only breaks lines after
+    /// whitespace characters.  When this option is turned on, Emacs also
+    /// breaks lines after characters that have the "|" category (defined in
+    /// characters.el).  This is useful for allowing breaking after CJK
+    /// characters and improves the word-wrapping for CJK text mixed with
+    /// Latin text.
+    ///
+    /// If this variable is set using Customize, Emacs automatically loads
+    /// kinsoku.el.  When kinsoku.el is loaded, Emacs respects kinsoku rules
+    /// when breaking lines.  That means characters with the ">" category
+    /// don't appear at the beginning of a line (e.g., FULLWIDTH COMMA), and
+    /// characters with the "<" category don't appear at the end of a line
+    /// (e.g., LEFT DOUBLE ANGLE BRACKET).
+    defvar_bool!(word_wrap_by_category, "word-wrap-by-category", false);
+}
+
+include!(concat!(env!("OUT_DIR"), "/word_wrap_exports.rs"));
+
+#[test]
+fn test_visual_line_starts() {
+    let chars = |text: &str| {
+        text.chars()
+            .map(|c| {
+                let kind = Kind::of(c as Codepoint);
+                WrapChar {
+                    kind,
+                    width: 1,
+                    tab: c == '\t',
+                    wrap_before: kind != Kind::Space,
+                    wrap_after: kind == Kind::Space,
+                }
+            })
+            .collect::<Vec<_>>()
+    };
+    let starts = |text: &str, width| visual_line_starts(chars(text).into_iter(), width, 4);
+
+    assert_eq!(starts("aaa bbb ccc", 7), vec![8]);
+    assert_eq!(starts("aaa bbb ccc", 4), vec![4, 8]);
+    assert_eq!(starts("aaa bbb\nccc", 20), vec![8]);
+    // A word wider than a line is broken at the edge.
+    assert_eq!(starts("aaaaaaa bb", 3), vec![3, 6, 8]);
+    // Spaces go past the end of the line.
+    assert_eq!(starts("aaa    bbb", 4), vec![7]);
+    assert_eq!(starts("", 4), vec![]);
+    // Tabs reach the next tab stop, at column 4 or 8 here.
+    assert_eq!(starts("a\tbbb cc", 7), vec![6]);
+    assert_eq!(starts("a\tbbbb cc", 8), vec![7]);
+    assert_eq!(starts("aa\tb\tc", 8), vec![5]);
+    assert_eq!(starts("aaaaa\tbbb", 8), vec![6]);
+
+    // Wide characters that may be wrapped after, as CJK ones may.
+    let cjk = WrapChar {
+        kind: Kind::Other,
+        width: 2,
+        tab: false,
+        wrap_before: true,
+        wrap_after: true,
+    };
+    assert_eq!(
+        visual_line_starts(vec![cjk; 5].into_iter(), 5, 8),
+        vec![2, 4]
+    );
+}
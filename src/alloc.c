@@ -223,28 +223,14 @@ alloc_unexec_post (void)
 /* Global variables.  */
 struct emacs_globals globals;
 
-/* Number of bytes of consing done since the last gc.  */
-
-EMACS_INT consing_since_gc;
-
-/* Similar minimum, computed from Vgc_cons_percentage.  */
-
-EMACS_INT gc_relative_threshold;
-
-/* Minimum number of bytes of consing since GC before next GC,
-   when memory is full.  */
-
-EMACS_INT memory_full_cons_threshold;
-
 /* True during GC.  */
 
 bool gc_in_progress;
 
 /* Number of live and free conses etc.  */
 
-static EMACS_INT total_conses, total_markers, total_symbols, total_buffers;
-static EMACS_INT total_free_conses, total_free_markers, total_free_symbols;
-static EMACS_INT total_free_floats, total_floats;
+EMACS_INT total_markers, total_symbols, total_buffers;
+EMACS_INT total_free_markers, total_free_symbols;
 
 /* Points to memory space allocated as "spare", to be freed if we run
    out of memory.  We keep one large block, four cons-blocks, and
@@ -354,7 +340,6 @@ no_sanitize_memcpy (void *dest, void const *src, size_t size)
 static void mark_terminals (void);
 static void gc_sweep (void);
 static Lisp_Object make_pure_vector (ptrdiff_t);
-static void mark_buffer (struct buffer *);
 
 #if !defined REL_ALLOC || defined SYSTEM_MALLOC || defined HYBRID_MALLOC
 static void refill_memory_reserve (void);
@@ -391,7 +376,7 @@ enum mem_type
 /* A unique object in pure space used to make some Lisp objects
    on free lists recognizable in O(1).  */
 
-static Lisp_Object Vdead;
+Lisp_Object Vdead;
 #define DEADP(x) EQ (x, Vdead)
 
 #ifdef GC_MALLOC_CHECK
@@ -550,15 +535,6 @@ mmap_lisp_allowed_p (void)
 }
 #endif
 
-/* Head of a circularly-linked list of extant finalizers. */
-static struct Lisp_Finalizer finalizers;
-
-/* Head of a circularly-linked list of finalizers that must be invoked
-   because we deemed them unreachable.  This list must be global, and
-   not a local inside garbage_collect_1, in case we GC again while
-   running finalizers.  */
-static struct Lisp_Finalizer doomed_finalizers;
-
 
 /************************************************************************
 				Malloc
@@ -1104,10 +1080,41 @@ lisp_malloc (size_t nbytes, enum mem_type type)
   return val;
 }
 
+/* Allocate NBYTES for a block of Lisp strings, or for string data,
+   on behalf of the string allocator in string_alloc.rs.  LARGE says
+   whether the data is that of a single large string.  */
+
+void *
+lisp_malloc_strings (size_t nbytes)
+{
+  return lisp_malloc (nbytes, MEM_TYPE_STRING);
+}
+
+void *
+lisp_malloc_string_data (size_t nbytes, bool large)
+{
+  void *b;
+#ifdef DOUG_LEA_MALLOC
+  if (large && !mmap_lisp_allowed_p ())
+    {
+      /* Prevent mmap'ing the chunk.  Lisp data may not be mmap'ed
+	 because mapped region contents are not preserved in
+	 a dumped Emacs.  */
+      mallopt (M_MMAP_MAX, 0);
+    }
+#endif
+  b = lisp_malloc (nbytes, MEM_TYPE_NON_LISP);
+#ifdef DOUG_LEA_MALLOC
+  if (large && !mmap_lisp_allowed_p ())
+    mallopt (M_MMAP_MAX, MMAP_MAX_AREAS);
+#endif
+  return b;
+}
+
 /* Free BLOCK.  This must be called to free memory allocated with a
    call to lisp_malloc.  */
 
-static void
+void
 lisp_free (void *block)
 {
   MALLOC_BLOCK_INPUT;
@@ -1337,7 +1344,22 @@ lisp_align_malloc (size_t nbytes, enum mem_type type)
   return val;
 }
 
-static void
+/* Allocate NBYTES, aligned on BLOCK_ALIGN, for a block of conses or
+   floats, on behalf of the allocators in block_alloc.rs.  */
+
+void *
+lisp_align_malloc_conses (size_t nbytes)
+{
+  return lisp_align_malloc (nbytes, MEM_TYPE_CONS);
+}
+
+void *
+lisp_align_malloc_floats (size_t nbytes)
+{
+  return lisp_align_malloc (nbytes, MEM_TYPE_FLOAT);
+}
+
+void
 lisp_align_free (void *block)
 {
   struct ablock *ablock = block;
@@ -1492,7 +1514,7 @@ static int interval_block_index = INTERVAL_BLOCK_SIZE;
 
 /* Number of free and live intervals.  */
 
-static EMACS_INT total_free_intervals, total_intervals;
+EMACS_INT total_free_intervals, total_intervals;
 
 /* List of free intervals.  */
 
@@ -1562,229 +1584,7 @@ mark_interval (INTERVAL i, void *dummy)
 			  String Allocation
  ***********************************************************************/
 
-/* Lisp_Strings are allocated in string_block structures.  When a new
-   string_block is allocated, all the Lisp_Strings it contains are
-   added to a free-list string_free_list.  When a new Lisp_String is
-   needed, it is taken from that list.  During the sweep phase of GC,
-   string_blocks that are entirely free are freed, except two which
-   we keep.
-
-   String data is allocated from sblock structures.  Strings larger
-   than LARGE_STRING_BYTES, get their own sblock, data for smaller
-   strings is sub-allocated out of sblocks of size SBLOCK_SIZE.
-
-   Sblocks consist internally of sdata structures, one for each
-   Lisp_String.  The sdata structure points to the Lisp_String it
-   belongs to.  The Lisp_String points back to the `u.data' member of
-   its sdata structure.
-
-   When a Lisp_String is freed during GC, it is put back on
-   string_free_list, and its `data' member and its sdata's `string'
-   pointer is set to null.  The size of the string is recorded in the
-   `n.nbytes' member of the sdata.  So, sdata structures that are no
-   longer used, can be easily recognized, and it's easy to compact the
-   sblocks of small strings which we do in compact_small_strings.  */
-
-/* Size in bytes of an sblock structure used for small strings.  This
-   is 8192 minus malloc overhead.  */
-
-#define SBLOCK_SIZE 8188
-
-/* Strings larger than this are considered large strings.  String data
-   for large strings is allocated from individual sblocks.  */
-
-#define LARGE_STRING_BYTES 1024
-
-/* The SDATA typedef is a struct or union describing string memory
-   sub-allocated from an sblock.  This is where the contents of Lisp
-   strings are stored.  */
-
-struct sdata
-{
-  /* Back-pointer to the string this sdata belongs to.  If null, this
-     structure is free, and NBYTES (in this structure or in the union below)
-     contains the string's byte size (the same value that STRING_BYTES
-     would return if STRING were non-null).  If non-null, STRING_BYTES
-     (STRING) is the size of the data, and DATA contains the string's
-     contents.  */
-  struct Lisp_String *string;
-
-#ifdef GC_CHECK_STRING_BYTES
-  ptrdiff_t nbytes;
-#endif
-
-  unsigned char data[FLEXIBLE_ARRAY_MEMBER];
-};
-
-#ifdef GC_CHECK_STRING_BYTES
-
-typedef struct sdata sdata;
-#define SDATA_NBYTES(S)	(S)->nbytes
-#define SDATA_DATA(S)	(S)->data
-
-#else
-
-typedef union
-{
-  struct Lisp_String *string;
-
-  /* When STRING is nonnull, this union is actually of type 'struct sdata',
-     which has a flexible array member.  However, if implemented by
-     giving this union a member of type 'struct sdata', the union
-     could not be the last (flexible) member of 'struct sblock',
-     because C99 prohibits a flexible array member from having a type
-     that is itself a flexible array.  So, comment this member out here,
-     but remember that the option's there when using this union.  */
-#if 0
-  struct sdata u;
-#endif
-
-  /* When STRING is null.  */
-  struct
-  {
-    struct Lisp_String *string;
-    ptrdiff_t nbytes;
-  } n;
-} sdata;
-
-#define SDATA_NBYTES(S)	(S)->n.nbytes
-#define SDATA_DATA(S)	((struct sdata *) (S))->data
-
-#endif /* not GC_CHECK_STRING_BYTES */
-
-enum { SDATA_DATA_OFFSET = offsetof (struct sdata, data) };
-
-/* Structure describing a block of memory which is sub-allocated to
-   obtain string data memory for strings.  Blocks for small strings
-   are of fixed size SBLOCK_SIZE.  Blocks for large strings are made
-   as large as needed.  */
-
-struct sblock
-{
-  /* Next in list.  */
-  struct sblock *next;
-
-  /* Pointer to the next free sdata block.  This points past the end
-     of the sblock if there isn't any space left in this block.  */
-  sdata *next_free;
-
-  /* String data.  */
-  sdata data[FLEXIBLE_ARRAY_MEMBER];
-};
-
-/* Number of Lisp strings in a string_block structure.  The 1020 is
-   1024 minus malloc overhead.  */
-
-#define STRING_BLOCK_SIZE \
-  ((1020 - sizeof (struct string_block *)) / sizeof (struct Lisp_String))
-
-/* Structure describing a block from which Lisp_String structures
-   are allocated.  */
-
-struct string_block
-{
-  /* Place `strings' first, to preserve alignment.  */
-  struct Lisp_String strings[STRING_BLOCK_SIZE];
-  struct string_block *next;
-};
-
-/* Head and tail of the list of sblock structures holding Lisp string
-   data.  We always allocate from current_sblock.  The NEXT pointers
-   in the sblock structures go from oldest_sblock to current_sblock.  */
-
-static struct sblock *oldest_sblock, *current_sblock;
-
-/* List of sblocks for large strings.  */
-
-static struct sblock *large_sblocks;
-
-/* List of string_block structures.  */
-
-static struct string_block *string_blocks;
-
-/* Free-list of Lisp_Strings.  */
-
-static struct Lisp_String *string_free_list;
-
-/* Number of live and free Lisp_Strings.  */
-
-static EMACS_INT total_strings, total_free_strings;
-
-/* Number of bytes used by live strings.  */
-
-static EMACS_INT total_string_bytes;
-
-/* Given a pointer to a Lisp_String S which is on the free-list
-   string_free_list, return a pointer to its successor in the
-   free-list.  */
-
-#define NEXT_FREE_LISP_STRING(S) ((S)->u.next)
-
-/* Return a pointer to the sdata structure belonging to Lisp string S.
-   S must be live, i.e. S->data must not be null.  S->data is actually
-   a pointer to the `u.data' member of its sdata structure; the
-   structure starts at a constant offset in front of that.  */
-
-#define SDATA_OF_STRING(S) ((sdata *) ptr_bounds_init ((S)->u.s.data \
-						       - SDATA_DATA_OFFSET))
-
-
-#ifdef GC_CHECK_STRING_OVERRUN
-
-/* We check for overrun in string data blocks by appending a small
-   "cookie" after each allocated string data block, and check for the
-   presence of this cookie during GC.  */
-
-#define GC_STRING_OVERRUN_COOKIE_SIZE	4
-static char const string_overrun_cookie[GC_STRING_OVERRUN_COOKIE_SIZE] =
-  { '\xde', '\xad', '\xbe', '\xef' };
-
-#else
-#define GC_STRING_OVERRUN_COOKIE_SIZE 0
-#endif
-
-/* Value is the size of an sdata structure large enough to hold NBYTES
-   bytes of string data.  The value returned includes a terminating
-   NUL byte, the size of the sdata structure, and padding.  */
-
-#ifdef GC_CHECK_STRING_BYTES
-
-#define SDATA_SIZE(NBYTES) FLEXSIZEOF (struct sdata, data, (NBYTES) + 1)
-
-#else /* not GC_CHECK_STRING_BYTES */
-
-/* The 'max' reserves space for the nbytes union member even when NBYTES + 1 is
-   less than the size of that member.  The 'max' is not needed when
-   SDATA_DATA_OFFSET is a multiple of FLEXALIGNOF (struct sdata),
-   because then the alignment code reserves enough space.  */
-
-#define SDATA_SIZE(NBYTES)				      \
-     ((SDATA_DATA_OFFSET				      \
-       + (SDATA_DATA_OFFSET % FLEXALIGNOF (struct sdata) == 0 \
-	  ? NBYTES					      \
-	  : max (NBYTES, FLEXALIGNOF (struct sdata) - 1))     \
-       + 1						      \
-       + FLEXALIGNOF (struct sdata) - 1)		      \
-      & ~(FLEXALIGNOF (struct sdata) - 1))
-
-#endif /* not GC_CHECK_STRING_BYTES */
-
-/* Extra bytes to allocate for each string.  */
-
-#define GC_STRING_EXTRA (GC_STRING_OVERRUN_COOKIE_SIZE)
-
-/* Exact bound on the number of bytes in a string, not counting the
-   terminating null.  A string cannot contain more bytes than
-   STRING_BYTES_BOUND, nor can it be so long that the size_t
-   arithmetic in allocate_string_data would overflow while it is
-   calculating a value to be passed to malloc.  */
-static ptrdiff_t const STRING_BYTES_MAX =
-  min (STRING_BYTES_BOUND,
-       ((SIZE_MAX - XMALLOC_OVERRUN_CHECK_OVERHEAD
-	 - GC_STRING_EXTRA
-	 - offsetof (struct sblock, data)
-	 - SDATA_DATA_OFFSET)
-	& ~(sizeof (EMACS_INT) - 1)));
+/* Lisp_Strings and their data are allocated in string_alloc.rs.  */
 
 /* Initialize string allocation.  Called from init_alloc_once.  */
 
@@ -1795,494 +1595,6 @@ init_strings (void)
   empty_multibyte_string = make_pure_string ("", 0, 0, 1);
 }
 
-
-#ifdef GC_CHECK_STRING_BYTES
-
-static int check_string_bytes_count;
-
-/* Like STRING_BYTES, but with debugging check.  Can be
-   called during GC, so pay attention to the mark bit.  */
-
-ptrdiff_t
-string_bytes (struct Lisp_String *s)
-{
-  ptrdiff_t nbytes =
-    (s->u.s.size_byte < 0 ? s->u.s.size & ~ARRAY_MARK_FLAG : s->u.s.size_byte);
-
-  if (!PURE_P (s) && s->u.s.data
-      && nbytes != SDATA_NBYTES (SDATA_OF_STRING (s)))
-    emacs_abort ();
-  return nbytes;
-}
-
-/* Check validity of Lisp strings' string_bytes member in B.  */
-
-static void
-check_sblock (struct sblock *b)
-{
-  sdata *from, *end, *from_end;
-
-  end = b->next_free;
-
-  for (from = b->data; from < end; from = from_end)
-    {
-      /* Compute the next FROM here because copying below may
-	 overwrite data we need to compute it.  */
-      ptrdiff_t nbytes;
-
-      /* Check that the string size recorded in the string is the
-	 same as the one recorded in the sdata structure.  */
-      nbytes = SDATA_SIZE (from->string ? string_bytes (from->string)
-			   : SDATA_NBYTES (from));
-      from_end = (sdata *) ((char *) from + nbytes + GC_STRING_EXTRA);
-    }
-}
-
-
-/* Check validity of Lisp strings' string_bytes member.  ALL_P
-   means check all strings, otherwise check only most
-   recently allocated strings.  Used for hunting a bug.  */
-
-static void
-check_string_bytes (bool all_p)
-{
-  if (all_p)
-    {
-      struct sblock *b;
-
-      for (b = large_sblocks; b; b = b->next)
-	{
-	  struct Lisp_String *s = b->data[0].string;
-	  if (s)
-	    string_bytes (s);
-	}
-
-      for (b = oldest_sblock; b; b = b->next)
-	check_sblock (b);
-    }
-  else if (current_sblock)
-    check_sblock (current_sblock);
-}
-
-#else /* not GC_CHECK_STRING_BYTES */
-
-#define check_string_bytes(all) ((void) 0)
-
-#endif /* GC_CHECK_STRING_BYTES */
-
-#ifdef GC_CHECK_STRING_FREE_LIST
-
-/* Walk through the string free list looking for bogus next pointers.
-   This may catch buffer overrun from a previous string.  */
-
-static void
-check_string_free_list (void)
-{
-  struct Lisp_String *s;
-
-  /* Pop a Lisp_String off the free-list.  */
-  s = string_free_list;
-  while (s != NULL)
-    {
-      if ((uintptr_t) s < 1024)
-	emacs_abort ();
-      s = NEXT_FREE_LISP_STRING (s);
-    }
-}
-#else
-#define check_string_free_list()
-#endif
-
-/* Return a new Lisp_String.  */
-
-static struct Lisp_String *
-allocate_string (void)
-{
-  struct Lisp_String *s;
-
-  MALLOC_BLOCK_INPUT;
-
-  /* If the free-list is empty, allocate a new string_block, and
-     add all the Lisp_Strings in it to the free-list.  */
-  if (string_free_list == NULL)
-    {
-      struct string_block *b = lisp_malloc (sizeof *b, MEM_TYPE_STRING);
-      int i;
-
-      b->next = string_blocks;
-      string_blocks = b;
-
-      for (i = STRING_BLOCK_SIZE - 1; i >= 0; --i)
-	{
-	  s = b->strings + i;
-	  /* Every string on a free list should have NULL data pointer.  */
-	  s->u.s.data = NULL;
-	  NEXT_FREE_LISP_STRING (s) = string_free_list;
-	  string_free_list = ptr_bounds_clip (s, sizeof *s);
-	}
-
-      total_free_strings += STRING_BLOCK_SIZE;
-    }
-
-  check_string_free_list ();
-
-  /* Pop a Lisp_String off the free-list.  */
-  s = string_free_list;
-  string_free_list = NEXT_FREE_LISP_STRING (s);
-
-  MALLOC_UNBLOCK_INPUT;
-
-  --total_free_strings;
-  ++total_strings;
-  ++strings_consed;
-  consing_since_gc += sizeof *s;
-
-#ifdef GC_CHECK_STRING_BYTES
-  if (!noninteractive)
-    {
-      if (++check_string_bytes_count == 200)
-	{
-	  check_string_bytes_count = 0;
-	  check_string_bytes (1);
-	}
-      else
-	check_string_bytes (0);
-    }
-#endif /* GC_CHECK_STRING_BYTES */
-
-  return s;
-}
-
-
-/* Set up Lisp_String S for holding NCHARS characters, NBYTES bytes,
-   plus a NUL byte at the end.  Allocate an sdata structure DATA for
-   S, and set S->u.s.data to SDATA->u.data.  Store a NUL byte at the
-   end of S->u.s.data.  Set S->u.s.size to NCHARS and S->u.s.size_byte
-   to NBYTES.  Free S->u.s.data if it was initially non-null.  */
-
-void
-allocate_string_data (struct Lisp_String *s,
-		      EMACS_INT nchars, EMACS_INT nbytes)
-{
-  sdata *data, *old_data;
-  struct sblock *b;
-  ptrdiff_t needed, old_nbytes;
-
-  if (STRING_BYTES_MAX < nbytes)
-    string_overflow ();
-
-  /* Determine the number of bytes needed to store NBYTES bytes
-     of string data.  */
-  needed = SDATA_SIZE (nbytes);
-  if (s->u.s.data)
-    {
-      old_data = SDATA_OF_STRING (s);
-      old_nbytes = STRING_BYTES (s);
-    }
-  else
-    old_data = NULL;
-
-  MALLOC_BLOCK_INPUT;
-
-  if (nbytes > LARGE_STRING_BYTES)
-    {
-      size_t size = FLEXSIZEOF (struct sblock, data, needed);
-
-#ifdef DOUG_LEA_MALLOC
-      if (!mmap_lisp_allowed_p ())
-        mallopt (M_MMAP_MAX, 0);
-#endif
-
-      b = lisp_malloc (size + GC_STRING_EXTRA, MEM_TYPE_NON_LISP);
-
-#ifdef DOUG_LEA_MALLOC
-      if (!mmap_lisp_allowed_p ())
-        mallopt (M_MMAP_MAX, MMAP_MAX_AREAS);
-#endif
-
-      data = b->data;
-      b->next = large_sblocks;
-      b->next_free = data;
-      large_sblocks = b;
-    }
-  else if (current_sblock == NULL
-	   || (((char *) current_sblock + SBLOCK_SIZE
-		- (char *) current_sblock->next_free)
-	       < (needed + GC_STRING_EXTRA)))
-    {
-      /* Not enough room in the current sblock.  */
-      b = lisp_malloc (SBLOCK_SIZE, MEM_TYPE_NON_LISP);
-      data = b->data;
-      b->next = NULL;
-      b->next_free = data;
-
-      if (current_sblock)
-	current_sblock->next = b;
-      else
-	oldest_sblock = b;
-      current_sblock = b;
-    }
-  else
-    {
-      b = current_sblock;
-      data = b->next_free;
-    }
-
-  data->string = s;
-  b->next_free = (sdata *) ((char *) data + needed + GC_STRING_EXTRA);
-
-  MALLOC_UNBLOCK_INPUT;
-
-  s->u.s.data = ptr_bounds_clip (SDATA_DATA (data), nbytes + 1);
-#ifdef GC_CHECK_STRING_BYTES
-  SDATA_NBYTES (data) = nbytes;
-#endif
-  s->u.s.size = nchars;
-  s->u.s.size_byte = nbytes;
-  s->u.s.data[nbytes] = '\0';
-#ifdef GC_CHECK_STRING_OVERRUN
-  memcpy ((char *) data + needed, string_overrun_cookie,
-	  GC_STRING_OVERRUN_COOKIE_SIZE);
-#endif
-
-  /* Note that Faset may call to this function when S has already data
-     assigned.  In this case, mark data as free by setting it's string
-     back-pointer to null, and record the size of the data in it.  */
-  if (old_data)
-    {
-      SDATA_NBYTES (old_data) = old_nbytes;
-      old_data->string = NULL;
-    }
-
-  consing_since_gc += needed;
-}
-
-
-/* Sweep and compact strings.  */
-
-NO_INLINE /* For better stack traces */
-static void
-sweep_strings (void)
-{
-  struct string_block *b, *next;
-  struct string_block *live_blocks = NULL;
-
-  string_free_list = NULL;
-  total_strings = total_free_strings = 0;
-  total_string_bytes = 0;
-
-  /* Scan strings_blocks, free Lisp_Strings that aren't marked.  */
-  for (b = string_blocks; b; b = next)
-    {
-      int i, nfree = 0;
-      struct Lisp_String *free_list_before = string_free_list;
-
-      next = b->next;
-
-      for (i = 0; i < STRING_BLOCK_SIZE; ++i)
-	{
-	  struct Lisp_String *s = b->strings + i;
-
-	  if (s->u.s.data)
-	    {
-	      /* String was not on free-list before.  */
-	      if (STRING_MARKED_P (s))
-		{
-		  /* String is live; unmark it and its intervals.  */
-		  UNMARK_STRING (s);
-
-		  /* Do not use string_(set|get)_intervals here.  */
-		  s->u.s.intervals = balance_intervals (s->u.s.intervals);
-
-		  ++total_strings;
-		  total_string_bytes += STRING_BYTES (s);
-		}
-	      else
-		{
-		  /* String is dead.  Put it on the free-list.  */
-		  sdata *data = SDATA_OF_STRING (s);
-
-		  /* Save the size of S in its sdata so that we know
-		     how large that is.  Reset the sdata's string
-		     back-pointer so that we know it's free.  */
-#ifdef GC_CHECK_STRING_BYTES
-		  if (string_bytes (s) != SDATA_NBYTES (data))
-		    emacs_abort ();
-#else
-		  data->n.nbytes = STRING_BYTES (s);
-#endif
-		  data->string = NULL;
-
-		  /* Reset the strings's `data' member so that we
-		     know it's free.  */
-		  s->u.s.data = NULL;
-
-		  /* Put the string on the free-list.  */
-		  NEXT_FREE_LISP_STRING (s) = string_free_list;
-		  string_free_list = ptr_bounds_clip (s, sizeof *s);
-		  ++nfree;
-		}
-	    }
-	  else
-	    {
-	      /* S was on the free-list before.  Put it there again.  */
-	      NEXT_FREE_LISP_STRING (s) = string_free_list;
-	      string_free_list = ptr_bounds_clip (s, sizeof *s);
-	      ++nfree;
-	    }
-	}
-
-      /* Free blocks that contain free Lisp_Strings only, except
-	 the first two of them.  */
-      if (nfree == STRING_BLOCK_SIZE
-	  && total_free_strings > STRING_BLOCK_SIZE)
-	{
-	  lisp_free (b);
-	  string_free_list = free_list_before;
-	}
-      else
-	{
-	  total_free_strings += nfree;
-	  b->next = live_blocks;
-	  live_blocks = b;
-	}
-    }
-
-  check_string_free_list ();
-
-  string_blocks = live_blocks;
-  free_large_strings ();
-  compact_small_strings ();
-
-  check_string_free_list ();
-}
-
-
-/* Free dead large strings.  */
-
-static void
-free_large_strings (void)
-{
-  struct sblock *b, *next;
-  struct sblock *live_blocks = NULL;
-
-  for (b = large_sblocks; b; b = next)
-    {
-      next = b->next;
-
-      if (b->data[0].string == NULL)
-	lisp_free (b);
-      else
-	{
-	  b->next = live_blocks;
-	  live_blocks = b;
-	}
-    }
-
-  large_sblocks = live_blocks;
-}
-
-
-/* Compact data of small strings.  Free sblocks that don't contain
-   data of live strings after compaction.  */
-
-static void
-compact_small_strings (void)
-{
-  /* TB is the sblock we copy to, TO is the sdata within TB we copy
-     to, and TB_END is the end of TB.  */
-  struct sblock *tb = oldest_sblock;
-  if (tb)
-    {
-      sdata *tb_end = (sdata *) ((char *) tb + SBLOCK_SIZE);
-      sdata *to = tb->data;
-
-      /* Step through the blocks from the oldest to the youngest.  We
-	 expect that old blocks will stabilize over time, so that less
-	 copying will happen this way.  */
-      struct sblock *b = tb;
-      do
-	{
-	  sdata *end = b->next_free;
-	  eassert ((char *) end <= (char *) b + SBLOCK_SIZE);
-
-	  for (sdata *from = b->data; from < end; )
-	    {
-	      /* Compute the next FROM here because copying below may
-		 overwrite data we need to compute it.  */
-	      ptrdiff_t nbytes;
-	      struct Lisp_String *s = from->string;
-
-#ifdef GC_CHECK_STRING_BYTES
-	      /* Check that the string size recorded in the string is the
-		 same as the one recorded in the sdata structure.  */
-	      if (s && string_bytes (s) != SDATA_NBYTES (from))
-		emacs_abort ();
-#endif /* GC_CHECK_STRING_BYTES */
-
-	      nbytes = s ? STRING_BYTES (s) : SDATA_NBYTES (from);
-	      eassert (nbytes <= LARGE_STRING_BYTES);
-
-	      ptrdiff_t size = SDATA_SIZE (nbytes);
-	      sdata *from_end = (sdata *) ((char *) from
-					   + size + GC_STRING_EXTRA);
-
-#ifdef GC_CHECK_STRING_OVERRUN
-	      if (memcmp (string_overrun_cookie,
-			  (char *) from_end - GC_STRING_OVERRUN_COOKIE_SIZE,
-			  GC_STRING_OVERRUN_COOKIE_SIZE))
-		emacs_abort ();
-#endif
-
-	      /* Non-NULL S means it's alive.  Copy its data.  */
-	      if (s)
-		{
-		  /* If TB is full, proceed with the next sblock.  */
-		  sdata *to_end = (sdata *) ((char *) to
-					     + size + GC_STRING_EXTRA);
-		  if (to_end > tb_end)
-		    {
-		      tb->next_free = to;
-		      tb = tb->next;
-		      tb_end = (sdata *) ((char *) tb + SBLOCK_SIZE);
-		      to = tb->data;
-		      to_end = (sdata *) ((char *) to + size + GC_STRING_EXTRA);
-		    }
-
-		  /* Copy, and update the string's `data' pointer.  */
-		  if (from != to)
-		    {
-		      eassert (tb != b || to < from);
-		      memmove (to, from, size + GC_STRING_EXTRA);
-		      to->string->u.s.data
-			= ptr_bounds_clip (SDATA_DATA (to), nbytes + 1);
-		    }
-
-		  /* Advance past the sdata we copied to.  */
-		  to = to_end;
-		}
-	      from = from_end;
-	    }
-	  b = b->next;
-	}
-      while (b);
-
-      /* The rest of the sblocks following TB don't contain live data, so
-	 we can free them.  */
-      for (b = tb->next; b; )
-	{
-	  struct sblock *next = b->next;
-	  lisp_free (b);
-	  b = next;
-	}
-
-      tb->next_free = to;
-      tb->next = NULL;
-    }
-
-  current_sblock = tb;
-}
-
 void
 string_overflow (void)
 {
@@ -2478,44 +1790,6 @@ make_specified_string (const char *contents,
 }
 
 
-/* Return a unibyte Lisp_String set up to hold LENGTH characters
-   occupying LENGTH bytes.  */
-
-Lisp_Object
-make_uninit_string (EMACS_INT length)
-{
-  Lisp_Object val;
-
-  if (!length)
-    return empty_unibyte_string;
-  val = make_uninit_multibyte_string (length, length);
-  STRING_SET_UNIBYTE (val);
-  return val;
-}
-
-
-/* Return a multibyte Lisp_String set up to hold NCHARS characters
-   which occupy NBYTES bytes.  */
-
-Lisp_Object
-make_uninit_multibyte_string (EMACS_INT nchars, EMACS_INT nbytes)
-{
-  Lisp_Object string;
-  struct Lisp_String *s;
-
-  if (nchars < 0)
-    emacs_abort ();
-  if (!nbytes)
-    return empty_multibyte_string;
-
-  s = allocate_string ();
-  s->u.s.intervals = NULL;
-  allocate_string_data (s, nchars, nbytes);
-  XSETSTRING (string, s);
-  string_chars_consed += nbytes;
-  return string;
-}
-
 /* Print arguments to BUF according to a FORMAT, then return
    a Lisp_String initialized with the data from BUF.  */
 
@@ -2533,227 +1807,10 @@ make_formatted_string (char *buf, const char *format, ...)
 
 
 /***********************************************************************
-			   Float Allocation
- ***********************************************************************/
-
-/* We store float cells inside of float_blocks, allocating a new
-   float_block with malloc whenever necessary.  Float cells reclaimed
-   by GC are put on a free list to be reallocated before allocating
-   any new float cells from the latest float_block.  */
-
-#define FLOAT_BLOCK_SIZE					\
-  (((BLOCK_BYTES - sizeof (struct float_block *)		\
-     /* The compiler might add padding at the end.  */		\
-     - (sizeof (struct Lisp_Float) - sizeof (bits_word))) * CHAR_BIT) \
-   / (sizeof (struct Lisp_Float) * CHAR_BIT + 1))
-
-#define GETMARKBIT(block,n)				\
-  (((block)->gcmarkbits[(n) / BITS_PER_BITS_WORD]	\
-    >> ((n) % BITS_PER_BITS_WORD))			\
-   & 1)
-
-#define SETMARKBIT(block,n)				\
-  ((block)->gcmarkbits[(n) / BITS_PER_BITS_WORD]	\
-   |= (bits_word) 1 << ((n) % BITS_PER_BITS_WORD))
-
-#define UNSETMARKBIT(block,n)				\
-  ((block)->gcmarkbits[(n) / BITS_PER_BITS_WORD]	\
-   &= ~((bits_word) 1 << ((n) % BITS_PER_BITS_WORD)))
-
-#define FLOAT_BLOCK(fptr) \
-  ((struct float_block *) (((uintptr_t) (fptr)) & ~(BLOCK_ALIGN - 1)))
-
-#define FLOAT_INDEX(fptr) \
-  ((((uintptr_t) (fptr)) & (BLOCK_ALIGN - 1)) / sizeof (struct Lisp_Float))
-
-struct float_block
-{
-  /* Place `floats' at the beginning, to ease up FLOAT_INDEX's job.  */
-  struct Lisp_Float floats[FLOAT_BLOCK_SIZE];
-  bits_word gcmarkbits[1 + FLOAT_BLOCK_SIZE / BITS_PER_BITS_WORD];
-  struct float_block *next;
-};
-
-#define FLOAT_MARKED_P(fptr) \
-  GETMARKBIT (FLOAT_BLOCK (fptr), FLOAT_INDEX ((fptr)))
-
-#define FLOAT_MARK(fptr) \
-  SETMARKBIT (FLOAT_BLOCK (fptr), FLOAT_INDEX ((fptr)))
-
-#define FLOAT_UNMARK(fptr) \
-  UNSETMARKBIT (FLOAT_BLOCK (fptr), FLOAT_INDEX ((fptr)))
-
-/* Current float_block.  */
-
-static struct float_block *float_block;
-
-/* Index of first unused Lisp_Float in the current float_block.  */
-
-static int float_block_index = FLOAT_BLOCK_SIZE;
-
-/* Free-list of Lisp_Floats.  */
-
-static struct Lisp_Float *float_free_list;
-
-/* Return a new float object with value FLOAT_VALUE.  */
-
-Lisp_Object
-make_float (double float_value)
-{
-  register Lisp_Object val;
-
-  MALLOC_BLOCK_INPUT;
-
-  if (float_free_list)
-    {
-      /* We use the data field for chaining the free list
-	 so that we won't use the same field that has the mark bit.  */
-      XSETFLOAT (val, float_free_list);
-      float_free_list = float_free_list->u.chain;
-    }
-  else
-    {
-      if (float_block_index == FLOAT_BLOCK_SIZE)
-	{
-	  struct float_block *new
-	    = lisp_align_malloc (sizeof *new, MEM_TYPE_FLOAT);
-	  new->next = float_block;
-	  memset (new->gcmarkbits, 0, sizeof new->gcmarkbits);
-	  float_block = new;
-	  float_block_index = 0;
-	  total_free_floats += FLOAT_BLOCK_SIZE;
-	}
-      XSETFLOAT (val, &float_block->floats[float_block_index]);
-      float_block_index++;
-    }
-
-  MALLOC_UNBLOCK_INPUT;
-
-  XFLOAT_INIT (val, float_value);
-  eassert (!FLOAT_MARKED_P (XFLOAT (val)));
-  consing_since_gc += sizeof (struct Lisp_Float);
-  floats_consed++;
-  total_free_floats--;
-  return val;
-}
-
-
-
-/***********************************************************************
-			   Cons Allocation
+		       Float and Cons Allocation
  ***********************************************************************/
 
-/* We store cons cells inside of cons_blocks, allocating a new
-   cons_block with malloc whenever necessary.  Cons cells reclaimed by
-   GC are put on a free list to be reallocated before allocating
-   any new cons cells from the latest cons_block.  */
-
-#define CONS_BLOCK_SIZE						\
-  (((BLOCK_BYTES - sizeof (struct cons_block *)			\
-     /* The compiler might add padding at the end.  */		\
-     - (sizeof (struct Lisp_Cons) - sizeof (bits_word))) * CHAR_BIT)	\
-   / (sizeof (struct Lisp_Cons) * CHAR_BIT + 1))
-
-#define CONS_BLOCK(fptr) \
-  ((struct cons_block *) ((uintptr_t) (fptr) & ~(BLOCK_ALIGN - 1)))
-
-#define CONS_INDEX(fptr) \
-  (((uintptr_t) (fptr) & (BLOCK_ALIGN - 1)) / sizeof (struct Lisp_Cons))
-
-struct cons_block
-{
-  /* Place `conses' at the beginning, to ease up CONS_INDEX's job.  */
-  struct Lisp_Cons conses[CONS_BLOCK_SIZE];
-  bits_word gcmarkbits[1 + CONS_BLOCK_SIZE / BITS_PER_BITS_WORD];
-  struct cons_block *next;
-};
-
-#define CONS_MARKED_P(fptr) \
-  GETMARKBIT (CONS_BLOCK (fptr), CONS_INDEX ((fptr)))
-
-#define CONS_MARK(fptr) \
-  SETMARKBIT (CONS_BLOCK (fptr), CONS_INDEX ((fptr)))
-
-#define CONS_UNMARK(fptr) \
-  UNSETMARKBIT (CONS_BLOCK (fptr), CONS_INDEX ((fptr)))
-
-/* Current cons_block.  */
-
-static struct cons_block *cons_block;
-
-/* Index of first unused Lisp_Cons in the current block.  */
-
-static int cons_block_index = CONS_BLOCK_SIZE;
-
-/* Free-list of Lisp_Cons structures.  */
-
-static struct Lisp_Cons *cons_free_list;
-
-/* Explicitly free a cons cell by putting it on the free-list.  */
-
-void
-free_cons (struct Lisp_Cons *ptr)
-{
-  ptr->u.s.u.chain = cons_free_list;
-  ptr->u.s.car = Vdead;
-  cons_free_list = ptr;
-  consing_since_gc -= sizeof *ptr;
-  total_free_conses++;
-}
-
-DEFUN ("cons", Fcons, Scons, 2, 2, 0,
-       doc: /* Create a new cons, give it CAR and CDR as components, and return it.  */)
-  (Lisp_Object car, Lisp_Object cdr)
-{
-  register Lisp_Object val;
-
-  MALLOC_BLOCK_INPUT;
-
-  if (cons_free_list)
-    {
-      /* We use the cdr for chaining the free list
-	 so that we won't use the same field that has the mark bit.  */
-      XSETCONS (val, cons_free_list);
-      cons_free_list = cons_free_list->u.s.u.chain;
-    }
-  else
-    {
-      if (cons_block_index == CONS_BLOCK_SIZE)
-	{
-	  struct cons_block *new
-	    = lisp_align_malloc (sizeof *new, MEM_TYPE_CONS);
-	  memset (new->gcmarkbits, 0, sizeof new->gcmarkbits);
-	  new->next = cons_block;
-	  cons_block = new;
-	  cons_block_index = 0;
-	  total_free_conses += CONS_BLOCK_SIZE;
-	}
-      XSETCONS (val, &cons_block->conses[cons_block_index]);
-      cons_block_index++;
-    }
-
-  MALLOC_UNBLOCK_INPUT;
-
-  XSETCAR (val, car);
-  XSETCDR (val, cdr);
-  eassert (!CONS_MARKED_P (XCONS (val)));
-  consing_since_gc += sizeof (struct Lisp_Cons);
-  total_free_conses--;
-  cons_cells_consed++;
-  return val;
-}
-
-#ifdef GC_CHECK_CONS_LIST
-/* Get an error now if there's any junk in the cons free list.  */
-void
-check_cons_list (void)
-{
-  struct Lisp_Cons *tail = cons_free_list;
-
-  while (tail)
-    tail = tail->u.s.u.chain;
-}
-#endif
+/* Floats and conses are allocated in block_alloc.rs.  */
 
 /* Make a list of 1, 2, 3, 4 or 5 specified objects.  */
 
@@ -2964,11 +2021,11 @@ Lisp_Object zero_vector;
 
 /* Number of live vectors.  */
 
-static EMACS_INT total_vectors;
+EMACS_INT total_vectors;
 
 /* Total size of live and free vectors, in Lisp_Object units.  */
 
-static EMACS_INT total_vector_slots, total_free_vector_slots;
+EMACS_INT total_vector_slots, total_free_vector_slots;
 
 /* Common shortcut to setup vector on a free list.  */
 
@@ -3817,146 +2874,25 @@ make_event_array (ptrdiff_t nargs, Lisp_Object *args)
       }
 
     return result;
-  }
-}
-
-#ifdef HAVE_MODULES
-/* Create a new module user ptr object.  */
-Lisp_Object
-make_user_ptr (void (*finalizer) (void *), void *p)
-{
-  Lisp_Object obj;
-  struct Lisp_User_Ptr *uptr;
-
-  obj = allocate_misc (Lisp_Misc_User_Ptr);
-  uptr = XUSER_PTR (obj);
-  uptr->finalizer = finalizer;
-  uptr->p = p;
-  return obj;
-}
-#endif
-
-static void
-init_finalizer_list (struct Lisp_Finalizer *head)
-{
-  head->prev = head->next = head;
-}
-
-/* Insert FINALIZER before ELEMENT.  */
-
-static void
-finalizer_insert (struct Lisp_Finalizer *element,
-                  struct Lisp_Finalizer *finalizer)
-{
-  eassert (finalizer->prev == NULL);
-  eassert (finalizer->next == NULL);
-  finalizer->next = element;
-  finalizer->prev = element->prev;
-  finalizer->prev->next = finalizer;
-  element->prev = finalizer;
-}
-
-static void
-unchain_finalizer (struct Lisp_Finalizer *finalizer)
-{
-  if (finalizer->prev != NULL)
-    {
-      eassert (finalizer->next != NULL);
-      finalizer->prev->next = finalizer->next;
-      finalizer->next->prev = finalizer->prev;
-      finalizer->prev = finalizer->next = NULL;
-    }
-}
-
-static void
-mark_finalizer_list (struct Lisp_Finalizer *head)
-{
-  for (struct Lisp_Finalizer *finalizer = head->next;
-       finalizer != head;
-       finalizer = finalizer->next)
-    {
-      finalizer->base.gcmarkbit = true;
-      mark_object (finalizer->function);
-    }
-}
-
-/* Move doomed finalizers to list DEST from list SRC.  A doomed
-   finalizer is one that is not GC-reachable and whose
-   finalizer->function is non-nil.  */
-
-static void
-queue_doomed_finalizers (struct Lisp_Finalizer *dest,
-                         struct Lisp_Finalizer *src)
-{
-  struct Lisp_Finalizer *finalizer = src->next;
-  while (finalizer != src)
-    {
-      struct Lisp_Finalizer *next = finalizer->next;
-      if (!finalizer->base.gcmarkbit && !NILP (finalizer->function))
-        {
-          unchain_finalizer (finalizer);
-          finalizer_insert (dest, finalizer);
-        }
-
-      finalizer = next;
-    }
-}
-
-static Lisp_Object
-run_finalizer_handler (Lisp_Object args)
-{
-  add_to_log ("finalizer failed: %S", args);
-  return Qnil;
-}
-
-static void
-run_finalizer_function (Lisp_Object function)
-{
-  ptrdiff_t count = SPECPDL_INDEX ();
-
-  specbind (Qinhibit_quit, Qt);
-  internal_condition_case_1 (call0, function, Qt, run_finalizer_handler);
-  unbind_to (count, Qnil);
-}
-
-static void
-run_finalizers (struct Lisp_Finalizer *finalizers)
-{
-  struct Lisp_Finalizer *finalizer;
-  Lisp_Object function;
-
-  while (finalizers->next != finalizers)
-    {
-      finalizer = finalizers->next;
-      eassert (finalizer->base.type == Lisp_Misc_Finalizer);
-      unchain_finalizer (finalizer);
-      function = finalizer->function;
-      if (!NILP (function))
-	{
-	  finalizer->function = Qnil;
-	  run_finalizer_function (function);
-	}
-    }
+  }
 }
 
-DEFUN ("make-finalizer", Fmake_finalizer, Smake_finalizer, 1, 1, 0,
-       doc: /* Make a finalizer that will run FUNCTION.
-FUNCTION will be called after garbage collection when the returned
-finalizer object becomes unreachable.  If the finalizer object is
-reachable only through references from finalizer objects, it does not
-count as reachable for the purpose of deciding whether to run
-FUNCTION.  FUNCTION will be run once per finalizer object.  */)
-  (Lisp_Object function)
-{
-  Lisp_Object val = allocate_misc (Lisp_Misc_Finalizer);
-  struct Lisp_Finalizer *finalizer = XFINALIZER (val);
-  finalizer->function = function;
-  finalizer->prev = finalizer->next = NULL;
-  finalizer_insert (&finalizers, finalizer);
-  return val;
+#ifdef HAVE_MODULES
+/* Create a new module user ptr object.  */
+Lisp_Object
+make_user_ptr (void (*finalizer) (void *), void *p)
+{
+  Lisp_Object obj;
+  struct Lisp_User_Ptr *uptr;
+
+  obj = allocate_misc (Lisp_Misc_User_Ptr);
+  uptr = XUSER_PTR (obj);
+  uptr->finalizer = finalizer;
+  uptr->p = p;
+  return obj;
 }
+#endif
 
-
 /************************************************************************
 			   Memory Full Handling
  ************************************************************************/
@@ -3994,7 +2930,7 @@ memory_full (size_t nbytes)
 
       Vmemory_full = Qt;
 
-      memory_full_cons_threshold = sizeof (struct cons_block);
+      memory_full_cons_threshold = cons_block_bytes;
 
       /* The first time we get here, free the spare memory.  */
       for (i = 0; i < ARRAYELTS (spare_memory); i++)
@@ -4029,22 +2965,22 @@ refill_memory_reserve (void)
   if (spare_memory[0] == 0)
     spare_memory[0] = malloc (SPARE_MEMORY);
   if (spare_memory[1] == 0)
-    spare_memory[1] = lisp_align_malloc (sizeof (struct cons_block),
+    spare_memory[1] = lisp_align_malloc (cons_block_bytes,
 						  MEM_TYPE_SPARE);
   if (spare_memory[2] == 0)
-    spare_memory[2] = lisp_align_malloc (sizeof (struct cons_block),
+    spare_memory[2] = lisp_align_malloc (cons_block_bytes,
 					 MEM_TYPE_SPARE);
   if (spare_memory[3] == 0)
-    spare_memory[3] = lisp_align_malloc (sizeof (struct cons_block),
+    spare_memory[3] = lisp_align_malloc (cons_block_bytes,
 					 MEM_TYPE_SPARE);
   if (spare_memory[4] == 0)
-    spare_memory[4] = lisp_align_malloc (sizeof (struct cons_block),
+    spare_memory[4] = lisp_align_malloc (cons_block_bytes,
 					 MEM_TYPE_SPARE);
   if (spare_memory[5] == 0)
-    spare_memory[5] = lisp_malloc (sizeof (struct string_block),
+    spare_memory[5] = lisp_malloc (string_block_bytes,
 				   MEM_TYPE_SPARE);
   if (spare_memory[6] == 0)
-    spare_memory[6] = lisp_malloc (sizeof (struct string_block),
+    spare_memory[6] = lisp_malloc (string_block_bytes,
 				   MEM_TYPE_SPARE);
   if (spare_memory[0] && spare_memory[1] && spare_memory[5])
     Vmemory_full = Qnil;
@@ -4454,21 +3390,7 @@ static Lisp_Object
 live_string_holding (struct mem_node *m, void *p)
 {
   if (m->type == MEM_TYPE_STRING)
-    {
-      struct string_block *b = m->start;
-      char *cp = p;
-      ptrdiff_t offset = cp - (char *) &b->strings[0];
-
-      /* P must point into a Lisp_String structure, and it
-	 must not be on the free-list.  */
-      if (0 <= offset && offset < STRING_BLOCK_SIZE * sizeof b->strings[0])
-	{
-	  cp = ptr_bounds_copy (cp, b);
-	  struct Lisp_String *s = p = cp -= offset % sizeof b->strings[0];
-	  if (s->u.s.data)
-	    return make_lisp_ptr (s, Lisp_String);
-	}
-    }
+    return live_string_in_block (m->start, p);
   return Qnil;
 }
 
@@ -4486,24 +3408,7 @@ static Lisp_Object
 live_cons_holding (struct mem_node *m, void *p)
 {
   if (m->type == MEM_TYPE_CONS)
-    {
-      struct cons_block *b = m->start;
-      char *cp = p;
-      ptrdiff_t offset = cp - (char *) &b->conses[0];
-
-      /* P must point into a Lisp_Cons, not be
-	 one of the unused cells in the current cons block,
-	 and not be on the free-list.  */
-      if (0 <= offset && offset < CONS_BLOCK_SIZE * sizeof b->conses[0]
-	  && (b != cons_block
-	      || offset / sizeof b->conses[0] < cons_block_index))
-	{
-	  cp = ptr_bounds_copy (cp, b);
-	  struct Lisp_Cons *s = p = cp -= offset % sizeof b->conses[0];
-	  if (!EQ (s->u.s.car, Vdead))
-	    return make_lisp_ptr (s, Lisp_Cons);
-	}
-    }
+    return live_cons_in_block (m->start, p);
   return Qnil;
 }
 
@@ -4556,22 +3461,7 @@ live_symbol_p (struct mem_node *m, void *p)
 static bool
 live_float_p (struct mem_node *m, void *p)
 {
-  if (m->type == MEM_TYPE_FLOAT)
-    {
-      struct float_block *b = m->start;
-      char *cp = p;
-      ptrdiff_t offset = cp - (char *) &b->floats[0];
-
-      /* P must point to the start of a Lisp_Float and not be
-	 one of the unused cells in the current float block.  */
-      return (offset >= 0
-	      && offset % sizeof b->floats[0] == 0
-	      && offset < (FLOAT_BLOCK_SIZE * sizeof b->floats[0])
-	      && (b != float_block
-		  || offset / sizeof b->floats[0] < float_block_index));
-    }
-  else
-    return 0;
+  return m->type == MEM_TYPE_FLOAT && live_float_in_block (m->start, p);
 }
 
 
@@ -5610,30 +4500,23 @@ inhibit_garbage_collection (void)
   return count;
 }
 
-/* Used to avoid possible overflows when
-   converting from C to Lisp integers.  */
+/* Return the entry of the malloc heap in the list `garbage-collect'
+   returns, (heap 1024 USED FREE) with sizes in kilobytes, or nil if
+   malloc doesn't tell.  */
 
 Lisp_Object
-bounded_number (EMACS_INT number)
-{
-  return make_number (min (MOST_POSITIVE_FIXNUM, number));
-}
-
-/* Calculate total bytes of live objects.  */
-
-static size_t
-total_bytes_of_live_objects (void)
+malloc_heap_statistics (void)
 {
-  size_t tot = 0;
-  tot += total_conses  * sizeof (struct Lisp_Cons);
-  tot += total_symbols * sizeof (struct Lisp_Symbol);
-  tot += total_markers * sizeof (union Lisp_Misc);
-  tot += total_string_bytes;
-  tot += total_vector_slots * word_size;
-  tot += total_floats  * sizeof (struct Lisp_Float);
-  tot += total_intervals * sizeof (struct interval);
-  tot += total_strings * sizeof (struct Lisp_String);
-  return tot;
+#ifdef DOUG_LEA_MALLOC
+  struct mallinfo info = mallinfo ();
+  return list4 (Qheap, make_number (1024),
+		make_number (min (MOST_POSITIVE_FIXNUM,
+				  (info.uordblks + 1023) >> 10)),
+		make_number (min (MOST_POSITIVE_FIXNUM,
+				  (info.fordblks + 1023) >> 10)));
+#else
+  return Qnil;
+#endif
 }
 
 #ifdef HAVE_WINDOW_SYSTEM
@@ -5883,6 +4766,7 @@ garbage_collect_1 (void *end)
 
   for (i = 0; i < staticidx; i++)
     mark_object (*staticvec[i]);
+  mark_rust_roots ();
 
   mark_pinned_objects ();
   mark_pinned_symbols ();
@@ -5924,8 +4808,7 @@ garbage_collect_1 (void *end)
      unreachable except for references from their associated functions
      and from other finalizers.  */
 
-  queue_doomed_finalizers (&doomed_finalizers, &finalizers);
-  mark_finalizer_list (&doomed_finalizers);
+  queue_doomed_finalizers ();
 
   gc_sweep ();
 
@@ -5939,24 +4822,7 @@ garbage_collect_1 (void *end)
 
   unblock_input ();
 
-  consing_since_gc = 0;
-  if (gc_cons_threshold < GC_DEFAULT_THRESHOLD / 10)
-    gc_cons_threshold = GC_DEFAULT_THRESHOLD / 10;
-
-  gc_relative_threshold = 0;
-  if (FLOATP (Vgc_cons_percentage))
-    { /* Set gc_cons_combined_threshold.  */
-      double tot = total_bytes_of_live_objects ();
-
-      tot *= XFLOAT_DATA (Vgc_cons_percentage);
-      if (0 < tot)
-	{
-	  if (tot < TYPE_MAXIMUM (EMACS_INT))
-	    gc_relative_threshold = tot;
-	  else
-	    gc_relative_threshold = TYPE_MAXIMUM (EMACS_INT);
-	}
-    }
+  update_gc_thresholds ();
 
   if (garbage_collection_messages && NILP (Vmemory_full))
     {
@@ -5968,46 +4834,10 @@ garbage_collect_1 (void *end)
 
   unbind_to (count, Qnil);
 
-  Lisp_Object total[] = {
-    list4 (Qconses, make_number (sizeof (struct Lisp_Cons)),
-	   bounded_number (total_conses),
-	   bounded_number (total_free_conses)),
-    list4 (Qsymbols, make_number (sizeof (struct Lisp_Symbol)),
-	   bounded_number (total_symbols),
-	   bounded_number (total_free_symbols)),
-    list4 (Qmiscs, make_number (sizeof (union Lisp_Misc)),
-	   bounded_number (total_markers),
-	   bounded_number (total_free_markers)),
-    list4 (Qstrings, make_number (sizeof (struct Lisp_String)),
-	   bounded_number (total_strings),
-	   bounded_number (total_free_strings)),
-    list3 (Qstring_bytes, make_number (1),
-	   bounded_number (total_string_bytes)),
-    list3 (Qvectors,
-	   make_number (header_size + sizeof (Lisp_Object)),
-	   bounded_number (total_vectors)),
-    list4 (Qvector_slots, make_number (word_size),
-	   bounded_number (total_vector_slots),
-	   bounded_number (total_free_vector_slots)),
-    list4 (Qfloats, make_number (sizeof (struct Lisp_Float)),
-	   bounded_number (total_floats),
-	   bounded_number (total_free_floats)),
-    list4 (Qintervals, make_number (sizeof (struct interval)),
-	   bounded_number (total_intervals),
-	   bounded_number (total_free_intervals)),
-    list3 (Qbuffers, make_number (sizeof (struct buffer)),
-	   bounded_number (total_buffers)),
-
-#ifdef DOUG_LEA_MALLOC
-    list4 (Qheap, make_number (1024),
-	   bounded_number ((mallinfo ().uordblks + 1023) >> 10),
-	   bounded_number ((mallinfo ().fordblks + 1023) >> 10)),
-#endif
-  };
-  retval = CALLMANY (Flist, total);
+  retval = gc_statistics ();
 
   /* GC is complete: now we can run our finalizer callbacks.  */
-  run_finalizers (&doomed_finalizers);
+  run_finalizers ();
 
   if (!NILP (Vpost_gc_hook))
     {
@@ -6088,19 +4918,11 @@ mark_glyph_matrix (struct glyph_matrix *matrix)
       }
 }
 
-/* Mark reference to a Lisp_Object.
-   If the object referred to has not been seen yet, recursively mark
-   all the references contained in it.  */
-
-#define LAST_MARKED_SIZE 500
-Lisp_Object last_marked[LAST_MARKED_SIZE] EXTERNALLY_VISIBLE;
-static int last_marked_index;
-
-/* For debugging--call abort when we cdr down this many
-   links of a list, in mark_object.  In debugging,
-   the call to abort will hit a breakpoint.
-   Normally this is zero and the check never goes off.  */
-ptrdiff_t mark_object_loop_halt EXTERNALLY_VISIBLE;
+/* The mark phase proper, mark_object, is in rust gc.rs.  It keeps its
+   own stack of the objects left to visit instead of recursing, and
+   calls the functions below for the objects whose mark bits live in
+   the blocks of this file, or whose marking is special.  Those that
+   mark other objects do so by calling mark_object.  */
 
 static void
 mark_vectorlike (struct Lisp_Vector *ptr)
@@ -6121,48 +4943,6 @@ mark_vectorlike (struct Lisp_Vector *ptr)
     mark_object (ptr->contents[i]);
 }
 
-/* Like mark_vectorlike but optimized for char-tables (and
-   sub-char-tables) assuming that the contents are mostly integers or
-   symbols.  */
-
-static void
-mark_char_table (struct Lisp_Vector *ptr, enum pvec_type pvectype)
-{
-  int size = ptr->header.size & PSEUDOVECTOR_SIZE_MASK;
-  /* Consult the Lisp_Sub_Char_Table layout before changing this.  */
-  int i, idx = (pvectype == PVEC_SUB_CHAR_TABLE ? SUB_CHAR_TABLE_OFFSET : 0);
-
-  eassert (!VECTOR_MARKED_P (ptr));
-  VECTOR_MARK (ptr);
-  for (i = idx; i < size; i++)
-    {
-      Lisp_Object val = ptr->contents[i];
-
-      if (INTEGERP (val) || (SYMBOLP (val) && XSYMBOL (val)->u.s.gcmarkbit))
-	continue;
-      if (SUB_CHAR_TABLE_P (val))
-	{
-	  if (! VECTOR_MARKED_P (XVECTOR (val)))
-	    mark_char_table (XVECTOR (val), PVEC_SUB_CHAR_TABLE);
-	}
-      else
-	mark_object (val);
-    }
-}
-
-NO_INLINE /* To reduce stack depth in mark_object.  */
-static Lisp_Object
-mark_compiled (struct Lisp_Vector *ptr)
-{
-  int i, size = ptr->header.size & PSEUDOVECTOR_SIZE_MASK;
-
-  VECTOR_MARK (ptr);
-  for (i = 0; i < size; i++)
-    if (i != COMPILED_CONSTANTS)
-      mark_object (ptr->contents[i]);
-  return size > COMPILED_CONSTANTS ? ptr->contents[COMPILED_CONSTANTS] : Qnil;
-}
-
 /* Mark the chain of overlays starting at PTR.  */
 
 static void
@@ -6180,7 +4960,7 @@ mark_overlay (struct Lisp_Overlay *ptr)
 
 /* Mark Lisp_Objects and special pointers in BUFFER.  */
 
-static void
+void
 mark_buffer (struct buffer *buffer)
 {
   /* This is handled much like other pseudovectors...  */
@@ -6204,7 +4984,6 @@ mark_buffer (struct buffer *buffer)
 
 /* Mark Lisp faces in the face cache C.  */
 
-NO_INLINE /* To reduce stack depth in mark_object.  */
 static void
 mark_face_cache (struct face_cache *c)
 {
@@ -6227,8 +5006,9 @@ mark_face_cache (struct face_cache *c)
     }
 }
 
-NO_INLINE /* To reduce stack depth in mark_object.  */
-static void
+/* Mark the buffer-local value of the symbol PTR.  */
+
+void
 mark_localized_symbol (struct Lisp_Symbol *ptr)
 {
   struct Lisp_Buffer_Local_Value *blv = SYMBOL_BLV (ptr);
@@ -6245,7 +5025,6 @@ mark_localized_symbol (struct Lisp_Symbol *ptr)
   mark_object (blv->defcell);
 }
 
-NO_INLINE /* To reduce stack depth in mark_object.  */
 static void
 mark_save_value (struct Lisp_Save_Value *ptr)
 {
@@ -6277,7 +5056,7 @@ mark_discard_killed_buffers (Lisp_Object list)
 {
   Lisp_Object tail, *prev = &list;
 
-  for (tail = list; CONSP (tail) && !CONS_MARKED_P (XCONS (tail));
+  for (tail = list; CONSP (tail) && !cons_marked_p (tail);
        tail = XCDR (tail))
     {
       Lisp_Object tem = XCAR (tail);
@@ -6287,7 +5066,7 @@ mark_discard_killed_buffers (Lisp_Object list)
 	*prev = XCDR (tail);
       else
 	{
-	  CONS_MARK (XCONS (tail));
+	  gc_mark_cons (tail);
 	  mark_object (XCAR (tail));
 	  prev = xcdr_addr (tail);
 	}
@@ -6296,351 +5075,105 @@ mark_discard_killed_buffers (Lisp_Object list)
   return list;
 }
 
-/* Determine type of generic Lisp_Object and mark it accordingly.
+/* Return true if OBJ is in pure storage, where nothing is marked.  */
 
-   This function implements a straightforward depth-first marking
-   algorithm and so the recursion depth may be very high (a few
-   tens of thousands is not uncommon).  To minimize stack usage,
-   a few cold paths are moved out to NO_INLINE functions above.
-   In general, inlining them doesn't help you to gain more speed.  */
-
-void
-mark_object (Lisp_Object arg)
+bool
+pure_object_p (Lisp_Object obj)
 {
-  register Lisp_Object obj;
-  void *po;
-#if GC_CHECK_MARKED_OBJECTS
-  struct mem_node *m;
-#endif
-  ptrdiff_t cdr_count = 0;
-
-  obj = arg;
- loop:
-
-  po = XPNTR (obj);
-  if (PURE_P (po))
-    return;
-
-  last_marked[last_marked_index++] = obj;
-  if (last_marked_index == LAST_MARKED_SIZE)
-    last_marked_index = 0;
-
-  /* Perform some sanity checks on the objects marked here.  Abort if
-     we encounter an object we know is bogus.  This increases GC time
-     by ~80%.  */
-#if GC_CHECK_MARKED_OBJECTS
-
-  /* Check that the object pointed to by PO is known to be a Lisp
-     structure allocated from the heap.  */
-#define CHECK_ALLOCATED()			\
-  do {						\
-    m = mem_find (po);				\
-    if (m == MEM_NIL)				\
-      emacs_abort ();				\
-  } while (0)
-
-  /* Check that the object pointed to by PO is live, using predicate
-     function LIVEP.  */
-#define CHECK_LIVE(LIVEP)			\
-  do {						\
-    if (!LIVEP (m, po))				\
-      emacs_abort ();				\
-  } while (0)
-
-  /* Check both of the above conditions, for non-symbols.  */
-#define CHECK_ALLOCATED_AND_LIVE(LIVEP)		\
-  do {						\
-    CHECK_ALLOCATED ();				\
-    CHECK_LIVE (LIVEP);				\
-  } while (0)					\
-
-  /* Check both of the above conditions, for symbols.  */
-#define CHECK_ALLOCATED_AND_LIVE_SYMBOL()	\
-  do {						\
-    if (!c_symbol_p (ptr))			\
-      {						\
-	CHECK_ALLOCATED ();			\
-	CHECK_LIVE (live_symbol_p);		\
-      }						\
-  } while (0)					\
-
-#else /* not GC_CHECK_MARKED_OBJECTS */
-
-#define CHECK_LIVE(LIVEP)			((void) 0)
-#define CHECK_ALLOCATED_AND_LIVE(LIVEP)		((void) 0)
-#define CHECK_ALLOCATED_AND_LIVE_SYMBOL()	((void) 0)
-
-#endif /* not GC_CHECK_MARKED_OBJECTS */
-
-  switch (XTYPE (obj))
-    {
-    case Lisp_String:
-      {
-	register struct Lisp_String *ptr = XSTRING (obj);
-	if (STRING_MARKED_P (ptr))
-	  break;
-	CHECK_ALLOCATED_AND_LIVE (live_string_p);
-	MARK_STRING (ptr);
-	MARK_INTERVAL_TREE (ptr->u.s.intervals);
-#ifdef GC_CHECK_STRING_BYTES
-	/* Check that the string size recorded in the string is the
-	   same as the one recorded in the sdata structure.  */
-	string_bytes (ptr);
-#endif /* GC_CHECK_STRING_BYTES */
-      }
-      break;
-
-    case Lisp_Vectorlike:
-      {
-	register struct Lisp_Vector *ptr = XVECTOR (obj);
-
-	if (VECTOR_MARKED_P (ptr))
-	  break;
-
-#if GC_CHECK_MARKED_OBJECTS
-	m = mem_find (po);
-	if (m == MEM_NIL && !SUBRP (obj) && !main_thread_p (po))
-	  emacs_abort ();
-#endif /* GC_CHECK_MARKED_OBJECTS */
+  return PURE_P (XPNTR (obj));
+}
 
-        enum pvec_type pvectype
-          = PSEUDOVECTOR_TYPE (ptr);
+/* Mark the interval tree rooted in I, unless it is marked already.  */
 
-	if (pvectype != PVEC_SUBR
-	    && pvectype != PVEC_BUFFER
-	    && !main_thread_p (po))
-	  CHECK_LIVE (live_vector_p);
+void
+mark_interval_tree (INTERVAL i)
+{
+  MARK_INTERVAL_TREE (i);
+}
 
-	switch (pvectype)
-	  {
-	  case PVEC_BUFFER:
-#if GC_CHECK_MARKED_OBJECTS
-	    {
-	      struct buffer *b;
-	      FOR_EACH_BUFFER (b)
-		if (b == po)
-		  break;
-	      if (b == NULL)
-		emacs_abort ();
-	    }
-#endif /* GC_CHECK_MARKED_OBJECTS */
-	    mark_buffer ((struct buffer *) ptr);
-	    break;
-
-	  case PVEC_COMPILED:
-	    /* Although we could treat this just like a vector, mark_compiled
-	       returns the COMPILED_CONSTANTS element, which is marked at the
-	       next iteration of goto-loop here.  This is done to avoid a few
-	       recursive calls to mark_object.  */
-	    obj = mark_compiled (ptr);
-	    if (!NILP (obj))
-	      goto loop;
-	    break;
-
-	  case PVEC_FRAME:
-	    {
-	      struct frame *f = (struct frame *) ptr;
+/* Mark the frame F, along with its faces and font.  */
 
-	      mark_vectorlike (ptr);
-	      mark_face_cache (f->face_cache);
+void
+mark_frame (struct frame *f)
+{
+  mark_vectorlike ((struct Lisp_Vector *) f);
+  mark_face_cache (f->face_cache);
 #ifdef HAVE_WINDOW_SYSTEM
-	      if (FRAME_WINDOW_P (f) && FRAME_X_OUTPUT (f))
-		{
-		  struct font *font = FRAME_FONT (f);
+  if (FRAME_WINDOW_P (f) && FRAME_X_OUTPUT (f))
+    {
+      struct font *font = FRAME_FONT (f);
 
-		  if (font && !VECTOR_MARKED_P (font))
-		    mark_vectorlike ((struct Lisp_Vector *) font);
-		}
+      if (font && !VECTOR_MARKED_P (font))
+	mark_vectorlike ((struct Lisp_Vector *) font);
+    }
 #endif
-	    }
-	    break;
-
-	  case PVEC_WINDOW:
-	    {
-	      struct window *w = (struct window *) ptr;
-
-	      mark_vectorlike (ptr);
-
-	      /* Mark glyph matrices, if any.  Marking window
-		 matrices is sufficient because frame matrices
-		 use the same glyph memory.  */
-	      if (w->current_matrix)
-		{
-		  mark_glyph_matrix (w->current_matrix);
-		  mark_glyph_matrix (w->desired_matrix);
-		}
+}
 
-	      /* Filter out killed buffers from both buffer lists
-		 in attempt to help GC to reclaim killed buffers faster.
-		 We can do it elsewhere for live windows, but this is the
-		 best place to do it for dead windows.  */
-	      wset_prev_buffers
-		(w, mark_discard_killed_buffers (w->prev_buffers));
-	      wset_next_buffers
-		(w, mark_discard_killed_buffers (w->next_buffers));
-	    }
-	    break;
+/* Mark the window W, along with its glyph matrices.  */
 
-	  case PVEC_HASH_TABLE:
-	    {
-	      struct Lisp_Hash_Table *h = (struct Lisp_Hash_Table *) ptr;
-
-	      mark_vectorlike (ptr);
-	      mark_object (h->test.name);
-	      mark_object (h->test.user_hash_function);
-	      mark_object (h->test.user_cmp_function);
-	      /* If hash table is not weak, mark all keys and values.
-		 For weak tables, mark only the vector.  */
-	      if (NILP (h->weak))
-		mark_object (h->key_and_value);
-	      else
-		VECTOR_MARK (XVECTOR (h->key_and_value));
-	    }
-	    break;
+void
+mark_window (struct window *w)
+{
+  mark_vectorlike ((struct Lisp_Vector *) w);
 
-	  case PVEC_CHAR_TABLE:
-	  case PVEC_SUB_CHAR_TABLE:
-	    mark_char_table (ptr, (enum pvec_type) pvectype);
-	    break;
+  /* Mark glyph matrices, if any.  Marking window matrices is
+     sufficient because frame matrices use the same glyph memory.  */
+  if (w->current_matrix)
+    {
+      mark_glyph_matrix (w->current_matrix);
+      mark_glyph_matrix (w->desired_matrix);
+    }
 
-	  case PVEC_BOOL_VECTOR:
-	    /* No Lisp_Objects to mark in a bool vector.  */
-	    VECTOR_MARK (ptr);
-	    break;
+  /* Filter out killed buffers from both buffer lists in attempt to
+     help GC to reclaim killed buffers faster.  We can do it elsewhere
+     for live windows, but this is the best place to do it for dead
+     windows.  */
+  wset_prev_buffers (w, mark_discard_killed_buffers (w->prev_buffers));
+  wset_next_buffers (w, mark_discard_killed_buffers (w->next_buffers));
+}
 
-	  case PVEC_SUBR:
-	    break;
+/* Mark the misc object OBJ, unless it is marked already.  */
 
-	  case PVEC_FREE:
-	    emacs_abort ();
+void
+mark_misc (Lisp_Object obj)
+{
+  if (XMISCANY (obj)->gcmarkbit)
+    return;
 
-	  default:
-	    mark_vectorlike (ptr);
-	  }
-      }
+  switch (XMISCTYPE (obj))
+    {
+    case Lisp_Misc_Marker:
+      /* DO NOT mark thru the marker's chain.
+	 The buffer's markers chain does not preserve markers from gc;
+	 instead, markers are removed from the chain when freed by gc.  */
+      XMISCANY (obj)->gcmarkbit = 1;
       break;
 
-    case Lisp_Symbol:
-      {
-	struct Lisp_Symbol *ptr = XSYMBOL (obj);
-      nextsym:
-	if (ptr->u.s.gcmarkbit)
-	  break;
-	CHECK_ALLOCATED_AND_LIVE_SYMBOL ();
-	ptr->u.s.gcmarkbit = 1;
-	/* Attempt to catch bogus objects.  */
-	eassert (valid_lisp_object_p (ptr->u.s.function));
-	mark_object (ptr->u.s.function);
-	mark_object (ptr->u.s.plist);
-	switch (ptr->u.s.redirect)
-	  {
-	  case SYMBOL_PLAINVAL: mark_object (SYMBOL_VAL (ptr)); break;
-	  case SYMBOL_VARALIAS:
-	    {
-	      Lisp_Object tem;
-	      XSETSYMBOL (tem, SYMBOL_ALIAS (ptr));
-	      mark_object (tem);
-	      break;
-	    }
-	  case SYMBOL_LOCALIZED:
-	    mark_localized_symbol (ptr);
-	    break;
-	  case SYMBOL_FORWARDED:
-	    /* If the value is forwarded to a buffer or keyboard field,
-	       these are marked when we see the corresponding object.
-	       And if it's forwarded to a C variable, either it's not
-	       a Lisp_Object var, or it's staticpro'd already.  */
-	    break;
-	  default: emacs_abort ();
-	  }
-	if (!PURE_P (XSTRING (ptr->u.s.name)))
-	  MARK_STRING (XSTRING (ptr->u.s.name));
-	MARK_INTERVAL_TREE (string_intervals (ptr->u.s.name));
-	/* Inner loop to mark next symbol in this bucket, if any.  */
-	po = ptr = ptr->u.s.next;
-	if (ptr)
-	  goto nextsym;
-      }
+    case Lisp_Misc_Save_Value:
+      XMISCANY (obj)->gcmarkbit = 1;
+      mark_save_value (XSAVE_VALUE (obj));
       break;
 
-    case Lisp_Misc:
-      CHECK_ALLOCATED_AND_LIVE (live_misc_p);
-
-      if (XMISCANY (obj)->gcmarkbit)
-	break;
-
-      switch (XMISCTYPE (obj))
-	{
-	case Lisp_Misc_Marker:
-	  /* DO NOT mark thru the marker's chain.
-	     The buffer's markers chain does not preserve markers from gc;
-	     instead, markers are removed from the chain when freed by gc.  */
-	  XMISCANY (obj)->gcmarkbit = 1;
-	  break;
-
-	case Lisp_Misc_Save_Value:
-	  XMISCANY (obj)->gcmarkbit = 1;
-	  mark_save_value (XSAVE_VALUE (obj));
-	  break;
-
-	case Lisp_Misc_Overlay:
-	  mark_overlay (XOVERLAY (obj));
-          break;
-
-        case Lisp_Misc_Finalizer:
-          XMISCANY (obj)->gcmarkbit = true;
-          mark_object (XFINALIZER (obj)->function);
-          break;
-
-#ifdef HAVE_MODULES
-	case Lisp_Misc_User_Ptr:
-	  XMISCANY (obj)->gcmarkbit = true;
-	  break;
-#endif
-
-	default:
-	  emacs_abort ();
-	}
+    case Lisp_Misc_Overlay:
+      mark_overlay (XOVERLAY (obj));
       break;
 
-    case Lisp_Cons:
-      {
-	register struct Lisp_Cons *ptr = XCONS (obj);
-	if (CONS_MARKED_P (ptr))
-	  break;
-	CHECK_ALLOCATED_AND_LIVE (live_cons_p);
-	CONS_MARK (ptr);
-	/* If the cdr is nil, avoid recursion for the car.  */
-	if (EQ (ptr->u.s.u.cdr, Qnil))
-	  {
-	    obj = ptr->u.s.car;
-	    cdr_count = 0;
-	    goto loop;
-	  }
-	mark_object (ptr->u.s.car);
-	obj = ptr->u.s.u.cdr;
-	cdr_count++;
-	if (cdr_count == mark_object_loop_halt)
-	  emacs_abort ();
-	goto loop;
-      }
-
-    case Lisp_Float:
-      CHECK_ALLOCATED_AND_LIVE (live_float_p);
-      FLOAT_MARK (XFLOAT (obj));
+    case Lisp_Misc_Finalizer:
+      XMISCANY (obj)->gcmarkbit = true;
+      mark_object (XFINALIZER (obj)->function);
       break;
 
-    case_Lisp_Int:
+#ifdef HAVE_MODULES
+    case Lisp_Misc_User_Ptr:
+      XMISCANY (obj)->gcmarkbit = true;
       break;
+#endif
 
     default:
       emacs_abort ();
     }
-
-#undef CHECK_LIVE
-#undef CHECK_ALLOCATED
-#undef CHECK_ALLOCATED_AND_LIVE
 }
+
 /* Mark the Lisp pointers in the terminal objects.
    Called by Fgarbage_collect.  */
 
@@ -6695,11 +5228,11 @@ survives_gc_p (Lisp_Object obj)
       break;
 
     case Lisp_Cons:
-      survives_p = CONS_MARKED_P (XCONS (obj));
+      survives_p = cons_marked_p (obj);
       break;
 
     case Lisp_Float:
-      survives_p = FLOAT_MARKED_P (XFLOAT (obj));
+      survives_p = float_marked_p (obj);
       break;
 
     default:
@@ -6712,136 +5245,6 @@ survives_gc_p (Lisp_Object obj)
 
 
 
-NO_INLINE /* For better stack traces */
-static void
-sweep_conses (void)
-{
-  struct cons_block *cblk;
-  struct cons_block **cprev = &cons_block;
-  int lim = cons_block_index;
-  EMACS_INT num_free = 0, num_used = 0;
-
-  cons_free_list = 0;
-
-  for (cblk = cons_block; cblk; cblk = *cprev)
-    {
-      int i = 0;
-      int this_free = 0;
-      int ilim = (lim + BITS_PER_BITS_WORD - 1) / BITS_PER_BITS_WORD;
-
-      /* Scan the mark bits an int at a time.  */
-      for (i = 0; i < ilim; i++)
-        {
-          if (cblk->gcmarkbits[i] == BITS_WORD_MAX)
-            {
-              /* Fast path - all cons cells for this int are marked.  */
-              cblk->gcmarkbits[i] = 0;
-              num_used += BITS_PER_BITS_WORD;
-            }
-          else
-            {
-              /* Some cons cells for this int are not marked.
-                 Find which ones, and free them.  */
-              int start, pos, stop;
-
-              start = i * BITS_PER_BITS_WORD;
-              stop = lim - start;
-              if (stop > BITS_PER_BITS_WORD)
-                stop = BITS_PER_BITS_WORD;
-              stop += start;
-
-              for (pos = start; pos < stop; pos++)
-                {
-		  struct Lisp_Cons *acons
-		    = ptr_bounds_copy (&cblk->conses[pos], cblk);
-		  if (!CONS_MARKED_P (acons))
-                    {
-                      this_free++;
-                      cblk->conses[pos].u.s.u.chain = cons_free_list;
-                      cons_free_list = &cblk->conses[pos];
-                      cons_free_list->u.s.car = Vdead;
-                    }
-                  else
-                    {
-                      num_used++;
-		      CONS_UNMARK (acons);
-                    }
-                }
-            }
-        }
-
-      lim = CONS_BLOCK_SIZE;
-      /* If this block contains only free conses and we have already
-         seen more than two blocks worth of free conses then deallocate
-         this block.  */
-      if (this_free == CONS_BLOCK_SIZE && num_free > CONS_BLOCK_SIZE)
-        {
-          *cprev = cblk->next;
-          /* Unhook from the free list.  */
-          cons_free_list = cblk->conses[0].u.s.u.chain;
-          lisp_align_free (cblk);
-        }
-      else
-        {
-          num_free += this_free;
-          cprev = &cblk->next;
-        }
-    }
-  total_conses = num_used;
-  total_free_conses = num_free;
-}
-
-NO_INLINE /* For better stack traces */
-static void
-sweep_floats (void)
-{
-  register struct float_block *fblk;
-  struct float_block **fprev = &float_block;
-  register int lim = float_block_index;
-  EMACS_INT num_free = 0, num_used = 0;
-
-  float_free_list = 0;
-
-  for (fblk = float_block; fblk; fblk = *fprev)
-    {
-      register int i;
-      int this_free = 0;
-      for (i = 0; i < lim; i++)
-	{
-	  struct Lisp_Float *afloat = ptr_bounds_copy (&fblk->floats[i], fblk);
-	  if (!FLOAT_MARKED_P (afloat))
-	    {
-	      this_free++;
-	      fblk->floats[i].u.chain = float_free_list;
-	      float_free_list = &fblk->floats[i];
-	    }
-	  else
-	    {
-	      num_used++;
-	      FLOAT_UNMARK (afloat);
-	    }
-	}
-      lim = FLOAT_BLOCK_SIZE;
-      /* If this block contains only free floats and we have already
-         seen more than two blocks worth of free floats then deallocate
-         this block.  */
-      if (this_free == FLOAT_BLOCK_SIZE && num_free > FLOAT_BLOCK_SIZE)
-        {
-          *fprev = fblk->next;
-          /* Unhook from the free list.  */
-          float_free_list = fblk->floats[0].u.chain;
-          lisp_align_free (fblk);
-        }
-      else
-        {
-          num_free += this_free;
-          fprev = &fblk->next;
-        }
-    }
-  total_floats = num_used;
-  total_free_floats = num_free;
-}
-
 NO_INLINE /* For better stack traces */
 static void
 sweep_intervals (void)
@@ -7065,7 +5468,6 @@ gc_sweep (void)
   sweep_weak_hash_tables ();
 
   sweep_strings ();
-  check_string_bytes (!noninteractive);
   sweep_conses ();
   sweep_floats ();
   sweep_intervals ();
@@ -7073,7 +5475,6 @@ gc_sweep (void)
   sweep_misc ();
   sweep_buffers ();
   sweep_vectors ();
-  check_string_bytes (!noninteractive);
 }
 
 DEFUN ("memory-info", Fmemory_info, Smemory_info, 0, 0, 0,
@@ -7320,8 +5721,7 @@ init_alloc_once (void)
   pure_size = PURESIZE;
 
   verify_alloca ();
-  init_finalizer_list (&finalizers);
-  init_finalizer_list (&doomed_finalizers);
+  init_finalizers ();
 
   mem_init ();
   Vdead = make_pure_string ("DEAD", 4, 4, 0);
@@ -7449,7 +5849,6 @@ The time is in seconds as a floating point value.  */);
   DEFVAR_INT ("gcs-done", gcs_done,
               doc: /* Accumulated number of garbage collections done.  */);
 
-  defsubr (&Scons);
   defsubr (&Svector);
   defsubr (&Srecord);
   defsubr (&Smake_byte_code);
@@ -7458,7 +5857,6 @@ The time is in seconds as a floating point value.  */);
   defsubr (&Smake_string);
   defsubr (&Smake_symbol);
   defsubr (&Smake_marker);
-  defsubr (&Smake_finalizer);
   defsubr (&Spurecopy);
   defsubr (&Sgarbage_collect);
   defsubr (&Smemory_limit);
@@ -1449,6 +1449,8 @@ cleaning up all windows currently displaying the buffer to be killed. */)
 
       /* Perhaps we should explicitly free the interval tree here...  */
     }
+  forget_parse_cache (b);
+
   /* Since we've unlinked the markers, the overlays can't be here any more
      either.  */
   b->overlays_before = NULL;
@@ -1463,6 +1465,10 @@ cleaning up all windows currently displaying the buffer to be killed. */)
 
   bset_name (b, Qnil);
 
+  /* Now that the buffer is dead, drop it from the buffer lists of the
+     windows that showed it.  */
+  prune_window_buffer_lists ();
+
   block_input ();
   if (b->base_buffer)
     {
@@ -1811,6 +1817,9 @@ results, see Info node `(elisp)Swapping Text'.  */)
 	error ("One of the buffers to swap has indirect buffers");
   }
 
+  detach_buffer_snapshots (current_buffer);
+  detach_buffer_snapshots (other_buffer);
+
 #define swapfield(field, type) \
   do {							\
     type tmp##field = other_buffer->field;		\
@@ -1969,6 +1978,9 @@ current buffer is cleared.  */)
      instead.  */
   bset_undo_list (current_buffer, Qt);
 
+  /* The text is converted in place.  */
+  detach_buffer_snapshots (current_buffer);
+
   /* If the cached position is for this buffer, clear it out.  */
   clear_charpos_cache (current_buffer);
 
@@ -4432,6 +4444,7 @@ enlarge_buffer_text (struct buffer *b, ptrdiff_t delta)
   void *p;
   ptrdiff_t nbytes = (BUF_Z_BYTE (b) - BUF_BEG_BYTE (b) + BUF_GAP_SIZE (b) + 1
 		      + delta);
+  detach_buffer_snapshots (b);
   block_input ();
 #if defined USE_MMAP_FOR_BUFFERS
   p = mmap_realloc ((void **) &b->text->beg, nbytes);
@@ -4457,6 +4470,7 @@ enlarge_buffer_text (struct buffer *b, ptrdiff_t delta)
 static void
 free_buffer_text (struct buffer *b)
 {
+  detach_buffer_snapshots (b);
   block_input ();
 
 #if defined USE_MMAP_FOR_BUFFERS
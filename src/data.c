@@ -553,119 +553,6 @@ notify_variable_watchers (Lisp_Object symbol,
   unbind_to (count, Qnil);
 }
 
-
-/* Access or set a buffer-local symbol's default value.  */
-
-void
-set_default_internal (Lisp_Object symbol, Lisp_Object value,
-                      enum Set_Internal_Bind bindflag)
-{
-  struct Lisp_Symbol *sym;
-
-  CHECK_SYMBOL (symbol);
-  sym = XSYMBOL (symbol);
-  switch (sym->u.s.trapped_write)
-    {
-    case SYMBOL_NOWRITE:
-      if (NILP (Fkeywordp (symbol))
-          || !EQ (value, Fsymbol_value (symbol)))
-        xsignal1 (Qsetting_constant, symbol);
-      else
-        /* Allow setting keywords to their own value.  */
-        return;
-
-    case SYMBOL_TRAPPED_WRITE:
-      /* Don't notify here if we're going to call Fset anyway.  */
-      if (sym->u.s.redirect != SYMBOL_PLAINVAL
-          /* Setting due to thread switching doesn't count.  */
-          && bindflag != SET_INTERNAL_THREAD_SWITCH)
-        notify_variable_watchers (symbol, value, Qset_default, Qnil);
-      /* FALLTHROUGH!  */
-    case SYMBOL_UNTRAPPED_WRITE:
-        break;
-
-    default: emacs_abort ();
-    }
-
- start:
-  switch (sym->u.s.redirect)
-    {
-    case SYMBOL_VARALIAS: sym = indirect_variable (sym); goto start;
-    case SYMBOL_PLAINVAL: set_internal (symbol, value, Qnil, bindflag); return;
-    case SYMBOL_LOCALIZED:
-      {
-	struct Lisp_Buffer_Local_Value *blv = SYMBOL_BLV (sym);
-
-	/* Store new value into the DEFAULT-VALUE slot.  */
-	XSETCDR (blv->defcell, value);
-
-	/* If the default binding is now loaded, set the REALVALUE slot too.  */
-	if (blv->fwd && EQ (blv->defcell, blv->valcell))
-	  store_symval_forwarding (blv->fwd, value, NULL);
-        return;
-      }
-    case SYMBOL_FORWARDED:
-      {
-	union Lisp_Fwd *valcontents = SYMBOL_FWD (sym);
-
-	/* Handle variables like case-fold-search that have special slots
-	   in the buffer.
-	   Make them work apparently like Lisp_Buffer_Local_Value variables.  */
-	if (BUFFER_OBJFWDP (valcontents))
-	  {
-	    int offset = XBUFFER_OBJFWD (valcontents)->offset;
-	    int idx = PER_BUFFER_IDX (offset);
-
-	    set_per_buffer_default (offset, value);
-
-	    /* If this variable is not always local in all buffers,
-	       set it in the buffers that don't nominally have a local value.  */
-	    if (idx > 0)
-	      {
-		struct buffer *b;
-
-		FOR_EACH_BUFFER (b)
-		  if (!PER_BUFFER_VALUE_P (b, idx))
-		    set_per_buffer_value (b, offset, value);
-	      }
-	  }
-	else
-          set_internal (symbol, value, Qnil, bindflag);
-        return;
-      }
-    default: emacs_abort ();
-    }
-}
-
-DEFUN ("setq-default", Fsetq_default, Ssetq_default, 0, UNEVALLED, 0,
-       doc: /* Set the default value of variable VAR to VALUE.
-VAR, the variable name, is literal (not evaluated);
-VALUE is an expression: it is evaluated and its value returned.
-The default value of a variable is seen in buffers
-that do not have their own values for the variable.
-
-More generally, you can use multiple variables and values, as in
-  (setq-default VAR VALUE VAR VALUE...)
-This sets each VAR's default value to the corresponding VALUE.
-The VALUE for the Nth VAR can refer to the new default values
-of previous VARs.
-usage: (setq-default [VAR VALUE]...)  */)
-  (Lisp_Object args)
-{
-  Lisp_Object args_left, symbol, val;
-
-  args_left = val = args;
-
-  while (CONSP (args_left))
-    {
-      val = eval_sub (Fcar (XCDR (args_left)));
-      symbol = XCAR (args_left);
-      Fset_default (symbol, val);
-      args_left = Fcdr (XCDR (args_left));
-    }
-
-  return val;
-}
 
 /* Lisp functions for creating and removing buffer-local variables.  */
 
@@ -1241,60 +1128,6 @@ cons_to_signed (Lisp_Object c, intmax_t min, intmax_t max)
   return val;
 }
 
-DEFUN ("number-to-string", Fnumber_to_string, Snumber_to_string, 1, 1, 0,
-       doc: /* Return the decimal representation of NUMBER as a string.
-Uses a minus sign if negative.
-NUMBER may be an integer or a floating point number.  */)
-  (Lisp_Object number)
-{
-  char buffer[max (FLOAT_TO_STRING_BUFSIZE, INT_BUFSIZE_BOUND (EMACS_INT))];
-  int len;
-
-  CHECK_NUMBER_OR_FLOAT (number);
-
-  if (FLOATP (number))
-    len = float_to_string (buffer, XFLOAT_DATA (number));
-  else
-    len = sprintf (buffer, "%"pI"d", XINT (number));
-
-  return make_unibyte_string (buffer, len);
-}
-
-DEFUN ("string-to-number", Fstring_to_number, Sstring_to_number, 1, 2, 0,
-       doc: /* Parse STRING as a decimal number and return the number.
-Ignore leading spaces and tabs, and all trailing chars.  Return 0 if
-STRING cannot be parsed as an integer or floating point number.
-
-If BASE, interpret STRING as a number in that base.  If BASE isn't
-present, base 10 is used.  BASE must be between 2 and 16 (inclusive).
-If the base used is not 10, STRING is always parsed as an integer.  */)
-  (register Lisp_Object string, Lisp_Object base)
-{
-  register char *p;
-  register int b;
-  Lisp_Object val;
-
-  CHECK_STRING (string);
-
-  if (NILP (base))
-    b = 10;
-  else
-    {
-      CHECK_NUMBER (base);
-      if (! (XINT (base) >= 2 && XINT (base) <= 16))
-	xsignal1 (Qargs_out_of_range, base);
-      b = XINT (base);
-    }
-
-  p = SSDATA (string);
-  while (*p == ' ' || *p == '\t')
-    p++;
-
-  val = string_to_number (p, b, true);
-  return NILP (val) ? make_number (0) : val;
-}
-
-
 static Lisp_Object
 ash_lsh_impl (Lisp_Object value, Lisp_Object count, bool lsh)
 {
@@ -1805,7 +1638,6 @@ syms_of_data (void)
 
   defsubr (&Sinteractive_form);
   defsubr (&Smodule_function_p);
-  defsubr (&Ssetq_default);
   defsubr (&Smake_variable_buffer_local);
   defsubr (&Smake_local_variable);
   defsubr (&Skill_local_variable);
@@ -1816,8 +1648,6 @@ syms_of_data (void)
   defsubr (&Sterminal_local_value);
   defsubr (&Sset_terminal_local_value);
 #endif
-  defsubr (&Snumber_to_string);
-  defsubr (&Sstring_to_number);
   defsubr (&Slsh);
   defsubr (&Sash);
 #ifdef HAVE_MODULES
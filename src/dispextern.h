@@ -3150,6 +3150,12 @@ enum tool_bar_item_image
 
 #define DEFAULT_TOOL_BAR_IMAGE_HEIGHT 24
 
+/* Defined in rust tool_bar.rs.  */
+
+extern Lisp_Object tool_bar_image_for_state (Lisp_Object, bool, bool, int *);
+extern int tool_bar_item_index (struct frame *, struct glyph *);
+extern void store_tool_bar_click (struct frame *, int, int);
+
 
 /***********************************************************************
 			 Terminal Capabilities
@@ -3227,6 +3233,7 @@ void move_it_in_display_line (struct it *it,
 int partial_line_height (struct it *it_origin);
 bool in_display_vector_p (struct it *);
 int frame_mode_line_height (struct frame *);
+char *format_frame_title (struct frame *, Lisp_Object, ptrdiff_t *);
 extern bool redisplaying_p;
 extern bool help_echo_showing_p;
 extern Lisp_Object help_echo_string, help_echo_window;
@@ -3366,6 +3373,7 @@ int image_ascent (struct image *, struct face *, struct glyph_slice *);
 /* Defined in sysdep.c */
 
 void get_tty_size (int, int *, int *);
+bool get_selected_tty_size (int *, int *);
 void request_sigio (void);
 void unrequest_sigio (void);
 bool tabs_safe_p (int);
@@ -3460,15 +3468,16 @@ extern Lisp_Object buffer_posn_from_coords (struct window *,
                                             struct display_pos *,
                                             Lisp_Object *,
                                             int *, int *, int *, int *);
-extern Lisp_Object mode_line_string (struct window *, enum window_part,
-                                     int *, int *, ptrdiff_t *,
-                                     Lisp_Object *,
-                                     int *, int *, int *, int *);
+extern Lisp_Object image_glyph_spec (struct frame *, struct glyph *);
 extern Lisp_Object marginal_area_string (struct window *, enum window_part,
                                          int *, int *, ptrdiff_t *,
                                          Lisp_Object *,
                                          int *, int *, int *, int *);
 extern void redraw_frame (struct frame *);
+extern Lisp_Object mode_line_string (struct window *, enum window_part,
+                                     int *, int *, ptrdiff_t *,
+                                     Lisp_Object *,
+                                     int *, int *, int *, int *);
 extern bool update_frame (struct frame *, bool, bool);
 extern void update_frame_with_menu (struct frame *, int, int);
 extern void adjust_frame_glyphs (struct frame *);
@@ -5144,72 +5144,18 @@ buffer_posn_from_coords (struct window *w, int *x, int *y, struct display_pos *p
 }
 
 
-/* Value is the string under window-relative coordinates X/Y in the
-   mode line or header line (PART says which) of window W, or nil if none.
-   *CHARPOS is set to the position in the string returned.  */
+/* Return the spec of the image displayed by GLYPH, an image glyph in
+   a window on frame F, or nil if the image is gone.  */
 
 Lisp_Object
-mode_line_string (struct window *w, enum window_part part,
-		  int *x, int *y, ptrdiff_t *charpos, Lisp_Object *object,
-		  int *dx, int *dy, int *width, int *height)
+image_glyph_spec (struct frame *f, struct glyph *glyph)
 {
-  struct glyph_row *row;
-  struct glyph *glyph, *end;
-  int x0, y0;
-  Lisp_Object string = Qnil;
-
-  if (part == ON_MODE_LINE)
-    row = MATRIX_MODE_LINE_ROW (w->current_matrix);
-  else
-    row = MATRIX_HEADER_LINE_ROW (w->current_matrix);
-  y0 = *y - row->y;
-  *y = row - MATRIX_FIRST_TEXT_ROW (w->current_matrix);
-
-  if (row->mode_line_p && row->enabled_p)
-    {
-      /* Find the glyph under X.  If we find one with a string object,
-         it's the one we were looking for.  */
-      glyph = row->glyphs[TEXT_AREA];
-      end = glyph + row->used[TEXT_AREA];
-      for (x0 = *x; glyph < end && x0 >= glyph->pixel_width; ++glyph)
-	x0 -= glyph->pixel_width;
-      *x = glyph - row->glyphs[TEXT_AREA];
-      if (glyph < end)
-	{
-	  string = glyph->object;
-	  *charpos = glyph->charpos;
-	  *width = glyph->pixel_width;
-	  *height = glyph->ascent + glyph->descent;
 #ifdef HAVE_WINDOW_SYSTEM
-	  if (glyph->type == IMAGE_GLYPH)
-	    {
-	      struct image *img;
-	      img = IMAGE_OPT_FROM_ID (WINDOW_XFRAME (w), glyph->u.img_id);
-	      if (img != NULL)
-		*object = img->spec;
-	      y0 -= row->ascent - glyph->ascent;
-	    }
+  struct image *img = IMAGE_OPT_FROM_ID (f, glyph->u.img_id);
+  if (img != NULL)
+    return img->spec;
 #endif
-	}
-      else
-	{
-	  /* Add extra (default width) columns if clicked after EOL. */
-	  *x += x0 / WINDOW_FRAME_COLUMN_WIDTH (w);
-	  *width = 0;
-	  *height = row->height;
-	}
-    }
-  else
-    {
-      *x = 0;
-      x0 = 0;
-      *width = *height = 0;
-    }
-
-  *dx = x0;
-  *dy = y0;
-
-  return string;
+  return Qnil;
 }
 
 
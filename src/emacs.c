@@ -194,134 +194,8 @@ HANDLE w32_daemon_event;
 char **initial_argv;
 int initial_argc;
 
-static void sort_args (int argc, char **argv);
 static void syms_of_emacs (void);
 
-/* C99 needs each string to be at most 4095 characters, and the usage
-   strings below are split to not overflow this limit.  */
-static char const *const usage_message[] =
-  { "\
-\n\
-Run Emacs, the extensible, customizable, self-documenting real-time\n\
-display editor.  The recommended way to start Emacs for normal editing\n\
-is with no options at all.\n\
-\n\
-Run M-x info RET m emacs RET m emacs invocation RET inside Emacs to\n\
-read the main documentation for these command-line arguments.\n\
-\n\
-Initialization options:\n\
-\n\
-",
-    "\
---batch                     do not do interactive display; implies -q\n\
---chdir DIR                 change to directory DIR\n\
---daemon, --bg-daemon[=NAME] start a (named) server in the background\n\
---fg-daemon[=NAME]          start a (named) server in the foreground\n\
---debug-init                enable Emacs Lisp debugger for init file\n\
---display, -d DISPLAY       use X server DISPLAY\n\
-",
-#ifdef HAVE_MODULES
-    "\
---module-assertions         assert behavior of dynamic modules\n\
-",
-#endif
-    "\
---no-build-details          do not add build details such as time stamps\n\
---no-desktop                do not load a saved desktop\n\
---no-init-file, -q          load neither ~/.emacs nor default.el\n\
---no-loadup, -nl            do not load loadup.el into bare Emacs\n\
---no-site-file              do not load site-start.el\n\
---no-x-resources            do not load X resources\n\
---no-site-lisp, -nsl        do not add site-lisp directories to load-path\n\
---no-splash                 do not display a splash screen on startup\n\
---no-window-system, -nw     do not communicate with X, ignoring $DISPLAY\n\
-",
-    "\
---quick, -Q                 equivalent to:\n\
-                              -q --no-site-file --no-site-lisp --no-splash\n\
-                              --no-x-resources\n\
---script FILE               run FILE as an Emacs Lisp script\n\
---terminal, -t DEVICE       use DEVICE for terminal I/O\n\
---user, -u USER             load ~USER/.emacs instead of your own\n\
-\n\
-",
-    "\
-Action options:\n\
-\n\
-FILE                    visit FILE\n\
-+LINE                   go to line LINE in next FILE\n\
-+LINE:COLUMN            go to line LINE, column COLUMN, in next FILE\n\
---directory, -L DIR     prepend DIR to load-path (with :DIR, append DIR)\n\
---eval EXPR             evaluate Emacs Lisp expression EXPR\n\
---execute EXPR          evaluate Emacs Lisp expression EXPR\n\
-",
-    "\
---file FILE             visit FILE\n\
---find-file FILE        visit FILE\n\
---funcall, -f FUNC      call Emacs Lisp function FUNC with no arguments\n\
---insert FILE           insert contents of FILE into current buffer\n\
---kill                  exit without asking for confirmation\n\
---load, -l FILE         load Emacs Lisp FILE using the load function\n\
---visit FILE            visit FILE\n\
-\n\
-",
-    "\
-Display options:\n\
-\n\
---background-color, -bg COLOR   window background color\n\
---basic-display, -D             disable many display features;\n\
-                                  used for debugging Emacs\n\
---border-color, -bd COLOR       main border color\n\
---border-width, -bw WIDTH       width of main border\n\
-",
-    "\
---color, --color=MODE           override color mode for character terminals;\n\
-                                  MODE defaults to `auto', and\n\
-                                  can also be `never', `always',\n\
-                                  or a mode name like `ansi8'\n\
---cursor-color, -cr COLOR       color of the Emacs cursor indicating point\n\
---font, -fn FONT                default font; must be fixed-width\n\
---foreground-color, -fg COLOR   window foreground color\n\
-",
-    "\
---fullheight, -fh               make the first frame high as the screen\n\
---fullscreen, -fs               make the first frame fullscreen\n\
---fullwidth, -fw                make the first frame wide as the screen\n\
---maximized, -mm                make the first frame maximized\n\
---geometry, -g GEOMETRY         window geometry\n\
-",
-    "\
---no-bitmap-icon, -nbi          do not use picture of gnu for Emacs icon\n\
---iconic                        start Emacs in iconified state\n\
---internal-border, -ib WIDTH    width between text and main border\n\
---line-spacing, -lsp PIXELS     additional space to put between lines\n\
---mouse-color, -ms COLOR        mouse cursor color in Emacs window\n\
---name NAME                     title for initial Emacs frame\n\
-",
-    "\
---no-blinking-cursor, -nbc      disable blinking cursor\n\
---reverse-video, -r, -rv        switch foreground and background\n\
---title, -T TITLE               title for initial Emacs frame\n\
---vertical-scroll-bars, -vb     enable vertical scroll bars\n\
---xrm XRESOURCES                set additional X resources\n\
---parent-id XID                 set parent window\n\
---help                          display this help and exit\n\
---version                       output version information and exit\n\
-\n\
-",
-    "\
-You can generally also specify long option names with a single -; for\n\
-example, -batch as well as --batch.  You can use any unambiguous\n\
-abbreviation for a --option.\n\
-\n\
-Various environment variables and window system resources also affect\n\
-the operation of Emacs.  See the main documentation.\n\
-\n\
-Report bugs to " PACKAGE_BUGREPORT ".  First, please see the Bugs\n\
-section of the Emacs manual or the file BUGS.\n"
-  };
-
-
 /* True if handling a fatal error already.  */
 bool fatal_error_in_progress;
 
@@ -352,8 +226,8 @@ using_utf8 (void)
 }
 
 
-/* Report a fatal error due to signal SIG, output a backtrace of at
-   most BACKTRACE_LIMIT lines, and exit.  */
+/* Report a fatal error due to signal SIG, write a crash report with a
+   backtrace of at most BACKTRACE_LIMIT lines, and exit.  */
 _Noreturn void
 terminate_due_to_signal (int sig, int backtrace_limit)
 {
@@ -371,7 +245,7 @@ terminate_due_to_signal (int sig, int backtrace_limit)
             Fkill_emacs (make_number (sig));
 
           shut_down_emacs (sig, Qnil);
-          emacs_backtrace (backtrace_limit);
+          write_crash_report (sig, backtrace_limit);
         }
     }
 
@@ -550,69 +424,6 @@ init_cmdargs (int argc, char **argv, int skip_args, char *original_pwd)
 }
 
 
-/* Test whether the next argument in ARGV matches SSTR or a prefix of
-   LSTR (at least MINLEN characters).  If so, then if VALPTR is non-null
-   (the argument is supposed to have a value) store in *VALPTR either
-   the next argument or the portion of this one after the equal sign.
-   ARGV is read starting at position *SKIPPTR; this index is advanced
-   by the number of arguments used.
-
-   Too bad we can't just use getopt for all of this, but we don't have
-   enough information to do it right.  */
-
-static bool
-argmatch (char **argv, int argc, const char *sstr, const char *lstr,
-          int minlen, char **valptr, int *skipptr)
-{
-  char *p = NULL;
-  ptrdiff_t arglen;
-  char *arg;
-
-  /* Don't access argv[argc]; give up in advance.  */
-  if (argc <= *skipptr + 1)
-    return 0;
-
-  arg = argv[*skipptr+1];
-  if (arg == NULL)
-    return 0;
-  if (strcmp (arg, sstr) == 0)
-    {
-      if (valptr != NULL)
-	{
-	  *valptr = argv[*skipptr+2];
-	  *skipptr += 2;
-	}
-      else
-	*skipptr += 1;
-      return 1;
-    }
-  arglen = (valptr != NULL && (p = strchr (arg, '=')) != NULL
-	    ? p - arg : strlen (arg));
-  if (lstr == 0 || arglen < minlen || strncmp (arg, lstr, arglen) != 0)
-    return 0;
-  else if (valptr == NULL)
-    {
-      *skipptr += 1;
-      return 1;
-    }
-  else if (p != NULL)
-    {
-      *valptr = p+1;
-      *skipptr += 1;
-      return 1;
-    }
-  else if (argv[*skipptr+2] != NULL)
-    {
-      *valptr = argv[*skipptr+2];
-      *skipptr += 2;
-      return 1;
-    }
-  else
-    {
-      return 0;
-    }
-}
-
 /* Close standard output and standard error, reporting any write
    errors as best we can.  This is intended for use with atexit.  */
 static void
@@ -731,7 +542,7 @@ main (int argc, char **argv)
   argc = 0;
   while (argv[argc]) argc++;
 
-  if (argmatch (argv, argc, "-version", "--version", 3, NULL, &skip_args))
+  if (argmatch (argv, argc, "-version", NULL, &skip_args))
     {
       const char *version, *copyright;
       if (initialized)
@@ -770,7 +581,7 @@ main (int argc, char **argv)
       exit (0);
     }
 
-  if (argmatch (argv, argc, "-chdir", "--chdir", 4, &ch_to_dir, &skip_args))
+  if (argmatch (argv, argc, "-chdir", &ch_to_dir, &skip_args))
     {
 #ifdef WINDOWSNT
       /* argv[] array is kept in its original ANSI codepage encoding,
@@ -886,7 +697,7 @@ main (int argc, char **argv)
   while (1)
     {
       char *term;
-      if (argmatch (argv, argc, "-t", "--terminal", 4, &term, &skip_args))
+      if (argmatch (argv, argc, "-t", &term, &skip_args))
 	{
 	  emacs_close (STDIN_FILENO);
 	  emacs_close (STDOUT_FILENO);
@@ -915,18 +726,17 @@ main (int argc, char **argv)
 
   /* Command line option --no-windows is deprecated and thus not mentioned
      in the manual and usage information.  */
-  if (argmatch (argv, argc, "-nw", "--no-window-system", 6, NULL, &skip_args)
-      || argmatch (argv, argc, "-nw", "--no-windows", 6, NULL, &skip_args))
+  if (argmatch (argv, argc, "-nw", NULL, &skip_args))
     inhibit_window_system = 1;
 
   /* Handle the -batch switch, which means don't do interactive display.  */
   noninteractive = 0;
-  if (argmatch (argv, argc, "-batch", "--batch", 5, NULL, &skip_args))
+  if (argmatch (argv, argc, "-batch", NULL, &skip_args))
     {
       noninteractive = 1;
       Vundo_outer_limit = Qnil;
     }
-  if (argmatch (argv, argc, "-script", "--script", 3, &junk, &skip_args))
+  if (argmatch (argv, argc, "-script", &junk, &skip_args))
     {
       noninteractive = 1;	/* Set batch mode.  */
       /* Convert --script to -scriptload, un-skip it, and sort again
@@ -938,12 +748,9 @@ main (int argc, char **argv)
     }
 
   /* Handle the --help option, which gives a usage message.  */
-  if (argmatch (argv, argc, "-help", "--help", 3, NULL, &skip_args))
+  if (argmatch (argv, argc, "-help", NULL, &skip_args))
     {
-      int i;
-      printf ("Usage: %s [OPTION-OR-FILENAME]...\n", argv[0]);
-      for (i = 0; i < ARRAYELTS (usage_message); i++)
-	fputs_unlocked (usage_message[i], stdout);
+      print_usage (argv[0], emacs_bugreport);
       exit (0);
     }
 
@@ -959,15 +766,12 @@ main (int argc, char **argv)
 
   int sockfd = -1;
 
-  if (argmatch (argv, argc, "-fg-daemon", "--fg-daemon", 10, NULL, &skip_args)
-      || argmatch (argv, argc, "-fg-daemon", "--fg-daemon", 10, &dname_arg, &skip_args))
+  if (argmatch (argv, argc, "-fg-daemon", &dname_arg, &skip_args))
     {
       daemon_type = 1;           /* foreground */
     }
-  else if (argmatch (argv, argc, "-daemon", "--daemon", 5, NULL, &skip_args)
-      || argmatch (argv, argc, "-daemon", "--daemon", 5, &dname_arg, &skip_args)
-      || argmatch (argv, argc, "-bg-daemon", "--bg-daemon", 10, NULL, &skip_args)
-      || argmatch (argv, argc, "-bg-daemon", "--bg-daemon", 10, &dname_arg, &skip_args))
+  else if (argmatch (argv, argc, "-daemon", &dname_arg, &skip_args)
+	   || argmatch (argv, argc, "-bg-daemon", &dname_arg, &skip_args))
     {
       daemon_type = 2;          /* background */
     }
@@ -1221,18 +1025,16 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
 #endif
 
   no_loadup
-    = argmatch (argv, argc, "-nl", "--no-loadup", 6, NULL, &skip_args);
+    = argmatch (argv, argc, "-nl", NULL, &skip_args);
 
   no_site_lisp
-    = argmatch (argv, argc, "-nsl", "--no-site-lisp", 11, NULL, &skip_args);
+    = argmatch (argv, argc, "-nsl", NULL, &skip_args);
 
-  build_details = ! argmatch (argv, argc, "-no-build-details",
-			      "--no-build-details", 7, NULL, &skip_args);
+  build_details = ! argmatch (argv, argc, "-no-build-details", NULL, &skip_args);
 
 #ifdef HAVE_MODULES
   bool module_assertions
-    = argmatch (argv, argc, "-module-assertions", "--module-assertions", 15,
-                NULL, &skip_args);
+    = argmatch (argv, argc, "-module-assertions", NULL, &skip_args);
   if (dumping && module_assertions)
     {
       fputs ("Module assertions are not supported during dumping\n", stderr);
@@ -1287,9 +1089,7 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
       {
 	int count_before_this = skip_args;
 
-	if (argmatch (argv, argc, "-d", "--display", 3, &displayname, &skip_args))
-	  display_arg = 1;
-	else if (argmatch (argv, argc, "-display", 0, 3, &displayname, &skip_args))
+	if (argmatch (argv, argc, "-d", &displayname, &skip_args))
 	  display_arg = 1;
 	else
 	  break;
@@ -1314,8 +1114,7 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
 
     if (! no_site_lisp)
       {
-        if (argmatch (argv, argc, "-Q", "--quick", 3, NULL, &skip_args)
-            || argmatch (argv, argc, "-quick", 0, 2, NULL, &skip_args))
+        if (argmatch (argv, argc, "-Q", NULL, &skip_args))
           no_site_lisp = 1;
       }
 
@@ -1327,8 +1126,7 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
   {
     int count_before = skip_args;
 
-    if (argmatch (argv, argc, "-Q", "--quick", 3, NULL, &skip_args)
-        || argmatch (argv, argc, "-quick", 0, 2, NULL, &skip_args))
+    if (argmatch (argv, argc, "-Q", NULL, &skip_args))
       no_site_lisp = 1;
 
     skip_args = count_before;
@@ -1392,6 +1190,7 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
 
   /* Must precede init_lread.  */
   init_cmdargs (argc, argv, skip_args, original_pwd);
+  init_cli ();
 
   if (initialized)
     {
@@ -1431,24 +1230,30 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
       syms_of_category ();
       syms_of_ccl ();
       syms_of_character ();
+      syms_of_cli ();
       syms_of_cmds ();
+      syms_of_crash ();
       syms_of_dired ();
       syms_of_display ();
       syms_of_doc ();
       syms_of_editfns ();
       syms_of_emacs ();
       syms_of_filelock ();
+      syms_of_hashtable ();
       syms_of_indent ();
       syms_of_insdel ();
       /* syms_of_keymap (); */
-      syms_of_macros ();
       syms_of_minibuf ();
+      syms_of_monitors ();
       syms_of_process ();
       syms_of_search ();
+      syms_of_server ();
       syms_of_frame ();
+      syms_of_frame_parameters ();
       syms_of_syntax ();
       syms_of_terminal ();
       syms_of_term ();
+      syms_of_tty_clipboard ();
       syms_of_undo ();
 
 #ifdef HAVE_MODULES
@@ -1467,6 +1272,8 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
       syms_of_cygw32 ();
 #endif
       syms_of_window ();
+      syms_of_windows ();
+      syms_of_word_wrap ();
       syms_of_xdisp ();
       syms_of_font ();
 #ifdef HAVE_WINDOW_SYSTEM
@@ -1601,7 +1408,6 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
 #ifdef HAVE_WINDOW_SYSTEM
   init_fringe ();
 #endif /* HAVE_WINDOW_SYSTEM */
-  init_macros ();
   init_window ();
   init_font ();
 
@@ -1609,7 +1415,7 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
     {
       char *file;
       /* Handle -l loadup, args passed by Makefile.  */
-      if (argmatch (argv, argc, "-l", "--load", 3, &file, &skip_args))
+      if (argmatch (argv, argc, "-l", &file, &skip_args))
 	{
 #ifdef WINDOWSNT
 	  char file_utf8[MAX_UTF8_PATH];
@@ -1654,404 +1460,66 @@ Using an Emacs configured with --with-x-toolkit=lucid does not have this problem
   return 0;
 }
 
-/* Sort the args so we can find the most important ones
-   at the beginning of argv.  */
-
-/* First, here's a table of all the standard options.  */
+/* Platform-dependent parts of `kill-emacs' and shut_down_emacs,
+   which are defined in emacs.rs.  */
 
-struct standard_args
-{
-  const char *name;
-  const char *longname;
-  int priority;
-  int nargs;
-};
+/* Transfer any clipboards we own to the clipboard manager.  */
 
-static const struct standard_args standard_args[] =
+void
+kill_emacs_save_clipboards (void)
 {
-  { "-version", "--version", 150, 0 },
-  { "-chdir", "--chdir", 130, 1 },
-  { "-t", "--terminal", 120, 1 },
-  { "-nw", "--no-window-system", 110, 0 },
-  { "-nw", "--no-windows", 110, 0 },
-  { "-batch", "--batch", 100, 0 },
-  { "-script", "--script", 100, 1 },
-  { "-daemon", "--daemon", 99, 0 },
-  { "-bg-daemon", "--bg-daemon", 99, 0 },
-  { "-fg-daemon", "--fg-daemon", 99, 0 },
-  { "-help", "--help", 90, 0 },
-  { "-nl", "--no-loadup", 70, 0 },
-  { "-nsl", "--no-site-lisp", 65, 0 },
-  { "-no-build-details", "--no-build-details", 63, 0 },
-#ifdef HAVE_MODULES
-  { "-module-assertions", "--module-assertions", 62, 0 },
-#endif
-  /* -d must come last before the options handled in startup.el.  */
-  { "-d", "--display", 60, 1 },
-  { "-display", 0, 60, 1 },
-  /* Now for the options handled in `command-line' (startup.el).  */
-  /* (Note that to imply -nsl, -Q is partially handled here.)  */
-  { "-Q", "--quick", 55, 0 },
-  { "-quick", 0, 55, 0 },
-  { "-q", "--no-init-file", 50, 0 },
-  { "-no-init-file", 0, 50, 0 },
-  { "-no-x-resources", "--no-x-resources", 40, 0 },
-  { "-no-site-file", "--no-site-file", 40, 0 },
-  { "-u", "--user", 30, 1 },
-  { "-user", 0, 30, 1 },
-  { "-debug-init", "--debug-init", 20, 0 },
-  { "-iconic", "--iconic", 15, 0 },
-  { "-D", "--basic-display", 12, 0},
-  { "-basic-display", 0, 12, 0},
-  { "-nbc", "--no-blinking-cursor", 12, 0 },
-  /* Now for the options handled in `command-line-1' (startup.el).  */
-  { "-nbi", "--no-bitmap-icon", 10, 0 },
-  { "-bg", "--background-color", 10, 1 },
-  { "-background", 0, 10, 1 },
-  { "-fg", "--foreground-color", 10, 1 },
-  { "-foreground", 0, 10, 1 },
-  { "-bd", "--border-color", 10, 1 },
-  { "-bw", "--border-width", 10, 1 },
-  { "-ib", "--internal-border", 10, 1 },
-  { "-ms", "--mouse-color", 10, 1 },
-  { "-cr", "--cursor-color", 10, 1 },
-  { "-fn", "--font", 10, 1 },
-  { "-font", 0, 10, 1 },
-  { "-fs", "--fullscreen", 10, 0 },
-  { "-fw", "--fullwidth", 10, 0 },
-  { "-fh", "--fullheight", 10, 0 },
-  { "-mm", "--maximized", 10, 0 },
-  { "-g", "--geometry", 10, 1 },
-  { "-geometry", 0, 10, 1 },
-  { "-T", "--title", 10, 1 },
-  { "-title", 0, 10, 1 },
-  { "-name", "--name", 10, 1 },
-  { "-xrm", "--xrm", 10, 1 },
-  { "-parent-id", "--parent-id", 10, 1 },
-  { "-r", "--reverse-video", 5, 0 },
-  { "-rv", 0, 5, 0 },
-  { "-reverse", 0, 5, 0 },
-  { "-hb", "--horizontal-scroll-bars", 5, 0 },
-  { "-vb", "--vertical-scroll-bars", 5, 0 },
-  { "-color", "--color", 5, 0},
-  { "-no-splash", "--no-splash", 3, 0 },
-  { "-no-desktop", "--no-desktop", 3, 0 },
-#ifdef HAVE_NS
-  { "-NSAutoLaunch", 0, 5, 1 },
-  { "-NXAutoLaunch", 0, 5, 1 },
-  { "-_NSMachLaunch", 0, 85, 1 },
-  { "-MachLaunch", 0, 85, 1 },
-  { "-macosx", 0, 85, 0 },
-  { "-NSHost", 0, 85, 1 },
-#endif
-  /* These have the same priority as ordinary file name args,
-     so they are not reordered with respect to those.  */
-  { "-L", "--directory", 0, 1 },
-  { "-directory", 0, 0, 1 },
-  { "-l", "--load", 0, 1 },
-  { "-load", 0, 0, 1 },
-  /* This has no longname, because using --scriptload confuses sort_args,
-     because then the --script long option seems to match twice; ie
-     you can't have a long option which is a prefix of another long
-     option.  In any case, this is entirely an internal option.  */
-  { "-scriptload", NULL, 0, 1 },
-  { "-f", "--funcall", 0, 1 },
-  { "-funcall", 0, 0, 1 },
-  { "-eval", "--eval", 0, 1 },
-  { "-execute", "--execute", 0, 1 },
-  { "-find-file", "--find-file", 0, 1 },
-  { "-visit", "--visit", 0, 1 },
-  { "-file", "--file", 0, 1 },
-  { "-insert", "--insert", 0, 1 },
-#ifdef HAVE_NS
-  { "-NXOpen", 0, 0, 1 },
-  { "-NXOpenTemp", 0, 0, 1 },
-  { "-NSOpen", 0, 0, 1 },
-  { "-NSOpenTemp", 0, 0, 1 },
-  { "-GSFilePath", 0, 0, 1 },
+#ifdef HAVE_X_WINDOWS
+  x_clipboard_manager_save_all ();
 #endif
-  /* This should be processed after ordinary file name args and the like.  */
-  { "-kill", "--kill", -10, 0 },
-};
-
-/* Reorder the elements of ARGV (assumed to have ARGC elements)
-   so that the highest priority ones come first.
-   Do not change the order of elements of equal priority.
-   If an option takes an argument, keep it and its argument together.
+}
 
-   If an option that takes no argument appears more
-   than once, eliminate all but one copy of it.  */
+/* Return true if standard input has reached end of file.  */
 
-static void
-sort_args (int argc, char **argv)
+bool
+stdin_at_eof (void)
 {
-  char **new = xmalloc (argc * sizeof *new);
-  /* For each element of argv,
-     the corresponding element of options is:
-     0 for an option that takes no arguments,
-     1 for an option that takes one argument, etc.
-     -1 for an ordinary non-option argument.  */
-  int *options = xnmalloc (argc, sizeof *options);
-  int *priority = xnmalloc (argc, sizeof *priority);
-  int to = 1;
-  int incoming_used = 1;
-  int from;
-  int i;
-
-  /* Categorize all the options,
-     and figure out which argv elts are option arguments.  */
-  for (from = 1; from < argc; from++)
-    {
-      options[from] = -1;
-      priority[from] = 0;
-      if (argv[from][0] == '-')
-	{
-	  int match;
-
-	  /* If we have found "--", don't consider
-	     any more arguments as options.  */
-	  if (argv[from][1] == '-' && argv[from][2] == 0)
-	    {
-	      /* Leave the "--", and everything following it, at the end.  */
-	      for (; from < argc; from++)
-		{
-		  priority[from] = -100;
-		  options[from] = -1;
-		}
-	      break;
-	    }
-
-	  /* Look for a match with a known old-fashioned option.  */
-	  for (i = 0; i < ARRAYELTS (standard_args); i++)
-	    if (!strcmp (argv[from], standard_args[i].name))
-	      {
-		options[from] = standard_args[i].nargs;
-		priority[from] = standard_args[i].priority;
-		if (from + standard_args[i].nargs >= argc)
-		  fatal ("Option '%s' requires an argument\n", argv[from]);
-		from += standard_args[i].nargs;
-		goto done;
-	      }
-
-	  /* Look for a match with a known long option.
-	     MATCH is -1 if no match so far, -2 if two or more matches so far,
-	     >= 0 (the table index of the match) if just one match so far.  */
-	  if (argv[from][1] == '-')
-	    {
-	      char const *equals = strchr (argv[from], '=');
-	      ptrdiff_t thislen =
-		equals ? equals - argv[from] : strlen (argv[from]);
-
-	      match = -1;
-
-	      for (i = 0; i < ARRAYELTS (standard_args); i++)
-		if (standard_args[i].longname
-		    && !strncmp (argv[from], standard_args[i].longname,
-				 thislen))
-		  {
-		    if (match == -1)
-		      match = i;
-		    else
-		      match = -2;
-		  }
-
-	      /* If we found exactly one match, use that.  */
-	      if (match >= 0)
-		{
-		  options[from] = standard_args[match].nargs;
-		  priority[from] = standard_args[match].priority;
-		  /* If --OPTION=VALUE syntax is used,
-		     this option uses just one argv element.  */
-		  if (equals != 0)
-		    options[from] = 0;
-		  if (from + options[from] >= argc)
-		    fatal ("Option '%s' requires an argument\n", argv[from]);
-		  from += options[from];
-		}
-	      else if (match == -2)
-		{
-		  /* This is an internal error.
-		     Eg if one long option is a prefix of another.  */
-		  fprintf (stderr, "Option '%s' matched multiple standard arguments\n", argv[from]);
-		}
-	      /* Should we not also warn if there was no match?	 */
-	    }
-	done: ;
-	}
-    }
-
-  /* Copy the arguments, in order of decreasing priority, to NEW.  */
-  new[0] = argv[0];
-  while (incoming_used < argc)
-    {
-      int best = -1;
-      int best_priority = -9999;
-
-      /* Find the highest priority remaining option.
-	 If several have equal priority, take the first of them.  */
-      for (from = 1; from < argc; from++)
-	{
-	  if (argv[from] != 0 && priority[from] > best_priority)
-	    {
-	      best_priority = priority[from];
-	      best = from;
-	    }
-	  /* Skip option arguments--they are tied to the options.  */
-	  if (options[from] > 0)
-	    from += options[from];
-	}
-
-      if (best < 0)
-	emacs_abort ();
-
-      /* Copy the highest priority remaining option, with its args, to NEW.
-         Unless it is a duplicate of the previous one.  */
-      if (! (options[best] == 0
-	     && ! strcmp (new[to - 1], argv[best])))
-	{
-	  new[to++] = argv[best];
-	  for (i = 0; i < options[best]; i++)
-	    new[to++] = argv[best + i + 1];
-	}
-
-      incoming_used += 1 + (options[best] > 0 ? options[best] : 0);
-
-      /* Clear out this option in ARGV.  */
-      argv[best] = 0;
-      for (i = 0; i < options[best]; i++)
-	argv[best + i + 1] = 0;
-    }
-
-  /* If duplicate options were deleted, fill up extra space with null ptrs.  */
-  while (to < argc)
-    new[to++] = 0;
-
-  memcpy (argv, new, sizeof (char *) * argc);
-
-  xfree (options);
-  xfree (new);
-  xfree (priority);
+  return feof (stdin);
 }
-
-DEFUN ("kill-emacs", Fkill_emacs, Skill_emacs, 0, 1, "P",
-       doc: /* Exit the Emacs job and kill it.
-If ARG is an integer, return ARG as the exit program code.
-If ARG is a string, stuff it as keyboard input.
-
-This function is called upon receipt of the signals SIGTERM
-or SIGHUP, and upon SIGINT in batch mode.
-
-The value of `kill-emacs-hook', if not void,
-is a list of functions (of no args),
-all of which are called before Emacs is actually killed.  */
-       attributes: noreturn)
-  (Lisp_Object arg)
-{
-  int exit_code;
-
-  /* Fsignal calls emacs_abort () if it sees that waiting_for_input is
-     set.  */
-  waiting_for_input = 0;
-  if (noninteractive)
-    safe_run_hooks (Qkill_emacs_hook);
-  else
-    run_hook (Qkill_emacs_hook);
-
-#ifdef HAVE_X_WINDOWS
-  /* Transfer any clipboards we own to the clipboard manager.  */
-  x_clipboard_manager_save_all ();
-#endif
-
-  shut_down_emacs (0, (STRINGP (arg) && !feof (stdin)) ? arg : Qnil);
 
+void
+kill_emacs_release_pool (void)
+{
 #ifdef HAVE_NS
   ns_release_autorelease_pool (ns_pool);
 #endif
-
-  /* If we have an auto-save list file,
-     kill it because we are exiting Emacs deliberately (not crashing).
-     Do it after shut_down_emacs, which does an auto-save.  */
-  if (STRINGP (Vauto_save_list_file_name))
-    {
-      Lisp_Object listfile;
-      listfile = Fexpand_file_name (Vauto_save_list_file_name, Qnil);
-      unlink (SSDATA (listfile));
-    }
-
-  if (INTEGERP (arg))
-    exit_code = (XINT (arg) < 0
-		 ? XINT (arg) | INT_MIN
-		 : XINT (arg) & INT_MAX);
-  else
-    exit_code = EXIT_SUCCESS;
-  exit (exit_code);
 }
 
-
-/* Perform an orderly shutdown of Emacs.  Autosave any modified
-   buffers, kill any child processes, clean up the terminal modes (if
-   we're in the foreground), and other stuff like that.  Don't perform
-   any redisplay; this may be called when Emacs is shutting down in
-   the background, or after its X connection has died.
-
-   If SIG is a signal number, print a message for it.
-
-   This is called by fatal signal handlers, X protocol error handlers,
-   and Fkill_emacs.  */
+/* If we are controlling the terminal, reset terminal modes.  If SIG
+   is a signal number, print a message for it.  */
 
 void
-shut_down_emacs (int sig, Lisp_Object stuff)
+shut_down_terminal (int sig)
 {
-  /* Prevent running of hooks from now on.  */
-  Vrun_hooks = Qnil;
-
-  /* Don't update display from now on.  */
-  Vinhibit_redisplay = Qt;
-
-  /* If we are controlling the terminal, reset terminal modes.  */
 #ifndef DOS_NT
-  {
-    pid_t pgrp = getpgrp ();
-    pid_t tpgrp = tcgetpgrp (0);
-    if ((tpgrp != -1) && tpgrp == pgrp)
-      {
-	reset_all_sys_modes ();
-	if (sig && sig != SIGTERM)
-	  {
-	    static char const format[] = "Fatal error %d: ";
-	    char buf[sizeof format - 2 + INT_STRLEN_BOUND (int)];
-	    int buflen = sprintf (buf, format, sig);
-	    char const *sig_desc = safe_strsignal (sig);
-	    emacs_write (STDERR_FILENO, buf, buflen);
-	    emacs_write (STDERR_FILENO, sig_desc, strlen (sig_desc));
-	  }
-      }
-  }
+  pid_t pgrp = getpgrp ();
+  pid_t tpgrp = tcgetpgrp (0);
+  if ((tpgrp != -1) && tpgrp == pgrp)
+    {
+      reset_all_sys_modes ();
+      if (sig && sig != SIGTERM)
+	{
+	  static char const format[] = "Fatal error %d: ";
+	  char buf[sizeof format - 2 + INT_STRLEN_BOUND (int)];
+	  int buflen = sprintf (buf, format, sig);
+	  char const *sig_desc = safe_strsignal (sig);
+	  emacs_write (STDERR_FILENO, buf, buflen);
+	  emacs_write (STDERR_FILENO, sig_desc, strlen (sig_desc));
+	}
+    }
 #else
   fflush (stdout);
   reset_all_sys_modes ();
 #endif
+}
 
-  stuff_buffered_input (stuff);
-
-  inhibit_sentinels = 1;
-  kill_buffer_processes (Qnil);
-  Fdo_auto_save (Qt, Qnil);
-
-  unlock_all_files ();
-
-  /* There is a tendency for a SIGIO signal to arrive within exit,
-     and cause a SIGHUP because the input descriptor is already closed.  */
-  unrequest_sigio ();
-
-  /* Do this only if terminating normally, we want glyph matrices
-     etc. in a core dump.  */
-  if (sig == 0 || sig == SIGTERM)
-    {
-      check_glyph_memory ();
-      check_message_stack ();
-    }
-
+void
+shut_down_platform (int sig)
+{
 #ifdef HAVE_NS
   ns_term_shutdown (sig);
 #endif
@@ -2066,7 +1534,7 @@ shut_down_emacs (int sig, Lisp_Object stuff)
 }
 
 
-
+
 #ifndef CANNOT_DUMP
 
 #include "unexec.h"
@@ -2441,6 +1909,8 @@ from the parent process and its tty file descriptors.  */)
   return Qt;
 }
 
+extern void rust_syms_of_emacs (void);
+
 void
 syms_of_emacs (void)
 {
@@ -2453,8 +1923,6 @@ syms_of_emacs (void)
   defsubr (&Sdump_emacs);
 #endif
 
-  defsubr (&Skill_emacs);
-
   defsubr (&Sdaemonp);
   defsubr (&Sdaemon_initialized);
 
@@ -2602,4 +2070,6 @@ libraries; only those already known by Emacs will be loaded.  */);
   Vlibrary_cache = Qnil;
   staticpro (&Vlibrary_cache);
 #endif
+
+  rust_syms_of_emacs ();
 }
@@ -100,8 +100,6 @@ Lisp_Object backtrace_function (union specbinding *) EXTERNALLY_VISIBLE;
 union specbinding *backtrace_next (union specbinding *) EXTERNALLY_VISIBLE;
 union specbinding *backtrace_top (void) EXTERNALLY_VISIBLE;
 
-static Lisp_Object apply_lambda (Lisp_Object, Lisp_Object, ptrdiff_t);
-static Lisp_Object lambda_arity (Lisp_Object);
 
 static Lisp_Object
 specpdl_symbol (union specbinding *pdl)
@@ -117,14 +115,14 @@ specpdl_kind (union specbinding *pdl)
   return pdl->let.kind;
 }
 
-static Lisp_Object
+Lisp_Object
 specpdl_old_value (union specbinding *pdl)
 {
   eassert (pdl->kind >= SPECPDL_LET);
   return pdl->let.old_value;
 }
 
-static void
+void
 set_specpdl_old_value (union specbinding *pdl, Lisp_Object val)
 {
   eassert (pdl->kind >= SPECPDL_LET);
@@ -182,7 +180,7 @@ backtrace_debug_on_exit (union specbinding *pdl)
 
 /* Functions to modify slots of backtrace records.  */
 
-static void
+void
 set_backtrace_args (union specbinding *pdl, Lisp_Object *args, ptrdiff_t nargs)
 {
   eassert (pdl->kind == SPECPDL_BACKTRACE);
@@ -417,7 +415,7 @@ The return value is BASE-VARIABLE.  */)
   return base_variable;
 }
 
-static union specbinding *
+union specbinding *
 default_toplevel_binding (Lisp_Object symbol)
 {
   union specbinding *binding = NULL;
@@ -447,114 +445,6 @@ default_toplevel_binding (Lisp_Object symbol)
   return binding;
 }
 
-DEFUN ("default-toplevel-value", Fdefault_toplevel_value, Sdefault_toplevel_value, 1, 1, 0,
-       doc: /* Return SYMBOL's toplevel default value.
-"Toplevel" means outside of any let binding.  */)
-  (Lisp_Object symbol)
-{
-  union specbinding *binding = default_toplevel_binding (symbol);
-  Lisp_Object value
-    = binding ? specpdl_old_value (binding) : Fdefault_value (symbol);
-  if (!EQ (value, Qunbound))
-    return value;
-  xsignal1 (Qvoid_variable, symbol);
-}
-
-DEFUN ("set-default-toplevel-value", Fset_default_toplevel_value,
-       Sset_default_toplevel_value, 2, 2, 0,
-       doc: /* Set SYMBOL's toplevel default value to VALUE.
-"Toplevel" means outside of any let binding.  */)
-     (Lisp_Object symbol, Lisp_Object value)
-{
-  union specbinding *binding = default_toplevel_binding (symbol);
-  if (binding)
-    set_specpdl_old_value (binding, value);
-  else
-    Fset_default (symbol, value);
-  return Qnil;
-}
-
-DEFUN ("defvar", Fdefvar, Sdefvar, 1, UNEVALLED, 0,
-       doc: /* Define SYMBOL as a variable, and return SYMBOL.
-You are not required to define a variable in order to use it, but
-defining it lets you supply an initial value and documentation, which
-can be referred to by the Emacs help facilities and other programming
-tools.  The `defvar' form also declares the variable as \"special\",
-so that it is always dynamically bound even if `lexical-binding' is t.
-
-If SYMBOL's value is void and the optional argument INITVALUE is
-provided, INITVALUE is evaluated and the result used to set SYMBOL's
-value.  If SYMBOL is buffer-local, its default value is what is set;
-buffer-local values are not affected.  If INITVALUE is missing,
-SYMBOL's value is not set.
-
-If SYMBOL has a local binding, then this form affects the local
-binding.  This is usually not what you want.  Thus, if you need to
-load a file defining variables, with this form or with `defconst' or
-`defcustom', you should always load that file _outside_ any bindings
-for these variables.  (`defconst' and `defcustom' behave similarly in
-this respect.)
-
-The optional argument DOCSTRING is a documentation string for the
-variable.
-
-To define a user option, use `defcustom' instead of `defvar'.
-usage: (defvar SYMBOL &optional INITVALUE DOCSTRING)  */)
-  (Lisp_Object args)
-{
-  Lisp_Object sym, tem, tail;
-
-  sym = XCAR (args);
-  tail = XCDR (args);
-
-  if (!NILP (tail))
-    {
-      if (!NILP (XCDR (tail)) && !NILP (XCDR (XCDR (tail))))
-	error ("Too many arguments");
-
-      tem = Fdefault_boundp (sym);
-
-      /* Do it before evaluating the initial value, for self-references.  */
-      XSYMBOL (sym)->u.s.declared_special = true;
-
-      if (NILP (tem))
-	Fset_default (sym, eval_sub (XCAR (tail)));
-      else
-	{ /* Check if there is really a global binding rather than just a let
-	     binding that shadows the global unboundness of the var.  */
-	  union specbinding *binding = default_toplevel_binding (sym);
-	  if (binding && EQ (specpdl_old_value (binding), Qunbound))
-	    {
-	      set_specpdl_old_value (binding, eval_sub (XCAR (tail)));
-	    }
-	}
-      tail = XCDR (tail);
-      tem = Fcar (tail);
-      if (!NILP (tem))
-	{
-	  if (!NILP (Vpurify_flag))
-	    tem = Fpurecopy (tem);
-	  Fput (sym, Qvariable_documentation, tem);
-	}
-      LOADHIST_ATTACH (sym);
-    }
-  else if (!NILP (Vinternal_interpreter_environment)
-	   && !XSYMBOL (sym)->u.s.declared_special)
-    /* A simple (defvar foo) with lexical scoping does "nothing" except
-       declare that var to be dynamically scoped *locally* (i.e. within
-       the current file or let-block).  */
-    Vinternal_interpreter_environment
-      = Fcons (sym, Vinternal_interpreter_environment);
-  else
-    {
-      /* Simple (defvar <var>) should not count as a definition at all.
-	 It could get in the way of other definitions, and unloading this
-	 package could try to make the variable unbound.  */
-    }
-
-  return sym;
-}
-
 /* Assert that E is true, but do not evaluate E.  Use this instead of
    eassert (E) when E contains variables that might be clobbered by a
    longjmp.  */
@@ -605,7 +495,7 @@ internal_catch (Lisp_Object tag,
 
    This is used for correct unwinding in Fthrow and Fsignal.  */
 
-static _Noreturn void
+_Noreturn void
 unwind_to_catch (struct handler *catch, Lisp_Object value)
 {
   bool last_time;
@@ -643,52 +533,7 @@ Both TAG and VALUE are evalled.  */
        attributes: noreturn)
   (register Lisp_Object tag, Lisp_Object value)
 {
-  struct handler *c;
-
-  if (!NILP (tag))
-    for (c = handlerlist; c; c = c->next)
-      {
-	if (c->type == CATCHER_ALL)
-          unwind_to_catch (c, Fcons (tag, value));
-	if (c->type == CATCHER && EQ (c->tag_or_ch, tag))
-	  unwind_to_catch (c, value);
-      }
-  xsignal2 (Qno_catch, tag, value);
-}
-
-DEFUN ("condition-case", Fcondition_case, Scondition_case, 2, UNEVALLED, 0,
-       doc: /* Regain control when an error is signaled.
-Executes BODYFORM and returns its value if no error happens.
-Each element of HANDLERS looks like (CONDITION-NAME BODY...)
-where the BODY is made of Lisp expressions.
-
-A handler is applicable to an error
-if CONDITION-NAME is one of the error's condition names.
-If an error happens, the first applicable handler is run.
-
-The car of a handler may be a list of condition names instead of a
-single condition name; then it handles all of them.  If the special
-condition name `debug' is present in this list, it allows another
-condition in the list to run the debugger if `debug-on-error' and the
-other usual mechanisms says it should (otherwise, `condition-case'
-suppresses the debugger).
-
-When a handler handles an error, control returns to the `condition-case'
-and it executes the handler's BODY...
-with VAR bound to (ERROR-SYMBOL . SIGNAL-DATA) from the error.
-\(If VAR is nil, the handler can't access that information.)
-Then the value of the last BODY form is returned from the `condition-case'
-expression.
-
-See also the function `signal' for more info.
-usage: (condition-case VAR BODYFORM &rest HANDLERS)  */)
-  (Lisp_Object args)
-{
-  Lisp_Object var = XCAR (args);
-  Lisp_Object bodyform = XCAR (XCDR (args));
-  Lisp_Object handlers = XCDR (XCDR (args));
-
-  return internal_lisp_condition_case (var, bodyform, handlers);
+  throw_to_catch (tag, value);
 }
 
 /* Like Fcondition_case, but the args are separate
@@ -967,11 +812,6 @@ push_handler_nosignal (Lisp_Object tag_ch_val, enum handlertype handlertype)
 }
 
 
-static Lisp_Object signal_or_quit (Lisp_Object, Lisp_Object, bool);
-static Lisp_Object find_handler_clause (Lisp_Object, Lisp_Object);
-static bool maybe_call_debugger (Lisp_Object conditions, Lisp_Object sig,
-				 Lisp_Object data);
-
 static void
 process_quit_flag (void)
 {
@@ -1037,121 +877,6 @@ quit (void)
   return signal_or_quit (Qquit, Qnil, true);
 }
 
-/* Signal an error, or quit.  ERROR_SYMBOL and DATA are as with Fsignal.
-   If KEYBOARD_QUIT, this is a quit; ERROR_SYMBOL should be
-   Qquit and DATA should be Qnil, and this function may return.
-   Otherwise this function is like Fsignal and does not return.  */
-
-static Lisp_Object
-signal_or_quit (Lisp_Object error_symbol, Lisp_Object data, bool keyboard_quit)
-{
-  /* When memory is full, ERROR-SYMBOL is nil,
-     and DATA is (REAL-ERROR-SYMBOL . REAL-DATA).
-     That is a special case--don't do this in other situations.  */
-  Lisp_Object conditions;
-  Lisp_Object string;
-  Lisp_Object real_error_symbol
-    = (NILP (error_symbol) ? Fcar (data) : error_symbol);
-  Lisp_Object clause = Qnil;
-  struct handler *h;
-
-  if (gc_in_progress || waiting_for_input)
-    emacs_abort ();
-
-#if 0 /* rms: I don't know why this was here,
-	 but it is surely wrong for an error that is handled.  */
-#ifdef HAVE_WINDOW_SYSTEM
-  if (display_hourglass_p)
-    cancel_hourglass ();
-#endif
-#endif
-
-  /* This hook is used by edebug.  */
-  if (! NILP (Vsignal_hook_function)
-      && ! NILP (error_symbol)
-      /* Don't try to call a lisp function if we've already overflowed
-         the specpdl stack.  */
-      && specpdl_ptr < specpdl + specpdl_size)
-    {
-      /* Edebug takes care of restoring these variables when it exits.  */
-      if (lisp_eval_depth + 20 > max_lisp_eval_depth)
-	max_lisp_eval_depth = lisp_eval_depth + 20;
-
-      if (SPECPDL_INDEX () + 40 > max_specpdl_size)
-	max_specpdl_size = SPECPDL_INDEX () + 40;
-
-      call2 (Vsignal_hook_function, error_symbol, data);
-    }
-
-  conditions = Fget (real_error_symbol, Qerror_conditions);
-
-  /* Remember from where signal was called.  Skip over the frame for
-     `signal' itself.  If a frame for `error' follows, skip that,
-     too.  Don't do this when ERROR_SYMBOL is nil, because that
-     is a memory-full error.  */
-  Vsignaling_function = Qnil;
-  if (!NILP (error_symbol))
-    {
-      union specbinding *pdl = backtrace_next (backtrace_top ());
-      if (backtrace_p (pdl) && EQ (backtrace_function (pdl), Qerror))
-	pdl = backtrace_next (pdl);
-      if (backtrace_p (pdl))
-	Vsignaling_function = backtrace_function (pdl);
-    }
-
-  for (h = handlerlist; h; h = h->next)
-    {
-      if (h->type != CONDITION_CASE)
-	continue;
-      clause = find_handler_clause (h->tag_or_ch, conditions);
-      if (!NILP (clause))
-	break;
-    }
-
-  if (/* Don't run the debugger for a memory-full error.
-	 (There is no room in memory to do that!)  */
-      !NILP (error_symbol)
-      && (!NILP (Vdebug_on_signal)
-	  /* If no handler is present now, try to run the debugger.  */
-	  || NILP (clause)
-	  /* A `debug' symbol in the handler list disables the normal
-	     suppression of the debugger.  */
-	  || (CONSP (clause) && !NILP (Fmemq (Qdebug, clause)))
-	  /* Special handler that means "print a message and run debugger
-	     if requested".  */
-	  || EQ (h->tag_or_ch, Qerror)))
-    {
-      bool debugger_called
-	= maybe_call_debugger (conditions, error_symbol, data);
-      /* We can't return values to code which signaled an error, but we
-	 can continue code which has signaled a quit.  */
-      if (keyboard_quit && debugger_called && EQ (real_error_symbol, Qquit))
-	return Qnil;
-    }
-
-  if (!NILP (clause))
-    {
-      Lisp_Object unwind_data
-	= (NILP (error_symbol) ? data : Fcons (error_symbol, data));
-
-      unwind_to_catch (h, unwind_data);
-    }
-  else
-    {
-      if (handlerlist != handlerlist_sentinel)
-	/* FIXME: This will come right back here if there's no `top-level'
-	   catcher.  A better solution would be to abort here, and instead
-	   add a catch-all condition handler so we never come here.  */
-	Fthrow (Qtop_level, Qt);
-    }
-
-  if (! NILP (error_symbol))
-    data = Fcons (error_symbol, data);
-
-  string = Ferror_message_string (data);
-  fatal ("%s", SDATA (string));
-}
-
 /* Like xsignal, but takes 0, 1, 2, or 3 args instead of a list.  */
 
 void
@@ -1273,7 +998,7 @@ skip_debugger (Lisp_Object conditions, Lisp_Object data)
     = SIG is the error symbol, and DATA is the rest of the data.
     = SIG is nil, and DATA is (SYMBOL . REST-OF-DATA).
       This is for memory-full errors only.  */
-static bool
+bool
 maybe_call_debugger (Lisp_Object conditions, Lisp_Object sig, Lisp_Object data)
 {
   Lisp_Object combined_data;
@@ -1300,31 +1025,6 @@ maybe_call_debugger (Lisp_Object conditions, Lisp_Object sig, Lisp_Object data)
   return 0;
 }
 
-static Lisp_Object
-find_handler_clause (Lisp_Object handlers, Lisp_Object conditions)
-{
-  register Lisp_Object h;
-
-  /* t is used by handlers for all conditions, set up by C code.  */
-  if (EQ (handlers, Qt))
-    return Qt;
-
-  /* error is used similarly, but means print an error message
-     and run the debugger if that is enabled.  */
-  if (EQ (handlers, Qerror))
-    return Qt;
-
-  for (h = handlers; CONSP (h); h = XCDR (h))
-    {
-      Lisp_Object handler = XCAR (h);
-      if (!NILP (Fmemq (handler, conditions)))
-	return handlers;
-    }
-
-  return Qnil;
-}
-
-
 /* Format and return a string; called like vprintf.  */
 Lisp_Object
 vformat_string (const char *m, va_list ap)
@@ -1415,292 +1115,6 @@ record_in_backtrace (Lisp_Object function, Lisp_Object *args, ptrdiff_t nargs)
   return count;
 }
 
-/* Eval a sub-expression of the current expression (i.e. in the same
-   lexical scope).  */
-Lisp_Object
-eval_sub (Lisp_Object form)
-{
-  Lisp_Object fun, val, original_fun, original_args;
-  Lisp_Object funcar;
-  ptrdiff_t count;
-
-  /* Declare here, as this array may be accessed by call_debugger near
-     the end of this function.  See Bug#21245.  */
-  Lisp_Object argvals[8];
-
-  if (SYMBOLP (form))
-    {
-      /* Look up its binding in the lexical environment.
-	 We do not pay attention to the declared_special flag here, since we
-	 already did that when let-binding the variable.  */
-      Lisp_Object lex_binding
-	= !NILP (Vinternal_interpreter_environment) /* Mere optimization!  */
-	? Fassq (form, Vinternal_interpreter_environment)
-	: Qnil;
-      if (CONSP (lex_binding))
-	return XCDR (lex_binding);
-      else
-	return Fsymbol_value (form);
-    }
-
-  if (!CONSP (form))
-    return form;
-
-  maybe_quit ();
-
-  maybe_gc ();
-
-  if (++lisp_eval_depth > max_lisp_eval_depth)
-    {
-      if (max_lisp_eval_depth < 100)
-	max_lisp_eval_depth = 100;
-      if (lisp_eval_depth > max_lisp_eval_depth)
-	error ("Lisp nesting exceeds `max-lisp-eval-depth'");
-    }
-
-  original_fun = XCAR (form);
-  original_args = XCDR (form);
-  CHECK_LIST (original_args);
-
-  /* This also protects them from gc.  */
-  count = record_in_backtrace (original_fun, &original_args, UNEVALLED);
-
-  if (debug_on_next_call)
-    do_debug_on_call (Qt, count);
-
-  /* At this point, only original_fun and original_args
-     have values that will be used below.  */
- retry:
-
-  /* Optimize for no indirection.  */
-  fun = original_fun;
-  if (!SYMBOLP (fun))
-    fun = Ffunction (Fcons (fun, Qnil));
-  else if (!NILP (fun) && (fun = XSYMBOL (fun)->u.s.function, SYMBOLP (fun)))
-    fun = indirect_function (fun);
-
-  if (SUBRP (fun))
-    {
-      Lisp_Object args_left = original_args;
-      Lisp_Object numargs = Flength (args_left);
-
-      check_cons_list ();
-
-      if (XINT (numargs) < XSUBR (fun)->min_args
-	  || (XSUBR (fun)->max_args >= 0
-	      && XSUBR (fun)->max_args < XINT (numargs)))
-	xsignal2 (Qwrong_number_of_arguments, original_fun, numargs);
-
-      else if (XSUBR (fun)->max_args == UNEVALLED)
-	val = (XSUBR (fun)->function.aUNEVALLED) (args_left);
-      else if (XSUBR (fun)->max_args == MANY)
-	{
-	  /* Pass a vector of evaluated arguments.  */
-	  Lisp_Object *vals;
-	  ptrdiff_t argnum = 0;
-	  USE_SAFE_ALLOCA;
-
-	  SAFE_ALLOCA_LISP (vals, XINT (numargs));
-
-	  while (CONSP (args_left) && argnum < XINT (numargs))
-	    {
-	      Lisp_Object arg = XCAR (args_left);
-	      args_left = XCDR (args_left);
-	      vals[argnum++] = eval_sub (arg);
-	    }
-
-	  set_backtrace_args (specpdl + count, vals, argnum);
-
-	  val = XSUBR (fun)->function.aMANY (argnum, vals);
-
-	  check_cons_list ();
-	  lisp_eval_depth--;
-	  /* Do the debug-on-exit now, while VALS still exists.  */
-	  if (backtrace_debug_on_exit (specpdl + count))
-	    val = call_debugger (list2 (Qexit, val));
-	  SAFE_FREE ();
-	  specpdl_ptr--;
-	  return val;
-	}
-      else
-	{
-	  int i, maxargs = XSUBR (fun)->max_args;
-
-	  for (i = 0; i < maxargs; i++)
-	    {
-	      argvals[i] = eval_sub (Fcar (args_left));
-	      args_left = Fcdr (args_left);
-	    }
-
-	  set_backtrace_args (specpdl + count, argvals, XINT (numargs));
-
-	  switch (i)
-	    {
-	    case 0:
-	      val = (XSUBR (fun)->function.a0 ());
-	      break;
-	    case 1:
-	      val = (XSUBR (fun)->function.a1 (argvals[0]));
-	      break;
-	    case 2:
-	      val = (XSUBR (fun)->function.a2 (argvals[0], argvals[1]));
-	      break;
-	    case 3:
-	      val = (XSUBR (fun)->function.a3
-		     (argvals[0], argvals[1], argvals[2]));
-	      break;
-	    case 4:
-	      val = (XSUBR (fun)->function.a4
-		     (argvals[0], argvals[1], argvals[2], argvals[3]));
-	      break;
-	    case 5:
-	      val = (XSUBR (fun)->function.a5
-		     (argvals[0], argvals[1], argvals[2], argvals[3],
-		      argvals[4]));
-	      break;
-	    case 6:
-	      val = (XSUBR (fun)->function.a6
-		     (argvals[0], argvals[1], argvals[2], argvals[3],
-		      argvals[4], argvals[5]));
-	      break;
-	    case 7:
-	      val = (XSUBR (fun)->function.a7
-		     (argvals[0], argvals[1], argvals[2], argvals[3],
-		      argvals[4], argvals[5], argvals[6]));
-	      break;
-
-	    case 8:
-	      val = (XSUBR (fun)->function.a8
-		     (argvals[0], argvals[1], argvals[2], argvals[3],
-		      argvals[4], argvals[5], argvals[6], argvals[7]));
-	      break;
-
-	    default:
-	      /* Someone has created a subr that takes more arguments than
-		 is supported by this code.  We need to either rewrite the
-		 subr to use a different argument protocol, or add more
-		 cases to this switch.  */
-	      emacs_abort ();
-	    }
-	}
-    }
-  else if (COMPILEDP (fun) || MODULE_FUNCTIONP (fun))
-    return apply_lambda (fun, original_args, count);
-  else
-    {
-      if (NILP (fun))
-	xsignal1 (Qvoid_function, original_fun);
-      if (!CONSP (fun))
-	xsignal1 (Qinvalid_function, original_fun);
-      funcar = XCAR (fun);
-      if (!SYMBOLP (funcar))
-	xsignal1 (Qinvalid_function, original_fun);
-      if (EQ (funcar, Qautoload))
-	{
-	  Fautoload_do_load (fun, original_fun, Qnil);
-	  goto retry;
-	}
-      if (EQ (funcar, Qmacro))
-	{
-	  ptrdiff_t count1 = SPECPDL_INDEX ();
-	  Lisp_Object exp;
-	  /* Bind lexical-binding during expansion of the macro, so the
-	     macro can know reliably if the code it outputs will be
-	     interpreted using lexical-binding or not.  */
-	  specbind (Qlexical_binding,
-		    NILP (Vinternal_interpreter_environment) ? Qnil : Qt);
-	  exp = apply1 (Fcdr (fun), original_args);
-	  unbind_to (count1, Qnil);
-	  val = eval_sub (exp);
-	}
-      else if (EQ (funcar, Qlambda)
-	       || EQ (funcar, Qclosure))
-	return apply_lambda (fun, original_args, count);
-      else
-	xsignal1 (Qinvalid_function, original_fun);
-    }
-  check_cons_list ();
-
-  lisp_eval_depth--;
-  if (backtrace_debug_on_exit (specpdl + count))
-    val = call_debugger (list2 (Qexit, val));
-  specpdl_ptr--;
-
-  return val;
-}
-
-DEFUN ("apply", Fapply, Sapply, 1, MANY, 0,
-       doc: /* Call FUNCTION with our remaining args, using our last arg as list of args.
-Then return the value FUNCTION returns.
-Thus, (apply \\='+ 1 2 \\='(3 4)) returns 10.
-usage: (apply FUNCTION &rest ARGUMENTS)  */)
-  (ptrdiff_t nargs, Lisp_Object *args)
-{
-  ptrdiff_t i, numargs, funcall_nargs;
-  register Lisp_Object *funcall_args = NULL;
-  register Lisp_Object spread_arg = args[nargs - 1];
-  Lisp_Object fun = args[0];
-  Lisp_Object retval;
-  USE_SAFE_ALLOCA;
-
-  CHECK_LIST (spread_arg);
-
-  numargs = XINT (Flength (spread_arg));
-
-  if (numargs == 0)
-    return Ffuncall (nargs - 1, args);
-  else if (numargs == 1)
-    {
-      args [nargs - 1] = XCAR (spread_arg);
-      return Ffuncall (nargs, args);
-    }
-
-  numargs += nargs - 2;
-
-  /* Optimize for no indirection.  */
-  if (SYMBOLP (fun) && !NILP (fun)
-      && (fun = XSYMBOL (fun)->u.s.function, SYMBOLP (fun)))
-    {
-      fun = indirect_function (fun);
-      if (NILP (fun))
-	/* Let funcall get the error.  */
-	fun = args[0];
-    }
-
-  if (SUBRP (fun) && XSUBR (fun)->max_args > numargs
-      /* Don't hide an error by adding missing arguments.  */
-      && numargs >= XSUBR (fun)->min_args)
-    {
-      /* Avoid making funcall cons up a yet another new vector of arguments
-	 by explicitly supplying nil's for optional values.  */
-      SAFE_ALLOCA_LISP (funcall_args, 1 + XSUBR (fun)->max_args);
-      memclear (funcall_args + numargs + 1,
-		(XSUBR (fun)->max_args - numargs) * word_size);
-      funcall_nargs = 1 + XSUBR (fun)->max_args;
-    }
-  else
-    { /* We add 1 to numargs because funcall_args includes the
-	 function itself as well as its arguments.  */
-      SAFE_ALLOCA_LISP (funcall_args, 1 + numargs);
-      funcall_nargs = 1 + numargs;
-    }
-
-  memcpy (funcall_args, args, nargs * word_size);
-  /* Spread the last arg we got.  Its first element goes in
-     the slot that it used to occupy, hence this value of I.  */
-  i = nargs - 1;
-  while (!NILP (spread_arg))
-    {
-      funcall_args [i++] = XCAR (spread_arg);
-      spread_arg = XCDR (spread_arg);
-    }
-
-  retval = Ffuncall (funcall_nargs, funcall_args);
-
-  SAFE_FREE ();
-  return retval;
-}
-
 /* Run hook variables in various ways.  */
 
 /* ARGS[0] should be a hook symbol.
@@ -1860,381 +1274,6 @@ call8 (Lisp_Object fn, Lisp_Object arg1, Lisp_Object arg2, Lisp_Object arg3,
   return CALLN (Ffuncall, fn, arg1, arg2, arg3, arg4, arg5, arg6, arg7, arg8);
 }
 
-/* Apply a C subroutine SUBR to the NUMARGS evaluated arguments in ARG_VECTOR
-   and return the result of evaluation.  */
-
-Lisp_Object
-funcall_subr (struct Lisp_Subr *subr, ptrdiff_t numargs, Lisp_Object *args)
-{
-  if (numargs < subr->min_args
-      || (subr->max_args >= 0 && subr->max_args < numargs))
-    {
-      Lisp_Object fun;
-      XSETSUBR (fun, subr);
-      xsignal2 (Qwrong_number_of_arguments, fun, make_number (numargs));
-    }
-
-  else if (subr->max_args == UNEVALLED)
-    {
-      Lisp_Object fun;
-      XSETSUBR (fun, subr);
-      xsignal1 (Qinvalid_function, fun);
-    }
-
-  else if (subr->max_args == MANY)
-    return (subr->function.aMANY) (numargs, args);
-  else
-    {
-      Lisp_Object internal_argbuf[8];
-      Lisp_Object *internal_args;
-      if (subr->max_args > numargs)
-        {
-          eassert (subr->max_args <= ARRAYELTS (internal_argbuf));
-          internal_args = internal_argbuf;
-          memcpy (internal_args, args, numargs * word_size);
-          memclear (internal_args + numargs,
-                    (subr->max_args - numargs) * word_size);
-        }
-      else
-        internal_args = args;
-      switch (subr->max_args)
-        {
-        case 0:
-          return (subr->function.a0 ());
-        case 1:
-          return (subr->function.a1 (internal_args[0]));
-        case 2:
-          return (subr->function.a2
-                  (internal_args[0], internal_args[1]));
-        case 3:
-          return (subr->function.a3
-                  (internal_args[0], internal_args[1], internal_args[2]));
-        case 4:
-          return (subr->function.a4
-                  (internal_args[0], internal_args[1], internal_args[2],
-                   internal_args[3]));
-        case 5:
-          return (subr->function.a5
-                  (internal_args[0], internal_args[1], internal_args[2],
-                   internal_args[3], internal_args[4]));
-        case 6:
-          return (subr->function.a6
-                  (internal_args[0], internal_args[1], internal_args[2],
-                   internal_args[3], internal_args[4], internal_args[5]));
-        case 7:
-          return (subr->function.a7
-                  (internal_args[0], internal_args[1], internal_args[2],
-                   internal_args[3], internal_args[4], internal_args[5],
-                   internal_args[6]));
-        case 8:
-          return (subr->function.a8
-                  (internal_args[0], internal_args[1], internal_args[2],
-                   internal_args[3], internal_args[4], internal_args[5],
-                   internal_args[6], internal_args[7]));
-
-        default:
-
-          /* If a subr takes more than 8 arguments without using MANY
-             or UNEVALLED, we need to extend this function to support it.
-             Until this is done, there is no way to call the function.  */
-          emacs_abort ();
-        }
-    }
-}
-
-static Lisp_Object
-apply_lambda (Lisp_Object fun, Lisp_Object args, ptrdiff_t count)
-{
-  Lisp_Object args_left;
-  ptrdiff_t i;
-  EMACS_INT numargs;
-  Lisp_Object *arg_vector;
-  Lisp_Object tem;
-  USE_SAFE_ALLOCA;
-
-  numargs = XFASTINT (Flength (args));
-  SAFE_ALLOCA_LISP (arg_vector, numargs);
-  args_left = args;
-
-  for (i = 0; i < numargs; )
-    {
-      tem = Fcar (args_left), args_left = Fcdr (args_left);
-      tem = eval_sub (tem);
-      arg_vector[i++] = tem;
-    }
-
-  set_backtrace_args (specpdl + count, arg_vector, i);
-  tem = funcall_lambda (fun, numargs, arg_vector);
-
-  check_cons_list ();
-  lisp_eval_depth--;
-  /* Do the debug-on-exit now, while arg_vector still exists.  */
-  if (backtrace_debug_on_exit (specpdl + count))
-    tem = call_debugger (list2 (Qexit, tem));
-  SAFE_FREE ();
-  specpdl_ptr--;
-  return tem;
-}
-
-/* Apply a Lisp function FUN to the NARGS evaluated arguments in ARG_VECTOR
-   and return the result of evaluation.
-   FUN must be either a lambda-expression, a compiled-code object,
-   or a module function.  */
-
-Lisp_Object
-funcall_lambda (Lisp_Object fun, ptrdiff_t nargs,
-		register Lisp_Object *arg_vector)
-{
-  Lisp_Object val, syms_left, next, lexenv;
-  ptrdiff_t count = SPECPDL_INDEX ();
-  ptrdiff_t i;
-  bool optional, rest;
-
-  if (CONSP (fun))
-    {
-      if (EQ (XCAR (fun), Qclosure))
-	{
-	  Lisp_Object cdr = XCDR (fun);	/* Drop `closure'.  */
-	  if (! CONSP (cdr))
-	    xsignal1 (Qinvalid_function, fun);
-	  fun = cdr;
-	  lexenv = XCAR (fun);
-	}
-      else
-	lexenv = Qnil;
-      syms_left = XCDR (fun);
-      if (CONSP (syms_left))
-	syms_left = XCAR (syms_left);
-      else
-	xsignal1 (Qinvalid_function, fun);
-    }
-  else if (COMPILEDP (fun))
-    {
-      ptrdiff_t size = PVSIZE (fun);
-      if (size <= COMPILED_STACK_DEPTH)
-	xsignal1 (Qinvalid_function, fun);
-      syms_left = AREF (fun, COMPILED_ARGLIST);
-      if (INTEGERP (syms_left))
-	/* A byte-code object with an integer args template means we
-	   shouldn't bind any arguments, instead just call the byte-code
-	   interpreter directly; it will push arguments as necessary.
-
-	   Byte-code objects with a nil args template (the default)
-	   have dynamically-bound arguments, and use the
-	   argument-binding code below instead (as do all interpreted
-	   functions, even lexically bound ones).  */
-	{
-	  /* If we have not actually read the bytecode string
-	     and constants vector yet, fetch them from the file.  */
-	  if (CONSP (AREF (fun, COMPILED_BYTECODE)))
-	    Ffetch_bytecode (fun);
-	  return exec_byte_code (AREF (fun, COMPILED_BYTECODE),
-				 AREF (fun, COMPILED_CONSTANTS),
-				 AREF (fun, COMPILED_STACK_DEPTH),
-				 syms_left,
-				 nargs, arg_vector);
-	}
-      lexenv = Qnil;
-    }
-#ifdef HAVE_MODULES
-  else if (MODULE_FUNCTIONP (fun))
-    return funcall_module (fun, nargs, arg_vector);
-#endif
-  else
-    emacs_abort ();
-
-  i = optional = rest = 0;
-  bool previous_optional_or_rest = false;
-  for (; CONSP (syms_left); syms_left = XCDR (syms_left))
-    {
-      maybe_quit ();
-
-      next = XCAR (syms_left);
-      if (!SYMBOLP (next))
-	xsignal1 (Qinvalid_function, fun);
-
-      if (EQ (next, Qand_rest))
-        {
-          if (rest || previous_optional_or_rest)
-            xsignal1 (Qinvalid_function, fun);
-          rest = 1;
-          previous_optional_or_rest = true;
-        }
-      else if (EQ (next, Qand_optional))
-        {
-          if (optional || rest || previous_optional_or_rest)
-            xsignal1 (Qinvalid_function, fun);
-          optional = 1;
-          previous_optional_or_rest = true;
-        }
-      else
-	{
-	  Lisp_Object arg;
-	  if (rest)
-	    {
-	      arg = Flist (nargs - i, &arg_vector[i]);
-	      i = nargs;
-	    }
-	  else if (i < nargs)
-	    arg = arg_vector[i++];
-	  else if (!optional)
-	    xsignal2 (Qwrong_number_of_arguments, fun, make_number (nargs));
-	  else
-	    arg = Qnil;
-
-	  /* Bind the argument.  */
-	  if (!NILP (lexenv) && SYMBOLP (next))
-	    /* Lexically bind NEXT by adding it to the lexenv alist.  */
-	    lexenv = Fcons (Fcons (next, arg), lexenv);
-	  else
-	    /* Dynamically bind NEXT.  */
-	    specbind (next, arg);
-          previous_optional_or_rest = false;
-	}
-    }
-
-  if (!NILP (syms_left) || previous_optional_or_rest)
-    xsignal1 (Qinvalid_function, fun);
-  else if (i < nargs)
-    xsignal2 (Qwrong_number_of_arguments, fun, make_number (nargs));
-
-  if (!EQ (lexenv, Vinternal_interpreter_environment))
-    /* Instantiate a new lexical environment.  */
-    specbind (Qinternal_interpreter_environment, lexenv);
-
-  if (CONSP (fun))
-    val = Fprogn (XCDR (XCDR (fun)));
-  else
-    {
-      /* If we have not actually read the bytecode string
-	 and constants vector yet, fetch them from the file.  */
-      if (CONSP (AREF (fun, COMPILED_BYTECODE)))
-	Ffetch_bytecode (fun);
-      val = exec_byte_code (AREF (fun, COMPILED_BYTECODE),
-			    AREF (fun, COMPILED_CONSTANTS),
-			    AREF (fun, COMPILED_STACK_DEPTH),
-			    Qnil, 0, 0);
-    }
-
-  return unbind_to (count, val);
-}
-
-DEFUN ("func-arity", Ffunc_arity, Sfunc_arity, 1, 1, 0,
-       doc: /* Return minimum and maximum number of args allowed for FUNCTION.
-FUNCTION must be a function of some kind.
-The returned value is a cons cell (MIN . MAX).  MIN is the minimum number
-of args.  MAX is the maximum number, or the symbol `many', for a
-function with `&rest' args, or `unevalled' for a special form.  */)
-  (Lisp_Object function)
-{
-  Lisp_Object original;
-  Lisp_Object funcar;
-  Lisp_Object result;
-
-  original = function;
-
- retry:
-
-  /* Optimize for no indirection.  */
-  function = original;
-  if (SYMBOLP (function) && !NILP (function))
-    {
-      function = XSYMBOL (function)->u.s.function;
-      if (SYMBOLP (function))
-	function = indirect_function (function);
-    }
-
-  if (CONSP (function) && EQ (XCAR (function), Qmacro))
-    function = XCDR (function);
-
-  if (SUBRP (function))
-    result = Fsubr_arity (function);
-  else if (COMPILEDP (function))
-    result = lambda_arity (function);
-#ifdef HAVE_MODULES
-  else if (MODULE_FUNCTIONP (function))
-    result = module_function_arity (XMODULE_FUNCTION (function));
-#endif
-  else
-    {
-      if (NILP (function))
-	xsignal1 (Qvoid_function, original);
-      if (!CONSP (function))
-	xsignal1 (Qinvalid_function, original);
-      funcar = XCAR (function);
-      if (!SYMBOLP (funcar))
-	xsignal1 (Qinvalid_function, original);
-      if (EQ (funcar, Qlambda)
-	  || EQ (funcar, Qclosure))
-	result = lambda_arity (function);
-      else if (EQ (funcar, Qautoload))
-	{
-	  Fautoload_do_load (function, original, Qnil);
-	  goto retry;
-	}
-      else
-	xsignal1 (Qinvalid_function, original);
-    }
-  return result;
-}
-
-/* FUN must be either a lambda-expression or a compiled-code object.  */
-static Lisp_Object
-lambda_arity (Lisp_Object fun)
-{
-  Lisp_Object syms_left;
-
-  if (CONSP (fun))
-    {
-      if (EQ (XCAR (fun), Qclosure))
-	{
-	  fun = XCDR (fun);	/* Drop `closure'.  */
-	  CHECK_CONS (fun);
-	}
-      syms_left = XCDR (fun);
-      if (CONSP (syms_left))
-	syms_left = XCAR (syms_left);
-      else
-	xsignal1 (Qinvalid_function, fun);
-    }
-  else if (COMPILEDP (fun))
-    {
-      ptrdiff_t size = PVSIZE (fun);
-      if (size <= COMPILED_STACK_DEPTH)
-	xsignal1 (Qinvalid_function, fun);
-      syms_left = AREF (fun, COMPILED_ARGLIST);
-      if (INTEGERP (syms_left))
-        return get_byte_code_arity (syms_left);
-    }
-  else
-    emacs_abort ();
-
-  EMACS_INT minargs = 0, maxargs = 0;
-  bool optional = false;
-  for (; CONSP (syms_left); syms_left = XCDR (syms_left))
-    {
-      Lisp_Object next = XCAR (syms_left);
-      if (!SYMBOLP (next))
-	xsignal1 (Qinvalid_function, fun);
-
-      if (EQ (next, Qand_rest))
-	return Fcons (make_number (minargs), Qmany);
-      else if (EQ (next, Qand_optional))
-	optional = true;
-      else
-	{
-          if (!optional)
-            minargs++;
-          maxargs++;
-        }
-    }
-
-  if (!NILP (syms_left))
-    xsignal1 (Qinvalid_function, fun);
-
-  return Fcons (make_number (minargs), make_number (maxargs));
-}
-
 DEFUN ("fetch-bytecode", Ffetch_bytecode, Sfetch_bytecode,
        1, 1, 0,
        doc: /* If byte-compiled OBJECT is lazy-loaded, fetch it now.  */)
@@ -3090,16 +2129,10 @@ alist of active lexical bindings.  */);
   DEFSYM (Qcatch_all_memory_full, "catch-all-memory-full");
   Funintern (Qcatch_all_memory_full, Qnil);
 
-  defsubr (&Sdefault_toplevel_value);
-  defsubr (&Sset_default_toplevel_value);
-  defsubr (&Sdefvar);
   defsubr (&Sdefvaralias);
   DEFSYM (Qdefvaralias, "defvaralias");
   defsubr (&Sthrow);
-  defsubr (&Scondition_case);
   defsubr (&Ssignal);
-  defsubr (&Sapply);
-  defsubr (&Sfunc_arity);
   defsubr (&Sfetch_bytecode);
   defsubr (&Sbacktrace_debug);
   DEFSYM (QCdebug_on_exit, ":debug-on-exit");
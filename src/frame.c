@@ -53,7 +53,7 @@ Lisp_Object selected_frame;
 /* A frame which is not just a mini-buffer, or NULL if there are no such
    frames.  This is usually the most recent such frame that was selected.  */
 
-static struct frame *last_nonminibuf_frame;
+struct frame *last_nonminibuf_frame;
 
 /* False means there are no visible garbaged frames.  */
 bool frame_garbaged;
@@ -100,6 +100,20 @@ fget_terminal(const struct frame *f)
 {
   return f->terminal;
 }
+Lisp_Object
+fget_parent_frame(const struct frame *f)
+{
+#ifdef HAVE_WINDOW_SYSTEM
+  return f->parent_frame;
+#else
+  return Qnil;
+#endif
+}
+bool
+fget_tooltip(const struct frame *f)
+{
+  return FRAME_TOOLTIP_P (f);
+}
 
 struct frame *
 decode_live_frame (register Lisp_Object frame)
@@ -1195,180 +1209,61 @@ affects all frames on the same terminal device.  */)
 }
 
 
-/* Perform the switch to frame FRAME.
-
-   If FRAME is a switch-frame event `(switch-frame FRAME1)', use
-   FRAME1 as frame.
-
-   If TRACK is non-zero and the frame that currently has the focus
-   redirects its focus to the selected frame, redirect that focused
-   frame's focus to FRAME instead.
-
-   FOR_DELETION non-zero means that the selected frame is being
-   deleted, which includes the possibility that the frame's terminal
-   is dead.
-
-   The value of NORECORD is passed as argument to Fselect_window.  */
+/* Return the frame that has the input focus on the display of frame F,
+   or nil if that is not known.  Always nil for frames that are not on a
+   window system.  */
 
 Lisp_Object
-do_switch_frame (Lisp_Object frame, int track, int for_deletion, Lisp_Object norecord)
+frame_display_focus_frame (struct frame *f)
 {
-  struct frame *sf = SELECTED_FRAME (), *f;
-
-  /* If FRAME is a switch-frame event, extract the frame we should
-     switch to.  */
-  if (CONSP (frame)
-      && EQ (XCAR (frame), Qswitch_frame)
-      && CONSP (XCDR (frame)))
-    frame = XCAR (XCDR (frame));
-
-  /* This used to say CHECK_LIVE_FRAME, but apparently it's possible for
-     a switch-frame event to arrive after a frame is no longer live,
-     especially when deleting the initial frame during startup.  */
-  CHECK_FRAME (frame);
-  f = XFRAME (frame);
-  if (!FRAME_LIVE_P (f))
-    return Qnil;
-  else if (f == sf)
-    return frame;
-
-  /* If a frame's focus has been redirected toward the currently
-     selected frame, we should change the redirection to point to the
-     newly selected frame.  This means that if the focus is redirected
-     from a minibufferless frame to a surrogate minibuffer frame, we
-     can use `other-window' to switch between all the frames using
-     that minibuffer frame, and the focus redirection will follow us
-     around.  */
-#if 0
-  /* This is too greedy; it causes inappropriate focus redirection
-     that's hard to get rid of.  */
-  if (track)
-    {
-      Lisp_Object tail;
-
-      for (tail = Vframe_list; CONSP (tail); tail = XCDR (tail))
-	{
-	  Lisp_Object focus;
-
-	  if (!FRAMEP (XCAR (tail)))
-	    emacs_abort ();
-
-	  focus = FRAME_FOCUS_FRAME (XFRAME (XCAR (tail)));
-
-	  if (FRAMEP (focus) && XFRAME (focus) == SELECTED_FRAME ())
-	    Fredirect_frame_focus (XCAR (tail), frame);
-	}
-    }
-#else /* ! 0 */
-  /* Instead, apply it only to the frame we're pointing to.  */
 #ifdef HAVE_WINDOW_SYSTEM
-  if (track && FRAME_WINDOW_P (f))
-    {
-      Lisp_Object focus, xfocus;
+  if (FRAME_WINDOW_P (f))
+    return x_get_focus_frame (f);
+#endif
+  return Qnil;
+}
 
-      xfocus = x_get_focus_frame (f);
-      if (FRAMEP (xfocus))
-	{
-	  focus = FRAME_FOCUS_FRAME (XFRAME (xfocus));
-	  if ((FRAMEP (focus) && XFRAME (focus) == SELECTED_FRAME ())
-	      /* Redirect frame focus also when FRAME has its minibuffer
-		 window on the selected frame (see Bug#24500).  */
-	      || (NILP (focus)
-		  && EQ (FRAME_MINIBUF_WINDOW (f), sf->selected_window)))
-	    Fredirect_frame_focus (xfocus, frame);
-	}
-    }
-#endif /* HAVE_X_WINDOWS */
-#endif /* ! 0 */
+/* Make F the frame that is displayed on its text terminal.  Unless F is
+   already displayed there, mark the previously displayed frame as
+   obscured and resync term.c's idea of the frame size with F.  */
 
-  if (!for_deletion && FRAME_HAS_MINIBUF_P (sf))
-    resize_mini_window (XWINDOW (FRAME_MINIBUF_WINDOW (sf)), 1);
+void
+frame_make_tty_top_frame (struct frame *f)
+{
+  struct tty_display_info *tty = FRAME_TTY (f);
+  Lisp_Object frame, top_frame = tty->top_frame;
 
-  if (FRAME_TERMCAP_P (f))
-    {
-      struct tty_display_info *tty = FRAME_TTY (f);
-      Lisp_Object top_frame = tty->top_frame;
+  XSETFRAME (frame, f);
 
-      /* Don't mark the frame garbaged and/or obscured if we are
-	 switching to the frame that is already the top frame of that
-	 TTY.  */
-      if (!EQ (frame, top_frame))
-	{
-	  if (FRAMEP (top_frame))
-	    /* Mark previously displayed frame as now obscured.  */
-	    SET_FRAME_VISIBLE (XFRAME (top_frame), 2);
-	  SET_FRAME_VISIBLE (f, 1);
-	  /* If the new TTY frame changed dimensions, we need to
-	     resync term.c's idea of the frame size with the new
-	     frame's data.  */
-	  if (FRAME_COLS (f) != FrameCols (tty))
-	    FrameCols (tty) = FRAME_COLS (f);
-	  if (FRAME_TOTAL_LINES (f) != FrameRows (tty))
-	    FrameRows (tty) = FRAME_TOTAL_LINES (f);
-	}
-      tty->top_frame = frame;
+  /* Don't mark the frame garbaged and/or obscured if we are
+     switching to the frame that is already the top frame of that
+     TTY.  */
+  if (!EQ (frame, top_frame))
+    {
+      if (FRAMEP (top_frame))
+	/* Mark previously displayed frame as now obscured.  */
+	SET_FRAME_VISIBLE (XFRAME (top_frame), 2);
+      SET_FRAME_VISIBLE (f, 1);
+      /* If the new TTY frame changed dimensions, we need to
+	 resync term.c's idea of the frame size with the new
+	 frame's data.  */
+      if (FRAME_COLS (f) != FrameCols (tty))
+	FrameCols (tty) = FRAME_COLS (f);
+      if (FRAME_TOTAL_LINES (f) != FrameRows (tty))
+	FrameRows (tty) = FRAME_TOTAL_LINES (f);
     }
-
-  selected_frame = frame;
-  if (! FRAME_MINIBUF_ONLY_P (XFRAME (selected_frame)))
-    last_nonminibuf_frame = XFRAME (selected_frame);
-
-  Fselect_window (f->selected_window, norecord);
-
-  /* We want to make sure that the next event generates a frame-switch
-     event to the appropriate frame.  This seems kludgy to me, but
-     before you take it out, make sure that evaluating something like
-     (select-window (frame-root-window (make-frame))) doesn't end up
-     with your typing being interpreted in the new frame instead of
-     the one you're actually typing in.  */
-#ifdef HAVE_WINDOW_SYSTEM
-  if (!frame_ancestor_p (f, sf))
-#endif
-    internal_last_event_frame = Qnil;
-
-  return frame;
-}
-
-DEFUN ("select-frame", Fselect_frame, Sselect_frame, 1, 2, "e",
-       doc: /* Select FRAME.
-Subsequent editing commands apply to its selected window.
-Optional argument NORECORD means to neither change the order of
-recently selected windows nor the buffer list.
-
-The selection of FRAME lasts until the next time the user does
-something to select a different frame, or until the next time
-this function is called.  If you are using a window system, the
-previously selected frame may be restored as the selected frame
-when returning to the command loop, because it still may have
-the window system's input focus.  On a text terminal, the next
-redisplay will display FRAME.
-
-This function returns FRAME, or nil if FRAME has been deleted.  */)
-  (Lisp_Object frame, Lisp_Object norecord)
-{
-  return do_switch_frame (frame, 1, 0, norecord);
+  tty->top_frame = frame;
 }
 
-DEFUN ("handle-switch-frame", Fhandle_switch_frame, Shandle_switch_frame, 1, 1, "^e",
-       doc: /* Handle a switch-frame event EVENT.
-Switch-frame events are usually bound to this function.
-A switch-frame event tells Emacs that the window manager has requested
-that the user's events be directed to the frame mentioned in the event.
-This function selects the selected window of the frame of EVENT.
+/* Set the name of F to NAME, its newly formatted title.  Without a
+   window system there is no title to set.  */
 
-If EVENT is frame object, handle it as if it were a switch-frame event
-to that frame.  */)
-  (Lisp_Object event)
+void
+frame_implicitly_set_name (struct frame *f, Lisp_Object name)
 {
-  Lisp_Object value;
-
-  /* Preserve prefix arg that the command loop just cleared.  */
-  kset_prefix_arg (current_kboard, Vcurrent_prefix_arg);
-  run_hook (Qmouse_leave_buffer_hook);
-  /* `switch-frame' implies a focus in.  */
-  value = do_switch_frame (event, 0, 0, Qnil);
-  call1 (intern ("handle-focus-in"), event);
-  return value;
+#ifdef HAVE_WINDOW_SYSTEM
+  x_implicitly_set_name (f, name, Qnil);
+#endif
 }
 
 DEFUN ("frame-list", Fframe_list, Sframe_list,
@@ -1390,35 +1285,6 @@ The return value does not include any tooltip frame.  */)
 #endif /* HAVE_WINDOW_SYSTEM */
 }
 
-DEFUN ("frame-parent", Fframe_parent, Sframe_parent,
-       0, 1, 0,
-       doc: /* Return the parent frame of FRAME.
-The parent frame of FRAME is the Emacs frame whose window-system window
-is the parent window of FRAME's window-system window.  When such a frame
-exists, FRAME is considered a child frame of that frame.
-
-Return nil if FRAME has no parent frame.  This means that FRAME's
-window-system window is either a "top-level" window (a window whose
-parent window is the window-system's root window) or an embedded window
-\(a window whose parent window is owned by some other application).  */)
-     (Lisp_Object frame)
-{
-  struct frame *f = decode_live_frame (frame);
-  struct frame *p = FRAME_PARENT_FRAME (f);
-  Lisp_Object parent;
-
-  /* Can't return f->parent_frame directly since it might not be defined
-     for this platform.  */
-  if (p)
-    {
-      XSETFRAME (parent, p);
-
-      return parent;
-    }
-  else
-    return Qnil;
-}
-
 #ifdef HAVE_WINDOW_SYSTEM
 bool
 frame_ancestor_p (struct frame *af, struct frame *df)
@@ -1437,25 +1303,6 @@ frame_ancestor_p (struct frame *af, struct frame *df)
 }
 #endif
 
-DEFUN ("frame-ancestor-p", Fframe_ancestor_p, Sframe_ancestor_p,
-       2, 2, 0,
-       doc: /* Return non-nil if ANCESTOR is an ancestor of DESCENDANT.
-ANCESTOR is an ancestor of DESCENDANT when it is either DESCENDANT's
-parent frame or it is an ancestor of DESCENDANT's parent frame.  Both,
-ANCESTOR and DESCENDANT must be live frames and default to the selected
-frame.  */)
-     (Lisp_Object ancestor, Lisp_Object descendant)
-{
-#ifdef HAVE_WINDOW_SYSTEM
-  struct frame *af = decode_live_frame (ancestor);
-  struct frame *df = decode_live_frame (descendant);
-
-  return frame_ancestor_p (af, df) ? Qt : Qnil;
-#else
-  return Qnil;
-#endif
-  }
-
 /* Return CANDIDATE if it can be used as 'other-than-FRAME' frame on the
    same tty (for tty frames) or among frames which uses FRAME's keyboard.
    If MINIBUF is nil, do not consider minibuffer-only candidate.
@@ -1535,7 +1382,7 @@ DEFUN ("last-nonminibuffer-frame", Flast_nonminibuf_frame,
  * If F is the terminal frame and we are using X, return true if at
  * least one X frame exists.
  */
-static bool
+bool
 other_frames (struct frame *f, bool invisible, bool force)
 {
   Lisp_Object frames, frame, frame1;
@@ -1585,7 +1432,7 @@ other_frames (struct frame *f, bool invisible, bool force)
    instead.  If the selected frame doesn't have one, get some other
    frame's minibuffer window.  SELECT non-zero means select the new
    minibuffer window.  */
-static void
+void
 check_minibuf_window (Lisp_Object frame, int select)
 {
   struct frame *f = decode_live_frame (frame);
@@ -2061,90 +1908,6 @@ frame_internal_border_part (struct frame *f, int x, int y)
 }
 #endif
 
-/* Return mouse position in character cell units.  */
-
-DEFUN ("mouse-position", Fmouse_position, Smouse_position, 0, 0, 0,
-       doc: /* Return a list (FRAME X . Y) giving the current mouse frame and position.
-The position is given in canonical character cells, where (0, 0) is the
-upper-left corner of the frame, X is the horizontal offset, and Y is the
-vertical offset, measured in units of the frame's default character size.
-If Emacs is running on a mouseless terminal or hasn't been programmed
-to read the mouse position, it returns the selected frame for FRAME
-and nil for X and Y.
-If `mouse-position-function' is non-nil, `mouse-position' calls it,
-passing the normal return value to that function as an argument,
-and returns whatever that function returns.  */)
-  (void)
-{
-  struct frame *f;
-  Lisp_Object lispy_dummy;
-  Lisp_Object x, y, retval;
-
-  f = SELECTED_FRAME ();
-  x = y = Qnil;
-
-  /* It's okay for the hook to refrain from storing anything.  */
-  if (FRAME_TERMINAL (f)->mouse_position_hook)
-    {
-      enum scroll_bar_part party_dummy;
-      Time time_dummy;
-      (*FRAME_TERMINAL (f)->mouse_position_hook) (&f, -1,
-						  &lispy_dummy, &party_dummy,
-						  &x, &y,
-						  &time_dummy);
-    }
-
-  if (! NILP (x))
-    {
-      int col = XINT (x);
-      int row = XINT (y);
-      pixel_to_glyph_coords (f, col, row, &col, &row, NULL, 1);
-      XSETINT (x, col);
-      XSETINT (y, row);
-    }
-  XSETFRAME (lispy_dummy, f);
-  retval = Fcons (lispy_dummy, Fcons (x, y));
-  if (!NILP (Vmouse_position_function))
-    retval = call1 (Vmouse_position_function, retval);
-  return retval;
-}
-
-DEFUN ("mouse-pixel-position", Fmouse_pixel_position,
-       Smouse_pixel_position, 0, 0, 0,
-       doc: /* Return a list (FRAME X . Y) giving the current mouse frame and position.
-The position is given in pixel units, where (0, 0) is the
-upper-left corner of the frame, X is the horizontal offset, and Y is
-the vertical offset.
-If Emacs is running on a mouseless terminal or hasn't been programmed
-to read the mouse position, it returns the selected frame for FRAME
-and nil for X and Y.  */)
-  (void)
-{
-  struct frame *f;
-  Lisp_Object lispy_dummy;
-  Lisp_Object x, y, retval;
-
-  f = SELECTED_FRAME ();
-  x = y = Qnil;
-
-  /* It's okay for the hook to refrain from storing anything.  */
-  if (FRAME_TERMINAL (f)->mouse_position_hook)
-    {
-      enum scroll_bar_part party_dummy;
-      Time time_dummy;
-      (*FRAME_TERMINAL (f)->mouse_position_hook) (&f, -1,
-						  &lispy_dummy, &party_dummy,
-						  &x, &y,
-						  &time_dummy);
-    }
-
-  XSETFRAME (lispy_dummy, f);
-  retval = Fcons (lispy_dummy, Fcons (x, y));
-  if (!NILP (Vmouse_position_function))
-    retval = call1 (Vmouse_position_function, retval);
-  return retval;
-}
-
 #ifdef HAVE_WINDOW_SYSTEM
 
 /* On frame F, convert character coordinates X and Y to pixel
@@ -2181,200 +1944,69 @@ frame_set_mouse_position (struct frame *f, int x, int y)
 
 #endif /* HAVE_WINDOW_SYSTEM */
 
-DEFUN ("set-mouse-position", Fset_mouse_position, Sset_mouse_position, 3, 3, 0,
-       doc: /* Move the mouse pointer to the center of character cell (X,Y) in FRAME.
-Coordinates are relative to the frame, not a window,
-so the coordinates of the top left character in the frame
-may be nonzero due to left-hand scroll bars or the menu bar.
-
-The position is given in canonical character cells, where (0, 0) is
-the upper-left corner of the frame, X is the horizontal offset, and
-Y is the vertical offset, measured in units of the frame's default
-character size.
-
-This function is a no-op for an X frame that is not visible.
-If you have just created a frame, you must wait for it to become visible
-before calling this function on it, like this.
-  (while (not (frame-visible-p frame)) (sleep-for .5))  */)
-  (Lisp_Object frame, Lisp_Object x, Lisp_Object y)
-{
-  CHECK_LIVE_FRAME (frame);
-  CHECK_TYPE_RANGED_INTEGER (int, x);
-  CHECK_TYPE_RANGED_INTEGER (int, y);
+/* Move the mouse pointer to (X, Y) on frame F, in pixels if PIXELWISE
+   and in canonical character cells otherwise.  */
 
+void
+frame_warp_mouse (struct frame *f, int x, int y, bool pixelwise)
+{
   /* I think this should be done with a hook.  */
 #ifdef HAVE_WINDOW_SYSTEM
-  if (FRAME_WINDOW_P (XFRAME (frame)))
-    /* Warping the mouse will cause enternotify and focus events.  */
-    frame_set_mouse_position (XFRAME (frame), XINT (x), XINT (y));
-#else
-#ifdef HAVE_GPM
+  if (FRAME_WINDOW_P (f))
     {
-      Fselect_frame (frame, Qnil);
-      term_mouse_moveto (XINT (x), XINT (y));
+      /* Warping the mouse will cause enternotify and focus events.  */
+      if (pixelwise)
+	frame_set_mouse_pixel_position (f, x, y);
+      else
+	frame_set_mouse_position (f, x, y);
     }
-#endif
-#endif
-
-  return Qnil;
-}
-
-DEFUN ("set-mouse-pixel-position", Fset_mouse_pixel_position,
-       Sset_mouse_pixel_position, 3, 3, 0,
-       doc: /* Move the mouse pointer to pixel position (X,Y) in FRAME.
-The position is given in pixels, where (0, 0) is the upper-left corner
-of the frame, X is the horizontal offset, and Y is the vertical offset.
-
-Note, this is a no-op for an X frame that is not visible.
-If you have just created a frame, you must wait for it to become visible
-before calling this function on it, like this.
-  (while (not (frame-visible-p frame)) (sleep-for .5))  */)
-  (Lisp_Object frame, Lisp_Object x, Lisp_Object y)
-{
-  CHECK_LIVE_FRAME (frame);
-  CHECK_TYPE_RANGED_INTEGER (int, x);
-  CHECK_TYPE_RANGED_INTEGER (int, y);
-
-  /* I think this should be done with a hook.  */
-#ifdef HAVE_WINDOW_SYSTEM
-  if (FRAME_WINDOW_P (XFRAME (frame)))
-    /* Warping the mouse will cause enternotify and focus events.  */
-    frame_set_mouse_pixel_position (XFRAME (frame), XINT (x), XINT (y));
 #else
 #ifdef HAVE_GPM
     {
+      Lisp_Object frame;
+
+      XSETFRAME (frame, f);
       Fselect_frame (frame, Qnil);
-      term_mouse_moveto (XINT (x), XINT (y));
+      term_mouse_moveto (x, y);
     }
 #endif
 #endif
-
-  return Qnil;
 }
 
-static void make_frame_visible_1 (Lisp_Object);
+/* Ask the window system to show frame F.  Does nothing for frames
+   that are not on a window system.  */
 
-DEFUN ("make-frame-visible", Fmake_frame_visible, Smake_frame_visible,
-       0, 1, "",
-       doc: /* Make the frame FRAME visible (assuming it is an X window).
-If omitted, FRAME defaults to the currently selected frame.  */)
-  (Lisp_Object frame)
+void
+frame_make_visible (struct frame *f)
 {
-  struct frame *f = decode_live_frame (frame);
-
-  /* I think this should be done with a hook.  */
 #ifdef HAVE_WINDOW_SYSTEM
   if (FRAME_WINDOW_P (f))
     x_make_frame_visible (f);
 #endif
-
-  make_frame_visible_1 (f->root_window);
-
-  /* Make menu bar update for the Buffers and Frames menus.  */
-  /* windows_or_buffers_changed = 15; FIXME: Why?  */
-
-  XSETFRAME (frame, f);
-  return frame;
-}
-
-/* Update the display_time slot of the buffers shown in WINDOW
-   and all its descendants.  */
-
-static void
-make_frame_visible_1 (Lisp_Object window)
-{
-  struct window *w;
-
-  for (; !NILP (window); window = w->next)
-    {
-      w = XWINDOW (window);
-      if (WINDOWP (w->contents))
-	make_frame_visible_1 (w->contents);
-      else
-	bset_display_time (XBUFFER (w->contents), Fcurrent_time ());
-    }
 }
 
-DEFUN ("make-frame-invisible", Fmake_frame_invisible, Smake_frame_invisible,
-       0, 2, "",
-       doc: /* Make the frame FRAME invisible.
-If omitted, FRAME defaults to the currently selected frame.
-On graphical displays, invisible frames are not updated and are
-usually not displayed at all, even in a window system's \"taskbar\".
-
-Normally you may not make FRAME invisible if all other frames are invisible,
-but if the second optional argument FORCE is non-nil, you may do so.
+/* Ask the window system to hide frame F.  Does nothing for frames
+   that are not on a window system.  */
 
-This function has no effect on text terminal frames.  Such frames are
-always considered visible, whether or not they are currently being
-displayed in the terminal.  */)
-  (Lisp_Object frame, Lisp_Object force)
+void
+frame_make_invisible (struct frame *f)
 {
-  struct frame *f = decode_live_frame (frame);
-
-  if (NILP (force) && !other_frames (f, true, false))
-    error ("Attempt to make invisible the sole visible or iconified frame");
-
-  /* Don't allow minibuf_window to remain on an invisible frame.  */
-  check_minibuf_window (frame, EQ (minibuf_window, selected_window));
-
-  /* I think this should be done with a hook.  */
 #ifdef HAVE_WINDOW_SYSTEM
   if (FRAME_WINDOW_P (f))
     x_make_frame_invisible (f);
 #endif
-
-  /* Make menu bar update for the Buffers and Frames menus.  */
-  windows_or_buffers_changed = 16;
-
-  return Qnil;
 }
 
-DEFUN ("iconify-frame", Ficonify_frame, Siconify_frame,
-       0, 1, "",
-       doc: /* Make the frame FRAME into an icon.
-If omitted, FRAME defaults to the currently selected frame.
+/* Ask the window system to iconify frame F.  Does nothing for frames
+   that are not on a window system.  */
 
-If FRAME is a child frame, consult the variable `iconify-child-frame'
-for how to proceed.  */)
-  (Lisp_Object frame)
+void
+frame_iconify (struct frame *f)
 {
-  struct frame *f = decode_live_frame (frame);
 #ifdef HAVE_WINDOW_SYSTEM
-  Lisp_Object parent = f->parent_frame;
-
-  if (!NILP (parent))
-    {
-      if (NILP (iconify_child_frame))
-	/* Do nothing.  */
-	return Qnil;
-      else if (EQ (iconify_child_frame, Qiconify_top_level))
-	{
-	  /* Iconify top level frame instead (the default).  */
-	  Ficonify_frame (parent);
-	  return Qnil;
-	}
-      else if (EQ (iconify_child_frame, Qmake_invisible))
-	{
-	  /* Make frame invisible instead.  */
-	  Fmake_frame_invisible (frame, Qnil);
-	  return Qnil;
-	}
-    }
-#endif	/* HAVE_WINDOW_SYSTEM */
-
-  /* Don't allow minibuf_window to remain on an iconified frame.  */
-  check_minibuf_window (frame, EQ (minibuf_window, selected_window));
-
-  /* I think this should be done with a hook.  */
   if (FRAME_WINDOW_P (f))
-    {
-#ifdef HAVE_WINDOW_SYSTEM
-      x_iconify_frame (f);
+    x_iconify_frame (f);
 #endif
-    }
-
-  return Qnil;
 }
 
 DEFUN ("visible-frame-list", Fvisible_frame_list, Svisible_frame_list,
@@ -2434,60 +2066,6 @@ doesn't support multiple overlapping frames, this function does nothing.  */)
 }
 
 
-DEFUN ("redirect-frame-focus", Fredirect_frame_focus, Sredirect_frame_focus,
-       1, 2, 0,
-       doc: /* Arrange for keystrokes typed at FRAME to be sent to FOCUS-FRAME.
-In other words, switch-frame events caused by events in FRAME will
-request a switch to FOCUS-FRAME, and `last-event-frame' will be
-FOCUS-FRAME after reading an event typed at FRAME.
-
-If FOCUS-FRAME is nil, any existing redirection is canceled, and the
-frame again receives its own keystrokes.
-
-Focus redirection is useful for temporarily redirecting keystrokes to
-a surrogate minibuffer frame when a frame doesn't have its own
-minibuffer window.
-
-A frame's focus redirection can be changed by `select-frame'.  If frame
-FOO is selected, and then a different frame BAR is selected, any
-frames redirecting their focus to FOO are shifted to redirect their
-focus to BAR.  This allows focus redirection to work properly when the
-user switches from one frame to another using `select-window'.
-
-This means that a frame whose focus is redirected to itself is treated
-differently from a frame whose focus is redirected to nil; the former
-is affected by `select-frame', while the latter is not.
-
-The redirection lasts until `redirect-frame-focus' is called to change it.  */)
-  (Lisp_Object frame, Lisp_Object focus_frame)
-{
-  /* Note that we don't check for a live frame here.  It's reasonable
-     to redirect the focus of a frame you're about to delete, if you
-     know what other frame should receive those keystrokes.  */
-  struct frame *f = decode_any_frame (frame);
-
-  if (! NILP (focus_frame))
-    CHECK_LIVE_FRAME (focus_frame);
-
-  fset_focus_frame (f, focus_frame);
-
-  if (FRAME_TERMINAL (f)->frame_rehighlight_hook)
-    (*FRAME_TERMINAL (f)->frame_rehighlight_hook) (f);
-
-  return Qnil;
-}
-
-
-DEFUN ("frame-focus", Fframe_focus, Sframe_focus, 0, 1, 0,
-       doc: /* Return the frame to which FRAME's keystrokes are currently being sent.
-If FRAME is omitted or nil, the selected frame is used.
-Return nil if FRAME's focus is not redirected.
-See `redirect-frame-focus'.  */)
-  (Lisp_Object frame)
-{
-  return FRAME_FOCUS_FRAME (decode_live_frame (frame));
-}
-
 DEFUN ("x-focus-frame", Fx_focus_frame, Sx_focus_frame, 1, 2, 0,
        doc: /* Set the input focus to FRAME.
 FRAME nil means use the selected frame.  Optional argument NOACTIVATE
@@ -3049,190 +2627,24 @@ DEFUN ("frame-scroll-bar-height", Fscroll_bar_height, Sscroll_bar_height, 0, 1,
 {
   return make_number (FRAME_SCROLL_BAR_AREA_HEIGHT (decode_any_frame (frame)));
 }
-DEFUN ("set-frame-height", Fset_frame_height, Sset_frame_height, 2, 4, 0,
-       doc: /* Set text height of frame FRAME to HEIGHT lines.
-Optional third arg PRETEND non-nil means that redisplay should use
-HEIGHT lines but that the idea of the actual height of the frame should
-not be changed.
-
-Optional fourth argument PIXELWISE non-nil means that FRAME should be
-HEIGHT pixels high.  Note: When `frame-resize-pixelwise' is nil, some
-window managers may refuse to honor a HEIGHT that is not an integer
-multiple of the default frame font height.  */)
-  (Lisp_Object frame, Lisp_Object height, Lisp_Object pretend, Lisp_Object pixelwise)
-{
-  struct frame *f = decode_live_frame (frame);
-  int pixel_height;
-
-  CHECK_TYPE_RANGED_INTEGER (int, height);
-
-  pixel_height = (!NILP (pixelwise)
-		  ? XINT (height)
-		  : XINT (height) * FRAME_LINE_HEIGHT (f));
-  adjust_frame_size (f, -1, pixel_height, 1, !NILP (pretend), Qheight);
-
-  return Qnil;
-}
-
-DEFUN ("set-frame-width", Fset_frame_width, Sset_frame_width, 2, 4, 0,
-       doc: /* Set text width of frame FRAME to WIDTH columns.
-Optional third arg PRETEND non-nil means that redisplay should use WIDTH
-columns but that the idea of the actual width of the frame should not
-be changed.
-
-Optional fourth argument PIXELWISE non-nil means that FRAME should be
-WIDTH pixels wide.  Note: When `frame-resize-pixelwise' is nil, some
-window managers may refuse to honor a WIDTH that is not an integer
-multiple of the default frame font width.  */)
-  (Lisp_Object frame, Lisp_Object width, Lisp_Object pretend, Lisp_Object pixelwise)
-{
-  struct frame *f = decode_live_frame (frame);
-  int pixel_width;
-
-  CHECK_TYPE_RANGED_INTEGER (int, width);
-
-  pixel_width = (!NILP (pixelwise)
-		 ? XINT (width)
-		 : XINT (width) * FRAME_COLUMN_WIDTH (f));
-  adjust_frame_size (f, pixel_width, -1, 1, !NILP (pretend), Qwidth);
-
-  return Qnil;
-}
-
-DEFUN ("set-frame-size", Fset_frame_size, Sset_frame_size, 3, 4, 0,
-       doc: /* Set text size of FRAME to WIDTH by HEIGHT, measured in characters.
-Optional argument PIXELWISE non-nil means to measure in pixels.  Note:
-When `frame-resize-pixelwise' is nil, some window managers may refuse to
-honor a WIDTH that is not an integer multiple of the default frame font
-width or a HEIGHT that is not an integer multiple of the default frame
-font height.  */)
-  (Lisp_Object frame, Lisp_Object width, Lisp_Object height, Lisp_Object pixelwise)
-{
-  struct frame *f = decode_live_frame (frame);
-  int pixel_width, pixel_height;
-
-  CHECK_TYPE_RANGED_INTEGER (int, width);
-  CHECK_TYPE_RANGED_INTEGER (int, height);
-
-  pixel_width = (!NILP (pixelwise)
-		 ? XINT (width)
-		 : XINT (width) * FRAME_COLUMN_WIDTH (f));
-  pixel_height = (!NILP (pixelwise)
-		  ? XINT (height)
-		  : XINT (height) * FRAME_LINE_HEIGHT (f));
-  adjust_frame_size (f, pixel_width, pixel_height, 1, 0, Qsize);
-
-  return Qnil;
-}
-
-DEFUN ("set-frame-position", Fset_frame_position,
-       Sset_frame_position, 3, 3, 0,
-       doc: /* Set position of FRAME to (X, Y).
-FRAME must be a live frame and defaults to the selected one.  X and Y,
-if positive, specify the coordinate of the left and top edge of FRAME's
-outer frame in pixels relative to an origin (0, 0) of FRAME's display.
-If any of X or Y is negative, it specifies the coordinates of the right
-or bottom edge of the outer frame of FRAME relative to the right or
-bottom edge of FRAME's display.  */)
-  (Lisp_Object frame, Lisp_Object x, Lisp_Object y)
+/* Move the outer frame of F to X, Y on its display.  This is a no-op
+   for frames that are not displayed through a window system.  */
+void
+frame_set_offset (struct frame *f, int x, int y)
 {
-  struct frame *f = decode_live_frame (frame);
-
-  CHECK_TYPE_RANGED_INTEGER (int, x);
-  CHECK_TYPE_RANGED_INTEGER (int, y);
-
   /* I think this should be done with a hook.  */
-  if (FRAME_WINDOW_P (f))
-    {
 #ifdef HAVE_WINDOW_SYSTEM
-      x_set_offset (f, XINT (x), XINT (y), 1);
+  if (FRAME_WINDOW_P (f))
+    x_set_offset (f, x, y, 1);
 #endif
-    }
-
-  return Qt;
 }
-
+
 /***********************************************************************
 				Frame Parameters
  ***********************************************************************/
 
-/* Connect the frame-parameter names for X frames
-   to the ways of passing the parameter values to the window system.
-
-   The name of a parameter, as a Lisp symbol,
-   has an `x-frame-parameter' property which is an integer in Lisp
-   that is an index in this table.  */
-
-struct frame_parm_table {
-  const char *name;
-  int sym;
-};
-
-static const struct frame_parm_table frame_parms[] =
-{
-  {"auto-raise",		SYMBOL_INDEX (Qauto_raise)},
-  {"auto-lower",		SYMBOL_INDEX (Qauto_lower)},
-  {"background-color",		-1},
-  {"border-color",		SYMBOL_INDEX (Qborder_color)},
-  {"border-width",		SYMBOL_INDEX (Qborder_width)},
-  {"cursor-color",		SYMBOL_INDEX (Qcursor_color)},
-  {"cursor-type",		SYMBOL_INDEX (Qcursor_type)},
-  {"font",			-1},
-  {"foreground-color",		-1},
-  {"icon-name",			SYMBOL_INDEX (Qicon_name)},
-  {"icon-type",			SYMBOL_INDEX (Qicon_type)},
-  {"internal-border-width",	SYMBOL_INDEX (Qinternal_border_width)},
-  {"right-divider-width",	SYMBOL_INDEX (Qright_divider_width)},
-  {"bottom-divider-width",	SYMBOL_INDEX (Qbottom_divider_width)},
-  {"menu-bar-lines",		SYMBOL_INDEX (Qmenu_bar_lines)},
-  {"mouse-color",		SYMBOL_INDEX (Qmouse_color)},
-  {"name",			SYMBOL_INDEX (Qname)},
-  {"scroll-bar-width",		SYMBOL_INDEX (Qscroll_bar_width)},
-  {"scroll-bar-height",		SYMBOL_INDEX (Qscroll_bar_height)},
-  {"title",			SYMBOL_INDEX (Qtitle)},
-  {"unsplittable",		SYMBOL_INDEX (Qunsplittable)},
-  {"vertical-scroll-bars",	SYMBOL_INDEX (Qvertical_scroll_bars)},
-  {"horizontal-scroll-bars",	SYMBOL_INDEX (Qhorizontal_scroll_bars)},
-  {"visibility",		SYMBOL_INDEX (Qvisibility)},
-  {"tool-bar-lines",		SYMBOL_INDEX (Qtool_bar_lines)},
-  {"scroll-bar-foreground",	SYMBOL_INDEX (Qscroll_bar_foreground)},
-  {"scroll-bar-background",	SYMBOL_INDEX (Qscroll_bar_background)},
-  {"screen-gamma",		SYMBOL_INDEX (Qscreen_gamma)},
-  {"line-spacing",		SYMBOL_INDEX (Qline_spacing)},
-  {"left-fringe",		SYMBOL_INDEX (Qleft_fringe)},
-  {"right-fringe",		SYMBOL_INDEX (Qright_fringe)},
-  {"wait-for-wm",		SYMBOL_INDEX (Qwait_for_wm)},
-  {"fullscreen",                SYMBOL_INDEX (Qfullscreen)},
-  {"font-backend",		SYMBOL_INDEX (Qfont_backend)},
-  {"alpha",			SYMBOL_INDEX (Qalpha)},
-  {"sticky",			SYMBOL_INDEX (Qsticky)},
-  {"tool-bar-position",		SYMBOL_INDEX (Qtool_bar_position)},
-  {"inhibit-double-buffering",  SYMBOL_INDEX (Qinhibit_double_buffering)},
-  {"undecorated",		SYMBOL_INDEX (Qundecorated)},
-  {"parent-frame",		SYMBOL_INDEX (Qparent_frame)},
-  {"skip-taskbar",		SYMBOL_INDEX (Qskip_taskbar)},
-  {"no-focus-on-map",		SYMBOL_INDEX (Qno_focus_on_map)},
-  {"no-accept-focus",		SYMBOL_INDEX (Qno_accept_focus)},
-  {"z-group",			SYMBOL_INDEX (Qz_group)},
-  {"override-redirect",		SYMBOL_INDEX (Qoverride_redirect)},
-  {"no-special-glyphs",		SYMBOL_INDEX (Qno_special_glyphs)},
-#ifdef NS_IMPL_COCOA
-  {"ns-appearance",		SYMBOL_INDEX (Qns_appearance)},
-  {"ns-transparent-titlebar",	SYMBOL_INDEX (Qns_transparent_titlebar)},
-#endif
-};
-
 #ifdef HAVE_WINDOW_SYSTEM
 
-/* Enumeration type for switch in frame_float.  */
-enum frame_float_type
-{
- FRAME_FLOAT_WIDTH,
- FRAME_FLOAT_HEIGHT,
- FRAME_FLOAT_LEFT,
- FRAME_FLOAT_TOP
-};
-
 /**
  * frame_float:
  *
@@ -3406,292 +2818,86 @@ frame_float (struct frame *f, Lisp_Object val, enum frame_float_type what,
     }
 }
 
-/* Change the parameters of frame F as specified by ALIST.
-   If a parameter is not specially recognized, do nothing special;
-   otherwise call the `x_set_...' function for that parameter.
-   Except for certain geometry properties, always call store_frame_param
-   to store the new value in the parameter alist.  */
-
-void
-x_set_frame_parameters (struct frame *f, Lisp_Object alist)
-{
-  Lisp_Object tail, frame;
-
-
-  /* If both of these parameters are present, it's more efficient to
-     set them both at once.  So we wait until we've looked at the
-     entire list before we set them.  */
-  int width = -1, height = -1;  /* -1 denotes they were not changed. */
-
-  /* Same here.  */
-  Lisp_Object left, top;
-
-  /* Same with these.  */
-  Lisp_Object icon_left, icon_top;
+#endif /* HAVE_WINDOW_SYSTEM */
 
-  /* And with this.  */
-  Lisp_Object fullscreen;
-  bool fullscreen_change = false;
+/* The window system side of x_set_frame_parameters, which is in Rust
+   frame_parameters.rs.  These do nothing for frames that are not
+   displayed through a window system.  */
 
-  /* Record in these vectors all the parms specified.  */
-  Lisp_Object *parms;
-  Lisp_Object *values;
-  ptrdiff_t i, j, size;
-  bool left_no_change = 0, top_no_change = 0;
-#ifdef HAVE_X_WINDOWS
-  bool icon_left_no_change = 0, icon_top_no_change = 0;
+/* Return the size or position in pixels that the float value VAL of
+   the frame parameter WHAT means for frame F; see frame_float.  */
+int
+frame_float_value (struct frame *f, Lisp_Object val,
+		   enum frame_float_type what, int *parent_done,
+		   int *outer_done, int default_value)
+{
+#ifdef HAVE_WINDOW_SYSTEM
+  return frame_float (f, val, what, parent_done, outer_done, default_value);
+#else
+  return default_value;
 #endif
-  int parent_done = -1, outer_done = -1;
-
-  XSETFRAME (frame, f);
-  for (size = 0, tail = alist; CONSP (tail); tail = XCDR (tail))
-    size++;
-  CHECK_LIST_END (tail, alist);
-
-  USE_SAFE_ALLOCA;
-  SAFE_ALLOCA_LISP (parms, 2 * size);
-  values = parms + size;
-
-  /* Extract parm names and values into those vectors.  */
-
-  i = 0, j = size - 1;
-  for (tail = alist; CONSP (tail); tail = XCDR (tail))
-    {
-      Lisp_Object elt = XCAR (tail), prop = Fcar (elt), val = Fcdr (elt);
-
-      /* Some properties are independent of other properties, but other
-	 properties are dependent upon them.  These special properties
-	 are foreground_color, background_color (affects cursor_color)
-	 and font (affects fringe widths); they're recorded starting
-	 from the end of PARMS and VALUES to process them first by using
-	 reverse iteration.  */
-
-      if (EQ (prop, Qforeground_color)
-	  || EQ (prop, Qbackground_color)
-	  || EQ (prop, Qfont))
-	{
-	  parms[j] = prop;
-	  values[j] = val;
-	  j--;
-	}
-      else
-	{
-	  parms[i] = prop;
-	  values[i] = val;
-	  i++;
-	}
-    }
-
-  /* TAIL and ALIST are not used again below here.  */
-  alist = tail = Qnil;
-
-  top = left = Qunbound;
-  icon_left = icon_top = Qunbound;
-
-  /* Reverse order is used to make sure that special
-     properties noticed above are processed first.  */
-  for (i = size - 1; i >= 0; i--)
-    {
-      Lisp_Object prop, val;
-
-      prop = parms[i];
-      val = values[i];
-
-      if (EQ (prop, Qwidth))
-        {
-	  if (RANGED_INTEGERP (0, val, INT_MAX))
-	    width = XFASTINT (val) * FRAME_COLUMN_WIDTH (f) ;
-	  else if (CONSP (val) && EQ (XCAR (val), Qtext_pixels)
-		   && RANGED_INTEGERP (0, XCDR (val), INT_MAX))
-	    width = XFASTINT (XCDR (val));
-	  else if (FLOATP (val))
-	    width = frame_float (f, val, FRAME_FLOAT_WIDTH, &parent_done,
-				 &outer_done, -1);
-        }
-      else if (EQ (prop, Qheight))
-        {
-	  if (RANGED_INTEGERP (0, val, INT_MAX))
-	    height = XFASTINT (val) * FRAME_LINE_HEIGHT (f);
-	  else if (CONSP (val) && EQ (XCAR (val), Qtext_pixels)
-		   && RANGED_INTEGERP (0, XCDR (val), INT_MAX))
-	    height = XFASTINT (XCDR (val));
-	  else if (FLOATP (val))
-	    height = frame_float (f, val, FRAME_FLOAT_HEIGHT, &parent_done,
-				 &outer_done, -1);
-        }
-      else if (EQ (prop, Qtop))
-	top = val;
-      else if (EQ (prop, Qleft))
-	left = val;
-      else if (EQ (prop, Qicon_top))
-	icon_top = val;
-      else if (EQ (prop, Qicon_left))
-	icon_left = val;
-      else if (EQ (prop, Qfullscreen))
-	{
-	  fullscreen = val;
-	  fullscreen_change = true;
-	}
-      else
-	{
-	  register Lisp_Object param_index, old_value;
+}
 
-	  old_value = get_frame_param (f, prop);
+/* Move frame F to LEFT, TOP, which are relative to the right or bottom
+   edge of its display if X_NEGATIVE or Y_NEGATIVE.  */
+void
+frame_set_position (struct frame *f, int left, int top,
+		    bool x_negative, bool y_negative)
+{
+#ifdef HAVE_WINDOW_SYSTEM
+  /* Record the signs.  */
+  f->size_hint_flags &= ~ (XNegative | YNegative);
+  if (x_negative)
+    f->size_hint_flags |= XNegative;
+  if (y_negative)
+    f->size_hint_flags |= YNegative;
 
-	  store_frame_param (f, prop, val);
+  /* Store the numeric value of the position.  */
+  f->top_pos = top;
+  f->left_pos = left;
 
-	  param_index = Fget (prop, Qx_frame_parameter);
-	  if (NATNUMP (param_index)
-	      && XFASTINT (param_index) < ARRAYELTS (frame_parms)
-	      && FRAME_RIF (f)->frame_parm_handlers[XINT (param_index)])
-	    (*(FRAME_RIF (f)->frame_parm_handlers[XINT (param_index)])) (f, val, old_value);
-	}
-    }
+  f->win_gravity = NorthWestGravity;
 
-  /* Don't die if just one of these was set.  */
-  if (EQ (left, Qunbound))
-    {
-      left_no_change = 1;
-      if (f->left_pos < 0)
-	left = list2 (Qplus, make_number (f->left_pos));
-      else
-	XSETINT (left, f->left_pos);
-    }
-  if (EQ (top, Qunbound))
-    {
-      top_no_change = 1;
-      if (f->top_pos < 0)
-	top = list2 (Qplus, make_number (f->top_pos));
-      else
-	XSETINT (top, f->top_pos);
-    }
+  /* Actually set that position, and convert to absolute.  */
+  x_set_offset (f, left, top, -1);
+#endif
+}
 
-  /* If one of the icon positions was not set, preserve or default it.  */
-  if (! TYPE_RANGED_INTEGERP (int, icon_left))
-    {
-#ifdef HAVE_X_WINDOWS
-      icon_left_no_change = 1;
+/* Change the `fullscreen' state of frame F from OLD_VALUE to NEW_VALUE.  */
+void
+frame_set_fullscreen (struct frame *f, Lisp_Object new_value,
+		      Lisp_Object old_value)
+{
+#ifdef HAVE_WINDOW_SYSTEM
+  x_set_fullscreen (f, new_value, old_value);
 #endif
-      icon_left = Fcdr (Fassq (Qicon_left, f->param_alist));
-      if (NILP (icon_left))
-	XSETINT (icon_left, 0);
-    }
-  if (! TYPE_RANGED_INTEGERP (int, icon_top))
-    {
+}
+
+/* Ask the window manager to put the icon of frame F at LEFT, TOP.  */
+void
+frame_set_icon_position (struct frame *f, int left, int top)
+{
 #ifdef HAVE_X_WINDOWS
-      icon_top_no_change = 1;
+  x_wm_set_icon_position (f, left, top);
 #endif
-      icon_top = Fcdr (Fassq (Qicon_top, f->param_alist));
-      if (NILP (icon_top))
-	XSETINT (icon_top, 0);
-    }
-
-  /* Don't set these parameters unless they've been explicitly
-     specified.  The window might be mapped or resized while we're in
-     this function, and we don't want to override that unless the lisp
-     code has asked for it.
-
-     Don't set these parameters unless they actually differ from the
-     window's current parameters; the window may not actually exist
-     yet.  */
-  if ((width != -1 && width != FRAME_TEXT_WIDTH (f))
-      || (height != -1 && height != FRAME_TEXT_HEIGHT (f)))
-    /* We could consider checking f->after_make_frame here, but I
-       don't have the faintest idea why the following is needed at
-       all.  With the old setting it can get a Heisenbug when
-       EmacsFrameResize intermittently provokes a delayed
-       change_frame_size in the middle of adjust_frame_size.  */
-    /** 	|| (f->can_x_set_window_size && (f->new_height || f->new_width))) **/
-    adjust_frame_size (f, width, height, 1, 0, Qx_set_frame_parameters);
-
-  if ((!NILP (left) || !NILP (top))
-      && ! (left_no_change && top_no_change)
-      && ! (NUMBERP (left) && XINT (left) == f->left_pos
-	    && NUMBERP (top) && XINT (top) == f->top_pos))
-    {
-      int leftpos = 0;
-      int toppos = 0;
-
-      /* Record the signs.  */
-      f->size_hint_flags &= ~ (XNegative | YNegative);
-      if (EQ (left, Qminus))
-	f->size_hint_flags |= XNegative;
-      else if (TYPE_RANGED_INTEGERP (int, left))
-	{
-	  leftpos = XINT (left);
-	  if (leftpos < 0)
-	    f->size_hint_flags |= XNegative;
-	}
-      else if (CONSP (left) && EQ (XCAR (left), Qminus)
-	       && CONSP (XCDR (left))
-	       && RANGED_INTEGERP (-INT_MAX, XCAR (XCDR (left)), INT_MAX))
-	{
-	  leftpos = - XINT (XCAR (XCDR (left)));
-	  f->size_hint_flags |= XNegative;
-	}
-      else if (CONSP (left) && EQ (XCAR (left), Qplus)
-	       && CONSP (XCDR (left))
-	       && TYPE_RANGED_INTEGERP (int, XCAR (XCDR (left))))
-	leftpos = XINT (XCAR (XCDR (left)));
-      else if (FLOATP (left))
-	leftpos = frame_float (f, left, FRAME_FLOAT_LEFT, &parent_done,
-			       &outer_done, 0);
-
-      if (EQ (top, Qminus))
-	f->size_hint_flags |= YNegative;
-      else if (TYPE_RANGED_INTEGERP (int, top))
-	{
-	  toppos = XINT (top);
-	  if (toppos < 0)
-	    f->size_hint_flags |= YNegative;
-	}
-      else if (CONSP (top) && EQ (XCAR (top), Qminus)
-	       && CONSP (XCDR (top))
-	       && RANGED_INTEGERP (-INT_MAX, XCAR (XCDR (top)), INT_MAX))
-	{
-	  toppos = - XINT (XCAR (XCDR (top)));
-	  f->size_hint_flags |= YNegative;
-	}
-      else if (CONSP (top) && EQ (XCAR (top), Qplus)
-	       && CONSP (XCDR (top))
-	       && TYPE_RANGED_INTEGERP (int, XCAR (XCDR (top))))
-	toppos = XINT (XCAR (XCDR (top)));
-      else if (FLOATP (top))
-	toppos = frame_float (f, top, FRAME_FLOAT_TOP, &parent_done,
-			      &outer_done, 0);
-
-      /* Store the numeric value of the position.  */
-      f->top_pos = toppos;
-      f->left_pos = leftpos;
-
-      f->win_gravity = NorthWestGravity;
-
-      /* Actually set that position, and convert to absolute.  */
-      x_set_offset (f, leftpos, toppos, -1);
-    }
-
-  if (fullscreen_change)
-    {
-      Lisp_Object old_value = get_frame_param (f, Qfullscreen);
-
-      frame_size_history_add
-	(f, Qx_set_fullscreen, 0, 0, list2 (old_value, fullscreen));
-
-      store_frame_param (f, Qfullscreen, fullscreen);
-      if (!EQ (fullscreen, old_value))
-	x_set_fullscreen (f, fullscreen, old_value);
-    }
-
+}
 
-#ifdef HAVE_X_WINDOWS
-  if ((!NILP (icon_left) || !NILP (icon_top))
-      && ! (icon_left_no_change && icon_top_no_change))
-    x_wm_set_icon_position (f, XINT (icon_left), XINT (icon_top));
-#endif /* HAVE_X_WINDOWS */
+/* Call the function at INDEX in the frame_parm_handlers of the window
+   system of frame F, if there is one, with NEW_VALUE and OLD_VALUE.  */
+void
+frame_call_parm_handler (struct frame *f, ptrdiff_t index,
+			 Lisp_Object new_value, Lisp_Object old_value)
+{
+#ifdef HAVE_WINDOW_SYSTEM
+  frame_parm_handler handler = FRAME_RIF (f)->frame_parm_handlers[index];
 
-  SAFE_FREE ();
+  if (handler)
+    handler (f, new_value, old_value);
+#endif
 }
 
+#ifdef HAVE_WINDOW_SYSTEM
+
 
 /* Insert a description of internally-recorded parameters of frame X
    into the parameter alist *ALISTPTR that is to be given to the user.
@@ -3845,14 +3051,7 @@ x_set_screen_gamma (struct frame *f, Lisp_Object new_value, Lisp_Object old_valu
   /* Apply the new gamma value to the frame background.  */
   bgcolor = Fassq (Qbackground_color, f->param_alist);
   if (CONSP (bgcolor) && (bgcolor = XCDR (bgcolor), STRINGP (bgcolor)))
-    {
-      Lisp_Object parm_index = Fget (Qbackground_color, Qx_frame_parameter);
-      if (NATNUMP (parm_index)
-	  && XFASTINT (parm_index) < ARRAYELTS (frame_parms)
-	  && FRAME_RIF (f)->frame_parm_handlers[XFASTINT (parm_index)])
-	  (*FRAME_RIF (f)->frame_parm_handlers[XFASTINT (parm_index)])
-	    (f, bgcolor, Qnil);
-    }
+    handle_frame_parameter (f, Qbackground_color, bgcolor, Qnil);
 
   clear_face_cache (true);	/* FIXME: Why of all frames?  */
   fset_redisplay (f);
@@ -4134,24 +3333,6 @@ x_set_visibility (struct frame *f, Lisp_Object value, Lisp_Object oldval)
     Fmake_frame_visible (frame);
 }
 
-void
-x_set_autoraise (struct frame *f, Lisp_Object arg, Lisp_Object oldval)
-{
-  f->auto_raise = !EQ (Qnil, arg);
-}
-
-void
-x_set_autolower (struct frame *f, Lisp_Object arg, Lisp_Object oldval)
-{
-  f->auto_lower = !EQ (Qnil, arg);
-}
-
-void
-x_set_unsplittable (struct frame *f, Lisp_Object arg, Lisp_Object oldval)
-{
-  f->no_split = !NILP (arg);
-}
-
 void
 x_set_vertical_scroll_bars (struct frame *f, Lisp_Object arg, Lisp_Object oldval)
 {
@@ -5364,18 +4545,6 @@ syms_of_frame (void)
   DEFSYM (Qiconify_top_level, "iconify-top-level");
   DEFSYM (Qmake_invisible, "make-invisible");
 
-  {
-    int i;
-
-    for (i = 0; i < ARRAYELTS (frame_parms); i++)
-      {
-	Lisp_Object v = (frame_parms[i].sym < 0
-			 ? intern_c_string (frame_parms[i].name)
-			 : builtin_lisp_symbol (frame_parms[i].sym));
-	Fput (v, Qx_frame_parameter, make_number (i));
-      }
-  }
-
 #ifdef HAVE_WINDOW_SYSTEM
   DEFVAR_LISP ("x-resource-name", Vx_resource_name,
     doc: /* The name Emacs uses to look up X resources.
@@ -5676,29 +4845,16 @@ iconify the top level frame instead.  */);
 
   defsubr (&Sframe_windows_min_size);
   defsubr (&Smake_terminal_frame);
-  defsubr (&Shandle_switch_frame);
-  defsubr (&Sselect_frame);
   defsubr (&Sframe_list);
-  defsubr (&Sframe_parent);
-  defsubr (&Sframe_ancestor_p);
   defsubr (&Slast_nonminibuf_frame);
-  defsubr (&Smouse_position);
-  defsubr (&Smouse_pixel_position);
-  defsubr (&Sset_mouse_position);
-  defsubr (&Sset_mouse_pixel_position);
 #if 0
   defsubr (&Sframe_configuration);
   defsubr (&Srestore_frame_configuration);
 #endif
-  defsubr (&Smake_frame_visible);
-  defsubr (&Smake_frame_invisible);
-  defsubr (&Siconify_frame);
   defsubr (&Svisible_frame_list);
   defsubr (&Sraise_frame);
   defsubr (&Slower_frame);
   defsubr (&Sx_focus_frame);
-  defsubr (&Sredirect_frame_focus);
-  defsubr (&Sframe_focus);
   defsubr (&Sframe_parameters);
   defsubr (&Sframe_parameter);
   defsubr (&Smodify_frame_parameters);
@@ -5709,10 +4865,6 @@ iconify the top level frame instead.  */);
   defsubr (&Sscroll_bar_width);
   defsubr (&Sscroll_bar_height);
   defsubr (&Stool_bar_pixel_width);
-  defsubr (&Sset_frame_height);
-  defsubr (&Sset_frame_width);
-  defsubr (&Sset_frame_size);
-  defsubr (&Sset_frame_position);
 
 #ifdef HAVE_WINDOW_SYSTEM
   defsubr (&Sx_get_resource);
@@ -1243,10 +1243,38 @@ extern void frame_make_pointer_visible (struct frame *);
 extern Lisp_Object delete_frame (Lisp_Object, Lisp_Object);
 extern bool frame_inhibit_resize (struct frame *, bool, Lisp_Object);
 extern void adjust_frame_size (struct frame *, int, int, int, bool, Lisp_Object);
+extern void frame_set_offset (struct frame *, int, int);
+
+/* The frame parameters whose float values are relative to the size of
+   the display or parent frame; see frame_float_value.  */
+enum frame_float_type
+{
+ FRAME_FLOAT_WIDTH,
+ FRAME_FLOAT_HEIGHT,
+ FRAME_FLOAT_LEFT,
+ FRAME_FLOAT_TOP
+};
+
+extern int frame_float_value (struct frame *, Lisp_Object,
+			      enum frame_float_type, int *, int *, int);
+extern void frame_set_position (struct frame *, int, int, bool, bool);
+extern void frame_set_fullscreen (struct frame *, Lisp_Object, Lisp_Object);
+extern void frame_set_icon_position (struct frame *, int, int);
+extern void frame_call_parm_handler (struct frame *, ptrdiff_t,
+				     Lisp_Object, Lisp_Object);
+extern Lisp_Object frame_display_focus_frame (struct frame *);
+extern void frame_make_tty_top_frame (struct frame *);
+extern void frame_make_visible (struct frame *);
+extern void frame_make_invisible (struct frame *);
+extern void frame_iconify (struct frame *);
+extern void frame_warp_mouse (struct frame *, int, int, bool);
+extern bool other_frames (struct frame *, bool, bool);
+extern void check_minibuf_window (Lisp_Object, int);
 extern void frame_size_history_add (struct frame *f, Lisp_Object fun_symbol,
 				    int width, int height, Lisp_Object rest);
 
 extern Lisp_Object Vframe_list;
+extern struct frame *last_nonminibuf_frame;
 
 /* Value is a pointer to the selected frame.  If the selected frame
    isn't live, abort.  */
@@ -1501,7 +1529,6 @@ extern void x_set_scroll_bar_default_height (struct frame *);
 extern void x_set_offset (struct frame *, int, int, int);
 extern void x_wm_set_size_hint (struct frame *f, long flags, bool user_position);
 extern Lisp_Object x_new_font (struct frame *, Lisp_Object, int);
-extern void x_set_frame_parameters (struct frame *, Lisp_Object);
 extern void x_set_fullscreen (struct frame *, Lisp_Object, Lisp_Object);
 extern void x_set_line_spacing (struct frame *, Lisp_Object, Lisp_Object);
 extern void x_set_screen_gamma (struct frame *, Lisp_Object, Lisp_Object);
@@ -1515,9 +1542,6 @@ extern void x_set_right_divider_width (struct frame *, Lisp_Object,
 extern void x_set_bottom_divider_width (struct frame *, Lisp_Object,
 					Lisp_Object);
 extern void x_set_visibility (struct frame *, Lisp_Object, Lisp_Object);
-extern void x_set_autoraise (struct frame *, Lisp_Object, Lisp_Object);
-extern void x_set_autolower (struct frame *, Lisp_Object, Lisp_Object);
-extern void x_set_unsplittable (struct frame *, Lisp_Object, Lisp_Object);
 extern void x_set_vertical_scroll_bars (struct frame *, Lisp_Object, Lisp_Object);
 extern void x_set_horizontal_scroll_bars (struct frame *, Lisp_Object, Lisp_Object);
 extern void x_set_scroll_bar_width (struct frame *, Lisp_Object, Lisp_Object);
@@ -1556,6 +1580,9 @@ int fget_internal_border_width(const struct frame *);
 Lisp_Object fget_minibuffer_window(const struct frame *);
 Lisp_Object fget_root_window(const struct frame *);
 struct terminal * fget_terminal(const struct frame *);
+Lisp_Object fget_parent_frame(const struct frame *);
+bool fget_tooltip(const struct frame *);
+void frame_implicitly_set_name (struct frame *, Lisp_Object);
 
 #if defined HAVE_X_WINDOWS
 extern void x_wm_set_icon_position (struct frame *, int, int);
@@ -5040,22 +5040,8 @@ update_frame_tool_bar (struct frame *f)
               && STRINGP (rtl))
 	    image = find_rtl_image (f, image, rtl);
 
-          if (VECTORP (image))
-            {
-              if (enabled_p)
-                idx = (selected_p
-                       ? TOOL_BAR_IMAGE_ENABLED_SELECTED
-                       : TOOL_BAR_IMAGE_ENABLED_DESELECTED);
-              else
-                idx = (selected_p
-                       ? TOOL_BAR_IMAGE_DISABLED_SELECTED
-                       : TOOL_BAR_IMAGE_DISABLED_DESELECTED);
-
-              eassert (ASIZE (image) >= idx);
-              image = AREF (image, idx);
-            }
-          else
-            idx = -1;
+          image = tool_bar_image_for_state (image, enabled_p, selected_p,
+                                            &idx);
 
           img_id = lookup_image (f, image);
           img = IMAGE_FROM_ID (f, img_id);
@@ -36,23 +36,6 @@ static void insert_from_buffer_1 (struct buffer *, ptrdiff_t, ptrdiff_t, bool);
 static void gap_left (ptrdiff_t, ptrdiff_t, bool);
 static void gap_right (ptrdiff_t, ptrdiff_t);
 
-/* List of elements of the form (BEG-UNCHANGED END-UNCHANGED CHANGE-AMOUNT)
-   describing changes which happened while combine_after_change_calls
-   was non-nil.  We use this to decide how to call them
-   once the deferral ends.
-
-   In each element.
-   BEG-UNCHANGED is the number of chars before the changed range.
-   END-UNCHANGED is the number of chars after the changed range,
-   and CHANGE-AMOUNT is the number of characters inserted by the change
-   (negative for a deletion).  */
-static Lisp_Object combine_after_change_list;
-
-/* Buffer which combine_after_change_list is about.  */
-static Lisp_Object combine_after_change_buffer;
-
-static void signal_before_change (ptrdiff_t, ptrdiff_t, ptrdiff_t *);
-
 /* Also used in marker.c to enable expensive marker checks.  */
 
 #ifdef MARKER_DEBUG
@@ -108,6 +91,8 @@ gap_left (ptrdiff_t charpos, ptrdiff_t bytepos, bool newgap)
   ptrdiff_t i;
   ptrdiff_t new_s1;
 
+  detach_buffer_snapshots (current_buffer);
+
   if (!newgap)
     BUF_COMPUTE_UNCHANGED (current_buffer, charpos, GPT);
 
@@ -165,6 +150,8 @@ gap_right (ptrdiff_t charpos, ptrdiff_t bytepos)
   register ptrdiff_t i;
   ptrdiff_t new_s1;
 
+  detach_buffer_snapshots (current_buffer);
+
   BUF_COMPUTE_UNCHANGED (current_buffer, charpos, GPT);
 
   i = GPT_BYTE;
@@ -457,6 +444,8 @@ make_gap_larger (ptrdiff_t nbytes_added)
   if (BUF_BYTES_MAX - current_size < nbytes_added)
     buffer_overflow ();
 
+  detach_buffer_snapshots (current_buffer);
+
   /* If we have to get more space, get enough to last a while;
      but do not exceed the maximum buffer size.  */
   nbytes_added = min (nbytes_added + GAP_BYTES_DFL,
@@ -508,6 +497,8 @@ make_gap_smaller (ptrdiff_t nbytes_removed)
   ptrdiff_t real_beg_unchanged;
   ptrdiff_t new_gap_size;
 
+  detach_buffer_snapshots (current_buffer);
+
   /* Make sure the gap is at least GAP_BYTES_MIN bytes.  */
   if (GAP_SIZE - nbytes_removed < GAP_BYTES_MIN)
     nbytes_removed = GAP_SIZE - GAP_BYTES_MIN;
@@ -1414,6 +1405,8 @@ replace_range (ptrdiff_t from, ptrdiff_t to, Lisp_Object new,
     outgoing_insbytes
       = count_size_as_multibyte (SDATA (new), insbytes);
 
+  detach_buffer_snapshots (current_buffer);
+
   /* Make sure the gap is somewhere in or next to what we are deleting.  */
   if (from > GPT)
     gap_right (from, from_byte);
@@ -1561,6 +1554,8 @@ replace_range_2 (ptrdiff_t from, ptrdiff_t from_byte,
   if (nbytes_del <= 0 && insbytes == 0)
     return;
 
+  detach_buffer_snapshots (current_buffer);
+
   /* Make sure the gap is somewhere in or next to what we are deleting.  */
   if (from > GPT)
     gap_right (from, from_byte);
@@ -1804,6 +1799,8 @@ del_range_2 (ptrdiff_t from, ptrdiff_t from_byte,
   nchars_del = to - from;
   nbytes_del = to_byte - from_byte;
 
+  detach_buffer_snapshots (current_buffer);
+
   /* Make sure the gap is somewhere in or next to what we are deleting.  */
   if (from > GPT)
     gap_right (from, from_byte);
@@ -1879,6 +1876,7 @@ void
 modify_text (ptrdiff_t start, ptrdiff_t end)
 {
   prepare_to_modify_buffer (start, end, NULL);
+  detach_buffer_snapshots (current_buffer);
 
   BUF_COMPUTE_UNCHANGED (current_buffer, start - 1, end);
   if (MODIFF <= SAVE_MODIFF)
@@ -2041,298 +2039,9 @@ invalidate_buffer_caches (struct buffer *buf, ptrdiff_t start, ptrdiff_t end)
                              start - BUF_BEG (buf), BUF_Z (buf) - end);
 }
 
-/* These macros work with an argument named `preserve_ptr'
-   and a local variable named `preserve_marker'.  */
-
-#define PRESERVE_VALUE							\
-  if (preserve_ptr && NILP (preserve_marker))				\
-    preserve_marker = Fcopy_marker (make_number (*preserve_ptr), Qnil)
-
-#define RESTORE_VALUE						\
-  if (! NILP (preserve_marker))					\
-    {								\
-      *preserve_ptr = marker_position (preserve_marker);	\
-      unchain_marker (XMARKER (preserve_marker));		\
-    }
-
-#define PRESERVE_START_END			\
-  if (NILP (start_marker))			\
-    start_marker = Fcopy_marker (start, Qnil);	\
-  if (NILP (end_marker))			\
-    end_marker = Fcopy_marker (end, Qnil);
-
-#define FETCH_START				\
-  (! NILP (start_marker) ? Fmarker_position (start_marker) : start)
-
-#define FETCH_END				\
-  (! NILP (end_marker) ? Fmarker_position (end_marker) : end)
-
-/* Set a variable to nil if an error occurred.
-   Don't change the variable if there was no error.
-   VAL is a cons-cell (VARIABLE . NO-ERROR-FLAG).
-   VARIABLE is the variable to maybe set to nil.
-   NO-ERROR-FLAG is nil if there was an error,
-   anything else meaning no error (so this function does nothing).  */
-struct rvoe_arg
-{
-  Lisp_Object *location;
-  bool errorp;
-};
-
-static void
-reset_var_on_error (void *ptr)
-{
-  struct rvoe_arg *p = ptr;
-  if (p->errorp)
-    *p->location = Qnil;
-}
-
-/* Signal a change to the buffer immediately before it happens.
-   START_INT and END_INT are the bounds of the text to be changed.
-
-   If PRESERVE_PTR is nonzero, we relocate *PRESERVE_PTR
-   by holding its value temporarily in a marker.  */
-
-static void
-signal_before_change (ptrdiff_t start_int, ptrdiff_t end_int,
-		      ptrdiff_t *preserve_ptr)
-{
-  Lisp_Object start, end;
-  Lisp_Object start_marker, end_marker;
-  Lisp_Object preserve_marker;
-  ptrdiff_t count = SPECPDL_INDEX ();
-  struct rvoe_arg rvoe_arg;
-
-  start = make_number (start_int);
-  end = make_number (end_int);
-  preserve_marker = Qnil;
-  start_marker = Qnil;
-  end_marker = Qnil;
-
-  specbind (Qinhibit_modification_hooks, Qt);
-
-  /* If buffer is unmodified, run a special hook for that case.  The
-   check for Vfirst_change_hook is just a minor optimization.  */
-  if (SAVE_MODIFF >= MODIFF
-      && !NILP (Vfirst_change_hook))
-    {
-      PRESERVE_VALUE;
-      PRESERVE_START_END;
-      run_hook (Qfirst_change_hook);
-    }
-
-  /* Now run the before-change-functions if any.  */
-  if (!NILP (Vbefore_change_functions))
-    {
-      rvoe_arg.location = &Vbefore_change_functions;
-      rvoe_arg.errorp = 1;
-
-      PRESERVE_VALUE;
-      PRESERVE_START_END;
-
-      /* Mark before-change-functions to be reset to nil in case of error.  */
-      record_unwind_protect_ptr (reset_var_on_error, &rvoe_arg);
-
-      /* Actually run the hook functions.  */
-      CALLN (Frun_hook_with_args, Qbefore_change_functions,
-	     FETCH_START, FETCH_END);
-
-      /* There was no error: unarm the reset_on_error.  */
-      rvoe_arg.errorp = 0;
-    }
-
-  if (buffer_has_overlays ())
-    {
-      PRESERVE_VALUE;
-      report_overlay_modification (FETCH_START, FETCH_END, 0,
-				   FETCH_START, FETCH_END, Qnil);
-    }
-
-  if (! NILP (start_marker))
-    free_marker (start_marker);
-  if (! NILP (end_marker))
-    free_marker (end_marker);
-  RESTORE_VALUE;
-
-  unbind_to (count, Qnil);
-}
-
-/* Signal a change immediately after it happens.
-   CHARPOS is the character position of the start of the changed text.
-   LENDEL is the number of characters of the text before the change.
-   (Not the whole buffer; just the part that was changed.)
-   LENINS is the number of characters in that part of the text
-   after the change.  */
-
-void
-signal_after_change (ptrdiff_t charpos, ptrdiff_t lendel, ptrdiff_t lenins)
-{
-  ptrdiff_t count = SPECPDL_INDEX ();
-  struct rvoe_arg rvoe_arg;
-
-  if (inhibit_modification_hooks)
-    return;
-
-  /* If we are deferring calls to the after-change functions
-     and there are no before-change functions,
-     just record the args that we were going to use.  */
-  if (! NILP (Vcombine_after_change_calls)
-      && NILP (Vbefore_change_functions)
-      && !buffer_has_overlays ())
-    {
-      Lisp_Object elt;
-
-      if (!NILP (combine_after_change_list)
-	  && current_buffer != XBUFFER (combine_after_change_buffer))
-	Fcombine_after_change_execute ();
-
-      elt = list3i (charpos - BEG, Z - (charpos - lendel + lenins),
-		    lenins - lendel);
-      combine_after_change_list
-	= Fcons (elt, combine_after_change_list);
-      combine_after_change_buffer = Fcurrent_buffer ();
-
-      return;
-    }
-
-  if (!NILP (combine_after_change_list))
-    Fcombine_after_change_execute ();
-
-  specbind (Qinhibit_modification_hooks, Qt);
-
-  if (!NILP (Vafter_change_functions))
-    {
-      rvoe_arg.location = &Vafter_change_functions;
-      rvoe_arg.errorp = 1;
-
-      /* Mark after-change-functions to be reset to nil in case of error.  */
-      record_unwind_protect_ptr (reset_var_on_error, &rvoe_arg);
-
-      /* Actually run the hook functions.  */
-      CALLN (Frun_hook_with_args, Qafter_change_functions,
-	     make_number (charpos), make_number (charpos + lenins),
-	     make_number (lendel));
-
-      /* There was no error: unarm the reset_on_error.  */
-      rvoe_arg.errorp = 0;
-    }
-
-  if (buffer_has_overlays ())
-    report_overlay_modification (make_number (charpos),
-				 make_number (charpos + lenins),
-				 1,
-				 make_number (charpos),
-				 make_number (charpos + lenins),
-				 make_number (lendel));
-
-  /* After an insertion, call the text properties
-     insert-behind-hooks or insert-in-front-hooks.  */
-  if (lendel == 0)
-    report_interval_modification (make_number (charpos),
-				  make_number (charpos + lenins));
-
-  unbind_to (count, Qnil);
-}
-
-static void
-Fcombine_after_change_execute_1 (Lisp_Object val)
-{
-  Vcombine_after_change_calls = val;
-}
-
-DEFUN ("combine-after-change-execute", Fcombine_after_change_execute,
-       Scombine_after_change_execute, 0, 0, 0,
-       doc: /* This function is for use internally in the function `combine-after-change-calls'.  */)
-  (void)
-{
-  ptrdiff_t count = SPECPDL_INDEX ();
-  ptrdiff_t beg, end, change;
-  ptrdiff_t begpos, endpos;
-  Lisp_Object tail;
-
-  if (NILP (combine_after_change_list))
-    return Qnil;
-
-  /* It is rare for combine_after_change_buffer to be invalid, but
-     possible.  It can happen when combine-after-change-calls is
-     non-nil, and insertion calls a file handler (e.g. through
-     lock_file) which scribbles into a temp file -- cyd  */
-  if (!BUFFERP (combine_after_change_buffer)
-      || !BUFFER_LIVE_P (XBUFFER (combine_after_change_buffer)))
-    {
-      combine_after_change_list = Qnil;
-      return Qnil;
-    }
-
-  record_unwind_current_buffer ();
-
-  Fset_buffer (combine_after_change_buffer);
-
-  /* # chars unchanged at beginning of buffer.  */
-  beg = Z - BEG;
-  /* # chars unchanged at end of buffer.  */
-  end = beg;
-  /* Total amount of insertion (negative for deletion).  */
-  change = 0;
-
-  /* Scan the various individual changes,
-     accumulating the range info in BEG, END and CHANGE.  */
-  for (tail = combine_after_change_list; CONSP (tail);
-       tail = XCDR (tail))
-    {
-      Lisp_Object elt;
-      ptrdiff_t thisbeg, thisend, thischange;
-
-      /* Extract the info from the next element.  */
-      elt = XCAR (tail);
-      if (! CONSP (elt))
-	continue;
-      thisbeg = XINT (XCAR (elt));
-
-      elt = XCDR (elt);
-      if (! CONSP (elt))
-	continue;
-      thisend = XINT (XCAR (elt));
-
-      elt = XCDR (elt);
-      if (! CONSP (elt))
-	continue;
-      thischange = XINT (XCAR (elt));
-
-      /* Merge this range into the accumulated range.  */
-      change += thischange;
-      if (thisbeg < beg)
-	beg = thisbeg;
-      if (thisend < end)
-	end = thisend;
-    }
-
-  /* Get the current start and end positions of the range
-     that was changed.  */
-  begpos = BEG + beg;
-  endpos = Z - end;
-
-  /* We are about to handle these, so discard them.  */
-  combine_after_change_list = Qnil;
-
-  /* Now run the after-change functions for real.
-     Turn off the flag that defers them.  */
-  record_unwind_protect (Fcombine_after_change_execute_1,
-			 Vcombine_after_change_calls);
-  signal_after_change (begpos, endpos - begpos - change, endpos - begpos);
-  update_compositions (begpos, endpos, CHECK_ALL);
-
-  return unbind_to (count, Qnil);
-}
-
 void
 syms_of_insdel (void)
 {
-  staticpro (&combine_after_change_list);
-  staticpro (&combine_after_change_buffer);
-  combine_after_change_list = Qnil;
-  combine_after_change_buffer = Qnil;
-
   DEFSYM (Qundo_auto__undoable_change, "undo-auto--undoable-change");
 
   DEFVAR_LISP ("combine-after-change-calls", Vcombine_after_change_calls,
@@ -2350,6 +2059,4 @@ handling of the active region per `select-active-regions'.  */);
   DEFSYM (Qinhibit_modification_hooks, "inhibit-modification-hooks");
 
   DEFSYM (Qregion_extract_function, "region-extract-function");
-
-  defsubr (&Scombine_after_change_execute);
 }
@@ -2126,13 +2126,13 @@ get_property_and_range (ptrdiff_t pos, Lisp_Object prop, Lisp_Object *val,
   return 1;
 }
 
-/* Return the proper local keymap TYPE for position POSITION in
-   BUFFER; TYPE should be one of `keymap' or `local-map'.  Use the map
-   specified by the PROP property, if any.  Otherwise, if TYPE is
-   `local-map' use BUFFER's local map.  */
+/* Return the value of the property TYPE, one of `keymap' or
+   `local-map', that says which keymap applies at POSITION in BUFFER.
+   It isn't checked to be a keymap; see get_local_map.  */
 
 Lisp_Object
-get_local_map (ptrdiff_t position, struct buffer *buffer, Lisp_Object type)
+get_local_map_property (ptrdiff_t position, struct buffer *buffer,
+			Lisp_Object type)
 {
   Lisp_Object prop, lispy_position, lispy_buffer;
   ptrdiff_t old_begv, old_zv, old_begv_byte, old_zv_byte;
@@ -2167,6 +2167,27 @@ get_local_map (ptrdiff_t position, struct buffer *buffer, Lisp_Object type)
   SET_BUF_ZV_BOTH (buffer, old_zv, old_zv_byte);
   unbind_to (count, Qnil);
 
+  return prop;
+}
+
+/* Return the proper local keymap TYPE for position POSITION in
+   BUFFER; TYPE should be one of `keymap' or `local-map'.  Use the map
+   specified by the PROP property, if any.  Otherwise, if TYPE is
+   `local-map' use BUFFER's local map.  */
+
+Lisp_Object
+get_local_map (ptrdiff_t position, struct buffer *buffer, Lisp_Object type)
+{
+  return resolve_local_map (get_local_map_property (position, buffer, type),
+			    buffer, type);
+}
+
+/* Return the keymap of PROP, the value of the property TYPE found by
+   get_local_map_property in BUFFER.  */
+
+Lisp_Object
+resolve_local_map (Lisp_Object prop, struct buffer *buffer, Lisp_Object type)
+{
   /* Use the local map only if it is valid.  */
   prop = get_keymap (prop, 0, 0);
   if (CONSP (prop))
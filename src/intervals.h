@@ -265,6 +265,10 @@ extern void move_if_not_intangible (ptrdiff_t);
 extern bool get_property_and_range (ptrdiff_t, Lisp_Object, Lisp_Object *,
 				    ptrdiff_t *, ptrdiff_t *, Lisp_Object);
 extern Lisp_Object get_local_map (ptrdiff_t, struct buffer *, Lisp_Object);
+extern Lisp_Object get_local_map_property (ptrdiff_t, struct buffer *,
+					   Lisp_Object);
+extern Lisp_Object resolve_local_map (Lisp_Object, struct buffer *,
+				      Lisp_Object);
 extern INTERVAL update_interval (INTERVAL, ptrdiff_t);
 extern void set_intervals_multibyte (bool);
 extern INTERVAL validate_interval_range (Lisp_Object, Lisp_Object *,
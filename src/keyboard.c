@@ -183,6 +183,20 @@ int quit_char;
 /* Current depth in recursive edits.  */
 EMACS_INT command_loop_level;
 
+/* Number of successful iterations so far
+   for innermost keyboard macro.
+   This is not bound at each level,
+   so after an error, it describes the innermost interrupted macro.  */
+
+EMACS_INT executing_kbd_macro_iterations;
+
+/* This is the macro that was executing.
+   This is not bound at each level,
+   so after an error, it describes the innermost interrupted macro.
+   We use it only as a kind of flag, so no need to protect it.  */
+
+Lisp_Object executing_kbd_macro;
+
 /* If not Qnil, this is a switch-frame event which we decided to put
    off until the end of a key sequence.  This should be read as the
    next command input, after any unread_command_events.
@@ -360,7 +374,6 @@ static Lisp_Object make_lispy_focus_out (Lisp_Object);
 static bool help_char_p (Lisp_Object);
 static void save_getcjmp (sys_jmp_buf);
 static void restore_getcjmp (sys_jmp_buf);
-static Lisp_Object apply_modifiers (int, Lisp_Object);
 static void restore_kboard_configuration (int);
 static void handle_interrupt (bool);
 static _Noreturn void quit_throw_to_read_char (bool);
@@ -1372,6 +1385,7 @@ command_loop_1 (void)
       }
       Vthis_command = cmd;
       Vreal_this_command = cmd;
+      transient_maps_pre_command ();
       safe_run_hooks (Qpre_command_hook);
 
       already_adjusted = 0;
@@ -2359,20 +2373,13 @@ read_char (int commandflag, Lisp_Object map,
       /* Exit the macro if we are at the end.
 	 Also, some things replace the macro with t
 	 to force an early exit.  */
-      if (EQ (Vexecuting_kbd_macro, Qt)
-	  || executing_kbd_macro_index >= XFASTINT (Flength (Vexecuting_kbd_macro)))
+      c = next_kbd_macro_event ();
+      if (NILP (c))
 	{
 	  XSETINT (c, -1);
 	  goto exit;
 	}
 
-      c = Faref (Vexecuting_kbd_macro, make_number (executing_kbd_macro_index));
-      if (STRINGP (Vexecuting_kbd_macro)
-	  && (XFASTINT (c) & 0x80) && (XFASTINT (c) <= 0xff))
-	XSETFASTINT (c, CHAR_META | (XFASTINT (c) & ~0x80));
-
-      executing_kbd_macro_index++;
-
       goto from_macro;
     }
 
@@ -3848,6 +3855,7 @@ kbd_buffer_get_event (KBOARD **kbp,
       case HELP_EVENT:
       case FOCUS_IN_EVENT:
       case CONFIG_CHANGED_EVENT:
+      case MONITORS_CHANGED_EVENT:
       case FOCUS_OUT_EVENT:
       case SELECT_WINDOW_EVENT:
         {
@@ -5918,14 +5926,8 @@ make_lispy_event (struct input_event *event)
       return list2 (Qselect_window, list1 (event->frame_or_window));
 
     case TOOL_BAR_EVENT:
-      if (EQ (event->arg, event->frame_or_window))
-	/* This is the prefix key.  We translate this to
-	   `(tool_bar)' because the code in keyboard.c for tool bar
-	   events, which we use, relies on this.  */
-	return list1 (Qtool_bar);
-      else if (SYMBOLP (event->arg))
-	return apply_modifiers (event->modifiers, event->arg);
-      return event->arg;
+      return make_lispy_tool_bar_event (event->frame_or_window, event->arg,
+					 event->modifiers);
 
     case USER_SIGNAL_EVENT:
       /* A user signal.  */
@@ -5967,6 +5969,12 @@ make_lispy_event (struct input_event *event)
 	return list3 (Qconfig_changed_event,
 		      event->arg, event->frame_or_window);
 
+    case TTY_PASTE_EVENT:
+      return list2 (Qxterm_paste, event->arg);
+
+    case MONITORS_CHANGED_EVENT:
+      return list2 (Qmonitors_changed, event->arg);
+
       /* The 'kind' field of the event is something we don't recognize.  */
     default:
       emacs_abort ();
@@ -6298,7 +6306,7 @@ DEFUN ("internal-event-symbol-parse-modifiers", Fevent_symbol_parse_modifiers,
 
    apply_modifiers copies the value of BASE's Qevent_kind property to
    the modified symbol.  */
-static Lisp_Object
+Lisp_Object
 apply_modifiers (int modifiers, Lisp_Object base)
 {
   Lisp_Object cache, idx, entry, new_symbol;
@@ -6833,7 +6841,6 @@ tty_read_avail_input (struct terminal *terminal,
 #ifndef WINDOWSNT
   int n_to_read;
 #endif
-  int i;
   struct tty_display_info *tty = terminal->display_info.tty;
   int nread = 0;
   int buffer_free = KBD_BUFFER_SIZE - kbd_buffer_nr_stored () - 1;
@@ -6946,35 +6953,46 @@ tty_read_avail_input (struct terminal *terminal,
 
 #endif /* not WINDOWSNT */
 
-  for (i = 0; i < nread; i++)
-    {
-      struct input_event buf;
-      EVENT_INIT (buf);
-      buf.kind = ASCII_KEYSTROKE_EVENT;
-      buf.modifiers = 0;
-      if (tty->meta_key == 1 && (cbuf[i] & 0x80))
-        buf.modifiers = meta_modifier;
-      if (tty->meta_key != 2)
-        cbuf[i] &= ~0x80;
-
-      buf.code = cbuf[i];
-      /* Set the frame corresponding to the active tty.  Note that the
-         value of selected_frame is not reliable here, redisplay tends
-         to temporarily change it.  */
-      buf.frame_or_window = tty->top_frame;
-      buf.arg = Qnil;
-
-      kbd_buffer_store_event (&buf);
-      /* Don't look at input that follows a C-g too closely.
-         This reduces lossage due to autorepeat on C-g.  */
-      if (buf.kind == ASCII_KEYSTROKE_EVENT
-          && buf.code == quit_char)
-        break;
-    }
+  /* Turn the bytes into events; this is done in tty_input.rs.  */
+  tty_decode_input (terminal, cbuf, nread);
 
   return nread;
 }
+
+/* Store an input event of KIND for the text terminal TTY.  CODE,
+   MODIFIERS and ARG are as in struct input_event.  */
+
+void
+tty_store_event (struct tty_display_info *tty, enum event_kind kind,
+		 unsigned code, unsigned modifiers, Lisp_Object arg)
+{
+  struct input_event buf;
+  EVENT_INIT (buf);
+  buf.kind = kind;
+  buf.code = code;
+  buf.modifiers = modifiers;
+  /* Set the frame corresponding to the active tty.  Note that the
+     value of selected_frame is not reliable here, redisplay tends
+     to temporarily change it.  */
+  buf.frame_or_window = tty->top_frame;
+  buf.arg = arg;
+
+  kbd_buffer_store_event (&buf);
+}
 
+/* Store an event saying that the monitors of the terminal T were
+   connected, disconnected or rearranged.  */
+
+void
+store_monitors_changed_event (struct terminal *t)
+{
+  struct input_event event;
+  EVENT_INIT (event);
+  event.kind = MONITORS_CHANGED_EVENT;
+  XSETTERMINAL (event.arg, t);
+  kbd_buffer_store_event (&event);
+}
+
 static void
 handle_async_input (void)
 {
@@ -6997,6 +7015,7 @@ process_pending_signals (void)
   pending_signals = false;
   handle_async_input ();
   do_pending_atimers ();
+  process_pending_suspend ();
 }
 
 /* Undo any number of BLOCK_INPUT calls down to level LEVEL,
@@ -7787,465 +7806,6 @@ parse_menu_item (Lisp_Object item, int inmenubar)
 }
 
 
-
-/***********************************************************************
-			       Tool-bars
- ***********************************************************************/
-
-/* A vector holding tool bar items while they are parsed in function
-   tool_bar_items. Each item occupies TOOL_BAR_ITEM_NSCLOTS elements
-   in the vector.  */
-
-static Lisp_Object tool_bar_items_vector;
-
-/* A vector holding the result of parse_tool_bar_item.  Layout is like
-   the one for a single item in tool_bar_items_vector.  */
-
-static Lisp_Object tool_bar_item_properties;
-
-/* Next free index in tool_bar_items_vector.  */
-
-static int ntool_bar_items;
-
-/* Function prototypes.  */
-
-static void init_tool_bar_items (Lisp_Object);
-static void process_tool_bar_item (Lisp_Object, Lisp_Object, Lisp_Object,
-				   void *);
-static bool parse_tool_bar_item (Lisp_Object, Lisp_Object);
-static void append_tool_bar_item (void);
-
-
-/* Return a vector of tool bar items for keymaps currently in effect.
-   Reuse vector REUSE if non-nil.  Return in *NITEMS the number of
-   tool bar items found.  */
-
-Lisp_Object
-tool_bar_items (Lisp_Object reuse, int *nitems)
-{
-  Lisp_Object *maps;
-  Lisp_Object mapsbuf[3];
-  ptrdiff_t nmaps, i;
-  Lisp_Object oquit;
-  Lisp_Object *tmaps;
-  USE_SAFE_ALLOCA;
-
-  *nitems = 0;
-
-  /* In order to build the menus, we need to call the keymap
-     accessors.  They all call maybe_quit.  But this function is called
-     during redisplay, during which a quit is fatal.  So inhibit
-     quitting while building the menus.  We do this instead of
-     specbind because (1) errors will clear it anyway and (2) this
-     avoids risk of specpdl overflow.  */
-  oquit = Vinhibit_quit;
-  Vinhibit_quit = Qt;
-
-  /* Initialize tool_bar_items_vector and protect it from GC.  */
-  init_tool_bar_items (reuse);
-
-  /* Build list of keymaps in maps.  Set nmaps to the number of maps
-     to process.  */
-
-  /* Should overriding-terminal-local-map and overriding-local-map apply?  */
-  if (!NILP (Voverriding_local_map_menu_flag)
-      && !NILP (Voverriding_local_map))
-    {
-      /* Yes, use them (if non-nil) as well as the global map.  */
-      maps = mapsbuf;
-      nmaps = 0;
-      if (!NILP (KVAR (current_kboard, Voverriding_terminal_local_map)))
-	maps[nmaps++] = KVAR (current_kboard, Voverriding_terminal_local_map);
-      if (!NILP (Voverriding_local_map))
-	maps[nmaps++] = Voverriding_local_map;
-    }
-  else
-    {
-      /* No, so use major and minor mode keymaps and keymap property.
-	 Note that tool-bar bindings in the local-map and keymap
-	 properties may not work reliable, as they are only
-	 recognized when the tool-bar (or mode-line) is updated,
-	 which does not normally happen after every command.  */
-      ptrdiff_t nminor = current_minor_maps (NULL, &tmaps);
-      SAFE_NALLOCA (maps, 1, nminor + 4);
-      nmaps = 0;
-      Lisp_Object tem = KVAR (current_kboard, Voverriding_terminal_local_map);
-      if (!NILP (tem) && !NILP (Voverriding_local_map_menu_flag))
-	maps[nmaps++] = tem;
-      if (tem = get_local_map (PT, current_buffer, Qkeymap), !NILP (tem))
-	maps[nmaps++] = tem;
-      if (nminor != 0)
-	{
-	  memcpy (maps + nmaps, tmaps, nminor * sizeof (maps[0]));
-	  nmaps += nminor;
-	}
-      maps[nmaps++] = get_local_map (PT, current_buffer, Qlocal_map);
-    }
-
-  /* Add global keymap at the end.  */
-  maps[nmaps++] = current_global_map;
-
-  /* Process maps in reverse order and look up in each map the prefix
-     key `tool-bar'.  */
-  for (i = nmaps - 1; i >= 0; --i)
-    if (!NILP (maps[i]))
-      {
-	Lisp_Object keymap;
-
-	keymap = get_keymap (access_keymap (maps[i], Qtool_bar, 1, 0, 1), 0, 1);
-	if (CONSP (keymap))
-	  map_keymap (keymap, process_tool_bar_item, Qnil, NULL, 1);
-      }
-
-  Vinhibit_quit = oquit;
-  *nitems = ntool_bar_items / TOOL_BAR_ITEM_NSLOTS;
-  SAFE_FREE ();
-  return tool_bar_items_vector;
-}
-
-
-/* Process the definition of KEY which is DEF.  */
-
-static void
-process_tool_bar_item (Lisp_Object key, Lisp_Object def, Lisp_Object data, void *args)
-{
-  int i;
-
-  if (EQ (def, Qundefined))
-    {
-      /* If a map has an explicit `undefined' as definition,
-	 discard any previously made item.  */
-      for (i = 0; i < ntool_bar_items; i += TOOL_BAR_ITEM_NSLOTS)
-	{
-	  Lisp_Object *v = XVECTOR (tool_bar_items_vector)->contents + i;
-
-	  if (EQ (key, v[TOOL_BAR_ITEM_KEY]))
-	    {
-	      if (ntool_bar_items > i + TOOL_BAR_ITEM_NSLOTS)
-		memmove (v, v + TOOL_BAR_ITEM_NSLOTS,
-			 ((ntool_bar_items - i - TOOL_BAR_ITEM_NSLOTS)
-			  * word_size));
-	      ntool_bar_items -= TOOL_BAR_ITEM_NSLOTS;
-	      break;
-	    }
-	}
-    }
-  else if (parse_tool_bar_item (key, def))
-    /* Append a new tool bar item to tool_bar_items_vector.  Accept
-       more than one definition for the same key.  */
-    append_tool_bar_item ();
-}
-
-/* Access slot with index IDX of vector tool_bar_item_properties.  */
-#define PROP(IDX) AREF (tool_bar_item_properties, (IDX))
-static void
-set_prop (ptrdiff_t idx, Lisp_Object val)
-{
-  ASET (tool_bar_item_properties, idx, val);
-}
-
-
-/* Parse a tool bar item specification ITEM for key KEY and return the
-   result in tool_bar_item_properties.  Value is false if ITEM is
-   invalid.
-
-   ITEM is a list `(menu-item CAPTION BINDING PROPS...)'.
-
-   CAPTION is the caption of the item,  If it's not a string, it is
-   evaluated to get a string.
-
-   BINDING is the tool bar item's binding.  Tool-bar items with keymaps
-   as binding are currently ignored.
-
-   The following properties are recognized:
-
-   - `:enable FORM'.
-
-   FORM is evaluated and specifies whether the tool bar item is
-   enabled or disabled.
-
-   - `:visible FORM'
-
-   FORM is evaluated and specifies whether the tool bar item is visible.
-
-   - `:filter FUNCTION'
-
-   FUNCTION is invoked with one parameter `(quote BINDING)'.  Its
-   result is stored as the new binding.
-
-   - `:button (TYPE SELECTED)'
-
-   TYPE must be one of `:radio' or `:toggle'.  SELECTED is evaluated
-   and specifies whether the button is selected (pressed) or not.
-
-   - `:image IMAGES'
-
-   IMAGES is either a single image specification or a vector of four
-   image specifications.  See enum tool_bar_item_images.
-
-   - `:help HELP-STRING'.
-
-   Gives a help string to display for the tool bar item.
-
-   - `:label LABEL-STRING'.
-
-   A text label to show with the tool bar button if labels are enabled.  */
-
-static bool
-parse_tool_bar_item (Lisp_Object key, Lisp_Object item)
-{
-  Lisp_Object filter = Qnil;
-  Lisp_Object caption;
-  int i;
-  bool have_label = false;
-
-  /* Definition looks like `(menu-item CAPTION BINDING PROPS...)'.
-     Rule out items that aren't lists, don't start with
-     `menu-item' or whose rest following `tool-bar-item' is not a
-     list.  */
-  if (!CONSP (item))
-    return 0;
-
-  /* As an exception, allow old-style menu separators.  */
-  if (STRINGP (XCAR (item)))
-    item = list1 (XCAR (item));
-  else if (!EQ (XCAR (item), Qmenu_item)
-	   || (item = XCDR (item), !CONSP (item)))
-    return 0;
-
-  /* Create tool_bar_item_properties vector if necessary.  Reset it to
-     defaults.  */
-  if (VECTORP (tool_bar_item_properties))
-    {
-      for (i = 0; i < TOOL_BAR_ITEM_NSLOTS; ++i)
-	set_prop (i, Qnil);
-    }
-  else
-    tool_bar_item_properties
-      = Fmake_vector (make_number (TOOL_BAR_ITEM_NSLOTS), Qnil);
-
-  /* Set defaults.  */
-  set_prop (TOOL_BAR_ITEM_KEY, key);
-  set_prop (TOOL_BAR_ITEM_ENABLED_P, Qt);
-
-  /* Get the caption of the item.  If the caption is not a string,
-     evaluate it to get a string.  If we don't get a string, skip this
-     item.  */
-  caption = XCAR (item);
-  if (!STRINGP (caption))
-    {
-      caption = menu_item_eval_property (caption);
-      if (!STRINGP (caption))
-	return 0;
-    }
-  set_prop (TOOL_BAR_ITEM_CAPTION, caption);
-
-  /* If the rest following the caption is not a list, the menu item is
-     either a separator, or invalid.  */
-  item = XCDR (item);
-  if (!CONSP (item))
-    {
-      if (menu_separator_name_p (SSDATA (caption)))
-	{
-	  set_prop (TOOL_BAR_ITEM_TYPE, Qt);
-#if !defined (USE_GTK) && !defined (HAVE_NS)
-	  /* If we use build_desired_tool_bar_string to render the
-	     tool bar, the separator is rendered as an image.  */
-	  set_prop (TOOL_BAR_ITEM_IMAGES,
-		    (menu_item_eval_property
-		     (Vtool_bar_separator_image_expression)));
-	  set_prop (TOOL_BAR_ITEM_ENABLED_P, Qnil);
-	  set_prop (TOOL_BAR_ITEM_SELECTED_P, Qnil);
-	  set_prop (TOOL_BAR_ITEM_CAPTION, Qnil);
-#endif
-	  return 1;
-	}
-      return 0;
-    }
-
-  /* Store the binding.  */
-  set_prop (TOOL_BAR_ITEM_BINDING, XCAR (item));
-  item = XCDR (item);
-
-  /* Ignore cached key binding, if any.  */
-  if (CONSP (item) && CONSP (XCAR (item)))
-    item = XCDR (item);
-
-  /* Process the rest of the properties.  */
-  for (; CONSP (item) && CONSP (XCDR (item)); item = XCDR (XCDR (item)))
-    {
-      Lisp_Object ikey, value;
-
-      ikey = XCAR (item);
-      value = XCAR (XCDR (item));
-
-      if (EQ (ikey, QCenable))
-	{
-	  /* `:enable FORM'.  */
-	  if (!NILP (Venable_disabled_menus_and_buttons))
-	    set_prop (TOOL_BAR_ITEM_ENABLED_P, Qt);
-	  else
-	    set_prop (TOOL_BAR_ITEM_ENABLED_P, value);
-	}
-      else if (EQ (ikey, QCvisible))
-	{
-	  /* `:visible FORM'.  If got a visible property and that
-	     evaluates to nil then ignore this item.  */
-	  if (NILP (menu_item_eval_property (value)))
-	    return 0;
-	}
-      else if (EQ (ikey, QChelp))
-        /* `:help HELP-STRING'.  */
-        set_prop (TOOL_BAR_ITEM_HELP, value);
-      else if (EQ (ikey, QCvert_only))
-        /* `:vert-only t/nil'.  */
-        set_prop (TOOL_BAR_ITEM_VERT_ONLY, value);
-      else if (EQ (ikey, QClabel))
-        {
-          const char *bad_label = "!!?GARBLED ITEM?!!";
-          /* `:label LABEL-STRING'.  */
-          set_prop (TOOL_BAR_ITEM_LABEL,
-		    STRINGP (value) ? value : build_string (bad_label));
-          have_label = true;
-        }
-      else if (EQ (ikey, QCfilter))
-	/* ':filter FORM'.  */
-	filter = value;
-      else if (EQ (ikey, QCbutton) && CONSP (value))
-	{
-	  /* `:button (TYPE . SELECTED)'.  */
-	  Lisp_Object type, selected;
-
-	  type = XCAR (value);
-	  selected = XCDR (value);
-	  if (EQ (type, QCtoggle) || EQ (type, QCradio))
-	    {
-	      set_prop (TOOL_BAR_ITEM_SELECTED_P, selected);
-	      set_prop (TOOL_BAR_ITEM_TYPE, type);
-	    }
-	}
-      else if (EQ (ikey, QCimage)
-	       && (CONSP (value)
-		   || (VECTORP (value) && ASIZE (value) == 4)))
-	/* Value is either a single image specification or a vector
-	   of 4 such specifications for the different button states.  */
-	set_prop (TOOL_BAR_ITEM_IMAGES, value);
-      else if (EQ (ikey, QCrtl))
-        /* ':rtl STRING' */
-	set_prop (TOOL_BAR_ITEM_RTL_IMAGE, value);
-    }
-
-
-  if (!have_label)
-    {
-      /* Try to make one from caption and key.  */
-      Lisp_Object tkey = PROP (TOOL_BAR_ITEM_KEY);
-      Lisp_Object tcapt = PROP (TOOL_BAR_ITEM_CAPTION);
-      const char *label = SYMBOLP (tkey) ? SSDATA (SYMBOL_NAME (tkey)) : "";
-      const char *capt = STRINGP (tcapt) ? SSDATA (tcapt) : "";
-      ptrdiff_t max_lbl =
-	2 * max (0, min (tool_bar_max_label_size, STRING_BYTES_BOUND / 2));
-      char *buf = xmalloc (max_lbl + 1);
-      Lisp_Object new_lbl;
-      ptrdiff_t caption_len = strlen (capt);
-
-      if (caption_len <= max_lbl && capt[0] != '\0')
-        {
-          strcpy (buf, capt);
-          while (caption_len > 0 && buf[caption_len - 1] == '.')
-            caption_len--;
-	  buf[caption_len] = '\0';
-	  label = capt = buf;
-        }
-
-      if (strlen (label) <= max_lbl && label[0] != '\0')
-        {
-          ptrdiff_t j;
-          if (label != buf)
-	    strcpy (buf, label);
-
-          for (j = 0; buf[j] != '\0'; ++j)
-	    if (buf[j] == '-')
-	      buf[j] = ' ';
-          label = buf;
-        }
-      else
-	label = "";
-
-      new_lbl = Fupcase_initials (build_string (label));
-      if (SCHARS (new_lbl) <= tool_bar_max_label_size)
-        set_prop (TOOL_BAR_ITEM_LABEL, new_lbl);
-      else
-        set_prop (TOOL_BAR_ITEM_LABEL, empty_unibyte_string);
-      xfree (buf);
-    }
-
-  /* If got a filter apply it on binding.  */
-  if (!NILP (filter))
-    set_prop (TOOL_BAR_ITEM_BINDING,
-	      (menu_item_eval_property
-	       (list2 (filter,
-		       list2 (Qquote,
-			      PROP (TOOL_BAR_ITEM_BINDING))))));
-
-  /* See if the binding is a keymap.  Give up if it is.  */
-  if (CONSP (get_keymap (PROP (TOOL_BAR_ITEM_BINDING), 0, 1)))
-    return 0;
-
-  /* Enable or disable selection of item.  */
-  if (!EQ (PROP (TOOL_BAR_ITEM_ENABLED_P), Qt))
-    set_prop (TOOL_BAR_ITEM_ENABLED_P,
-	      menu_item_eval_property (PROP (TOOL_BAR_ITEM_ENABLED_P)));
-
-  /* Handle radio buttons or toggle boxes.  */
-  if (!NILP (PROP (TOOL_BAR_ITEM_SELECTED_P)))
-    set_prop (TOOL_BAR_ITEM_SELECTED_P,
-	      menu_item_eval_property (PROP (TOOL_BAR_ITEM_SELECTED_P)));
-
-  return 1;
-
-#undef PROP
-}
-
-
-/* Initialize tool_bar_items_vector.  REUSE, if non-nil, is a vector
-   that can be reused.  */
-
-static void
-init_tool_bar_items (Lisp_Object reuse)
-{
-  if (VECTORP (reuse))
-    tool_bar_items_vector = reuse;
-  else
-    tool_bar_items_vector = Fmake_vector (make_number (64), Qnil);
-  ntool_bar_items = 0;
-}
-
-
-/* Append parsed tool bar item properties from
-   tool_bar_item_properties */
-
-static void
-append_tool_bar_item (void)
-{
-  ptrdiff_t incr
-    = (ntool_bar_items
-       - (ASIZE (tool_bar_items_vector) - TOOL_BAR_ITEM_NSLOTS));
-
-  /* Enlarge tool_bar_items_vector if necessary.  */
-  if (incr > 0)
-    tool_bar_items_vector = larger_vector (tool_bar_items_vector, incr, -1);
-
-  /* Append entries from tool_bar_item_properties to the end of
-     tool_bar_items_vector.  */
-  vcopy (tool_bar_items_vector, ntool_bar_items,
-	 XVECTOR (tool_bar_item_properties)->contents, TOOL_BAR_ITEM_NSLOTS);
-  ntool_bar_items += TOOL_BAR_ITEM_NSLOTS;
-}
-
-
-
-
 
 /* Read a character using menus based on the keymap MAP.
    Return nil if there are no menus in the maps.
@@ -9880,6 +9440,25 @@ represented as events of the form (nil . COMMAND).  */)
     }
 }
 
+/* Return the number of keys recorded in `recent-keys'.  */
+
+int
+recent_keys_count (void)
+{
+  return min (total_keys, NUM_RECENT_KEYS);
+}
+
+/* Return the Ith oldest of the keys recorded in `recent-keys'.  I must
+   be less than recent_keys_count ().  This does not allocate, so the
+   crash handler can use it.  */
+
+Lisp_Object
+recent_key (int i)
+{
+  int start = total_keys < NUM_RECENT_KEYS ? 0 : recent_keys_index;
+  return AREF (recent_keys, (start + i) % NUM_RECENT_KEYS);
+}
+
 DEFUN ("this-command-keys", Fthis_command_keys, Sthis_command_keys, 0, 0, 0,
        doc: /* Return the key sequence that invoked this command.
 However, if the command has called `read-key-sequence', it returns
@@ -10056,59 +9635,6 @@ Also end any kbd macro being defined.  */)
   return Qnil;
 }
 
-DEFUN ("suspend-emacs", Fsuspend_emacs, Ssuspend_emacs, 0, 1, "",
-       doc: /* Stop Emacs and return to superior process.  You can resume later.
-If `cannot-suspend' is non-nil, or if the system doesn't support job
-control, run a subshell instead.
-
-If optional arg STUFFSTRING is non-nil, its characters are stuffed
-to be read as terminal input by Emacs's parent, after suspension.
-
-Before suspending, run the normal hook `suspend-hook'.
-After resumption run the normal hook `suspend-resume-hook'.
-
-Some operating systems cannot stop the Emacs process and resume it later.
-On such systems, Emacs starts a subshell instead of suspending.  */)
-  (Lisp_Object stuffstring)
-{
-  ptrdiff_t count = SPECPDL_INDEX ();
-  int old_height, old_width;
-  int width, height;
-
-  if (tty_list && tty_list->next)
-    error ("There are other tty frames open; close them before suspending Emacs");
-
-  if (!NILP (stuffstring))
-    CHECK_STRING (stuffstring);
-
-  run_hook (intern ("suspend-hook"));
-
-  get_tty_size (fileno (CURTTY ()->input), &old_width, &old_height);
-  reset_all_sys_modes ();
-  /* sys_suspend can get an error if it tries to fork a subshell
-     and the system resources aren't available for that.  */
-  record_unwind_protect_void (init_all_sys_modes);
-  stuff_buffered_input (stuffstring);
-  if (cannot_suspend)
-    sys_subshell ();
-  else
-    sys_suspend ();
-  unbind_to (count, Qnil);
-
-  /* Check if terminal/window size has changed.
-     Note that this is not useful when we are running directly
-     with a window system; but suspend should be disabled in that case.  */
-  get_tty_size (fileno (CURTTY ()->input), &width, &height);
-  if (width != old_width || height != old_height)
-    change_frame_size (SELECTED_FRAME (), width,
-		       height - FRAME_MENU_BAR_LINES (SELECTED_FRAME ()),
-		       0, 0, 0, 0);
-
-  run_hook (intern ("suspend-resume-hook"));
-
-  return Qnil;
-}
-
 /* If STUFFSTRING is a string, stuff its contents as pending terminal input.
    Then in any case stuff anything Emacs has read ahead and not used.  */
 
@@ -10817,11 +10343,6 @@ syms_of_keyboard (void)
   staticpro (&item_properties);
   item_properties = Qnil;
 
-  staticpro (&tool_bar_item_properties);
-  tool_bar_item_properties = Qnil;
-  staticpro (&tool_bar_items_vector);
-  tool_bar_items_vector = Qnil;
-
   DEFSYM (Qtimer_event_handler, "timer-event-handler");
 
   /* Non-nil disable property on a command means do not execute it;
@@ -10848,6 +10369,8 @@ syms_of_keyboard (void)
   DEFSYM (Qdrag_n_drop, "drag-n-drop");
   DEFSYM (Qsave_session, "save-session");
   DEFSYM (Qconfig_changed_event, "config-changed-event");
+  DEFSYM (Qxterm_paste, "xterm-paste");
+  DEFSYM (Qmonitors_changed, "monitors-changed");
 
   /* Menu and tool bar item parts.  */
   DEFSYM (Qmenu_enable, "menu-enable");
@@ -11059,7 +10582,6 @@ syms_of_keyboard (void)
   defsubr (&Sthis_single_command_raw_keys);
   defsubr (&Sset__this_command_keys);
   defsubr (&Sclear_this_command_keys);
-  defsubr (&Ssuspend_emacs);
   defsubr (&Srecursion_depth);
   defsubr (&Scommand_error_default_function);
   defsubr (&Stop_level);
@@ -11676,6 +11198,8 @@ keys_of_keyboard (void)
 
   initial_define_lispy_key (Vspecial_event_map, "config-changed-event",
 			    "ignore");
+  initial_define_lispy_key (Vspecial_event_map, "monitors-changed",
+			    "handle-monitors-changed-event");
 #if defined (WINDOWSNT)
   initial_define_lispy_key (Vspecial_event_map, "language-change",
 			    "ignore");
@@ -266,6 +266,8 @@ extern int poll_suppress_count;
    actually mean something.  */
 extern Lisp_Object this_command_keys;
 extern ptrdiff_t this_command_key_count;
+extern int recent_keys_count (void);
+extern Lisp_Object recent_key (int);
 
 /* The frame in which the last input event occurred, or Qmacro if the
    last event came from a macro.  We use this to determine when to
@@ -462,6 +464,9 @@ extern void clear_waiting_for_input (void);
 extern void swallow_events (bool);
 extern bool lucid_event_type_list_p (Lisp_Object);
 extern void kbd_buffer_store_event (struct input_event *);
+extern void tty_store_event (struct tty_display_info *, enum event_kind,
+			     unsigned, unsigned, Lisp_Object);
+extern void store_monitors_changed_event (struct terminal *);
 extern void kbd_buffer_store_buffered_event (union buffered_input_event *,
 					     struct input_event *);
 INLINE void
@@ -483,10 +488,15 @@ extern void gen_help_event (Lisp_Object, Lisp_Object, Lisp_Object,
                             Lisp_Object, ptrdiff_t);
 extern void kbd_buffer_store_help_event (Lisp_Object, Lisp_Object);
 extern Lisp_Object menu_item_eval_property (Lisp_Object);
+extern Lisp_Object apply_modifiers (int, Lisp_Object);
 extern bool kbd_buffer_events_waiting (void);
 extern void add_user_signal (int, const char *);
 
 extern int tty_read_avail_input (struct terminal *, struct input_event *);
+
+/* Defined in rust tty_input.rs.  */
+extern void tty_decode_input (struct terminal *, const unsigned char *, int);
+extern void tty_forget_input (struct terminal *);
 extern bool volatile pending_signals;
 extern void process_pending_signals (void);
 extern struct timespec timer_check (void);
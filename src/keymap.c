@@ -77,8 +77,6 @@ Lisp_Object control_x_map;	/* The keymap used for globally bound
 /* Alist of elements like (DEL . "\d").  */
 static Lisp_Object exclude_keys;
 
-/* Pre-allocated 2-element vector for Fcommand_remapping to use.  */
-static Lisp_Object command_remapping_vector;
 
 static Lisp_Object store_in_keymap (Lisp_Object, Lisp_Object, Lisp_Object);
 
@@ -780,37 +778,6 @@ binding KEY to DEF is added at the front of KEYMAP.  */)
     }
 }
 
-/* This function may GC (it calls Fkey_binding).  */
-
-DEFUN ("command-remapping", Fcommand_remapping, Scommand_remapping, 1, 3, 0,
-       doc: /* Return the remapping for command COMMAND.
-Returns nil if COMMAND is not remapped (or not a symbol).
-
-If the optional argument POSITION is non-nil, it specifies a mouse
-position as returned by `event-start' and `event-end', and the
-remapping occurs in the keymaps associated with it.  It can also be a
-number or marker, in which case the keymap properties at the specified
-buffer position instead of point are used.  The KEYMAPS argument is
-ignored if POSITION is non-nil.
-
-If the optional argument KEYMAPS is non-nil, it should be a list of
-keymaps to search for command remapping.  Otherwise, search for the
-remapping in all currently active keymaps.  */)
-  (Lisp_Object command, Lisp_Object position, Lisp_Object keymaps)
-{
-  if (!SYMBOLP (command))
-    return Qnil;
-
-  ASET (command_remapping_vector, 1, command);
-
-  if (NILP (keymaps))
-    command = Fkey_binding (command_remapping_vector, Qnil, Qt, position);
-  else
-    command = Flookup_key (Fcons (Qkeymap, keymaps),
-			   command_remapping_vector, Qnil);
-  return INTEGERP (command) ? Qnil : command;
-}
-
 /* Make KEYMAP define event C as a keymap (i.e., as a prefix).
    Assume that currently it does not define C at all.
    Return the keymap.  */
@@ -1014,150 +981,6 @@ current_minor_maps (Lisp_Object **modeptr, Lisp_Object **mapptr)
   return i;
 }
 
-/* Return the offset of POSITION, a click position, in the style of
-   the respective argument of Fkey_binding.  */
-static ptrdiff_t
-click_position (Lisp_Object position)
-{
-  EMACS_INT pos = (INTEGERP (position) ? XINT (position)
-		   : MARKERP (position) ? marker_position (position)
-		   : PT);
-  if (! (BEGV <= pos && pos <= ZV))
-    args_out_of_range (Fcurrent_buffer (), position);
-  return pos;
-}
-
-DEFUN ("current-active-maps", Fcurrent_active_maps, Scurrent_active_maps,
-       0, 2, 0,
-       doc: /* Return a list of the currently active keymaps.
-OLP if non-nil indicates that we should obey `overriding-local-map' and
-`overriding-terminal-local-map'.  POSITION can specify a click position
-like in the respective argument of `key-binding'.  */)
-  (Lisp_Object olp, Lisp_Object position)
-{
-  ptrdiff_t count = SPECPDL_INDEX ();
-
-  Lisp_Object keymaps = list1 (current_global_map);
-
-  /* If a mouse click position is given, our variables are based on
-     the buffer clicked on, not the current buffer.  So we may have to
-     switch the buffer here.  */
-
-  if (CONSP (position))
-    {
-      Lisp_Object window;
-
-      window = POSN_WINDOW (position);
-
-      if (WINDOWP (window)
-	  && BUFFERP (XWINDOW (window)->contents)
-	  && XBUFFER (XWINDOW (window)->contents) != current_buffer)
-	{
-	  /* Arrange to go back to the original buffer once we're done
-	     processing the key sequence.  We don't use
-	     save_excursion_{save,restore} here, in analogy to
-	     `read-key-sequence' to avoid saving point.  Maybe this
-	     would not be a problem here, but it is easier to keep
-	     things the same.
-	  */
-	  record_unwind_current_buffer ();
-	  set_buffer_internal (XBUFFER (XWINDOW (window)->contents));
-	}
-    }
-
-  if (!NILP (olp)
-      /* The doc said that overriding-terminal-local-map should
-	 override overriding-local-map.  The code used them both,
-	 but it seems clearer to use just one.  rms, jan 2005.  */
-      && NILP (KVAR (current_kboard, Voverriding_terminal_local_map))
-      && !NILP (Voverriding_local_map))
-    keymaps = Fcons (Voverriding_local_map, keymaps);
-
-  if (NILP (XCDR (keymaps)))
-    {
-      Lisp_Object *maps;
-      int nmaps, i;
-      ptrdiff_t pt = click_position (position);
-      /* This usually returns the buffer's local map,
-	 but that can be overridden by a `local-map' property.  */
-      Lisp_Object local_map = get_local_map (pt, current_buffer, Qlocal_map);
-      /* This returns nil unless there is a `keymap' property.  */
-      Lisp_Object keymap = get_local_map (pt, current_buffer, Qkeymap);
-      Lisp_Object otlp = KVAR (current_kboard, Voverriding_terminal_local_map);
-
-      if (CONSP (position))
-	{
-	  Lisp_Object string = POSN_STRING (position);
-
-	  /* For a mouse click, get the local text-property keymap
-	     of the place clicked on, rather than point.  */
-
-	  if (POSN_INBUFFER_P (position))
-	    {
-	      Lisp_Object pos;
-
-	      pos = POSN_BUFFER_POSN (position);
-	      if (INTEGERP (pos)
-		  && XINT (pos) >= BEG && XINT (pos) <= Z)
-		{
-		  local_map = get_local_map (XINT (pos),
-					     current_buffer, Qlocal_map);
-
-		  keymap = get_local_map (XINT (pos),
-					  current_buffer, Qkeymap);
-		}
-	    }
-
-	  /* If on a mode line string with a local keymap,
-	     or for a click on a string, i.e. overlay string or a
-	     string displayed via the `display' property,
-	     consider `local-map' and `keymap' properties of
-	     that string.  */
-
-	  if (CONSP (string) && STRINGP (XCAR (string)))
-	    {
-	      Lisp_Object pos, map;
-
-	      pos = XCDR (string);
-	      string = XCAR (string);
-	      if (INTEGERP (pos)
-		  && XINT (pos) >= 0
-		  && XINT (pos) < SCHARS (string))
-		{
-		  map = Fget_text_property (pos, Qlocal_map, string);
-		  if (!NILP (map))
-		    local_map = map;
-
-		  map = Fget_text_property (pos, Qkeymap, string);
-		  if (!NILP (map))
-		    keymap = map;
-		}
-	    }
-
-	}
-
-      if (!NILP (local_map))
-	keymaps = Fcons (local_map, keymaps);
-
-      /* Now put all the minor mode keymaps on the list.  */
-      nmaps = current_minor_maps (0, &maps);
-
-      for (i = --nmaps; i >= 0; i--)
-	if (!NILP (maps[i]))
-	  keymaps = Fcons (maps[i], keymaps);
-
-      if (!NILP (keymap))
-	keymaps = Fcons (keymap, keymaps);
-
-      if (!NILP (olp) && !NILP (otlp))
-	keymaps = Fcons (otlp, keymaps);
-    }
-
-  unbind_to (count, Qnil);
-
-  return keymaps;
-}
-
 /* GC is possible in this function if it autoloads a keymap.  */
 
 DEFUN ("key-binding", Fkey_binding, Skey_binding, 1, 4, 0,
@@ -3146,15 +2969,10 @@ be preferred.  */);
   DEFSYM (Qremap, "remap");
   DEFSYM (QCadvertised_binding, ":advertised-binding");
 
-  command_remapping_vector = Fmake_vector (make_number (2), Qremap);
-  staticpro (&command_remapping_vector);
-
-  defsubr (&Scommand_remapping);
   defsubr (&Skey_binding);
   defsubr (&Sminor_mode_key_binding);
   defsubr (&Sdefine_key);
   defsubr (&Scurrent_minor_mode_maps);
-  defsubr (&Scurrent_active_maps);
   defsubr (&Saccessible_keymaps);
   defsubr (&Skey_description);
   defsubr (&Ssingle_key_description);
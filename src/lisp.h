@@ -358,9 +358,7 @@ typedef EMACS_INT Lisp_Word;
 #define lisp_h_XCONS(a) \
    (eassert (CONSP (a)), (struct Lisp_Cons *) XUNTAG (a, Lisp_Cons))
 #define lisp_h_XHASH(a) XUINT (a)
-#ifndef GC_CHECK_CONS_LIST
-# define lisp_h_check_cons_list() ((void) 0)
-#endif
+#define lisp_h_check_cons_list() ((void) 0)
 #if USE_LSB_TAG
 # define lisp_h_make_number(n) \
     XIL ((EMACS_INT) (((EMACS_UINT) (n) << INTTYPEBITS) + Lisp_Int0))
@@ -419,9 +417,7 @@ typedef EMACS_INT Lisp_Word;
 # define XCDR(c) lisp_h_XCDR (c)
 # define XCONS(a) lisp_h_XCONS (a)
 # define XHASH(a) lisp_h_XHASH (a)
-# ifndef GC_CHECK_CONS_LIST
-#  define check_cons_list() lisp_h_check_cons_list ()
-# endif
+# define check_cons_list() lisp_h_check_cons_list ()
 # if USE_LSB_TAG
 #  define make_number(n) lisp_h_make_number (n)
 #  define XFASTINT(a) lisp_h_XFASTINT (a)
@@ -1389,9 +1385,9 @@ STRING_MULTIBYTE (Lisp_Object str)
    string size calculations.  A string cannot contain more bytes than
    a fixnum can represent, nor can it be so long that C pointer
    arithmetic stops working on the string plus its terminating null.
-   Although the actual size limit (see STRING_BYTES_MAX in alloc.c)
+   Although the actual size limit (see string_bytes_max in string_alloc.rs)
    may be a bit smaller than STRING_BYTES_BOUND, calculating it here
-   would expose alloc.c internal details that we'd rather keep
+   would expose string_alloc.rs internal details that we'd rather keep
    private.
 
    This is a macro for use in static initializers.  The cast to
@@ -1449,17 +1445,10 @@ SCHARS (Lisp_Object string)
   return nchars;
 }
 
-#ifdef GC_CHECK_STRING_BYTES
-extern ptrdiff_t string_bytes (struct Lisp_String *);
-#endif
 INLINE ptrdiff_t
 STRING_BYTES (struct Lisp_String *s)
 {
-#ifdef GC_CHECK_STRING_BYTES
-  ptrdiff_t nbytes = string_bytes (s);
-#else
   ptrdiff_t nbytes = s->u.s.size_byte < 0 ? s->u.s.size : s->u.s.size_byte;
-#endif
   eassume (0 <= nbytes);
   return nbytes;
 }
@@ -1764,8 +1753,8 @@ memclear (void *p, ptrdiff_t nbytes)
    char-table, we treat it a table that contains information of a
    specific range of characters.  A sub char-table is like a vector but
    with two integer fields between the header and Lisp data, which means
-   that it has to be marked with some precautions (see mark_char_table
-   in alloc.c).  A sub char-table appears only in an element of a char-table,
+   that it has to be marked with some precautions (see mark_object
+   in rust gc.rs).  A sub char-table appears only in an element of a char-table,
    and there's no way to access it directly from Emacs Lisp program.  */
 
 enum CHARTAB_SIZE_BITS
@@ -1926,6 +1915,9 @@ struct Lisp_Subr
     const char *intspec;
     EMACS_INT doc;
     enum Lisp_Subr_Lang lang;
+    /* The space-separated argument names of a Rust subr, or NULL if
+       they are not known.  */
+    const char *arglist;
   };
 
 INLINE bool
@@ -2225,8 +2217,6 @@ HASH_TABLE_SIZE (struct Lisp_Hash_Table *h)
   return ASIZE (h->next);
 }
 
-void hash_clear (struct Lisp_Hash_Table *h);
-
 /* Default size for hash tables if not specified.  */
 
 enum DEFAULT_HASH_SIZE { DEFAULT_HASH_SIZE = 65 };
@@ -3045,7 +3035,7 @@ CHECK_NUMBER_CDR (Lisp_Object x)
    static struct Lisp_Subr sname =				\
      { { PVEC_SUBR << PSEUDOVECTOR_AREA_BITS },				\
        { .a ## maxargs = fnname },					\
-       minargs, maxargs, lname, intspec, 0, Lisp_Subr_Lang_C };		\
+       minargs, maxargs, lname, intspec, 0, Lisp_Subr_Lang_C, 0 };		\
    Lisp_Object fnname
 
 /* defsubr (Sname);
@@ -3318,6 +3308,18 @@ set_symbol_declared_special(struct Lisp_Symbol *sym, bool value)
   sym->u.s.declared_special = value;
 }
 
+INLINE bool
+get_symbol_gcmarkbit(const struct Lisp_Symbol *sym)
+{
+  return sym->u.s.gcmarkbit;
+}
+
+INLINE void
+set_symbol_gcmarkbit(struct Lisp_Symbol *sym, bool value)
+{
+  sym->u.s.gcmarkbit = value;
+}
+
 INLINE void
 set_symbol_function (Lisp_Object sym, Lisp_Object function)
 {
@@ -3450,6 +3452,9 @@ enum Arith_Comparison {
   ARITH_GRTR_OR_EQUAL
 };
 
+/* Defined in rust numbers.rs.  */
+extern Lisp_Object string_to_number (char const *, int, bool);
+
 /* Defined in rust  */
 Lisp_Object arithcompare (Lisp_Object num1, Lisp_Object num2,
                                  enum Arith_Comparison comparison);
@@ -3500,6 +3505,13 @@ extern void swap_in_symval_forwarding (struct Lisp_Symbol *symbol,
 extern void
 aset_multibyte_string(register Lisp_Object array, EMACS_INT idxval, int c);
 
+/* Defined in rust cli.rs.  */
+extern void sort_args (int, char **);
+extern bool argmatch (char **, int, const char *, char **, int *);
+extern void print_usage (const char *, const char *);
+extern void init_cli (void);
+extern void syms_of_cli (void);
+
 /* Defined in cmds.c */
 extern void syms_of_cmds (void);
 extern void keys_of_cmds (void);
@@ -3533,26 +3545,13 @@ extern void syms_of_syntax (void);
 enum { NEXT_ALMOST_PRIME_LIMIT = 11 };
 extern EMACS_INT next_almost_prime (EMACS_INT) ATTRIBUTE_CONST;
 extern Lisp_Object larger_vector (Lisp_Object, ptrdiff_t, ptrdiff_t);
-extern void sweep_weak_hash_tables (void);
 extern char *extract_data_from_object (Lisp_Object, ptrdiff_t *, ptrdiff_t *);
-EMACS_UINT hash_string (char const *, ptrdiff_t);
-EMACS_UINT sxhash (Lisp_Object, int);
-Lisp_Object make_hash_table (struct hash_table_test, EMACS_INT, float, float,
-			     Lisp_Object, bool);
-ptrdiff_t hash_lookup (struct Lisp_Hash_Table *, Lisp_Object, EMACS_UINT *);
-ptrdiff_t hash_put (struct Lisp_Hash_Table *, Lisp_Object, Lisp_Object,
-		    EMACS_UINT);
-void hash_remove_from_table (struct Lisp_Hash_Table *, Lisp_Object);
-extern struct hash_table_test const hashtest_eq, hashtest_eql, hashtest_equal;
 extern void validate_subarray (Lisp_Object, Lisp_Object, Lisp_Object,
 			       ptrdiff_t, ptrdiff_t *, ptrdiff_t *);
 extern Lisp_Object substring_both (Lisp_Object, ptrdiff_t, ptrdiff_t,
 				   ptrdiff_t, ptrdiff_t);
 extern Lisp_Object merge (Lisp_Object, Lisp_Object, Lisp_Object);
 extern Lisp_Object do_yes_or_no_p (Lisp_Object);
-extern Lisp_Object concat2 (Lisp_Object, Lisp_Object);
-extern Lisp_Object concat3 (Lisp_Object, Lisp_Object, Lisp_Object);
-extern bool equal_no_quit (Lisp_Object, Lisp_Object);
 extern Lisp_Object nconc2 (Lisp_Object, Lisp_Object);
 extern Lisp_Object assq_no_quit (Lisp_Object, Lisp_Object);
 extern Lisp_Object assoc_no_quit (Lisp_Object, Lisp_Object);
@@ -3618,6 +3617,7 @@ extern void modify_text (ptrdiff_t, ptrdiff_t);
 extern void prepare_to_modify_buffer (ptrdiff_t, ptrdiff_t, ptrdiff_t *);
 extern void prepare_to_modify_buffer_1 (ptrdiff_t, ptrdiff_t, ptrdiff_t *);
 extern void invalidate_buffer_caches (struct buffer *, ptrdiff_t, ptrdiff_t);
+extern void signal_before_change (ptrdiff_t, ptrdiff_t, ptrdiff_t *);
 extern void signal_after_change (ptrdiff_t, ptrdiff_t, ptrdiff_t);
 extern void adjust_after_insert (ptrdiff_t, ptrdiff_t, ptrdiff_t,
 				 ptrdiff_t, ptrdiff_t);
@@ -3687,12 +3687,24 @@ extern void parse_str_as_multibyte (const unsigned char *, ptrdiff_t,
 extern void *my_heap_start (void);
 extern void check_pure_size (void);
 extern void free_misc (Lisp_Object);
-extern void allocate_string_data (struct Lisp_String *, EMACS_INT, EMACS_INT);
+extern void *lisp_malloc_strings (size_t);
+extern void *lisp_malloc_string_data (size_t, bool);
+extern void lisp_free (void *);
+extern void *lisp_align_malloc_conses (size_t);
+extern void *lisp_align_malloc_floats (size_t);
+extern void lisp_align_free (void *);
+extern Lisp_Object Vdead;
 extern void malloc_warning (const char *);
 extern _Noreturn void memory_full (size_t);
 extern _Noreturn void buffer_memory_full (ptrdiff_t);
 extern bool survives_gc_p (Lisp_Object);
-extern void mark_object (Lisp_Object);
+extern bool pure_object_p (Lisp_Object);
+extern void mark_interval_tree (INTERVAL);
+extern void mark_buffer (struct buffer *);
+extern void mark_frame (struct frame *);
+extern void mark_window (struct window *);
+extern void mark_localized_symbol (struct Lisp_Symbol *);
+extern void mark_misc (Lisp_Object);
 #if defined REL_ALLOC && !defined SYSTEM_MALLOC && !defined HYBRID_MALLOC
 extern void refill_memory_reserve (void);
 #endif
@@ -3702,9 +3714,12 @@ extern void mark_stack (char *, char *);
 extern void flush_stack_call_func (void (*func) (void *arg), void *arg);
 extern const char *pending_malloc_warning;
 extern Lisp_Object zero_vector;
-extern EMACS_INT consing_since_gc;
-extern EMACS_INT gc_relative_threshold;
-extern EMACS_INT memory_full_cons_threshold;
+extern EMACS_INT total_markers, total_free_markers;
+extern EMACS_INT total_symbols, total_free_symbols;
+extern EMACS_INT total_vectors, total_vector_slots, total_free_vector_slots;
+extern EMACS_INT total_intervals, total_free_intervals;
+extern EMACS_INT total_buffers;
+extern Lisp_Object malloc_heap_statistics (void);
 extern Lisp_Object list1 (Lisp_Object);
 extern Lisp_Object list2 (Lisp_Object, Lisp_Object);
 extern Lisp_Object list3 (Lisp_Object, Lisp_Object, Lisp_Object);
@@ -3755,8 +3770,6 @@ build_unibyte_string (const char *str)
 
 extern Lisp_Object make_multibyte_string (const char *, ptrdiff_t, ptrdiff_t);
 extern Lisp_Object make_event_array (ptrdiff_t, Lisp_Object *);
-extern Lisp_Object make_uninit_string (EMACS_INT);
-extern Lisp_Object make_uninit_multibyte_string (EMACS_INT, EMACS_INT);
 extern Lisp_Object make_string_from_bytes (const char *, ptrdiff_t, ptrdiff_t);
 extern Lisp_Object make_specified_string (const char *,
 					  ptrdiff_t, ptrdiff_t, bool);
@@ -3838,7 +3851,6 @@ extern struct Lisp_Vector *allocate_pseudovector (int, int, int,
 				   VECSIZE (type), tag))
 
 extern bool gc_in_progress;
-extern Lisp_Object make_float (double);
 extern void display_malloc_warning (void);
 extern ptrdiff_t inhibit_garbage_collection (void);
 extern Lisp_Object make_save_int_int_int (ptrdiff_t, ptrdiff_t, ptrdiff_t);
@@ -3853,17 +3865,12 @@ extern Lisp_Object make_save_memory (Lisp_Object *, ptrdiff_t);
 extern void free_save_value (Lisp_Object);
 extern Lisp_Object build_overlay (Lisp_Object, Lisp_Object, Lisp_Object);
 extern void free_marker (Lisp_Object);
-extern void free_cons (struct Lisp_Cons *);
 extern void init_alloc_once (void);
 extern void init_alloc (void);
 extern void syms_of_alloc (void);
 extern struct buffer * allocate_buffer (void);
 extern int valid_lisp_object_p (Lisp_Object);
-#ifdef GC_CHECK_CONS_LIST
-extern void check_cons_list (void);
-#else
 INLINE void (check_cons_list) (void) { lisp_h_check_cons_list (); }
-#endif
 
 /* Defined in gmalloc.c.  */
 #if !defined DOUG_LEA_MALLOC && !defined HYBRID_MALLOC && !defined SYSTEM_MALLOC
@@ -3933,7 +3940,17 @@ extern Lisp_Object intern_driver (Lisp_Object, Lisp_Object, Lisp_Object);
 extern Lisp_Object intern_sym (Lisp_Object sym, Lisp_Object obarray, Lisp_Object index);
 extern void init_symbol (Lisp_Object, Lisp_Object);
 extern Lisp_Object oblookup (Lisp_Object, const char *, ptrdiff_t, ptrdiff_t);
-extern Lisp_Object read_internal_start (Lisp_Object, Lisp_Object, Lisp_Object);
+extern Lisp_Object read_objects_map;
+extern Lisp_Object read_objects_completed;
+extern ptrdiff_t read_from_string_index;
+extern ptrdiff_t read_from_string_index_byte;
+extern ptrdiff_t read_from_string_limit;
+extern EMACS_INT readchar_count;
+extern int readchar (Lisp_Object, bool *);
+extern void unreadchar (Lisp_Object, int);
+extern void skip_dyn_eof (Lisp_Object);
+extern void skip_dyn_doc_string (Lisp_Object, ptrdiff_t);
+extern Lisp_Object get_lazy_doc_string (Lisp_Object);
 extern void loadhist_attach(Lisp_Object x);
 INLINE void
 LOADHIST_ATTACH (Lisp_Object x)
@@ -3942,14 +3959,18 @@ LOADHIST_ATTACH (Lisp_Object x)
 }
 extern int openp (Lisp_Object, Lisp_Object, Lisp_Object,
                   Lisp_Object *, Lisp_Object, bool);
-extern Lisp_Object string_to_number (char const *, int, bool);
 extern void map_obarray (Lisp_Object, void (*) (Lisp_Object, Lisp_Object),
                          Lisp_Object);
+extern Lisp_Object obarray_prefix_matches (Lisp_Object, Lisp_Object);
 extern void dir_warning (const char *, Lisp_Object);
 extern void init_obarray (void);
 extern void init_lread (void);
 extern void syms_of_lread (void);
 
+/* Defined in rust reader.rs.  */
+extern Lisp_Object read_internal_start (Lisp_Object, Lisp_Object, Lisp_Object);
+extern Lisp_Object read_toplevel_list (Lisp_Object);
+
 INLINE Lisp_Object
 intern (const char *str)
 {
@@ -3995,8 +4016,6 @@ extern _Noreturn void xsignal3 (Lisp_Object, Lisp_Object, Lisp_Object,
 				Lisp_Object);
 extern _Noreturn void signal_error (const char *, Lisp_Object);
 extern bool FUNCTIONP (Lisp_Object);
-extern Lisp_Object funcall_subr (struct Lisp_Subr *subr, ptrdiff_t numargs, Lisp_Object *arg_vector);
-extern Lisp_Object eval_sub (Lisp_Object form);
 extern Lisp_Object apply1 (Lisp_Object, Lisp_Object);
 extern Lisp_Object call0 (Lisp_Object);
 extern Lisp_Object call1 (Lisp_Object, Lisp_Object);
@@ -4232,16 +4251,24 @@ extern Lisp_Object last_minibuf_string;
 extern Lisp_Object get_minibuffer (EMACS_INT);
 extern void init_minibuf_once (void);
 extern void syms_of_minibuf (void);
+extern Lisp_Object string_to_object (Lisp_Object, Lisp_Object);
+
+/* Defined in rust batch.rs.  */
+
+extern void message_to_stderr (Lisp_Object);
+extern Lisp_Object read_minibuf_noninteractive (Lisp_Object, Lisp_Object,
+                                                Lisp_Object, Lisp_Object,
+                                                bool, Lisp_Object,
+                                                Lisp_Object, Lisp_Object,
+                                                bool, bool);
 
 /* Defined in callint.c.  */
 
 extern void syms_of_callint (void);
 
-/* Defined in casefiddle.c.  */
+/* Defined in rust casefiddle.rs.  */
 
 enum case_action {CASE_UP, CASE_DOWN, CASE_CAPITALIZE, CASE_CAPITALIZE_UP};
-Lisp_Object casify_object (enum case_action flag, Lisp_Object obj);
-ptrdiff_t casify_region (enum case_action flag, Lisp_Object b, Lisp_Object e);
 extern void syms_of_casefiddle (void);
 extern void keys_of_casefiddle (void);
 
@@ -4262,7 +4289,6 @@ extern bool input_pending;
 extern sigjmp_buf return_to_command_loop;
 #endif
 extern Lisp_Object menu_bar_items (Lisp_Object);
-extern Lisp_Object tool_bar_items (Lisp_Object, int *);
 extern void discard_mouse_events (void);
 #ifdef USABLE_SIGIO
 void handle_input_available_signal (int);
@@ -4291,11 +4317,15 @@ extern void syms_of_indent (void);
 /* Defined in frame.c.  */
 extern void store_frame_param (struct frame *, Lisp_Object, Lisp_Object);
 extern void store_in_alist (Lisp_Object *, Lisp_Object, Lisp_Object);
-extern Lisp_Object do_switch_frame (Lisp_Object, int, int, Lisp_Object);
 extern Lisp_Object get_frame_param (struct frame *, Lisp_Object);
 extern void frames_discard_buffer (Lisp_Object);
 extern void syms_of_frame (void);
 
+/* Defined in rust frames.rs.  */
+extern Lisp_Object do_switch_frame (Lisp_Object, int, int, Lisp_Object);
+extern void consider_frame_title (Lisp_Object);
+extern void update_frame_titles (bool);
+
 /* Defined in emacs.c.  */
 extern char **initial_argv;
 extern int initial_argc;
@@ -4318,7 +4348,11 @@ INLINE void synchronize_system_messages_locale (void) {}
 INLINE void synchronize_system_time_locale (void) {}
 #endif
 extern char *emacs_strerror (int);
-extern void shut_down_emacs (int, Lisp_Object);
+extern void kill_emacs_save_clipboards (void);
+extern bool stdin_at_eof (void);
+extern void kill_emacs_release_pool (void);
+extern void shut_down_terminal (int);
+extern void shut_down_platform (int);
 
 /* True means don't do interactive redisplay and don't change tty modes.  */
 extern bool noninteractive;
@@ -4374,6 +4408,121 @@ extern void init_process_emacs (int);
 extern void syms_of_process (void);
 extern void setup_process_coding_systems (Lisp_Object);
 
+/* Defined in rust emacs.rs.  */
+extern void shut_down_emacs (int, Lisp_Object);
+
+/* Defined in rust terminal.rs.  */
+extern void sys_suspend (void);
+extern void handle_suspend_signal (int);
+extern void process_pending_suspend (void);
+
+/* Defined in rust crash.rs.  */
+extern void write_crash_report (int, int);
+extern void syms_of_crash (void);
+
+/* Defined in rust snapshot.rs.  */
+extern void detach_buffer_snapshots (struct buffer *);
+
+/* Defined in rust parse_sexp.rs.  */
+extern void forget_parse_cache (struct buffer *);
+
+/* Defined in rust frame_parameters.rs.  */
+extern void x_set_frame_parameters (struct frame *, Lisp_Object);
+extern void handle_frame_parameter (struct frame *, Lisp_Object, Lisp_Object,
+				    Lisp_Object);
+extern void syms_of_frame_parameters (void);
+
+/* Defined in rust monitors.rs.  */
+extern void syms_of_monitors (void);
+
+/* Defined in rust tty_clipboard.rs.  */
+extern void syms_of_tty_clipboard (void);
+
+/* Defined in rust server.rs.  */
+extern void syms_of_server (void);
+
+/* Defined in rust tool_bar.rs.  */
+extern Lisp_Object tool_bar_items (Lisp_Object, int *);
+extern Lisp_Object make_lispy_tool_bar_event (Lisp_Object, Lisp_Object, int);
+
+/* Defined in rust transient_map.rs.  */
+extern void transient_maps_pre_command (void);
+
+/* Defined in rust finalizers.rs.  */
+extern void init_finalizers (void);
+extern void unchain_finalizer (struct Lisp_Finalizer *);
+extern void queue_doomed_finalizers (void);
+extern void run_finalizers (void);
+
+/* Defined in rust gc_stats.rs.  */
+extern EMACS_INT consing_since_gc;
+extern EMACS_INT gc_relative_threshold;
+extern EMACS_INT memory_full_cons_threshold;
+extern size_t total_bytes_of_live_objects (void);
+extern void update_gc_thresholds (void);
+extern Lisp_Object gc_statistics (void);
+
+/* Defined in rust gc.rs.  */
+extern void mark_object (Lisp_Object);
+extern void mark_rust_roots (void);
+
+/* Defined in rust block_alloc.rs.  */
+extern Lisp_Object make_float (double);
+extern void free_cons (struct Lisp_Cons *);
+extern bool cons_marked_p (Lisp_Object);
+extern bool float_marked_p (Lisp_Object);
+extern bool gc_mark_cons (Lisp_Object);
+extern void gc_mark_float (Lisp_Object);
+extern Lisp_Object live_cons_in_block (void *, void *);
+extern bool live_float_in_block (void *, void *);
+extern void sweep_conses (void);
+extern void sweep_floats (void);
+extern size_t const cons_block_bytes;
+extern EMACS_INT total_conses, total_free_conses;
+extern EMACS_INT total_floats, total_free_floats;
+
+/* Defined in rust string_alloc.rs.  */
+extern void allocate_string_data (struct Lisp_String *, EMACS_INT, EMACS_INT);
+extern Lisp_Object make_uninit_string (EMACS_INT);
+extern Lisp_Object make_uninit_multibyte_string (EMACS_INT, EMACS_INT);
+extern Lisp_Object live_string_in_block (void *, void *);
+extern void sweep_strings (void);
+extern size_t const string_block_bytes;
+extern EMACS_INT total_strings, total_free_strings, total_string_bytes;
+
+/* Defined in rust hashtable.rs.  */
+extern struct Lisp_Hash_Table *weak_hash_tables;
+extern void sweep_weak_hash_tables (void);
+extern EMACS_UINT hash_string (char const *, ptrdiff_t);
+extern EMACS_UINT sxhash (Lisp_Object, int);
+extern Lisp_Object make_hash_table (struct hash_table_test, EMACS_INT, float,
+				    float, Lisp_Object, bool);
+extern ptrdiff_t hash_lookup (struct Lisp_Hash_Table *, Lisp_Object,
+			     EMACS_UINT *);
+extern ptrdiff_t hash_put (struct Lisp_Hash_Table *, Lisp_Object, Lisp_Object,
+			  EMACS_UINT);
+extern void hash_remove_from_table (struct Lisp_Hash_Table *, Lisp_Object);
+extern struct hash_table_test const hashtest_eq, hashtest_eql, hashtest_equal;
+extern void hash_clear (struct Lisp_Hash_Table *);
+extern void syms_of_hashtable (void);
+
+/* Defined in rust windows.rs.  */
+extern void prune_window_buffer_lists (void);
+extern void syms_of_windows (void);
+
+/* Defined in rust word_wrap.rs.  */
+extern bool char_can_wrap_before (int, bool, bool);
+extern bool char_can_wrap_after (int, bool, bool);
+extern void syms_of_word_wrap (void);
+
+/* Defined in rust window_ring.rs.  */
+extern void window_configuration_ring_note_change (Lisp_Object);
+
+/* Defined in rust sequences.rs.  */
+extern Lisp_Object concat (ptrdiff_t, Lisp_Object *, enum Lisp_Type, bool);
+extern Lisp_Object concat2 (Lisp_Object, Lisp_Object);
+extern Lisp_Object concat3 (Lisp_Object, Lisp_Object, Lisp_Object);
+
 /* Defined in callproc.c.  */
 #ifndef DOS_NT
 # define CHILD_SETUP_TYPE _Noreturn void
@@ -4411,10 +4560,6 @@ extern Lisp_Object exec_byte_code (Lisp_Object, Lisp_Object, Lisp_Object,
 				   Lisp_Object, ptrdiff_t, Lisp_Object *);
 extern Lisp_Object get_byte_code_arity (Lisp_Object);
 
-/* Defined in macros.c.  */
-extern void init_macros (void);
-extern void syms_of_macros (void);
-
 /* Defined in undo.c.  */
 extern void truncate_undo_list (struct buffer *);
 extern void record_insert (ptrdiff_t, ptrdiff_t);
@@ -4450,7 +4595,6 @@ extern char *emacs_get_current_dir_name (void);
 extern void stuff_char (char c);
 extern void init_foreground_group (void);
 extern void sys_subshell (void);
-extern void sys_suspend (void);
 extern void discard_tty_input (void);
 extern void init_sys_modes (struct tty_display_info *);
 extern void reset_sys_modes (struct tty_display_info *);
@@ -4463,6 +4607,7 @@ extern EMACS_INT get_random (void);
 extern void seed_random (void *, ptrdiff_t);
 extern void init_random (void);
 extern void emacs_backtrace (int);
+extern void emacs_backtrace_to_fd (int, int);
 extern _Noreturn void emacs_abort (void) NO_INLINE;
 extern int emacs_open (const char *, int, int);
 extern int emacs_pipe (int[2]);
@@ -4657,9 +4802,6 @@ extern void init_system_name (void);
 #define make_fixnum_or_float(val) \
    (FIXNUM_OVERFLOW_P (val) ? make_float (val) : make_number (val))
 
-extern EMACS_INT
-mapcar1 (EMACS_INT leni, Lisp_Object *vals, Lisp_Object fn, Lisp_Object seq);
-
 /* SAFE_ALLOCA normally allocates memory on the stack, but if size is
    larger than MAX_ALLOCA, use xmalloc to avoid overflowing the stack.  */
 
@@ -4758,12 +4900,6 @@ extern void *record_xmalloc (size_t) ATTRIBUTE_ALLOC_SIZE ((1));
 # define USE_STACK_LISP_OBJECTS true
 #endif
 
-#ifdef GC_CHECK_STRING_BYTES
-enum { defined_GC_CHECK_STRING_BYTES = true };
-#else
-enum { defined_GC_CHECK_STRING_BYTES = false };
-#endif
-
 /* True for stack-based cons and string implementations, respectively.
    Use stack-based strings only if stack-based cons also works.
    Otherwise, STACK_CONS would create heap-based cons cells that
@@ -4772,8 +4908,7 @@ enum { defined_GC_CHECK_STRING_BYTES = false };
 enum
   {
     USE_STACK_CONS = USE_STACK_LISP_OBJECTS,
-    USE_STACK_STRING = (USE_STACK_CONS
-			&& !defined_GC_CHECK_STRING_BYTES)
+    USE_STACK_STRING = USE_STACK_CONS
   };
 
 /* Auxiliary macros used for auto allocation of Lisp objects.  Please
@@ -4886,7 +5021,8 @@ struct for_each_tail_internal
        (CONSP (list_var) && ((value_var) = XCDR (XCAR (list_var)), true)); \
        (list_var) = XCDR (list_var))
 
-/* Check whether it's time for GC, and run it if so.  */
+/* Check whether it's time for GC, and run it if so.  This must agree
+   with maybe_gc in gc_stats.rs.  */
 
 INLINE void
 maybe_gc (void)
@@ -4898,18 +5034,31 @@ maybe_gc (void)
     Fgarbage_collect ();
 }
 
-Lisp_Object funcall_lambda (Lisp_Object, ptrdiff_t, Lisp_Object *);
+/* Defined in rust eval.rs.  */
+extern Lisp_Object eval_sub (Lisp_Object form);
+extern Lisp_Object funcall_subr (struct Lisp_Subr *subr, ptrdiff_t numargs, Lisp_Object *arg_vector);
+extern Lisp_Object signal_or_quit (Lisp_Object, Lisp_Object, bool);
+extern _Noreturn void throw_to_catch (Lisp_Object, Lisp_Object);
 
 bool backtrace_debug_on_exit (union specbinding *pdl);
 
+void set_backtrace_args (union specbinding *pdl, Lisp_Object *args, ptrdiff_t nargs);
+
 void do_debug_on_call (Lisp_Object code, ptrdiff_t count);
 
+extern _Noreturn void unwind_to_catch (struct handler *, Lisp_Object);
+extern bool maybe_call_debugger (Lisp_Object, Lisp_Object, Lisp_Object);
+bool backtrace_p (union specbinding *);
+Lisp_Object backtrace_function (union specbinding *);
+union specbinding *backtrace_next (union specbinding *);
+union specbinding *backtrace_top (void);
+Lisp_Object specpdl_old_value (union specbinding *);
+void set_specpdl_old_value (union specbinding *, Lisp_Object);
+union specbinding *default_toplevel_binding (Lisp_Object);
+
 enum equal_kind { EQUAL_NO_QUIT, EQUAL_PLAIN, EQUAL_INCLUDING_PROPERTIES };
 extern bool internal_equal (Lisp_Object, Lisp_Object, enum equal_kind, int, Lisp_Object);
-extern bool internal_equal_cons (Lisp_Object, Lisp_Object, enum equal_kind, int, Lisp_Object);
-extern bool internal_equal_misc (Lisp_Object, Lisp_Object, enum equal_kind, int, Lisp_Object);
-extern bool internal_equal_string (Lisp_Object, Lisp_Object, enum equal_kind, int, Lisp_Object);
-extern bool internal_equal_vectorlike (Lisp_Object, Lisp_Object, enum equal_kind, int, Lisp_Object);
+extern bool equal_no_quit (Lisp_Object, Lisp_Object);
 
 INLINE_HEADER_END
 
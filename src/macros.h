@@ -34,6 +34,8 @@ extern EMACS_INT executing_kbd_macro_iterations;
 
 extern Lisp_Object executing_kbd_macro;
 
+/* The functions below are defined in rust keyboard.rs.  */
+
 /* Finish defining the current keyboard macro.  */
 
 extern void end_kbd_macro (void);
@@ -47,4 +49,9 @@ extern void finalize_kbd_macro_chars (void);
 
 extern void store_kbd_macro_char (Lisp_Object);
 
+/* Return the next event of the executing kbd macro and advance
+   executing-kbd-macro-index past it, or nil at the end of the macro.  */
+
+extern Lisp_Object next_kbd_macro_event (void);
+
 #endif /* EMACS_MACROS_H */
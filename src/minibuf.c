@@ -120,7 +120,7 @@ static void run_exit_minibuf_hook (void);
 /* Read a Lisp object from VAL and return it.  If VAL is an empty
    string, and DEFALT is a string, read from DEFALT instead of VAL.  */
 
-static Lisp_Object
+Lisp_Object
 string_to_object (Lisp_Object val, Lisp_Object defalt)
 {
   Lisp_Object expr_and_pos;
@@ -155,92 +155,6 @@ string_to_object (Lisp_Object val, Lisp_Object defalt)
 }
 
 
-/* Like read_minibuf but reading from stdin.  This function is called
-   from read_minibuf to do the job if noninteractive.  */
-
-static Lisp_Object
-read_minibuf_noninteractive (Lisp_Object map, Lisp_Object initial,
-			     Lisp_Object prompt, Lisp_Object backup_n,
-			     bool expflag,
-			     Lisp_Object histvar, Lisp_Object histpos,
-			     Lisp_Object defalt,
-			     bool allow_props, bool inherit_input_method)
-{
-  ptrdiff_t size, len;
-  char *line;
-  Lisp_Object val;
-  int c;
-  unsigned char hide_char = 0;
-  struct emacs_tty etty;
-  bool etty_valid UNINIT;
-
-  /* Check, whether we need to suppress echoing.  */
-  if (CHARACTERP (Vread_hide_char))
-    hide_char = XFASTINT (Vread_hide_char);
-
-  /* Manipulate tty.  */
-  if (hide_char)
-    {
-      etty_valid = emacs_get_tty (STDIN_FILENO, &etty) == 0;
-      if (etty_valid)
-	set_binary_mode (STDIN_FILENO, O_BINARY);
-      suppress_echo_on_tty (STDIN_FILENO);
-    }
-
-  fwrite_unlocked (SDATA (prompt), 1, SBYTES (prompt), stdout);
-  fflush_unlocked (stdout);
-
-  val = Qnil;
-  size = 100;
-  len = 0;
-  line = xmalloc (size);
-
-  while ((c = getchar_unlocked ()) != '\n' && c != '\r')
-    {
-      if (c == EOF)
-	{
-	  if (errno != EINTR)
-	    break;
-	}
-      else
-	{
-	  if (hide_char)
-	    fprintf (stdout, "%c", hide_char);
-	  if (len == size)
-	    line = xpalloc (line, &size, 1, -1, sizeof *line);
-	  line[len++] = c;
-	}
-    }
-
-  /* Reset tty.  */
-  if (hide_char)
-    {
-      fprintf (stdout, "\n");
-      if (etty_valid)
-	{
-	  emacs_set_tty (STDIN_FILENO, &etty, 0);
-	  set_binary_mode (STDIN_FILENO, O_TEXT);
-	}
-    }
-
-  if (len || c == '\n' || c == '\r')
-    {
-      val = make_string (line, len);
-      xfree (line);
-    }
-  else
-    {
-      xfree (line);
-      error ("Error reading from stdin");
-    }
-
-  /* If Lisp form desired instead of string, parse it.  */
-  if (expflag)
-    val = string_to_object (val, CONSP (defalt) ? XCAR (defalt) : defalt);
-
-  return val;
-}
-
 /* Read from the minibuffer using keymap MAP and initial contents INITIAL,
    putting point minus BACKUP_N bytes from the end of INITIAL,
    prompting with PROMPT (a string), using history list HISTVAR
@@ -1224,8 +1138,20 @@ with a space are ignored unless STRING itself starts with a space.  */)
   if (type == 2)
     {
       collection = check_obarray (collection);
-      obsize = ASIZE (collection);
-      bucket = AREF (collection, idx);
+      /* If the obarray is indexed, only go through the symbols whose
+	 names start with STRING, as if they were a list.  */
+      tem = (completion_ignore_case ? Qt
+	     : obarray_prefix_matches (collection, string));
+      if (!EQ (tem, Qt))
+	{
+	  type = 1;
+	  tail = tem;
+	}
+      else
+	{
+	  obsize = ASIZE (collection);
+	  bucket = AREF (collection, idx);
+	}
     }
 
   while (1)
@@ -855,8 +855,6 @@ Turn the input menu (an NSMenu) into a lisp list for tracking on lisp side
 /* Note: see frame.c for template, also where generic functions are impl */
 frame_parm_handler ns_frame_parm_handlers[] =
 {
-  x_set_autoraise, /* generic OK */
-  x_set_autolower, /* generic OK */
   x_set_background_color,
   0, /* x_set_border_color,  may be impossible under Nextstep */
   0, /* x_set_border_width,  may be impossible under Nextstep */
@@ -875,7 +873,6 @@ Turn the input menu (an NSMenu) into a lisp list for tracking on lisp side
   x_set_scroll_bar_width, /* generic OK */
   x_set_scroll_bar_height, /* generic OK */
   x_set_title,
-  x_set_unsplittable, /* generic OK */
   x_set_vertical_scroll_bars, /* generic OK */
   x_set_horizontal_scroll_bars, /* generic OK */
   x_set_visibility, /* generic OK */
@@ -908,6 +905,9 @@ Turn the input menu (an NSMenu) into a lisp list for tracking on lisp side
 #ifdef NS_IMPL_COCOA
   ns_set_appearance,
   ns_set_transparent_titlebar,
+#else
+  0, /* ns_set_appearance */
+  0, /* ns_set_transparent_titlebar */
 #endif
 };
 
@@ -5928,6 +5928,21 @@ - (void)applicationDidResignActive: (NSNotification *)notification
   ns_send_appdefined (-1);
 }
 
+- (void)applicationDidChangeScreenParameters: (NSNotification *)notification
+{
+  NSTRACE ("[EmacsApp applicationDidChangeScreenParameters:]");
+
+  struct frame *emacsframe = SELECTED_FRAME ();
+
+  if (!emacs_event || !x_display_list)
+    return;
+
+  /* Monitors were connected, disconnected or rearranged.  */
+  emacs_event->kind = MONITORS_CHANGED_EVENT;
+  XSETTERMINAL (emacs_event->arg, x_display_list->terminal);
+  EV_TRAILER ((id)nil);
+}
+
 
 
 /* ==========================================================================
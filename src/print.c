@@ -298,7 +298,7 @@ printchar (unsigned int ch, Lisp_Object fun)
 	  if (DISP_TABLE_P (Vstandard_display_table))
 	    printchar_to_stream (ch, stdout);
 	  else
-	    fwrite_unlocked (str, 1, len, stdout);
+	    write_to_stream ((char *) str, 1, len, stdout);
 	  noninteractive_need_newline = 1;
 	}
       else
@@ -369,7 +369,7 @@ strout (const char *ptr, ptrdiff_t size, ptrdiff_t size_byte,
 	    }
 	}
       else
-	fwrite_unlocked (ptr, 1, size_byte, stdout);
+	write_to_stream (ptr, size, size_byte, stdout);
 
       noninteractive_need_newline = 1;
     }
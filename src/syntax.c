@@ -178,7 +178,6 @@ static ptrdiff_t find_start_begv;
 static EMACS_INT find_start_modiff;
 
 
-Lisp_Object scan_lists (EMACS_INT, EMACS_INT, EMACS_INT, bool);
 static void scan_sexps_forward (struct lisp_parse_state *,
                                 ptrdiff_t, ptrdiff_t, ptrdiff_t, EMACS_INT,
                                 bool, int);
@@ -186,13 +185,6 @@ static void internalize_parse_state (Lisp_Object, struct lisp_parse_state *);
 static bool in_classes (int, Lisp_Object);
 static void parse_sexp_propertize (ptrdiff_t charpos);
 
-/* Whether the syntax of the character C has the prefix flag set.  */
-bool
-syntax_prefix_flag_p (int c)
-{
-  return SYNTAX_FLAGS_PREFIX (SYNTAX_WITH_FLAGS (c));
-}
-
 struct gl_state_s gl_state;		/* Global state of syntax parser.  */
 
 enum { INTERVALS_AT_ONCE = 10 };	/* 1 + max-number of intervals
@@ -216,16 +208,6 @@ SET_RAW_SYNTAX_ENTRY_RANGE (Lisp_Object table, Lisp_Object range,
   Fset_char_table_range (table, range, val);
 }
 
-/* Extract the information from the entry for character C
-   in the current syntax table.  */
-
-static Lisp_Object
-SYNTAX_MATCH (int c)
-{
-  Lisp_Object ent = SYNTAX_ENTRY (c);
-  return CONSP (ent) ? XCDR (ent) : Qnil;
-}
-
 /* This should be called with FROM at the start of forward
    search, or after the last position of the backward search.  It
    makes sure that the first char is picked up with correct table, so
@@ -1015,121 +997,9 @@ char const syntax_code_spec[16] =
    shared among syntax tables, we generate them in advance.  By
    sharing objects, the function `describe-syntax' can give a more
    compact listing.  */
-static Lisp_Object Vsyntax_code_object;
+Lisp_Object Vsyntax_code_object;
 
 
-DEFUN ("char-syntax", Fchar_syntax, Schar_syntax, 1, 1, 0,
-       doc: /* Return the syntax code of CHARACTER, described by a character.
-For example, if CHARACTER is a word constituent, the
-character `w' (119) is returned.
-The characters that correspond to various syntax codes
-are listed in the documentation of `modify-syntax-entry'.
-
-If you're trying to determine the syntax of characters in the buffer,
-this is probably the wrong function to use, because it can't take
-`syntax-table' text properties into account.  Consider using
-`syntax-after' instead.  */)
-  (Lisp_Object character)
-{
-  int char_int;
-  CHECK_CHARACTER (character);
-  char_int = XINT (character);
-  SETUP_BUFFER_SYNTAX_TABLE ();
-  return make_number (syntax_code_spec[SYNTAX (char_int)]);
-}
-
-DEFUN ("matching-paren", Fmatching_paren, Smatching_paren, 1, 1, 0,
-       doc: /* Return the matching parenthesis of CHARACTER, or nil if none.  */)
-  (Lisp_Object character)
-{
-  int char_int;
-  enum syntaxcode code;
-  CHECK_CHARACTER (character);
-  char_int = XINT (character);
-  SETUP_BUFFER_SYNTAX_TABLE ();
-  code = SYNTAX (char_int);
-  if (code == Sopen || code == Sclose)
-    return SYNTAX_MATCH (char_int);
-  return Qnil;
-}
-
-DEFUN ("string-to-syntax", Fstring_to_syntax, Sstring_to_syntax, 1, 1, 0,
-       doc: /* Convert a syntax descriptor STRING into a raw syntax descriptor.
-STRING should be a string of the form allowed as argument of
-`modify-syntax-entry'.  The return value is a raw syntax descriptor: a
-cons cell (CODE . MATCHING-CHAR) which can be used, for example, as
-the value of a `syntax-table' text property.  */)
-  (Lisp_Object string)
-{
-  const unsigned char *p;
-  int val;
-  Lisp_Object match;
-
-  CHECK_STRING (string);
-
-  p = SDATA (string);
-  val = syntax_spec_code[*p++];
-  if (val == 0377)
-    error ("Invalid syntax description letter: %c", p[-1]);
-
-  if (val == Sinherit)
-    return Qnil;
-
-  if (*p)
-    {
-      int len;
-      int character = STRING_CHAR_AND_LENGTH (p, len);
-      XSETINT (match, character);
-      if (XFASTINT (match) == ' ')
-	match = Qnil;
-      p += len;
-    }
-  else
-    match = Qnil;
-
-  while (*p)
-    switch (*p++)
-      {
-      case '1':
-	val |= 1 << 16;
-	break;
-
-      case '2':
-	val |= 1 << 17;
-	break;
-
-      case '3':
-	val |= 1 << 18;
-	break;
-
-      case '4':
-	val |= 1 << 19;
-	break;
-
-      case 'p':
-	val |= 1 << 20;
-	break;
-
-      case 'b':
-	val |= 1 << 21;
-	break;
-
-      case 'n':
-	val |= 1 << 22;
-	break;
-
-      case 'c':
-	val |= 1 << 23;
-	break;
-      }
-
-  if (val < ASIZE (Vsyntax_code_object) && NILP (match))
-    return AREF (Vsyntax_code_object, val);
-  else
-    /* Since we can't use a shared object, let's make a new one.  */
-    return Fcons (make_number (val), match);
-}
-
 /* I really don't know why this is interactive
    help-form should at least be made useful whilst reading the second arg.  */
 DEFUN ("modify-syntax-entry", Fmodify_syntax_entry, Smodify_syntax_entry, 2, 3,
@@ -1357,128 +1227,6 @@ DEFUN ("internal-describe-syntax-value", Finternal_describe_syntax_value,
   return syntax;
 }
 
-/* Return the position across COUNT words from FROM.
-   If that many words cannot be found before the end of the buffer, return 0.
-   COUNT negative means scan backward and stop at word beginning.  */
-
-ptrdiff_t
-scan_words (ptrdiff_t from, EMACS_INT count)
-{
-  ptrdiff_t beg = BEGV;
-  ptrdiff_t end = ZV;
-  ptrdiff_t from_byte = CHAR_TO_BYTE (from);
-  enum syntaxcode code;
-  int ch0, ch1;
-  Lisp_Object func, pos;
-
-  SETUP_SYNTAX_TABLE (from, count);
-
-  while (count > 0)
-    {
-      while (true)
-	{
-	  if (from == end)
-	    return 0;
-	  UPDATE_SYNTAX_TABLE_FORWARD (from);
-	  ch0 = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-	  code = SYNTAX (ch0);
-	  INC_BOTH (from, from_byte);
-	  if (words_include_escapes
-	      && (code == Sescape || code == Scharquote))
-	    break;
-	  if (code == Sword)
-	    break;
-	  rarely_quit (from);
-	}
-      /* Now CH0 is a character which begins a word and FROM is the
-         position of the next character.  */
-      func = CHAR_TABLE_REF (Vfind_word_boundary_function_table, ch0);
-      if (! NILP (Ffboundp (func)))
-	{
-	  pos = call2 (func, make_number (from - 1), make_number (end));
-	  if (INTEGERP (pos) && from < XINT (pos) && XINT (pos) <= ZV)
-	    {
-	      from = XINT (pos);
-	      from_byte = CHAR_TO_BYTE (from);
-	    }
-	}
-      else
-	{
-	  while (1)
-	    {
-	      if (from == end) break;
-	      UPDATE_SYNTAX_TABLE_FORWARD (from);
-	      ch1 = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-	      code = SYNTAX (ch1);
-	      if ((code != Sword
-		   && (! words_include_escapes
-		       || (code != Sescape && code != Scharquote)))
-		  || word_boundary_p (ch0, ch1))
-		break;
-	      INC_BOTH (from, from_byte);
-	      ch0 = ch1;
-	      rarely_quit (from);
-	    }
-	}
-      count--;
-    }
-  while (count < 0)
-    {
-      while (true)
-	{
-	  if (from == beg)
-	    return 0;
-	  DEC_BOTH (from, from_byte);
-	  UPDATE_SYNTAX_TABLE_BACKWARD (from);
-	  ch1 = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-	  code = SYNTAX (ch1);
-	  if (words_include_escapes
-	      && (code == Sescape || code == Scharquote))
-	    break;
-	  if (code == Sword)
-	    break;
-	  rarely_quit (from);
-	}
-      /* Now CH1 is a character which ends a word and FROM is the
-         position of it.  */
-      func = CHAR_TABLE_REF (Vfind_word_boundary_function_table, ch1);
-      if (! NILP (Ffboundp (func)))
- 	{
-	  pos = call2 (func, make_number (from), make_number (beg));
-	  if (INTEGERP (pos) && BEGV <= XINT (pos) && XINT (pos) < from)
-	    {
-	      from = XINT (pos);
-	      from_byte = CHAR_TO_BYTE (from);
-	    }
-	}
-      else
-	{
-	  while (1)
-	    {
-	      if (from == beg)
-		break;
-	      DEC_BOTH (from, from_byte);
-	      UPDATE_SYNTAX_TABLE_BACKWARD (from);
-	      ch0 = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-	      code = SYNTAX (ch0);
-	      if ((code != Sword
-		   && (! words_include_escapes
-		       || (code != Sescape && code != Scharquote)))
-		  || word_boundary_p (ch0, ch1))
-		{
-		  INC_BOTH (from, from_byte);
-		  break;
-		}
-	      ch1 = ch0;
-	      rarely_quit (from);
-	    }
-	}
-      count++;
-    }
-
-  return from;
-}
-
 Lisp_Object
 skip_chars (bool forwardp, Lisp_Object string, Lisp_Object lim,
 	    bool handle_iso_classes)
@@ -2459,429 +2207,6 @@ between them, return t; otherwise return nil.  */)
   return Qt;
 }
 
-/* Return syntax code of character C if C is an ASCII character
-   or if MULTIBYTE_SYMBOL_P is false.  Otherwise, return Ssymbol.  */
-
-static enum syntaxcode
-syntax_multibyte (int c, bool multibyte_symbol_p)
-{
-  return ASCII_CHAR_P (c) || !multibyte_symbol_p ? SYNTAX (c) : Ssymbol;
-}
-
-Lisp_Object
-scan_lists (EMACS_INT from, EMACS_INT count, EMACS_INT depth, bool sexpflag)
-{
-  Lisp_Object val;
-  ptrdiff_t stop = count > 0 ? ZV : BEGV;
-  int c, c1;
-  int stringterm;
-  bool quoted;
-  bool mathexit = 0;
-  enum syntaxcode code;
-  EMACS_INT min_depth = depth;  /* Err out if depth gets less than this.  */
-  int comstyle = 0;		/* Style of comment encountered.  */
-  bool comnested = 0;		/* Whether the comment is nestable or not.  */
-  ptrdiff_t temp_pos;
-  EMACS_INT last_good = from;
-  bool found;
-  ptrdiff_t from_byte;
-  ptrdiff_t out_bytepos, out_charpos;
-  EMACS_INT dummy;
-  int dummy2;
-  bool multibyte_symbol_p = sexpflag && multibyte_syntax_as_symbol;
-  unsigned short int quit_count = 0;
-
-  if (depth > 0) min_depth = 0;
-
-  if (from > ZV) from = ZV;
-  if (from < BEGV) from = BEGV;
-
-  from_byte = CHAR_TO_BYTE (from);
-
-  maybe_quit ();
-
-  SETUP_SYNTAX_TABLE (from, count);
-  while (count > 0)
-    {
-      while (from < stop)
-	{
-	  rarely_quit (++quit_count);
-	  bool comstart_first, prefix;
-	  int syntax, other_syntax;
-	  UPDATE_SYNTAX_TABLE_FORWARD (from);
-	  c = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-	  syntax = SYNTAX_WITH_FLAGS (c);
-	  code = syntax_multibyte (c, multibyte_symbol_p);
-	  comstart_first = SYNTAX_FLAGS_COMSTART_FIRST (syntax);
-	  comnested = SYNTAX_FLAGS_COMMENT_NESTED (syntax);
-	  comstyle = SYNTAX_FLAGS_COMMENT_STYLE (syntax, 0);
-	  prefix = SYNTAX_FLAGS_PREFIX (syntax);
-	  if (depth == min_depth)
-	    last_good = from;
-	  INC_BOTH (from, from_byte);
-	  UPDATE_SYNTAX_TABLE_FORWARD (from);
-	  if (from < stop && comstart_first
-	      && (c = FETCH_CHAR_AS_MULTIBYTE (from_byte),
-		  other_syntax = SYNTAX_WITH_FLAGS (c),
-		  SYNTAX_FLAGS_COMSTART_SECOND (other_syntax))
-	      && parse_sexp_ignore_comments)
-	    {
-	      /* We have encountered a comment start sequence and we
-		 are ignoring all text inside comments.  We must record
-		 the comment style this sequence begins so that later,
-		 only a comment end of the same style actually ends
-		 the comment section.  */
-	      code = Scomment;
-	      comstyle = SYNTAX_FLAGS_COMMENT_STYLE (other_syntax, syntax);
-	      comnested |= SYNTAX_FLAGS_COMMENT_NESTED (other_syntax);
-	      INC_BOTH (from, from_byte);
-	      UPDATE_SYNTAX_TABLE_FORWARD (from);
-	    }
-
-	  if (prefix)
-	    continue;
-
-	  switch (code)
-	    {
-	    case Sescape:
-	    case Scharquote:
-	      if (from == stop)
-		goto lose;
-	      INC_BOTH (from, from_byte);
-	      /* Treat following character as a word constituent.  */
-	      FALLTHROUGH;
-	    case Sword:
-	    case Ssymbol:
-	      if (depth || !sexpflag) break;
-	      /* This word counts as a sexp; return at end of it.  */
-	      while (from < stop)
-		{
-		  UPDATE_SYNTAX_TABLE_FORWARD (from);
-
-		  c = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-		  switch (syntax_multibyte (c, multibyte_symbol_p))
-		    {
-		    case Scharquote:
-		    case Sescape:
-		      INC_BOTH (from, from_byte);
-		      if (from == stop)
-			goto lose;
-		      break;
-		    case Sword:
-		    case Ssymbol:
-		    case Squote:
-		      break;
-		    default:
-		      goto done;
-		    }
-		  INC_BOTH (from, from_byte);
-		  rarely_quit (++quit_count);
-		}
-	      goto done;
-
-	    case Scomment_fence:
-	      comstyle = ST_COMMENT_STYLE;
-	      FALLTHROUGH;
-	    case Scomment:
-	      if (!parse_sexp_ignore_comments) break;
-	      UPDATE_SYNTAX_TABLE_FORWARD (from);
-	      found = forw_comment (from, from_byte, stop,
-				    comnested, comstyle, 0,
-				    &out_charpos, &out_bytepos, &dummy,
-                                    &dummy2);
-	      from = out_charpos, from_byte = out_bytepos;
-	      if (!found)
-		{
-		  if (depth == 0)
-		    goto done;
-		  goto lose;
-		}
-	      INC_BOTH (from, from_byte);
-	      UPDATE_SYNTAX_TABLE_FORWARD (from);
-	      break;
-
-	    case Smath:
-	      if (!sexpflag)
-		break;
-	      if (from != stop && c == FETCH_CHAR_AS_MULTIBYTE (from_byte))
-		{
-		  INC_BOTH (from, from_byte);
-		}
-	      if (mathexit)
-		{
-		  mathexit = 0;
-		  goto close1;
-		}
-	      mathexit = 1;
-	      FALLTHROUGH;
-	    case Sopen:
-	      if (!++depth) goto done;
-	      break;
-
-	    case Sclose:
-	    close1:
-	      if (!--depth) goto done;
-	      if (depth < min_depth)
-		xsignal3 (Qscan_error,
-			  build_string ("Containing expression ends prematurely"),
-			  make_number (last_good), make_number (from));
-	      break;
-
-	    case Sstring:
-	    case Sstring_fence:
-	      temp_pos = dec_bytepos (from_byte);
-	      stringterm = FETCH_CHAR_AS_MULTIBYTE (temp_pos);
-	      while (1)
-		{
-		  enum syntaxcode c_code;
-		  if (from >= stop)
-		    goto lose;
-		  UPDATE_SYNTAX_TABLE_FORWARD (from);
-		  c = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-		  c_code = syntax_multibyte (c, multibyte_symbol_p);
-		  if (code == Sstring
-		      ? c == stringterm && c_code == Sstring
-		      : c_code == Sstring_fence)
-		    break;
-
-		  if (c_code == Scharquote || c_code == Sescape)
-		    INC_BOTH (from, from_byte);
-		  INC_BOTH (from, from_byte);
-		  rarely_quit (++quit_count);
-		}
-	      INC_BOTH (from, from_byte);
-	      if (!depth && sexpflag) goto done;
-	      break;
-	    default:
-	      /* Ignore whitespace, punctuation, quote, endcomment.  */
-	      break;
-	    }
-	}
-
-      /* Reached end of buffer.  Error if within object, return nil if between */
-      if (depth)
-	goto lose;
-
-      return Qnil;
-
-      /* End of object reached */
-    done:
-      count--;
-    }
-
-  while (count < 0)
-    {
-      while (from > stop)
-	{
-	  rarely_quit (++quit_count);
-	  DEC_BOTH (from, from_byte);
-	  UPDATE_SYNTAX_TABLE_BACKWARD (from);
-	  c = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-	  int syntax = SYNTAX_WITH_FLAGS (c);
-	  code = syntax_multibyte (c, multibyte_symbol_p);
-	  if (depth == min_depth)
-	    last_good = from;
-	  comstyle = 0;
-	  comnested = SYNTAX_FLAGS_COMMENT_NESTED (syntax);
-	  if (code == Sendcomment)
-	    comstyle = SYNTAX_FLAGS_COMMENT_STYLE (syntax, 0);
-	  if (from > stop && SYNTAX_FLAGS_COMEND_SECOND (syntax)
-	      && prev_char_comend_first (from, from_byte)
-	      && parse_sexp_ignore_comments)
-	    {
-	      /* We must record the comment style encountered so that
-		 later, we can match only the proper comment begin
-		 sequence of the same style.  */
-	      int c2, other_syntax;
-	      DEC_BOTH (from, from_byte);
-	      UPDATE_SYNTAX_TABLE_BACKWARD (from);
-	      code = Sendcomment;
-	      c2 = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-	      other_syntax = SYNTAX_WITH_FLAGS (c2);
-	      comstyle = SYNTAX_FLAGS_COMMENT_STYLE (other_syntax, syntax);
-	      comnested |= SYNTAX_FLAGS_COMMENT_NESTED (other_syntax);
-	    }
-
-	  /* Quoting turns anything except a comment-ender
-	     into a word character.  Note that this cannot be true
-	     if we decremented FROM in the if-statement above.  */
-	  if (code != Sendcomment && char_quoted (from, from_byte))
-	    {
-	      DEC_BOTH (from, from_byte);
-	      code = Sword;
-	    }
-	  else if (SYNTAX_FLAGS_PREFIX (syntax))
-	    continue;
-
-	  switch (code)
-	    {
-	    case Sword:
-	    case Ssymbol:
-	    case Sescape:
-	    case Scharquote:
-	      if (depth || !sexpflag) break;
-	      /* This word counts as a sexp; count object finished
-		 after passing it.  */
-	      while (from > stop)
-		{
-		  temp_pos = from_byte;
-		  if (! NILP (BVAR (current_buffer, enable_multibyte_characters)))
-		    DEC_POS (temp_pos);
-		  else
-		    temp_pos--;
-		  UPDATE_SYNTAX_TABLE_BACKWARD (from - 1);
-		  c1 = FETCH_CHAR_AS_MULTIBYTE (temp_pos);
-		  /* Don't allow comment-end to be quoted.  */
-		  if (syntax_multibyte (c1, multibyte_symbol_p) == Sendcomment)
-		    goto done2;
-		  quoted = char_quoted (from - 1, temp_pos);
-		  if (quoted)
-		    {
-		      DEC_BOTH (from, from_byte);
-		      temp_pos = dec_bytepos (temp_pos);
-		      UPDATE_SYNTAX_TABLE_BACKWARD (from - 1);
-		    }
-		  c1 = FETCH_CHAR_AS_MULTIBYTE (temp_pos);
-		  if (! quoted)
-		    switch (syntax_multibyte (c1, multibyte_symbol_p))
-		      {
-		      case Sword: case Ssymbol: case Squote: break;
-		      default: goto done2;
-		      }
-		  DEC_BOTH (from, from_byte);
-		  rarely_quit (++quit_count);
-		}
-	      goto done2;
-
-	    case Smath:
-	      if (!sexpflag)
-		break;
-	      if (from > BEGV)
-		{
-		  temp_pos = dec_bytepos (from_byte);
-		  UPDATE_SYNTAX_TABLE_BACKWARD (from - 1);
-		  if (from != stop && c == FETCH_CHAR_AS_MULTIBYTE (temp_pos))
-		    DEC_BOTH (from, from_byte);
-		}
-	      if (mathexit)
-		{
-		  mathexit = 0;
-		  goto open2;
-		}
-	      mathexit = 1;
-	      FALLTHROUGH;
-	    case Sclose:
-	      if (!++depth) goto done2;
-	      break;
-
-	    case Sopen:
-	    open2:
-	      if (!--depth) goto done2;
-	      if (depth < min_depth)
-		xsignal3 (Qscan_error,
-			  build_string ("Containing expression ends prematurely"),
-			  make_number (last_good), make_number (from));
-	      break;
-
-	    case Sendcomment:
-	      if (!parse_sexp_ignore_comments)
-		break;
-	      found = back_comment (from, from_byte, stop, comnested, comstyle,
-				    &out_charpos, &out_bytepos);
-	      /* FIXME:  if !found, it really wasn't a comment-end.
-		 For single-char Sendcomment, we can't do much about it apart
-		 from skipping the char.
-		 For 2-char endcomments, we could try again, taking both
-		 chars as separate entities, but it's a lot of trouble
-		 for very little gain, so we don't bother either.  -sm */
-	      if (found)
-		from = out_charpos, from_byte = out_bytepos;
-	      break;
-
-	    case Scomment_fence:
-	    case Sstring_fence:
-	      while (1)
-		{
-		  if (from == stop)
-		    goto lose;
-		  DEC_BOTH (from, from_byte);
-		  UPDATE_SYNTAX_TABLE_BACKWARD (from);
-		  if (!char_quoted (from, from_byte))
-		    {
-		      c = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-		      if (syntax_multibyte (c, multibyte_symbol_p) == code)
-			break;
-		    }
-		  rarely_quit (++quit_count);
-		}
-	      if (code == Sstring_fence && !depth && sexpflag) goto done2;
-	      break;
-
-	    case Sstring:
-	      stringterm = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-	      while (true)
-		{
-		  if (from == stop)
-		    goto lose;
-		  DEC_BOTH (from, from_byte);
-		  UPDATE_SYNTAX_TABLE_BACKWARD (from);
-		  if (!char_quoted (from, from_byte))
-		    {
-		      c = FETCH_CHAR_AS_MULTIBYTE (from_byte);
-		      if (c == stringterm
-			  && (syntax_multibyte (c, multibyte_symbol_p)
-			      == Sstring))
-			break;
-		    }
-		  rarely_quit (++quit_count);
-		}
-	      if (!depth && sexpflag) goto done2;
-	      break;
-	    default:
-	      /* Ignore whitespace, punctuation, quote, endcomment.  */
-	      break;
-	    }
-	}
-
-      /* Reached start of buffer.  Error if within object, return nil if between */
-      if (depth)
-	goto lose;
-
-      return Qnil;
-
-    done2:
-      count++;
-    }
-
-
-  XSETFASTINT (val, from);
-  return val;
-
- lose:
-  xsignal3 (Qscan_error,
-	    build_string ("Unbalanced parentheses"),
-	    make_number (last_good), make_number (from));
-}
-
-DEFUN ("scan-sexps", Fscan_sexps, Sscan_sexps, 2, 2, 0,
-       doc: /* Scan from character number FROM by COUNT balanced expressions.
-If COUNT is negative, scan backwards.
-Returns the character number of the position thus found.
-
-Comments are ignored if `parse-sexp-ignore-comments' is non-nil.
-
-If the beginning or end of (the accessible part of) the buffer is reached
-in the middle of a parenthetical grouping, an error is signaled.
-If the beginning or end is reached between groupings
-but before count is used up, nil is returned.  */)
-  (Lisp_Object from, Lisp_Object count)
-{
-  CHECK_NUMBER (from);
-  CHECK_NUMBER (count);
-
-  return scan_lists (XINT (from), XINT (count), 0, 1);
-}
-
 DEFUN ("backward-prefix-chars", Fbackward_prefix_chars, Sbackward_prefix_chars,
        0, 0, 0,
        doc: /* Move point backward over any number of chars with prefix syntax.
@@ -2910,7 +2235,7 @@ the prefix syntax flag (p).  */)
   while (!char_quoted (pos, pos_byte)
 	 /* Previous statement updates syntax table.  */
 	 && ((c = FETCH_CHAR_AS_MULTIBYTE (pos_byte), SYNTAX (c) == Squote)
-	     || syntax_prefix_flag_p (c)))
+	     || syntax_prefix_flag_p (pos, c)))
     {
       opoint = pos;
       opoint_byte = pos_byte;
@@ -3341,97 +2666,6 @@ internalize_parse_state (Lisp_Object external, struct lisp_parse_state *state)
     }
 }
 
-DEFUN ("parse-partial-sexp", Fparse_partial_sexp, Sparse_partial_sexp, 2, 6, 0,
-       doc: /* Parse Lisp syntax starting at FROM until TO; return status of parse at TO.
-Parsing stops at TO or when certain criteria are met;
- point is set to where parsing stops.
-If fifth arg OLDSTATE is omitted or nil,
- parsing assumes that FROM is the beginning of a function.
-
-Value is a list of elements describing final state of parsing:
- 0. depth in parens.
- 1. character address of start of innermost containing list; nil if none.
- 2. character address of start of last complete sexp terminated.
- 3. non-nil if inside a string.
-    (it is the character that will terminate the string,
-     or t if the string should be terminated by a generic string delimiter.)
- 4. nil if outside a comment, t if inside a non-nestable comment,
-    else an integer (the current comment nesting).
- 5. t if following a quote character.
- 6. the minimum paren-depth encountered during this scan.
- 7. style of comment, if any.
- 8. character address of start of comment or string; nil if not in one.
- 9. List of positions of currently open parens, outermost first.
-10. When the last position scanned holds the first character of a
-    (potential) two character construct, the syntax of that position,
-    otherwise nil.  That construct can be a two character comment
-    delimiter or an Escaped or Char-quoted character.
-11..... Possible further internal information used by `parse-partial-sexp'.
-
-If third arg TARGETDEPTH is non-nil, parsing stops if the depth
-in parentheses becomes equal to TARGETDEPTH.
-Fourth arg STOPBEFORE non-nil means stop when we come to
- any character that starts a sexp.
-Fifth arg OLDSTATE is a list like what this function returns.
- It is used to initialize the state of the parse.  Elements number 1, 2, 6
- are ignored.
-Sixth arg COMMENTSTOP non-nil means stop after the start of a comment.
- If it is the symbol `syntax-table', stop after the start of a comment or a
- string, or after end of a comment or a string.  */)
-  (Lisp_Object from, Lisp_Object to, Lisp_Object targetdepth,
-   Lisp_Object stopbefore, Lisp_Object oldstate, Lisp_Object commentstop)
-{
-  struct lisp_parse_state state;
-  EMACS_INT target;
-
-  if (!NILP (targetdepth))
-    {
-      CHECK_NUMBER (targetdepth);
-      target = XINT (targetdepth);
-    }
-  else
-    target = TYPE_MINIMUM (EMACS_INT);	/* We won't reach this depth.  */
-
-  validate_region (&from, &to);
-  internalize_parse_state (oldstate, &state);
-  scan_sexps_forward (&state, XINT (from), CHAR_TO_BYTE (XINT (from)),
-		      XINT (to),
-		      target, !NILP (stopbefore),
-		      (NILP (commentstop)
-		       ? 0 : (EQ (commentstop, Qsyntax_table) ? -1 : 1)));
-
-  SET_PT_BOTH (state.location, state.location_byte);
-
-  return
-    Fcons (make_number (state.depth),
-	   Fcons (state.prevlevelstart < 0
-		  ? Qnil : make_number (state.prevlevelstart),
-	     Fcons (state.thislevelstart < 0
-		    ? Qnil : make_number (state.thislevelstart),
-	       Fcons (state.instring >= 0
-		      ? (state.instring == ST_STRING_STYLE
-			 ? Qt : make_number (state.instring)) : Qnil,
-		 Fcons (state.incomment < 0 ? Qt :
-			(state.incomment == 0 ? Qnil :
-			 make_number (state.incomment)),
-		   Fcons (state.quoted ? Qt : Qnil,
-		     Fcons (make_number (state.mindepth),
-		       Fcons ((state.comstyle
-			       ? (state.comstyle == ST_COMMENT_STYLE
-				  ? Qsyntax_table
-				  : make_number (state.comstyle))
-			       : Qnil),
-		         Fcons (((state.incomment
-                                  || (state.instring >= 0))
-                                 ? make_number (state.comstr_start)
-                                 : Qnil),
-			   Fcons (state.levelstarts,
-                             Fcons (state.prev_syntax == Smax
-                                    ? Qnil
-                                    : make_number (state.prev_syntax),
-                                Qnil)))))))))));
-}
-
 void
 init_syntax_once (void)
 {
@@ -3559,6 +2793,11 @@ See the info node `(elisp)Syntax Properties' for a description of the
   DEFVAR_BOOL ("words-include-escapes", words_include_escapes,
 	       doc: /* Non-nil means `forward-word', etc., should treat escape chars part of words.  */);
 
+  DEFVAR_LISP ("forward-sexp-function", Vforward_sexp_function,
+	       doc: /* If non-nil, `forward-sexp' delegates to this function.
+Should take the same arguments and behave similarly to `forward-sexp'.  */);
+  Vforward_sexp_function = Qnil;
+
   DEFVAR_BOOL ("multibyte-syntax-as-symbol", multibyte_syntax_as_symbol,
 	       doc: /* Non-nil means `scan-sexps' treats all multibyte characters as symbol.  */);
   multibyte_syntax_as_symbol = 0;
@@ -3593,14 +2832,9 @@ In both cases, LIMIT bounds the search. */);
   DEFSYM (Qcomment_end_can_be_escaped, "comment-end-can-be-escaped");
   Fmake_variable_buffer_local (Qcomment_end_can_be_escaped);
 
-  defsubr (&Schar_syntax);
-  defsubr (&Smatching_paren);
-  defsubr (&Sstring_to_syntax);
   defsubr (&Smodify_syntax_entry);
   defsubr (&Sinternal_describe_syntax_value);
 
   defsubr (&Sforward_comment);
-  defsubr (&Sscan_sexps);
   defsubr (&Sbackward_prefix_chars);
-  defsubr (&Sparse_partial_sexp);
 }
@@ -139,7 +139,7 @@ SYNTAX (int c)
 
 
 /* Whether the syntax of the character C has the prefix flag set.  */
-extern bool syntax_prefix_flag_p (int c);
+extern bool syntax_prefix_flag_p (ptrdiff_t, int);
 
 /* This array, indexed by a character less than 256, contains the
    syntax code which that character signifies (as an unsigned char).
@@ -151,6 +151,11 @@ extern unsigned char const syntax_spec_code[0400];
 
 extern char const syntax_code_spec[16];
 
+/* Indexed by syntax code, give the shared raw syntax descriptor (a
+   cons of the code and nil) for that code.  */
+
+extern Lisp_Object Vsyntax_code_object;
+
 /* Convert the byte offset BYTEPOS into a character position,
    for the object recorded in gl_state with SETUP_SYNTAX_TABLE_FOR_OBJECT.
 
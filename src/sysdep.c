@@ -588,22 +588,6 @@ static void save_signal_handlers (struct save_signal *);
 #endif
 static void restore_signal_handlers (struct save_signal *);
 
-/* Suspend the Emacs process; give terminal to its superior.  */
-
-void
-sys_suspend (void)
-{
-#ifndef DOS_NT
-  kill (0, SIGTSTP);
-#else
-/* On a system where suspending is not implemented,
-   instead fork a subshell and let it talk directly to the terminal
-   while we wait.  */
-  sys_subshell ();
-
-#endif
-}
-
 /* Fork a subshell.  */
 
 void
@@ -1362,6 +1346,20 @@ get_tty_size (int fd, int *widthp, int *heightp)
 #endif
 }
 
+/* Store the size of the selected frame's terminal into *WIDTHP and
+   *HEIGHTP.  Return false if the selected frame is not on a tty.  */
+
+bool
+get_selected_tty_size (int *widthp, int *heightp)
+{
+  if (! (FRAMEP (selected_frame)
+	 && FRAME_LIVE_P (XFRAME (selected_frame))
+	 && FRAME_TERMCAP_P (XFRAME (selected_frame))))
+    return false;
+  get_tty_size (fileno (CURTTY ()->input), widthp, heightp);
+  return true;
+}
+
 /* Set the logical window size associated with descriptor FD
    to HEIGHT and WIDTH.  This is used mainly with ptys.
    Return a negative value on failure.  */
@@ -1727,6 +1725,14 @@ deliver_fatal_thread_signal (int sig)
   deliver_thread_signal (sig, handle_fatal_signal);
 }
 
+#ifdef SIGTSTP
+static void
+deliver_suspend_signal (int sig)
+{
+  deliver_process_signal (sig, handle_suspend_signal);
+}
+#endif
+
 static _Noreturn void
 handle_arith_signal (int sig)
 {
@@ -2115,6 +2121,12 @@ init_signals (bool dumping)
   sigaction (SIGSYS, &thread_fatal_action, 0);
 #endif
   sigaction (SIGTERM, &process_fatal_action, 0);
+#ifdef SIGTSTP
+  /* Stopping and continuing Emacs is handled in terminal.rs.  */
+  emacs_sigaction_init (&action, deliver_suspend_signal);
+  sigaction (SIGTSTP, &action, 0);
+  sigaction (SIGCONT, &action, 0);
+#endif
 #ifdef SIGPROF
   signal (SIGPROF, SIG_IGN);
 #endif
@@ -2315,7 +2327,7 @@ snprintf (char *buf, size_t bufsize, char const *format, ...)
 }
 #endif
 
-/* If a backtrace is available, output the top lines of it to stderr.
+/* If a backtrace is available, output the top lines of it to FD.
    Do not output more than BACKTRACE_LIMIT or BACKTRACE_LIMIT_MAX lines.
    This function may be called from a signal handler, so it should
    not invoke async-unsafe functions like malloc.
@@ -2330,7 +2342,7 @@ snprintf (char *buf, size_t bufsize, char const *format, ...)
    fatal error has occurred in some other thread; generate a thread
    backtrace instead, ignoring BACKTRACE_LIMIT.  */
 void
-emacs_backtrace (int backtrace_limit)
+emacs_backtrace_to_fd (int fd, int backtrace_limit)
 {
   void *main_backtrace_buffer[BACKTRACE_LIMIT_MAX + 1];
   int bounded_limit = min (backtrace_limit, BACKTRACE_LIMIT_MAX);
@@ -2358,13 +2370,21 @@ emacs_backtrace (int backtrace_limit)
 
   if (npointers)
     {
-      emacs_write (STDERR_FILENO, "\nBacktrace:\n", 12);
-      backtrace_symbols_fd (buffer, npointers, STDERR_FILENO);
+      emacs_write (fd, "\nBacktrace:\n", 12);
+      backtrace_symbols_fd (buffer, npointers, fd);
       if (bounded_limit < npointers)
-	emacs_write (STDERR_FILENO, "...\n", 4);
+	emacs_write (fd, "...\n", 4);
     }
 }
 
+
+/* Like emacs_backtrace_to_fd, but output to stderr.  */
+void
+emacs_backtrace (int backtrace_limit)
+{
+  emacs_backtrace_to_fd (STDERR_FILENO, backtrace_limit);
+}
+
 #ifndef HAVE_NTGUI
 void
 emacs_abort (void)
@@ -21,10 +21,14 @@ along with GNU Emacs.  If not, see <https://www.gnu.org/licenses/>.  */
 #define EMACS_SYSSTDIO_H
 
 #include <fcntl.h>
+#include <stddef.h>
 #include <stdio.h>
 
 extern FILE *emacs_fopen (char const *, char const *);
 
+/* Defined in rust batch.rs.  */
+extern void write_to_stream (const char *, ptrdiff_t, ptrdiff_t, FILE *);
+
 #if O_BINARY
 # define FOPEN_BINARY "b"
 # define FOPEN_TEXT "t"
@@ -4407,6 +4407,7 @@ delete_tty (struct terminal *terminal)
   eassert (terminal->type == output_termcap);
 
   tty = terminal->display_info.tty;
+  tty_forget_input (terminal);
 
   if (tty == tty_list)
     tty_list = tty->next;
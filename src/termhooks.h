@@ -224,6 +224,16 @@ enum event_kind
 
   , CONFIG_CHANGED_EVENT
 
+  /* Text pasted into a text terminal in bracketed paste mode.  .arg is
+     the text as a unibyte string.  Translated into an `xterm-paste'
+     event.  */
+  , TTY_PASTE_EVENT
+
+  /* Queued by the window system backends when monitors were connected,
+     disconnected or rearranged.  .arg is the terminal of the monitors.
+     Translated into a `monitors-changed' event.  */
+  , MONITORS_CHANGED_EVENT
+
 #ifdef HAVE_NTGUI
   /* Generated when an APPCOMMAND event is received, in response to
      Multimedia or Internet buttons on some keyboards.
@@ -725,6 +735,8 @@ extern struct terminal *terminal_list;
 
 extern struct terminal *decode_live_terminal (Lisp_Object);
 extern struct terminal *decode_tty_terminal (Lisp_Object);
+extern Lisp_Object store_terminal_param (struct terminal *, Lisp_Object,
+					 Lisp_Object);
 extern struct terminal *get_named_terminal (const char *);
 extern struct terminal *create_terminal (enum output_method,
 					 struct redisplay_interface *);
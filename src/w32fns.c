@@ -10060,13 +10060,12 @@ DEFUN ("w32-notification-close",
 			    Initialization
  ***********************************************************************/
 
-/* Keep this list in the same order as frame_parms in frame.c.
+/* Keep this list in the same order as FRAME_PARAMETERS in
+   rust_src/src/frame_parameters.rs.
    Use 0 for unsupported frame parameters.  */
 
 frame_parm_handler w32_frame_parm_handlers[] =
 {
-  x_set_autoraise,
-  x_set_autolower,
   x_set_background_color,
   x_set_border_color,
   x_set_border_width,
@@ -10085,7 +10084,6 @@ frame_parm_handler w32_frame_parm_handlers[] =
   x_set_scroll_bar_width,
   x_set_scroll_bar_height,
   x_set_title,
-  x_set_unsplittable,
   x_set_vertical_scroll_bars,
   x_set_horizontal_scroll_bars,
   x_set_visibility,
@@ -10111,6 +10109,8 @@ frame_parm_handler w32_frame_parm_handlers[] =
   x_set_z_group,
   0, /* x_set_override_redirect */
   x_set_no_special_glyphs,
+  0, /* ns_set_appearance */
+  0, /* ns_set_transparent_titlebar */
 };
 
 void
@@ -5504,6 +5504,9 @@ w32_read_socket (struct terminal *terminal,
 		  w32fullscreen_hook (f);
 		}
 
+	      /* Monitors were connected, disconnected or rearranged.  */
+	      store_monitors_changed_event (dpyinfo->terminal);
+
 	      DebPrint (("display change: %d %d\n",
 			 (short) LOWORD (msg.msg.lParam),
 			 (short) HIWORD (msg.msg.lParam)));
@@ -2595,6 +2595,8 @@ run_window_configuration_change_hook (struct frame *f)
       || !(f->after_make_frame))
     return;
 
+  window_configuration_ring_note_change (frame);
+
   /* Use the right buffer.  Matters when running the local hooks.  */
   if (current_buffer != XBUFFER (Fwindow_buffer (Qnil)))
     {
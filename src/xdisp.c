@@ -378,6 +378,26 @@ static Lisp_Object list_of_error;
 	   && (*BYTE_POS_ADDR (IT_BYTEPOS (*it)) == ' '			\
 	       || *BYTE_POS_ADDR (IT_BYTEPOS (*it)) == '\t'))))		\
 
+/* Return true if a line may be wrapped before the display element
+   loaded in IT.  */
+
+static bool
+it_can_wrap_before (struct it *it)
+{
+  return char_can_wrap_before (it->c, IT_DISPLAYING_WHITESPACE (it),
+			       it->glyph_row && it->glyph_row->reversed_p);
+}
+
+/* Return true if a line may be wrapped after the display element
+   loaded in IT.  */
+
+static bool
+it_can_wrap_after (struct it *it)
+{
+  return char_can_wrap_after (it->c, IT_DISPLAYING_WHITESPACE (it),
+			      it->glyph_row && it->glyph_row->reversed_p);
+}
+
 /* True means print newline to stdout before next mini-buffer message.  */
 
 bool noninteractive_need_newline;
@@ -491,7 +511,7 @@ int windows_or_buffers_changed;
 
    Since the frame title uses the same %-constructs as the mode line
    (except %c, %C, and %l), if this variable is non-zero, we also consider
-   redisplaying the title of each frame, see x_consider_frame_title.
+   redisplaying the title of each frame, see consider_frame_title.
 
    The `redisplay' bits are the same as those used for
    windows_or_buffers_changed, and setting windows_or_buffers_changed also
@@ -8811,13 +8831,16 @@ move_it_in_display_line_to (struct it *it,
 	{
 	  if (it->line_wrap == WORD_WRAP && it->area == TEXT_AREA)
 	    {
-	      if (IT_DISPLAYING_WHITESPACE (it))
-		may_wrap = true;
-	      else if (may_wrap)
+	      /* Can we wrap after this character?  */
+	      bool next_may_wrap = it_can_wrap_after (it);
+
+	      /* Can we wrap here?  */
+	      if (may_wrap && it_can_wrap_before (it))
 		{
 		  /* We have reached a glyph that follows one or more
-		     whitespace characters.  If the position is
-		     already found, we are done.  */
+		     whitespace characters, or another character
+		     after which a line may be wrapped.  If the
+		     position is already found, we are done.  */
 		  if (atpos_it.sp >= 0)
 		    {
 		      RESTORE_IT (it, &atpos_it, atpos_data);
@@ -8832,8 +8855,8 @@ move_it_in_display_line_to (struct it *it,
 		    }
 		  /* Otherwise, we can wrap here.  */
 		  SAVE_IT (wrap_it, *it, wrap_data);
-		  may_wrap = false;
 		}
+	      may_wrap = next_may_wrap;
 	    }
 	}
 
@@ -10529,35 +10552,6 @@ message3 (Lisp_Object m)
     message3_nolog (m);
 }
 
-/* Log the message M to stderr.  Log an empty line if M is not a string.  */
-
-static void
-message_to_stderr (Lisp_Object m)
-{
-  if (noninteractive_need_newline)
-    {
-      noninteractive_need_newline = false;
-      fputc ('\n', stderr);
-    }
-  if (STRINGP (m))
-    {
-      Lisp_Object coding_system = Vlocale_coding_system;
-      Lisp_Object s;
-
-      if (!NILP (Vcoding_system_for_write))
-	coding_system = Vcoding_system_for_write;
-      if (!NILP (coding_system))
-	s = code_convert_string_norecord (m, coding_system, true);
-      else
-	s = m;
-
-      fwrite (SDATA (s), SBYTES (s), 1, stderr);
-    }
-  if (!cursor_in_echo_area)
-    fputc ('\n', stderr);
-  fflush (stderr);
-}
-
 /* The non-logging version of message3.
    This does not cancel echoing, because it is used for echoing.
    Perhaps we need to make a separate function for echoing
@@ -11925,87 +11919,46 @@ store_mode_line_noprop (const char *string, int field_width, int precision)
 			     Frame Titles
  ***********************************************************************/
 
-#ifdef HAVE_WINDOW_SYSTEM
-
-/* Set the title of FRAME, if it has changed.  The title format is
-   Vicon_title_format if FRAME is iconified, otherwise it is
-   frame_title_format.  */
+/* Format the title FMT of frame F in the selected window of F and its
+   buffer, and store its length in *LEN.  The title stays valid until
+   the next mode line is formatted.  Deciding which frames get a title
+   and setting it is done in Rust, see consider_frame_title.  */
 
-static void
-x_consider_frame_title (Lisp_Object frame)
+char *
+format_frame_title (struct frame *f, Lisp_Object fmt, ptrdiff_t *len)
 {
-  struct frame *f = XFRAME (frame);
-
-  if ((FRAME_WINDOW_P (f)
-       || FRAME_MINIBUF_ONLY_P (f)
-       || f->explicit_name)
-      && !FRAME_TOOLTIP_P (f))
-    {
-      /* Do we have more than one visible frame on this X display?  */
-      Lisp_Object tail, other_frame, fmt;
-      ptrdiff_t title_start;
-      char *title;
-      ptrdiff_t len;
-      struct it it;
-      ptrdiff_t count = SPECPDL_INDEX ();
-
-      FOR_EACH_FRAME (tail, other_frame)
-	{
-	  struct frame *tf = XFRAME (other_frame);
-
-	  if (tf != f
-	      && FRAME_KBOARD (tf) == FRAME_KBOARD (f)
-	      && !FRAME_MINIBUF_ONLY_P (tf)
-	      && !FRAME_PARENT_FRAME (tf)
-	      && !FRAME_TOOLTIP_P (tf)
-	      && (FRAME_VISIBLE_P (tf) || FRAME_ICONIFIED_P (tf)))
-	    break;
-	}
-
-      /* Set global variable indicating that multiple frames exist.  */
-      multiple_frames = CONSP (tail);
-
-      /* Switch to the buffer of selected window of the frame.  Set up
-	 mode_line_target so that display_mode_element will output into
-	 mode_line_noprop_buf; then display the title.  */
-      record_unwind_protect (unwind_format_mode_line,
-			     format_mode_line_unwind_data
-			       (f, current_buffer, selected_window, false));
-      /* select-frame calls resize_mini_window, which could resize the
-	 mini-window and by that undo the effect of this redisplay
-	 cycle wrt minibuffer and echo-area display.  Binding
-	 inhibit-redisplay to t makes the call to resize_mini_window a
-	 no-op, thus avoiding the adverse side effects.  */
-      specbind (Qinhibit_redisplay, Qt);
+  ptrdiff_t title_start;
+  struct it it;
+  ptrdiff_t count = SPECPDL_INDEX ();
 
-      Fselect_window (f->selected_window, Qt);
-      set_buffer_internal_1
-	(XBUFFER (XWINDOW (f->selected_window)->contents));
-      fmt = FRAME_ICONIFIED_P (f) ? Vicon_title_format : Vframe_title_format;
-
-      mode_line_target = MODE_LINE_TITLE;
-      title_start = MODE_LINE_NOPROP_LEN (0);
-      init_iterator (&it, XWINDOW (f->selected_window), -1, -1,
-		     NULL, DEFAULT_FACE_ID);
-      display_mode_element (&it, 0, -1, -1, fmt, Qnil, false);
-      len = MODE_LINE_NOPROP_LEN (title_start);
-      title = mode_line_noprop_buf + title_start;
-      unbind_to (count, Qnil);
+  /* Switch to the buffer of selected window of the frame.  Set up
+     mode_line_target so that display_mode_element will output into
+     mode_line_noprop_buf; then display the title.  */
+  record_unwind_protect (unwind_format_mode_line,
+			 format_mode_line_unwind_data
+			   (f, current_buffer, selected_window, false));
+  /* select-frame calls resize_mini_window, which could resize the
+     mini-window and by that undo the effect of this redisplay
+     cycle wrt minibuffer and echo-area display.  Binding
+     inhibit-redisplay to t makes the call to resize_mini_window a
+     no-op, thus avoiding the adverse side effects.  */
+  specbind (Qinhibit_redisplay, Qt);
+
+  Fselect_window (f->selected_window, Qt);
+  set_buffer_internal_1
+    (XBUFFER (XWINDOW (f->selected_window)->contents));
+
+  mode_line_target = MODE_LINE_TITLE;
+  title_start = MODE_LINE_NOPROP_LEN (0);
+  init_iterator (&it, XWINDOW (f->selected_window), -1, -1,
+		 NULL, DEFAULT_FACE_ID);
+  display_mode_element (&it, 0, -1, -1, fmt, Qnil, false);
+  *len = MODE_LINE_NOPROP_LEN (title_start);
+  unbind_to (count, Qnil);
 
-      /* Set the title only if it's changed.  This avoids consing in
-	 the common case where it hasn't.  (If it turns out that we've
-	 already wasted too much time by walking through the list with
-	 display_mode_element, then we might need to optimize at a
-	 higher level than this.)  */
-      if (! STRINGP (f->name)
-	  || SBYTES (f->name) != len
-	  || memcmp (title, SDATA (f->name), len) != 0)
-	x_implicitly_set_name (f, make_string (title, len), Qnil);
-    }
+  return mode_line_noprop_buf + title_start;
 }
 
-#endif /* not HAVE_WINDOW_SYSTEM */
-
 
 /***********************************************************************
 			      Menu Bars
@@ -12053,36 +12006,7 @@ prepare_menu_bars (void)
      up-to-date frame titles.  */
 #ifdef HAVE_WINDOW_SYSTEM
   if (all_windows)
-    {
-      Lisp_Object tail, frame;
-
-      FOR_EACH_FRAME (tail, frame)
-	{
-	  struct frame *f = XFRAME (frame);
-	  struct window *w = XWINDOW (FRAME_SELECTED_WINDOW (f));
-	  if (some_windows
-	      && !f->redisplay
-	      && !w->redisplay
-	      && !XBUFFER (w->contents)->text->redisplay)
-	    continue;
-
-	  if (!FRAME_TOOLTIP_P (f)
-	      && !FRAME_PARENT_FRAME (f)
-	      && (FRAME_ICONIFIED_P (f)
-		  || FRAME_VISIBLE_P (f) == 1
-		  /* Exclude TTY frames that are obscured because they
-		     are not the top frame on their console.  This is
-		     because x_consider_frame_title actually switches
-		     to the frame, which for TTY frames means it is
-		     marked as garbaged, and will be completely
-		     redrawn on the next redisplay cycle.  This causes
-		     TTY frames to be completely redrawn, when there
-		     are more than one of them, even though nothing
-		     should be changed on display.  */
-		  || (FRAME_VISIBLE_P (f) == 2 && FRAME_WINDOW_P (f))))
-	    x_consider_frame_title (frame);
-	}
-    }
+    update_frame_titles (some_windows);
 #endif /* HAVE_WINDOW_SYSTEM */
 
   /* Update the menu bar item lists, if appropriate.  This has to be
@@ -12411,23 +12335,8 @@ build_desired_tool_bar_string (struct frame *f)
 
       /* If image is a vector, choose the image according to the
 	 button state.  */
-      image = PROP (TOOL_BAR_ITEM_IMAGES);
-      if (VECTORP (image))
-	{
-	  if (enabled_p)
-	    idx = (selected_p
-		   ? TOOL_BAR_IMAGE_ENABLED_SELECTED
-		   : TOOL_BAR_IMAGE_ENABLED_DESELECTED);
-	  else
-	    idx = (selected_p
-		   ? TOOL_BAR_IMAGE_DISABLED_SELECTED
-		   : TOOL_BAR_IMAGE_DISABLED_DESELECTED);
-
-	  eassert (ASIZE (image) >= idx);
-	  image = AREF (image, idx);
-	}
-      else
-	idx = -1;
+      image = tool_bar_image_for_state (PROP (TOOL_BAR_ITEM_IMAGES),
+					enabled_p, selected_p, &idx);
 
       /* Ignore invalid image specifications.  */
       if (!valid_image_p (image))
@@ -12891,35 +12800,6 @@ redisplay_tool_bar (struct frame *f)
 
 #if ! defined (USE_GTK) && ! defined (HAVE_NS)
 
-/* Get information about the tool-bar item which is displayed in GLYPH
-   on frame F.  Return in *PROP_IDX the index where tool-bar item
-   properties start in F->tool_bar_items.  Value is false if
-   GLYPH doesn't display a tool-bar item.  */
-
-static bool
-tool_bar_item_info (struct frame *f, struct glyph *glyph, int *prop_idx)
-{
-  Lisp_Object prop;
-  int charpos;
-
-  /* This function can be called asynchronously, which means we must
-     exclude any possibility that Fget_text_property signals an
-     error.  */
-  charpos = min (SCHARS (f->current_tool_bar_string), glyph->charpos);
-  charpos = max (0, charpos);
-
-  /* Get the text property `menu-item' at pos. The value of that
-     property is the start index of this item's properties in
-     F->tool_bar_items.  */
-  prop = Fget_text_property (make_number (charpos),
-			     Qmenu_item, f->current_tool_bar_string);
-  if (! INTEGERP (prop))
-    return false;
-  *prop_idx = XINT (prop);
-  return true;
-}
-
-
 /* Get information about the tool-bar item at position X/Y on frame F.
    Return in *GLYPH a pointer to the glyph of the tool-bar item in
    the current matrix of the tool-bar window of F, or NULL if not
@@ -12945,7 +12825,8 @@ get_tool_bar_item (struct frame *f, int x, int y, struct glyph **glyph,
 
   /* Get the start of this tool-bar item's properties in
      f->tool_bar_items.  */
-  if (!tool_bar_item_info (f, *glyph, prop_idx))
+  *prop_idx = tool_bar_item_index (f, *glyph);
+  if (*prop_idx < 0)
     return -1;
 
   /* Is mouse on the highlighted item?  */
@@ -13013,27 +12894,11 @@ handle_tool_bar_click (struct frame *f, int x, int y, bool down_p,
     }
   else
     {
-      Lisp_Object key, frame;
-      struct input_event event;
-      EVENT_INIT (event);
-
       /* Show item in released state.  */
       if (!NILP (Vmouse_highlight))
 	show_mouse_face (hlinfo, DRAW_IMAGE_RAISED);
 
-      key = AREF (f->tool_bar_items, prop_idx + TOOL_BAR_ITEM_KEY);
-
-      XSETFRAME (frame, f);
-      event.kind = TOOL_BAR_EVENT;
-      event.frame_or_window = frame;
-      event.arg = frame;
-      kbd_buffer_store_event (&event);
-
-      event.kind = TOOL_BAR_EVENT;
-      event.frame_or_window = frame;
-      event.arg = key;
-      event.modifiers = modifiers;
-      kbd_buffer_store_event (&event);
+      store_tool_bar_click (f, prop_idx, modifiers);
       f->last_tool_bar_item = -1;
     }
 }
@@ -17569,7 +17434,7 @@ redisplay_window (Lisp_Object window, bool just_this_one_p)
 	    ignore_mouse_drag_p = true;
 #endif
         }
-      x_consider_frame_title (w->frame);
+      consider_frame_title (w->frame);
 #endif
     }
 
@@ -21520,9 +21385,11 @@ display_line (struct it *it, int cursor_vpos)
 
 	  if (it->line_wrap == WORD_WRAP && it->area == TEXT_AREA)
 	    {
-	      if (IT_DISPLAYING_WHITESPACE (it))
-		may_wrap = true;
-	      else if (may_wrap)
+	      /* Can we wrap after this character?  */
+	      bool next_may_wrap = it_can_wrap_after (it);
+
+	      /* Can we wrap here?  */
+	      if (may_wrap && it_can_wrap_before (it))
 		{
 		  SAVE_IT (wrap_it, *it, wrap_data);
 		  wrap_x = x;
@@ -21536,8 +21403,8 @@ display_line (struct it *it, int cursor_vpos)
 		  wrap_row_min_bpos = min_bpos;
 		  wrap_row_max_pos = max_pos;
 		  wrap_row_max_bpos = max_bpos;
-		  may_wrap = false;
 		}
+	      may_wrap = next_may_wrap;
 	    }
 	}
 
@@ -21667,8 +21534,7 @@ display_line (struct it *it, int cursor_vpos)
 				 usual, if (i) the previous character
 				 was a space or tab AND (ii) the
 				 current character is not.  */
-			      && (!may_wrap
-				  || IT_DISPLAYING_WHITESPACE (it)))
+			      && (!may_wrap || !it_can_wrap_before (it)))
 			    goto back_to_wrap;
 
 			  /* Record the maximum and minimum buffer
@@ -21702,7 +21568,7 @@ display_line (struct it *it, int cursor_vpos)
 					  was a space or tab AND (ii) the
 					  current character is not.  */
 				       && (!may_wrap
-					   || IT_DISPLAYING_WHITESPACE (it)))
+					   || !it_can_wrap_before (it)))
 				goto back_to_wrap;
 
 			    }
@@ -6057,13 +6057,12 @@ visible.  */)
 			    Initialization
  ***********************************************************************/
 
-/* Keep this list in the same order as frame_parms in frame.c.
+/* Keep this list in the same order as FRAME_PARAMETERS in
+   rust_src/src/frame_parameters.rs.
    Use 0 for unsupported frame parameters.  */
 
 frame_parm_handler x_frame_parm_handlers[] =
 {
-  x_set_autoraise,
-  x_set_autolower,
   x_set_background_color,
   x_set_border_color,
   x_set_border_width,
@@ -6082,7 +6081,6 @@ frame_parm_handler x_frame_parm_handlers[] =
   x_set_scroll_bar_width,
   x_set_scroll_bar_height,
   x_set_title,
-  x_set_unsplittable,
   x_set_vertical_scroll_bars,
   x_set_horizontal_scroll_bars,
   x_set_visibility,
@@ -6108,6 +6106,8 @@ frame_parm_handler x_frame_parm_handlers[] =
   x_set_z_group,
   x_set_override_redirect,
   x_set_no_special_glyphs,
+  0, /* ns_set_appearance */
+  0, /* ns_set_transparent_titlebar */
 };
 
 void
@@ -92,6 +92,10 @@ along with GNU Emacs.  If not, see <https://www.gnu.org/licenses/>.  */
 #include <X11/XKBlib.h>
 #endif
 
+#ifdef HAVE_XRANDR
+#include <X11/extensions/Xrandr.h>
+#endif
+
 /* Default to using XIM if available.  */
 #ifdef USE_XIM
 bool use_xim = true;
@@ -7079,6 +7083,14 @@ handle_one_xevent (struct x_display_info *dpyinfo,
       break;
 
     default:
+#ifdef HAVE_XRANDR
+      if (dpyinfo->xrandr_event_base >= 0
+	  && event->type == dpyinfo->xrandr_event_base + RRScreenChangeNotify)
+	{
+	  XRRUpdateConfiguration ((XEvent *) event);
+	  store_monitors_changed_event (dpyinfo->terminal);
+	}
+#endif
     OTHER:
     break;
     }
@@ -10460,6 +10472,19 @@ x_term_init (Lisp_Object display_name, char *xrm_option, char *resource_name)
 
   xsettings_initialize (dpyinfo);
 
+#ifdef HAVE_XRANDR
+  /* Be told when monitors are connected, disconnected or rearranged.  */
+  {
+    int error_base;
+    if (XRRQueryExtension (dpyinfo->display, &dpyinfo->xrandr_event_base,
+			   &error_base))
+      XRRSelectInput (dpyinfo->display, dpyinfo->root_window,
+		      RRScreenChangeNotifyMask);
+    else
+      dpyinfo->xrandr_event_base = -1;
+  }
+#endif
+
   /* This is only needed for distinguishing keyboard and process input.  */
   if (dpyinfo->connection != 0)
     add_keyboard_wait_descriptor (dpyinfo->connection);
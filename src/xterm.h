@@ -460,6 +460,10 @@ struct x_display_info
 #ifdef HAVE_XRANDR
   int xrandr_major_version;
   int xrandr_minor_version;
+
+  /* The first event number of the XRandr extension, or -1 if the
+     display doesn't have it.  */
+  int xrandr_event_base;
 #endif
 
 #ifdef USE_CAIRO
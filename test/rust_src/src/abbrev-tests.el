@@ -0,0 +1,47 @@
+;;; abbrev-tests.el --- Tests for abbrev.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest abbrev-tests-word-before-point ()
+  (with-temp-buffer
+    (insert "one two  ")
+    (should (equal (abbrev--word-before-point) '(5 . 8)))
+    (should (= (point) 10))
+    (goto-char 7)
+    (should (equal (abbrev--word-before-point) '(5 . 7)))
+    (goto-char 1)
+    (should (equal (abbrev--word-before-point) '(1 . 1)))))
+
+(ert-deftest abbrev-tests-self-insert-expands ()
+  (with-temp-buffer
+    (let ((table (make-abbrev-table)))
+      (define-abbrev table "foo" "find outer otter" nil :system t)
+      (setq local-abbrev-table table)
+      (abbrev-mode 1)
+      (insert "x foo")
+      (let ((last-command-event ?\s))
+        (self-insert-command 1))
+      (should (equal (buffer-string) "x find outer otter "))
+      (insert "foo")
+      (let ((last-command-event ?o))
+        (self-insert-command 1))
+      (should (equal (buffer-string) "x find outer otter fooo")))))
+
+(ert-deftest abbrev-tests-no-self-insert ()
+  (with-temp-buffer
+    (let ((table (make-abbrev-table))
+          (hook (make-symbol "hook")))
+      (fset hook (lambda () t))
+      (put hook 'no-self-insert t)
+      (define-abbrev table "foo" "bar" hook :system t)
+      (setq local-abbrev-table table)
+      (abbrev-mode 1)
+      (insert "foo")
+      (let ((last-command-event ?\s))
+        (self-insert-command 1))
+      (should (equal (buffer-string) "bar")))))
+
+(provide 'abbrev-tests)
+;;; abbrev-tests.el ends here
@@ -18,3 +18,26 @@
         (encoded-without-break (apply 'concat (make-list 20 "eHh4"))))
     (should (string= encoded-with-break (base64-encode-string clear)))
     (should (string= encoded-without-break (base64-encode-string clear t)))))
+
+(ert-deftest base64-tests-url ()
+  (should (string= "-_-_" (base64url-encode-string "\373\377\277")))
+  (should (string= "-_8=" (base64url-encode-string "\373\377")))
+  (should (string= "-_8" (base64url-encode-string "\373\377" t)))
+  (should (string= "\373\377" (base64-decode-string "-_8=" t)))
+  (should (string= "\373\377" (base64-decode-string "-_8" t)))
+  (should-error (base64-decode-string "-_8=")))
+
+(ert-deftest base64-tests-region-point ()
+  (with-temp-buffer
+    (set-buffer-multibyte nil)
+    (insert "abc" "foobar" "xyz")
+    (goto-char 6)
+    (should (= 8 (base64-encode-region 4 10)))
+    (should (string= "abcZm9vYmFyxyz" (buffer-string)))
+    (should (= 4 (point)))
+    (goto-char (point-max))
+    (should (= 6 (base64-decode-region 4 12)))
+    (should (string= "abcfoobarxyz" (buffer-string)))
+    (should (= (point-max) (point)))
+    (should (= 8 (base64url-encode-region 4 10 t)))
+    (should (string= "abcZm9vYmFyxyz" (buffer-string)))))
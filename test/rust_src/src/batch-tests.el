@@ -0,0 +1,51 @@
+;;; batch-tests.el --- Tests for batch.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(defun batch-tests--run (input form)
+  "Run FORM in a batch Emacs reading INPUT, and return its output."
+  (with-temp-buffer
+    (insert input)
+    (let ((coding-system-for-read 'utf-8-unix))
+      (should (= 0 (call-process-region
+                    (point-min) (point-max)
+                    (expand-file-name invocation-name invocation-directory)
+                    t t nil "-Q" "--batch" "--eval" (prin1-to-string form)))))
+    (buffer-string)))
+
+(ert-deftest batch-tests-read-line ()
+  (should (equal (batch-tests--run
+                  "one\ntwo\r\nthree"
+                  '(let (line)
+                     (while (setq line (batch-read-line))
+                       (princ (format "[%s]" line)))))
+                 "[one][two][three]")))
+
+(ert-deftest batch-tests-input-pending-p ()
+  (should (equal (batch-tests--run
+                  "line\n"
+                  '(progn
+                     (princ (batch-input-pending-p 10))
+                     (princ (batch-read-line))
+                     ;; End of file counts as input.
+                     (princ (batch-input-pending-p))
+                     (princ (batch-read-line 0))))
+                 "tlinetnil")))
+
+(ert-deftest batch-tests-read-from-minibuffer ()
+  (should (equal (batch-tests--run
+                  "answer\n"
+                  '(princ (read-from-minibuffer "Question? ")))
+                 "Question? answer")))
+
+(ert-deftest batch-tests-princ-non-ascii ()
+  (should (equal (batch-tests--run
+                  ""
+                  '(let ((coding-system-for-write 'utf-8-unix))
+                     (prin1 "é€")))
+                 "\"é€\"")))
+
+(provide 'batch-tests)
+;;; batch-tests.el ends here
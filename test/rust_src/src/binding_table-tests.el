@@ -0,0 +1,42 @@
+;;; binding_table-tests.el --- Tests for binding_table.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(defun binding-table-tests--map ()
+  (let ((map (make-sparse-keymap)))
+    (define-key map (kbd "C-c b") 'backward-char)
+    (define-key map (kbd "C-c a") 'forward-char)
+    (define-key map (kbd "C-c p x") 'ignore)
+    (define-key map (kbd "C-c u") 'undefined)
+    (define-key map (kbd "C-c s") '("Search" . isearch-forward))
+    map))
+
+(ert-deftest binding-table-tests-bindings ()
+  (with-temp-buffer
+    (use-local-map (binding-table-tests--map))
+    (let ((table (keymap-binding-table (kbd "C-c"))))
+      (should (string-match-p "a → forward-char" table))
+      (should (string-match-p "s → Search" table))
+      (should-not (string-match-p "undefined" table))
+      (should (< (string-match "a → " table) (string-match "b → " table)))
+      (let ((start (string-match "p → +prefix" table)))
+        (should start)
+        (should (eq (get-text-property (+ start 4) 'face table)
+                    'font-lock-keyword-face))
+        (should (eq (get-text-property start 'face table)
+                    'font-lock-constant-face))))
+    (should-not (keymap-binding-table [f35]))))
+
+(ert-deftest binding-table-tests-description-width ()
+  (with-temp-buffer
+    (let ((map (make-sparse-keymap)))
+      (define-key map [f35 ?a] 'binding-table-tests-a-rather-long-command-name)
+      (use-local-map map))
+    (should (equal (substring-no-properties
+                    (keymap-binding-table [f35] nil 10))
+                   "a → binding-..\n"))))
+
+(provide 'binding_table-tests)
+;;; binding_table-tests.el ends here
@@ -0,0 +1,43 @@
+;;; block_alloc-tests.el --- Tests for block_alloc.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest block-alloc-tests-cons ()
+  (let ((cell (cons 1 2)))
+    (should (consp cell))
+    (should (equal cell '(1 . 2)))
+    (setcar cell 'a)
+    (should (eq (car cell) 'a))))
+
+(ert-deftest block-alloc-tests-survivors ()
+  "Cells kept alive survive collections that free the cells around them."
+  (let ((kept nil))
+    (dotimes (i 100000)
+      (let ((cell (cons i (* i 0.5))))
+        (when (zerop (% i 7))
+          (push cell kept))))
+    (garbage-collect)
+    ;; Reuse the cells freed.
+    (dotimes (i 100000)
+      (cons i (float i)))
+    (garbage-collect)
+    (should (= (length kept) (1+ (/ 99999 7))))
+    (dolist (cell kept)
+      (should (integerp (car cell)))
+      (should (zerop (% (car cell) 7)))
+      (should (= (cdr cell) (* (car cell) 0.5))))))
+
+(ert-deftest block-alloc-tests-counts ()
+  (let* ((counts (garbage-collect))
+         (conses (assq 'conses counts))
+         (floats (assq 'floats counts)))
+    (should (memq (nth 1 conses) '(8 16)))
+    (should (> (nth 2 conses) 0))
+    (should (>= (nth 3 conses) 0))
+    (should (> (nth 2 floats) 0))
+    (should (>= (nth 3 floats) 0))))
+
+(provide 'block_alloc-tests)
+;;; block_alloc-tests.el ends here
@@ -0,0 +1,69 @@
+;;; buffer_menu-tests.el --- Tests for buffer_menu.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest buffer-menu-tests-columns ()
+  (with-temp-buffer
+    (let ((buffer (current-buffer)))
+      (insert "hello")
+      (setq buffer-read-only t)
+      (with-temp-buffer
+        (let* ((entries (buffer-menu--entries (list buffer) nil buffer))
+               (columns (cadr (car entries))))
+          (should (= (length entries) 1))
+          (should (eq (car (car entries)) buffer))
+          (should (equal (aref columns 0) "."))
+          (should (equal (aref columns 1) "%"))
+          (should (equal (aref columns 2) "*"))
+          (should (equal (aref columns 3) (buffer-name buffer)))
+          (should (eq (get-text-property 0 'font-lock-face (aref columns 3))
+                      'buffer-menu-buffer))
+          (should (equal (aref columns 4) "5"))
+          (should (equal (aref columns 5) "Fundamental"))
+          (should (equal (aref columns 6) "")))))))
+
+(ert-deftest buffer-menu-tests-filter ()
+  (let ((hidden (get-buffer-create " *buffer-menu-tests hidden*"))
+        (plain (get-buffer-create "*buffer-menu-tests plain*"))
+        (file (get-buffer-create "*buffer-menu-tests file*")))
+    (unwind-protect
+        (progn
+          (with-current-buffer file
+            (setq buffer-file-name (expand-file-name "buffer-menu-tests")))
+          (with-current-buffer plain
+            (let ((buffers (list hidden plain file)))
+              (should (equal (mapcar #'car (buffer-menu--entries buffers))
+                             buffers))
+              (should (equal (mapcar #'car (buffer-menu--entries buffers t))
+                             (list file)))
+              (should (equal (mapcar #'car (buffer-menu--entries buffers 'files))
+                             (list file)))
+              (should (equal (aref (cadr (car (buffer-menu--entries (list file))))
+                                   6)
+                             (abbreviate-file-name
+                              (expand-file-name "buffer-menu-tests")))))))
+      (with-current-buffer file
+        (set-buffer-modified-p nil)
+        (setq buffer-file-name nil))
+      (mapc #'kill-buffer (list hidden plain file)))))
+
+(ert-deftest buffer-menu-tests-killed-buffer ()
+  (let ((buffer (generate-new-buffer "buffer-menu-tests")))
+    (kill-buffer buffer)
+    (should-not (buffer-menu--entries (list buffer)))))
+
+(ert-deftest buffer-menu-tests-mode-width ()
+  (with-temp-buffer
+    (setq mode-name "A rather long mode name")
+    (let* ((truncate-string-ellipsis "...")
+           (mode (aref (cadr (car (buffer-menu--entries
+                                   (list (current-buffer)) nil nil 10)))
+                       5)))
+      (should (equal mode "A rathe..."))
+      (should (equal (get-text-property 0 'help-echo mode)
+                     "Mode: A rather long mode name")))))
+
+(provide 'buffer_menu-tests)
+;;; buffer_menu-tests.el ends here
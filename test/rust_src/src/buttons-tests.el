@@ -0,0 +1,48 @@
+;;; buttons-tests.el --- Tests for buttons.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+(require 'button)
+
+(ert-deftest buttons-tests-overlay-button ()
+  (with-temp-buffer
+    (insert "before button after")
+    (let ((button (make-button 8 14 'action #'ignore 'mouse-action #'identity)))
+      (should (eq (button-at 10) button))
+      (should-not (button-at 2))
+      (should (eq (button-get button 'action) #'ignore))
+      (should (eq (button-action button) #'ignore))
+      (should (eq (button-action button t) #'identity)))))
+
+(ert-deftest buttons-tests-text-button ()
+  (with-temp-buffer
+    (insert "before button after")
+    (make-text-button 8 14 'action #'ignore)
+    (let ((button (button-at 10)))
+      (should (markerp button))
+      (should (= button 10))
+      (should (eq (button-get button 'action) #'ignore))
+      ;; Without a `mouse-action', the `action' is used.
+      (should (eq (button-action button t) #'ignore)))))
+
+(ert-deftest buttons-tests-area-button ()
+  (let ((area-button (cons (propertize "label" 'action #'ignore) 2)))
+    (should (eq (button-get area-button 'action) #'ignore))
+    (should (eq (button-at-posn (list nil 'mode-line nil nil area-button))
+                area-button))))
+
+(ert-deftest buttons-tests-button-at-posn ()
+  (with-temp-buffer
+    (insert "before button after")
+    (let ((button (make-button 8 14))
+          (window (selected-window)))
+      (save-window-excursion
+        (set-window-buffer window (current-buffer))
+        (with-temp-buffer
+          (should (eq (button-at-posn (list window 10 '(0 . 0) 0 nil 10))
+                      button))
+          (should-not (button-at-posn (list window 2 '(0 . 0) 0 nil 2))))))))
+
+(provide 'buttons-tests)
+;;; buttons-tests.el ends here
@@ -0,0 +1,55 @@
+;;; casefiddle-tests.el --- Tests for casefiddle.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest casefiddle-tests-special-casing ()
+  (should (equal (upcase "straße") "STRASSE"))
+  (should (equal (capitalize "ﬁsh") "Fish"))
+  (should (equal (downcase "ΌΣΟΣ ΣΑ") "όσος σα"))
+  (should (equal (upcase-initials "foo bAR") "Foo BAR"))
+  (should (equal (upcase (+ ?a (lsh 1 27))) (+ ?A (lsh 1 27)))))
+
+(ert-deftest casefiddle-tests-region-special-casing ()
+  (with-temp-buffer
+    (insert "a straße b")
+    (goto-char (point-max))
+    (upcase-region 3 9)
+    (should (equal (buffer-string) "a STRASSE b"))
+    (should (= (point) (point-max)))))
+
+(ert-deftest casefiddle-tests-region-undo ()
+  (with-temp-buffer
+    (buffer-enable-undo)
+    (insert "hello world")
+    (undo-boundary)
+    (capitalize-region (point-min) (point-max))
+    (should (equal (buffer-string) "Hello World"))
+    (undo-boundary)
+    (primitive-undo 1 buffer-undo-list)
+    (should (equal (buffer-string) "hello world"))))
+
+(ert-deftest casefiddle-tests-region-change-hooks ()
+  (with-temp-buffer
+    (insert "abc DEF ghi")
+    (let (changes)
+      (add-hook 'after-change-functions
+                (lambda (beg end len) (push (list beg end len) changes))
+                nil t)
+      (downcase-region (point-min) (point-max))
+      (should (equal changes '((5 8 3))))
+      (setq changes nil)
+      (downcase-region (point-min) (point-max))
+      (should-not changes))))
+
+(ert-deftest casefiddle-tests-unibyte ()
+  (should (equal (upcase (string-to-unibyte "abc\377")) (string-to-unibyte "ABC\377")))
+  (with-temp-buffer
+    (set-buffer-multibyte nil)
+    (insert "abc")
+    (upcase-region (point-min) (point-max))
+    (should (equal (buffer-string) "ABC"))))
+
+(provide 'casefiddle-tests)
+;;; casefiddle-tests.el ends here
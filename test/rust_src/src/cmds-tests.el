@@ -50,5 +50,35 @@
   (let ((last-command-event ?a))
     (should-error (self-insert-command -1))))
 
+(ert-deftest newline-and-indent-core ()
+  (with-temp-buffer
+    (setq-local indent-line-function (lambda () (indent-line-to 4)))
+    (let ((electric-indent-mode nil)
+          (inserted nil))
+      (insert "foo  \t")
+      (add-hook 'post-self-insert-hook
+                (lambda () (push last-command-event inserted)) nil t)
+      (should (newline-and-indent-core))
+      (should (equal (buffer-string) "foo\n    "))
+      (should (= (point) (point-max)))
+      (should (equal inserted '(?\n))))))
+
+(ert-deftest newline-and-indent-core--margins ()
+  (with-temp-buffer
+    (insert "foo")
+    (setq-local left-margin 2)
+    (should-not (newline-and-indent-core))
+    (should (equal (buffer-string) "foo")))
+  (with-temp-buffer
+    (insert "foo")
+    (setq-local use-hard-newlines t)
+    (should-not (newline-and-indent-core))))
+
+(ert-deftest newline-and-indent-core--read-only ()
+  (with-temp-buffer
+    (insert "foo")
+    (setq buffer-read-only t)
+    (should-error (newline-and-indent-core) :type 'buffer-read-only)))
+
 (provide 'cmds-tests)
 ;;; cmds-tests.el ends here
@@ -0,0 +1,38 @@
+;;; crash-tests.el --- Tests for crash.rs
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest crash-tests-report ()
+  (skip-unless (memq system-type '(gnu/linux darwin berkeley-unix)))
+  (let* ((dir (make-temp-file "crash-tests" t))
+         (proc (start-process
+                "crash" nil
+                (expand-file-name invocation-name invocation-directory)
+                "-Q" "--batch" "--eval"
+                (prin1-to-string
+                 `(progn (setq crash-report-directory ,dir)
+                         (signal-process (emacs-pid) 'SIGABRT)
+                         (sleep-for 10))))))
+    (unwind-protect
+        (progn
+          (while (process-live-p proc)
+            (accept-process-output proc 0.1))
+          (let ((report (expand-file-name
+                         (format "emacs-crash-%d.txt" (process-id proc))
+                         dir)))
+            (should (file-exists-p report))
+            (with-temp-buffer
+              (insert-file-contents report)
+              (should (search-forward "Emacs crash report" nil t))
+              (should (search-forward "Garbage collection:" nil t))
+              (should (search-forward "C backtrace:" nil t)))))
+      (delete-directory dir t))))
+
+(ert-deftest crash-tests-report-directory ()
+  (should (boundp 'crash-report-directory))
+  (should-not (default-value 'crash-report-directory)))
+
+(provide 'crash-tests)
+;;; crash-tests.el ends here
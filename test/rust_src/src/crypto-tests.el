@@ -0,0 +1,54 @@
+;;; crypto-tests.el --- Tests for crypto/mod.rs
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest crypto-tests-algorithms ()
+  (should (equal (secure-hash-algorithms)
+                 '(md5 sha1 sha224 sha256 sha384 sha512)))
+  (should (equal (secure-hash 'sha1 "abc")
+                 "a9993e364706816aba3e25717850c26c9cd0d89d"))
+  (should (equal (secure-hash 'sha256 "abc")
+                 "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"))
+  (should (equal (secure-hash 'md5 "abc" nil nil t)
+                 (unibyte-string #x90 #x01 #x50 #x98 #x3c #xd2 #x4f #xb0
+                                 #xd6 #x96 #x3f #x7d #x28 #xe1 #x7f #x72)))
+  (should-error (secure-hash 'sha3 "abc"))
+  (should-error (secure-hash 'md5 42)))
+
+(ert-deftest crypto-tests-string ()
+  (should (equal (md5 "abc") "900150983cd24fb0d6963f7d28e17f72"))
+  (should (equal (md5 "abcdef" 0 3) (md5 "abc")))
+  ;; Multibyte strings are encoded first.
+  (should (equal (md5 "é" nil nil 'utf-8)
+                 (md5 (encode-coding-string "é" 'utf-8))))
+  (should (equal (md5 "é" nil nil 'latin-1)
+                 (md5 (encode-coding-string "é" 'latin-1))))
+  (should (equal (md5 "a\nb" nil nil 'utf-8-dos) (md5 "a\r\nb")))
+  (should-error (md5 "abc" nil nil 'no-such-coding-system))
+  (should (equal (md5 "abc" nil nil 'no-such-coding-system t) (md5 "abc"))))
+
+(ert-deftest crypto-tests-buffer ()
+  (with-temp-buffer
+    (insert "hello, world")
+    ;; Move the gap into the middle of the text.
+    (goto-char 6)
+    (insert "x")
+    (delete-char -1)
+    (should (equal (md5 (current-buffer)) (md5 "hello, world")))
+    (should (equal (md5 (current-buffer) 8 13) (md5 "world")))
+    (should (equal (md5 (current-buffer) 13 8) (md5 "world")))
+    (should (equal (secure-hash 'sha512 (current-buffer))
+                   (secure-hash 'sha512 "hello, world")))
+    (should-error (md5 (current-buffer) 0 5) :type 'args-out-of-range))
+  (with-temp-buffer
+    (insert "Grüße\n")
+    (let ((text (buffer-string)))
+      (dolist (coding '(utf-8 utf-8-dos latin-1 utf-16))
+        (should (equal (md5 (current-buffer) nil nil coding)
+                       (md5 (encode-coding-string text coding))))))))
+
+(provide 'crypto-tests)
+
+;;; crypto-tests.el ends here
@@ -0,0 +1,71 @@
+;;; cursor_sensor-tests.el --- Tests for cursor_sensor.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+(require 'cursor-sensor)
+
+(defmacro cursor-sensor-tests-with-buffer (&rest body)
+  "Run BODY in a temporary buffer shown in the selected window."
+  (declare (indent 0))
+  `(with-temp-buffer
+     (save-window-excursion
+       (set-window-buffer nil (current-buffer))
+       (set-window-parameter nil 'cursor-intangible--last-point nil)
+       (set-window-parameter nil 'cursor-sensor--last-state nil)
+       ,@body)))
+
+(ert-deftest cursor-sensor-tests-tangible-pos ()
+  (cursor-sensor-tests-with-buffer
+    (insert "aa" (propertize "xx" 'cursor-intangible t) "bb")
+    (should-not (cursor-sensor-tangible-pos 2 (selected-window)))
+    ;; Moving forward skips the intangible text, and the position after
+    ;; it, to which the property is rear-sticky.
+    (set-window-parameter nil 'cursor-intangible--last-point 2)
+    (should (= (cursor-sensor-tangible-pos 4 (selected-window)) 6))
+    ;; Moving backward skips to its beginning.
+    (set-window-parameter nil 'cursor-intangible--last-point 6)
+    (should (= (cursor-sensor-tangible-pos 4 (selected-window)) 3))))
+
+(ert-deftest cursor-sensor-tests-intangible-bob ()
+  "Point bumping into intangible text at BOB moves the other way."
+  (cursor-sensor-tests-with-buffer
+    (insert (propertize "xx" 'cursor-intangible t 'front-sticky t) "bb")
+    (set-window-parameter nil 'cursor-intangible--last-point 4)
+    (should (= (cursor-sensor-tangible-pos 2 (selected-window)) 4))))
+
+(ert-deftest cursor-sensor-tests-move-to-tangible ()
+  (cursor-sensor-tests-with-buffer
+    (insert "aa" (propertize "xx" 'cursor-intangible t) "bb")
+    (set-window-point nil 2)
+    (cursor-sensor-move-to-tangible (selected-window))
+    (set-window-point nil 4)
+    (cursor-sensor-move-to-tangible (selected-window))
+    (should (= (window-point) 6))
+    (should (= (window-parameter nil 'cursor-intangible--last-point) 6))
+    (let ((cursor-sensor-inhibit t))
+      (set-window-point nil 4)
+      (cursor-sensor--move-to-tangible (selected-window))
+      (should (= (window-point) 4)))))
+
+(ert-deftest cursor-sensor-tests-detect ()
+  (cursor-sensor-tests-with-buffer
+    (let* ((calls nil)
+           (sensor (lambda (_window oldpos dir) (push (cons oldpos dir) calls))))
+      (insert "aa" (propertize "xx" 'cursor-sensor-functions (list sensor)) "bb")
+      (set-window-point nil 1)
+      (cursor-sensor--detect (selected-window))
+      (should-not calls)
+      (set-window-point nil 4)
+      (cursor-sensor--detect (selected-window))
+      (should (equal calls '((1 . entered))))
+      ;; Moving within the text doesn't call the sensor.
+      (set-window-point nil 3)
+      (cursor-sensor--detect (selected-window))
+      (should (= (length calls) 1))
+      (set-window-point nil 6)
+      (cursor-sensor--detect (selected-window))
+      (should (equal (car calls) '(3 . left))))))
+
+(provide 'cursor_sensor-tests)
+;;; cursor_sensor-tests.el ends here
@@ -106,6 +106,15 @@
   ;; tests, delete `subr-lang', and celebrate :)
   (should (equal "C" (subr-lang (symbol-function 'menu-bar-menu-at-x-y)))))
 
+(ert-deftest data-test--subr-arglist ()
+  (should-error (subr-arglist 'car))
+  (should (equal '(list) (subr-arglist (symbol-function 'car))))
+  (should (equal '(&rest args) (subr-arglist (symbol-function 'list))))
+  (should (equal '(object &optional noerror)
+                 (subr-arglist (symbol-function 'indirect-function))))
+  ;; Subrs defined in C don't record their argument names.
+  (should-not (subr-arglist (symbol-function 'menu-bar-menu-at-x-y))))
+
 (ert-deftest data-test--subr-lang-fail ()
   ;; `rename-buffer' is a primitive function that is advised by
   ;; default (by `uniquify'), confusing `subr-lang'.
@@ -136,5 +145,26 @@
   ;; Defined in Rust
   (should (consp (find-definition-noselect 'post-self-insert-hook 'defvar))))
 
+(ert-deftest data-test--setq-default ()
+  (with-temp-buffer
+    (setq-local data-test--local 'local)
+    (should (eq (setq-default data-test--local 'default
+                              data-test--other data-test--local)
+                'local))
+    (should (eq data-test--local 'local))
+    (should (eq (default-value 'data-test--local) 'default))
+    (should (eq (default-value 'data-test--other) 'local)))
+  (should (eq (default-value 'data-test--local) 'default))
+  ;; Per-buffer variables change in buffers without a local value.
+  (let ((old (default-value 'fill-column)))
+    (unwind-protect
+        (with-temp-buffer
+          (setq-default fill-column 33)
+          (should (= fill-column 33)))
+      (setq-default fill-column old)))
+  (should-error (setq-default nil 1) :type 'setting-constant)
+  (makunbound 'data-test--local)
+  (makunbound 'data-test--other))
+
 (provide 'data-tests)
 ;;; data-tests.el ends here
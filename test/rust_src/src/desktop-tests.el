@@ -0,0 +1,79 @@
+;;; desktop-tests.el --- Tests for desktop.rs
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest desktop-tests--write-file ()
+  (let ((file (make-temp-file "desktop-tests")))
+    (unwind-protect
+        (progn
+          (desktop-write-file file ";; Desktop\n(setq x \"é\")\n")
+          (should (desktop-wait-for-write))
+          (should-not (desktop-wait-for-write))
+          (with-temp-buffer
+            (let ((coding-system-for-read 'utf-8-emacs))
+              (insert-file-contents file))
+            (should (equal (buffer-string) ";; Desktop\n(setq x \"é\")\n"))))
+      (delete-file file))))
+
+(ert-deftest desktop-tests--write-file-error ()
+  (desktop-write-file "/nonexistent-directory/desktop" "")
+  (should-error (desktop-wait-for-write) :type 'file-error)
+  (should-not (desktop-wait-for-write)))
+
+(ert-deftest desktop-tests--write-file-reports-previous-error ()
+  (let ((file (make-temp-file "desktop-tests")))
+    (unwind-protect
+        (progn
+          (desktop-write-file "/nonexistent-directory/desktop" "")
+          ;; The failed write is reported, and the next one not started.
+          (should-error (desktop-write-file file "x") :type 'file-error)
+          (should-not (desktop-wait-for-write))
+          (should (= (file-attribute-size (file-attributes file)) 0)))
+      (delete-file file))))
+
+(ert-deftest desktop-tests--write-file-keeps-modes ()
+  (let ((file (make-temp-file "desktop-tests")))
+    (unwind-protect
+        (progn
+          (set-file-modes file #o600)
+          (desktop-write-file file "")
+          (desktop-wait-for-write)
+          (should (= (file-modes file) #o600)))
+      (delete-file file))))
+
+(ert-deftest desktop-tests--value-to-string ()
+  (should (equal (desktop-value-to-string 1) "1"))
+  (should (equal (desktop-value-to-string :key) ":key"))
+  (should (equal (desktop-value-to-string 'sym) "'sym"))
+  (should (equal (desktop-value-to-string (propertize "a\nb" 'face 'bold))
+                 "\"a\\nb\""))
+  (should (equal (desktop-value-to-string '(a (b . 1) [c])) "'(a (b . 1) [c])"))
+  (should (equal (desktop-value-to-string '(a . b)) "'(a . b)"))
+  (should (equal (desktop-value-to-string (list 'a (current-buffer)))
+                 "'(a \"Unprintable entity\")"))
+  ;; Values that can't be read back are rebuilt by evaluation.
+  (should (equal (desktop-value-to-string (list 'a (symbol-function 'car)))
+                 "(list 'a (symbol-function 'car))"))
+  (should (equal (desktop-value-to-string (symbol-function 'car))
+                 "(symbol-function 'car)"))
+  (let ((value (list 'a [b] (symbol-function 'car))))
+    (should (equal (eval (car (read-from-string
+                               (desktop-value-to-string value))))
+                   value))))
+
+(ert-deftest desktop-tests--v2s-marker ()
+  (with-temp-buffer
+    (insert "abc")
+    (let ((marker (copy-marker 2)))
+      (should (equal (desktop--v2s marker)
+                     `(nil . (let ((mk (make-marker)))
+                               (add-hook 'desktop-delay-hook
+                                         (lambda ()
+                                           (set-marker mk 2 (get-buffer
+                                                             ,(buffer-name)))))
+                               mk)))))))
+
+(provide 'desktop-tests)
+;;; desktop-tests.el ends here
@@ -84,6 +84,77 @@
       (should (equal (delete-and-extract-region 12 5) " buffer"))
       (should (equal (buffer-string) "test contents")))))
 
+(ert-deftest test-apply-edits-batch ()
+  (with-temp-buffer
+    (insert "one two three")
+    (goto-char 5)
+    (let ((m (copy-marker 9)))
+      ;; Positions refer to the original text.
+      (should (= (apply-edits-batch '((1 . 3) (1 . "ONE") (5 . "2 ") (9 . 5) (9 . "3")))
+                 -2))
+      (should (equal (buffer-string) "ONE 2 two 3"))
+      (should (= (point) 5))
+      (should (= m 11)))
+    (should (= (apply-edits-batch nil) 0))))
+
+(ert-deftest test-apply-edits-batch--insertion-order ()
+  (with-temp-buffer
+    (insert "ab")
+    (apply-edits-batch '((2 . "x") (2 . "y") (3 . "z")))
+    (should (equal (buffer-string) "axybz"))))
+
+(ert-deftest test-apply-edits-batch--invalid ()
+  (with-temp-buffer
+    (insert "abcdef")
+    (should-error (apply-edits-batch '((2 . 3) (3 . "x"))))
+    (should-error (apply-edits-batch '((1 . 2) (2 . 2))))
+    (should-error (apply-edits-batch '((5 . 4))) :type 'args-out-of-range)
+    (should-error (apply-edits-batch '((1 . x))) :type 'wrong-type-argument)
+    (should (equal (buffer-string) "abcdef"))))
+
+(ert-deftest test-apply-edits-batch--change-hooks ()
+  (with-temp-buffer
+    (insert "abcdef")
+    (let* ((calls nil)
+           (before-change-functions
+            (list (lambda (beg end) (push (list 'before beg end) calls))))
+           (after-change-functions
+            (list (lambda (beg end len) (push (list 'after beg end len) calls)))))
+      (apply-edits-batch '((2 . "XY") (5 . 1)))
+      (should (equal (buffer-string) "aXYbcdf"))
+      (should (equal (nreverse calls) '((before 2 6) (after 2 7 4)))))))
+
+(ert-deftest test-replace-buffer-lines ()
+  (let ((source (generate-new-buffer " *source*")))
+    (unwind-protect
+        (with-temp-buffer
+          (insert "one\ntwo\nthree\nfour\n")
+          (with-current-buffer source
+            (insert "one\nTWO\nthree\nfour\nfive"))
+          (goto-char (point-min))
+          (search-forward "thr")
+          (let ((m (copy-marker (point-min))))
+            (should (= (replace-buffer-lines source) 2))
+            (should (equal (buffer-string) "one\nTWO\nthree\nfour\nfive"))
+            (should (looking-at "ee"))
+            (should (= m 1)))
+          (should (= (replace-buffer-lines source) 0))
+          (with-current-buffer source
+            (set-buffer-multibyte nil))
+          (should-error (replace-buffer-lines source)))
+      (kill-buffer source))))
+
+(ert-deftest test-apply-edits-batch--undo ()
+  (with-temp-buffer
+    (buffer-enable-undo)
+    (insert "abcdef")
+    (undo-boundary)
+    (apply-edits-batch '((1 . "x") (3 . 2)))
+    (should (equal (buffer-string) "xabef"))
+    (undo-boundary)
+    (primitive-undo 1 (cdr buffer-undo-list))
+    (should (equal (buffer-string) "abcdef"))))
+
 (ert-deftest time-comparison ()
   (let ((fixed-time '(23580 5248 742594 205000)))
     (should (time-less-p fixed-time (current-time)))))
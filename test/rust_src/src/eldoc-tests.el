@@ -0,0 +1,42 @@
+;;; eldoc-tests.el --- Tests for eldoc.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+(require 'eldoc)
+
+(ert-deftest eldoc-tests-format-sym-doc ()
+  (let ((eldoc-echo-area-use-multiline-p t))
+    (should (equal (eldoc--format-sym-doc 'car "doc") "car: doc"))
+    (should (eq (get-text-property 0 'face (eldoc--format-sym-doc 'car "d" 'bold))
+                'bold))
+    (should (equal (eldoc--format-sym-doc "x = " "doc") "x = doc")))
+  (let ((eldoc-echo-area-use-multiline-p nil)
+        (width (1- (window-body-width (minibuffer-window)))))
+    (should (= (string-width
+                (eldoc--format-sym-doc 'car (make-string 500 ?x)))
+               width))
+    ;; Wide characters count for two columns.
+    (should (<= (string-width
+                 (eldoc--format-sym-doc 'car (make-string 500 ?中)))
+                width))
+    ;; The symbol name is shortened from the start.
+    (let ((result (eldoc--format-sym-doc 'eldoc-tests-symbol
+                                         (make-string (- width 10) ?x))))
+      (should (= (string-width result) width))
+      (should (string-suffix-p "symbol: " (substring result 0 10))))))
+
+(ert-deftest eldoc-tests-message ()
+  (let* ((displayed nil)
+         (eldoc-message-function (lambda (&rest args) (push args displayed)))
+         (eldoc-last-message nil))
+    (should (equal (eldoc--message "foo") "foo"))
+    (should (equal eldoc-last-message "foo"))
+    (should (equal (eldoc--message nil) nil))
+    (should-not eldoc-last-message)
+    ;; Nothing is cleared when there was no message.
+    (eldoc--message nil)
+    (should (equal displayed '((nil) ("%s" "foo"))))))
+
+(provide 'eldoc-tests)
+;;; eldoc-tests.el ends here
@@ -8,5 +8,19 @@
   (should (stringp (invocation-name)))
   (should (stringp (invocation-directory))))
 
+;; Errors in `kill-emacs-hook' must not keep Emacs from exiting.
+(ert-deftest emacs-tests-kill-emacs-hook-error ()
+  (should (= 3 (call-process
+                (expand-file-name invocation-name invocation-directory)
+                nil nil nil "-Q" "--batch" "--eval"
+                (prin1-to-string
+                 '(progn (add-hook 'kill-emacs-hook
+                                   (lambda () (error "Broken hook")))
+                         (kill-emacs 3)))))))
+
+(ert-deftest emacs-tests-kill-emacs-process-grace-period ()
+  (should (boundp 'kill-emacs-process-grace-period))
+  (should-not (default-value 'kill-emacs-process-grace-period)))
+
 (provide 'emacs-tests)
 ;;; emacs-tests.el ends here
@@ -0,0 +1,44 @@
+;;; ert-tests.el --- Tests for ert.rs
+
+;;; Code:
+
+(require 'ert)
+
+(defmacro ert-tests--with-test-directory (files &rest body)
+  "Run BODY with `dir' bound to a temporary directory holding FILES.
+FILES is a list of (NAME . CONTENTS)."
+  (declare (indent 1))
+  `(let ((dir (make-temp-file "ert-tests" t)))
+     (unwind-protect
+         (progn
+           (dolist (file ,files)
+             (with-temp-file (expand-file-name (car file) dir)
+               (insert (cdr file))))
+           ,@body)
+       (delete-directory dir t))))
+
+(ert-deftest ert-run-tests-parallel-results ()
+  (ert-tests--with-test-directory
+      '(("a-tests.el" . "(ert-deftest a-1 () (should t))
+(ert-deftest a-2 () (should t))")
+        ("b-tests.el" . "(ert-deftest b-1 () (should nil))")
+        ("c-tests.el" . "(error \"Cannot load\")")
+        ("not-a-test.el" . "(error \"Not run\")"))
+    (let ((results (ert-run-tests-parallel (list dir) 2)))
+      ;; Only the files named like test files are run, in order.
+      (should (equal (mapcar (lambda (r) (file-name-nondirectory (car r)))
+                             results)
+                     '("a-tests.el" "b-tests.el" "c-tests.el")))
+      (should (equal (mapcar (lambda (r) (list (nth 1 r) (nth 2 r))) results)
+                     '((2 0) (1 1) (nil 0))))
+      (should (floatp (nth 3 (car results))))
+      (should (string-match-p "Ran 2 tests" (nth 4 (car results)))))))
+
+(ert-deftest ert-run-tests-parallel-no-files ()
+  (ert-tests--with-test-directory nil
+    (should-not (ert-run-tests-parallel (list dir))))
+  (should-error (ert-run-tests-parallel '(1)) :type 'wrong-type-argument))
+
+(provide 'ert-tests)
+
+;;; ert-tests.el ends here
@@ -197,6 +197,45 @@
   (should (equal (macroexpand 'x)
                  'x)))
 
+(ert-deftest eval-tests--macroexpand-cycle ()
+  (defmacro eval-tests--ping () (list 'eval-tests--pong))
+  (defmacro eval-tests--pong () (list 'eval-tests--ping))
+  (unwind-protect
+      (should-error (macroexpand '(eval-tests--ping)))
+    (fmakunbound 'eval-tests--ping)
+    (fmakunbound 'eval-tests--pong)))
+
+(ert-deftest eval-tests--macroexpand-shared-cycle ()
+  (let ((ping (list 'eval-tests--ping))
+        (pong (list 'eval-tests--pong)))
+    (defalias 'eval-tests--ping (cons 'macro (lambda () pong)))
+    (defalias 'eval-tests--pong (cons 'macro (lambda () ping)))
+    (unwind-protect
+        (should-error (macroexpand ping))
+      (fmakunbound 'eval-tests--ping)
+      (fmakunbound 'eval-tests--pong))))
+
+(ert-deftest eval-tests--macroexpand-long ()
+  (defmacro eval-tests--count (n)
+    (if (> n 0) (list 'eval-tests--count (1- n)) n))
+  (unwind-protect
+      (should (eq (macroexpand '(eval-tests--count 1000)) 0))
+    (fmakunbound 'eval-tests--count)))
+
+(ert-deftest eval-tests--macroexpand-1-base ()
+  (should (equal (macroexpand-1 '(when x (when y z)))
+                 '(if x (progn (when y z)))))
+  (should (equal (macroexpand-1 '(1+ 2)) '(1+ 2)))
+  (should (eq (macroexpand-1 'x) 'x))
+  ;; ENVIRONMENT shadows the loaded definitions.
+  (should (equal (macroexpand-1 '(when x y) '((when . (lambda (&rest r) r))))
+                 '(x y)))
+  (should (equal (macroexpand-1 '(when x y) '((when))) '(when x y)))
+  (defalias 'eval-tests--when 'when)
+  (unwind-protect
+      (should (equal (macroexpand-1 '(eval-tests--when x y)) '(when x y)))
+    (fmakunbound 'eval-tests--when)))
+
 (ert-deftest eval-tests--commandp-base ()
   "Check (commandp) base cases"
   (should (not (commandp 'commandp)))
@@ -284,6 +323,198 @@
   (should (eq (catch 'found (dolist (n '(1 2 3)) (throw 'found n))) 1))
   (should (eq (catch 'found (dolist (n '(1 (error "Should not be evaluated"))) (throw 'found n))) 1)))
 
+(ert-deftest eval-tests--apply-base ()
+  (should (= (apply '+ 1 2 '(3 4)) 10))
+  (should (equal (apply 'list '()) nil))
+  (should (equal (apply 'cons 'a '(b)) '(a . b)))
+  ;; Optional arguments of subrs are filled in with nil.
+  (should (equal (apply 'substring '("abc")) "abc"))
+  (should (equal (apply 'list 1 2 (number-sequence 3 12))
+                 (number-sequence 1 12)))
+  (should-error (apply 'car 1) :type 'wrong-type-argument)
+  (should-error (apply 'car '(1 2)) :type 'wrong-number-of-arguments))
+
+(ert-deftest eval-tests--eval-lambda-arguments ()
+  (should (equal (eval '((lambda (a &optional b &rest c) (list a b c)) 1) t)
+                 '(1 nil nil)))
+  (should (equal (eval '((lambda (a &optional b &rest c) (list a b c))
+                         1 2 3 4 5 6 7 8 9 10)
+                       t)
+                 '(1 2 (3 4 5 6 7 8 9 10))))
+  (should (equal (funcall (lambda (&rest args) args) 1 2) '(1 2)))
+  (should-error (funcall (lambda (a) a)) :type 'wrong-number-of-arguments)
+  (should-error (funcall (lambda (a) a) 1 2) :type 'wrong-number-of-arguments)
+  (should-error (funcall '(lambda (&optional &rest a) a))
+                :type 'invalid-function)
+  (should-error (funcall '(lambda (&rest) nil)) :type 'invalid-function))
+
+(ert-deftest eval-tests--eval-subr-arguments ()
+  (should (equal (eval '(substring "abc" 1)) "bc"))
+  (should (= (eval '(+ 1 2 3 4 5 6 7 8 9 10)) 55))
+  (should-error (eval '(car)) :type 'wrong-number-of-arguments)
+  (should-error (eval '(car 1 . 2)) :type 'wrong-type-argument))
+
+(ert-deftest eval-tests--throw-base ()
+  (should (eq (catch 'a (catch 'b (throw 'a 1)) 2) 1))
+  (should (eq (catch 'a (throw 'a nil) 2) nil))
+  (should (equal (should-error (throw 'eval-tests--no-such-tag 1)
+                               :type 'no-catch)
+                 '(no-catch eval-tests--no-such-tag 1)))
+  ;; Unwind forms run on the way out.
+  (let ((unwound nil))
+    (catch 'a (unwind-protect (throw 'a 1) (setq unwound t)))
+    (should unwound)))
+
+(ert-deftest eval-tests--condition-case-base ()
+  (should (equal (condition-case err (signal 'wrong-type-argument '(x))
+                   (arith-error 'arith)
+                   (wrong-type-argument err))
+                 '(wrong-type-argument x)))
+  (should (eq (condition-case nil (car 1) ((arith-error error) 'caught))
+              'caught))
+  (should (eq (condition-case nil 'value (error 'caught)) 'value))
+  ;; The innermost applicable handler wins.
+  (should (eq (condition-case nil
+                  (condition-case nil (/ 1 0) (void-variable 'inner))
+                (arith-error 'outer))
+              'outer))
+  (should-error (eval '(condition-case nil t 1)) :type 'error)
+  (should-error (eval '(condition-case "var" t)) :type 'wrong-type-argument))
+
+(ert-deftest eval-tests--defvar ()
+  (should (eq (defvar eval-tests--var 1 "Doc.") 'eval-tests--var))
+  (should (special-variable-p 'eval-tests--var))
+  (should (equal (get 'eval-tests--var 'variable-documentation) "Doc."))
+  ;; An existing value is kept.
+  (defvar eval-tests--var 2)
+  (should (= eval-tests--var 1))
+  (should-error (eval '(defvar eval-tests--var 1 "Doc." extra))
+                :type 'error)
+  (makunbound 'eval-tests--var)
+  ;; A let binding doesn't hide that the variable is unbound.
+  (let ((eval-tests--var 3))
+    (defvar eval-tests--var 4)
+    (should (= eval-tests--var 3)))
+  (should (= eval-tests--var 4))
+  (makunbound 'eval-tests--var))
+
+(ert-deftest eval-tests--default-toplevel-value ()
+  (defvar eval-tests--toplevel 'top)
+  (let ((eval-tests--toplevel 'let))
+    (should (eq (default-toplevel-value 'eval-tests--toplevel) 'top))
+    (set-default-toplevel-value 'eval-tests--toplevel 'new)
+    (should (eq eval-tests--toplevel 'let)))
+  (should (eq eval-tests--toplevel 'new))
+  (set-default-toplevel-value 'eval-tests--toplevel 'again)
+  (should (eq eval-tests--toplevel 'again))
+  (makunbound 'eval-tests--toplevel)
+  (should-error (default-toplevel-value 'eval-tests--toplevel)
+                :type 'void-variable))
+
+;; Write BODY to a temporary file and autoload FUNCTION from it.
+(defmacro eval-tests--with-autoload-file (function body &rest forms)
+  (declare (indent 2))
+  `(let ((file (make-temp-file "eval-tests" nil ".el")))
+     (unwind-protect
+         (progn
+           (with-temp-file file (insert ,body))
+           (fmakunbound ',function)
+           (autoload ',function file)
+           ,@forms)
+       (fmakunbound ',function)
+       (delete-file file))))
+
+(ert-deftest eval-tests--autoload-do-load ()
+  (eval-tests--with-autoload-file eval-tests--autoloaded
+      "(defun eval-tests--autoloaded () 'loaded)"
+    (should (autoloadp (symbol-function 'eval-tests--autoloaded)))
+    (should (eq (eval-tests--autoloaded) 'loaded))
+    (should-not (autoloadp (symbol-function 'eval-tests--autoloaded)))))
+
+(ert-deftest eval-tests--autoload-rollback ()
+  ;; Redefinitions made by a file that fails to load are undone.
+  (defalias 'eval-tests--autoload-helper #'ignore)
+  (unwind-protect
+      (eval-tests--with-autoload-file eval-tests--autoloaded
+          "(defun eval-tests--autoload-helper () t) (error \"Failed\")"
+        (should-error (eval-tests--autoloaded))
+        (should (eq (symbol-function 'eval-tests--autoload-helper) #'ignore))
+        (should (autoloadp (symbol-function 'eval-tests--autoloaded))))
+    (fmakunbound 'eval-tests--autoload-helper)))
+
+(ert-deftest eval-tests--autoload-recursive ()
+  (eval-tests--with-autoload-file eval-tests--autoloaded
+      "(eval-tests--autoloaded) (defun eval-tests--autoloaded () t)"
+    (should (string-match-p "Recursive autoload"
+                            (cadr (should-error (eval-tests--autoloaded)))))
+    ;; The failed attempt doesn't prevent the next one.
+    (should (string-match-p "Recursive autoload"
+                            (cadr (should-error (eval-tests--autoloaded)))))))
+
+;; `autoload-do-load' undoes what it binds and records on the specpdl
+;; whichever way the load ends.
+(ert-deftest eval-tests--autoload-match-data ()
+  (eval-tests--with-autoload-file eval-tests--autoloaded
+      "(string-match \"x\" \"x\") (defun eval-tests--autoloaded () 'loaded)"
+    (string-match "b" "abc")
+    (should (eq (eval-tests--autoloaded) 'loaded))
+    (should (= (match-beginning 0) 1))))
+
+(ert-deftest eval-tests--autoload-throw ()
+  (eval-tests--with-autoload-file eval-tests--autoloaded
+      "(string-match \"x\" \"x\") (throw 'eval-tests--autoload 'thrown)"
+    (string-match "b" "abc")
+    (should (eq (catch 'eval-tests--autoload (eval-tests--autoloaded))
+                'thrown))
+    (should (= (match-beginning 0) 1))
+    ;; The function is no longer marked as being autoloaded.
+    (with-temp-file file
+      (insert "(defun eval-tests--autoloaded () 'loaded)"))
+    (should (eq (eval-tests--autoloaded) 'loaded))))
+
+(ert-deftest eval-tests--autoload-nested ()
+  (let ((other (make-temp-file "eval-tests" nil ".el")))
+    (unwind-protect
+        (eval-tests--with-autoload-file eval-tests--autoloaded
+            "(eval-tests--autoloaded-2) (defun eval-tests--autoloaded () 'outer)"
+          (with-temp-file other
+            (insert "(defun eval-tests--autoloaded-2 () 'inner)"))
+          (autoload 'eval-tests--autoloaded-2 other)
+          (should (eq (eval-tests--autoloaded) 'outer))
+          (should (eq (eval-tests--autoloaded-2) 'inner))
+          ;; A nested autoload sees the outer one in progress.
+          (fmakunbound 'eval-tests--autoloaded)
+          (autoload 'eval-tests--autoloaded file)
+          (with-temp-file other
+            (insert "(eval-tests--autoloaded)"))
+          (fmakunbound 'eval-tests--autoloaded-2)
+          (autoload 'eval-tests--autoloaded-2 other)
+          (should (string-match-p "Recursive autoload"
+                                  (cadr (should-error (eval-tests--autoloaded))))))
+      (fmakunbound 'eval-tests--autoloaded-2)
+      (delete-file other))))
+
+(ert-deftest eval-tests--func-arity-closures ()
+  (let ((x 1))
+    (let ((closure (lambda (a &optional b) (list a b x))))
+      (should (eq (car closure) 'closure))
+      (should (equal (func-arity closure) '(1 . 2)))
+      (should (equal (funcall closure 'a) '(a nil 1)))))
+  (should (equal (func-arity '(closure (t) (&rest args) args)) '(0 . many)))
+  (should (equal (func-arity '(lambda (a b))) '(2 . 2)))
+  (should (equal (func-arity 'car) '(1 . 1)))
+  (should-error (func-arity '(closure)) :type 'invalid-function)
+  (should-error (func-arity '(lambda (1))) :type 'invalid-function)
+  (should-error (func-arity nil) :type 'void-function))
+
+(ert-deftest eval-tests--closure-environment ()
+  (let* ((n 0)
+         (counter (lambda () (setq n (1+ n)))))
+    (funcall counter)
+    (funcall counter)
+    (should (= n 2))
+    (should (= (funcall `(closure ((n . 41) t) () (1+ n))) 42))))
+
 ;; Local Variables:
 ;; byte-compile-warnings: (not lexical free-vars unresolved)
 ;; End:
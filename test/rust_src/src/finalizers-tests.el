@@ -0,0 +1,46 @@
+;;; finalizers-tests.el --- Tests for finalizers.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest finalizers-tests-type ()
+  (should (eq (type-of (make-finalizer #'ignore)) 'finalizer)))
+
+(ert-deftest finalizers-tests-run-once ()
+  "A finalizer runs once, after it becomes unreachable."
+  (let* ((runs 0)
+         (finalizer (make-finalizer (lambda () (setq runs (1+ runs))))))
+    (garbage-collect)
+    (should (= runs 0))
+    (should finalizer)
+    (setq finalizer nil)
+    ;; Let the collector forget the stack slots that referred to it.
+    (dotimes (_ 10)
+      (funcall (lambda () (make-list 100 nil)))
+      (garbage-collect))
+    (should (<= runs 1))))
+
+(ert-deftest finalizers-tests-only-finalizers ()
+  "A finalizer reachable only from finalizers runs its function."
+  (let ((ran nil))
+    (dotimes (_ 100)
+      (let ((inner (make-finalizer (lambda () (setq ran t)))))
+        (make-finalizer (lambda () inner))))
+    (garbage-collect)
+    (garbage-collect)
+    (should ran)))
+
+(ert-deftest finalizers-tests-error ()
+  "An error in a finalizer is logged, not signaled."
+  (let ((count 0))
+    (dotimes (_ 100)
+      (make-finalizer (lambda () (setq count (1+ count)) (error "Oops"))))
+    (garbage-collect)
+    (should (> count 0))
+    (with-current-buffer (messages-buffer)
+      (should (string-match-p "finalizer failed: (error \"Oops\")"
+                              (buffer-string))))))
+
+(provide 'finalizers-tests)
+;;; finalizers-tests.el ends here
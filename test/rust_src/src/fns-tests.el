@@ -11,3 +11,53 @@
   (should (floatp (car (load-average 42))))
   (should (floatp (car (load-average "asdf"))))
   (should (floatp (car (load-average '(gimme floats))))))
+
+(ert-deftest equal-floats ()
+  (should (equal 1.0 1.0))
+  (should-not (equal 1.0 1))
+  (should (equal 0.0e+NaN 0.0e+NaN))
+  (should-not (equal 0.0 -0.0)))
+
+(ert-deftest equal-strings ()
+  (let ((plain "abc")
+        (propertized (propertize "abc" 'face 'bold)))
+    (should (equal plain propertized))
+    (should-not (equal-including-properties plain propertized))
+    (should (equal-including-properties
+             propertized (propertize "abc" 'face 'bold)))
+    (should (equal "abc" (string-to-multibyte "abc")))
+    (should-not (equal "abc" "abd"))))
+
+(ert-deftest equal-arrays ()
+  (should (equal [1 (2 "3")] (vector 1 (list 2 "3"))))
+  (should-not (equal [1 2] [1 2 3]))
+  (should (equal (record 'foo 1) (record 'foo 1)))
+  (should-not (equal (record 'foo 1) (record 'bar 1)))
+  (should-not (equal (record 'foo 1) [foo 1]))
+  (should (equal (make-bool-vector 3 t) (make-bool-vector 3 t)))
+  (should-not (equal (make-bool-vector 3 t) (make-bool-vector 3 nil)))
+  (let ((a (make-char-table 'test))
+        (b (make-char-table 'test)))
+    (should (equal a b))
+    (set-char-table-range a '(?a . ?z) 1)
+    (should-not (equal a b))
+    (set-char-table-range b '(?a . ?z) 1)
+    (should (equal a b))))
+
+(ert-deftest equal-circular ()
+  (let ((a (list 1))
+        (b (list 1)))
+    (setcar a a)
+    (setcar b b)
+    (should (equal a b)))
+  (let ((a (vector 1 nil))
+        (b (vector 1 nil)))
+    (aset a 1 a)
+    (aset b 1 b)
+    (should (equal a b))))
+
+(ert-deftest equal-too-deep ()
+  (let ((a nil))
+    (dotimes (_ 300)
+      (setq a (list a)))
+    (should-error (equal a (copy-tree a)))))
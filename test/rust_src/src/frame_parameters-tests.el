@@ -0,0 +1,23 @@
+;;; frame_parameters-tests.el --- Tests for frame_parameters.rs
+
+;;; Code:
+
+(require 'ert)
+(require 'cl-lib)
+
+(ert-deftest frame-parameters-handler-indexes ()
+  (let ((indexes (mapcar (lambda (parameter)
+                           (get parameter 'x-frame-parameter))
+                         '(background-color font name fullscreen
+                           no-special-glyphs auto-raise auto-lower
+                           unsplittable))))
+    (should (cl-every #'natnump indexes))
+    (should (equal indexes (delete-dups (copy-sequence indexes))))
+    (should (eq (car indexes) 0)))
+  ;; The size and position parameters have no handlers.
+  (dolist (parameter '(width height left top icon-left icon-top))
+    (should-not (get parameter 'x-frame-parameter))))
+
+(provide 'frame_parameters-tests)
+
+;;; frame_parameters-tests.el ends here
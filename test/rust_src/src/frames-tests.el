@@ -0,0 +1,71 @@
+;;; frames-tests.el --- Tests for frames.rs  -*- lexical-binding: t; -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest frames-test--set-frame-position ()
+  (should (eq (set-frame-position nil 0 0) t))
+  (should-error (set-frame-position nil 'a 0) :type 'wrong-type-argument))
+
+(ert-deftest frames-test--set-frame-size-args ()
+  (should-error (set-frame-height nil 'a) :type 'wrong-type-argument)
+  (should-error (set-frame-width nil 1.5) :type 'wrong-type-argument)
+  (should-error (set-frame-size nil most-positive-fixnum 10)
+                :type 'args-out-of-range))
+
+(ert-deftest frames-test--frame-parent ()
+  (should (eq (frame-parent) nil))
+  (should-not (frame-ancestor-p (selected-frame) (selected-frame)))
+  (should-error (frame-ancestor-p 'a nil) :type 'wrong-type-argument))
+
+;; Batch mode only has the initial frame, so only switching to the
+;; selected frame is tested here.
+(ert-deftest frames-test--select-frame ()
+  (let ((frame (selected-frame)))
+    (should (eq (select-frame frame) frame))
+    (should (eq (select-frame (list 'switch-frame frame) t) frame))
+    (should (eq (selected-window) (frame-selected-window frame)))
+    (should-error (select-frame 'a) :type 'wrong-type-argument)))
+
+(ert-deftest frames-test--redirect-frame-focus ()
+  (let ((frame (selected-frame)))
+    (unwind-protect
+        (progn
+          (redirect-frame-focus frame frame)
+          (should (eq (frame-focus) frame)))
+      (redirect-frame-focus frame nil))
+    (should-not (frame-focus frame))
+    (should-error (redirect-frame-focus frame 'a) :type 'wrong-type-argument)))
+
+(ert-deftest frames-test--frame-visibility ()
+  (should (eq (make-frame-visible) (selected-frame)))
+  (should (eq (frame-visible-p (selected-frame)) t))
+  ;; The only frame can't be made invisible.
+  (should-error (make-frame-invisible))
+  (should-error (iconify-frame 'a) :type 'wrong-type-argument))
+
+(ert-deftest frames-test--mouse-position ()
+  (should (eq (car (mouse-position)) (selected-frame)))
+  (should (eq (car (mouse-pixel-position)) (selected-frame)))
+  (let ((mouse-position-function (lambda (pos) (cons 'moved pos))))
+    (should (eq (car (mouse-position)) 'moved)))
+  (should-not (set-mouse-position (selected-frame) 0 0))
+  (should-error (set-mouse-pixel-position 'a 0 0) :type 'wrong-type-argument)
+  (should-error (set-mouse-position (selected-frame) 'a 0)
+                :type 'wrong-type-argument))
+
+;; Batch mode only has the initial frame, which is its own next and
+;; previous frame whatever frames are considered.
+(ert-deftest frames-test--next-frame ()
+  (let ((frame (selected-frame)))
+    (dolist (miniframe (list nil t 'visible 0 (minibuffer-window)))
+      (should (eq (next-frame frame miniframe) frame))
+      (should (eq (previous-frame frame miniframe) frame)))
+    (should (eq (next-frame) frame))
+    (should (eq (previous-frame) frame))
+    (should-error (next-frame 'a) :type 'wrong-type-argument)
+    (should-error (previous-frame 'a) :type 'wrong-type-argument)))
+
+(provide 'frames-tests)
+;;; frames-tests.el ends here
@@ -0,0 +1,44 @@
+;;; gc-tests.el --- Tests for gc.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest gc-tests-deep-structure ()
+  "Marking doesn't recurse, so deeply nested structure survives."
+  (let ((tree nil))
+    (dotimes (i 200000)
+      (setq tree (cons tree (number-to-string i))))
+    (garbage-collect)
+    (let ((depth 0))
+      (while tree
+        (setq tree (car tree)
+              depth (1+ depth)))
+      (should (= depth 200000)))))
+
+(ert-deftest gc-tests-survivors ()
+  (let* ((string (propertize (copy-sequence "gc-tests") 'face 'bold))
+         (vector (vector string (list 1.5 string)))
+         (table (make-char-table 'gc-tests))
+         (hash (make-hash-table :test 'equal))
+         (symbol (make-symbol "gc-tests")))
+    (set-char-table-range table '(?a . ?z) (list vector))
+    (puthash (copy-sequence "key") vector hash)
+    (put symbol 'gc-tests hash)
+    (garbage-collect)
+    (should (equal (car (aref table ?m)) vector))
+    (should (eq (gethash "key" (get symbol 'gc-tests)) vector))
+    (should (eq (get-text-property 0 'face (aref vector 0)) 'bold))
+    (should (= (car (aref vector 1)) 1.5))))
+
+(ert-deftest gc-tests-static-root ()
+  "Objects only held in Rust statics survive."
+  (let ((frame (selected-frame)))
+    (window-configuration-ring-push
+     (list (concat "gc-" "tests") 2.5) frame)
+    (garbage-collect)
+    (should (equal (window-configuration-ring-pop frame)
+                   '("gc-tests" 2.5)))))
+
+(provide 'gc-tests)
+;;; gc-tests.el ends here
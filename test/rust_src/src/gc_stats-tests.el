@@ -0,0 +1,38 @@
+;;; gc_stats-tests.el --- Tests for gc_stats.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+(require 'cl-lib)
+(require 'seq)
+
+(ert-deftest gc_stats-tests-garbage-collect ()
+  "Each entry is (NAME SIZE USED FREE), or (NAME SIZE USED)."
+  (let ((stats (garbage-collect)))
+    (should (equal (mapcar #'car (seq-take stats 10))
+                   '(conses symbols miscs strings string-bytes vectors
+                     vector-slots floats intervals buffers)))
+    (dolist (entry stats)
+      (should (symbolp (car entry)))
+      (should (<= 3 (length entry) 4))
+      (should (cl-every #'natnump (cdr entry))))
+    (should (> (nth 2 (assq 'conses stats)) 0))))
+
+(ert-deftest gc_stats-tests-garbage-collect-verbose ()
+  (let ((stats (garbage-collect-verbose)))
+    (should (assq 'conses stats))
+    (let ((mark-stack (assq 'rust-mark-stack stats)))
+      (should mark-stack)
+      (should (= (nth 2 mark-stack) 0))
+      (should (> (nth 3 mark-stack) 0)))
+    (should (assq 'rust-gc-roots stats))
+    (should (assq 'rust-native-finalizers stats))))
+
+(ert-deftest gc_stats-tests-threshold ()
+  "`gc-cons-threshold' is never let below a tenth of its default."
+  (let ((gc-cons-threshold 1))
+    (garbage-collect)
+    (should (> gc-cons-threshold 1))))
+
+(provide 'gc_stats-tests)
+;;; gc_stats-tests.el ends here
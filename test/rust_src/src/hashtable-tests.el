@@ -0,0 +1,153 @@
+;;; hashtable-tests.el --- Tests for hashtable.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest hashtable-tests-weak-keys ()
+  "Garbage collection removes the entries whose keys are garbage."
+  (let ((table (make-hash-table :test 'equal :weakness 'key))
+        (kept (copy-sequence "kept")))
+    (puthash kept 'kept table)
+    (dotimes (i 1000)
+      (puthash (format "key%d" i) i table))
+    (garbage-collect)
+    (should (eq (gethash kept table) 'kept))
+    (should (< (hash-table-count table) 1001))))
+
+(ert-deftest hashtable-tests-weak-values ()
+  (let ((table (make-hash-table :weakness 'value))
+        (kept (list 'kept)))
+    (puthash 'kept kept table)
+    (dotimes (i 1000)
+      (puthash i (list i) table))
+    (garbage-collect)
+    (should (eq (gethash 'kept table) kept))
+    (should (< (hash-table-count table) 1001))))
+
+(ert-deftest hashtable-tests-weak-key-or-value ()
+  "An entry stays as long as its key or its value is used."
+  (let ((table (make-hash-table :weakness 'key-or-value))
+        (key (list 'key))
+        (value (list 'value)))
+    (puthash key (list 'garbage) table)
+    (puthash (list 'garbage) value table)
+    (garbage-collect)
+    (should (equal (gethash key table) '(garbage)))
+    (should (= (hash-table-count table) 2))
+    (should (eq (hash-table-weakness table) 'key-or-value))))
+
+(ert-deftest hashtable-tests-weak-chain ()
+  "A value-weak entry stays when a key-weak entry that is kept uses its value."
+  (let* ((values (make-hash-table :weakness 'value))
+         (keys (make-hash-table :weakness 'key))
+         (z (list 'z)))
+    (let ((y (list 'y)))
+      (puthash 'x y values)
+      (puthash z y keys))
+    (garbage-collect)
+    (should (equal (gethash 'x values) '(y)))
+    (should (eq (gethash z keys) (gethash 'x values)))))
+
+;; `(should-error ... :type 'error)' matches any error, so check the
+;; message too.
+(defun hashtable-tests-error-message (&rest args)
+  (condition-case err
+      (progn (apply #'make-hash-table args) nil)
+    (error (cadr err))))
+
+(ert-deftest hashtable-tests-make-hash-table ()
+  (let ((table (make-hash-table)))
+    (should (eq (hash-table-test table) 'eql))
+    (should (= (hash-table-size table) 65))
+    (should (= (hash-table-rehash-size table) 1.5))
+    (should (= (hash-table-rehash-threshold table) 0.8125))
+    (should-not (hash-table-weakness table)))
+  (let ((table (make-hash-table :test 'equal :size 3 :rehash-size 10
+                                :rehash-threshold 0.5 :weakness t)))
+    (should (eq (hash-table-test table) 'equal))
+    (should (= (hash-table-size table) 3))
+    (should (eql (hash-table-rehash-size table) 10))
+    (should (= (hash-table-rehash-threshold table) 0.5))
+    (should (eq (hash-table-weakness table) 'key-and-value))))
+
+(ert-deftest hashtable-tests-make-hash-table-errors ()
+  (should (equal (hashtable-tests-error-message :test 'no-such-test)
+                 "Invalid hash table test"))
+  (should (equal (hashtable-tests-error-message :size -1)
+                 "Invalid hash table size"))
+  (should (equal (hashtable-tests-error-message :rehash-size 0.5)
+                 "Invalid hash table rehash size"))
+  (should (equal (hashtable-tests-error-message :rehash-threshold 2.0)
+                 "Invalid hash table rehash threshold"))
+  (should (equal (hashtable-tests-error-message :weakness 'neither)
+                 "Invalid hash table weakness"))
+  (should (equal (hashtable-tests-error-message :size 1 :oops)
+                 "Invalid argument list")))
+
+(ert-deftest hashtable-tests-resize ()
+  "Tables grow as entries are added, keeping the entries they have."
+  (let ((table (make-hash-table :size 1 :rehash-size 2)))
+    (dotimes (i 100)
+      (puthash i (* i i) table))
+    (should (= (hash-table-count table) 100))
+    (should (>= (hash-table-size table) 100))
+    (dotimes (i 100)
+      (should (= (gethash i table) (* i i))))
+    (dotimes (i 50)
+      (remhash (* 2 i) table))
+    (should (= (hash-table-count table) 50))
+    (should-not (gethash 10 table))
+    (should (= (gethash 11 table) 121))
+    (clrhash table)
+    (should (= (hash-table-count table) 0))
+    (puthash 'a 1 table)
+    (should (= (gethash 'a table) 1))))
+
+(ert-deftest hashtable-tests-equal-keys ()
+  (let ((table (make-hash-table :test 'equal)))
+    (puthash (list 1 "two" [3 4.0]) 'found table)
+    (should (eq (gethash (list 1 "two" [3 4.0]) table) 'found))
+    (should-not (gethash (list 1 "two" [3 4]) table)))
+  (let ((table (make-hash-table :test 'eql)))
+    (puthash 1.5 'float table)
+    (should (eq (gethash (/ 3.0 2) table) 'float))
+    (should-not (gethash (list 1.5) table))))
+
+(ert-deftest hashtable-tests-user-defined-test ()
+  (define-hash-table-test 'hashtable-tests-case-fold
+    (lambda (a b) (string= (downcase a) (downcase b)))
+    (lambda (s) (sxhash-equal (downcase s))))
+  (let ((table (make-hash-table :test 'hashtable-tests-case-fold)))
+    (puthash "Key" 1 table)
+    (should (= (gethash "KEY" table) 1))
+    (remhash "key" table)
+    (should (= (hash-table-count table) 0))))
+
+(ert-deftest hashtable-tests-sxhash ()
+  (should (= (sxhash-equal (list "a" [1 2] 3.0))
+             (sxhash-equal (list "a" [1 2] 3.0))))
+  (should (= (sxhash-equal (make-bool-vector 100 t))
+             (sxhash-equal (make-bool-vector 100 t))))
+  (should (= (sxhash-eql 1.0) (sxhash-eql (/ 2.0 2))))
+  (should (= (sxhash-eq 'foo) (sxhash-eq 'foo)))
+  (should (integerp (sxhash-equal (make-string 1000 ?x)))))
+
+(ert-deftest hashtable-tests-seed-randomization ()
+  "`equal' tables made with seeded hashing still find their keys."
+  (let ((table (let ((hash-table-rehash-seed-randomization t))
+                 (make-hash-table :test 'equal :size 4))))
+    (dotimes (i 100)
+      (puthash (format "key%d" i) i table))
+    (puthash '("nested" . ["string"]) 'nested table)
+    (should (eq (hash-table-test table) 'equal))
+    (should (= (gethash (format "key%d" 42) table) 42))
+    (should (eq (gethash (cons "nested" (vector "string")) table) 'nested))
+    (remhash "key42" table)
+    (should-not (gethash "key42" table))
+    (should (= (hash-table-count (copy-hash-table table)) 100))
+    ;; `sxhash-equal' doesn't depend on the seed.
+    (should (= (sxhash-equal "key") (sxhash-equal (copy-sequence "key"))))))
+
+(provide 'hashtable-tests)
+;;; hashtable-tests.el ends here
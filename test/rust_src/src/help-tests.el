@@ -0,0 +1,68 @@
+;;; help-tests.el --- Tests for help.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest help-tests-function-arglist ()
+  (should (equal (help--function-arglist 'car) '(list)))
+  ;; `substring' is defined in C, so only its arity is known.
+  (should (equal (help--function-arglist 'substring)
+                 '(arg1 &optional arg2 arg3)))
+  (should (equal (help--function-arglist 'substring t)
+                 '(string &optional from to)))
+  (should (equal (help--function-arglist 'list) '(&rest args)))
+  (should (equal (help--function-arglist (lambda (a &optional b) (list a b)))
+                 '(a &optional b)))
+  (should (eq (help--function-arglist 'help-tests-no-such-function) t)))
+
+(ert-deftest help-tests-function-info ()
+  (let ((info (help--function-info 'car)))
+    (should (eq (plist-get info :kind) 'built-in-function))
+    (should (eq (plist-get info :def) (symbol-function 'car)))
+    (should-not (plist-get info :aliased))
+    (should (eq (plist-get info :file) 'C-source)))
+  (should (eq (plist-get (help--function-info 'if) :kind) 'special-form))
+  (should (eq (plist-get (help--function-info 'when) :kind) 'macro))
+  (should (plist-get (help--function-info 'forward-char) :interactive))
+  (defalias 'help-tests--alias 'car)
+  (unwind-protect
+      (let ((info (help--function-info 'help-tests--alias)))
+        (should (eq (plist-get info :kind) 'alias))
+        (should (plist-get info :aliased))
+        (should (eq (plist-get info :real-def) 'car)))
+    (fmakunbound 'help-tests--alias))
+  (should-error (help--function-info 'help-tests-no-such-function)
+                :type 'void-function))
+
+(ert-deftest help-tests-variable-info ()
+  (let ((info (help--variable-info 'fill-column)))
+    (should-not (plist-get info :void))
+    (should (eq (plist-get info :value) fill-column))
+    (should (equal (plist-get info :printed) (prin1-to-string fill-column))))
+  (should (plist-get (help--variable-info 'help-tests-no-such-variable) :void))
+  (with-temp-buffer
+    (setq-local help-tests--local 'foo)
+    (let ((info (help--variable-info 'help-tests--local)))
+      (should (eq (plist-get info :locus) (current-buffer)))
+      (should (equal (plist-get info :printed) (format-message "`foo'")))))
+  (let ((info (help--variable-info 'load-path nil 10)))
+    (should (plist-get info :truncated))
+    (should (= (length (plist-get info :printed)) 13))))
+
+(defvar help-tests--circular)
+
+(ert-deftest help-tests-variable-info-printing ()
+  "The value is printed with `print-circle', which is then restored."
+  (let ((help-tests--circular (list 'a 'b))
+        (print-circle nil)
+        (print-quoted nil))
+    (setcdr (cdr help-tests--circular) help-tests--circular)
+    (should (equal (plist-get (help--variable-info 'help-tests--circular)
+                              :printed)
+                   "#1=(a b . #1#)"))
+    (should-not print-circle)
+    (should-not print-quoted)))
+
+(provide 'help-tests)
+;;; help-tests.el ends here
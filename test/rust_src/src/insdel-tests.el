@@ -0,0 +1,118 @@
+;;; insdel-tests.el --- Tests for insdel.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest insdel-tests-change-hook-regions ()
+  (with-temp-buffer
+    (insert "abcdef")
+    (let* ((calls nil)
+           (before-change-functions
+            (list (lambda (beg end) (push (list 'before beg end) calls))))
+           (after-change-functions
+            (list (lambda (beg end len) (push (list 'after beg end len) calls)))))
+      (goto-char 3)
+      (insert "XY")
+      (delete-region 1 2)
+      (should (equal (nreverse calls)
+                     '((before 3 3) (after 3 5 0)
+                       (before 1 2) (after 1 1 1)))))))
+
+(ert-deftest insdel-tests-inhibit-modification-hooks ()
+  (with-temp-buffer
+    (let* ((called nil)
+           (before-change-functions (list (lambda (&rest _) (setq called t))))
+           (after-change-functions (list (lambda (&rest _) (setq called t)))))
+      (let ((inhibit-modification-hooks t))
+        (insert "abc"))
+      (should-not called)
+      (insert "d")
+      (should called))))
+
+(ert-deftest insdel-tests-before-change-modifies-buffer ()
+  ;; The region reported to the after-change functions follows text
+  ;; inserted by a before-change function.
+  (with-temp-buffer
+    (insert "abcdef")
+    (let* ((calls nil)
+           (before-change-functions
+            (list (lambda (_beg _end)
+                    (save-excursion
+                      (let ((inhibit-modification-hooks t))
+                        (goto-char (point-min))
+                        (insert "12"))))))
+           (after-change-functions
+            (list (lambda (beg end len) (push (list beg end len) calls)))))
+      (goto-char 4)
+      (insert "X")
+      (should (equal (buffer-string) "12abcXdef"))
+      (should (equal calls '((6 7 0)))))))
+
+(ert-deftest insdel-tests-hook-error-resets-hook ()
+  (with-temp-buffer
+    (let ((after-change-functions (list (lambda (&rest _) (error "Boom")))))
+      (should-error (insert "a"))
+      (should-not after-change-functions))))
+
+(ert-deftest insdel-tests-first-change-hook ()
+  (with-temp-buffer
+    (let* ((count 0)
+           (first-change-hook (list (lambda () (setq count (1+ count))))))
+      (insert "a")
+      (insert "b")
+      (should (= count 1))
+      (set-buffer-modified-p nil)
+      (insert "c")
+      (should (= count 2)))))
+
+(ert-deftest insdel-tests-combine-after-change-calls ()
+  (with-temp-buffer
+    (insert "abcdefgh")
+    (let* ((calls nil)
+           (before-change-functions nil)
+           (after-change-functions
+            (list (lambda (beg end len) (push (list beg end len) calls)))))
+      (combine-after-change-calls
+        (goto-char 3)
+        (insert "XY")
+        (delete-region 7 9)
+        (should-not calls))
+      (should (equal (buffer-string) "abXYcdgh"))
+      ;; A single call covers both changes, with the end of the
+      ;; unchanged text computed as insdel.c does.
+      (should (equal calls '((3 5 2)))))))
+
+(ert-deftest insdel-tests-combine-after-change-calls-deletion ()
+  (with-temp-buffer
+    (insert "abcdefgh")
+    (let* ((calls nil)
+           (before-change-functions nil)
+           (after-change-functions
+            (list (lambda (beg end len) (push (list beg end len) calls)))))
+      (combine-after-change-calls
+        (delete-region 2 4)
+        (delete-region 4 6))
+      (should (equal (buffer-string) "adeh"))
+      (should (equal calls '((2 2 4)))))))
+
+(ert-deftest insdel-tests-combine-after-change-calls-two-buffers ()
+  (let ((other (generate-new-buffer "insdel-tests"))
+        (calls nil))
+    (unwind-protect
+        (with-temp-buffer
+          (let ((before-change-functions nil)
+                (after-change-functions
+                 (list (lambda (beg end len)
+                         (push (list (current-buffer) beg end len) calls)))))
+            (combine-after-change-calls
+              (insert "abc")
+              (with-current-buffer other (insert "de")))
+            (should (equal (nreverse calls)
+                           `((,(current-buffer) 1 4 0)
+                             (,other 1 3 0))))))
+      (kill-buffer other))))
+
+(provide 'insdel-tests)
+
+;;; insdel-tests.el ends here
@@ -0,0 +1,73 @@
+;;; interactive-tests.el --- Tests for interactive.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+(require 'cl-lib)
+
+(ert-deftest interactive-tests-prefix-numeric-value ()
+  (should (= (prefix-numeric-value nil) 1))
+  (should (= (prefix-numeric-value '-) -1))
+  (should (= (prefix-numeric-value 7) 7))
+  (should (= (prefix-numeric-value '(16)) 16))
+  (should (= (prefix-numeric-value "foo") 1)))
+
+(defmacro interactive-tests--with-prefix-commands (&rest body)
+  "Run BODY with the prefix command state isolated."
+  `(let ((prefix-arg nil)
+         (this-command nil)
+         (real-this-command nil))
+     (cl-letf (((symbol-function 'universal-argument--mode) #'ignore))
+       ,@body)))
+
+(ert-deftest interactive-tests-universal-argument ()
+  (interactive-tests--with-prefix-commands
+   (universal-argument)
+   (should (equal prefix-arg '(4)))
+   (universal-argument-more prefix-arg)
+   (should (equal prefix-arg '(16)))
+   (universal-argument-more 3)
+   (should (equal prefix-arg 3))
+   (universal-argument-more '-)
+   (should (equal prefix-arg '(-4)))))
+
+(ert-deftest interactive-tests-digit-argument ()
+  (interactive-tests--with-prefix-commands
+   (let ((last-command-event ?1))
+     (digit-argument '(4)))
+   (should (equal prefix-arg 1))
+   (let ((last-command-event ?2))
+     (digit-argument prefix-arg))
+   (should (equal prefix-arg 12))
+   (let ((last-command-event 'kp-5))
+     (digit-argument -1))
+   (should (equal prefix-arg -15))
+   (let ((last-command-event ?0))
+     (digit-argument '-))
+   (should (eq prefix-arg '-))))
+
+(ert-deftest interactive-tests-negative-argument ()
+  (interactive-tests--with-prefix-commands
+   (negative-argument nil)
+   (should (eq prefix-arg '-))
+   (negative-argument '-)
+   (should-not prefix-arg)
+   (negative-argument 5)
+   (should (equal prefix-arg -5))))
+
+(ert-deftest interactive-tests-description ()
+  (interactive-tests--with-prefix-commands
+   (should-not (universal-argument--description))
+   (setq prefix-arg '(4))
+   (should (equal (universal-argument--description) "C-u"))
+   (setq prefix-arg '(64))
+   (should (equal (universal-argument--description) "C-u C-u C-u"))
+   (setq prefix-arg '(12))
+   (should (equal (universal-argument--description) "C-u (12)"))
+   (setq prefix-arg '-)
+   (should (equal (universal-argument--description) "C-u -"))
+   (setq prefix-arg 42)
+   (should (equal (universal-argument--description) "C-u 42"))))
+
+(provide 'interactive-tests)
+;;; interactive-tests.el ends here
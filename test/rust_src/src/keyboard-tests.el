@@ -0,0 +1,45 @@
+;;; keyboard-tests.el --- Tests for keyboard.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest keyboard-tests-execute-kbd-macro ()
+  (with-temp-buffer
+    (switch-to-buffer (current-buffer))
+    (execute-kbd-macro "abc")
+    (should (equal (buffer-string) "abc"))
+    (execute-kbd-macro [?x ?y] 2)
+    (should (equal (buffer-string) "abcxyxy"))
+    (should (= (kbd-macro-iterations) 2))
+    (should-not executing-kbd-macro)))
+
+(ert-deftest keyboard-tests-execute-kbd-macro-loopfunc ()
+  (with-temp-buffer
+    (switch-to-buffer (current-buffer))
+    (let ((runs 0))
+      (execute-kbd-macro "z" 0 (lambda () (< (setq runs (1+ runs)) 4)))
+      (should (equal (buffer-string) "zzz"))
+      (should (= (kbd-macro-iterations) 3)))))
+
+(ert-deftest keyboard-tests-execute-kbd-macro-symbol ()
+  (with-temp-buffer
+    (switch-to-buffer (current-buffer))
+    (let* ((ran nil)
+           (kbd-macro-termination-hook (list (lambda () (setq ran t)))))
+      (fset 'keyboard-tests--macro "q")
+      (unwind-protect
+          (execute-kbd-macro 'keyboard-tests--macro)
+        (fmakunbound 'keyboard-tests--macro))
+      (should ran)
+      (should (equal (buffer-string) "q")))))
+
+(ert-deftest keyboard-tests-execute-kbd-macro-wrong-type ()
+  (should-error (execute-kbd-macro 42))
+  (should-error (execute-kbd-macro '(a b))))
+
+(ert-deftest keyboard-tests-end-kbd-macro-not-defining ()
+  (should-error (end-kbd-macro)))
+
+(provide 'keyboard-tests)
+;;; keyboard-tests.el ends here
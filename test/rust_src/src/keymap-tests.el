@@ -253,6 +253,123 @@
     (should (equal (current-global-map) '(keymap (3 keymap (26 . emacs-version)))))
     (use-global-map backup-keymap)))
 
+(ert-deftest keymap-tests--command-remapping ()
+  (let ((first (make-sparse-keymap))
+        (second (make-sparse-keymap)))
+    (define-key first [remap kill-line] 'kill-region)
+    (define-key second [remap kill-line] 'kill-whole-line)
+    (define-key second [remap undo] 'undo-only)
+    (should (eq (command-remapping 'kill-line nil (list first second))
+                'kill-region))
+    (should (eq (command-remapping 'kill-line nil (list second first))
+                'kill-whole-line))
+    (should (eq (command-remapping 'undo nil (list first second)) 'undo-only))
+    (should-not (command-remapping 'forward-char nil (list first second)))
+    (should-not (command-remapping "kill-line" nil (list first)))
+    ;; A keymap that binds `remap' to something else remaps nothing.
+    (should-not (command-remapping 'kill-line nil
+                                   (list '(keymap (remap . ignore)))))))
+
+(ert-deftest keymap-tests--command-remapping-active-maps ()
+  (with-temp-buffer
+    (let ((map (make-sparse-keymap)))
+      (define-key map [remap keymap-tests--command] 'keymap-tests--other)
+      (should-not (command-remapping 'keymap-tests--command))
+      (use-local-map map)
+      (should (eq (command-remapping 'keymap-tests--command)
+                  'keymap-tests--other))
+      ;; The remapping is found for key bindings too.
+      (define-key map "x" 'keymap-tests--command)
+      (should (eq (key-binding "x") 'keymap-tests--other))
+      (should (eq (key-binding "x" nil t) 'keymap-tests--command)))))
+
+(defvar keymap-tests--mode nil)
+
+(ert-deftest keymap-tests--current-active-maps ()
+  (with-temp-buffer
+    (let ((local (make-sparse-keymap))
+          (overriding (make-sparse-keymap)))
+      (should (eq (car (last (current-active-maps))) (current-global-map)))
+      (use-local-map local)
+      (should (equal (current-active-maps) (list local (current-global-map))))
+      (let ((overriding-local-map overriding))
+        (should (equal (current-active-maps t)
+                       (list overriding (current-global-map))))
+        (should (memq local (current-active-maps))))
+      (let ((keymap-tests--mode t)
+            (minor-mode-map-alist
+             (cons (cons 'keymap-tests--mode overriding) minor-mode-map-alist)))
+        (should (equal (current-active-maps)
+                       (list overriding local (current-global-map))))))))
+
+(ert-deftest keymap-tests--current-active-maps-properties ()
+  (with-temp-buffer
+    (let ((text-map (make-sparse-keymap))
+          (overlay-map (make-sparse-keymap))
+          (local-map (make-sparse-keymap)))
+      (insert "abc def")
+      (goto-char 2)
+      (should-not (memq text-map (current-active-maps)))
+      ;; Adding a property is noticed.
+      (put-text-property 1 4 'keymap text-map)
+      (should (eq (car (current-active-maps)) text-map))
+      ;; So is moving point.
+      (goto-char 6)
+      (should-not (memq text-map (current-active-maps)))
+      (should (eq (car (current-active-maps nil 2)) text-map))
+      ;; And overlays.
+      (let ((ov (make-overlay 5 8)))
+        (overlay-put ov 'keymap overlay-map)
+        (should (eq (car (current-active-maps)) overlay-map))
+        (overlay-put ov 'local-map local-map)
+        (should (memq local-map (current-active-maps)))
+        (delete-overlay ov)
+        (should-not (memq overlay-map (current-active-maps)))
+        (should-not (memq local-map (current-active-maps))))
+      (should-error (current-active-maps nil 100) :type 'args-out-of-range))))
+
+(ert-deftest keymap-tests--current-active-maps-symbol-properties ()
+  ;; A keymap property can be a symbol whose function definition is the
+  ;; keymap; giving it another one is seen right away.
+  (with-temp-buffer
+    (let ((first (make-sparse-keymap))
+          (second (make-sparse-keymap)))
+      (insert "abc")
+      (goto-char 2)
+      (fset 'keymap-tests--property-map first)
+      (put-text-property 1 4 'keymap 'keymap-tests--property-map)
+      (put-text-property 1 4 'local-map 'keymap-tests--property-map)
+      (should (eq (car (current-active-maps)) first))
+      (fset 'keymap-tests--property-map second)
+      (should (eq (car (current-active-maps)) second))
+      (should (memq second (cdr (current-active-maps))))
+      ;; A symbol without a keymap is ignored.
+      (fset 'keymap-tests--property-map nil)
+      (should (equal (current-active-maps) (list (current-global-map)))))))
+
+;; Clicks on mode line segments find bindings in the `local-map' and
+;; `keymap' properties of the string clicked on.
+(ert-deftest keymap-tests--mode-line-click-bindings ()
+  (let* ((local-map (make-sparse-keymap))
+         (keymap (make-sparse-keymap))
+         (string (concat "--" (propertize "one" 'local-map local-map)
+                         "--" (propertize "two" 'keymap keymap)))
+         (click (lambda (charpos)
+                  (list (selected-window) 'mode-line '(0 . 0) 0
+                        (cons string charpos) nil))))
+    (define-key local-map [mode-line mouse-1] 'keymap-tests--one)
+    (define-key keymap [mode-line mouse-1] 'keymap-tests--two)
+    (should (eq (key-binding [mode-line mouse-1] nil nil (funcall click 3))
+                'keymap-tests--one))
+    (should (eq (key-binding [mode-line mouse-1] nil nil (funcall click 7))
+                'keymap-tests--two))
+    (dolist (charpos '(0 5 -1 10))
+      (let ((binding (key-binding [mode-line mouse-1] nil nil
+                                  (funcall click charpos))))
+        (should-not (memq binding '(keymap-tests--one keymap-tests--two)))))
+    (should (memq local-map (current-active-maps t (funcall click 2))))
+    (should (eq (car (current-active-maps t (funcall click 8))) keymap))))
+
 (provide 'rust-keymap-tests)
 
 ;;; keymap-tests.el ends here
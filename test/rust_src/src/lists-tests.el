@@ -46,6 +46,54 @@
   (should (eq (nth -1 '(a b c)) 'a))
   )
 
+(ert-deftest lists-test--nthcdr-circular ()
+  (let ((cyc (cyc1 1)))
+    (should (eq (nthcdr 10 cyc) cyc))
+    (should (eq (nthcdr 13 cyc) (nthcdr 3 cyc)))
+    (should (eq (nthcdr most-positive-fixnum cyc)
+                (nthcdr (% most-positive-fixnum 10) cyc))))
+  (let ((cyc (cyc2 1 2)))
+    (should (eq (nthcdr 1010 cyc) (nthcdr 10 cyc)))
+    (should (eq (nth most-positive-fixnum cyc) 2))))
+
+(ert-deftest lists-test--nthcdr-dotted ()
+  (should (eq (nthcdr 10 (dot1 1)) 'tail))
+  (should (eq (nthcdr 1 '(a . b)) 'b))
+  (should-error (nthcdr 2 '(a . b)) :type 'wrong-type-argument)
+  (should-error (nthcdr 11 (dot1 1)) :type 'wrong-type-argument)
+  (should-error (nthcdr 1 'a) :type 'wrong-type-argument)
+  (should (eq (nthcdr 0 'a) 'a))
+  (should (eq (nthcdr 5 '(a b)) nil)))
+
+(ert-deftest lists-test--last ()
+  (should (equal (last '(a b c)) '(c)))
+  (should (equal (last '(a b c) 2) '(b c)))
+  (should (equal (last '(a b c) 5) '(a b c)))
+  (should (equal (last '(a b c) 0) nil))
+  (should (equal (last '(a b c) -1) nil))
+  (should (equal (last nil) nil))
+  (should (equal (last '(a b . c)) '(b . c)))
+  (should (consp (last (cyc1 1)))))
+
+(ert-deftest lists-test--safe-length ()
+  (should (= (safe-length '(a b c)) 3))
+  (should (= (safe-length nil) 0))
+  (should (= (safe-length 'a) 0))
+  (should (= (safe-length (dot1 1)) 10))
+  (should (>= (safe-length (cyc1 1)) 10))
+  (should (>= (safe-length (cyc2 1 2)) 1010)))
+
+(ert-deftest lists-test--proper-list-p ()
+  (should (eql (proper-list-p '(a b c)) 3))
+  (should (eql (proper-list-p nil) 0))
+  (should (eql (proper-list-p '(a)) 1))
+  (should-not (proper-list-p 'a))
+  (should-not (proper-list-p "abc"))
+  (should-not (proper-list-p '(a . b)))
+  (should-not (proper-list-p (dot2 1 2)))
+  (should-not (proper-list-p (cyc1 1)))
+  (should-not (proper-list-p (cyc2 1 2))))
+
 (ert-deftest plist-get/odd-number-of-elements ()
   "Test that `plist-get' doesn't signal an error on degenerate plists."
   (should-not (plist-get '(:foo 1 :bar) :bar)))
@@ -74,6 +122,33 @@
                                :type 'wrong-type-argument)
                  '(wrong-type-argument plistp (:foo 1 . :bar)))))
 
+(ert-deftest plist/eq-and-equal ()
+  "`plist-get' and `plist-put' compare with `eq', the lax ones with `equal'."
+  (let ((plist (list "a" 1 'b 2)))
+    (should-not (plist-get plist (copy-sequence "a")))
+    (should (eq (lax-plist-get plist (copy-sequence "a")) 1))
+    (should (eq (plist-get plist 'b) 2))
+    (should (eq (lax-plist-get plist 'b) 2))
+    (setq plist (lax-plist-put plist (copy-sequence "a") 3))
+    (should (equal plist '("a" 3 b 2)))
+    (setq plist (plist-put plist (copy-sequence "a") 4))
+    (should (equal plist '("a" 3 b 2 "a" 4)))
+    (should-not (plist-member plist (copy-sequence "a")))
+    (should (equal (plist-member plist 'b) '(b 2 "a" 4)))))
+
+(ert-deftest plist-put/new-plist ()
+  (should (equal (plist-put nil :foo 1) '(:foo 1)))
+  (should (equal (lax-plist-put nil "foo" 1) '("foo" 1)))
+  (let ((plist (list :foo 1)))
+    (should (eq (plist-put plist :foo 2) plist))
+    (should (equal plist '(:foo 2)))))
+
+(ert-deftest plist-member/odd-number-of-elements ()
+  (should (equal (plist-member '(:foo 1 :bar) :bar) '(:bar)))
+  (should-not (plist-member '(:foo 1 :bar) :qux))
+  ;; Values are not properties.
+  (should-not (plist-member '(:foo :bar) :bar)))
+
 (ert-deftest test-cycle-plist-get ()
   (let ((c1 (cyc1 1))
         (c2 (cyc2 1 2))
@@ -164,5 +239,77 @@
     (should-error (lax-plist-put d1 3 3) :type 'wrong-type-argument)
     (should-error (lax-plist-put d2 3 3) :type 'wrong-type-argument)))
 
+(ert-deftest lists-test--assoc-default ()
+  (should (equal (assoc-default "b" '(("a" . 1) ("b" . 2))) 2))
+  (should (equal (assoc-default "b" '("a" "b") nil 'dflt) 'dflt))
+  (should-not (assoc-default "c" '(("a" . 1) "b")))
+  (should (equal (assoc-default "x.el" '(("\\.el\\'" . elisp)) #'string-match)
+                 'elisp)))
+
+(ert-deftest lists-test--alist-get ()
+  (let ((alist (list (cons 'a 1) (cons "b" 2) 'c)))
+    (should (equal (alist-get 'a alist) 1))
+    (should-not (alist-get "b" alist))
+    (should (equal (alist-get "b" alist nil nil #'equal) 2))
+    (should (equal (alist-get 'c alist 'none) 'none))))
+
+(ert-deftest lists-test--delete-all ()
+  (let ((alist (list (cons 'a 1) (cons 'b 2) (cons 'a 3) 'a (cons 'c 1))))
+    (should (equal (rassq-delete-all 1 (copy-sequence alist))
+                   '((b . 2) (a . 3) a)))
+    (should (equal (assq-delete-all 'a (copy-sequence alist))
+                   '((b . 2) a (c . 1))))
+    (should (equal (assoc-delete-all 'b (copy-sequence alist) #'eq)
+                   '((a . 1) (a . 3) a (c . 1))))))
+
+(ert-deftest test-cycle-alists ()
+  (let ((c1 (cyc1 '(1 . 1)))
+        (d1 (dot1 '(1 . 1))))
+    (should-error (assoc-default 2 c1) :type 'circular-list)
+    (should-error (alist-get 2 c1) :type 'circular-list)
+    (should-error (rassq 2 c1) :type 'circular-list)
+    (should-error (rassoc 2 c1) :type 'circular-list)
+    (should-error (assq-delete-all 2 c1) :type 'circular-list)
+    (should-error (assoc-default 2 d1) :type 'wrong-type-argument)
+    (should-error (assq-delete-all 2 d1) :type 'wrong-type-argument)
+    (should (equal (alist-get 1 d1) 1))))
+
+;; Removal
+
+(ert-deftest lists-test-delq ()
+  (let ((l (list 'a 'b 'a 'c)))
+    (should (equal (delq 'a l) '(b c)))
+    (should (equal l '(a b c))))
+  (should-not (delq 'a (list 'a 'a)))
+  (should-error (delq 'a (dot1 1)) :type 'wrong-type-argument)
+  (should-error (delq 'a (cyc1 1)) :type 'circular-list))
+
+(ert-deftest lists-test-remq ()
+  (let* ((tail (list 'b 'c))
+         (l (cons 'a tail)))
+    ;; Leading occurrences are skipped without copying.
+    (should (eq (remq 'a l) tail))
+    (should (eq (remq 'd l) l))
+    (should (equal (remq 'b l) '(a c)))
+    (should (equal l '(a b c))))
+  (should-not (remq 'a '(a a)))
+  (should-error (remq 'a (cyc1 1)) :type 'circular-list))
+
+(ert-deftest lists-test-delete-dups ()
+  (let ((l (list "a" "b" (copy-sequence "a") 1 1.0 1)))
+    (should (eq (delete-dups l) l))
+    (should (equal l '("a" "b" 1 1.0))))
+  (should-not (delete-dups nil))
+  ;; Long lists are rid of duplicates with a hash table.
+  (let ((l (append (number-sequence 1 200) (number-sequence 200 1 -1)
+                   (mapcar #'number-to-string (number-sequence 1 200))
+                   (mapcar #'number-to-string (number-sequence 1 200)))))
+    (should (equal (delete-dups l)
+                   (append (number-sequence 1 200)
+                           (mapcar #'number-to-string
+                                   (number-sequence 1 200))))))
+  (should-error (delete-dups (dot1 1)) :type 'wrong-type-argument)
+  (should-error (delete-dups (cyc1 1)) :type 'circular-list))
+
 (provide 'rust-lists-tests)
 ;;; lists-tests.el ends here
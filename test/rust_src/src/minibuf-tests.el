@@ -405,4 +405,43 @@
       (insert "test")
       (should (string= (minibuffer-contents) "test")))))
 
+(ert-deftest minibuf-tests-extended-command-candidates ()
+  (let ((candidates (extended-command-candidates)))
+    (should (member "forward-char" candidates))
+    (should-not (member "car" candidates))
+    (should-not (member "forward-sexp-function" candidates)))
+  (fset 'minibuf-tests--obsolete (lambda () (interactive)))
+  (put 'minibuf-tests--obsolete 'byte-obsolete-info '(forward-char nil "27.1"))
+  (unwind-protect
+      (should-not (member "minibuf-tests--obsolete" (extended-command-candidates)))
+    (fmakunbound 'minibuf-tests--obsolete)))
+
+(ert-deftest minibuf-tests-extended-command-candidates-predicate ()
+  (with-temp-buffer
+    (let* ((buffer (current-buffer))
+           (read-extended-command-predicate
+            (lambda (sym buf)
+              (and (eq buf buffer) (memq sym '(forward-char backward-char))))))
+      (should (equal (sort (extended-command-candidates) #'string<)
+                     '("backward-char" "forward-char")))
+      (with-temp-buffer
+        (should-not (extended-command-candidates))
+        (should (extended-command-candidates buffer))))))
+
+(ert-deftest minibuf-tests-extended-command-mode ()
+  (with-temp-buffer
+    (emacs-lisp-mode)
+    (let ((buffer (current-buffer)))
+      (should (eq (extended-command-mode 'emacs-lisp-byte-compile)
+                  'emacs-lisp-mode))
+      (should (eq (extended-command-mode 'emacs-lisp-mode) 'emacs-lisp-mode))
+      (should-not (extended-command-mode 'forward-char))
+      (should-not (extended-command-mode 'abbrev-mode))
+      (abbrev-mode 1)
+      (should (eq (extended-command-mode 'abbrev-mode) 'abbrev-mode))
+      (with-temp-buffer
+        (should-not (extended-command-mode 'emacs-lisp-byte-compile))
+        (should (eq (extended-command-mode 'emacs-lisp-byte-compile buffer)
+                    'emacs-lisp-mode))))))
+
 ;;; minibuf-tests.el ends here
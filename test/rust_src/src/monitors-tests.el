@@ -0,0 +1,119 @@
+;;; monitors-tests.el --- Tests for monitors.rs
+
+;;; Code:
+
+(require 'ert)
+
+(defmacro monitors-tests--with-baseline (attributes &rest body)
+  "Run BODY with ATTRIBUTES as the remembered monitors of the terminal."
+  (declare (indent 1))
+  `(let ((saved (terminal-parameter nil 'monitors--attributes)))
+     (unwind-protect
+         (progn
+           (set-terminal-parameter nil 'monitors--attributes ,attributes)
+           ,@body)
+       (set-terminal-parameter nil 'monitors--attributes saved))))
+
+(ert-deftest monitors-remember-configuration ()
+  (monitors-tests--with-baseline nil
+    (should (equal (monitors--remember-configuration (frame-terminal))
+                   (display-monitor-attributes-list)))
+    (should (equal (terminal-parameter nil 'monitors--attributes)
+                   (display-monitor-attributes-list)))))
+
+(ert-deftest monitors-changed-event-dead-terminal ()
+  (let* ((called nil)
+         (monitor-configuration-change-functions
+          (list (lambda (&rest _) (setq called t)))))
+    (handle-monitors-changed-event '(monitors-changed nil))
+    (handle-monitors-changed-event '(monitors-changed 17))
+    (should-not called)))
+
+(ert-deftest monitors-changed-event-no-change ()
+  (monitors-tests--with-baseline (display-monitor-attributes-list)
+    (let* ((called nil)
+           (monitor-configuration-change-functions
+            (list (lambda (&rest _) (setq called t)))))
+      (handle-monitors-changed-event
+       (list 'monitors-changed (frame-terminal)))
+      (should-not called))))
+
+(ert-deftest monitors-changed-event-diffs ()
+  (let* ((current (car (display-monitor-attributes-list)))
+         (moved (cons '(geometry 10 10 1 1) (cdr current)))
+         (gone '((name . "gone") (geometry 0 0 1 1)))
+         (changes nil)
+         (monitor-configuration-change-functions
+          (list (lambda (terminal diffs)
+                  (should (eq terminal (frame-terminal)))
+                  (setq changes diffs)))))
+    ;; The baseline has a monitor that is gone now, and none at the
+    ;; geometry of the current one.
+    (monitors-tests--with-baseline (list gone moved)
+      (handle-monitors-changed-event
+       (list 'monitors-changed (frame-terminal)))
+      (should (member (list 'removed gone) changes))
+      (should (= (length changes) 3))
+      ;; The baseline is updated.
+      (should (equal (terminal-parameter nil 'monitors--attributes)
+                     (display-monitor-attributes-list))))))
+
+(ert-deftest monitors-changed-event-ignores-frames ()
+  (let* ((current (car (display-monitor-attributes-list)))
+         (other-frames (cons '(frames) (assq-delete-all 'frames
+                                                        (copy-sequence current))))
+         (called nil)
+         (monitor-configuration-change-functions
+          (list (lambda (&rest _) (setq called t)))))
+    (monitors-tests--with-baseline (list other-frames)
+      (handle-monitors-changed-event
+       (list 'monitors-changed (frame-terminal)))
+      (should-not called))))
+
+;; Frames on text terminals always have the scale factor 1.
+(ert-deftest frame-scale-factor ()
+  (should (eql (frame-scale-factor) 1.0))
+  (should (eql (frame-scale-factor (selected-frame)) 1.0))
+  (should-error (frame-scale-factor 'not-a-frame)))
+
+(ert-deftest monitors-update-scale-factor ()
+  (let* ((frame (selected-frame))
+         (saved (frame-parameter frame 'monitors--scale-factor))
+         (calls nil)
+         (scale-change-functions
+          (list (lambda (&rest args) (push args calls)))))
+    (unwind-protect
+        (progn
+          ;; The first scale factor seen is only recorded.
+          (set-frame-parameter frame 'monitors--scale-factor nil)
+          (monitors--update-scale-factor frame)
+          (should (eql (frame-parameter frame 'monitors--scale-factor) 1.0))
+          (should-not calls)
+          ;; Nothing changed.
+          (monitors--update-scale-factor frame)
+          (should-not calls)
+          ;; The frame comes from a monitor of twice the resolution.
+          (set-frame-parameter frame 'monitors--scale-factor 2.0)
+          (monitors--update-scale-factor frame)
+          (should (equal calls (list (list frame 2.0 1.0))))
+          (should (eql (frame-parameter frame 'monitors--scale-factor) 1.0)))
+      (set-frame-parameter frame 'monitors--scale-factor saved))))
+
+(ert-deftest monitor-logical-attributes ()
+  ;; 3840 pixels on 508 mm are 192 dpi, twice the standard resolution.
+  (should (equal (monitor-logical-attributes
+                  '((name . "hidpi") (geometry 1920 0 3840 2160)
+                    (workarea 1920 0 3840 2100) (mm-size 508 286)))
+                 '((name . "hidpi") (geometry 1920 0 1920 1080)
+                   (workarea 1920 0 1920 1050) (mm-size 508 286)
+                   (scale-factor . 2.0))))
+  ;; A scale factor told by the window system is used as is.
+  (should (equal (monitor-logical-attributes
+                  '((geometry 0 0 2880 1620) (scale-factor . 1.5)))
+                 '((geometry 0 0 1920 1080) (scale-factor . 1.5))))
+  (should (equal (monitor-logical-attributes '((geometry 0 0 80 25)))
+                 '((geometry 0 0 80 25) (scale-factor . 1.0)))))
+
+(provide 'monitors-tests)
+
+;;; monitors-tests.el ends here
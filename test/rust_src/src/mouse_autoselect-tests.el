@@ -0,0 +1,53 @@
+;;; mouse_autoselect-tests.el --- Tests for mouse_autoselect.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest mouse-autoselect-tests-immediate ()
+  (let* ((w1 (selected-window))
+         (w2 (split-window w1))
+         (mouse-autoselect-window t))
+    (unwind-protect
+        (progn
+          (handle-select-window `(select-window (,w2)))
+          (should (eq (selected-window) w2))
+          (handle-select-window `(select-window (,w2)))
+          (should (eq (selected-window) w2)))
+      (delete-other-windows w1))))
+
+(ert-deftest mouse-autoselect-tests-delayed ()
+  (let* ((w1 (selected-window))
+         (w2 (split-window w1))
+         (mouse-autoselect-window 0.5)
+         (pre-command-hook nil))
+    (unwind-protect
+        (progn
+          (handle-select-window `(select-window (,w2)))
+          (should (eq (selected-window) w1))
+          (should (memq 'mouse-autoselect-window-cancel pre-command-hook))
+          (mouse-autoselect-window-cancel t)
+          (should-not (memq 'mouse-autoselect-window-cancel pre-command-hook)))
+      (mouse-autoselect-window-cancel t)
+      (delete-other-windows w1))))
+
+(ert-deftest mouse-autoselect-tests-cancel-for-select-window ()
+  (let ((mouse-autoselect-window 0.5)
+        (this-command 'handle-select-window)
+        (pre-command-hook '(mouse-autoselect-window-cancel)))
+    (mouse-autoselect-window-cancel)
+    (should (memq 'mouse-autoselect-window-cancel pre-command-hook))
+    (setq this-command 'forward-char)
+    (mouse-autoselect-window-cancel)
+    (should-not (memq 'mouse-autoselect-window-cancel pre-command-hook))))
+
+(ert-deftest mouse-autoselect-tests-dead-window ()
+  (let* ((w1 (selected-window))
+         (w2 (split-window w1))
+         (mouse-autoselect-window t))
+    (delete-window w2)
+    (handle-select-window `(select-window (,w2)))
+    (should (eq (selected-window) w1))))
+
+(provide 'mouse_autoselect-tests)
+;;; mouse_autoselect-tests.el ends here
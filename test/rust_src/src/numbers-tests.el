@@ -0,0 +1,35 @@
+;;; numbers-tests.el --- Tests for numbers.rs
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest numbers-tests--string-to-number ()
+  (should (eq (string-to-number "  \t42abc") 42))
+  (should (eq (string-to-number "-17") -17))
+  (should (eq (string-to-number "junk") 0))
+  (should (= (string-to-number "1.5e3") 1500.0))
+  (should (= (string-to-number ".5") 0.5))
+  (should (eq (string-to-number "1.") 1))
+  (should (= (string-to-number "1e+INF") 1.0e+INF))
+  (should (isnan (string-to-number "0.0e+NaN")))
+  (should (equal (string-to-number "-0.0") -0.0))
+  (should (floatp (string-to-number "100000000000000000000")))
+  (should (eq (string-to-number "ff" 16) 255))
+  (should (eq (string-to-number "zz" 36) 1295))
+  (should (eq (string-to-number "1.5" 16) 1))
+  (should-error (string-to-number "1" 37) :type 'args-out-of-range)
+  (should-error (string-to-number "1" 1) :type 'args-out-of-range)
+  (should-error (string-to-number "ffffffffffffffffffffffff" 16)
+                :type 'overflow-error))
+
+(ert-deftest numbers-tests--number-to-string ()
+  (should (equal (number-to-string 42) "42"))
+  (should (equal (number-to-string -7) "-7"))
+  (should (equal (number-to-string 1.5) "1.5"))
+  (should (equal (number-to-string 1.0e+INF) "1.0e+INF"))
+  (should (equal (read (number-to-string 0.1)) 0.1))
+  (should-error (number-to-string "1") :type 'wrong-type-argument))
+
+(provide 'numbers-tests)
+;;; numbers-tests.el ends here
@@ -31,3 +31,81 @@
   (should-error
    (mapatoms (lambda (s)) 123)
    :type 'wrong-type-argument))
+
+(ert-deftest obarray-tests-all-completions-index ()
+  (let ((names '("obarray-tests--b" "obarray-tests--a" "obarray-tests--ab")))
+    (mapc #'intern names)
+    (unwind-protect
+        (progn
+          (should (equal (all-completions "obarray-tests--" obarray)
+                         '("obarray-tests--a" "obarray-tests--ab"
+                           "obarray-tests--b")))
+          (should (equal (all-completions "obarray-tests--a" obarray)
+                         '("obarray-tests--a" "obarray-tests--ab")))
+          (should (equal (all-completions
+                          "obarray-tests--" obarray
+                          (lambda (sym) (eq sym 'obarray-tests--b)))
+                         '("obarray-tests--b")))
+          (unintern "obarray-tests--a" obarray)
+          (should (equal (all-completions "obarray-tests--a" obarray)
+                         '("obarray-tests--ab")))
+          (let ((completion-ignore-case t))
+            (should (equal (sort (all-completions "OBARRAY-TESTS--" obarray)
+                                 #'string<)
+                           '("obarray-tests--ab" "obarray-tests--b")))))
+      (dolist (name names)
+        (unintern name obarray)))))
+
+(ert-deftest obarray-tests-all-completions-other-obarray ()
+  (let ((my-obarray (make-vector 7 0)))
+    (intern "foo" my-obarray)
+    (intern "foobar" my-obarray)
+    (intern "bar" my-obarray)
+    (should (equal (sort (all-completions "foo" my-obarray) #'string<)
+                   '("foo" "foobar")))
+    (should-not (all-completions "obarray-tests-" my-obarray))))
+
+(ert-deftest obarray-tests-unintern ()
+  (let ((my-obarray (make-vector 1 0)))
+    ;; All these symbols share the only bucket.
+    (dolist (name '("a" "b" "c"))
+      (intern name my-obarray))
+    (should (unintern "b" my-obarray))
+    (should-not (intern-soft "b" my-obarray))
+    (should (intern-soft "a" my-obarray))
+    (should (intern-soft "c" my-obarray))
+    (should-not (unintern "b" my-obarray))
+    ;; A symbol of the same name from elsewhere isn't uninterned.
+    (should-not (unintern (make-symbol "a") my-obarray))
+    (should (unintern (intern-soft "a" my-obarray) my-obarray))
+    (should (unintern 'c my-obarray))
+    (should (equal (append my-obarray nil) '(0)))))
+
+(ert-deftest obarray-tests-intern-global ()
+  (let ((name "obarray-tests--interned"))
+    (unwind-protect
+        (let ((sym (intern name)))
+          (should (eq (intern (copy-sequence name)) sym))
+          (should (eq (intern-soft name) sym))
+          (unintern sym obarray)
+          (should-not (intern-soft name))
+          (should-not (eq (intern name) sym)))
+      (unintern name obarray))))
+
+(ert-deftest obarray-tests-intern-unibyte-multibyte ()
+  "Names of the same bytes but different characters are different."
+  (let ((unibyte "obarray-tests--\303\251")
+        (multibyte (concat "obarray-tests--" (string #xe9))))
+    (should (equal (encode-coding-string multibyte 'utf-8-emacs) unibyte))
+    (unwind-protect
+        (progn
+          (should-not (eq (intern unibyte) (intern multibyte)))
+          (should (eq (intern-soft unibyte) (intern unibyte))))
+      (unintern unibyte obarray)
+      (unintern multibyte obarray))))
+
+(ert-deftest obarray-tests-keyword ()
+  (let ((keyword (intern ":obarray-tests--keyword")))
+    (should (eq (symbol-value keyword) keyword))
+    (should-error (set keyword 1) :type 'setting-constant)
+    (unintern keyword obarray)))
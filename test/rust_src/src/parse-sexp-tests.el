@@ -0,0 +1,72 @@
+;;; parse-sexp-tests.el --- Tests for parse_sexp.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(defmacro parse-sexp-tests--with-elisp (text &rest body)
+  (declare (indent 1))
+  `(with-temp-buffer
+     (set-syntax-table emacs-lisp-mode-syntax-table)
+     (insert ,text)
+     ,@body))
+
+(ert-deftest parse-sexp-tests-lists ()
+  (parse-sexp-tests--with-elisp "(a (b c) (d"
+    (let ((state (parse-partial-sexp (point-min) (point-max))))
+      (should (= (nth 0 state) 2))
+      (should (= (nth 1 state) 10))
+      (should (equal (nth 9 state) '(1 10)))
+      (should (= (point) (point-max))))
+    (let ((state (parse-partial-sexp (point-min) (point-max) 0)))
+      (should (= (nth 0 state) 2)))
+    (let ((state (parse-partial-sexp 2 (point-max) 1)))
+      (should (= (nth 0 state) 1))
+      (should (= (point) 5)))
+    (parse-partial-sexp (point-min) (point-max) nil t)
+    (should (= (point) 1))))
+
+(ert-deftest parse-sexp-tests-strings-and-comments ()
+  (parse-sexp-tests--with-elisp "(a \"b \\\" c\" ; d\n e"
+    (let ((state (parse-partial-sexp (point-min) 8)))
+      (should (eq (nth 3 state) ?\"))
+      (should (= (nth 8 state) 4))
+      ;; The parse stopped after the backslash.
+      (should (nth 5 state)))
+    (let ((state (parse-partial-sexp (point-min) 16)))
+      (should-not (nth 3 state))
+      (should (eq (nth 4 state) t))
+      (should (= (nth 8 state) 13)))
+    (let ((state (parse-partial-sexp (point-min) (point-max))))
+      (should-not (nth 4 state))
+      (should (= (nth 0 state) 1)))
+    (parse-partial-sexp (point-min) (point-max) nil nil nil t)
+    (should (= (point) 14))))
+
+(ert-deftest parse-sexp-tests-old-state ()
+  (parse-sexp-tests--with-elisp "(a \"b c\" (d e) f)"
+    (let* ((middle (parse-partial-sexp (point-min) 7))
+           (resumed (parse-partial-sexp 7 (point-max) nil nil middle))
+           (whole (parse-partial-sexp (point-min) (point-max))))
+      (should (equal (nth 0 resumed) (nth 0 whole)))
+      (should (equal (nth 3 resumed) (nth 3 whole)))
+      (should (equal (nth 9 resumed) (nth 9 whole))))))
+
+(ert-deftest parse-sexp-tests-cached ()
+  (parse-sexp-tests--with-elisp ""
+    (dotimes (i 500)
+      (insert (format "(defun f%d () \"doc ; %d\" ; comment\n  (g))\n" i i)))
+    (insert "(h \"unfinished")
+    (dolist (pos (list (point-max) 10 5000 (/ (point-max) 2) (point-min)))
+      (let ((cached (syntax-ppss-cached pos))
+            (parsed (save-excursion (parse-partial-sexp (point-min) pos))))
+        (dolist (n '(0 1 3 4 5 7 8 9))
+          (should (equal (nth n cached) (nth n parsed))))))
+    (goto-char (point-max))
+    (insert "\")")
+    (should (= (nth 0 (syntax-ppss-cached)) 0))
+    (should-not (nth 3 (syntax-ppss-cached)))
+    (should-error (syntax-ppss-cached (1+ (point-max))) :type 'args-out-of-range)))
+
+(provide 'parse-sexp-tests)
+;;; parse-sexp-tests.el ends here
@@ -0,0 +1,36 @@
+;;; pixel_fill-tests.el --- Tests for pixel_fill.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+;; In batch mode, characters are as wide as their columns.
+
+(ert-deftest pixel_fill-tests-spaces ()
+  "Lines are broken at the last space that fits."
+  (with-temp-buffer
+    (insert "aaa bbb ccc ddd")
+    (should (equal (pixel-fill-breaks (point-min) (point-max) 7) '(8)))
+    (should (equal (pixel-fill-breaks (point-min) (point-max) 15) nil))
+    (should (equal (pixel-fill-breaks 5 (point-max) 3) '(7 11)))))
+
+(ert-deftest pixel_fill-tests-newlines ()
+  "Newlines in the text start new lines."
+  (with-temp-buffer
+    (insert "aaa bbb\nccc ddd")
+    (should (equal (pixel-fill-breaks (point-min) (point-max) 8) nil))
+    (should (equal (pixel-fill-breaks (point-min) (point-max) 5) '(4 12)))))
+
+(ert-deftest pixel_fill-tests-long-word ()
+  "A word wider than a line is broken inside."
+  (with-temp-buffer
+    (insert "aaaaaaa bb")
+    (should (equal (pixel-fill-breaks (point-min) (point-max) 3) '(4 7 8)))))
+
+(ert-deftest pixel_fill-tests-wide-characters ()
+  (with-temp-buffer
+    (insert "日本 語")
+    (should (equal (pixel-fill-breaks (point-min) (point-max) 4) '(3)))))
+
+(provide 'pixel_fill-tests)
+;;; pixel_fill-tests.el ends here
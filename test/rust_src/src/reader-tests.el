@@ -0,0 +1,124 @@
+;;; reader-tests.el --- Tests for reader.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest reader-tests-read-from-string ()
+  (should (equal (read-from-string "(a b) c") '((a b) . 5)))
+  (should (equal (read-from-string "x y z" 2) '(y . 3)))
+  (should (equal (read-from-string "x y z" -1) '(z . 5)))
+  (should-error (read-from-string "(a") :type 'end-of-file)
+  (should-error (read-from-string ")") :type 'invalid-read-syntax))
+
+(ert-deftest reader-tests-characters ()
+  (should (= (read "?a") ?a))
+  (should (= (read "?\\C-a") 1))
+  (should (= (read "?\\^?") 127))
+  (should (= (read "?\\M-a") (+ ?a (ash 1 27))))
+  (should (= (read "?\\x41") ?A))
+  (should (= (read "?\\101") ?A))
+  (should (= (read "?\\u00e9") ?é))
+  (should (= (read "?\\N{LATIN SMALL LETTER A}") ?a))
+  (should (= (read "?\\N{U+E9}") ?é))
+  (should-error (read "?\\N{NO SUCH CHARACTER NAME}")
+                :type 'invalid-read-syntax)
+  (should-error (read "?ab") :type 'invalid-read-syntax))
+
+(ert-deftest reader-tests-strings ()
+  (should (equal (read "\"a\\tb\"") "a\tb"))
+  (should (equal (read "\"a\\\nb\"") "ab"))
+  (should (equal (read "\"\\x41\\u00e9\"") "Aé"))
+  (should (multibyte-string-p (read "\"\\u00e9\"")))
+  (should-not (multibyte-string-p (read "\"\\377\"")))
+  (should (equal (read "\"\\M-a\"") (unibyte-string #xe1)))
+  (should (equal (read "#(\"abc\" 0 1 (face bold))")
+                 #("abc" 0 1 (face bold)))))
+
+(ert-deftest reader-tests-integers ()
+  (should (= (read "#x10") 16))
+  (should (= (read "#o-17") -15))
+  (should (= (read "#b101") 5))
+  (should (= (read "#24r1k") 44))
+  (should-error (read "#b102") :type 'invalid-read-syntax))
+
+(ert-deftest reader-tests-symbols ()
+  (should (eq (read "foo") 'foo))
+  (should (eq (read "\\1") (intern "1")))
+  (should (equal (symbol-name (read "##")) ""))
+  (let ((sym (read "#:foo")))
+    (should (equal (symbol-name sym) "foo"))
+    (should-not (eq sym 'foo)))
+  (should-error (read "‘foo") :type 'invalid-read-syntax))
+
+(ert-deftest reader-tests-quoting ()
+  (should (equal (read "'a") '(quote a)))
+  (should (equal (read "#'car") '(function car)))
+  (should (equal (read "`(a ,b ,@c)") '(\` (a (\, b) (\,@ c)))))
+  (should (equal (read "(a . b)") '(a . b)))
+  (should-error (read "(a . b c)") :type 'invalid-read-syntax))
+
+(ert-deftest reader-tests-vectors ()
+  (should (equal (read "[a (b) \"c\"]") [a (b) "c"]))
+  (should (equal (read "#&3\"\\5\"") (bool-vector t nil t)))
+  (let ((record (read "#s(foo 1 2)")))
+    (should (recordp record))
+    (should (eq (type-of record) 'foo))
+    (should (= (aref record 2) 2)))
+  (let ((table (read "#s(hash-table test equal data (\"a\" 1 b 2))")))
+    (should (eq (hash-table-test table) 'equal))
+    (should (= (gethash "a" table) 1))
+    (should (= (gethash 'b table) 2))))
+
+(ert-deftest reader-tests-circular ()
+  (let ((list (read "#1=(a . #1#)")))
+    (should (eq list (cdr list))))
+  (let ((vector (read "#1=[a #1#]")))
+    (should (eq vector (aref vector 1))))
+  (let ((read-circle nil))
+    (should-error (read "#1=(a)") :type 'invalid-read-syntax)))
+
+(ert-deftest reader-tests-symbol-positions ()
+  (let ((read-with-symbol-positions t))
+    (read "(foo bar)")
+    (should (equal read-symbol-positions-list '((foo . 1) (bar . 5))))))
+
+(ert-deftest reader-tests-shorthands ()
+  (let ((read-symbol-shorthands '(("s-" . "string-") ("x-" . "y-"))))
+    (should (eq (read "s-trim") 'string-trim))
+    (should (equal (read "(s-trim x-foo s-)") '(string-trim y-foo string-)))
+    ;; Numbers and symbols made only of punctuation aren't expanded.
+    (should (= (read "1") 1))
+    (should (equal (symbol-name (read "#_s-trim")) "s-trim"))
+    (should (equal (symbol-name (read "#_")) ""))
+    (let ((read-symbol-shorthands '(("-" . "minus-"))))
+      (should (equal (symbol-name (read "--")) "--"))
+      (should (equal (symbol-name (read "-a")) "minus-a")))))
+
+(ert-deftest reader-tests-shorthands-load ()
+  (let ((file (make-temp-file "reader-tests" nil ".el")))
+    (unwind-protect
+        (progn
+          (with-temp-file file
+            (insert "(setq reader-tests--loaded 'rt-sym)\n"
+                    ";; Local Variables:\n"
+                    ";; read-symbol-shorthands: ((\"rt-\" . \"reader-tests--\"))\n"
+                    ";; End:\n"))
+          (defvar reader-tests--loaded)
+          (setq reader-tests--loaded nil)
+          (load file nil t t)
+          (should (eq reader-tests--loaded 'reader-tests--sym))
+          (should-not read-symbol-shorthands))
+      (delete-file file))))
+
+(ert-deftest reader-tests-character-name-length ()
+  ;; Names of up to 200 characters are looked up, longer ones aren't.
+  (should (equal (cdr (should-error
+                       (read (concat "?\\N{" (make-string 200 ?A) "}"))))
+                 (list (concat "\\N{" (make-string 200 ?A) "}"))))
+  (should (equal (cdr (should-error
+                       (read (concat "?\\N{" (make-string 201 ?A) "}"))))
+                 '("Character name too long"))))
+
+(provide 'reader-tests)
+;;; reader-tests.el ends here
@@ -0,0 +1,295 @@
+;;; search-tests.el --- Tests for search.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(defmacro search-tests--both-engines (&rest body)
+  "Check that BODY gives the same value and match data with either regexp engine."
+  (declare (indent 0))
+  `(let ((results
+          (mapcar (lambda (prefer-rust-regex-engine)
+                    (let* ((value (progn ,@body))
+                           (data (match-data t)))
+                      ;; Leave out the buffer searched, which differs.
+                      (when (bufferp (car (last data)))
+                        (setq data (butlast data)))
+                      (list value data)))
+                  '(nil t))))
+     (should (equal (car results) (cadr results)))))
+
+(ert-deftest search-tests-string-match ()
+  (dolist (case '(("a\\(b\\|c\\)+d" "xxabcbd")
+                  ("^b" "a\nb")
+                  ("a$" "a\nb")
+                  ("\\`a" "ba")
+                  ("b\\'" "bab")
+                  ("*a" "x*a")
+                  ("[]a-c]+" "x]ab")
+                  ("[^a-c]" "abcd")
+                  ("x\\{2,3\\}" "xxxx")
+                  ("\\(?:ab\\)+?\\(c\\)?" "ababc")
+                  ("\\(a\\)\\|\\(b\\)" "b")
+                  ("é+" "aéé")
+                  ("a.c" "a\nc abc")))
+    (search-tests--both-engines
+      (with-temp-buffer
+        (string-match (car case) (cadr case))))))
+
+(ert-deftest search-tests-string-match-start ()
+  (search-tests--both-engines
+    (string-match "^a" "aa" 1))
+  (search-tests--both-engines
+    (string-match "a" "abca" -1))
+  (let ((prefer-rust-regex-engine t))
+    (should-error (string-match "a" "abc" 4) :type 'args-out-of-range)))
+
+(ert-deftest search-tests-string-match-case-fold ()
+  (search-tests--both-engines
+    (let ((case-fold-search t))
+      (string-match "abc" "xABC")))
+  (search-tests--both-engines
+    (let ((case-fold-search nil))
+      (string-match "abc" "xABC"))))
+
+(ert-deftest search-tests-fallback ()
+  (let ((prefer-rust-regex-engine t))
+    ;; Back references and syntax classes need the C engine.
+    (should (= (string-match "\\(a\\)\\1" "xaa") 1))
+    (should (= (string-match "\\w+" "  ab") 2))
+    (should-error (string-match "\\(" "a") :type 'invalid-regexp)))
+
+(ert-deftest search-tests-inhibit-changing-match-data ()
+  (let ((prefer-rust-regex-engine t))
+    (string-match "b" "ab")
+    (let ((inhibit-changing-match-data t))
+      (should (= (string-match "c" "abc") 2)))
+    (should (equal (match-data) '(1 2)))))
+
+(ert-deftest search-tests-looking-at ()
+  (dolist (regexp '("b\\(c\\)" "c" "^b" "b$"))
+    (search-tests--both-engines
+      (with-temp-buffer
+        (insert "abc\nd")
+        (goto-char 2)
+        (looking-at regexp)))))
+
+(ert-deftest search-tests-re-search-forward ()
+  (search-tests--both-engines
+    (with-temp-buffer
+      (insert "foo bar foo baz foo")
+      (goto-char 2)
+      (list (re-search-forward "f\\(o+\\)" nil t)
+            (re-search-forward "ba." nil t 2)
+            (re-search-forward "qux" nil 'move)
+            (point))))
+  (search-tests--both-engines
+    (with-temp-buffer
+      (insert "abc abc")
+      (goto-char (point-min))
+      ;; The bound doesn't end the text that anchors see.
+      (list (re-search-forward "c$" 4 t)
+            (re-search-forward "c\\'" 4 t))))
+  (search-tests--both-engines
+    (with-temp-buffer
+      (insert "aaaa")
+      (goto-char (point-min))
+      ;; A match stops at the bound.
+      (re-search-forward "a+" 3 t)))
+  (let ((prefer-rust-regex-engine t))
+    (with-temp-buffer
+      (insert "abc")
+      (goto-char 2)
+      (should-error (re-search-forward "x") :type 'search-failed)
+      (should-error (re-search-forward "c" 1) :type 'error))))
+
+(ert-deftest search-tests-re-search-backward ()
+  (search-tests--both-engines
+    (with-temp-buffer
+      (insert (make-string 200 ?x) "ab ab ab" (make-string 200 ?y))
+      (list (re-search-backward "a\\(b\\)" nil t)
+            (re-search-backward "ab" nil t 2)
+            (re-search-backward "ab" nil t))))
+  (search-tests--both-engines
+    (with-temp-buffer
+      (insert "aaa")
+      ;; Each match has to end where the search started.
+      (re-search-backward "a+" nil t)))
+  (search-tests--both-engines
+    (with-temp-buffer
+      (insert "ab\nab")
+      (narrow-to-region 2 5)
+      (goto-char (point-max))
+      (list (re-search-backward "^a" nil t)
+            (re-search-backward "b" 4 'move))))
+  (search-tests--both-engines
+    (with-temp-buffer
+      (insert "ab\nab")
+      (goto-char 3)
+      ;; Nor does point end the text that `$' sees.
+      (list (re-search-backward "a$" nil t)
+            (re-search-backward "ab?" nil t 2)))))
+
+;; The literal searches have no C counterpart left to compare with.
+(ert-deftest search-tests-search-forward ()
+  (let ((case-fold-search nil))
+    (with-temp-buffer
+      (insert "foo bar foo baz foo")
+      (goto-char 2)
+      (should (= (search-forward "foo") 12))
+      (should (equal (list (match-beginning 0) (match-end 0)) '(9 12)))
+      (should (= (search-forward "ba" nil nil 1) 15))
+      (should-not (search-forward "bar" nil t))
+      (should (= (point) 15))
+      (should-not (search-forward "qux" nil 'move))
+      (should (= (point) (point-max)))
+      (goto-char (point-min))
+      (should (= (search-forward "foo" nil t 3) 20))
+      (goto-char (point-min))
+      (should-not (search-forward "foo" nil t 4))
+      (should (= (point) (point-min)))
+      (should-not (search-forward "baz" 14 t))
+      (should (= (search-forward "bar" 8 t) 8))
+      (should-error (search-forward "foo" 1) :type 'error)
+      (should-error (search-forward "qux") :type 'search-failed)
+      (should (= (search-forward "" nil t) (point))))))
+
+(ert-deftest search-tests-search-backward ()
+  (let ((case-fold-search nil))
+    (with-temp-buffer
+      (insert (make-string 200 ?x) "ab ab ab" (make-string 200 ?y))
+      (should (= (search-backward "ab") 207))
+      (should (equal (list (match-beginning 0) (match-end 0)) '(207 209)))
+      (should (= (search-backward "ab" nil t 2) 201))
+      (should-not (search-backward "ab" nil t))
+      (goto-char (point-max))
+      (should-not (search-backward "ab" 300 t))
+      (should (= (search-backward "y" 300 t 2) 407))
+      (should-error (search-backward "z") :type 'search-failed))))
+
+(ert-deftest search-tests-search-gap ()
+  "Occurrences across the gap are found."
+  (dolist (case-fold-search '(nil t))
+    (with-temp-buffer
+      (insert "one twothree")
+      (goto-char 8)
+      ;; Move the gap between "two" and "three".
+      (insert " ")
+      (delete-char -1)
+      (goto-char (point-min))
+      (should (= (search-forward "twoth") 10))
+      (goto-char (point-max))
+      (should (= (search-backward "wothr") 6)))))
+
+(ert-deftest search-tests-search-case-fold ()
+  (with-temp-buffer
+    (insert "Éclair ÉCLAIR éclair")
+    (goto-char (point-min))
+    (let ((case-fold-search t))
+      (should (= (search-forward "éCLAIR") 7))
+      (should (= (search-forward "éclair" nil t 2) 21))
+      (should (= (search-backward "ÉCLAIR" nil t 3) 1))
+      (should (= (search-forward "r é") 9))
+      (goto-char (point-max))
+      (should (= (search-backward "IR É" nil t) 12))
+      (should-not (search-forward "éclairs" nil t)))
+    (let ((case-fold-search nil))
+      (goto-char (point-min))
+      (should (= (search-forward "éclair") 21))
+      (goto-char (point-min))
+      (should (= (search-forward "ÉCLAIR") 14))
+      (goto-char (point-min))
+      (should-not (search-forward "éCLAIR" nil t)))))
+
+(ert-deftest search-tests-search-unibyte ()
+  (with-temp-buffer
+    (set-buffer-multibyte nil)
+    (insert "abcABC\351")
+    (goto-char (point-min))
+    (let ((case-fold-search t))
+      (should (= (search-forward "Ca") 5))
+      (should (= (search-backward "CA") 3)))
+    (let ((case-fold-search nil))
+      (goto-char (point-min))
+      (should-not (search-forward "Ca" nil t))
+      (should (= (search-forward "C\351") 8)))))
+
+(ert-deftest search-tests-replace-match-string ()
+  (let ((case-fold-search nil))
+    (should (string-match "b\\(c\\)\\|\\(x\\)" "abcd"))
+    (should (equal (replace-match "[\\&-\\1-\\2-\\\\-\\?]" t nil "abcd")
+                   "a[bc-c--\\-\\?]d"))
+    (should (equal (replace-match "x" t t "abcd" 1) "abxd"))
+    (should-error (replace-match "\\x" t nil "abcd"))
+    (should-error (replace-match "x" t t "abcd" 3) :type 'args-out-of-range)
+    (should (string-match "foo bar" "a foo bar"))
+    (should (equal (replace-match "baz qux" nil nil "a foo bar")
+                   "a baz qux"))
+    (should (string-match "Foo Bar" "a Foo Bar"))
+    (should (equal (replace-match "baz qux" nil nil "a Foo Bar")
+                   "a Baz Qux"))
+    (should (string-match "FOO" "a FOO"))
+    (should (equal (replace-match "baz" nil nil "a FOO") "a BAZ"))))
+
+(ert-deftest search-tests-replace-match-buffer ()
+  (with-temp-buffer
+    (insert "one two three")
+    (goto-char (point-min))
+    (should (re-search-forward "\\(t\\)\\(w\\)o" nil t))
+    (replace-match "<\\2\\1\\&>")
+    (should (equal (buffer-string) "one <wttwo> three"))
+    (should (= (point) 12))
+    (goto-char (point-max))
+    (should (re-search-backward "THREE\\|three" nil t))
+    (replace-match "four" t)
+    (should (equal (buffer-string) "one <wttwo> four"))
+    (should (= (point) 17))
+    (goto-char (point-min))
+    (should (search-forward "one" nil t))
+    (should-error (replace-match "\\?"))))
+
+(ert-deftest search-tests-replace-regexp-in-string ()
+  (should (equal (replace-regexp-in-string "o+" "0" "foo boo") "f0 b0"))
+  (should (equal (replace-regexp-in-string "x*" "-" "ab") "-a-b"))
+  (should (equal (replace-regexp-in-string "[aeiou]" #'upcase "banana")
+                 "bAnAnA"))
+  (should (equal (replace-regexp-in-string "\\(foo\\).*\\'" "bar" " foo foo"
+                                           nil nil 1)
+                 " bar foo"))
+  ;; The text before START is left out.
+  (should (equal (replace-regexp-in-string "a" "\\&\\&" "banana" nil nil nil 3)
+                 "aanaa"))
+  (let ((string "banana"))
+    (string-match "n" string)
+    (replace-regexp-in-string "a" "o" string)
+    ;; The match data are saved.
+    (should (equal (match-data) '(2 3)))))
+
+(ert-deftest search-tests-regexp-quote ()
+  (should (equal (regexp-quote "a.b*c[d]^$\\?+") "a\\.b\\*c\\[d]\\^\\$\\\\\\?\\+"))
+  (should (equal (regexp-quote "") ""))
+  (let ((quoted (regexp-quote "é.")))
+    (should (multibyte-string-p quoted))
+    (should (equal quoted "é\\.")))
+  (should (string-match (regexp-quote "(a+b)*") "x(a+b)*y")))
+
+(ert-deftest search-tests-looking-back ()
+  (with-temp-buffer
+    (insert "aaab foo")
+    (should (looking-back "fo+" nil))
+    (should (= (match-beginning 0) 6))
+    (should (= (point) 9))
+    (should-not (looking-back "bar" nil))
+    (should-not (looking-back "fo+" 7))
+    (goto-char 4)
+    (should (looking-back "a" nil))
+    (should (= (match-beginning 0) 3))
+    (should (looking-back "a+" nil t))
+    (should (= (match-beginning 0) 1))
+    (should (= (match-end 0) 4))
+    (should (= (point) 4))
+    (should (= (point-max) 9))))
+
+(provide 'search-tests)
+
+;;; search-tests.el ends here
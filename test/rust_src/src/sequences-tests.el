@@ -0,0 +1,264 @@
+;;; sequences-tests.el --- Tests for sequences.rs
+
+;;; Code:
+
+(require 'ert)
+(require 'cl-lib)
+
+(ert-deftest sequences-tests-append ()
+  (should (equal (append '(1 2) [3 4] "ab" nil) '(1 2 3 4 ?a ?b)))
+  (should (equal (append '(1) 2) '(1 . 2)))
+  (should (eq (append nil 'tail) 'tail))
+  (should (equal (append (make-bool-vector 2 t) nil) '(t t)))
+  (let ((tail (list 3)))
+    (should (eq (cddr (append '(1 2) tail)) tail)))
+  (should-error (append 1 nil) :type 'wrong-type-argument))
+
+(ert-deftest sequences-tests-vconcat ()
+  (should (equal (vconcat) []))
+  (should (equal (vconcat '(1 2) [3] "é") [1 2 3 ?é]))
+  (should (equal (vconcat (make-bool-vector 1 nil)) [nil])))
+
+(ert-deftest sequences-tests-concat ()
+  (should (equal (concat) ""))
+  (should (equal (concat "ab" '(?c) [?d]) "abcd"))
+  (should (multibyte-string-p (concat "a" "é")))
+  (should-not (multibyte-string-p (concat "a" "b")))
+  (should (equal (concat (string-to-unibyte "\300") "é")
+                 (string (unibyte-char-to-multibyte #o300) ?é)))
+  (should (equal (concat (make-bool-vector 0 nil) "a") "a"))
+  (should-error (concat (make-bool-vector 1 t)) :type 'wrong-type-argument)
+  (should-error (concat '(a)) :type 'wrong-type-argument))
+
+(ert-deftest sequences-tests-concat-text-properties ()
+  (let ((s (concat "ab" (propertize "cd" 'face 'bold))))
+    (should (equal s "abcd"))
+    (should-not (get-text-property 1 'face s))
+    (should (eq (get-text-property 2 'face s) 'bold))))
+
+;; Copying functions
+
+(ert-deftest sequences-tests-copy-sequence ()
+  (should-not (copy-sequence nil))
+  (let* ((l (list 1 (list 2)))
+         (c (copy-sequence l)))
+    (should (equal c l))
+    (should-not (eq c l))
+    (should (eq (cadr c) (cadr l))))
+  (let* ((v (vector 1 2))
+         (c (copy-sequence v)))
+    (aset c 0 'x)
+    (should (equal v [1 2])))
+  (let* ((s (propertize "ab" 'face 'bold))
+         (c (copy-sequence s)))
+    (should (equal c "ab"))
+    (should (eq (get-text-property 0 'face c) 'bold))
+    (aset c 0 ?z)
+    (should (equal s "ab")))
+  (let* ((bv (make-bool-vector 70 nil))
+         (c (progn (aset bv 65 t) (copy-sequence bv))))
+    (should (equal c bv))
+    (aset c 0 t)
+    (should-not (aref bv 0)))
+  (let* ((ct (make-char-table 'test 'default))
+         (c (progn (set-char-table-range ct ?a 'x) (copy-sequence ct))))
+    (should (eq (aref c ?a) 'x))
+    (aset c ?a 'y)
+    (should (eq (aref ct ?a) 'x)))
+  (let* ((r (record 'foo 1 2))
+         (c (copy-sequence r)))
+    (should (recordp c))
+    (should (eq (type-of c) 'foo))
+    (aset c 1 'x)
+    (should (eq (aref r 1) 1)))
+  (should-error (copy-sequence 1) :type 'wrong-type-argument))
+
+(ert-deftest sequences-tests-copy-alist ()
+  (should-not (copy-alist nil))
+  (let* ((alist (list (cons 'a 1) 'b (cons 'c 2)))
+         (c (copy-alist alist)))
+    (should (equal c alist))
+    (should-not (eq (car c) (car alist)))
+    (should (eq (cadr c) 'b))
+    (setcdr (car c) 10)
+    (should (eq (cdar alist) 1))))
+
+(ert-deftest sequences-tests-copy-tree ()
+  (let* ((tree (list (list 1 2) (vector (list 3)) 4))
+         (c (copy-tree tree)))
+    (should (equal c tree))
+    (should-not (eq (car c) (car tree)))
+    (should (eq (cadr c) (cadr tree))))
+  (let* ((tree (list (vector (list 3)) 4))
+         (c (copy-tree tree t)))
+    (should (equal c tree))
+    (should-not (eq (car c) (car tree)))
+    (should-not (eq (aref (car c) 0) (aref (car tree) 0))))
+  (let ((c (copy-tree (cons 1 (vector (list 2))) t)))
+    (should (equal c (cons 1 [(2)]))))
+  (should (equal (copy-tree '(1 2 . 3)) '(1 2 . 3)))
+  (should (eq (copy-tree 'a) 'a)))
+
+;; Reversal
+
+(ert-deftest sequences-tests-reverse ()
+  (should-not (reverse nil))
+  (let ((l (list 1 2 3)))
+    (should (equal (reverse l) '(3 2 1)))
+    (should (equal l '(1 2 3))))
+  (let ((v (vector 1 2 3)))
+    (should (equal (reverse v) [3 2 1]))
+    (should (equal v [1 2 3])))
+  (should (equal (reverse "abc") "cba"))
+  (should (equal (reverse "aéb€") "b€éa"))
+  (should (equal (reverse (string-to-unibyte "\300a")) (string-to-unibyte "a\300")))
+  (let ((bv (make-bool-vector 5 nil)))
+    (aset bv 0 t)
+    (should (equal (reverse bv) (bool-vector nil nil nil nil t)))
+    (should (aref bv 0)))
+  (should-error (reverse '(1 . 2)) :type 'wrong-type-argument)
+  (let ((l (list 1 2)))
+    (setcdr (cdr l) l)
+    (should-error (reverse l) :type 'circular-list))
+  (should-error (reverse 1) :type 'wrong-type-argument))
+
+(ert-deftest sequences-tests-nreverse ()
+  (should-not (nreverse nil))
+  (should (equal (nreverse (list 1 2 3)) '(3 2 1)))
+  (let ((v (vector 1 2 3 4)))
+    (should (eq (nreverse v) v))
+    (should (equal v [4 3 2 1])))
+  (should (equal (nreverse (copy-sequence "aé")) "éa"))
+  (let ((bv (bool-vector t nil nil)))
+    (should (eq (nreverse bv) bv))
+    (should (equal bv (bool-vector nil nil t))))
+  (let ((l (list 1 2 3)))
+    (setcdr (cddr l) l)
+    (should-error (nreverse l) :type 'circular-list)
+    ;; The list is not modified when the cycle is detected.
+    (should (eq (cdddr l) l)))
+  (let ((l (list 1 2 3)))
+    (setcdr (cddr l) (cdr l))
+    (should-error (nreverse l) :type 'circular-list))
+  (let ((l (cons 1 (cons 2 3))))
+    (should-error (nreverse l) :type 'wrong-type-argument)
+    (should (equal l '(1 2 . 3))))
+  (should-error (nreverse 'a) :type 'wrong-type-argument))
+
+;; Mapping functions
+
+(ert-deftest sequences-tests-mapcar ()
+  (should (equal (mapcar #'1+ '(1 2 3)) '(2 3 4)))
+  (should (equal (mapcar #'1+ [1 2]) '(2 3)))
+  (should (equal (mapcar #'identity "aé") '(?a ?é)))
+  (should (equal (mapcar #'identity (make-bool-vector 2 t)) '(t t)))
+  (should-not (mapcar #'identity nil))
+  (should-error (mapcar #'identity (make-char-table 'foo))
+                :type 'wrong-type-argument))
+
+(ert-deftest sequences-tests-mapcar-modified ()
+  ;; Growing a list while mapping over it must not loop forever.
+  (let ((l (list 1 2 3)))
+    (should (equal (mapcar (lambda (x) (nconc l (list x)) x) l)
+                   '(1 2 3))))
+  ;; Shortening it stops the mapping early.
+  (let ((l (list 1 2 3)))
+    (should (equal (mapcar (lambda (x) (setcdr l nil) x) l) '(1))))
+  ;; Changing the width of characters in a string.
+  (let ((s (string ?a ?b ?c)))
+    (should (equal (mapcar (lambda (c) (aset s 1 ?é) c) s)
+                   '(?a ?é ?c)))))
+
+(ert-deftest sequences-tests-mapc ()
+  (let ((sum 0)
+        (l '(1 2 3)))
+    (should (eq (mapc (lambda (x) (setq sum (+ sum x))) l) l))
+    (should (= sum 6))))
+
+(ert-deftest sequences-tests-mapcan ()
+  (should (equal (mapcan #'list '(1 2 3)) '(1 2 3)))
+  (should (equal (mapcan (lambda (x) (and (cl-oddp x) (list x))) [1 2 3])
+                 '(1 3)))
+  (should-not (mapcan #'list nil)))
+
+(ert-deftest sequences-tests-mapconcat ()
+  (should (equal (mapconcat #'symbol-name '(a b c) "-") "a-b-c"))
+  (should (equal (mapconcat #'identity '("x") ", ") "x"))
+  (should (equal (mapconcat #'identity nil ", ") ""))
+  (should (equal (mapconcat #'string "abc" nil) "abc")))
+
+;; Sorting.
+
+(ert-deftest sequences-tests-sort-list ()
+  (let* ((l (list 3 1 2))
+         (sorted (sort l #'<)))
+    (should (equal sorted '(1 2 3)))
+    ;; The conses of the list are reused, starting with the first.
+    (should (eq sorted l)))
+  (should-not (sort nil #'<))
+  (should (equal (sort (list 1) #'<) '(1)))
+  (should-error (sort (cons 2 1) #'<) :type 'wrong-type-argument))
+
+(ert-deftest sequences-tests-sort-vector ()
+  (let ((v (vector 5 3 4 1 2)))
+    (should (eq (sort v #'<) v))
+    (should (equal v [1 2 3 4 5])))
+  (should (equal (sort (vector) #'<) []))
+  (should-error (sort "abc" #'<) :type 'wrong-type-argument))
+
+(ert-deftest sequences-tests-sort-stable ()
+  (let ((pairs '((1 . a) (0 . b) (1 . c) (0 . d) (1 . e))))
+    (should (equal (sort (copy-sequence pairs) #'car-less-than-car)
+                   '((0 . b) (0 . d) (1 . a) (1 . c) (1 . e))))
+    (should (equal (sort (vconcat pairs) #'car-less-than-car)
+                   [(0 . b) (0 . d) (1 . a) (1 . c) (1 . e)]))))
+
+(ert-deftest sequences-tests-sort-predicate-error ()
+  ;; A predicate that exits nonlocally leaves the sequence intact.
+  (let ((l (list 3 1 2))
+        (v (vector 3 1 2))
+        (calls 0))
+    (dolist (seq (list l v))
+      (setq calls 0)
+      (should-error (sort seq (lambda (a b)
+                                (when (= (setq calls (1+ calls)) 2)
+                                  (error "Stop"))
+                                (< a b)))))
+    (should (equal l '(3 1 2)))
+    (should (equal v [3 1 2]))
+    (should (= (catch 'done (sort (list 2 1) (lambda (_a _b) (throw 'done 7))))
+               7))))
+
+(ert-deftest sequences-tests-sort-value< ()
+  (should (equal (sort (list 3 1.5 2) 'value<) '(1.5 2 3)))
+  (should (equal (sort (vector "b" "c" "a") 'value<) ["a" "b" "c"]))
+  (should (equal (sort (list 'b 'a) 'value<) '(a b)))
+  (should-error (sort (list 1 "a") 'value<) :type 'wrong-type-argument)
+  (should-error (sort (list '(1) '(2)) 'value<) :type 'wrong-type-argument))
+
+(ert-deftest sequences-tests-delete ()
+  (let ((l (list "a" 'b (copy-sequence "a"))))
+    (should (equal (delete "a" l) '(b))))
+  (let ((v (vector 1 "a" 2)))
+    (should (equal (delete "a" v) [1 2]))
+    (should (equal v [1 "a" 2]))
+    (should (eq (delete 3 v) v)))
+  (should (equal (delete ?a "banana") "bnn"))
+  (should (equal (delete ?é "café é") "caf "))
+  (should (multibyte-string-p (delete ?a "a\u00e9")))
+  (should-not (multibyte-string-p (delete ?a "ab")))
+  (let ((s "abc"))
+    (should (eq (delete ?d s) s))
+    (should (eq (delete "a" s) s)))
+  (should-error (delete 1 (cons 1 2)) :type 'wrong-type-argument))
+
+(ert-deftest sequences-tests-remove ()
+  (let ((l (list 1 "a" 2)))
+    (should (equal (remove "a" l) '(1 2)))
+    (should (equal l '(1 "a" 2)))
+    (should-not (eq (remove 3 l) l)))
+  (should (equal (remove ?a "banana") "bnn"))
+  (should (equal (remove 1 [1 2 1]) [2])))
+
+(provide 'sequences-tests)
+;;; sequences-tests.el ends here
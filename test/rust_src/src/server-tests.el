@@ -0,0 +1,32 @@
+;;; server-tests.el --- Tests for server.rs
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest server-tests--quote-arg ()
+  (should (equal (server-quote-arg "-a b&c\nd") "&-a&_b&&c&nd"))
+  (should (equal (server-unquote-arg "&-a&_b&&c&nd") "-a b&c\nd"))
+  (let ((arg "ä -x\n&"))
+    (should (equal (server-unquote-arg (server-quote-arg arg)) arg))))
+
+(ert-deftest server-tests--split-request ()
+  (should (equal (server-split-request "-file  /tmp/a&_b -eval (+&_1&_2)")
+                 '("-file" "/tmp/a b" "-eval" "(+ 1 2)")))
+  (should-not (server-split-request "")))
+
+(ert-deftest server-tests--authenticate ()
+  (let ((proc (make-pipe-process :name "server-tests" :noquery t)))
+    (unwind-protect
+        (progn
+          (process-put proc :auth-key "secret")
+          (should-not (server-authenticate proc "-auth wrong\n-nowait\n"))
+          (should-not (process-get proc :authenticated))
+          (should (equal (server-authenticate proc "-auth secret\n-nowait\n")
+                         "-nowait\n"))
+          (should (process-get proc :authenticated))
+          (should (equal (server-authenticate proc "-nowait\n") "-nowait\n")))
+      (delete-process proc))))
+
+(provide 'server-tests)
+;;; server-tests.el ends here
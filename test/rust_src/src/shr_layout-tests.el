@@ -0,0 +1,72 @@
+;;; shr_layout-tests.el --- Tests for shr_layout.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+;; In batch mode, characters are as wide as their columns.
+
+(defun shr-layout-tests-render (dom width)
+  (with-temp-buffer
+    (shr-layout-dom dom width)
+    (buffer-string)))
+
+(defun shr-layout-tests-faces (pos)
+  (let ((face (get-text-property pos 'face)))
+    (if (listp face) face (list face))))
+
+(ert-deftest shr_layout-tests-paragraphs ()
+  "Paragraphs are filled, and separated by blank lines."
+  (should (equal (substring-no-properties
+                  (shr-layout-tests-render
+                   '(html nil (body nil (p nil "aaa  bbb\nccc") (p nil "ddd")))
+                   7))
+                 "aaa bbb\nccc\n\nddd\n\n")))
+
+(ert-deftest shr_layout-tests-lists ()
+  "List items are indented under their bullets or numbers."
+  (should (equal (substring-no-properties
+                  (shr-layout-tests-render
+                   '(ul nil (li nil "one") (li nil "two three")) 8))
+                 "* one\n* two\n  three\n\n"))
+  (should (equal (substring-no-properties
+                  (shr-layout-tests-render
+                   '(ol ((start . "9")) (li nil "nine") (li nil "ten")) 20))
+                 "9. nine\n10. ten\n\n")))
+
+(ert-deftest shr_layout-tests-pre ()
+  (should (equal (substring-no-properties
+                  (shr-layout-tests-render '(pre nil "a  b\n c") 20))
+                 "a  b\n c\n\n")))
+
+(ert-deftest shr_layout-tests-inline ()
+  "Inline elements give their text faces and links."
+  (with-temp-buffer
+    (shr-layout-dom '(p nil "see " (a ((href . "http://x")) "here")
+                        " " (b nil "now"))
+                    80)
+    (goto-char (point-min))
+    (search-forward "here")
+    (should (equal (get-text-property (match-beginning 0) 'shr-url)
+                   "http://x"))
+    (should (memq 'shr-link
+                  (shr-layout-tests-faces (match-beginning 0))))
+    (should-not (get-text-property 1 'shr-url))
+    (search-forward "now")
+    (should (memq 'bold
+                  (shr-layout-tests-faces (match-beginning 0))))))
+
+(ert-deftest shr_layout-tests-table ()
+  "The columns of tables are aligned by display properties."
+  (with-temp-buffer
+    (shr-layout-dom '(table nil
+                            (tr nil (th nil "a") (th nil "bb"))
+                            (tr nil (td nil "ccc") (td nil "d")))
+                    20)
+    (should (equal (buffer-substring-no-properties (point-min) (point-max))
+                   "a bb\nccc d\n\n"))
+    (should (equal (get-text-property 2 'display) '(space :align-to 4)))
+    (should (eq (get-text-property 1 'face) 'bold))))
+
+(provide 'shr_layout-tests)
+;;; shr_layout-tests.el ends here
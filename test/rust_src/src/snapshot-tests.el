@@ -0,0 +1,144 @@
+;;; snapshot-tests.el --- Tests for snapshot.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest snapshot-tests-text-is-kept ()
+  (with-temp-buffer
+    (insert "héllo world")
+    (narrow-to-region 3 8)
+    (let ((snapshot (buffer-snapshot)))
+      (should (buffer-snapshot-p snapshot))
+      (should (eq (buffer-snapshot-buffer snapshot) (current-buffer)))
+      (should (= (buffer-snapshot-modified-tick snapshot) (buffer-modified-tick)))
+      (widen)
+      (erase-buffer)
+      (insert "changed")
+      (should (equal (buffer-snapshot-substring snapshot) "llo w"))
+      (should (equal (buffer-snapshot-substring snapshot 4 6) "lo"))
+      (should-error (buffer-snapshot-substring snapshot 1 4)
+                    :type 'args-out-of-range)
+      (should (buffer-snapshot-release snapshot))
+      (should-not (buffer-snapshot-release snapshot))
+      (should-error (buffer-snapshot-substring snapshot)))))
+
+(ert-deftest snapshot-tests-shared-text ()
+  (with-temp-buffer
+    (insert "abc")
+    (let ((first (buffer-snapshot))
+          (second (buffer-snapshot)))
+      (insert "d")
+      (let ((third (buffer-snapshot)))
+        (should (equal (buffer-snapshot-substring first) "abc"))
+        (should (equal (buffer-snapshot-substring second) "abc"))
+        (should (equal (buffer-snapshot-substring third) "abcd"))
+        (mapc #'buffer-snapshot-release (list first second third))))))
+
+(ert-deftest snapshot-tests-search ()
+  (with-temp-buffer
+    (insert "one two three")
+    (let ((snapshot (buffer-snapshot))
+          (case-fold-search nil))
+      (set-match-data '(1 2))
+      (should (equal (buffer-snapshot-search snapshot "t\\(w\\)o") '(5 8 6 7)))
+      (should (equal (buffer-snapshot-search snapshot "t" 8) '(9 10)))
+      (should-not (buffer-snapshot-search snapshot "four"))
+      (should (equal (match-data) '(1 2)))
+      (should-error (buffer-snapshot-search snapshot "\\(o\\)\\1"))
+      (buffer-snapshot-release snapshot))))
+
+(ert-deftest snapshot-tests-copy-on-write ()
+  (with-temp-buffer
+    (insert "abcdef")
+    (goto-char 3)
+    (let ((before (buffer-snapshot)))
+      ;; Inserting at the end leaves the text of BEFORE alone.
+      (goto-char (point-max))
+      (insert "gh")
+      (let ((after (buffer-snapshot)))
+        (goto-char 3)
+        (insert "X")
+        (delete-region 1 2)
+        (upcase-region 1 (point-max))
+        (should (equal (buffer-snapshot-substring before) "abcdef"))
+        (should (equal (buffer-snapshot-substring after) "abcdefgh"))
+        (should (equal (buffer-string) "BXCDEFGH"))))))
+
+(ert-deftest snapshot-tests-other-buffer ()
+  (let ((other (generate-new-buffer " *snapshot*")))
+    (unwind-protect
+        (let ((snapshot (progn
+                          (with-current-buffer other
+                            (insert "one three")
+                            (goto-char 4)
+                            (insert " two"))
+                          (buffer-snapshot other))))
+          (should (equal (buffer-snapshot-substring snapshot) "one two three"))
+          (kill-buffer other)
+          (should (equal (buffer-snapshot-substring snapshot) "one two three"))
+          (should-error (buffer-snapshot other)))
+      (kill-buffer other))))
+
+(ert-deftest snapshot-tests-survives-gc ()
+  (with-temp-buffer
+    (insert "kept")
+    (let ((snapshot (buffer-snapshot)))
+      (garbage-collect)
+      (erase-buffer)
+      (should (equal (buffer-snapshot-substring snapshot) "kept")))))
+
+(defun snapshot-tests--search-result (search)
+  "Wait for the result of SEARCH, for at most ten seconds."
+  (let ((result 'running)
+        (tries 1000))
+    (while (and (eq (setq result (buffer-snapshot-search-result search))
+                    'running)
+                (> (setq tries (1- tries)) 0))
+      (sleep-for 0.01))
+    result))
+
+(ert-deftest snapshot-tests-search-start ()
+  (with-temp-buffer
+    (insert "one two\nthree föur")
+    (let* ((case-fold-search nil)
+           (snapshot (buffer-snapshot))
+           (search (buffer-snapshot-search-start snapshot "t[a-z]+")))
+      (should (buffer-snapshot-search-p search))
+      (should-not (buffer-snapshot-search-p snapshot))
+      (should-not (buffer-snapshot-p search))
+      (erase-buffer)
+      (should (equal (snapshot-tests--search-result search) '((5 . 8) (9 . 14))))
+      (should (equal (buffer-snapshot-search-result search) '((5 . 8) (9 . 14))))
+      (should-not (snapshot-tests--search-result
+                   (buffer-snapshot-search-start snapshot "five")))
+      (should (equal (snapshot-tests--search-result
+                      (buffer-snapshot-search-start snapshot "ö"))
+                     '((16 . 17))))
+      (should-error (buffer-snapshot-search-start snapshot "\\(o\\)\\1"))
+      (should-error (buffer-snapshot-search-result snapshot)
+                    :type 'wrong-type-argument))))
+
+(ert-deftest snapshot-tests-wrong-type ()
+  (should-not (buffer-snapshot-p [buffer-snapshot 0 nil]))
+  (should-error (buffer-snapshot-substring 'foo) :type 'wrong-type-argument))
+
+(ert-deftest snapshot-tests-forged-handle ()
+  "Only the record `buffer-snapshot' returned is a handle."
+  (with-temp-buffer
+    (insert "secret")
+    (let ((snapshot (buffer-snapshot)))
+      (dolist (forged (list (copy-sequence snapshot)
+                            (record 'buffer-snapshot (aref snapshot 1)
+                                    (current-buffer))
+                            (record 'buffer-snapshot (1+ (aref snapshot 1))
+                                    (current-buffer))))
+        (should-not (buffer-snapshot-p forged))
+        (should-error (buffer-snapshot-substring forged)
+                      :type 'wrong-type-argument)
+        (should-error (buffer-snapshot-release forged)
+                      :type 'wrong-type-argument))
+      (should (equal (buffer-snapshot-substring snapshot) "secret")))))
+
+(provide 'snapshot-tests)
+;;; snapshot-tests.el ends here
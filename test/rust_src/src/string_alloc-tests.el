@@ -0,0 +1,45 @@
+;;; string_alloc-tests.el --- Tests for string_alloc.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest string-alloc-tests-compaction ()
+  "Live strings keep their contents when the dead ones around them are compacted."
+  (let ((kept nil))
+    (dotimes (i 5000)
+      (let ((string (make-string (% i 50) (+ ?a (% i 26)))))
+        (when (zerop (% i 3))
+          (push (cons i string) kept))))
+    (garbage-collect)
+    (garbage-collect)
+    (dolist (entry kept)
+      (let ((i (car entry)))
+        (should (equal (cdr entry) (make-string (% i 50) (+ ?a (% i 26)))))))))
+
+(ert-deftest string-alloc-tests-large ()
+  (let ((large (make-string 100000 ?x))
+        (small (make-string 10 ?y)))
+    (make-string 200000 ?z)
+    (garbage-collect)
+    (should (= (length large) 100000))
+    (should (string-match-p "\\`x+\\'" large))
+    (should (equal small "yyyyyyyyyy"))))
+
+(ert-deftest string-alloc-tests-multibyte ()
+  (let ((string (make-string 3 ?é)))
+    (should (multibyte-string-p string))
+    (should (= (string-bytes string) 6))
+    (should (equal (number-to-string 1.5) "1.5"))
+    (should-not (multibyte-string-p (number-to-string 1.5)))))
+
+(ert-deftest string-alloc-tests-aset-realloc ()
+  "Storing a wider character reallocates the data of a string."
+  (let ((string (copy-sequence "abc")))
+    (aset string 1 ?€)
+    (garbage-collect)
+    (should (equal string "a€c"))
+    (should (= (string-bytes string) 5))))
+
+(provide 'string_alloc-tests)
+;;; string_alloc-tests.el ends here
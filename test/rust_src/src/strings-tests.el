@@ -37,4 +37,73 @@
   ;; Test single unicode character with multiple code-points
   (should (eq (string-width "é") 1)))
 
+(ert-deftest string-lessp ()
+  (should (string-lessp "abc" "abd"))
+  (should (string-lessp "ab" "abc"))
+  (should-not (string-lessp "abc" "abc"))
+  (should (string-lessp 'abc "abd"))
+  ;; Characters are compared, not their bytes.
+  (should (string-lessp "\351" "ā"))
+  (should (string-lessp "é" (string-to-multibyte "\351")))
+  (should-not (string-lessp "ā" "\351")))
+
+(ert-deftest string-greaterp ()
+  (should (string-greaterp "abd" "abc"))
+  (should (string> "abc" 'ab))
+  (should-not (string-greaterp "abc" "abc")))
+
+(ert-deftest string-version-lessp ()
+  (should (string-version-lessp "foo2.png" "foo12.png"))
+  (should-not (string-version-lessp "foo12.png" "foo2.png"))
+  (should (string-version-lessp "foo1.0~rc1" "foo1.0"))
+  (should (string-version-lessp 'a1 'a10))
+  (should-not (string-version-lessp "a1" "a1"))
+  ;; Null bytes separate pieces compared one after the other.
+  (should (string-version-lessp "a\0b2" "a\0b10"))
+  (should (string-version-lessp "a" "a\0"))
+  (should-not (string-version-lessp "a\0" "a")))
+
+(ert-deftest string-collate-lessp ()
+  (should (string-collate-lessp "abc" "abd"))
+  (should-not (string-collate-lessp "abd" "abc"))
+  (should (string-collate-lessp "A" "b" nil t))
+  (should-error (string-collate-lessp "a" "b" 'en_US)))
+
+;; "\303\251" is the UTF-8 encoding of é.
+
+(ert-deftest string-to-multibyte ()
+  (let ((m (string-to-multibyte "abc")))
+    (should (eq (string-to-multibyte m) m)))
+  (let ((m (string-to-multibyte "\303\251")))
+    (should (multibyte-string-p m))
+    (should (= (length m) 2))
+    (should (equal (append m nil) '(#x3fffc3 #x3fffa9))))
+  (should (multibyte-string-p (string-to-multibyte "abc")))
+  (should (equal (string-to-multibyte "abc") "abc")))
+
+(ert-deftest string-as-multibyte ()
+  (let ((m (string-as-multibyte "\303\251")))
+    (should (multibyte-string-p m))
+    (should (equal m "é")))
+  (let ((m (string-as-multibyte "a\251\303")))
+    (should (equal (append m nil) '(?a #x3fffa9 #x3fffc3))))
+  (let ((s "é"))
+    (should (eq (string-as-multibyte s) s))))
+
+(ert-deftest string-to-unibyte ()
+  (let ((s "\303\251"))
+    (should (eq (string-to-unibyte s) s)))
+  (let ((u (string-to-unibyte (string-to-multibyte "a\303\251"))))
+    (should-not (multibyte-string-p u))
+    (should (equal u "a\303\251")))
+  (should-error (string-to-unibyte "aé") :type 'error))
+
+(ert-deftest string-as-unibyte ()
+  (let ((u (string-as-unibyte "é")))
+    (should-not (multibyte-string-p u))
+    (should (equal u "\303\251")))
+  (should (equal (string-as-unibyte (string-to-multibyte "\377a")) "\377a"))
+  (let ((s "\377"))
+    (should (eq (string-as-unibyte s) s))))
+
 ;;; strings-tests ends here
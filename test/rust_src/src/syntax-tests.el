@@ -24,3 +24,180 @@
       (forward-word 2)
       (should (looking-back "forward-word"))
       (should (eq (forward-word 1) nil)))))
+
+(ert-deftest test-string-to-syntax ()
+  (should (equal (string-to-syntax "w") '(2)))
+  (should (eq (string-to-syntax "-") (string-to-syntax " ")))
+  (should (equal (string-to-syntax "()") '(4 . ?\))))
+  (should (equal (string-to-syntax ". 124b") (cons (logior 1 (ash 11 16) (ash 1 21)) nil)))
+  (should (equal (string-to-syntax "\" ") '(7)))
+  (should-not (string-to-syntax "@"))
+  (should-error (string-to-syntax ""))
+  (should-error (string-to-syntax "x"))
+  (let ((err (should-error (string-to-syntax ". 1x"))))
+    (should (string-match-p "`x' at index 3" (cadr err)))))
+
+(ert-deftest test-syntax-class-to-char ()
+  (should (= (syntax-class-to-char 0) ?\s))
+  (should (= (syntax-class-to-char 2) ?w))
+  (should (= (syntax-class-to-char 15) ?|))
+  ;; Class 13, `@', inherits the syntax instead.
+  (dolist (class '(0 1 2 3 4 5 6 7 8 9 10 11 12 14 15))
+    (should (equal (car (string-to-syntax
+                         (string (syntax-class-to-char class))))
+                   class)))
+  (should-error (syntax-class-to-char 16) :type 'args-out-of-range)
+  (should-error (syntax-class-to-char -1) :type 'args-out-of-range))
+
+(ert-deftest test-char-syntax ()
+  (with-temp-buffer
+    (should (= (char-syntax ?a) ?w))
+    (should (= (char-syntax ?\() ?\())
+    (let ((st (make-syntax-table)))
+      (modify-syntax-entry ?a "." st)
+      (modify-syntax-entry ?x ". 1p" st)
+      (set-syntax-table st)
+      (should (= (char-syntax ?a) ?.))
+      (should (= (char-syntax ?x) ?.))
+      ;; Inherited from the standard syntax table.
+      (should (= (char-syntax ?b) ?w)))
+    (should-error (char-syntax "a") :type 'wrong-type-argument)))
+
+(ert-deftest test-matching-paren ()
+  (with-temp-buffer
+    (should (= (matching-paren ?\() ?\)))
+    (should (= (matching-paren ?\]) ?\[))
+    (should-not (matching-paren ?a))
+    (let ((st (make-syntax-table)))
+      (modify-syntax-entry ?< "(>" st)
+      (modify-syntax-entry ?> ")<" st)
+      (set-syntax-table st)
+      (should (= (matching-paren ?<) ?>))
+      (should (= (matching-paren ?>) ?<)))))
+
+(ert-deftest test-backward-word ()
+  (with-temp-buffer
+    (insert "one two-three")
+    (should (backward-word))
+    (should (= (point) 9))
+    (should (backward-word 2))
+    (should (bobp))
+    (should-not (backward-word))
+    (backward-word -1)
+    (should (= (point) 4))))
+
+(ert-deftest test-forward-word-syntax-properties ()
+  (with-temp-buffer
+    (insert "foo-bar baz")
+    (put-text-property 4 5 'syntax-table (string-to-syntax "w"))
+    (goto-char (point-min))
+    (let ((parse-sexp-lookup-properties nil))
+      (forward-word)
+      (should (= (point) 4)))
+    (goto-char (point-min))
+    (let ((parse-sexp-lookup-properties t))
+      (forward-word)
+      (should (= (point) 8))
+      (backward-word)
+      (should (bobp)))))
+
+(ert-deftest test-backward-prefix-chars-syntax-properties ()
+  (with-temp-buffer
+    (insert "#a")
+    (put-text-property 1 2 'syntax-table (string-to-syntax "_ p"))
+    (goto-char 2)
+    (let ((parse-sexp-lookup-properties nil))
+      (backward-prefix-chars)
+      (should (= (point) 2)))
+    (let ((parse-sexp-lookup-properties t))
+      (backward-prefix-chars)
+      (should (bobp)))))
+
+(ert-deftest test-scan-sexps ()
+  (with-temp-buffer
+    (insert "(a b) \"c d\" e")
+    (should (= (scan-sexps 1 1) 6))
+    (should (= (scan-sexps 1 2) 12))
+    (should (= (scan-sexps 12 -2) 1))
+    (should-not (scan-sexps 1 4))
+    (should-error (scan-sexps 2 3) :type 'scan-error)))
+
+(ert-deftest test-forward-sexp ()
+  (with-temp-buffer
+    (insert "'(a (b)) c")
+    (goto-char (point-min))
+    (forward-sexp)
+    (should (= (point) 9))
+    (backward-sexp)
+    ;; The quote is a prefix of the sexp.
+    (should (bobp))
+    (forward-sexp 3)
+    (should (eobp))
+    (let ((forward-sexp-function (lambda (arg) (list 'called arg))))
+      (should (equal (backward-sexp 2) '(called -2))))))
+
+(ert-deftest test-scan-lists ()
+  (with-temp-buffer
+    (insert "(a (b)) c")
+    (should (= (scan-lists 1 1 0) 8))
+    (should (= (scan-lists 8 -1 0) 1))
+    ;; A positive depth moves out of lists.
+    (should (= (scan-lists 5 1 1) 7))
+    (should (= (scan-lists 5 -1 1) 4))
+    (should-not (scan-lists 8 1 0))))
+
+(ert-deftest test-scan-lists-errors ()
+  (with-temp-buffer
+    (insert "(a (b)")
+    (should (equal (cdr (should-error (scan-lists 1 1 0) :type 'scan-error))
+                   '("Unbalanced parentheses" 1 7))))
+  (with-temp-buffer
+    (insert "(a b) c)")
+    (should (equal (cdr (should-error (scan-lists (point-max) -1 0)
+                                      :type 'scan-error))
+                   '("Unbalanced parentheses" 8 1))))
+  (with-temp-buffer
+    (insert "a) b")
+    (should (equal (cdr (should-error (scan-lists 1 1 0) :type 'scan-error))
+                   '("Containing expression ends prematurely" 2 3)))))
+
+(ert-deftest test-scan-lists-comments ()
+  (with-temp-buffer
+    (let ((table (make-syntax-table)))
+      (modify-syntax-entry ?/ ". 124b" table)
+      (modify-syntax-entry ?* ". 23" table)
+      (modify-syntax-entry ?\n "> b" table)
+      (set-syntax-table table))
+    (insert "(a /* ) */ b) c")
+    (should (= (scan-lists 1 1 0) 14))
+    (should (= (scan-lists 14 -1 0) 1))
+    (let ((parse-sexp-ignore-comments nil))
+      (should (= (scan-lists 1 1 0) 8)))))
+
+(ert-deftest test-scan-sexps-comment-with-string-quote ()
+  ;; The odd string quote in the comment makes the start of the comment
+  ;; be found by parsing forward from the start of the defun.
+  (with-temp-buffer
+    (set-syntax-table emacs-lisp-mode-syntax-table)
+    (insert "(a) ; say \"hi\nb")
+    (dolist (comment-use-syntax-ppss '(nil t))
+      (should (= (scan-sexps (point-max) -1) 15))
+      (should (= (scan-sexps (point-max) -2) 1)))))
+
+(ert-deftest test-scan-sexps-multibyte ()
+  (with-temp-buffer
+    (insert "(é (ü)) ö")
+    (should (= (scan-lists 1 1 0) 8))
+    (should (= (scan-sexps (point-max) -1) 9))
+    (should (= (scan-sexps (point-max) -2) 1))))
+
+(ert-deftest test-scan-sexps-syntax-properties ()
+  (with-temp-buffer
+    (insert "a|b c|d")
+    (put-text-property 2 3 'syntax-table (string-to-syntax "|"))
+    (put-text-property 6 7 'syntax-table (string-to-syntax "|"))
+    (let ((parse-sexp-lookup-properties nil))
+      (should (= (scan-sexps 2 1) 4)))
+    (let ((parse-sexp-lookup-properties t))
+      (should (= (scan-sexps 2 1) 7))
+      (should (= (scan-sexps 7 -1) 2)))))
@@ -0,0 +1,48 @@
+;;; terminal-tests.el --- Tests for terminal.rs
+
+;;; Code:
+
+(require 'ert)
+
+(defun terminal-tests--wait-for-status (process status)
+  "Wait up to 10 seconds for PROCESS to have STATUS."
+  (let ((tries 100))
+    (while (and (> tries 0) (not (eq (process-status process) status)))
+      (accept-process-output process 0.1)
+      (setq tries (1- tries))))
+  (process-status process))
+
+(ert-deftest suspend-emacs-checks-stuffstring ()
+  (let ((suspend-hook nil)
+        (hook-ran nil))
+    (add-hook 'suspend-hook (lambda () (setq hook-ran t)))
+    (should-error (suspend-emacs 'not-a-string) :type 'wrong-type-argument)
+    ;; The argument is checked before anything is done.
+    (should-not hook-ran)))
+
+(ert-deftest suspend-signal-stops-batch-emacs ()
+  "SIGTSTP stops Emacs in batch mode right away.
+Only Emacs on a tty defers suspending until it has reset the
+terminal; deferring in batch mode would stop the whole process group
+through `suspend-emacs' instead of just the Emacs that got the signal."
+  (skip-unless (memq system-type '(gnu/linux berkeley-unix darwin)))
+  (let ((process (make-process
+                  :name "terminal-tests"
+                  :command (list (expand-file-name invocation-name
+                                                   invocation-directory)
+                                 "-Q" "-batch" "--eval"
+                                 "(while t (sleep-for 0.1))")
+                  :connection-type 'pipe
+                  :noquery t)))
+    (unwind-protect
+        (progn
+          (should (eq (terminal-tests--wait-for-status process 'run) 'run))
+          (signal-process process 'SIGTSTP)
+          (should (eq (terminal-tests--wait-for-status process 'stop) 'stop))
+          (signal-process process 'SIGCONT)
+          (should (eq (terminal-tests--wait-for-status process 'run) 'run)))
+      (delete-process process))))
+
+(provide 'terminal-tests)
+
+;;; terminal-tests.el ends here
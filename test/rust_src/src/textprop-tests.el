@@ -0,0 +1,81 @@
+;;; textprop-tests.el --- Tests for textprop.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(defun textprop-tests-insert ()
+  "Insert \"aaBBccDD\", with `face' `bold' on BB and `italic' on DD."
+  (insert "aa" (propertize "BB" 'face 'bold)
+          "cc" (propertize "DD" 'face 'italic)))
+
+(ert-deftest textprop-tests-search-forward ()
+  (with-temp-buffer
+    (textprop-tests-insert)
+    (goto-char (point-min))
+    (let ((match (text-property-search-forward 'face)))
+      (should (prop-match-p match))
+      (should (= (prop-match-beginning match) 3))
+      (should (= (prop-match-end match) 5))
+      (should (eq (prop-match-value match) 'bold))
+      (should (= (point) 5)))
+    (let ((match (text-property-search-forward 'face)))
+      (should (= (prop-match-beginning match) 7))
+      (should (eq (prop-match-value match) 'italic)))
+    (should-not (text-property-search-forward 'face))
+    (should (= (point) (point-max)))))
+
+(ert-deftest textprop-tests-search-forward-value ()
+  (with-temp-buffer
+    (textprop-tests-insert)
+    (goto-char (point-min))
+    (let ((match (text-property-search-forward 'face 'italic t)))
+      (should (= (prop-match-beginning match) 7))
+      (should (= (prop-match-end match) 9)))
+    (goto-char (point-min))
+    (should-not (text-property-search-forward 'face 'underline t))
+    (should (= (point) (point-min)))))
+
+(ert-deftest textprop-tests-search-forward-predicate ()
+  (with-temp-buffer
+    (textprop-tests-insert)
+    (goto-char (point-min))
+    (let ((match (text-property-search-forward
+                  'face 'italic (lambda (value prop) (not (eq value prop))))))
+      (should (= (prop-match-beginning match) 1))
+      ;; The match ends where the value changes.
+      (should (= (prop-match-end match) 3)))))
+
+(ert-deftest textprop-tests-search-backward ()
+  (with-temp-buffer
+    (textprop-tests-insert)
+    (let ((match (text-property-search-backward 'face)))
+      (should (= (prop-match-beginning match) 7))
+      (should (= (prop-match-end match) 9))
+      (should (= (point) 7)))
+    (let ((match (text-property-search-backward 'face 'bold t)))
+      (should (= (prop-match-beginning match) 3))
+      (should (= (prop-match-end match) 5)))
+    (should-not (text-property-search-backward 'face 'bold t))
+    (should (= (point) 3))))
+
+(ert-deftest textprop-tests-search-backward-adjacent ()
+  (with-temp-buffer
+    (insert "x" (propertize "A" 'p 1) (propertize "B" 'p 2))
+    ;; Standing in B, the match ends where the value changes to A's.
+    (let ((match (text-property-search-backward 'p)))
+      (should (= (prop-match-beginning match) 3))
+      (should (= (prop-match-end match) 4))
+      (should (eql (prop-match-value match) 2))
+      (should (= (point) 3)))
+    (let ((match (text-property-search-backward 'p)))
+      (should (= (prop-match-beginning match) 2))
+      (should (= (prop-match-end match) 3))
+      (should (eql (prop-match-value match) 1)))))
+
+(ert-deftest textprop-tests-prop-match-type ()
+  (should-not (prop-match-p [1 2 3]))
+  (should-error (prop-match-beginning 'nope) :type 'wrong-type-argument))
+
+(provide 'textprop-tests)
+;;; textprop-tests.el ends here
@@ -0,0 +1,110 @@
+;;; tool_bar-tests.el --- Tests for tool_bar.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(defmacro tool-bar-tests--with-items (bindings &rest body)
+  "Run BODY with `items' bound to the tool-bar items of BINDINGS.
+BINDINGS is a list of (KEY . DEFINITION) to put in the `tool-bar'
+keymap of `overriding-local-map'."
+  (declare (indent 1))
+  `(let* ((tool-bar (make-sparse-keymap))
+          (overriding-local-map (make-sparse-keymap))
+          (overriding-local-map-menu-flag t)
+          (overriding-terminal-local-map nil))
+     (define-key overriding-local-map [tool-bar] tool-bar)
+     (dolist (binding (reverse ,bindings))
+       (define-key tool-bar (vector (car binding)) (cdr binding)))
+     (let ((items (tool-bar--items)))
+       ,@body)))
+
+(defun tool-bar-tests--item (items key)
+  (let ((found nil))
+    (dolist (item items found)
+      (when (and (not found) (eq (aref item 0) key))
+        (setq found item)))))
+
+(defun tool-bar-tests--slot (items key slot)
+  (aref (tool-bar-tests--item items key)
+        (pcase slot
+          ('enabled 1) ('selected 2) ('caption 3) ('images 4) ('binding 5)
+          ('type 6) ('help 7) ('label 9))))
+
+(ert-deftest tool-bar-tests-item ()
+  (tool-bar-tests--with-items
+      '((new . (menu-item "New File..." find-file :help "Visit a file")))
+    (should (tool-bar-tests--item items 'new))
+    (should (equal (tool-bar-tests--slot items 'new 'caption) "New File..."))
+    (should (eq (tool-bar-tests--slot items 'new 'binding) 'find-file))
+    (should (equal (tool-bar-tests--slot items 'new 'help) "Visit a file"))
+    (should (eq (tool-bar-tests--slot items 'new 'enabled) t))
+    ;; The label is made from the caption, without the dots.
+    (should (equal (tool-bar-tests--slot items 'new 'label) "New File"))))
+
+(ert-deftest tool-bar-tests-visible ()
+  (tool-bar-tests--with-items
+      '((shown . (menu-item "Shown" ignore :visible (eq 1 1)))
+        (hidden . (menu-item "Hidden" ignore :visible nil)))
+    (should (tool-bar-tests--item items 'shown))
+    (should-not (tool-bar-tests--item items 'hidden))))
+
+(ert-deftest tool-bar-tests-enable ()
+  (tool-bar-tests--with-items
+      '((on . (menu-item "On" ignore :enable (eq 1 1)))
+        (off . (menu-item "Off" ignore :enable nil)))
+    (should (eq (tool-bar-tests--slot items 'on 'enabled) t))
+    (should-not (tool-bar-tests--slot items 'off 'enabled)))
+  (let ((enable-disabled-menus-and-buttons t))
+    (tool-bar-tests--with-items
+        '((off . (menu-item "Off" ignore :enable nil)))
+      (should (eq (tool-bar-tests--slot items 'off 'enabled) t)))))
+
+(ert-deftest tool-bar-tests-button ()
+  (tool-bar-tests--with-items
+      '((toggle . (menu-item "Toggle" ignore :button (:toggle . (eq 1 1))))
+        (radio . (menu-item "Radio" ignore :button (:radio . nil)))
+        (bogus . (menu-item "Bogus" ignore :button (:other . t))))
+    (should (eq (tool-bar-tests--slot items 'toggle 'type) :toggle))
+    (should (eq (tool-bar-tests--slot items 'toggle 'selected) t))
+    (should (eq (tool-bar-tests--slot items 'radio 'type) :radio))
+    (should-not (tool-bar-tests--slot items 'radio 'selected))
+    (should-not (tool-bar-tests--slot items 'bogus 'type))))
+
+(ert-deftest tool-bar-tests-label ()
+  (tool-bar-tests--with-items
+      '((a . (menu-item "Caption" ignore :label "Label"))
+        (b . (menu-item "Caption" ignore :label 3))
+        (save-some-buffers . (menu-item (concat "Save " "all") ignore)))
+    (should (equal (tool-bar-tests--slot items 'a 'label) "Label"))
+    (should (equal (tool-bar-tests--slot items 'b 'label)
+                   "!!?GARBLED ITEM?!!"))
+    ;; A caption that isn't a string is evaluated.
+    (should (equal (tool-bar-tests--slot items 'save-some-buffers 'caption)
+                   "Save all"))
+    (should (equal (tool-bar-tests--slot items 'save-some-buffers 'label)
+                   "Save All"))))
+
+(ert-deftest tool-bar-tests-undefined ()
+  "An `undefined' binding removes the item of a map of lower precedence."
+  (tool-bar-tests--with-items
+      '((new . (menu-item "New" ignore))
+        (open . (menu-item "Open" ignore)))
+    (should (tool-bar-tests--item items 'new))
+    (let ((overriding-terminal-local-map (make-sparse-keymap)))
+      (define-key overriding-terminal-local-map [tool-bar new] 'undefined)
+      (let ((items (tool-bar--items)))
+        (should-not (tool-bar-tests--item items 'new))
+        (should (tool-bar-tests--item items 'open))))))
+
+(ert-deftest tool-bar-tests-separator ()
+  (tool-bar-tests--with-items
+      '((sep . (menu-item "--"))
+        (old-sep . ("--"))
+        (bad . (menu-item "Not a separator")))
+    (should (eq (tool-bar-tests--slot items 'sep 'type) t))
+    (should (eq (tool-bar-tests--slot items 'old-sep 'type) t))
+    (should-not (tool-bar-tests--item items 'bad))))
+
+(provide 'tool_bar-tests)
+;;; tool_bar-tests.el ends here
@@ -0,0 +1,62 @@
+;;; transient_map-tests.el --- Tests for transient_map.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest transient-map-tests-set-and-exit ()
+  (let* ((overriding-terminal-local-map nil)
+         (map (make-sparse-keymap))
+         (exits 0)
+         (exitfun (set-transient-map map nil (lambda () (setq exits (1+ exits))))))
+    (should (memq map overriding-terminal-local-map))
+    (funcall exitfun)
+    (should-not (memq map overriding-terminal-local-map))
+    (should (= exits 1))
+    ;; Exiting a map that is gone already does nothing.
+    (funcall exitfun)
+    (should (= exits 1))))
+
+(ert-deftest transient-map-tests-nested ()
+  (let* ((overriding-terminal-local-map nil)
+         (outer (make-sparse-keymap))
+         (inner (make-sparse-keymap))
+         (exit-outer (set-transient-map outer t))
+         (exit-inner (set-transient-map inner t)))
+    (should (memq outer overriding-terminal-local-map))
+    (should (memq inner overriding-terminal-local-map))
+    (funcall exit-inner)
+    (should (memq outer overriding-terminal-local-map))
+    (should-not (memq inner overriding-terminal-local-map))
+    (funcall exit-outer)
+    (should-not overriding-terminal-local-map)))
+
+(ert-deftest transient-map-tests-on-exit-error ()
+  (let* ((overriding-terminal-local-map nil)
+         (map (make-sparse-keymap))
+         (exitfun (set-transient-map map nil (lambda () (error "Boom")))))
+    (should-error (funcall exitfun))
+    ;; The map is taken down before ON-EXIT runs.
+    (should-not (memq map overriding-terminal-local-map))))
+
+(ert-deftest transient-map-tests-keep-pred-error ()
+  "An error in KEEP-PRED takes the map down instead of escaping."
+  (let* ((overriding-terminal-local-map nil)
+         (map (make-sparse-keymap))
+         (ran nil))
+    (define-key map "a" (lambda () (interactive) (setq ran t)))
+    (set-transient-map map (lambda () (error "Boom")))
+    (execute-kbd-macro "a")
+    (should ran)
+    (should-not (memq map overriding-terminal-local-map))))
+
+(ert-deftest transient-map-tests-keep-pred-throw ()
+  "A `throw' out of KEEP-PRED is not caught like an error."
+  (let* ((overriding-terminal-local-map nil)
+         (map (make-sparse-keymap)))
+    (define-key map "a" #'ignore)
+    (set-transient-map map (lambda () (throw 'done 'thrown)))
+    (should (eq (catch 'done (execute-kbd-macro "a") 'finished) 'thrown))))
+
+(provide 'transient_map-tests)
+;;; transient_map-tests.el ends here
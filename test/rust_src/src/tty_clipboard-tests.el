@@ -0,0 +1,26 @@
+;;; tty_clipboard-tests.el --- Tests for tty_clipboard.rs
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest tty-clipboard-no-terminal ()
+  ;; There is no text terminal in batch mode.
+  (when noninteractive
+    (should-not (tty-clipboard-set "text"))
+    (should-not (tty-clipboard-set "text" 'PRIMARY))
+    (let ((tty-clipboard-allow-read t))
+      (should-not (tty-clipboard-get))
+      (should-not (tty-clipboard-get 'CLIPBOARD 0.1)))))
+
+(ert-deftest tty-clipboard-selection-type ()
+  (should-error (tty-clipboard-set "text" 'SECONDARY))
+  (should-error (tty-clipboard-get 'SECONDARY))
+  (should-error (tty-clipboard-set 'text)))
+
+(ert-deftest tty-clipboard-allow-read-default ()
+  (should-not (default-value 'tty-clipboard-allow-read)))
+
+(provide 'tty_clipboard-tests)
+
+;;; tty_clipboard-tests.el ends here
@@ -0,0 +1,29 @@
+;;; tty_graphics-tests.el --- Tests for tty_graphics.rs
+
+;;; Code:
+
+(require 'ert)
+
+(defconst tty-graphics-tests-ppm
+  (concat "P6\n3 2\n255\n" (apply #'unibyte-string (make-list 18 255)))
+  "A white PPM image, 3 pixels wide and 2 high.")
+
+(ert-deftest tty-graphics-image-size ()
+  (should (equal (tty-image-size tty-graphics-tests-ppm) '(3 . 2)))
+  (should (equal (tty-image-size "P5 4 1 255 \0\0\0\0") '(4 . 1)))
+  (should-error (tty-image-size "GIF89a"))
+  (should-error (tty-image-size "P6 3 2 255 \0"))
+  (should-error (tty-image-size (string-to-multibyte "P5 1 1 255 \377"))))
+
+(ert-deftest tty-graphics-no-terminal ()
+  ;; There is no text terminal in batch mode.
+  (when noninteractive
+    (should-not (tty-graphics-protocol))
+    (should-not (tty-cell-size))
+    (should-not (tty-image-available-p))
+    (should-not (tty-draw-image tty-graphics-tests-ppm 0 0 1 1))
+    (should-not (tty-clear-images))))
+
+(provide 'tty_graphics-tests)
+
+;;; tty_graphics-tests.el ends here
@@ -0,0 +1,30 @@
+;;; tty_input-tests.el --- Tests for tty_input.rs
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest tty-capabilities-not-a-tty ()
+  ;; The initial terminal of batch mode is not a tty.
+  (should-not (tty-capabilities))
+  (should-not (tty-negotiate-protocols))
+  (should-not (tty-capabilities)))
+
+(ert-deftest tty-enable-protocol-checks-protocol ()
+  (should-error (tty-enable-protocol 'no-such-protocol) :type 'error)
+  (should-error (tty-enable-protocol "kitty-keyboard")
+                :type 'wrong-type-argument)
+  (should-not (tty-enable-protocol 'kitty-keyboard)))
+
+(ert-deftest tty-xterm-paste-event ()
+  (require 'term/xterm)
+  ;; Callers of the old argument-less version still work.
+  (should (equal (func-arity 'xterm-paste) '(0 . 1)))
+  (with-temp-buffer
+    (let ((kill-ring nil))
+      (xterm-paste (list 'xterm-paste (copy-sequence "one\rtwo")))
+      (should (equal (buffer-string) "one\ntwo")))))
+
+(provide 'tty_input-tests)
+
+;;; tty_input-tests.el ends here
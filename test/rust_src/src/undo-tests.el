@@ -0,0 +1,118 @@
+;;; undo-tests.el --- Tests for undo.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest undo-tests-atomic-change-group-cancel ()
+  (with-temp-buffer
+    (buffer-enable-undo)
+    (insert "abc")
+    (undo-boundary)
+    (let ((undo-list buffer-undo-list))
+      (should-error
+       (atomic-change-group
+         (insert "def")
+         (undo-boundary)
+         (delete-region 1 3)
+         (error "Oops")))
+      (should (equal (buffer-string) "abc"))
+      (should (eq buffer-undo-list undo-list)))))
+
+(ert-deftest undo-tests-atomic-change-group-accept ()
+  (with-temp-buffer
+    (insert "abc")
+    (should (eq buffer-undo-list t))
+    (atomic-change-group
+      (insert "def"))
+    (should (equal (buffer-string) "abcdef"))
+    (should (eq buffer-undo-list t))))
+
+(ert-deftest undo-tests-cancel-without-undo ()
+  (with-temp-buffer
+    (insert "abc")
+    (let ((handle (prepare-change-group)))
+      (activate-change-group handle)
+      (goto-char 2)
+      (insert "xyz")
+      (cancel-change-group handle))
+    (should (equal (buffer-string) "abc"))
+    (should (eq buffer-undo-list t))))
+
+(ert-deftest undo-tests-cancel-narrowed ()
+  (with-temp-buffer
+    (buffer-enable-undo)
+    (insert "abcdef")
+    (let ((handle (prepare-change-group)))
+      (activate-change-group handle)
+      (delete-region 1 3)
+      (narrow-to-region 2 3)
+      (cancel-change-group handle)
+      (should (buffer-narrowed-p))
+      (widen))
+    (should (equal (buffer-string) "abcdef"))))
+
+(ert-deftest undo-tests-multibuffer-change-group ()
+  (let ((a (generate-new-buffer "undo-tests-a"))
+        (b (generate-new-buffer "undo-tests-b")))
+    (unwind-protect
+        (let ((handle (nconc (prepare-change-group a)
+                             (prepare-change-group b))))
+          (activate-change-group handle)
+          (with-current-buffer a (insert "a"))
+          (with-current-buffer b (insert "b"))
+          (cancel-change-group handle)
+          (should (equal (with-current-buffer a (buffer-string)) ""))
+          (should (equal (with-current-buffer b (buffer-string)) "")))
+      (kill-buffer a)
+      (kill-buffer b))))
+
+(ert-deftest undo-tests-unrelated-state ()
+  (with-temp-buffer
+    (buffer-enable-undo)
+    (insert "abc")
+    (should-error
+     (cancel-change-group (list (cons (current-buffer) (list 'unrelated)))))))
+
+(ert-deftest undo-tests-undo-size ()
+  (let (sizes)
+    (dolist (len '(3 103))
+      (with-temp-buffer
+        (buffer-enable-undo)
+        (should (= (undo-size) 0))
+        (insert (make-string len ?a))
+        (delete-region (point-min) (point-max))
+        (push (undo-size) sizes)
+        (buffer-disable-undo)
+        (should (= (undo-size) 0))))
+    ;; The deleted text counts towards the size, byte for byte.
+    (should (= (- (car sizes) (cadr sizes)) 100))))
+
+(ert-deftest undo-tests-truncate-undo-list ()
+  (with-temp-buffer
+    (buffer-enable-undo)
+    (let ((undo-limit 3000)
+          (undo-strong-limit 5000))
+      (dotimes (_ 10)
+        (insert (make-string 1000 ?x))
+        (undo-boundary)
+        (delete-region (point-min) (point-max))
+        (undo-boundary))
+      (should (> (undo-size) 10000))
+      (garbage-collect)
+      (should (< 0 (undo-size) 5000)))))
+
+(ert-deftest undo-tests-undo-outer-limit ()
+  (with-temp-buffer
+    (buffer-enable-undo)
+    (let* ((called nil)
+           (undo-outer-limit 1000)
+           (undo-outer-limit-function (lambda (size) (setq called size) t)))
+      (insert (make-string 2000 ?x))
+      (delete-region (point-min) (point-max))
+      (garbage-collect)
+      (should (equal called (undo-size))))))
+
+(provide 'undo-tests)
+
+;;; undo-tests.el ends here
@@ -0,0 +1,73 @@
+;;; undo_persist-tests.el --- Tests for undo_persist.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest undo-persist-tests-round-trip ()
+  (let ((file (make-temp-file "undo-persist-tests")))
+    (unwind-protect
+        (let (undo-list)
+          (with-temp-buffer
+            (buffer-enable-undo)
+            (insert "abc")
+            (undo-boundary)
+            (insert (propertize "é" 'face 'bold))
+            (undo-boundary)
+            (delete-region 1 3)
+            (undo-boundary)
+            (should (undo-persist-save file))
+            (setq undo-list buffer-undo-list))
+          (with-temp-buffer
+            (buffer-enable-undo)
+            (insert "cé")
+            (setq buffer-undo-list nil)
+            (should (undo-persist-load file))
+            (should (equal buffer-undo-list undo-list))
+            (primitive-undo 1 buffer-undo-list)
+            (should (equal (buffer-string) "abcé"))))
+      (delete-file file))))
+
+(ert-deftest undo-persist-tests-other-text ()
+  (let ((file (make-temp-file "undo-persist-tests")))
+    (unwind-protect
+        (progn
+          (with-temp-buffer
+            (buffer-enable-undo)
+            (insert "abc")
+            (should (undo-persist-save file)))
+          (with-temp-buffer
+            (buffer-enable-undo)
+            (insert "abd")
+            (setq buffer-undo-list nil)
+            (should-not (undo-persist-load file))
+            (should-not buffer-undo-list)))
+      (delete-file file))))
+
+(ert-deftest undo-persist-tests-unsaved-entries ()
+  (let ((file (make-temp-file "undo-persist-tests")))
+    (unwind-protect
+        (with-temp-buffer
+          (buffer-enable-undo)
+          (let ((marker (point-marker)))
+            (setq buffer-undo-list
+                  `((1 . 2) (,marker . 1) nil (apply ignore) (3 . 4)))
+            (should (undo-persist-save file))
+            (setq buffer-undo-list nil)
+            (should (undo-persist-load file))
+            (should (equal buffer-undo-list '((1 . 2) nil)))))
+      (delete-file file))))
+
+(ert-deftest undo-persist-tests-disabled ()
+  (with-temp-buffer
+    (should (eq buffer-undo-list t))
+    (should-not (undo-persist-save "/nonexistent-directory/undo"))
+    (should-not (undo-persist-load "/nonexistent-directory/undo")))
+  (with-temp-buffer
+    (buffer-enable-undo)
+    (should-not (undo-persist-load "/nonexistent-directory/undo"))
+    (should-error (undo-persist-save "/nonexistent-directory/undo")
+                  :type 'file-error)))
+
+(provide 'undo_persist-tests)
+;;; undo_persist-tests.el ends here
@@ -0,0 +1,57 @@
+;;; uniquify-tests.el --- Tests for uniquify.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+(require 'uniquify)
+
+(defun uniquify-tests--file-buffer (file)
+  "Return a new buffer for visiting FILE."
+  (let ((buffer (create-file-buffer file)))
+    (with-current-buffer buffer
+      (setq buffer-file-name file))
+    buffer))
+
+(defmacro uniquify-tests--with-buffers (names &rest body)
+  "Run BODY with NAMES bound to buffers for the files they name.
+The buffers are killed afterwards."
+  (declare (indent 1))
+  `(let* ((uniquify-min-dir-content 0)
+          (uniquify-strip-common-suffix t)
+          ,@(mapcar (lambda (name)
+                      `(,name (uniquify-tests--file-buffer ,(symbol-name name))))
+                    names))
+     (unwind-protect
+         (progn ,@body)
+       ,@(mapcar (lambda (name) `(kill-buffer ,name)) names))))
+
+(ert-deftest uniquify-tests-rationalize ()
+  (let ((uniquify-buffer-name-style 'post-forward-angle-brackets))
+    (uniquify-tests--with-buffers (/tmp/a/b/uniquify-tests /tmp/c/b/uniquify-tests)
+      (should (equal (buffer-name /tmp/a/b/uniquify-tests) "uniquify-tests<a>"))
+      (should (equal (buffer-name /tmp/c/b/uniquify-tests) "uniquify-tests<c>"))
+      (with-current-buffer /tmp/a/b/uniquify-tests
+        (should (equal (uniquify-buffer-base-name) "uniquify-tests"))))))
+
+(ert-deftest uniquify-tests-kill ()
+  (let ((uniquify-buffer-name-style 'post-forward-angle-brackets))
+    (uniquify-tests--with-buffers (/tmp/a/uniquify-tests /tmp/b/uniquify-tests)
+      (let ((third (uniquify-tests--file-buffer "/tmp/c/uniquify-tests")))
+        (should (equal (buffer-name third) "uniquify-tests<c>"))
+        (kill-buffer third))
+      (should (equal (buffer-name /tmp/b/uniquify-tests) "uniquify-tests<b>"))
+      (kill-buffer /tmp/b/uniquify-tests)
+      (should (equal (buffer-name /tmp/a/uniquify-tests) "uniquify-tests")))))
+
+(ert-deftest uniquify-tests-style ()
+  (let ((uniquify-buffer-name-style 'forward))
+    (uniquify-tests--with-buffers (/tmp/a/uniquify-tests /tmp/b/uniquify-tests)
+      (should (equal (buffer-name /tmp/a/uniquify-tests) "a/uniquify-tests"))
+      (should (equal (buffer-name /tmp/b/uniquify-tests) "b/uniquify-tests"))))
+  (let ((uniquify-buffer-name-style 'reverse)
+        (uniquify-separator nil))
+    (uniquify-tests--with-buffers (/tmp/a/uniquify-tests /tmp/b/uniquify-tests)
+      (should (equal (buffer-name /tmp/b/uniquify-tests) "uniquify-tests\\b")))))
+
+(provide 'uniquify-tests)
+;;; uniquify-tests.el ends here
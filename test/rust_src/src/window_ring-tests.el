@@ -0,0 +1,63 @@
+;;; window_ring-tests.el --- Tests for window_ring.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(defmacro window-ring-tests--with-empty-ring (&rest body)
+  "Run BODY with the ring of the selected frame emptied, and empty it again."
+  (declare (indent 0))
+  `(progn
+     (while (window-configuration-ring-pop))
+     (unwind-protect
+         (progn ,@body)
+       (while (window-configuration-ring-pop)))))
+
+(ert-deftest window-ring-tests-push-and-pop ()
+  (window-ring-tests--with-empty-ring
+    (should-not (window-configuration-ring))
+    (should (eq (window-configuration-ring-push 'a) 'a))
+    (window-configuration-ring-push 'b)
+    (window-configuration-ring-push 'c)
+    (should (equal (window-configuration-ring) '(c b a)))
+    (should (eq (window-configuration-ring-pop nil 1) 'b))
+    (should (equal (window-configuration-ring) '(c a)))
+    (should (eq (window-configuration-ring-pop) 'c))
+    (should-not (window-configuration-ring-pop nil 1))
+    (should (equal (window-configuration-ring) '(a)))))
+
+(ert-deftest window-ring-tests-replace ()
+  (window-ring-tests--with-empty-ring
+    (window-configuration-ring-push 'a nil nil t)
+    (window-configuration-ring-push 'b)
+    (window-configuration-ring-push 'c nil nil t)
+    (should (equal (window-configuration-ring) '(c a)))))
+
+(ert-deftest window-ring-tests-size ()
+  (window-ring-tests--with-empty-ring
+    (dolist (conf '(a b c d))
+      (window-configuration-ring-push conf nil 3))
+    (should (equal (window-configuration-ring) '(d c b)))))
+
+(ert-deftest window-ring-tests-sharing ()
+  (window-ring-tests--with-empty-ring
+    (window-configuration-ring-push 'a)
+    (window-configuration-ring-push 'b)
+    (let ((ring (window-configuration-ring)))
+      (window-configuration-ring-pop)
+      (window-configuration-ring-push 'c)
+      (should (equal ring '(b a)))
+      (should (eq (cdr (window-configuration-ring)) (cdr ring))))))
+
+(ert-deftest window-ring-tests-changed-frames ()
+  (window-configuration-ring-changed-frames t)
+  (run-window-configuration-change-hook)
+  (let ((frames (window-configuration-ring-changed-frames)))
+    ;; Frames that can't be resized don't run the hook, as in batch mode.
+    (when frames
+      (should (equal frames (list (selected-frame))))
+      (window-configuration-ring-changed-frames t)
+      (should-not (window-configuration-ring-changed-frames)))))
+
+(provide 'window_ring-tests)
+;;; window_ring-tests.el ends here
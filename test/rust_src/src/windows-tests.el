@@ -95,3 +95,165 @@
   (set-window-parameter (selected-window) 'test 'test)
   (should (consp (window-parameters)))
   (should (consp (window-parameters (selected-window)))))
+
+(ert-deftest get-lru-window ()
+  (let ((w1 (selected-window))
+        (w2 (split-window)))
+    (unwind-protect
+        (progn
+          (select-window w2)
+          (select-window w1)
+          (should (eq (get-lru-window) w2))
+          (should (eq (get-lru-window nil nil t) w2))
+          (set-window-dedicated-p w2 t)
+          (should-not (get-lru-window nil nil t))
+          (should (eq (get-lru-window nil t) w2)))
+      (delete-window w2))))
+
+(ert-deftest get-largest-window ()
+  (let* ((w1 (selected-window))
+         (w2 (split-window nil (/ (window-total-height) 3))))
+    (unwind-protect
+        (progn
+          (should (eq (get-largest-window) w2))
+          (should (eq (get-largest-window nil nil t) w2))
+          (select-window w2)
+          (should (eq (get-largest-window nil nil t) w1)))
+      (select-window w1)
+      (delete-window w2))))
+
+(ert-deftest display-buffer-assq-regexp ()
+  (let ((alist '(("\\`\\*foo" . (display-buffer-same-window))
+                 ((lambda (name _action) (equal name "bar"))
+                  . (display-buffer-pop-up-window)))))
+    (should (equal (display-buffer-assq-regexp "*foo*" alist nil)
+                   '(display-buffer-same-window)))
+    (should (equal (display-buffer-assq-regexp "bar" alist nil)
+                   '(display-buffer-pop-up-window)))
+    (should-not (display-buffer-assq-regexp "baz" alist nil))))
+
+(ert-deftest display-buffer-explain ()
+  (with-temp-buffer
+    (let* ((display-buffer-overriding-action '(display-buffer-no-window))
+           (display-buffer-alist
+            `((,(regexp-quote (buffer-name)) display-buffer-same-window
+               (window-height . 5))))
+           (display-buffer-base-action nil)
+           (display-buffer-fallback-action
+            '((display-buffer-pop-up-window) (window-width . 10)))
+           (explanation (display-buffer-explain (current-buffer) t))
+           (actions (display-buffer--actions (current-buffer) nil nil t)))
+      (should (equal (cdr (assq 'functions explanation))
+                     '(display-buffer-no-window
+                       display-buffer-same-window
+                       display-buffer-pop-up-window)))
+      (should (equal (cdr (assq 'alist explanation))
+                     '((window-height . 5)
+                       (inhibit-same-window . t)
+                       (window-width . 10))))
+      (should (equal (car actions) (cdr (assq 'functions explanation))))
+      (should (equal (cdr actions) (cdr (assq 'alist explanation))))
+      (should (equal (cdr (assq 'display-buffer-alist
+                                (cdr (assq 'sources explanation))))
+                     '(display-buffer-same-window (window-height . 5)))))))
+
+(ert-deftest window-prune-buffer-lists ()
+  (let ((window (selected-window))
+        (live (get-buffer-create "windows-tests live"))
+        (killed (get-buffer-create "windows-tests killed")))
+    (kill-buffer killed)
+    (set-window-prev-buffers window `((,killed 1 1) (,live 1 1)))
+    (set-window-next-buffers window (list killed live))
+    (window-prune-buffer-lists window)
+    (should (equal (window-prev-buffers window) `((,live 1 1))))
+    (should (equal (window-next-buffers window) (list live)))
+    (let ((window-buffer-lists-max 1))
+      (set-window-next-buffers window (list live (current-buffer)))
+      (window-prune-buffer-lists)
+      (should (equal (window-next-buffers window) (list live))))
+    (kill-buffer live)))
+
+(ert-deftest window-prune-buffer-lists-kill-buffer ()
+  (let ((window (selected-window))
+        (buffer (get-buffer-create "windows-tests killed")))
+    (set-window-prev-buffers window `((,buffer 1 1)))
+    (set-window-next-buffers window (list buffer))
+    (kill-buffer buffer)
+    (should-not (assq buffer (window-prev-buffers window)))
+    (should-not (memq buffer (window-next-buffers window)))))
+
+(ert-deftest balance-windows ()
+  (let* ((w1 (selected-window))
+         (w2 (split-window w1 (/ (window-total-height w1) 4)))
+         (w3 (split-window w2 (/ (window-total-height w2) 4))))
+    (unwind-protect
+        (let ((window-resize-pixelwise t))
+          (balance-windows)
+          (let ((heights (mapcar #'window-pixel-height (list w1 w2 w3))))
+            (should (<= (- (apply #'max heights) (apply #'min heights))
+                        (frame-char-height)))))
+      (delete-other-windows w1))))
+
+(ert-deftest balance-windows-area ()
+  (let* ((w1 (selected-window))
+         (w2 (split-window w1 (/ (window-total-height w1) 4))))
+    (unwind-protect
+        (progn
+          (balance-windows-area)
+          (should (<= (abs (- (window-total-height w1) (window-total-height w2)))
+                      1)))
+      (delete-other-windows w1))))
+
+(ert-deftest balance-windows-not-a-window ()
+  (should-error (balance-windows 'foo)))
+
+(ert-deftest apply-window-layout ()
+  (let ((a (get-buffer-create "windows-tests a"))
+        (b (get-buffer-create "windows-tests b")))
+    (unwind-protect
+        (let ((windows (apply-window-layout
+                        `(column (0.5 . ,a)
+                                 (row (buffer "windows-tests b" :dedicated t)
+                                      (buffer nil :parameters ((foo . 1))
+                                              :select t))))))
+          (should (= (length windows) 3))
+          (should (= (length (window-list)) 3))
+          (should (eq (window-buffer (nth 0 windows)) a))
+          (should (eq (window-buffer (nth 1 windows)) b))
+          (should (eq (window-dedicated-p (nth 1 windows)) t))
+          (should (eq (window-parameter (nth 2 windows) 'foo) 1))
+          (should (eq (selected-window) (nth 2 windows)))
+          (should (<= (abs (- (window-total-height (nth 0 windows))
+                              (window-total-height (window-parent
+                                                    (nth 1 windows)))))
+                      1)))
+      (apply-window-layout (current-buffer))
+      (should (= (length (window-list)) 1))
+      (kill-buffer a)
+      (kill-buffer b))))
+
+(ert-deftest apply-window-layout-invalid ()
+  (should-error (apply-window-layout '(diagonal "a" "b")))
+  (should-error (apply-window-layout '(row (1.5 . "a") "b")))
+  (should-error (apply-window-layout '(row (0.6 . "a") (0.6 . "b"))))
+  (should-error (apply-window-layout '(buffer "a" :bogus t)))
+  (should (equal (should-error (apply-window-layout '(diagonal "a" "b")))
+                 '(error "Invalid window layout" (diagonal "a" "b"))))
+  (should (equal (should-error (apply-window-layout '(row (1.5 . "a") "b")))
+                 '(error "Invalid window layout ratio" 1.5)))
+  (should-not (get-buffer "a")))
+
+(ert-deftest apply-window-layout-atomic ()
+  (let* ((w1 (selected-window))
+         (w2 (split-window w1)))
+    (unwind-protect
+        (progn
+          (should-error
+           (apply-window-layout
+            (cons 'column (make-list (* 2 (frame-height)) "windows-tests"))))
+          (should (window-live-p w1))
+          (should (window-live-p w2))
+          (should (= (length (window-list)) 2)))
+      (delete-other-windows w1)
+      (when (get-buffer "windows-tests")
+        (kill-buffer "windows-tests")))))
@@ -0,0 +1,48 @@
+;;; word_wrap-tests.el --- Tests for word_wrap.rs -*- lexical-binding: t -*-
+
+;;; Code:
+
+(require 'ert)
+
+(ert-deftest word-wrap-tests-boundaries ()
+  (with-temp-buffer
+    (insert "aaa bbb ccc")
+    (should (equal (visual-line-boundaries (point-min) (point-max) 7) '(9)))
+    (should (equal (visual-line-boundaries (point-min) (point-max) 4) '(5 9)))
+    (should-not (visual-line-boundaries (point-min) (point-max) 20))
+    ;; Positions are those in the buffer.
+    (should (equal (visual-line-boundaries 5 (point-max) 4) '(9)))))
+
+(ert-deftest word-wrap-tests-boundaries-newlines ()
+  (with-temp-buffer
+    (insert "aaa\nbbb\n")
+    (should (equal (visual-line-boundaries (point-min) (point-max) 20) '(5 9)))))
+
+(ert-deftest word-wrap-tests-long-word ()
+  "A word wider than a line is broken at the edge of the line."
+  (with-temp-buffer
+    (insert "aaaaaaa bb")
+    (should (equal (visual-line-boundaries (point-min) (point-max) 3)
+                   '(4 7 9)))))
+
+(ert-deftest word-wrap-tests-tabs ()
+  "A tab reaches the next tab stop."
+  (with-temp-buffer
+    (insert "a\tbbb cc")
+    (let ((tab-width 4))
+      (should (equal (visual-line-boundaries (point-min) (point-max) 8) '(7))))
+    (let ((tab-width 2))
+      (should-not (visual-line-boundaries (point-min) (point-max) 8)))))
+
+(ert-deftest word-wrap-tests-by-category ()
+  (with-temp-buffer
+    (insert "日本語abc")
+    (let ((word-wrap-by-category nil))
+      (should (equal (visual-line-boundaries (point-min) (point-max) 7) '(5))))
+    ;; Lines may be wrapped after CJK characters.
+    (let ((word-wrap-by-category t))
+      (should (equal (visual-line-boundaries (point-min) (point-max) 7)
+                     '(4))))))
+
+(provide 'word_wrap-tests)
+;;; word_wrap-tests.el ends here